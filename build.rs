@@ -1,5 +1,40 @@
-#[cfg(windows)]
+use std::path::Path;
+use std::process::Command;
+
 fn main() {
+    embed_build_info();
+    #[cfg(windows)]
+    windows_resources();
+}
+
+/// `core::build_info` reads these back: the target triple and the commit built from.
+fn embed_build_info() {
+    println!("cargo:rustc-env=AEONMI_TARGET={}", std::env::var("TARGET").unwrap_or_default());
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=AEONMI_GIT_COMMIT={commit}");
+    println!("cargo:rerun-if-changed=build.rs");
+    // Re-embed when HEAD moves: HEAD itself for a detached checkout, the branch ref otherwise
+    // (a missing path would make every build rerun the script, so only existing ones are named).
+    let head = Path::new(".git/HEAD");
+    if head.is_file() {
+        println!("cargo:rerun-if-changed=.git/HEAD");
+        if let Some(reference) = std::fs::read_to_string(head).ok().and_then(|h| h.strip_prefix("ref: ").map(|r| r.trim().to_string())) {
+            let path = Path::new(".git").join(reference);
+            if path.is_file() {
+                println!("cargo:rerun-if-changed={}", path.display());
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+fn windows_resources() {
     let mut res = winres::WindowsResource::new();
     // Set icon (ensure assets/icon.ico exists and is a proper ICO)
     res.set_icon("assets/icon.ico");
@@ -11,6 +46,3 @@ fn main() {
         eprintln!("winres compile warning: {e}");
     }
 }
-
-#[cfg(not(windows))]
-fn main() {}
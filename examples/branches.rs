@@ -28,10 +28,14 @@ fn main() {
                         Stmt::Let {
                             name: "i".into(),
                             value: Some(Expr::Lit(Lit::Number(0.0))),
+                            line: 0,
+                            column: 0,
                         },
                         Stmt::Let {
                             name: "acc".into(),
                             value: Some(Expr::Lit(Lit::Number(0.0))),
+                            line: 0,
+                            column: 0,
                         },
                         // while (i < LIMIT) { ... }
                         Stmt::While {
@@ -61,6 +65,8 @@ fn main() {
                                                     op: BinOp::Add,
                                                     right: Box::new(Expr::Ident("i".into())),
                                                 },
+                                                line: 0,
+                                                column: 0,
                                             }],
                                         },
                                         else_block: Some(Block {
@@ -71,6 +77,8 @@ fn main() {
                                                     op: BinOp::Add,
                                                     right: Box::new(Expr::Lit(Lit::Number(1.0))),
                                                 },
+                                                line: 0,
+                                                column: 0,
                                             }],
                                         }),
                                     },
@@ -82,6 +90,8 @@ fn main() {
                                             op: BinOp::Add,
                                             right: Box::new(Expr::Lit(Lit::Number(1.0))),
                                         },
+                                        line: 0,
+                                        column: 0,
                                     },
                                 ],
                             },
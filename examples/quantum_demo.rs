@@ -1,6 +1,6 @@
 #[cfg(feature = "quantum")]
 fn main() -> anyhow::Result<()> {
-    use aeonmi_project::commands::quantum::quantum_run;
+    use aeonmi_project::commands::quantum;
     use std::env;
     use std::path::PathBuf;
 
@@ -16,7 +16,7 @@ fn main() -> anyhow::Result<()> {
     let backend = &args[0];
     let file = PathBuf::from(&args[1]);
     let shots = args.get(2).and_then(|s| s.parse::<usize>().ok());
-    quantum_run(file, backend, shots)
+    quantum::main(file, shots, backend)
 }
 
 #[cfg(not(feature = "quantum"))]
@@ -20,6 +20,8 @@ fn main() {
                         Stmt::Let {
                             name: "x".into(),
                             value: Some(Expr::Lit(Lit::Number(2.0))),
+                            line: 0,
+                            column: 0,
                         },
                         Stmt::Expr(Expr::Call {
                             callee: Box::new(Expr::Ident("print".into())),
@@ -297,6 +297,35 @@ document.getElementById('saveBtn').addEventListener('click', async () => {
 });
 
 // Diagnostics & symbols
+function diagMarker(d) {
+  const severity = d.severity === 'warning' ? monaco.MarkerSeverity.Warning : d.severity === 'info' ? monaco.MarkerSeverity.Info : monaco.MarkerSeverity.Error;
+  return {
+    message: d.message,
+    severity,
+    startLineNumber: d.line || 1,
+    startColumn: d.column || 1,
+    endLineNumber: d.endLine || d.line || 1,
+    endColumn: d.endColumn || (d.column || 1) + 1
+  };
+}
+
+// Full (unbudgeted) analysis of `src`; replaces the 'aeonmi' markers if the buffer is unchanged.
+let completingDiagnosticsFor = null;
+function completeDiagnostics(src) {
+  completingDiagnosticsFor = src;
+  setTimeout(async () => {
+    if (completingDiagnosticsFor !== src) return;
+    try {
+      const val = await window.__TAURI__.invoke('aeonmi_diagnostics_complete', { source: src });
+      const payload = typeof val === 'string' ? JSON.parse(val) : val;
+      if (completingDiagnosticsFor !== src || !monacoEditor || monacoEditor.getValue() !== src) return;
+      const typeMarkers = monaco.editor.getModelMarkers({ owner: 'aeonmi' }).filter(m => m.severity === monaco.MarkerSeverity.Info && !m.message.startsWith('analysis truncated'));
+      monaco.editor.setModelMarkers(monacoEditor.getModel(), 'aeonmi', [...(payload.diagnostics || []).map(diagMarker), ...typeMarkers]);
+    } catch (e) { console.warn('background diagnostics failed', e); }
+    finally { if (completingDiagnosticsFor === src) completingDiagnosticsFor = null; }
+  }, 0);
+}
+
 async function runDiagnostics() {
   if (!monacoEditor) return;
   const src = monacoEditor.getValue();
@@ -311,14 +340,9 @@ async function runDiagnostics() {
     }
     let baseMarkers = [];
     if (diagPayload) {
-      baseMarkers = (diagPayload.diagnostics || []).map(d => ({
-        message: d.message,
-        severity: d.severity === 'warning' ? monaco.MarkerSeverity.Warning : monaco.MarkerSeverity.Error,
-        startLineNumber: d.line || 1,
-        startColumn: d.column || 1,
-        endLineNumber: d.endLine || d.line || 1,
-        endColumn: d.endColumn || (d.column || 1) + 1
-      }));
+      baseMarkers = (diagPayload.diagnostics || []).map(diagMarker);
+      // Analysis ran out of time: show what we have now, fill in the rest in the background
+      if (diagPayload.truncated && window.__TAURI__?.invoke) { completeDiagnostics(src); }
     }
     // Fetch type diagnostics (non-blocking best effort)
     let typeMarkers = [];
@@ -558,7 +582,15 @@ function applyPrefsToUI(prefs) {
   if (prefAutoAI) prefAutoAI.checked = prefs.autoAI === '1';
   if (prefAutoQuantum) prefAutoQuantum.checked = prefs.autoQuantumSim === '1';
 }
-async function refreshPrefsPanel() { const prefs = await loadPrefsBackend(); applyPrefsToUI(prefs); }
+async function refreshPrefsPanel() { const prefs = await loadPrefsBackend(); applyPrefsToUI(prefs); refreshAiUsage(); }
+async function refreshAiUsage() {
+  const el = document.getElementById('aiUsageSummary'); if (!el || !window.__TAURI__?.invoke) return;
+  try {
+    const raw = await window.__TAURI__.invoke('ai_usage', { since: '7d' }); const u = typeof raw === 'string' ? JSON.parse(raw) : raw; const t = u.total || {};
+    const cost = t.calls && t.unpriced_calls === t.calls ? 'cost n/a' : `~$${(t.est_cost||0).toFixed(4)}`;
+    el.textContent = `${t.calls||0} calls (${t.failures||0} failed), ${(t.prompt_tokens||0)+(t.completion_tokens||0)} tokens, ${cost}`;
+  } catch(e) { el.textContent = 'unavailable'; }
+}
 openSettings?.addEventListener('click', async () => { await refreshPrefsPanel(); settingsPanel.style.display='block'; });
 closeSettings?.addEventListener('click', ()=> settingsPanel.style.display='none');
 savePrefsBtn?.addEventListener('click', async () => {
@@ -618,10 +650,12 @@ async function updateQuantumCircuit(src) {
     const raw = await window.__TAURI__.invoke('aeonmi_quantum_circuit', { source: src });
     const data = typeof raw === 'string' ? JSON.parse(raw) : raw;
     if (!data || typeof data !== 'object') { quantumCircuitPanel.style.display='none'; return; }
-    const gates = Array.isArray(data.gates) ? data.gates : [];
+    // Backend gates name qubits by wire label ('a', 'r[0]'); map them onto row indices.
+    const wires = Array.isArray(data.wires) ? data.wires : [];
+    const gates = (Array.isArray(data.gates) ? data.gates : []).map(g => ({ name: g.name || g.gate, qubits: (Array.isArray(g.qubits) ? g.qubits : []).map(q => typeof q === 'number' ? q : wires.indexOf(q)).filter(i => i >= 0) }));
     if (!gates.length) { quantumCircuitPanel.style.display='none'; return; }
-    const qcount = data.qubits || Math.max(0, ...gates.map(g=> (Array.isArray(g.qubits)?Math.max(...g.qubits):-1))) + 1;
-    const lines = Array.from({ length: qcount }, (_,i)=>({ label:`q${i}: `, cells:[] }));
+    const qcount = wires.length || data.qubits || Math.max(0, ...gates.map(g=> (g.qubits.length?Math.max(...g.qubits):-1))) + 1;
+    const lines = Array.from({ length: qcount }, (_,i)=>({ label: wires[i] ? `${wires[i]}: ` : `q${i}: `, cells:[] }));
     // Assign each gate to a timeline column sequentially
     gates.forEach((g, idx) => {
       const name = (g.name||'?').toUpperCase();
@@ -647,14 +681,14 @@ async function updateQuantumCircuit(src) {
     const header = '     ' + gates.map((_,i)=> String(i).padEnd(4,' ')).join('');
     const body = lines.map(l => l.label + l.cells.map(c=> c||'    ').join('')).join('\n');
     const legend = '\nLegend: [X] gate, ║ multi-qubit span';
-    circuitText.textContent = header + '\n' + body + legend;
-    drawCircuitCanvas(gates, qcount);
+    circuitText.textContent = data.ascii || (header + '\n' + body + legend);
+    drawCircuitCanvas(gates, qcount, wires);
     quantumCircuitPanel.style.display='block';
   } catch(e) {
     if (quantumCircuitPanel) quantumCircuitPanel.style.display='none';
   }
 }
-function drawCircuitCanvas(gates, qcount){ if(!circuitCanvas) return; const ctx = circuitCanvas.getContext('2d'); if(!ctx) return; circuitCanvas.style.display='block'; const W = circuitCanvas.width = circuitCanvas.clientWidth; const H = circuitCanvas.height = circuitCanvas.clientHeight; ctx.clearRect(0,0,W,H); const margin=20; const rowH=(H-2*margin)/(qcount||1); const colW = Math.max(40, (W-2*margin)/Math.max(1,gates.length)); ctx.strokeStyle='#377'; ctx.lineWidth=1; ctx.font='11px monospace'; ctx.fillStyle='#9cf'; for(let q=0;q<qcount;q++){ const y=margin+q*rowH; ctx.beginPath(); ctx.moveTo(margin,y); ctx.lineTo(W-margin,y); ctx.stroke(); ctx.fillText('q'+q,4,y+4); }
+function drawCircuitCanvas(gates, qcount, wires){ if(!circuitCanvas) return; const ctx = circuitCanvas.getContext('2d'); if(!ctx) return; circuitCanvas.style.display='block'; const W = circuitCanvas.width = circuitCanvas.clientWidth; const H = circuitCanvas.height = circuitCanvas.clientHeight; ctx.clearRect(0,0,W,H); const margin=20; const rowH=(H-2*margin)/(qcount||1); const colW = Math.max(40, (W-2*margin)/Math.max(1,gates.length)); ctx.strokeStyle='#377'; ctx.lineWidth=1; ctx.font='11px monospace'; ctx.fillStyle='#9cf'; for(let q=0;q<qcount;q++){ const y=margin+q*rowH; ctx.beginPath(); ctx.moveTo(margin,y); ctx.lineTo(W-margin,y); ctx.stroke(); ctx.fillText((wires&&wires[q])||('q'+q),4,y+4); }
   gates.forEach((g,i)=>{ const x = margin + i*colW + colW/2; const qs = Array.isArray(g.qubits)? g.qubits.slice().sort((a,b)=>a-b):[]; if(!qs.length) return; if(qs.length===1){ const y = margin + qs[0]*rowH; ctx.fillStyle='#2a8'; ctx.beginPath(); ctx.arc(x,y,10,0,Math.PI*2); ctx.fill(); ctx.fillStyle='#fff'; ctx.fillText((g.name||'?').substr(0,3).toUpperCase(), x-12, y+4); } else { const top = margin + qs[0]*rowH; const bottom = margin + qs[qs.length-1]*rowH; ctx.strokeStyle='#a83'; ctx.lineWidth=3; ctx.beginPath(); ctx.moveTo(x, top); ctx.lineTo(x, bottom); ctx.stroke(); ctx.fillStyle='#a83'; ctx.beginPath(); ctx.arc(x, top, 9,0,Math.PI*2); ctx.arc(x, bottom, 9,0,Math.PI*2); ctx.fill(); ctx.fillStyle='#000'; ctx.fillText((g.name||'?').substr(0,2).toUpperCase(), x-10, top+4); ctx.fillText((g.name||'?').substr(0,2).toUpperCase(), x-10, bottom+4); }
   }); }
 
@@ -710,11 +744,36 @@ async function renameAt(line, column) {
   model.setValue(src.replace(re, newName));
   runDiagnostics(); fetchSymbols();
 }
-function extractFunctionAt(line, column) {
+// Backend edits use 1-based byte columns; Monaco counts UTF-16 units.
+function byteColumn(lineText, column) {
+  return new TextEncoder().encode(lineText.slice(0, column - 1)).length + 1;
+}
+// Let the backend validate and apply a TextEdit list (rename, quick fixes, extract-function)
+// so the buffer matches what it computed. The result lands as one undo step; the returned
+// reverse edits restore the previous text. Returns null when the edits were rejected.
+async function applyBackendEdits(edits) {
+  const model = monacoEditor?.getModel(); if (!model) return null;
+  let applied;
+  try {
+    applied = JSON.parse(await window.__TAURI__.invoke('aeonmi_apply_edits', { source: model.getValue(), editsJson: JSON.stringify(edits) }));
+  } catch (e) { term.writeln(`[Edits rejected: ${e}]`); return null; }
+  monacoEditor.pushUndoStop();
+  model.pushEditOperations([], [{ range: model.getFullModelRange(), text: applied.text }], () => null);
+  monacoEditor.pushUndoStop();
+  runDiagnostics(); fetchSymbols();
+  return applied.reverse;
+}
+async function extractFunctionAt(line, column) {
   // naive: extract current line into function
   const model = monacoEditor.getModel(); if (!model) return;
   const text = model.getLineContent(line).trim(); if (!text) { term.writeln('[Empty line]'); return; }
   const name = prompt('Function name:', 'extracted_part'); if (!name) return;
+  const lineText = model.getLineContent(line);
+  const edits = [
+    { line, column: 1, endLine: line, endColumn: byteColumn(lineText, lineText.length + 1), newText: `${name}();` },
+    { line: 1, column: 1, endLine: 1, endColumn: 1, newText: `fn ${name}() {\n  ${text}\n}\n\n` }
+  ];
+  if (window.__TAURI__?.invoke) { await applyBackendEdits(edits); return; }
   model.applyEdits([
     { range: new monaco.Range(line,1,line, model.getLineMaxColumn(line)), text: `${name}();` },
     { range: new monaco.Range(1,1,1,1), text: `fn ${name}() {\n  ${text}\n}\n\n` }
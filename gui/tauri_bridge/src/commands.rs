@@ -2,15 +2,19 @@ use std::path::PathBuf;
 use aeonmi_project::commands::compile::compile_pipeline;
 use aeonmi_project::cli::EmitKind;
 use aeonmi_project::core::lexer::{Lexer, LexerError};
-use aeonmi_project::core::parser::{Parser as AeParser, ParserError};
-use aeonmi_project::core::semantic_analyzer::{SemanticAnalyzer, Severity};
+use aeonmi_project::core::parser::Parser as AeParser;
+use aeonmi_project::core::semantic_analyzer::{function_names, SemanticAnalyzer, SemanticDiagnostic};
+use aeonmi_project::core::code_actions::QuickFix;
+use aeonmi_project::core::error_index;
 use aeonmi_project::core::symbols::{collect_symbols};
 use aeonmi_project::core::code_actions::suggest_actions;
+use aeonmi_project::core::preview::emit_preview;
 use aeonmi_project::core::types::TypeContext;
-use aeonmi_project::core::incremental::{parse_or_cached, parse_or_partial, DIAG_CACHE, LAST_REPLACED_INDEX, TYPE_DIAG_CACHE, CALL_GRAPH_METRICS, VAR_DEPS, record_reinfer_event, persist_metrics, record_function_infer, get_deep_propagation, record_savings};
-use aeonmi_project::core::quantum_extract::{extract_circuit, circuit_to_json, circuit_to_pseudo_qasm};
+use aeonmi_project::core::analysis_budget::{AnalysisBudget, Truncation};
+use aeonmi_project::core::incremental::{lock_cache, parse_or_partial, DIAG_CACHE, LAST_REPLACED_INDEX, LAST_TRIVIA_SHIFT, TYPE_DIAG_CACHE, CALL_GRAPH_METRICS, VAR_DEPS, record_reinfer_event, persist_metrics, record_function_infer, record_function_site, get_deep_propagation, record_savings, compute_var_deps_items, quantum_circuit_cached};
+use aeonmi_project::core::quantum_extract::{circuit_to_ascii, circuit_to_json, circuit_to_pseudo_qasm, circuit_from_json, circuit_to_ai};
 use aeonmi_project::core::ast::ASTNode;
-use aeonmi_project::core::incremental::{snapshot_call_graph_metrics, VAR_DEPS, FUNCTION_METRICS, get_deep_propagation, SAVINGS_METRICS};
+use aeonmi_project::core::incremental::{snapshot_call_graph_metrics, VAR_DEPS, FUNCTION_METRICS, get_deep_propagation, SAVINGS_METRICS, QUANTUM_CIRCUIT_CACHE};
 
 #[tauri::command]
 pub fn aeonmi_compile_ai(input: String, out: Option<String>) -> Result<String, String> {
@@ -30,16 +34,67 @@ pub fn aeonmi_compile_ai(input: String, out: Option<String>) -> Result<String, S
 
 #[tauri::command]
 pub fn aeonmi_run_native(input: String) -> Result<String, String> {
-    std::env::set_var("AEONMI_NATIVE", "1");
-    aeonmi_project::commands::run::main_with_opts(PathBuf::from(&input), None, false, false)
+    use aeonmi_project::commands::run::{main_with_opts, ExecutionMode};
+    main_with_opts(PathBuf::from(&input), None, false, false, ExecutionMode::Native)
         .map_err(|e| e.to_string())?;
     Ok("ok".into())
 }
 
+#[tauri::command]
+pub fn aeonmi_run_source(source: String) -> Result<serde_json::Value, String> {
+    let run = aeonmi_project::commands::run::run_source_captured(&source)?;
+    let events: Vec<serde_json::Value> = run.events.iter().map(|e| serde_json::json!({"text": e.text, "line": e.line, "column": e.column})).collect();
+    Ok(serde_json::json!({"output": run.output, "events": events}))
+}
+
+/// Emitted JS or canonical AI for the preview pane, with per-function line mappings for scroll-sync.
+/// `kind` is "js" or "ai". Compile errors return `{ok:false, diagnostics}` instead of partial output.
+#[tauri::command]
+pub fn aeonmi_emit_preview(source: String, kind: String) -> Result<serde_json::Value, String> {
+    let kind = match kind.to_ascii_lowercase().as_str() { "js" => EmitKind::Js, "ai" => EmitKind::Ai, other => return Err(format!("unknown emit kind '{other}' (expected js or ai)")) };
+    match emit_preview(&source, kind) {
+        Ok(p) => Ok(serde_json::json!({"ok": true, "text": p.text, "mappings": p.mappings})),
+        Err(diagnostics) => Ok(serde_json::json!({"ok": false, "diagnostics": diagnostics})),
+    }
+}
+
+#[derive(serde::Serialize)]
+struct Related { message: String, line: usize, column: usize, endLine: usize, endColumn: usize }
+#[derive(serde::Serialize)]
+struct Diag {
+    message: String, line: usize, column: usize, endLine: usize, endColumn: usize, severity: String, rule: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")] code: Option<&'static str>,
+    #[serde(rename = "relatedInformation", skip_serializing_if = "Vec::is_empty")] related: Vec<Related>,
+    #[serde(rename = "quickFix", skip_serializing_if = "Option::is_none")] quick_fix: Option<QuickFix>,
+}
+fn from_sema(d: &SemanticDiagnostic) -> Diag {
+    Diag {
+        message: d.message.clone(), line: d.line, column: d.column, endLine: d.line, endColumn: d.column + d.len,
+        severity: d.severity.as_str().into(), rule: d.rule, code: d.rule,
+        related: d.related.iter().map(|r| Related { message: r.message.clone(), line: r.line, column: r.column, endLine: r.line, endColumn: r.column + r.len }).collect(),
+        quick_fix: d.fix.clone(),
+    }
+}
+
+/// Time a keystroke's full analysis may take before `aeonmi_diagnostics` answers with what it
+/// has; the editor then asks `aeonmi_diagnostics_complete` for the rest in the background.
+const DIAGNOSTICS_BUDGET: std::time::Duration = std::time::Duration::from_millis(150);
+
+/// Analyze the whole of `ast` (under `budget`, if any) and rebuild the diagnostics cache from it.
+fn full_analysis(ast: &ASTNode, budget: Option<AnalysisBudget>) -> (Vec<SemanticDiagnostic>, Option<Truncation>) {
+    let mut sema = SemanticAnalyzer::new();
+    if let Some(b) = budget { sema = sema.with_budget(b); }
+    let sema_diags = sema.analyze_with_spans(ast);
+    let mut cache = lock_cache("diagnostics", &DIAG_CACHE);
+    cache.per_node = if let ASTNode::Program(items) = ast { items.iter().map(|_| Vec::new()).collect() } else { Vec::new() };
+    cache.reported = sema_diags.clone();
+    (sema_diags, sema.truncation().cloned())
+}
+
+/// Diagnostics for `source`. A full analysis that runs past [`DIAGNOSTICS_BUDGET`] returns the
+/// diagnostics found so far plus an `info` note, with `truncated` set to where it stopped.
 #[tauri::command]
 pub fn aeonmi_diagnostics(source: String) -> Result<serde_json::Value, String> {
-    #[derive(serde::Serialize)]
-    struct Diag { message: String, line: usize, column: usize, endLine: usize, endColumn: usize, severity: String }
     let mut lexer = Lexer::from_str(&source);
     let tokens = match lexer.tokenize() { Ok(t)=>t, Err(e)=> {
             let (line, col) = match e {
@@ -49,51 +104,76 @@ pub fn aeonmi_diagnostics(source: String) -> Result<serde_json::Value, String> {
                 | LexerError::InvalidQubitLiteral(_, l, c)
                 | LexerError::UnterminatedComment(l, c) => (l, c),
                 _ => (0,0)
-            }; return Ok(serde_json::json!({"diagnostics": [Diag{ message: e.to_string(), line, column: col, endLine: line, endColumn: col+1, severity: "error".into() }]})); }};
+            }; return Ok(serde_json::json!({"diagnostics": [Diag{ message: e.to_string(), line, column: col, endLine: line, endColumn: col+1, severity: "error".into(), rule: None, code: Some(error_index::LEX_ERROR), related: Vec::new(), quick_fix: None }]})); }};
     // Incremental: attempt partial reparse; fallback to cached/full parse
     let ast_opt: Option<(ASTNode,bool)> = match parse_or_partial(&source) { Ok(t)=>Some(t), Err(_)=>None };
+    if ast_opt.is_some() && lock_cache("trivia_shift", &LAST_TRIVIA_SHIFT).is_some() {
+        // Only whitespace/comments changed: the cached diagnostics were already moved to their new lines
+        let cache = lock_cache("diagnostics", &DIAG_CACHE);
+        return Ok(serde_json::json!({"diagnostics": cache.reported.iter().map(from_sema).collect::<Vec<_>>()}));
+    }
     let mut diags: Vec<Diag> = Vec::new();
+    let mut truncated: Option<Truncation> = None;
     if let Some((ast, partial)) = ast_opt {
             if partial {
                 // Only re-analyze replaced node if available
-                let replaced = *LAST_REPLACED_INDEX.lock().unwrap();
+                let replaced = *lock_cache("replaced_index", &LAST_REPLACED_INDEX);
                 if let (Some(r), ASTNode::Program(items)) = (replaced, &ast) {
                     // Ensure diag cache sized
                     {
-                        let mut cache = DIAG_CACHE.lock().unwrap();
+                        let mut cache = lock_cache("diagnostics", &DIAG_CACHE);
                         if cache.per_node.len() != items.len() { cache.per_node = vec![Vec::new(); items.len()]; }
                         // Recompute diagnostics for node r only
-                        let mut sema = SemanticAnalyzer::new();
+                        // Calls from node r may target functions declared anywhere in the file
+                        let mut sema = SemanticAnalyzer::new().with_known_functions(function_names(&ast));
                         let node_diags = sema.analyze_with_spans(&items[r]);
                         cache.per_node[r] = node_diags;
                         // Merge all cached diags
-                        for vecd in &cache.per_node { for d in vecd { diags.push(Diag { message: d.message.clone(), line: d.line, column: d.column, endLine: d.line, endColumn: d.column + d.len, severity: (if d.severity == Severity::Warning {"warning"} else {"error"}).into() }); } }
+                        for vecd in &cache.per_node { for d in vecd { diags.push(from_sema(d)); } }
+                        cache.reported = cache.per_node.iter().flatten().cloned().collect();
                     }
                 } else {
                     // Fallback full analysis
-                    let mut sema = SemanticAnalyzer::new();
-                    let sema_diags = sema.analyze_with_spans(&ast);
-                    let mut cache = DIAG_CACHE.lock().unwrap();
-                    cache.per_node = if let ASTNode::Program(items) = &ast { items.iter().map(|_| Vec::new()).collect() } else { Vec::new() };
-                    for d in sema_diags { diags.push(Diag { message: d.message, line: d.line, column: d.column, endLine: d.line, endColumn: d.column + d.len, severity: (if d.severity == Severity::Warning {"warning"} else {"error"}).into() }); }
+                    let (sema_diags, t) = full_analysis(&ast, Some(AnalysisBudget::new(DIAGNOSTICS_BUDGET)));
+                    diags.extend(sema_diags.iter().map(from_sema));
+                    truncated = t;
                 }
             } else {
                 // Full analysis (cache rebuild)
-                let mut sema = SemanticAnalyzer::new();
-                let sema_diags = sema.analyze_with_spans(&ast);
-                let mut cache = DIAG_CACHE.lock().unwrap();
-                cache.per_node = if let ASTNode::Program(items) = &ast { items.iter().map(|_| Vec::new()).collect() } else { Vec::new() };
-                for d in sema_diags { diags.push(Diag { message: d.message, line: d.line, column: d.column, endLine: d.line, endColumn: d.column + d.len, severity: (if d.severity == Severity::Warning {"warning"} else {"error"}).into() }); }
+                let (sema_diags, t) = full_analysis(&ast, Some(AnalysisBudget::new(DIAGNOSTICS_BUDGET)));
+                diags.extend(sema_diags.iter().map(from_sema));
+                truncated = t;
             }
     } else {
         // Fallback full parse path for error reporting
         let mut parser = AeParser::new(tokens.clone());
         match parser.parse() {
-            Ok(ast) => { let mut sema = SemanticAnalyzer::new(); for d in sema.analyze_with_spans(&ast) { diags.push(Diag { message: d.message, line: d.line, column: d.column, endLine: d.line, endColumn: d.column + d.len, severity: (if d.severity == Severity::Warning {"warning"} else {"error"}).into() }); } }
-            Err(ParserError { message, line, column }) => { diags.push(Diag { message: format!("Parsing error: {message}"), line, column, endLine: line, endColumn: column+1, severity: "error".into() }); }
+            Ok(ast) => {
+                let mut sema = SemanticAnalyzer::new().with_budget(AnalysisBudget::new(DIAGNOSTICS_BUDGET));
+                for d in &sema.analyze_with_spans(&ast) { diags.push(from_sema(d)); }
+                truncated = sema.truncation().cloned();
+            }
+            Err(e) => {
+                let related = e.related.iter().map(|r| Related { message: r.message.clone(), line: r.line, column: r.column, endLine: r.line, endColumn: r.column + r.len }).collect();
+                let message = match &e.detail.hint { Some(h) => format!("Parsing error: {} (hint: {h})", e.summary()), None => format!("Parsing error: {}", e.summary()) };
+                diags.push(Diag { message, line: e.line, column: e.column, endLine: e.line, endColumn: e.column+1, severity: "error".into(), rule: None, code: Some(error_index::PARSE_ERROR), related, quick_fix: None });
+            }
         }
     }
-    Ok(serde_json::json!({"diagnostics": diags}))
+    match truncated {
+        Some(t) => Ok(serde_json::json!({"diagnostics": diags, "truncated": {"analyzed": t.analyzed, "total": t.total, "elapsedMs": t.elapsed_ms}})),
+        None => Ok(serde_json::json!({"diagnostics": diags})),
+    }
+}
+
+/// The rest of a truncated [`aeonmi_diagnostics`]: `source` analyzed without a budget, off the
+/// UI thread. Also refreshes the diagnostics cache, so trivia-only edits serve the full list.
+#[tauri::command(async)]
+pub fn aeonmi_diagnostics_complete(source: String) -> Result<serde_json::Value, String> {
+    let tokens = Lexer::from_str(&source).tokenize().map_err(|e| e.to_string())?;
+    let ast = AeParser::new(tokens).parse().map_err(|e| e.to_string())?;
+    let (sema_diags, _) = full_analysis(&ast, None);
+    Ok(serde_json::json!({"diagnostics": sema_diags.iter().map(from_sema).collect::<Vec<_>>()}))
 }
 
 #[tauri::command]
@@ -137,9 +217,11 @@ pub fn aeonmi_quantum_run(source: String) -> Result<String, String> {
     }).to_string())
 }
 
+/// The `aeonmi info --json` report, so the GUI can hide panels for features this build lacks.
 #[tauri::command]
 pub fn aeonmi_version() -> Result<String, String> {
-    Ok(env!("CARGO_PKG_VERSION").to_string())
+    let info = aeonmi_project::core::build_info::BuildInfo::current(aeonmi_project::config::default_config_path().as_deref());
+    serde_json::to_string(&info).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -165,10 +247,14 @@ pub fn aeonmi_code_actions(source: String) -> Result<String, String> {
 #[tauri::command]
 pub fn aeonmi_types(source: String) -> Result<String, String> {
     let (ast, partial) = parse_or_partial(&source).map_err(|e| e)?;
+    if lock_cache("trivia_shift", &LAST_TRIVIA_SHIFT).is_some() {
+        // Trivia-only edit: serve the shifted cache instead of inferring over the unshifted AST
+        return Ok(serde_json::to_string(&lock_cache("type_diagnostics", &TYPE_DIAG_CACHE).reported).unwrap());
+    }
     if partial {
-        let replaced = *LAST_REPLACED_INDEX.lock().unwrap();
+        let replaced = *lock_cache("replaced_index", &LAST_REPLACED_INDEX);
         if let (Some(r), ASTNode::Program(items)) = (replaced, &ast) {
-            let mut cache = TYPE_DIAG_CACHE.lock().unwrap();
+            let mut cache = lock_cache("type_diagnostics", &TYPE_DIAG_CACHE);
             if cache.per_node.len() != items.len() { cache.per_node = vec![Vec::new(); items.len()]; }
             // Recompute only that node with a fresh TypeContext in a Program wrapper (to keep structure)
             let mut ctx = TypeContext::new();
@@ -188,32 +274,7 @@ pub fn aeonmi_types(source: String) -> Result<String, String> {
                     }
                 }
                 // Variable dependency collection (reads/writes) per function
-                {
-                    use aeonmi_project::core::ast::ASTNode as N;
-                    let mut var_reads: std::collections::HashMap<String, std::collections::HashSet<usize>> = std::collections::HashMap::new();
-                    let mut var_writes: std::collections::HashMap<String, std::collections::HashSet<usize>> = std::collections::HashMap::new();
-                    fn walk(idx: usize, n: &N, reads: &mut std::collections::HashMap<String, std::collections::HashSet<usize>>, writes: &mut std::collections::HashMap<String, std::collections::HashSet<usize>>) {
-                        match n {
-                            N::Assignment { name, value, .. } => { writes.entry(name.clone()).or_default().insert(idx); walk(idx, value, reads, writes); },
-                            N::VariableDecl { name, value, .. } => { writes.entry(name.clone()).or_default().insert(idx); walk(idx, value, reads, writes); },
-                            N::Identifier(name) | N::IdentifierSpanned { name, .. } => { reads.entry(name.clone()).or_default().insert(idx); },
-                            N::Function { body, .. } => { for c in body { walk(idx, c, reads, writes); } },
-                            N::Block(b) => { for c in b { walk(idx, c, reads, writes); } },
-                            N::If { condition, then_branch, else_branch } => { walk(idx, condition, reads, writes); walk(idx, then_branch, reads, writes); if let Some(e)=else_branch { walk(idx, e, reads, writes); } },
-                            N::While { condition, body } => { walk(idx, condition, reads, writes); walk(idx, body, reads, writes); },
-                            N::For { init, condition, increment, body } => { if let Some(i)=init { walk(idx, i, reads, writes); } if let Some(c)=condition { walk(idx, c, reads, writes); } if let Some(inc)=increment { walk(idx, inc, reads, writes); } walk(idx, body, reads, writes); },
-                            N::BinaryExpr { left, right, .. } => { walk(idx, left, reads, writes); walk(idx, right, reads, writes); },
-                            N::UnaryExpr { expr, .. } => { walk(idx, expr, reads, writes); },
-                            N::Call { callee, args } => { walk(idx, callee, reads, writes); for a in args { walk(idx, a, reads, writes); } },
-                            N::Return(e) | N::Log(e) => { walk(idx, e, reads, writes); },
-                            _ => {}
-                        }
-                    }
-                    for (idx, node) in items.iter().enumerate() { if let N::Function { body, .. } = node { for c in body { walk(idx, c, &mut var_reads, &mut var_writes); } } }
-                    let mut vd = VAR_DEPS.lock().unwrap(); vd.reads.clear(); vd.writes.clear();
-                    for (k,v) in var_reads { vd.reads.insert(k, v); }
-                    for (k,v) in var_writes { vd.writes.insert(k, v); }
-                }
+                *lock_cache("var_deps", &VAR_DEPS) = compute_var_deps_items(items);
                 // Reverse edges for call-based deps
                 let mut rev: Vec<Vec<usize>> = vec![Vec::new(); items.len()];
                 for (i, outs) in calls.iter().enumerate() { for &t in outs { rev[t].push(i); } }
@@ -223,7 +284,7 @@ pub fn aeonmi_types(source: String) -> Result<String, String> {
                     for &caller in &rev[*changed_idx] { to_reinfer.insert(caller); }
                 }
                 // Variable-level invalidation: functions that read vars written by changed function
-                let vd = VAR_DEPS.lock().unwrap().clone();
+                let vd = lock_cache("var_deps", &VAR_DEPS).clone();
                 // Identify variables written by changed function
                 let mut written: std::collections::HashSet<String> = std::collections::HashSet::new();
                 for (var, writers) in vd.writes.iter() { if writers.contains(&r) { written.insert(var.clone()); } }
@@ -237,12 +298,12 @@ pub fn aeonmi_types(source: String) -> Result<String, String> {
                 let mut reinfer_count = 0usize;
                 let mut partial_elapsed_ns: u128 = 0;
                 let reinfer_indices: Vec<usize> = to_reinfer.iter().cloned().collect();
-                for idx in &reinfer_indices { if let ASTNode::Function { .. } = &items[*idx] { let start = std::time::Instant::now(); let mut dep_ctx = TypeContext::new(); dep_ctx.infer_program(&ASTNode::Program(vec![items[*idx].clone()])); let dur = start.elapsed().as_nanos(); record_function_infer(*idx, dur); cache.per_node[*idx] = dep_ctx.diags.clone(); reinfer_count+=1; partial_elapsed_ns += dur; } }
+                for idx in &reinfer_indices { if let ASTNode::Function { name, line, .. } = &items[*idx] { let start = std::time::Instant::now(); let mut dep_ctx = TypeContext::new(); dep_ctx.infer_program(&ASTNode::Program(vec![items[*idx].clone()])); let dur = start.elapsed().as_nanos(); record_function_infer(*idx, dur); record_function_site(*idx, name, None, *line); cache.per_node[*idx] = dep_ctx.diags.clone(); reinfer_count+=1; partial_elapsed_ns += dur; } }
                 if reinfer_count>0 { record_reinfer_event(reinfer_count); }
                 // After recording function metrics, compute refined savings estimate:
                 // estimated_full = sum(actual for changed+reinferred) + sum(avg for untouched)
                 {
-                    let fm = FUNCTION_METRICS.lock().unwrap();
+                    let fm = lock_cache("function_metrics", &FUNCTION_METRICS);
                     // estimated full: for each defined function if it was reinferred use its actual dur this cycle (approx last_ns), else its average cost so far (avg_ns) or last_ns if 0 runs
                     let mut est_full: u128 = 0;
                     let defined: Vec<usize> = items.iter().enumerate().filter_map(|(i,n)| match n { ASTNode::Function { .. } => Some(i), _=>None }).collect();
@@ -255,23 +316,24 @@ pub fn aeonmi_types(source: String) -> Result<String, String> {
                 }
                 // Update metrics (functions, edges, variable edges)
                 {
-                    let mut metrics = CALL_GRAPH_METRICS.lock().unwrap();
+                    let mut metrics = lock_cache("call_graph", &CALL_GRAPH_METRICS);
                     metrics.functions = name_by_index.iter().filter(|o| o.is_some()).count();
                     metrics.edges = calls.iter().map(|v| v.len()).sum();
                     // variable edges: total function reads + writes unique pairs
-                    let vd = VAR_DEPS.lock().unwrap();
+                    let vd = lock_cache("var_deps", &VAR_DEPS);
                     metrics.variable_edges = vd.reads.values().map(|s| s.len()).sum::<usize>() + vd.writes.values().map(|s| s.len()).sum::<usize>();
                 }
                 persist_metrics();
             }
             let merged: Vec<_> = cache.per_node.iter().flat_map(|v| v.clone()).collect();
+            cache.reported = merged.clone();
             return Ok(serde_json::to_string(&merged).unwrap());
         }
     }
     let mut ctx = TypeContext::new(); ctx.infer_program(&ast);
     // Rebuild full cache
     if let ASTNode::Program(items) = &ast {
-        let mut cache = TYPE_DIAG_CACHE.lock().unwrap(); cache.per_node = vec![Vec::new(); items.len()];
+        let mut cache = lock_cache("type_diagnostics", &TYPE_DIAG_CACHE); cache.per_node = vec![Vec::new(); items.len()];
         // Rebuild call graph & var deps fully
         let mut name_by_index: Vec<Option<String>> = Vec::with_capacity(items.len());
         for node in items.iter() { if let ASTNode::Function { name, .. } = node { name_by_index.push(Some(name.clone())); } else { name_by_index.push(None); } }
@@ -279,90 +341,77 @@ pub fn aeonmi_types(source: String) -> Result<String, String> {
         for (idx, opt) in name_by_index.iter().enumerate() { if let Some(n)=opt { index_by_name.insert(n.clone(), idx); } }
         let mut calls: Vec<Vec<usize>> = vec![Vec::new(); items.len()];
         for (idx, node) in items.iter().enumerate() { if let ASTNode::Function { body, .. } = node { collect_calls(body, &index_by_name, &mut calls[idx]); } }
-        // variable deps optimized walk
-        use aeonmi_project::core::ast::ASTNode as N;
-        let mut var_reads: std::collections::HashMap<String, std::collections::HashSet<usize>> = std::collections::HashMap::new();
-        let mut var_writes: std::collections::HashMap<String, std::collections::HashSet<usize>> = std::collections::HashMap::new();
-        fn walk(idx: usize, n: &N, reads: &mut std::collections::HashMap<String, std::collections::HashSet<usize>>, writes: &mut std::collections::HashMap<String, std::collections::HashSet<usize>>) {
-            match n {
-                N::Assignment { name, value, .. } => { writes.entry(name.clone()).or_default().insert(idx); walk(idx, value, reads, writes); },
-                N::VariableDecl { name, value, .. } => { writes.entry(name.clone()).or_default().insert(idx); walk(idx, value, reads, writes); },
-                N::Identifier(name) | N::IdentifierSpanned { name, .. } => { reads.entry(name.clone()).or_default().insert(idx); },
-                N::Function { body, .. } => { for c in body { walk(idx, c, reads, writes); } },
-                N::Block(b) => { for c in b { walk(idx, c, reads, writes); } },
-                N::If { condition, then_branch, else_branch } => { walk(idx, condition, reads, writes); walk(idx, then_branch, reads, writes); if let Some(e)=else_branch { walk(idx, e, reads, writes); } },
-                N::While { condition, body } => { walk(idx, condition, reads, writes); walk(idx, body, reads, writes); },
-                N::For { init, condition, increment, body } => { if let Some(i)=init { walk(idx, i, reads, writes); } if let Some(c)=condition { walk(idx, c, reads, writes); } if let Some(inc)=increment { walk(idx, inc, reads, writes); } walk(idx, body, reads, writes); },
-                N::BinaryExpr { left, right, .. } => { walk(idx, left, reads, writes); walk(idx, right, reads, writes); },
-                N::UnaryExpr { expr, .. } => { walk(idx, expr, reads, writes); },
-                N::Call { callee, args } => { walk(idx, callee, reads, writes); for a in args { walk(idx, a, reads, writes); } },
-                N::Return(e) | N::Log(e) => { walk(idx, e, reads, writes); },
-                _ => {}
-            }
-        }
-        for (idx, node) in items.iter().enumerate() { if let N::Function { body, .. } = node { for c in body { walk(idx, c, &mut var_reads, &mut var_writes); } } }
+        // variable deps (scope-aware: locals and params never create edges)
+        *lock_cache("var_deps", &VAR_DEPS) = compute_var_deps_items(items);
         {
-            let mut vd = VAR_DEPS.lock().unwrap(); vd.reads.clear(); vd.writes.clear();
-            for (k,v) in var_reads { vd.reads.insert(k, v); }
-            for (k,v) in var_writes { vd.writes.insert(k, v); }
-        }
-        {
-            let mut metrics = CALL_GRAPH_METRICS.lock().unwrap();
+            let mut metrics = lock_cache("call_graph", &CALL_GRAPH_METRICS);
             metrics.functions = name_by_index.iter().filter(|o| o.is_some()).count();
             metrics.edges = calls.iter().map(|v| v.len()).sum();
-            let vd = VAR_DEPS.lock().unwrap();
+            let vd = lock_cache("var_deps", &VAR_DEPS);
             metrics.variable_edges = vd.reads.values().map(|s| s.len()).sum::<usize>() + vd.writes.values().map(|s| s.len()).sum::<usize>();
         }
         persist_metrics();
     }
+    lock_cache("type_diagnostics", &TYPE_DIAG_CACHE).reported = ctx.diags.clone();
     Ok(serde_json::to_string(&ctx.diags).unwrap())
 }
 
 #[tauri::command]
 pub fn aeonmi_quantum_circuit(source: String) -> Result<String, String> {
-    let ast = parse_or_cached(&source).map_err(|e| e)?;
-    let circ = extract_circuit(&ast);
-    Ok(serde_json::to_string(&circ).unwrap())
+    // Re-extracts only the top-level items changed since the last preview.
+    let circ = quantum_circuit_cached(&source)?;
+    let mut v = serde_json::to_value(&circ).unwrap();
+    v["ascii"] = serde_json::Value::String(circuit_to_ascii(&circ));
+    Ok(v.to_string())
 }
 
 #[tauri::command]
 pub fn aeonmi_quantum_circuit_export(source: String) -> Result<String, String> {
-    let ast = parse_or_cached(&source).map_err(|e| e)?;
-    let circ = extract_circuit(&ast);
+    let circ = quantum_circuit_cached(&source)?;
     let json = circuit_to_json(&circ);
     let qasm = circuit_to_pseudo_qasm(&circ);
     Ok(serde_json::json!({"json": json, "pseudo_qasm": qasm}).to_string())
 }
 
+/// Circuit JSON (schema v1) back to .ai source; validation errors name the offending op.
+#[tauri::command]
+pub fn aeonmi_quantum_circuit_import(json: String) -> Result<String, String> {
+    let circ = circuit_from_json(&json).map_err(|e| e.to_string())?;
+    Ok(circuit_to_ai(&circ))
+}
+
+/// Starter circuit templates with their parameters (name, range, default).
+#[tauri::command]
+pub fn quantum_templates_list() -> Result<String, String> {
+    serde_json::to_string(aeonmi_project::core::quantum_templates::TEMPLATES).map_err(|e| e.to_string())
+}
+
+/// `.ai` source for a template; missing params take their defaults.
+#[tauri::command]
+pub fn quantum_template_instantiate(name: String, params: std::collections::BTreeMap<String, usize>) -> Result<String, String> {
+    aeonmi_project::core::quantum_templates::instantiate(&name, &params)
+}
+
+/// Completions at a 1-based (line, column); works on buffers that do not parse.
+#[tauri::command]
+pub fn aeonmi_complete(source: String, line: usize, column: usize) -> Result<String, String> {
+    let items = aeonmi_project::core::completion::complete(&source, line, column);
+    serde_json::to_string(&items).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn aeonmi_rename_symbol(source: String, line: usize, column: usize, new_name: String) -> Result<String, String> {
-    use crate::core::scope_map::ScopeMap;
-    let ast = parse_or_cached(&source).map_err(|e| e)?;
-    let sm = ScopeMap::build(&ast);
-    // Determine original name by scanning occurrences matching (line,column)
-    let mut original: Option<String> = None;
-    for (name, occs) in &sm.symbols { if occs.iter().any(|o| o.line==line && o.column==column) { original = Some(name.clone()); break; } }
-    let Some(orig_name) = original else { return Err("symbol not found at position".into()); };
-    if orig_name == new_name { return Ok(source); }
-    let occs = sm.occurrences_in_same_scope(&orig_name, line, column);
-    if occs.is_empty() { return Err("no occurrences in scope".into()); }
-    // Replace occurrences carefully by line/column editing
-    let mut lines: Vec<String> = source.lines().map(|s| s.to_string()).collect();
-    for (ol, oc, _is_def) in occs {
-        if let Some(line_str) = lines.get_mut(ol.saturating_sub(1)) {
-            // columns are 1-based; ensure slice safety
-            if oc>0 && oc-1 < line_str.len() {
-                // simple word boundary check
-                // Find token starting at oc
-                if line_str[oc-1..].starts_with(&orig_name) {
-                    let before = &line_str[..oc-1];
-                    let after = &line_str[oc-1+orig_name.len()..];
-                    *line_str = format!("{before}{new_name}{after}");
-                }
-            }
-        }
-    }
-    Ok(lines.join("\n"))
+    crate::core::scope_map::rename_symbol(&source, line, column, &new_name)
+}
+
+/// Apply a JSON list of `TextEdit`s to `source` in one step. Returns `{"text", "reverse"}`;
+/// `reverse` applied to `text` restores `source`, for an atomic undo. Overlapping or stale
+/// edits fail with `edit N: ...`, N being the 0-based index of the rejected edit.
+#[tauri::command]
+pub fn aeonmi_apply_edits(source: String, edits_json: String) -> Result<String, String> {
+    let edits: Vec<aeonmi_project::core::code_actions::TextEdit> = serde_json::from_str(&edits_json).map_err(|e| format!("invalid edits: {e}"))?;
+    let applied = aeonmi_project::core::code_actions::apply_edits(&source, &edits).map_err(|e| e.to_string())?;
+    serde_json::to_string(&applied).map_err(|e| e.to_string())
 }
 
 fn function_body_calls(body: &Vec<ASTNode>, target: &str) -> bool {
@@ -380,9 +429,11 @@ fn function_body_calls(body: &Vec<ASTNode>, target: &str) -> bool {
             ASTNode::While { condition, body } => { scan(condition,target,found); scan(body,target,found); }
             ASTNode::For { init, condition, increment, body } => { if let Some(i)=init { scan(i,target,found); } if let Some(c)=condition { scan(c,target,found); } if let Some(inc)=increment { scan(inc,target,found); } scan(body,target,found); }
             ASTNode::Assignment { value, .. } | ASTNode::VariableDecl { value, .. } => scan(value,target,found),
-            ASTNode::Return(e) | ASTNode::Log(e) | ASTNode::UnaryExpr { expr: e, .. } => scan(e,target,found),
+            ASTNode::Return(e) | ASTNode::Log { expr: e, .. } | ASTNode::UnaryExpr { expr: e, .. } => scan(e,target,found),
             ASTNode::BinaryExpr { left, right, .. } => { scan(left,target,found); scan(right,target,found); },
-            ASTNode::Call { .. } | ASTNode::Identifier(_) | ASTNode::IdentifierSpanned { .. } | ASTNode::NumberLiteral(_) | ASTNode::StringLiteral(_) | ASTNode::BooleanLiteral(_) | ASTNode::QuantumOp { .. } | ASTNode::HieroglyphicOp { .. } | ASTNode::Error(_) => {}
+            ASTNode::Member { target: e, .. } => scan(e,target,found),
+            ASTNode::Match { subject, arms, .. } => { scan(subject,target,found); for arm in arms { scan(&arm.body,target,found); } }
+            ASTNode::EnumDecl { .. } | ASTNode::Call { .. } | ASTNode::Identifier(_) | ASTNode::IdentifierSpanned { .. } | ASTNode::NumberLiteral(_) | ASTNode::StringLiteral(_) | ASTNode::BooleanLiteral(_) | ASTNode::QuantumOp { .. } | ASTNode::QubitDecl { .. } | ASTNode::QregDecl { .. } | ASTNode::Index { .. } | ASTNode::HieroglyphicOp { .. } | ASTNode::Error(_) => {}
         }
     }
     for stmt in body { let mut f=false; scan(stmt, target, &mut f); if f { return true; } }
@@ -403,8 +454,9 @@ fn collect_calls(body: &Vec<ASTNode>, index_by_name: &std::collections::HashMap<
             ASTNode::While { condition, body } => { scan(condition,map,out); scan(body,map,out); }
             ASTNode::For { init, condition, increment, body } => { if let Some(i)=init { scan(i,map,out); } if let Some(c)=condition { scan(c,map,out); } if let Some(inc)=increment { scan(inc,map,out); } scan(body,map,out); }
             ASTNode::Assignment { value, .. } | ASTNode::VariableDecl { value, .. } => scan(value,map,out),
-            ASTNode::Return(e) | ASTNode::Log(e) | ASTNode::UnaryExpr { expr: e, .. } => scan(e,map,out),
+            ASTNode::Return(e) | ASTNode::Log { expr: e, .. } | ASTNode::UnaryExpr { expr: e, .. } => scan(e,map,out),
             ASTNode::BinaryExpr { left, right, .. } => { scan(left,map,out); scan(right,map,out); }
+            ASTNode::Match { subject, arms, .. } => { scan(subject,map,out); for arm in arms { scan(&arm.body,map,out); } }
             _ => {}
         }
     }
@@ -414,7 +466,7 @@ fn collect_calls(body: &Vec<ASTNode>, index_by_name: &std::collections::HashMap<
 #[tauri::command]
 pub fn aeonmi_metrics() -> Result<String, String> {
     let m = snapshot_call_graph_metrics();
-    let vd = VAR_DEPS.lock().unwrap();
+    let vd = lock_cache("var_deps", &VAR_DEPS);
     let reads: std::collections::HashMap<String, usize> = vd.reads.iter().map(|(k,v)| (k.clone(), v.len())).collect();
     let writes: std::collections::HashMap<String, usize> = vd.writes.iter().map(|(k,v)| (k.clone(), v.len())).collect();
     Ok(serde_json::json!({
@@ -422,10 +474,13 @@ pub fn aeonmi_metrics() -> Result<String, String> {
         "callEdges": m.edges,
         "variableEdges": m.variable_edges,
         "reinferEvents": m.reinfer_events,
+        "fastLiteralPatches": m.fast_literal_patch,
+        "triviaFastPath": m.trivia_fast_path,
         "varReads": reads,
         "varWrites": writes,
     "deepPropagation": get_deep_propagation(),
-    "functionInference": FUNCTION_METRICS.lock().unwrap().iter().map(|(idx, fm)| (idx.to_string(), serde_json::json!({"runs": fm.runs, "total_ns": fm.total_ns, "last_ns": fm.last_ns, "avg_ns": if fm.runs>0 { fm.total_ns / fm.runs as u128 } else { 0 }}))).collect::<serde_json::Value>(),
-    "savings": { let sm = SAVINGS_METRICS.lock().unwrap(); serde_json::json!({"cumulative_savings_ns": sm.cumulative_savings_ns, "cumulative_partial_ns": sm.cumulative_partial_ns, "cumulative_estimated_full_ns": sm.cumulative_estimated_full_ns}) },
+    "functionInference": lock_cache("function_metrics", &FUNCTION_METRICS).iter().map(|(idx, fm)| (idx.to_string(), serde_json::json!({"runs": fm.runs, "total_ns": fm.total_ns, "last_ns": fm.last_ns, "avg_ns": if fm.runs>0 { fm.total_ns / fm.runs as u128 } else { 0 }}))).collect::<serde_json::Value>(),
+    "quantumExtract": { let qc = lock_cache("quantum_circuit", &QUANTUM_CIRCUIT_CACHE); serde_json::json!({"hits": qc.hits, "misses": qc.misses}) },
+    "savings": { let sm = lock_cache("savings", &SAVINGS_METRICS); serde_json::json!({"cumulative_savings_ns": sm.cumulative_savings_ns, "cumulative_partial_ns": sm.cumulative_partial_ns, "cumulative_estimated_full_ns": sm.cumulative_estimated_full_ns}) },
     }).to_string())
 }
\ No newline at end of file
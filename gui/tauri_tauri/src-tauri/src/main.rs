@@ -4,19 +4,22 @@ use anyhow::Result;
 use std::path::{PathBuf, Path};
 use serde_json::json;
 // import aeonmi tauri bridge commands for direct invoke (diagnostics, compile, etc.)
-use aeonmi_project::gui::tauri_bridge::commands::{aeonmi_compile_ai, aeonmi_run_native, aeonmi_diagnostics, aeonmi_ai, aeonmi_quantum_run, aeonmi_version, aeonmi_symbols, aeonmi_code_actions, aeonmi_types, aeonmi_quantum_circuit, aeonmi_rename_symbol, aeonmi_metrics};
-use aeonmi_project::core::incremental::{load_metrics, reset_metrics_session, reset_metrics_full, set_deep_propagation, get_deep_propagation};
+use aeonmi_project::gui::tauri_bridge::commands::{aeonmi_compile_ai, aeonmi_run_native, aeonmi_run_source, aeonmi_emit_preview, aeonmi_diagnostics, aeonmi_diagnostics_complete, aeonmi_ai, aeonmi_quantum_run, aeonmi_version, aeonmi_symbols, aeonmi_code_actions, aeonmi_types, aeonmi_quantum_circuit, aeonmi_quantum_circuit_import, aeonmi_rename_symbol, aeonmi_apply_edits, aeonmi_complete, aeonmi_metrics, quantum_templates_list, quantum_template_instantiate};
+use aeonmi_project::core::incremental::{cache_health, force_persist_metrics, load_metrics, reset_metrics_session, reset_metrics_full, set_deep_propagation, get_deep_propagation, MetricsRuntimeConfig};
 use std::process::{Command, Stdio};
-use std::fs;
 use tauri;
 use std::sync::{Mutex, Arc};
+use std::sync::atomic::{AtomicBool, Ordering};
 use portable_pty::{CommandBuilder, MasterPty, PtySize};
 use std::io::Write;
 use std::collections::HashMap;
 use once_cell::sync::Lazy;
 use aeonmi_project::core::ai_provider::ProviderRegistry;
 use aeonmi_project::core::api_keys::{set_api_key, get_api_key, delete_api_key};
+use aeonmi_project::ai::key_check::{set_validated, validate_stored};
 use aeonmi_project::core::artifact_cache::{set_cache_logging, cache_stats}; // logging toggle + stats
+use aeonmi_project::core::fs_allow::{FsAccessError, FsAllowList};
+use aeonmi_project::config::project_root;
 
 // We'll reuse the ai registry by depending on the workspace crate if accessible; placeholder simplified dynamic dispatch copied if not.
 
@@ -43,6 +46,8 @@ struct PtyEntry {
     writer: Mutex<Box<dyn std::io::Write + Send>>,
     buffer: Mutex<String>,
     title: Mutex<String>,
+    /// Set by `pty_close` / window close; the reader task exits at its next wake-up.
+    stopped: AtomicBool,
 }
 
 fn save_prefs() {
@@ -75,13 +80,14 @@ fn pty_create(window: tauri::Window, repl: bool, title: Option<String>) -> Resul
     let mut reader = master.try_clone_reader().map_err(|e| e.to_string())?;
     let writer = master.take_writer().ok_or("no writer")?;
     let default_title = if repl { "REPL" } else { if cfg!(windows) { "cmd" } else { "shell" } }.to_string();
-    let entry = Arc::new(PtyEntry { child: Mutex::new(child), master: Mutex::new(master), writer: Mutex::new(writer), buffer: Mutex::new(String::new()), title: Mutex::new(title.unwrap_or(default_title)) });
+    let entry = Arc::new(PtyEntry { child: Mutex::new(child), master: Mutex::new(master), writer: Mutex::new(writer), buffer: Mutex::new(String::new()), title: Mutex::new(title.unwrap_or(default_title)), stopped: AtomicBool::new(false) });
     PTY_REGISTRY.lock().unwrap().insert(id.clone(), entry.clone());
     // Spawn reader task
     let win = window.clone();
+    let reader_entry = entry.clone();
     tokio::spawn(async move {
         let mut buf = [0u8; 1024];
-        loop {
+        while !reader_entry.stopped.load(Ordering::SeqCst) {
             match reader.read(&mut buf) {
                 Ok(n) if n > 0 => {
                     let chunk = String::from_utf8_lossy(&buf[..n]).to_string();
@@ -115,9 +121,15 @@ fn pty_write(id: String, data: String) -> Result<(), String> {
     } else { Err("unknown pty".into()) }
 }
 
+/// Stop the reader task and kill the child (which also unblocks a pending read).
+fn stop_pty(entry: &PtyEntry) {
+    entry.stopped.store(true, Ordering::SeqCst);
+    let _ = entry.child.lock().unwrap().kill();
+}
+
 #[tauri::command]
 fn pty_close(id: String) -> Result<(), String> {
-    if let Some(entry) = PTY_REGISTRY.lock().unwrap().remove(&id) { let _ = entry.child.lock().unwrap().kill(); }
+    if let Some(entry) = PTY_REGISTRY.lock().unwrap().remove(&id) { stop_pty(&entry); }
     Ok(())
 }
 
@@ -176,11 +188,15 @@ fn ai_chat(provider: Option<String>, prompt: String, stream: bool) -> Result<Str
         // streaming not supported via exec fallback yet
     }
     let prov = provider.unwrap_or_default();
-    let mut args = vec!["run","--","ai","chat"]; if !prov.is_empty() { args.push("--provider"); args.push(&prov); } args.push(&prompt);
+    let mut args = vec!["run","--","ai","chat","--json"]; if !prov.is_empty() { args.push("--provider"); args.push(&prov); } args.push(&prompt);
     let (code, stdout, stderr) = run_capture_output(&args).map_err(|e| e)?;
     if code != 0 { return Err(format!("chat exited {code}: {stderr}")); }
+    // `provider` is who answered: with an `[ai] fallback` chain it can differ from `requested`.
+    if let Ok(answer) = serde_json::from_str::<serde_json::Value>(&stdout) {
+        return Ok(json!({"provider": answer["provider"], "requested": prov, "output": answer["reply"], "failed": answer["failed"]}).to_string());
+    }
     let combined = if stdout.trim().is_empty() { stderr } else { stdout };
-    Ok(json!({"provider": prov, "output": combined}).to_string())
+    Ok(json!({"provider": prov, "requested": prov, "output": combined, "failed": []}).to_string())
 }
  
 #[tauri::command]
@@ -206,6 +222,14 @@ fn ai_chat_stream(window: tauri::Window, provider: Option<String>, prompt: Strin
     Ok(())
 }
 
+#[tauri::command]
+fn ai_usage(since: Option<String>) -> Result<String, String> {
+    use aeonmi_project::core::ai_usage::{ledger_path, load, now_secs, parse_since, summarize, PriceTable};
+    let since = since.as_deref().map(parse_since).transpose()?.map(|s| now_secs().saturating_sub(s));
+    let prices = PriceTable::from_config(aeonmi_project::config::default_config_path().as_deref()).map_err(|e| e.to_string())?;
+    Ok(serde_json::to_string(&summarize(&load(&ledger_path()), since, &prices)).unwrap())
+}
+
 #[tauri::command]
 fn aeonmi_quantum_simulate(source: String) -> Result<String, String> {
     // Very small stub: derive number of (pseudo) qubits from distinct identifiers 'q' digits, build uniform or simple entangled state
@@ -291,23 +315,66 @@ fn tauri_compile(path: String, emit: Option<String>) -> Result<String, String> {
     }
 }
 
+// File access from the webview is limited to the project root and directories the user
+// picked in a native dialog; the frontend cannot add roots itself.
+static FS_ALLOW: Lazy<Mutex<FsAllowList>> = Lazy::new(|| {
+    let root = project_root(Path::new("Aeonmi.toml"));
+    Mutex::new(FsAllowList::with_root(&root).unwrap_or_default())
+});
+
+/// Record a directory the user chose in a file dialog. Deliberately not a Tauri command.
+fn fs_allow_root(path: &Path) -> Result<PathBuf, FsAccessError> {
+    FS_ALLOW.lock().unwrap().allow_root(path)
+}
+
 #[tauri::command]
-fn load_file(path: String) -> Result<String, String> {
-    fs::read_to_string(&path).map_err(|e| format!("load error: {e}"))
+fn load_file(path: String) -> Result<String, FsAccessError> {
+    FS_ALLOW.lock().unwrap().read(Path::new(&path))
 }
 
 #[tauri::command]
-fn save_file(path: String, contents: String) -> Result<bool, String> {
-    fs::write(&path, contents).map_err(|e| format!("save error: {e}"))?;
+fn save_file(path: String, contents: String) -> Result<bool, FsAccessError> {
+    FS_ALLOW.lock().unwrap().write(Path::new(&path), &contents)?;
     Ok(true)
 }
 
+/// Native "open" dialog; the picked file's directory becomes an allowed root.
+#[tauri::command]
+async fn fs_open_dialog() -> Result<Option<String>, FsAccessError> {
+    let Some(file) = tauri::api::dialog::blocking::FileDialogBuilder::new().add_filter("Aeonmi", &["ai"]).pick_file() else { return Ok(None) };
+    if let Some(dir) = file.parent() { fs_allow_root(dir)?; }
+    Ok(Some(file.display().to_string()))
+}
+
+/// Native "save as" dialog; the chosen file's directory becomes an allowed root.
 #[tauri::command]
-fn api_key_set(provider: String, key: String) -> Result<(), String> { set_api_key(&provider, &key) }
+async fn fs_save_dialog() -> Result<Option<String>, FsAccessError> {
+    let Some(file) = tauri::api::dialog::blocking::FileDialogBuilder::new().add_filter("Aeonmi", &["ai"]).save_file() else { return Ok(None) };
+    if let Some(dir) = file.parent() { fs_allow_root(dir)?; }
+    Ok(Some(file.display().to_string()))
+}
+
+/// Allowed roots and every save attempted this session.
+#[tauri::command]
+fn fs_audit_log() -> serde_json::Value {
+    let allow = FS_ALLOW.lock().unwrap();
+    json!({ "roots": allow.roots(), "writes": allow.audit_log() })
+}
+
+#[tauri::command]
+fn api_key_set(provider: String, key: String, validate: Option<bool>) -> Result<(), String> {
+    // With `validate`, keys the provider rejects (or that can't be checked right now) are not stored.
+    if validate.unwrap_or(false) { set_validated(&provider, &key) } else { set_api_key(&provider, &key) }
+}
 #[tauri::command]
 fn api_key_get(provider: String) -> Result<Option<String>, String> { Ok(get_api_key(&provider)) }
 #[tauri::command]
 fn api_key_delete(provider: String) -> Result<(), String> { delete_api_key(&provider) }
+#[tauri::command]
+fn api_key_validate(provider: String) -> Result<String, String> {
+    // {"status":"ok"} | {"status":"unauthorized","message":..} | {"status":"network_error","message":..}
+    validate_stored(&provider).map(|status| json!(status).to_string())
+}
 
 #[tauri::command]
 fn cache_logging(enable: bool) -> Result<(), String> { set_cache_logging(enable); Ok(()) }
@@ -315,6 +382,10 @@ fn cache_logging(enable: bool) -> Result<(), String> { set_cache_logging(enable)
 #[tauri::command]
 fn cache_stats_get() -> Result<String, String> { let (entries, bytes) = cache_stats(); Ok(json!({"entries": entries, "bytes": bytes}).to_string()) }
 
+/// Incremental caches cleared after a panic poisoned their lock (`healthy` is false once any was).
+#[tauri::command]
+fn cache_health_get() -> Result<String, String> { serde_json::to_string(&cache_health()).map_err(|e| e.to_string()) }
+
 #[tauri::command]
 fn metrics_reset() -> Result<(), String> { reset_metrics_session(); Ok(()) }
 
@@ -326,6 +397,26 @@ fn metrics_set_deep(enable: bool) -> Result<(), String> { set_deep_propagation(e
 #[tauri::command]
 fn metrics_get_deep() -> Result<bool, String> { Ok(get_deep_propagation()) }
 
+fn metrics_config_json(errors: Vec<String>) -> String {
+    let cfg = MetricsRuntimeConfig::current();
+    json!({"ema_alpha": cfg.ema_alpha, "window": cfg.window, "history_cap": cfg.history_cap, "errors": errors}).to_string()
+}
+
+#[tauri::command]
+fn metrics_config_get() -> Result<String, String> { Ok(metrics_config_json(Vec::new())) }
+
+/// Apply the given knobs (unset ones keep their value; `reset` starts from the defaults) and persist
+/// them to prefs. Out-of-range values change nothing and come back in `errors`.
+#[tauri::command]
+fn metrics_config_set(ema_alpha: Option<u64>, window: Option<usize>, history_cap: Option<usize>, reset: Option<bool>) -> Result<String, String> {
+    let base = if reset.unwrap_or(false) { MetricsRuntimeConfig::default() } else { MetricsRuntimeConfig::current() };
+    let cfg = MetricsRuntimeConfig { ema_alpha: ema_alpha.unwrap_or(base.ema_alpha), window: window.unwrap_or(base.window), history_cap: history_cap.unwrap_or(base.history_cap) };
+    if let Err(errors) = cfg.apply() { return Ok(metrics_config_json(errors)); }
+    cfg.write_prefs(&mut PREFS.lock().unwrap());
+    save_prefs();
+    Ok(metrics_config_json(Vec::new()))
+}
+
 #[tauri::command]
 fn run_js(path: String) -> Result<String, String> {
     // Assumes path already compiled to JS (or is JS). Use node.
@@ -349,11 +440,14 @@ fn main() {
         }
     };
 
+    // Runtime knobs first: loading metrics sizes the savings history by the configured cap.
+    let metrics_cfg = MetricsRuntimeConfig::from_prefs(&PREFS.lock().unwrap());
+    if let Err(errors) = metrics_cfg.apply() { eprintln!("ignoring metrics prefs: {}", errors.join("; ")); }
     // Load persisted metrics early
     load_metrics();
     tauri::Builder::default()
-    .on_window_event(|_w, e| { if let tauri::WindowEvent::CloseRequested { .. } = e { let mut reg = PTY_REGISTRY.lock().unwrap(); for (_id, entry) in reg.drain() { let _ = entry.child.lock().unwrap().kill(); } } })
-    .invoke_handler(tauri::generate_handler![tauri_compile, load_file, save_file, run_js, ai_list_providers, ai_set_provider, ai_chat, ai_chat_stream, aeonmi_compile_ai, aeonmi_run_native, aeonmi_diagnostics, aeonmi_ai, aeonmi_quantum_run, aeonmi_quantum_simulate, aeonmi_version, aeonmi_symbols, aeonmi_code_actions, aeonmi_types, aeonmi_quantum_circuit, aeonmi_rename_symbol, aeonmi_metrics, metrics_reset, metrics_reset_full, metrics_set_deep, metrics_get_deep, pty_create, pty_write, pty_close, pty_resize, pty_list, pty_list_detailed, pty_rename, pty_buffer, pty_export, prefs_get_all, prefs_set, api_key_set, api_key_get, api_key_delete, cache_logging, cache_stats_get])
+    .on_window_event(|_w, e| { if let tauri::WindowEvent::CloseRequested { .. } = e { let mut reg = PTY_REGISTRY.lock().unwrap(); for (_id, entry) in reg.drain() { stop_pty(&entry); } force_persist_metrics(); } })
+    .invoke_handler(tauri::generate_handler![tauri_compile, load_file, save_file, fs_open_dialog, fs_save_dialog, fs_audit_log, run_js, ai_list_providers, ai_set_provider, ai_chat, ai_chat_stream, ai_usage, aeonmi_compile_ai, aeonmi_run_native, aeonmi_run_source, aeonmi_emit_preview, aeonmi_diagnostics, aeonmi_diagnostics_complete, aeonmi_ai, aeonmi_quantum_run, aeonmi_quantum_simulate, aeonmi_version, aeonmi_symbols, aeonmi_code_actions, aeonmi_types, aeonmi_quantum_circuit, aeonmi_quantum_circuit_import, aeonmi_rename_symbol, aeonmi_apply_edits, aeonmi_complete, aeonmi_metrics, quantum_templates_list, quantum_template_instantiate, metrics_reset, metrics_reset_full, metrics_set_deep, metrics_get_deep, metrics_config_get, metrics_config_set, pty_create, pty_write, pty_close, pty_resize, pty_list, pty_list_detailed, pty_rename, pty_buffer, pty_export, prefs_get_all, prefs_set, api_key_set, api_key_get, api_key_delete, api_key_validate, cache_logging, cache_stats_get, cache_health_get])
         .run(context)
         .expect("error while running tauri application");
 }
@@ -26,6 +26,7 @@ const aiListBtn = document.getElementById('ai_list');
 const aiStreamBtn = document.getElementById('ai_stream');
 const aiSetKeyBtn = document.getElementById('ai_set_key');
 const aiShowKeyBtn = document.getElementById('ai_show_key');
+const aiCheckKeyBtn = document.getElementById('ai_check_key');
 const aiDeleteKeyBtn = document.getElementById('ai_delete_key');
 let monacoEditor = null;
 let monacoInstance = null;
@@ -147,21 +148,41 @@ if (window.require) {
   });
 }
 
+// File commands only reach the project and directories picked in a native dialog.
+// A path outside them (or an empty path field) falls back to the dialog, which allows its directory.
+const fsErrorText = (e) => (e && e.message) ? e.message : String(e);
+async function pickPath(dialog) {
+  const picked = await invoke(dialog);
+  if (picked) filepathInput.value = picked;
+  return picked;
+}
+
 loadBtn?.addEventListener('click', async () => {
-  const path = filepathInput.value.trim() || 'examples/hello.ai';
+  let path = filepathInput.value.trim() || await pickPath('fs_open_dialog');
+  if (!path) return;
   try {
-    const data = await invoke('load_file', { path });
+    let data;
+    try { data = await invoke('load_file', { path }); }
+    catch (e) {
+      if (e?.code !== 'outside_allowed_roots' || !(path = await pickPath('fs_open_dialog'))) throw e;
+      data = await invoke('load_file', { path });
+    }
     setSource(data);
     status.textContent = 'loaded';
-  } catch (e) { alert('Load failed: ' + e); }
+  } catch (e) { alert('Load failed: ' + fsErrorText(e)); }
 });
 
 saveBtn?.addEventListener('click', async () => {
-  const path = filepathInput.value.trim() || 'untitled.ai';
+  let path = filepathInput.value.trim() || await pickPath('fs_save_dialog');
+  if (!path) return;
   try {
-    await invoke('save_file', { path, contents: getSource() });
+    try { await invoke('save_file', { path, contents: getSource() }); }
+    catch (e) {
+      if (e?.code !== 'outside_allowed_roots' || !(path = await pickPath('fs_save_dialog'))) throw e;
+      await invoke('save_file', { path, contents: getSource() });
+    }
     status.textContent = 'saved';
-  } catch (e) { alert('Save failed: ' + e); }
+  } catch (e) { alert('Save failed: ' + fsErrorText(e)); }
 });
 
 async function doCompile(ai) {
@@ -247,8 +268,10 @@ aiSendBtn?.addEventListener('click', async () => {
     const respStr = await invoke('ai_chat', { provider: prov, prompt, stream: false });
     let resp = {}; try { resp = JSON.parse(respStr); } catch {}
     const text = resp.output || respStr;
+    const answered = resp.provider || prov;
+    for (const f of resp.failed || []) { aiOutput.textContent += `[${f.provider} failed: ${f.error}]\n`; }
     aiOutput.textContent += text;
-    term.write(`\r\n[ai:${prov}] ${text.replace(/\n/g,'\r\n')}\r\n`);
+    term.write(`\r\n[ai:${answered}] ${text.replace(/\n/g,'\r\n')}\r\n`);
   } catch (e) {
     aiOutput.textContent += 'Error: ' + e;
     term.write(`\r\n[ai-error:${prov}] ${String(e)}\r\n`);
@@ -285,6 +308,11 @@ aiShowKeyBtn?.addEventListener('click', async () => {
   try { const keyOpt = await invoke('api_key_get', { provider: prov }); if (keyOpt) { alert(`Key(${prov}): ${'*'.repeat(Math.max(0,keyOpt.length-4))}${keyOpt.slice(-4)}`); } else { alert('No key stored'); } } catch(e){ alert('Show failed: '+e); }
 });
 
+aiCheckKeyBtn?.addEventListener('click', async () => {
+  const prov = aiProviderSelect.value; if (!prov) return;
+  try { const res = JSON.parse(await invoke('api_key_validate', { provider: prov })); status.textContent = res.status === 'ok' ? `key for ${prov} works` : res.message; } catch(e){ alert('Check failed: '+e); }
+});
+
 aiDeleteKeyBtn?.addEventListener('click', async () => {
   const prov = aiProviderSelect.value; if (!prov) return;
   if (!confirm(`Delete key for ${prov}?`)) return;
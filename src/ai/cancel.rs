@@ -0,0 +1,83 @@
+//! Cancellation for in-flight provider requests.
+//!
+//! A [`CancelToken`] is shared by the caller and the provider. Providers check it before
+//! sending and between chunks; once it is cancelled they stop reading, dropping the response
+//! (which closes the connection and aborts the request), and fail with [`Cancelled`].
+//! The Ctrl-C handler in `main` cancels whichever token is registered with [`set_active`].
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// `Err(Cancelled)` once cancelled.
+    pub fn check(&self) -> anyhow::Result<()> {
+        if self.is_cancelled() {
+            return Err(Cancelled.into());
+        }
+        Ok(())
+    }
+
+    /// Sleep for `d`, waking early (with `Err(Cancelled)`) when cancelled.
+    pub fn sleep(&self, d: Duration) -> anyhow::Result<()> {
+        let end = Instant::now() + d;
+        loop {
+            self.check()?;
+            let now = Instant::now();
+            if now >= end {
+                return Ok(());
+            }
+            std::thread::sleep((end - now).min(Duration::from_millis(10)));
+        }
+    }
+}
+
+/// The request was cancelled by the caller; whatever was streamed so far is a partial reply.
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "request cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+pub fn is_cancelled(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<Cancelled>().is_some()
+}
+
+static ACTIVE: Mutex<Option<CancelToken>> = Mutex::new(None);
+
+/// Register the token Ctrl-C should cancel (`None` once the request is over).
+pub fn set_active(token: Option<CancelToken>) {
+    if let Ok(mut slot) = ACTIVE.lock() {
+        *slot = token;
+    }
+}
+
+/// Cancel the active request, if any; true when there was one.
+pub fn interrupt() -> bool {
+    match ACTIVE.lock().ok().and_then(|slot| slot.clone()) {
+        Some(token) => {
+            token.cancel();
+            true
+        }
+        None => false,
+    }
+}
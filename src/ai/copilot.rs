@@ -1,5 +1,5 @@
 use anyhow::{Result, anyhow, bail};
-use super::AiProvider;
+use super::{transport, AiProvider, ProviderError};
 use std::time::Duration;
 
 #[derive(Default)]
@@ -23,11 +23,10 @@ impl AiProvider for Copilot {
         let trimmed = prompt.trim();
         if trimmed.is_empty() { bail!("empty prompt"); }
         let key = std::env::var("GITHUB_COPILOT_TOKEN")
-            .map_err(|_| anyhow!("GITHUB_COPILOT_TOKEN not set"))?;
-        // Endpoint placeholder; actual Copilot APIs may differ or require websocket; adjust when official public API is used.
-        let endpoint = std::env::var("AEONMI_COPILOT_ENDPOINT").unwrap_or_else(|_| "https://api.githubcopilot.com/v1/chat/completions".to_string());
+            .map_err(|_| ProviderError::MissingKey("GITHUB_COPILOT_TOKEN"))?;
+        let endpoint = transport::copilot_endpoint();
         let req = ChatRequest { messages: vec![ChatMessage { role: "user", content: trimmed }] };
-        let client = reqwest::blocking::Client::builder().timeout(Duration::from_secs(45)).build()?;
+        let client = transport::client(Duration::from_secs(45))?;
         let resp = client.post(&endpoint)
             .bearer_auth(&key)
             .header("Content-Type", "application/json")
@@ -36,7 +35,7 @@ impl AiProvider for Copilot {
         if !resp.status().is_success() {
             let status = resp.status();
             let text = resp.text().unwrap_or_default();
-            bail!("copilot http error {status}: {text}");
+            return Err(ProviderError::Http { provider: "copilot", status: status.as_u16(), body: text }.into());
         }
         let cr: ChatResponse = resp.json()?;
         let content = cr.choices.first()
@@ -1,5 +1,6 @@
 use anyhow::{Result, anyhow, bail};
-use super::AiProvider;
+use super::{transport, AiProvider, ProviderError};
+use crate::core::ai_usage::TokenUsage;
 use std::time::Duration;
 
 #[derive(Default)]
@@ -11,23 +12,27 @@ struct ChatRequest<'a> { model: &'a str, messages: Vec<ChatMessage<'a>> }
 struct ChatMessage<'a> { role: &'a str, content: &'a str }
 
 #[derive(serde::Deserialize, Debug)]
-struct ChatResponse { choices: Vec<Choice> }
+struct ChatResponse { choices: Vec<Choice>, #[serde(default)] usage: Option<Usage> }
 #[derive(serde::Deserialize, Debug)]
 struct Choice { message: ChoiceMessage }
 #[derive(serde::Deserialize, Debug)]
 struct ChoiceMessage { content: String }
+#[derive(serde::Deserialize, Debug)]
+struct Usage { prompt_tokens: u64, completion_tokens: u64 }
 
 impl AiProvider for DeepSeek {
     fn name(&self) -> &'static str { "deepseek" }
-    fn chat(&self, prompt: &str) -> Result<String> {
+    fn model(&self) -> String { std::env::var("AEONMI_DEEPSEEK_MODEL").unwrap_or_else(|_| "deepseek-chat".to_string()) }
+    fn chat(&self, prompt: &str) -> Result<String> { self.chat_with_usage(prompt).map(|(t, _)| t) }
+    fn chat_with_usage(&self, prompt: &str) -> Result<(String, Option<TokenUsage>)> {
         let trimmed = prompt.trim();
         if trimmed.is_empty() { bail!("empty prompt"); }
         let key = std::env::var("DEEPSEEK_API_KEY")
-            .map_err(|_| anyhow!("DEEPSEEK_API_KEY not set"))?;
-        let model = std::env::var("AEONMI_DEEPSEEK_MODEL").unwrap_or_else(|_| "deepseek-chat".to_string());
+            .map_err(|_| ProviderError::MissingKey("DEEPSEEK_API_KEY"))?;
+        let model = self.model();
         let req = ChatRequest { model: &model, messages: vec![ChatMessage { role: "user", content: trimmed }] };
-        let client = reqwest::blocking::Client::builder().timeout(Duration::from_secs(45)).build()?;
-        let resp = client.post("https://api.deepseek.com/chat/completions")
+        let client = transport::client(Duration::from_secs(45))?;
+        let resp = client.post(transport::url(transport::DEEPSEEK, "/chat/completions"))
             .bearer_auth(&key)
             .header("Content-Type", "application/json")
             .json(&req)
@@ -35,13 +40,14 @@ impl AiProvider for DeepSeek {
         if !resp.status().is_success() {
             let status = resp.status();
             let text = resp.text().unwrap_or_default();
-            bail!("deepseek http error {status}: {text}");
+            return Err(ProviderError::Http { provider: "deepseek", status: status.as_u16(), body: text }.into());
         }
         let cr: ChatResponse = resp.json()?;
         let content = cr.choices.first()
             .map(|c| c.message.content.trim().to_string())
             .filter(|s| !s.is_empty())
             .ok_or_else(|| anyhow!("empty response"))?;
-        Ok(content)
+        let usage = cr.usage.map(|u| TokenUsage { prompt_tokens: u.prompt_tokens, completion_tokens: u.completion_tokens });
+        Ok((content, usage))
     }
 }
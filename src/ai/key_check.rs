@@ -0,0 +1,79 @@
+//! Validation pings for API keys: the cheapest authenticated request each provider offers
+//! (its models list, or a one-token completion where there is none), sent through
+//! [`transport`]. A key only ever travels in the `Authorization` header; messages never
+//! quote it, nor the response body (providers echo part of a rejected key there).
+use super::transport;
+use crate::core::api_keys::{get_api_key, set_api_key};
+use serde::Serialize;
+use serde_json::json;
+use std::time::Duration;
+
+/// Outcome of a validation ping.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum KeyStatus {
+    /// The provider accepted the key.
+    Ok,
+    /// The provider answered 401/403.
+    Unauthorized { message: String },
+    /// No answer, or one that says nothing about the key (rate limits, server errors).
+    NetworkError { message: String },
+}
+
+pub const PROVIDERS: [&str; 4] = ["openai", "copilot", "perplexity", "deepseek"];
+
+/// Where to create a key for `provider`.
+pub fn key_page(provider: &str) -> Option<&'static str> {
+    match provider {
+        "openai" => Some("https://platform.openai.com/api-keys"),
+        "copilot" => Some("https://github.com/settings/tokens"),
+        "perplexity" => Some("https://www.perplexity.ai/settings/api"),
+        "deepseek" => Some("https://platform.deepseek.com/api_keys"),
+        _ => None,
+    }
+}
+
+fn known(provider: &str) -> Result<&'static str, String> {
+    key_page(provider).ok_or_else(|| format!("unknown provider `{provider}` (expected one of: {})", PROVIDERS.join(", ")))
+}
+
+/// Ping `provider` with `key`. `Err` only for an unknown provider or an unusable client.
+pub fn validate_key(provider: &str, key: &str) -> Result<KeyStatus, String> {
+    let page = known(provider)?;
+    let client = transport::client(Duration::from_secs(15)).map_err(|e| e.to_string())?;
+    let messages = json!([{ "role": "user", "content": "ping" }]);
+    let req = match provider {
+        "openai" => client.get(transport::url(transport::OPENAI, "/v1/models")),
+        "deepseek" => client.get(transport::url(transport::DEEPSEEK, "/models")),
+        // Perplexity has no models endpoint.
+        "perplexity" => {
+            let model = std::env::var("AEONMI_PERPLEXITY_MODEL").unwrap_or_else(|_| "llama-3.1-sonar-small-chat".to_string());
+            client.post(transport::url(transport::PERPLEXITY, "/chat/completions")).json(&json!({ "model": model, "messages": messages, "max_tokens": 1 }))
+        }
+        _ => client.post(transport::copilot_endpoint()).json(&json!({ "messages": messages, "max_tokens": 1 })),
+    };
+    Ok(match req.bearer_auth(key).send() {
+        Err(e) => KeyStatus::NetworkError { message: format!("could not reach {provider}: {e}") },
+        Ok(resp) if resp.status().is_success() => KeyStatus::Ok,
+        Ok(resp) => match resp.status().as_u16() {
+            code @ (401 | 403) => KeyStatus::Unauthorized { message: format!("{provider} rejected the key (HTTP {code}); create a new one at {page}") },
+            code => KeyStatus::NetworkError { message: format!("{provider} answered HTTP {code}; try again later") },
+        },
+    })
+}
+
+/// Ping `provider` with its stored key.
+pub fn validate_stored(provider: &str) -> Result<KeyStatus, String> {
+    let page = known(provider)?;
+    let key = get_api_key(provider).ok_or_else(|| format!("no key stored for {provider}; create one at {page}"))?;
+    validate_key(provider, &key)
+}
+
+/// Store `key` for `provider` only once the provider has accepted it.
+pub fn set_validated(provider: &str, key: &str) -> Result<(), String> {
+    match validate_key(provider, key)? {
+        KeyStatus::Ok => set_api_key(provider, key),
+        KeyStatus::Unauthorized { message } => Err(format!("key not stored: {message}")),
+        KeyStatus::NetworkError { message } => Err(format!("key not stored, it could not be validated: {message}")),
+    }
+}
@@ -0,0 +1,99 @@
+//! Basic terminal rendering of markdown replies (`ai chat --markdown`): `**bold**`,
+//! `` `inline code` ``, `#` headings and fenced code blocks. Blocks tagged `aeonmi`, `ai` or
+//! `qube` are colored by the lexer; other blocks are left as they are. Without color the
+//! reply comes back unchanged, since raw markdown reads fine as plain text.
+use crate::core::highlight::{self, Class};
+use crate::core::style::{Role, Style};
+
+const AEONMI_FENCES: &[&str] = &["aeonmi", "ai", "qube"];
+
+pub fn render(text: &str, style: Style) -> String {
+    if !style.color {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    let mut fence: Option<(String, String)> = None; // (language, collected code)
+    for line in text.split_inclusive('\n') {
+        let bare = line.trim_end_matches(['\n', '\r']);
+        let ending = &line[bare.len()..];
+        if let Some(lang) = bare.trim_start().strip_prefix("```") {
+            match fence.take() {
+                Some((lang, code)) => {
+                    out.push_str(&render_code(&lang, &code, style));
+                    out.push_str(&style.paint(bare, Role::Dim));
+                }
+                None => {
+                    fence = Some((lang.trim().to_ascii_lowercase(), String::new()));
+                    out.push_str(&style.paint(bare, Role::Dim));
+                }
+            }
+            out.push_str(ending);
+            continue;
+        }
+        match &mut fence {
+            Some((_, code)) => code.push_str(line),
+            None => {
+                out.push_str(&render_line(bare, style));
+                out.push_str(ending);
+            }
+        }
+    }
+    // an unterminated fence (e.g. a truncated reply) still shows its code
+    if let Some((lang, code)) = fence {
+        out.push_str(&render_code(&lang, &code, style));
+    }
+    out
+}
+
+fn render_code(lang: &str, code: &str, style: Style) -> String {
+    if !AEONMI_FENCES.contains(&lang) {
+        return code.to_string();
+    }
+    let Ok(segments) = highlight::segments(code) else {
+        return code.to_string();
+    };
+    segments
+        .into_iter()
+        .map(|(class, text)| match class.map(role_for) {
+            Some(role) => style.paint(&text, role),
+            None => text,
+        })
+        .collect()
+}
+
+fn role_for(class: Class) -> Role {
+    match class {
+        Class::Keyword => Role::Section,
+        Class::Quantum => Role::Quantum,
+        Class::Glyph | Class::Num | Class::Bool => Role::Name,
+        Class::Str => Role::Builtin,
+        Class::Comment => Role::Dim,
+    }
+}
+
+/// One line outside code blocks.
+fn render_line(line: &str, style: Style) -> String {
+    let trimmed = line.trim_start_matches('#');
+    if trimmed.len() < line.len() && trimmed.starts_with(' ') {
+        return style.paint(trimmed.trim_start(), Role::Title);
+    }
+    let mut out = String::new();
+    let mut rest = line;
+    loop {
+        let bold = rest.find("**");
+        let code = rest.find('`');
+        let (start, marker, role) = match (bold, code) {
+            (Some(b), Some(c)) if c < b => (c, "`", Role::Name),
+            (Some(b), _) => (b, "**", Role::Emphasis),
+            (None, Some(c)) => (c, "`", Role::Name),
+            (None, None) => break,
+        };
+        let inner = &rest[start + marker.len()..];
+        let Some(end) = inner.find(marker) else { break };
+        out.push_str(&rest[..start]);
+        out.push_str(&style.paint(&inner[..end], role));
+        rest = &inner[end + marker.len()..];
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,72 @@
+//! Offline provider for tests and demos; enabled by setting `AEONMI_AI_MOCK`.
+//! `AEONMI_AI_MOCK_DELAY_MS` makes it stream word by word with that pause before each chunk.
+//! Code requests (`ai suggest` prompts) get a canned snippet; with `AEONMI_AI_MOCK_BAD_CODE=N`
+//! the first N of them get one that does not parse.
+//! `AEONMI_AI_MOCK_OUTAGE=STATUS` adds a `mock-outage` provider that fails every call with
+//! that HTTP status (streams break off after the first chunk), for trying fallback chains.
+use anyhow::{Result, bail};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use super::{AiProvider, ProviderError};
+use super::cancel::CancelToken;
+use crate::core::ai_usage::TokenUsage;
+
+#[derive(Default)]
+pub struct Mock {
+    /// Artificial latency before every streamed chunk.
+    pub delay: Duration,
+    /// Code replies still to be broken.
+    pub bad_code: AtomicUsize,
+    /// Fail as `mock-outage` with this HTTP status.
+    pub outage: Option<u16>,
+}
+
+/// What the mock answers code requests with.
+pub const SNIPPET: &str = "function dedupe(text) {\n    let out = \"\";\n    for ch in text {\n        let seen = false;\n        for prev in out {\n            if (prev == ch) {\n                seen = true;\n            }\n        }\n        if (!seen) {\n            out = out + ch;\n        }\n    }\n    return out;\n}\n";
+
+impl Mock {
+    pub fn from_env() -> Self {
+        let ms = std::env::var("AEONMI_AI_MOCK_DELAY_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(0);
+        let bad = std::env::var("AEONMI_AI_MOCK_BAD_CODE").ok().and_then(|v| v.parse().ok()).unwrap_or(0);
+        Self { delay: Duration::from_millis(ms), bad_code: AtomicUsize::new(bad), outage: None }
+    }
+
+    fn outage_error(&self, status: u16) -> anyhow::Error {
+        ProviderError::Http { provider: self.name(), status, body: "simulated outage".into() }.into()
+    }
+}
+
+impl AiProvider for Mock {
+    fn name(&self) -> &'static str { if self.outage.is_some() { "mock-outage" } else { "mock" } }
+    fn model(&self) -> String { "mock-1".to_string() }
+    fn chat(&self, prompt: &str) -> Result<String> { self.chat_with_usage(prompt).map(|(t, _)| t) }
+    fn chat_with_usage(&self, prompt: &str) -> Result<(String, Option<TokenUsage>)> {
+        let trimmed = prompt.trim();
+        if trimmed.is_empty() { bail!("empty prompt"); }
+        if let Some(status) = self.outage { return Err(self.outage_error(status)); }
+        let reply = if trimmed.contains(super::suggest::CODE_ONLY) {
+            let broken = self.bad_code.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1)).is_ok();
+            // like real models, fence the code even though the prompt asks not to
+            let code = if broken { SNIPPET.trim_end().trim_end_matches('}') } else { SNIPPET };
+            format!("```aeonmi\n{}\n```", code.trim_end())
+        } else {
+            format!("mock: {trimmed}")
+        };
+        // one token per whitespace-separated word, like a (very) naive tokenizer
+        let usage = TokenUsage { prompt_tokens: trimmed.split_whitespace().count() as u64, completion_tokens: reply.split_whitespace().count() as u64 };
+        Ok((reply, Some(usage)))
+    }
+    fn chat_stream(&self, prompt: &str, cancel: &CancelToken, cb: &mut dyn FnMut(&str)) -> Result<()> {
+        if let Some(status) = self.outage {
+            cb("mock: ");
+            return Err(self.outage_error(status));
+        }
+        let reply = self.chat(prompt)?;
+        // each chunk keeps its trailing space so the chunks concatenate to the full reply
+        for chunk in reply.split_inclusive(' ') {
+            cancel.sleep(self.delay)?;
+            cb(chunk);
+        }
+        Ok(())
+    }
+}
@@ -1,17 +1,32 @@
 //! AI Mother Module skeleton: multi-provider abstraction.
 use anyhow::Result;
+use crate::core::ai_usage::{self, TokenUsage, UsageRecord};
+use cancel::CancelToken;
 
 pub trait AiProvider: Send + Sync {
     fn name(&self) -> &'static str;
+    /// Model name recorded in the usage ledger (and used for price lookup).
+    fn model(&self) -> String { self.name().to_string() }
     fn chat(&self, prompt: &str) -> Result<String>;
-    fn chat_stream(&self, _prompt: &str, _cb: &mut dyn FnMut(&str)) -> Result<()> {
+    /// Like `chat`, also returning token counts when the API reports them.
+    fn chat_with_usage(&self, prompt: &str) -> Result<(String, Option<TokenUsage>)> {
+        Ok((self.chat(prompt)?, None))
+    }
+    /// Stream the reply in chunks, failing with [`cancel::Cancelled`] once `cancel` fires.
+    fn chat_stream(&self, prompt: &str, cancel: &CancelToken, cb: &mut dyn FnMut(&str)) -> Result<()> {
         // Default fallback: call non-streaming and emit once
-        let full = self.chat(_prompt)?;
-        _cb(&full);
+        cancel.check()?;
+        let full = self.chat(prompt)?;
+        cancel.check()?;
+        cb(&full);
         Ok(())
     }
 }
 
+pub mod cancel;
+pub mod markdown;
+pub mod spinner;
+
 #[cfg(feature = "ai-openai")]
 pub mod openai;
 #[cfg(feature = "ai-copilot")]
@@ -20,15 +35,135 @@ pub mod copilot;
 pub mod perplexity;
 #[cfg(feature = "ai-deepseek")]
 pub mod deepseek;
+#[cfg(any(feature = "ai-openai", feature = "ai-copilot", feature = "ai-perplexity", feature = "ai-deepseek"))]
+pub mod transport;
+#[cfg(any(feature = "ai-openai", feature = "ai-copilot", feature = "ai-perplexity", feature = "ai-deepseek"))]
+pub mod key_check;
+pub mod mock;
+pub mod review;
+pub mod suggest;
+
+/// The prompt template `[ai.prompts] <key>` of the TOML config names (relative to the
+/// config file), if any. A missing config file is not an error; a missing template is.
+pub fn configured_prompt(config: Option<&std::path::Path>, key: &str) -> Result<Option<String>> {
+    use anyhow::Context;
+    let Some(config) = config else { return Ok(None) };
+    let Ok(txt) = std::fs::read_to_string(config) else { return Ok(None) };
+    #[derive(serde::Deserialize, Default)]
+    struct Cfg { #[serde(default)] ai: AiCfg }
+    #[derive(serde::Deserialize, Default)]
+    struct AiCfg { #[serde(default)] prompts: std::collections::BTreeMap<String, toml::Value> }
+    let cfg: Cfg = toml::from_str(&txt).with_context(|| format!("parsing {}", config.display()))?;
+    let Some(file) = cfg.ai.prompts.get(key).and_then(toml::Value::as_str) else { return Ok(None) };
+    let path = config.parent().unwrap_or(std::path::Path::new(".")).join(file);
+    let text = std::fs::read_to_string(&path).with_context(|| format!("reading {key} prompt {}", path.display()))?;
+    Ok(Some(text))
+}
+
+/// Run a chat call and append it to the usage ledger (success or failure).
+pub fn chat_recorded(p: &dyn AiProvider, prompt: &str) -> Result<String> {
+    let start = std::time::Instant::now();
+    let res = p.chat_with_usage(prompt);
+    let usage = res.as_ref().ok().and_then(|(_, u)| *u);
+    ai_usage::record(&UsageRecord::new(p.name(), &p.model(), usage, start.elapsed().as_millis() as u64, res.is_ok()));
+    res.map(|(text, _)| text)
+}
+
+/// Streaming variant of [`chat_recorded`]; streams don't report token counts.
+pub fn chat_stream_recorded(p: &dyn AiProvider, prompt: &str, cancel: &CancelToken, cb: &mut dyn FnMut(&str)) -> Result<()> {
+    let start = std::time::Instant::now();
+    let res = p.chat_stream(prompt, cancel, cb);
+    ai_usage::record(&UsageRecord::new(p.name(), &p.model(), None, start.elapsed().as_millis() as u64, res.is_ok()));
+    res
+}
+
+/// A provider that could not answer at all, as opposed to one refusing the prompt.
+#[derive(Debug)]
+pub enum ProviderError {
+    /// The API answered with an error status.
+    Http { provider: &'static str, status: u16, body: String },
+    /// The environment variable holding its credentials is not set.
+    MissingKey(&'static str),
+}
+
+impl std::fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProviderError::Http { provider, status, body } => write!(f, "{provider} http error {status}: {body}"),
+            ProviderError::MissingKey(var) => write!(f, "{var} not set in environment"),
+        }
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+/// Whether another provider may do better: the service was unreachable, overloaded, rate
+/// limited or not configured. Content errors (a bad prompt, an empty reply) and
+/// cancellation are final.
+pub fn retryable(err: &anyhow::Error) -> bool {
+    match err.downcast_ref::<ProviderError>() {
+        Some(ProviderError::Http { status, .. }) => *status >= 500 || *status == 408 || *status == 429,
+        Some(ProviderError::MissingKey(_)) => true,
+        None => network_failure(err),
+    }
+}
+
+fn rate_limited(err: &anyhow::Error) -> bool {
+    matches!(err.downcast_ref::<ProviderError>(), Some(ProviderError::Http { status: 429, .. }))
+}
+
+#[cfg(any(feature = "ai-openai", feature = "ai-copilot", feature = "ai-perplexity", feature = "ai-deepseek"))]
+fn network_failure(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<reqwest::Error>().is_some_and(|e| e.is_timeout() || e.is_connect() || e.is_request())
+}
+
+#[cfg(not(any(feature = "ai-openai", feature = "ai-copilot", feature = "ai-perplexity", feature = "ai-deepseek")))]
+fn network_failure(_err: &anyhow::Error) -> bool {
+    false
+}
+
+/// How often a rate-limited provider is asked again before the chain moves on.
+pub const RATE_LIMIT_RETRIES: u32 = 2;
+
+/// A provider in a fallback chain that failed before another answered.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FailedAttempt {
+    pub provider: &'static str,
+    pub error: String,
+}
+
+/// A reply and who gave it.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Answer {
+    pub provider: &'static str,
+    pub reply: String,
+    /// Providers tried first, in order.
+    pub failed: Vec<FailedAttempt>,
+}
+
+/// What a streaming fallback chat reports as it goes.
+pub enum StreamEvent<'a> {
+    Chunk(&'a str),
+    /// `failed` broke off; the reply starts over from `next`. Chunks seen so far are void.
+    Restart { failed: &'static str, error: String, next: &'static str },
+}
 
 pub struct AiRegistry {
     providers: Vec<Box<dyn AiProvider>>,
+    fallback: Vec<String>,
+    retry_delay: std::time::Duration,
+}
+
+impl Default for AiRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl AiRegistry {
     #[allow(unused_mut)]
     pub fn new() -> Self {
-        let mut r = Self { providers: Vec::new() };
+        let mut r = Self::with_providers(Vec::new());
         #[cfg(feature = "ai-openai")]
         { r.providers.push(Box::new(openai::OpenAi::default())); }
         #[cfg(feature = "ai-copilot")]
@@ -37,11 +172,102 @@ impl AiRegistry {
         { r.providers.push(Box::new(perplexity::Perplexity::default())); }
         #[cfg(feature = "ai-deepseek")]
         { r.providers.push(Box::new(deepseek::DeepSeek::default())); }
+        if let Some(status) = std::env::var("AEONMI_AI_MOCK_OUTAGE").ok().and_then(|v| v.parse().ok()) {
+            r.providers.push(Box::new(mock::Mock { outage: Some(status), ..Default::default() }));
+        }
+        if std::env::var_os("AEONMI_AI_MOCK").is_some() { r.providers.push(Box::new(mock::Mock::from_env())); }
     r
     }
+    pub fn with_providers(providers: Vec<Box<dyn AiProvider>>) -> Self {
+        Self { providers, fallback: Vec::new(), retry_delay: std::time::Duration::from_secs(1) }
+    }
+    /// Provider names to try in order by the `*_with_fallback` calls (`ai.fallback` in the config).
+    pub fn with_fallback(mut self, chain: Vec<String>) -> Self {
+        self.fallback = chain;
+        self
+    }
+    /// First pause before asking a rate-limited provider again; it doubles each time.
+    pub fn with_retry_delay(mut self, delay: std::time::Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
     pub fn list(&self) -> Vec<&'static str> { self.providers.iter().map(|p| p.name()).collect() }
-    pub fn first(&self) -> Option<&Box<dyn AiProvider>> { self.providers.first() }
+    pub fn first(&self) -> Option<&dyn AiProvider> { self.providers.first().map(|b| b.as_ref()) }
     pub fn get(&self, name: &str) -> Option<&dyn AiProvider> {
         self.providers.iter().find(|p| p.name() == name).map(|b| b.as_ref())
     }
+
+    /// The enabled providers of the fallback chain, in order; names that are not enabled
+    /// are skipped. Without a chain, just the first enabled provider.
+    pub fn chain(&self) -> Vec<&dyn AiProvider> {
+        if self.fallback.is_empty() {
+            return self.first().into_iter().collect();
+        }
+        let mut out: Vec<&dyn AiProvider> = Vec::new();
+        for p in self.fallback.iter().filter_map(|n| self.get(n)) {
+            if !out.iter().any(|q| q.name() == p.name()) {
+                out.push(p);
+            }
+        }
+        out
+    }
+
+    /// Call `attempt` on `p`, again after a growing pause while it is rate limited.
+    fn with_retries<T>(&self, p: &dyn AiProvider, mut attempt: impl FnMut(&dyn AiProvider) -> Result<T>) -> Result<T> {
+        let mut delay = self.retry_delay;
+        for _ in 0..RATE_LIMIT_RETRIES {
+            match attempt(p) {
+                Err(e) if rate_limited(&e) => {
+                    std::thread::sleep(delay);
+                    delay *= 2;
+                }
+                res => return res,
+            }
+        }
+        attempt(p)
+    }
+
+    /// Chat with the first provider of the chain that answers, moving on only after
+    /// [`retryable`] failures. Every attempt goes to the usage ledger.
+    pub fn chat_with_fallback(&self, prompt: &str) -> Result<Answer> {
+        let mut failed = Vec::new();
+        for p in self.chain() {
+            match self.with_retries(p, |p| chat_recorded(p, prompt)) {
+                Ok(reply) => return Ok(Answer { provider: p.name(), reply, failed }),
+                Err(e) if retryable(&e) => failed.push(FailedAttempt { provider: p.name(), error: e.to_string() }),
+                Err(e) => return Err(e),
+            }
+        }
+        Err(exhausted(&failed))
+    }
+
+    /// Streaming [`chat_with_fallback`](Self::chat_with_fallback): when a provider breaks
+    /// off, even mid-reply, `cb` gets a [`StreamEvent::Restart`] and the next one starts
+    /// from scratch. Returns the provider that finished, and those that failed.
+    pub fn chat_stream_with_fallback(&self, prompt: &str, cancel: &CancelToken, cb: &mut dyn FnMut(StreamEvent)) -> Result<(&'static str, Vec<FailedAttempt>)> {
+        let chain = self.chain();
+        let mut failed = Vec::new();
+        for (i, p) in chain.iter().enumerate() {
+            let res = self.with_retries(*p, |p| chat_stream_recorded(p, prompt, cancel, &mut |c| cb(StreamEvent::Chunk(c))));
+            match res {
+                Ok(()) => return Ok((p.name(), failed)),
+                Err(e) if retryable(&e) => {
+                    if let Some(next) = chain.get(i + 1) {
+                        cb(StreamEvent::Restart { failed: p.name(), error: e.to_string(), next: next.name() });
+                    }
+                    failed.push(FailedAttempt { provider: p.name(), error: e.to_string() });
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(exhausted(&failed))
+    }
+}
+
+fn exhausted(failed: &[FailedAttempt]) -> anyhow::Error {
+    if failed.is_empty() {
+        return anyhow::anyhow!("no provider of the fallback chain is enabled");
+    }
+    let tried: Vec<String> = failed.iter().map(|f| format!("{}: {}", f.provider, f.error)).collect();
+    anyhow::anyhow!("every provider failed ({})", tried.join("; "))
 }
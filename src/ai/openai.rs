@@ -1,5 +1,7 @@
 use anyhow::{Result, anyhow, bail};
-use super::AiProvider;
+use super::{transport, AiProvider, ProviderError};
+use super::cancel::CancelToken;
+use crate::core::ai_usage::TokenUsage;
 use std::time::Duration;
 
 #[derive(Default)]
@@ -22,26 +24,30 @@ struct ChatMessage<'a> {
 #[derive(serde::Deserialize, Debug)]
 struct ChatResponse {
     choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<Usage>,
 }
 #[derive(serde::Deserialize, Debug)]
 struct Choice { message: ChoiceMessage }
 #[derive(serde::Deserialize, Debug)]
 struct ChoiceMessage { content: String }
+#[derive(serde::Deserialize, Debug)]
+struct Usage { prompt_tokens: u64, completion_tokens: u64 }
 
 impl AiProvider for OpenAi {
     fn name(&self) -> &'static str { "openai" }
-    fn chat(&self, prompt: &str) -> Result<String> {
+    fn model(&self) -> String { std::env::var("AEONMI_OPENAI_MODEL").unwrap_or_else(|_| "gpt-4o-mini".to_string()) }
+    fn chat(&self, prompt: &str) -> Result<String> { self.chat_with_usage(prompt).map(|(t, _)| t) }
+    fn chat_with_usage(&self, prompt: &str) -> Result<(String, Option<TokenUsage>)> {
         let trimmed = prompt.trim();
         if trimmed.is_empty() { bail!("empty prompt"); }
         let key = std::env::var("OPENAI_API_KEY")
-            .map_err(|_| anyhow!("OPENAI_API_KEY not set in environment"))?;
-        let model = std::env::var("AEONMI_OPENAI_MODEL").unwrap_or_else(|_| "gpt-4o-mini".to_string());
+            .map_err(|_| ProviderError::MissingKey("OPENAI_API_KEY"))?;
+        let model = self.model();
     let req = ChatRequest { model: &model, messages: vec![ChatMessage { role: "user", content: trimmed }], temperature: 0.7, stream: None };
-        let client = reqwest::blocking::Client::builder()
-            .timeout(Duration::from_secs(45))
-            .build()?;
+        let client = transport::client(Duration::from_secs(45))?;
         let resp = client
-            .post("https://api.openai.com/v1/chat/completions")
+            .post(transport::url(transport::OPENAI, "/v1/chat/completions"))
             .bearer_auth(&key)
             .header("Content-Type", "application/json")
             .json(&req)
@@ -49,36 +55,41 @@ impl AiProvider for OpenAi {
         if !resp.status().is_success() {
             let status = resp.status();
             let text = resp.text().unwrap_or_default();
-            bail!("openai http error {status}: {text}");
+            return Err(ProviderError::Http { provider: "openai", status: status.as_u16(), body: text }.into());
         }
         let cr: ChatResponse = resp.json()?;
         let content = cr.choices.first()
             .map(|c| c.message.content.trim().to_string())
             .filter(|s| !s.is_empty())
             .ok_or_else(|| anyhow!("empty response"))?;
-        Ok(content)
+        let usage = cr.usage.map(|u| TokenUsage { prompt_tokens: u.prompt_tokens, completion_tokens: u.completion_tokens });
+        Ok((content, usage))
     }
+    fn chat_stream(&self, prompt: &str, cancel: &CancelToken, cb: &mut dyn FnMut(&str)) -> Result<()> { self.stream_chat(prompt, cancel, cb) }
 }
 
 impl OpenAi {
-    fn stream_chat(&self, prompt: &str, cb: &mut dyn FnMut(&str)) -> Result<()> {
+    fn stream_chat(&self, prompt: &str, cancel: &CancelToken, cb: &mut dyn FnMut(&str)) -> Result<()> {
         let trimmed = prompt.trim();
         if trimmed.is_empty() { bail!("empty prompt"); }
         let key = std::env::var("OPENAI_API_KEY")
-            .map_err(|_| anyhow!("OPENAI_API_KEY not set in environment"))?;
+            .map_err(|_| ProviderError::MissingKey("OPENAI_API_KEY"))?;
         let model = std::env::var("AEONMI_OPENAI_MODEL").unwrap_or_else(|_| "gpt-4o-mini".to_string());
         let req = ChatRequest { model: &model, messages: vec![ChatMessage { role: "user", content: trimmed }], temperature: 0.7, stream: Some(true) };
-        let client = reqwest::blocking::Client::builder().timeout(Duration::from_secs(120)).build()?;
-        let resp = client.post("https://api.openai.com/v1/chat/completions")
+        let client = transport::client(Duration::from_secs(120))?;
+        cancel.check()?;
+        let resp = client.post(transport::url(transport::OPENAI, "/v1/chat/completions"))
             .bearer_auth(&key)
             .header("Content-Type", "application/json")
             .json(&req)
             .send()?;
-        if !resp.status().is_success() { let status = resp.status(); let text = resp.text().unwrap_or_default(); bail!("openai http error {status}: {text}"); }
+        if !resp.status().is_success() { let status = resp.status(); let text = resp.text().unwrap_or_default(); return Err(ProviderError::Http { provider: "openai", status: status.as_u16(), body: text }.into()); }
         use std::io::{BufRead, BufReader};
         let mut reader = BufReader::new(resp);
         let mut line = String::new();
         loop {
+            // returning drops the reader, which closes the connection and aborts the request
+            cancel.check()?;
             line.clear();
             let n = reader.read_line(&mut line)?; if n == 0 { break; }
             let trimmed_line = line.trim_start();
@@ -99,6 +110,3 @@ impl OpenAi {
     }
 }
 
-impl super::AiProvider for OpenAi {
-    fn chat_stream(&self, prompt: &str, cb: &mut dyn FnMut(&str)) -> Result<()> { self.stream_chat(prompt, cb) }
-}
@@ -1,5 +1,6 @@
 use anyhow::{Result, anyhow, bail};
-use super::AiProvider;
+use super::{transport, AiProvider, ProviderError};
+use crate::core::ai_usage::TokenUsage;
 use std::time::Duration;
 
 #[derive(Default)]
@@ -16,23 +17,27 @@ struct ChatRequest<'a> {
 struct ChatMessage<'a> { role: &'a str, content: &'a str }
 
 #[derive(serde::Deserialize, Debug)]
-struct ChatResponse { choices: Vec<Choice> }
+struct ChatResponse { choices: Vec<Choice>, #[serde(default)] usage: Option<Usage> }
 #[derive(serde::Deserialize, Debug)]
 struct Choice { message: ChoiceMessage }
 #[derive(serde::Deserialize, Debug)]
 struct ChoiceMessage { content: String }
+#[derive(serde::Deserialize, Debug)]
+struct Usage { prompt_tokens: u64, completion_tokens: u64 }
 
 impl AiProvider for Perplexity {
     fn name(&self) -> &'static str { "perplexity" }
-    fn chat(&self, prompt: &str) -> Result<String> {
+    fn model(&self) -> String { std::env::var("AEONMI_PERPLEXITY_MODEL").unwrap_or_else(|_| "llama-3.1-sonar-small-chat".to_string()) }
+    fn chat(&self, prompt: &str) -> Result<String> { self.chat_with_usage(prompt).map(|(t, _)| t) }
+    fn chat_with_usage(&self, prompt: &str) -> Result<(String, Option<TokenUsage>)> {
         let trimmed = prompt.trim();
         if trimmed.is_empty() { bail!("empty prompt"); }
         let key = std::env::var("PERPLEXITY_API_KEY")
-            .map_err(|_| anyhow!("PERPLEXITY_API_KEY not set"))?;
-        let model = std::env::var("AEONMI_PERPLEXITY_MODEL").unwrap_or_else(|_| "llama-3.1-sonar-small-chat".to_string());
+            .map_err(|_| ProviderError::MissingKey("PERPLEXITY_API_KEY"))?;
+        let model = self.model();
         let req = ChatRequest { model: &model, messages: vec![ChatMessage { role: "user", content: trimmed }], temperature: 0.7 };
-        let client = reqwest::blocking::Client::builder().timeout(Duration::from_secs(45)).build()?;
-        let resp = client.post("https://api.perplexity.ai/chat/completions")
+        let client = transport::client(Duration::from_secs(45))?;
+        let resp = client.post(transport::url(transport::PERPLEXITY, "/chat/completions"))
             .bearer_auth(&key)
             .header("Content-Type", "application/json")
             .json(&req)
@@ -40,13 +45,14 @@ impl AiProvider for Perplexity {
         if !resp.status().is_success() {
             let status = resp.status();
             let text = resp.text().unwrap_or_default();
-            bail!("perplexity http error {status}: {text}");
+            return Err(ProviderError::Http { provider: "perplexity", status: status.as_u16(), body: text }.into());
         }
         let cr: ChatResponse = resp.json()?;
         let content = cr.choices.first()
             .map(|c| c.message.content.trim().to_string())
             .filter(|s| !s.is_empty())
             .ok_or_else(|| anyhow!("empty response"))?;
-        Ok(content)
+        let usage = cr.usage.map(|u| TokenUsage { prompt_tokens: u.prompt_tokens, completion_tokens: u.completion_tokens });
+        Ok((content, usage))
     }
 }
@@ -0,0 +1,314 @@
+//! `aeonmi ai review`: review the changed lines of .ai files, one function at a time.
+//!
+//! A unified diff is split per file ([`parse_diff`]); each file's changes are then cut
+//! into [`Chunk`]s along top-level function boundaries found by the parser, so a
+//! provider always sees whole functions. Changes outside any function become a
+//! `<top level>` chunk with a few lines of context. Each chunk is rendered through the
+//! review prompt template (embedded, or `[ai.prompts] review = "file"` in the config)
+//! and the reply lines of the form `- L<line> [severity] text` become
+//! [`ReviewComment`]s. [`lint_comments`] is the offline stand-in: semantic and type
+//! diagnostics on changed lines, worded as review comments.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::core::ast::ASTNode;
+use crate::core::lexer::Lexer;
+use crate::core::parser::Parser;
+use crate::core::preview::function_source_ranges;
+use crate::core::semantic_analyzer::{SemanticAnalyzer, Severity};
+use crate::core::style::{Role, Style};
+use crate::core::types::TypeContext;
+
+/// Review prompt used when the config names none.
+pub const DEFAULT_TEMPLATE: &str = include_str!("prompts/review.md");
+
+/// Unchanged lines shown around a change outside any function.
+pub const CONTEXT: usize = 3;
+
+/// Scope name of chunks that are not inside a function.
+pub const TOP_LEVEL: &str = "<top level>";
+
+/// One `@@` hunk of a file's diff.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hunk {
+    /// First line of the hunk in the new file (1-based).
+    pub new_start: usize,
+    pub new_len: usize,
+    /// The hunk, header included.
+    pub text: String,
+}
+
+/// What the diff says changed in one file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileDiff {
+    /// Path on the new side, relative to the repository root.
+    pub path: String,
+    pub hunks: Vec<Hunk>,
+    /// New-side lines that were added or edited, plus the line a pure deletion sits at.
+    pub changed: BTreeSet<usize>,
+}
+
+/// Split `git diff` output into per-file changes. Deleted files are dropped: there is
+/// nothing left to review.
+pub fn parse_diff(diff: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    let mut new_line = 0usize;
+    for line in diff.lines() {
+        if line.starts_with("diff --git ") {
+            files.push(FileDiff { path: String::new(), hunks: Vec::new(), changed: BTreeSet::new() });
+            continue;
+        }
+        let Some(file) = files.last_mut() else { continue };
+        if file.hunks.is_empty() && line.starts_with("+++ ") {
+            let path = &line[4..];
+            file.path = path.strip_prefix("b/").unwrap_or(path).to_string();
+        } else if file.hunks.is_empty() && !line.starts_with("@@ ") {
+            // `index`, `---` and mode lines before the first hunk
+        } else if let Some(header) = line.strip_prefix("@@ ") {
+            let (new_start, new_len) = hunk_new_range(header);
+            file.hunks.push(Hunk { new_start, new_len, text: format!("{line}\n") });
+            new_line = new_start;
+        } else if let Some(hunk) = file.hunks.last_mut() {
+            hunk.text.push_str(line);
+            hunk.text.push('\n');
+            match line.chars().next() {
+                Some('+') => {
+                    file.changed.insert(new_line);
+                    new_line += 1;
+                }
+                Some('-') => {
+                    file.changed.insert(new_line.max(1));
+                }
+                Some('\\') => {}
+                _ => new_line += 1,
+            }
+        }
+    }
+    files.retain(|f| !f.path.is_empty() && f.path != "/dev/null" && !f.hunks.is_empty());
+    files
+}
+
+/// `-a,b +c,d @@ ...` → `(c, d)`; a missing length means 1.
+fn hunk_new_range(header: &str) -> (usize, usize) {
+    let new = header.split_whitespace().find_map(|part| part.strip_prefix('+')).unwrap_or("1");
+    let (start, len) = new.split_once(',').unwrap_or((new, "1"));
+    (start.parse().unwrap_or(1), len.parse().unwrap_or(1))
+}
+
+/// A piece of a changed file sent for review on its own.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Chunk {
+    pub file: String,
+    /// Function name, or [`TOP_LEVEL`].
+    pub scope: String,
+    /// Lines of the new file the chunk covers (1-based, inclusive).
+    pub start: usize,
+    pub end: usize,
+    pub changed: Vec<usize>,
+    /// The hunks that touch the chunk.
+    pub diff: String,
+    /// The chunk's lines, each prefixed with its line number.
+    pub source: String,
+}
+
+/// Cut `diff`'s changes to `source` (the new file) into function-sized chunks. A file
+/// that does not parse is reviewed as one top-level chunk per group of changed lines.
+pub fn chunk(diff: &FileDiff, source: &str) -> Vec<Chunk> {
+    let lines: Vec<&str> = source.lines().collect();
+    let last = lines.len().max(1);
+    let functions = Lexer::from_str(source)
+        .tokenize()
+        .ok()
+        .and_then(|tokens| Parser::new(tokens.clone()).parse().ok().map(|ast| function_source_ranges(&ast, &tokens)))
+        .unwrap_or_default();
+    let mut in_fn: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    let mut loose: Vec<usize> = Vec::new();
+    // a deletion at the end of the file sits one past its last line
+    let changed: BTreeSet<usize> = diff.changed.iter().map(|l| (*l).min(last)).collect();
+    for l in changed {
+        match functions.iter().position(|(_, start, end)| (*start..=*end).contains(&l)) {
+            Some(i) => in_fn.entry(i).or_default().push(l),
+            None => loose.push(l),
+        }
+    }
+    let mut spans: Vec<(String, usize, usize, Vec<usize>)> =
+        in_fn.into_iter().map(|(i, changed)| (functions[i].0.clone(), functions[i].1, functions[i].2, changed)).collect();
+    // Nearby top-level changes share one chunk; context never reaches into a function.
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for l in loose {
+        match groups.last_mut() {
+            Some(g) if l - g[g.len() - 1] <= 2 * CONTEXT + 1 => g.push(l),
+            _ => groups.push(vec![l]),
+        }
+    }
+    for g in groups {
+        let inside = |l: usize| functions.iter().any(|(_, s, e)| (*s..=*e).contains(&l));
+        let mut start = g[0];
+        while start > 1 && g[0] - start < CONTEXT && !inside(start - 1) {
+            start -= 1;
+        }
+        let mut end = g[g.len() - 1];
+        while end < last && end - g[g.len() - 1] < CONTEXT && !inside(end + 1) {
+            end += 1;
+        }
+        spans.push((TOP_LEVEL.to_string(), start, end, g));
+    }
+    spans.sort_by_key(|s| s.1);
+    spans
+        .into_iter()
+        .map(|(scope, start, end, changed)| {
+            let width = end.to_string().len();
+            let source = (start..=end).map(|n| format!("{n:>width$} | {}\n", lines.get(n - 1).unwrap_or(&""))).collect();
+            let hunks = diff
+                .hunks
+                .iter()
+                .filter(|h| h.new_start <= end && start < h.new_start + h.new_len.max(1))
+                .map(|h| h.text.as_str())
+                .collect();
+            Chunk { file: diff.path.clone(), scope, start, end, changed, diff: hunks, source }
+        })
+        .collect()
+}
+
+/// The review prompt, with `{{file}}`, `{{scope}}`, `{{start}}`, `{{end}}`,
+/// `{{changed}}`, `{{diff}}` and `{{source}}` placeholders.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptTemplate(pub String);
+
+impl Default for PromptTemplate {
+    fn default() -> Self {
+        PromptTemplate(DEFAULT_TEMPLATE.to_string())
+    }
+}
+
+impl PromptTemplate {
+    /// `[ai.prompts] review` from the TOML config (relative to the config file), else the
+    /// embedded template. A missing config file is not an error; a missing template is.
+    pub fn from_config(config: Option<&Path>) -> Result<Self> {
+        Ok(super::configured_prompt(config, "review")?.map(PromptTemplate).unwrap_or_default())
+    }
+
+    pub fn render(&self, c: &Chunk) -> String {
+        let changed = c.changed.iter().map(|l| l.to_string()).collect::<Vec<_>>().join(", ");
+        self.0
+            .replace("{{file}}", &c.file)
+            .replace("{{scope}}", &c.scope)
+            .replace("{{start}}", &c.start.to_string())
+            .replace("{{end}}", &c.end.to_string())
+            .replace("{{changed}}", &changed)
+            .replace("{{diff}}", c.diff.trim_end())
+            .replace("{{source}}", c.source.trim_end())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReviewComment {
+    pub file: String,
+    pub line: usize,
+    /// `error`, `warning` or `info`.
+    pub severity: String,
+    pub scope: String,
+    /// Provider name, or `lint` for offline findings.
+    pub source: String,
+    pub message: String,
+}
+
+/// Comments in a provider's `reply` about `c`. Lines in the `- L<line> [severity] text`
+/// form become one comment each; a reply with none of them (other than `LGTM`) is kept
+/// whole as an `info` comment on the chunk's first line.
+pub fn parse_reply(c: &Chunk, reply: &str, source: &str) -> Vec<ReviewComment> {
+    let comment = |line: usize, severity: &str, message: &str| ReviewComment {
+        file: c.file.clone(),
+        line,
+        severity: severity.to_string(),
+        scope: c.scope.clone(),
+        source: source.to_string(),
+        message: message.trim().to_string(),
+    };
+    let found: Vec<ReviewComment> = reply
+        .lines()
+        .filter_map(|l| {
+            let rest = l.trim_start().trim_start_matches(['-', '*']).trim_start().strip_prefix('L')?;
+            let digits = rest.len() - rest.trim_start_matches(|ch: char| ch.is_ascii_digit()).len();
+            let line: usize = rest[..digits].parse().ok()?;
+            let rest = rest[digits..].trim_start();
+            let (severity, message) = match rest.strip_prefix('[').and_then(|r| r.split_once(']')) {
+                Some((sev, msg)) if ["error", "warning", "info"].contains(&sev.trim()) => (sev.trim(), msg),
+                _ => ("info", rest.trim_start_matches(':')),
+            };
+            Some(comment(line, severity, message))
+        })
+        .collect();
+    let reply = reply.trim();
+    if found.is_empty() && !reply.is_empty() && !reply.eq_ignore_ascii_case("lgtm") {
+        return vec![comment(c.start, "info", reply)];
+    }
+    found
+}
+
+/// Offline review: semantic and type diagnostics of `ast` that fall on `c`'s changed lines.
+pub fn lint_comments(c: &Chunk, ast: &ASTNode) -> Vec<ReviewComment> {
+    let mut out = Vec::new();
+    let mut push = |line: usize, severity: &str, message: String| {
+        if c.changed.contains(&line) {
+            out.push(ReviewComment { file: c.file.clone(), line, severity: severity.into(), scope: c.scope.clone(), source: "lint".into(), message });
+        }
+    };
+    for d in SemanticAnalyzer::new().analyze_with_spans(ast) {
+        let severity = if d.severity == Severity::Error { "error" } else { "warning" };
+        let mut message = match d.rule {
+            Some(rule) => format!("{} ({rule})", d.message),
+            None => d.message.clone(),
+        };
+        if let Some(fix) = &d.fix {
+            message.push_str(&format!("; suggestion: {}", fix.title));
+        }
+        push(d.line, severity, message);
+    }
+    let mut types = TypeContext::new();
+    types.infer_program(ast);
+    for d in types.diags {
+        push(d.line, "error", d.message);
+    }
+    out
+}
+
+/// Comments grouped by file, each file's sorted by line, then a summary line.
+pub fn render(comments: &[ReviewComment], chunks: usize, st: &Style) -> String {
+    let mut by_file: BTreeMap<&str, Vec<&ReviewComment>> = BTreeMap::new();
+    for c in comments {
+        by_file.entry(&c.file).or_default().push(c);
+    }
+    let mut out = String::new();
+    for (file, mut list) in by_file {
+        list.sort_by_key(|c| c.line);
+        out.push_str(&st.paint(file, Role::Strong));
+        out.push('\n');
+        for c in list {
+            let role = match c.severity.as_str() {
+                "error" => Role::Error,
+                "warning" => Role::Warn,
+                _ => Role::Dim,
+            };
+            let first = c.message.lines().next().unwrap_or("");
+            out.push_str(&format!("  {:>5}  {}  {}  {}\n", format!("L{}", c.line), st.paint(&format!("{:<7}", c.severity), role), first, st.paint(&format!("({}, {})", c.scope, c.source), Role::Dim)));
+            for more in c.message.lines().skip(1) {
+                out.push_str(&format!("{:>9}{more}\n", ""));
+            }
+        }
+    }
+    let files = comments.iter().map(|c| &c.file).collect::<BTreeSet<_>>().len();
+    out.push_str(&format!(
+        "{} comment{} in {} file{} ({} chunk{} reviewed)\n",
+        comments.len(),
+        if comments.len() == 1 { "" } else { "s" },
+        files,
+        if files == 1 { "" } else { "s" },
+        chunks,
+        if chunks == 1 { "" } else { "s" }
+    ));
+    out
+}
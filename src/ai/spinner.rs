@@ -0,0 +1,57 @@
+//! Progress line on stderr while waiting for a provider: a spinner frame, a label and the
+//! elapsed time. Drawn only when stderr is a terminal, so piped output never sees it.
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::core::style::{Role, Style};
+
+const FRAMES_UNICODE: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const FRAMES_ASCII: &[&str] = &["|", "/", "-", "\\"];
+
+pub struct Spinner {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Spinner {
+    pub fn start(label: String, style: Style) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        if !std::io::stderr().is_terminal() {
+            return Self { stop, handle: None };
+        }
+        let flag = stop.clone();
+        let handle = std::thread::spawn(move || {
+            let frames = if style.unicode { FRAMES_UNICODE } else { FRAMES_ASCII };
+            let start = Instant::now();
+            let mut i = 0;
+            while !flag.load(Ordering::SeqCst) {
+                let frame = style.paint(frames[i % frames.len()], Role::Brand);
+                eprint!("\r{} {} {:.1}s", frame, label, start.elapsed().as_secs_f64());
+                std::io::stderr().flush().ok();
+                i += 1;
+                std::thread::sleep(Duration::from_millis(80));
+            }
+            // clear the spinner line so the reply starts at column 0
+            eprint!("\r\x1b[2K");
+            std::io::stderr().flush().ok();
+        });
+        Self { stop, handle: Some(handle) }
+    }
+
+    /// Stop and erase the line; later calls do nothing.
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,142 @@
+//! `aeonmi ai suggest`: Aeonmi code from a natural-language request.
+//!
+//! The request is rendered through the suggest prompt template (embedded, or
+//! `[ai.prompts] suggest = "file"` in the config) and the reply is checked with the real
+//! lexer and parser. A reply that does not parse is sent back once, with the parse error
+//! appended to the prompt; [`insert`] then places the code into a file at a given line,
+//! indented to fit the block it lands in.
+
+use anyhow::{bail, Result};
+use std::path::Path;
+
+use super::AiProvider;
+use crate::core::lexer::Lexer;
+use crate::core::parser::Parser;
+
+/// Suggest prompt used when the config names none.
+pub const DEFAULT_TEMPLATE: &str = include_str!("prompts/suggest.md");
+
+/// The embedded template's instruction to answer with code alone; the mock provider
+/// answers prompts containing it with a canned snippet.
+pub const CODE_ONLY: &str = "Answer with Aeonmi code only";
+
+/// The suggest prompt, with a `{{request}}` placeholder.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuggestTemplate(pub String);
+
+impl Default for SuggestTemplate {
+    fn default() -> Self {
+        SuggestTemplate(DEFAULT_TEMPLATE.to_string())
+    }
+}
+
+impl SuggestTemplate {
+    /// `[ai.prompts] suggest` from the TOML config, else the embedded template.
+    pub fn from_config(config: Option<&Path>) -> Result<Self> {
+        Ok(super::configured_prompt(config, "suggest")?.map(SuggestTemplate).unwrap_or_default())
+    }
+
+    pub fn render(&self, request: &str) -> String {
+        self.0.replace("{{request}}", request.trim())
+    }
+}
+
+/// What was appended to the prompt for the second attempt.
+pub fn retry_prompt(first: &str, code: &str, error: &str) -> String {
+    format!(
+        "{}\n\nYour previous answer did not parse:\n```aeonmi\n{}\n```\nParser error: {error}\nReply with the corrected code only.\n",
+        first.trim_end(),
+        code.trim_end()
+    )
+}
+
+/// The code in a reply: the first fenced block when there is one, else the whole reply.
+pub fn extract_code(reply: &str) -> String {
+    let mut lines = reply.lines().skip_while(|l| !l.trim_start().starts_with("```"));
+    if lines.next().is_some() {
+        let body: Vec<&str> = lines.take_while(|l| !l.trim_start().starts_with("```")).collect();
+        return format!("{}\n", body.join("\n").trim_matches('\n'));
+    }
+    format!("{}\n", reply.trim_matches('\n').trim_end())
+}
+
+/// `Ok` when `code` lexes and parses, else the first error as `line:column: message`.
+pub fn check(code: &str) -> Result<(), String> {
+    let tokens = Lexer::from_str(code).tokenize().map_err(|e| e.to_string())?;
+    match Parser::new(tokens).parse() {
+        Ok(_) if code.trim().is_empty() => Err("the reply contains no code".into()),
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("{}:{}: {}", e.line, e.column, e.summary())),
+    }
+}
+
+/// A reply that parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub code: String,
+    /// 1, or 2 when the first reply had to be retried.
+    pub attempts: usize,
+}
+
+/// Both replies failed to parse; holds the second one's parse error.
+#[derive(Debug)]
+pub struct Unparsable(pub String);
+
+impl std::fmt::Display for Unparsable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the reply still does not parse after a retry: {}", self.0)
+    }
+}
+
+impl std::error::Error for Unparsable {}
+
+/// Ask `p` for code; `on_retry` hears the parse error of a first reply that is retried.
+pub fn generate(p: &dyn AiProvider, prompt: &str, on_retry: &mut dyn FnMut(&str)) -> Result<Suggestion> {
+    let code = extract_code(&super::chat_recorded(p, prompt)?);
+    let Err(error) = check(&code) else { return Ok(Suggestion { code, attempts: 1 }) };
+    on_retry(&error);
+    let code = extract_code(&super::chat_recorded(p, &retry_prompt(prompt, &code, &error))?);
+    match check(&code) {
+        Ok(()) => Ok(Suggestion { code, attempts: 2 }),
+        Err(error) => Err(Unparsable(error).into()),
+    }
+}
+
+/// `file:line` → `(file, line)`; the line is 1-based.
+pub fn parse_location(spec: &str) -> Result<(String, usize)> {
+    let Some((file, line)) = spec.rsplit_once(':') else { bail!("expected FILE:LINE, got '{spec}'") };
+    match line.parse::<usize>() {
+        Ok(n) if n > 0 && !file.is_empty() => Ok((file.to_string(), n)),
+        _ => bail!("expected FILE:LINE with a line number from 1, got '{spec}'"),
+    }
+}
+
+fn leading_ws(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// `text` with `code` inserted before its `line` (one past the end appends). The code is
+/// re-indented to the enclosing block: the indentation of the last non-blank line above,
+/// one level deeper when that line opens a block.
+pub fn insert(text: &str, line: usize, code: &str) -> Result<String> {
+    let lines: Vec<&str> = text.lines().collect();
+    if line == 0 || line > lines.len() + 1 {
+        bail!("line {line} is outside the file (1..={})", lines.len() + 1);
+    }
+    let above = lines[..line - 1].iter().rev().find(|l| !l.trim().is_empty());
+    let indent = match above {
+        Some(l) if l.trim_end().ends_with('{') => {
+            let unit = if leading_ws(l).contains('\t') { "\t" } else { "    " };
+            format!("{}{unit}", leading_ws(l))
+        }
+        Some(l) => leading_ws(l).to_string(),
+        None => String::new(),
+    };
+    let strip = code.lines().filter(|l| !l.trim().is_empty()).map(|l| leading_ws(l).len()).min().unwrap_or(0);
+    let mut out: Vec<String> = lines[..line - 1].iter().map(|l| l.to_string()).collect();
+    for l in code.lines() {
+        out.push(if l.trim().is_empty() { String::new() } else { format!("{indent}{}", &l[strip.min(leading_ws(l).len())..]) });
+    }
+    out.extend(lines[line - 1..].iter().map(|l| l.to_string()));
+    Ok(out.join("\n") + "\n")
+}
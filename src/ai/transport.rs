@@ -0,0 +1,27 @@
+//! HTTP plumbing shared by the hosted providers: one client configuration and one place
+//! where provider hosts can be redirected (`AEONMI_AI_BASE_URL`, for proxies and tests).
+use anyhow::Result;
+use reqwest::blocking::Client;
+use std::time::Duration;
+
+pub const OPENAI: &str = "https://api.openai.com";
+pub const COPILOT: &str = "https://api.githubcopilot.com";
+pub const PERPLEXITY: &str = "https://api.perplexity.ai";
+pub const DEEPSEEK: &str = "https://api.deepseek.com";
+
+/// A blocking client whose requests give up after `timeout`.
+pub fn client(timeout: Duration) -> Result<Client> {
+    Ok(Client::builder().timeout(timeout).build()?)
+}
+
+/// `origin` + `path`, with `origin` replaced by `AEONMI_AI_BASE_URL` when that is set.
+pub fn url(origin: &str, path: &str) -> String {
+    let origin = std::env::var("AEONMI_AI_BASE_URL").unwrap_or_else(|_| origin.to_string());
+    format!("{}{path}", origin.trim_end_matches('/'))
+}
+
+/// The Copilot chat endpoint; `AEONMI_COPILOT_ENDPOINT` overrides the whole URL.
+pub fn copilot_endpoint() -> String {
+    // Endpoint placeholder; actual Copilot APIs may differ or require websocket; adjust when official public API is used.
+    std::env::var("AEONMI_COPILOT_ENDPOINT").unwrap_or_else(|_| url(COPILOT, "/v1/chat/completions"))
+}
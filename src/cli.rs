@@ -1,4 +1,4 @@
-use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
 use crate::cli_vault::VaultCommand as VaultSubcommand;
@@ -12,6 +12,19 @@ pub enum EmitKind {
     Ai,
 }
 
+/// Ranking column for `metrics-top`.
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq)]
+pub enum TopSort {
+    /// recent (exponential moving average) inference time
+    Ema,
+    /// lifetime average inference time
+    Avg,
+    /// cumulative inference time
+    Total,
+    /// number of inferences
+    Runs,
+}
+
 #[derive(Copy, Clone, Debug, ValueEnum)]
 pub enum BackendKind {
     #[clap(alias = "titan")]
@@ -43,6 +56,34 @@ pub struct AeonmiCli {
     #[arg(long = "debug-titan", action = ArgAction::SetTrue, global = true)]
     pub debug_titan: bool,
 
+    /// Global: suppress human-readable output (exit code still reports status)
+    #[arg(long = "quiet", short = 'q', action = ArgAction::SetTrue, global = true)]
+    pub quiet: bool,
+
+    /// Global: internal logs on stderr with timestamps and targets (-v debug, -vv trace). Env AEONMI_DEBUG=1|2 also works.
+    #[arg(short = 'v', long = "verbose", action = ArgAction::Count, global = true)]
+    pub verbose: u8,
+
+    /// Global: emit machine-readable `@@DIAG:` JSON diagnostics on stderr
+    #[arg(long = "diag-json", action = ArgAction::SetTrue, global = true)]
+    pub diag_json: bool,
+
+    /// Global: diagnostics `typecheck` and `lint` print before summarizing the rest (0 = all)
+    #[arg(long = "max-diagnostics", value_name = "N", default_value_t = crate::core::diagnostics::DEFAULT_MAX_DIAGNOSTICS, global = true)]
+    pub max_diagnostics: usize,
+
+    /// Global: ASCII-only output (no box drawing or glyph decorations). Env AEONMI_UNICODE=0 also works.
+    #[arg(long = "no-unicode", action = ArgAction::SetTrue, global = true)]
+    pub no_unicode: bool,
+
+    /// Global: verify the IR after lowering (always on in debug builds); violations are internal compiler errors
+    #[arg(long = "verify-ir", action = ArgAction::SetTrue, global = true)]
+    pub verify_ir: bool,
+
+    /// Global: stop semantic analysis and type inference between top-level items after MS milliseconds, reporting what was found so far
+    #[arg(long = "analysis-timeout-ms", value_name = "MS", global = true)]
+    pub analysis_timeout_ms: Option<u64>,
+
     /// Global: path to config (TOML); default: ~/.aeonmi/qpoly.toml
     #[arg(long = "config", value_name = "FILE", global = true)]
     pub config: Option<PathBuf>,
@@ -71,6 +112,51 @@ pub struct AeonmiCli {
     pub cmd: Option<Command>,
 }
 
+/// How batch commands expand directory and glob inputs (see `io::discover`).
+#[derive(Debug, Clone, Default, Args)]
+pub struct DiscoveryArgs {
+    /// Skip paths matching this glob (repeatable); without a `/` it matches any path component
+    #[arg(long = "exclude", value_name = "GLOB")]
+    pub exclude: Vec<String>,
+    /// File extension to pick up in directories and globs (repeatable; default: ai)
+    #[arg(long = "ext", value_name = "EXT")]
+    pub ext: Vec<String>,
+}
+
+impl DiscoveryArgs {
+    pub fn discover(&self, inputs: &[PathBuf]) -> anyhow::Result<Vec<PathBuf>> {
+        crate::io::discover::discover(inputs, &self.ext, &self.exclude)
+    }
+}
+
+/// `format` options; each overrides the same key of the manifest's `[format]` table.
+#[derive(Debug, Clone, Default, Args)]
+pub struct FormatStyleArgs {
+    /// Spaces per indent level (default 4)
+    #[arg(long = "indent-width", value_name = "N", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=16))]
+    pub indent_width: Option<usize>,
+    /// Wrap lines wider than N after a comma inside brackets (0: never wrap, the default)
+    #[arg(long = "max-line-width", value_name = "N")]
+    pub max_line_width: Option<usize>,
+    /// Operator glyphs: preserve (default), ascii (`≤` → `<=`) or unicode (`<=` → `≤`)
+    #[arg(long = "glyphs", value_name = "MODE")]
+    pub glyphs: Option<crate::core::formatter::GlyphMode>,
+    /// End every expression statement with `;` (`--always-semicolons=false` to turn the manifest's setting off)
+    #[arg(long = "always-semicolons", value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub always_semicolons: Option<bool>,
+}
+
+impl FormatStyleArgs {
+    pub fn settings(&self) -> crate::config::FormatSettings {
+        crate::config::FormatSettings {
+            indent_width: self.indent_width,
+            max_line_width: self.max_line_width,
+            glyphs: self.glyphs,
+            always_semicolons: self.always_semicolons,
+        }
+    }
+}
+
 #[derive(Debug, Subcommand)]
 pub enum Command {
     /// Emit compiled output
@@ -87,7 +173,7 @@ pub enum Command {
         #[arg(long = "emit", value_enum, default_value_t = EmitKind::Js, visible_alias = "format")]
         emit: EmitKind,
 
-        /// Output file path (short: -o). Defaults by format.
+        /// Output file path (short: -o). Defaults by format; `-` writes the code to stdout.
         #[arg(
             short = 'o',
             long = "out",
@@ -97,6 +183,10 @@ pub enum Command {
         )]
         out: PathBuf,
 
+        /// Fail instead of replacing an existing output file
+        #[arg(long = "no-overwrite", action = ArgAction::SetTrue)]
+        no_overwrite: bool,
+
         /// Dump tokens (debug)
         #[arg(long = "tokens", action = ArgAction::SetTrue)]
         tokens: bool,
@@ -111,6 +201,12 @@ pub enum Command {
         /// Watch input for changes and re-run the emit when modified
         #[arg(long = "watch", action = ArgAction::SetTrue)]
         watch: bool,
+        /// Also write <out>.diag.json: the semantic and type diagnostics, source hash and compiler version
+        #[arg(long = "diag-sidecar", action = ArgAction::SetTrue)]
+        diag_sidecar: bool,
+        /// Also write <out>.map, a source map from the emitted JS back to the .ai file (JS only)
+        #[arg(long = "source-map", action = ArgAction::SetTrue)]
+        source_map: bool,
     },
 
     /// Run an .ai file directly (compile-to-js + execute with Node if available)
@@ -140,37 +236,242 @@ pub enum Command {
         /// Disassemble compiled bytecode (implies --bytecode)
         #[arg(long = "disasm", action = ArgAction::SetTrue)]
         disasm: bool,
+        /// Record rand/time_ms/measure/env results to FILE (implies --native)
+        #[arg(long = "record", value_name = "FILE", conflicts_with = "replay")]
+        record: Option<PathBuf>,
+        /// Replay results recorded with --record from FILE (implies --native)
+        #[arg(long = "replay", value_name = "FILE")]
+        replay: Option<PathBuf>,
+        /// Stop with an out-of-memory error once live values hold about MB megabytes (implies --native)
+        #[arg(long = "max-memory-mb", value_name = "MB")]
+        max_memory_mb: Option<usize>,
+        /// Write a source map next to the compiled JS and run Node with --enable-source-maps,
+        /// so stack traces name .ai lines
+        #[arg(long = "source-map", action = ArgAction::SetTrue)]
+        source_map: bool,
     },
 
-    /// Quantum execution (Titan local or Qiskit backends)
+    /// Compile an .ai file to JS, skipping the compile while no file it was built from changed
+    ///
+    /// Examples:
+    ///   aeonmi build main.ai -o main.js
+    ///   aeonmi build --explain main.js
+    Build {
+        #[arg(value_name = "INPUT", required_unless_present = "explain")]
+        input: Option<PathBuf>,
+        /// Output file path (short: -o)
+        #[arg(short = 'o', long = "out", value_name = "FILE", default_value = "output.js")]
+        out: PathBuf,
+        /// Print the dependency graph recorded for ARTIFACT (<ARTIFACT>.deps.json) instead of building
+        #[arg(long = "explain", value_name = "ARTIFACT", conflicts_with = "input")]
+        explain: Option<PathBuf>,
+    },
+
+    /// Quantum execution (Titan local or Qiskit backends), `quantum import` a circuit, or `quantum template`
+    #[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
     Quantum {
-        #[arg(value_enum, value_name = "BACKEND")]
-        backend: BackendKind,
-        #[arg(value_name = "FILE")]
-        file: PathBuf,
+        #[command(subcommand)]
+        action: Option<QuantumAction>,
+        #[arg(value_enum, value_name = "BACKEND", required = true)]
+        backend: Option<BackendKind>,
+        #[arg(value_name = "FILE", required = true)]
+        file: Option<PathBuf>,
         #[arg(long = "shots", value_name = "N")]
         shots: Option<usize>,
+        /// Qiskit optimization level for the aer backend; prints transpile stats as JSON
+        #[arg(long = "transpile-level", value_name = "0..3", value_parser = clap::value_parser!(u8).range(0..=3))]
+        transpile_level: Option<u8>,
+        /// Basis gates to transpile to (aer backend), e.g. `cx,rz,sx,x`
+        #[arg(long = "basis-gates", value_name = "GATES", value_delimiter = ',')]
+        basis_gates: Vec<String>,
+        /// Transpile against this fake backend's coupling map (aer backend), e.g. `FakeManilaV2`
+        #[arg(long = "fake-backend", value_name = "NAME")]
+        fake_backend: Option<String>,
+        /// Seed the measurement sampler (titan backend)
+        #[arg(long = "seed", value_name = "SEED")]
+        seed: Option<u64>,
+        /// Write a run bundle (source, circuit, histogram, statevector, manifest) to DIR (titan backend)
+        #[arg(long = "save-run", value_name = "DIR")]
+        save_run: Option<PathBuf>,
     },
 
-    /// Format .ai files
+    /// Format .ai files (files, directories or globs such as `src/**/*.ai`)
+    #[command(visible_alias = "fmt")]
     Format {
-        #[arg(value_name = "INPUTS")]
+        #[arg(value_name = "INPUTS", conflicts_with = "stdin")]
         inputs: Vec<PathBuf>,
+        #[command(flatten)]
+        discovery: DiscoveryArgs,
+        /// Report files that need formatting (with --stdin: print a unified diff) and exit 1
         #[arg(long = "check", action = ArgAction::SetTrue)]
         check: bool,
+        /// Keep each reformatted file's original next to it as `<file>.orig`
+        #[arg(long = "backup", action = ArgAction::SetTrue, conflicts_with = "stdin")]
+        backup: bool,
+        /// Format source read from stdin and write it to stdout (exit 3 if it does not parse)
+        #[arg(long = "stdin", action = ArgAction::SetTrue)]
+        stdin: bool,
+        /// Path the stdin buffer belongs to, for diagnostics and Aeonmi.toml lookup
+        #[arg(long = "stdin-filename", value_name = "PATH")]
+        stdin_filename: Option<PathBuf>,
+        #[command(flatten)]
+        style: FormatStyleArgs,
+    },
+
+    /// Type-check .ai files without emitting code (exit 3 on type errors)
+    #[command(visible_alias = "check")]
+    Typecheck {
+        #[arg(value_name = "INPUTS", required = true)]
+        inputs: Vec<PathBuf>,
+        #[command(flatten)]
+        discovery: DiscoveryArgs,
+        /// Show per-function inference time, slowest first
+        #[arg(long = "timings", action = ArgAction::SetTrue)]
+        timings: bool,
+        /// Output JSON (diagnostics and timings) instead of text
+        #[arg(long = "json", action = ArgAction::SetTrue)]
+        json: bool,
+        /// Print each file with its semantic and type diagnostics drawn under the lines they point at
+        #[arg(long = "annotate", action = ArgAction::SetTrue, conflicts_with_all = ["json", "timings"])]
+        annotate: bool,
+    },
+
+    /// Print the lowered IR of a .ai file and verify it (exit 1 on violations)
+    Ir {
+        #[arg(value_name = "INPUT")]
+        input: PathBuf,
+        #[arg(long = "json", action = ArgAction::SetTrue)]
+        json: bool,
+    },
+
+    /// List the symbols of a .ai file with kinds, positions and inferred types
+    Symbols {
+        #[arg(value_name = "INPUT")]
+        input: PathBuf,
+        /// Output JSON (a tree: functions hold their parameters and locals)
+        #[arg(long = "json", action = ArgAction::SetTrue, conflicts_with = "tree")]
+        json: bool,
+        /// Indent parameters and locals under their function
+        #[arg(long = "tree", action = ArgAction::SetTrue)]
+        tree: bool,
+    },
+
+    /// Symbol tree of a .ai file (same as `symbols --tree`)
+    Outline {
+        #[arg(value_name = "INPUT")]
+        input: PathBuf,
+    },
+
+    /// Structural diff of two .ai files: functions added, removed or renamed, statements changed
+    #[command(name = "diff-ast")]
+    DiffAst {
+        #[arg(value_name = "OLD")]
+        old: PathBuf,
+        #[arg(value_name = "NEW")]
+        new: PathBuf,
+        /// Output the changes as JSON
+        #[arg(long = "json", action = ArgAction::SetTrue)]
+        json: bool,
+    },
+
+    /// Check ```aeonmi code blocks in markdown docs
+    Doctest {
+        #[arg(value_name = "FILES", required = true)]
+        files: Vec<PathBuf>,
+    },
+
+    /// Explain a diagnostic code (e.g. AEO-P001) offline
+    ExplainError {
+        #[arg(value_name = "CODE")]
+        code: Option<String>,
+        /// List every known code
+        #[arg(long)]
+        list: bool,
+    },
+
+    /// Report which external tools (node, python, rustc) are installed and what is unavailable without them
+    Doctor,
+
+    /// Version, commit, enabled cargo features, emit kinds and config file paths of this binary
+    Info {
+        /// Machine-readable report for editors and CI
+        #[arg(long = "json", action = ArgAction::SetTrue)]
+        json: bool,
+    },
+
+    /// Check for a newer Aeonmi release (only when asked) and reinstall it with `cargo install`
+    Upgrade {
+        /// Ask the release feed for the latest version and show breaking changes since yours
+        #[arg(long)]
+        check: bool,
+        /// Check, then run `cargo install aeonmi --locked` after confirmation
+        #[arg(long)]
+        install: bool,
+        /// Install without asking
+        #[arg(long, short = 'y', requires = "install")]
+        yes: bool,
+        /// Never touch the network; just report the installed version. Env AEONMI_OFFLINE=1 also works.
+        #[arg(long)]
+        offline: bool,
+    },
+
+    /// Bundled example programs (list, show source, run)
+    Examples {
+        #[command(subcommand)]
+        action: ExamplesAction,
     },
 
-    /// Lint .ai files
+    /// Numerical demos backed by the Titan math library
+    Math {
+        #[command(subcommand)]
+        action: MathAction,
+    },
+
+    /// Lint .ai files (files, directories or globs)
     Lint {
         #[arg(value_name = "INPUTS")]
         inputs: Vec<PathBuf>,
+        #[command(flatten)]
+        discovery: DiscoveryArgs,
         #[arg(long = "fix", action = ArgAction::SetTrue)]
         fix: bool,
     },
 
-    /// Interactive REPL (placeholder for now)
+    /// Interactive session: entries share one interpreter (:help lists the meta-commands)
     Repl,
 
+    /// HTTP JSON API for compiling and running snippets (GET /health, POST /compile, POST /run)
+    ///
+    /// Examples:
+    ///   aeonmi serve --port 8080 --sandbox
+    Serve {
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+        /// Disable builtins that read the host (env())
+        #[arg(long = "sandbox", action = ArgAction::SetTrue)]
+        sandbox: bool,
+        /// Wall-clock limit per /run request
+        #[arg(long = "timeout-ms", value_name = "MS", default_value_t = 2000)]
+        timeout_ms: u64,
+        /// Largest accepted request body
+        #[arg(long = "max-body", value_name = "BYTES", default_value_t = 64 * 1024)]
+        max_body: usize,
+        /// Captured output per run before it is cut off with an error
+        #[arg(long = "max-output", value_name = "BYTES", default_value_t = 64 * 1024)]
+        max_output: usize,
+        /// Largest string a program may build
+        #[arg(long = "max-string", value_name = "BYTES", default_value_t = 1024 * 1024)]
+        max_string: usize,
+        /// Approximate memory a run's live values may hold
+        #[arg(long = "max-memory-mb", value_name = "MB", default_value_t = 64)]
+        max_memory_mb: usize,
+        /// Requests handled at once; extra ones get 503
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+    },
+
     /// Editor: line-mode by default; pass --tui for TUI editor
     Edit {
         #[arg(value_name = "FILE")]
@@ -216,11 +517,19 @@ pub enum Command {
         #[arg(value_name = "FILE")]
         file: PathBuf,
     },
+    /// Export a source file as json (AST), js, py, qasm or html (highlighted page)
     Export {
         #[arg(value_name = "FILE")]
         file: PathBuf,
-        #[arg(value_name = "FORMAT")]
+        /// json | js | py | qasm | html (default: the --out extension)
+        #[arg(long = "format", value_name = "FORMAT")]
         format: Option<String>,
+        /// Output path (default: next to FILE with the format's extension); `-` for stdout
+        #[arg(short = 'o', long = "out", value_name = "FILE")]
+        out: Option<PathBuf>,
+        /// Fail instead of replacing an existing output file
+        #[arg(long = "no-overwrite", action = ArgAction::SetTrue)]
+        no_overwrite: bool,
     },
     Upload {
         #[arg(value_name = "PATH")]
@@ -263,18 +572,27 @@ pub enum Command {
 
     /// Run a Cargo command (passthrough to system `cargo`). Example: aeonmi cargo build --release
     Cargo {
+        /// Inject vault entries into the child's environment (`VAR=entry` or names, comma-separated)
+        #[arg(long = "with-secrets", value_name = "NAMES")]
+        with_secrets: Option<String>,
         #[arg(value_name = "ARGS", trailing_var_arg = true)]
         args: Vec<String>,
     },
 
     /// Run a Python command / script (passthrough to `python`). Example: aeonmi python script.py
     Python {
+        /// Inject vault entries into the child's environment (`VAR=entry` or names, comma-separated)
+        #[arg(long = "with-secrets", value_name = "NAMES")]
+        with_secrets: Option<String>,
         #[arg(value_name = "ARGS", trailing_var_arg = true)]
         args: Vec<String>,
     },
 
     /// Run a Node.js command / script (passthrough to `node`). Example: aeonmi node file.js
     Node {
+        /// Inject vault entries into the child's environment (`VAR=entry` or names, comma-separated)
+        #[arg(long = "with-secrets", value_name = "NAMES")]
+        with_secrets: Option<String>,
         #[arg(value_name = "ARGS", trailing_var_arg = true)]
         args: Vec<String>,
     },
@@ -291,17 +609,66 @@ pub enum Command {
     #[command(name = "metrics-path")]
     MetricsPath,
 
-    /// Show top N slowest functions by average inference time
+    /// Show the top N slowest functions with their names and definition sites
     #[command(name = "metrics-top")]
     MetricsTop {
         /// Limit number of entries (default 10)
         #[arg(long = "limit", value_name = "N", default_value_t = 10)]
         limit: usize,
+        /// Column to rank by (descending)
+        #[arg(long = "sort", value_enum, default_value_t = TopSort::Ema)]
+        sort: TopSort,
+        /// Hide functions inferred fewer than N times
+        #[arg(long = "min-runs", value_name = "N", default_value_t = 0)]
+        min_runs: u64,
         /// Output JSON instead of table
         #[arg(long = "json", action = ArgAction::SetTrue)]
         json: bool,
     },
 
+    /// Live dashboard of the metrics file: top functions, savings, reinfer rate
+    #[command(name = "metrics-watch")]
+    MetricsWatch {
+        /// Number of functions shown (default 20)
+        #[arg(long = "limit", value_name = "N", default_value_t = 20)]
+        limit: usize,
+        /// Initial ranking column (`s` cycles it while watching)
+        #[arg(long = "sort", value_enum, default_value_t = TopSort::Ema)]
+        sort: TopSort,
+    },
+
+    /// Compare two metrics files (e.g. `metrics-dump` output before and after a refactor)
+    #[command(name = "metrics-diff")]
+    MetricsDiff {
+        /// BEFORE and AFTER metrics files (with --baseline, only AFTER; default: live metrics)
+        #[arg(value_name = "FILE", num_args = 0..=2)]
+        files: Vec<PathBuf>,
+        /// Baseline (before) metrics file captured earlier with `metrics-dump > file`
+        #[arg(long = "baseline", value_name = "FILE")]
+        baseline: Option<PathBuf>,
+        /// Slowdown (percent) or savings drop (percentage points) that counts as a regression
+        #[arg(long = "threshold", value_name = "PCT", default_value_t = 10.0)]
+        threshold: f64,
+        /// Exit non-zero when any regression exceeds the threshold
+        #[arg(long = "fail-on-regression", action = ArgAction::SetTrue)]
+        fail_on_regression: bool,
+        /// Output JSON instead of table
+        #[arg(long = "json", action = ArgAction::SetTrue)]
+        json: bool,
+    },
+
+    /// Inspect config: `config effective run` shows the flags `[defaults]` gives a subcommand
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Opt-in usage counters kept on this machine only (`config set stats.enabled true`)
+    Stats {
+        #[command(subcommand)]
+        action: StatsAction,
+    },
+
     /// Configure runtime metrics parameters (EMA alpha, window capacity)
     #[command(name = "metrics-config")]
     MetricsConfig {
@@ -389,15 +756,30 @@ pub enum Command {
         /// Additional arguments passed to the underlying runtime
         #[arg(value_name = "ARGS", trailing_var_arg = true)]
         args: Vec<String>,
-        /// Watch the file and re-run on change
+        /// Watch the file (plus `mod` files for .rs, the kept temp JS for .ai) and re-run on change
         #[arg(long = "watch", action = ArgAction::SetTrue)]
         watch: bool,
+        /// (--watch) Clear the terminal before each re-run
+        #[arg(long = "clear", action = ArgAction::SetTrue)]
+        clear: bool,
         /// Keep temporary compiled artifacts (e.g., __exec_tmp.js)
         #[arg(long = "keep-temp", action = ArgAction::SetTrue)]
         keep_temp: bool,
         /// (AI/JS only) Compile but skip executing node (useful for tests without node installed)
         #[arg(long = "no-run", action = ArgAction::SetTrue, hide = true)]
         no_run: bool,
+        /// (.ai only) Force the native VM instead of JS/node. Env AEONMI_NATIVE=1 also works.
+        #[arg(long = "native", action = ArgAction::SetTrue)]
+        native: bool,
+        /// Inject vault entries into the child's environment (`VAR=entry` or names, comma-separated)
+        #[arg(long = "with-secrets", value_name = "NAMES")]
+        with_secrets: Option<String>,
+        /// (.ai native runs) Deny `env()` to the program; secrets are refused
+        #[arg(long = "sandbox", action = ArgAction::SetTrue)]
+        sandbox: bool,
+        /// (.ai native runs) Stop with an out-of-memory error once live values hold about MB megabytes
+        #[arg(long = "max-memory-mb", value_name = "MB")]
+        max_memory_mb: Option<usize>,
     },
 
     /// Run an .ai file with the native VM (no JS / Node).
@@ -468,6 +850,23 @@ pub enum Command {
         file: std::path::PathBuf,
     },
 
+    /// Replay scripted edits, timing incremental vs full type inference per step
+    #[command(name = "metrics-replay")]
+    MetricsReplay {
+        /// Source file the edits apply to
+        #[arg(value_name = "FILE")]
+        file: std::path::PathBuf,
+        /// Edit script (JSON array of insert/delete/replace steps)
+        #[arg(value_name = "EDITS")]
+        edits: std::path::PathBuf,
+        /// Output JSON instead of table
+        #[arg(long = "json", action = ArgAction::SetTrue, conflicts_with = "csv")]
+        json: bool,
+        /// Output CSV instead of table
+        #[arg(long = "csv", action = ArgAction::SetTrue)]
+        csv: bool,
+    },
+
     /// Inject synthetic savings sample (test hook, feature debug-metrics)
     #[cfg(feature = "debug-metrics")]
     #[command(name = "metrics-inject-savings")]
@@ -485,23 +884,190 @@ pub enum Command {
         index: usize,
         #[arg(long = "dur", value_name = "NS")]
         dur: u128,
+        /// Function name to record as the definition site
+        #[arg(long = "name", value_name = "NAME")]
+        name: Option<String>,
+        /// Defining file (requires --name)
+        #[arg(long = "file", value_name = "FILE", requires = "name")]
+        file: Option<String>,
+        /// Definition line (requires --name)
+        #[arg(long = "line", value_name = "N", requires = "name", default_value_t = 0)]
+        line: usize,
     },
 }
 
 #[derive(Subcommand, Debug, Clone)]
 pub enum VmAction {
+    /// Start the resident interpreter in the background
     Start,
+    /// Shut the resident interpreter down
     Stop,
+    /// Uptime, evaluated modules, globals and mounted directories
     Status,
+    /// Drop every global and start over (mounts are kept)
     Reset,
+    /// Evaluate code in the running session; its globals persist for later evals
+    Eval {
+        /// Source text (read from --file when omitted)
+        #[arg(required_unless_present = "file", conflicts_with = "file")]
+        source: Option<String>,
+        #[arg(long, value_name = "FILE")]
+        file: Option<PathBuf>,
+    },
+    /// Save the session's globals as <config dir>/vm/snapshots/<NAME>.json
     Snapshot { name: String },
+    /// Replace the session's globals with a saved snapshot
     Restore { name: String },
+    /// Let read_file/write_file reach files under DIR
     Mount { dir: std::path::PathBuf },
+    /// Serve the session (spawned by `vm start`)
+    #[command(hide = true)]
+    Daemon,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum QuantumAction {
+    /// Turn a circuit JSON document (schema version 1) into an .ai or JS program
+    Import {
+        #[arg(value_name = "CIRCUIT_JSON")]
+        file: PathBuf,
+        #[arg(long, value_enum, default_value_t = EmitKind::Ai)]
+        emit: EmitKind,
+        /// Write here instead of stdout
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+    },
+    /// Compare two `--save-run` bundles: total variation distance between their histograms
+    Compare {
+        #[arg(value_name = "RUN_A")]
+        a: PathBuf,
+        #[arg(value_name = "RUN_B")]
+        b: PathBuf,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Generate a starter circuit (bell, ghz, grover, teleportation); without NAME, list them
+    Template {
+        #[arg(value_name = "NAME")]
+        name: Option<String>,
+        /// Register size (ghz, grover)
+        #[arg(long, value_name = "N")]
+        qubits: Option<usize>,
+        /// Marked basis state (grover)
+        #[arg(long, value_name = "N")]
+        marked: Option<usize>,
+        /// Bit to teleport, 0 or 1 (teleportation)
+        #[arg(long, value_name = "BIT")]
+        state: Option<usize>,
+        /// Shots in the printed histogram
+        #[arg(long, value_name = "N")]
+        shots: Option<usize>,
+        /// Write here instead of stdout
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ConfigAction {
+    /// Print every flag of a subcommand with its value after `[defaults]` from the user
+    /// config and the project `Aeonmi.toml`, and where the value came from
+    Effective {
+        /// Subcommand path, e.g. `run` or `ai review`
+        #[arg(value_name = "SUBCOMMAND")]
+        command: Vec<String>,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Set a dotted key in the user config, e.g. `config set stats.enabled true`
+    Set {
+        key: String,
+        /// A TOML value (`true`, `3`, `0.5`); anything else is stored as a string
+        value: String,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum StatsAction {
+    /// Invocations per subcommand: successes, failures and duration buckets
+    Show {
+        #[arg(long)]
+        json: bool,
+    },
+    /// Delete the recorded counters
+    Reset,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ExamplesAction {
+    /// List bundled examples with their descriptions and tags
+    List {
+        /// Only examples with this tag (e.g. quantum)
+        #[arg(long)]
+        tag: Option<String>,
+    },
+    /// Print an example's metadata and source
+    Show { name: String },
+    /// Run an example on the native VM
+    Run { name: String },
+}
+
+/// Dynamical system run by `math demo`.
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq)]
+pub enum MathSystem {
+    /// Lorenz attractor (sigma 10, rho 28, beta 8/3) from (1, 1, 1)
+    Lorenz,
+    /// Logistic map x -> r x (1 - x) from x = 0.2
+    Logistic,
+    /// y' = y from y = 1, whose exact solution is e^t
+    Exp,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum MathAction {
+    /// Simulate a dynamical system and summarize its trajectory (or write it as CSV)
+    Demo {
+        #[arg(value_enum, default_value_t = MathSystem::Lorenz)]
+        system: MathSystem,
+        /// Write every point as CSV, for plotting elsewhere
+        #[arg(long = "plot-csv", value_name = "FILE")]
+        plot_csv: Option<PathBuf>,
+        /// Step size (lorenz, exp)
+        #[arg(long, default_value_t = 0.01)]
+        dt: f64,
+        /// End time (lorenz, exp; default 20 and 1)
+        #[arg(long = "t-end")]
+        t_end: Option<f64>,
+        /// Growth rate (logistic)
+        #[arg(long, default_value_t = 3.7)]
+        r: f64,
+        /// Iterations (logistic)
+        #[arg(long, default_value_t = 100)]
+        iterations: usize,
+    },
+    /// Differentiate an expression symbolically, e.g. `math derive "x^2 + 3*x" --wrt x`
+    Derive {
+        /// Expression in + - * / ^ and sin, cos, tan, exp, ln, sqrt
+        expr: String,
+        /// Variable to differentiate with respect to
+        #[arg(long, default_value = "x")]
+        wrt: String,
+    },
 }
 
 #[derive(Subcommand, Debug, Clone)]
 pub enum AiAction {
-    Suggest,
+    /// Generate Aeonmi code from a description; it must parse (one retry) before it is used
+    Suggest {
+        /// What the code should do, e.g. "a function that deduplicates a list"
+        #[arg(long)]
+        prompt: String,
+        /// Insert the code into FILE before LINE (1-based) instead of printing it
+        #[arg(long, value_name = "FILE:LINE")]
+        insert_at: Option<String>,
+        #[arg(long)]
+        provider: Option<String>,
+    },
     Debug,
     Optimize,
     Explain {
@@ -511,9 +1077,50 @@ pub enum AiAction {
         rule: Option<String>,
     },
     Chat {
+        #[arg(long)]
         provider: Option<String>,
+        /// Prompt text (read from stdin when omitted)
         prompt: Option<String>,
+        #[arg(long)]
         list: bool,
+        /// Print the reply as it arrives (spinner until the first chunk; Ctrl-C cancels)
+        #[arg(long)]
         stream: bool,
+        /// Render markdown (bold, inline code, headings, highlighted aeonmi code blocks) once the reply is complete
+        #[arg(long)]
+        markdown: bool,
+        /// Put the final reply on the system clipboard (a temp file when there is none)
+        #[arg(long)]
+        copy: bool,
+        /// With --copy: copy even if the reply looks like it contains a secret
+        #[arg(long, requires = "copy")]
+        force: bool,
+        /// Print `{provider, reply, failed}` as JSON: who answered and who failed first
+        #[arg(long, conflicts_with_all = ["stream", "markdown"])]
+        json: bool,
+    },
+    /// Summarize recorded AI calls (tokens, wall time, estimated cost)
+    Usage {
+        /// Only count calls newer than this (e.g. 30m, 12h, 7d)
+        #[arg(long)]
+        since: Option<String>,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Review changed .ai files function by function (`git diff`, `--staged` or `--range A..B`)
+    Review {
+        /// Review staged changes instead of the working tree
+        #[arg(long, conflicts_with = "range")]
+        staged: bool,
+        /// Review a commit range, e.g. `main..HEAD`
+        #[arg(long, value_name = "A..B")]
+        range: Option<String>,
+        #[arg(long)]
+        provider: Option<String>,
+        /// Report lint findings on the changed lines instead of asking a provider
+        #[arg(long)]
+        offline: bool,
+        #[arg(long)]
+        json: bool,
     },
 }
@@ -0,0 +1,139 @@
+//! `aeonmi ai review`: review the .ai changes in the working tree (`git diff`), the index
+//! (`--staged`) or a commit range (`--range A..B`) with the AI provider, or offline with
+//! lint findings (`--offline`). See [`crate::ai::review`] for chunking and prompts.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::exit_codes::{fail, GENERIC, USAGE};
+use crate::ai::review::{self, Chunk, PromptTemplate, ReviewComment};
+use crate::ai::{AiProvider, AiRegistry};
+use crate::core::lexer::Lexer;
+use crate::core::parser::Parser;
+use crate::core::style;
+
+/// Which side of history is reviewed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffSource {
+    /// Unstaged changes, read from the working tree.
+    WorkTree,
+    /// Staged changes, read from the index.
+    Staged,
+    /// `A..B`: B's files against A (B defaults to `HEAD`).
+    Range(String),
+}
+
+impl DiffSource {
+    fn diff_args(&self) -> Vec<String> {
+        match self {
+            DiffSource::WorkTree => vec![],
+            DiffSource::Staged => vec!["--cached".into()],
+            DiffSource::Range(r) => vec![r.clone()],
+        }
+    }
+
+    /// Contents of `path` (relative to the repository root) on the reviewed side.
+    fn read(&self, root: &Path, path: &str) -> Result<String> {
+        match self {
+            DiffSource::WorkTree => std::fs::read_to_string(root.join(path)).map_err(|e| fail(GENERIC, format!("{path}: {e}"))),
+            DiffSource::Staged => git(root, &["show", &format!(":{path}")]),
+            DiffSource::Range(r) => {
+                let new = r.rsplit_once("..").map(|(_, b)| b.trim_start_matches('.')).filter(|b| !b.is_empty()).unwrap_or("HEAD");
+                git(root, &["show", &format!("{new}:{path}")])
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReviewReport {
+    pub chunks: Vec<Chunk>,
+    pub comments: Vec<ReviewComment>,
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let out = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .map_err(|e| fail(GENERIC, format!("cannot run git: {e}")))?;
+    if !out.status.success() {
+        let err = String::from_utf8_lossy(&out.stderr);
+        return Err(fail(GENERIC, format!("git {}: {}", args.join(" "), err.trim())));
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// Chunks of every changed .ai file under `dir`'s repository.
+pub fn collect(dir: &Path, source: &DiffSource) -> Result<Vec<(Chunk, String)>> {
+    let root = PathBuf::from(git(dir, &["rev-parse", "--show-toplevel"])?.trim());
+    let mut args = vec!["diff", "--no-color", "--no-ext-diff", "--unified=3"];
+    let extra = source.diff_args();
+    args.extend(extra.iter().map(String::as_str));
+    args.extend(["--", "*.ai"]);
+    let diff = git(&root, &args)?;
+    let mut out = Vec::new();
+    for file in review::parse_diff(&diff) {
+        let text = source.read(&root, &file.path)?;
+        for c in review::chunk(&file, &text) {
+            out.push((c, text.clone()));
+        }
+    }
+    Ok(out)
+}
+
+/// The provider named `name`, else the first enabled one. `hint` ends the message
+/// shown when none is enabled.
+pub(crate) fn pick_provider<'a>(reg: &'a AiRegistry, name: Option<&str>, hint: &str) -> Result<&'a dyn AiProvider> {
+    let name = name.map(str::to_string).or_else(|| reg.list().first().map(|s| s.to_string()));
+    let Some(name) = name else {
+        return Err(fail(USAGE, format!("no AI provider enabled (build with --features ai-openai, ..., set AEONMI_AI_MOCK=1{hint})")));
+    };
+    reg.get(&name).ok_or_else(|| fail(USAGE, format!("provider '{name}' not found in enabled set: {:?}", reg.list())))
+}
+
+/// Review every chunk: with the provider's replies, or with lint findings when `offline`.
+pub fn review(chunks: Vec<(Chunk, String)>, offline: bool, provider: Option<&str>, template: &PromptTemplate) -> Result<ReviewReport> {
+    let mut comments = Vec::new();
+    if offline {
+        for (c, text) in &chunks {
+            let ast = Lexer::from_str(text).tokenize().ok().and_then(|t| Parser::new(t).parse().ok());
+            match ast {
+                Some(ast) => comments.extend(review::lint_comments(c, &ast)),
+                None => comments.push(ReviewComment {
+                    file: c.file.clone(),
+                    line: c.start,
+                    severity: "error".into(),
+                    scope: c.scope.clone(),
+                    source: "lint".into(),
+                    message: "file does not parse; run `aeonmi typecheck` on it".into(),
+                }),
+            }
+        }
+    } else if !chunks.is_empty() {
+        let reg = AiRegistry::new();
+        let p = pick_provider(&reg, provider, ", or use --offline")?;
+        for (c, _) in &chunks {
+            let reply = crate::ai::chat_recorded(p, &template.render(c)).map_err(|e| fail(GENERIC, format!("{}: {e}", p.name())))?;
+            comments.extend(review::parse_reply(c, &reply, p.name()));
+        }
+    }
+    comments.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+    Ok(ReviewReport { chunks: chunks.into_iter().map(|(c, _)| c).collect(), comments })
+}
+
+pub fn main(source: DiffSource, offline: bool, provider: Option<String>, json: bool, config: Option<PathBuf>) -> Result<()> {
+    let template = PromptTemplate::from_config(config.as_deref()).map_err(|e| fail(USAGE, format!("{e:#}")))?;
+    let chunks = collect(&std::env::current_dir()?, &source)?;
+    let report = review(chunks, offline, provider.as_deref(), &template)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if report.chunks.is_empty() {
+        println!("no .ai changes to review");
+    } else {
+        print!("{}", review::render(&report.comments, report.chunks.len(), &style::current()));
+    }
+    Ok(())
+}
@@ -0,0 +1,51 @@
+//! `aeonmi ai suggest --prompt TEXT [--insert-at FILE:LINE]`: ask the provider for Aeonmi
+//! code, make sure it parses (retrying once), then print it or insert it into a file.
+//! See [`crate::ai::suggest`].
+
+use anyhow::Result;
+use std::path::PathBuf;
+
+use super::ai_review::pick_provider;
+use super::exit_codes::{fail, COMPILE_DIAG, GENERIC, IO, USAGE};
+use crate::ai::suggest::{self, SuggestTemplate};
+use crate::ai::AiRegistry;
+use crate::core::style::{self, Role};
+
+pub fn main(prompt: String, insert_at: Option<String>, provider: Option<String>, config: Option<PathBuf>) -> Result<()> {
+    if prompt.trim().is_empty() {
+        return Err(fail(USAGE, "--prompt is empty"));
+    }
+    // Check the target before spending a provider call on it.
+    let target = match &insert_at {
+        Some(spec) => {
+            let (file, line) = suggest::parse_location(spec).map_err(|e| fail(USAGE, e.to_string()))?;
+            let text = std::fs::read_to_string(&file).map_err(|e| fail(IO, format!("{file}: {e}")))?;
+            suggest::insert(&text, line, "").map_err(|e| fail(USAGE, format!("{file}: {e}")))?;
+            Some((file, line, text))
+        }
+        None => None,
+    };
+    let template = SuggestTemplate::from_config(config.as_deref()).map_err(|e| fail(USAGE, format!("{e:#}")))?;
+    let reg = AiRegistry::new();
+    let p = pick_provider(&reg, provider.as_deref(), "")?;
+    let st = style::current();
+    let mut on_retry = |error: &str| {
+        if !crate::core::diagnostics::quiet() {
+            eprintln!("{} reply did not parse ({error}); asking again", st.paint("warn:", Role::Warn));
+        }
+    };
+    let s = suggest::generate(p, &template.render(&prompt), &mut on_retry).map_err(|e| {
+        let code = if e.is::<suggest::Unparsable>() { COMPILE_DIAG } else { GENERIC };
+        fail(code, format!("{}: {e:#}", p.name()))
+    })?;
+    match target {
+        Some((file, line, text)) => {
+            let updated = suggest::insert(&text, line, &s.code).map_err(|e| fail(USAGE, format!("{file}: {e}")))?;
+            std::fs::write(&file, updated).map_err(|e| fail(IO, format!("{file}: {e}")))?;
+            let n = s.code.lines().count();
+            println!("inserted {n} line{} into {file} at line {line}", if n == 1 { "" } else { "s" });
+        }
+        None => print!("{}", s.code),
+    }
+    Ok(())
+}
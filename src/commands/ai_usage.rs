@@ -0,0 +1,25 @@
+//! `aeonmi ai usage`: summarize the AI call ledger with estimated cost.
+
+use anyhow::Result;
+use std::path::PathBuf;
+
+use super::exit_codes::{fail, USAGE};
+use crate::core::ai_usage::{ledger_path, load, now_secs, parse_since, render_summary, summarize, PriceTable};
+
+pub fn main(since: Option<String>, json: bool, config: Option<PathBuf>) -> Result<()> {
+    let since = match since.as_deref().map(parse_since).transpose() {
+        Ok(secs) => secs.map(|s| now_secs().saturating_sub(s)),
+        Err(e) => return Err(fail(USAGE, e)),
+    };
+    let prices = PriceTable::from_config(config.as_deref())?;
+    let path = ledger_path();
+    let summary = summarize(&load(&path), since, &prices);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    } else if summary.total.calls == 0 {
+        println!("no AI calls recorded in {}", path.display());
+    } else {
+        print!("{}", render_summary(&summary));
+    }
+    Ok(())
+}
@@ -0,0 +1,43 @@
+//! `aeonmi build`: compile an `.ai` program to JS, skipping the compile while nothing it
+//! was built from changed (see `deps_manifest`). `run` goes through [`ensure`] too.
+//! `build --explain <artifact>` prints the dependency graph recorded for an artifact.
+
+use std::path::Path;
+
+use crate::commands::compile::compile_tracked;
+use crate::commands::exit_codes::{fail, IO};
+use crate::config::LexerSettings;
+use crate::core::deps_manifest::{self, BuildOptions, DepsManifest};
+use crate::core::diagnostics::quiet;
+
+/// Compile `input` to `out` unless `out` is up to date. `Ok(true)` when it was compiled.
+pub fn ensure(input: &Path, out: &Path, pretty: bool, skip_sema: bool, source_map: bool) -> anyhow::Result<bool> {
+    // An unreadable manifest is the compile's to report
+    if let Ok(settings) = LexerSettings::discover(input) {
+        let options = BuildOptions { sema_skipped: skip_sema, source_map, plugins: settings.enabled() };
+        match deps_manifest::validate(out, input, &options) {
+            Ok(_) => {
+                debug_log!("reusing {}: no dependency changed", out.display());
+                return Ok(false);
+            }
+            Err(stale) => debug_log!("recompiling {}: {}", out.display(), stale),
+        }
+    }
+    compile_tracked(input.to_path_buf(), out.to_path_buf(), pretty, skip_sema, source_map)?;
+    Ok(true)
+}
+
+pub fn main(input: &Path, out: &Path, pretty: bool, skip_sema: bool) -> anyhow::Result<()> {
+    if !ensure(input, out, pretty, skip_sema, false)? && !quiet() {
+        println!("ok: '{}' is up to date.", out.display());
+    }
+    Ok(())
+}
+
+pub fn explain(artifact: &Path) -> anyhow::Result<()> {
+    let Some(manifest) = DepsManifest::load(artifact) else {
+        return Err(fail(IO, format!("no dependency manifest for '{}' (expected {})", artifact.display(), deps_manifest::manifest_path(artifact).display())));
+    };
+    print!("{}", manifest.explain(artifact));
+    Ok(())
+}
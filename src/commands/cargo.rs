@@ -0,0 +1,18 @@
+//! `aeonmi cargo ...`: run the system `cargo` with the arguments as given, optionally with
+//! vault secrets in its environment. Also how `aeonmi upgrade --install` reinstalls.
+
+use anyhow::Result;
+use std::path::Path;
+
+use crate::commands::secrets;
+use crate::config::LexerSettings;
+
+pub fn passthrough(with_secrets: Option<&str>, args: &[String]) -> Result<()> {
+    let secrets = secrets::prepare(with_secrets, Path::new(LexerSettings::MANIFEST), false, "cargo")?;
+    let status = secrets.apply(&mut std::process::Command::new("cargo")).args(args).status();
+    match status {
+        Ok(s) if s.success() => Ok(()),
+        Ok(s) => anyhow::bail!("cargo exited with status {}", s),
+        Err(e) => Err(secrets.scrub_error(anyhow::anyhow!("failed to execute cargo: {e}"))),
+    }
+}
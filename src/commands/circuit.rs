@@ -0,0 +1,54 @@
+//! `aeonmi quantum import circuit.json`: synthesize a program from a circuit document.
+//! `aeonmi quantum template ghz --qubits 4`: generate a starter circuit.
+
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::exit_codes::{fail, COMPILE_DIAG, IO, USAGE};
+use crate::cli::EmitKind;
+use crate::core::code_generator::CodeGenerator;
+use crate::core::lexer::Lexer;
+use crate::core::parser::Parser;
+use crate::core::quantum_extract::{circuit_from_json, circuit_to_ai};
+use crate::core::quantum_templates::{self, TEMPLATES};
+
+pub fn import(file: &Path, emit: EmitKind, out: Option<&Path>) -> Result<()> {
+    let text = std::fs::read_to_string(file).map_err(|e| fail(IO, format!("could not read '{}': {}", file.display(), e)))?;
+    let circuit = circuit_from_json(&text).map_err(|e| fail(COMPILE_DIAG, format!("{}: {}", file.display(), e)))?;
+    let program = format!("// imported from {}\n{}", file.display(), circuit_to_ai(&circuit));
+    let output = match emit {
+        EmitKind::Ai => program,
+        EmitKind::Js => {
+            let tokens = Lexer::from_str(&program).tokenize().map_err(|e| fail(COMPILE_DIAG, e.to_string()))?;
+            let ast = Parser::new(tokens).parse().map_err(|e| fail(COMPILE_DIAG, e.to_string()))?;
+            CodeGenerator::new().generate(&ast).map_err(|e| fail(COMPILE_DIAG, e))?
+        }
+    };
+    match out {
+        Some(path) => std::fs::write(path, output).map_err(|e| fail(IO, format!("could not write '{}': {}", path.display(), e))),
+        None => {
+            print!("{}", output);
+            Ok(())
+        }
+    }
+}
+
+/// Instantiate template `name` with `params` (flags the template lacks are an error), or list templates.
+pub fn template(name: Option<&str>, params: &BTreeMap<String, usize>, out: Option<&Path>) -> Result<()> {
+    let Some(name) = name else {
+        for t in TEMPLATES {
+            let ps: Vec<String> = t.params.iter().map(|p| format!("--{} {}..={} (default {})", p.name, p.min, p.max, p.default)).collect();
+            println!("{:<14} {}\n{:<14} {}", t.name, t.description, "", ps.join(", "));
+        }
+        return Ok(());
+    };
+    let source = quantum_templates::instantiate(name, params).map_err(|e| fail(USAGE, e))?;
+    match out {
+        Some(path) => std::fs::write(path, source).map_err(|e| fail(IO, format!("could not write '{}': {}", path.display(), e))),
+        None => {
+            print!("{}", source);
+            Ok(())
+        }
+    }
+}
@@ -2,16 +2,23 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::exit;
 
-use colored::Colorize;
 
 use crate::cli::EmitKind;
+use crate::config::LexerSettings;
 use crate::core::code_generator::CodeGenerator;
-use crate::core::diagnostics::{print_error, emit_json_error, Span};
+use crate::core::deps_manifest::{manifest_path, BuildOptions, DepsManifest};
+use crate::core::diag_sidecar::{sidecar_path, DiagSidecar};
+use crate::core::diagnostics::{emit_json_error_coded, emit_json_error_with_notes, parse_error_parts, print_error_coded, quiet, render_error_with_notes, report_error, report_parse_error, Span};
+use crate::core::error_index;
+use crate::core::imports;
+use crate::core::style::{self, Role};
 use crate::core::lexer::{Lexer, LexerError};
-use crate::core::parser::{Parser as AeParser, ParserError}; // JS + AI backends
+use crate::core::parser::Parser as AeParser; // JS + AI backends
 use crate::core::artifact_cache::{get_artifact, put_artifact};
 use sha1::{Sha1, Digest};
 
+use super::exit_codes;
+
 #[allow(dead_code, clippy::too_many_arguments)]
 pub fn main_with_opts(
     input: PathBuf,
@@ -46,155 +53,322 @@ pub fn compile_pipeline(
     pretty: bool,
     skip_sema: bool,    // honored via note (codegen path doesn’t need it)
     _debug_titan: bool, // wired for Titan debug; unused in this frontend
+) -> anyhow::Result<()> {
+    compile_with(input, emit, out, print_tokens, print_ast, pretty, skip_sema, false, Extras::default())
+}
+
+/// Files written next to the output besides the code itself; both need `out` to be a file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Extras {
+    /// `<out>.diag.json` (`emit --diag-sidecar`).
+    pub diag_sidecar: bool,
+    /// `<out>.map` plus a `sourceMappingURL` comment (`--source-map`; JS only).
+    pub source_map: bool,
+    /// `<out>.deps.json`, so `run` and `build` can reuse the output (see `deps_manifest`).
+    pub deps_manifest: bool,
+}
+
+/// `compile_pipeline` that also writes `<out>.diag.json` (`emit --diag-sidecar`); `out` must be a file.
+#[allow(clippy::too_many_arguments)]
+pub fn compile_pipeline_with_sidecar(
+    input: Option<PathBuf>,
+    emit: EmitKind,
+    out: PathBuf,
+    print_tokens: bool,
+    print_ast: bool,
+    pretty: bool,
+    skip_sema: bool,
+) -> anyhow::Result<()> {
+    let extras = Extras { diag_sidecar: true, ..Extras::default() };
+    compile_with(input, emit, out, print_tokens, print_ast, pretty, skip_sema, false, extras)
+}
+
+/// `compile_pipeline` writing the files `extras` asks for alongside `out`.
+#[allow(clippy::too_many_arguments)]
+pub fn compile_pipeline_with_extras(
+    input: Option<PathBuf>,
+    emit: EmitKind,
+    out: PathBuf,
+    print_tokens: bool,
+    print_ast: bool,
+    pretty: bool,
+    skip_sema: bool,
+    extras: Extras,
+) -> anyhow::Result<()> {
+    compile_with(input, emit, out, print_tokens, print_ast, pretty, skip_sema, false, extras)
+}
+
+/// Compile `input` to JS at `out` ahead of running it (`run`, `exec`): the status notes
+/// go to stderr so stdout carries only the program's own output. With `source_map`,
+/// `<out>.map` lets `node --enable-source-maps` report `.ai` lines.
+pub fn compile_for_run(input: PathBuf, out: PathBuf, pretty: bool, skip_sema: bool, source_map: bool) -> anyhow::Result<()> {
+    let extras = Extras { source_map, ..Extras::default() };
+    compile_with(Some(input), EmitKind::Js, out, false, false, pretty, skip_sema, true, extras)
+}
+
+/// `compile_for_run` that also records `<out>.deps.json` (`run`, `build`).
+pub fn compile_tracked(input: PathBuf, out: PathBuf, pretty: bool, skip_sema: bool, source_map: bool) -> anyhow::Result<()> {
+    let extras = Extras { source_map, deps_manifest: true, ..Extras::default() };
+    compile_with(Some(input), EmitKind::Js, out, false, false, pretty, skip_sema, true, extras)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn compile_with(
+    input: Option<PathBuf>,
+    emit: EmitKind,
+    out: PathBuf,
+    print_tokens: bool,
+    print_ast: bool,
+    pretty: bool,
+    skip_sema: bool,
+    notes_to_stderr: bool,
+    extras: Extras,
 ) -> anyhow::Result<()> {
     let input_path = input.as_deref()
         .unwrap_or_else(|| Path::new("examples/hello.ai"));
 
-    // Load source (with fallback)
+    // Load source (fallback only when no input was given)
     let source = match fs::read_to_string(input_path) {
         Ok(s) => s,
+        Err(e) if input.is_some() => {
+            if !quiet() {
+                eprintln!(
+                    "{} could not read '{}': {}",
+                    style::current().paint("error:", Role::Error),
+                    input_path.display(),
+                    e
+                );
+            }
+            exit(exit_codes::IO);
+        }
         Err(_) => {
-            eprintln!(
-                "{} Could not read '{}', using default inline code.",
-                "warn:".yellow().bold(),
-                input_path.display()
-            );
+            if !quiet() {
+                eprintln!(
+                    "{} Could not read '{}', using default inline code.",
+                    style::current().paint("warn:", Role::Warn),
+                    input_path.display()
+                );
+            }
             "let x = 42;\nlog(x);".to_string()
         }
     };
 
+    // With `--out -` stdout carries only the emitted code; everything else moves to stderr.
+    let to_stdout = is_stdout(&out);
+    let info = |text: &str| if to_stdout || notes_to_stderr { eprintln!("{}", text) } else { println!("{}", text) };
+
     if print_tokens || print_ast {
-        println!("=== Source Code ===\n{}\n", source);
+        info(&format!("=== Source Code ===\n{}\n", source));
     }
 
+    // Lexer plugins from the project manifest ([lexer.plugins] in Aeonmi.toml)
+    let lexer_settings = match LexerSettings::discover(input_path) {
+        Ok(s) => s,
+        Err(e) => {
+            if !quiet() {
+                eprintln!("{} {:#}", style::current().paint("error:", Role::Error), e);
+            }
+            exit(exit_codes::USAGE);
+        }
+    };
+
     // Lex
-    let mut lexer = Lexer::from_str(&source);
+    let lexer_options = lexer_settings.lexer_options();
+    let mut lexer = Lexer::with_options(&source, lexer_options.clone());
     let tokens = match lexer.tokenize() {
         Ok(t) => t,
         Err(e) => {
-            if pretty {
-                match e {
-                    LexerError::UnexpectedCharacter(_, line, col)
-                    | LexerError::UnterminatedString(line, col)
-                    | LexerError::InvalidNumber(_, line, col)
-                    | LexerError::InvalidQubitLiteral(_, line, col)
-                    | LexerError::UnterminatedComment(line, col) => {
-                        emit_json_error(
-                            &input_path.display().to_string(),
-                            &format!("{}", e),
-                            &Span::single(line, col),
-                        );
-                        print_error(
-                            &input_path.display().to_string(),
-                            &source,
-                            &format!("{}", e),
-                            Span::single(line, col),
-                        );
-                    }
-                    _ => {
-                        // Fallback: show full diagnostic for other lexer errors
-                        eprintln!("{} Lexing error: {}", "error:".bright_red(), e);
-                    }
-                }
-            } else {
-                eprintln!("{} Lexing error: {}", "error:".bright_red(), e);
-            }
-            exit(1);
+            let (line, col) = e.position();
+            report_error(
+                &input_path.display().to_string(),
+                &source,
+                &format!("Lexing error: {}", e),
+                Span::single(line, col),
+                Some(error_index::LEX_ERROR),
+                pretty,
+            );
+            exit(exit_codes::COMPILE_DIAG);
         }
     };
 
     if print_tokens {
-        println!("=== Tokens ===");
+        info("=== Tokens ===");
         for token in &tokens {
-            println!("{}", token);
+            info(&token.to_string());
         }
-        println!();
+        info("");
     }
 
     // Parse
     let mut parser = AeParser::new(tokens.clone());
     let ast = match parser.parse() {
         Ok(a) => a,
-        Err(ParserError {
-            message,
-            line,
-            column,
-        }) => {
-            if pretty {
-                emit_json_error(
-                    &input_path.display().to_string(),
-                    &format!("Parsing error: {}", message),
-                    &Span::single(line, column),
-                );
-                print_error(
-                    &input_path.display().to_string(),
-                    &source,
-                    &format!("Parsing error: {}", message),
-                    Span::single(line, column),
-                );
-            } else {
-                eprintln!("{} Parsing error: {}", "error:".bright_red(), message);
-            }
-            exit(1);
+        Err(e) => {
+            report_parse_error(&input_path.display().to_string(), &source, &e, pretty);
+            exit(exit_codes::COMPILE_DIAG);
         }
     };
 
+    // Splice `import`ed files in, so everything below sees one program
+    let flattened = match imports::resolve(ast, input_path, &source, &lexer_options) {
+        Ok(f) => f,
+        Err(e) => {
+            e.report(pretty);
+            exit(exit_codes::COMPILE_DIAG);
+        }
+    };
+    let ast = &flattened.program;
+
+    if print_tokens {
+        for file in &flattened.imported {
+            info(&format!("=== Tokens ({}) ===", file.path.display()));
+            for token in &file.tokens {
+                info(&token.to_string());
+            }
+            info("");
+        }
+    }
+
     if print_ast {
-        println!("=== AST ===\n{:#?}\n", ast);
+        info(&format!("=== AST ===\n{:#?}\n", ast));
+        if !flattened.imported.is_empty() {
+            info(&format!("=== Origins ===\n{}\n", flattened.origin_lines().join("\n")));
+        }
     }
 
     // Honor --no-sema with a clear note (expected by tests)
-    if skip_sema {
-        println!("note: semantic analysis skipped");
+    if skip_sema && !quiet() {
+        info("note: semantic analysis skipped");
     }
 
     // Artifact cache key: hash(source)+emit kind
     let mut hasher = Sha1::new(); hasher.update(source.as_bytes()); hasher.update(match emit { EmitKind::Ai=>b"AI", EmitKind::Js=>b"JS" });
+    // plugins change what the source means, so they are part of the key
+    for name in lexer_settings.enabled() { hasher.update(b"\0"); hasher.update(name.as_bytes()); }
+    // and so does every imported file
+    for file in &flattened.imported { hasher.update(b"\0"); hasher.update(file.source.as_bytes()); }
     let key = format!("{:x}", hasher.finalize());
-    let output_string = if let Some(entry) = get_artifact(&key) { String::from_utf8(entry.data).unwrap_or_default() } else {
+    // A source map names the input and output paths, so mapped output bypasses the cache.
+    let mut source_map = None;
+    let output_string = if extras.source_map && matches!(emit, EmitKind::Js) && !to_stdout {
+        let name = crate::core::source_map::source_name(input_path, &out);
+        match CodeGenerator::new().generate_source_mapped(ast, &out, &name, &source) {
+            Ok((code, map)) => {
+                source_map = Some(map);
+                code
+            }
+            Err(e) => {
+                if !quiet() {
+                    eprintln!("{} JS emit failed: {}", style::current().paint("error:", Role::Error), e);
+                }
+                exit(exit_codes::COMPILE_DIAG);
+            }
+        }
+    } else if let Some(entry) = get_artifact(&key) { String::from_utf8(entry.data).unwrap_or_default() } else {
         let generated = match emit {
             EmitKind::Ai => {
                 let mut gen = CodeGenerator::new_ai();
-                match gen.generate(&ast) { Ok(s)=>s, Err(e)=>{ eprintln!("{} AI emit failed: {}", "error:".bright_red().bold(), e); exit(1);} }
+                match gen.generate(ast) { Ok(s)=>s, Err(e)=>{ if !quiet() { eprintln!("{} AI emit failed: {}", style::current().paint("error:", Role::Error), e); } exit(exit_codes::COMPILE_DIAG);} }
             }
             EmitKind::Js => {
                 let mut gen = CodeGenerator::new();
-                match gen.generate(&ast) { Ok(s)=>s, Err(e)=>{ eprintln!("{} JS emit failed: {}", "error:".bright_red().bold(), e); exit(1);} }
+                match gen.generate(ast) { Ok(s)=>s, Err(e)=>{ if !quiet() { eprintln!("{} JS emit failed: {}", style::current().paint("error:", Role::Error), e); } exit(exit_codes::COMPILE_DIAG);} }
             }
         };
         put_artifact(key.clone(), generated.as_bytes().to_vec());
         generated
     };
+    let file = input_path.display().to_string();
+    let sidecar = extras.diag_sidecar.then(|| cached_sidecar(&key, &file, &source, &flattened, skip_sema));
+
+    if to_stdout {
+        use std::io::Write;
+        let mut stdout = std::io::stdout().lock();
+        if let Err(e) = stdout.write_all(output_string.as_bytes()).and_then(|_| stdout.flush()) {
+            if !quiet() {
+                eprintln!("{} could not write to stdout: {}", style::current().paint("error:", Role::Error), e);
+            }
+            exit(exit_codes::IO);
+        }
+        crate::core::incremental::persist_metrics();
+        crate::core::incremental::ensure_metrics_file_exists();
+        return Ok(());
+    }
 
     // Ensure output directory exists
     if let Some(parent) = out.parent() {
         if !parent.as_os_str().is_empty() {
             if let Err(e) = fs::create_dir_all(parent) {
-                eprintln!(
-                    "{} could not create output dir '{}': {}",
-                    "error:".bright_red().bold(),
-                    parent.display(),
-                    e
-                );
-                exit(1);
+                if !quiet() {
+                    eprintln!(
+                        "{} could not create output dir '{}': {}",
+                        style::current().paint("error:", Role::Error),
+                        parent.display(),
+                        e
+                    );
+                }
+                exit(exit_codes::IO);
             }
         }
     }
 
     // Write file
     if let Err(e) = fs::write(&out, output_string) {
-        if pretty {
-            eprintln!("{} {}", "error:".bright_red().bold(), e);
-        } else {
-            eprintln!("Failed to write output: {}", e);
+        if !quiet() {
+            if pretty {
+                eprintln!("{} {}", style::current().paint("error:", Role::Error), e);
+            } else {
+                eprintln!("Failed to write output: {}", e);
+            }
         }
-        exit(1);
+        exit(exit_codes::IO);
     }
 
     // Match legacy success phrasing exactly (tests depend on it)
-    match emit {
-        EmitKind::Js => println!("ok: wrote js to '{}'.", out.display()),
-        EmitKind::Ai => println!("ok: wrote ai to '{}'.", out.display()),
+    if !quiet() {
+        match emit {
+            EmitKind::Js => info(&format!("ok: wrote js to '{}'.", out.display())),
+            EmitKind::Ai => info(&format!("ok: wrote ai to '{}'.", out.display())),
+        }
+    }
+
+    if let Some(map) = source_map {
+        let path = crate::core::source_map::map_path(&out);
+        if let Err(e) = fs::write(&path, map.to_json() + "\n") {
+            if !quiet() {
+                eprintln!("{} could not write '{}': {}", style::current().paint("error:", Role::Error), path.display(), e);
+            }
+            exit(exit_codes::IO);
+        }
+        if !quiet() {
+            info(&format!("ok: wrote source map to '{}'.", path.display()));
+        }
+    }
+
+    if let Some(sidecar) = sidecar {
+        let path = sidecar_path(&out);
+        let json = serde_json::to_string_pretty(&sidecar).unwrap_or_default();
+        if let Err(e) = fs::write(&path, json + "\n") {
+            if !quiet() {
+                eprintln!("{} could not write '{}': {}", style::current().paint("error:", Role::Error), path.display(), e);
+            }
+            exit(exit_codes::IO);
+        }
+        if !quiet() {
+            let n = sidecar.diagnostics.len();
+            info(&format!("ok: wrote {} diagnostic{} to '{}'.", n, if n == 1 { "" } else { "s" }, path.display()));
+        }
+    }
+
+    // Written last: a manifest next to a half-written artifact would vouch for it
+    if extras.deps_manifest {
+        let options = BuildOptions { sema_skipped: skip_sema, source_map: extras.source_map, plugins: lexer_settings.enabled() };
+        if let Err(e) = DepsManifest::collect(&flattened, &source, options).save(&out) {
+            if !quiet() {
+                eprintln!("{} could not write '{}': {}", style::current().paint("error:", Role::Error), manifest_path(&out).display(), e);
+            }
+            exit(exit_codes::IO);
+        }
     }
 
     // Trigger debounced metrics persistence (CLI path) so metrics file may exist outside GUI.
@@ -204,6 +378,74 @@ pub fn compile_pipeline(
     Ok(())
 }
 
+/// The sidecar for the compile cached under `key`, from the artifact cache when an
+/// earlier compile of the same source stored one. Only `file` is not part of the key,
+/// so it is refreshed on the way out; imported files are named relative to it, so their
+/// paths are.
+fn cached_sidecar(key: &str, file: &str, source: &str, flat: &imports::Flattened, skip_sema: bool) -> DiagSidecar {
+    let mut diag_key = format!("{key}.diag{}", if skip_sema { ".nosema" } else { "" });
+    for f in &flat.imported {
+        diag_key.push('\0');
+        diag_key.push_str(&f.path.display().to_string());
+    }
+    let cached = get_artifact(&diag_key).and_then(|e| serde_json::from_slice::<DiagSidecar>(&e.data).ok());
+    match cached {
+        Some(sidecar) => DiagSidecar { file: file.to_string(), ..sidecar },
+        None => {
+            let sidecar = DiagSidecar::collect(file, source, &flat.numbered(), skip_sema).located(flat);
+            // a pass cut short by --analysis-timeout-ms is not the source's full record
+            let truncated = sidecar.diagnostics.iter().any(|d| d.severity == "info");
+            if let (false, Ok(bytes)) = (truncated, serde_json::to_vec(&sidecar)) {
+                put_artifact(diag_key, bytes);
+            }
+            sidecar
+        }
+    }
+}
+
+/// `--out -`: emitted code goes to stdout.
+pub fn is_stdout(out: &Path) -> bool {
+    out.as_os_str() == "-"
+}
+
+/// The path an emit from `input` should write. With `no_overwrite` an existing target is a usage
+/// error; an `out` naming the input itself becomes `<stem>.out.<ext>` (with a warning) so the
+/// source is never clobbered. `-` (stdout) is returned unchanged.
+pub fn output_target(input: &Path, out: PathBuf, no_overwrite: bool) -> anyhow::Result<PathBuf> {
+    if is_stdout(&out) {
+        return Ok(out);
+    }
+    let same_file = match (input.canonicalize(), out.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    };
+    let target = if same_file {
+        let stem = out.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        let renamed = match out.extension() {
+            Some(ext) => out.with_file_name(format!("{}.out.{}", stem, ext.to_string_lossy())),
+            None => out.with_file_name(format!("{}.out", stem)),
+        };
+        if !quiet() {
+            eprintln!(
+                "{} output '{}' is the input file; writing '{}' instead",
+                style::current().paint("warn:", Role::Warn),
+                out.display(),
+                renamed.display()
+            );
+        }
+        renamed
+    } else {
+        out
+    };
+    if no_overwrite && target.exists() {
+        return Err(exit_codes::fail(
+            exit_codes::USAGE,
+            format!("'{}' already exists (remove it or drop --no-overwrite)", target.display()),
+        ));
+    }
+    Ok(target)
+}
+
 /// Soft variant for in-process editor use: never calls process::exit, returns Err instead.
 #[allow(clippy::too_many_arguments)]
 pub fn compile_pipeline_soft(
@@ -219,7 +461,9 @@ pub fn compile_pipeline_soft(
     let input_path = input.as_deref().unwrap_or_else(|| Path::new("examples/hello.ai"));
     let source = fs::read_to_string(input_path).unwrap_or_else(|_| "let x = 42;\nlog(x);".to_string());
     if print_tokens || print_ast { println!("=== Source Code ===\n{}\n", source); }
-    let mut lexer = Lexer::from_str(&source);
+    let lexer_settings = LexerSettings::discover(input_path)?;
+    let lexer_options = lexer_settings.lexer_options();
+    let mut lexer = Lexer::with_options(&source, lexer_options.clone());
     let tokens = match lexer.tokenize() {
         Ok(t) => t,
         Err(e) => {
@@ -229,9 +473,10 @@ pub fn compile_pipeline_soft(
                     | LexerError::UnterminatedString(line, col)
                     | LexerError::InvalidNumber(_, line, col)
                     | LexerError::InvalidQubitLiteral(_, line, col)
+                    | LexerError::PluginError(_, line, col)
                     | LexerError::UnterminatedComment(line, col) => {
-                        emit_json_error(&input_path.display().to_string(), &format!("{}", e), &Span::single(line, col));
-                        print_error(&input_path.display().to_string(), &source, &format!("{}", e), Span::single(line, col));
+                        emit_json_error_coded(&input_path.display().to_string(), &format!("{}", e), Span::single(line, col), Some(error_index::LEX_ERROR));
+                        print_error_coded(&input_path.display().to_string(), &source, &format!("{}", e), Span::single(line, col), Some(error_index::LEX_ERROR));
                     }
                     _ => eprintln!("lex error: {e}"),
                 }
@@ -243,22 +488,31 @@ pub fn compile_pipeline_soft(
     let mut parser = AeParser::new(tokens.clone());
     let ast = match parser.parse() {
         Ok(a) => a,
-        Err(ParserError { message, line, column }) => {
+        Err(e) => {
             if pretty {
-                emit_json_error(&input_path.display().to_string(), &format!("Parsing error: {}", message), &Span::single(line, column));
-                print_error(&input_path.display().to_string(), &source, &format!("Parsing error: {}", message), Span::single(line, column));
-            } else { eprintln!("parse error: {}", message); }
+                let (title, span, notes) = parse_error_parts(&e);
+                emit_json_error_with_notes(&input_path.display().to_string(), &title, &span, Some(error_index::PARSE_ERROR), &notes);
+                eprint!("{}", render_error_with_notes(&style::current(), &input_path.display().to_string(), &source, &title, &span, Some(error_index::PARSE_ERROR), &notes));
+            } else { eprintln!("parse error: {}", e.summary()); }
             return Err(anyhow::anyhow!("parse failed"));
         }
     };
+    let flattened = imports::resolve(ast, input_path, &source, &lexer_options).map_err(|e| {
+        if pretty { e.report(true); } else { eprintln!("import error: {e}"); }
+        anyhow::anyhow!("import failed")
+    })?;
+    let ast = &flattened.program;
     if print_ast { println!("=== AST ===\n{:#?}\n", ast); }
     if skip_sema { println!("note: semantic analysis skipped"); }
     let mut hasher = Sha1::new(); hasher.update(source.as_bytes()); hasher.update(match emit { EmitKind::Ai=>b"AI", EmitKind::Js=>b"JS" });
+    // plugins change what the source means, so they are part of the key
+    for name in lexer_settings.enabled() { hasher.update(b"\0"); hasher.update(name.as_bytes()); }
+    for file in &flattened.imported { hasher.update(b"\0"); hasher.update(file.source.as_bytes()); }
     let key = format!("{:x}", hasher.finalize());
     let output_string = if let Some(entry) = get_artifact(&key) { String::from_utf8(entry.data).unwrap_or_default() } else {
         let generated = match emit {
-            EmitKind::Ai => { let mut gen = CodeGenerator::new_ai(); gen.generate(&ast).map_err(|e| anyhow::anyhow!("AI emit failed: {e}"))? }
-            EmitKind::Js => { let mut gen = CodeGenerator::new(); gen.generate(&ast).map_err(|e| anyhow::anyhow!("JS emit failed: {e}"))? }
+            EmitKind::Ai => { let mut gen = CodeGenerator::new_ai(); gen.generate(ast).map_err(|e| anyhow::anyhow!("AI emit failed: {e}"))? }
+            EmitKind::Js => { let mut gen = CodeGenerator::new(); gen.generate(ast).map_err(|e| anyhow::anyhow!("JS emit failed: {e}"))? }
         }; put_artifact(key.clone(), generated.as_bytes().to_vec()); generated };
     if let Some(parent) = out.parent() { if !parent.as_os_str().is_empty() { fs::create_dir_all(parent).map_err(|e| anyhow::anyhow!("dir create failed: {e}"))?; } }
     fs::write(&out, &output_string).map_err(|e| anyhow::anyhow!("write failed: {e}"))?;
@@ -0,0 +1,304 @@
+//! Default flags from config: the `[defaults]` tables of the user config (`--config`, else
+//! `~/.aeonmi/qpoly.toml`) and of the nearest `Aeonmi.toml` above the current directory.
+//!
+//! [`apply`] runs before the real parse: it parses the command line once to find the
+//! subcommand and which flags were typed, then inserts the configured flags that were not,
+//! right after the subcommand name. So the command line always wins, then the project
+//! manifest, then the user config; within one file a subcommand's table beats the scalars
+//! at the top of `[defaults]`. A default that conflicts with a typed flag is dropped.
+//! Unknown subcommands, unknown flags and values of the wrong kind are warnings.
+//!
+//! `aeonmi config effective run` shows the merged result for a subcommand;
+//! `aeonmi config set stats.enabled true` edits the user config.
+
+use anyhow::Result;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, CommandFactory};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use super::exit_codes::{fail, IO, USAGE};
+use crate::cli::AeonmiCli;
+use crate::config::{self, DefaultSettings, EditorSettings, StatsSettings};
+use crate::core::style::{self, Role};
+
+/// One config file's `[defaults]` table.
+#[derive(Debug, Clone)]
+pub struct Layer {
+    /// Where it came from, as shown in warnings and the effective view.
+    pub origin: String,
+    pub table: toml::Table,
+}
+
+/// User config first, then the project manifest (later layers win).
+pub fn load_layers(user: Option<&Path>, warnings: &mut Vec<String>) -> Vec<Layer> {
+    // `nearest_manifest` searches from its argument's directory: a bare name means the cwd.
+    let project = config::nearest_manifest(Path::new(config::LexerSettings::MANIFEST));
+    let mut layers = Vec::new();
+    for path in user.into_iter().map(Path::to_path_buf).chain(project) {
+        let Ok(txt) = std::fs::read_to_string(&path) else { continue };
+        match DefaultSettings::from_toml_str(&txt) {
+            Ok(d) if !d.table.is_empty() => layers.push(Layer { origin: path.display().to_string(), table: d.table }),
+            Ok(_) => {}
+            Err(e) => warnings.push(format!("{}: [defaults] ignored: {}", path.display(), e.message())),
+        }
+    }
+    layers
+}
+
+/// The flag `key` names on `cmd`, by id (`pretty_errors`) or long name (`pretty-errors`).
+fn find_arg<'a>(cmd: &'a clap::Command, key: &str) -> Option<&'a Arg> {
+    let id = key.replace('-', "_");
+    cmd.get_arguments().find(|a| a.get_id().as_str() == id || a.get_long() == Some(key))
+}
+
+fn scalar(v: &toml::Value) -> Option<String> {
+    match v {
+        toml::Value::String(s) => Some(s.clone()),
+        toml::Value::Integer(i) => Some(i.to_string()),
+        toml::Value::Float(f) => Some(f.to_string()),
+        toml::Value::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Command-line words that set `arg` to `value`, or why `value` does not fit the flag.
+fn flag_args(arg: &Arg, value: &toml::Value) -> Result<Vec<OsString>, String> {
+    let Some(long) = arg.get_long() else { return Err("is not a flag".into()) };
+    let flag = OsString::from(format!("--{long}"));
+    match (arg.get_action(), value) {
+        (ArgAction::SetTrue, toml::Value::Boolean(b)) => Ok(if *b { vec![flag] } else { vec![] }),
+        (ArgAction::SetFalse, toml::Value::Boolean(b)) => Ok(if *b { vec![] } else { vec![flag] }),
+        (ArgAction::SetTrue | ArgAction::SetFalse, _) => Err("expects true or false".into()),
+        (ArgAction::Count, toml::Value::Integer(n)) if *n >= 0 => Ok(vec![flag; *n as usize]),
+        (ArgAction::Count, _) => Err("expects a count".into()),
+        (ArgAction::Append, toml::Value::Array(items)) => items
+            .iter()
+            .map(|v| scalar(v).map(|s| OsString::from(format!("--{long}={s}"))).ok_or_else(|| "expects a list of values".to_string()))
+            .collect(),
+        (ArgAction::Set | ArgAction::Append, v) => scalar(v).map(|s| vec![OsString::from(format!("--{long}={s}"))]).ok_or_else(|| "expects a single value".into()),
+        _ => Err("cannot be set from config".into()),
+    }
+}
+
+/// Warn about every key in `table` that does not name a subcommand or a flag that takes it.
+fn validate(cmd: &clap::Command, table: &toml::Table, prefix: &str, origin: &str, warnings: &mut Vec<String>) {
+    for (key, value) in table {
+        let name = format!("{prefix}{key}");
+        match value {
+            toml::Value::Table(sub) => match cmd.find_subcommand(key) {
+                Some(sc) => validate(sc, sub, &format!("{name}."), origin, warnings),
+                None => warnings.push(format!("{origin}: [defaults] {name}: unknown subcommand '{key}'")),
+            },
+            _ => match find_arg(cmd, key) {
+                None => warnings.push(format!("{origin}: [defaults] {name}: unknown flag '{key}' for `{}`", cmd.get_name())),
+                Some(arg) => {
+                    if let Err(e) = flag_args(arg, value) {
+                        warnings.push(format!("{origin}: [defaults] {name}: --{} {e}", arg.get_long().unwrap_or(key)));
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// Configured values for the subcommand at `path`: flag id → (value, origin).
+fn merge<'a>(layers: &'a [Layer], leaf: &clap::Command, path: &[String]) -> BTreeMap<String, (&'a toml::Value, &'a str)> {
+    let mut out = BTreeMap::new();
+    for layer in layers {
+        let mut table = Some(&layer.table);
+        for depth in 0..=path.len() {
+            let Some(t) = table else { break };
+            for (key, value) in t {
+                if value.is_table() {
+                    continue;
+                }
+                // Tables above the leaf contribute global flags only; bad values were warned about.
+                let arg = find_arg(leaf, key).filter(|a| (depth == path.len() || a.is_global_set()) && flag_args(a, value).is_ok());
+                if let Some(arg) = arg {
+                    out.insert(arg.get_id().to_string(), (value, layer.origin.as_str()));
+                }
+            }
+            table = path.get(depth).and_then(|name| t.get(name)).and_then(toml::Value::as_table);
+        }
+    }
+    out
+}
+
+/// Subcommand names from the root to the invoked leaf, with the leaf's matches.
+fn leaf(mut m: &ArgMatches) -> (Vec<String>, &ArgMatches) {
+    let mut path = Vec::new();
+    while let Some((name, sub)) = m.subcommand() {
+        path.push(name.to_string());
+        m = sub;
+    }
+    (path, m)
+}
+
+/// Subcommand names from the root to the invoked leaf, e.g. `["vm", "eval"]`.
+pub fn command_path(m: &ArgMatches) -> Vec<String> {
+    leaf(m).0
+}
+
+fn find_command<'a>(root: &'a clap::Command, path: &[String]) -> Option<&'a clap::Command> {
+    path.iter().try_fold(root, |cmd, name| cmd.find_subcommand(name))
+}
+
+/// `argv` with the configured default flags inserted, plus warnings for the config.
+/// Command lines clap rejects (including `--help`) pass through untouched.
+pub fn apply(argv: Vec<OsString>) -> (Vec<OsString>, Vec<String>) {
+    let mut root = AeonmiCli::command();
+    root.build();
+    let Ok(matches) = root.clone().try_get_matches_from(&argv) else { return (argv, Vec::new()) };
+    let (path, m) = leaf(&matches);
+    let mut warnings = Vec::new();
+    // Global, so the leaf sees `--config` wherever it was typed.
+    let user = config::resolve_config_path(&m.get_one::<PathBuf>("config").cloned());
+    let layers = load_layers(user.as_deref(), &mut warnings);
+    for layer in &layers {
+        validate(&root, &layer.table, "", &layer.origin, &mut warnings);
+    }
+    let Some(cmd) = find_command(&root, &path) else { return (argv, warnings) };
+    let typed = |a: &Arg| m.value_source(a.get_id().as_str()) == Some(ValueSource::CommandLine);
+
+    let mut extra = Vec::new();
+    for (id, (value, _)) in merge(&layers, cmd, &path) {
+        let Some(arg) = cmd.get_arguments().find(|a| a.get_id().as_str() == id) else { continue };
+        if typed(arg) || cmd.get_arg_conflicts_with(arg).into_iter().any(typed) {
+            continue;
+        }
+        extra.extend(flag_args(arg, value).unwrap_or_default());
+    }
+    if extra.is_empty() {
+        return (argv, warnings);
+    }
+    // Right after the subcommand name, so passthrough commands (`cargo`, `exec`) do not
+    // swallow them as trailing arguments.
+    let mut at = 1;
+    for name in &path {
+        match argv.iter().skip(at).position(|a| a.to_str() == Some(name)) {
+            Some(i) => at += i + 1,
+            None => break,
+        }
+    }
+    let mut out = argv;
+    let tail = out.split_off(at.min(out.len()));
+    out.extend(extra);
+    out.extend(tail);
+    (out, warnings)
+}
+
+/// One flag of `config effective`.
+#[derive(Debug, Serialize)]
+pub struct EffectiveFlag {
+    pub flag: String,
+    pub value: String,
+    /// Config file the value came from, or `default`.
+    pub source: String,
+}
+
+/// Every flag of the subcommand at `path` with the value a bare invocation would get.
+/// Problems in the config were already reported by [`apply`].
+pub fn effective(path: &[String], user: Option<&Path>) -> Result<Vec<EffectiveFlag>> {
+    let mut root = AeonmiCli::command();
+    root.build();
+    let cmd = find_command(&root, path).ok_or_else(|| fail(USAGE, format!("unknown subcommand '{}'", path.join(" "))))?;
+    let layers = load_layers(user, &mut Vec::new());
+    let merged = merge(&layers, cmd, path);
+    let mut flags = Vec::new();
+    for arg in cmd.get_arguments() {
+        let Some(long) = arg.get_long() else { continue };
+        if arg.is_hide_set() || matches!(arg.get_action(), ArgAction::Help | ArgAction::HelpShort | ArgAction::HelpLong | ArgAction::Version) {
+            continue;
+        }
+        let (value, source) = match merged.get(arg.get_id().as_str()) {
+            Some((v, origin)) => (scalar(v).unwrap_or_else(|| v.to_string()), origin.to_string()),
+            None => {
+                let d: Vec<String> = arg.get_default_values().iter().map(|v| v.to_string_lossy().into_owned()).collect();
+                let value = match arg.get_action() {
+                    ArgAction::Count if d.is_empty() => "0".into(),
+                    _ if d.is_empty() => "(unset)".into(),
+                    _ => d.join(","),
+                };
+                (value, "default".into())
+            }
+        };
+        flags.push(EffectiveFlag { flag: format!("--{long}"), value, source });
+    }
+    Ok(flags)
+}
+
+pub fn print_warnings(warnings: &[String]) {
+    if crate::core::diagnostics::quiet() {
+        return;
+    }
+    let st = style::current();
+    for w in warnings {
+        eprintln!("{} {w}", st.paint("warn:", Role::Warn));
+    }
+}
+
+/// `aeonmi config effective <subcommand...>`.
+pub fn main_effective(path: Vec<String>, config: Option<PathBuf>, json: bool) -> Result<()> {
+    let flags = effective(&path, config.as_deref())?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&flags)?);
+        return Ok(());
+    }
+    let st = style::current();
+    let title = if path.is_empty() { "aeonmi".to_string() } else { path.join(" ") };
+    println!("{}", st.paint(&format!("{title} (effective defaults)"), Role::Section));
+    let fw = flags.iter().map(|f| f.flag.len()).max().unwrap_or(0);
+    let vw = flags.iter().map(|f| f.value.len()).max().unwrap_or(0);
+    for f in &flags {
+        let role = if f.source == "default" { Role::Dim } else { Role::Strong };
+        println!("  {:<fw$}  {:<vw$}  {}", f.flag, f.value, st.paint(&f.source, role));
+    }
+    Ok(())
+}
+
+/// `aeonmi config set KEY VALUE`: one dotted key into the user config, keeping the rest
+/// of the file (comments included). VALUE is taken as a TOML value when it is one
+/// (`true`, `3`, `0.5`), else as a string.
+pub fn main_set(key: &str, value: &str, config: Option<PathBuf>) -> Result<()> {
+    let Some(path) = config.or_else(config::default_config_path) else {
+        return Err(fail(USAGE, "config set: no home directory; pass --config"));
+    };
+    let names: Vec<&str> = key.split('.').collect();
+    let Some((last, tables)) = names.split_last().filter(|_| names.iter().all(|n| !n.trim().is_empty())) else {
+        return Err(fail(USAGE, format!("config set: invalid key `{key}`")));
+    };
+    let txt = match std::fs::read_to_string(&path) {
+        Ok(t) => t,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(fail(IO, format!("read {}: {e}", path.display()))),
+    };
+    let mut doc: toml_edit::DocumentMut = txt.parse().map_err(|e| fail(USAGE, format!("parse {}: {e}", path.display())))?;
+    let mut table = doc.as_table_mut();
+    for name in tables {
+        table = table
+            .entry(name)
+            .or_insert(toml_edit::table())
+            .as_table_mut()
+            .ok_or_else(|| fail(USAGE, format!("config set: `{name}` in {} is not a table", path.display())))?;
+    }
+    let mut parsed = value.parse::<toml_edit::Value>().unwrap_or_else(|_| value.into());
+    parsed.decor_mut().clear();
+    let shown = parsed.to_string();
+    // Keep a trailing comment on the line being replaced.
+    if let Some(old) = table.get(last).and_then(toml_edit::Item::as_value) {
+        *parsed.decor_mut() = old.decor().clone();
+    }
+    table[*last] = toml_edit::value(parsed);
+    // Refuse values the settings that read this file would reject.
+    let out = doc.to_string();
+    if let Err(e) = EditorSettings::from_toml_str(&out).and(StatsSettings::from_toml_str(&out)).and(DefaultSettings::from_toml_str(&out)) {
+        return Err(fail(USAGE, format!("config set: {key} = {value}: {}", e.message())));
+    }
+    config::ensure_parent_dir(&path)?;
+    std::fs::write(&path, out).map_err(|e| fail(IO, format!("write {}: {e}", path.display())))?;
+    println!("{key} = {shown} ({})", path.display());
+    Ok(())
+}
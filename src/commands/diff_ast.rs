@@ -0,0 +1,445 @@
+//! `aeonmi diff-ast`: compare two .ai files by structure instead of text.
+//!
+//! Both files are parsed and every position is dropped before comparing, so a reformat
+//! reports "no semantic changes". Top-level functions are paired by name, then the
+//! leftovers by body: an identical body (same fingerprint), or one whose statements
+//! mostly match, makes a rename. Statements of paired functions, and the top-level
+//! statements, are aligned by longest common subsequence. A replaced loop, `if` or block
+//! whose header is unchanged is descended into, so an edit deep in a loop is reported
+//! at the statement that changed rather than as a whole new loop.
+
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+use crate::commands::exit_codes::{self, reported};
+use crate::commands::typecheck::parse_file;
+use crate::core::ast::{ASTNode, FunctionParam, MatchArm, Pattern};
+use crate::core::style::{self, Role, Style};
+
+/// Share of matching statements above which an unpaired function counts as renamed.
+const RENAME_SIMILARITY: f64 = 0.6;
+/// Statement text in reports is cut to this many characters.
+const MAX_TEXT: usize = 60;
+
+/// Scope name of statements outside any function.
+pub const TOP_LEVEL: &str = "<top level>";
+
+/// One semantic difference. Lines refer to the new file, except for removals.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+    FunctionAdded { name: String, line: usize },
+    FunctionRemoved { name: String, line: usize },
+    /// `similarity` is 1.0 when the bodies are identical; otherwise body changes follow.
+    FunctionRenamed { from: String, to: String, line: usize, similarity: f64 },
+    ParamsChanged { function: String, line: usize, old: Vec<String>, new: Vec<String> },
+    /// `scope` is the function, then any enclosing statements, joined by ` > `.
+    StatementAdded { scope: String, line: usize, text: String },
+    StatementRemoved { scope: String, line: usize, text: String },
+    StatementChanged { scope: String, line: usize, old: String, new: String },
+}
+
+/// Semantic changes from `old` to `new` (both `Program`s).
+pub fn diff(old: &ASTNode, new: &ASTNode) -> Vec<Change> {
+    let (old_fns, old_stmts) = split(old);
+    let (new_fns, new_stmts) = split(new);
+    let mut out = Vec::new();
+    let mut new_used = vec![false; new_fns.len()];
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    let mut old_unpaired = Vec::new();
+    for (i, f) in old_fns.iter().enumerate() {
+        match new_fns.iter().position(|g| fn_name(g) == fn_name(f)) {
+            Some(j) if !new_used[j] => {
+                new_used[j] = true;
+                pairs.push((i, j));
+            }
+            _ => old_unpaired.push(i),
+        }
+    }
+    // Renames: an identical body first, then the most similar one above the threshold.
+    let mut removed = Vec::new();
+    for i in old_unpaired {
+        let hash = fingerprint(fn_body(old_fns[i]));
+        let free = || (0..new_fns.len()).filter(|&j| !new_used[j]);
+        let best = free().find(|&j| fingerprint(fn_body(new_fns[j])) == hash).map(|j| (j, 1.0)).or_else(|| {
+            free()
+                .map(|j| (j, similarity(fn_body(old_fns[i]), fn_body(new_fns[j]))))
+                .filter(|&(_, s)| s >= RENAME_SIMILARITY)
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+        });
+        match best {
+            Some((j, similarity)) => {
+                new_used[j] = true;
+                out.push(Change::FunctionRenamed { from: fn_name(old_fns[i]).to_string(), to: fn_name(new_fns[j]).to_string(), line: first_line(new_fns[j]), similarity });
+                pairs.push((i, j));
+            }
+            None => removed.push(i),
+        }
+    }
+    for i in removed {
+        out.push(Change::FunctionRemoved { name: fn_name(old_fns[i]).to_string(), line: first_line(old_fns[i]) });
+    }
+    pairs.sort_by_key(|&(_, j)| j);
+    for (i, j) in pairs {
+        let (f, g) = (old_fns[i], new_fns[j]);
+        let (old_params, new_params) = (param_names(f), param_names(g));
+        if old_params != new_params {
+            out.push(Change::ParamsChanged { function: fn_name(g).to_string(), line: first_line(g), old: old_params, new: new_params });
+        }
+        diff_block(fn_name(g), fn_body(f), fn_body(g), &mut out);
+    }
+    for (g, _) in new_fns.iter().zip(&new_used).filter(|(_, used)| !**used) {
+        out.push(Change::FunctionAdded { name: fn_name(g).to_string(), line: first_line(g) });
+    }
+    let (old_stmts, new_stmts): (Vec<ASTNode>, Vec<ASTNode>) = (old_stmts.into_iter().cloned().collect(), new_stmts.into_iter().cloned().collect());
+    diff_block(TOP_LEVEL, &old_stmts, &new_stmts, &mut out);
+    out
+}
+
+fn split(program: &ASTNode) -> (Vec<&ASTNode>, Vec<&ASTNode>) {
+    match program {
+        ASTNode::Program(items) => items.iter().partition(|n| matches!(n, ASTNode::Function { .. })),
+        other => (vec![], vec![other]),
+    }
+}
+
+fn fn_name(f: &ASTNode) -> &str {
+    match f {
+        ASTNode::Function { name, .. } => name,
+        _ => "",
+    }
+}
+
+fn fn_body(f: &ASTNode) -> &[ASTNode] {
+    match f {
+        ASTNode::Function { body, .. } => body,
+        _ => &[],
+    }
+}
+
+fn param_names(f: &ASTNode) -> Vec<String> {
+    match f {
+        ASTNode::Function { params, .. } => params.iter().map(|p| p.name.clone()).collect(),
+        _ => vec![],
+    }
+}
+
+/// Hash of the position-free form of `stmts`.
+pub fn fingerprint(stmts: &[ASTNode]) -> u64 {
+    let mut h = DefaultHasher::new();
+    for s in stmts {
+        format!("{:?}", canon(s)).hash(&mut h);
+    }
+    h.finish()
+}
+
+/// Share of statements (0..=1) the two lists have in common, in order.
+fn similarity(a: &[ASTNode], b: &[ASTNode]) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let (a, b): (Vec<ASTNode>, Vec<ASTNode>) = (a.iter().map(canon).collect(), b.iter().map(canon).collect());
+    let common = align(&a, &b).iter().filter(|op| matches!(op, Op::Same)).count();
+    2.0 * common as f64 / (a.len() + b.len()) as f64
+}
+
+enum Op {
+    Same,
+    Removed(usize),
+    Added(usize),
+}
+
+/// Edit script from `a` to `b` by longest common subsequence.
+fn align(a: &[ASTNode], b: &[ASTNode]) -> Vec<Op> {
+    let (n, m) = (a.len(), b.len());
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+    let (mut i, mut j, mut ops) = (0, 0, Vec::new());
+    while i < n || j < m {
+        if i < n && j < m && a[i] == b[j] {
+            ops.push(Op::Same);
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            ops.push(Op::Added(j));
+            j += 1;
+        } else {
+            ops.push(Op::Removed(i));
+            i += 1;
+        }
+    }
+    ops
+}
+
+/// Diff two statement lists in `scope`. Within each run of edits, removals and additions
+/// are paired in order as changes; the surplus is reported as removed or added.
+fn diff_block(scope: &str, old: &[ASTNode], new: &[ASTNode], out: &mut Vec<Change>) {
+    let (a, b): (Vec<ASTNode>, Vec<ASTNode>) = (old.iter().map(canon).collect(), new.iter().map(canon).collect());
+    let (mut removed, mut added) = (Vec::new(), Vec::new());
+    let flush = |removed: &mut Vec<usize>, added: &mut Vec<usize>, out: &mut Vec<Change>| {
+        for k in 0..removed.len().max(added.len()) {
+            match (removed.get(k), added.get(k)) {
+                (Some(&i), Some(&j)) => diff_stmt(scope, &old[i], &new[j], out),
+                (Some(&i), None) => out.push(Change::StatementRemoved { scope: scope.to_string(), line: first_line(&old[i]), text: show(&old[i]) }),
+                (None, Some(&j)) => out.push(Change::StatementAdded { scope: scope.to_string(), line: first_line(&new[j]), text: show(&new[j]) }),
+                (None, None) => {}
+            }
+        }
+        removed.clear();
+        added.clear();
+    };
+    for op in align(&a, &b) {
+        match op {
+            Op::Same => flush(&mut removed, &mut added, out),
+            Op::Removed(i) => removed.push(i),
+            Op::Added(j) => added.push(j),
+        }
+    }
+    flush(&mut removed, &mut added, out);
+}
+
+/// Report `old` replaced by `new`, descending into compound statements whose header is unchanged.
+fn diff_stmt(scope: &str, old: &ASTNode, new: &ASTNode, out: &mut Vec<Change>) {
+    use ASTNode::*;
+    let inner = |header: &ASTNode| format!("{scope} > {}", show(header));
+    match (old, new) {
+        (While { condition: c1, body: b1 }, While { condition: c2, body: b2 }) if canon(c1) == canon(c2) => {
+            diff_block(&inner(new), stmts(b1), stmts(b2), out);
+        }
+        (For { init: i1, condition: c1, increment: n1, body: b1 }, For { init: i2, condition: c2, increment: n2, body: b2 })
+            if opt_canon(i1) == opt_canon(i2) && opt_canon(c1) == opt_canon(c2) && opt_canon(n1) == opt_canon(n2) =>
+        {
+            diff_block(&inner(new), stmts(b1), stmts(b2), out);
+        }
+        (If { condition: c1, then_branch: t1, else_branch: e1 }, If { condition: c2, then_branch: t2, else_branch: e2 }) if canon(c1) == canon(c2) => {
+            diff_block(&inner(new), stmts(t1), stmts(t2), out);
+            let else_stmts = |e: &Option<Box<ASTNode>>| e.as_deref().map(stmts).unwrap_or(&[]).to_vec();
+            diff_block(&format!("{} > else", inner(new)), &else_stmts(e1), &else_stmts(e2), out);
+        }
+        (Block(b1), Block(b2)) => diff_block(&format!("{scope} > {{ }}"), b1, b2, out),
+        _ => out.push(Change::StatementChanged { scope: scope.to_string(), line: first_line(new), old: show(old), new: show(new) }),
+    }
+}
+
+fn stmts(node: &ASTNode) -> &[ASTNode] {
+    match node {
+        ASTNode::Block(items) => items,
+        other => std::slice::from_ref(other),
+    }
+}
+
+fn opt_canon(node: &Option<Box<ASTNode>>) -> Option<ASTNode> {
+    node.as_deref().map(canon)
+}
+
+fn canon_pattern(p: &Pattern) -> Pattern {
+    match p {
+        Pattern::Name { name, .. } => Pattern::Name { name: name.clone(), line: 0, column: 0 },
+        Pattern::Array { items, .. } => Pattern::Array { items: items.iter().map(canon_pattern).collect(), line: 0, column: 0 },
+    }
+}
+
+/// `node` with every position zeroed and spanned identifiers made plain.
+fn canon(node: &ASTNode) -> ASTNode {
+    use ASTNode::*;
+    let b = |n: &ASTNode| Box::new(canon(n));
+    let all = |ns: &[ASTNode]| ns.iter().map(canon).collect::<Vec<_>>();
+    match node {
+        Program(items) => Program(all(items)),
+        Function { name, params, body, .. } => Function {
+            name: name.clone(),
+            line: 0,
+            column: 0,
+            params: params.iter().map(|p| FunctionParam { name: p.name.clone(), line: 0, column: 0 }).collect(),
+            body: all(body),
+        },
+        VariableDecl { name, value, .. } => VariableDecl { name: name.clone(), value: b(value), line: 0, column: 0 },
+        DestructuringDecl { targets, values, .. } => DestructuringDecl { targets: targets.iter().map(canon_pattern).collect(), values: all(values), line: 0, column: 0 },
+        EnumDecl { name, variants, .. } => EnumDecl { name: name.clone(), variants: variants.clone(), line: 0, column: 0 },
+        Import { path, .. } => Import { path: path.clone(), line: 0, column: 0 },
+        Block(items) => Block(all(items)),
+        Return(e) => Return(b(e)),
+        Log { expr, .. } => Log { expr: b(expr), line: 0, column: 0 },
+        If { condition, then_branch, else_branch } => If { condition: b(condition), then_branch: b(then_branch), else_branch: else_branch.as_deref().map(b) },
+        While { condition, body } => While { condition: b(condition), body: b(body) },
+        For { init, condition, increment, body } => For {
+            init: init.as_deref().map(b),
+            condition: condition.as_deref().map(b),
+            increment: increment.as_deref().map(b),
+            body: b(body),
+        },
+        ForIn { key, var, iterable, body, .. } => ForIn { key: key.clone(), var: var.clone(), iterable: b(iterable), body: b(body), line: 0, column: 0 },
+        Break { .. } => Break { line: 0, column: 0 },
+        Continue { .. } => Continue { line: 0, column: 0 },
+        Match { subject, arms, .. } => Match {
+            subject: b(subject),
+            arms: arms.iter().map(|a| MatchArm { pattern: a.pattern.as_ref().map(canon), body: canon(&a.body), line: 0, column: 0 }).collect(),
+            line: 0,
+            column: 0,
+        },
+        Assignment { name, value, .. } => Assignment { name: name.clone(), value: b(value), line: 0, column: 0 },
+        Call { callee, args } => Call { callee: b(callee), args: all(args) },
+        BinaryExpr { op, left, right } => BinaryExpr { op: op.clone(), left: b(left), right: b(right) },
+        UnaryExpr { op, expr } => UnaryExpr { op: op.clone(), expr: b(expr) },
+        Index { target, index, .. } => Index { target: b(target), index: b(index), line: 0, column: 0 },
+        Member { target, name, .. } => Member { target: b(target), name: name.clone(), line: 0, column: 0 },
+        ArrayLiteral { items, .. } => ArrayLiteral { items: all(items), line: 0, column: 0 },
+        ObjectLiteral { fields, .. } => ObjectLiteral { fields: fields.iter().map(|(k, v)| (k.clone(), canon(v))).collect(), line: 0, column: 0 },
+        IdentifierSpanned { name, .. } => Identifier(name.clone()),
+        QubitDecl { name, .. } => QubitDecl { name: name.clone(), line: 0, column: 0 },
+        QregDecl { name, size, .. } => QregDecl { name: name.clone(), size: *size, line: 0, column: 0 },
+        QuantumOp { op, qubits, .. } => QuantumOp { op: op.clone(), qubits: all(qubits), line: 0, column: 0 },
+        HieroglyphicOp { symbol, args } => HieroglyphicOp { symbol: symbol.clone(), args: all(args) },
+        Identifier(_) | NumberLiteral(_) | StringLiteral(_) | BooleanLiteral(_) | Error(_) => node.clone(),
+    }
+}
+
+fn children(node: &ASTNode) -> Vec<&ASTNode> {
+    use ASTNode::*;
+    match node {
+        Program(items) | Block(items) => items.iter().collect(),
+        Function { body, .. } => body.iter().collect(),
+        VariableDecl { value, .. } | Assignment { value, .. } => vec![value],
+        DestructuringDecl { values, .. } => values.iter().collect(),
+        Return(e) | Log { expr: e, .. } | UnaryExpr { expr: e, .. } => vec![e],
+        If { condition, then_branch, else_branch } => [Some(&**condition), Some(&**then_branch), else_branch.as_deref()].into_iter().flatten().collect(),
+        While { condition, body } => vec![condition, body],
+        For { init, condition, increment, body } => [init.as_deref(), condition.as_deref(), increment.as_deref(), Some(&**body)].into_iter().flatten().collect(),
+        ForIn { iterable, body, .. } => vec![iterable, body],
+        Match { subject, arms, .. } => std::iter::once(&**subject).chain(arms.iter().flat_map(|a| a.pattern.iter().chain([&a.body]))).collect(),
+        Call { callee, args } => std::iter::once(&**callee).chain(args).collect(),
+        BinaryExpr { left, right, .. } => vec![left, right],
+        Index { target, index, .. } => vec![target, index],
+        Member { target, .. } => vec![target],
+        ArrayLiteral { items, .. } => items.iter().collect(),
+        ObjectLiteral { fields, .. } => fields.iter().map(|(_, v)| v).collect(),
+        QuantumOp { qubits: args, .. } | HieroglyphicOp { args, .. } => args.iter().collect(),
+        _ => vec![],
+    }
+}
+
+/// The node's own line, else the first one found among its children (0 if none).
+fn first_line(node: &ASTNode) -> usize {
+    use ASTNode::*;
+    match node {
+        Function { line, .. } | VariableDecl { line, .. } | DestructuringDecl { line, .. } | EnumDecl { line, .. } | Log { line, .. } | ForIn { line, .. } | Break { line, .. } | Continue { line, .. } | Match { line, .. } | Assignment { line, .. } | Index { line, .. } | Member { line, .. } | ArrayLiteral { line, .. } | ObjectLiteral { line, .. } | IdentifierSpanned { line, .. } | QubitDecl { line, .. } | QregDecl { line, .. } | QuantumOp { line, .. } if *line > 0 => *line,
+        _ => children(node).into_iter().map(first_line).find(|&l| l > 0).unwrap_or(0),
+    }
+}
+
+/// One-line source-like rendering of `node`, shortened to [`MAX_TEXT`] characters.
+pub fn show(node: &ASTNode) -> String {
+    let text = render_node(node);
+    if text.chars().count() <= MAX_TEXT {
+        return text;
+    }
+    text.chars().take(MAX_TEXT - 1).chain(['…']).collect()
+}
+
+fn render_node(node: &ASTNode) -> String {
+    use ASTNode::*;
+    let list = |ns: &[ASTNode]| ns.iter().map(render_node).collect::<Vec<_>>().join(", ");
+    // Nested binary operands get parentheses so precedence survives the rendering.
+    let operand = |n: &ASTNode| match n {
+        BinaryExpr { .. } => format!("({})", render_node(n)),
+        _ => render_node(n),
+    };
+    match node {
+        Program(_) => "program".into(),
+        Function { name, params, .. } => format!("function {name}({})", params.iter().map(|p| p.name.as_str()).collect::<Vec<_>>().join(", ")),
+        VariableDecl { name, value, .. } => format!("let {name} = {}", render_node(value)),
+        DestructuringDecl { targets, values, .. } => {
+            format!("let {} = {}", targets.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", "), list(values))
+        }
+        EnumDecl { name, variants, .. } => format!("enum {name} {{ {} }}", variants.join(", ")),
+        Import { path, .. } => format!("import {path:?}"),
+        Block(_) => "{ … }".into(),
+        Return(e) => format!("return {}", render_node(e)),
+        Log { expr, .. } => format!("log({})", render_node(expr)),
+        If { condition, .. } => format!("if ({})", render_node(condition)),
+        While { condition, .. } => format!("while ({})", render_node(condition)),
+        For { init, condition, increment, .. } => {
+            let part = |n: &Option<Box<ASTNode>>| n.as_deref().map(render_node).unwrap_or_default();
+            format!("for ({}; {}; {})", part(init), part(condition), part(increment))
+        }
+        ForIn { key, var, iterable, .. } => match key {
+            Some(k) => format!("for {k}, {var} in {}", render_node(iterable)),
+            None => format!("for {var} in {}", render_node(iterable)),
+        },
+        Break { .. } => "break".into(),
+        Continue { .. } => "continue".into(),
+        Match { subject, .. } => format!("match ({})", render_node(subject)),
+        Assignment { name, value, .. } => format!("{name} = {}", render_node(value)),
+        Call { callee, args } => format!("{}({})", render_node(callee), list(args)),
+        BinaryExpr { op, left, right } => format!("{} {op} {}", operand(left), operand(right)),
+        UnaryExpr { op, expr } => format!("{op}{}", operand(expr)),
+        Index { target, index, .. } => format!("{}[{}]", render_node(target), render_node(index)),
+        Member { target, name, .. } => format!("{}.{name}", render_node(target)),
+        ArrayLiteral { items, .. } => format!("[{}]", list(items)),
+        ObjectLiteral { fields, .. } if fields.is_empty() => "{}".into(),
+        ObjectLiteral { fields, .. } => {
+            format!("{{ {} }}", fields.iter().map(|(k, v)| format!("{k:?}: {}", render_node(v))).collect::<Vec<_>>().join(", "))
+        }
+        Identifier(name) | IdentifierSpanned { name, .. } => name.clone(),
+        NumberLiteral(n) => n.to_string(),
+        StringLiteral(s) => format!("{s:?}"),
+        BooleanLiteral(b) => b.to_string(),
+        QubitDecl { name, .. } => format!("qubit {name}"),
+        QregDecl { name, size, .. } => format!("qreg {name}[{size}]"),
+        QuantumOp { op, qubits, .. } => format!("{op}({})", list(qubits)),
+        HieroglyphicOp { symbol, args } => format!("{symbol}({})", list(args)),
+        Error(e) => format!("<error: {e}>"),
+    }
+}
+
+fn at(line: usize) -> String {
+    if line > 0 { format!(" (line {line})") } else { String::new() }
+}
+
+/// One line per change (`+` added, `-` removed, `~` changed), then a summary line.
+pub fn render(changes: &[Change], st: &Style) -> String {
+    let arrow = st.glyph("→", "->");
+    let mut out = String::new();
+    for c in changes {
+        let (mark, role, text) = match c {
+            Change::FunctionAdded { name, line } => ("+", Role::Strong, format!("function {name}{}", at(*line))),
+            Change::FunctionRemoved { name, line } => ("-", Role::Error, format!("function {name}{}", at(*line))),
+            Change::FunctionRenamed { from, to, line, similarity } => {
+                let body = if *similarity >= 1.0 { "identical body".to_string() } else { format!("{:.0}% of statements unchanged", similarity * 100.0) };
+                let line = if *line > 0 { format!("line {line}, ") } else { String::new() };
+                ("~", Role::Warn, format!("function {from} renamed to {to} ({line}{body})"))
+            }
+            Change::ParamsChanged { function, line, old, new } => ("~", Role::Warn, format!("{function}{}: parameters ({}) {arrow} ({})", at(*line), old.join(", "), new.join(", "))),
+            Change::StatementAdded { scope, line, text } => ("+", Role::Strong, format!("{scope}{}: `{text}`", at(*line))),
+            Change::StatementRemoved { scope, line, text } => ("-", Role::Error, format!("{scope}{}: `{text}`", at(*line))),
+            Change::StatementChanged { scope, line, old, new } => ("~", Role::Warn, format!("{scope}{}: `{old}` {arrow} `{new}`", at(*line))),
+        };
+        out.push_str(&format!("{} {text}\n", st.paint(mark, role)));
+    }
+    out.push_str(&match changes.len() {
+        0 => "no semantic changes\n".to_string(),
+        1 => "1 semantic change\n".to_string(),
+        n => format!("{n} semantic changes\n"),
+    });
+    out
+}
+
+/// Compare `old` and `new`; parse failures are reported as diagnostics and exit with 3.
+pub fn main(old: &Path, new: &Path, json: bool, pretty: bool) -> anyhow::Result<()> {
+    let (Some(a), Some(b)) = (parse_file(old, pretty)?, parse_file(new, pretty)?) else {
+        return Err(reported(exit_codes::COMPILE_DIAG, "diff-ast: input failed to parse"));
+    };
+    let changes = diff(&a, &b);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({ "old": old.display().to_string(), "new": new.display().to_string(), "changes": changes }))?);
+    } else {
+        print!("{}", render(&changes, &style::current()));
+    }
+    Ok(())
+}
@@ -0,0 +1,150 @@
+//! Markdown doc-test harness: validates fenced ```aeonmi blocks in guides.
+//!
+//! - ```aeonmi       → must lex, parse and emit JS
+//! - ```aeonmi,run   → additionally runs on the native VM and must exit 0 (see
+//!                     `aeonmi run`'s return-value convention); if the next fence
+//!                     is ```output its body must match the captured output.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::run::run_source_captured;
+use crate::core::code_generator::CodeGenerator;
+use crate::core::lexer::Lexer;
+use crate::core::parser::Parser as AeParser;
+
+/// One fenced ```aeonmi block pulled out of a markdown file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocBlock {
+    /// 1-based line of the opening fence.
+    pub line: usize,
+    pub source: String,
+    pub run: bool,
+    pub expected: Option<String>,
+}
+
+/// A block that failed, with the markdown location it came from.
+#[derive(Debug, Clone)]
+pub struct DocFailure {
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for DocFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.message)
+    }
+}
+
+pub fn main(files: Vec<PathBuf>) -> Result<i32> {
+    let mut passed = 0usize;
+    let mut failed = 0usize;
+    for f in files {
+        let (count, failures) = check_file(&f)?;
+        for fail in &failures {
+            eprintln!("{}", fail);
+        }
+        failed += failures.len();
+        passed += count - failures.len();
+    }
+    println!("doctest: {} passed, {} failed", passed, failed);
+    Ok(if failed == 0 { 0 } else { 1 })
+}
+
+/// Check every block in `path`; returns (blocks seen, failures).
+pub fn check_file(path: &Path) -> Result<(usize, Vec<DocFailure>)> {
+    let md = fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let blocks = extract_blocks(&md);
+    let failures = blocks
+        .iter()
+        .filter_map(|b| {
+            check_block(b).err().map(|message| DocFailure {
+                file: path.display().to_string(),
+                line: b.line,
+                message,
+            })
+        })
+        .collect();
+    Ok((blocks.len(), failures))
+}
+
+/// Pull ```aeonmi fences (and their trailing ```output fences) out of markdown.
+pub fn extract_blocks(md: &str) -> Vec<DocBlock> {
+    // (info string, opening line, body)
+    let mut fences: Vec<(String, usize, String)> = Vec::new();
+    let mut open: Option<(String, usize, String)> = None;
+    for (idx, line) in md.lines().enumerate() {
+        let trimmed = line.trim_start();
+        match open.as_mut() {
+            None => {
+                if let Some(info) = trimmed.strip_prefix("```") {
+                    open = Some((info.trim().to_string(), idx + 1, String::new()));
+                }
+            }
+            Some((_, _, body)) => {
+                if trimmed.trim_end() == "```" {
+                    fences.push(open.take().unwrap());
+                } else {
+                    body.push_str(line);
+                    body.push('\n');
+                }
+            }
+        }
+    }
+
+    let mut blocks = Vec::new();
+    for (i, (info, line, body)) in fences.iter().enumerate() {
+        let mut tags = info.split(',').map(str::trim);
+        if tags.next() != Some("aeonmi") {
+            continue;
+        }
+        let run = tags.any(|t| t == "run");
+        let expected = if run {
+            fences
+                .get(i + 1)
+                .filter(|(next, _, _)| next == "output")
+                .map(|(_, _, out)| out.clone())
+        } else {
+            None
+        };
+        blocks.push(DocBlock {
+            line: *line,
+            source: body.clone(),
+            run,
+            expected,
+        });
+    }
+    blocks
+}
+
+/// Compile (and optionally run) a single block in memory.
+pub fn check_block(block: &DocBlock) -> Result<(), String> {
+    let tokens = Lexer::from_str(&block.source)
+        .tokenize()
+        .map_err(|e| format!("lexing error: {}", e))?;
+    let ast = AeParser::new(tokens)
+        .parse()
+        .map_err(|e| format!("parsing error: {} (block line {})", e.message, e.line))?;
+    CodeGenerator::new()
+        .generate(&ast)
+        .map_err(|e| format!("emit error: {}", e))?;
+    if !block.run {
+        return Ok(());
+    }
+    let run = run_source_captured(&block.source)?;
+    if run.exit_code != 0 {
+        return Err(format!("program exited with code {}", run.exit_code));
+    }
+    if let Some(expected) = &block.expected {
+        let actual = run.output;
+        if actual.trim_end() != expected.trim_end() {
+            return Err(format!(
+                "output mismatch\n--- expected\n{}--- actual\n{}",
+                expected, actual
+            ));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,11 @@
+//! `aeonmi doctor`: report external runtimes and what degrades without them.
+
+use anyhow::Result;
+
+use crate::core::toolchain::{doctor_report, version, Tool};
+
+pub fn main() -> Result<()> {
+    let found: Vec<(Tool, Option<String>)> = Tool::ALL.iter().map(|t| (*t, version(*t))).collect();
+    print!("{}", doctor_report(&found));
+    Ok(())
+}
@@ -10,6 +10,7 @@ use super::compile::compile_pipeline;
 use crate::cli::EmitKind;
 use crate::config::{default_config_path, ensure_parent_dir}; // <- config helpers
 use crate::core::qpoly::QPolyMap;
+use crate::core::toolchain::{self, Tool};
 
 // TUI entry (when --tui)
 use crate::tui::editor::run_editor_tui;
@@ -143,10 +144,14 @@ pub fn main(
                         /*skip_sema*/ false,
                         /*debug_titan*/ false,
                     )?;
-                    match std::process::Command::new("node").arg(&out).status() {
-                        Ok(s) if !s.success() => eprintln!("(warn) node exit: {s}"),
-                        Err(e) => eprintln!("(warn) node not available: {e}"),
-                        _ => {}
+                    if !toolchain::available(Tool::Node) {
+                        eprintln!("(warn) {}", toolchain::missing_tool_message(Tool::Node, "run (compiled to aeonmi.run.js)"));
+                    } else {
+                        match std::process::Command::new("node").arg(&out).status() {
+                            Ok(s) if !s.success() => eprintln!("(warn) node exit: {s}"),
+                            Err(e) => eprintln!("(warn) node not available: {e}"),
+                            _ => {}
+                        }
                     }
                 }
                 other if other.starts_with(":o ") => {
@@ -0,0 +1,46 @@
+//! `aeonmi examples list|show|run`: the bundled programs from `core::examples`.
+
+use anyhow::Result;
+
+use super::exit_codes::{fail, USAGE};
+use crate::cli::ExamplesAction;
+use crate::core::examples::{self, Example};
+
+pub fn main(action: ExamplesAction, pretty: bool, no_sema: bool) -> Result<()> {
+    match action {
+        ExamplesAction::List { tag } => {
+            for e in examples::list(tag.as_deref()) {
+                println!("{:<18}{:<40}[{}]", e.name, e.description, e.tags.join(", "));
+            }
+            Ok(())
+        }
+        ExamplesAction::Show { name } => {
+            let e = lookup(&name)?;
+            println!("// {} — {} [{}]", e.file, e.description, e.tags.join(", "));
+            print!("{}", e.source);
+            for (path, source) in e.modules {
+                println!("\n// {path}");
+                print!("{source}");
+            }
+            Ok(())
+        }
+        ExamplesAction::Run { name } => run(&name, pretty, no_sema),
+    }
+}
+
+/// Run a bundled example from its embedded source (no file on disk needed). One that
+/// imports modules is written to a temporary directory first, so the imports resolve.
+pub fn run(name: &str, pretty: bool, no_sema: bool) -> Result<()> {
+    let e = lookup(name)?;
+    if e.modules.is_empty() {
+        return super::run::run_source(e.file, e.source, pretty, no_sema);
+    }
+    let dir = tempfile::tempdir()?;
+    let entry = e.write_to(dir.path())?;
+    super::run::run_source(&entry.display().to_string(), e.source, pretty, no_sema)
+}
+
+fn lookup(name: &str) -> Result<&'static Example> {
+    examples::find(name)
+        .ok_or_else(|| fail(USAGE, format!("unknown example '{}'; run `aeonmi examples list`", name)))
+}
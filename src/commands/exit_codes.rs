@@ -0,0 +1,63 @@
+//! Process exit-code contract for `compile`/`emit`, `run` and `exec`.
+//!
+//! | code | meaning                                   |
+//! |------|-------------------------------------------|
+//! | 0    | success                                   |
+//! | 1    | generic / unclassified failure            |
+//! | 2    | usage error (bad flag value, unsupported) |
+//! | 3    | compile diagnostics (lex/parse/type/emit) |
+//! | 4    | runtime error (native VM or child JS)     |
+//! | 5    | io error (unreadable input or output)     |
+//!
+//! `run` also exits with the code the program's `main` returns (see [`crate::core::vm::exit_code`]).
+
+use std::fmt;
+
+pub const OK: i32 = 0;
+pub const GENERIC: i32 = 1;
+pub const USAGE: i32 = 2;
+pub const COMPILE_DIAG: i32 = 3;
+pub const RUNTIME: i32 = 4;
+pub const IO: i32 = 5;
+
+/// Error carrying a specific exit code. `reported` means diagnostics were already printed.
+#[derive(Debug)]
+pub struct ExitError {
+    pub code: i32,
+    pub message: String,
+    pub reported: bool,
+}
+
+impl fmt::Display for ExitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ExitError {}
+
+/// Failure that still needs to be shown to the user.
+pub fn fail(code: i32, message: impl Into<String>) -> anyhow::Error {
+    anyhow::Error::new(ExitError { code, message: message.into(), reported: false })
+}
+
+/// Failure whose diagnostics were already printed; only the exit code matters.
+pub fn reported(code: i32, message: impl Into<String>) -> anyhow::Error {
+    anyhow::Error::new(ExitError { code, message: message.into(), reported: true })
+}
+
+/// Map an error bubbling out of a command to its exit code.
+pub fn code_for(err: &anyhow::Error) -> i32 {
+    if let Some(e) = err.downcast_ref::<ExitError>() {
+        return e.code;
+    }
+    if err.chain().any(|c| c.downcast_ref::<std::io::Error>().is_some()) {
+        return IO;
+    }
+    GENERIC
+}
+
+/// True when the error's message has already reached the user.
+pub fn is_reported(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<ExitError>().map(|e| e.reported).unwrap_or(false)
+}
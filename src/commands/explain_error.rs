@@ -0,0 +1,28 @@
+//! `aeonmi explain-error <CODE>`: print the offline explanation for a diagnostic code.
+
+use anyhow::Result;
+
+use super::exit_codes::{fail, USAGE};
+use crate::core::error_index::{lookup, INDEX};
+
+pub fn main(code: Option<String>, list: bool) -> Result<()> {
+    if list {
+        for doc in INDEX {
+            println!("{}  {}", doc.code, doc.summary);
+        }
+        return Ok(());
+    }
+    let Some(code) = code else {
+        return Err(fail(USAGE, "expected an error code (e.g. AEO-P001) or --list"));
+    };
+    match lookup(&code) {
+        Some(doc) => {
+            print!("{}", doc.markdown);
+            Ok(())
+        }
+        None => Err(fail(
+            USAGE,
+            format!("unknown error code '{}'; run `aeonmi explain-error --list`", code),
+        )),
+    }
+}
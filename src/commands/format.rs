@@ -1,33 +1,287 @@
-//! Batch formatter for .ai files with --check mode.
+//! Batch formatter for .ai files with --check mode and optional `.orig` backups.
+//!
+//! Files whose formatted text equals the original are never written, so their mtimes
+//! stay put and watchers stay quiet. `.aeonmi/fmt-cache.json` remembers the SHA-1 of
+//! every file last seen in canonical form, together with the options it was formatted
+//! under; a later run that finds the same hash (under the same [`FORMATTER_VERSION`])
+//! skips formatting it. A summary of formatted, unchanged, cached and skipped
+//! (unreadable) files goes to stderr.
+//!
+//! Options come from the `[format]` table of each file's nearest `Aeonmi.toml`, with
+//! the command-line flags taking precedence.
+//!
+//! `--stdin` is the editor mode: one buffer in, the formatted buffer out (or, with
+//! `--check`, a unified diff), with no cache and no summary. The buffer must parse.
 
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 
-use crate::core::formatter::format_ai;
+use crate::commands::exit_codes::{fail, reported, COMPILE_DIAG, IO, USAGE};
+use crate::config::{nearest_manifest, FormatSettings, LexerSettings};
+use crate::core::diagnostics::{report_error, report_parse_error, Span};
+use crate::core::error_index;
+use crate::core::formatter::{format_ai_with, FormatOptions, FORMATTER_VERSION};
+use crate::core::lexer::{Lexer, LexerOptions};
+use crate::core::parser::Parser;
 use crate::io::atomic::atomic_write;
 
-pub fn main(paths: Vec<PathBuf>, check: bool) -> Result<i32> {
-    let mut changed = 0usize;
+/// Hashes of files known to be formatted, keyed by path as given on the command line.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FmtCache {
+    pub version: u32,
+    pub files: BTreeMap<String, String>,
+}
+
+impl FmtCache {
+    pub fn path() -> PathBuf {
+        std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")).join(".aeonmi").join("fmt-cache.json")
+    }
+
+    /// The cache on disk, or an empty one when it is missing, unreadable or from another formatter version.
+    pub fn load() -> Self {
+        fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|s| serde_json::from_str::<FmtCache>(&s).ok())
+            .filter(|c| c.version == FORMATTER_VERSION)
+            .unwrap_or(FmtCache { version: FORMATTER_VERSION, files: BTreeMap::new() })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        atomic_write(&path, serde_json::to_string_pretty(self)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FormatCounts {
+    /// Rewritten, or in `--check` mode, reported as needing it.
+    pub formatted: usize,
+    pub unchanged: usize,
+    pub cached: usize,
+    pub skipped: usize,
+}
+
+/// `style` holds the `--indent-width`, ... flags, which win over each manifest's `[format]`.
+pub fn main(paths: Vec<PathBuf>, check: bool, backup: bool, style: &FormatSettings) -> Result<i32> {
+    let mut cache = FmtCache::load();
+    let mut counts = FormatCounts::default();
+    let mut dirty = false;
+    // options per manifest: most files share one
+    let mut options: BTreeMap<Option<PathBuf>, FormatOptions> = BTreeMap::new();
     for p in paths {
         let Ok(orig) = fs::read_to_string(&p) else {
             eprintln!("warn: cannot read {}", p.display());
+            counts.skipped += 1;
             continue;
         };
-        let formatted = format_ai(&orig);
-
+        let manifest = nearest_manifest(&p);
+        let opt = match options.get(&manifest) {
+            Some(opt) => *opt,
+            None => {
+                let opt = format_options(Some(&p), style)?;
+                options.insert(manifest, opt);
+                opt
+            }
+        };
+        let key = p.display().to_string();
+        let hash = cache_hash(&orig, &opt);
+        if cache.files.get(&key) == Some(&hash) {
+            counts.cached += 1;
+            continue;
+        }
+        let formatted = format_ai_with(&orig, &opt);
+        if normalized(&orig) == normalized(&formatted) {
+            counts.unchanged += 1;
+            cache.files.insert(key, hash);
+            dirty = true;
+            continue;
+        }
+        counts.formatted += 1;
         if check {
-            if normalized(&orig) != normalized(&formatted) {
-                println!("{}", p.display());
-                changed += 1;
+            println!("{}", p.display());
+            continue;
+        }
+        if backup {
+            atomic_write(backup_path(&p), orig.as_bytes())?;
+        }
+        atomic_write(&p, formatted.as_bytes())?;
+        println!("formatted {}", p.display());
+        cache.files.insert(key, cache_hash(&formatted, &opt));
+        dirty = true;
+    }
+    if dirty {
+        if let Err(e) = cache.save() {
+            eprintln!("warn: cannot write {}: {e}", FmtCache::path().display());
+        }
+    }
+    eprintln!("{}", summary(&counts, check));
+    Ok(if counts.formatted == 0 { 0 } else { 1 })
+}
+
+/// `format --stdin`: format the buffer on stdin to stdout. `filename` (`--stdin-filename`)
+/// names it in diagnostics and the diff, and picks the `Aeonmi.toml` whose lexer plugins
+/// and `[format]` options apply. Under `check`, prints a unified diff when the buffer is
+/// not canonical and returns 1, like the batch mode; a buffer that does not parse exits 3.
+pub fn main_stdin(filename: Option<PathBuf>, check: bool, pretty: bool, style: &FormatSettings) -> Result<i32> {
+    let mut source = String::new();
+    if let Err(e) = std::io::stdin().read_to_string(&mut source) {
+        return Err(fail(IO, format!("cannot read stdin: {e}")));
+    }
+    let name = filename.as_ref().map_or_else(|| "<stdin>".to_string(), |p| p.display().to_string());
+    let lexer = match &filename {
+        Some(p) => LexerSettings::discover(p).map_err(|e| fail(USAGE, format!("{e:#}")))?.lexer_options(),
+        None => LexerOptions::default(),
+    };
+    let opt = format_options(filename.as_deref(), style)?;
+    let tokens = match Lexer::with_options(&source, lexer).tokenize() {
+        Ok(t) => t,
+        Err(e) => {
+            let (line, col) = e.position();
+            report_error(&name, &source, &format!("Lexing error: {e}"), Span::single(line, col), Some(error_index::LEX_ERROR), pretty);
+            return Err(reported(COMPILE_DIAG, "lexing failed"));
+        }
+    };
+    if let Err(e) = Parser::new(tokens).parse() {
+        report_parse_error(&name, &source, &e, pretty);
+        return Err(reported(COMPILE_DIAG, "parsing failed"));
+    }
+
+    let formatted = format_ai_with(&source, &opt);
+    if !check {
+        print!("{formatted}");
+        return Ok(0);
+    }
+    if normalized(&source) == normalized(&formatted) {
+        return Ok(0);
+    }
+    print!("{}", unified_diff(&name, &format!("{name} (formatted)"), &source, &formatted));
+    Ok(1)
+}
+
+/// Lines of context around each change in `unified_diff`.
+const DIFF_CONTEXT: usize = 3;
+
+/// `diff -u` style differences from `old` to `new`, line by line (line endings ignored);
+/// empty when the lines are equal.
+pub fn unified_diff(old_name: &str, new_name: &str, old: &str, new: &str) -> String {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let ops = line_ops(&a, &b);
+    let changed: Vec<usize> = ops.iter().enumerate().filter(|(_, op)| !matches!(op, LineOp::Same(_))).map(|(i, _)| i).collect();
+    if changed.is_empty() {
+        return String::new();
+    }
+    let mut out = format!("--- {old_name}\n+++ {new_name}\n");
+    // changes closer than twice the context share a hunk
+    let mut groups: Vec<(usize, usize)> = Vec::new();
+    for &i in &changed {
+        match groups.last_mut() {
+            Some((_, end)) if i - *end <= 2 * DIFF_CONTEXT => *end = i,
+            _ => groups.push((i, i)),
+        }
+    }
+    for (first, last) in groups {
+        let hunk = &ops[first.saturating_sub(DIFF_CONTEXT)..(last + DIFF_CONTEXT + 1).min(ops.len())];
+        // lines of each side before the hunk: what the header counts from when a side is empty
+        let (a_before, b_before) = ops[..first.saturating_sub(DIFF_CONTEXT)].iter().fold((0, 0), |(x, y), op| match op {
+            LineOp::Same(_) => (x + 1, y + 1),
+            LineOp::Removed(_) => (x + 1, y),
+            LineOp::Added(_) => (x, y + 1),
+        });
+        let a_len = hunk.iter().filter(|op| !matches!(op, LineOp::Added(_))).count();
+        let b_len = hunk.iter().filter(|op| !matches!(op, LineOp::Removed(_))).count();
+        let start = |before: usize, len: usize| if len == 0 { before } else { before + 1 };
+        out.push_str(&format!("@@ -{},{} +{},{} @@\n", start(a_before, a_len), a_len, start(b_before, b_len), b_len));
+        for op in hunk {
+            match *op {
+                LineOp::Same(i) => out.push_str(&format!(" {}\n", a[i])),
+                LineOp::Removed(i) => out.push_str(&format!("-{}\n", a[i])),
+                LineOp::Added(j) => out.push_str(&format!("+{}\n", b[j])),
             }
-        } else if normalized(&orig) != normalized(&formatted) {
-            atomic_write(&p, formatted.as_bytes())?;
-            println!("formatted {}", p.display());
-            changed += 1;
         }
     }
-    Ok(if changed == 0 { 0 } else { 1 })
+    out
+}
+
+enum LineOp {
+    /// Index into `a`.
+    Same(usize),
+    Removed(usize),
+    Added(usize),
+}
+
+/// Edit script from `a` to `b` by longest common subsequence, after trimming the common
+/// prefix and suffix (formatting usually leaves most lines alone).
+fn line_ops(a: &[&str], b: &[&str]) -> Vec<LineOp> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev()).take_while(|(x, y)| x == y).count();
+    let (mid_a, mid_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    let (n, m) = (mid_a.len(), mid_b.len());
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if mid_a[i] == mid_b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+    let mut ops: Vec<LineOp> = (0..prefix).map(LineOp::Same).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && mid_a[i] == mid_b[j] {
+            ops.push(LineOp::Same(prefix + i));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(LineOp::Removed(prefix + i));
+            i += 1;
+        } else {
+            ops.push(LineOp::Added(prefix + j));
+            j += 1;
+        }
+    }
+    ops.extend((a.len() - suffix..a.len()).map(LineOp::Same));
+    ops
+}
+
+/// `2 formatted, 5 unchanged, 3 cached, 0 skipped` (`would format` under `--check`).
+pub fn summary(c: &FormatCounts, check: bool) -> String {
+    let verb = if check { "would format" } else { "formatted" };
+    format!("{} {verb}, {} unchanged, {} cached, {} skipped", c.formatted, c.unchanged, c.cached, c.skipped)
+}
+
+/// `x.ai` → `x.ai.orig`.
+pub fn backup_path(p: &Path) -> PathBuf {
+    let mut name = p.as_os_str().to_owned();
+    name.push(".orig");
+    PathBuf::from(name)
+}
+
+/// `style` over the `[format]` table of `source`'s manifest (none without a source).
+fn format_options(source: Option<&Path>, style: &FormatSettings) -> Result<FormatOptions> {
+    let manifest = match source {
+        Some(p) => FormatSettings::discover(p).map_err(|e| fail(USAGE, format!("{e:#}")))?,
+        None => FormatSettings::default(),
+    };
+    Ok(style.clone().or(manifest).format_options())
+}
+
+/// Cache entry for `text` in canonical form under `opt`.
+fn cache_hash(text: &str, opt: &FormatOptions) -> String {
+    sha1_hex(&format!("{opt:?}\n{text}"))
+}
+
+fn sha1_hex(s: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(s.as_bytes());
+    format!("{:x}", hasher.finalize())
 }
 
 fn normalized(s: &str) -> String {
@@ -1,9 +1,18 @@
 use anyhow::Result;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
 use std::io::Write;
 use chrono::Local;
 
+use super::exit_codes::{fail, COMPILE_DIAG, IO, USAGE};
+use crate::core::code_generator::CodeGenerator;
+use crate::core::highlight;
+use crate::core::imports;
+use crate::core::lexer::{Lexer, LexerOptions};
+use crate::core::parser::Parser;
+use crate::core::py_emitter::PyEmitter;
+use crate::core::quantum_extract::{circuit_to_pseudo_qasm, extract_circuit};
+
 pub fn new_file(path: Option<PathBuf>) -> Result<()> {
     let target = path.unwrap_or_else(|| PathBuf::from("untitled.ai"));
     if target.exists() {
@@ -58,11 +67,104 @@ pub fn import(path: PathBuf) -> Result<()> {
     Ok(())
 }
 
-pub fn export(path: PathBuf, format: Option<String>) -> Result<()> {
-    println!("export: '{}' as '{}' (placeholder)", path.display(), format.unwrap_or_else(|| "auto".into()));
+/// Formats `export` writes, in the order they are listed to the user; each is also its file extension.
+pub const EXPORT_FORMATS: &[&str] = &["json", "js", "py", "qasm", "html"];
+
+/// Export `path` as `format` (or the format named by `out`'s extension), writing to `out`
+/// or next to the source with the format's extension (`-` prints it instead).
+pub fn export(path: PathBuf, format: Option<String>, out: Option<PathBuf>, no_overwrite: bool) -> Result<()> {
+    let supported = EXPORT_FORMATS.join(", ");
+    let format = format
+        .or_else(|| out.as_ref().and_then(|o| o.extension()).map(|e| e.to_string_lossy().into_owned()))
+        .ok_or_else(|| fail(USAGE, format!("export: pass --format (supported: {})", supported)))?
+        .to_ascii_lowercase();
+    if !EXPORT_FORMATS.contains(&format.as_str()) {
+        return Err(fail(USAGE, format!("export: unknown format '{}' (supported: {})", format, supported)));
+    }
+    let source = fs::read_to_string(&path).map_err(|e| fail(IO, format!("could not read '{}': {}", path.display(), e)))?;
+    let text = export_source(&source, &format, &path)?;
+    let dest = out.unwrap_or_else(|| path.with_extension(&format));
+    let dest = crate::commands::compile::output_target(&path, dest, no_overwrite)?;
+    if crate::commands::compile::is_stdout(&dest) {
+        print!("{}", text);
+        return Ok(());
+    }
+    fs::write(&dest, text).map_err(|e| fail(IO, format!("could not write '{}': {}", dest.display(), e)))?;
+    println!("export: '{}' -> '{}' ({})", path.display(), dest.display(), format);
     Ok(())
 }
 
+/// Render `source`, read from `path`, in one of [`EXPORT_FORMATS`]. Files it `import`s are
+/// spliced in first, so the other backends see the whole program; `html` shows `source` alone.
+pub fn export_source(source: &str, format: &str, path: &Path) -> Result<String> {
+    let title = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    if format == "html" {
+        return highlight_html(source, &title);
+    }
+    let tokens = Lexer::from_str(source).tokenize().map_err(|e| fail(COMPILE_DIAG, e.to_string()))?;
+    let ast = Parser::new(tokens).parse().map_err(|e| fail(COMPILE_DIAG, e.to_string()))?;
+    let ast = imports::resolve(ast, path, source, &LexerOptions::default()).map_err(|e| fail(COMPILE_DIAG, format!("import error: {e}")))?.program;
+    match format {
+        "json" => Ok(serde_json::to_string_pretty(&ast)? + "\n"),
+        "js" => CodeGenerator::new().generate(&ast).map_err(|e| fail(COMPILE_DIAG, e)),
+        "py" => Ok(PyEmitter::new().generate(&ast)),
+        "qasm" => {
+            let circuit = extract_circuit(&ast);
+            if circuit.gates.is_empty() {
+                return Err(fail(USAGE, "export: no quantum operations to export as qasm"));
+            }
+            if let Some(d) = circuit.diagnostics.first() {
+                return Err(fail(COMPILE_DIAG, format!("{}:{}:{}: export: {}", title, d.line, d.column, d.message)));
+            }
+            Ok(circuit_to_pseudo_qasm(&circuit))
+        }
+        other => Err(fail(USAGE, format!("export: unknown format '{}' (supported: {})", other, EXPORT_FORMATS.join(", ")))),
+    }
+}
+
+const HTML_STYLE: &str = "body { margin: 0; background: #1e1e2e; }
+pre.aeonmi { margin: 0; padding: 1em; color: #cdd6f4; font: 14px/1.5 monospace; }
+.kw { color: #cba6f7; font-weight: bold; }
+.quantum { color: #f5c2e7; font-weight: bold; }
+.glyph { color: #fab387; }
+.str { color: #a6e3a1; }
+.num, .bool { color: #fab387; }
+.comment { color: #7f849c; font-style: italic; }
+";
+
+/// A standalone page with `source` colored by token kind; everything is HTML-escaped.
+fn highlight_html(source: &str, title: &str) -> Result<String> {
+    let segments = highlight::segments(source).map_err(|e| fail(COMPILE_DIAG, e.to_string()))?;
+    let mut code = String::new();
+    for (class, text) in segments {
+        match class {
+            Some(class) => code.push_str(&format!("<span class=\"{}\">{}</span>", class.css(), html_escape(&text))),
+            None => code.push_str(&html_escape(&text)),
+        }
+    }
+    Ok(format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n<pre class=\"aeonmi\"><code>{}</code></pre>\n</body>\n</html>\n",
+        html_escape(title),
+        HTML_STYLE,
+        code
+    ))
+}
+
+fn html_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
 pub fn upload(path: PathBuf) -> Result<()> {
     use std::fs;
     use std::env;
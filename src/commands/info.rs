@@ -0,0 +1,15 @@
+//! `aeonmi info [--json]`: what this binary supports (see `core::build_info`).
+use anyhow::Result;
+use std::path::PathBuf;
+
+use crate::core::build_info::BuildInfo;
+
+pub fn main(json: bool, config: Option<PathBuf>) -> Result<()> {
+    let info = BuildInfo::current(config.as_deref());
+    if json {
+        println!("{}", serde_json::to_string_pretty(&info)?);
+    } else {
+        print!("{}", info.render());
+    }
+    Ok(())
+}
@@ -0,0 +1,44 @@
+//! `aeonmi ir <file.ai> [--json]`: print the lowered IR module, then verify it.
+//!
+//! Verification always runs here (see [`crate::core::ir_verify`]); violations are printed
+//! after the module as internal compiler errors and exit with 1, so a lowering bug can
+//! be inspected next to the IR that shows it.
+
+use serde::Serialize;
+use std::path::Path;
+
+use crate::commands::exit_codes::{self, fail, reported};
+use crate::core::diagnostics::quiet;
+use crate::core::ir::Module;
+use crate::core::ir_verify::{self, Violation};
+use crate::core::lowering::lower_unverified;
+use crate::core::style::{self, Role};
+
+#[derive(Debug, Serialize)]
+pub struct IrReport {
+    pub module: Module,
+    pub violations: Vec<Violation>,
+}
+
+pub fn main(input: &Path, json: bool, pretty: bool) -> anyhow::Result<()> {
+    let Some((_, flat)) = super::typecheck::load(input, pretty)? else {
+        return Err(reported(exit_codes::COMPILE_DIAG, "parsing failed"));
+    };
+    let module = lower_unverified(&flat.program, "main").map_err(|e| fail(exit_codes::COMPILE_DIAG, format!("lowering error: {e}")))?;
+    let report = IrReport { violations: ir_verify::verify(&module), module };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print!("{}", report.module);
+        if !quiet() {
+            let st = style::current();
+            for v in &report.violations {
+                eprintln!("{} {v}", st.paint("error:", Role::Error));
+            }
+        }
+    }
+    if !report.violations.is_empty() {
+        return Err(reported(exit_codes::GENERIC, format!("{} IR violation(s)", report.violations.len())));
+    }
+    Ok(())
+}
@@ -1,26 +1,32 @@
 use std::path::PathBuf;
 
-#[allow(dead_code)]
+use crate::core::diagnostics::{Diagnostic, DiagnosticSink};
+use crate::core::style;
+
 pub fn main_with_opts(inputs: Vec<PathBuf>, fix: bool) -> anyhow::Result<()> {
     use std::fs;
     use anyhow::Context;
 
     let mut problems = 0usize;
+    let mut sink = DiagnosticSink::new();
+    let mut summaries = Vec::new();
     for p in inputs {
         let content = fs::read_to_string(&p).with_context(|| format!("reading {}", p.display()))?;
+        let file = p.display().to_string();
+        let mut found = Vec::new();
         let mut out = String::new();
         let mut changed = false;
-        for line in content.lines() {
+        for (i, line) in content.lines().enumerate() {
             let trimmed_end = line.trim_end();
             if trimmed_end.len() != line.len() {
-                problems += 1;
+                found.push(Diagnostic::warning(&file, i + 1, trimmed_end.len() + 1, "trailing whitespace"));
                 changed = true;
             }
             out.push_str(trimmed_end);
             out.push('\n');
             // simple style: top-level lines that look like statements should end with ';'
             if !trimmed_end.ends_with(';') && trimmed_end.starts_with("let ") {
-                problems += 1;
+                found.push(Diagnostic::warning(&file, i + 1, trimmed_end.len() + 1, "statement should end with ';'"));
                 if fix {
                     out.pop();
                     out.push(';');
@@ -29,13 +35,19 @@ pub fn main_with_opts(inputs: Vec<PathBuf>, fix: bool) -> anyhow::Result<()> {
                 }
             }
         }
+        problems += found.len();
         if fix && changed {
             fs::write(&p, out).with_context(|| format!("writing {}", p.display()))?;
             println!("fixed {}", p.display());
-        } else if problems > 0 {
-            println!("{}: {} problems", p.display(), problems);
+        } else if !found.is_empty() {
+            summaries.push(format!("{}: {} problems", file, found.len()));
+            found.into_iter().for_each(|d| sink.push(d));
         }
     }
+    print!("{}", sink.render(&style::current()));
+    for s in summaries {
+        println!("{s}");
+    }
     if problems > 0 { std::process::exit(1); }
     Ok(())
 }
@@ -0,0 +1,89 @@
+//! `aeonmi math demo`: trajectories from Titan's dynamical-systems module, summarized on
+//! stdout or written with `--plot-csv` for an external plotter. Bad step sizes and
+//! trajectories that blow up are reported as errors, never as NaN rows.
+//!
+//! `aeonmi math derive`: a symbolic derivative from Titan's `symbolic_math`, printed in
+//! canonical form on its own line so it can be piped.
+use anyhow::Result;
+use std::convert::Infallible;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use super::exit_codes::{fail, IO, RUNTIME, USAGE};
+use crate::cli::MathSystem;
+use crate::core::style::{self, Role};
+use crate::core::titan::chaos_theory_dynamical_systems::{logistic_map, ode_trajectory, TrajectoryError};
+use crate::core::titan::symbolic_math::derivative;
+
+/// Column names and rows of a computed trajectory.
+struct Trajectory {
+    columns: &'static [&'static str],
+    rows: Vec<Vec<f64>>,
+}
+
+fn lorenz(_t: f64, s: &[f64]) -> Result<Vec<f64>, Infallible> {
+    let (sigma, rho, beta) = (10.0, 28.0, 8.0 / 3.0);
+    Ok(vec![sigma * (s[1] - s[0]), s[0] * (rho - s[2]) - s[1], s[0] * s[1] - beta * s[2]])
+}
+
+fn compute(system: MathSystem, dt: f64, t_end: Option<f64>, r: f64, iterations: usize) -> Result<Trajectory, TrajectoryError<Infallible>> {
+    let ode = |points: Vec<(f64, Vec<f64>)>| points.into_iter().map(|(t, y)| std::iter::once(t).chain(y).collect()).collect();
+    Ok(match system {
+        MathSystem::Lorenz => Trajectory {
+            columns: &["t", "x", "y", "z"],
+            rows: ode(ode_trajectory(lorenz, &[1.0, 1.0, 1.0], (0.0, t_end.unwrap_or(20.0)), dt)?),
+        },
+        MathSystem::Exp => Trajectory {
+            columns: &["t", "y"],
+            rows: ode(ode_trajectory(|_, y: &[f64]| Ok(y.to_vec()), &[1.0], (0.0, t_end.unwrap_or(1.0)), dt)?),
+        },
+        MathSystem::Logistic => Trajectory {
+            columns: &["n", "x"],
+            rows: logistic_map(r, 0.2, iterations)?.into_iter().enumerate().map(|(n, x)| vec![n as f64, x]).collect(),
+        },
+    })
+}
+
+fn csv(t: &Trajectory) -> String {
+    let mut out = t.columns.join(",") + "\n";
+    for row in &t.rows {
+        let cells: Vec<String> = row.iter().map(f64::to_string).collect();
+        let _ = writeln!(out, "{}", cells.join(","));
+    }
+    out
+}
+
+fn row_text(t: &Trajectory, row: &[f64]) -> String {
+    t.columns.iter().zip(row).map(|(c, v)| format!("{c} = {v:.6}")).collect::<Vec<_>>().join(", ")
+}
+
+pub fn demo(system: MathSystem, plot_csv: Option<&Path>, dt: f64, t_end: Option<f64>, r: f64, iterations: usize) -> Result<()> {
+    let name = format!("{system:?}").to_lowercase();
+    let trajectory = compute(system, dt, t_end, r, iterations).map_err(|e| match e {
+        TrajectoryError::Unstable { .. } => fail(RUNTIME, format!("{name}: {e}")),
+        e => fail(USAGE, format!("{name}: {e}")),
+    })?;
+    if let Some(path) = plot_csv {
+        std::fs::write(path, csv(&trajectory)).map_err(|e| fail(IO, format!("cannot write {}: {e}", path.display())))?;
+        println!("{name}: wrote {} points to {}", trajectory.rows.len(), path.display());
+        return Ok(());
+    }
+    let st = style::current();
+    println!("{} ({} points)", st.paint(&name, Role::Section), trajectory.rows.len());
+    if let (Some(first), Some(last)) = (trajectory.rows.first(), trajectory.rows.last()) {
+        println!("  first: {}", row_text(&trajectory, first));
+        println!("  last:  {}", row_text(&trajectory, last));
+    }
+    if system == MathSystem::Exp {
+        let last = trajectory.rows.last().map_or(0.0, |row| row[0]);
+        println!("  exact: y = {:.6} (e^t)", last.exp());
+    }
+    println!("{}", st.paint("write every point with --plot-csv FILE", Role::Dim));
+    Ok(())
+}
+
+pub fn derive(expr: &str, wrt: &str) -> Result<()> {
+    let d = derivative(expr, wrt).map_err(|e| fail(USAGE, format!("math derive: {e}")))?;
+    println!("{d}");
+    Ok(())
+}
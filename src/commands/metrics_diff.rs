@@ -0,0 +1,217 @@
+//! `aeonmi metrics-diff`: compare two persisted metrics files (e.g. before and after a
+//! refactor, each captured with `metrics-dump > file`).
+//!
+//! Both files are migrated to the current schema first, so a baseline written by an
+//! older build still diffs cleanly. Functions are aligned by name (the site recorded with
+//! their timings), falling back to `#<index>` for entries without one. A function whose
+//! EMA or average inference time grows by more than the threshold, or cumulative savings
+//! that drop by more than the threshold in percentage points, count as regressions.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::commands::exit_codes::{self, fail};
+use crate::core::incremental::{metrics_file_location, migrate_metrics};
+use crate::core::style::{self, Role, Style};
+
+/// Timings of one function in one file.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct FunctionStats {
+    pub runs: u64,
+    pub ema_ns: u64,
+    pub avg_ns: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FunctionDelta {
+    pub name: String,
+    /// `None` when the function only exists in the other file.
+    pub before: Option<FunctionStats>,
+    pub after: Option<FunctionStats>,
+    /// Relative change in percent; `None` unless the function is in both files with a nonzero baseline.
+    pub ema_change_pct: Option<f64>,
+    pub avg_change_pct: Option<f64>,
+    pub runs_delta: i64,
+    pub regression: bool,
+}
+
+/// A scalar compared across the two files.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Change {
+    pub before: f64,
+    pub after: f64,
+    pub delta: f64,
+}
+
+impl Change {
+    fn new(before: f64, after: f64) -> Self {
+        Change { before, after, delta: after - before }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricsDiff {
+    pub threshold_pct: f64,
+    pub functions: Vec<FunctionDelta>,
+    pub call_graph_functions: Change,
+    pub call_graph_edges: Change,
+    pub variable_edges: Change,
+    pub savings_pct: Change,
+    pub partial_pct: Change,
+    /// One line per regression beyond the threshold.
+    pub regressions: Vec<String>,
+}
+
+/// Read `path` and migrate it to the current metrics schema.
+pub fn load(path: &Path) -> anyhow::Result<serde_json::Value> {
+    let text = std::fs::read_to_string(path).map_err(|e| fail(exit_codes::IO, format!("{}: {e}", path.display())))?;
+    let val = serde_json::from_str(&text).map_err(|e| fail(exit_codes::USAGE, format!("{}: invalid JSON: {e}", path.display())))?;
+    migrate_metrics(val).map_err(|e| fail(exit_codes::USAGE, format!("{}: {e}", path.display())))
+}
+
+fn num(v: &serde_json::Value, path: &[&str]) -> f64 {
+    path.iter().try_fold(v, |v, k| v.get(k)).and_then(|v| v.as_f64()).unwrap_or(0.0)
+}
+
+fn functions(v: &serde_json::Value) -> BTreeMap<String, FunctionStats> {
+    let Some(obj) = v.get("functionMetrics").and_then(|f| f.as_object()) else { return BTreeMap::new(); };
+    obj.iter()
+        .map(|(idx, f)| {
+            let name = f.get("name").and_then(|n| n.as_str()).map(str::to_string).unwrap_or_else(|| format!("#{idx}"));
+            let get = |k: &str| f.get(k).and_then(|x| x.as_u64()).unwrap_or(0);
+            (name, FunctionStats { runs: get("runs"), ema_ns: get("ema_ns"), avg_ns: get("avg_ns") })
+        })
+        .collect()
+}
+
+fn pct_change(before: u64, after: u64) -> Option<f64> {
+    (before > 0).then(|| (after as f64 - before as f64) * 100.0 / before as f64)
+}
+
+/// Compare two migrated metrics documents; `threshold_pct` bounds what counts as a regression.
+pub fn diff(before: &serde_json::Value, after: &serde_json::Value, threshold_pct: f64) -> MetricsDiff {
+    let (old, new) = (functions(before), functions(after));
+    let mut names: Vec<&String> = old.keys().chain(new.keys()).collect();
+    names.sort();
+    names.dedup();
+    let mut regressions = Vec::new();
+    let functions = names
+        .into_iter()
+        .map(|name| {
+            let (b, a) = (old.get(name).copied(), new.get(name).copied());
+            let (ema, avg) = match (b, a) {
+                (Some(b), Some(a)) => (pct_change(b.ema_ns, a.ema_ns), pct_change(b.avg_ns, a.avg_ns)),
+                _ => (None, None),
+            };
+            let mut regression = false;
+            for (label, change) in [("ema", ema), ("avg", avg)] {
+                if let Some(c) = change.filter(|&c| c > threshold_pct) {
+                    regressions.push(format!("{name}: {label} +{c:.1}%"));
+                    regression = true;
+                }
+            }
+            FunctionDelta {
+                name: name.clone(),
+                before: b,
+                after: a,
+                ema_change_pct: ema,
+                avg_change_pct: avg,
+                runs_delta: a.map_or(0, |s| s.runs as i64) - b.map_or(0, |s| s.runs as i64),
+                regression,
+            }
+        })
+        .collect();
+    let savings_pct = Change::new(num(before, &["savings", "cumulative_savings_pct"]), num(after, &["savings", "cumulative_savings_pct"]));
+    if -savings_pct.delta > threshold_pct {
+        regressions.push(format!("cumulative savings {:.1} pts", savings_pct.delta));
+    }
+    MetricsDiff {
+        threshold_pct,
+        functions,
+        call_graph_functions: Change::new(num(before, &["metrics", "functions"]), num(after, &["metrics", "functions"])),
+        call_graph_edges: Change::new(num(before, &["metrics", "edges"]), num(after, &["metrics", "edges"])),
+        variable_edges: Change::new(num(before, &["metrics", "variable_edges"]), num(after, &["metrics", "variable_edges"])),
+        savings_pct,
+        partial_pct: Change::new(num(before, &["savings", "cumulative_partial_pct"]), num(after, &["savings", "cumulative_partial_pct"])),
+        regressions,
+    }
+}
+
+fn stat(v: Option<u64>) -> String {
+    v.map_or_else(|| "-".to_string(), |n| n.to_string())
+}
+
+fn pct(c: Option<f64>) -> String {
+    c.map_or_else(String::new, |c| format!(" ({c:+.1}%)"))
+}
+
+/// Render `d` as a function table followed by the call-graph and savings summary.
+pub fn render(d: &MetricsDiff, st: &Style) -> String {
+    let arrow = st.glyph("→", "->");
+    let rows: Vec<[String; 4]> = d
+        .functions
+        .iter()
+        .map(|f| {
+            let (b, a) = (f.before, f.after);
+            [
+                f.name.clone(),
+                format!("{} {arrow} {} ({:+})", stat(b.map(|s| s.runs)), stat(a.map(|s| s.runs)), f.runs_delta),
+                format!("{} {arrow} {}{}", stat(b.map(|s| s.ema_ns)), stat(a.map(|s| s.ema_ns)), pct(f.ema_change_pct)),
+                format!("{} {arrow} {}{}", stat(b.map(|s| s.avg_ns)), stat(a.map(|s| s.avg_ns)), pct(f.avg_change_pct)),
+            ]
+        })
+        .collect();
+    let headers = ["function", "runs", "ema_ns", "avg_ns"];
+    let w: Vec<usize> = (0..4).map(|c| rows.iter().map(|r| r[c].chars().count()).chain([headers[c].len()]).max().unwrap_or(0)).collect();
+    let mut out = String::new();
+    let header = format!("{:<w0$}  {:<w1$}  {:<w2$}  {}", headers[0], headers[1], headers[2], headers[3], w0 = w[0], w1 = w[1], w2 = w[2]);
+    out.push_str(&st.paint(&header, Role::Section));
+    out.push('\n');
+    for (r, f) in rows.iter().zip(&d.functions) {
+        let pad = |s: &str, w: usize| format!("{s}{}", " ".repeat(w.saturating_sub(s.chars().count())));
+        let line = format!("{}  {}  {}  {}", pad(&r[0], w[0]), pad(&r[1], w[1]), pad(&r[2], w[2]), r[3]);
+        out.push_str(&if f.regression { st.paint(&line, Role::Error) } else { line });
+        out.push('\n');
+    }
+    let count = |c: &Change| format!("{} {arrow} {} ({:+})", c.before, c.after, c.delta);
+    let points = |c: &Change| format!("{:.1}% {arrow} {:.1}% ({:+.1} pts)", c.before, c.after, c.delta);
+    out.push_str(&format!("call graph: functions {}, edges {}\n", count(&d.call_graph_functions), count(&d.call_graph_edges)));
+    out.push_str(&format!("variable edges: {}\n", count(&d.variable_edges)));
+    out.push_str(&format!("savings: cumulative {}, partial {}\n", points(&d.savings_pct), points(&d.partial_pct)));
+    if d.regressions.is_empty() {
+        out.push_str(&format!("no regressions beyond {}%\n", d.threshold_pct));
+    } else {
+        out.push_str(&st.paint(&format!("regressions beyond {}%:", d.threshold_pct), Role::Error));
+        out.push('\n');
+        for r in &d.regressions {
+            out.push_str(&format!("  {r}\n"));
+        }
+    }
+    out
+}
+
+/// `files` are `BEFORE AFTER`; with `baseline` they are just `[AFTER]`, and AFTER defaults to
+/// the live metrics file.
+pub fn main(files: &[PathBuf], baseline: Option<&Path>, threshold_pct: f64, fail_on_regression: bool, json: bool) -> anyhow::Result<()> {
+    let (before, after) = match (baseline, files) {
+        (Some(b), []) => (b.to_path_buf(), metrics_file_location()),
+        (Some(b), [a]) => (b.to_path_buf(), a.clone()),
+        (None, [b, a]) => (b.clone(), a.clone()),
+        (Some(_), _) => return Err(fail(exit_codes::USAGE, "with --baseline, pass at most one metrics file (the after side)")),
+        (None, _) => return Err(fail(exit_codes::USAGE, "metrics-diff needs BEFORE and AFTER files (or --baseline FILE)")),
+    };
+    if threshold_pct.is_nan() || threshold_pct < 0.0 {
+        return Err(fail(exit_codes::USAGE, format!("--threshold must be a non-negative percentage, got {threshold_pct}")));
+    }
+    let d = diff(&load(&before)?, &load(&after)?, threshold_pct);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&d)?);
+    } else {
+        print!("{}", render(&d, &style::current()));
+    }
+    if fail_on_regression && !d.regressions.is_empty() {
+        return Err(fail(exit_codes::GENERIC, format!("{} regression(s) beyond {}%", d.regressions.len(), threshold_pct)));
+    }
+    Ok(())
+}
@@ -0,0 +1,212 @@
+//! `aeonmi metrics-replay <file.ai> <edits.json>`: apply a scripted sequence of line
+//! edits and, for every step, time the incremental path (`parse_or_partial` plus
+//! per-function re-inference, as the GUI `aeonmi_types` command does) against a
+//! forced full parse + inference. Timings are measured, not estimated; the reinfer
+//! sets are deterministic, so CI can assert on those alone.
+//!
+//! The edit script is a JSON array applied in order; lines are 1-based and inclusive:
+//!
+//! ```json
+//! [
+//!   {"op": "replace", "start": 8, "end": 8, "lines": ["    return x + 20;"], "label": "tweak offset"},
+//!   {"op": "insert", "at": 20, "lines": ["function extra() {", "    return 1;", "}"]},
+//!   {"op": "delete", "start": 20, "end": 22}
+//! ]
+//! ```
+//!
+//! `insert` puts its lines before line `at` (one past the last line appends).
+//!
+//! A cache reset after a panic (see `incremental::lock_cache`) is reported under
+//! `cacheHealth` in `--json` and as a warning after the table.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Instant;
+
+use super::exit_codes::{fail, COMPILE_DIAG, IO, USAGE};
+use crate::core::ast::ASTNode;
+use crate::core::incremental::{cache_health, lock_cache, parse_or_cached, parse_or_partial, reinfer_set, LAST_REPLACED_INDEX};
+use crate::core::lexer::Lexer;
+use crate::core::parser::Parser;
+use crate::core::types::TypeContext;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Edit {
+    Insert { at: usize, lines: Vec<String> },
+    Delete { start: usize, end: usize },
+    Replace { start: usize, end: usize, lines: Vec<String> },
+}
+
+#[derive(Debug, Deserialize)]
+struct Step {
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(flatten)]
+    edit: Edit,
+}
+
+#[derive(Debug, Serialize)]
+struct StepReport {
+    step: usize,
+    label: String,
+    /// `partial` when a single top-level item was spliced, else `full`.
+    mode: &'static str,
+    changed: Option<String>,
+    reinfer: Vec<String>,
+    incremental_ns: u64,
+    full_ns: u64,
+    savings_ns: i64,
+    cumulative_savings_ns: i64,
+}
+
+pub fn main(file: &Path, edits: &Path, json: bool, csv: bool) -> Result<()> {
+    let source = std::fs::read_to_string(file).map_err(|e| fail(IO, format!("could not read '{}': {}", file.display(), e)))?;
+    let script = std::fs::read_to_string(edits).map_err(|e| fail(IO, format!("could not read '{}': {}", edits.display(), e)))?;
+    let steps: Vec<Step> =
+        serde_json::from_str(&script).map_err(|e| fail(USAGE, format!("{}: invalid edit script: {}", edits.display(), e)))?;
+    let reports = replay(&source, &steps).map_err(|e| fail(COMPILE_DIAG, format!("{}: {}", file.display(), e)))?;
+
+    let (incremental, full): (u64, u64) = reports.iter().fold((0, 0), |(i, f), r| (i + r.incremental_ns, f + r.full_ns));
+    let savings = reports.last().map(|r| r.cumulative_savings_ns).unwrap_or(0);
+    if json {
+        let doc = serde_json::json!({
+            "file": file.display().to_string(),
+            "steps": reports,
+            "total": {"incremental_ns": incremental, "full_ns": full, "savings_ns": savings},
+            "cacheHealth": cache_health(),
+        });
+        println!("{}", serde_json::to_string_pretty(&doc)?);
+    } else if csv {
+        println!("step,label,mode,changed,reinfer_count,incremental_ns,full_ns,savings_ns,cumulative_savings_ns");
+        for r in &reports {
+            println!(
+                "{},{},{},{},{},{},{},{},{}",
+                r.step,
+                csv_field(&r.label),
+                r.mode,
+                csv_field(r.changed.as_deref().unwrap_or("")),
+                r.reinfer.len(),
+                r.incremental_ns,
+                r.full_ns,
+                r.savings_ns,
+                r.cumulative_savings_ns
+            );
+        }
+    } else {
+        println!("{:<5}{:<28}{:<9}{:<16}{:>8}{:>12}{:>12}{:>12}", "step", "label", "mode", "changed", "reinfer", "incr_us", "full_us", "saved_us");
+        for r in &reports {
+            println!(
+                "{:<5}{:<28}{:<9}{:<16}{:>8}{:>12.1}{:>12.1}{:>12.1}",
+                r.step,
+                r.label,
+                r.mode,
+                r.changed.as_deref().unwrap_or("-"),
+                r.reinfer.len(),
+                r.incremental_ns as f64 / 1000.0,
+                r.full_ns as f64 / 1000.0,
+                r.savings_ns as f64 / 1000.0
+            );
+        }
+        let pct = if full > 0 { savings as f64 * 100.0 / full as f64 } else { 0.0 };
+        println!(
+            "total: incremental {:.1}us, full {:.1}us, saved {:.1}us ({:.1}%)",
+            incremental as f64 / 1000.0,
+            full as f64 / 1000.0,
+            savings as f64 / 1000.0,
+            pct
+        );
+        for r in cache_health().resets {
+            println!("warning: the {} cache was reset after a panic; timings after it are from a cold cache", r.cache);
+        }
+    }
+    Ok(())
+}
+
+fn replay(source: &str, steps: &[Step]) -> Result<Vec<StepReport>, String> {
+    let mut lines: Vec<String> = source.lines().map(str::to_string).collect();
+    // Prime the parse cache with the unedited source; this baseline is not reported.
+    parse_or_cached(source)?;
+    let mut reports = Vec::with_capacity(steps.len());
+    let mut cumulative = 0i64;
+    for (i, step) in steps.iter().enumerate() {
+        let n = i + 1;
+        let label = step.label.clone().unwrap_or_else(|| format!("step {}", n));
+        apply(&mut lines, &step.edit).map_err(|e| format!("step {} ({}): {}", n, label, e))?;
+        let text = lines.join("\n") + "\n";
+
+        *lock_cache("replaced_index", &LAST_REPLACED_INDEX) = None;
+        let start = Instant::now();
+        let (ast, _) = parse_or_partial(&text).map_err(|e| format!("step {} ({}): {}", n, label, e))?;
+        let replaced = *lock_cache("replaced_index", &LAST_REPLACED_INDEX);
+        let ASTNode::Program(items) = &ast else { return Err(format!("step {} ({}): expected a program", n, label)) };
+        let (mode, changed, reinfer) = match replaced {
+            Some(r) if r < items.len() => {
+                let deps = reinfer_set(items, r);
+                for idx in std::iter::once(r).chain(deps.iter().copied()) {
+                    TypeContext::new().infer_program(&ASTNode::Program(vec![items[idx].clone()]));
+                }
+                ("partial", Some(item_name(items, r)), deps.iter().map(|&d| item_name(items, d)).collect())
+            }
+            _ => {
+                TypeContext::new().infer_program(&ast);
+                ("full", None, Vec::new())
+            }
+        };
+        let incremental_ns = start.elapsed().as_nanos() as u64;
+
+        let start = Instant::now();
+        let tokens = Lexer::from_str(&text).tokenize().map_err(|e| e.to_string())?;
+        let full_ast = Parser::new(tokens).parse().map_err(|e| e.to_string())?;
+        TypeContext::new().infer_program(&full_ast);
+        let full_ns = start.elapsed().as_nanos() as u64;
+
+        let savings_ns = full_ns as i64 - incremental_ns as i64;
+        cumulative += savings_ns;
+        reports.push(StepReport { step: n, label, mode, changed, reinfer, incremental_ns, full_ns, savings_ns, cumulative_savings_ns: cumulative });
+    }
+    Ok(reports)
+}
+
+fn apply(lines: &mut Vec<String>, edit: &Edit) -> Result<(), String> {
+    let check = |start: usize, end: usize| {
+        if start == 0 || start > end || end > lines.len() {
+            Err(format!("line range {}-{} is outside 1-{}", start, end, lines.len()))
+        } else {
+            Ok(())
+        }
+    };
+    match edit {
+        Edit::Insert { at, lines: new } => {
+            if *at == 0 || *at > lines.len() + 1 {
+                return Err(format!("insert position {} is outside 1-{}", at, lines.len() + 1));
+            }
+            lines.splice(at - 1..at - 1, new.iter().cloned());
+        }
+        Edit::Delete { start, end } => {
+            check(*start, *end)?;
+            lines.drain(start - 1..*end);
+        }
+        Edit::Replace { start, end, lines: new } => {
+            check(*start, *end)?;
+            lines.splice(start - 1..*end, new.iter().cloned());
+        }
+    }
+    Ok(())
+}
+
+fn item_name(items: &[ASTNode], idx: usize) -> String {
+    match &items[idx] {
+        ASTNode::Function { name, .. } | ASTNode::VariableDecl { name, .. } | ASTNode::Assignment { name, .. } => name.clone(),
+        _ => format!("#{}", idx),
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
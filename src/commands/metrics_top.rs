@@ -0,0 +1,183 @@
+//! `aeonmi metrics-top`: hottest functions from the persisted inference metrics.
+//!
+//! Metrics are keyed by top-level item index; rows show the function name, defining
+//! file (when the recorder knew it) and line from the site stored next to each timing.
+//! Entries recorded before sites existed fall back to `#<index>`. The table adapts to
+//! the terminal width (`COLUMNS` wins when set) by truncating the name and location
+//! columns with a marker; `--json` always carries the full values.
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::cli::TopSort;
+use crate::core::incremental::{lock_cache, FunctionInferenceMetric, FUNCTION_METRICS};
+use crate::core::style::{self, Role, Style};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TopRow {
+    pub index: usize,
+    pub name: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub runs: u64,
+    pub last_ns: u128,
+    pub total_ns: u128,
+    pub avg_ns: u128,
+    pub ema_ns: u128,
+    /// Share of `total_ns` across every recorded function, not just the rows shown.
+    pub pct_total: f64,
+}
+
+impl TopRow {
+    pub fn label(&self) -> String {
+        self.name.clone().unwrap_or_else(|| format!("#{}", self.index))
+    }
+
+    pub fn location(&self) -> String {
+        match (&self.file, self.line) {
+            (Some(f), Some(l)) => format!("{f}:{l}"),
+            (None, Some(l)) => format!("line {l}"),
+            (Some(f), None) => f.clone(),
+            (None, None) => "-".to_string(),
+        }
+    }
+}
+
+/// Rank `metrics` by `sort` (descending, ties by index), drop functions with fewer than
+/// `min_runs` runs and keep the first `limit`.
+pub fn rank(
+    metrics: &std::collections::HashMap<usize, FunctionInferenceMetric>,
+    sort: TopSort,
+    min_runs: u64,
+    limit: usize,
+) -> Vec<TopRow> {
+    let grand_total: u128 = metrics.values().map(|m| m.total_ns).sum();
+    let mut rows: Vec<TopRow> = metrics
+        .iter()
+        .filter(|(_, m)| m.runs >= min_runs)
+        .map(|(&index, m)| TopRow {
+            index,
+            name: m.site.as_ref().map(|s| s.name.clone()),
+            file: m.site.as_ref().and_then(|s| s.file.clone()),
+            line: m.site.as_ref().map(|s| s.line).filter(|&l| l > 0),
+            runs: m.runs,
+            last_ns: m.last_ns,
+            total_ns: m.total_ns,
+            avg_ns: if m.runs > 0 { m.total_ns / m.runs as u128 } else { 0 },
+            ema_ns: m.ema_ns,
+            pct_total: if grand_total > 0 { m.total_ns as f64 * 100.0 / grand_total as f64 } else { 0.0 },
+        })
+        .collect();
+    let key = |r: &TopRow| match sort {
+        TopSort::Ema => r.ema_ns,
+        TopSort::Avg => r.avg_ns,
+        TopSort::Total => r.total_ns,
+        TopSort::Runs => r.runs as u128,
+    };
+    rows.sort_by(|a, b| key(b).cmp(&key(a)).then(a.index.cmp(&b.index)));
+    rows.truncate(limit);
+    rows
+}
+
+const NUM_HEADERS: [&str; 6] = ["runs", "ema_ns", "avg_ns", "total_ns", "last_ns", "%total"];
+const MIN_NAME: usize = 8;
+const MIN_LOC: usize = 8;
+
+/// Render `rows` as a table no wider than `width` (numeric columns are never cut).
+pub fn render_table(rows: &[TopRow], width: usize, st: &Style) -> String {
+    let cells: Vec<[String; 6]> = rows
+        .iter()
+        .map(|r| {
+            [
+                r.runs.to_string(),
+                r.ema_ns.to_string(),
+                r.avg_ns.to_string(),
+                r.total_ns.to_string(),
+                r.last_ns.to_string(),
+                format!("{:.1}", r.pct_total),
+            ]
+        })
+        .collect();
+    let num_w: Vec<usize> = (0..6).map(|c| cells.iter().map(|row| row[c].len()).chain([NUM_HEADERS[c].len()]).max().unwrap_or(0)).collect();
+    let idx_w = rows.iter().map(|r| r.index.to_string().len()).chain([3]).max().unwrap_or(3);
+    let labels: Vec<String> = rows.iter().map(TopRow::label).collect();
+    let locs: Vec<String> = rows.iter().map(TopRow::location).collect();
+    let mut name_w = labels.iter().map(|l| l.chars().count()).chain(["function".len()]).max().unwrap_or(MIN_NAME);
+    let mut loc_w = locs.iter().map(|l| l.chars().count()).chain(["location".len()]).max().unwrap_or(MIN_LOC);
+
+    // idx, function, location and the numeric columns, separated by two spaces
+    let fixed = idx_w + num_w.iter().sum::<usize>() + 2 * (2 + NUM_HEADERS.len());
+    let mut over = (fixed + name_w + loc_w).saturating_sub(width);
+    let cut = over.min(loc_w.saturating_sub(MIN_LOC));
+    loc_w -= cut;
+    over -= cut;
+    name_w -= over.min(name_w.saturating_sub(MIN_NAME));
+
+    let marker = st.glyph("…", "~");
+    let mut out = String::new();
+    let mut header = format!("{:<idx_w$}  {:<name_w$}  {:<loc_w$}", "idx", "function", "location");
+    for (h, w) in NUM_HEADERS.iter().zip(&num_w) {
+        header.push_str(&format!("  {h:>w$}"));
+    }
+    out.push_str(&st.paint(&header, Role::Section));
+    out.push('\n');
+    for ((r, row), (label, loc)) in rows.iter().zip(&cells).zip(labels.iter().zip(&locs)) {
+        let name = pad(&truncate_end(label, name_w, marker), name_w);
+        let loc = pad(&truncate_start(loc, loc_w, marker), loc_w);
+        let mut line = format!("{:<idx_w$}  {}  {}", r.index, st.paint(&name, Role::Name), loc);
+        for (v, w) in row.iter().zip(&num_w) {
+            line.push_str(&format!("  {v:>w$}"));
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+fn pad(s: &str, w: usize) -> String {
+    format!("{s}{}", " ".repeat(w.saturating_sub(s.chars().count())))
+}
+
+/// Keep the start of a name: `very_long_fu…`.
+fn truncate_end(s: &str, w: usize, marker: &str) -> String {
+    if s.chars().count() <= w {
+        return s.to_string();
+    }
+    let keep = w.saturating_sub(marker.chars().count());
+    format!("{}{marker}", s.chars().take(keep).collect::<String>())
+}
+
+/// Keep the end of a location, where the file name and line are: `…/lib/util.ai:12`.
+fn truncate_start(s: &str, w: usize, marker: &str) -> String {
+    let n = s.chars().count();
+    if n <= w {
+        return s.to_string();
+    }
+    let keep = w.saturating_sub(marker.chars().count());
+    format!("{marker}{}", s.chars().skip(n - keep).collect::<String>())
+}
+
+pub fn main(limit: usize, sort: TopSort, min_runs: u64, json: bool) -> anyhow::Result<()> {
+    use crate::core::incremental::{get_deep_propagation, load_metrics, EMA_ALPHA_RUNTIME, WINDOW_CAP_RUNTIME};
+    use std::sync::atomic::Ordering;
+    load_metrics();
+    let data = lock_cache("function_metrics", &FUNCTION_METRICS).clone();
+    let rows = rank(&data, sort, min_runs, limit);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+    println!(
+        "(deepPropagation={} ema_alpha={} window={} sort={})",
+        get_deep_propagation(),
+        EMA_ALPHA_RUNTIME.load(Ordering::Relaxed),
+        WINDOW_CAP_RUNTIME.load(Ordering::Relaxed),
+        sort.to_possible_value().map(|v| v.get_name().to_string()).unwrap_or_default()
+    );
+    if rows.is_empty() {
+        println!("no function metrics{}", if min_runs > 0 { format!(" with at least {min_runs} runs") } else { String::new() });
+    } else {
+        print!("{}", render_table(&rows, crate::core::columns::terminal_width(), &style::current()));
+    }
+    Ok(())
+}
@@ -1,14 +1,42 @@
+pub mod ai_review;
+pub mod ai_suggest;
+pub mod ai_usage;
 pub mod ast;
+pub mod build;
+pub mod cargo;
+pub mod circuit;
 pub mod compile;
+pub mod defaults;
+pub mod diff_ast;
+pub mod doctest;
+pub mod doctor;
 pub mod edit;
+pub mod examples;
+pub mod exit_codes;
+pub mod explain_error;
 pub mod format;
+pub mod info;
+pub mod ir;
 pub mod fs;
 pub mod lint;
+pub mod math;
+pub mod metrics_diff;
+pub mod metrics_replay;
+pub mod metrics_top;
+pub mod quantum_compare;
 pub mod repl;
 pub mod run;
+pub mod secrets;
+pub mod serve;
+pub mod shell_cwd;
+pub mod stats;
+pub mod symbols;
 pub mod tokens;
+pub mod typecheck;
+pub mod upgrade;
 pub mod vault;
 pub mod vm;
+pub mod watch;
 
 #[cfg(feature = "quantum")]
 pub mod quantum;
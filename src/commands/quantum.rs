@@ -3,9 +3,12 @@
 //! Now reads a tiny `.ai` file and executes: `qubits`, `h`, `x`, `cnot`.
 
 use anyhow::{anyhow, bail, Context, Result};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
-use crate::core::quantum_ir::{parse_ai_to_ir, OpKind};
+use crate::core::quantum_ir::{parse_ai_to_ir, Circuit, OpKind};
+use crate::core::run_bundle::{self, Histogram, RunBundle, RunManifest};
+use crate::core::state_inspect::StateView;
 use crate::core::titan::ops;
 use crate::core::titan::{
     gates,
@@ -14,23 +17,60 @@ use crate::core::titan::{
 
 use nalgebra::DMatrix;
 use num_complex::Complex64 as C64;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 #[cfg(feature = "qiskit")]
 use crate::core::titan::qiskit_bridge;
 
+/// `--transpile-level`, `--basis-gates` and `--fake-backend`, passed through to Qiskit's `transpile`.
+#[derive(Debug, Clone, Default)]
+pub struct TranspileOptions {
+    pub level: Option<u8>,
+    pub basis_gates: Vec<String>,
+    pub fake_backend: Option<String>,
+}
+
+impl TranspileOptions {
+    pub fn is_set(&self) -> bool {
+        self.level.is_some() || !self.basis_gates.is_empty() || self.fake_backend.is_some()
+    }
+}
+
+/// `--seed` and `--save-run`: reproducible sampling, and a bundle of the run on disk
+/// (see [`crate::core::run_bundle`]).
+#[derive(Debug, Clone, Default)]
+pub struct RecordOptions {
+    pub seed: Option<u64>,
+    pub save_run: Option<PathBuf>,
+}
+
+/// Shots sampled when `--save-run` is given without `--shots`.
+const DEFAULT_SAVED_SHOTS: usize = 1024;
+
 pub fn main(file: PathBuf, shots: Option<usize>, backend: &str) -> Result<()> {
-    quantum_run(file, backend, shots)
+    quantum_run(file, backend, shots, &TranspileOptions::default(), &RecordOptions::default())
 }
 
-pub fn quantum_run(file: PathBuf, backend: &str, shots: Option<usize>) -> Result<()> {
+/// Run `file` on `backend`. With transpile options the Aer path prints the histogram and
+/// the before/after transpilation statistics as JSON.
+pub fn quantum_run(file: PathBuf, backend: &str, shots: Option<usize>, transpile: &TranspileOptions, record: &RecordOptions) -> Result<()> {
     let be = backend.to_ascii_lowercase();
+    if transpile.is_set() && !matches!(be.as_str(), "aer" | "qiskit") {
+        bail!("--transpile-level, --basis-gates and --fake-backend need the aer backend");
+    }
+    if (record.seed.is_some() || record.save_run.is_some()) && be != "titan" {
+        bail!("--seed and --save-run need the titan backend");
+    }
 
     match be.as_str() {
-        "titan" => run_titan(file, shots),
+        "titan" => run_titan(file, shots, record),
         "aer" | "qiskit" => {
             #[cfg(feature = "qiskit")]
             {
+                if transpile.is_set() {
+                    return run_aer_transpiled(file, shots, transpile);
+                }
                 run_aer(file, shots)
             }
             #[cfg(not(feature = "qiskit"))]
@@ -53,14 +93,61 @@ pub fn quantum_run(file: PathBuf, backend: &str, shots: Option<usize>) -> Result
     }
 }
 
-fn run_titan(file: PathBuf, shots: Option<usize>) -> Result<()> {
+fn run_titan(file: PathBuf, shots: Option<usize>, record: &RecordOptions) -> Result<()> {
+    let started = Instant::now();
     let circ = parse_ai_to_ir(&file).context("parse .ai failed")?;
+    let psi = simulate(&circ)?;
 
-    // Start in |0..0>
-    let mut psi = QState::zeros(circ.n_qubits);
+    let Some(nshots) = shots.or(record.save_run.as_ref().map(|_| DEFAULT_SAVED_SHOTS)) else {
+        println!("Titan |ψ_out> amplitudes: {:?}", psi.data.as_slice());
+        return Ok(());
+    };
+    // An unseeded run still draws from a recorded seed, so a saved bundle can be replayed.
+    let seed = record.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let histogram = sample(&psi, circ.n_qubits, nshots, &mut StdRng::seed_from_u64(seed));
+    let shown: Vec<String> = histogram.iter().map(|(k, c)| format!("{k}: {c}")).collect();
+    println!("Titan shots => {}", shown.join(", "));
 
-    // Apply ops sequentially
-    for op in circ.ops {
+    if let Some(dir) = &record.save_run {
+        let source = std::fs::read_to_string(&file).with_context(|| format!("failed to read {}", file.display()))?;
+        let statevector = (circ.n_qubits <= run_bundle::MAX_STATEVECTOR_QUBITS).then(|| psi.data.iter().map(|a| [a.re, a.im]).collect());
+        let mut bundle = RunBundle {
+            manifest: RunManifest {
+                schema: run_bundle::SCHEMA,
+                compiler_version: env!("CARGO_PKG_VERSION").to_string(),
+                source_file: file.display().to_string(),
+                source_sha1: run_bundle::source_sha1(&source),
+                backend: "titan".into(),
+                qubits: circ.n_qubits,
+                shots: nshots,
+                seed,
+                noise: None,
+                wall_time_ms: started.elapsed().as_secs_f64() * 1000.0,
+                created_at: chrono::Utc::now().to_rfc3339(),
+                files: Vec::new(),
+            },
+            source,
+            circuit: serde_json::to_value(&circ)?,
+            histogram,
+            statevector,
+        };
+        bundle.write(dir)?;
+        println!("Saved run → {}", dir.display());
+    }
+    Ok(())
+}
+
+/// Simulate `file` on Titan and keep the final statevector, for the shell's `qstate`.
+pub fn titan_state(file: &Path) -> Result<StateView> {
+    let circ = parse_ai_to_ir(file).context("parse .ai failed")?;
+    let psi = simulate(&circ)?;
+    Ok(StateView { source: file.display().to_string(), qubits: circ.n_qubits, amplitudes: psi.data.iter().map(|a| [a.re, a.im]).collect() })
+}
+
+/// Apply the circuit's ops to |0..0⟩.
+fn simulate(circ: &Circuit) -> Result<QState> {
+    let mut psi = QState::ground(circ.n_qubits);
+    for op in &circ.ops {
         match op.kind {
             OpKind::H => {
                 let u = gates::h();
@@ -84,21 +171,7 @@ fn run_titan(file: PathBuf, shots: Option<usize>) -> Result<()> {
         }
     }
 
-    if let Some(nshots) = shots {
-        if circ.n_qubits == 1 {
-            let (c0, c1) = sample_1q(&psi, nshots);
-            println!("Titan shots => 0: {c0}, 1: {c1}");
-        } else {
-            println!(
-                "(Titan) shots currently supported for n=1 only; printing amplitudes instead."
-            );
-            println!("Titan |ψ_out> amplitudes: {:?}", psi.data.as_slice());
-        }
-    } else {
-        println!("Titan |ψ_out> amplitudes: {:?}", psi.data.as_slice());
-    }
-
-    Ok(())
+    Ok(psi)
 }
 
 #[cfg(feature = "qiskit")]
@@ -148,7 +221,7 @@ fn run_aer(file: PathBuf, shots: Option<usize>) -> Result<()> {
             // and then a CNOT via a tiny Python helper would be ideal, but our current
             // qiskit_bridge exposes only 1q unitary shots. For now: simulate on Titan
             // and sample locally to show balanced counts.
-            let mut psi = QState::zeros(2);
+            let mut psi = QState::ground(2);
             let h = expand_1q(&gates::h(), 2, 0);
             let qop_h = QOp::try_new_unitary(h).map_err(|e: String| anyhow!(e))?;
             psi = qop_h.apply(&psi).map_err(|e: String| anyhow!(e))?;
@@ -186,14 +259,23 @@ fn run_aer(file: PathBuf, shots: Option<usize>) -> Result<()> {
     bail!("Aer path currently exposes 1-qubit unitaries only (or Bell demo).");
 }
 
+#[cfg(feature = "qiskit")]
+fn run_aer_transpiled(file: PathBuf, shots: Option<usize>, opts: &TranspileOptions) -> Result<()> {
+    let circ = parse_ai_to_ir(&file).context("parse .ai failed")?;
+    let run = qiskit_bridge::run_circuit(&circ, shots.unwrap_or(2000), opts.level, &opts.basis_gates, opts.fake_backend.as_deref())?;
+    println!("{}", serde_json::to_string_pretty(&run)?);
+    Ok(())
+}
+
 /// Build an n-qubit unitary that applies `u` (2×2) on `target` and I elsewhere.
+/// Qubit `q` is bit `q` of the basis index, as in [`ops::cnot_n`].
 fn expand_1q(u: &DMatrix<C64>, n: usize, target: usize) -> DMatrix<C64> {
     debug_assert_eq!(u.nrows(), 2);
     debug_assert_eq!(u.ncols(), 2);
 
     let id2 = DMatrix::<C64>::from_diagonal_element(2, 2, C64::new(1.0, 0.0));
     let mut out = DMatrix::<C64>::from_diagonal_element(1, 1, C64::new(1.0, 0.0));
-    for q in 0..n {
+    for q in (0..n).rev() {
         out = kron(&out, if q == target { u } else { &id2 });
     }
     out
@@ -217,23 +299,23 @@ fn kron(a: &DMatrix<C64>, b: &DMatrix<C64>) -> DMatrix<C64> {
     m
 }
 
-/// Simple 1-qubit sampler (|ψ⟩ = a|0⟩ + b|1⟩ → counts for 0/1).
-fn sample_1q(psi: &QState, shots: usize) -> (usize, usize) {
-    let a0 = psi.data[0];
-    let a1 = psi.data[1];
-    let p1 = a1.norm_sqr();
-    let p0 = (1.0 - p1).max(0.0); // numerical safety
-
-    let mut c0 = 0usize;
-    let mut c1 = 0usize;
-    let mut rng = rand::thread_rng();
+/// Measure every qubit `shots` times; outcomes are bitstrings with qubit 0 first.
+fn sample(psi: &QState, n: usize, shots: usize, rng: &mut impl Rng) -> Histogram {
+    let cumulative: Vec<f64> = psi
+        .data
+        .iter()
+        .scan(0.0, |acc, a| {
+            *acc += a.norm_sqr();
+            Some(*acc)
+        })
+        .collect();
+    let total = cumulative.last().copied().unwrap_or(0.0);
+    let mut hist = Histogram::new();
     for _ in 0..shots {
-        let r: f64 = rng.gen();
-        if r < p0 {
-            c0 += 1;
-        } else {
-            c1 += 1;
-        }
+        let r = rng.gen::<f64>() * total;
+        let i = cumulative.partition_point(|&c| c <= r).min(cumulative.len() - 1);
+        let bits: String = (0..n).map(|q| if i >> q & 1 == 1 { '1' } else { '0' }).collect();
+        *hist.entry(bits).or_default() += 1;
     }
-    (c0, c1)
+    hist
 }
@@ -0,0 +1,66 @@
+//! `aeonmi quantum compare RUN_A RUN_B`: total variation distance between the histograms
+//! of two run bundles (see [`crate::core::run_bundle`]), with their provenance side by side.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::path::Path;
+
+use super::exit_codes::{fail, IO};
+use crate::core::run_bundle::{self, Histogram, RunBundle, RunManifest};
+use crate::core::style::{self, Role, Style};
+
+#[derive(Debug, Serialize)]
+pub struct Comparison {
+    pub a: RunManifest,
+    pub b: RunManifest,
+    pub total_variation: f64,
+    pub same_source: bool,
+    /// Outcome → (count in A, count in B), over the outcomes either run observed.
+    pub outcomes: Vec<(String, usize, usize)>,
+}
+
+pub fn compare(a: &RunBundle, b: &RunBundle) -> Comparison {
+    let count = |h: &Histogram, k: &str| h.get(k).copied().unwrap_or(0);
+    let keys: std::collections::BTreeSet<&String> = a.histogram.keys().chain(b.histogram.keys()).collect();
+    Comparison {
+        a: a.manifest.clone(),
+        b: b.manifest.clone(),
+        total_variation: run_bundle::total_variation(&a.histogram, &b.histogram),
+        same_source: a.manifest.source_sha1 == b.manifest.source_sha1,
+        outcomes: keys.into_iter().map(|k| (k.clone(), count(&a.histogram, k), count(&b.histogram, k))).collect(),
+    }
+}
+
+fn describe(m: &RunManifest) -> String {
+    let noise = m.noise.as_deref().unwrap_or("none");
+    format!("{}, {} qubits, {} shots, seed {}, noise {}, source {}", m.backend, m.qubits, m.shots, m.seed, noise, &m.source_sha1[..m.source_sha1.len().min(8)])
+}
+
+pub fn render(c: &Comparison, st: &Style) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("{} {}\n", st.paint("A:", Role::Section), describe(&c.a)));
+    out.push_str(&format!("{} {}\n", st.paint("B:", Role::Section), describe(&c.b)));
+    if !c.same_source {
+        out.push_str(&format!("{} the runs come from different sources\n", st.paint("warn:", Role::Warn)));
+    }
+    let width = c.outcomes.iter().map(|o| o.0.len()).max().unwrap_or(0).max("outcome".len());
+    out.push_str(&st.paint(&format!("{:<width$}  {:>8}  {:>8}", "outcome", "A", "B"), Role::Dim));
+    out.push('\n');
+    for (k, a, b) in &c.outcomes {
+        let pad = " ".repeat(width - k.len());
+        out.push_str(&format!("{}{pad}  {:>8}  {:>8}\n", st.paint(k, Role::Name), a, b));
+    }
+    out.push_str(&format!("{} {:.4}\n", st.paint("total variation distance:", Role::Strong), c.total_variation));
+    out
+}
+
+pub fn main(a: &Path, b: &Path, json: bool) -> Result<()> {
+    let load = |p: &Path| RunBundle::load(p).map_err(|e| fail(IO, format!("{e:#}")));
+    let c = compare(&load(a)?, &load(b)?);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&c)?);
+    } else {
+        print!("{}", render(&c, &style::current()));
+    }
+    Ok(())
+}
@@ -1,4 +1,290 @@
+//! `aeonmi repl`: read-eval-print over one interpreter session.
+//!
+//! Every entry runs at global scope ([`Interpreter::run_session`]), so the variables and
+//! functions of earlier entries stay visible; an entry that fails to parse or stops with a
+//! runtime error leaves them as they were. An entry with an unclosed `(`, `[`, `{`, string
+//! or block comment continues on the next line behind a `...` prompt. When an entry ends
+//! with an expression (`1 + 2`, `f(2);`), its value is echoed as `log` would print it,
+//! unless it is `null`.
+//!
+//! Entries are appended to `<config dir>/aeonmi/repl_history` as they are read, so the
+//! history survives the session; `:history` lists the most recent ones.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::commands::vm::split_session;
+use crate::core::imports;
+use crate::core::lexer::{Lexer, LexerOptions, Markers};
+use crate::core::parser::Parser;
+use crate::core::vm::{Interpreter, OutputBuffer, Value};
+use crate::core::vm_display::LOG_DEPTH;
+
+pub const PROMPT: &str = "ae> ";
+pub const CONTINUATION_PROMPT: &str = "... ";
+/// Entries kept in the history file; older ones are dropped when the REPL starts.
+pub const HISTORY_LIMIT: usize = 1000;
+
+const HELP: &str = "\
+:env            list the session's variables and functions
+:load <file.ai> run a file in the session (imports resolve relative to it)
+:reset          start over with an empty session
+:history [N]    show the last N entries (default 20)
+:cancel         drop a half-typed entry
+:help           this list
+:quit           leave (Ctrl-D works too)
+";
+
 pub fn main() -> anyhow::Result<()> {
-    println!("(repl) placeholder: starting REPL...");
+    let mut repl = Repl::new(Some(history_path()));
+    println!("Aeonmi REPL {} - :help for commands, Ctrl-D to exit", env!("CARGO_PKG_VERSION"));
+    repl.run(&mut io::stdin().lock(), &mut io::stdout())?;
     Ok(())
 }
+
+/// `$AEONMI_CONFIG_DIR/repl_history`, else `repl_history` in the user config dir.
+pub fn history_path() -> PathBuf {
+    if let Ok(base) = std::env::var("AEONMI_CONFIG_DIR") {
+        return PathBuf::from(base).join("repl_history");
+    }
+    dirs_next::config_dir().unwrap_or(std::env::temp_dir()).join("aeonmi").join("repl_history")
+}
+
+/// What a complete entry did.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reply {
+    /// Program output or a meta-command's listing, possibly empty.
+    Output(String),
+    /// The entry was not run, or stopped part way; `output` is what it printed first.
+    Error { output: String, message: String },
+    Quit,
+}
+
+pub struct Repl {
+    interp: Interpreter,
+    history: Vec<String>,
+    history_file: Option<PathBuf>,
+}
+
+impl Repl {
+    /// A fresh session; with a `history_file`, earlier entries are loaded from it and new
+    /// ones appended to it.
+    pub fn new(history_file: Option<PathBuf>) -> Self {
+        let history = history_file.as_deref().map(load_history).unwrap_or_default();
+        if let Some(path) = &history_file {
+            // Trim a file that grew past the limit; the session keeps appending after that.
+            if fs::read_to_string(path).is_ok_and(|txt| txt.lines().count() > history.len()) {
+                let _ = save_history(path, &history);
+            }
+        }
+        Repl { interp: Interpreter::new(), history, history_file }
+    }
+
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// Prompt, read and evaluate entries from `input` until `:quit` or end of input.
+    pub fn run(&mut self, input: &mut dyn BufRead, out: &mut dyn Write) -> io::Result<()> {
+        let mut entry = String::new();
+        loop {
+            write!(out, "{}", if entry.is_empty() { PROMPT } else { CONTINUATION_PROMPT })?;
+            out.flush()?;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                writeln!(out)?;
+                return Ok(());
+            }
+            let line = line.trim_end_matches(['\n', '\r']);
+            if !entry.is_empty() && line.trim() == ":cancel" {
+                entry.clear();
+                continue;
+            }
+            if entry.is_empty() && line.trim().is_empty() {
+                continue;
+            }
+            entry.push_str(line);
+            entry.push('\n');
+            if is_incomplete(&entry) {
+                continue;
+            }
+            let text = std::mem::take(&mut entry);
+            self.record(text.trim_end());
+            match self.eval(&text) {
+                Reply::Output(s) => write!(out, "{s}")?,
+                Reply::Error { output, message } => writeln!(out, "{output}error: {message}")?,
+                Reply::Quit => return Ok(()),
+            }
+        }
+    }
+
+    /// Evaluate one complete entry: a `:command` or source to run in the session.
+    pub fn eval(&mut self, entry: &str) -> Reply {
+        let trimmed = entry.trim();
+        let Some(command) = trimmed.strip_prefix(':') else {
+            let file = std::env::current_dir().unwrap_or_default().join("<repl>");
+            return self.run_source(entry, &file);
+        };
+        let (name, arg) = command.split_once(char::is_whitespace).map_or((command, ""), |(n, a)| (n, a.trim()));
+        match name {
+            "quit" | "q" | "exit" => Reply::Quit,
+            "help" => Reply::Output(HELP.to_string()),
+            "reset" => {
+                self.interp = Interpreter::new();
+                Reply::Output("(session reset)\n".into())
+            }
+            "env" => Reply::Output(self.env_listing()),
+            "load" if arg.is_empty() => error(":load needs a file"),
+            "load" => {
+                let path = Path::new(arg);
+                match fs::read_to_string(path) {
+                    Ok(source) => self.run_source(&source, path),
+                    Err(e) => error(format!("reading {arg}: {e}")),
+                }
+            }
+            "history" => {
+                let n = if arg.is_empty() { Ok(20) } else { arg.parse::<usize>() };
+                let Ok(n) = n else { return error(format!(":history takes a count, not `{arg}`")) };
+                let start = self.history.len().saturating_sub(n);
+                let mut s = String::new();
+                for (i, e) in self.history.iter().enumerate().skip(start) {
+                    s.push_str(&format!("{:>4}  {}\n", i + 1, e.replace('\n', "\n      ")));
+                }
+                Reply::Output(s)
+            }
+            _ => error(format!("unknown command :{name} (try :help)")),
+        }
+    }
+
+    /// `name = value` per global, functions as `name(params)`.
+    fn env_listing(&self) -> String {
+        let globals = self.interp.globals();
+        if globals.is_empty() {
+            return "(empty)\n".into();
+        }
+        let mut s = String::new();
+        for (name, value) in globals {
+            match value {
+                Value::Function(f) => s.push_str(&format!("{name}({})\n", f.params.join(", "))),
+                v => s.push_str(&format!("{name} = {}\n", v.display_pretty(LOG_DEPTH))),
+            }
+        }
+        s
+    }
+
+    /// Parse `source` (resolving its imports against `file`) and run it at global scope.
+    fn run_source(&mut self, source: &str, file: &Path) -> Reply {
+        let options = LexerOptions::default();
+        let lowered = Lexer::with_options(source, options.clone())
+            .tokenize()
+            .map_err(|e| format!("lexing error: {e}"))
+            .and_then(|tokens| Parser::new(tokens).parse().map_err(|e| format!("parsing error: {e}")))
+            .and_then(|program| imports::resolve(program, file, source, &options).map_err(|e| e.message()))
+            .and_then(|flat| split_session(flat.program));
+        let (m, top) = match lowered {
+            Ok(parts) => parts,
+            Err(message) => return Reply::Error { output: String::new(), message },
+        };
+        let buf = OutputBuffer::new();
+        self.interp.set_output(Box::new(buf.clone()));
+        let result = self.interp.run_session(&m, &top);
+        self.interp.set_output(Box::new(io::sink()));
+        match result {
+            Ok(Value::Null) => Reply::Output(buf.contents()),
+            Ok(value) => Reply::Output(format!("{}{}\n", buf.contents(), value.display_pretty(LOG_DEPTH))),
+            Err(e) => Reply::Error { output: buf.contents(), message: format!("runtime error: {}", e.message) },
+        }
+    }
+
+    fn record(&mut self, entry: &str) {
+        if self.history.last().map(String::as_str) == Some(entry) {
+            return;
+        }
+        self.history.push(entry.to_string());
+        if let Some(path) = &self.history_file {
+            // History is a convenience: a read-only config dir must not stop the session.
+            let _ = append_history(path, entry);
+        }
+    }
+}
+
+fn error(message: impl Into<String>) -> Reply {
+    Reply::Error { output: String::new(), message: message.into() }
+}
+
+/// Whether `src` ends inside a string or block comment, or with more `(`, `[` or `{`
+/// opened than closed. Extra closers count as complete, so the parser reports them.
+pub fn is_incomplete(src: &str) -> bool {
+    let markers = Markers::default();
+    let mut depth = 0i64;
+    let mut chars = src.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => loop {
+                match chars.next() {
+                    None => return true,
+                    Some('\\') => {
+                        chars.next();
+                    }
+                    Some('"') => break,
+                    Some(_) => {}
+                }
+            },
+            '/' if chars.peek() == Some(&'/') => {
+                chars.by_ref().find(|&c| c == '\n');
+            }
+            c if c == markers.line_comment => {
+                chars.by_ref().find(|&c| c == '\n');
+            }
+            // A closed comment is skipped by the guard itself.
+            c if c == markers.block_comment_start && !chars.by_ref().any(|c| c == markers.block_comment_end) => return true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    depth > 0
+}
+
+/// One entry per line, with `\` and newlines escaped so multi-line entries stay whole.
+fn escape(entry: &str) -> String {
+    entry.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// The last [`HISTORY_LIMIT`] entries in `path`; a missing file is an empty history.
+pub fn load_history(path: &Path) -> Vec<String> {
+    let txt = fs::read_to_string(path).unwrap_or_default();
+    let entries: Vec<String> = txt.lines().filter(|l| !l.is_empty()).map(unescape).collect();
+    entries[entries.len().saturating_sub(HISTORY_LIMIT)..].to_vec()
+}
+
+fn save_history(path: &Path, entries: &[String]) -> io::Result<()> {
+    let txt: String = entries.iter().map(|e| escape(e) + "\n").collect();
+    fs::write(path, txt)
+}
+
+fn append_history(path: &Path, entry: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut f = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(f, "{}", escape(entry))
+}
@@ -1,16 +1,21 @@
-use colored::Colorize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use super::compile::compile_pipeline;
-use crate::cli::EmitKind;
+use super::build;
 
 // Native interpreter pieces
-use crate::core::lexer::Lexer;
-use crate::core::parser::{Parser as AeParser, ParserError};
+use crate::config::LexerSettings;
+use crate::core::lexer::{Lexer, LexerOptions};
+use crate::core::parser::Parser as AeParser;
 use crate::core::lowering::lower_ast_to_ir;
-use crate::core::vm::Interpreter;
-use crate::core::diagnostics::{print_error, emit_json_error, Span};
-use crate::core::lexer::LexerError;
+use crate::core::vm::{exit_code, Interpreter, Limits, LogEvent, OutputBuffer, RuntimeError};
+use crate::core::vm_trace::{self, Trace};
+use crate::core::diagnostics::{quiet, report_error, report_parse_error, Span};
+use crate::core::error_index;
+use crate::core::imports;
+use crate::core::style::{self, Role};
+use crate::core::toolchain::{self, Tool};
+
+use super::exit_codes::{self, reported};
 
 /// Public native interpreter entry (no JS emission)
 pub fn run_native(
@@ -18,120 +23,243 @@ pub fn run_native(
     pretty: bool,
     no_sema: bool,
 ) -> anyhow::Result<()> {
+    run_native_traced(input, pretty, no_sema, None, None, Limits::default())
+}
+
+/// Native run that records nondeterministic builtin results to `record`, or
+/// replays them from `replay` (see `vm_trace`). At most one should be set.
+/// `limits` apply to the program (`run --max-memory-mb`).
+pub fn run_native_traced(
+    input: &PathBuf,
+    pretty: bool,
+    no_sema: bool,
+    record: Option<&Path>,
+    replay: Option<&Path>,
+    limits: Limits,
+) -> anyhow::Result<()> {
+    let trace = match replay {
+        Some(path) => match vm_trace::load(path) {
+            Ok(entries) => Some(Trace::replaying(entries)),
+            Err(e) => return Err(exit_codes::fail(exit_codes::IO, format!("cannot read trace {}", e))),
+        },
+        None => record.map(|_| Trace::recording()),
+    };
     let source = std::fs::read_to_string(input)?;
+    let lexer = LexerSettings::discover(input)
+        .map_err(|e| exit_codes::fail(exit_codes::USAGE, format!("{:#}", e)))?
+        .lexer_options();
+    run_source_with(&input.display().to_string(), &source, lexer, pretty, no_sema, trace, record, limits)
+}
+
+/// Run in-memory `source` on the native VM with output on stdout; `name` labels diagnostics.
+pub fn run_source(name: &str, source: &str, pretty: bool, no_sema: bool) -> anyhow::Result<()> {
+    run_source_with(name, source, LexerOptions::default(), pretty, no_sema, None, None, Limits::default())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_source_with(
+    name: &str,
+    source: &str,
+    lexer_options: LexerOptions,
+    pretty: bool,
+    no_sema: bool,
+    trace: Option<Trace>,
+    record: Option<&Path>,
+    limits: Limits,
+) -> anyhow::Result<()> {
     // Lex
-    let mut lexer = Lexer::from_str(&source);
+    let mut lexer = Lexer::with_options(source, lexer_options.clone());
     let tokens = match lexer.tokenize() {
         Ok(t) => t,
         Err(e) => {
-            if pretty {
-                match e {
-                    LexerError::UnexpectedCharacter(_, line, col)
-                    | LexerError::UnterminatedString(line, col)
-                    | LexerError::InvalidNumber(_, line, col)
-                    | LexerError::InvalidQubitLiteral(_, line, col)
-                    | LexerError::UnterminatedComment(line, col) => {
-                        emit_json_error(
-                            &input.display().to_string(),
-                            &format!("{}", e),
-                            &Span::single(line, col),
-                        );
-                        print_error(
-                            &input.display().to_string(),
-                            &source,
-                            &format!("{}", e),
-                            Span::single(line, col),
-                        );
-                    }
-                    _ => eprintln!("{} Lexing error: {}", "error:".bright_red(), e),
-                }
-            } else {
-                eprintln!("{} Lexing error: {}", "error:".bright_red(), e);
-            }
-            return Ok(()); // mimic compile path exit(1) without aborting process
+            let (line, col) = e.position();
+            report_error(
+                name,
+                source,
+                &format!("Lexing error: {}", e),
+                Span::single(line, col),
+                Some(error_index::LEX_ERROR),
+                pretty,
+            );
+            return Err(reported(exit_codes::COMPILE_DIAG, "lexing failed"));
         }
     };
     // Parse
     let mut parser = AeParser::new(tokens.clone());
     let ast = match parser.parse() {
         Ok(a) => a,
-        Err(ParserError { message, line, column }) => {
-            if pretty {
-                emit_json_error(
-                    &input.display().to_string(),
-                    &format!("Parsing error: {}", message),
-                    &Span::single(line, column),
-                );
-                print_error(
-                    &input.display().to_string(),
-                    &source,
-                    &format!("Parsing error: {}", message),
-                    Span::single(line, column),
-                );
-            } else {
-                eprintln!("{} Parsing error: {}", "error:".bright_red(), message);
-            }
-            return Ok(());
+        Err(e) => {
+            report_parse_error(name, source, &e, pretty);
+            return Err(reported(exit_codes::COMPILE_DIAG, "parsing failed"));
         }
     };
-    if no_sema {
-        println!("note: semantic analysis skipped (native)");
+    // Imports resolve against `name`'s directory (the working directory for in-memory sources)
+    let ast = match imports::resolve(ast, Path::new(name), source, &lexer_options) {
+        Ok(f) => f.program,
+        Err(e) => {
+            e.report(pretty);
+            return Err(reported(exit_codes::COMPILE_DIAG, "import failed"));
+        }
+    };
+    if no_sema && !quiet() {
+        eprintln!("note: semantic analysis skipped (native)");
     }
     // Lower & interpret
-    println!("DEBUG: RUN PATH - native: executing '{}' via Aeonmi VM", input.display());
-    match lower_ast_to_ir(&ast, "main") {
-        Ok(module) => {
-            let mut interp = Interpreter::new();
-            if let Err(e) = interp.run_module(&module) {
-                eprintln!("{} runtime error: {}", "error:".bright_red(), e.message);
+    debug_log!("native: executing '{}' via Aeonmi VM", name);
+    let module = match lower_ast_to_ir(&ast, "main") {
+        Ok(m) => m,
+        Err(e) => {
+            if !quiet() {
+                eprintln!("{} lowering error: {}", style::current().paint("error:", Role::Error), e);
             }
+            return Err(reported(exit_codes::COMPILE_DIAG, "lowering failed"));
         }
-        Err(e) => eprintln!("{} lowering error: {}", "error:".bright_red(), e),
+    };
+    let mut interp = Interpreter::new();
+    interp.set_limits(limits);
+    if let Some(t) = trace {
+        interp.set_trace(t);
     }
-    Ok(())
+    let result = interp.run_main(&module);
+    let trace = interp.take_trace();
+    // A partial recording still helps reproduce the failure, so save it either way.
+    if let (Some(path), Some(t)) = (record, &trace) {
+        vm_trace::save(path, t.entries())
+            .map_err(|e| exit_codes::fail(exit_codes::IO, format!("cannot write trace {}: {}", path.display(), e)))?;
+    }
+    let result = result.and_then(|v| match &trace {
+        Some(t @ Trace::Replay { .. }) => t.finish().map(|_| v).map_err(|d| RuntimeError::new(d.to_string())),
+        _ => Ok(v),
+    });
+    match result {
+        Err(e) => {
+            if !quiet() {
+                eprintln!("{} runtime error: {}", style::current().paint("error:", Role::Error), e.message);
+            }
+            Err(reported(exit_codes::RUNTIME, format!("runtime error: {}", e.message)))
+        }
+        Ok(v) => program_exit(exit_code(&v)),
+    }
+}
+
+/// `Ok` for 0, otherwise the code `main` asked the process to exit with.
+fn program_exit(code: i32) -> anyhow::Result<()> {
+    if code == exit_codes::OK {
+        return Ok(());
+    }
+    Err(reported(code, format!("program exited with code {}", code)))
+}
+
+/// Captured result of an in-memory native run (output text plus structured log events).
+#[derive(Debug, Clone, Default)]
+pub struct CapturedRun {
+    pub output: String,
+    pub events: Vec<LogEvent>,
+    /// What `aeonmi run` would exit with, from the value `main` returned.
+    pub exit_code: i32,
+}
+
+/// Run `source` on the native VM without touching process stdout or stdin
+/// (`read_line` / `read_all` see an empty input). Errors are returned as a message prefixed with the failing stage.
+pub fn run_source_captured(source: &str) -> Result<CapturedRun, String> {
+    let tokens = Lexer::from_str(source)
+        .tokenize()
+        .map_err(|e| format!("lexing error: {}", e))?;
+    let ast = AeParser::new(tokens)
+        .parse()
+        .map_err(|e| format!("parsing error: {}", e))?;
+    let module = lower_ast_to_ir(&ast, "main").map_err(|e| format!("lowering error: {}", e))?;
+    let buf = OutputBuffer::new();
+    let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut interp = Interpreter::new();
+    interp.set_output(Box::new(buf.clone()));
+    interp.set_input(Box::new(std::io::empty()));
+    let sink = events.clone();
+    interp.on_log(move |ev| sink.lock().unwrap().push(ev.clone()));
+    let returned = interp
+        .run_main(&module)
+        .map_err(|e| format!("runtime error: {}", e.message))?;
+    let events = std::mem::take(&mut *events.lock().unwrap());
+    Ok(CapturedRun { output: buf.contents(), events, exit_code: exit_code(&returned) })
+}
+
+/// How `run` / `exec` execute an `.ai` program. Passed explicitly rather than through
+/// the environment, so one run's choice never leaks into the next (the shell runs many).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExecutionMode {
+    /// Compile to JS and run with Node, or the native VM when Node is missing.
+    #[default]
+    Auto,
+    /// Always the native VM.
+    Native,
 }
 
+impl ExecutionMode {
+    /// `Native` if `native` is set, else the mode the process started in.
+    pub fn resolve(native: bool) -> Self {
+        if native { ExecutionMode::Native } else { Self::startup() }
+    }
+
+    /// `Native` when the process was started with `AEONMI_NATIVE=1`. The variable is read
+    /// once and cached; later changes to it are ignored.
+    pub fn startup() -> Self {
+        static STARTUP: std::sync::OnceLock<ExecutionMode> = std::sync::OnceLock::new();
+        *STARTUP.get_or_init(|| match std::env::var("AEONMI_NATIVE").ok().as_deref() {
+            Some("1") => ExecutionMode::Native,
+            _ => ExecutionMode::Auto,
+        })
+    }
+}
+
+/// Loads the compiled file under Node so an uncaught exception exits with
+/// [`exit_codes::RUNTIME`] (as on the native VM) rather than Node's 1, which a program
+/// returning `false` also produces.
+const NODE_ENTRY: &str = "process.on(\"uncaughtException\", (e) => { console.error(e); process.exit(4); }); require(require(\"path\").resolve(process.argv[1]));";
+
 pub fn main_with_opts(
     input: PathBuf,
     out: Option<PathBuf>,
     pretty: bool,
     no_sema: bool,
+    mode: ExecutionMode,
+    source_map: bool,
 ) -> anyhow::Result<()> {
-    // Force native interpreter path if env requests or if node missing
-    let force_native = std::env::var("AEONMI_NATIVE").ok().as_deref() == Some("1");
-    let node_available = std::process::Command::new("node")
-        .arg("--version")
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false);
+    // Native when asked for, or when node is missing
+    let force_native = mode == ExecutionMode::Native;
+    let node_available = !force_native && toolchain::available(Tool::Node);
 
     if force_native || !node_available {
-        if !node_available && !force_native {
-            println!("(node not found — falling back to native interpreter)");
+        if !force_native && !quiet() {
+            eprintln!("{}", toolchain::NODE_FALLBACK_NOTICE);
         }
         return run_native(&input, pretty, no_sema);
     }
 
     let out_path = out.unwrap_or_else(|| PathBuf::from("aeonmi.run.js"));
-    compile_pipeline(
-        Some(input.clone()),
-        EmitKind::Js,
-        out_path.clone(),
-        false,
-        false,
-        pretty,
-        no_sema,
-        false,
-    )?;
-    match std::process::Command::new("node").arg(&out_path).status() {
-        Ok(status) if !status.success() => eprintln!(
-            "{} JS runtime exited with status: {}",
-            "warn:".yellow().bold(),
-            status
-        ),
+    // Reused while its dependency manifest still matches the sources
+    build::ensure(&input, &out_path, pretty, no_sema, source_map)?;
+    let mut node = std::process::Command::new("node");
+    if source_map {
+        node.arg("--enable-source-maps");
+    }
+    match node.args(["-e", NODE_ENTRY]).arg(&out_path).status() {
+        // The program's own exit code, from `main`'s return value
+        Ok(status) if status.code().is_some_and(|c| c != exit_codes::OK) => {
+            return program_exit(status.code().unwrap_or(exit_codes::RUNTIME));
+        }
+        Ok(status) if !status.success() => {
+            if !quiet() {
+                eprintln!(
+                    "{} JS runtime exited with status: {}",
+                    style::current().paint("warn:", Role::Warn),
+                    status
+                );
+            }
+            return Err(reported(exit_codes::RUNTIME, format!("JS runtime exited with status: {}", status)));
+        }
         Err(err) => eprintln!(
             "{} Could not launch Node.js: {} (compiled output is at '{}')",
-            "warn:".yellow().bold(),
+            style::current().paint("warn:", Role::Warn),
             err,
             out_path.display()
         ),
@@ -0,0 +1,205 @@
+//! Vault entries injected as environment variables into child processes
+//! (`exec`, `cargo`, `python`, `node`).
+//!
+//! Values only ever reach the child through `Command::envs`; the parent environment is never
+//! touched. Selection comes from `--with-secrets a,b` or, without it, every `VAR = "entry"`
+//! pair of the manifest's `[secrets]` table. Each injection is audit-logged in the vault by
+//! variable and entry name (never the value), and errors that bubble up past the child are
+//! scrubbed of the values before they are printed. Native `.ai` runs execute inside this
+//! process, so they get no secrets.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::Result;
+use serde_json::json;
+
+use crate::commands::exit_codes::{self, fail, ExitError};
+use crate::config::SecretSettings;
+use crate::vault::DomainQuantumVault;
+
+/// One environment variable to fill from one vault entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretSpec {
+    pub var: String,
+    pub entry: String,
+}
+
+/// Secret flags pulled out of trailing passthrough arguments.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SecretFlags {
+    pub with_secrets: Option<String>,
+    pub sandbox: bool,
+}
+
+impl SecretFlags {
+    /// Remove `--with-secrets LIST` / `--with-secrets=LIST` (and `--sandbox` when `sandbox_flag`)
+    /// from `args`, leaving everything else for the child in order.
+    pub fn extract(args: &mut Vec<String>, sandbox_flag: bool) -> Self {
+        let mut flags = Self::default();
+        let mut kept = Vec::with_capacity(args.len());
+        let mut it = std::mem::take(args).into_iter();
+        while let Some(a) = it.next() {
+            if a == "--with-secrets" {
+                flags.with_secrets = it.next();
+            } else if let Some(list) = a.strip_prefix("--with-secrets=") {
+                flags.with_secrets = Some(list.to_string());
+            } else if sandbox_flag && a == "--sandbox" {
+                flags.sandbox = true;
+            } else {
+                kept.push(a);
+            }
+        }
+        *args = kept;
+        flags
+    }
+}
+
+/// Parse `--with-secrets`: comma-separated `VAR=entry` pairs or bare names. A bare name picks the
+/// manifest variable of that name, else the vault entry of that name as an upper-cased variable
+/// (`api.example.com` → `API_EXAMPLE_COM`).
+pub fn select(list: &str, manifest: &BTreeMap<String, String>) -> Result<Vec<SecretSpec>, String> {
+    let mut specs = Vec::new();
+    for item in list.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let spec = match item.split_once('=') {
+            Some((var, entry)) => SecretSpec { var: var.trim().to_string(), entry: entry.trim().to_string() },
+            None => match manifest.get(item) {
+                Some(entry) => SecretSpec { var: item.to_string(), entry: entry.clone() },
+                None => SecretSpec { var: env_name(item), entry: item.to_string() },
+            },
+        };
+        if !valid_var(&spec.var) {
+            return Err(format!("invalid environment variable name '{}'", spec.var));
+        }
+        if spec.entry.is_empty() {
+            return Err(format!("no vault entry given for {}", spec.var));
+        }
+        specs.retain(|s: &SecretSpec| s.var != spec.var);
+        specs.push(spec);
+    }
+    if specs.is_empty() {
+        return Err("--with-secrets needs at least one name".into());
+    }
+    Ok(specs)
+}
+
+fn env_name(entry: &str) -> String {
+    entry
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect()
+}
+
+fn valid_var(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Resolved values, ready to hand to child commands.
+#[derive(Default)]
+pub struct SecretEnv {
+    vars: Vec<(String, String)>,
+}
+
+impl std::fmt::Debug for SecretEnv {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<&str> = self.vars.iter().map(|(k, _)| k.as_str()).collect();
+        f.debug_struct("SecretEnv").field("vars", &names).finish()
+    }
+}
+
+impl SecretEnv {
+    /// Decrypt each entry from `vault` and record one `secret_inject` audit entry per variable.
+    /// JSON string secrets are injected as-is; any other JSON value as its compact text.
+    pub fn resolve(specs: &[SecretSpec], vault: &mut DomainQuantumVault, command: &str) -> Result<Self> {
+        let mut vars = Vec::with_capacity(specs.len());
+        for spec in specs {
+            let value = vault
+                .retrieve_secret(&spec.entry)
+                .map_err(|e| fail(exit_codes::USAGE, format!("secret '{}' for {}: {e}", spec.entry, spec.var)))?;
+            let value = match value {
+                serde_json::Value::String(s) => s,
+                other => other.to_string(),
+            };
+            vault.audit("secret_inject", json!({ "var": spec.var, "entry": spec.entry, "command": command }))?;
+            vars.push((spec.var.clone(), value));
+        }
+        Ok(Self { vars })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
+    /// Add the variables to `cmd`'s environment only.
+    pub fn apply<'c>(&self, cmd: &'c mut Command) -> &'c mut Command {
+        cmd.envs(self.vars.iter().map(|(k, v)| (k, v)))
+    }
+
+    /// `text` with every secret value replaced by `***`.
+    pub fn scrub(&self, text: &str) -> String {
+        let mut out = text.to_string();
+        for (_, v) in &self.vars {
+            if !v.is_empty() {
+                out = out.replace(v.as_str(), "***");
+            }
+        }
+        out
+    }
+
+    /// [`scrub`](Self::scrub) an error's message, keeping its exit code.
+    pub fn scrub_error(&self, err: anyhow::Error) -> anyhow::Error {
+        if self.is_empty() {
+            return err;
+        }
+        let code = exit_codes::code_for(&err);
+        let reported = exit_codes::is_reported(&err);
+        let message = match err.downcast_ref::<ExitError>() {
+            Some(e) => self.scrub(&e.message),
+            None => self.scrub(&format!("{err:#}")),
+        };
+        anyhow::Error::new(ExitError { code, message, reported })
+    }
+
+    /// Replace the panic hook with one that prints the panic scrubbed of secret values,
+    /// so a crash while children run cannot echo them into a terminal or log.
+    pub fn guard_panics(&self) {
+        if self.is_empty() {
+            return;
+        }
+        let values: Vec<String> = self.vars.iter().map(|(_, v)| v.clone()).filter(|v| !v.is_empty()).collect();
+        std::panic::set_hook(Box::new(move |info| {
+            let mut msg = info.to_string();
+            for v in &values {
+                msg = msg.replace(v.as_str(), "***");
+            }
+            eprintln!("{msg}");
+        }));
+    }
+}
+
+/// Everything a command needs before spawning children: pick the specs (flag or manifest near
+/// `near`), refuse under `--sandbox`, then open the default vault and resolve them.
+pub fn prepare(with_secrets: Option<&str>, near: &Path, sandbox: bool, command: &str) -> Result<SecretEnv> {
+    let manifest = SecretSettings::discover(near).map_err(|e| fail(exit_codes::USAGE, format!("{e:#}")))?;
+    let specs = match with_secrets {
+        Some(list) => select(list, &manifest.vars).map_err(|e| fail(exit_codes::USAGE, e))?,
+        None => manifest
+            .vars
+            .iter()
+            .map(|(var, entry)| SecretSpec { var: var.clone(), entry: entry.clone() })
+            .collect(),
+    };
+    if specs.is_empty() {
+        return Ok(SecretEnv::default());
+    }
+    if sandbox {
+        return Err(fail(exit_codes::USAGE, "refusing to inject secrets under --sandbox"));
+    }
+    let mut vault = DomainQuantumVault::open_default()?;
+    let env = SecretEnv::resolve(&specs, &mut vault, command)?;
+    env.guard_panics();
+    Ok(env)
+}
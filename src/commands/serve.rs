@@ -0,0 +1,332 @@
+//! `aeonmi serve`: a small HTTP/JSON API so a web playground can compile and run
+//! snippets without shelling out per request.
+//!
+//! * `GET /health` → `{"status":"ok"}`
+//! * `POST /compile` `{"source", "emit": "js"|"ai"}` → `{"ok", "output", "diagnostics"}`
+//! * `POST /run` `{"source"}` → `{"ok", "output", "error", "elapsed_ms"}`
+//!
+//! Each run gets a fresh interpreter with empty stdin, a wall-clock deadline, a call depth
+//! limit and caps on string size, live value memory and captured output. `--sandbox` also disables `env()`,
+//! the only builtin that reads the host. The server speaks just enough HTTP/1.1 for JSON
+//! clients: one request per connection, bodies sized by `Content-Length`.
+//!
+//! Ctrl-C stops accepting, lets open connections finish, flushes metrics and prints how
+//! many requests were served; a second Ctrl-C exits immediately.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value as Json};
+
+use crate::cli::EmitKind;
+use crate::core::lexer::Lexer;
+use crate::core::lowering::lower_ast_to_ir;
+use crate::core::parser::Parser;
+use crate::core::preview::emit_preview;
+use crate::core::shutdown::ShutdownToken;
+use crate::core::vm::{Interpreter, Limits, OutputBuffer};
+
+/// Request line plus headers; anything longer is rejected before the body is read.
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+/// Stack for run threads: deep (but limited) Aeonmi recursion nests many Rust frames.
+const RUN_STACK_BYTES: usize = 64 * 1024 * 1024;
+const MAX_CALL_DEPTH: usize = 512;
+/// How often the accept loop checks for a shutdown request.
+const ACCEPT_POLL: Duration = Duration::from_millis(50);
+/// Connections open at once beyond `concurrency`: health checks and requests still being
+/// read. Each connection is a thread, so past this a new one is answered 503 on the spot.
+const CONNECTION_HEADROOM: usize = 16;
+
+#[derive(Debug, Clone)]
+pub struct ServeOptions {
+    pub host: String,
+    /// 0 picks a free port; the chosen address is printed on the first stdout line.
+    pub port: u16,
+    pub sandbox: bool,
+    pub timeout: Duration,
+    pub max_body: usize,
+    pub max_output: usize,
+    pub max_string: usize,
+    /// Approximate bytes a run's live values may hold.
+    pub max_memory: usize,
+    /// Requests handled at once; more get 503 until a slot frees up.
+    pub concurrency: usize,
+}
+
+impl Default for ServeOptions {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".into(),
+            port: 8080,
+            sandbox: false,
+            timeout: Duration::from_millis(2000),
+            max_body: 64 * 1024,
+            max_output: 64 * 1024,
+            max_string: 1024 * 1024,
+            max_memory: 64 * 1024 * 1024,
+            concurrency: 4,
+        }
+    }
+}
+
+pub fn main(opts: ServeOptions) -> Result<()> {
+    let listener = TcpListener::bind((opts.host.as_str(), opts.port))
+        .with_context(|| format!("bind {}:{}", opts.host, opts.port))?;
+    println!("listening on http://{}", listener.local_addr()?);
+    std::io::stdout().flush().ok();
+    let shutdown = crate::core::shutdown::listen();
+    let served = serve(listener, opts, shutdown.token())?;
+    crate::core::incremental::force_persist_metrics();
+    println!("[serve] stopped after {served} request{}", if served == 1 { "" } else { "s" });
+    Ok(())
+}
+
+/// Accept connections on `listener` until `stop` is raised; each is handled on its own
+/// thread, up to `concurrency` + [`CONNECTION_HEADROOM`] at once. Once stopped, requests
+/// still running get up to the run timeout (plus a second) to answer. Returns how many
+/// connections were accepted.
+pub fn serve(listener: TcpListener, opts: ServeOptions, stop: &ShutdownToken) -> Result<usize> {
+    let opts = Arc::new(opts);
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let open = Arc::new(AtomicUsize::new(0));
+    let max_open = opts.concurrency + CONNECTION_HEADROOM;
+    // Non-blocking accepts so the stop flag is seen between connections.
+    listener.set_nonblocking(true).context("set listener non-blocking")?;
+    let mut served = 0;
+    while !stop.is_requested() {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let _ = stream.set_nonblocking(false);
+                served += 1;
+                // Taken before spawning, so idle clients cannot pile up threads
+                let Some(conn) = Slot::acquire(&open, max_open) else {
+                    let _ = stream.set_write_timeout(Some(Duration::from_secs(1)));
+                    let _ = write_response(&stream, 503, &json!({ "error": "too many open connections, retry later" }));
+                    continue;
+                };
+                let (opts, in_flight) = (opts.clone(), in_flight.clone());
+                std::thread::spawn(move || {
+                    handle(stream, &opts, &in_flight);
+                    drop(conn);
+                });
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => std::thread::sleep(ACCEPT_POLL),
+            Err(_) => continue,
+        }
+    }
+    drop(listener);
+    let deadline = Instant::now() + opts.timeout + Duration::from_secs(1);
+    while open.load(Ordering::SeqCst) > 0 && Instant::now() < deadline {
+        std::thread::sleep(ACCEPT_POLL);
+    }
+    Ok(served)
+}
+
+/// A held concurrency (or connection) slot, released when the last clone drops (a
+/// timed-out run keeps its slot until the interpreter actually stops).
+struct Slot(Arc<AtomicUsize>);
+
+impl Slot {
+    fn acquire(in_flight: &Arc<AtomicUsize>, limit: usize) -> Option<Arc<Slot>> {
+        let prev = in_flight.fetch_add(1, Ordering::SeqCst);
+        let slot = Arc::new(Slot(in_flight.clone()));
+        (prev < limit).then_some(slot)
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+fn handle(stream: TcpStream, opts: &ServeOptions, in_flight: &Arc<AtomicUsize>) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let (status, body) = match read_request(&stream, opts.max_body) {
+        Err((status, msg)) => (status, json!({ "error": msg })),
+        Ok(req) if req.method == "GET" && req.path == "/health" => (200, json!({ "status": "ok" })),
+        Ok(req) => match Slot::acquire(in_flight, opts.concurrency) {
+            None => (503, json!({ "error": "server busy, retry later" })),
+            Some(slot) => route(&req, opts, slot),
+        },
+    };
+    let _ = write_response(&stream, status, &body);
+}
+
+fn read_request(stream: &TcpStream, max_body: usize) -> Result<Request, (u16, String)> {
+    let bad = |msg: &str| (400, msg.to_string());
+    let mut reader = BufReader::new(stream).take(MAX_HEAD_BYTES as u64);
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|_| bad("unreadable request"))?;
+    let mut parts = line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(m), Some(t)) => (m.to_string(), t),
+        _ => return Err(bad("malformed request line")),
+    };
+    let path = target.split('?').next().unwrap_or(target).to_string();
+    let mut content_length = 0usize;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).map_err(|_| bad("unreadable headers"))? == 0 {
+            return Err((431, "request headers too large or truncated".into()));
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().map_err(|_| bad("invalid Content-Length"))?;
+            }
+        }
+    }
+    if content_length > max_body {
+        return Err((413, format!("request body of {} bytes exceeds the {} byte limit", content_length, max_body)));
+    }
+    let mut reader = reader.into_inner();
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).map_err(|_| bad("truncated request body"))?;
+    Ok(Request { method, path, body })
+}
+
+fn write_response(mut stream: &TcpStream, status: u16, body: &Json) -> std::io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Error",
+    };
+    let text = body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        text.len(),
+        text
+    )?;
+    stream.flush()
+}
+
+#[derive(Deserialize)]
+struct CompileRequest {
+    source: String,
+    #[serde(default)]
+    emit: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RunRequest {
+    source: String,
+}
+
+fn route(req: &Request, opts: &ServeOptions, slot: Arc<Slot>) -> (u16, Json) {
+    match (req.method.as_str(), req.path.as_str()) {
+        ("POST", "/compile") => match serde_json::from_slice::<CompileRequest>(&req.body) {
+            Ok(r) => compile(r),
+            Err(e) => (400, json!({ "error": format!("invalid request: {}", e) })),
+        },
+        ("POST", "/run") => match serde_json::from_slice::<RunRequest>(&req.body) {
+            Ok(r) => run(r.source, opts, slot),
+            Err(e) => (400, json!({ "error": format!("invalid request: {}", e) })),
+        },
+        (_, "/health" | "/compile" | "/run") => (405, json!({ "error": format!("{} not allowed on {}", req.method, req.path) })),
+        _ => (404, json!({ "error": format!("no route for {}", req.path) })),
+    }
+}
+
+fn compile(req: CompileRequest) -> (u16, Json) {
+    let kind = match req.emit.as_deref().unwrap_or("js") {
+        "js" => EmitKind::Js,
+        "ai" => EmitKind::Ai,
+        other => return (400, json!({ "error": format!("unknown emit kind '{}' (expected js or ai)", other) })),
+    };
+    match emit_preview(&req.source, kind) {
+        Ok(p) => (200, json!({ "ok": true, "output": p.text, "diagnostics": [] })),
+        Err(diagnostics) => (200, json!({ "ok": false, "output": Json::Null, "diagnostics": diagnostics })),
+    }
+}
+
+fn run(source: String, opts: &ServeOptions, slot: Arc<Slot>) -> (u16, Json) {
+    let start = Instant::now();
+    let limits = Limits {
+        deadline: Some(start + opts.timeout),
+        max_call_depth: Some(MAX_CALL_DEPTH),
+        max_string_bytes: Some(opts.max_string),
+        max_memory_bytes: Some(opts.max_memory),
+        deny_env: opts.sandbox,
+    };
+    let max_output = opts.max_output;
+    let (tx, rx) = mpsc::channel();
+    let spawned = std::thread::Builder::new().stack_size(RUN_STACK_BYTES).spawn(move || {
+        let _slot = slot;
+        let _ = tx.send(run_limited(&source, limits, max_output));
+    });
+    if spawned.is_err() {
+        return (503, json!({ "error": "could not start a run" }));
+    }
+    // The interpreter stops itself at the deadline; the grace period covers a builtin
+    // that is slow to return, after which the client gets an answer regardless.
+    let (output, error) = rx
+        .recv_timeout(opts.timeout + Duration::from_millis(250))
+        .unwrap_or_else(|_| (String::new(), Some("runtime error: time limit exceeded".into())));
+    let elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
+    (200, json!({ "ok": error.is_none(), "output": output, "error": error, "elapsed_ms": elapsed_ms }))
+}
+
+/// Output so far and the error that stopped the program, if any.
+fn run_limited(source: &str, limits: Limits, max_output: usize) -> (String, Option<String>) {
+    let tokens = match Lexer::from_str(source).tokenize() {
+        Ok(t) => t,
+        Err(e) => return (String::new(), Some(format!("lexing error: {}", e))),
+    };
+    let ast = match Parser::new(tokens).parse() {
+        Ok(a) => a,
+        Err(e) => return (String::new(), Some(format!("parsing error: {}", e))),
+    };
+    let module = match lower_ast_to_ir(&ast, "main") {
+        Ok(m) => m,
+        Err(e) => return (String::new(), Some(format!("lowering error: {}", e))),
+    };
+    let buf = OutputBuffer::new();
+    let mut interp = Interpreter::new();
+    interp.set_output(Box::new(CappedOutput { buf: buf.clone(), left: max_output }));
+    interp.set_input(Box::new(std::io::empty()));
+    interp.set_limits(limits);
+    let error = interp.run_module(&module).err().map(|e| format!("runtime error: {}", e.message));
+    (buf.contents(), error)
+}
+
+/// Output sink that fails once `left` bytes are used up.
+struct CappedOutput {
+    buf: OutputBuffer,
+    left: usize,
+}
+
+impl Write for CappedOutput {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        if data.len() > self.left {
+            return Err(std::io::Error::other("output limit exceeded"));
+        }
+        self.left -= data.len();
+        self.buf.write(data)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+//! Working directory of one interactive shell.
+//!
+//! The shell never calls `std::env::set_current_dir`: compiles and watchers spawned from
+//! the same process would see the change mid-run. Instead every builtin resolves its path
+//! arguments with [`ShellCwd::resolve`], and external commands get the directory through
+//! `Command::current_dir`. Paths are normalized lexically (like a shell's logical `pwd`),
+//! so `cd link/..` returns to where it started.
+
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug, Clone)]
+pub struct ShellCwd {
+    current: PathBuf,
+    previous: Option<PathBuf>,
+}
+
+impl ShellCwd {
+    pub fn new(start: PathBuf) -> Self {
+        Self { current: start, previous: None }
+    }
+
+    pub fn current(&self) -> &Path {
+        &self.current
+    }
+
+    /// `arg` (already expanded) against the shell's directory; absolute paths are kept.
+    pub fn resolve(&self, arg: impl AsRef<Path>) -> PathBuf {
+        normalize(&self.current.join(arg))
+    }
+
+    /// `cd [DIR]`: no argument goes to `home`, `-` to the previous directory.
+    /// Returns the new directory, or a message when the target is not a directory.
+    pub fn cd(&mut self, arg: Option<&str>, home: Option<&Path>) -> Result<&Path, String> {
+        let target = match arg {
+            None => home.map(Path::to_path_buf).ok_or("cd: no home directory")?,
+            Some("-") => self.previous.clone().ok_or("cd: no previous directory")?,
+            Some(dir) => self.resolve(dir),
+        };
+        if !target.is_dir() {
+            return Err(format!("cd: not a directory: {}", target.display()));
+        }
+        self.previous = Some(std::mem::replace(&mut self.current, target));
+        Ok(&self.current)
+    }
+}
+
+/// Drop `.` components and fold `..` into its parent without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for c in path.components() {
+        match c {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    out.push(c);
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Expand a leading `~` (or `~/...`) to `home` and `$VAR` / `${VAR}` via `var`.
+/// Unset variables expand to nothing, as in POSIX shells; a `$` not followed by a name is literal.
+pub fn expand_with(arg: &str, home: Option<&Path>, var: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::new();
+    let mut rest = arg;
+    if let Some(home) = home {
+        if rest == "~" || rest.starts_with("~/") || rest.starts_with("~\\") {
+            out.push_str(&home.to_string_lossy());
+            rest = &rest[1..];
+        }
+    }
+    while let Some(i) = rest.find('$') {
+        out.push_str(&rest[..i]);
+        let after = &rest[i + 1..];
+        let (name, tail) = if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], &braced[end + 1..]),
+                None => ("", after),
+            }
+        } else if after.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            let end = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        } else {
+            ("", after)
+        };
+        if name.is_empty() {
+            out.push('$');
+        } else {
+            out.push_str(&var(name).unwrap_or_default());
+        }
+        rest = tail;
+    }
+    out.push_str(rest);
+    out
+}
+
+/// [`expand_with`] using the process environment and the user's home directory.
+pub fn expand(arg: &str) -> String {
+    expand_with(arg, dirs_next::home_dir().as_deref(), |name| std::env::var(name).ok())
+}
@@ -0,0 +1,55 @@
+//! `aeonmi stats show|reset`: the opt-in usage counters of `core::usage_stats`. They are
+//! kept on this machine only; nothing is ever sent anywhere.
+use anyhow::Result;
+use serde::Serialize;
+use std::path::PathBuf;
+
+use super::exit_codes::{fail, IO};
+use crate::config::StatsSettings;
+use crate::core::style::{self, Role};
+use crate::core::usage_stats::{load, render, reset, stats_path, UsageStats};
+
+const LOCAL_ONLY: &str = "local only: nothing leaves this machine";
+
+#[derive(Serialize)]
+struct Report {
+    enabled: bool,
+    local_only: bool,
+    path: PathBuf,
+    #[serde(flatten)]
+    stats: UsageStats,
+}
+
+pub fn main_show(json: bool, config: Option<PathBuf>) -> Result<()> {
+    let enabled = StatsSettings::load(config.as_deref()).enabled;
+    let path = stats_path();
+    let stats = load(&path);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&Report { enabled, local_only: true, path, stats })?);
+        return Ok(());
+    }
+    let st = style::current();
+    println!("{} ({LOCAL_ONLY})", st.paint("usage statistics", Role::Section));
+    let toggle = if enabled { ("on", "false") } else { ("off", "true") };
+    println!("recording: {} (`aeonmi config set stats.enabled {}`)", toggle.0, toggle.1);
+    println!("file: {}", path.display());
+    let since = stats.since.and_then(|s| chrono::DateTime::from_timestamp(s as i64, 0));
+    match since {
+        Some(t) if !stats.commands.is_empty() => {
+            println!("since: {}\n", t.format("%Y-%m-%d %H:%M UTC"));
+            print!("{}", render(&stats));
+        }
+        _ => println!("no invocations recorded"),
+    }
+    Ok(())
+}
+
+pub fn main_reset() -> Result<()> {
+    let path = stats_path();
+    match reset(&path) {
+        Ok(true) => println!("stats: cleared {}", path.display()),
+        Ok(false) => println!("stats: nothing recorded"),
+        Err(e) => return Err(fail(IO, format!("remove {}: {e}", path.display()))),
+    }
+    Ok(())
+}
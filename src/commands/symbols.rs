@@ -0,0 +1,134 @@
+//! `aeonmi symbols <file.ai> [--json|--tree]` and `aeonmi outline <file.ai>`.
+//!
+//! Symbols come from [`collect_symbols`], grouped per top-level item: a function owns its
+//! parameters and the locals declared in its body. Each symbol carries the type inferred
+//! for its declaration (see [`TypeContext::type_at`]). The default output is one
+//! tab-separated line per symbol (`line:col`, kind, `container.name`, type), meant for
+//! grep and shell completion; `--tree` (and `outline`) indents children under their
+//! function, and `--json` prints the same tree as an array. Output is written item by item
+//! as it is produced, so large files start printing at once.
+
+use serde::Serialize;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::commands::exit_codes::{self, reported};
+use crate::core::ast::ASTNode;
+use crate::core::style::{self, Role, Style};
+use crate::core::symbols::{collect_symbols, SymbolInfo, SymbolKind};
+use crate::core::types::{TypeContext, TypeKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Lines,
+    Tree,
+    Json,
+}
+
+/// A symbol with its inferred type and the symbols declared inside it.
+#[derive(Debug, Clone, Serialize)]
+pub struct OutlineNode {
+    #[serde(flatten)]
+    pub symbol: SymbolInfo,
+    /// `None` for declarations the type checker does not type (enums, qubits).
+    #[serde(rename = "type")]
+    pub ty: Option<String>,
+    pub children: Vec<OutlineNode>,
+}
+
+fn node(symbol: SymbolInfo, types: &TypeContext) -> OutlineNode {
+    let ty = types.type_at(symbol.line, symbol.column, &symbol.name).map(TypeKind::to_string);
+    OutlineNode { symbol, ty, children: Vec::new() }
+}
+
+/// Outline of one top-level item: a function with its parameters and locals as children,
+/// or the item's own symbols (several for a destructuring `let`).
+pub fn outline_item(item: &ASTNode, types: &TypeContext) -> Vec<OutlineNode> {
+    let mut symbols = collect_symbols(item).into_iter();
+    match item {
+        ASTNode::Function { .. } => {
+            let Some(f) = symbols.next() else { return Vec::new() };
+            let mut f = node(f, types);
+            f.children = symbols.map(|s| node(s, types)).collect();
+            vec![f]
+        }
+        _ => symbols.map(|s| node(s, types)).collect(),
+    }
+}
+
+fn items(ast: &ASTNode) -> &[ASTNode] {
+    match ast {
+        ASTNode::Program(items) => items,
+        other => std::slice::from_ref(other),
+    }
+}
+
+/// `line:col<TAB>kind<TAB>container.name<TAB>type` (`-` when untyped) for `n` and its children.
+pub fn write_lines(out: &mut impl Write, n: &OutlineNode, container: Option<&str>) -> io::Result<()> {
+    let name = match container {
+        Some(c) => format!("{c}.{}", n.symbol.name),
+        None => n.symbol.name.clone(),
+    };
+    writeln!(out, "{}:{}\t{}\t{name}\t{}", n.symbol.line, n.symbol.column, n.symbol.kind.as_str(), n.ty.as_deref().unwrap_or("-"))?;
+    for c in &n.children {
+        write_lines(out, c, Some(&name))?;
+    }
+    Ok(())
+}
+
+fn tree_label(n: &OutlineNode, st: &Style) -> String {
+    let name = st.paint(&n.symbol.name, if n.symbol.kind == SymbolKind::Function { Role::Strong } else { Role::Name });
+    let ty = match n.ty.as_deref() {
+        Some(t) if t != "Unknown" => format!(": {t}"),
+        _ => String::new(),
+    };
+    let at = st.paint(&format!("{}:{}", n.symbol.line, n.symbol.column), Role::Dim);
+    format!("{} {name}{ty}  {at}", n.symbol.kind.as_str())
+}
+
+/// `n` and its children as tree rows under `prefix`; `last` picks the closing branch glyph.
+pub fn write_tree(out: &mut impl Write, n: &OutlineNode, prefix: &str, last: bool, st: &Style) -> io::Result<()> {
+    let branch = if last { st.glyph("└── ", "`-- ") } else { st.glyph("├── ", "|-- ") };
+    writeln!(out, "{prefix}{branch}{}", tree_label(n, st))?;
+    let inner = format!("{prefix}{}", if last { "    " } else { st.glyph("│   ", "|   ") });
+    for (i, c) in n.children.iter().enumerate() {
+        write_tree(out, c, &inner, i + 1 == n.children.len(), st)?;
+    }
+    Ok(())
+}
+
+pub fn main(input: &Path, format: Format, pretty: bool) -> anyhow::Result<()> {
+    let Some(ast) = super::typecheck::parse_file(input, pretty)? else {
+        return Err(reported(exit_codes::COMPILE_DIAG, "parsing failed"));
+    };
+    let mut types = TypeContext::new();
+    types.infer_program(&ast);
+    let st = style::current();
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    // Items are outlined and written one at a time; the tree needs one item of lookahead
+    // to know which top-level row closes it.
+    let mut nodes = items(&ast).iter().flat_map(|it| outline_item(it, &types)).peekable();
+    match format {
+        Format::Lines => {
+            for n in nodes {
+                write_lines(&mut out, &n, None)?;
+            }
+        }
+        Format::Tree => {
+            writeln!(out, "{}", st.paint(&input.display().to_string(), Role::Section))?;
+            while let Some(n) = nodes.next() {
+                write_tree(&mut out, &n, "", nodes.peek().is_none(), &st)?;
+            }
+        }
+        Format::Json => {
+            write!(out, "[")?;
+            for (i, n) in nodes.enumerate() {
+                write!(out, "{}\n  ", if i == 0 { "" } else { "," })?;
+                serde_json::to_writer(&mut out, &n)?;
+            }
+            writeln!(out, "\n]")?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
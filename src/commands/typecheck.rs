@@ -0,0 +1,301 @@
+//! `aeonmi typecheck`: run type inference alone, as a fast semantic gate for CI.
+//!
+//! Inputs go through [`crate::io::discover`], so directories and globs work as in `format`.
+//!
+//! Diagnostics come from one pass over the whole program. Each top-level function is
+//! then inferred on its own and timed, and the sample goes through
+//! `record_function_infer` under the function's item index (the key the GUI uses), so
+//! `metrics-top` ranks CLI and GUI runs together. Any type diagnostic exits with 3.
+//!
+//! Under `--analysis-timeout-ms` inference stops between top-level items once the budget
+//! is spent; the report keeps what it found and says where it stopped, and only the
+//! functions it reached are timed.
+//!
+//! `--annotate` (usually spelled `aeonmi check --annotate`) prints each file instead, with
+//! the semantic analyzer's and type inference's diagnostics drawn under the lines they
+//! point at.
+//!
+//! Both see each file's imports (see [`load`]); a diagnostic inside an imported file is
+//! reported against that file, as its own report or annotated listing.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::commands::exit_codes::{self, fail, reported};
+use crate::config::LexerSettings;
+use crate::core::analysis_budget::{AnalysisBudget, Truncation};
+use crate::core::ast::ASTNode;
+use crate::core::diag_sidecar::{DiagSidecar, SidecarDiagnostic};
+use crate::core::diagnostics::{max_diagnostics, render_annotated, report_error, report_parse_error, AnnotateOptions, Annotation, Diagnostic, DiagnosticSink, Span};
+use crate::core::error_index;
+use crate::core::incremental::{force_persist_metrics, load_metrics, record_function_infer, record_function_site};
+use crate::core::imports::{self, Flattened};
+use crate::core::lexer::{Lexer, LexerOptions};
+use crate::core::parser::Parser;
+use crate::core::style::{self, Role, Style};
+use crate::core::types::{TypeContext, TypeDiagnostic};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FunctionTiming {
+    /// Top-level item index, the key of the function's metrics entry.
+    pub index: usize,
+    pub name: String,
+    pub line: usize,
+    pub ns: u128,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TypecheckReport {
+    pub file: String,
+    pub diagnostics: Vec<TypeDiagnostic>,
+    /// Slowest first.
+    pub timings: Vec<FunctionTiming>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub truncated: Option<Truncation>,
+}
+
+/// Type-check `ast`, timing and recording each top-level function.
+pub fn check(ast: &ASTNode, file: &str) -> TypecheckReport {
+    check_located(ast, file, |line| (file.to_string(), line)).remove(0)
+}
+
+/// Type-check a program with its imports spliced in: the report for `file` first, then one
+/// per imported file that has a diagnostic or a function, positions in that file.
+pub fn check_flattened(flat: &Flattened, file: &str) -> Vec<TypecheckReport> {
+    check_located(&flat.numbered(), file, |line| {
+        let (path, line) = flat.locate(line);
+        (path.display().to_string(), line)
+    })
+}
+
+/// [`check`], with every position in `ast` mapped to a file and a line in it by `locate`.
+fn check_located(ast: &ASTNode, file: &str, locate: impl Fn(usize) -> (String, usize)) -> Vec<TypecheckReport> {
+    let mut ctx = TypeContext::new();
+    if let Some(budget) = AnalysisBudget::from_flag() {
+        ctx = ctx.with_budget(budget);
+    }
+    ctx.infer_program(ast);
+    let items: &[ASTNode] = match ast {
+        ASTNode::Program(items) => &items[..ctx.truncated.as_ref().map_or(items.len(), |t| t.analyzed)],
+        _ => &[],
+    };
+    let mut reports = vec![TypecheckReport { file: file.to_string(), diagnostics: Vec::new(), timings: Vec::new(), truncated: ctx.truncated }];
+    for d in ctx.diags {
+        let (file, line) = locate(d.line);
+        report_for(&mut reports, file).diagnostics.push(TypeDiagnostic { line, ..d });
+    }
+    for (index, item) in items.iter().enumerate() {
+        let ASTNode::Function { name, line, .. } = item else { continue };
+        let start = Instant::now();
+        TypeContext::new().infer_program(&ASTNode::Program(vec![item.clone()]));
+        let ns = start.elapsed().as_nanos();
+        let (file, line) = locate(*line);
+        record_function_infer(index, ns);
+        record_function_site(index, name, Some(&file), line);
+        report_for(&mut reports, file).timings.push(FunctionTiming { index, name: name.clone(), line, ns });
+    }
+    for r in &mut reports {
+        r.timings.sort_by(|a, b| b.ns.cmp(&a.ns).then(a.index.cmp(&b.index)));
+    }
+    reports
+}
+
+/// The report for `file` in `reports`, added when it is not there yet.
+fn report_for(reports: &mut Vec<TypecheckReport>, file: String) -> &mut TypecheckReport {
+    let i = match reports.iter().position(|r| r.file == file) {
+        Some(i) => i,
+        None => {
+            reports.push(TypecheckReport { file, diagnostics: Vec::new(), timings: Vec::new(), truncated: None });
+            reports.len() - 1
+        }
+    };
+    &mut reports[i]
+}
+
+/// Queue `report`'s diagnostics (`file:line:col: error: ...`, without the position when
+/// inference has none).
+pub fn feed(report: &TypecheckReport, sink: &mut DiagnosticSink) {
+    for d in &report.diagnostics {
+        sink.push(Diagnostic::error(&report.file, d.line, d.column, d.message.clone()));
+    }
+}
+
+/// Diagnostics through a [`DiagnosticSink`], then [`render_summary`].
+pub fn render(report: &TypecheckReport, timings: bool, st: &Style) -> String {
+    let mut sink = DiagnosticSink::new();
+    feed(report, &mut sink);
+    sink.render(st) + &render_summary(report, timings, st)
+}
+
+/// The timings table when `timings` is set, then a one-line summary.
+pub fn render_summary(report: &TypecheckReport, timings: bool, st: &Style) -> String {
+    let mut out = String::new();
+    if timings && !report.timings.is_empty() {
+        let w = report.timings.iter().map(|t| t.name.chars().count()).chain(["function".len()]).max().unwrap_or(0);
+        out.push_str(&st.paint(&format!("{:<w$}  {:>6}  {:>10}", "function", "line", "infer_ns"), Role::Section));
+        out.push('\n');
+        for t in &report.timings {
+            out.push_str(&format!("{:<w$}  {:>6}  {:>10}\n", t.name, t.line, t.ns));
+        }
+    }
+    let n = report.diagnostics.len();
+    let summary = format!("{}: {} type error{} in {} function{}", report.file, n, if n == 1 { "" } else { "s" }, report.timings.len(), if report.timings.len() == 1 { "" } else { "s" });
+    out.push_str(&if n == 0 { summary } else { st.paint(&summary, Role::Error) });
+    out.push('\n');
+    if let Some(t) = &report.truncated {
+        out.push_str(&format!("{}: note: {}\n", report.file, t));
+    }
+    out
+}
+
+/// Lex and parse `input` alone, reporting a failure as a coded diagnostic (`None`).
+pub(crate) fn parse_file(input: &Path, pretty: bool) -> anyhow::Result<Option<ASTNode>> {
+    Ok(read_and_parse(input, pretty)?.map(|(_, _, ast)| ast))
+}
+
+/// The front-end of every command that analyses or lowers a program (`typecheck`,
+/// `check --annotate`, `ir`): [`parse_file`], then the file's imports spliced in by
+/// [`imports::resolve`]. Hands back the source text with the program; `None` once a
+/// failure is reported.
+pub(crate) fn load(input: &Path, pretty: bool) -> anyhow::Result<Option<(String, Flattened)>> {
+    let Some((source, options, ast)) = read_and_parse(input, pretty)? else {
+        return Ok(None);
+    };
+    match imports::resolve(ast, input, &source, &options) {
+        Ok(flat) => Ok(Some((source, flat))),
+        Err(e) => {
+            e.report(pretty);
+            Ok(None)
+        }
+    }
+}
+
+/// [`parse_file`], also handing back the source text and the lexer options imports are read with.
+fn read_and_parse(input: &Path, pretty: bool) -> anyhow::Result<Option<(String, LexerOptions, ASTNode)>> {
+    let file = input.display().to_string();
+    let source = std::fs::read_to_string(input).map_err(|e| fail(exit_codes::IO, format!("{file}: {e}")))?;
+    let settings = LexerSettings::discover(input).map_err(|e| fail(exit_codes::USAGE, format!("{e:#}")))?;
+    let options = settings.lexer_options();
+    let tokens = match Lexer::with_options(&source, options.clone()).tokenize() {
+        Ok(t) => t,
+        Err(e) => {
+            let (line, col) = e.position();
+            report_error(&file, &source, &format!("Lexing error: {e}"), Span::single(line, col), Some(error_index::LEX_ERROR), pretty);
+            return Ok(None);
+        }
+    };
+    match Parser::new(tokens).parse() {
+        Ok(ast) => Ok(Some((source, options, ast))),
+        Err(e) => {
+            report_parse_error(&file, &source, &e, pretty);
+            Ok(None)
+        }
+    }
+}
+
+/// Check every file in `inputs` (already expanded by discovery); `--json` prints an array of reports.
+pub fn main(inputs: &[PathBuf], timings: bool, json: bool, pretty: bool) -> anyhow::Result<()> {
+    if inputs.is_empty() {
+        return Err(fail(exit_codes::USAGE, "typecheck: no input files matched"));
+    }
+    load_metrics();
+    let (mut reports, mut failed) = (Vec::new(), 0usize);
+    for input in inputs {
+        match load(input, pretty)? {
+            Some((_, flat)) => reports.extend(check_flattened(&flat, &input.display().to_string())),
+            None => failed += 1,
+        }
+    }
+    force_persist_metrics();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    } else {
+        // Every file's diagnostics first, sorted and capped together, then per-file summaries.
+        let st = style::current();
+        let mut sink = DiagnosticSink::new();
+        for r in &reports {
+            feed(r, &mut sink);
+        }
+        print!("{}", sink.render(&st));
+        for r in &reports {
+            print!("{}", render_summary(r, timings, &st));
+        }
+    }
+    let errors: usize = reports.iter().map(|r| r.diagnostics.len()).sum();
+    if errors > 0 || failed > 0 {
+        return Err(reported(exit_codes::COMPILE_DIAG, format!("{errors} type error(s), {failed} file(s) failed to parse")));
+    }
+    Ok(())
+}
+
+/// Semantic and type diagnostics of `ast` as [`Annotation`]s, in the order the passes found them.
+pub fn annotations(file: &str, source: &str, ast: &ASTNode) -> Vec<Annotation> {
+    DiagSidecar::collect(file, source, ast, false).diagnostics.into_iter().map(annotation).collect()
+}
+
+/// [`annotations`] of a program with its imports spliced in, per file: `file` (`source`)
+/// first, then each imported file with a diagnostic, in load order.
+pub fn annotations_flattened(flat: &Flattened, file: &str, source: &str) -> Vec<(String, String, Vec<Annotation>)> {
+    let mut per_file = vec![(file.to_string(), source.to_string(), Vec::new())];
+    for d in DiagSidecar::collect(file, source, &flat.numbered(), false).located(flat).diagnostics {
+        let i = match &d.file {
+            None => 0,
+            Some(name) => match per_file.iter().position(|(f, _, _)| f == name) {
+                Some(i) => i,
+                None => {
+                    let text = flat.imported.iter().find(|f| f.path.display().to_string() == *name).map(|f| f.source.clone());
+                    per_file.push((name.clone(), text.unwrap_or_default(), Vec::new()));
+                    per_file.len() - 1
+                }
+            },
+        };
+        per_file[i].2.push(annotation(d));
+    }
+    per_file
+}
+
+fn annotation(d: SidecarDiagnostic) -> Annotation {
+    Annotation {
+        severity: match d.severity.as_str() { "error" => "error", "info" => "info", _ => "warning" },
+        line: d.line,
+        col: d.column,
+        len: d.len,
+        message: match d.code {
+            Some(code) => format!("{} [{}]", d.message, code),
+            None => d.message,
+        },
+    }
+}
+
+/// `--annotate`: every file printed with its diagnostics under the offending lines (see
+/// [`render_annotated`]), capped per file by `--max-diagnostics`, then a count. Exits 3 when
+/// any file has an error or does not parse; warnings alone pass.
+pub fn annotate(inputs: &[PathBuf], pretty: bool) -> anyhow::Result<()> {
+    if inputs.is_empty() {
+        return Err(fail(exit_codes::USAGE, "check: no input files matched"));
+    }
+    let st = style::current();
+    let opt = AnnotateOptions { width: crate::core::columns::terminal_width(), max: max_diagnostics(), full_source: true };
+    let (mut errors, mut failed) = (0usize, 0usize);
+    for input in inputs {
+        let Some((source, flat)) = load(input, pretty)? else {
+            failed += 1;
+            continue;
+        };
+        for (file, source, found) in annotations_flattened(&flat, &input.display().to_string(), &source) {
+            let n_err = found.iter().filter(|a| a.severity == "error").count();
+            errors += n_err;
+            println!("{}", st.paint(&file, Role::Section));
+            print!("{}", render_annotated(&st, &source, &found, &opt));
+            let plural = |n: usize| if n == 1 { "" } else { "s" };
+            let n_warn = found.iter().filter(|a| a.severity == "warning").count();
+            let summary = format!("{}: {} error{}, {} warning{}", file, n_err, plural(n_err), n_warn, plural(n_warn));
+            println!("{}", if n_err == 0 { summary } else { st.paint(&summary, Role::Error) });
+        }
+    }
+    if errors > 0 || failed > 0 {
+        return Err(reported(exit_codes::COMPILE_DIAG, format!("{errors} error(s), {failed} file(s) failed to parse")));
+    }
+    Ok(())
+}
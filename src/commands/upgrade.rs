@@ -0,0 +1,288 @@
+//! `aeonmi upgrade`: ask the release feed whether a newer Aeonmi exists and, on request,
+//! reinstall it through `cargo install`.
+//!
+//! Nothing here runs unless the user types `aeonmi upgrade --check` or `--install`; no other
+//! command touches the network for updates. `--offline` (or `AEONMI_OFFLINE=1`) turns both
+//! into a local report of the installed version.
+//!
+//! The feed is the crates.io API by default. A build can point it at a GitHub releases URL
+//! with `AEONMI_UPGRADE_URL` set at compile time (the same variable at run time wins, for
+//! mirrors and tests). GitHub release notes are scanned for breaking changes; crates.io has
+//! no notes to scan.
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::fmt;
+use std::io::{BufRead, Write};
+
+use crate::commands::exit_codes::{fail, USAGE};
+
+pub const CRATE_NAME: &str = "aeonmi";
+pub const CRATES_IO_FEED: &str = "https://crates.io/api/v1/crates/aeonmi";
+
+/// The command `--install` runs, after `cargo`.
+pub const INSTALL_ARGS: [&str; 3] = ["install", CRATE_NAME, "--locked"];
+
+/// Where `--check` looks: `AEONMI_UPGRADE_URL` at run time, then at build time, then crates.io.
+pub fn feed_url() -> String {
+    std::env::var("AEONMI_UPGRADE_URL")
+        .ok()
+        .filter(|u| !u.trim().is_empty())
+        .or_else(|| option_env!("AEONMI_UPGRADE_URL").map(str::to_string))
+        .unwrap_or_else(|| CRATES_IO_FEED.to_string())
+}
+
+/// `AEONMI_OFFLINE` set to anything but empty, `0` or `false`.
+pub fn offline_env() -> bool {
+    std::env::var("AEONMI_OFFLINE").is_ok_and(|v| !matches!(v.trim(), "" | "0" | "false"))
+}
+
+/// `MAJOR.MINOR.PATCH[-pre][+build]`, optionally prefixed with `v` (GitHub tags).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Option<String>,
+}
+
+impl Version {
+    pub fn parse(text: &str) -> Option<Version> {
+        let text = text.trim();
+        let text = text.strip_prefix('v').unwrap_or(text);
+        let text = text.split('+').next()?;
+        let (core, pre) = match text.split_once('-') {
+            Some((core, pre)) if !pre.is_empty() => (core, Some(pre.to_string())),
+            Some(_) => return None,
+            None => (text, None),
+        };
+        let mut nums = core.split('.').map(|n| n.parse::<u64>().ok());
+        let version = Version { major: nums.next()??, minor: nums.next()??, patch: nums.next()??, pre };
+        nums.next().is_none().then_some(version)
+    }
+
+    /// The version this binary was built as.
+    pub fn current() -> Version {
+        Version::parse(env!("CARGO_PKG_VERSION")).expect("CARGO_PKG_VERSION is semver")
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch).cmp(&(other.major, other.minor, other.patch)).then_with(|| {
+            match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                // 1.0.0-rc.1 < 1.0.0
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(a), Some(b)) => compare_pre(a, b),
+            }
+        })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Semver precedence for pre-release tags: numeric identifiers compare as numbers and sort
+/// before alphanumeric ones; a shorter list of equal identifiers sorts first.
+fn compare_pre(a: &str, b: &str) -> Ordering {
+    let mut left = a.split('.');
+    let mut right = b.split('.');
+    loop {
+        match (left.next(), right.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => {
+                let ord = match (x.parse::<u64>(), y.parse::<u64>()) {
+                    (Ok(x), Ok(y)) => x.cmp(&y),
+                    (Ok(_), Err(_)) => Ordering::Less,
+                    (Err(_), Ok(_)) => Ordering::Greater,
+                    (Err(_), Err(_)) => x.cmp(y),
+                };
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if let Some(pre) = &self.pre {
+            write!(f, "-{pre}")?;
+        }
+        Ok(())
+    }
+}
+
+/// One published version and its release notes (empty for crates.io).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    pub version: Version,
+    pub notes: String,
+}
+
+/// What the feed says: the newest stable version and every release it listed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Feed {
+    pub latest: Version,
+    pub releases: Vec<Release>,
+}
+
+/// Parse a crates.io crate response (`{"crate": {...}, "versions": [...]}`) or a GitHub
+/// releases list (`[{"tag_name", "body", "draft", "prerelease"}, ...]`). Yanked versions,
+/// drafts and pre-releases never count as the latest.
+pub fn parse_feed(body: &str) -> Result<Feed> {
+    let json: Value = serde_json::from_str(body).context("release feed is not JSON")?;
+    let releases: Vec<Release> = match &json {
+        Value::Array(items) => items
+            .iter()
+            .filter(|r| !r["draft"].as_bool().unwrap_or(false) && !r["prerelease"].as_bool().unwrap_or(false))
+            .filter_map(|r| {
+                let version = Version::parse(r["tag_name"].as_str()?)?;
+                Some(Release { version, notes: r["body"].as_str().unwrap_or_default().to_string() })
+            })
+            .collect(),
+        Value::Object(_) if json.get("crate").is_some() => {
+            let mut releases: Vec<Release> = json["versions"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter(|v| !v["yanked"].as_bool().unwrap_or(false))
+                .filter_map(|v| Version::parse(v["num"].as_str()?))
+                .map(|version| Release { version, notes: String::new() })
+                .collect();
+            // the crate summary alone (no `versions`) still names the newest stable release
+            if let Some(max) = json["crate"]["max_stable_version"].as_str().and_then(Version::parse) {
+                if !releases.iter().any(|r| r.version == max) {
+                    releases.push(Release { version: max, notes: String::new() });
+                }
+            }
+            releases
+        }
+        _ => anyhow::bail!("release feed is neither a crates.io crate nor a GitHub releases list"),
+    };
+    let latest = releases
+        .iter()
+        .map(|r| &r.version)
+        .filter(|v| v.pre.is_none())
+        .max()
+        .cloned()
+        .context("release feed lists no stable version")?;
+    Ok(Feed { latest, releases })
+}
+
+/// Breaking changes announced by the releases after `current` up to `latest`, oldest first:
+/// bullets under a heading that says "breaking", or bullets that say it themselves.
+pub fn breaking_highlights(feed: &Feed, current: &Version) -> Vec<String> {
+    let mut newer: Vec<&Release> = feed.releases.iter().filter(|r| &r.version > current && r.version <= feed.latest).collect();
+    newer.sort_by(|a, b| a.version.cmp(&b.version));
+    let mut out = Vec::new();
+    for release in newer {
+        let mut in_breaking_section = false;
+        for line in release.notes.lines() {
+            let line = line.trim();
+            if line.starts_with('#') {
+                in_breaking_section = line.to_ascii_lowercase().contains("breaking");
+                continue;
+            }
+            let Some(item) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) else { continue };
+            if in_breaking_section || item.to_ascii_lowercase().contains("breaking") {
+                out.push(format!("{}: {}", release.version, item.trim()));
+            }
+        }
+    }
+    out
+}
+
+/// The `--check` report for `current` against `feed`.
+pub fn report(current: &Version, feed: &Feed) -> String {
+    let mut out = match current.cmp(&feed.latest) {
+        Ordering::Less => format!("aeonmi {current} is installed; {} is available.\n", feed.latest),
+        Ordering::Equal => format!("aeonmi {current} is up to date.\n"),
+        Ordering::Greater => format!("aeonmi {current} is newer than the latest release ({}).\n", feed.latest),
+    };
+    if current < &feed.latest {
+        let highlights = breaking_highlights(feed, current);
+        if !highlights.is_empty() {
+            out.push_str("Breaking changes since your version:\n");
+            for h in highlights {
+                out.push_str(&format!("  - {h}\n"));
+            }
+        } else if feed.releases.iter().all(|r| r.notes.is_empty()) {
+            out.push_str("(this feed carries no release notes)\n");
+        }
+        out.push_str(&format!("Run `aeonmi upgrade --install` to install it (cargo {}).\n", INSTALL_ARGS.join(" ")));
+    }
+    out
+}
+
+/// Read a yes/no answer; anything but `y`/`yes` is no.
+pub fn confirm(prompt: &str, input: &mut impl BufRead) -> bool {
+    print!("{prompt} [y/N] ");
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    input.read_line(&mut answer).is_ok() && matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
+
+#[cfg(feature = "self-update")]
+fn fetch(url: &str) -> Result<String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(std::time::Duration::from_secs(15))
+        // crates.io rejects requests without a user agent
+        .user_agent(concat!("aeonmi/", env!("CARGO_PKG_VERSION"), " (upgrade check)"))
+        .build()?;
+    let resp = client.get(url).send().with_context(|| format!("could not reach {url}"))?;
+    let status = resp.status();
+    if !status.is_success() {
+        anyhow::bail!("{url} answered HTTP {}", status.as_u16());
+    }
+    Ok(resp.text()?)
+}
+
+#[cfg(not(feature = "self-update"))]
+fn fetch(url: &str) -> Result<String> {
+    anyhow::bail!("this build cannot check for updates (rebuild with --features self-update, or look at {url})")
+}
+
+pub fn main(check: bool, install: bool, yes: bool, offline: bool) -> Result<()> {
+    let current = Version::current();
+    if !check && !install {
+        println!("aeonmi {current}");
+        println!("Run `aeonmi upgrade --check` to look for a newer release.");
+        return Ok(());
+    }
+    if offline || offline_env() {
+        println!("aeonmi {current} (offline: not checking for updates)");
+        if install {
+            return Err(fail(USAGE, "--install needs the network; drop --offline / AEONMI_OFFLINE"));
+        }
+        return Ok(());
+    }
+
+    let url = feed_url();
+    let feed = parse_feed(&fetch(&url)?).with_context(|| format!("reading {url}"))?;
+    print!("{}", report(&current, &feed));
+    if !install {
+        return Ok(());
+    }
+    if current >= feed.latest {
+        println!("Nothing to install.");
+        return Ok(());
+    }
+    let command = format!("cargo {}", INSTALL_ARGS.join(" "));
+    if !yes && !confirm(&format!("Run `{command}`?"), &mut std::io::stdin().lock()) {
+        println!("Not installing.");
+        return Ok(());
+    }
+    let args: Vec<String> = INSTALL_ARGS.iter().map(|a| a.to_string()).collect();
+    crate::commands::cargo::passthrough(None, &args)
+}
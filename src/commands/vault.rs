@@ -1,4 +1,4 @@
-use std::{fs, thread, time::Duration};
+use std::{fs, time::Duration};
 
 use anyhow::{Context, Result};
 use crossterm::terminal::{
@@ -123,18 +123,25 @@ pub fn dispatch(command: VaultCommand, use_tui: bool) -> Result<()> {
             vault.audit(&args.category, detail)?;
             println!("Audit entry appended.");
         }
-        VaultCommand::Watch(args) => loop {
-            let status = vault.generate_status()?;
-            if use_tui {
-                render_tui(&status)?;
-            } else {
-                print_status(&status);
+        VaultCommand::Watch(args) => {
+            let listener = crate::core::shutdown::listen();
+            let mut refreshes = 0;
+            loop {
+                let status = vault.generate_status()?;
+                refreshes += 1;
+                if use_tui {
+                    render_tui(&status)?;
+                } else {
+                    print_status(&status);
+                }
+                if args.once || !listener.token().sleep(Duration::from_secs(args.interval)) {
+                    break;
+                }
             }
-            if args.once {
-                break;
+            if !args.once {
+                println!("[vault watch] stopped after {refreshes} refresh{}", if refreshes == 1 { "" } else { "es" });
             }
-            thread::sleep(Duration::from_secs(args.interval));
-        },
+        }
         VaultCommand::Sync => {
             vault.sync()?;
             println!("Vault synchronized.");
@@ -1,91 +1,452 @@
-use anyhow::Result;
-use std::path::PathBuf;
+//! `aeonmi vm`: a resident interpreter session.
+//!
+//! `vm start` spawns `aeonmi vm daemon` in the background. The daemon keeps one
+//! [`Interpreter`] alive and serves the other actions over a localhost TCP socket
+//! (available on every platform, unlike unix sockets and named pipes). It records its
+//! pid, port and a random token in `<config dir>/vm/daemon.json`; requests must carry
+//! the token, so only someone who can read the config dir can drive the session. Each
+//! connection carries one JSON request line and gets one JSON response line back.
+//!
+//! `vm eval` runs code at global scope ([`Interpreter::run_session`]), so its variables
+//! and functions persist between evals. Snapshots of those globals go to
+//! `<config dir>/vm/snapshots/<name>.json` ([`crate::core::vm_snapshot`]).
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::commands::exit_codes::{self, fail};
+use crate::core::ast::ASTNode;
+use crate::core::ir::{Block, Decl};
+use crate::core::lexer::Lexer;
+use crate::core::lowering::lower_ast_to_ir;
+use crate::core::parser::Parser;
+use crate::core::vm::{Interpreter, Limits, OutputBuffer};
+use crate::core::vm_snapshot::{self, Snapshot};
+
+/// Wall-clock budget of a single `vm eval`; the daemon serves one request at a time.
+const EVAL_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn vm_dir() -> PathBuf {
+    if let Ok(base) = std::env::var("AEONMI_CONFIG_DIR") {
+        return PathBuf::from(base).join("vm");
+    }
+    dirs_next::config_dir().unwrap_or(std::env::temp_dir()).join("aeonmi").join("vm")
+}
+
+fn info_path() -> PathBuf {
+    vm_dir().join("daemon.json")
+}
+
+/// Snapshot names become file names, so they are kept to a safe alphabet.
+fn snapshot_path(name: &str) -> Result<PathBuf, String> {
+    let ok = !name.is_empty() && !name.starts_with('.') && name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c));
+    if !ok {
+        return Err(format!("invalid snapshot name `{name}` (use letters, digits, `-`, `_` and `.`)"));
+    }
+    Ok(vm_dir().join("snapshots").join(format!("{name}.json")))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DaemonInfo {
+    pid: u32,
+    port: u16,
+    token: String,
+    /// Unix seconds.
+    started: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Op {
+    Status,
+    Eval { source: String, module: Option<String> },
+    Snapshot { name: String },
+    Restore { name: String },
+    Mount { dir: PathBuf },
+    Reset,
+    Stop,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Request {
+    token: String,
+    #[serde(flatten)]
+    op: Op,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Response {
+    /// Program output (for `eval`) or a one-line confirmation.
+    #[serde(default)]
+    output: String,
+    #[serde(default)]
+    error: Option<String>,
+    /// Exit code for the client; see [`exit_codes`].
+    #[serde(default)]
+    code: i32,
+    #[serde(default)]
+    status: Option<Status>,
+}
+
+impl Response {
+    fn ok(output: impl Into<String>) -> Self {
+        Response { output: output.into(), ..Default::default() }
+    }
+    fn fail(code: i32, error: impl Into<String>) -> Self {
+        Response { error: Some(error.into()), code, ..Default::default() }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Status {
+    pid: u32,
+    uptime_secs: u64,
+    modules: Vec<String>,
+    globals: Vec<String>,
+    mounts: Vec<PathBuf>,
+}
+
+// ---------- client ----------
+
+fn read_info() -> Option<DaemonInfo> {
+    serde_json::from_str(&fs::read_to_string(info_path()).ok()?).ok()
+}
+
+fn send(info: &DaemonInfo, op: Op) -> std::io::Result<Response> {
+    let stream = TcpStream::connect_timeout(&([127, 0, 0, 1], info.port).into(), Duration::from_secs(2))?;
+    let mut line = serde_json::to_string(&Request { token: info.token.clone(), op })?;
+    line.push('\n');
+    (&stream).write_all(line.as_bytes())?;
+    let mut reply = String::new();
+    BufReader::new(&stream).read_line(&mut reply)?;
+    serde_json::from_str(&reply).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// The daemon described by `daemon.json`, if it answers.
+fn running() -> Option<(DaemonInfo, Status)> {
+    let info = read_info()?;
+    let status = send(&info, Op::Status).ok()?.status?;
+    Some((info, status))
+}
+
+fn request(op: Op) -> Result<Response> {
+    let not_running = || fail(exit_codes::GENERIC, "vm is not running (start it with `aeonmi vm start`)");
+    let info = read_info().ok_or_else(not_running)?;
+    send(&info, op).map_err(|_| not_running())
+}
+
+/// Print the response's output; its error becomes the command's.
+fn finish(resp: Response) -> Result<()> {
+    print!("{}", resp.output);
+    match resp.error {
+        Some(e) => Err(fail(resp.code, e)),
+        None => Ok(()),
+    }
+}
 
 pub fn start() -> Result<()> {
-    println!("vm: start (placeholder)");
-    Ok(())
+    if let Some((info, _)) = running() {
+        println!("vm: already running (pid {}, port {})", info.pid, info.port);
+        return Ok(());
+    }
+    let dir = vm_dir();
+    fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+    let _ = fs::remove_file(info_path());
+    let log = fs::File::create(dir.join("daemon.log"))?;
+    let mut child = std::process::Command::new(std::env::current_exe()?)
+        .args(["vm", "daemon"])
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(log)
+        .spawn()
+        .context("spawning the vm daemon")?;
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        if let Some((info, _)) = running() {
+            println!("vm: started (pid {}, port {})", info.pid, info.port);
+            return Ok(());
+        }
+        if let Ok(Some(code)) = child.try_wait() {
+            return Err(fail(exit_codes::GENERIC, format!("vm daemon exited with {code} (see {})", dir.join("daemon.log").display())));
+        }
+        if Instant::now() > deadline {
+            let _ = child.kill();
+            return Err(fail(exit_codes::GENERIC, "vm daemon did not come up within 10s"));
+        }
+        std::thread::sleep(Duration::from_millis(25));
+    }
 }
 
 pub fn stop() -> Result<()> {
-    println!("vm: stop (placeholder)");
-    Ok(())
+    let Some(info) = read_info() else {
+        println!("vm: not running");
+        return Ok(());
+    };
+    match send(&info, Op::Stop) {
+        Ok(resp) => finish(resp),
+        Err(_) => {
+            // Nothing answers on the recorded port: a daemon that died without cleaning up.
+            let _ = fs::remove_file(info_path());
+            println!("vm: not running");
+            Ok(())
+        }
+    }
 }
 
 pub fn status() -> Result<()> {
-    println!("vm: status: running (placeholder)");
+    let Some((info, st)) = running() else {
+        println!("vm: not running");
+        return Ok(());
+    };
+    let list = |items: Vec<String>| if items.is_empty() { "-".to_string() } else { items.join(", ") };
+    println!("vm: running (pid {}, port {})", st.pid, info.port);
+    println!("uptime: {}s", st.uptime_secs);
+    println!("modules: {} ({})", st.modules.len(), list(st.modules.clone()));
+    println!("globals: {}", list(st.globals));
+    println!("mounts: {}", list(st.mounts.iter().map(|p| p.display().to_string()).collect()));
     Ok(())
 }
 
 pub fn reset() -> Result<()> {
-    println!("vm: reset (placeholder)");
-    Ok(())
+    finish(request(Op::Reset)?)
+}
+
+pub fn eval(source: Option<String>, file: Option<PathBuf>) -> Result<()> {
+    let (source, module) = match (source, file) {
+        (Some(s), _) => (s, None),
+        (None, Some(f)) => {
+            let text = fs::read_to_string(&f).with_context(|| format!("reading {}", f.display()))?;
+            (text, Some(f.display().to_string()))
+        }
+        (None, None) => return Err(fail(exit_codes::USAGE, "vm eval needs source text or --file")),
+    };
+    finish(request(Op::Eval { source, module })?)
 }
 
 pub fn snapshot(name: String) -> Result<()> {
-    use std::env;
-    use std::fs;
-    let repo = env::current_dir()?;
-    let vmdir = repo.join(".aeonmi");
-    if !vmdir.exists() {
-        fs::create_dir_all(&vmdir)?;
-    }
-    let snaps = vmdir.join("vm_snapshots");
-    if !snaps.exists() {
-        fs::create_dir_all(&snaps)?;
-    }
-    let dest = snaps.join(&name);
-    if dest.exists() {
-        println!("overwriting snapshot '{}'", name);
-        fs::remove_dir_all(&dest)?;
-    }
-    // For simplicity, snapshot the whole repo into the snapshot dir
-    let options = fs_extra::dir::CopyOptions::new();
-    fs_extra::dir::copy(&repo, &dest, &options)?;
-    println!("vm: snapshot '{}' created", name);
-    Ok(())
+    finish(request(Op::Snapshot { name })?)
 }
 
 pub fn restore(name: String) -> Result<()> {
-    use std::env;
-    use std::fs;
-    let repo = env::current_dir()?;
-    let snaps = repo.join(".aeonmi").join("vm_snapshots");
-    let src = snaps.join(&name);
-    if !src.exists() {
-        anyhow::bail!("snapshot '{}' not found", name);
-    }
-    // For safety, copy snapshot to a temporary dir and then move
-    let tmp = repo.join(".aeonmi").join("tmp_restore");
-    if tmp.exists() {
-        fs::remove_dir_all(&tmp)?;
-    }
-    let options = fs_extra::dir::CopyOptions::new();
-    fs_extra::dir::copy(&src, &tmp, &options)?;
-    // Now copy contents back into repo (overwriting)
-    for entry in std::fs::read_dir(&tmp)? {
-        let entry = entry?;
-        let name = entry.file_name();
-        let srcp = entry.path();
-        let dst = repo.join(name);
-        if dst.exists() {
-            if dst.is_dir() {
-                fs::remove_dir_all(&dst)?;
-            } else {
-                fs::remove_file(&dst)?;
+    finish(request(Op::Restore { name })?)
+}
+
+pub fn mount(dir: PathBuf) -> Result<()> {
+    // Resolved here: the daemon's working directory need not be ours.
+    let dir = dir.canonicalize().with_context(|| format!("mount {}", dir.display()))?;
+    if !dir.is_dir() {
+        return Err(fail(exit_codes::USAGE, format!("mount: {} is not a directory", dir.display())));
+    }
+    finish(request(Op::Mount { dir })?)
+}
+
+// ---------- daemon ----------
+
+struct Session {
+    interp: Interpreter,
+    /// Evaluated modules, oldest first: file paths, or `<eval N>` for inline source.
+    modules: Vec<String>,
+    started: Instant,
+}
+
+impl Session {
+    fn new() -> Self {
+        Session { interp: Interpreter::new(), modules: Vec::new(), started: Instant::now() }
+    }
+
+    /// A fresh interpreter with the current one's mounts.
+    fn fresh_interpreter(&self) -> Interpreter {
+        let mut interp = Interpreter::new();
+        for dir in self.interp.mounts() {
+            let _ = interp.mount(dir);
+        }
+        interp
+    }
+
+    fn handle(&mut self, op: Op) -> Response {
+        match op {
+            Op::Status => Response {
+                status: Some(Status {
+                    pid: std::process::id(),
+                    uptime_secs: self.started.elapsed().as_secs(),
+                    modules: self.modules.clone(),
+                    globals: self.interp.globals().into_iter().map(|(k, _)| k).collect(),
+                    mounts: self.interp.mounts().to_vec(),
+                }),
+                ..Default::default()
+            },
+            Op::Eval { source, module } => self.eval(&source, module),
+            Op::Snapshot { name } => self.snapshot(&name),
+            Op::Restore { name } => self.restore(&name),
+            Op::Mount { dir } => match self.interp.mount(&dir) {
+                Ok(()) => Response::ok(format!("vm: mounted '{}'\n", dir.display())),
+                Err(e) => Response::fail(exit_codes::IO, format!("mount {}: {e}", dir.display())),
+            },
+            Op::Reset => {
+                self.interp = self.fresh_interpreter();
+                self.modules.clear();
+                Response::ok("vm: reset\n")
+            }
+            Op::Stop => Response::ok("vm: stopped\n"),
+        }
+    }
+
+    fn eval(&mut self, source: &str, module: Option<String>) -> Response {
+        let name = module.unwrap_or_else(|| format!("<eval {}>", self.modules.len() + 1));
+        let (m, top) = match lower_session(source) {
+            Ok(parts) => parts,
+            Err(e) => return Response::fail(exit_codes::COMPILE_DIAG, format!("{name}: {e}")),
+        };
+        let buf = OutputBuffer::new();
+        self.interp.set_output(Box::new(buf.clone()));
+        self.interp.set_limits(Limits { deadline: Some(Instant::now() + EVAL_TIMEOUT), ..Default::default() });
+        let result = self.interp.run_session(&m, &top);
+        self.interp.set_output(Box::new(std::io::sink()));
+        match result {
+            Ok(_) => {
+                self.modules.push(name);
+                Response::ok(buf.contents())
             }
+            Err(e) => Response { output: buf.contents(), ..Response::fail(exit_codes::RUNTIME, format!("runtime error: {}", e.message)) },
         }
-        if srcp.is_dir() {
-            fs_extra::dir::copy(&srcp, &dst, &options)?;
-        } else {
-            fs::copy(&srcp, &dst)?;
+    }
+
+    fn snapshot(&self, name: &str) -> Response {
+        let path = match snapshot_path(name) {
+            Ok(p) => p,
+            Err(e) => return Response::fail(exit_codes::USAGE, e),
+        };
+        let snap = vm_snapshot::capture(&self.interp, &self.modules);
+        let count = snap.globals.len();
+        let written = path.parent().map_or(Ok(()), fs::create_dir_all).and_then(|()| {
+            fs::write(&path, serde_json::to_string_pretty(&snap).map_err(std::io::Error::other)?)
+        });
+        match written {
+            Ok(()) => Response::ok(format!("vm: snapshot '{name}' saved ({count} globals) to {}\n", path.display())),
+            Err(e) => Response::fail(exit_codes::IO, format!("writing {}: {e}", path.display())),
+        }
+    }
+
+    fn restore(&mut self, name: &str) -> Response {
+        let path = match snapshot_path(name) {
+            Ok(p) => p,
+            Err(e) => return Response::fail(exit_codes::USAGE, e),
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(t) => t,
+            Err(_) => return Response::fail(exit_codes::IO, format!("snapshot '{name}' not found ({})", path.display())),
+        };
+        let snap: Snapshot = match serde_json::from_str(&text) {
+            Ok(s) => s,
+            Err(e) => return Response::fail(exit_codes::GENERIC, format!("{}: {e}", path.display())),
+        };
+        let modules = snap.modules.clone();
+        let mut interp = self.fresh_interpreter();
+        if let Err(e) = vm_snapshot::restore(snap, &mut interp) {
+            return Response::fail(exit_codes::GENERIC, format!("{}: {e}", path.display()));
         }
+        self.interp = interp;
+        self.modules = modules;
+        Response::ok(format!("vm: restored snapshot '{name}'\n"))
+    }
+}
+
+/// Top-level functions and enums as a module, and everything else as the statements to
+/// run at global scope.
+fn lower_session(source: &str) -> Result<(crate::core::ir::Module, Block), String> {
+    let tokens = Lexer::from_str(source).tokenize().map_err(|e| format!("lexing error: {e}"))?;
+    split_session(Parser::new(tokens).parse().map_err(|e| format!("parsing error: {e}"))?)
+}
+
+/// [`lower_session`] for an already parsed (and import-resolved) program; shared with `aeonmi repl`.
+pub(crate) fn split_session(program: ASTNode) -> Result<(crate::core::ir::Module, Block), String> {
+    let items = match program {
+        ASTNode::Program(items) => items,
+        other => vec![other],
+    };
+    let (decls, stmts): (Vec<_>, Vec<_>) = items.into_iter().partition(|n| matches!(n, ASTNode::Function { .. } | ASTNode::EnumDecl { .. }));
+    let lower = |items| lower_ast_to_ir(&ASTNode::Program(items), "session").map_err(|e| format!("lowering error: {e}"));
+    let m = lower(decls)?;
+    // The statements come back as the body of a synthesized `main`.
+    let top = lower(stmts)?.decls.into_iter().find_map(|d| match d {
+        Decl::Fn(f) => Some(f.body),
+        _ => None,
+    });
+    Ok((m, top.unwrap_or(Block { stmts: Vec::new() })))
+}
+
+fn random_token() -> String {
+    use rand::RngCore;
+    let mut bytes = [0u8; 16];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn serve_one(stream: TcpStream, token: &str, session: &mut Session) -> std::io::Result<bool> {
+    // A client that connects and never sends must not wedge the session.
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let (resp, stop) = match serde_json::from_str::<Request>(&line) {
+        Ok(req) if req.token == token => {
+            let stop = matches!(req.op, Op::Stop);
+            (session.handle(req.op), stop)
+        }
+        Ok(_) => (Response::fail(exit_codes::GENERIC, "vm: bad token"), false),
+        Err(e) => (Response::fail(exit_codes::USAGE, format!("vm: bad request: {e}")), false),
+    };
+    let mut reply = serde_json::to_string(&resp)?;
+    reply.push('\n');
+    (&stream).write_all(reply.as_bytes())?;
+    Ok(stop)
+}
+
+pub fn daemon() -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).context("binding the vm socket")?;
+    let info = DaemonInfo {
+        pid: std::process::id(),
+        port: listener.local_addr()?.port(),
+        token: random_token(),
+        started: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+    };
+    write_info(&info)?;
+    let mut session = Session::new();
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        match serve_one(stream, &info.token, &mut session) {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(e) => eprintln!("vm: {e}"),
+        }
+    }
+    // Leave the file alone if a newer daemon has replaced it.
+    if read_info().is_some_and(|i| i.pid == info.pid) {
+        let _ = fs::remove_file(info_path());
     }
-    fs::remove_dir_all(&tmp)?;
-    println!("vm: restored snapshot '{}'", name);
     Ok(())
 }
 
-pub fn mount(dir: PathBuf) -> Result<()> {
-    println!("vm: mount '{}' (placeholder)", dir.display());
+/// Written to a temporary file and renamed, so `vm start` never reads half of it.
+fn write_info(info: &DaemonInfo) -> Result<()> {
+    let path = info_path();
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_string_pretty(info)?)?;
+    restrict(&tmp);
+    fs::rename(&tmp, &path)?;
     Ok(())
 }
+
+/// The token file is readable by its owner only, where the platform supports that.
+#[cfg(unix)]
+fn restrict(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+    let _ = fs::set_permissions(path, fs::Permissions::from_mode(0o600));
+}
+
+#[cfg(not(unix))]
+fn restrict(_path: &Path) {}
@@ -0,0 +1,368 @@
+//! Shared file watcher for re-run loops (the shell `watch` builtin and the `--watch` flags of
+//! `emit`, `run`, `native` and `exec`), built on `notify`.
+//!
+//! Parent directories are watched non-recursively and events are filtered down to the
+//! target files, so editors that save via rename-and-replace still trigger a re-run.
+//! Bursts of events are debounced into a single wake-up. Ctrl-C raises the token from
+//! [`crate::core::shutdown::listen`], which stops the loop without exiting the caller;
+//! [`run_loop`] then removes temp files, flushes metrics and prints a summary.
+//!
+//! The shell tracks the files named on the command line, and what each `.ai` one imports.
+//! `exec --watch` also tracks what the run depends on: the imports and the kept temp JS
+//! for `.ai`, and for `.rs` every file reachable through `mod name;` declarations (see
+//! [`rust_mod_deps`]). The `.ai` commands track their entry file and its imports through
+//! [`watch_ai`].
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use crate::commands::exit_codes::{code_for, is_reported};
+use crate::config::LexerSettings;
+use crate::core::imports::{self, ImportError};
+use crate::core::lexer::Lexer;
+use crate::core::parser::Parser;
+use crate::core::shutdown::ShutdownToken;
+use crate::core::style::{Role, Style};
+
+/// Quiet period that ends a burst of file events.
+pub const DEBOUNCE: Duration = Duration::from_millis(100);
+/// How often a waiting loop re-checks its stop flag.
+const POLL: Duration = Duration::from_millis(100);
+
+/// Clears the terminal and homes the cursor between runs.
+pub const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// Flags whose value is an output path; those files are written by the command, not read.
+const OUTPUT_FLAGS: &[&str] = &["--out", "-o"];
+
+/// Files an internal command reads: every non-flag argument that names an existing file
+/// (relative to `cwd`), skipping the values of output flags. Duplicates are dropped.
+pub fn watch_targets(args: &[String], cwd: &Path) -> Vec<PathBuf> {
+    let mut out = Vec::new();
+    let mut seen = HashSet::new();
+    let mut i = 0;
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if OUTPUT_FLAGS.contains(&arg.as_str()) {
+            i += 1;
+            continue;
+        }
+        if arg.starts_with('-') {
+            continue;
+        }
+        let path = cwd.join(arg);
+        if path.is_file() && seen.insert(path.canonicalize().unwrap_or_else(|_| path.clone())) {
+            out.push(path);
+        }
+    }
+    out
+}
+
+/// `(name, #[path])` for each out-of-line `mod name;` in Rust source. Line comments are
+/// skipped; inline `mod name { ... }` blocks have no file of their own and are ignored.
+fn mod_decls(src: &str) -> Vec<(String, Option<String>)> {
+    let mut out = Vec::new();
+    let mut path_attr: Option<String> = None;
+    for line in src.lines() {
+        let line = line.split("//").next().unwrap_or("").trim();
+        if let Some(rest) = line.strip_prefix("#[path") {
+            path_attr = rest.split('"').nth(1).map(str::to_string);
+            continue;
+        }
+        // `mod x;` behind any visibility (`pub`, `pub(crate)`, ...)
+        let decl = match line.find("mod ") {
+            Some(i) if line[..i].trim().is_empty() || line.starts_with("pub") => &line[i..],
+            _ => line,
+        };
+        if let Some(name) = decl.strip_prefix("mod ").and_then(|r| r.trim().strip_suffix(';')) {
+            out.push((name.trim().to_string(), path_attr.take()));
+        } else if !line.is_empty() && !line.starts_with("#[") {
+            path_attr = None;
+        }
+    }
+    out
+}
+
+/// Every file reachable from the crate root `root` through `mod` declarations, sorted and
+/// without `root` itself. Resolution follows rustc: `name.rs` or `name/mod.rs` next to a
+/// root or `mod.rs` file, under `<stem>/` for other files, and `#[path]` relative to the
+/// declaring file's directory. Unresolvable modules are skipped.
+pub fn rust_mod_deps(root: &Path) -> Vec<PathBuf> {
+    let mut seen: HashSet<PathBuf> = HashSet::from([root.to_path_buf()]);
+    let mut queue = vec![(root.to_path_buf(), true)];
+    while let Some((file, is_root)) = queue.pop() {
+        let Ok(src) = std::fs::read_to_string(&file) else { continue };
+        let dir = file.parent().map(Path::to_path_buf).unwrap_or_default();
+        let owns_dir = is_root || file.file_name().is_some_and(|n| n == "mod.rs");
+        let child_dir = if owns_dir { dir.clone() } else { dir.join(file.file_stem().unwrap_or_default()) };
+        for (name, path) in mod_decls(&src) {
+            let found = match path {
+                Some(p) => Some(dir.join(p)).filter(|p| p.is_file()),
+                None => [child_dir.join(format!("{name}.rs")), child_dir.join(&name).join("mod.rs")].into_iter().find(|p| p.is_file()),
+            };
+            if let Some(dep) = found {
+                if seen.insert(dep.clone()) {
+                    queue.push((dep, false));
+                }
+            }
+        }
+    }
+    seen.remove(root);
+    let mut deps: Vec<PathBuf> = seen.into_iter().collect();
+    deps.sort();
+    deps
+}
+
+/// What `exec --watch` reruns on: the input, its `mod` files for `.rs`, and for `.ai` its
+/// imports and `temp_js` (the kept compile output) when given.
+pub fn exec_watch_targets(file: &Path, temp_js: Option<&Path>) -> Vec<PathBuf> {
+    let mut out = vec![file.to_path_buf()];
+    match file.extension().and_then(|e| e.to_str()) {
+        Some("rs") => out.extend(rust_mod_deps(file)),
+        Some("ai") => {
+            out = ai_watch_targets(file);
+            out.extend(temp_js.map(Path::to_path_buf));
+        }
+        _ => {}
+    }
+    out
+}
+
+/// What an `.ai` entry point's `--watch` loop reruns on: the entry file and every file it
+/// imports, directly or through another import, as [`imports::resolve`] loads them. When
+/// the imports do not resolve, the file at fault (the missing import, the one that does not
+/// parse, or the files of a cycle) is watched instead, so fixing it triggers the next run.
+pub fn ai_watch_targets(entry: &Path) -> Vec<PathBuf> {
+    let mut out = vec![entry.to_path_buf()];
+    let Ok(source) = std::fs::read_to_string(entry) else { return out };
+    let Ok(settings) = LexerSettings::discover(entry) else { return out };
+    let options = settings.lexer_options();
+    let Ok(tokens) = Lexer::with_options(&source, options.clone()).tokenize() else { return out };
+    let Ok(ast) = Parser::new(tokens).parse() else { return out };
+    match imports::resolve(ast, entry, &source, &options) {
+        Ok(flat) => out.extend(flat.imported.into_iter().map(|f| f.path)),
+        Err(e) => match *e {
+            ImportError::NotFound { path, .. } => out.push(path),
+            ImportError::Lex { file, .. } | ImportError::Parse { file, .. } => out.push(PathBuf::from(file)),
+            ImportError::Cycle { chain, .. } => out.extend(chain),
+        },
+    }
+    out
+}
+
+/// [`ai_watch_targets`] of every `.ai` file in `targets`, the other files as they are:
+/// what the shell's `watch` tracks. Duplicates are dropped.
+pub fn with_ai_imports(targets: &[PathBuf]) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    for t in targets {
+        let found = if t.extension().is_some_and(|e| e == "ai") { ai_watch_targets(t) } else { vec![t.clone()] };
+        for path in found {
+            if seen.insert(absolute(&path)) {
+                out.push(path);
+            }
+        }
+    }
+    out
+}
+
+/// `emit/run/native --watch`: [`run_loop`] over `run`, waking when one of the entry's
+/// [`ai_watch_targets`] changes. Targets are collected again before each wait, so files
+/// the entry starts depending on are picked up without restarting. The runs never write
+/// their own targets, so nothing is drained: a save made during a run triggers the next.
+pub fn watch_ai(entry: &Path, run: impl FnMut(usize) -> anyhow::Result<()>) -> anyhow::Result<()> {
+    let mut watcher = Watcher::new(&ai_watch_targets(entry))?;
+    let listener = crate::core::shutdown::listen();
+    let wait = |stop: &AtomicBool| {
+        for target in ai_watch_targets(entry) {
+            // a target that vanished is picked up again once it is back
+            let _ = watcher.add(&target);
+        }
+        watcher.wait(stop)
+    };
+    run_loop(listener.token(), &LoopOptions::from_env(), wait, run);
+    Ok(())
+}
+
+/// One-line summary printed after each watched run: `[watch] run 3 · 41ms · ok`.
+pub fn status_line(run: usize, elapsed: Duration, result: &anyhow::Result<()>, st: &Style) -> String {
+    let dot = st.glyph("·", "-");
+    let outcome = match result {
+        Ok(()) => "ok".to_string(),
+        Err(e) => format!("exit {}", code_for(e)),
+    };
+    let line = format!("[watch] run {run} {dot} {}ms {dot} {outcome}", elapsed.as_millis());
+    st.paint(&line, if result.is_ok() { Role::Section } else { Role::Error })
+}
+
+/// Settings for [`run_loop`].
+#[derive(Debug, Clone, Default)]
+pub struct LoopOptions {
+    /// Clear the screen before every run after the first.
+    pub clear: bool,
+    /// Stop after the first run (`AEONMI_WATCH_ONCE=1`, used by tests).
+    pub once: bool,
+    /// Removed on the way out if they still exist (e.g. `__exec_tmp.js` without `--keep-temp`).
+    pub temp_files: Vec<PathBuf>,
+}
+
+impl LoopOptions {
+    pub fn from_env() -> Self {
+        LoopOptions { once: std::env::var("AEONMI_WATCH_ONCE").ok().as_deref() == Some("1"), ..Default::default() }
+    }
+}
+
+/// What a finished [`run_loop`] did: `[watch] stopped after 3 runs (1 failed)`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub runs: usize,
+    pub failed: usize,
+    /// Temp files that existed at teardown and were deleted.
+    pub removed: Vec<PathBuf>,
+}
+
+impl Summary {
+    pub fn line(&self, st: &Style) -> String {
+        let plural = if self.runs == 1 { "" } else { "s" };
+        let mut line = format!("[watch] stopped after {} run{plural}", self.runs);
+        if self.failed > 0 {
+            line.push_str(&format!(" ({} failed)", self.failed));
+        }
+        if !self.removed.is_empty() {
+            let names: Vec<String> = self.removed.iter().map(|p| p.display().to_string()).collect();
+            line.push_str(&format!("; removed {}", names.join(", ")));
+        }
+        st.paint(&line, Role::Section)
+    }
+}
+
+/// The loop behind the `--watch` commands: call `run`, print its [`status_line`], then
+/// `wait` for the next change, until `stop` is raised (between runs or while waiting) or
+/// the wait gives up. Teardown always runs: leftover `temp_files` are removed, metrics
+/// are flushed and the summary line is printed.
+pub fn run_loop(
+    stop: &ShutdownToken,
+    opts: &LoopOptions,
+    mut wait: impl FnMut(&AtomicBool) -> Wake,
+    mut run: impl FnMut(usize) -> anyhow::Result<()>,
+) -> Summary {
+    let st = crate::core::style::current();
+    let mut summary = Summary::default();
+    while !stop.is_requested() {
+        let n = summary.runs + 1;
+        if opts.clear && n > 1 {
+            print!("{CLEAR_SCREEN}");
+        }
+        let started = Instant::now();
+        let result = run(n);
+        summary.runs = n;
+        if let Err(e) = &result {
+            summary.failed += 1;
+            if !is_reported(e) && !crate::core::diagnostics::quiet() {
+                eprintln!("{} {e:#}", st.paint("error:", Role::Error));
+            }
+        }
+        println!("{}", status_line(n, started.elapsed(), &result, &st));
+        if opts.once || wait(stop.flag()) == Wake::Stopped {
+            break;
+        }
+    }
+    summary.removed = opts.temp_files.iter().filter(|p| std::fs::remove_file(p).is_ok()).cloned().collect();
+    crate::core::incremental::force_persist_metrics();
+    println!("{}", summary.line(&st));
+    summary
+}
+
+/// Why [`Watcher::wait`] returned.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Wake {
+    /// One or more targets changed; paths are in the order first seen.
+    Changed(Vec<PathBuf>),
+    /// The stop flag was raised.
+    Stopped,
+}
+
+pub struct Watcher {
+    inner: RecommendedWatcher,
+    rx: Receiver<notify::Result<Event>>,
+    dirs: HashSet<PathBuf>,
+    targets: HashSet<PathBuf>,
+}
+
+impl Watcher {
+    pub fn new(targets: &[PathBuf]) -> notify::Result<Self> {
+        let (tx, rx) = channel();
+        let inner = notify::recommended_watcher(tx)?;
+        let mut w = Watcher { inner, rx, dirs: HashSet::new(), targets: HashSet::new() };
+        for t in targets {
+            w.add(t)?;
+        }
+        Ok(w)
+    }
+
+    /// Start tracking another file (e.g. a dependency discovered after the first run).
+    pub fn add(&mut self, path: &Path) -> notify::Result<()> {
+        let abs = absolute(path);
+        let dir = abs.parent().map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from("."));
+        if self.dirs.insert(dir.clone()) {
+            self.inner.watch(&dir, RecursiveMode::NonRecursive)?;
+        }
+        self.targets.insert(abs);
+        Ok(())
+    }
+
+    /// Block until a target changes (then stay until events go quiet for [`DEBOUNCE`])
+    /// or `stop` is raised. Reads and metadata-only access never count as changes.
+    pub fn wait(&self, stop: &AtomicBool) -> Wake {
+        let mut changed: Vec<PathBuf> = Vec::new();
+        loop {
+            if stop.load(Ordering::SeqCst) {
+                return Wake::Stopped;
+            }
+            let timeout = if changed.is_empty() { POLL } else { DEBOUNCE };
+            match self.rx.recv_timeout(timeout) {
+                Ok(Ok(event)) => {
+                    if matches!(event.kind, EventKind::Access(_)) {
+                        continue;
+                    }
+                    for p in event.paths {
+                        let p = absolute(&p);
+                        if self.targets.contains(&p) && !changed.contains(&p) {
+                            changed.push(p);
+                        }
+                    }
+                }
+                Ok(Err(_)) => {}
+                Err(RecvTimeoutError::Timeout) if !changed.is_empty() => return Wake::Changed(changed),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return Wake::Stopped,
+            }
+        }
+    }
+
+    /// Discard pending events until none arrive for [`DEBOUNCE`], so files the run itself
+    /// wrote (e.g. the temp JS) do not immediately trigger the next run.
+    pub fn drain(&self) {
+        while self.rx.recv_timeout(DEBOUNCE).is_ok() {}
+    }
+}
+
+/// Canonical form when the file exists, else the parent canonicalized plus the file name,
+/// so a path reported for a just-deleted file still matches its target.
+fn absolute(path: &Path) -> PathBuf {
+    if let Ok(p) = path.canonicalize() {
+        return p;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) => {
+            let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+            dir.canonicalize().map(|d| d.join(name)).unwrap_or_else(|_| path.to_path_buf())
+        }
+        _ => path.to_path_buf(),
+    }
+}
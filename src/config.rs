@@ -1,4 +1,5 @@
 use anyhow::{Context, Result};
+use serde::Deserialize;
 use std::path::{Path, PathBuf};
 
 pub fn default_config_path() -> Option<PathBuf> {
@@ -21,3 +22,304 @@ pub fn ensure_parent_dir(path: &Path) -> Result<()> {
     }
     Ok(())
 }
+
+/// `[editor]` table of the config file (the same TOML as the QPoly rules).
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EditorSettings {
+    /// Run the formatter on the buffer before each save (off by default).
+    #[serde(default)]
+    pub format_on_save: bool,
+    /// Show the minimap column at the right edge of the buffer (off by default; F7 toggles it).
+    #[serde(default)]
+    pub minimap: bool,
+}
+
+impl EditorSettings {
+    pub fn from_toml_str(txt: &str) -> Result<Self, toml::de::Error> {
+        #[derive(Deserialize, Default)]
+        struct Cfg {
+            #[serde(default)]
+            editor: EditorSettings,
+        }
+        Ok(toml::from_str::<Cfg>(txt)?.editor)
+    }
+
+    /// Settings from `path` (or the default config); missing or unreadable files give the defaults.
+    pub fn load(path: Option<&Path>) -> Self {
+        let path = path.map(Path::to_path_buf).or_else(default_config_path);
+        path.and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|txt| Self::from_toml_str(&txt).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// `[stats]` table of the user config: local usage counters (see `core::usage_stats`).
+///
+/// ```toml
+/// [stats]
+/// enabled = true
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StatsSettings {
+    /// Count subcommand invocations on this machine (off by default).
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+impl StatsSettings {
+    pub fn from_toml_str(txt: &str) -> Result<Self, toml::de::Error> {
+        #[derive(Deserialize, Default)]
+        struct Cfg {
+            #[serde(default)]
+            stats: StatsSettings,
+        }
+        Ok(toml::from_str::<Cfg>(txt)?.stats)
+    }
+
+    /// Settings from `path` (or the default config); missing or unreadable files give the defaults.
+    pub fn load(path: Option<&Path>) -> Self {
+        let path = path.map(Path::to_path_buf).or_else(default_config_path);
+        path.and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|txt| Self::from_toml_str(&txt).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// `[ai]` table of the user config: the providers `ai chat` falls back on, in order, when
+/// one is unreachable, overloaded or rate limited. Names that are not enabled are skipped.
+///
+/// ```toml
+/// [ai]
+/// fallback = ["openai", "deepseek", "mock"]
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AiSettings {
+    #[serde(default)]
+    pub fallback: Vec<String>,
+}
+
+impl AiSettings {
+    pub fn from_toml_str(txt: &str) -> Result<Self, toml::de::Error> {
+        #[derive(Deserialize, Default)]
+        struct Cfg {
+            #[serde(default)]
+            ai: AiSettings,
+        }
+        Ok(toml::from_str::<Cfg>(txt)?.ai)
+    }
+
+    /// Settings from `path` (or the default config); missing or unreadable files give the defaults.
+    pub fn load(path: Option<&Path>) -> Self {
+        let path = path.map(Path::to_path_buf).or_else(default_config_path);
+        path.and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|txt| Self::from_toml_str(&txt).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// `[lexer.plugins]` table of the project manifest (`Aeonmi.toml`): built-in plugin name → enabled.
+///
+/// ```toml
+/// [lexer.plugins]
+/// emoji-ops = true
+/// dlp-redactor = true
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LexerSettings {
+    #[serde(default)]
+    pub plugins: std::collections::BTreeMap<String, bool>,
+}
+
+impl LexerSettings {
+    pub const MANIFEST: &'static str = "Aeonmi.toml";
+
+    pub fn from_toml_str(txt: &str) -> Result<Self, toml::de::Error> {
+        #[derive(Deserialize, Default)]
+        struct Cfg {
+            #[serde(default)]
+            lexer: LexerSettings,
+        }
+        Ok(toml::from_str::<Cfg>(txt)?.lexer)
+    }
+
+    /// Settings from the nearest `Aeonmi.toml` in `source`'s directory or above.
+    /// No manifest gives the defaults; an unreadable or malformed one is an error.
+    pub fn discover(source: &Path) -> Result<Self> {
+        let Some(manifest) = nearest_manifest(source) else {
+            return Ok(Self::default());
+        };
+        let txt = std::fs::read_to_string(&manifest)
+            .with_context(|| format!("read {}", manifest.display()))?;
+        let settings = Self::from_toml_str(&txt)
+            .with_context(|| format!("parse {}", manifest.display()))?;
+        crate::core::lexer_plugins::validate(&settings.enabled())
+            .map_err(|e| anyhow::anyhow!("{}: {}", manifest.display(), e))?;
+        Ok(settings)
+    }
+
+    /// Names of the plugins switched on, in name order.
+    pub fn enabled(&self) -> Vec<String> {
+        self.plugins.iter().filter(|(_, on)| **on).map(|(n, _)| n.clone()).collect()
+    }
+
+    pub fn lexer_options(&self) -> crate::core::lexer::LexerOptions {
+        crate::core::lexer::LexerOptions { plugins: self.enabled(), ..Default::default() }
+    }
+}
+
+/// Directory of the nearest `Aeonmi.toml` above `source`, or the current directory.
+pub fn project_root(source: &Path) -> PathBuf {
+    nearest_manifest(source)
+        .and_then(|m| m.parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")))
+}
+
+/// The nearest `Aeonmi.toml` in `source`'s directory or above.
+pub fn nearest_manifest(source: &Path) -> Option<PathBuf> {
+    let start = source.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let start = start.canonicalize().unwrap_or_else(|_| start.to_path_buf());
+    start
+        .ancestors()
+        .map(|dir| dir.join(LexerSettings::MANIFEST))
+        .find(|manifest| manifest.is_file())
+}
+
+/// `[defaults]` table of the user config or the project manifest: default flag values per
+/// subcommand, keyed by flag name (`pretty_errors` or `pretty-errors`). Scalars directly under
+/// `[defaults]` apply to every command; nested tables follow the subcommand path. See
+/// `commands::defaults` for how they are merged and applied.
+///
+/// ```toml
+/// [defaults]
+/// pretty_errors = true
+///
+/// [defaults.run]
+/// native = true
+///
+/// [defaults.ai.review]
+/// offline = true
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct DefaultSettings {
+    pub table: toml::Table,
+}
+
+impl DefaultSettings {
+    pub fn from_toml_str(txt: &str) -> Result<Self, toml::de::Error> {
+        #[derive(Deserialize, Default)]
+        struct Cfg {
+            #[serde(default)]
+            defaults: DefaultSettings,
+        }
+        Ok(toml::from_str::<Cfg>(txt)?.defaults)
+    }
+}
+
+/// `[secrets]` table of the project manifest: environment variable → vault entry.
+/// Entries are only read when a command injects secrets (see `commands::secrets`).
+///
+/// ```toml
+/// [secrets]
+/// REGISTRAR_TOKEN = "example.com"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct SecretSettings {
+    pub vars: std::collections::BTreeMap<String, String>,
+}
+
+impl SecretSettings {
+    pub fn from_toml_str(txt: &str) -> Result<Self, toml::de::Error> {
+        #[derive(Deserialize, Default)]
+        struct Cfg {
+            #[serde(default)]
+            secrets: SecretSettings,
+        }
+        Ok(toml::from_str::<Cfg>(txt)?.secrets)
+    }
+
+    /// Mapping from the nearest `Aeonmi.toml` (see [`LexerSettings::discover`]).
+    pub fn discover(source: &Path) -> Result<Self> {
+        let Some(manifest) = nearest_manifest(source) else {
+            return Ok(Self::default());
+        };
+        let txt = std::fs::read_to_string(&manifest)
+            .with_context(|| format!("read {}", manifest.display()))?;
+        Self::from_toml_str(&txt).with_context(|| format!("parse {}", manifest.display()))
+    }
+}
+
+/// `[format]` table of the project manifest: `aeonmi format` options. Unset keys keep the
+/// formatter's defaults; `format`'s flags override them per run.
+///
+/// ```toml
+/// [format]
+/// indent_width = 2
+/// max_line_width = 100   # 0: never wrap
+/// glyphs = "ascii"       # preserve | ascii | unicode
+/// always_semicolons = true
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct FormatSettings {
+    pub indent_width: Option<usize>,
+    pub max_line_width: Option<usize>,
+    pub glyphs: Option<crate::core::formatter::GlyphMode>,
+    pub always_semicolons: Option<bool>,
+}
+
+impl FormatSettings {
+    /// Widest indent accepted; anything more is almost certainly a typo.
+    pub const MAX_INDENT_WIDTH: usize = 16;
+
+    pub fn from_toml_str(txt: &str) -> Result<Self, toml::de::Error> {
+        #[derive(Deserialize, Default)]
+        struct Cfg {
+            #[serde(default)]
+            format: FormatSettings,
+        }
+        Ok(toml::from_str::<Cfg>(txt)?.format)
+    }
+
+    /// Settings from the nearest `Aeonmi.toml` (see [`LexerSettings::discover`]).
+    pub fn discover(source: &Path) -> Result<Self> {
+        let Some(manifest) = nearest_manifest(source) else {
+            return Ok(Self::default());
+        };
+        let txt = std::fs::read_to_string(&manifest)
+            .with_context(|| format!("read {}", manifest.display()))?;
+        let settings = Self::from_toml_str(&txt).with_context(|| format!("parse {}", manifest.display()))?;
+        settings.validate().map_err(|e| anyhow::anyhow!("{}: [format] {}", manifest.display(), e))?;
+        Ok(settings)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self.indent_width {
+            Some(w) if w == 0 || w > Self::MAX_INDENT_WIDTH => {
+                Err(format!("indent_width must be between 1 and {}, not {w}", Self::MAX_INDENT_WIDTH))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// `self`'s keys, falling back on `base` for the ones it leaves unset.
+    pub fn or(self, base: FormatSettings) -> FormatSettings {
+        FormatSettings {
+            indent_width: self.indent_width.or(base.indent_width),
+            max_line_width: self.max_line_width.or(base.max_line_width),
+            glyphs: self.glyphs.or(base.glyphs),
+            always_semicolons: self.always_semicolons.or(base.always_semicolons),
+        }
+    }
+
+    pub fn format_options(&self) -> crate::core::formatter::FormatOptions {
+        let defaults = crate::core::formatter::FormatOptions::default();
+        crate::core::formatter::FormatOptions {
+            indent_width: self.indent_width.unwrap_or(defaults.indent_width),
+            max_line_width: self.max_line_width.or(defaults.max_line_width).filter(|&w| w > 0),
+            glyphs: self.glyphs.unwrap_or(defaults.glyphs),
+            always_semicolons: self.always_semicolons.unwrap_or(defaults.always_semicolons),
+            ..defaults
+        }
+    }
+}
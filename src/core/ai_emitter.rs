@@ -34,9 +34,14 @@ impl AiEmitter {
 /// Produces deterministic output (sorted decls/imports, 2-space indent, LF)
 /// Header includes a simple FNV-1a 64-bit hash of the body.
 pub fn emit_ai(module: &Module) -> String {
+    emit_ai_mapped(module).0
+}
+
+/// As [`emit_ai`], plus the 1-based inclusive output lines of each `fn` decl.
+pub fn emit_ai_mapped(module: &Module) -> (String, Vec<(String, usize, usize)>) {
     // 1) Body
     let mut body = String::new();
-    write_module(&mut body, module);
+    let fns = write_module(&mut body, module);
 
     // 2) Hash header
     let hash = fnv1a64(body.as_bytes());
@@ -47,11 +52,19 @@ pub fn emit_ai(module: &Module) -> String {
     writeln!(&mut out, "// hash:{:016x}", hash).unwrap();
     writeln!(&mut out, "// tool:aeonmi unknown").unwrap();
     out.push('\n');
+    let header_lines = out.lines().count();
     out.push_str(&body);
-    out
+    let fns = fns.into_iter().map(|(n, a, b)| (n, a + header_lines, b + header_lines)).collect();
+    (out, fns)
+}
+
+fn line_of(dst: &str) -> usize {
+    dst.matches('\n').count() + 1
 }
 
-fn write_module(dst: &mut String, m: &Module) {
+/// Returns the body lines (1-based, inclusive) each function decl occupies.
+fn write_module(dst: &mut String, m: &Module) -> Vec<(String, usize, usize)> {
+    let mut fns = Vec::new();
     // Imports first (re-sort defensively)
     let mut imports = m.imports.clone();
     imports.sort_by(|a, b| {
@@ -87,6 +100,7 @@ fn write_module(dst: &mut String, m: &Module) {
                 dst.push_str(";\n");
             }
             Decl::Fn(f) => {
+                let start = line_of(dst);
                 write!(dst, "fn {}(", escape_sym(&f.name)).unwrap();
                 for (pi, p) in f.params.iter().enumerate() {
                     if pi > 0 {
@@ -96,6 +110,7 @@ fn write_module(dst: &mut String, m: &Module) {
                 }
                 dst.push_str(") ");
                 write_block(dst, &f.body, 0);
+                fns.push((f.name.clone(), start, line_of(dst)));
                 dst.push('\n');
             }
         }
@@ -103,6 +118,7 @@ fn write_module(dst: &mut String, m: &Module) {
             dst.push('\n');
         }
     }
+    fns
 }
 
 fn write_block(dst: &mut String, b: &Block, indent: usize) {
@@ -122,7 +138,7 @@ fn write_stmt(dst: &mut String, s: &Stmt, indent: usize) {
             write_expr(dst, e, indent);
             dst.push_str(";\n");
         }
-        Stmt::Let { name, value } => {
+        Stmt::Let { name, value, .. } => {
             indent_spaces(dst, indent);
             write!(dst, "let {}", escape_sym(name)).unwrap();
             if let Some(v) = value {
@@ -131,13 +147,52 @@ fn write_stmt(dst: &mut String, s: &Stmt, indent: usize) {
             }
             dst.push_str(";\n");
         }
-        Stmt::Assign { target, value } => {
+        // Lowering pairs the targets with an array of the values, so the source form comes back.
+        Stmt::Destructure { pattern, value, .. } => {
+            indent_spaces(dst, indent);
+            dst.push_str("let ");
+            match (pattern, value) {
+                (Pattern::Array(targets), crate::core::ir::Expr::Array(values)) if targets.len() == values.len() => {
+                    for (i, t) in targets.iter().enumerate() {
+                        if i > 0 {
+                            dst.push_str(", ");
+                        }
+                        write_pattern(dst, t);
+                    }
+                    dst.push_str(" = ");
+                    for (i, v) in values.iter().enumerate() {
+                        if i > 0 {
+                            dst.push_str(", ");
+                        }
+                        write_expr(dst, v, indent);
+                    }
+                }
+                _ => {
+                    write_pattern(dst, pattern);
+                    dst.push_str(" = ");
+                    write_expr(dst, value, indent);
+                }
+            }
+            dst.push_str(";\n");
+        }
+        Stmt::Assign { target, value, .. } => {
             indent_spaces(dst, indent);
             write_expr(dst, target, indent);
             dst.push_str(" = ");
             write_expr(dst, value, indent);
             dst.push_str(";\n");
         }
+        Stmt::Log { args, .. } => {
+            indent_spaces(dst, indent);
+            dst.push_str("log(");
+            for (i, a) in args.iter().enumerate() {
+                if i > 0 {
+                    dst.push_str(", ");
+                }
+                write_expr(dst, a, indent);
+            }
+            dst.push_str(");\n");
+        }
         Return(None) => {
             indent_spaces(dst, indent);
             dst.push_str("return;\n");
@@ -169,10 +224,39 @@ fn write_stmt(dst: &mut String, s: &Stmt, indent: usize) {
             write_block(dst, body, indent);
             dst.push('\n');
         }
+        Block(b) => {
+            indent_spaces(dst, indent);
+            write_block(dst, b, indent);
+            dst.push('\n');
+        }
+        Break => {
+            indent_spaces(dst, indent);
+            dst.push_str("break;\n");
+        }
+        Continue => {
+            indent_spaces(dst, indent);
+            dst.push_str("continue;\n");
+        }
         _ => { /* extend as needed */ }
     }
 }
 
+fn write_pattern(dst: &mut String, p: &Pattern) {
+    match p {
+        Pattern::Name(name) => dst.push_str(&escape_sym(name)),
+        Pattern::Array(items) => {
+            dst.push('[');
+            for (i, it) in items.iter().enumerate() {
+                if i > 0 {
+                    dst.push_str(", ");
+                }
+                write_pattern(dst, it);
+            }
+            dst.push(']');
+        }
+    }
+}
+
 fn indent_spaces(dst: &mut String, count: usize) {
     for _ in 0..count {
         dst.push(' ');
@@ -214,21 +298,51 @@ fn escape_sym(s: &str) -> String {
 }
 
 // Fixed: Removed underscore from the indent parameter
+/// Binding strength of `op` in the parser's grammar (higher binds tighter).
+fn precedence(op: &BinOp) -> u8 {
+    use BinOp::*;
+    match op {
+        Or => 1,
+        And => 2,
+        Eq | Ne => 3,
+        Lt | Le | Gt | Ge => 4,
+        Add | Sub => 5,
+        Mul | Div | Mod => 6,
+        Pow => 7,
+    }
+}
+
 fn write_expr(dst: &mut String, e: &Expr, indent: usize) {
     match e {
         Expr::Lit(l) => match l {
             crate::core::ir::Lit::Null => dst.push_str("null"),
             crate::core::ir::Lit::Bool(b) => dst.push_str(&format!("{}", b)),
             crate::core::ir::Lit::Number(n) => {
-                if n.fract() == 0.0 {
+                if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
                     dst.push_str(&format!("{}", *n as i64));
                 } else {
                     dst.push_str(&format!("{}", n));
                 }
             }
-            crate::core::ir::Lit::String(s) => dst.push_str(&format!("\"{}\"", s)),
+            // literal `${` must not come back as interpolation
+            crate::core::ir::Lit::String(s) => dst.push_str(&format!("\"{}\"", s.replace("${", "\\${"))),
         },
         Expr::Ident(s) => dst.push_str(s),
+        // `__push("xs", v)` / `__pop("xs", line, column)` are lowered `push(xs, v)` / `pop(xs)`
+        Expr::Call { callee, args }
+            if matches!(&**callee, Expr::Ident(f) if f == "__push" || f == "__pop")
+                && matches!(args.first(), Some(Expr::Lit(crate::core::ir::Lit::String(_)))) =>
+        {
+            let (Expr::Ident(f), Some(Expr::Lit(crate::core::ir::Lit::String(var)))) = (&**callee, args.first()) else { unreachable!() };
+            dst.push_str(&format!("{}({}", &f[2..], var));
+            if f == "__push" {
+                for a in &args[1..] {
+                    dst.push_str(", ");
+                    write_expr(dst, a, indent);
+                }
+            }
+            dst.push(')');
+        }
         Expr::Call { callee, args } => {
             write_expr(dst, callee, indent);
             dst.push('(');
@@ -241,11 +355,31 @@ fn write_expr(dst: &mut String, e: &Expr, indent: usize) {
             dst.push(')');
         }
         Expr::Binary { left, op, right } => {
-            write_expr(dst, left, indent);
+            // a looser left operand needs parentheses; `**` is right-associative and binds
+            // tighter than unary minus, so any compound left operand does
+            let wrap = match &**left {
+                Expr::Binary { op: inner, .. } => precedence(inner) < precedence(op) || *op == BinOp::Pow,
+                Expr::Unary { .. } => *op == BinOp::Pow,
+                _ => false,
+            };
+            if wrap {
+                dst.push('(');
+                write_expr(dst, left, indent);
+                dst.push(')');
+            } else {
+                write_expr(dst, left, indent);
+            }
             dst.push(' ');
             dst.push_str(&format!("{}", op));
             dst.push(' ');
-            write_expr(dst, right, indent);
+            // operators are left-associative, so a binary right operand had parentheses
+            if matches!(**right, Expr::Binary { .. }) {
+                dst.push('(');
+                write_expr(dst, right, indent);
+                dst.push(')');
+            } else {
+                write_expr(dst, right, indent);
+            }
         }
         Expr::Unary { op, expr } => {
             use crate::core::ir::UnOp;
@@ -254,7 +388,24 @@ fn write_expr(dst: &mut String, e: &Expr, indent: usize) {
                 UnOp::Not => "!",
             };
             dst.push_str(s);
-            write_expr(dst, expr, indent);
+            if matches!(**expr, Expr::Binary { .. }) {
+                dst.push('(');
+                write_expr(dst, expr, indent);
+                dst.push(')');
+            } else {
+                write_expr(dst, expr, indent);
+            }
+        }
+        Expr::Index { target, index } => {
+            write_expr(dst, target, indent);
+            dst.push('[');
+            write_expr(dst, index, indent);
+            dst.push(']');
+        }
+        Expr::Member { target, name } => {
+            write_expr(dst, target, indent);
+            dst.push('.');
+            dst.push_str(&escape_sym(name));
         }
         Expr::Array(items) => {
             dst.push('[');
@@ -272,7 +423,8 @@ fn write_expr(dst: &mut String, e: &Expr, indent: usize) {
                 if i > 0 {
                     dst.push_str(", ");
                 }
-                dst.push_str(&escape_sym(k));
+                // keys are always written quoted so a keyword-named key re-parses
+                write_expr(dst, &Expr::Lit(crate::core::ir::Lit::String(k.clone())), indent);
                 dst.push_str(": ");
                 write_expr(dst, v, indent);
             }
@@ -0,0 +1,200 @@
+//! AI usage ledger: one JSON line per provider call, plus rollups and cost estimates.
+//!
+//! The ledger lives at `<config dir>/aeonmi/ai_usage.jsonl` (or `$AEONMI_CONFIG_DIR/ai_usage.jsonl`).
+//! Writing is best effort: [`record`] swallows every error so accounting can never
+//! break a chat call. Prices come from the `[ai.prices]` table of the config file:
+//!
+//! ```toml
+//! [ai.prices."gpt-4o-mini"]
+//! prompt_per_1k = 0.00015
+//! completion_per_1k = 0.0006
+//! ```
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Token counts as reported by the provider API.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageRecord {
+    /// Unix seconds when the call finished.
+    pub ts: u64,
+    pub provider: String,
+    pub model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completion_tokens: Option<u64>,
+    pub wall_ms: u64,
+    pub ok: bool,
+}
+
+impl UsageRecord {
+    pub fn new(provider: &str, model: &str, usage: Option<TokenUsage>, wall_ms: u64, ok: bool) -> Self {
+        Self {
+            ts: now_secs(),
+            provider: provider.to_string(),
+            model: model.to_string(),
+            prompt_tokens: usage.map(|u| u.prompt_tokens),
+            completion_tokens: usage.map(|u| u.completion_tokens),
+            wall_ms,
+            ok,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub struct ModelPrice {
+    #[serde(default)]
+    pub prompt_per_1k: f64,
+    #[serde(default)]
+    pub completion_per_1k: f64,
+}
+
+/// Per-model prices in the config's currency (USD by convention).
+#[derive(Debug, Clone, Default)]
+pub struct PriceTable {
+    pub models: BTreeMap<String, ModelPrice>,
+}
+
+impl PriceTable {
+    /// Read `[ai.prices]` from a TOML config; a missing file or table yields an empty table.
+    pub fn from_config(path: Option<&Path>) -> anyhow::Result<Self> {
+        let Some(path) = path.filter(|p| p.exists()) else { return Ok(Self::default()) };
+        let txt = fs::read_to_string(path)?;
+        Self::from_toml_str(&txt).map_err(|e| anyhow::anyhow!("parsing {}: {e}", path.display()))
+    }
+
+    pub fn from_toml_str(txt: &str) -> Result<Self, toml::de::Error> {
+        #[derive(Deserialize, Default)]
+        struct Cfg { #[serde(default)] ai: AiCfg }
+        #[derive(Deserialize, Default)]
+        struct AiCfg { #[serde(default)] prices: BTreeMap<String, ModelPrice> }
+        let cfg: Cfg = toml::from_str(txt)?;
+        Ok(Self { models: cfg.ai.prices })
+    }
+
+    /// Estimated cost of one call; `None` when the model is unpriced or tokens weren't reported.
+    pub fn cost(&self, rec: &UsageRecord) -> Option<f64> {
+        let price = self.models.get(&rec.model)?;
+        let (p, c) = (rec.prompt_tokens?, rec.completion_tokens.unwrap_or(0));
+        Some(p as f64 / 1000.0 * price.prompt_per_1k + c as f64 / 1000.0 * price.completion_per_1k)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageRollup {
+    pub calls: usize,
+    pub failures: usize,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub wall_ms: u64,
+    /// Sum over calls that could be priced.
+    pub est_cost: f64,
+    /// Calls with no price or no token counts (not included in `est_cost`).
+    pub unpriced_calls: usize,
+}
+
+impl UsageRollup {
+    fn add(&mut self, rec: &UsageRecord, prices: &PriceTable) {
+        self.calls += 1;
+        if !rec.ok { self.failures += 1; }
+        self.prompt_tokens += rec.prompt_tokens.unwrap_or(0);
+        self.completion_tokens += rec.completion_tokens.unwrap_or(0);
+        self.wall_ms += rec.wall_ms;
+        match prices.cost(rec) {
+            Some(c) => self.est_cost += c,
+            None => self.unpriced_calls += 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageSummary {
+    /// Only records with `ts >= since` were counted (None = all).
+    pub since: Option<u64>,
+    pub total: UsageRollup,
+    /// Keyed by `provider/model`.
+    pub by_model: BTreeMap<String, UsageRollup>,
+}
+
+pub fn ledger_path() -> PathBuf {
+    if let Ok(base) = std::env::var("AEONMI_CONFIG_DIR") { return PathBuf::from(base).join("ai_usage.jsonl"); }
+    dirs_next::config_dir().unwrap_or(std::env::temp_dir()).join("aeonmi").join("ai_usage.jsonl")
+}
+
+pub fn append(path: &Path, rec: &UsageRecord) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() { fs::create_dir_all(dir)?; }
+    let mut line = serde_json::to_string(rec).map_err(std::io::Error::other)?;
+    line.push('\n');
+    // single write so concurrent appenders don't interleave within a line
+    OpenOptions::new().create(true).append(true).open(path)?.write_all(line.as_bytes())
+}
+
+/// Append to the default ledger, ignoring failures (accounting must not break the caller).
+pub fn record(rec: &UsageRecord) {
+    if let Err(e) = append(&ledger_path(), rec) {
+        if crate::core::debug::is_enabled() { eprintln!("ai usage ledger write failed: {e}"); }
+    }
+}
+
+/// Load every readable record; malformed lines (e.g. a torn write) are skipped.
+pub fn load(path: &Path) -> Vec<UsageRecord> {
+    let Ok(txt) = fs::read_to_string(path) else { return Vec::new() };
+    txt.lines().filter_map(|l| serde_json::from_str(l).ok()).collect()
+}
+
+pub fn summarize(records: &[UsageRecord], since: Option<u64>, prices: &PriceTable) -> UsageSummary {
+    let mut s = UsageSummary { since, ..Default::default() };
+    for rec in records.iter().filter(|r| since.is_none_or(|t| r.ts >= t)) {
+        s.total.add(rec, prices);
+        s.by_model.entry(format!("{}/{}", rec.provider, rec.model)).or_default().add(rec, prices);
+    }
+    s
+}
+
+/// Parse a lookback like `30m`, `12h`, `7d` or `2w` into seconds.
+pub fn parse_since(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (num, unit) = s.split_at(split);
+    let n: u64 = num.parse().map_err(|_| format!("invalid duration '{s}' (expected e.g. 7d, 12h)"))?;
+    let mult = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" | "" => 3600,
+        "d" => 86_400,
+        "w" => 604_800,
+        _ => return Err(format!("unknown duration unit '{unit}' (use s, m, h, d or w)")),
+    };
+    Ok(n * mult)
+}
+
+pub fn render_summary(s: &UsageSummary) -> String {
+    let mut out = String::new();
+    let row = |name: &str, r: &UsageRollup| {
+        format!(
+            "{:<32} {:>6} {:>6} {:>10} {:>10} {:>10}  {}\n",
+            name, r.calls, r.failures, r.prompt_tokens, r.completion_tokens, format!("{:.1}s", r.wall_ms as f64 / 1000.0),
+            if r.unpriced_calls == r.calls && r.calls > 0 { "n/a".to_string() } else { format!("${:.4}", r.est_cost) }
+        )
+    };
+    out.push_str(&format!("{:<32} {:>6} {:>6} {:>10} {:>10} {:>10}  {}\n", "provider/model", "calls", "failed", "prompt", "completion", "wall", "est. cost"));
+    for (name, r) in &s.by_model { out.push_str(&row(name, r)); }
+    out.push_str(&row("total", &s.total));
+    if s.total.unpriced_calls > 0 {
+        out.push_str(&format!("({} call(s) without a price or token counts; add [ai.prices] to the config)\n", s.total.unpriced_calls));
+    }
+    out
+}
+
+pub fn now_secs() -> u64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
@@ -0,0 +1,78 @@
+//! Cooperative time limit for the semantic analyzer and type inference.
+//!
+//! Both passes check an [`AnalysisBudget`] between top-level items; once it has run out
+//! they stop, keep what they found so far and record a [`Truncation`]. The GUI gives
+//! every keystroke's diagnostics a budget and completes the analysis in the background;
+//! the CLI only uses one under `--analysis-timeout-ms`.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::core::ast::ASTNode;
+
+/// `--analysis-timeout-ms` plus one; 0 means the flag was not given.
+static TIMEOUT_MS: AtomicU64 = AtomicU64::new(0);
+
+/// `--analysis-timeout-ms`: budget for CLI analysis passes (`None` = unlimited).
+pub fn set_timeout_ms(ms: Option<u64>) {
+    TIMEOUT_MS.store(ms.map_or(0, |ms| ms.saturating_add(1)), Ordering::Relaxed);
+}
+
+/// Called before each top-level item is analyzed.
+type ItemVisitor = Box<dyn FnMut(&ASTNode) + Send>;
+
+pub struct AnalysisBudget {
+    started: Instant,
+    limit: Duration,
+    visitor: Option<ItemVisitor>,
+}
+
+impl AnalysisBudget {
+    /// A budget of `limit`, counted from now.
+    pub fn new(limit: Duration) -> Self {
+        Self { started: Instant::now(), limit, visitor: None }
+    }
+
+    /// The `--analysis-timeout-ms` budget, if the flag was given.
+    pub fn from_flag() -> Option<Self> {
+        match TIMEOUT_MS.load(Ordering::Relaxed) {
+            0 => None,
+            ms => Some(Self::new(Duration::from_millis(ms - 1))),
+        }
+    }
+
+    /// Run `f` before each item is analyzed; tests pass a slow one to force truncation.
+    pub fn with_item_visitor(mut self, f: impl FnMut(&ASTNode) + Send + 'static) -> Self {
+        self.visitor = Some(Box::new(f));
+        self
+    }
+
+    /// Whether item `index` of `items` may still be analyzed. Past the deadline this is the
+    /// [`Truncation`] to report instead.
+    pub fn admit(&mut self, index: usize, items: &[ASTNode]) -> Result<(), Truncation> {
+        let elapsed = self.started.elapsed();
+        if elapsed >= self.limit {
+            return Err(Truncation { analyzed: index, total: items.len(), elapsed_ms: elapsed.as_millis() });
+        }
+        if let Some(visit) = &mut self.visitor {
+            visit(&items[index]);
+        }
+        Ok(())
+    }
+}
+
+/// Where an analysis pass stopped: `analyzed` of `total` top-level items.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Truncation {
+    pub analyzed: usize,
+    pub total: usize,
+    pub elapsed_ms: u128,
+}
+
+impl std::fmt::Display for Truncation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "analysis truncated after {} of {} items (took {} ms)", self.analyzed, self.total, self.elapsed_ms)
+    }
+}
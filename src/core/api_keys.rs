@@ -40,7 +40,7 @@ fn fallback_sha256(user: &str, host: &str, salt: &[u8]) -> [u8;32] {
     let mut hasher = Sha256::new(); hasher.update(user.as_bytes()); hasher.update(host.as_bytes()); hasher.update(salt); let out = hasher.finalize(); let mut arr=[0u8;32]; arr.copy_from_slice(&out[..32]); arr
 }
 
-fn storage_path() -> PathBuf {
+pub fn storage_path() -> PathBuf {
     if let Ok(base) = std::env::var("AEONMI_CONFIG_DIR") { return PathBuf::from(base).join("keys.json"); }
     config_dir().unwrap_or(std::env::temp_dir()).join("aeonmi").join("keys.json")
 }
@@ -2,9 +2,11 @@
 //! Includes Assignment and Call nodes to support expression statements.
 
 use crate::core::token::TokenKind;
+use serde::Serialize;
 
 /// Represents nodes in the Abstract Syntax Tree.
-#[derive(Debug, Clone, PartialEq)]
+/// Serializes externally tagged (`{"VariableDecl": {...}}`) for `export --format json`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
 #[allow(dead_code)] // Many variants used only in experimental passes / future features
 pub enum ASTNode {
     // Program root
@@ -23,10 +25,35 @@ pub enum ASTNode {
         line: usize,
         column: usize,
     },
+    /// `let a, b = 1, 2;` or `let [x, [y, z]] = pair();`: one target per value, each a name
+    /// or an array pattern. A plain `let x = e;` stays a `VariableDecl`.
+    DestructuringDecl {
+        targets: Vec<Pattern>,
+        values: Vec<ASTNode>,
+        line: usize,
+        column: usize,
+    },
+    /// `enum Status { Todo, Doing, Done }`; variants keep their declaration order.
+    EnumDecl {
+        name: String,
+        variants: Vec<String>,
+        line: usize,
+        column: usize,
+    },
+    /// `import "lib/util.ai";`, path as written; `core::imports` splices the file in before analysis.
+    Import {
+        path: String,
+        line: usize,
+        column: usize,
+    },
     // Statements / simple stmt-like exprs
     Block(Vec<ASTNode>),
     Return(Box<ASTNode>),
-    Log(Box<ASTNode>),
+    Log {
+        expr: Box<ASTNode>,
+        line: usize,
+        column: usize,
+    },
     // Control flow
     If {
         condition: Box<ASTNode>,
@@ -43,6 +70,26 @@ pub enum ASTNode {
         increment: Option<Box<ASTNode>>,
         body: Box<ASTNode>,
     },
+    /// `for x in xs { }` or `for k, v in xs { }`: `key` is the first name of the two-name form.
+    ForIn {
+        key: Option<String>,
+        var: String,
+        iterable: Box<ASTNode>,
+        body: Box<ASTNode>,
+        line: usize,
+        column: usize,
+    },
+    /// `break;`, only valid inside a loop.
+    Break { line: usize, column: usize },
+    /// `continue;`, only valid inside a loop.
+    Continue { line: usize, column: usize },
+    /// `match (subject) { pattern => stmt ... }`: runs the first arm whose pattern equals the subject.
+    Match {
+        subject: Box<ASTNode>,
+        arms: Vec<MatchArm>,
+        line: usize,
+        column: usize,
+    },
     // Expressions
     Assignment {
         name: String,
@@ -63,6 +110,32 @@ pub enum ASTNode {
         op: TokenKind,
         expr: Box<ASTNode>,
     },
+    /// `target[index]`, e.g. a register element `r[0]`.
+    Index {
+        target: Box<ASTNode>,
+        index: Box<ASTNode>,
+        line: usize,
+        column: usize,
+    },
+    /// `target.name`, e.g. an enum variant `Status.Done`.
+    Member {
+        target: Box<ASTNode>,
+        name: String,
+        line: usize,
+        column: usize,
+    },
+    /// `[1, 2, 3]`; a trailing comma is allowed.
+    ArrayLiteral {
+        items: Vec<ASTNode>,
+        line: usize,
+        column: usize,
+    },
+    /// `{ "k": v, name: w }`: keys are string literals or bare names, kept in source order.
+    ObjectLiteral {
+        fields: Vec<(String, ASTNode)>,
+        line: usize,
+        column: usize,
+    },
     #[allow(dead_code)]
     Identifier(String),
     IdentifierSpanned { name: String, line: usize, column: usize, len: usize },
@@ -70,9 +143,23 @@ pub enum ASTNode {
     StringLiteral(String),
     BooleanLiteral(bool),
     // Quantum & Hieroglyphic
+    QubitDecl {
+        name: String,
+        line: usize,
+        column: usize,
+    },
+    /// `qreg r[size];` — a named register of `size` qubits addressed as `r[i]`.
+    QregDecl {
+        name: String,
+        size: usize,
+        line: usize,
+        column: usize,
+    },
     QuantumOp {
         op: TokenKind,
         qubits: Vec<ASTNode>,
+        line: usize,
+        column: usize,
     },
     HieroglyphicOp {
         symbol: String,
@@ -83,13 +170,65 @@ pub enum ASTNode {
     Error(String),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct FunctionParam {
     pub name: String,
     pub line: usize,
     pub column: usize,
 }
 
+/// Left-hand side of a destructuring `let`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Pattern {
+    Name { name: String, line: usize, column: usize },
+    /// `[a, [b, c]]`: the value must have exactly one element per item.
+    Array { items: Vec<Pattern>, line: usize, column: usize },
+}
+
+impl Pattern {
+    /// Every name the pattern binds, left to right, with its position.
+    pub fn names(&self) -> Vec<(&str, usize, usize)> {
+        match self {
+            Pattern::Name { name, line, column } => vec![(name.as_str(), *line, *column)],
+            Pattern::Array { items, .. } => items.iter().flat_map(Pattern::names).collect(),
+        }
+    }
+
+    pub fn position(&self) -> (usize, usize) {
+        match self {
+            Pattern::Name { line, column, .. } | Pattern::Array { line, column, .. } => (*line, *column),
+        }
+    }
+}
+
+/// Source form, `[a, [b, c]]`; also valid as a JS binding pattern.
+impl std::fmt::Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pattern::Name { name, .. } => f.write_str(name),
+            Pattern::Array { items, .. } => {
+                f.write_str("[")?;
+                for (i, it) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{it}")?;
+                }
+                f.write_str("]")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MatchArm {
+    /// None for the `_` catch-all.
+    pub pattern: Option<ASTNode>,
+    pub body: ASTNode,
+    pub line: usize,
+    pub column: usize,
+}
+
 impl ASTNode {
     // Utility constructors
     #[allow(dead_code)]
@@ -138,6 +277,18 @@ impl ASTNode {
             expr: Box::new(expr),
         }
     }
+    pub fn new_index_at(target: ASTNode, index: ASTNode, line: usize, column: usize) -> Self {
+        Self::Index { target: Box::new(target), index: Box::new(index), line, column }
+    }
+    pub fn new_member_at(target: ASTNode, name: &str, line: usize, column: usize) -> Self {
+        Self::Member { target: Box::new(target), name: name.to_string(), line, column }
+    }
+    pub fn new_array_literal_at(items: Vec<ASTNode>, line: usize, column: usize) -> Self {
+        Self::ArrayLiteral { items, line, column }
+    }
+    pub fn new_object_literal_at(fields: Vec<(String, ASTNode)>, line: usize, column: usize) -> Self {
+        Self::ObjectLiteral { fields, line, column }
+    }
     pub fn new_identifier_spanned(name: &str, line: usize, column: usize, len: usize) -> Self { Self::IdentifierSpanned { name: name.into(), line, column, len } }
     pub fn new_if(cond: ASTNode, then_branch: ASTNode, else_branch: Option<ASTNode>) -> Self {
         Self::If {
@@ -165,14 +316,41 @@ impl ASTNode {
             body: Box::new(body),
         }
     }
+    pub fn new_for_in_at(key: Option<String>, var: &str, iterable: ASTNode, body: ASTNode, line: usize, column: usize) -> Self {
+        Self::ForIn { key, var: var.to_string(), iterable: Box::new(iterable), body: Box::new(body), line, column }
+    }
     pub fn new_log(expr: ASTNode) -> Self {
-        Self::Log(Box::new(expr))
+        Self::Log { expr: Box::new(expr), line: 0, column: 0 }
+    }
+    pub fn new_log_at(expr: ASTNode, line: usize, column: usize) -> Self {
+        Self::Log { expr: Box::new(expr), line, column }
     }
     pub fn new_return(expr: ASTNode) -> Self {
         Self::Return(Box::new(expr))
     }
     pub fn new_quantum_op(op: TokenKind, qubits: Vec<ASTNode>) -> Self {
-        Self::QuantumOp { op, qubits }
+        Self::QuantumOp { op, qubits, line: 0, column: 0 }
+    }
+    pub fn new_quantum_op_at(op: TokenKind, qubits: Vec<ASTNode>, line: usize, column: usize) -> Self {
+        Self::QuantumOp { op, qubits, line, column }
+    }
+    pub fn new_qubit_decl_at(name: &str, line: usize, column: usize) -> Self {
+        Self::QubitDecl { name: name.to_string(), line, column }
+    }
+    pub fn new_qreg_decl_at(name: &str, size: usize, line: usize, column: usize) -> Self {
+        Self::QregDecl { name: name.to_string(), size, line, column }
+    }
+    pub fn new_destructuring_decl_at(targets: Vec<Pattern>, values: Vec<ASTNode>, line: usize, column: usize) -> Self {
+        Self::DestructuringDecl { targets, values, line, column }
+    }
+    pub fn new_enum_decl_at(name: &str, variants: Vec<String>, line: usize, column: usize) -> Self {
+        Self::EnumDecl { name: name.to_string(), variants, line, column }
+    }
+    pub fn new_import_at(path: &str, line: usize, column: usize) -> Self {
+        Self::Import { path: path.to_string(), line, column }
+    }
+    pub fn new_match_at(subject: ASTNode, arms: Vec<MatchArm>, line: usize, column: usize) -> Self {
+        Self::Match { subject: Box::new(subject), arms, line, column }
     }
     pub fn new_hieroglyphic_op(symbol: &str, args: Vec<ASTNode>) -> Self {
         Self::HieroglyphicOp {
@@ -211,7 +389,7 @@ mod tests {
     #[test]
     fn test_quantum_op_node() {
         let qop = ASTNode::new_quantum_op(TokenKind::Superpose, vec![ASTNode::Identifier("q1".into())]);
-        if let ASTNode::QuantumOp { op, qubits } = qop {
+        if let ASTNode::QuantumOp { op, qubits, .. } = qop {
             assert_eq!(op, TokenKind::Superpose);
             assert_eq!(qubits[0], ASTNode::Identifier("q1".into()));
         } else {
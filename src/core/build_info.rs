@@ -0,0 +1,115 @@
+//! `aeonmi info --json` and the GUI's `aeonmi_version`: what this binary was built with,
+//! so editors and CI can check for a feature instead of probing for it.
+//!
+//! The commit and target triple are embedded by `build.rs`; a build outside a git
+//! checkout reports no commit.
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::cli::EmitKind;
+use crate::core::incremental::{metrics_file_location, METRICS_SCHEMA, METRICS_VERSION};
+
+/// Bumped when the layout of the report changes.
+pub const SCHEMA: u32 = 1;
+
+/// Every cargo feature of the crate, enabled or not.
+pub const FEATURES: &[(&str, bool)] = &[
+    ("quantum", cfg!(feature = "quantum")),
+    ("bytecode", cfg!(feature = "bytecode")),
+    ("qiskit", cfg!(feature = "qiskit")),
+    ("ai-openai", cfg!(feature = "ai-openai")),
+    ("ai-copilot", cfg!(feature = "ai-copilot")),
+    ("ai-perplexity", cfg!(feature = "ai-perplexity")),
+    ("ai-deepseek", cfg!(feature = "ai-deepseek")),
+    ("kdf-argon2", cfg!(feature = "kdf-argon2")),
+    ("self-update", cfg!(feature = "self-update")),
+    ("debug-metrics", cfg!(feature = "debug-metrics")),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsSchema {
+    pub schema: String,
+    pub version: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigFile {
+    pub path: PathBuf,
+    pub exists: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    pub schema: u32,
+    pub version: String,
+    /// Short hash of the commit built from.
+    pub git_commit: Option<String>,
+    /// e.g. `x86_64-unknown-linux-gnu`.
+    pub target: String,
+    /// Feature name → compiled in.
+    pub features: BTreeMap<String, bool>,
+    /// Values `emit --emit` accepts.
+    pub emit_kinds: Vec<String>,
+    pub metrics: MetricsSchema,
+    /// `config` (qpoly.toml), `manifest` (nearest Aeonmi.toml), `metrics`, `usage_stats`,
+    /// `ai_usage` and `api_keys`; missing ones are listed with `exists: false`.
+    pub config_files: BTreeMap<String, ConfigFile>,
+}
+
+impl BuildInfo {
+    /// The report for this binary, with `config` as the user config in use (`--config` or the default).
+    pub fn current(config: Option<&Path>) -> Self {
+        let features = FEATURES.iter().map(|(name, on)| (name.to_string(), *on)).collect();
+        let emit_kinds = EmitKind::value_variants()
+            .iter()
+            .filter_map(|k| k.to_possible_value().map(|v| v.get_name().to_string()))
+            .collect();
+        let mut config_files = BTreeMap::new();
+        let mut add = |key: &str, path: PathBuf| {
+            let exists = path.is_file();
+            config_files.insert(key.to_string(), ConfigFile { path, exists });
+        };
+        if let Some(path) = config {
+            add("config", path.to_path_buf());
+        }
+        if let Some(path) = crate::config::nearest_manifest(Path::new("./")) {
+            add("manifest", path);
+        }
+        add("metrics", metrics_file_location());
+        add("usage_stats", crate::core::usage_stats::stats_path());
+        add("ai_usage", crate::core::ai_usage::ledger_path());
+        add("api_keys", crate::core::api_keys::storage_path());
+        BuildInfo {
+            schema: SCHEMA,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_commit: option_env!("AEONMI_GIT_COMMIT").filter(|c| !c.is_empty()).map(str::to_string),
+            target: option_env!("AEONMI_TARGET").unwrap_or("unknown").to_string(),
+            features,
+            emit_kinds,
+            metrics: MetricsSchema { schema: METRICS_SCHEMA.to_string(), version: METRICS_VERSION },
+            config_files,
+        }
+    }
+
+    /// The human-readable form `aeonmi info` prints without `--json`.
+    pub fn render(&self) -> String {
+        let mut out = format!("aeonmi {}", self.version);
+        if let Some(commit) = &self.git_commit {
+            out.push_str(&format!(" ({commit})"));
+        }
+        out.push_str(&format!("\ntarget: {}\n", self.target));
+        let enabled: Vec<&str> = self.features.iter().filter(|(_, on)| **on).map(|(n, _)| n.as_str()).collect();
+        out.push_str(&format!("features: {}\n", if enabled.is_empty() { "(none)".to_string() } else { enabled.join(", ") }));
+        out.push_str(&format!("emit kinds: {}\n", self.emit_kinds.join(", ")));
+        out.push_str(&format!("metrics schema: {} v{}\n", self.metrics.schema, self.metrics.version));
+        out.push_str("config files:\n");
+        for (key, file) in &self.config_files {
+            let note = if file.exists { "" } else { " (missing)" };
+            out.push_str(&format!("  {key}: {}{note}\n", file.path.display()));
+        }
+        out
+    }
+}
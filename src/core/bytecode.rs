@@ -1,6 +1,11 @@
 //! Simple bytecode IR (feature: bytecode)
 //! Stack-based. Operands push values; instructions operate on stack.
-//! Initial subset: literals, load/store local, arithmetic, comparison, jumps, call, return.
+//! Subset: literals, load/store local, arithmetic, comparison, `!`/`&&`/`||`, if/while/for,
+//! block-scoped `let` (including `let a, b = 1, 2;`), log, calls to top-level functions (in any order), return,
+//! `break`/`continue`, enums (variants are compiled to their index) and `match`.
+//! Anything else is reported as [`Unsupported`] by [`BytecodeCompiler::try_compile`]
+//! instead of being compiled best-effort; `tests/conformance` checks parity with the
+//! tree-walking VM and the JS backend.
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum OpCode {
@@ -11,6 +16,10 @@ pub enum OpCode {
     Sub,
     Mul,
     Div,
+    Mod,
+    Pow,
+    Neg,
+    Not,
     Eq,
     Ne,
     Lt,
@@ -22,9 +31,10 @@ pub enum OpCode {
     Pop,
     Nop,
     Jump(u32),          // absolute pc
-    JumpIfFalse(u32),   // absolute pc
-    Call(u16, u8),      // function index, arg count (placeholder)
+    JumpIfFalse(u32),   // absolute pc; pops the condition
+    Call(u16, u8),      // function index, arg count
     Return,
+    Log,                // pop and print
 }
 
 #[derive(Debug, Default)]
@@ -33,6 +43,21 @@ pub struct Chunk {
     pub constants: Vec<Constant>,
     pub functions: Vec<FunctionInfo>,
     pub opt_stats: OptimizationStats,
+    /// Local slots used by top-level code (the root frame).
+    pub main_locals: u16,
+}
+
+/// A construct the bytecode compiler refuses to compile. `line` is 0 when the AST node has no position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unsupported { pub construct: String, pub detail: String, pub line: usize, pub column: usize }
+
+impl std::fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.line > 0 { write!(f, "{}:{}: ", self.line, self.column)?; }
+        write!(f, "unsupported construct in bytecode: {}", self.construct)?;
+        if !self.detail.is_empty() { write!(f, " ({})", self.detail)?; }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -46,40 +71,115 @@ impl Chunk {    pub fn add_const(&mut self, c: Constant) -> u16 { let idx = self
 #[derive(Debug, Default, Clone)]
 pub struct OptimizationStats { pub const_folds: u32, pub chain_folds: u32, pub dce_if: u32, pub dce_while: u32, pub dce_for: u32, pub pops_eliminated: u32 }
 
-use crate::core::ast::ASTNode;
+use crate::core::ast::{ASTNode, Pattern};
 use crate::core::token::TokenKind;
 
 pub struct BytecodeCompiler {
     chunk: Chunk,
-    locals: Vec<String>, // current function locals
+    scopes: Vec<Vec<(String, u16)>>, // current function's block scopes, innermost last
+    next_slot: u16,
     functions: Vec<(String, usize, usize, u16)>, // temp table: name, start, arity, max locals
     current_function: Option<String>,
     local_max: u16,
+    unsupported: Vec<Unsupported>,
+    enums: Vec<(String, Vec<String>)>, // compile-time only: `E.V` becomes the index of V
+    loops: Vec<LoopJumps>, // enclosing loops, innermost last
 }
 
+/// `Jump`s emitted for `break`/`continue` in a loop, patched once its layout is known.
+#[derive(Default)]
+struct LoopJumps { breaks: Vec<usize>, continues: Vec<usize> }
+
+impl Default for BytecodeCompiler { fn default() -> Self { Self::new() } }
+
 impl BytecodeCompiler {
-    pub fn new() -> Self { Self { chunk: Chunk { code: Vec::new(), constants: Vec::new(), functions: Vec::new(), opt_stats: OptimizationStats::default() }, locals: Vec::new(), functions: Vec::new(), current_function: None, local_max: 0 } }
-    pub fn compile(mut self, ast: &ASTNode) -> Chunk { self.visit(ast); self.run_peephole(); for (n,s,a,l) in self.functions { self.chunk.functions.push(FunctionInfo { name: n, start: s, arity: a as u8, locals: l }); } self.chunk }
+    pub fn new() -> Self { Self { chunk: Chunk::default(), scopes: vec![Vec::new()], next_slot: 0, functions: Vec::new(), current_function: None, local_max: 0, unsupported: Vec::new(), enums: Vec::new(), loops: Vec::new() } }
+    /// Lenient entry kept for the unit tests: unsupported constructs are skipped. Prefer [`Self::try_compile`].
+    pub fn compile(self, ast: &ASTNode) -> Chunk { self.build(ast).0 }
+    /// Compile, or list every construct that has no bytecode lowering.
+    pub fn try_compile(self, ast: &ASTNode) -> Result<Chunk, Vec<Unsupported>> { let (chunk, unsupported) = self.build(ast); if unsupported.is_empty() { Ok(chunk) } else { Err(unsupported) } }
+
+    fn build(mut self, ast: &ASTNode) -> (Chunk, Vec<Unsupported>) {
+        // Declare every top-level function first so calls may precede definitions.
+        if let ASTNode::Program(items) = ast { for it in items { if let ASTNode::Function { name, params, .. } = it { if !self.functions.iter().any(|f| &f.0 == name) { self.functions.push((name.clone(), usize::MAX, params.len(), 0)); } } } }
+        // Likewise enums, so functions may name an enum declared further down.
+        if let ASTNode::Program(items) = ast { for it in items { if let ASTNode::EnumDecl { name, variants, .. } = it { self.declare_enum(name, variants); } } }
+        self.visit(ast);
+        self.chunk.main_locals = self.local_max;
+        self.run_peephole();
+        for (n,s,a,l) in self.functions { self.chunk.functions.push(FunctionInfo { name: n, start: s, arity: a as u8, locals: l }); }
+        (self.chunk, self.unsupported)
+    }
+
+    fn unsupported(&mut self, construct: &str, detail: String, (line, column): (usize, usize)) {
+        self.unsupported.push(Unsupported { construct: construct.to_string(), detail, line, column });
+    }
+
+    /// Slot for a `let`: reused when the innermost scope already declares `name`, fresh otherwise (shadowing).
+    fn declare(&mut self, name: &str) -> u16 {
+        if let Some((_, slot)) = self.scopes.last().and_then(|s| s.iter().find(|(n, _)| n == name)) { return *slot; }
+        let slot = self.next_slot; self.next_slot += 1; self.local_max = self.local_max.max(self.next_slot);
+        self.scopes.last_mut().unwrap().push((name.to_string(), slot)); slot
+    }
+
+    fn declare_enum(&mut self, name: &str, variants: &[String]) {
+        self.enums.retain(|(n, _)| n != name);
+        self.enums.push((name.to_string(), variants.to_vec()));
+    }
 
-    fn local_index(&mut self, name: &str) -> u16 {
-        if let Some(pos) = self.locals.iter().position(|n| n == name) { return pos as u16; }
-        self.locals.push(name.to_string());
-        let idx = (self.locals.len()-1) as u16; if idx+1 > self.local_max { self.local_max = idx+1; } idx
+    fn resolve(&self, name: &str) -> Option<u16> {
+        self.scopes.iter().rev().find_map(|s| s.iter().rev().find(|(n, _)| n == name).map(|(_, slot)| *slot))
+    }
+
+    /// Slot of a visible local, or an unsupported diagnostic (globals and closures are not compiled).
+    fn resolve_or_report(&mut self, name: &str, pos: (usize, usize)) -> u16 {
+        if let Some(slot) = self.resolve(name) { return slot; }
+        let detail = if self.functions.iter().any(|f| f.0 == name) { format!("function `{}` used as a value", name) }
+            else if let Some(f) = &self.current_function { format!("`{}` is not a parameter or local of `{}`; functions can't read top-level variables", name, f) }
+            else { format!("`{}` is not declared", name) };
+        self.unsupported("variable reference", detail, pos); 0
+    }
+
+    /// Visit in statement position: expression statements drop their value.
+    fn visit_stmt(&mut self, n: &ASTNode) {
+        self.visit(n);
+        if matches!(n, ASTNode::Call { .. } | ASTNode::BinaryExpr { .. } | ASTNode::UnaryExpr { .. } | ASTNode::Member { .. } | ASTNode::Identifier(_) | ASTNode::IdentifierSpanned { .. } | ASTNode::NumberLiteral(_) | ASTNode::StringLiteral(_) | ASTNode::BooleanLiteral(_)) { self.chunk.emit(OpCode::Pop); }
+    }
+
+    /// Compile a loop body, collecting its `break`/`continue` jumps.
+    fn visit_loop_body(&mut self, body: &ASTNode) -> LoopJumps {
+        self.loops.push(LoopJumps::default());
+        self.visit_scoped(body);
+        self.loops.pop().unwrap()
+    }
+
+    fn patch_jumps(&mut self, at: &[usize], target: u32) {
+        for &pos in at { if let OpCode::Jump(ref mut t) = self.chunk.code[pos] { *t = target; } }
+    }
+
+    fn visit_scoped(&mut self, n: &ASTNode) {
+        self.scopes.push(Vec::new());
+        match n { ASTNode::Block(items) => { for it in items { self.visit_stmt(it); } } other => self.visit_stmt(other) }
+        self.scopes.pop();
     }
 
     fn visit(&mut self, n: &ASTNode) {
         match n {
-            ASTNode::Program(items) => { for it in items { self.visit(it); } }
-            ASTNode::Function { name, params, body, .. } => {
-                // Forward declare so recursive calls inside body resolve.
+            ASTNode::Program(items) => { for it in items { self.visit_stmt(it); } }
+            ASTNode::Function { name, params, body, line, column } => {
+                if self.current_function.is_some() { self.unsupported("nested function", format!("`{}`", name), (*line, *column)); return; }
+                // Body is laid out of line: top-level code jumps over it.
+                let skip = self.chunk.code.len();
+                self.chunk.emit(OpCode::Jump(0));
                 let start = self.chunk.code.len();
-                let fn_index = self.functions.len();
-                self.functions.push((name.clone(), start, params.len(), 0)); // locals filled later
+                let fn_index = match self.functions.iter().position(|f| &f.0 == name) { Some(i) => i, None => { self.functions.push((name.clone(), start, params.len(), 0)); self.functions.len() - 1 } };
+                self.functions[fn_index].1 = start;
                 let prev_fn = self.current_function.replace(name.clone());
-                self.locals.clear();
+                let saved = (std::mem::replace(&mut self.scopes, vec![Vec::new()]), self.next_slot, self.local_max);
+                self.next_slot = 0;
                 self.local_max = 0;
-                for p in params { self.locals.push(p.name.clone()); self.local_max = self.local_max.max(self.locals.len() as u16); }
-                for stmt in body { self.visit(stmt); }
+                for p in params { self.declare(&p.name); }
+                for stmt in body { self.visit_stmt(stmt); }
                 // If function didn't end with explicit return, push Null and return implicitly.
                 if !matches!(self.chunk.code.last(), Some(OpCode::Return)) {
                     let null_idx = self.null_const();
@@ -87,38 +187,92 @@ impl BytecodeCompiler {
                     self.chunk.emit(OpCode::Return);
                 }
                 // Patch locals count for this function
-                if let Some(entry) = self.functions.get_mut(fn_index) { entry.3 = self.local_max; }
+                self.functions[fn_index].3 = self.local_max;
                 self.current_function = prev_fn;
-                self.locals.clear();
-                self.local_max = 0;
+                (self.scopes, self.next_slot, self.local_max) = saved;
+                let after = self.chunk.code.len() as u32;
+                if let OpCode::Jump(ref mut t) = self.chunk.code[skip] { *t = after; }
             }
             ASTNode::VariableDecl { name, value, .. } => { 
                 self.visit(value); 
-                let idx = self.local_index(name); 
+                let idx = self.declare(name); 
                 self.chunk.emit(OpCode::StoreLocal(idx)); 
                 // Clean value from stack; declarations as statements shouldn't leak.
                 self.chunk.emit(OpCode::Pop);
             }
-            ASTNode::Assignment { name, value, .. } => { 
+            // Without arrays only `let a, b = 1, 2;` compiles: every value first, then the stores.
+            ASTNode::DestructuringDecl { targets, values, line, column } => {
+                if targets.iter().any(|t| matches!(t, Pattern::Array { .. })) {
+                    self.unsupported("destructuring", "array patterns need arrays".into(), (*line, *column));
+                    return;
+                }
+                for v in values { self.visit(v); }
+                for (name, ..) in targets.iter().rev().flat_map(Pattern::names) {
+                    let idx = self.declare(name);
+                    self.chunk.emit(OpCode::StoreLocal(idx));
+                    self.chunk.emit(OpCode::Pop);
+                }
+            }
+            ASTNode::Assignment { name, value, line, column } => { 
                 self.visit(value); 
-                let idx = self.local_index(name); 
+                let idx = self.resolve_or_report(name, (*line, *column)); 
                 self.chunk.emit(OpCode::StoreLocal(idx)); 
                 // Treat assignment as statement for now; discard value.
                 self.chunk.emit(OpCode::Pop);
             }
+            ASTNode::EnumDecl { name, variants, .. } => { self.declare_enum(name, variants); }
+            ASTNode::Member { target, name, line, column } => {
+                let enum_name = match &**target { ASTNode::Identifier(n) | ASTNode::IdentifierSpanned { name: n, .. } => Some(n), _ => None };
+                let variant = enum_name.and_then(|e| self.enums.iter().find(|(n, _)| n == e)).map(|(e, vs)| (e.clone(), vs.iter().position(|v| v == name)));
+                match variant {
+                    Some((_, Some(idx))) => { let c = self.chunk.add_const(Constant::Number(idx as f64)); self.chunk.emit(OpCode::LoadConst(c)); }
+                    Some((e, None)) => self.unsupported("member access", format!("enum `{}` has no variant `{}`", e, name), (*line, *column)),
+                    None => self.unsupported("member access", "only enum variants are compiled".into(), (*line, *column)),
+                }
+            }
+            ASTNode::Match { subject, arms, .. } => {
+                // Subject in a hidden local; each arm is `==`, then a jump past the rest.
+                self.scopes.push(Vec::new());
+                self.visit(subject);
+                let slot = self.declare("__match");
+                self.chunk.emit(OpCode::StoreLocal(slot));
+                self.chunk.emit(OpCode::Pop);
+                let mut end_jumps = Vec::new();
+                for arm in arms {
+                    let Some(pattern) = &arm.pattern else { self.visit_scoped(&arm.body); break; };
+                    self.chunk.emit(OpCode::LoadLocal(slot));
+                    self.visit(pattern);
+                    self.chunk.emit(OpCode::Eq);
+                    let skip = self.chunk.code.len();
+                    self.chunk.emit(OpCode::JumpIfFalse(0));
+                    self.visit_scoped(&arm.body);
+                    end_jumps.push(self.chunk.code.len());
+                    self.chunk.emit(OpCode::Jump(0));
+                    let next = self.chunk.code.len() as u32;
+                    if let OpCode::JumpIfFalse(ref mut t) = self.chunk.code[skip] { *t = next; }
+                }
+                let end = self.chunk.code.len() as u32;
+                for j in end_jumps { if let OpCode::Jump(ref mut t) = self.chunk.code[j] { *t = end; } }
+                self.scopes.pop();
+            }
             ASTNode::NumberLiteral(v) => { let c = self.chunk.add_const(Constant::Number(*v)); self.chunk.emit(OpCode::LoadConst(c)); }
             ASTNode::StringLiteral(s) => { let c = self.chunk.add_const(Constant::String(s.clone())); self.chunk.emit(OpCode::LoadConst(c)); }
             ASTNode::BooleanLiteral(b) => { let c = self.chunk.add_const(Constant::Bool(*b)); self.chunk.emit(OpCode::LoadConst(c)); }
-            ASTNode::Identifier(name) => { let idx = self.local_index(name); self.chunk.emit(OpCode::LoadLocal(idx)); }
-            ASTNode::IdentifierSpanned { name, .. } => { let idx = self.local_index(name); self.chunk.emit(OpCode::LoadLocal(idx)); }
+            ASTNode::Identifier(name) => { let idx = self.resolve_or_report(name, (0, 0)); self.chunk.emit(OpCode::LoadLocal(idx)); }
+            ASTNode::IdentifierSpanned { name, line, column, .. } => { let idx = self.resolve_or_report(name, (*line, *column)); self.chunk.emit(OpCode::LoadLocal(idx)); }
             ASTNode::BinaryExpr { .. } => { self.emit_binary_or_fold(n); }
+            ASTNode::UnaryExpr { op, expr } => {
+                if let Some(cst) = self.fold_const(n) { self.chunk.opt_stats.const_folds += 1; let idx = self.chunk.add_const(cst); self.chunk.emit(OpCode::LoadConst(idx)); return; }
+                self.visit(expr);
+                match op { TokenKind::Minus => self.chunk.emit(OpCode::Neg), TokenKind::Bang => self.chunk.emit(OpCode::Not), _ => {} } // unary `+` is the identity
+            }
             ASTNode::Return(expr) => { self.visit(expr); self.chunk.emit(OpCode::Return); }
-            ASTNode::Log(expr) => { self.visit(expr); self.chunk.emit(OpCode::Pop); } // discard for now
-            ASTNode::Block(items) => { for it in items { self.visit(it); } }
+            ASTNode::Log { expr, .. } => { self.visit(expr); self.chunk.emit(OpCode::Log); }
+            ASTNode::Block(_) => { self.visit_scoped(n); }
             ASTNode::If { condition, then_branch, else_branch } => {
                 if let Some(Constant::Bool(b)) = self.fold_const(condition) { // DCE
                     self.chunk.opt_stats.dce_if += 1;
-                    if b { self.visit(then_branch); } else if let Some(e)=else_branch { self.visit(e); }
+                    if b { self.visit_scoped(then_branch); } else if let Some(e)=else_branch { self.visit_scoped(e); }
                     return;
                 }
                 // condition
@@ -127,7 +281,7 @@ impl BytecodeCompiler {
                 let cond_jump_pos = self.chunk.code.len();
                 self.chunk.emit(OpCode::JumpIfFalse(0));
                 // then branch
-                self.visit(then_branch);
+                self.visit_scoped(then_branch);
                 // if else present, emit jump over else
                 if let Some(e) = else_branch {
                     let after_then_jump = self.chunk.code.len();
@@ -136,7 +290,7 @@ impl BytecodeCompiler {
                     let else_start = self.chunk.code.len() as u32;
                     if let OpCode::JumpIfFalse(ref mut target) = self.chunk.code[cond_jump_pos] { *target = else_start; }
                     // else body
-                    self.visit(e);
+                    self.visit_scoped(e);
                     // patch jump after then to after else
                     let after_else = self.chunk.code.len() as u32;
                     if let OpCode::Jump(ref mut t) = self.chunk.code[after_then_jump] { *t = after_else; }
@@ -152,15 +306,17 @@ impl BytecodeCompiler {
                 self.visit(condition);
                 let jump_if_false_pos = self.chunk.code.len();
                 self.chunk.emit(OpCode::JumpIfFalse(0));
-                self.visit(body);
+                let jumps = self.visit_loop_body(body);
                 // jump back to loop start
                 self.chunk.emit(OpCode::Jump(loop_start));
                 let after_loop = self.chunk.code.len() as u32;
                 if let OpCode::JumpIfFalse(ref mut target) = self.chunk.code[jump_if_false_pos] { *target = after_loop; }
+                self.patch_jumps(&jumps.breaks, after_loop);
+                self.patch_jumps(&jumps.continues, loop_start);
             }
             ASTNode::For { init, condition, increment, body } => {
-                // init
-                if let Some(i) = init { self.visit(i); }
+                // init (declared in the enclosing scope, as in the tree-walking VM)
+                if let Some(i) = init { self.visit_stmt(i); }
                 let loop_start = self.chunk.code.len() as u32;
                 // condition
                 let cond_is_false = if let Some(c) = condition { if let Some(Constant::Bool(false)) = self.fold_const(c) { true } else { false } } else { false };
@@ -172,31 +328,43 @@ impl BytecodeCompiler {
                 let jump_if_false_pos = self.chunk.code.len();
                 self.chunk.emit(OpCode::JumpIfFalse(0));
                 // body
-                self.visit(body);
-                // increment
-                if let Some(inc) = increment { self.visit(inc); self.chunk.emit(OpCode::Pop); }
+                let jumps = self.visit_loop_body(body);
+                // increment (`continue` lands here)
+                let step = self.chunk.code.len() as u32;
+                if let Some(inc) = increment { self.visit_stmt(inc); }
                 // jump back
                 self.chunk.emit(OpCode::Jump(loop_start));
                 let after_for = self.chunk.code.len() as u32;
                 if let OpCode::JumpIfFalse(ref mut target) = self.chunk.code[jump_if_false_pos] { *target = after_for; }
+                self.patch_jumps(&jumps.breaks, after_for);
+                self.patch_jumps(&jumps.continues, step);
             }
-            ASTNode::Call { callee, args } => {
-                if let ASTNode::Identifier(name) = &**callee {
-                    if let Some((idx,_start,arity,_locs)) = self.functions.iter().enumerate().find_map(|(i,(n,s,a,l))| if n==name { Some((i,*s,*a,*l)) } else { None }) {
-                        for a in args { self.visit(a); }
-                        self.chunk.emit(OpCode::Call(idx as u16, arity as u8));
-                    }
+            ASTNode::Break { line, column } | ASTNode::Continue { line, column } => {
+                let pos = self.chunk.code.len();
+                let is_break = matches!(n, ASTNode::Break { .. });
+                match self.loops.last_mut() {
+                    Some(l) => { if is_break { l.breaks.push(pos) } else { l.continues.push(pos) } self.chunk.emit(OpCode::Jump(0)); }
+                    None => self.unsupported(if is_break { "break" } else { "continue" }, "outside of a loop".into(), (*line, *column)),
                 }
             }
-            _ => { /* quantum ops not yet */ }
+            ASTNode::Call { callee, args } => {
+                let name = match &**callee { ASTNode::Identifier(n) | ASTNode::IdentifierSpanned { name: n, .. } => n, _ => { self.unsupported("call", "callee is not a function name".into(), (0, 0)); return; } };
+                let Some((idx, arity)) = self.functions.iter().enumerate().find_map(|(i,(n,_,a,_))| (n==name).then_some((i,*a))) else {
+                    self.unsupported("call", format!("`{}` is not a top-level function (builtins are not compiled)", name), (0, 0)); return;
+                };
+                if arity != args.len() { self.unsupported("call", format!("`{}` expects {} args, got {}", name, arity, args.len()), (0, 0)); return; }
+                for a in args { self.visit(a); }
+                self.chunk.emit(OpCode::Call(idx as u16, arity as u8));
+            }
+            other => { let pos = node_pos(other); self.unsupported(node_kind(other), String::new(), pos); }
         }
     }
 
     fn translate_bin(&mut self, op: &TokenKind) {
         use TokenKind::*;
-        let bc = match op { Plus=>OpCode::Add, Minus=>OpCode::Sub, Star=>OpCode::Mul, Slash=>OpCode::Div,
+        let bc = match op { Plus=>OpCode::Add, Minus=>OpCode::Sub, Star=>OpCode::Mul, Slash=>OpCode::Div, Percent=>OpCode::Mod, StarStar=>OpCode::Pow,
             DoubleEquals=>OpCode::Eq, NotEquals=>OpCode::Ne, LessThan=>OpCode::Lt, LessEqual=>OpCode::Le, GreaterThan=>OpCode::Gt, GreaterEqual=>OpCode::Ge,
-            _ => OpCode::Pop };
+            other => { self.unsupported("operator", format!("`{}`", other), (0, 0)); return; } };
         self.chunk.emit(bc);
     }
     fn emit_binary_or_fold(&mut self, node: &ASTNode) {
@@ -204,38 +372,52 @@ impl BytecodeCompiler {
         if let ASTNode::BinaryExpr { op, left, right } = node {
             use TokenKind::*;
             match op {
-                AndAnd => { // short-circuit: evaluate left; if false -> skip right
+                // Short-circuit; the result is a bool, as in the tree-walking VM.
+                AndAnd => { // left false -> false without evaluating right
                     self.visit(left);
-                    let jump_pos = self.chunk.code.len();
+                    let left_false = self.chunk.code.len();
                     self.chunk.emit(OpCode::JumpIfFalse(0));
-                    // if left true, pop it and evaluate right, leaving right
-                    self.chunk.emit(OpCode::Pop);
                     self.visit(right);
-                    let after = self.chunk.code.len() as u32;
-                    if let OpCode::JumpIfFalse(ref mut t) = self.chunk.code[jump_pos] { *t = after; }
+                    let right_false = self.chunk.code.len();
+                    self.chunk.emit(OpCode::JumpIfFalse(0));
+                    self.emit_bool_tail(&[left_false, right_false]);
                 }
-                OrOr => { // short-circuit: if left true skip right
+                OrOr => { // left true -> true without evaluating right
                     self.visit(left);
-                    let jump_pos = self.chunk.code.len();
-                    // JumpIfFalse goes over next pop/eval when false; we invert by adding Jump for true path
+                    let left_false = self.chunk.code.len();
                     self.chunk.emit(OpCode::JumpIfFalse(0));
-                    // left true: keep it, jump over evaluation of right
-                    let skip_right_jump = self.chunk.code.len();
+                    let t = self.chunk.add_const(Constant::Bool(true));
+                    self.chunk.emit(OpCode::LoadConst(t));
+                    let done = self.chunk.code.len();
                     self.chunk.emit(OpCode::Jump(0));
-                    // patch JumpIfFalse target to evaluate right
                     let right_start = self.chunk.code.len() as u32;
-                    if let OpCode::JumpIfFalse(ref mut t) = self.chunk.code[jump_pos] { *t = right_start; }
-                    // evaluate right (discard left first)
-                    self.chunk.emit(OpCode::Pop);
+                    if let OpCode::JumpIfFalse(ref mut t) = self.chunk.code[left_false] { *t = right_start; }
                     self.visit(right);
-                    let after_right = self.chunk.code.len() as u32;
-                    if let OpCode::Jump(ref mut t) = self.chunk.code[skip_right_jump] { *t = after_right; }
+                    let right_false = self.chunk.code.len();
+                    self.chunk.emit(OpCode::JumpIfFalse(0));
+                    self.emit_bool_tail(&[right_false]);
+                    let end = self.chunk.code.len() as u32;
+                    if let OpCode::Jump(ref mut t) = self.chunk.code[done] { *t = end; }
                 }
                 _ => { self.visit(left); self.visit(right); self.translate_bin(op); }
             }
         }
     }
 
+    /// `true` on fall-through; every `JumpIfFalse` in `false_jumps` lands on `false`.
+    fn emit_bool_tail(&mut self, false_jumps: &[usize]) {
+        let t = self.chunk.add_const(Constant::Bool(true));
+        let f = self.chunk.add_const(Constant::Bool(false));
+        self.chunk.emit(OpCode::LoadConst(t));
+        let done = self.chunk.code.len();
+        self.chunk.emit(OpCode::Jump(0));
+        let false_at = self.chunk.code.len() as u32;
+        for &j in false_jumps { if let OpCode::JumpIfFalse(ref mut t) = self.chunk.code[j] { *t = false_at; } }
+        self.chunk.emit(OpCode::LoadConst(f));
+        let end = self.chunk.code.len() as u32;
+        if let OpCode::Jump(ref mut t) = self.chunk.code[done] { *t = end; }
+    }
+
     fn null_const(&mut self) -> u16 {
         if let Some(idx) = self.chunk.constants.iter().position(|c| matches!(c, Constant::Null)) { idx as u16 } else { self.chunk.add_const(Constant::Null) }
     }
@@ -247,6 +429,12 @@ impl BytecodeCompiler {
             ASTNode::NumberLiteral(n) => Some(Constant::Number(*n)),
             ASTNode::StringLiteral(s) => Some(Constant::String(s.clone())),
             ASTNode::BooleanLiteral(b) => Some(Constant::Bool(*b)),
+            ASTNode::UnaryExpr { op, expr } => match (op, self.fold_const(expr)?) {
+                (Minus, Constant::Number(n)) => Some(Constant::Number(-n)),
+                (Plus, Constant::Number(n)) => Some(Constant::Number(n)),
+                (Bang, Constant::Bool(b)) => Some(Constant::Bool(!b)),
+                _ => None,
+            },
             ASTNode::BinaryExpr { op, left, right } => {
                 // Associative chain folding for +, *, &&, ||
                 if matches!(op, Plus | Star | AndAnd | OrOr) {
@@ -260,7 +448,10 @@ impl BytecodeCompiler {
                     (Plus, Constant::String(a), Constant::Number(b)) => Some(Constant::String(format!("{}{}", a, b))),
                     (Minus, Constant::Number(a), Constant::Number(b)) => Some(Constant::Number(a - b)),
                     (Star, Constant::Number(a), Constant::Number(b)) => Some(Constant::Number(a * b)),
-                    (Slash, Constant::Number(a), Constant::Number(b)) => Some(Constant::Number(if b==0.0 { 0.0 } else { a / b })),
+                    // `/` and `%` by zero are left for the VM to report at run time
+                    (Slash, Constant::Number(a), Constant::Number(b)) if b != 0.0 => Some(Constant::Number(a / b)),
+                    (Percent, Constant::Number(a), Constant::Number(b)) if b != 0.0 => Some(Constant::Number(a % b)),
+                    (StarStar, Constant::Number(a), Constant::Number(b)) => Some(Constant::Number(a.powf(b))),
                     (DoubleEquals, Constant::Number(a), Constant::Number(b)) => Some(Constant::Bool(a == b)),
                     (NotEquals, Constant::Number(a), Constant::Number(b)) => Some(Constant::Bool(a != b)),
                     (LessThan, Constant::Number(a), Constant::Number(b)) => Some(Constant::Bool(a < b)),
@@ -334,6 +525,24 @@ impl BytecodeCompiler {
     }
 }
 
+fn node_kind(n: &ASTNode) -> &'static str {
+    match n {
+        ASTNode::QuantumOp { .. } | ASTNode::QubitDecl { .. } | ASTNode::QregDecl { .. } => "quantum operation",
+        ASTNode::HieroglyphicOp { .. } => "glyph operation",
+        ASTNode::Index { .. } => "indexing",
+        ASTNode::ForIn { .. } => "for-in loop",
+        ASTNode::Error(_) => "parse error node",
+        _ => "expression",
+    }
+}
+
+fn node_pos(n: &ASTNode) -> (usize, usize) {
+    match n {
+        ASTNode::QuantumOp { line, column, .. } | ASTNode::QubitDecl { line, column, .. } | ASTNode::QregDecl { line, column, .. } | ASTNode::ForIn { line, column, .. } => (*line, *column),
+        _ => (0, 0),
+    }
+}
+
 // Simple textual disassembler (debug)
 pub fn disassemble(chunk: &Chunk) -> String {
     use std::fmt::Write;
@@ -343,5 +552,5 @@ pub fn disassemble(chunk: &Chunk) -> String {
     writeln!(&mut out, "== functions ({} ) ==", chunk.functions.len()).ok();
     for (i,f) in chunk.functions.iter().enumerate() { writeln!(&mut out, "fn#{i} {} start={} arity={} locals={}", f.name, f.start, f.arity, f.locals).ok(); }
     writeln!(&mut out, "== code ({} ops) ==", chunk.code.len()).ok();
-    for (i,op) in chunk.code.iter().enumerate() { use OpCode::*; match op { LoadConst(c)=>writeln!(&mut out, "{i:04} LOAD_CONST {c}").ok(), LoadLocal(l)=>writeln!(&mut out, "{i:04} LOAD_LOCAL {l}").ok(), StoreLocal(l)=>writeln!(&mut out, "{i:04} STORE_LOCAL {l}").ok(), Add=>writeln!(&mut out, "{i:04} ADD").ok(), Sub=>writeln!(&mut out, "{i:04} SUB").ok(), Mul=>writeln!(&mut out, "{i:04} MUL").ok(), Div=>writeln!(&mut out, "{i:04} DIV").ok(), Eq=>writeln!(&mut out, "{i:04} EQ").ok(), Ne=>writeln!(&mut out, "{i:04} NE").ok(), Lt=>writeln!(&mut out, "{i:04} LT").ok(), Le=>writeln!(&mut out, "{i:04} LE").ok(), Gt=>writeln!(&mut out, "{i:04} GT").ok(), Ge=>writeln!(&mut out, "{i:04} GE").ok(), And=>writeln!(&mut out, "{i:04} AND").ok(), Or=>writeln!(&mut out, "{i:04} OR").ok(), Pop=>writeln!(&mut out, "{i:04} POP").ok(), Nop=>writeln!(&mut out, "{i:04} NOP").ok(), Jump(t)=>writeln!(&mut out, "{i:04} JUMP {t}").ok(), JumpIfFalse(t)=>writeln!(&mut out, "{i:04} JUMP_IF_FALSE {t}").ok(), Call(f,a)=>writeln!(&mut out, "{i:04} CALL f={} argc={}", f,a).ok(), Return=>writeln!(&mut out, "{i:04} RETURN").ok(), }; }
+    for (i,op) in chunk.code.iter().enumerate() { use OpCode::*; match op { LoadConst(c)=>writeln!(&mut out, "{i:04} LOAD_CONST {c}").ok(), LoadLocal(l)=>writeln!(&mut out, "{i:04} LOAD_LOCAL {l}").ok(), StoreLocal(l)=>writeln!(&mut out, "{i:04} STORE_LOCAL {l}").ok(), Add=>writeln!(&mut out, "{i:04} ADD").ok(), Sub=>writeln!(&mut out, "{i:04} SUB").ok(), Mul=>writeln!(&mut out, "{i:04} MUL").ok(), Div=>writeln!(&mut out, "{i:04} DIV").ok(), Mod=>writeln!(&mut out, "{i:04} MOD").ok(), Pow=>writeln!(&mut out, "{i:04} POW").ok(), Neg=>writeln!(&mut out, "{i:04} NEG").ok(), Not=>writeln!(&mut out, "{i:04} NOT").ok(), Eq=>writeln!(&mut out, "{i:04} EQ").ok(), Ne=>writeln!(&mut out, "{i:04} NE").ok(), Lt=>writeln!(&mut out, "{i:04} LT").ok(), Le=>writeln!(&mut out, "{i:04} LE").ok(), Gt=>writeln!(&mut out, "{i:04} GT").ok(), Ge=>writeln!(&mut out, "{i:04} GE").ok(), And=>writeln!(&mut out, "{i:04} AND").ok(), Or=>writeln!(&mut out, "{i:04} OR").ok(), Pop=>writeln!(&mut out, "{i:04} POP").ok(), Nop=>writeln!(&mut out, "{i:04} NOP").ok(), Jump(t)=>writeln!(&mut out, "{i:04} JUMP {t}").ok(), JumpIfFalse(t)=>writeln!(&mut out, "{i:04} JUMP_IF_FALSE {t}").ok(), Call(f,a)=>writeln!(&mut out, "{i:04} CALL f={} argc={}", f,a).ok(), Return=>writeln!(&mut out, "{i:04} RETURN").ok(), Log=>writeln!(&mut out, "{i:04} LOG").ok(), }; }
     out }
@@ -0,0 +1,81 @@
+//! Copying text out of the CLI (`ai chat --copy`, the shell's `:copy`).
+//!
+//! Text goes to the system clipboard when there is one. Without it (headless CI, SSH,
+//! no display server) it is written to a temp file instead and the caller prints the
+//! path. Text the DLP scanner flags as a credential is refused unless `force` is set.
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::core::lexer_plugins::secret_kind;
+
+/// Where copied text ended up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Copied {
+    Clipboard,
+    /// No clipboard (the reason), so the text was written to this file.
+    File(PathBuf, String),
+}
+
+#[derive(Debug)]
+pub enum CopyError {
+    /// The DLP scanner found this kind of secret and `force` was not given.
+    Secret(&'static str),
+    /// Neither the clipboard nor the fallback file could be written.
+    Io(std::io::Error),
+}
+
+impl fmt::Display for CopyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CopyError::Secret(kind) => write!(f, "text looks like it contains {kind}; not copied (pass --force to copy it anyway)"),
+            CopyError::Io(e) => write!(f, "cannot write fallback file: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for CopyError {}
+
+impl fmt::Display for Copied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Copied::Clipboard => write!(f, "copied to the clipboard"),
+            Copied::File(path, why) => write!(f, "no clipboard ({why}); wrote {}", path.display()),
+        }
+    }
+}
+
+/// Copy `text` to the system clipboard, scanning it with the `dlp-redactor` rules first.
+pub fn copy(text: &str, force: bool) -> Result<Copied, CopyError> {
+    copy_with(text, force, secret_kind, system_clipboard)
+}
+
+/// [`copy`] with the DLP scanner and clipboard writer passed in.
+pub fn copy_with(
+    text: &str,
+    force: bool,
+    scan: impl Fn(&str) -> Option<&'static str>,
+    set_clipboard: impl FnOnce(&str) -> Result<(), String>,
+) -> Result<Copied, CopyError> {
+    if let Some(kind) = scan(text).filter(|_| !force) {
+        return Err(CopyError::Secret(kind));
+    }
+    match set_clipboard(text) {
+        Ok(()) => Ok(Copied::Clipboard),
+        Err(why) => write_fallback(text).map(|path| Copied::File(path, why)).map_err(CopyError::Io),
+    }
+}
+
+fn system_clipboard(text: &str) -> Result<(), String> {
+    if std::env::var_os("AEONMI_NO_CLIPBOARD").is_some() {
+        return Err("AEONMI_NO_CLIPBOARD is set".into());
+    }
+    let mut board = arboard::Clipboard::new().map_err(|e| e.to_string())?;
+    board.set_text(text).map_err(|e| e.to_string())
+}
+
+/// A fresh `aeonmi-copy-*.txt` in the temp dir, kept after exit so it can be read.
+fn write_fallback(text: &str) -> std::io::Result<PathBuf> {
+    let file = tempfile::Builder::new().prefix("aeonmi-copy-").suffix(".txt").tempfile()?;
+    std::fs::write(file.path(), text)?;
+    file.keep().map(|(_, path)| path).map_err(|e| e.error)
+}
@@ -13,6 +13,123 @@ pub struct CodeAction {
     pub column: usize,
 }
 
+/// One text replacement; an empty range is an insertion. Positions are 1-based, end exclusive.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextEdit {
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    pub new_text: String,
+}
+
+impl TextEdit {
+    pub fn insert(line: usize, column: usize, text: &str) -> Self {
+        Self { line, column, end_line: line, end_column: column, new_text: text.to_string() }
+    }
+}
+
+/// A diagnostic's fix: the edits a code action of the same `kind` would make.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuickFix {
+    pub title: String,
+    pub kind: String,
+    pub edits: Vec<TextEdit>,
+}
+
+/// The text after `apply_edits` and the edits that turn it back into the original, in
+/// document order; applying `reverse` to `text` is the editor's undo.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppliedEdits {
+    pub text: String,
+    pub reverse: Vec<TextEdit>,
+}
+
+/// Why `apply_edits` refused a list: `index` is the 0-based position of the offending edit.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EditError {
+    pub index: usize,
+    pub reason: String,
+}
+
+impl std::fmt::Display for EditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "edit {}: {}", self.index, self.reason)
+    }
+}
+
+/// Apply `edits` (from rename, a quick fix or extract-function) to `source` as one step.
+/// Columns are byte columns into `source` as given, the lexer's columns for NFC text.
+/// Edits must not overlap (touching is fine; insertions at one point keep their list
+/// order) and must lie inside the document, so an edit computed against an older buffer
+/// is rejected rather than applied somewhere else.
+pub fn apply_edits(source: &str, edits: &[TextEdit]) -> Result<AppliedEdits, EditError> {
+    let line_starts: Vec<usize> = std::iter::once(0).chain(source.match_indices('\n').map(|(i, _)| i + 1)).collect();
+    let mut ranges = Vec::with_capacity(edits.len());
+    for (index, e) in edits.iter().enumerate() {
+        let fail = |reason: String| EditError { index, reason };
+        let start = offset(source, &line_starts, e.line, e.column).map_err(fail)?;
+        let end = offset(source, &line_starts, e.end_line, e.end_column).map_err(fail)?;
+        if end < start {
+            return Err(fail(format!("ends ({}:{}) before it starts ({}:{})", e.end_line, e.end_column, e.line, e.column)));
+        }
+        ranges.push((start, end, index));
+    }
+    // stable, so insertions at one point stay in list order
+    ranges.sort_by_key(|&(start, end, _)| (start, end));
+    for pair in ranges.windows(2) {
+        let ((_, prev_end, prev), (start, _, index)) = (pair[0], pair[1]);
+        if start < prev_end {
+            let p = &edits[prev];
+            return Err(EditError { index, reason: format!("overlaps edit {prev} ({}:{}-{}:{})", p.line, p.column, p.end_line, p.end_column) });
+        }
+    }
+
+    let mut text = String::with_capacity(source.len());
+    let mut copied = 0;
+    let mut reverse = Vec::with_capacity(ranges.len());
+    let mut spans = Vec::with_capacity(ranges.len());
+    for &(start, end, index) in &ranges {
+        text.push_str(&source[copied..start]);
+        let new_start = text.len();
+        text.push_str(&edits[index].new_text);
+        spans.push((new_start, text.len(), &source[start..end]));
+        copied = end;
+    }
+    text.push_str(&source[copied..]);
+    let new_line_starts: Vec<usize> = std::iter::once(0).chain(text.match_indices('\n').map(|(i, _)| i + 1)).collect();
+    for (start, end, old) in spans {
+        let (line, column) = position(&new_line_starts, start);
+        let (end_line, end_column) = position(&new_line_starts, end);
+        reverse.push(TextEdit { line, column, end_line, end_column, new_text: old.to_string() });
+    }
+    Ok(AppliedEdits { text, reverse })
+}
+
+/// Byte offset of 1-based (`line`, `column`) in `source`.
+fn offset(source: &str, line_starts: &[usize], line: usize, column: usize) -> Result<usize, String> {
+    let Some(&start) = line.checked_sub(1).and_then(|l| line_starts.get(l)) else {
+        return Err(format!("line {line} is outside the document ({} lines)", line_starts.len()));
+    };
+    let end = line_starts.get(line).map_or(source.len(), |next| next - 1);
+    let len = end - start;
+    if column == 0 || column > len + 1 {
+        return Err(format!("column {column} is outside line {line} ({len} bytes)"));
+    }
+    let at = start + column - 1;
+    if !source.is_char_boundary(at) {
+        return Err(format!("{line}:{column} is inside a character"));
+    }
+    Ok(at)
+}
+
+/// 1-based (line, column) of byte offset `at`.
+fn position(line_starts: &[usize], at: usize) -> (usize, usize) {
+    let line = line_starts.partition_point(|&s| s <= at);
+    (line, at - line_starts[line - 1] + 1)
+}
+
 pub fn suggest_actions(ast: &ASTNode) -> Vec<CodeAction> {
     let mut v = Vec::new();
     collect(ast, &mut v);
@@ -49,6 +166,7 @@ fn add_missing_let_actions(ast: &ASTNode, out: &mut Vec<CodeAction>) {
     fn walk(node: &ASTNode, declared: &mut std::collections::HashSet<String>, out: &mut Vec<CodeAction>) {
         match node {
             ASTNode::VariableDecl { name, .. } => { declared.insert(name.clone()); }
+            ASTNode::DestructuringDecl { targets, .. } => { declared.extend(targets.iter().flat_map(|t| t.names()).map(|(n, ..)| n.to_string())); }
             ASTNode::Assignment { name, line, column, .. } => {
                 if !declared.contains(name) { out.push(CodeAction { title: format!("Add missing 'let' for '{name}'"), kind: "addMissingLet".into(), line: *line, column: *column }); declared.insert(name.clone()); }
             }
@@ -56,6 +174,11 @@ fn add_missing_let_actions(ast: &ASTNode, out: &mut Vec<CodeAction>) {
             ASTNode::Function { body, .. } => { for it in body { walk(it, &mut declared.clone(), out); } }
             ASTNode::If { then_branch, else_branch, .. } => { walk(then_branch, &mut declared.clone(), out); if let Some(e)=else_branch { walk(e, &mut declared.clone(), out); } }
             ASTNode::While { body, .. } | ASTNode::For { body, .. } => { walk(body, &mut declared.clone(), out); }
+            ASTNode::ForIn { key, var, body, .. } => {
+                let mut inner = declared.clone();
+                inner.extend(key.iter().chain([var]).cloned());
+                walk(body, &mut inner, out);
+            }
             _ => {}
         }
     }
@@ -78,7 +201,8 @@ fn add_inline_variable_actions(ast: &ASTNode, out: &mut Vec<CodeAction>) {
             ASTNode::Function { body, .. } => { for it in body { scan(it, map); } }
             ASTNode::If { then_branch, else_branch, .. } => { scan(then_branch, map); if let Some(e)=else_branch { scan(e, map); } }
             ASTNode::While { body, .. } | ASTNode::For { body, .. } => { scan(body, map); }
-            ASTNode::Assignment { value, .. } | ASTNode::Return(value) | ASTNode::Log(value) => { scan(value, map); }
+            ASTNode::ForIn { iterable, body, .. } => { scan(iterable, map); scan(body, map); }
+            ASTNode::Assignment { value, .. } | ASTNode::Return(value) | ASTNode::Log { expr: value, .. } => { scan(value, map); }
             ASTNode::BinaryExpr { left, right, .. } => { scan(left, map); scan(right, map); }
             ASTNode::UnaryExpr { expr, .. } => { scan(expr, map); }
             ASTNode::Call { callee, args } => { scan(callee, map); for a in args { scan(a, map); } }
@@ -1,8 +1,9 @@
 //! Aeonmi code generation front-end.
 //! - Default backend: **JS** (keeps legacy tests green)
 //! - Optional backend: **AI** (canonical .ai via AiEmitter)
-use crate::core::ai_emitter::AiEmitter;
-use crate::core::ast::ASTNode;
+use crate::core::ai_emitter::{emit_ai_mapped, AiEmitter};
+use crate::core::ast::{ASTNode, Pattern};
+use crate::core::source_map::{Mapping, SourceMap};
 use crate::core::token::TokenKind;
 use std::collections::BTreeSet;
 
@@ -15,14 +16,42 @@ pub enum Backend {
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 enum Helper {
     Len,
+    Iter,
+    Unpack,
+    Stdin,
+    Exit,
+    Arrays,
+    Mod,
+    Display,
+}
+
+/// Output lines (1-based, inclusive) a top-level function was emitted to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputSpan {
+    pub name: String,
+    pub start_line: usize,
+    pub end_line: usize,
 }
 
 pub struct CodeGenerator {
     indent: usize,
     backend: Backend,
     helpers: BTreeSet<Helper>,
+    spans: Vec<OutputSpan>,
+    /// Functions being emitted around the current node; 0 at the top level.
+    fn_depth: usize,
+    /// Mark statement starts in the output for `generate_source_mapped`.
+    mark_positions: bool,
+    /// Copy arrays and objects where the VM would (see [`CodeGenerator::stored_js`]); set
+    /// for programs that call `push` or `pop`, the only way to tell a copy from the original.
+    copy_values: bool,
 }
 
+/// Brackets a `line:col` statement mark in JS output while a source map is built. Private-use
+/// code points, which string literals escape, so emitted code never contains one.
+const MARK_OPEN: char = '\u{E000}';
+const MARK_CLOSE: char = '\u{E001}';
+
 impl Default for CodeGenerator {
     fn default() -> Self {
         Self::new()
@@ -35,6 +64,10 @@ impl CodeGenerator {
             indent: 0,
             backend: Backend::Js,
             helpers: BTreeSet::new(),
+            spans: Vec::new(),
+            fn_depth: 0,
+            mark_positions: false,
+            copy_values: false,
         }
     }
     pub fn new_ai() -> Self {
@@ -42,11 +75,82 @@ impl CodeGenerator {
             indent: 0,
             backend: Backend::Ai,
             helpers: BTreeSet::new(),
+            spans: Vec::new(),
+            fn_depth: 0,
+            mark_positions: false,
+            copy_values: false,
         }
     }
     pub fn generate(&mut self, ast: &ASTNode) -> Result<String, String> {
         self.generate_with_backend(ast, self.backend)
     }
+    /// Generate, also reporting where each top-level function landed in the output
+    /// (JS keeps source order; AI sorts decls by name).
+    pub fn generate_mapped(&mut self, ast: &ASTNode) -> Result<(String, Vec<OutputSpan>), String> {
+        match self.backend {
+            Backend::Js => {
+                self.spans.clear();
+                let out = self.emit_js(ast);
+                Ok((out, std::mem::take(&mut self.spans)))
+            }
+            Backend::Ai => {
+                let module = crate::core::lowering::lower_ast_to_ir(ast, "main")
+                    .map_err(|e| format!("AiEmitter error: lowering error: {e}"))?;
+                let (out, fns) = emit_ai_mapped(&module);
+                let spans = fns
+                    .into_iter()
+                    .map(|(name, start_line, end_line)| OutputSpan { name, start_line, end_line })
+                    .collect();
+                Ok((out, spans))
+            }
+        }
+    }
+    /// JS for `ast` plus a source map from it back to `source` (named `source_name` in the
+    /// map), ending with a `sourceMappingURL` comment for `js_file`. Each statement maps
+    /// to the source position of its first token that carries one.
+    pub fn generate_source_mapped(&mut self, ast: &ASTNode, js_file: &std::path::Path, source_name: &str, source: &str) -> Result<(String, SourceMap), String> {
+        self.mark_positions = true;
+        let marked = self.emit_js(ast);
+        self.mark_positions = false;
+        let source_lines: Vec<&str> = source.lines().collect();
+        let mut out = String::with_capacity(marked.len());
+        let mut mappings = Vec::new();
+        for (gen_line, line) in marked.split_inclusive('\n').enumerate() {
+            let mut rest = line;
+            let mut gen_col = 0;
+            while let Some(open) = rest.find(MARK_OPEN) {
+                let (before, after) = rest.split_at(open);
+                out.push_str(before);
+                gen_col += before.encode_utf16().count();
+                let after = &after[MARK_OPEN.len_utf8()..];
+                let mark = after.find(MARK_CLOSE).and_then(|close| {
+                    let (l, c) = after[..close].split_once(':')?;
+                    Some((l.parse::<usize>().ok()?, c.parse::<usize>().ok()?, close))
+                });
+                match mark {
+                    Some((src_line, src_col, close)) => {
+                        if let Some(text) = source_lines.get(src_line - 1) {
+                            let byte = crate::core::columns::source_offset(text, src_col).unwrap_or(0);
+                            mappings.push(Mapping { gen_line, gen_col, src_line: src_line - 1, src_col: text[..byte].encode_utf16().count() });
+                        }
+                        rest = &after[close + MARK_CLOSE.len_utf8()..];
+                    }
+                    None => {
+                        out.push(MARK_OPEN);
+                        gen_col += 1;
+                        rest = after;
+                    }
+                }
+            }
+            out.push_str(rest);
+        }
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+        out.push_str(&crate::core::source_map::url_comment(js_file));
+        let file = js_file.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        Ok((out, SourceMap::new(&file, source_name, source, &mappings)))
+    }
     pub fn generate_with_backend(
         &mut self,
         ast: &ASTNode,
@@ -67,12 +171,27 @@ impl CodeGenerator {
         match node {
             ASTNode::Program(items) => {
                 let saved_helpers = std::mem::take(&mut self.helpers);
+                let saved_copy = std::mem::replace(&mut self.copy_values, items.iter().any(mutates_arrays));
                 let mut body = String::new();
+                let mut spans = Vec::new();
                 for item in items {
-                    body.push_str(&self.emit_js(item));
+                    let start_line = body.matches('\n').count() + 1;
+                    body.push_str(&self.emit_stmt_js(item));
                     if !body.ends_with('\n') {
                         body.push('\n');
                     }
+                    if let ASTNode::Function { name, .. } = item {
+                        let end_line = body.matches('\n').count();
+                        spans.push(OutputSpan { name: name.clone(), start_line, end_line });
+                    }
+                }
+                // The native VM calls a parameterless `main` when the file has no top-level
+                // statements, and exits with what it returns; Node has to be told to.
+                let only_decls = items.iter().all(|it| matches!(it, ASTNode::Function { .. } | ASTNode::EnumDecl { .. }));
+                let has_main = items.iter().any(|it| matches!(it, ASTNode::Function { name, params, .. } if name == "main" && params.is_empty()));
+                if only_decls && has_main {
+                    self.helpers.insert(Helper::Exit);
+                    body.push_str("process.exitCode = __aeonmi_exit_code(main());\n");
                 }
 
                 let needed_helpers = std::mem::take(&mut self.helpers);
@@ -83,8 +202,14 @@ impl CodeGenerator {
                         out.push('\n');
                     }
                 }
+                let offset = out.matches('\n').count();
                 out.push_str(&body);
+                self.spans = spans
+                    .into_iter()
+                    .map(|s| OutputSpan { start_line: s.start_line + offset, end_line: s.end_line + offset, ..s })
+                    .collect();
                 self.helpers = saved_helpers;
+                self.copy_values = saved_copy;
                 out
             }
             ASTNode::Block(items) => {
@@ -93,7 +218,7 @@ impl CodeGenerator {
                 self.indent += 1;
                 for it in items {
                     s.push_str(&self.indent_str());
-                    s.push_str(&self.emit_js(it));
+                    s.push_str(&self.emit_stmt_js(it));
                     if !s.ends_with('\n') {
                         s.push('\n');
                     }
@@ -103,7 +228,52 @@ impl CodeGenerator {
                 s
             }
             ASTNode::VariableDecl { name, value, .. } => {
-                format!("let {} = {};\n", name, self.emit_expr_js(value))
+                format!("let {} = {};\n", name, self.stored_js(value))
+            }
+            // Array patterns go through `__aeonmi_unpack`, which checks arity like the VM does.
+            ASTNode::DestructuringDecl { targets, values, .. } => {
+                let values: Vec<String> = targets.iter().zip(values).map(|(t, v)| self.unpack_js(t, v)).collect();
+                let targets: Vec<String> = targets.iter().map(Pattern::to_string).collect();
+                if targets.len() == 1 {
+                    format!("let {} = {};\n", targets[0], values[0])
+                } else {
+                    format!("let [{}] = [{}];\n", targets.join(", "), values.join(", "))
+                }
+            }
+            // Variants are frozen strings, so `==` and logging behave like the native VM.
+            ASTNode::EnumDecl { name, variants, .. } => {
+                let fields = variants
+                    .iter()
+                    .map(|v| format!("{}: \"{}.{}\"", v, name, v))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("const {} = Object.freeze({{ {} }});\n", name, fields)
+            }
+            ASTNode::Match { subject, arms, .. } => {
+                let mut s = String::from("{\n");
+                self.indent += 1;
+                s.push_str(&self.indent_str());
+                s.push_str(&format!("const __match = {};\n", self.emit_expr_js(subject)));
+                s.push_str(&self.indent_str());
+                for (i, arm) in arms.iter().enumerate() {
+                    if i > 0 {
+                        s.push_str(" else ");
+                    }
+                    match &arm.pattern {
+                        Some(p) => {
+                            s.push_str(&format!("if (__match == {}) ", self.emit_expr_js(p)));
+                            s.push_str(&self.wrap_stmt_js(&arm.body));
+                        }
+                        // `_` catches everything; later arms are unreachable
+                        None => {
+                            s.push_str(&self.wrap_stmt_js(&arm.body));
+                            break;
+                        }
+                    }
+                }
+                self.indent -= 1;
+                s.push_str("\n}\n");
+                s
             }
             ASTNode::Function {
                 name, params, body, ..
@@ -119,13 +289,28 @@ impl CodeGenerator {
                         .join(", ")
                 ));
                 let block = ASTNode::Block(body.clone());
+                self.fn_depth += 1;
                 s.push_str(&self.emit_js(&block));
+                self.fn_depth -= 1;
                 s
             }
+            // A top-level `return` ends the program with an exit code (Node runs the file as a
+            // CommonJS module, where `return` is allowed outside functions).
+            ASTNode::Return(expr) if self.fn_depth == 0 => {
+                self.helpers.insert(Helper::Exit);
+                format!("process.exitCode = __aeonmi_exit_code({}); return;\n", self.emit_expr_js(expr))
+            }
             ASTNode::Return(expr) => format!("return {};\n", self.emit_expr_js(expr)),
-            ASTNode::Log(expr) => format!("console.log({});\n", self.emit_expr_js(expr)),
+            // Node prints scalars and strings as the VM does; anything else goes through the VM's layout
+            ASTNode::Log { expr, .. } if is_string_js(expr) || matches!(**expr, ASTNode::NumberLiteral(_) | ASTNode::BooleanLiteral(_)) => {
+                format!("console.log({});\n", self.emit_expr_js(expr))
+            }
+            ASTNode::Log { expr, .. } => {
+                self.helpers.insert(Helper::Display);
+                format!("console.log(__aeonmi_display({}));\n", self.emit_expr_js(expr))
+            }
             ASTNode::Assignment { name, value, .. } => {
-                format!("{} = {};\n", name, self.emit_expr_js(value))
+                format!("{} = {};\n", name, self.stored_js(value))
             }
             ASTNode::Call { .. } => format!("{};\n", self.emit_expr_js(node)),
             ASTNode::If {
@@ -178,14 +363,28 @@ impl CodeGenerator {
                 s.push('\n');
                 s
             }
+            ASTNode::ForIn { key, var, iterable, body, .. } => {
+                self.helpers.insert(Helper::Iter);
+                let head = match key {
+                    Some(k) => format!("let [{k}, {var}] of __aeonmi_entries({})", self.stored_js(iterable)),
+                    None => format!("let {var} of __aeonmi_iter({})", self.stored_js(iterable)),
+                };
+                format!("for ({head}) {}\n", self.wrap_stmt_js(body))
+            }
+            ASTNode::Break { .. } => "break;\n".into(),
+            ASTNode::Continue { .. } => "continue;\n".into(),
             ASTNode::BinaryExpr { .. }
             | ASTNode::UnaryExpr { .. }
+            | ASTNode::Index { .. }
+            | ASTNode::Member { .. }
+            | ASTNode::ArrayLiteral { .. }
+            | ASTNode::ObjectLiteral { .. }
             | ASTNode::Identifier(_)
             | ASTNode::IdentifierSpanned { .. }
             | ASTNode::NumberLiteral(_)
             | ASTNode::StringLiteral(_)
             | ASTNode::BooleanLiteral(_) => format!("{};\n", self.emit_expr_js(node)),
-            ASTNode::QuantumOp { op, qubits } => {
+            ASTNode::QuantumOp { op, qubits, .. } => {
                 let opname = match op {
                     TokenKind::Superpose => "superpose",
                     TokenKind::Entangle => "entangle",
@@ -200,6 +399,10 @@ impl CodeGenerator {
                     .join(", ");
                 format!("{}({});\n", opname, args)
             }
+            ASTNode::QubitDecl { name, .. } => format!("let {} = \"|0>\";\n", name),
+            ASTNode::QregDecl { name, size, .. } => {
+                format!("let {} = Array.from({{ length: {} }}, () => \"|0>\");\n", name, size)
+            }
             ASTNode::HieroglyphicOp { symbol, args } => {
                 let a = args
                     .iter()
@@ -208,6 +411,7 @@ impl CodeGenerator {
                     .join(", ");
                 format!("__glyph('{}', {});\n", symbol, a)
             }
+            ASTNode::Import { path, .. } => format!("/* unresolved import: {} */\n", path),
             ASTNode::Error(msg) => format!("/* ERROR NODE: {} */\n", msg),
         }
     }
@@ -216,43 +420,92 @@ impl CodeGenerator {
             ASTNode::Identifier(s) => s.clone(),
             ASTNode::IdentifierSpanned { name, .. } => name.clone(),
             ASTNode::NumberLiteral(n) => {
-                if n.fract() == 0.0 {
+                if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
                     format!("{}", *n as i64)
                 } else {
                     format!("{}", n)
                 }
             }
-            ASTNode::StringLiteral(s) => format!("\"{}\"", s),
+            // escaped so a string never reads as a source-map mark
+            ASTNode::StringLiteral(s) => format!("\"{}\"", s.replace(MARK_OPEN, "\\uE000").replace(MARK_CLOSE, "\\uE001")),
             ASTNode::BooleanLiteral(b) => format!("{}", b),
             ASTNode::BinaryExpr { op, left, right } => {
+                // JS rejects a bare unary operand on the left of `**`
+                let lhs = self.emit_expr_js(left);
+                let lhs = if *op == TokenKind::StarStar && matches!(**left, ASTNode::UnaryExpr { .. }) { format!("({})", lhs) } else { lhs };
+                if *op == TokenKind::Percent {
+                    self.helpers.insert(Helper::Mod);
+                    return format!("__aeonmi_mod({}, {})", lhs, self.emit_expr_js(right));
+                }
+                // string concatenation shows the other operand as the VM does (`[1, 2]`, not `1,2`)
+                if *op == TokenKind::Plus && (is_string_js(left) || is_string_js(right)) {
+                    let shown = |g: &mut Self, side: &ASTNode, js: String| {
+                        if is_string_js(side) {
+                            js
+                        } else {
+                            g.helpers.insert(Helper::Display);
+                            format!("__aeonmi_display({}, true)", js)
+                        }
+                    };
+                    let lhs = shown(self, left, lhs);
+                    let rhs = self.emit_expr_js(right);
+                    let rhs = shown(self, right, rhs);
+                    return format!("({} + {})", lhs, rhs);
+                }
                 format!(
                     "({} {} {})",
-                    self.emit_expr_js(left),
+                    lhs,
                     self.op_str(op),
                     self.emit_expr_js(right)
                 )
             }
             ASTNode::UnaryExpr { op, expr } => {
-                format!("{}{}", self.op_str(op), self.emit_expr_js(expr))
+                // `- -x` must not collapse into JS `--x`
+                if matches!(**expr, ASTNode::UnaryExpr { .. }) {
+                    format!("{}({})", self.op_str(op), self.emit_expr_js(expr))
+                } else {
+                    format!("{}{}", self.op_str(op), self.emit_expr_js(expr))
+                }
+            }
+            ASTNode::Index { target, index, .. } => {
+                format!("{}[{}]", self.emit_expr_js(target), self.emit_expr_js(index))
+            }
+            ASTNode::Member { target, name, .. } => format!("{}.{}", self.emit_expr_js(target), name),
+            ASTNode::ArrayLiteral { items, .. } => {
+                format!("[{}]", items.iter().map(|x| self.stored_js(x)).collect::<Vec<_>>().join(", "))
+            }
+            // keys are always quoted, so any string (or a reserved word) is a valid key
+            ASTNode::ObjectLiteral { fields, .. } if fields.is_empty() => "{}".into(),
+            ASTNode::ObjectLiteral { fields, .. } => {
+                let fields = fields
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", self.emit_expr_js(&ASTNode::StringLiteral(k.clone())), self.stored_js(v)))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{{ {} }}", fields)
             }
             ASTNode::Call { callee, args } => {
+                if let Some(js) = self.array_mutation_js(callee, args) {
+                    return js;
+                }
                 let mapped = match &**callee {
                     ASTNode::Identifier(name) => self.map_helper(name),
                     ASTNode::IdentifierSpanned { name, .. } => self.map_helper(name),
                     _ => None,
                 };
-                let c = mapped.unwrap_or_else(|| self.emit_expr_js(callee));
+                // the runtime helpers only read their arguments
                 let a = args
                     .iter()
-                    .map(|x| self.emit_expr_js(x))
+                    .map(|x| if mapped.is_some() { self.emit_expr_js(x) } else { self.stored_js(x) })
                     .collect::<Vec<_>>()
                     .join(", ");
+                let c = mapped.unwrap_or_else(|| self.emit_expr_js(callee));
                 format!("{}({})", c, a)
             }
             ASTNode::Assignment { name, value, .. } => {
-                format!("{} = {}", name, self.emit_expr_js(value))
+                format!("{} = {}", name, self.stored_js(value))
             }
-            ASTNode::QuantumOp { op, qubits } => {
+            ASTNode::QuantumOp { op, qubits, .. } => {
                 let opname = match op {
                     TokenKind::Superpose => "superpose",
                     TokenKind::Entangle => "entangle",
@@ -278,12 +531,63 @@ impl CodeGenerator {
             _ => "/*expr*/".into(),
         }
     }
+    /// `node` where it is stored (bound, passed or put in a container): the VM stores a copy,
+    /// so in a program that changes arrays a value that may be shared is copied.
+    fn stored_js(&mut self, node: &ASTNode) -> String {
+        let js = self.emit_expr_js(node);
+        let shared = matches!(node, ASTNode::Identifier(_) | ASTNode::IdentifierSpanned { .. } | ASTNode::Index { .. } | ASTNode::Member { .. } | ASTNode::Call { .. });
+        if self.copy_values && shared {
+            self.helpers.insert(Helper::Arrays);
+            format!("__aeonmi_copy({})", js)
+        } else {
+            js
+        }
+    }
+    /// `value` as bound to `target`: unchanged for a name, checked for an array pattern.
+    fn unpack_js(&mut self, target: &Pattern, value: &ASTNode) -> String {
+        let v = self.stored_js(value);
+        match target {
+            Pattern::Name { .. } => v,
+            Pattern::Array { .. } => {
+                self.helpers.insert(Helper::Unpack);
+                format!("__aeonmi_unpack({}, {})", v, shape_js(target))
+            }
+        }
+    }
+    /// `push(xs, v)` / `pop(xs)` on a variable: like the VM, a `null` variable becomes an
+    /// empty array first (the lowering rejects other first arguments for the VM).
+    fn array_mutation_js(&mut self, callee: &ASTNode, args: &[ASTNode]) -> Option<String> {
+        let name = match callee {
+            ASTNode::Identifier(name) | ASTNode::IdentifierSpanned { name, .. } => name.as_str(),
+            _ => return None,
+        };
+        let var = match (name, args.first()) {
+            ("push" | "pop", Some(ASTNode::Identifier(v) | ASTNode::IdentifierSpanned { name: v, .. })) => v.clone(),
+            _ => return None,
+        };
+        self.helpers.insert(Helper::Arrays);
+        let target = format!("({var} == null ? ({var} = []) : {var})");
+        Some(if name == "push" {
+            let rest: Vec<String> = args[1..].iter().map(|a| self.stored_js(a)).collect();
+            format!("__aeonmi_push({target}, {})", rest.join(", "))
+        } else {
+            format!("__aeonmi_pop({target}, \"{var}\")")
+        })
+    }
     fn map_helper(&mut self, name: &str) -> Option<String> {
         match name {
+            "push" | "pop" | "slice" => {
+                self.helpers.insert(Helper::Arrays);
+                Some(format!("__aeonmi_{}", name))
+            }
             "len" => {
                 self.helpers.insert(Helper::Len);
                 Some("__aeonmi_len".to_string())
             }
+            "read_line" | "read_all" => {
+                self.helpers.insert(Helper::Stdin);
+                Some(format!("__aeonmi_{}", name))
+            }
             _ => None,
         }
     }
@@ -297,7 +601,7 @@ impl CodeGenerator {
                 Helper::Len => {
                     prelude.push_str("const __aeonmi_len = (value) => {\n");
                     prelude.push_str(
-                        "    if (typeof value === \"string\") { return value.length; }\n",
+                        "    if (typeof value === \"string\") { return Array.from(value).length; }\n",
                     );
                     prelude.push_str("    if (Array.isArray(value)) { return value.length; }\n");
                     prelude.push_str(
@@ -308,6 +612,134 @@ impl CodeGenerator {
                     prelude.push_str("    throw new Error(\"len: unsupported type\");\n");
                     prelude.push_str("};\n");
                 }
+                Helper::Iter => {
+                    // same order as the VM's __iter / __iter_keys: code points, sorted map keys
+                    prelude.push_str("const __aeonmi_iter = (value) => {\n");
+                    prelude.push_str("    if (typeof value === \"string\") { return Array.from(value); }\n");
+                    prelude.push_str("    if (Array.isArray(value)) { return value; }\n");
+                    prelude.push_str("    if (value === null || value === undefined) { return []; }\n");
+                    prelude.push_str("    if (typeof value === \"object\") { return Object.keys(value).sort(); }\n");
+                    prelude.push_str("    throw new Error(\"cannot iterate over \" + value);\n");
+                    prelude.push_str("};\n");
+                    prelude.push_str("const __aeonmi_entries = (value) => {\n");
+                    prelude.push_str(
+                        "    if (typeof value === \"string\" || Array.isArray(value)) { return Array.from(value, (v, i) => [i, v]); }\n",
+                    );
+                    prelude.push_str("    return __aeonmi_iter(value).map((k) => [k, value[k]]);\n");
+                    prelude.push_str("};\n");
+                }
+                Helper::Arrays => {
+                    // same rules as the VM's __push / __pop / slice
+                    prelude.push_str("const __aeonmi_push = (items, value) => {\n");
+                    prelude.push_str("    if (!Array.isArray(items)) { throw new Error(\"push expects an array, got \" + items); }\n");
+                    prelude.push_str("    return items.push(value);\n");
+                    prelude.push_str("};\n");
+                    prelude.push_str("const __aeonmi_pop = (items, name) => {\n");
+                    prelude.push_str("    if (!Array.isArray(items)) { throw new Error(\"pop expects an array, got \" + items); }\n");
+                    prelude.push_str("    if (items.length === 0) { throw new Error(\"pop: `\" + name + \"` is empty\"); }\n");
+                    prelude.push_str("    return items.pop();\n");
+                    prelude.push_str("};\n");
+                    // the VM's values never share: a stored array or object is a deep copy
+                    prelude.push_str("const __aeonmi_copy = (value) => {\n");
+                    prelude.push_str("    if (Array.isArray(value)) { return value.map(__aeonmi_copy); }\n");
+                    prelude.push_str(
+                        "    if (value !== null && typeof value === \"object\" && !Object.isFrozen(value)) { return Object.fromEntries(Object.entries(value).map(([k, v]) => [k, __aeonmi_copy(v)])); }\n",
+                    );
+                    prelude.push_str("    return value;\n");
+                    prelude.push_str("};\n");
+                    prelude.push_str("const __aeonmi_slice = (items, start, end) => {\n");
+                    prelude.push_str("    if (items === null || items === undefined) { items = []; }\n");
+                    prelude.push_str("    if (typeof items === \"string\") { items = Array.from(items); }\n");
+                    prelude.push_str("    if (!Array.isArray(items)) { throw new Error(\"slice expects an array, got \" + items); }\n");
+                    prelude.push_str("    const clamp = (n) => Math.min(Math.max(Math.trunc(n), 0), items.length);\n");
+                    prelude.push_str("    const from = clamp(start);\n");
+                    prelude.push_str("    return items.slice(from, Math.max(clamp(end), from));\n");
+                    prelude.push_str("};\n");
+                }
+                Helper::Unpack => {
+                    // same checks and message as the VM's destructuring; `shape` is 0 for a name
+                    prelude.push_str("const __aeonmi_unpack = (value, shape) => {\n");
+                    prelude.push_str("    const items = typeof value === \"string\" ? Array.from(value) : value;\n");
+                    prelude.push_str("    if (!Array.isArray(items)) { throw new Error(\"cannot destructure \" + value); }\n");
+                    prelude.push_str(
+                        "    if (items.length !== shape.length) { throw new Error(\"destructuring expected \" + shape.length + \" values, got \" + items.length); }\n",
+                    );
+                    prelude.push_str("    return items.map((v, i) => (shape[i] === 0 ? v : __aeonmi_unpack(v, shape[i])));\n");
+                    prelude.push_str("};\n");
+                }
+                Helper::Stdin => {
+                    // stdin is read once on first use; lines are served from the buffer
+                    prelude.push_str("const __aeonmi_stdin = { text: null, pos: 0 };\n");
+                    prelude.push_str("const __aeonmi_stdin_fill = () => {\n");
+                    prelude.push_str(
+                        "    if (__aeonmi_stdin.text === null) { try { __aeonmi_stdin.text = require(\"fs\").readFileSync(0, \"utf8\"); } catch (_) { __aeonmi_stdin.text = \"\"; } }\n",
+                    );
+                    prelude.push_str("};\n");
+                    prelude.push_str("const __aeonmi_read_line = () => {\n");
+                    prelude.push_str("    __aeonmi_stdin_fill();\n");
+                    prelude.push_str("    const s = __aeonmi_stdin;\n");
+                    prelude.push_str("    if (s.pos >= s.text.length) { return null; }\n");
+                    prelude.push_str("    let end = s.text.indexOf(\"\\n\", s.pos);\n");
+                    prelude.push_str("    if (end < 0) { end = s.text.length; }\n");
+                    prelude.push_str("    let line = s.text.slice(s.pos, end);\n");
+                    prelude.push_str("    s.pos = end + 1;\n");
+                    prelude.push_str(
+                        "    if (line.endsWith(\"\\r\")) { line = line.slice(0, -1); }\n",
+                    );
+                    prelude.push_str("    return line;\n");
+                    prelude.push_str("};\n");
+                    prelude.push_str("const __aeonmi_read_all = () => {\n");
+                    prelude.push_str("    __aeonmi_stdin_fill();\n");
+                    prelude.push_str("    const rest = __aeonmi_stdin.text.slice(__aeonmi_stdin.pos);\n");
+                    prelude.push_str("    __aeonmi_stdin.pos = __aeonmi_stdin.text.length;\n");
+                    prelude.push_str("    return rest;\n");
+                    prelude.push_str("};\n");
+                }
+                Helper::Mod => {
+                    // the VM stops on a zero divisor where JS `%` would give NaN
+                    prelude.push_str("const __aeonmi_mod = (a, b) => {\n");
+                    prelude.push_str("    if (b === 0 && typeof a === \"number\") { throw new Error(\"modulo by zero\"); }\n");
+                    prelude.push_str("    return a % b;\n");
+                    prelude.push_str("};\n");
+                }
+                Helper::Display => {
+                    // vm_display: `plain` is display_plain (string `+`), otherwise display_pretty
+                    // at LOG_DEPTH and PRETTY_WIDTH (`log`)
+                    prelude.push_str("const __aeonmi_display = (value, plain) => {\n");
+                    prelude.push_str("    const ancestors = [];\n");
+                    prelude.push_str("    const render = (v, depth, indent) => {\n");
+                    prelude.push_str("        if (v !== null && typeof v === \"object\") {\n");
+                    prelude.push_str("            const [open, close] = Array.isArray(v) ? [\"[\", \"]\"] : [\"{\", \"}\"];\n");
+                    prelude.push_str("            if (ancestors.includes(v)) { return \"«cycle»\"; }\n");
+                    prelude.push_str("            if (!plain && depth >= 8) { return open + \"…\" + close; }\n");
+                    prelude.push_str("            ancestors.push(v);\n");
+                    prelude.push_str("            const parts = Array.isArray(v)\n");
+                    prelude.push_str("                ? v.map((item) => render(item, depth + 1, indent + 2))\n");
+                    prelude.push_str("                : Object.keys(v).sort().map((k) => k + \": \" + render(v[k], depth + 1, indent + 2));\n");
+                    prelude.push_str("            ancestors.pop();\n");
+                    prelude.push_str("            if (parts.length === 0) { return open + close; }\n");
+                    prelude.push_str("            const line = open + parts.join(\", \") + close;\n");
+                    prelude.push_str("            if (plain || (indent + Array.from(line).length <= 72 && !line.includes(\"\\n\"))) { return line; }\n");
+                    prelude.push_str("            const pad = \" \".repeat(indent + 2);\n");
+                    prelude.push_str("            return open + \"\\n\" + pad + parts.join(\",\\n\" + pad) + \"\\n\" + \" \".repeat(indent) + close;\n");
+                    prelude.push_str("        }\n");
+                    prelude.push_str("        if (typeof v === \"string\") { return depth > 0 ? JSON.stringify(v) : v; }\n");
+                    prelude.push_str("        if (typeof v === \"function\") { return \"<fn>\"; }\n");
+                    prelude.push_str("        return v === undefined ? \"null\" : String(v);\n");
+                    prelude.push_str("    };\n");
+                    prelude.push_str("    return render(value, 0, 0);\n");
+                    prelude.push_str("};\n");
+                }
+                Helper::Exit => {
+                    // same mapping as vm::exit_code
+                    prelude.push_str("const __aeonmi_exit_code = (value) => {\n");
+                    prelude.push_str(
+                        "    if (typeof value === \"number\") { return Number.isNaN(value) ? 0 : Math.min(255, Math.max(0, Math.trunc(value))); }\n",
+                    );
+                    prelude.push_str("    if (typeof value === \"boolean\") { return value ? 0 : 1; }\n");
+                    prelude.push_str("    return 0;\n");
+                    prelude.push_str("};\n");
+                }
             }
         }
         prelude
@@ -327,19 +759,29 @@ impl CodeGenerator {
                 s.push_str("{\n");
                 self.indent += 1;
                 s.push_str(&self.indent_str());
-                s.push_str(&self.emit_js(n));
+                s.push_str(&self.emit_stmt_js(n));
                 self.indent -= 1;
                 s.push('}');
                 s
             }
         }
     }
+    /// A statement, preceded by its source mark when building a source map.
+    fn emit_stmt_js(&mut self, n: &ASTNode) -> String {
+        let code = self.emit_js(n);
+        match statement_position(n).filter(|_| self.mark_positions) {
+            Some((line, column)) => format!("{MARK_OPEN}{line}:{column}{MARK_CLOSE}{code}"),
+            None => code,
+        }
+    }
     fn op_str(&self, op: &TokenKind) -> &'static str {
         match op {
             TokenKind::Plus => "+",
             TokenKind::Minus => "-",
             TokenKind::Star => "*",
             TokenKind::Slash => "/",
+            TokenKind::Percent => "%",
+            TokenKind::StarStar => "**",
             TokenKind::Equals => "=",
             TokenKind::DoubleEquals => "==",
             TokenKind::NotEquals => "!=",
@@ -347,6 +789,9 @@ impl CodeGenerator {
             TokenKind::LessEqual => "<=",
             TokenKind::GreaterThan => ">",
             TokenKind::GreaterEqual => ">=",
+            TokenKind::AndAnd => "&&",
+            TokenKind::OrOr => "||",
+            TokenKind::Bang => "!",
             // Only match the variants that exist in TokenKind
             _ => "/*op*/",
         }
@@ -365,6 +810,91 @@ impl CodeGenerator {
     }
 }
 
+/// Whether `node` calls `push` or `pop` anywhere.
+pub(crate) fn mutates_arrays(node: &ASTNode) -> bool {
+    let any = |nodes: &[ASTNode]| nodes.iter().any(mutates_arrays);
+    match node {
+        ASTNode::Call { callee, args } => {
+            matches!(&**callee, ASTNode::Identifier(n) | ASTNode::IdentifierSpanned { name: n, .. } if n == "push" || n == "pop")
+                || mutates_arrays(callee)
+                || any(args)
+        }
+        ASTNode::Program(items) | ASTNode::Block(items) | ASTNode::Function { body: items, .. } => any(items),
+        ASTNode::DestructuringDecl { values, .. } => any(values),
+        ASTNode::ArrayLiteral { items, .. } => any(items),
+        ASTNode::ObjectLiteral { fields, .. } => fields.iter().any(|(_, v)| mutates_arrays(v)),
+        ASTNode::QuantumOp { qubits: args, .. } | ASTNode::HieroglyphicOp { args, .. } => any(args),
+        ASTNode::VariableDecl { value, .. } | ASTNode::Assignment { value, .. } => mutates_arrays(value),
+        ASTNode::Return(e) | ASTNode::Log { expr: e, .. } | ASTNode::UnaryExpr { expr: e, .. } | ASTNode::Member { target: e, .. } => mutates_arrays(e),
+        ASTNode::BinaryExpr { left, right, .. } | ASTNode::Index { target: left, index: right, .. } => mutates_arrays(left) || mutates_arrays(right),
+        ASTNode::If { condition, then_branch, else_branch } => {
+            mutates_arrays(condition) || mutates_arrays(then_branch) || else_branch.as_deref().is_some_and(mutates_arrays)
+        }
+        ASTNode::While { condition, body } => mutates_arrays(condition) || mutates_arrays(body),
+        ASTNode::For { init, condition, increment, body } => {
+            [init, condition, increment].into_iter().flatten().any(|n| mutates_arrays(n)) || mutates_arrays(body)
+        }
+        ASTNode::ForIn { iterable, body, .. } => mutates_arrays(iterable) || mutates_arrays(body),
+        ASTNode::Match { subject, arms, .. } => {
+            mutates_arrays(subject) || arms.iter().any(|a| a.pattern.as_ref().is_some_and(mutates_arrays) || mutates_arrays(&a.body))
+        }
+        _ => false,
+    }
+}
+
+/// Whether `node` is a string in any run: a string literal, or a `+` with one on either side
+/// (every interpolation is one).
+fn is_string_js(node: &ASTNode) -> bool {
+    match node {
+        ASTNode::StringLiteral(_) => true,
+        ASTNode::BinaryExpr { op: TokenKind::Plus, left, right } => is_string_js(left) || is_string_js(right),
+        _ => false,
+    }
+}
+
+/// The nesting `__aeonmi_unpack` checks: `0` for a name, an array per array pattern.
+fn shape_js(p: &Pattern) -> String {
+    match p {
+        Pattern::Name { .. } => "0".into(),
+        Pattern::Array { items, .. } => format!("[{}]", items.iter().map(shape_js).collect::<Vec<_>>().join(", ")),
+    }
+}
+
+/// Where a statement starts in the source: its own position, or for the nodes that carry
+/// none (`return`, `if`, loops, calls) the first position found in their leading expression.
+fn statement_position(node: &ASTNode) -> Option<(usize, usize)> {
+    let pos = match node {
+        ASTNode::Function { line, column, .. }
+        | ASTNode::VariableDecl { line, column, .. }
+        | ASTNode::DestructuringDecl { line, column, .. }
+        | ASTNode::EnumDecl { line, column, .. }
+        | ASTNode::Import { line, column, .. }
+        | ASTNode::Log { line, column, .. }
+        | ASTNode::ForIn { line, column, .. }
+        | ASTNode::Break { line, column }
+        | ASTNode::Continue { line, column }
+        | ASTNode::Match { line, column, .. }
+        | ASTNode::Assignment { line, column, .. }
+        | ASTNode::Index { line, column, .. }
+        | ASTNode::Member { line, column, .. }
+        | ASTNode::ArrayLiteral { line, column, .. }
+        | ASTNode::ObjectLiteral { line, column, .. }
+        | ASTNode::IdentifierSpanned { line, column, .. }
+        | ASTNode::QubitDecl { line, column, .. }
+        | ASTNode::QregDecl { line, column, .. }
+        | ASTNode::QuantumOp { line, column, .. } => Some((*line, *column)),
+        ASTNode::Return(e) => statement_position(e),
+        ASTNode::If { condition, .. } | ASTNode::While { condition, .. } => statement_position(condition),
+        ASTNode::For { init, condition, .. } => init.iter().chain(condition).find_map(|n| statement_position(n)),
+        ASTNode::Call { callee, args } => statement_position(callee).or_else(|| args.iter().find_map(statement_position)),
+        ASTNode::BinaryExpr { left, right, .. } => statement_position(left).or_else(|| statement_position(right)),
+        ASTNode::UnaryExpr { expr, .. } => statement_position(expr),
+        ASTNode::HieroglyphicOp { args, .. } => args.iter().find_map(statement_position),
+        _ => None,
+    };
+    pos.filter(|&(line, _)| line > 0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -379,7 +909,7 @@ mod tests {
         let mut g = CodeGenerator::new(); // JS default
         let js = g.generate(&ast).unwrap();
         assert!(js.contains("let x = 42;"));
-        assert!(js.contains("console.log(x);"));
+        assert!(js.contains("console.log(__aeonmi_display(x));"));
     }
     #[test]
     fn gen_assignment_and_call_js() {
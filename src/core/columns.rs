@@ -0,0 +1,91 @@
+//! Column conventions shared by diagnostics, rename and the TUI.
+//!
+//! Internally a column is a 1-based UTF-8 byte offset into its line (what the lexer
+//! records, and what `&line[col - 1..]` expects). Two conversions cover the rest:
+//! display columns for anything drawn in a terminal (wide glyphs take two cells,
+//! combining marks none), and mapping back onto the raw source when it was not NFC —
+//! the lexer normalizes its input, so its offsets are into the NFC form of the line.
+
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Terminal cells `s` occupies.
+pub fn display_width(s: &str) -> usize {
+    UnicodeWidthStr::width(s)
+}
+
+/// Largest char boundary of `s` at or before byte `i`.
+pub fn floor_char_boundary(s: &str, i: usize) -> usize {
+    let mut i = i.min(s.len());
+    while !s.is_char_boundary(i) {
+        i -= 1;
+    }
+    i
+}
+
+/// 1-based display column of 1-based byte column `col` in `line`.
+pub fn display_col(line: &str, col: usize) -> usize {
+    display_width(&line[..floor_char_boundary(line, col.saturating_sub(1))]) + 1
+}
+
+/// Display width of the `len` bytes starting at byte column `col` (at least 1, for carets).
+pub fn display_len(line: &str, col: usize, len: usize) -> usize {
+    let start = floor_char_boundary(line, col.saturating_sub(1));
+    let end = floor_char_boundary(line, start + len);
+    display_width(&line[start..end]).max(1)
+}
+
+/// Byte range of `line` drawn in the display cells `[skip, skip + width)`; characters
+/// straddling either edge are left out. Only walks the line as far as the window.
+pub fn visible_range(line: &str, skip: usize, width: usize) -> std::ops::Range<usize> {
+    let (mut start, mut cells) = (line.len(), 0);
+    for (b, ch) in line.char_indices() {
+        if cells >= skip {
+            start = b;
+            break;
+        }
+        cells += ch.width().unwrap_or(0);
+    }
+    let mut end = start;
+    let mut used = cells - skip.min(cells);
+    for ch in line[start..].chars() {
+        let w = ch.width().unwrap_or(0);
+        if used + w > width {
+            break;
+        }
+        used += w;
+        end += ch.len_utf8();
+    }
+    start..end
+}
+
+/// Byte offset in the raw `line` of lexer column `col` (an offset into the line's NFC
+/// form); `None` when it lands inside a character or past the end.
+pub fn source_offset(line: &str, col: usize) -> Option<usize> {
+    let target = col.checked_sub(1)?;
+    if is_nfc(line) {
+        return (target <= line.len() && line.is_char_boundary(target)).then_some(target);
+    }
+    for b in line.char_indices().map(|(b, _)| b).chain(std::iter::once(line.len())) {
+        let nfc_len: usize = line[..b].nfc().map(char::len_utf8).sum();
+        if nfc_len >= target {
+            return (nfc_len == target).then_some(b);
+        }
+    }
+    None
+}
+
+/// Width to lay tables and wrapped text out to: `COLUMNS`, else the terminal size, else
+/// 100 for pipes.
+pub fn terminal_width() -> usize {
+    use std::io::IsTerminal;
+    if let Some(c) = std::env::var("COLUMNS").ok().and_then(|s| s.parse::<usize>().ok()).filter(|&c| c > 0) {
+        return c;
+    }
+    if std::io::stdout().is_terminal() {
+        if let Ok((w, _)) = crossterm::terminal::size() {
+            return w as usize;
+        }
+    }
+    100
+}
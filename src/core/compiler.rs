@@ -13,6 +13,7 @@ use crate::core::{
     parser::{Parser, ParserError},
     semantic_analyzer::SemanticAnalyzer,
     token::TokenKind,
+    toolchain::{self, Tool},
 };
 
 // Expose IR types so the CLI can request an IR build (stubbed for now).
@@ -75,7 +76,14 @@ impl Compiler {
         // 4) Semantic analysis (optional)
         if run_semantic {
             let mut analyzer = SemanticAnalyzer::new();
-            match analyzer.analyze(&ast) {
+            if let Some(budget) = crate::core::analysis_budget::AnalysisBudget::from_flag() {
+                analyzer = analyzer.with_budget(budget);
+            }
+            let result = analyzer.analyze(&ast);
+            if let Some(t) = analyzer.truncation() {
+                println!("Semantic Analyzer: {}.", t);
+            }
+            match result {
                 Ok(()) => println!("Semantic Analyzer: No semantic errors found."),
                 Err(e) => {
                     println!("Semantic Analyzer errors: {}", e);
@@ -204,6 +212,10 @@ pub fn compile_and_run_js(input: &PathBuf) -> Result<(), String> {
         .map_err(|e| format!("{e}"))?;
 
     // Try to run with node; warn but don't fail if it's missing or exits non-zero.
+    if !toolchain::available(Tool::Node) {
+        eprintln!("warn: {}", toolchain::missing_tool_message(Tool::Node, "run the compiled aeonmi.run.js"));
+        return Ok(());
+    }
     match Command::new("node").arg(&out).status() {
         Ok(status) => {
             if !status.success() {
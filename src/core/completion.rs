@@ -0,0 +1,199 @@
+//! Completion provider for editors (the GUI's `aeonmi_complete`).
+//!
+//! Candidates are keywords, builtins and quantum ops with signature snippets, the
+//! variables and functions visible at the cursor (from [`ScopeMap`]), and an enum's
+//! variants after `Enum.`. Buffers that do not parse (the usual state mid-edit) are
+//! completed against the buffer with the cursor line blanked, else the last good AST
+//! from the incremental cache, plus a token scan of the cursor line for its own `let`s.
+//! Positions are 1-based; `column` is the byte column just past the typed prefix.
+
+use serde::Serialize;
+
+use crate::core::ast::ASTNode;
+use crate::core::lexer::Lexer;
+use crate::core::parser::Parser;
+use crate::core::scope_map::ScopeMap;
+use crate::core::token::TokenKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompletionKind {
+    Variable,
+    Function,
+    Enum,
+    EnumMember,
+    Builtin,
+    QuantumOp,
+    Keyword,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CompletionItem {
+    pub label: String,
+    pub kind: CompletionKind,
+    /// Signature or short description shown next to the label.
+    pub detail: String,
+    /// Text to insert; calls use `${n:param}` snippet placeholders.
+    pub insert_text: String,
+}
+
+const KEYWORDS: &[&str] = &["let", "function", "return", "log", "if", "else", "for", "while", "in", "break", "continue", "match", "enum", "qubit", "qreg", "true", "false"];
+
+/// Builtins of the native VM: name and parameter names.
+const BUILTINS: &[(&str, &[&str])] = &[
+    ("len", &["value"]),
+    ("push", &["array", "value"]),
+    ("pop", &["array"]),
+    ("slice", &["array", "start", "end"]),
+    ("env", &["name"]),
+    ("rand", &[]),
+    ("read_line", &[]),
+    ("read_all", &[]),
+    ("read_file", &["path"]),
+    ("write_file", &["path", "text"]),
+    ("run_shots", &["shots", "body"]),
+    ("counts", &[]),
+    ("minimize", &["objective", "initial", "options"]),
+    ("grid_search", &["objective", "ranges"]),
+    ("simulate_ode", &["f", "y0", "t_span", "dt"]),
+    ("logistic_map", &["r", "x0", "n"]),
+    ("derivative", &["expr", "var"]),
+    ("simplify", &["expr"]),
+];
+
+const QUANTUM_OPS: &[(&str, &[&str])] = &[("superpose", &["q"]), ("entangle", &["a", "b"]), ("measure", &["q"]), ("dod", &["q"])];
+
+fn call_item(label: &str, kind: CompletionKind, params: &[String]) -> CompletionItem {
+    let snippet: Vec<String> = params.iter().enumerate().map(|(i, p)| format!("${{{}:{p}}}", i + 1)).collect();
+    CompletionItem {
+        label: label.to_string(),
+        kind,
+        detail: format!("{label}({})", params.join(", ")),
+        insert_text: format!("{label}({})", snippet.join(", ")),
+    }
+}
+
+fn plain_item(label: &str, kind: CompletionKind, detail: &str) -> CompletionItem {
+    CompletionItem { label: label.to_string(), kind, detail: detail.to_string(), insert_text: label.to_string() }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// The text left of the cursor on its line.
+fn line_prefix(source: &str, line: usize, column: usize) -> String {
+    let text = source.lines().nth(line.saturating_sub(1)).unwrap_or("");
+    text[..crate::core::columns::floor_char_boundary(text, column.saturating_sub(1))].to_string()
+}
+
+fn parse(source: &str) -> Option<ASTNode> {
+    let tokens = Lexer::from_str(source).tokenize().ok()?;
+    Parser::new(tokens).parse().ok()
+}
+
+/// The best AST available for a possibly broken buffer.
+fn best_ast(source: &str, line: usize) -> Option<ASTNode> {
+    if let Ok(ast) = crate::core::incremental::parse_or_cached(source) {
+        return Some(ast);
+    }
+    let blanked: Vec<&str> = source.lines().enumerate().map(|(i, l)| if i + 1 == line { "" } else { l }).collect();
+    parse(&blanked.join("\n")).or_else(crate::core::incremental::last_good_ast)
+}
+
+fn top_items(ast: &ASTNode) -> &[ASTNode] {
+    match ast {
+        ASTNode::Program(items) => items,
+        _ => &[],
+    }
+}
+
+fn item_line(node: &ASTNode) -> usize {
+    match node {
+        ASTNode::Function { line, .. } | ASTNode::VariableDecl { line, .. } | ASTNode::DestructuringDecl { line, .. } | ASTNode::EnumDecl { line, .. } | ASTNode::QubitDecl { line, .. } | ASTNode::QregDecl { line, .. } | ASTNode::Assignment { line, .. } | ASTNode::Log { line, .. } | ASTNode::Match { line, .. } => *line,
+        _ => 0,
+    }
+}
+
+/// Index of the top-level item whose lines contain `line` (items run until the next one starts).
+fn enclosing_item(items: &[ASTNode], line: usize) -> Option<usize> {
+    items.iter().enumerate().filter(|(_, n)| item_line(n) > 0 && item_line(n) <= line).map(|(i, _)| i).next_back()
+}
+
+/// Every candidate before filtering, each with its proximity rank (lower is closer).
+fn candidates(ast: Option<&ASTNode>, line_text: &str, line: usize) -> Vec<(u8, CompletionItem)> {
+    let mut out: Vec<(u8, CompletionItem)> = Vec::new();
+    if let Some(ast) = ast {
+        let items = top_items(ast);
+        let cursor_item = enclosing_item(items, line);
+        let sm = ScopeMap::build(ast);
+        for (name, entries) in &sm.symbols {
+            // The innermost visible definition decides kind and rank.
+            let visible = entries.iter().filter(|e| e.is_def).filter(|e| e.scope_id == 0 || (e.line <= line && enclosing_item(items, e.line) == cursor_item)).max_by_key(|e| sm.depth(e.scope_id));
+            let Some(def) = visible else { continue };
+            let item = if def.scope_id == 0 {
+                match items.iter().find(|n| matches!(n, ASTNode::Function { name: n, .. } | ASTNode::EnumDecl { name: n, .. } if n == name)) {
+                    Some(ASTNode::Function { params, .. }) => call_item(name, CompletionKind::Function, &params.iter().map(|p| p.name.clone()).collect::<Vec<_>>()),
+                    Some(ASTNode::EnumDecl { .. }) => plain_item(name, CompletionKind::Enum, "enum"),
+                    _ => plain_item(name, CompletionKind::Variable, "global"),
+                }
+            } else {
+                plain_item(name, CompletionKind::Variable, "local")
+            };
+            out.push((if def.scope_id == 0 { 1 } else { 0 }, item));
+        }
+    }
+    // Declarations on the cursor line itself never made it into an AST.
+    let words: Vec<&str> = line_text.split(|c: char| !is_ident_char(c)).filter(|w| !w.is_empty()).collect();
+    for pair in words.windows(2) {
+        if pair[0] == "let" && !out.iter().any(|(_, it)| it.label == pair[1]) {
+            out.push((0, plain_item(pair[1], CompletionKind::Variable, "local")));
+        }
+    }
+    out.extend(BUILTINS.iter().map(|(n, ps)| (2, call_item(n, CompletionKind::Builtin, &ps.iter().map(|p| p.to_string()).collect::<Vec<_>>()))));
+    out.extend(QUANTUM_OPS.iter().map(|(n, ps)| (3, call_item(n, CompletionKind::QuantumOp, &ps.iter().map(|p| p.to_string()).collect::<Vec<_>>()))));
+    out.extend(KEYWORDS.iter().map(|k| (4, plain_item(k, CompletionKind::Keyword, "keyword"))));
+    out
+}
+
+/// Variants of enum `name` declared in `ast`, or (for a broken buffer) on any line of `source`.
+fn enum_members(ast: Option<&ASTNode>, source: &str, name: &str) -> Vec<CompletionItem> {
+    let from_ast = ast.into_iter().flat_map(top_items).find_map(|n| match n {
+        ASTNode::EnumDecl { name: e, variants, .. } if e == name => Some(variants.clone()),
+        _ => None,
+    });
+    let variants = from_ast.or_else(|| {
+        let tokens = Lexer::from_str(source).tokenize().ok()?;
+        let at = tokens.windows(2).position(|w| w[0].kind == TokenKind::Enum && w[1].kind == TokenKind::Identifier(name.to_string()))?;
+        let body = tokens[at + 2..].iter().skip(1).take_while(|t| t.kind != TokenKind::CloseBrace);
+        Some(body.filter_map(|t| match &t.kind { TokenKind::Identifier(v) => Some(v.clone()), _ => None }).collect())
+    });
+    variants.unwrap_or_default().iter().map(|v| plain_item(v, CompletionKind::EnumMember, &format!("{name}.{v}"))).collect()
+}
+
+/// Completions at (`line`, `column`) of `source`, best first: prefix matches that keep case,
+/// then case-insensitive ones; within each, closer scopes first, then alphabetical.
+pub fn complete(source: &str, line: usize, column: usize) -> Vec<CompletionItem> {
+    let text = line_prefix(source, line, column);
+    let prefix: String = text.chars().rev().take_while(|&c| is_ident_char(c)).collect::<Vec<_>>().into_iter().rev().collect();
+    let before = &text[..text.len() - prefix.len()];
+    let ast = best_ast(source, line);
+    let ranked: Vec<(u8, CompletionItem)> = match before.strip_suffix('.') {
+        Some(head) => {
+            let target: String = head.chars().rev().take_while(|&c| is_ident_char(c)).collect::<Vec<_>>().into_iter().rev().collect();
+            enum_members(ast.as_ref(), source, &target).into_iter().map(|it| (0, it)).collect()
+        }
+        None => candidates(ast.as_ref(), &text, line),
+    };
+    let lower = prefix.to_lowercase();
+    let mut scored: Vec<(u8, u8, CompletionItem)> = ranked
+        .into_iter()
+        .filter_map(|(proximity, it)| {
+            let quality = if it.label.starts_with(&prefix) { 0 } else if it.label.to_lowercase().starts_with(&lower) { 1 } else { return None };
+            Some((quality, proximity, it))
+        })
+        .collect();
+    scored.sort_by(|a, b| (a.0, a.1, &a.2.label).cmp(&(b.0, b.1, &b.2.label)));
+    scored.dedup_by(|a, b| a.2.label == b.2.label);
+    scored.into_iter().map(|(_, _, it)| it).collect()
+}
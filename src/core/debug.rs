@@ -1,15 +1,66 @@
-//! Lightweight debug logging gated by AEONMI_DEBUG=1.
-use std::sync::OnceLock;
+//! Internal logging, off unless asked for with `-v` / `-vv` or `AEONMI_DEBUG=1|2`.
+//!
+//! Lines go to stderr as `<UTC time> <LEVEL> <target>: <message>`, where the target is the
+//! emitting module, so program output on stdout is never mixed with them.
+//! `debug_log!` is level 1 (`-v`), `trace_log!` level 2 (`-vv`, per-statement VM detail).
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
 
-static ENABLED: OnceLock<bool> = OnceLock::new();
+/// Not yet read from the environment.
+const UNSET: u8 = u8::MAX;
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(UNSET);
+
+/// Current level: 0 off, 1 debug, 2 trace.
+pub fn verbosity() -> u8 {
+    match VERBOSITY.load(Ordering::Relaxed) {
+        UNSET => {
+            let level = match std::env::var("AEONMI_DEBUG").ok().as_deref() {
+                Some("1") => 1,
+                Some("2") => 2,
+                _ => 0,
+            };
+            VERBOSITY.store(level, Ordering::Relaxed);
+            level
+        }
+        level => level,
+    }
+}
+
+/// Raise the level (from `-v` flags); a higher `AEONMI_DEBUG` still wins.
+pub fn set_verbosity(level: u8) {
+    let level = level.min(2).max(verbosity());
+    VERBOSITY.store(level, Ordering::Relaxed);
+}
 
 pub fn is_enabled() -> bool {
-    *ENABLED.get_or_init(|| std::env::var("AEONMI_DEBUG").ok().as_deref() == Some("1"))
+    verbosity() >= 1
+}
+
+pub fn trace_enabled() -> bool {
+    verbosity() >= 2
+}
+
+/// Write one log line; callers go through the macros, which skip formatting when disabled.
+pub fn log(level: u8, target: &str, args: fmt::Arguments<'_>) {
+    let name = if level >= 2 { "TRACE" } else { "DEBUG" };
+    eprintln!("{} {} {}: {}", chrono::Utc::now().format("%H:%M:%S%.3f"), name, target, args);
 }
 
 #[macro_export]
 macro_rules! debug_log {
     ($($arg:tt)*) => {{
-        if $crate::core::debug::is_enabled() { eprintln!($($arg)*); }
+        if $crate::core::debug::is_enabled() {
+            $crate::core::debug::log(1, module_path!(), format_args!($($arg)*));
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! trace_log {
+    ($($arg:tt)*) => {{
+        if $crate::core::debug::trace_enabled() {
+            $crate::core::debug::log(2, module_path!(), format_args!($($arg)*));
+        }
     }};
 }
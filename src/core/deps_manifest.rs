@@ -0,0 +1,193 @@
+//! `<artifact>.deps.json`: the files a compiled artifact was built from.
+//!
+//! `aeonmi run` and `aeonmi build` record the entry file and every file it imports, each
+//! with its SHA-1 and the file whose `import` loaded it, together with the compiler
+//! (version and build commit) and the settings the artifact was compiled under. Before reusing the artifact
+//! they hash those files again; the first one that no longer matches is why it gets
+//! recompiled (logged with `-v`). `aeonmi build --explain <artifact>` prints the graph.
+
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::core::imports::Flattened;
+use crate::io::atomic::atomic_write;
+
+/// Bumped when the layout of the file changes.
+pub const SCHEMA: u32 = 1;
+
+/// What the artifact depends on besides its sources.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildOptions {
+    pub sema_skipped: bool,
+    /// Written with `<artifact>.map`.
+    pub source_map: bool,
+    /// Enabled lexer plugins from `Aeonmi.toml`.
+    pub plugins: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dependency {
+    pub path: String,
+    pub sha1: String,
+    /// The file whose `import` loaded this one; absent for the entry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imported_by: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepsManifest {
+    pub schema: u32,
+    /// [`compiler_identity`] of the compiler that wrote the artifact.
+    pub compiler_version: String,
+    pub options: BuildOptions,
+    /// The entry first, then imported files in load order.
+    pub files: Vec<Dependency>,
+}
+
+/// Why an artifact cannot be reused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stale {
+    NoArtifact,
+    NoManifest,
+    /// The artifact was built from another entry file.
+    Entry(String),
+    Compiler(String),
+    Options,
+    Changed(String),
+    Missing(String),
+}
+
+impl fmt::Display for Stale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stale::NoArtifact => write!(f, "no artifact yet"),
+            Stale::NoManifest => write!(f, "no dependency manifest"),
+            Stale::Entry(entry) => write!(f, "built from {}", entry),
+            Stale::Compiler(version) => write!(f, "built by compiler {}", version),
+            Stale::Options => write!(f, "build options changed"),
+            Stale::Changed(path) => write!(f, "{} changed", path),
+            Stale::Missing(path) => write!(f, "{} is missing", path),
+        }
+    }
+}
+
+impl DepsManifest {
+    /// The manifest for an artifact compiled from `flat`, whose entry file read `source`.
+    pub fn collect(flat: &Flattened, source: &str, options: BuildOptions) -> Self {
+        let mut files = vec![Dependency { path: flat.entry.display().to_string(), sha1: sha1_hex(source.as_bytes()), imported_by: None }];
+        for file in &flat.imported {
+            files.push(Dependency {
+                path: file.path.display().to_string(),
+                sha1: sha1_hex(file.source.as_bytes()),
+                imported_by: Some(file.importer.display().to_string()),
+            });
+        }
+        DepsManifest { schema: SCHEMA, compiler_version: compiler_identity(), options, files }
+    }
+
+    /// The manifest recorded for `artifact`, if any (and of this schema).
+    pub fn load(artifact: &Path) -> Option<Self> {
+        std::fs::read_to_string(manifest_path(artifact))
+            .ok()
+            .and_then(|s| serde_json::from_str::<DepsManifest>(&s).ok())
+            .filter(|m| m.schema == SCHEMA)
+    }
+
+    pub fn save(&self, artifact: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        atomic_write(manifest_path(artifact), json + "\n")
+    }
+
+    /// `Ok` while `entry` built under `options` would give the same artifact: same
+    /// compiler, and every recorded file still has its recorded hash.
+    pub fn check(&self, entry: &Path, options: &BuildOptions) -> Result<(), Stale> {
+        let recorded = self.files.first().map(|d| d.path.as_str()).unwrap_or_default();
+        if recorded != entry.display().to_string() {
+            return Err(Stale::Entry(recorded.to_string()));
+        }
+        if self.compiler_version != compiler_identity() {
+            return Err(Stale::Compiler(self.compiler_version.clone()));
+        }
+        if self.options != *options {
+            return Err(Stale::Options);
+        }
+        self.files.iter().try_for_each(|d| match current_sha1(&d.path) {
+            Some(sha1) if sha1 == d.sha1 => Ok(()),
+            Some(_) => Err(Stale::Changed(d.path.clone())),
+            None => Err(Stale::Missing(d.path.clone())),
+        })
+    }
+
+    /// The graph as text: the settings, then each file under the one that imported it
+    /// with its short hash, marked when it changed or went missing since.
+    pub fn explain(&self, artifact: &Path) -> String {
+        let mut out = format!("{} (compiler {}", artifact.display(), self.compiler_version);
+        if self.options.sema_skipped {
+            out.push_str(", --no-sema");
+        }
+        if self.options.source_map {
+            out.push_str(", --source-map");
+        }
+        if !self.options.plugins.is_empty() {
+            out.push_str(&format!(", plugins: {}", self.options.plugins.join(", ")));
+        }
+        out.push_str(")\n");
+        let mut depths: Vec<(&str, usize)> = Vec::new();
+        for d in &self.files {
+            let depth = d
+                .imported_by
+                .as_deref()
+                .and_then(|by| depths.iter().find(|(p, _)| *p == by))
+                .map_or(0, |(_, depth)| depth + 1);
+            depths.push((&d.path, depth));
+            let state = match current_sha1(&d.path) {
+                Some(sha1) if sha1 == d.sha1 => "",
+                Some(_) => " (changed)",
+                None => " (missing)",
+            };
+            out.push_str(&format!("{}{}  {}{}\n", "  ".repeat(depth + 1), d.path, &d.sha1[..d.sha1.len().min(12)], state));
+        }
+        out
+    }
+}
+
+/// The manifest of `artifact` when the artifact (and its source map, if it was built with
+/// one) exists and [`DepsManifest::check`] passes.
+pub fn validate(artifact: &Path, entry: &Path, options: &BuildOptions) -> Result<DepsManifest, Stale> {
+    let map = crate::core::source_map::map_path(artifact);
+    if !artifact.is_file() || (options.source_map && !map.is_file()) {
+        return Err(Stale::NoArtifact);
+    }
+    let manifest = DepsManifest::load(artifact).ok_or(Stale::NoManifest)?;
+    manifest.check(entry, options)?;
+    Ok(manifest)
+}
+
+/// `0.2.0+1a2b3c4d5e6f`: the version plus the commit it was built from, so a compiler
+/// rebuilt with other codegen under the same version does not reuse old artifacts.
+/// Just the version when the build had no git checkout.
+pub fn compiler_identity() -> String {
+    match option_env!("AEONMI_GIT_COMMIT").filter(|c| !c.is_empty()) {
+        Some(commit) => format!("{}+{}", env!("CARGO_PKG_VERSION"), commit),
+        None => env!("CARGO_PKG_VERSION").to_string(),
+    }
+}
+
+/// `out.js` → `out.js.deps.json`.
+pub fn manifest_path(artifact: &Path) -> PathBuf {
+    let mut name = artifact.as_os_str().to_owned();
+    name.push(".deps.json");
+    PathBuf::from(name)
+}
+
+fn current_sha1(path: &str) -> Option<String> {
+    std::fs::read(path).ok().map(|bytes| sha1_hex(&bytes))
+}
+
+fn sha1_hex(bytes: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(bytes);
+    format!("{:x}", hasher.finalize())
+}
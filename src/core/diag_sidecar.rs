@@ -0,0 +1,110 @@
+//! `<out>.diag.json`: the diagnostics a compile saw, kept next to the emitted file.
+//!
+//! `aeonmi emit --diag-sidecar` writes one so archived output still carries its
+//! warnings. The record names the source (path and SHA-1), the compiler version and
+//! whether `--no-sema` skipped analysis, so a reader can tell an empty list from an
+//! unchecked one. Semantic diagnostics keep their rule codes; type diagnostics have none.
+//! A diagnostic inside an imported file names that file.
+
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::path::{Path, PathBuf};
+
+use crate::core::analysis_budget::AnalysisBudget;
+use crate::core::ast::ASTNode;
+use crate::core::imports::Flattened;
+use crate::core::semantic_analyzer::SemanticAnalyzer;
+use crate::core::types::TypeContext;
+
+/// Bumped when the layout of the file changes.
+pub const SCHEMA: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SidecarDiagnostic {
+    /// `semantic` or `types`.
+    pub pass: String,
+    /// `error`, `warning`, or `info` for a pass `--analysis-timeout-ms` cut short.
+    pub severity: String,
+    pub code: Option<String>,
+    pub message: String,
+    /// 1-based; 0 when the pass has no position for it.
+    pub line: usize,
+    pub column: usize,
+    pub len: usize,
+    /// The imported file the diagnostic points into; absent for the compiled file itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiagSidecar {
+    pub schema: u32,
+    pub file: String,
+    pub source_sha1: String,
+    pub compiler_version: String,
+    pub sema_skipped: bool,
+    pub diagnostics: Vec<SidecarDiagnostic>,
+}
+
+impl DiagSidecar {
+    /// Run the semantic analyzer and type inference over `ast` (neither when `sema_skipped`),
+    /// each under its own `--analysis-timeout-ms` budget when the flag is given.
+    pub fn collect(file: &str, source: &str, ast: &ASTNode, sema_skipped: bool) -> Self {
+        let mut diagnostics = Vec::new();
+        if !sema_skipped {
+            let mut sema = SemanticAnalyzer::new();
+            if let Some(budget) = AnalysisBudget::from_flag() { sema = sema.with_budget(budget); }
+            for d in sema.analyze_with_spans(ast) {
+                diagnostics.push(SidecarDiagnostic {
+                    pass: "semantic".into(),
+                    severity: d.severity.as_str().into(),
+                    code: d.rule.map(str::to_string),
+                    message: d.message,
+                    line: d.line,
+                    column: d.column,
+                    len: d.len.max(1),
+                    file: None,
+                });
+            }
+            let mut types = TypeContext::new();
+            if let Some(budget) = AnalysisBudget::from_flag() { types = types.with_budget(budget); }
+            types.infer_program(ast);
+            for d in types.diags {
+                diagnostics.push(SidecarDiagnostic { pass: "types".into(), severity: "error".into(), code: None, message: d.message, line: d.line, column: d.column, len: 1, file: None });
+            }
+            if let Some(t) = types.truncated {
+                diagnostics.push(SidecarDiagnostic { pass: "types".into(), severity: "info".into(), code: None, message: t.to_string(), line: 0, column: 0, len: 1, file: None });
+            }
+        }
+        let mut hasher = Sha1::new();
+        hasher.update(source.as_bytes());
+        DiagSidecar {
+            schema: SCHEMA,
+            file: file.to_string(),
+            source_sha1: format!("{:x}", hasher.finalize()),
+            compiler_version: env!("CARGO_PKG_VERSION").to_string(),
+            sema_skipped,
+            diagnostics,
+        }
+    }
+
+    /// For a sidecar collected over [`Flattened::numbered`]: every position moved back into
+    /// the file it points into, naming the file when it is an imported one.
+    pub fn located(mut self, flat: &Flattened) -> Self {
+        for d in &mut self.diagnostics {
+            let (path, line) = flat.locate(d.line);
+            if path != flat.entry {
+                d.file = Some(path.display().to_string());
+            }
+            d.line = line;
+        }
+        self
+    }
+}
+
+/// `out.js` → `out.js.diag.json`.
+pub fn sidecar_path(out: &Path) -> PathBuf {
+    let mut name = out.as_os_str().to_owned();
+    name.push(".diag.json");
+    PathBuf::from(name)
+}
@@ -1,8 +1,51 @@
 // src/core/diagnostics.rs
 //! Pretty, colored, file+line diagnostics (minimal, no external parser).
 
-use colored::Colorize;
+use crate::core::columns;
+use crate::core::parser::ParserError;
+use crate::core::semantic_analyzer::SemanticDiagnostic;
+use crate::core::style::{self, Role, Style};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
+static QUIET: AtomicBool = AtomicBool::new(false);
+static DIAG_JSON: AtomicBool = AtomicBool::new(false);
+static MAX_DIAGNOSTICS: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_DIAGNOSTICS);
+
+/// Entries a [`DiagnosticSink`] prints unless `--max-diagnostics` says otherwise.
+pub const DEFAULT_MAX_DIAGNOSTICS: usize = 50;
+/// A message repeated at least this many times is printed once, with a count.
+const GROUP_MIN: usize = 3;
+/// Locations listed under a grouped message.
+const GROUP_LOCATIONS: usize = 3;
+/// Cells of source shown in a snippet; longer lines are clipped around the span.
+const SNIPPET_WIDTH: usize = 100;
+
+/// Global output mode: `quiet` suppresses human diagnostics, `diag_json` forces `@@DIAG:` lines.
+pub fn set_output_mode(quiet: bool, diag_json: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+    DIAG_JSON.store(diag_json, Ordering::Relaxed);
+}
+
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+pub fn diag_json() -> bool {
+    DIAG_JSON.load(Ordering::Relaxed)
+}
+
+/// `--max-diagnostics`: entries a sink prints before summarizing the rest; 0 prints all.
+pub fn set_max_diagnostics(max: usize) {
+    MAX_DIAGNOSTICS.store(max, Ordering::Relaxed);
+}
+
+pub fn max_diagnostics() -> usize {
+    MAX_DIAGNOSTICS.load(Ordering::Relaxed)
+}
+
+/// A position in the source: 1-based line, byte column and length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Span {
     pub line: usize,
     pub col: usize,
@@ -13,32 +56,255 @@ impl Span {
     pub fn single(line: usize, col: usize) -> Self {
         Self { line, col, len: 1 }
     }
+
+    /// This span as the primary one of a [`MultiSpan`], with `message` labeling `at`.
+    pub fn with_label(self, at: Span, message: impl Into<String>) -> MultiSpan {
+        MultiSpan::new(self).with_label(at, message)
+    }
+
+    /// This span as the primary one of a [`MultiSpan`] carrying `fix`.
+    pub fn with_suggestion(self, fix: Suggestion) -> MultiSpan {
+        MultiSpan::new(self).with_suggestion(fix)
+    }
 }
 
-pub fn print_error(filename: &str, source: &str, title: &str, span: Span) {
-    eprintln!("{} {}", "error:".bright_red().bold(), title.bright_white());
-    let (ln, col) = (span.line, span.col);
-    let line_text = nth_line(source, ln).unwrap_or_default();
+/// A secondary span and what it says about the error, e.g. "'x' first declared here".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
 
-    // line number gutter
-    let ln_str = format!("{:>4}", ln);
-    eprintln!(
-        "{} {}",
-        "-->".bright_blue(),
-        format!("{}:{}:{}", filename, ln, col).bright_white()
-    );
-    eprintln!(" {} {}", ln_str.dimmed(), "|".dimmed());
-    eprintln!("{} {} {}", ln_str.dimmed(), "|".dimmed(), line_text);
+/// A machine-applicable fix: replace the `span.len` bytes at `span` with `replacement`
+/// (a `len` of 0 inserts before `span.col`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub message: String,
+    pub span: Span,
+    pub replacement: String,
+}
+
+impl Suggestion {
+    pub fn insert(message: impl Into<String>, line: usize, col: usize, text: impl Into<String>) -> Self {
+        Self { message: message.into(), span: Span { line, col, len: 0 }, replacement: text.into() }
+    }
 
-    // underline with ^^^^^
-    let underline = " ".repeat(col.saturating_sub(1)) + &"^".repeat(span.len.max(1));
-    eprintln!(
-        " {} {} {}",
-        " ".repeat(ln_str.len()).dimmed(),
-        "|".dimmed(),
-        underline.bright_red()
-    );
-    eprintln!();
+    pub fn replace(message: impl Into<String>, span: Span, text: impl Into<String>) -> Self {
+        Self { message: message.into(), span, replacement: text.into() }
+    }
+}
+
+/// Everything an error points at: the primary span (underlined with `^`), labeled
+/// secondary spans (underlined with `-`) and at most one suggested fix.
+///
+/// Every function taking a span accepts a plain [`Span`] as well, so single-span callers
+/// need not change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiSpan {
+    pub primary: Span,
+    pub labels: Vec<Label>,
+    pub suggestion: Option<Suggestion>,
+}
+
+impl MultiSpan {
+    pub fn new(primary: Span) -> Self {
+        Self { primary, labels: Vec::new(), suggestion: None }
+    }
+
+    pub fn with_label(mut self, at: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label { span: at, message: message.into() });
+        self
+    }
+
+    pub fn with_suggestion(mut self, fix: Suggestion) -> Self {
+        self.suggestion = Some(fix);
+        self
+    }
+}
+
+impl From<Span> for MultiSpan {
+    fn from(span: Span) -> Self {
+        MultiSpan::new(span)
+    }
+}
+
+impl From<&Span> for MultiSpan {
+    fn from(span: &Span) -> Self {
+        MultiSpan::new(*span)
+    }
+}
+
+impl From<&MultiSpan> for MultiSpan {
+    fn from(spans: &MultiSpan) -> Self {
+        spans.clone()
+    }
+}
+
+/// Related spans become labels; a quick fix made of one edit within a line becomes the suggestion.
+impl From<&SemanticDiagnostic> for MultiSpan {
+    fn from(d: &SemanticDiagnostic) -> Self {
+        let mut spans = MultiSpan::new(Span { line: d.line, col: d.column, len: d.len });
+        for r in &d.related {
+            spans = spans.with_label(Span { line: r.line, col: r.column, len: r.len }, r.message.clone());
+        }
+        if let Some(fix) = &d.fix {
+            if let [edit] = fix.edits.as_slice() {
+                if edit.line == edit.end_line && edit.end_column >= edit.column {
+                    let at = Span { line: edit.line, col: edit.column, len: edit.end_column - edit.column };
+                    spans.suggestion = Some(Suggestion::replace(fix.title.clone(), at, edit.new_text.clone()));
+                }
+            }
+        }
+        spans
+    }
+}
+
+pub fn print_error(filename: &str, source: &str, title: &str, span: impl Into<MultiSpan>) {
+    print_error_coded(filename, source, title, span, None);
+}
+
+/// Like [`print_error`], tagging the header with `code` and pointing at `aeonmi explain-error`.
+pub fn print_error_coded(filename: &str, source: &str, title: &str, span: impl Into<MultiSpan>, code: Option<&str>) {
+    eprint!("{}", render_error(&style::current(), filename, source, title, span, code));
+}
+
+/// Pretty error block (header, source lines, underlines, explain hint) as a string.
+pub fn render_error(st: &Style, filename: &str, source: &str, title: &str, span: impl Into<MultiSpan>, code: Option<&str>) -> String {
+    render_error_with_notes(st, filename, source, title, span, code, &Notes::default())
+}
+
+/// What an error says beyond its spans.
+#[derive(Default)]
+pub struct Notes<'a> {
+    pub hint: Option<&'a str>,
+}
+
+/// [`render_error`] followed by a `= hint:` line and the suggested fix, if any.
+pub fn render_error_with_notes(st: &Style, filename: &str, source: &str, title: &str, span: impl Into<MultiSpan>, code: Option<&str>, notes: &Notes) -> String {
+    let spans = span.into();
+    let head = match code {
+        Some(c) => format!("error[{}]:", c),
+        None => "error:".to_string(),
+    };
+    let mut out = format!("{} {}\n", st.paint(&head, Role::Error), st.paint(title, Role::Strong));
+    let last_line = spans.labels.iter().map(|l| l.span.line).chain(spans.suggestion.iter().map(|s| s.span.line)).fold(spans.primary.line, usize::max);
+    let width = last_line.to_string().len().max(4);
+    let pad = " ".repeat(width);
+    push_snippet(&mut out, st, filename, source, &spans, width);
+    if let Some(h) = notes.hint {
+        out.push_str(&format!(" {} {} hint: {}\n", pad, st.paint("=", Role::Dim), h));
+    }
+    if let Some(s) = &spans.suggestion {
+        out.push_str(&format!(" {} {} help: {}\n", pad, st.paint("=", Role::Dim), s.message));
+        push_suggestion(&mut out, st, source, s, width);
+    }
+    if let Some(c) = code {
+        out.push_str(&format!(
+            " {} {} run `aeonmi explain-error {}` for more\n",
+            pad,
+            st.paint("=", Role::Dim),
+            c
+        ));
+    }
+    out.push('\n');
+    out
+}
+
+/// One underline under a snippet line: `^` for the primary span, `-` and a message for a label.
+struct Mark<'a> {
+    col: usize,
+    len: usize,
+    label: Option<&'a str>,
+}
+
+/// `--> file:line:col` of the primary span, then every line a span is on, in order, each
+/// followed by its underlines; a `...` row stands for lines skipped in between.
+fn push_snippet(out: &mut String, st: &Style, filename: &str, source: &str, spans: &MultiSpan, width: usize) {
+    let primary = spans.primary;
+    let bar = st.paint("|", Role::Dim);
+    out.push_str(&format!(
+        "{} {}\n",
+        st.paint("-->", Role::Location),
+        st.paint(&format!("{}:{}:{}", filename, primary.line, primary.col), Role::Strong)
+    ));
+    out.push_str(&format!(" {} {}\n", st.paint(&format!("{:>width$}", primary.line), Role::Dim), bar));
+
+    let mut marks: Vec<(usize, Mark)> = vec![(primary.line, Mark { col: primary.col, len: primary.len, label: None })];
+    marks.extend(spans.labels.iter().map(|l| (l.span.line, Mark { col: l.span.col, len: l.span.len, label: Some(l.message.as_str()) })));
+    // by line; on a line the primary span first, then labels left to right
+    marks.sort_by_key(|(line, m)| (*line, m.label.is_some(), m.col));
+    let mut prev: Option<usize> = None;
+    for group in marks.chunk_by(|a, b| a.0 == b.0) {
+        let ln = group[0].0;
+        if prev.is_some_and(|p| ln > p + 1) {
+            out.push_str(&format!("{}\n", st.paint(&format!("{:>width$}", "..."), Role::Dim)));
+        }
+        prev = Some(ln);
+        let line_text = nth_line(source, ln).unwrap_or_default();
+        // (pad, width) in cells; col/len are bytes of the NFC line
+        let cells: Vec<(usize, usize)> = group
+            .iter()
+            .map(|(_, m)| {
+                let raw_col = columns::source_offset(&line_text, m.col).map_or(m.col, |b| b + 1);
+                (columns::display_col(&line_text, raw_col) - 1, columns::display_len(&line_text, raw_col, m.len.max(1)))
+            })
+            .collect();
+        let (shown, first_pad, first_width) = clip_snippet(st, &line_text, cells[0].0, cells[0].1);
+        let shift = first_pad as isize - cells[0].0 as isize;
+        out.push_str(&format!("{} {} {}\n", st.paint(&format!("{:>width$}", ln), Role::Dim), bar, shown));
+        for (i, (_, m)) in group.iter().enumerate() {
+            let (pad, carets) = if i == 0 { (first_pad, first_width) } else { (cells[i].0.saturating_add_signed(shift), cells[i].1.min(SNIPPET_WIDTH / 2)) };
+            let underline = match m.label {
+                None => st.paint(&(" ".repeat(pad) + &"^".repeat(carets)), Role::Error),
+                Some(msg) => format!("{} {}", st.paint(&(" ".repeat(pad) + &"-".repeat(carets)), Role::Location), msg),
+            };
+            out.push_str(&format!(" {} {} {}\n", " ".repeat(width), bar, underline));
+        }
+    }
+}
+
+/// The suggested line with the fix applied, `+` under inserted text and `~` under a
+/// replacement; nothing when the span does not fall on the line.
+fn push_suggestion(out: &mut String, st: &Style, source: &str, fix: &Suggestion, width: usize) {
+    let Some(line) = nth_line(source, fix.span.line) else { return };
+    let Some(start) = columns::source_offset(&line, fix.span.col) else { return };
+    let end = start + fix.span.len;
+    if end > line.len() || !line.is_char_boundary(end) {
+        return;
+    }
+    let patched = format!("{}{}{}", &line[..start], fix.replacement, &line[end..]);
+    let pad = columns::display_width(&line[..start]);
+    let (shown, pad, marks) = clip_snippet(st, &patched, pad, columns::display_width(&fix.replacement).max(1));
+    let bar = st.paint("|", Role::Dim);
+    let mark = if fix.span.len == 0 { "+" } else { "~" };
+    out.push_str(&format!("{} {} {}\n", st.paint(&format!("{:>width$}", fix.span.line), Role::Dim), bar, shown));
+    out.push_str(&format!(" {} {} {}\n", " ".repeat(width), bar, st.paint(&(" ".repeat(pad) + &mark.repeat(marks)), Role::Location)));
+}
+
+/// `line` cut to [`SNIPPET_WIDTH`] cells around the span (`pad` cells in, `carets` wide),
+/// with `…` where text was dropped; returns the text and the caret offset and width in it.
+fn clip_snippet(st: &Style, line: &str, pad: usize, carets: usize) -> (String, usize, usize) {
+    if columns::display_width(line) <= SNIPPET_WIDTH {
+        return (line.to_string(), pad, carets);
+    }
+    let marker = st.glyph("…", "...");
+    let room = SNIPPET_WIDTH - 2 * columns::display_width(marker);
+    let carets = carets.min(room / 2);
+    // Centre the span, but never run the window past the end of the line.
+    let skip = pad.saturating_sub((room - carets) / 2).min(columns::display_width(line).saturating_sub(room));
+    let range = columns::visible_range(line, skip, room);
+    let start_cell = columns::display_width(&line[..range.start]);
+    let mut shown = String::new();
+    let mut pad = pad.saturating_sub(start_cell);
+    if range.start > 0 {
+        shown.push_str(marker);
+        pad += columns::display_width(marker);
+    }
+    shown.push_str(&line[range.clone()]);
+    if range.end < line.len() {
+        shown.push_str(marker);
+    }
+    (shown, pad, carets)
 }
 
 #[derive(serde::Serialize)]
@@ -49,16 +315,352 @@ pub struct JsonDiagnostic<'a> {
     pub line: usize,
     pub col: usize,
     pub len: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'a str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<JsonNote<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<JsonSuggestion<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<&'a str>,
+}
+
+/// A labeled secondary span.
+#[derive(serde::Serialize)]
+pub struct JsonNote<'a> {
+    pub message: &'a str,
+    pub line: usize,
+    pub col: usize,
+    pub len: usize,
+}
+
+/// Replace `len` bytes at `line:col` (0 inserts) with `replacement`.
+#[derive(serde::Serialize)]
+pub struct JsonSuggestion<'a> {
+    pub message: &'a str,
+    pub line: usize,
+    pub col: usize,
+    pub len: usize,
+    pub replacement: &'a str,
 }
 
 /// Emit a machine-readable JSON line (prefixed) for downstream tools (GUI, editors).
-pub fn emit_json_error(file: &str, title: &str, span: &Span) {
-    let jd = JsonDiagnostic { severity: "error", message: title, file, line: span.line, col: span.col, len: span.len };
-    if let Ok(s) = serde_json::to_string(&jd) {
+pub fn emit_json_error(file: &str, title: &str, span: impl Into<MultiSpan>) {
+    emit_json_error_coded(file, title, span, None);
+}
+
+pub fn emit_json_error_coded(file: &str, title: &str, span: impl Into<MultiSpan>, code: Option<&str>) {
+    emit_json_error_with_notes(file, title, span, code, &Notes::default());
+}
+
+pub fn emit_json_error_with_notes(file: &str, title: &str, span: impl Into<MultiSpan>, code: Option<&str>, notes: &Notes) {
+    if let Some(s) = json_error(file, title, &span.into(), code, notes) {
         eprintln!("@@DIAG:{}", s);
     }
 }
 
+/// The JSON an `@@DIAG:` line carries, without the prefix.
+pub fn json_error(file: &str, title: &str, spans: &MultiSpan, code: Option<&str>, notes: &Notes) -> Option<String> {
+    let span = spans.primary;
+    let jd = JsonDiagnostic {
+        severity: "error",
+        message: title,
+        file,
+        line: span.line,
+        col: span.col,
+        len: span.len,
+        code,
+        notes: spans.labels.iter().map(|l| JsonNote { message: &l.message, line: l.span.line, col: l.span.col, len: l.span.len }).collect(),
+        suggestion: spans.suggestion.as_ref().map(|s| JsonSuggestion { message: &s.message, line: s.span.line, col: s.span.col, len: s.span.len, replacement: &s.replacement }),
+        hint: notes.hint,
+    };
+    serde_json::to_string(&jd).ok()
+}
+
+/// Report a spanned error honoring `--pretty-errors`, `--quiet` and `--diag-json`.
+pub fn report_error(filename: &str, source: &str, title: &str, span: impl Into<MultiSpan>, code: Option<&str>, pretty: bool) {
+    report_error_with_notes(filename, source, title, span, code, &Notes::default(), pretty);
+}
+
+/// [`report_error`] with a hint; the plain form keeps just the hint, or the suggestion's message.
+pub fn report_error_with_notes(filename: &str, source: &str, title: &str, span: impl Into<MultiSpan>, code: Option<&str>, notes: &Notes, pretty: bool) {
+    let spans = span.into();
+    if pretty || diag_json() {
+        emit_json_error_with_notes(filename, title, &spans, code, notes);
+    }
+    if quiet() {
+        return;
+    }
+    let st = style::current();
+    if pretty {
+        eprint!("{}", render_error_with_notes(&st, filename, source, title, &spans, code, notes));
+    } else {
+        eprint!("{}", render_plain_error(&st, filename, title, &spans, code, notes));
+    }
+}
+
+/// The one-line form of an error, `file:line:col: error[code]: title`, then the hint or the
+/// suggestion's message when there is one.
+pub fn render_plain_error(st: &Style, filename: &str, title: &str, span: impl Into<MultiSpan>, code: Option<&str>, notes: &Notes) -> String {
+    let spans = span.into();
+    let head = match code {
+        Some(c) => format!("error[{}]:", c),
+        None => "error:".to_string(),
+    };
+    let at = spans.primary;
+    let location = if at.line > 0 { format!("{}:{}:{}", filename, at.line, at.col) } else { filename.to_string() };
+    let mut out = format!("{}: {} {}\n", location, st.paint(&head, Role::Error), title);
+    if let Some(h) = notes.hint.or(spans.suggestion.as_ref().map(|s| s.message.as_str())) {
+        out.push_str(&format!("  {} hint: {}\n", st.paint("=", Role::Dim), h));
+    }
+    out
+}
+
+/// Title, spans and notes for a parse error under `code` [`error_index::PARSE_ERROR`]: its
+/// related locations become labels, and a parser suggestion stands in for the hint it spells out.
+///
+/// [`error_index::PARSE_ERROR`]: crate::core::error_index::PARSE_ERROR
+pub fn parse_error_parts(err: &ParserError) -> (String, MultiSpan, Notes<'_>) {
+    let mut spans = MultiSpan::new(Span::single(err.line, err.column));
+    for r in &err.detail.related {
+        spans = spans.with_label(Span { line: r.line, col: r.column, len: r.len }, r.message.clone());
+    }
+    spans.suggestion = err.detail.suggestion.clone();
+    let notes = Notes { hint: err.detail.hint.as_deref().filter(|_| err.detail.suggestion.is_none()) };
+    (format!("Parsing error: {}", err.summary()), spans, notes)
+}
+
+/// Report a parse error with its expected set, labeled related spans and hint or suggestion.
+pub fn report_parse_error(filename: &str, source: &str, err: &ParserError, pretty: bool) {
+    let (title, span, notes) = parse_error_parts(err);
+    report_error_with_notes(filename, source, &title, span, Some(crate::core::error_index::PARSE_ERROR), &notes, pretty);
+}
+
+/// One line-oriented diagnostic, as `typecheck` and `lint` print them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: &'static str,
+    pub file: String,
+    /// 1-based; 0 when the position is unknown.
+    pub line: usize,
+    pub col: usize,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn error(file: &str, line: usize, col: usize, message: impl Into<String>) -> Self {
+        Self { severity: "error", file: file.to_string(), line, col, message: message.into() }
+    }
+
+    pub fn warning(file: &str, line: usize, col: usize, message: impl Into<String>) -> Self {
+        Self { severity: "warning", ..Self::error(file, line, col, message) }
+    }
+
+    /// `file:line:col`, or just the file without a position.
+    pub fn location(&self) -> String {
+        if self.line > 0 { format!("{}:{}:{}", self.file, self.line, self.col) } else { self.file.clone() }
+    }
+}
+
+/// Collects diagnostics and prints them at the end: ordered by file then line, a message
+/// repeated on many lines folded into one entry, and at most `max` entries (0 for all)
+/// followed by a count of what was left out.
+pub struct DiagnosticSink {
+    max: usize,
+    items: Vec<Diagnostic>,
+}
+
+impl Default for DiagnosticSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DiagnosticSink {
+    /// A sink capped at the global `--max-diagnostics`.
+    pub fn new() -> Self {
+        Self::with_max(max_diagnostics())
+    }
+
+    pub fn with_max(max: usize) -> Self {
+        Self { max, items: Vec::new() }
+    }
+
+    pub fn push(&mut self, d: Diagnostic) {
+        self.items.push(d);
+    }
+
+    /// Entries in print order, each one diagnostic or a group of identical messages.
+    fn entries(&self) -> Vec<Vec<&Diagnostic>> {
+        let mut sorted: Vec<&Diagnostic> = self.items.iter().collect();
+        sorted.sort_by(|a, b| (&a.file, a.line, a.col).cmp(&(&b.file, b.line, b.col)));
+        let mut same: HashMap<(&str, &str), Vec<&Diagnostic>> = HashMap::new();
+        for d in &sorted {
+            same.entry((d.severity, d.message.as_str())).or_default().push(d);
+        }
+        let mut entries: Vec<Vec<&Diagnostic>> = Vec::new();
+        for d in sorted {
+            let group = &same[&(d.severity, d.message.as_str())];
+            if group.len() < GROUP_MIN {
+                entries.push(vec![d]);
+            } else if std::ptr::eq(group[0], d) {
+                entries.push(group.clone());
+            }
+        }
+        entries
+    }
+
+    pub fn render(&self, st: &Style) -> String {
+        let entries = self.entries();
+        let shown = if self.max == 0 { entries.len() } else { self.max.min(entries.len()) };
+        let mut out = String::new();
+        for entry in &entries[..shown] {
+            let d = entry[0];
+            let role = if d.severity == "error" { Role::Error } else { Role::Warn };
+            let label = st.paint(&format!("{}:", d.severity), role);
+            if entry.len() == 1 {
+                out.push_str(&format!("{}: {} {}\n", st.paint(&d.location(), Role::Strong), label, d.message));
+                continue;
+            }
+            out.push_str(&format!("{} {} ({} occurrences)\n", label, d.message, entry.len()));
+            // Distinct locations: diagnostics without a position all share the file name.
+            let mut at: Vec<String> = entry.iter().map(|d| d.location()).collect();
+            at.dedup();
+            let rest = at.len().saturating_sub(GROUP_LOCATIONS);
+            let at: Vec<String> = at.iter().take(GROUP_LOCATIONS).map(|l| st.paint(l, Role::Strong)).collect();
+            let more = if rest > 0 { format!(" and {rest} more") } else { String::new() };
+            out.push_str(&format!("  {} {}{}\n", st.paint("-->", Role::Location), at.join(", "), more));
+        }
+        let hidden: Vec<&str> = entries[shown..].iter().flatten().map(|d| d.severity).collect();
+        out.push_str(&more_line(st, &hidden));
+        out
+    }
+}
+
+/// `and N more errors (re-run ...)` for the severities left out by a cap; empty if none were.
+fn more_line(st: &Style, hidden: &[&str]) -> String {
+    let Some(&kind) = hidden.first() else { return String::new() };
+    let noun = if hidden.iter().all(|&s| s == kind) { kind } else { "diagnostic" };
+    let text = format!("and {} more {}{} (re-run with --max-diagnostics 0 to see all)", hidden.len(), noun, if hidden.len() == 1 { "" } else { "s" });
+    format!("{}\n", st.paint(&text, Role::Dim))
+}
+
+/// Narrowest column a message is wrapped to before it moves below its underline.
+const MIN_MESSAGE_WIDTH: usize = 24;
+
+/// One diagnostic drawn in place by [`render_annotated`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    /// `error` or `warning`.
+    pub severity: &'static str,
+    /// 1-based; 0 (or past the end of the file) when the position is unknown.
+    pub line: usize,
+    pub col: usize,
+    /// Underlined bytes; 0 counts as 1.
+    pub len: usize,
+    pub message: String,
+}
+
+/// Layout of [`render_annotated`].
+#[derive(Debug, Clone, Copy)]
+pub struct AnnotateOptions {
+    /// Cells per output line; messages wrap to fit.
+    pub width: usize,
+    /// Annotations drawn before the rest are summarized; 0 draws all.
+    pub max: usize,
+    /// Every source line, rather than only the annotated ones.
+    pub full_source: bool,
+}
+
+/// `source` with a line-number gutter and each annotation drawn under its line: an
+/// underline (`^` for errors, `-` for warnings) across the span, then a severity glyph
+/// and the message, wrapped to `width` and aligned with the span. Annotations sharing a
+/// line stack in column order, so overlapping spans each keep their own underline.
+/// Annotations without a usable position are listed before the source.
+pub fn render_annotated(st: &Style, source: &str, annotations: &[Annotation], opt: &AnnotateOptions) -> String {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut sorted: Vec<&Annotation> = annotations.iter().collect();
+    let placed = |a: &Annotation| a.line > 0 && a.line <= lines.len();
+    // unplaced first, then by position; errors before warnings at the same spot
+    sorted.sort_by_key(|a| (placed(a), a.line, a.col, a.severity != "error"));
+    let shown = if opt.max == 0 { sorted.len() } else { opt.max.min(sorted.len()) };
+    let (drawn, hidden) = sorted.split_at(shown);
+
+    let gutter = lines.len().max(1).to_string().len();
+    let bar = st.paint(st.glyph("│", "|"), Role::Dim);
+    let blank = format!("{} {} ", " ".repeat(gutter), bar);
+    let room = opt.width.saturating_sub(gutter + 3);
+    let mut out = String::new();
+    for a in drawn.iter().filter(|a| !placed(a)) {
+        let label = annotation_label(st, a);
+        push_wrapped(&mut out, &format!("{} {} ", " ".repeat(gutter), st.paint("=", Role::Dim)), "", 0, &label, &a.message, room);
+    }
+    let mut by_line: HashMap<usize, Vec<&Annotation>> = HashMap::new();
+    for a in drawn.iter().filter(|a| placed(a)) {
+        by_line.entry(a.line).or_default().push(a);
+    }
+    let mut last = 0usize;
+    for (i, text) in lines.iter().enumerate() {
+        let here = by_line.get(&(i + 1));
+        if !opt.full_source && here.is_none() {
+            continue;
+        }
+        if last > 0 && i + 1 > last + 1 && !opt.full_source {
+            out.push_str(&format!("{}\n", st.paint(&format!("{:>gutter$}", "..."), Role::Dim)));
+        }
+        last = i + 1;
+        out.push_str(&format!("{} {} {}\n", st.paint(&format!("{:>gutter$}", i + 1), Role::Dim), bar, text));
+        for a in here.into_iter().flatten() {
+            let raw_col = columns::source_offset(text, a.col).map_or(a.col, |b| b + 1);
+            let pad = columns::display_col(text, raw_col) - 1;
+            let carets = columns::display_len(text, raw_col, a.len.max(1)).max(1);
+            let (mark, role) = if a.severity == "error" { ("^", Role::Error) } else { ("-", Role::Warn) };
+            let underline = format!("{}{}", " ".repeat(pad), st.paint(&mark.repeat(carets), role));
+            let label = annotation_label(st, a);
+            // beside the underline when there is room, otherwise on the rows below, under the span
+            let beside = pad + carets + 1;
+            if room.saturating_sub(beside) >= MIN_MESSAGE_WIDTH {
+                push_wrapped(&mut out, &blank, &format!("{} ", underline), beside, &label, &a.message, room - beside);
+            } else {
+                out.push_str(&format!("{}{}\n", blank, underline));
+                let pad = pad.min(room.saturating_sub(MIN_MESSAGE_WIDTH));
+                push_wrapped(&mut out, &blank, &" ".repeat(pad), pad, &label, &a.message, room - pad);
+            }
+        }
+    }
+    out.push_str(&more_line(st, &hidden.iter().map(|a| a.severity).collect::<Vec<_>>()));
+    out
+}
+
+/// `✖ error:` / `▲ warning:` (ASCII `x` / `!`), painted; the plain text width is its char count.
+fn annotation_label(st: &Style, a: &Annotation) -> (String, usize) {
+    let (glyph, role) = if a.severity == "error" { (st.glyph("✖", "x"), Role::Error) } else { (st.glyph("▲", "!"), Role::Warn) };
+    let text = format!("{} {}:", glyph, a.severity);
+    (st.paint(&text, role), text.chars().count())
+}
+
+/// `lead`, `first` (`indent` cells wide), then `label message` with the words wrapped to
+/// `width` cells; later rows repeat `lead` and line up with the start of the message.
+fn push_wrapped(out: &mut String, lead: &str, first: &str, indent: usize, label: &(String, usize), message: &str, width: usize) {
+    let width = width.saturating_sub(label.1 + 1).max(1);
+    let mut rows: Vec<String> = vec![String::new()];
+    for word in message.split_whitespace() {
+        let row = rows.last_mut().unwrap();
+        if !row.is_empty() && columns::display_width(row) + 1 + columns::display_width(word) > width {
+            rows.push(word.to_string());
+        } else {
+            if !row.is_empty() {
+                row.push(' ');
+            }
+            row.push_str(word);
+        }
+    }
+    out.push_str(&format!("{}{}{} {}\n", lead, first, label.0, rows[0]));
+    for row in &rows[1..] {
+        out.push_str(&format!("{}{}{}\n", lead, " ".repeat(indent + label.1 + 1), row));
+    }
+}
+
 fn nth_line(src: &str, n: usize) -> Option<String> {
     src.lines().nth(n.saturating_sub(1)).map(|s| s.to_string())
 }
@@ -0,0 +1,75 @@
+//! Offline error index: long-form explanations for diagnostic codes (`AEO-…`).
+//!
+//! Each entry embeds `docs/errors/<CODE>.md`. Any new diagnostic code must be
+//! listed in [`EMITTED`] and get an index entry; a test enforces the pairing.
+use crate::core::quantum_lint;
+
+pub const LEX_ERROR: &str = "AEO-L001";
+pub const PARSE_ERROR: &str = "AEO-P001";
+pub const QREG_OUT_OF_BOUNDS: &str = "AEO-Q005";
+pub const CHAINED_COMPARISON: &str = "AEO-S001";
+pub const REDECLARATION: &str = "AEO-S002";
+pub const SHADOWED_VARIABLE: &str = "AEO-S003";
+pub const UNDECLARED_ASSIGNMENT: &str = "AEO-S004";
+pub const NON_EXHAUSTIVE_MATCH: &str = "AEO-S005";
+pub const ENUM_COMPARISON: &str = "AEO-S006";
+pub const UNKNOWN_VARIANT: &str = "AEO-S007";
+pub const NOT_ITERABLE: &str = "AEO-S008";
+pub const NOT_INDEXABLE: &str = "AEO-S009";
+pub const IMPORT_CYCLE: &str = "AEO-M001";
+pub const IMPORT_NOT_FOUND: &str = "AEO-M002";
+
+/// Every code the crate can attach to a diagnostic.
+pub const EMITTED: &[&str] = &[
+    LEX_ERROR,
+    PARSE_ERROR,
+    quantum_lint::RULE_DOUBLE_MEASURE,
+    quantum_lint::RULE_GATE_AFTER_MEASURE,
+    quantum_lint::RULE_SELF_ENTANGLE,
+    quantum_lint::RULE_NEVER_MEASURED,
+    QREG_OUT_OF_BOUNDS,
+    CHAINED_COMPARISON,
+    REDECLARATION,
+    SHADOWED_VARIABLE,
+    UNDECLARED_ASSIGNMENT,
+    NON_EXHAUSTIVE_MATCH,
+    ENUM_COMPARISON,
+    UNKNOWN_VARIANT,
+    NOT_ITERABLE,
+    NOT_INDEXABLE,
+    IMPORT_CYCLE,
+    IMPORT_NOT_FOUND,
+];
+
+pub struct ErrorDoc {
+    pub code: &'static str,
+    pub summary: &'static str,
+    /// Markdown: explanation, `## Example` and `## Fix` sections.
+    pub markdown: &'static str,
+}
+
+pub static INDEX: &[ErrorDoc] = &[
+    ErrorDoc { code: "AEO-L001", summary: "invalid token", markdown: include_str!("../../docs/errors/AEO-L001.md") },
+    ErrorDoc { code: "AEO-M001", summary: "import cycle", markdown: include_str!("../../docs/errors/AEO-M001.md") },
+    ErrorDoc { code: "AEO-M002", summary: "imported file not found", markdown: include_str!("../../docs/errors/AEO-M002.md") },
+    ErrorDoc { code: "AEO-P001", summary: "unexpected token", markdown: include_str!("../../docs/errors/AEO-P001.md") },
+    ErrorDoc { code: "AEO-Q001", summary: "qubit measured twice", markdown: include_str!("../../docs/errors/AEO-Q001.md") },
+    ErrorDoc { code: "AEO-Q002", summary: "gate applied after measurement", markdown: include_str!("../../docs/errors/AEO-Q002.md") },
+    ErrorDoc { code: "AEO-Q003", summary: "qubit entangled with itself", markdown: include_str!("../../docs/errors/AEO-Q003.md") },
+    ErrorDoc { code: "AEO-Q004", summary: "qubit never measured", markdown: include_str!("../../docs/errors/AEO-Q004.md") },
+    ErrorDoc { code: "AEO-Q005", summary: "register index out of bounds", markdown: include_str!("../../docs/errors/AEO-Q005.md") },
+    ErrorDoc { code: "AEO-S001", summary: "chained comparison", markdown: include_str!("../../docs/errors/AEO-S001.md") },
+    ErrorDoc { code: "AEO-S002", summary: "variable redeclared", markdown: include_str!("../../docs/errors/AEO-S002.md") },
+    ErrorDoc { code: "AEO-S003", summary: "variable shadows outer declaration", markdown: include_str!("../../docs/errors/AEO-S003.md") },
+    ErrorDoc { code: "AEO-S004", summary: "assignment to undeclared variable", markdown: include_str!("../../docs/errors/AEO-S004.md") },
+    ErrorDoc { code: "AEO-S005", summary: "match does not cover every enum variant", markdown: include_str!("../../docs/errors/AEO-S005.md") },
+    ErrorDoc { code: "AEO-S006", summary: "comparison between different enums", markdown: include_str!("../../docs/errors/AEO-S006.md") },
+    ErrorDoc { code: "AEO-S007", summary: "unknown enum variant", markdown: include_str!("../../docs/errors/AEO-S007.md") },
+    ErrorDoc { code: "AEO-S008", summary: "value is not iterable", markdown: include_str!("../../docs/errors/AEO-S008.md") },
+    ErrorDoc { code: "AEO-S009", summary: "value is not indexable", markdown: include_str!("../../docs/errors/AEO-S009.md") },
+];
+
+/// Look up a code, ignoring ASCII case (`aeo-l001` works too).
+pub fn lookup(code: &str) -> Option<&'static ErrorDoc> {
+    INDEX.iter().find(|d| d.code.eq_ignore_ascii_case(code.trim()))
+}
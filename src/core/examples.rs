@@ -0,0 +1,62 @@
+//! Bundled example programs, embedded at build time so `aeonmi examples` and the
+//! shell's `qexample` work from an installed binary without the repo checkout.
+//!
+//! Adding an example: drop the `.ai` file in `examples/` and add an entry to
+//! [`EXAMPLES`], listing the files it imports in `modules`. A test lexes, parses and
+//! lowers every entry.
+
+use std::path::{Path, PathBuf};
+
+pub struct Example {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub tags: &'static [&'static str],
+    /// File name under `examples/`, for messages and `examples show`.
+    pub file: &'static str,
+    pub source: &'static str,
+    /// Files the example imports: path relative to `file`'s directory, and source.
+    pub modules: &'static [(&'static str, &'static str)],
+}
+
+impl Example {
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Write the example and its modules under `dir` as laid out in `examples/`, so its
+    /// imports resolve; returns the path of the entry file.
+    pub fn write_to(&self, dir: &Path) -> std::io::Result<PathBuf> {
+        let entry = dir.join(self.file);
+        let base = entry.parent().unwrap_or(dir);
+        let files = std::iter::once((entry.clone(), self.source)).chain(self.modules.iter().map(|(p, s)| (base.join(p), *s)));
+        for (path, source) in files {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, source)?;
+        }
+        Ok(entry)
+    }
+}
+
+pub static EXAMPLES: &[Example] = &[
+    Example { name: "hello", description: "Variables and log", tags: &["basics"], file: "hello.ai", source: include_str!("../../examples/hello.ai"), modules: &[] },
+    Example { name: "functions", description: "Function declarations and calls", tags: &["basics"], file: "functions.ai", source: include_str!("../../examples/functions.ai"), modules: &[] },
+    Example { name: "math", description: "Arithmetic and operator precedence", tags: &["basics"], file: "math.ai", source: include_str!("../../examples/math.ai"), modules: &[] },
+    Example { name: "focusflow", description: "Task board with collections, an imported utils module and self-tests", tags: &["basics", "app"], file: "focusflow/focusflow.ai", source: include_str!("../../examples/focusflow/focusflow.ai"), modules: &[("utils.ai", include_str!("../../examples/focusflow/utils.ai"))] },
+    Example { name: "quantum", description: "Superpose and measure a single qubit", tags: &["quantum"], file: "quantum.ai", source: include_str!("../../examples/quantum.ai"), modules: &[] },
+    Example { name: "bell", description: "Bell state preparation", tags: &["quantum"], file: "bell.ai", source: include_str!("../../examples/bell.ai"), modules: &[] },
+    Example { name: "teleport", description: "Quantum teleportation protocol", tags: &["quantum"], file: "quantum_teleportation.ai", source: include_str!("../../examples/quantum_teleportation.ai"), modules: &[] },
+    Example { name: "error_correction", description: "3-qubit bit flip code", tags: &["quantum"], file: "quantum_error_correction.ai", source: include_str!("../../examples/quantum_error_correction.ai"), modules: &[] },
+    Example { name: "grover", description: "Grover's search algorithm", tags: &["quantum"], file: "grover_search.ai", source: include_str!("../../examples/grover_search.ai"), modules: &[] },
+];
+
+/// Look up an example by name, ignoring ASCII case.
+pub fn find(name: &str) -> Option<&'static Example> {
+    EXAMPLES.iter().find(|e| e.name.eq_ignore_ascii_case(name.trim()))
+}
+
+/// Examples carrying `tag`, or all of them when `tag` is `None`.
+pub fn list(tag: Option<&str>) -> impl Iterator<Item = &'static Example> + '_ {
+    EXAMPLES.iter().filter(move |e| tag.is_none_or(|t| e.has_tag(t)))
+}
@@ -3,22 +3,72 @@
 //! Design notes:
 //! - Token-agnostic but syntax-aware via simple rules.
 //! - Stable spacing around punctuation and operators.
-//! - Indentation with 4 spaces, newline rules around braces.
-//! - Idempotent: format(format(src)) == format(src)
+//! - Indentation with `indent_width` spaces (4 by default), newline rules around braces.
+//! - Idempotent: format(format(src)) == format(src), under any [`FormatOptions`].
+//!
+//! The defaults reproduce the formatter's historical output; the project manifest's
+//! `[format]` table (see `config::FormatSettings`) can widen the indent, wrap long
+//! lines, normalize operator glyphs and add the optional statement semicolons.
+//!
+//! Array and object literals are written on one line (`[1, 2]`, `{ "k": v }`, no trailing
+//! comma) unless that line would be wider than [`LITERAL_WIDTH`]; then the widest literal
+//! on it gets one element per line. A literal holding a comment keeps its line breaks.
+
+/// Bump whenever a change to the rules alters output, so `aeonmi format` drops its cache.
+pub const FORMATTER_VERSION: u32 = 4;
 
-#[derive(Debug, Clone, Copy)]
+/// Array and object literals wider than this (or than `max_line_width`, when set) are
+/// written one element per line.
+pub const LITERAL_WIDTH: usize = 80;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct FormatOptions {
     /// Spaces per indent level.
-    pub indent: usize,
+    pub indent_width: usize,
     /// Max consecutive blank lines allowed.
     pub max_blank: usize,
+    /// Break lines wider than this after a comma inside `(...)` or `[...]`; `None` never wraps.
+    pub max_line_width: Option<usize>,
+    /// How the comparison and assignment operators with a glyph spelling are written.
+    pub glyphs: GlyphMode,
+    /// End expression statements with the `;` the parser treats as optional.
+    pub always_semicolons: bool,
 }
 
 impl Default for FormatOptions {
     fn default() -> Self {
         Self {
-            indent: 4,
+            indent_width: 4,
             max_blank: 1,
+            max_line_width: None,
+            glyphs: GlyphMode::Preserve,
+            always_semicolons: false,
+        }
+    }
+}
+
+/// Spelling of the operators the lexer also accepts as glyphs: `≤ ≥ ≠ ≔` (and `＝` for `==`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GlyphMode {
+    /// Leave operators as written.
+    #[default]
+    Preserve,
+    /// `≤` -> `<=`, `≠` -> `!=`, ...
+    Ascii,
+    /// `<=` -> `≤`, `!=` -> `≠`, ... (`＝` still becomes `==`).
+    Unicode,
+}
+
+impl std::str::FromStr for GlyphMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "preserve" => Ok(Self::Preserve),
+            "ascii" => Ok(Self::Ascii),
+            "unicode" => Ok(Self::Unicode),
+            _ => Err(format!("unknown glyph mode `{s}` (expected preserve, ascii or unicode)")),
         }
     }
 }
@@ -55,11 +105,22 @@ pub fn format_ai_with(src: &str, opt: &FormatOptions) -> String {
     while cleaned.ends_with("\n\n") {
         cleaned.pop();
     }
+    if opt.always_semicolons {
+        cleaned = insert_semicolons(&cleaned);
+    }
 
     // 3) Token-ish pass: enforce spacing around punctuation/operators.
     //    (We avoid full tokenization to keep this decoupled and robust.)
     let mut out = String::with_capacity(cleaned.len() + cleaned.len() / 10);
     let mut indent = 0usize;
+    // open `(`/`[` outside comments, and the indent of the line the outermost one opened on:
+    // when wrapping, lines broken inside them continue one level deeper than that line
+    let mut nest = 0usize;
+    let mut nest_base = 0usize;
+    // every open bracket and brace, innermost last
+    let mut groups: Vec<Group> = Vec::new();
+    // end of the comment being copied; its glyphs and brackets are left alone
+    let mut comment_end = 0usize;
     let mut i = 0usize;
     let bytes = cleaned.as_bytes();
 
@@ -71,34 +132,61 @@ pub fn format_ai_with(src: &str, opt: &FormatOptions) -> String {
     while i < bytes.len() {
         let ch = bytes[i] as char;
 
-        // Simple string/char literal passthrough (handles quotes)
+        // String/char literals (with any `${...}` parts) are copied verbatim
         if ch == '"' || ch == '\'' {
-            let quote = ch;
             push_pending_space(&mut out, &mut need_space, &mut was_space);
-            out.push(quote);
-            i += 1;
-            while i < bytes.len() {
-                let c = bytes[i] as char;
-                out.push(c);
-                i += 1;
-                if c == '\\' && i < bytes.len() {
-                    // escape next
-                    out.push(bytes[i] as char);
-                    i += 1;
-                    continue;
-                }
-                if c == quote {
-                    break;
-                }
-            }
+            let len = string_len(&bytes[i..]);
+            out.push_str(&cleaned[i..i + len]);
+            i += len;
+            just_wrote_newline = false;
+            continue;
+        }
+
+        // Numeric literals are copied as one unit so `1e-9` never gets operator spacing.
+        if ch.is_ascii_digit() && (i == 0 || !is_word_byte(bytes[i - 1])) {
+            let len = number_len(&bytes[i..]);
+            push_pending_space(&mut out, &mut need_space, &mut was_space);
+            out.push_str(&canonical_number(&cleaned[i..i + len]));
+            i += len;
             just_wrote_newline = false;
             continue;
         }
 
+        if i >= comment_end && is_comment_start(&bytes[i..]) {
+            comment_end = i + comment_len(&bytes[i..]);
+        } else if i >= comment_end && opt.glyphs != GlyphMode::Preserve {
+            if let Some((len, spelled)) = operator_spelling(&cleaned[i..], opt.glyphs) {
+                push_pending_space(&mut out, &mut need_space, &mut was_space);
+                out.push_str(spelled);
+                i += len;
+                just_wrote_newline = false;
+                was_space = false;
+                need_space = false;
+                continue;
+            }
+        }
+
         match ch {
             // Braces/newline/indent rules
+            '{' if comment_end != i && opens_object(&out) => {
+                push_pending_space(&mut out, &mut need_space, &mut was_space);
+                if !out.is_empty() && !out.ends_with([' ', '\n', '(', '[']) {
+                    out.push(' ');
+                }
+                out.push_str("{ ");
+                let commented = literal_has_comment(&bytes[i..]);
+                if commented {
+                    indent += 1;
+                }
+                groups.push(Group::Object { commented });
+                just_wrote_newline = false;
+                was_space = true;
+                need_space = false;
+                i += 1;
+            }
             '{' => {
                 push_pending_space(&mut out, &mut need_space, &mut was_space);
+                groups.push(Group::Block);
                 // ensure a single space before '{' when appropriate
                 if !out.ends_with(' ') && !out.ends_with('\n') && !out.is_empty() {
                     out.push(' ');
@@ -106,19 +194,47 @@ pub fn format_ai_with(src: &str, opt: &FormatOptions) -> String {
                 out.push('{');
                 out.push('\n');
                 indent += 1;
-                write_indent(&mut out, opt.indent, indent);
+                nest = 0;
+                write_indent(&mut out, opt.indent_width, indent);
                 just_wrote_newline = true;
                 was_space = false;
                 need_space = false;
                 i += 1;
             }
             '}' => {
+                let object = loop {
+                    match groups.pop() {
+                        None | Some(Group::Block) => break None,
+                        Some(Group::Object { commented }) => break Some(commented),
+                        Some(_) => {}
+                    }
+                };
+                if let Some(commented) = object {
+                    if commented {
+                        indent = indent.saturating_sub(1);
+                    }
+                    if out.ends_with("{ ") {
+                        out.pop();
+                    } else {
+                        close_literal(&mut out, just_wrote_newline, opt.indent_width, indent);
+                        if !just_wrote_newline {
+                            out.push(' ');
+                        }
+                    }
+                    out.push('}');
+                    just_wrote_newline = false;
+                    was_space = false;
+                    need_space = true;
+                    i += 1;
+                    continue;
+                }
                 // dedent first
                 indent = indent.saturating_sub(1);
+                nest = 0;
                 if !just_wrote_newline {
                     out.push('\n');
                 }
-                write_indent(&mut out, opt.indent, indent);
+                write_indent(&mut out, opt.indent_width, indent);
                 out.push('}');
                 just_wrote_newline = false;
                 was_space = false;
@@ -127,14 +243,44 @@ pub fn format_ai_with(src: &str, opt: &FormatOptions) -> String {
             }
             '(' | '[' => {
                 push_pending_space(&mut out, &mut need_space, &mut was_space);
+                // `let[a, b]` destructures rather than indexes: canonical is `let [a, b]`.
+                if ch == '[' && ends_with_keyword(&out, "let") {
+                    out.push(' ');
+                }
                 out.push(ch);
+                if i >= comment_end {
+                    if nest == 0 {
+                        nest_base = leading_spaces(out.rsplit('\n').next().unwrap_or_default());
+                    }
+                    nest += 1;
+                    groups.push(if ch == '(' {
+                        Group::Paren
+                    } else {
+                        let commented = literal_has_comment(&bytes[i..]);
+                        if commented {
+                            indent += 1;
+                        }
+                        Group::Bracket { commented }
+                    });
+                }
                 just_wrote_newline = false;
                 was_space = false;
                 need_space = false;
                 i += 1;
             }
             ')' | ']' => {
+                if i >= comment_end && matches!(groups.last(), Some(Group::Paren | Group::Bracket { .. })) {
+                    if let Some(Group::Bracket { commented }) = groups.pop() {
+                        if commented {
+                            indent = indent.saturating_sub(1);
+                        }
+                        close_literal(&mut out, just_wrote_newline, opt.indent_width, indent);
+                    }
+                }
                 out.push(ch);
+                if i >= comment_end {
+                    nest = nest.saturating_sub(1);
+                }
                 just_wrote_newline = false;
                 was_space = false;
                 need_space = true;
@@ -151,7 +297,8 @@ pub fn format_ai_with(src: &str, opt: &FormatOptions) -> String {
             ';' => {
                 out.push(';');
                 out.push('\n');
-                write_indent(&mut out, opt.indent, indent);
+                nest = 0;
+                write_indent(&mut out, opt.indent_width, indent);
                 just_wrote_newline = true;
                 was_space = false;
                 need_space = false;
@@ -166,7 +313,15 @@ pub fn format_ai_with(src: &str, opt: &FormatOptions) -> String {
                 i += 1;
             }
             ' ' | '\t' => {
-                if !just_wrote_newline && !was_space {
+                if !just_wrote_newline && !was_space && !out.ends_with('[') {
+                    need_space = true;
+                }
+                i += 1;
+            }
+            // a line break inside a literal without comments is only a space: the
+            // literal is laid out again after this pass
+            '\n' if groups.iter().any(|g| matches!(g, Group::Bracket { commented: false } | Group::Object { commented: false })) => {
+                if !just_wrote_newline && !was_space && !out.ends_with('[') {
                     need_space = true;
                 }
                 i += 1;
@@ -176,7 +331,11 @@ pub fn format_ai_with(src: &str, opt: &FormatOptions) -> String {
                     out.push('\n');
                     just_wrote_newline = true;
                 }
-                write_indent(&mut out, opt.indent, indent);
+                if opt.max_line_width.is_some() && nest > 0 && matches!(groups.last(), Some(Group::Paren)) {
+                    write_indent(&mut out, 1, nest_base + opt.indent_width);
+                } else {
+                    write_indent(&mut out, opt.indent_width, indent);
+                }
                 was_space = false;
                 need_space = false;
                 i += 1;
@@ -201,7 +360,495 @@ pub fn format_ai_with(src: &str, opt: &FormatOptions) -> String {
     if !final_s.ends_with('\n') {
         final_s.push('\n');
     }
-    final_s
+    let final_s = expand_literals(&final_s, opt.indent_width, opt.max_line_width.unwrap_or(LITERAL_WIDTH));
+    match opt.max_line_width {
+        Some(width) => wrap_lines(&final_s, opt.indent_width, width),
+        None => final_s,
+    }
+}
+
+/// What an open bracket or brace in the main pass belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Group {
+    Paren,
+    /// An array literal, index or pattern.
+    Bracket { commented: bool },
+    Block,
+    Object { commented: bool },
+}
+
+/// Whether a `{` written after `out` opens an object literal rather than a block: it
+/// follows `=`, `(`, `[`, `,`, `:` or `return`, as in `insert_semicolons`.
+fn opens_object(out: &str) -> bool {
+    let before = out.trim_end();
+    before.ends_with(['=', '(', '[', ',', ':']) || ends_with_keyword(before, "return")
+}
+
+/// Whether the literal opening at `bytes[0]` holds a comment before its closing bracket.
+fn literal_has_comment(bytes: &[u8]) -> bool {
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' | b'\'' => {
+                i += string_len(&bytes[i..]);
+                continue;
+            }
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return false;
+                }
+            }
+            _ if is_comment_start(&bytes[i..]) => return true,
+            _ => {}
+        }
+        i += 1;
+    }
+    false
+}
+
+/// Before the closing bracket of a literal: drop a trailing `, ` and, when the closer
+/// starts a line, indent it to `level`.
+fn close_literal(out: &mut String, at_line_start: bool, indent_width: usize, level: usize) {
+    if at_line_start {
+        out.truncate(out.trim_end_matches(' ').len());
+        write_indent(out, indent_width, level);
+    } else {
+        if out.ends_with(", ") {
+            out.truncate(out.len() - 2);
+        }
+        out.truncate(out.trim_end_matches(' ').len());
+    }
+}
+
+/// Give the widest one-line literal of each line of `text` wider than `width` one element
+/// per line, one `indent_width` deeper than the line, then do the same to the lines that
+/// produces.
+fn expand_literals(text: &str, indent_width: usize, width: usize) -> String {
+    let mut out = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let (body, newline) = line.strip_suffix('\n').map_or((line, ""), |b| (b, "\n"));
+        expand_line(body, indent_width, width, &mut out);
+        out.push_str(newline);
+    }
+    out
+}
+
+fn expand_line(line: &str, indent_width: usize, width: usize, out: &mut String) {
+    use crate::core::columns::display_width;
+
+    let widest = (display_width(line) > width)
+        .then(|| literals(line).into_iter().max_by_key(|&(open, close)| (close - open, std::cmp::Reverse(open))))
+        .flatten();
+    let Some((open, close)) = widest else {
+        out.push_str(line);
+        return;
+    };
+    let indent = leading_spaces(line);
+    let mut pieces = vec![line[..=open].to_string()];
+    let elements = split_elements(&line[open + 1..close]);
+    let last = elements.len() - 1;
+    for (n, element) in elements.into_iter().enumerate() {
+        let comma = if n < last { "," } else { "" };
+        pieces.push(format!("{}{}{}", " ".repeat(indent + indent_width), element, comma));
+    }
+    pieces.push(format!("{}{}", " ".repeat(indent), &line[close..]));
+    for (n, piece) in pieces.iter().enumerate() {
+        if n > 0 {
+            out.push('\n');
+        }
+        expand_line(piece, indent_width, width, out);
+    }
+}
+
+/// `(open, close)` offsets of the non-empty array and object literals that open and close
+/// on formatted `line`. A `[` right after a name, `)`, `]`, `}` or string indexes instead.
+fn literals(line: &str) -> Vec<(usize, usize)> {
+    let bytes = line.as_bytes();
+    let mut open: Vec<Option<usize>> = Vec::new();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' | b'\'' => {
+                i += string_len(&bytes[i..]);
+                continue;
+            }
+            _ if is_comment_start(&bytes[i..]) => break,
+            b'[' => {
+                let index = i > 0 && (is_word_byte(bytes[i - 1]) || matches!(bytes[i - 1], b')' | b']' | b'}' | b'"' | b'\''));
+                open.push((!index).then_some(i));
+            }
+            // an object's `{` is never the last thing on its line, a block's always is
+            b'{' => open.push((i + 1 < bytes.len()).then_some(i)),
+            b'(' => open.push(None),
+            b')' | b']' | b'}' => {
+                if let Some(Some(at)) = open.pop() {
+                    if !line[at + 1..i].trim().is_empty() {
+                        found.push((at, i));
+                    }
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    found
+}
+
+/// The comma-separated elements of a literal's inside, trimmed.
+fn split_elements(inner: &str) -> Vec<&str> {
+    let bytes = inner.as_bytes();
+    let (mut depth, mut start) = (0usize, 0usize);
+    let mut elements = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' | b'\'' => {
+                i += string_len(&bytes[i..]);
+                continue;
+            }
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth = depth.saturating_sub(1),
+            b',' if depth == 0 => {
+                elements.push(inner[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    elements.push(inner[start..].trim());
+    elements
+}
+
+/// Two-character operators, in the lexer's order of preference; a glyph spelling follows.
+const PAIRED_OPERATORS: [(&str, Option<&str>); 9] = [
+    ("==", None),
+    ("=>", None),
+    ("!=", Some("≠")),
+    ("<=", Some("≤")),
+    (">=", Some("≥")),
+    (":=", Some("≔")),
+    ("&&", None),
+    ("||", None),
+    ("**", None),
+];
+
+/// Operator glyphs the lexer maps to a two-character operator.
+const OPERATOR_GLYPHS: [(char, &str); 5] = [('≤', "<="), ('≥', ">="), ('≠', "!="), ('＝', "=="), ('≔', ":=")];
+
+/// The operator starting `rest`, and how `mode` spells it. Pairs are
+/// matched whole, as the lexer does, so `=>=` stays a fat arrow and an `=`.
+fn operator_spelling(rest: &str, mode: GlyphMode) -> Option<(usize, &'static str)> {
+    let ch = rest.chars().next()?;
+    if let Some((_, ascii)) = OPERATOR_GLYPHS.iter().find(|(g, _)| *g == ch) {
+        let spelled = match (mode, ch) {
+            (GlyphMode::Unicode, '＝') | (GlyphMode::Ascii, _) => *ascii,
+            _ => PAIRED_OPERATORS.iter().find(|(op, _)| op == ascii).and_then(|(_, g)| *g)?,
+        };
+        return Some((ch.len_utf8(), spelled));
+    }
+    let (op, glyph) = PAIRED_OPERATORS.iter().find(|(op, _)| rest.starts_with(op))?;
+    Some((op.len(), glyph.filter(|_| mode == GlyphMode::Unicode).unwrap_or(op)))
+}
+
+/// Whether a line comment (`//` or `⍝`) starts at `bytes[0]`.
+fn is_comment_start(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"//") || bytes.starts_with("⍝".as_bytes())
+}
+
+/// Length of the comment at `bytes[0]` as the formatter sees it: up to the end of the line,
+/// or the first `;` or brace, after which the main pass starts a new line anyway.
+fn comment_len(bytes: &[u8]) -> usize {
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\n' | b';' | b'{' | b'}' => break,
+            b'"' | b'\'' => i += string_len(&bytes[i..]),
+            _ => i += 1,
+        }
+    }
+    i
+}
+
+/// Words that cannot end a statement, so no `;` goes after them.
+const OPENING_KEYWORDS: [&str; 17] = [
+    "let", "function", "fn", "return", "log", "qubit", "qreg", "enum", "match", "superpose", "entangle", "measure",
+    "dod", "if", "else", "for", "while",
+];
+
+/// Add the optional `;` after expression statements that end a line (or come right before
+/// the `}` closing their block). Only statement blocks are touched: not `enum` or `match`
+/// bodies, and not braces in expression position.
+fn insert_semicolons(src: &str) -> String {
+    let bytes = src.as_bytes();
+    let mut out = String::with_capacity(src.len() + src.len() / 20);
+    let mut copied = 0usize;
+    // just past the last token, when that token can end a statement
+    let mut end: Option<usize> = None;
+    // open `(`/`[`, true for the condition of an `if`, `while` or `for`
+    let mut parens: Vec<bool> = Vec::new();
+    // open `{`, true for a block of statements
+    let mut blocks: Vec<bool> = Vec::new();
+    // first word of the current statement, and the previous token when it is a word
+    let mut head: Option<&str> = None;
+    let mut prev_word: Option<&str> = None;
+    let mut prev = b';';
+    let mut i = 0usize;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b == b'"' || b == b'\'' {
+            i += string_len(&bytes[i..]);
+            end = Some(i);
+            prev_word = None;
+            prev = b;
+            continue;
+        }
+        if is_comment_start(&bytes[i..]) {
+            i += comment_len(&bytes[i..]);
+            continue;
+        }
+        let word_len = src[i..].chars().take_while(|&c| is_word_char(c)).map(char::len_utf8).sum::<usize>();
+        if word_len > 0 {
+            let word = &src[i..i + word_len];
+            head.get_or_insert(word);
+            i += word_len;
+            end = (!OPENING_KEYWORDS.contains(&word)).then_some(i);
+            prev_word = Some(word);
+            prev = b'a';
+            continue;
+        }
+        let statement_ends = parens.is_empty() && blocks.last().copied().unwrap_or(true);
+        match b {
+            b'\n' => {
+                if let Some(at) = end.filter(|_| statement_ends && starts_statement(&src[i..])) {
+                    out.push_str(&src[copied..at]);
+                    out.push(';');
+                    copied = at;
+                    end = None;
+                    head = None;
+                }
+            }
+            b' ' | b'\t' => {}
+            b'(' | b'[' => {
+                parens.push(b == b'(' && matches!(prev_word, Some("if" | "while" | "for")));
+                end = None;
+            }
+            b')' | b']' => {
+                let header = parens.pop().unwrap_or(false);
+                end = (!header).then_some(i + 1);
+            }
+            b'{' => {
+                let expression = matches!(prev, b'=' | b'(' | b'[' | b',' | b':') || prev_word == Some("return");
+                blocks.push(!expression && !matches!(head, Some("enum" | "match")));
+                end = None;
+                head = None;
+            }
+            b'}' => {
+                if let Some(at) = end.filter(|_| statement_ends) {
+                    out.push_str(&src[copied..at]);
+                    out.push(';');
+                    copied = at;
+                }
+                blocks.pop();
+                end = None;
+                head = None;
+            }
+            b';' => {
+                end = None;
+                head = None;
+            }
+            _ => end = None,
+        }
+        if !matches!(b, b' ' | b'\t' | b'\n') {
+            prev = b;
+            prev_word = None;
+        }
+        i += src[i..].chars().next().map_or(1, char::len_utf8);
+    }
+    out.push_str(&src[copied..]);
+    out
+}
+
+/// Identifier characters, plus the glyphs the lexer reads as hieroglyphic operations.
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || (!c.is_ascii() && !matches!(c, '≤' | '≥' | '≠' | '＝' | '≔' | '⍝' | '⦅' | '⦆'))
+}
+
+/// Whether the first token after `rest`'s whitespace and comments starts a new statement,
+/// rather than continuing the last one (an operator, `.`, `(`, `{`, `else`, ...).
+fn starts_statement(rest: &str) -> bool {
+    let bytes = rest.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i].is_ascii_whitespace() {
+            i += 1;
+        } else if is_comment_start(&bytes[i..]) {
+            i += comment_len(&bytes[i..]);
+        } else {
+            break;
+        }
+    }
+    let rest = &rest[i..];
+    let Some(c) = rest.chars().next() else {
+        return true;
+    };
+    let word: String = rest.chars().take_while(|&c| is_word_char(c)).collect();
+    (c == '"' || c == '\'' || !word.is_empty()) && !matches!(word.as_str(), "else" | "in")
+}
+
+/// Break lines of formatted `text` that are wider than `width` after the last comma inside
+/// `(...)` or `[...]` that keeps them within it (or, when none does, the first such comma).
+/// The rest continues one `indent_width` deeper than the line the outermost bracket opened
+/// on, which is where the main pass puts lines broken inside brackets. Lines without such a
+/// comma stay long.
+fn wrap_lines(text: &str, indent_width: usize, width: usize) -> String {
+    use crate::core::columns::display_width;
+
+    let bytes = text.as_bytes();
+    let mut out = String::with_capacity(text.len() + text.len() / 20);
+    // bracket depth outside comments, counted as the main pass does, and where the
+    // outermost open bracket is
+    let (mut nest, mut open) = (0usize, 0usize);
+    // where each output line starts in `text`, and its indent
+    let mut lines: Vec<(usize, usize)> = Vec::new();
+    let mut start = 0usize;
+    while start < text.len() {
+        // offsets just past each comma a break may follow, with its outermost open bracket
+        let mut commas: Vec<(usize, usize)> = Vec::new();
+        let mut comment_end = start;
+        let mut i = start;
+        while i < bytes.len() && bytes[i] != b'\n' {
+            match bytes[i] {
+                b'"' | b'\'' => {
+                    i += string_len(&bytes[i..]);
+                    continue;
+                }
+                _ if i >= comment_end && is_comment_start(&bytes[i..]) => comment_end = i + comment_len(&bytes[i..]),
+                _ if i < comment_end => {}
+                b'(' | b'[' => {
+                    if nest == 0 {
+                        open = i;
+                    }
+                    nest += 1;
+                }
+                b')' | b']' => nest = nest.saturating_sub(1),
+                b'{' | b'}' | b';' => nest = 0,
+                b',' if nest > 0 => commas.push((i + 1, open)),
+                _ => {}
+            }
+            i += 1;
+        }
+        let end = i.min(text.len());
+        lines.push((start, leading_spaces(&text[start..end])));
+        // the current piece of the line: `text[from..end]` after `prefix`
+        let (mut from, mut prefix) = (start, String::new());
+        let piece_width = |from: usize, to: usize, prefix: &str| {
+            let piece = &text[from..to];
+            display_width(prefix) + display_width(&piece[piece.rfind('\n').map_or(0, |n| n + 1)..])
+        };
+        while piece_width(from, end, &prefix) > width {
+            let mut candidates = commas.iter().filter(|&&(at, _)| at > from && !text[at..end].trim().is_empty());
+            let first = candidates.clone().next();
+            let Some(&(at, open)) = candidates.rfind(|&&(at, _)| piece_width(from, at, &prefix) <= width).or(first) else {
+                break;
+            };
+            out.push_str(&prefix);
+            out.push_str(&text[from..at]);
+            out.push('\n');
+            let base = lines.iter().rev().find(|&&(line, _)| line <= open).map_or(0, |&(_, indent)| indent);
+            prefix = " ".repeat(base + indent_width);
+            from = at + (text[at..end].len() - text[at..end].trim_start().len());
+            lines.push((from, prefix.len()));
+        }
+        out.push_str(&prefix);
+        out.push_str(&text[from..end]);
+        if end < text.len() {
+            out.push('\n');
+        }
+        start = end + 1;
+    }
+    out
+}
+
+fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+/// Whether `out` ends with the whole word `kw`.
+fn ends_with_keyword(out: &str, kw: &str) -> bool {
+    out.strip_suffix(kw).is_some_and(|rest| !rest.bytes().next_back().is_some_and(is_word_byte))
+}
+
+/// Length of the string literal opening at `bytes[0]`, through its closing quote (or the end
+/// of input). In a `"..."` string a `${...}` part is skipped whole, nested braces and
+/// strings included, so its quotes and braces are left alone.
+fn string_len(bytes: &[u8]) -> usize {
+    let quote = bytes[0];
+    let mut i = 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            c if c == quote => return i + 1,
+            b'$' if quote == b'"' && bytes.get(i + 1) == Some(&b'{') => {
+                i += 2;
+                let mut depth = 0usize;
+                while i < bytes.len() {
+                    match bytes[i] {
+                        b'"' => {
+                            i += string_len(&bytes[i..]);
+                            continue;
+                        }
+                        b'{' => depth += 1,
+                        b'}' if depth == 0 => break,
+                        b'}' => depth -= 1,
+                        _ => {}
+                    }
+                    i += 1;
+                }
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Length of the numeric literal at the start of `bytes`, using the lexer's rules
+/// (a sign only continues it right after a decimal exponent marker).
+fn number_len(bytes: &[u8]) -> usize {
+    let radix = bytes.len() >= 2 && bytes[0] == b'0' && matches!(bytes[1], b'x' | b'X' | b'b' | b'B');
+    let mut n = 0;
+    while n < bytes.len() {
+        let b = bytes[n];
+        let exp_sign = matches!(b, b'+' | b'-')
+            && !radix
+            && n > 0
+            && matches!(bytes[n - 1], b'e' | b'E')
+            && bytes.get(n + 1).is_some_and(u8::is_ascii_digit);
+        if !(is_word_byte(b) || b == b'.' || exp_sign) {
+            break;
+        }
+        n += 1;
+    }
+    n
+}
+
+/// Lowercase the radix prefix (`0X` -> `0x`) and exponent marker (`1E5` -> `1e5`);
+/// digits, including hex digits and `_` separators, are kept as written.
+fn canonical_number(lit: &str) -> String {
+    match lit.get(..2) {
+        Some("0X") | Some("0B") => format!("{}{}", &lit[..2].to_ascii_lowercase(), &lit[2..]),
+        Some("0x") | Some("0b") => lit.to_string(),
+        _ => lit.replace('E', "e"),
+    }
+}
+
+fn leading_spaces(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
 }
 
 fn write_indent(out: &mut String, indent_spaces: usize, level: usize) {
@@ -217,3 +864,47 @@ fn push_pending_space(out: &mut String, need_space: &mut bool, was_space: &mut b
         *was_space = true;
     }
 }
+
+/// Map a cursor from `before` to `after`, where `after` is `before` formatted (the
+/// two differ only in whitespace, so line/column correspondence follows the
+/// non-whitespace characters). Positions are 0-based `(line, byte column)`. A cursor
+/// right after a character stays right after it; one sitting in whitespace moves
+/// to just before the next character.
+pub fn remap_cursor(before: &str, after: &str, (line, col): (usize, usize)) -> (usize, usize) {
+    // Non-whitespace characters before the cursor, and whether it is glued to the last one.
+    let mut seen = 0usize;
+    let mut glued = false;
+    for (i, l) in before.split('\n').enumerate() {
+        if i > line {
+            break;
+        }
+        let l = if i == line { &l[..crate::core::columns::floor_char_boundary(l, col)] } else { l };
+        for ch in l.chars() {
+            if !ch.is_whitespace() {
+                seen += 1;
+            }
+        }
+        glued = i == line && l.chars().next_back().is_some_and(|c| !c.is_whitespace());
+    }
+    let mut count = 0usize;
+    let mut last = (0, 0);
+    for (row, l) in after.split('\n').enumerate() {
+        for (b, ch) in l.char_indices() {
+            if ch.is_whitespace() {
+                continue;
+            }
+            if count == seen && !glued {
+                return (row, b);
+            }
+            count += 1;
+            last = (row, b + ch.len_utf8());
+            if count == seen && glued {
+                return last;
+            }
+        }
+    }
+    if seen == 0 {
+        return (0, 0);
+    }
+    last
+}
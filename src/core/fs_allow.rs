@@ -0,0 +1,158 @@
+//! Allow-list for the GUI's file commands (`load_file` / `save_file`).
+//!
+//! The webview names files by path, so a compromised frontend could otherwise read or
+//! overwrite anything the user can. Every path is resolved (symlinks and `..` included)
+//! and must land under one of the allowed roots: the project root, plus directories the
+//! user picked in an OS file dialog ([`FsAllowList::allow_root`]). A file that does not
+//! exist yet is resolved through its directory, which must exist. Writes are recorded in
+//! an in-memory audit log for the session, denied ones included.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Why a file command was refused. Serializes as `{"code": ..., "path": ..., "message": ...}`
+/// so the frontend can tell a policy denial from an I/O failure.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum FsAccessError {
+    /// The resolved path is not under any allowed root.
+    OutsideAllowedRoots { path: String, resolved: String, roots: Vec<String>, message: String },
+    /// The path cannot be resolved (missing directory, no file name, dangling symlink).
+    InvalidPath { path: String, message: String },
+    /// Reading or writing an allowed path failed.
+    Io { path: String, message: String },
+}
+
+impl FsAccessError {
+    fn outside(path: &Path, resolved: &Path, roots: &[PathBuf]) -> Self {
+        FsAccessError::OutsideAllowedRoots {
+            path: path.display().to_string(),
+            resolved: resolved.display().to_string(),
+            roots: roots.iter().map(|r| r.display().to_string()).collect(),
+            message: format!("`{}` is outside the allowed directories", path.display()),
+        }
+    }
+
+    fn invalid(path: &Path, why: impl std::fmt::Display) -> Self {
+        FsAccessError::InvalidPath { path: path.display().to_string(), message: format!("{}: {}", path.display(), why) }
+    }
+
+    fn io(path: &Path, e: std::io::Error) -> Self {
+        FsAccessError::Io { path: path.display().to_string(), message: format!("{}: {}", path.display(), e) }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            FsAccessError::OutsideAllowedRoots { message, .. }
+            | FsAccessError::InvalidPath { message, .. }
+            | FsAccessError::Io { message, .. } => message,
+        }
+    }
+}
+
+impl std::fmt::Display for FsAccessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for FsAccessError {}
+
+/// One `write` attempt. `resolved` is empty when the path could not be resolved.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WriteRecord {
+    pub at_ms: u128,
+    pub path: String,
+    pub resolved: String,
+    pub bytes: usize,
+    /// `"ok"`, or the [`FsAccessError`] code.
+    pub outcome: String,
+}
+
+#[derive(Debug, Default)]
+pub struct FsAllowList {
+    roots: Vec<PathBuf>,
+    audit: Vec<WriteRecord>,
+}
+
+impl FsAllowList {
+    /// An allow-list holding just `root` (canonicalized).
+    pub fn with_root(root: &Path) -> Result<Self, FsAccessError> {
+        let mut list = FsAllowList::default();
+        list.allow_root(root)?;
+        Ok(list)
+    }
+
+    /// Allow everything under `dir` (canonicalized, so a symlinked root is recorded by its
+    /// target). Meant for directories the user picked in a file dialog; for a picked file,
+    /// pass its directory. Returns the recorded root.
+    pub fn allow_root(&mut self, dir: &Path) -> Result<PathBuf, FsAccessError> {
+        let root = dir.canonicalize().map_err(|e| FsAccessError::invalid(dir, e))?;
+        if !root.is_dir() {
+            return Err(FsAccessError::invalid(dir, "not a directory"));
+        }
+        if !self.roots.contains(&root) {
+            self.roots.push(root.clone());
+        }
+        Ok(root)
+    }
+
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// `path` with symlinks and `..` resolved, provided it lies under an allowed root.
+    /// Relative paths are taken from the current directory.
+    pub fn resolve(&self, path: &Path) -> Result<PathBuf, FsAccessError> {
+        let resolved = match path.canonicalize() {
+            Ok(p) => p,
+            // A dangling symlink would be followed by the write; its target is unknowable here.
+            Err(_) if path.symlink_metadata().is_ok() => return Err(FsAccessError::invalid(path, "dangling symlink")),
+            Err(_) => {
+                let name = match path.file_name() {
+                    Some(n) => n,
+                    None => return Err(FsAccessError::invalid(path, "not a file path")),
+                };
+                let dir = match path.parent() {
+                    Some(p) if !p.as_os_str().is_empty() => p,
+                    _ => Path::new("."),
+                };
+                dir.canonicalize().map_err(|e| FsAccessError::invalid(path, e))?.join(name)
+            }
+        };
+        if self.roots.iter().any(|r| resolved.starts_with(r)) {
+            Ok(resolved)
+        } else {
+            Err(FsAccessError::outside(path, &resolved, &self.roots))
+        }
+    }
+
+    pub fn read(&self, path: &Path) -> Result<String, FsAccessError> {
+        let resolved = self.resolve(path)?;
+        std::fs::read_to_string(&resolved).map_err(|e| FsAccessError::io(path, e))
+    }
+
+    /// Replace the file's contents, recording the attempt in the audit log.
+    pub fn write(&mut self, path: &Path, contents: &str) -> Result<PathBuf, FsAccessError> {
+        let result = self
+            .resolve(path)
+            .and_then(|resolved| std::fs::write(&resolved, contents).map(|_| resolved).map_err(|e| FsAccessError::io(path, e)));
+        let resolved = match &result {
+            Ok(p) => p.display().to_string(),
+            Err(FsAccessError::OutsideAllowedRoots { resolved, .. }) => resolved.clone(),
+            Err(_) => String::new(),
+        };
+        let outcome = match &result {
+            Ok(_) => "ok".to_string(),
+            Err(e) => serde_json::to_value(e).ok().and_then(|v| v["code"].as_str().map(str::to_string)).unwrap_or_default(),
+        };
+        let at_ms = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+        self.audit.push(WriteRecord { at_ms, path: path.display().to_string(), resolved, bytes: contents.len(), outcome });
+        result
+    }
+
+    /// Every write attempted this session, oldest first.
+    pub fn audit_log(&self) -> &[WriteRecord] {
+        &self.audit
+    }
+}
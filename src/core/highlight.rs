@@ -0,0 +1,120 @@
+//! Token classes for syntax highlighting (`export --format html`, `ai chat --markdown`).
+//! [`segments`] cuts a source into consecutive pieces that concatenate back to it, each
+//! tagged with a class (or none for plain punctuation, identifiers and whitespace).
+use crate::core::lexer::{Lexer, LexerError};
+use crate::core::token::TokenKind;
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    Keyword,
+    Quantum,
+    Glyph,
+    Str,
+    Num,
+    Bool,
+    Comment,
+}
+
+impl Class {
+    /// CSS class used by the HTML export.
+    pub fn css(self) -> &'static str {
+        match self {
+            Class::Keyword => "kw",
+            Class::Quantum => "quantum",
+            Class::Glyph => "glyph",
+            Class::Str => "str",
+            Class::Num => "num",
+            Class::Bool => "bool",
+            Class::Comment => "comment",
+        }
+    }
+
+    fn of(kind: &TokenKind) -> Option<Class> {
+        use TokenKind::*;
+        match kind {
+            Function | Let | If | Else | While | For | In | Break | Continue | Return | Log | Qubit | Qreg | Enum | Match => Some(Class::Keyword),
+            Superpose | Entangle | Measure | Dod => Some(Class::Quantum),
+            HieroglyphicOp(_) => Some(Class::Glyph),
+            StringLiteral(_) | InterpolatedString(_) => Some(Class::Str),
+            NumberLiteral(_) | QubitLiteral(_) => Some(Class::Num),
+            BooleanLiteral(_) => Some(Class::Bool),
+            _ => None,
+        }
+    }
+}
+
+/// Classified pieces of `source` (NFC-normalized first, as the lexer sees it). Text between
+/// tokens is whitespace or comments.
+pub fn segments(source: &str) -> Result<Vec<(Option<Class>, String)>, LexerError> {
+    let source: String = source.nfc().collect(); // token columns refer to the normalized text
+    let tokens = Lexer::from_str(&source).tokenize()?;
+    let starts: Vec<usize> = tokens
+        .iter()
+        .map(|t| if t.kind == TokenKind::EOF { source.len() } else { byte_offset(&source, t.line, t.column) })
+        .collect();
+    let mut out = Vec::new();
+    push_trivia(&mut out, &source[..starts.first().copied().unwrap_or(source.len())]);
+    for (i, tok) in tokens.iter().enumerate() {
+        if tok.kind == TokenKind::EOF {
+            break;
+        }
+        let segment = &source[starts[i]..starts.get(i + 1).copied().unwrap_or(source.len())];
+        let len = token_len(segment);
+        out.push((Class::of(&tok.kind), segment[..len].to_string()));
+        push_trivia(&mut out, &segment[len..]);
+    }
+    Ok(out)
+}
+
+/// Byte offset of the lexer position `line`:`column` (1-based, counted in chars).
+fn byte_offset(source: &str, line: usize, column: usize) -> usize {
+    let line_start = if line <= 1 {
+        0
+    } else {
+        source.match_indices('\n').nth(line - 2).map(|(i, _)| i + 1).unwrap_or(source.len())
+    };
+    source[line_start..].char_indices().nth(column.saturating_sub(1)).map(|(i, _)| line_start + i).unwrap_or(source.len())
+}
+
+/// Length of the token that opens `segment`: a whole string literal, or up to the first
+/// whitespace or comment.
+fn token_len(segment: &str) -> usize {
+    if segment.starts_with('"') {
+        let mut escaped = false;
+        for (i, c) in segment.char_indices().skip(1) {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => return i + 1,
+                _ => {}
+            }
+        }
+        return segment.len();
+    }
+    segment
+        .char_indices()
+        .find(|&(i, c)| c.is_whitespace() || c == '⍝' || c == '⦅' || segment[i..].starts_with("//"))
+        .map(|(i, _)| i)
+        .unwrap_or(segment.len())
+        .max(segment.chars().next().map_or(0, char::len_utf8))
+}
+
+/// Whitespace stays plain; anything else between tokens is a comment.
+fn push_trivia(out: &mut Vec<(Option<Class>, String)>, trivia: &str) {
+    let body = trivia.trim();
+    if body.is_empty() {
+        if !trivia.is_empty() {
+            out.push((None, trivia.to_string()));
+        }
+        return;
+    }
+    let lead = trivia.len() - trivia.trim_start().len();
+    if lead > 0 {
+        out.push((None, trivia[..lead].to_string()));
+    }
+    out.push((Some(Class::Comment), body.to_string()));
+    if lead + body.len() < trivia.len() {
+        out.push((None, trivia[lead + body.len()..].to_string()));
+    }
+}
@@ -0,0 +1,333 @@
+//! `import "lib/util.ai";`: resolves imports relative to the importing file and splices
+//! each imported file's top-level items in place of its `import`, so analysis and every
+//! backend see one flattened `Program`.
+//!
+//! A file is loaded at most once per program: importing it again (directly or through
+//! another file) is a no-op. An import that leads back to a file still being loaded is a
+//! cycle and reported at the offending `import`.
+//!
+//! Spliced items keep the line numbers of the file they came from. Analysis runs over
+//! [`Flattened::numbered`] instead, where every imported file's lines follow on from the
+//! files loaded before it, and [`Flattened::locate`] maps a position found there back to
+//! its file.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::core::ast::{ASTNode, Pattern};
+use crate::core::diagnostics::{report_error, report_parse_error, Span};
+use crate::core::error_index;
+use crate::core::lexer::{Lexer, LexerError, LexerOptions};
+use crate::core::parser::{Parser, ParserError};
+use crate::core::token::Token;
+
+/// An imported file, in load order.
+#[derive(Debug, Clone)]
+pub struct ImportedFile {
+    /// Joined onto the importer's directory as written, for display.
+    pub path: PathBuf,
+    /// The file whose `import` loaded this one (the entry or another imported file).
+    pub importer: PathBuf,
+    pub source: String,
+    pub tokens: Vec<Token>,
+    /// Added to this file's line numbers in [`Flattened::numbered`].
+    pub line_base: usize,
+}
+
+/// The program with every import spliced in.
+#[derive(Debug, Clone)]
+pub struct Flattened {
+    /// The file `resolve` was given.
+    pub entry: PathBuf,
+    pub program: ASTNode,
+    /// The file each top-level item of `program` came from, index for index.
+    pub origins: Vec<PathBuf>,
+    pub imported: Vec<ImportedFile>,
+}
+
+impl Flattened {
+    /// `[3] lib/util.ai` per top-level item, for `aeonmi ast`.
+    pub fn origin_lines(&self) -> Vec<String> {
+        self.origins.iter().enumerate().map(|(i, p)| format!("[{}] {}", i, p.display())).collect()
+    }
+
+    /// `program` with each imported item's lines moved up by its file's `line_base`, so a
+    /// line number names one position in one file. Positions an analysis of this program
+    /// reports go through [`Flattened::locate`].
+    pub fn numbered(&self) -> ASTNode {
+        let ASTNode::Program(items) = &self.program else { return self.program.clone() };
+        let items = items
+            .iter()
+            .zip(&self.origins)
+            .map(|(item, origin)| {
+                let mut item = item.clone();
+                if let Some(f) = self.imported.iter().find(|f| f.path == *origin) {
+                    shift_lines(&mut item, f.line_base);
+                }
+                item
+            })
+            .collect();
+        ASTNode::Program(items)
+    }
+
+    /// The file and line in it of `line` in [`Flattened::numbered`]. Line 0 (no position)
+    /// stays in the entry file.
+    pub fn locate(&self, line: usize) -> (&Path, usize) {
+        match self.imported.iter().rev().find(|f| line > f.line_base) {
+            Some(f) => (&f.path, line - f.line_base),
+            None => (&self.entry, line),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    /// `chain` runs from the file first entered to the one imported again.
+    Cycle { file: String, source: String, line: usize, column: usize, chain: Vec<PathBuf> },
+    NotFound { file: String, source: String, line: usize, column: usize, path: PathBuf, reason: String },
+    Lex { file: String, source: String, error: LexerError },
+    Parse { file: String, source: String, error: ParserError },
+}
+
+impl ImportError {
+    pub fn message(&self) -> String {
+        match self {
+            ImportError::Cycle { chain, .. } => format!(
+                "import cycle: {}",
+                chain.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(" -> ")
+            ),
+            ImportError::NotFound { path, reason, .. } => format!("cannot import '{}': {}", path.display(), reason),
+            ImportError::Lex { error, .. } => format!("Lexing error: {}", error),
+            ImportError::Parse { error, .. } => error.to_string(),
+        }
+    }
+
+    /// Print the diagnostic against the file that holds the failing `import` (or fails to parse).
+    pub fn report(&self, pretty: bool) {
+        match self {
+            ImportError::Cycle { file, source, line, column, .. } => {
+                report_error(file, source, &self.message(), Span::single(*line, *column), Some(error_index::IMPORT_CYCLE), pretty)
+            }
+            ImportError::NotFound { file, source, line, column, .. } => {
+                report_error(file, source, &self.message(), Span::single(*line, *column), Some(error_index::IMPORT_NOT_FOUND), pretty)
+            }
+            ImportError::Lex { file, source, error } => {
+                let (line, column) = error.position();
+                report_error(file, source, &self.message(), Span::single(line, column), Some(error_index::LEX_ERROR), pretty)
+            }
+            ImportError::Parse { file, source, error } => report_parse_error(file, source, error, pretty),
+        }
+    }
+}
+
+impl std::fmt::Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportError::Cycle { file, line, column, .. } | ImportError::NotFound { file, line, column, .. } => {
+                write!(f, "{}:{}:{}: {}", file, line, column, self.message())
+            }
+            ImportError::Lex { file, .. } | ImportError::Parse { file, .. } => write!(f, "{}: {}", file, self.message()),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+/// Splice the imports of `program`, parsed from `file` (`source`), lexing imported files with `options`.
+/// A program without imports comes back unchanged. The error is boxed: it carries whole sources.
+pub fn resolve(program: ASTNode, file: &Path, source: &str, options: &LexerOptions) -> Result<Flattened, Box<ImportError>> {
+    let next_base = source.lines().count() + 1;
+    let mut resolver = Resolver { options, loaded: HashSet::new(), stack: Vec::new(), origins: Vec::new(), imported: Vec::new(), next_base };
+    resolver.loaded.insert(identity(file));
+    resolver.stack.push((identity(file), file.to_path_buf()));
+    let items = resolver.splice(program, file, source)?;
+    Ok(Flattened { entry: file.to_path_buf(), program: ASTNode::Program(items), origins: resolver.origins, imported: resolver.imported })
+}
+
+struct Resolver<'a> {
+    options: &'a LexerOptions,
+    /// Canonical paths of every file entered so far.
+    loaded: HashSet<PathBuf>,
+    /// Files being loaded, outermost first: (canonical, display).
+    stack: Vec<(PathBuf, PathBuf)>,
+    origins: Vec<PathBuf>,
+    imported: Vec<ImportedFile>,
+    /// `line_base` of the next file loaded: past every line of the files before it.
+    next_base: usize,
+}
+
+impl Resolver<'_> {
+    fn splice(&mut self, program: ASTNode, file: &Path, source: &str) -> Result<Vec<ASTNode>, Box<ImportError>> {
+        let items = match program {
+            ASTNode::Program(items) => items,
+            other => vec![other],
+        };
+        let mut out = Vec::with_capacity(items.len());
+        for item in items {
+            let ASTNode::Import { path, line, column } = item else {
+                self.origins.push(file.to_path_buf());
+                out.push(item);
+                continue;
+            };
+            let target = file.parent().unwrap_or_else(|| Path::new("")).join(&path);
+            let id = identity(&target);
+            if let Some(start) = self.stack.iter().position(|(c, _)| *c == id) {
+                let mut chain: Vec<PathBuf> = self.stack[start..].iter().map(|(_, d)| d.clone()).collect();
+                chain.push(target);
+                return Err(Box::new(ImportError::Cycle { file: file.display().to_string(), source: source.to_string(), line, column, chain }));
+            }
+            if !self.loaded.insert(id.clone()) {
+                continue;
+            }
+            let text = std::fs::read_to_string(&target).map_err(|e| {
+                Box::new(ImportError::NotFound {
+                    file: file.display().to_string(),
+                    source: source.to_string(),
+                    line,
+                    column,
+                    path: target.clone(),
+                    reason: e.to_string(),
+                })
+            })?;
+            let name = target.display().to_string();
+            let tokens = Lexer::with_options(&text, self.options.clone())
+                .tokenize()
+                .map_err(|error| Box::new(ImportError::Lex { file: name.clone(), source: text.clone(), error }))?;
+            let parsed = Parser::new(tokens.clone())
+                .parse()
+                .map_err(|error| Box::new(ImportError::Parse { file: name.clone(), source: text.clone(), error }))?;
+            self.imported.push(ImportedFile { path: target.clone(), importer: file.to_path_buf(), source: text.clone(), tokens, line_base: self.next_base });
+            self.next_base += text.lines().count() + 1;
+            self.stack.push((id, target.clone()));
+            let spliced = self.splice(parsed, &target, &text)?;
+            self.stack.pop();
+            out.extend(spliced);
+        }
+        Ok(out)
+    }
+}
+
+/// The path imports are compared by: canonical when the file exists, as joined otherwise.
+fn identity(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Move every known (non-zero) line in `node` up by `by`.
+fn shift_lines(node: &mut ASTNode, by: usize) {
+    let shift = |line: &mut usize| {
+        if *line > 0 {
+            *line += by;
+        }
+    };
+    match node {
+        ASTNode::Program(items) | ASTNode::Block(items) => items.iter_mut().for_each(|n| shift_lines(n, by)),
+        ASTNode::Function { line, params, body, .. } => {
+            shift(line);
+            for p in params {
+                shift(&mut p.line);
+            }
+            body.iter_mut().for_each(|n| shift_lines(n, by));
+        }
+        ASTNode::VariableDecl { value, line, .. } | ASTNode::Assignment { value, line, .. } => {
+            shift(line);
+            shift_lines(value, by);
+        }
+        ASTNode::DestructuringDecl { targets, values, line, .. } => {
+            shift(line);
+            targets.iter_mut().for_each(|t| shift_pattern(t, by));
+            values.iter_mut().for_each(|n| shift_lines(n, by));
+        }
+        ASTNode::EnumDecl { line, .. }
+        | ASTNode::Import { line, .. }
+        | ASTNode::Break { line, .. }
+        | ASTNode::Continue { line, .. }
+        | ASTNode::IdentifierSpanned { line, .. }
+        | ASTNode::QubitDecl { line, .. }
+        | ASTNode::QregDecl { line, .. } => shift(line),
+        ASTNode::Return(expr) | ASTNode::UnaryExpr { expr, .. } => shift_lines(expr, by),
+        ASTNode::Log { expr, line, .. } => {
+            shift(line);
+            shift_lines(expr, by);
+        }
+        ASTNode::If { condition, then_branch, else_branch } => {
+            shift_lines(condition, by);
+            shift_lines(then_branch, by);
+            if let Some(e) = else_branch {
+                shift_lines(e, by);
+            }
+        }
+        ASTNode::While { condition, body } => {
+            shift_lines(condition, by);
+            shift_lines(body, by);
+        }
+        ASTNode::For { init, condition, increment, body } => {
+            for n in [init, condition, increment].into_iter().flatten() {
+                shift_lines(n, by);
+            }
+            shift_lines(body, by);
+        }
+        ASTNode::ForIn { iterable, body, line, .. } => {
+            shift(line);
+            shift_lines(iterable, by);
+            shift_lines(body, by);
+        }
+        ASTNode::Match { subject, arms, line, .. } => {
+            shift(line);
+            shift_lines(subject, by);
+            for arm in arms {
+                shift(&mut arm.line);
+                if let Some(p) = &mut arm.pattern {
+                    shift_lines(p, by);
+                }
+                shift_lines(&mut arm.body, by);
+            }
+        }
+        ASTNode::Call { callee, args } => {
+            shift_lines(callee, by);
+            args.iter_mut().for_each(|n| shift_lines(n, by));
+        }
+        ASTNode::BinaryExpr { left, right, .. } => {
+            shift_lines(left, by);
+            shift_lines(right, by);
+        }
+        ASTNode::Index { target, index, line, .. } => {
+            shift(line);
+            shift_lines(target, by);
+            shift_lines(index, by);
+        }
+        ASTNode::Member { target, line, .. } => {
+            shift(line);
+            shift_lines(target, by);
+        }
+        ASTNode::ArrayLiteral { items, line, .. } => {
+            shift(line);
+            items.iter_mut().for_each(|n| shift_lines(n, by));
+        }
+        ASTNode::ObjectLiteral { fields, line, .. } => {
+            shift(line);
+            fields.iter_mut().for_each(|(_, n)| shift_lines(n, by));
+        }
+        ASTNode::QuantumOp { qubits, line, .. } => {
+            shift(line);
+            qubits.iter_mut().for_each(|n| shift_lines(n, by));
+        }
+        ASTNode::HieroglyphicOp { args, .. } => args.iter_mut().for_each(|n| shift_lines(n, by)),
+        ASTNode::Identifier(_) | ASTNode::NumberLiteral(_) | ASTNode::StringLiteral(_) | ASTNode::BooleanLiteral(_) | ASTNode::Error(_) => {}
+    }
+}
+
+fn shift_pattern(pattern: &mut Pattern, by: usize) {
+    match pattern {
+        Pattern::Name { line, .. } => {
+            if *line > 0 {
+                *line += by;
+            }
+        }
+        Pattern::Array { items, line, .. } => {
+            if *line > 0 {
+                *line += by;
+            }
+            items.iter_mut().for_each(|p| shift_pattern(p, by));
+        }
+    }
+}
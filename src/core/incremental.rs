@@ -2,13 +2,14 @@
 //! Currently caches last full parse; future work will diff edited regions.
 
 use crate::core::lexer::Lexer;
-use crate::core::parser::{Parser as AeParser, ParserError};
+use crate::core::parser::Parser as AeParser;
 use crate::core::ast::ASTNode;
-use std::sync::Mutex;
+use crate::core::quantum_extract::{assemble, extract_item, QuantumCircuit, SubCircuit};
+use std::sync::{Mutex, MutexGuard};
 use once_cell::sync::Lazy;
 use sha1::{Sha1, Digest};
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::time::{Instant, Duration};
 
 #[derive(Debug, Clone)]
@@ -20,13 +21,41 @@ pub struct CachedParse {
     pub ast: ASTNode,
     pub source: String,
     pub top_spans: Vec<NodeSpan>,
+    /// String literal and comment bodies of `source`, in source order.
+    pub literals: Vec<LiteralSpan>,
+    /// `ast` positions predate a trivia edit that moved lines (`top_spans` do not); the next
+    /// edit that changes tokens reparses in full instead of patching or splicing.
+    pub shifted: bool,
+    /// Bumped for every new `ast`; a splice is exactly one generation after the tree it edited.
+    pub generation: u64,
+    /// Top-level items a splice replaced in that tree (the first is [`LAST_REPLACED_INDEX`]).
+    pub replaced: Option<std::ops::Range<usize>>,
 }
 
+static PARSE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+impl CachedParse {
+    fn new(hash: String, ast: ASTNode, source: &str) -> Self {
+        let top_spans = index_top_level(&ast, source);
+        let generation = PARSE_GENERATION.fetch_add(1, Ordering::Relaxed) + 1;
+        Self { hash, ast, source: source.to_string(), top_spans, literals: scan_literals(source), shifted: false, generation, replaced: None }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiteralKind { Str, LineComment, BlockComment }
+
+/// Byte range of a literal's body, delimiters excluded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiteralSpan { pub kind: LiteralKind, pub start: usize, pub end: usize }
+
 #[derive(Debug, Clone, Default)]
 pub struct DirtyInfo {
     pub changed: bool,
     pub first_changed_line: usize,
     pub last_changed_line: usize,
+    /// End of the changed region in the cached source's numbering (cached spans use it).
+    pub last_changed_old_line: usize,
 }
 
 static CACHE: Lazy<Mutex<Option<CachedParse>>> = Lazy::new(|| Mutex::new(None));
@@ -34,16 +63,47 @@ static CACHE: Lazy<Mutex<Option<CachedParse>>> = Lazy::new(|| Mutex::new(None));
 // Cache of last semantic diagnostics grouped per top-level node (index aligned with Program children)
 #[derive(Debug, Clone, Default)]
 #[allow(dead_code)]
-pub struct TopLevelDiagCache { pub per_node: Vec<Vec<crate::core::semantic_analyzer::SemanticDiagnostic>> }
+pub struct TopLevelDiagCache {
+    pub per_node: Vec<Vec<crate::core::semantic_analyzer::SemanticDiagnostic>>,
+    /// Diagnostics last reported for the whole file; a trivia-only edit serves these, shifted.
+    pub reported: Vec<crate::core::semantic_analyzer::SemanticDiagnostic>,
+}
 #[allow(dead_code)]
 pub static DIAG_CACHE: Lazy<Mutex<TopLevelDiagCache>> = Lazy::new(|| Mutex::new(TopLevelDiagCache::default()));
 pub static LAST_REPLACED_INDEX: Lazy<Mutex<Option<usize>>> = Lazy::new(|| Mutex::new(None));
 #[derive(Debug, Clone, Default)]
 #[allow(dead_code)]
-pub struct TopLevelTypeDiagCache { pub per_node: Vec<Vec<crate::core::types::TypeDiagnostic>> }
+pub struct TopLevelTypeDiagCache { pub per_node: Vec<Vec<crate::core::types::TypeDiagnostic>>, pub reported: Vec<crate::core::types::TypeDiagnostic> }
 #[allow(dead_code)]
 pub static TYPE_DIAG_CACHE: Lazy<Mutex<TopLevelTypeDiagCache>> = Lazy::new(|| Mutex::new(TopLevelTypeDiagCache::default()));
 
+/// Lines after `after_line` (1-based, in the previous source's numbering) moved by `delta`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineShift { pub after_line: usize, pub delta: isize }
+impl LineShift {
+    pub fn apply(&self, line: usize) -> usize { if line > self.after_line { line.saturating_add_signed(self.delta) } else { line } }
+}
+/// Set when the last edit only touched trivia (whitespace and comments): the cached AST was
+/// returned as is, so its positions predate the shift, while the cached diagnostics and
+/// top-level spans were moved. Cleared by the next edit that changes tokens.
+pub static LAST_TRIVIA_SHIFT: Lazy<Mutex<Option<LineShift>>> = Lazy::new(|| Mutex::new(None));
+
+/// Circuits of top-level items for [`quantum_circuit_cached`], so the GUI's circuit preview
+/// only re-extracts the items an edit touched.
+#[derive(Debug, Clone, Default)]
+pub struct QuantumCircuitCache {
+    /// Keyed by item fingerprint and the classical bits measured before the item; holds
+    /// the items of the last extraction only.
+    pub entries: HashMap<(u64, Vec<String>), SubCircuit>,
+    /// Fingerprint per top-level item of the tree last extracted, and that tree's generation.
+    pub fingerprints: Vec<u64>,
+    pub generation: Option<u64>,
+    /// Items with quantum code reused or re-extracted; classical items are not counted.
+    pub hits: u64,
+    pub misses: u64,
+}
+pub static QUANTUM_CIRCUIT_CACHE: Lazy<Mutex<QuantumCircuitCache>> = Lazy::new(|| Mutex::new(QuantumCircuitCache::default()));
+
 // Call graph + variable dependency metrics (global, for inspection & incremental invalidation stats)
 #[derive(Debug, Default, Clone)]
 pub struct CallGraphMetrics {
@@ -51,6 +111,8 @@ pub struct CallGraphMetrics {
     pub edges: usize,
     pub reinfer_events: usize,
     pub variable_edges: usize, // variable -> function or function -> variable
+    pub fast_literal_patch: usize, // edits applied inside a string/comment without reparsing
+    pub trivia_fast_path: usize, // whitespace/comment-only edits served from the cached AST
 }
 pub static CALL_GRAPH_METRICS: Lazy<Mutex<CallGraphMetrics>> = Lazy::new(|| Mutex::new(CallGraphMetrics::default()));
 
@@ -64,27 +126,36 @@ pub static DEEP_PROPAGATION: AtomicBool = AtomicBool::new(false);
 // Per-function inference timing metrics (index-based; names resolved on query)
 // ema_ns tracks an exponential moving average (recent-weighted) of inference duration.
 #[derive(Debug, Clone)]
-pub struct FunctionInferenceMetric { pub total_ns: u128, pub runs: u64, pub last_ns: u128, pub ema_ns: u128, pub window: VecDeque<u128>, pub last_run_epoch_ms: u64 }
-impl Default for FunctionInferenceMetric { fn default() -> Self { Self { total_ns:0, runs:0, last_ns:0, ema_ns:0, window:VecDeque::new(), last_run_epoch_ms:0 } } }
+pub struct FunctionInferenceMetric { pub total_ns: u128, pub runs: u64, pub last_ns: u128, pub ema_ns: u128, pub window: VecDeque<u128>, pub last_run_epoch_ms: u64, pub site: Option<FunctionSite> }
+impl Default for FunctionInferenceMetric { fn default() -> Self { Self { total_ns:0, runs:0, last_ns:0, ema_ns:0, window:VecDeque::new(), last_run_epoch_ms:0, site:None } } }
+/// Where a timed function is defined; `file` is only known when the caller tracks one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionSite { pub name: String, pub file: Option<String>, pub line: usize }
 pub static FUNCTION_METRICS: Lazy<Mutex<HashMap<usize, FunctionInferenceMetric>>> = Lazy::new(|| Mutex::new(HashMap::new()));
 static LAST_PERSIST: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
 const PERSIST_DEBOUNCE: Duration = Duration::from_millis(500);
 // Runtime configurable EMA alpha (1..=100) via env AEONMI_EMA_ALPHA (default 20)
 pub static EMA_ALPHA_RUNTIME: once_cell::sync::Lazy<std::sync::atomic::AtomicU64> = once_cell::sync::Lazy::new(|| {
-    let v = std::env::var("AEONMI_EMA_ALPHA").ok().and_then(|s| s.parse::<u64>().ok()).filter(|v| (1..=100).contains(v)).unwrap_or(20);
+    let v = std::env::var("AEONMI_EMA_ALPHA").ok().and_then(|s| s.parse::<u64>().ok()).filter(|v| EMA_ALPHA_RANGE.contains(v)).unwrap_or(20);
     std::sync::atomic::AtomicU64::new(v)
 });
 // Rolling window capacity via env AEONMI_METRICS_WINDOW (default 16, min 4, max 256)
 pub static WINDOW_CAP_RUNTIME: once_cell::sync::Lazy<std::sync::atomic::AtomicUsize> = once_cell::sync::Lazy::new(|| {
-    let v = std::env::var("AEONMI_METRICS_WINDOW").ok().and_then(|s| s.parse::<usize>().ok()).filter(|v| *v>=4 && *v<=256).unwrap_or(16);
+    let v = std::env::var("AEONMI_METRICS_WINDOW").ok().and_then(|s| s.parse::<usize>().ok()).filter(|v| WINDOW_CAP_RANGE.contains(v)).unwrap_or(16);
     std::sync::atomic::AtomicUsize::new(v)
 });
 static SESSION_START_EPOCH_MS: once_cell::sync::Lazy<u64> = once_cell::sync::Lazy::new(|| current_epoch_ms());
 
 fn current_epoch_ms() -> u64 { use std::time::{SystemTime, UNIX_EPOCH}; SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0) }
 pub fn session_start_epoch_ms() -> u64 { *SESSION_START_EPOCH_MS }
-pub fn set_ema_alpha(pct: u64) { if (1..=100).contains(&pct) { EMA_ALPHA_RUNTIME.store(pct, Ordering::Relaxed); } }
-pub fn set_window_capacity(n: usize) { if n>=4 && n<=256 { WINDOW_CAP_RUNTIME.store(n, Ordering::Relaxed); } }
+pub const EMA_ALPHA_RANGE: std::ops::RangeInclusive<u64> = 1..=100;
+pub const WINDOW_CAP_RANGE: std::ops::RangeInclusive<usize> = 4..=256;
+pub const HISTORY_CAP_RANGE: std::ops::RangeInclusive<usize> = 8..=256;
+fn check_range<T: PartialOrd + std::fmt::Display>(knob: &str, v: T, range: &std::ops::RangeInclusive<T>) -> Result<(), String> {
+    if range.contains(&v) { Ok(()) } else { Err(format!("{knob} must be between {} and {} (got {v})", range.start(), range.end())) }
+}
+pub fn set_ema_alpha(pct: u64) -> Result<(), String> { check_range("ema_alpha", pct, &EMA_ALPHA_RANGE)?; EMA_ALPHA_RUNTIME.store(pct, Ordering::Relaxed); Ok(()) }
+pub fn set_window_capacity(n: usize) -> Result<(), String> { check_range("window", n, &WINDOW_CAP_RANGE)?; WINDOW_CAP_RUNTIME.store(n, Ordering::Relaxed); Ok(()) }
 
 // Partial vs estimated full inference savings metrics with recent window history
 #[derive(Debug, Clone)]
@@ -96,8 +167,44 @@ impl SavingsMetrics { pub fn push_sample(&mut self, partial: u128, est_full: u12
  self.window_partial_ns = self.window_partial_ns.saturating_sub(old.partial_ns); self.window_est_full_ns = self.window_est_full_ns.saturating_sub(old.estimated_full_ns); }
  } self.history.push_back(sample); } }
 pub static SAVINGS_METRICS: Lazy<Mutex<SavingsMetrics>> = Lazy::new(|| Mutex::new(SavingsMetrics::default()));
+
+/// A cache [`lock_cache`] cleared because a panic poisoned its lock.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct CacheReset { pub cache: &'static str, pub epoch_ms: u64 }
+/// What [`cache_health`] reports: `healthy` until some cache had to be reset.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct CacheHealth { pub healthy: bool, pub resets: Vec<CacheReset> }
+/// Resets remembered for [`cache_health`], most recent last.
+const RESET_HISTORY: usize = 16;
+static CACHE_RESETS: Mutex<Vec<CacheReset>> = Mutex::new(Vec::new());
+
+/// Lock one of the caches above. A panic while one is held (say inside `TypeContext`)
+/// poisons it; rather than failing every later caller, and with them every GUI command
+/// until restart, the cache is cleared, the poison dropped and the reset logged and
+/// recorded for [`cache_health`].
+pub fn lock_cache<'a, T: Default>(name: &'static str, cache: &'a Mutex<T>) -> MutexGuard<'a, T> {
+    match cache.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => {
+            let mut guard = poisoned.into_inner();
+            *guard = T::default();
+            cache.clear_poison();
+            eprintln!("warning: {name} cache was reset after a panic");
+            let mut resets = CACHE_RESETS.lock().unwrap_or_else(|e| e.into_inner());
+            if resets.len() == RESET_HISTORY { resets.remove(0); }
+            resets.push(CacheReset { cache: name, epoch_ms: current_epoch_ms() });
+            guard
+        }
+    }
+}
+
+/// Caches reset after a panic in this process (the last [`RESET_HISTORY`]).
+pub fn cache_health() -> CacheHealth {
+    let resets = CACHE_RESETS.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    CacheHealth { healthy: resets.is_empty(), resets }
+}
 #[allow(dead_code)]
-pub fn record_savings(partial_ns: u128, estimated_full_ns: u128) { if partial_ns == 0 || estimated_full_ns == 0 { return; } if let Ok(mut sm) = SAVINGS_METRICS.lock() { sm.push_sample(partial_ns, estimated_full_ns); } }
+pub fn record_savings(partial_ns: u128, estimated_full_ns: u128) { if partial_ns == 0 || estimated_full_ns == 0 { return; } lock_cache("savings", &SAVINGS_METRICS).push_sample(partial_ns, estimated_full_ns); }
 /// Back-compat wrapper (tests expect this name). Records a partial inference duration and
 /// the estimated full duration. Ignores samples where either is zero or estimated is less
 /// than partial (invalid / inverted measurement).
@@ -105,20 +212,26 @@ pub fn record_partial_savings(partial_ns: u128, estimated_full_ns: u128) {
     if partial_ns == 0 || estimated_full_ns == 0 || estimated_full_ns < partial_ns { return; }
     record_savings(partial_ns, estimated_full_ns);
 }
-pub fn set_history_cap(n: usize) { if n>=8 && n<=256 { if let Ok(mut sm)=SAVINGS_METRICS.lock() { sm.history_cap = n; while sm.history.len()>sm.history_cap { if let Some(old)=sm.history.pop_front() { sm.window_partial_ns = sm.window_partial_ns.saturating_sub(old.partial_ns); sm.window_est_full_ns = sm.window_est_full_ns.saturating_sub(old.estimated_full_ns); } } } } }
+pub fn set_history_cap(n: usize) -> Result<(), String> { check_range("history_cap", n, &HISTORY_CAP_RANGE)?; { let mut sm = lock_cache("savings", &SAVINGS_METRICS); sm.history_cap = n; while sm.history.len()>sm.history_cap { if let Some(old)=sm.history.pop_front() { sm.window_partial_ns = sm.window_partial_ns.saturating_sub(old.partial_ns); sm.window_est_full_ns = sm.window_est_full_ns.saturating_sub(old.estimated_full_ns); } } } Ok(()) }
 
 #[allow(dead_code)]
 pub fn record_function_infer(idx: usize, dur: u128) {
-    if let Ok(mut m) = FUNCTION_METRICS.lock() {
-        let entry = m.entry(idx).or_insert_with(FunctionInferenceMetric::default);
-        entry.total_ns += dur; entry.runs += 1; entry.last_ns = dur;
+    // Pin the session start first: it is lazy, and a start taken after this sample would prune it as stale.
+    let _ = session_start_epoch_ms();
+    let mut m = lock_cache("function_metrics", &FUNCTION_METRICS);
+    let entry = m.entry(idx).or_default();
+    entry.total_ns += dur; entry.runs += 1; entry.last_ns = dur;
     entry.last_run_epoch_ms = current_epoch_ms();
     let alpha = EMA_ALPHA_RUNTIME.load(Ordering::Relaxed) as u128;
     if entry.runs == 1 { entry.ema_ns = dur; } else { entry.ema_ns = (entry.ema_ns * (100 - alpha) + dur * alpha) / 100; }
     let cap = WINDOW_CAP_RUNTIME.load(Ordering::Relaxed);
     if entry.window.len()==cap { entry.window.pop_front(); }
     entry.window.push_back(dur);
-    }
+}
+
+/// Attach (or refresh) the name and definition site of the function timed under `idx`.
+pub fn record_function_site(idx: usize, name: &str, file: Option<&str>, line: usize) {
+    lock_cache("function_metrics", &FUNCTION_METRICS).entry(idx).or_default().site = Some(FunctionSite { name: name.to_string(), file: file.map(str::to_string), line });
 }
 
 pub fn set_deep_propagation(v: bool) { DEEP_PROPAGATION.store(v, Ordering::Relaxed); }
@@ -131,21 +244,31 @@ fn metrics_file_path() -> std::path::PathBuf {
     base.join("aeonmi_metrics.json")
 }
 const METRICS_FILE: &str = "aeonmi_metrics.json"; // kept for legacy; actual path computed dynamically
-const METRICS_VERSION: u32 = 6; // bumped for rolling window & history
+pub const METRICS_VERSION: u32 = 7; // bumped for the quantum circuit cache counters
+/// Oldest metrics file version [`migrate_metrics`] can read.
+pub const OLDEST_METRICS_VERSION: u32 = 3;
+/// Value of the `schema` field, naming the format next to its `version`.
+pub const METRICS_SCHEMA: &str = "aeonmi.metrics";
 
 pub fn metrics_file_location() -> std::path::PathBuf { metrics_file_path() }
 
+// Sorted by variable and then index so the persisted file diffs cleanly.
+fn sorted_deps(deps: &HashMap<String, HashSet<usize>>) -> std::collections::BTreeMap<&str, Vec<usize>> {
+    deps.iter().map(|(k, set)| { let mut idx: Vec<usize> = set.iter().copied().collect(); idx.sort_unstable(); (k.as_str(), idx) }).collect()
+}
+
 pub fn build_metrics_json() -> serde_json::Value {
-    let m = CALL_GRAPH_METRICS.lock().ok().map(|g| g.clone()).unwrap_or_default();
-    let v = VAR_DEPS.lock().ok().map(|g| g.clone()).unwrap_or_default();
-    let fm = FUNCTION_METRICS.lock().ok().map(|g| g.clone()).unwrap_or_default();
-    let sm = SAVINGS_METRICS.lock().ok().map(|g| g.clone()).unwrap_or_default();
+    let m = lock_cache("call_graph", &CALL_GRAPH_METRICS).clone();
+    let v = lock_cache("var_deps", &VAR_DEPS).clone();
+    let fm = lock_cache("function_metrics", &FUNCTION_METRICS).clone();
+    let sm = lock_cache("savings", &SAVINGS_METRICS).clone();
+    let qc = { let c = lock_cache("quantum_circuit", &QUANTUM_CIRCUIT_CACHE); (c.hits, c.misses) };
     let session_start = session_start_epoch_ms();
     let mut pruned = 0usize;
-    let function_metrics: HashMap<String, serde_json::Value> = fm.iter().filter_map(|(idx, fm)| {
+    let function_metrics: std::collections::BTreeMap<String, serde_json::Value> = fm.iter().filter_map(|(idx, fm)| {
         if fm.last_run_epoch_ms>0 && fm.last_run_epoch_ms < session_start { pruned +=1; return None; }
         let window_avg_ns = if !fm.window.is_empty() { fm.window.iter().copied().sum::<u128>() / fm.window.len() as u128 } else { 0 };        
-        Some((idx.to_string(), serde_json::json!({
+        let mut entry = serde_json::json!({
             "runs": fm.runs,
             "total_ns": fm.total_ns,
             "last_ns": fm.last_ns,
@@ -153,7 +276,9 @@ pub fn build_metrics_json() -> serde_json::Value {
             "ema_ns": fm.ema_ns,
             "window_avg_ns": window_avg_ns,
             "last_run_epoch_ms": fm.last_run_epoch_ms
-        })))
+        });
+        if let Some(site) = &fm.site { entry["name"] = site.name.clone().into(); entry["file"] = site.file.clone().into(); entry["line"] = site.line.into(); }
+        Some((idx.to_string(), entry))
     }).collect();
     let savings_pct = if sm.cumulative_estimated_full_ns>0 { (sm.cumulative_savings_ns as f64 / sm.cumulative_estimated_full_ns as f64) * 100.0 } else { 0.0 };
     let partial_pct = if sm.cumulative_estimated_full_ns>0 { (sm.cumulative_partial_ns as f64 / sm.cumulative_estimated_full_ns as f64) * 100.0 } else { 0.0 };
@@ -161,15 +286,17 @@ pub fn build_metrics_json() -> serde_json::Value {
     let ema_alpha = EMA_ALPHA_RUNTIME.load(Ordering::Relaxed);
     let window_cap = WINDOW_CAP_RUNTIME.load(Ordering::Relaxed);
     serde_json::json!({
+        "schema": METRICS_SCHEMA,
         "version": METRICS_VERSION,
-        "metrics": {"functions": m.functions, "edges": m.edges, "reinfer_events": m.reinfer_events, "variable_edges": m.variable_edges},
-        "varReads": v.reads.iter().map(|(k, set)| (k, set.iter().collect::<Vec<_>>())).collect::<std::collections::HashMap<_,_>>(),
-        "varWrites": v.writes.iter().map(|(k, set)| (k, set.iter().collect::<Vec<_>>())).collect::<std::collections::HashMap<_,_>>(),
+        "metrics": {"functions": m.functions, "edges": m.edges, "reinfer_events": m.reinfer_events, "variable_edges": m.variable_edges, "fast_literal_patch": m.fast_literal_patch, "trivia_fast_path": m.trivia_fast_path},
+        "varReads": sorted_deps(&v.reads),
+        "varWrites": sorted_deps(&v.writes),
         "functionMetrics": function_metrics,
         "functionMetricsPruned": pruned,
         "emaAlphaPct": ema_alpha,
         "windowCapacity": window_cap,
         "deepPropagation": get_deep_propagation(),
+        "quantumExtract": {"hits": qc.0, "misses": qc.1},
         "savings": {"cumulative_savings_ns": sm.cumulative_savings_ns, "cumulative_partial_ns": sm.cumulative_partial_ns, "cumulative_estimated_full_ns": sm.cumulative_estimated_full_ns, "cumulative_savings_pct": savings_pct, "cumulative_partial_pct": partial_pct, "recent_window_partial_ns": sm.window_partial_ns, "recent_window_estimated_full_ns": sm.window_est_full_ns, "recent_window_savings_pct": recent_window_savings_pct, "recent_samples": sm.history.iter().map(|s| serde_json::json!({"partial_ns": s.partial_ns, "estimated_full_ns": s.estimated_full_ns, "savings_ns": s.savings_ns})).collect::<Vec<_>>() }
     })
 }
@@ -185,13 +312,13 @@ pub fn ensure_metrics_file_exists() {
 pub fn persist_metrics() {
     // Debounce check
     {
-        if let Ok(mut last) = LAST_PERSIST.lock() {
-            let now = Instant::now();
-            if let Some(prev) = *last { if now.duration_since(prev) < PERSIST_DEBOUNCE { return; } }
-            *last = Some(now);
-        }
+        let mut last = lock_cache("persist_clock", &LAST_PERSIST);
+        let now = Instant::now();
+        if let Some(prev) = *last { if now.duration_since(prev) < PERSIST_DEBOUNCE { return; } }
+        *last = Some(now);
     }
-    if CALL_GRAPH_METRICS.lock().is_ok() { // cheap check; build JSON anyway
+    if NEWER_METRICS_ON_DISK.load(Ordering::Relaxed) { return; }
+    {
         let json = build_metrics_json();
     let path = metrics_file_path();
     if let Err(e) = std::fs::write(&path, serde_json::to_string_pretty(&json).unwrap_or_default()) { eprintln!("persist_metrics write error: {e}"); }
@@ -200,7 +327,8 @@ pub fn persist_metrics() {
 
 /// Force persistence ignoring debounce (used by metrics-flush CLI)
 pub fn force_persist_metrics() {
-    if CALL_GRAPH_METRICS.lock().is_ok() {
+    if NEWER_METRICS_ON_DISK.load(Ordering::Relaxed) { return; }
+    {
         let json = build_metrics_json();
     let path = metrics_file_path();
     if let Err(e) = std::fs::write(&path, serde_json::to_string_pretty(&json).unwrap_or_default()) { eprintln!("force_persist_metrics write error: {e}"); }
@@ -227,110 +355,487 @@ pub fn compute_transitive_callers(changed: usize, rev: &Vec<Vec<usize>>, deep: b
     set
 }
 
+/// Why a metrics file could not be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetricsLoadError {
+    /// Written by a newer build; left untouched rather than guessed at.
+    Newer { found: u64 },
+    /// Not JSON, not a metrics document, or older than any version we can migrate.
+    Invalid(String),
+}
+impl std::fmt::Display for MetricsLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Newer { found } => write!(f, "metrics file has version {found}, newer than this build supports (version {METRICS_VERSION}); upgrade aeonmi or move the file aside"),
+            Self::Invalid(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+impl std::error::Error for MetricsLoadError {}
+
+// Set when the file on disk is newer than we understand, so persisting does not clobber it.
+static NEWER_METRICS_ON_DISK: AtomicBool = AtomicBool::new(false);
+
+type JsonMap = serde_json::Map<String, serde_json::Value>;
+fn u64_at(obj: &JsonMap, key: &str) -> u64 { obj.get(key).and_then(|v| v.as_u64()).unwrap_or(0) }
+fn pct_of(part: u64, whole: u64) -> f64 { if whole>0 { (part as f64 / whole as f64) * 100.0 } else { 0.0 } }
+fn for_each_function(obj: &mut JsonMap, mut f: impl FnMut(&mut JsonMap)) {
+    if let Some(fm) = obj.get_mut("functionMetrics").and_then(|v| v.as_object_mut()) { for entry in fm.values_mut().filter_map(|e| e.as_object_mut()) { f(entry); } }
+}
+
+// v4 added the per-function EMA and its alpha; seed the EMA with the last sample, as a first run does.
+fn migrate_v3_to_v4(obj: &mut JsonMap) {
+    for_each_function(obj, |f| { let last = u64_at(f, "last_ns"); f.entry("ema_ns").or_insert(last.into()); });
+    obj.entry("emaAlphaPct").or_insert(20.into());
+    obj.insert("version".into(), 4.into());
+}
+
+// v5 added savings percentages (derived from the ns totals) and the recent sample list.
+fn migrate_v4_to_v5(obj: &mut JsonMap) {
+    if let Some(sv) = obj.get_mut("savings").and_then(|v| v.as_object_mut()) {
+        let full = u64_at(sv, "cumulative_estimated_full_ns");
+        let (saved, partial) = (u64_at(sv, "cumulative_savings_ns"), u64_at(sv, "cumulative_partial_ns"));
+        sv.entry("cumulative_savings_pct").or_insert(pct_of(saved, full).into());
+        sv.entry("cumulative_partial_pct").or_insert(pct_of(partial, full).into());
+        sv.entry("recent_samples").or_insert(serde_json::json!([]));
+    }
+    obj.insert("version".into(), 5.into());
+}
+
+// v6 added the rolling window and literal-patch counter. An older file has no window, so its
+// window is the whole history: avg_ns per function and every recent sample for savings.
+fn migrate_v5_to_v6(obj: &mut JsonMap) {
+    for_each_function(obj, |f| {
+        let avg = u64_at(f, "avg_ns");
+        f.entry("window_avg_ns").or_insert(avg.into());
+        f.entry("last_run_epoch_ms").or_insert(0.into());
+    });
+    if let Some(m) = obj.get_mut("metrics").and_then(|v| v.as_object_mut()) { m.entry("fast_literal_patch").or_insert(0.into()); }
+    obj.entry("functionMetricsPruned").or_insert(0.into());
+    obj.entry("windowCapacity").or_insert(16.into());
+    if let Some(sv) = obj.get_mut("savings").and_then(|v| v.as_object_mut()) {
+        let samples: Vec<JsonMap> = sv.get("recent_samples").and_then(|v| v.as_array()).map(|a| a.iter().filter_map(|s| s.as_object().cloned()).collect()).unwrap_or_default();
+        let partial: u64 = samples.iter().map(|s| u64_at(s, "partial_ns")).sum();
+        let full: u64 = samples.iter().map(|s| u64_at(s, "estimated_full_ns")).sum();
+        let saved: u64 = samples.iter().map(|s| u64_at(s, "savings_ns")).sum();
+        sv.entry("recent_window_partial_ns").or_insert(partial.into());
+        sv.entry("recent_window_estimated_full_ns").or_insert(full.into());
+        sv.entry("recent_window_savings_pct").or_insert(pct_of(saved, full).into());
+    }
+    obj.insert("version".into(), 6.into());
+}
+
+// v7 added the circuit cache counters; nothing was cached before.
+fn migrate_v6_to_v7(obj: &mut JsonMap) {
+    obj.entry("quantumExtract").or_insert(serde_json::json!({"hits": 0, "misses": 0}));
+    obj.insert("version".into(), 7.into());
+}
+
+/// Bring a parsed metrics document up to [`METRICS_VERSION`], one version step at a time.
+/// Documents from a newer build are refused instead of being read best-effort.
+pub fn migrate_metrics(mut val: serde_json::Value) -> Result<serde_json::Value, MetricsLoadError> {
+    let Some(obj) = val.as_object_mut() else { return Err(MetricsLoadError::Invalid("metrics file is not a JSON object".into())); };
+    if let Some(schema) = obj.get("schema") {
+        if schema.as_str() != Some(METRICS_SCHEMA) { return Err(MetricsLoadError::Invalid(format!("unknown metrics schema {schema} (expected \"{METRICS_SCHEMA}\")"))); }
+    }
+    let version = obj.get("version").and_then(|v| v.as_u64()).ok_or_else(|| MetricsLoadError::Invalid("metrics file has no numeric \"version\" field".into()))?;
+    if version > METRICS_VERSION as u64 { return Err(MetricsLoadError::Newer { found: version }); }
+    if version < OLDEST_METRICS_VERSION as u64 { return Err(MetricsLoadError::Invalid(format!("metrics file version {version} predates the oldest supported version ({OLDEST_METRICS_VERSION})"))); }
+    const STEPS: [fn(&mut JsonMap); 4] = [migrate_v3_to_v4, migrate_v4_to_v5, migrate_v5_to_v6, migrate_v6_to_v7];
+    for step in &STEPS[(version - OLDEST_METRICS_VERSION as u64) as usize..] { step(obj); }
+    obj.insert("schema".into(), METRICS_SCHEMA.into());
+    Ok(val)
+}
+
+// Function indices were written as numbers, and as strings by some older builds; accept both.
+fn index_set(arr: &serde_json::Value) -> HashSet<usize> {
+    arr.as_array().map(|a| a.iter().filter_map(|v| v.as_u64().map(|n| n as usize).or_else(|| v.as_str().and_then(|s| s.parse().ok()))).collect()).unwrap_or_default()
+}
+
+/// Populate the global metrics from a document already migrated to [`METRICS_VERSION`].
+fn apply_metrics(val: &serde_json::Value) {
+    if let Some(mo) = val.get("metrics") { let mut m = lock_cache("call_graph", &CALL_GRAPH_METRICS);
+        m.functions = mo.get("functions").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+        m.edges = mo.get("edges").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+        m.reinfer_events = mo.get("reinfer_events").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+        m.variable_edges = mo.get("variable_edges").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+        m.fast_literal_patch = mo.get("fast_literal_patch").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+        m.trivia_fast_path = mo.get("trivia_fast_path").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+    }
+    {
+        let mut vd = lock_cache("var_deps", &VAR_DEPS);
+        if let Some(obj)=val.get("varReads").and_then(|v| v.as_object()) { for (k, arr) in obj { vd.reads.insert(k.clone(), index_set(arr)); } }
+        if let Some(obj)=val.get("varWrites").and_then(|v| v.as_object()) { for (k, arr) in obj { vd.writes.insert(k.clone(), index_set(arr)); } }
+    }
+    if let Some(fm) = val.get("functionMetrics") { let mut map = lock_cache("function_metrics", &FUNCTION_METRICS); if let Some(obj)=fm.as_object() { for (k,v) in obj { if let Ok(idx)=k.parse::<usize>() { let mut metric=FunctionInferenceMetric::default(); metric.runs=v.get("runs").and_then(|x| x.as_u64()).unwrap_or(0); metric.total_ns=v.get("total_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128; metric.last_ns=v.get("last_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128; metric.ema_ns=v.get("ema_ns").and_then(|x| x.as_u64()).unwrap_or(metric.last_ns as u64) as u128; metric.site=v.get("name").and_then(|x| x.as_str()).map(|n| FunctionSite { name: n.to_string(), file: v.get("file").and_then(|x| x.as_str()).map(str::to_string), line: v.get("line").and_then(|x| x.as_u64()).unwrap_or(0) as usize }); map.insert(idx, metric); } } } }
+    if let Some(dp)=val.get("deepPropagation") { if let Some(b)=dp.as_bool() { set_deep_propagation(b); } }
+    if let Some(qe) = val.get("quantumExtract") { let mut c = lock_cache("quantum_circuit", &QUANTUM_CIRCUIT_CACHE); c.hits = qe.get("hits").and_then(|x| x.as_u64()).unwrap_or(0); c.misses = qe.get("misses").and_then(|x| x.as_u64()).unwrap_or(0); }
+    if let Some(sv)=val.get("savings") { let mut sm = lock_cache("savings", &SAVINGS_METRICS); sm.cumulative_savings_ns = sv.get("cumulative_savings_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128; sm.cumulative_partial_ns = sv.get("cumulative_partial_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128; sm.cumulative_estimated_full_ns = sv.get("cumulative_estimated_full_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128; if let Some(arr)=sv.get("recent_samples").and_then(|x| x.as_array()) { for s in arr { let p = s.get("partial_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128; let e = s.get("estimated_full_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128; if p>0 && e>0 { sm.push_sample(p,e); } } } }
+}
+
+/// Load (migrating as needed) the metrics file at `path`; a missing file is not an error.
+pub fn load_metrics_from(path: &std::path::Path) -> Result<(), MetricsLoadError> {
+    let Ok(data) = std::fs::read_to_string(path) else { return Ok(()); };
+    let val = serde_json::from_str::<serde_json::Value>(&data).map_err(|e| MetricsLoadError::Invalid(format!("invalid JSON: {e}")))?;
+    apply_metrics(&migrate_metrics(val)?);
+    Ok(())
+}
+
 pub fn load_metrics() {
     let path = metrics_file_path();
-    if let Ok(data) = std::fs::read_to_string(path) {
-        if let Ok(val) = serde_json::from_str::<serde_json::Value>(&data) {
-            if let Some(mo) = val.get("metrics") { if let Ok(mut m) = CALL_GRAPH_METRICS.lock() {
-                m.functions = mo.get("functions").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
-                m.edges = mo.get("edges").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
-                m.reinfer_events = mo.get("reinfer_events").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
-                m.variable_edges = mo.get("variable_edges").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
-            }}
-            if let Some(fr) = val.get("varReads") { if let Ok(mut vd)=VAR_DEPS.lock() { if let Some(obj)=fr.as_object() { for (k, arr) in obj { let mut set: HashSet<usize> = HashSet::new(); if let Some(a)=arr.as_array() { for v in a { if let Some(s)=v.as_str() { if let Ok(idx)=s.parse::<usize>() { set.insert(idx); } } } } vd.reads.insert(k.clone(), set); } } } }
-            if let Some(fw) = val.get("varWrites") { if let Ok(mut vd)=VAR_DEPS.lock() { if let Some(obj)=fw.as_object() { for (k, arr) in obj { let mut set: HashSet<usize> = HashSet::new(); if let Some(a)=arr.as_array() { for v in a { if let Some(s)=v.as_str() { if let Ok(idx)=s.parse::<usize>() { set.insert(idx); } } } } vd.writes.insert(k.clone(), set); } } } }
-                if let Some(fm) = val.get("functionMetrics") { if let Ok(mut map)=FUNCTION_METRICS.lock() { if let Some(obj)=fm.as_object() { for (k,v) in obj { if let Ok(idx)=k.parse::<usize>() { let mut metric=FunctionInferenceMetric::default(); metric.runs=v.get("runs").and_then(|x| x.as_u64()).unwrap_or(0); metric.total_ns=v.get("total_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128; metric.last_ns=v.get("last_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128; metric.ema_ns=v.get("ema_ns").and_then(|x| x.as_u64()).unwrap_or(metric.last_ns as u64) as u128; map.insert(idx, metric); } } } } }
-            if let Some(dp)=val.get("deepPropagation") { if let Some(b)=dp.as_bool() { set_deep_propagation(b); } }
-            if let Some(sv)=val.get("savings") { if let Ok(mut sm)=SAVINGS_METRICS.lock() { sm.cumulative_savings_ns = sv.get("cumulative_savings_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128; sm.cumulative_partial_ns = sv.get("cumulative_partial_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128; sm.cumulative_estimated_full_ns = sv.get("cumulative_estimated_full_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128; if let Some(arr)=sv.get("recent_samples").and_then(|x| x.as_array()) { for s in arr { let p = s.get("partial_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128; let e = s.get("estimated_full_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128; if p>0 && e>0 { sm.push_sample(p,e); } } } } }
+    match load_metrics_from(&path) {
+        Ok(()) => {}
+        Err(e) => {
+            if matches!(e, MetricsLoadError::Newer { .. }) { NEWER_METRICS_ON_DISK.store(true, Ordering::Relaxed); }
+            eprintln!("metrics: {}: {e}", path.display());
         }
     }
 }
 
 #[allow(dead_code)]
-pub fn reset_metrics_session() { if let Ok(mut m)=CALL_GRAPH_METRICS.lock() { m.reinfer_events = 0; } }
+pub fn reset_metrics_session() { lock_cache("call_graph", &CALL_GRAPH_METRICS).reinfer_events = 0; }
+
+fn clear_metrics_state() {
+    *lock_cache("call_graph", &CALL_GRAPH_METRICS) = CallGraphMetrics::default();
+    *lock_cache("var_deps", &VAR_DEPS) = VarDeps::default();
+    lock_cache("function_metrics", &FUNCTION_METRICS).clear();
+    *lock_cache("savings", &SAVINGS_METRICS) = SavingsMetrics::default();
+    { let mut c = lock_cache("quantum_circuit", &QUANTUM_CIRCUIT_CACHE); c.hits = 0; c.misses = 0; }
+}
 
 #[allow(dead_code)]
 pub fn reset_metrics_full() {
-    if let Ok(mut cg)=CALL_GRAPH_METRICS.lock() { *cg = CallGraphMetrics::default(); }
-    if let Ok(mut vd)=VAR_DEPS.lock() { *vd = VarDeps::default(); }
-    if let Ok(mut fm)=FUNCTION_METRICS.lock() { fm.clear(); }
-    if let Ok(mut sm)=SAVINGS_METRICS.lock() { *sm = SavingsMetrics::default(); }
+    clear_metrics_state();
     persist_metrics();
 }
 
-pub fn reset_runtime_metrics_config() { set_ema_alpha(20); set_window_capacity(16); set_history_cap(32); }
+/// Replace the in-memory metrics with the file at `path` without persisting anything.
+/// Loading on top of live state would append the file's savings samples a second time.
+pub fn reload_metrics_from(path: &std::path::Path) -> Result<(), MetricsLoadError> {
+    clear_metrics_state();
+    load_metrics_from(path)
+}
+
+/// Function metrics as CSV (`index,runs,ema_ns,avg_ns,last_ns,total_ns`, slowest EMA first),
+/// or `None` when nothing has been recorded.
+pub fn function_metrics_csv() -> Option<String> {
+    let json = build_metrics_json();
+    let obj = json.get("functionMetrics").and_then(|v| v.as_object())?;
+    let mut rows: Vec<(usize, u64, u128, u128, u128, u128)> = Vec::new();
+    for (k, v) in obj {
+        if let Ok(idx) = k.parse::<usize>() {
+            let runs = v.get("runs").and_then(|x| x.as_u64()).unwrap_or(0);
+            let ema = v.get("ema_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128;
+            let avg = v.get("avg_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128;
+            let last = v.get("last_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128;
+            let total = v.get("total_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128;
+            rows.push((idx, runs, ema, avg, last, total));
+        }
+    }
+    rows.sort_by_key(|r| std::cmp::Reverse(r.2));
+    let mut csv = String::from("index,runs,ema_ns,avg_ns,last_ns,total_ns\n");
+    for (i, r, e, a, l, t) in rows {
+        csv.push_str(&format!("{i},{r},{e},{a},{l},{t}\n"));
+    }
+    Some(csv)
+}
+
+pub fn reset_runtime_metrics_config() { let _ = MetricsRuntimeConfig::default().apply(); }
+
+/// The runtime metrics knobs as one value: read back with [`MetricsRuntimeConfig::current`],
+/// validated and applied with [`MetricsRuntimeConfig::apply`], and stored in string preferences
+/// (the GUI's `prefs.json`) under `metrics.*` keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct MetricsRuntimeConfig { pub ema_alpha: u64, pub window: usize, pub history_cap: usize }
+impl Default for MetricsRuntimeConfig { fn default() -> Self { Self { ema_alpha: 20, window: 16, history_cap: 32 } } }
+impl MetricsRuntimeConfig {
+    pub const PREF_EMA_ALPHA: &'static str = "metrics.ema_alpha";
+    pub const PREF_WINDOW: &'static str = "metrics.window";
+    pub const PREF_HISTORY_CAP: &'static str = "metrics.history_cap";
+
+    pub fn current() -> Self {
+        let history_cap = lock_cache("savings", &SAVINGS_METRICS).history_cap;
+        Self { ema_alpha: EMA_ALPHA_RUNTIME.load(Ordering::Relaxed), window: WINDOW_CAP_RUNTIME.load(Ordering::Relaxed), history_cap }
+    }
+    /// Every out-of-range knob, one message each; empty when the config is valid.
+    pub fn validate(&self) -> Vec<String> {
+        [check_range("ema_alpha", self.ema_alpha, &EMA_ALPHA_RANGE), check_range("window", self.window, &WINDOW_CAP_RANGE), check_range("history_cap", self.history_cap, &HISTORY_CAP_RANGE)]
+            .into_iter().filter_map(Result::err).collect()
+    }
+    /// Apply all knobs, or none of them when any is out of range.
+    pub fn apply(&self) -> Result<(), Vec<String>> {
+        let errors = self.validate();
+        if !errors.is_empty() { return Err(errors); }
+        let _ = set_ema_alpha(self.ema_alpha); let _ = set_window_capacity(self.window); let _ = set_history_cap(self.history_cap);
+        Ok(())
+    }
+    /// Read the knobs stored by [`Self::write_prefs`]; missing or unparsable keys keep the default.
+    pub fn from_prefs(prefs: &HashMap<String, String>) -> Self {
+        let d = Self::default();
+        let get = |k: &str| prefs.get(k).and_then(|v| v.trim().parse::<u64>().ok());
+        Self {
+            ema_alpha: get(Self::PREF_EMA_ALPHA).unwrap_or(d.ema_alpha),
+            window: get(Self::PREF_WINDOW).map(|v| v as usize).unwrap_or(d.window),
+            history_cap: get(Self::PREF_HISTORY_CAP).map(|v| v as usize).unwrap_or(d.history_cap),
+        }
+    }
+    pub fn write_prefs(&self, prefs: &mut HashMap<String, String>) {
+        prefs.insert(Self::PREF_EMA_ALPHA.into(), self.ema_alpha.to_string());
+        prefs.insert(Self::PREF_WINDOW.into(), self.window.to_string());
+        prefs.insert(Self::PREF_HISTORY_CAP.into(), self.history_cap.to_string());
+    }
+}
 
 #[allow(dead_code)]
 pub fn record_reinfer_event(count: usize) {
-    if let Ok(mut m) = CALL_GRAPH_METRICS.lock() { m.reinfer_events += count; }
+    lock_cache("call_graph", &CALL_GRAPH_METRICS).reinfer_events += count;
 }
 
 #[allow(dead_code)]
-pub fn snapshot_call_graph_metrics() -> CallGraphMetrics { CALL_GRAPH_METRICS.lock().unwrap().clone() }
+pub fn snapshot_call_graph_metrics() -> CallGraphMetrics { lock_cache("call_graph", &CALL_GRAPH_METRICS).clone() }
 
 // Compute variable dependency map (reads/writes) for all top-level functions in an AST.
 // This mirrors logic used in the GUI command for selective reinference.
 #[allow(dead_code)]
 pub fn compute_var_deps(ast: &ASTNode) -> VarDeps {
-    let mut var_reads: HashMap<String, HashSet<usize>> = HashMap::new();
-    let mut var_writes: HashMap<String, HashSet<usize>> = HashMap::new();
+    match ast { ASTNode::Program(items) => compute_var_deps_items(items), _ => VarDeps::default() }
+}
+
+/// Scope-aware variable deps over top-level items (indices are item positions).
+/// Params, `let`s and loop variables declared inside a function shadow top-level names,
+/// so only reads/writes that resolve outside the function produce edges.
+pub fn compute_var_deps_items(items: &[ASTNode]) -> VarDeps {
     use crate::core::ast::ASTNode as N;
-    fn walk(idx: usize, n: &N, reads: &mut HashMap<String, HashSet<usize>>, writes: &mut HashMap<String, HashSet<usize>>) {
+    struct Walk<'a> { idx: usize, scopes: Vec<HashSet<&'a str>>, deps: VarDeps }
+    impl<'a> Walk<'a> {
+        fn is_local(&self, name: &str) -> bool { self.scopes.iter().any(|s| s.contains(name)) }
+        fn declare(&mut self, name: &'a str) { if let Some(s) = self.scopes.last_mut() { s.insert(name); } }
+        fn read(&mut self, name: &str) { if !self.is_local(name) { self.deps.reads.entry(name.to_string()).or_default().insert(self.idx); } }
+        fn write(&mut self, name: &str) { if !self.is_local(name) { self.deps.writes.entry(name.to_string()).or_default().insert(self.idx); } }
+        fn scoped(&mut self, body: &'a [N], declared: impl IntoIterator<Item = &'a str>) {
+            self.scopes.push(declared.into_iter().collect());
+            for c in body { self.walk(c); }
+            self.scopes.pop();
+        }
+        fn walk(&mut self, n: &'a N) {
+            match n {
+                N::Assignment { name, value, .. } => { self.walk(value); self.write(name); },
+                N::VariableDecl { name, value, .. } => { self.walk(value); self.declare(name); },
+                N::DestructuringDecl { targets, values, .. } => {
+                    for v in values { self.walk(v); }
+                    for (name, ..) in targets.iter().flat_map(|t| t.names()) { self.declare(name); }
+                },
+                N::QubitDecl { name, .. } | N::QregDecl { name, .. } => self.declare(name),
+                N::Identifier(name) | N::IdentifierSpanned { name, .. } => self.read(name),
+                N::Function { name, params, body, .. } => { self.declare(name); self.scoped(body, params.iter().map(|p| p.name.as_str())); },
+                N::Block(b) => self.scoped(b, []),
+                N::If { condition, then_branch, else_branch } => { self.walk(condition); self.walk(then_branch); if let Some(e)=else_branch { self.walk(e); } },
+                N::While { condition, body } => { self.walk(condition); self.walk(body); },
+                N::For { init, condition, increment, body } => {
+                    // the loop variable lives in its own scope around the body
+                    self.scopes.push(HashSet::new());
+                    if let Some(i)=init { self.walk(i); } if let Some(c)=condition { self.walk(c); } if let Some(inc)=increment { self.walk(inc); } self.walk(body);
+                    self.scopes.pop();
+                },
+                N::ForIn { key, var, iterable, body, .. } => {
+                    self.walk(iterable);
+                    self.scopes.push(HashSet::new());
+                    for name in key.iter().chain([var]) { self.declare(name); }
+                    self.walk(body);
+                    self.scopes.pop();
+                },
+                N::BinaryExpr { left, right, .. } => { self.walk(left); self.walk(right); },
+                N::UnaryExpr { expr, .. } => self.walk(expr),
+                N::Index { target, index, .. } => { self.walk(target); self.walk(index); },
+                N::Member { target, .. } => self.walk(target),
+                N::Match { subject, arms, .. } => {
+                    self.walk(subject);
+                    for arm in arms { if let Some(p)=&arm.pattern { self.walk(p); } self.walk(&arm.body); }
+                },
+                N::Call { callee, args } => { self.walk(callee); for a in args { self.walk(a); } },
+                N::Return(e) | N::Log { expr: e, .. } => self.walk(e),
+                N::QuantumOp { qubits, .. } => { for q in qubits { self.walk(q); } },
+                N::ArrayLiteral { items, .. } => { for it in items { self.walk(it); } },
+                N::ObjectLiteral { fields, .. } => { for (_, v) in fields { self.walk(v); } },
+                _ => {}
+            }
+        }
+    }
+    let mut w = Walk { idx: 0, scopes: Vec::new(), deps: VarDeps::default() };
+    for (idx, node) in items.iter().enumerate() {
+        if let N::Function { params, body, .. } = node { w.idx = idx; w.scoped(body, params.iter().map(|p| p.name.as_str())); }
+    }
+    w.deps
+}
+
+/// Call edges between top-level functions: `calls[i]` lists the item indices function `i` calls.
+pub fn compute_call_edges(items: &[ASTNode]) -> Vec<Vec<usize>> {
+    use crate::core::ast::ASTNode as N;
+    fn scan(n: &N, map: &HashMap<&str, usize>, out: &mut Vec<usize>) {
         match n {
-            N::Assignment { name, value, .. } => { writes.entry(name.clone()).or_default().insert(idx); walk(idx, value, reads, writes); },
-            N::VariableDecl { name, value, .. } => { writes.entry(name.clone()).or_default().insert(idx); walk(idx, value, reads, writes); },
-            N::Identifier(name) | N::IdentifierSpanned { name, .. } => { reads.entry(name.clone()).or_default().insert(idx); },
-            N::Function { body, .. } => { for c in body { walk(idx, c, reads, writes); } },
-            N::Block(b) => { for c in b { walk(idx, c, reads, writes); } },
-            N::If { condition, then_branch, else_branch } => { walk(idx, condition, reads, writes); walk(idx, then_branch, reads, writes); if let Some(e)=else_branch { walk(idx, e, reads, writes); } },
-            N::While { condition, body } => { walk(idx, condition, reads, writes); walk(idx, body, reads, writes); },
-            N::For { init, condition, increment, body } => { if let Some(i)=init { walk(idx, i, reads, writes); } if let Some(c)=condition { walk(idx, c, reads, writes); } if let Some(inc)=increment { walk(idx, inc, reads, writes); } walk(idx, body, reads, writes); },
-            N::BinaryExpr { left, right, .. } => { walk(idx, left, reads, writes); walk(idx, right, reads, writes); },
-            N::UnaryExpr { expr, .. } => { walk(idx, expr, reads, writes); },
-            N::Call { callee, args } => { walk(idx, callee, reads, writes); for a in args { walk(idx, a, reads, writes); } },
-            N::Return(e) | N::Log(e) => { walk(idx, e, reads, writes); },
+            N::Call { callee, args } => {
+                if let N::Identifier(name) | N::IdentifierSpanned { name, .. } = &**callee { if let Some(&i)=map.get(name.as_str()) { if !out.contains(&i) { out.push(i); } } }
+                scan(callee, map, out); for a in args { scan(a, map, out); }
+            }
+            N::Program(items) | N::Block(items) | N::Function { body: items, .. } => { for it in items { scan(it, map, out); } }
+            N::If { condition, then_branch, else_branch } => { scan(condition, map, out); scan(then_branch, map, out); if let Some(e)=else_branch { scan(e, map, out); } }
+            N::While { condition, body } => { scan(condition, map, out); scan(body, map, out); }
+            N::For { init, condition, increment, body } => { for c in [init, condition, increment].into_iter().flatten() { scan(c, map, out); } scan(body, map, out); }
+            N::ForIn { iterable, body, .. } => { scan(iterable, map, out); scan(body, map, out); }
+            N::Assignment { value, .. } | N::VariableDecl { value, .. } => scan(value, map, out),
+            N::DestructuringDecl { values, .. } => { for v in values { scan(v, map, out); } }
+            N::Return(e) | N::Log { expr: e, .. } | N::UnaryExpr { expr: e, .. } => scan(e, map, out),
+            N::BinaryExpr { left, right, .. } => { scan(left, map, out); scan(right, map, out); }
+            N::Index { target, index, .. } => { scan(target, map, out); scan(index, map, out); }
+            N::Member { target, .. } => scan(target, map, out),
+            N::Match { subject, arms, .. } => {
+                scan(subject, map, out);
+                for arm in arms { if let Some(p)=&arm.pattern { scan(p, map, out); } scan(&arm.body, map, out); }
+            }
+            N::ArrayLiteral { items, .. } => { for it in items { scan(it, map, out); } }
+            N::ObjectLiteral { fields, .. } => { for (_, v) in fields { scan(v, map, out); } }
             _ => {}
         }
     }
-    if let ASTNode::Program(items) = ast { for (idx, node) in items.iter().enumerate() { if let N::Function { body, .. } = node { for c in body { walk(idx, c, &mut var_reads, &mut var_writes); } } } }
-    VarDeps { reads: var_reads, writes: var_writes }
+    let index_by_name: HashMap<&str, usize> = items.iter().enumerate().filter_map(|(i, n)| match n { N::Function { name, .. } => Some((name.as_str(), i)), _ => None }).collect();
+    items.iter().map(|n| { let mut out = Vec::new(); if let N::Function { body, .. } = n { for st in body { scan(st, &index_by_name, &mut out); } } out }).collect()
+}
+
+/// Functions to re-infer after top-level item `changed` was replaced, excluding `changed`
+/// itself (sorted). Same policy as the GUI `aeonmi_types` command: direct callers plus
+/// readers of variables `changed` writes, widened to all transitive callers when deep
+/// propagation is on or the seed set is small.
+pub fn reinfer_set(items: &[ASTNode], changed: usize) -> Vec<usize> {
+    let calls = compute_call_edges(items);
+    let mut rev: Vec<Vec<usize>> = vec![Vec::new(); items.len()];
+    for (i, outs) in calls.iter().enumerate() { for &t in outs { rev[t].push(i); } }
+    let mut set: HashSet<usize> = HashSet::new();
+    if matches!(items.get(changed), Some(ASTNode::Function { .. })) { set.extend(rev[changed].iter().copied()); }
+    let vd = compute_var_deps_items(items);
+    for (var, writers) in &vd.writes {
+        if writers.contains(&changed) { if let Some(readers)=vd.reads.get(var) { set.extend(readers.iter().copied()); } }
+    }
+    set.remove(&changed);
+    if get_deep_propagation() || set.len() < 8 {
+        let mut queue: VecDeque<usize> = set.iter().copied().collect();
+        while let Some(cur) = queue.pop_front() { for &caller in &rev[cur] { if caller != changed && set.insert(caller) { queue.push_back(caller); } } }
+    }
+    let mut out: Vec<usize> = set.into_iter().collect();
+    out.sort_unstable();
+    out
 }
 
 /// Parse source using cached AST when unchanged. Returns AST and dirty info.
 pub fn parse_or_cached(source: &str) -> Result<ASTNode, String> {
-    let mut hasher = Sha1::new(); hasher.update(source.as_bytes()); let hash = format!("{:x}", hasher.finalize());
-    if let Some(cached) = CACHE.lock().unwrap().as_ref() {
+    let hash = sha1_hex(source);
+    if let Some(cached) = lock_cache("parse", &CACHE).as_ref() {
         if cached.hash == hash { return Ok(cached.ast.clone()); }
     }
     let _dirty = compute_dirty_info(source);
+    *lock_cache("trivia_shift", &LAST_TRIVIA_SHIFT) = None;
     // For now we still reparse whole file; future: region-based reparse using token window around dirty lines.
     let mut lexer = Lexer::from_str(source);
     let tokens = lexer.tokenize().map_err(|e| e.to_string())?;
     let mut parser = AeParser::new(tokens);
     match parser.parse() {
-        Ok(ast) => { let spans = index_top_level(&ast, source); *CACHE.lock().unwrap() = Some(CachedParse { hash, ast: ast.clone(), source: source.to_string(), top_spans: spans }); Ok(ast) },
-        Err(ParserError { message, line, column }) => Err(format!("{message} at {line}:{column}"))
+        Ok(ast) => { *lock_cache("parse", &CACHE) = Some(CachedParse::new(hash, ast.clone(), source)); Ok(ast) },
+        Err(e) => Err(e.to_string())
+    }
+}
+
+/// AST of the last source that parsed, for tools that must work on a broken buffer.
+pub fn last_good_ast() -> Option<ASTNode> { lock_cache("parse", &CACHE).as_ref().map(|c| c.ast.clone()) }
+
+/// The circuit of `source`, reusing the sub-circuits of top-level items unchanged since the
+/// last call. Items are told apart by fingerprint; when the tree is a splice of the one
+/// extracted last, only the items it replaced are fingerprinted again.
+pub fn quantum_circuit_cached(source: &str) -> Result<QuantumCircuit, String> {
+    let (ast, _) = parse_or_partial(source)?;
+    let (generation, replaced) = lock_cache("parse", &CACHE).as_ref().map_or((0, None), |c| (c.generation, c.replaced.clone()));
+    let items = match &ast { ASTNode::Program(items) => items.as_slice(), other => std::slice::from_ref(other) };
+    let mut cache = lock_cache("quantum_circuit", &QUANTUM_CIRCUIT_CACHE);
+    let same_items = cache.fingerprints.len() == items.len();
+    let unchanged = |i: usize| match cache.generation {
+        Some(g) if same_items && g == generation => true,
+        Some(g) if same_items && g + 1 == generation => replaced.as_ref().is_some_and(|r| !r.contains(&i)),
+        _ => false,
+    };
+    let fingerprints: Vec<u64> = items.iter().enumerate().map(|(i, item)| if unchanged(i) { cache.fingerprints[i] } else { item_fingerprint(item) }).collect();
+    let mut clbits: Vec<String> = Vec::new();
+    let mut entries = HashMap::with_capacity(items.len());
+    let mut parts = Vec::with_capacity(items.len());
+    for (item, fp) in items.iter().zip(&fingerprints) {
+        let key = (*fp, clbits.clone());
+        let part = match cache.entries.get(&key).cloned() {
+            Some(part) => { if !part.is_empty() { cache.hits += 1; } part }
+            None => { let part = extract_item(item, &clbits); if !part.is_empty() { cache.misses += 1; } part }
+        };
+        clbits.extend(part.clbits.iter().cloned());
+        entries.insert(key, part.clone());
+        parts.push(part);
     }
+    cache.entries = entries;
+    cache.fingerprints = fingerprints;
+    cache.generation = Some(generation);
+    drop(cache);
+    persist_metrics();
+    Ok(assemble(&parts))
+}
+
+/// Hash of an item with its positions: a gate's line is part of the circuit.
+fn item_fingerprint(item: &ASTNode) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut h = std::collections::hash_map::DefaultHasher::new();
+    format!("{:?}", item).hash(&mut h);
+    h.finish()
 }
 
 /// Attempt simplified partial parse: if dirty region lies strictly between pre-indexed top-level nodes, we reuse AST.
 #[allow(dead_code)]
 pub fn parse_or_partial(source: &str) -> Result<(ASTNode,bool), String> {
-    let cache_opt = CACHE.lock().unwrap().clone();
+    let cache_opt = lock_cache("parse", &CACHE).clone();
     if cache_opt.is_none() { return parse_or_cached(source).map(|a|(a,false)); }
     let prev = cache_opt.unwrap();
     let dirty = compute_dirty_info(source);
     if !dirty.changed { return Ok((prev.ast.clone(), false)); }
+    *lock_cache("trivia_shift", &LAST_TRIVIA_SHIFT) = None;
+    // Edits confined to one string literal or comment patch the cached tree without reparsing.
+    if let Some(ast) = patch_literal(&prev, source).filter(|_| !prev.shifted) {
+        *lock_cache("parse", &CACHE) = Some(CachedParse::new(sha1_hex(source), ast.clone(), source));
+        *lock_cache("replaced_index", &LAST_REPLACED_INDEX) = None;
+        lock_cache("call_graph", &CALL_GRAPH_METRICS).fast_literal_patch += 1;
+        return Ok((ast, true));
+    }
+    // Whitespace/comment-only edits (e.g. typing a comment above the first item) keep the AST;
+    // only the line numbers after the edit move.
+    let literals = scan_literals(source);
+    if let Some(shift) = trivia_shift(&prev, source, &literals) {
+        let mut top_spans: Vec<NodeSpan> = prev.top_spans.iter().map(|sp| NodeSpan { start_line: shift.apply(sp.start_line), end_line: shift.apply(sp.end_line) }).collect();
+        if let Some(last) = top_spans.last_mut() { last.end_line = source.lines().count().max(last.start_line); }
+        shift_cached_diagnostics(shift);
+        let shifted = prev.shifted || shift.delta != 0;
+        *lock_cache("parse", &CACHE) = Some(CachedParse { hash: sha1_hex(source), ast: prev.ast.clone(), source: source.to_string(), top_spans, literals, shifted, generation: prev.generation, replaced: prev.replaced.clone() });
+        *lock_cache("replaced_index", &LAST_REPLACED_INDEX) = None;
+        *lock_cache("trivia_shift", &LAST_TRIVIA_SHIFT) = Some(shift);
+        lock_cache("call_graph", &CALL_GRAPH_METRICS).trivia_fast_path += 1;
+        return Ok((prev.ast, true));
+    }
+    if prev.shifted { return parse_or_cached(source).map(|a|(a,false)); }
     // Count overlapping nodes
     let mut overlap_indices: Vec<usize> = Vec::new();
-    for (i, sp) in prev.top_spans.iter().enumerate() { if overlaps(dirty.first_changed_line, dirty.last_changed_line, sp.start_line, sp.end_line) { overlap_indices.push(i); } }
-    if !overlap_indices.is_empty() && overlap_indices.windows(2).all(|w| w[1]==w[0]+1) && overlap_indices.len() <= 8 {
+    for (i, sp) in prev.top_spans.iter().enumerate() { if overlaps(dirty.first_changed_line, dirty.last_changed_old_line, sp.start_line, sp.end_line) { overlap_indices.push(i); } }
+    // Nodes after the splice keep their old line numbers, so a splice that shifts lines is only safe at the end.
+    let shifts_later = source.lines().count() != prev.source.lines().count() && overlap_indices.last().is_some_and(|&l| l + 1 < prev.top_spans.len());
+    if !overlap_indices.is_empty() && overlap_indices.windows(2).all(|w| w[1]==w[0]+1) && overlap_indices.len() <= 8 && !shifts_later {
         // Reparse only the slice covering that node span lines; simplistic: extract source subset by lines and parse as program fragment
         let first = overlap_indices[0]; let last = *overlap_indices.last().unwrap();
         let sp = &prev.top_spans[first];
         let end_span = &prev.top_spans[last];
         let lines: Vec<&str> = source.lines().collect();
-        let fragment_src = lines[(sp.start_line.saturating_sub(1))..end_span.end_line.min(lines.len())].join("\n");
+        // The edit may add or remove lines inside the affected spans; shift their end into the new numbering.
+        let end_line = (end_span.end_line + lines.len()).saturating_sub(prev.source.lines().count());
+        // Pad with the lines above so spliced nodes keep absolute line numbers (spans for the next edit depend on them).
+        let fragment_src = "\n".repeat(sp.start_line.saturating_sub(1)) + &lines[(sp.start_line.saturating_sub(1))..end_line.clamp(sp.start_line.saturating_sub(1), lines.len())].join("\n");
         let mut lexer = Lexer::from_str(&fragment_src);
         if let Ok(tokens) = lexer.tokenize() {
             let mut parser = AeParser::new(tokens);
@@ -341,7 +846,10 @@ pub fn parse_or_partial(source: &str) -> Result<(ASTNode,bool), String> {
                         // Only proceed if counts match target replacement length
                         if new_items.len() == overlap_indices.len() {
                             for (offset, idx) in overlap_indices.iter().enumerate() { if *idx < old_items.len() { old_items[*idx] = new_items[offset].clone(); } }
-                            let updated = ASTNode::Program(old_items); let spans = index_top_level(&updated, source); *CACHE.lock().unwrap() = Some(CachedParse { hash: String::new(), ast: updated.clone(), source: source.to_string(), top_spans: spans }); *LAST_REPLACED_INDEX.lock().unwrap() = Some(first); return Ok((updated,true));
+                            let updated = ASTNode::Program(old_items);
+                            let mut spliced = CachedParse::new(String::new(), updated.clone(), source);
+                            spliced.replaced = Some(first..last + 1);
+                            *lock_cache("parse", &CACHE) = Some(spliced); *lock_cache("replaced_index", &LAST_REPLACED_INDEX) = Some(first); return Ok((updated,true));
                         }
                     }
                 }
@@ -360,6 +868,157 @@ pub fn parse_or_partial(source: &str) -> Result<(ASTNode,bool), String> {
 
 fn overlaps(a1: usize, a2: usize, b1: usize, b2: usize) -> bool { !(a2 < b1 || b2 < a1) }
 
+/// The line shift of an edit that only touched trivia: the changed lines relex to the same
+/// tokens at the same columns, and no string or block comment crosses their boundary (the
+/// lines are lexed on their own). `None` for any other edit.
+fn trivia_shift(prev: &CachedParse, new_src: &str, new_literals: &[LiteralSpan]) -> Option<LineShift> {
+    use crate::core::token::TokenKind;
+    let m = crate::core::lexer::Markers::default();
+    if prev.source.contains(m.ai_start) || new_src.contains(m.ai_start) { return None; }
+    let old: Vec<&str> = prev.source.split_inclusive('\n').collect();
+    let new: Vec<&str> = new_src.split_inclusive('\n').collect();
+    let pre = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let max_suf = old.len().min(new.len()) - pre;
+    let suf = old.iter().rev().zip(new.iter().rev()).take(max_suf).take_while(|(a, b)| a == b).count();
+    let (old_end, new_end) = (old.len() - suf, new.len() - suf);
+    let offset = |lines: &[&str], n: usize| lines[..n].iter().map(|l| l.len()).sum::<usize>();
+    let crosses = |lits: &[LiteralSpan], bounds: [usize; 2]| lits.iter().any(|l| l.kind != LiteralKind::LineComment && bounds.iter().any(|&b| l.start < b && b <= l.end));
+    if crosses(&prev.literals, [offset(&old, pre), offset(&old, old_end)]) || crosses(new_literals, [offset(&new, pre), offset(&new, new_end)]) { return None; }
+    let tokens = |region: &[&str]| Lexer::from_str(&region.concat()).tokenize().ok().map(|ts| ts.into_iter().filter(|t| t.kind != TokenKind::EOF).map(|t| (t.kind, t.lexeme, t.line, t.column)).collect::<Vec<_>>());
+    if tokens(&old[pre..old_end])? != tokens(&new[pre..new_end])? { return None; }
+    Some(LineShift { after_line: old_end, delta: new.len() as isize - old.len() as isize })
+}
+
+/// Move the cached semantic and type diagnostics (and their related spans and fixes) by `shift`.
+fn shift_cached_diagnostics(shift: LineShift) {
+    {
+        let mut cache = lock_cache("diagnostics", &DIAG_CACHE);
+        let cache = &mut *cache;
+        for d in cache.per_node.iter_mut().flatten().chain(cache.reported.iter_mut()) {
+            d.line = shift.apply(d.line);
+            for r in &mut d.related { r.line = shift.apply(r.line); }
+            for e in d.fix.iter_mut().flat_map(|f| f.edits.iter_mut()) { e.line = shift.apply(e.line); e.end_line = shift.apply(e.end_line); }
+        }
+    }
+    {
+        let mut cache = lock_cache("type_diagnostics", &TYPE_DIAG_CACHE);
+        let cache = &mut *cache;
+        for d in cache.per_node.iter_mut().flatten().chain(cache.reported.iter_mut()) { d.line = shift.apply(d.line); }
+    }
+}
+
+fn sha1_hex(source: &str) -> String { let mut hasher = Sha1::new(); hasher.update(source.as_bytes()); format!("{:x}", hasher.finalize()) }
+
+/// String literal and comment bodies, found the way the lexer finds them (default markers).
+/// Sources with AI blocks give no spans: their contents follow other rules.
+fn scan_literals(source: &str) -> Vec<LiteralSpan> {
+    let m = crate::core::lexer::Markers::default();
+    if source.contains(m.ai_start) { return Vec::new(); }
+    let mut out = Vec::new();
+    let mut it = source.char_indices().peekable();
+    while let Some((i, ch)) = it.next() {
+        if ch == '"' {
+            let start = i + 1;
+            let mut escape = false;
+            let mut end = None;
+            for (j, c) in it.by_ref() {
+                if escape { escape = false; } else if c == '\\' { escape = true; } else if c == '"' { end = Some(j); break; }
+            }
+            match end { Some(end) => out.push(LiteralSpan { kind: LiteralKind::Str, start, end }), None => break }
+        } else if ch == m.line_comment || (ch == '/' && it.peek().is_some_and(|&(_, c)| c == '/')) {
+            let start = if ch == '/' { it.next().map(|(j, c)| j + c.len_utf8()).unwrap_or(source.len()) } else { i + ch.len_utf8() };
+            let end = it.by_ref().find(|&(_, c)| c == '\n').map(|(j, _)| j).unwrap_or(source.len());
+            out.push(LiteralSpan { kind: LiteralKind::LineComment, start, end });
+        } else if ch == m.block_comment_start {
+            let start = i + ch.len_utf8();
+            let mut depth = 1usize;
+            let mut end = None;
+            for (j, c) in it.by_ref() {
+                if c == m.block_comment_start { depth += 1; } else if c == m.block_comment_end { depth -= 1; if depth == 0 { end = Some(j); break; } }
+            }
+            match end { Some(end) => out.push(LiteralSpan { kind: LiteralKind::BlockComment, start, end }), None => break }
+        }
+    }
+    out
+}
+
+/// String literals of `node` in source order.
+fn string_literals_mut<'a>(node: &'a mut ASTNode, out: &mut Vec<&'a mut String>) {
+    use crate::core::ast::ASTNode as N;
+    match node {
+        N::StringLiteral(s) => out.push(s),
+        N::Program(items) | N::Block(items) | N::Function { body: items, .. } => { for it in items { string_literals_mut(it, out); } }
+        N::VariableDecl { value: e, .. } | N::Assignment { value: e, .. } | N::Return(e) | N::Log { expr: e, .. } | N::UnaryExpr { expr: e, .. } | N::Member { target: e, .. } => string_literals_mut(e, out),
+        N::DestructuringDecl { values, .. } => { for v in values { string_literals_mut(v, out); } }
+        N::If { condition, then_branch, else_branch } => { string_literals_mut(condition, out); string_literals_mut(then_branch, out); if let Some(e) = else_branch { string_literals_mut(e, out); } }
+        N::While { condition, body } => { string_literals_mut(condition, out); string_literals_mut(body, out); }
+        N::For { init, condition, increment, body } => { for c in [init, condition, increment].into_iter().flatten() { string_literals_mut(c, out); } string_literals_mut(body, out); }
+        N::ForIn { iterable, body, .. } => { string_literals_mut(iterable, out); string_literals_mut(body, out); }
+        N::Call { callee, args } => { string_literals_mut(callee, out); for a in args { string_literals_mut(a, out); } }
+        N::BinaryExpr { left, right, .. } => { string_literals_mut(left, out); string_literals_mut(right, out); }
+        N::Index { target, index, .. } => { string_literals_mut(target, out); string_literals_mut(index, out); }
+        N::Match { subject, arms, .. } => {
+            string_literals_mut(subject, out);
+            for arm in arms { if let Some(p) = &mut arm.pattern { string_literals_mut(p, out); } string_literals_mut(&mut arm.body, out); }
+        }
+        N::QuantumOp { qubits: args, .. } | N::HieroglyphicOp { args, .. } => { for a in args { string_literals_mut(a, out); } }
+        _ => {}
+    }
+}
+
+/// The cached tree with the edit applied, when the edit lies inside a single string literal
+/// or comment body and cannot change any token boundary, line or later column. None means
+/// the edit needs a real parse (including when the cached spans disagree with the tree).
+fn patch_literal(prev: &CachedParse, new_src: &str) -> Option<ASTNode> {
+    let old_src = prev.source.as_str();
+    let (old_b, new_b) = (old_src.as_bytes(), new_src.as_bytes());
+    let mut pre = old_b.iter().zip(new_b).take_while(|(a, b)| a == b).count();
+    while !old_src.is_char_boundary(pre) || !new_src.is_char_boundary(pre) { pre -= 1; }
+    let max_suf = old_b.len().min(new_b.len()) - pre;
+    let mut suf = old_b.iter().rev().zip(new_b.iter().rev()).take(max_suf).take_while(|(a, b)| a == b).count();
+    while !old_src.is_char_boundary(old_b.len() - suf) || !new_src.is_char_boundary(new_b.len() - suf) { suf -= 1; }
+    let (old_end, new_end) = (old_b.len() - suf, new_b.len() - suf);
+    let (removed, inserted) = (&old_src[pre..old_end], &new_src[pre..new_end]);
+
+    let ordinal = prev.literals.iter().position(|l| l.start <= pre && old_end <= l.end)?;
+    let span = &prev.literals[ordinal];
+    if removed.matches('\n').count() != inserted.matches('\n').count() { return None; }
+    let body_end = span.end + new_end - old_end;
+    let body = &new_src[span.start..body_end];
+    let markers = crate::core::lexer::Markers::default();
+    let body_ok = match span.kind {
+        LiteralKind::Str => !body.contains(['"', '\\']),
+        LiteralKind::LineComment => !body.contains('\n'),
+        LiteralKind::BlockComment => !body.contains([markers.block_comment_start, markers.block_comment_end]),
+    };
+    if !body_ok { return None; }
+    // A width change moves whatever follows on the edited line; only allow that when nothing
+    // after the literal on that line carries a position (closers, separators, a comment).
+    let tail_width = |s: &str| s.rsplit('\n').next().unwrap_or("").chars().count();
+    if tail_width(removed) != tail_width(inserted) {
+        let edit_line_end = new_src[new_end..].find('\n').map(|i| new_end + i).unwrap_or(new_src.len());
+        if body_end < edit_line_end {
+            let close = if span.kind == LiteralKind::BlockComment { markers.block_comment_end.len_utf8() } else { 1 };
+            let rest = new_src[(body_end + close).min(edit_line_end)..edit_line_end].trim_start();
+            let inert = rest.starts_with("//") || rest.starts_with(markers.line_comment)
+                || rest.chars().all(|c| matches!(c, ')' | ']' | '}' | ';' | ',') || c.is_whitespace());
+            if !inert { return None; }
+        }
+    }
+
+    let mut ast = prev.ast.clone();
+    if span.kind == LiteralKind::Str {
+        let mut strings = Vec::new();
+        string_literals_mut(&mut ast, &mut strings);
+        let bodies: Vec<&LiteralSpan> = prev.literals.iter().filter(|l| l.kind == LiteralKind::Str).collect();
+        // stale spans: the tree's literals must be exactly the cached source's, in order
+        if strings.len() != bodies.len() || strings.iter().zip(&bodies).any(|(s, l)| s.as_str() != &old_src[l.start..l.end]) { return None; }
+        let k = prev.literals[..ordinal].iter().filter(|l| l.kind == LiteralKind::Str).count();
+        *strings[k] = body.to_string();
+    }
+    Some(ast)
+}
+
 fn index_top_level(ast: &ASTNode, source: &str) -> Vec<NodeSpan> {
     let mut spans = Vec::new();
     let total = source.lines().count().max(1);
@@ -374,22 +1033,23 @@ fn index_top_level(ast: &ASTNode, source: &str) -> Vec<NodeSpan> {
 fn node_start_line(node: &ASTNode) -> usize { match node { ASTNode::Function { line, .. } => *line, ASTNode::VariableDecl { line, .. } => *line, ASTNode::Assignment { line, .. } => *line, _ => 0 } }
 
 fn compute_dirty_info(new_src: &str) -> DirtyInfo {
-    let cache = CACHE.lock().unwrap();
+    let cache = lock_cache("parse", &CACHE);
     if let Some(prev) = cache.as_ref() {
-        if prev.source == new_src { return DirtyInfo { changed: false, first_changed_line: 0, last_changed_line:0 }; }
+        if prev.source == new_src { return DirtyInfo::default(); }
         let old_lines: Vec<&str> = prev.source.lines().collect();
         let new_lines: Vec<&str> = new_src.lines().collect();
         let mut first = 0usize; let mut last_old = old_lines.len().saturating_sub(1); let mut last_new = new_lines.len().saturating_sub(1);
         while first < old_lines.len() && first < new_lines.len() && old_lines[first] == new_lines[first] { first += 1; }
-        if first == old_lines.len() && first == new_lines.len() { return DirtyInfo { changed: false, first_changed_line:0, last_changed_line:0 }; }
+        if first == old_lines.len() && first == new_lines.len() { return DirtyInfo::default(); }
         while last_old>first && last_new>first && old_lines[last_old]==new_lines[last_new] { last_old-=1; last_new-=1; }
-        DirtyInfo { changed: true, first_changed_line: first+1, last_changed_line: (last_new+1).max(first+1) }
+        DirtyInfo { changed: true, first_changed_line: first+1, last_changed_line: (last_new+1).max(first+1), last_changed_old_line: (last_old+1).max(first+1) }
     } else {
-        DirtyInfo { changed: true, first_changed_line: 1, last_changed_line: new_src.lines().count() }
+        let n = new_src.lines().count();
+        DirtyInfo { changed: true, first_changed_line: 1, last_changed_line: n, last_changed_old_line: n }
     }
 }
 
 #[allow(dead_code)]
 pub fn dirty_region(source: &str) -> DirtyInfo { compute_dirty_info(source) }
 
-pub fn debug_snapshot() -> serde_json::Value { let fm = FUNCTION_METRICS.lock().unwrap().clone(); let sm = SAVINGS_METRICS.lock().unwrap().clone(); serde_json::json!({ "functions": fm.iter().map(|(i,m)| (i.to_string(), serde_json::json!({"runs": m.runs, "last_ns": m.last_ns, "ema_ns": m.ema_ns, "window": m.window, "last_run_epoch_ms": m.last_run_epoch_ms}))).collect::<serde_json::Value>(), "savings_history_len": sm.history.len(), "savings_recent_samples": sm.history.iter().map(|s| { serde_json::json!({"partial": s.partial_ns, "est_full": s.estimated_full_ns, "savings": s.savings_ns}) }).collect::<Vec<_>>() }) }
+pub fn debug_snapshot() -> serde_json::Value { let fm = lock_cache("function_metrics", &FUNCTION_METRICS).clone(); let sm = lock_cache("savings", &SAVINGS_METRICS).clone(); serde_json::json!({ "functions": fm.iter().map(|(i,m)| (i.to_string(), serde_json::json!({"runs": m.runs, "last_ns": m.last_ns, "ema_ns": m.ema_ns, "window": m.window, "last_run_epoch_ms": m.last_run_epoch_ms}))).collect::<serde_json::Value>(), "savings_history_len": sm.history.len(), "savings_recent_samples": sm.history.iter().map(|s| { serde_json::json!({"partial": s.partial_ns, "est_full": s.estimated_full_ns, "savings": s.savings_ns}) }).collect::<Vec<_>>() }) }
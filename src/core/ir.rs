@@ -3,9 +3,10 @@
 
 #![allow(dead_code)]
 
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Module {
     pub name: String,
     pub imports: Vec<Import>,
@@ -22,13 +23,13 @@ impl Default for Module {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Ord, PartialOrd)]
+#[derive(Debug, Clone, PartialEq, Eq, Ord, PartialOrd, Serialize, Deserialize)]
 pub struct Import {
     pub path: String,          // e.g., "std/io"
     pub alias: Option<String>, // e.g., "io"
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Decl {
     Const(ConstDecl),
     Let(LetDecl),
@@ -45,31 +46,31 @@ impl Decl {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ConstDecl {
     pub name: String,
     pub value: Expr,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LetDecl {
     pub name: String,
     pub value: Option<Expr>, // `let x;` or `let x = expr;`
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FnDecl {
     pub name: String,
     pub params: Vec<String>,
     pub body: Block,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Block {
     pub stmts: Vec<Stmt>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Stmt {
     Expr(Expr),
     Return(Option<Expr>),
@@ -82,24 +83,49 @@ pub enum Stmt {
         cond: Expr,
         body: Block,
     },
+    /// A bare `{ ... }` block; its `let`s are scoped to it.
+    Block(Block),
     For {
         // Desugared to while at lowering if needed; included for readability in IR.
         init: Option<Box<Stmt>>,
         cond: Option<Expr>,
-        step: Option<Expr>,
+        /// A statement, since the usual step (`i = i + 1`) is an assignment.
+        step: Option<Box<Stmt>>,
         body: Block,
     },
+    /// Leave the innermost `while`/`for`.
+    Break,
+    /// Skip to the innermost loop's next iteration (running a `for`'s step first).
+    Continue,
+    /// `line`/`column` locate the declaration (0 = unknown), as for `Log`.
     Let {
         name: String,
         value: Option<Expr>,
+        line: usize,
+        column: usize,
+    },
+    /// `let [x, [y, z]] = value;`; `let a, b = 1, 2;` is an array pattern over `[1, 2]`.
+    Destructure {
+        pattern: Pattern,
+        value: Expr,
+        line: usize,
+        column: usize,
     },
     Assign {
         target: Expr, // Identifier or Index/Member in a future extension
         value: Expr,
+        line: usize,
+        column: usize,
+    },
+    /// `log(...)` statement; keeps the source span for structured output (0 = unknown).
+    Log {
+        args: Vec<Expr>,
+        line: usize,
+        column: usize,
     },
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Expr {
     Lit(Lit),
     Ident(String),
@@ -118,9 +144,25 @@ pub enum Expr {
     },
     Array(Vec<Expr>),
     Object(Vec<(String, Expr)>), // simple map/object
+    Index {
+        target: Box<Expr>,
+        index: Box<Expr>,
+    },
+    /// `target.name`: an enum variant or an object field.
+    Member {
+        target: Box<Expr>,
+        name: String,
+    },
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Pattern {
+    Name(String),
+    /// Matches an array (or a string's characters) of exactly this many elements.
+    Array(Vec<Pattern>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Lit {
     Null,
     Bool(bool),
@@ -128,13 +170,14 @@ pub enum Lit {
     String(String),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BinOp {
     Add,
     Sub,
     Mul,
     Div,
     Mod,
+    Pow,
     Eq,
     Ne,
     Lt,
@@ -145,7 +188,7 @@ pub enum BinOp {
     Or,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum UnOp {
     Neg,
     Not,
@@ -160,6 +203,7 @@ impl fmt::Display for BinOp {
             Mul => "*",
             Div => "/",
             Mod => "%",
+            Pow => "**",
             Eq => "==",
             Ne => "!=",
             Lt => "<",
@@ -172,3 +216,142 @@ impl fmt::Display for BinOp {
         write!(f, "{}", s)
     }
 }
+
+impl fmt::Display for UnOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            UnOp::Neg => "-",
+            UnOp::Not => "!",
+        })
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pattern::Name(n) => f.write_str(n),
+            Pattern::Array(items) => write!(f, "[{}]", items.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", ")),
+        }
+    }
+}
+
+fn list(items: &[Expr]) -> String {
+    items.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+/// Fully parenthesized, so the dump shows exactly how lowering grouped operators.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Lit(Lit::Null) => f.write_str("null"),
+            Expr::Lit(Lit::Bool(b)) => write!(f, "{b}"),
+            Expr::Lit(Lit::Number(n)) => write!(f, "{n}"),
+            Expr::Lit(Lit::String(s)) => write!(f, "{s:?}"),
+            Expr::Ident(n) => f.write_str(n),
+            Expr::Call { callee, args } => write!(f, "{callee}({})", list(args)),
+            Expr::Binary { left, op, right } => write!(f, "({left} {op} {right})"),
+            Expr::Unary { op, expr } => write!(f, "{op}{expr}"),
+            Expr::Array(items) => write!(f, "[{}]", list(items)),
+            Expr::Object(fields) => {
+                let fields: Vec<String> = fields.iter().map(|(k, v)| format!("{k}: {v}")).collect();
+                write!(f, "{{{}}}", fields.join(", "))
+            }
+            Expr::Index { target, index } => write!(f, "{target}[{index}]"),
+            Expr::Member { target, name } => write!(f, "{target}.{name}"),
+        }
+    }
+}
+
+fn span(line: usize, column: usize) -> String {
+    if line == 0 { String::new() } else { format!("  # {line}:{column}") }
+}
+
+/// `s` without its trailing `;` and span, for `for (...)` headers.
+fn header(s: &Stmt) -> String {
+    match s {
+        Stmt::Let { name, value: Some(v), .. } => format!("let {name} = {v}"),
+        Stmt::Let { name, value: None, .. } => format!("let {name}"),
+        Stmt::Assign { target, value, .. } => format!("{target} = {value}"),
+        Stmt::Expr(e) => e.to_string(),
+        other => format!("{other:?}"),
+    }
+}
+
+fn write_block(out: &mut String, b: &Block, depth: usize) {
+    for s in &b.stmts {
+        write_stmt(out, s, depth);
+    }
+}
+
+fn write_stmt(out: &mut String, s: &Stmt, depth: usize) {
+    let pad = "    ".repeat(depth);
+    let open = |out: &mut String, head: String| out.push_str(&format!("{pad}{head} {{\n"));
+    let close = |out: &mut String| out.push_str(&format!("{pad}}}\n"));
+    match s {
+        Stmt::Expr(e) => out.push_str(&format!("{pad}{e};\n")),
+        Stmt::Return(Some(e)) => out.push_str(&format!("{pad}return {e};\n")),
+        Stmt::Return(None) => out.push_str(&format!("{pad}return;\n")),
+        Stmt::If { cond, then_block, else_block } => {
+            open(out, format!("if {cond}"));
+            write_block(out, then_block, depth + 1);
+            if let Some(b) = else_block {
+                out.push_str(&format!("{pad}}} else {{\n"));
+                write_block(out, b, depth + 1);
+            }
+            close(out);
+        }
+        Stmt::While { cond, body } => {
+            open(out, format!("while {cond}"));
+            write_block(out, body, depth + 1);
+            close(out);
+        }
+        Stmt::Block(b) => {
+            out.push_str(&format!("{pad}{{\n"));
+            write_block(out, b, depth + 1);
+            close(out);
+        }
+        Stmt::For { init, cond, step, body } => {
+            let part = |s: &Option<Box<Stmt>>| s.as_deref().map(header).unwrap_or_default();
+            let cond = cond.as_ref().map(|c| c.to_string()).unwrap_or_default();
+            open(out, format!("for ({}; {cond}; {})", part(init), part(step)));
+            write_block(out, body, depth + 1);
+            close(out);
+        }
+        Stmt::Break => out.push_str(&format!("{pad}break;\n")),
+        Stmt::Continue => out.push_str(&format!("{pad}continue;\n")),
+        Stmt::Let { line, column, .. } => out.push_str(&format!("{pad}{};{}\n", header(s), span(*line, *column))),
+        Stmt::Destructure { pattern, value, line, column } => {
+            out.push_str(&format!("{pad}let {pattern} = {value};{}\n", span(*line, *column)))
+        }
+        Stmt::Assign { line, column, .. } => out.push_str(&format!("{pad}{};{}\n", header(s), span(*line, *column))),
+        Stmt::Log { args, line, column } => out.push_str(&format!("{pad}log({});{}\n", list(args), span(*line, *column))),
+    }
+}
+
+/// The readable form `aeonmi ir` prints: one block per declaration, in module order,
+/// with compiler temporaries and intrinsic calls left visible.
+impl fmt::Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = format!("module {}\n", self.name);
+        for i in &self.imports {
+            match &i.alias {
+                Some(a) => out.push_str(&format!("import {} as {a};\n", i.path)),
+                None => out.push_str(&format!("import {};\n", i.path)),
+            }
+        }
+        for d in &self.decls {
+            out.push('\n');
+            match d {
+                Decl::Const(c) => out.push_str(&format!("const {} = {};\n", c.name, c.value)),
+                Decl::Let(LetDecl { name, value: Some(v) }) => out.push_str(&format!("let {name} = {v};\n")),
+                Decl::Let(LetDecl { name, value: None }) => out.push_str(&format!("let {name};\n")),
+                Decl::Fn(func) => {
+                    out.push_str(&format!("fn {}({}) {{\n", func.name, func.params.join(", ")));
+                    write_block(&mut out, &func.body, 1);
+                    out.push_str("}\n");
+                }
+            }
+        }
+        f.write_str(&out)
+    }
+}
@@ -0,0 +1,254 @@
+//! IR verifier: invariants lowering must establish, checked after lowering in debug
+//! builds and under `--verify-ir` (and always by `aeonmi ir`).
+//!
+//! * Compiler temporaries (`__`-prefixed names such as `__for_i` or `__match`) are bound
+//!   by a `let`, parameter or pattern in an enclosing scope before they are read.
+//!   `__` names in callee position are intrinsics and are checked against
+//!   [`INTRINSICS`] instead.
+//! * Control flow is well formed: `break`/`continue` sit inside a loop of the same
+//!   function, a `for` header holds only simple statements, and assignments target
+//!   a name, an index or a member.
+//! * Call sites match signatures: every intrinsic call passes what the intrinsic takes,
+//!   and destructuring binds each name once, from as many values as its pattern has
+//!   elements. User functions are not checked: a wrong argument count, a duplicate
+//!   parameter or a second `main` is the program's own error, reported by the VM.
+//!
+//! A violation is an internal compiler error naming the offending function.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Serialize;
+
+use crate::core::ir::*;
+
+static FORCED: AtomicBool = AtomicBool::new(false);
+
+/// `--verify-ir`: verify after every lowering, also in release builds.
+pub fn set_forced(on: bool) {
+    FORCED.store(on, Ordering::Relaxed);
+}
+
+/// Whether lowering verifies its output.
+pub fn enabled() -> bool {
+    cfg!(debug_assertions) || FORCED.load(Ordering::Relaxed)
+}
+
+/// Intrinsics lowering emits calls to: name, minimum and maximum argument count
+/// (`None` = variadic).
+pub const INTRINSICS: &[(&str, usize, Option<usize>)] = &[
+    ("__qubit", 1, Some(1)),
+    ("__qreg", 2, Some(2)),
+    ("__iter", 1, Some(1)),
+    ("__iter_keys", 1, Some(1)),
+    ("__enum", 1, None),
+    ("__glyph", 1, None),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Violation {
+    /// Function (or top-level declaration) the violation was found in.
+    pub function: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "internal compiler error: invalid IR in `{}`: {}", self.function, self.message)
+    }
+}
+
+/// One message for all of `violations`, as lowering reports it.
+pub fn internal_error(violations: &[Violation]) -> String {
+    let mut out = violations.iter().map(Violation::to_string).collect::<Vec<_>>().join("\n");
+    out.push_str("\nthis is a compiler bug; please report it with the source that triggered it");
+    out
+}
+
+fn is_temporary(name: &str) -> bool {
+    name.starts_with("__")
+}
+
+struct Checker {
+    function: String,
+    violations: Vec<Violation>,
+    /// Temporaries bound in each open scope, innermost last.
+    scopes: Vec<HashSet<String>>,
+    loops: usize,
+}
+
+impl Checker {
+    fn report(&mut self, message: String) {
+        self.violations.push(Violation { function: self.function.clone(), message });
+    }
+
+    fn bind(&mut self, name: &str) {
+        if is_temporary(name) {
+            self.scopes.last_mut().expect("open scope").insert(name.to_string());
+        }
+    }
+
+    fn bound(&self, name: &str) -> bool {
+        self.scopes.iter().any(|s| s.contains(name))
+    }
+
+    fn block(&mut self, b: &Block) {
+        self.scopes.push(HashSet::new());
+        for s in &b.stmts {
+            self.stmt(s);
+        }
+        self.scopes.pop();
+    }
+
+    fn header(&mut self, s: &Stmt, what: &str) {
+        match s {
+            Stmt::Let { .. } | Stmt::Assign { .. } | Stmt::Expr(_) | Stmt::Return(_) => self.stmt(s),
+            _ => self.report(format!("`for` {what} is not a simple statement")),
+        }
+    }
+
+    fn stmt(&mut self, s: &Stmt) {
+        match s {
+            Stmt::Expr(e) => self.expr(e),
+            Stmt::Return(e) => {
+                if let Some(e) = e {
+                    self.expr(e);
+                }
+            }
+            Stmt::If { cond, then_block, else_block } => {
+                self.expr(cond);
+                self.block(then_block);
+                if let Some(b) = else_block {
+                    self.block(b);
+                }
+            }
+            Stmt::While { cond, body } => {
+                self.expr(cond);
+                self.loops += 1;
+                self.block(body);
+                self.loops -= 1;
+            }
+            Stmt::Block(b) => self.block(b),
+            Stmt::For { init, cond, step, body } => {
+                self.scopes.push(HashSet::new());
+                if let Some(init) = init {
+                    self.header(init, "initializer");
+                }
+                if let Some(cond) = cond {
+                    self.expr(cond);
+                }
+                self.loops += 1;
+                self.block(body);
+                self.loops -= 1;
+                if let Some(step) = step {
+                    self.header(step, "step");
+                }
+                self.scopes.pop();
+            }
+            Stmt::Break if self.loops == 0 => self.report("`break` outside a loop".into()),
+            Stmt::Continue if self.loops == 0 => self.report("`continue` outside a loop".into()),
+            Stmt::Break | Stmt::Continue => {}
+            Stmt::Let { name, value, .. } => {
+                if let Some(v) = value {
+                    self.expr(v);
+                }
+                self.bind(name);
+            }
+            Stmt::Destructure { pattern, value, .. } => {
+                self.expr(value);
+                if let (Pattern::Array(items), Expr::Array(values)) = (pattern, value) {
+                    if items.len() != values.len() {
+                        self.report(format!("destructuring binds {} names from {} values", items.len(), values.len()));
+                    }
+                }
+                let mut seen = HashSet::new();
+                self.pattern(pattern, &mut seen);
+            }
+            Stmt::Assign { target, value, .. } => {
+                self.expr(value);
+                match target {
+                    Expr::Ident(name) if is_temporary(name) && !self.bound(name) => {
+                        self.report(format!("temporary `{name}` is assigned before it is defined"))
+                    }
+                    Expr::Ident(_) => {}
+                    Expr::Index { .. } | Expr::Member { .. } => self.expr(target),
+                    other => self.report(format!("assignment to a non-place expression {other:?}")),
+                }
+            }
+            Stmt::Log { args, .. } => args.iter().for_each(|a| self.expr(a)),
+        }
+    }
+
+    fn pattern(&mut self, p: &Pattern, seen: &mut HashSet<String>) {
+        match p {
+            Pattern::Name(name) => {
+                if !seen.insert(name.clone()) {
+                    self.report(format!("destructuring binds `{name}` twice"));
+                }
+                self.bind(name);
+            }
+            Pattern::Array(items) => items.iter().for_each(|i| self.pattern(i, seen)),
+        }
+    }
+
+    fn expr(&mut self, e: &Expr) {
+        match e {
+            Expr::Lit(_) => {}
+            Expr::Ident(name) => {
+                if is_temporary(name) && !self.bound(name) {
+                    self.report(format!("temporary `{name}` is used before it is defined"));
+                }
+            }
+            Expr::Call { callee, args } => {
+                match callee.as_ref() {
+                    Expr::Ident(name) if is_temporary(name) => {
+                        if let Some((_, min, max)) = INTRINSICS.iter().find(|(n, ..)| n == name) {
+                            if args.len() < *min || max.is_some_and(|m| args.len() > m) {
+                                let takes = match max {
+                                    Some(m) if m == min => format!("{min}"),
+                                    Some(m) => format!("{min} to {m}"),
+                                    None => format!("at least {min}"),
+                                };
+                                self.report(format!("intrinsic `{name}` takes {takes} argument(s), called with {}", args.len()));
+                            }
+                        }
+                    }
+                    other => self.expr(other),
+                }
+                args.iter().for_each(|a| self.expr(a));
+            }
+            Expr::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Unary { expr, .. } => self.expr(expr),
+            Expr::Array(items) => items.iter().for_each(|i| self.expr(i)),
+            Expr::Object(fields) => fields.iter().for_each(|(_, v)| self.expr(v)),
+            Expr::Index { target, index } => {
+                self.expr(target);
+                self.expr(index);
+            }
+            Expr::Member { target, .. } => self.expr(target),
+        }
+    }
+}
+
+/// Every invariant `m` breaks; empty when the module is well formed.
+pub fn verify(m: &Module) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for d in &m.decls {
+        let mut c = Checker { function: d.name().to_string(), violations: Vec::new(), scopes: vec![HashSet::new()], loops: 0 };
+        match d {
+            Decl::Fn(f) => {
+                f.params.iter().for_each(|p| c.bind(p));
+                // The body shares the parameters' scope, as in the VM.
+                f.body.stmts.iter().for_each(|s| c.stmt(s));
+            }
+            Decl::Const(ConstDecl { value, .. }) | Decl::Let(LetDecl { value: Some(value), .. }) => c.expr(value),
+            Decl::Let(_) => {}
+        }
+        violations.extend(c.violations);
+    }
+    violations
+}
@@ -1,7 +1,7 @@
 #![allow(dead_code, unused_variables, unused_mut)]
 
 // ...existing code...
-use crate::core::token::{Token, TokenKind};
+use crate::core::token::{StringPart, Token, TokenKind};
 use std::fmt;
 use std::sync::{Arc, Mutex};
 use unicode_ident::{is_xid_continue, is_xid_start};
@@ -41,6 +41,8 @@ pub struct LexerOptions {
     pub language_mode: Option<String>,
     pub dynamic_config: Option<Arc<Mutex<LexerDynamicConfig>>>,
     pub dlp_plugins: Vec<Arc<dyn DlpPlugin + Send + Sync>>,
+    /// Built-in optional plugins to install by name (see `lexer_plugins::AVAILABLE`).
+    pub plugins: Vec<String>,
     pub cli_mode: bool,
 }
 impl Default for LexerOptions {
@@ -53,6 +55,7 @@ impl Default for LexerOptions {
             language_mode: None,
             dynamic_config: None,
             dlp_plugins: Vec::new(),
+            plugins: Vec::new(),
             cli_mode: false,
         }
     }
@@ -79,6 +82,8 @@ pub struct LexerView {
 pub enum LexerError {
     UnexpectedCharacter(char, usize, usize),
     UnterminatedString(usize, usize),
+    /// A `${` inside a string with no matching `}`; the position is the `{`.
+    UnterminatedInterpolation(usize, usize),
     InvalidNumber(String, usize, usize),
     InvalidGlyph(String, usize, usize),
     UnterminatedComment(usize, usize),
@@ -98,6 +103,9 @@ impl fmt::Display for LexerError {
             UnterminatedString(line, col) => {
                 write!(f, "Unterminated string starting at {}:{}", line, col)
             }
+            UnterminatedInterpolation(line, col) => {
+                write!(f, "Unterminated interpolation: '${{' at {}:{} is never closed with '}}'", line, col)
+            }
             InvalidNumber(num, line, col) => {
                 write!(f, "Invalid number literal '{}' at {}:{}", num, line, col)
             }
@@ -140,6 +148,26 @@ impl fmt::Display for LexerError {
 }
 impl std::error::Error for LexerError {}
 
+impl LexerError {
+    /// 1-based (line, column) where the error was detected.
+    pub fn position(&self) -> (usize, usize) {
+        use LexerError::*;
+        match self {
+            UnexpectedCharacter(_, line, col)
+            | InvalidNumber(_, line, col)
+            | InvalidGlyph(_, line, col)
+            | PluginError(_, line, col)
+            | Diagnostic(_, line, col, _)
+            | InvalidQubitLiteral(_, line, col) => (*line, *col),
+            UnterminatedString(line, col)
+            | UnterminatedInterpolation(line, col)
+            | UnterminatedComment(line, col)
+            | UnauthorizedAIAccess(line, col)
+            | AIContentTooLarge(line, col) => (*line, *col),
+        }
+    }
+}
+
 pub trait CustomTokenKind: Send + Sync {
     fn try_match(&self, lexer: &Lexer) -> Option<TokenKind>;
     fn name(&self) -> &str;
@@ -147,9 +175,17 @@ pub trait CustomTokenKind: Send + Sync {
 
 /// NOTE: Changed to accept a read-only `LexerView` to avoid E0502.
 pub trait LexerPlugin: Send + Sync {
+    /// Name used to prefix errors the plugin reports.
+    fn name(&self) -> &str {
+        "plugin"
+    }
     fn before_token(&mut self, _view: LexerView) {}
     fn after_token(&mut self, _view: LexerView, _token: &Token) {}
     fn on_error(&mut self, _view: LexerView, _error: &LexerError) {}
+    /// Polled after `after_token`; an error here aborts lexing with `PluginError` at the token.
+    fn take_error(&mut self) -> Option<String> {
+        None
+    }
 }
 
 pub trait DlpPlugin: Send + Sync {
@@ -165,13 +201,17 @@ pub struct Lexer {
     chars: std::str::CharIndices<'static>,
     current: Option<(usize, char)>,
     line: usize,
+    /// 1-based UTF-8 byte column of `current` within its line (0 on a newline).
     col: usize,
+    line_start: usize,
     options: LexerOptions,
     in_ai_block: bool,
     plugins: Vec<Box<dyn LexerPlugin>>,
     custom_token_kinds: Vec<Arc<dyn CustomTokenKind>>,
     pub token_cache: Vec<Token>,
     pub event_bus: Option<Arc<Mutex<Vec<String>>>>,
+    /// Set when `options.plugins` names an unknown plugin; reported by the first `next_token`.
+    plugin_setup_error: Option<String>,
 }
 
 impl Lexer {
@@ -197,13 +237,21 @@ impl Lexer {
             current: None,
             line: 1,
             col: 0,
+            line_start: 0,
             options,
             in_ai_block: false,
             plugins: Vec::new(),
             custom_token_kinds: Vec::new(),
             token_cache: Vec::new(),
             event_bus: None,
+            plugin_setup_error: None,
         };
+        let names = lexer.options.plugins.clone();
+        for name in &names {
+            if let Err(e) = crate::core::lexer_plugins::install(&mut lexer, name) {
+                lexer.plugin_setup_error.get_or_insert(e);
+            }
+        }
         lexer.advance_char();
         lexer
     }
@@ -217,6 +265,26 @@ impl Lexer {
         self.event_bus = Some(bus);
     }
 
+    /// Character at the cursor (what a `CustomTokenKind` is asked to match).
+    pub fn current_char(&self) -> Option<char> {
+        self.current.map(|(_, c)| c)
+    }
+
+    /// Run `after_token` on every plugin, then surface the first error any of them reported.
+    fn after_token_hooks(&mut self, token: &Token) -> Result<(), LexerError> {
+        let view = self.view();
+        for plugin in self.plugins.iter_mut() {
+            plugin.after_token(view, token);
+        }
+        for plugin in self.plugins.iter_mut() {
+            if let Some(msg) = plugin.take_error() {
+                let msg = format!("{}: {}", plugin.name(), msg);
+                return Err(LexerError::PluginError(msg, token.line, token.column));
+            }
+        }
+        Ok(())
+    }
+
     /// Lightweight, copyable snapshot for plugins.
     #[inline]
     fn view(&self) -> LexerView {
@@ -234,12 +302,13 @@ impl Lexer {
     #[inline]
     fn advance_char(&mut self) {
         self.current = self.chars.next();
-        if let Some((_, ch)) = self.current {
+        if let Some((idx, ch)) = self.current {
             if ch == '\n' {
                 self.line += 1;
                 self.col = 0;
+                self.line_start = idx + 1;
             } else {
-                self.col += 1;
+                self.col = idx - self.line_start + 1;
             }
         }
     }
@@ -248,6 +317,9 @@ impl Lexer {
         self.chars.clone().next().map(|(_, c)| c)
     }
     pub fn next_token(&mut self) -> Result<Option<Token>, LexerError> {
+        if let Some(msg) = self.plugin_setup_error.take() {
+            return Err(LexerError::PluginError(msg, 1, 1));
+        }
         loop {
             let ch = match self.current {
                 Some((_, ch)) => ch,
@@ -271,14 +343,7 @@ impl Lexer {
                     let (line, col) = self.pos();
                     self.advance_char();
                     let token = Token::new(tok, String::new(), line, col);
-
-                    // plugin: after token
-                    {
-                        let view = self.view();
-                        for plugin in self.plugins.iter_mut() {
-                            plugin.after_token(view, &token);
-                        }
-                    }
+                    self.after_token_hooks(&token)?;
                     return Ok(Some(token));
                 }
             }
@@ -296,10 +361,7 @@ impl Lexer {
                 let (line, col) = self.pos();
                 self.advance_char();
                 let token = Token::new(tok_kind, ch.to_string(), line, col);
-                {
-                    let view = self.view();
-                    for plugin in self.plugins.iter_mut() { plugin.after_token(view, &token); }
-                }
+                self.after_token_hooks(&token)?;
                 return Ok(Some(token));
             }
 
@@ -373,12 +435,7 @@ impl Lexer {
                         dlp.before_emit_token(&token);
                     }
                     // plugin: after token
-                    {
-                        let view = self.view();
-                        for plugin in self.plugins.iter_mut() {
-                            plugin.after_token(view, &token);
-                        }
-                    }
+                    self.after_token_hooks(&token)?;
                     // DLP (after)
                     for dlp in self.options.dlp_plugins.iter() {
                         dlp.after_emit_token(&token);
@@ -512,24 +569,32 @@ impl Lexer {
     }
     fn lex_ascii_number(&mut self) -> Result<Token, LexerError> {
         let (line, col) = self.pos();
+        let num_str = self.scan_number_run(false);
+        parse_ascii_number(&num_str)
+            .map(|n| Token::new(TokenKind::NumberLiteral(n), num_str.clone(), line, col))
+            .ok_or(LexerError::InvalidNumber(num_str, line, col))
+    }
+    /// Consume the whole literal-looking run (digits, letters, `_`, `.`, and a sign
+    /// right after a decimal exponent) so malformed forms like `1.2.3` or `0x` are
+    /// reported as one bad literal instead of splitting into stray tokens.
+    fn scan_number_run(&mut self, glyphs: bool) -> String {
         let mut num_str = String::new();
-        let mut has_decimal = false;
         while let Some((_, ch)) = self.current {
-            if ch.is_ascii_digit() {
+            let radix_prefixed = matches!(num_str.get(..2), Some("0x" | "0X" | "0b" | "0B"));
+            let exp_sign = (ch == '+' || ch == '-')
+                && !radix_prefixed
+                && num_str.ends_with(['e', 'E'])
+                && self.peek_char().is_some_and(|c| c.is_ascii_digit());
+            if ch.is_ascii_alphanumeric() || ch == '_' || ch == '.' || exp_sign {
                 num_str.push(ch);
-                self.advance_char();
-            } else if ch == '.' && !has_decimal {
-                has_decimal = true;
-                num_str.push(ch);
-                self.advance_char();
+            } else if glyphs && is_numeric_glyph(ch) {
+                num_str.push(glyph_to_digit(ch).map(|d| (b'0' + d) as char).unwrap_or(ch));
             } else {
                 break;
             }
+            self.advance_char();
         }
         num_str
-            .parse::<f64>()
-            .map(|n| Token::new(TokenKind::NumberLiteral(n), num_str.clone(), line, col))
-            .map_err(|_| LexerError::InvalidNumber(num_str, line, col))
     }
     fn lex_glyph_number(&mut self) -> Result<Token, LexerError> {
         let (line, col) = self.pos();
@@ -550,46 +615,37 @@ impl Lexer {
     }
     fn lex_number_mixed(&mut self) -> Result<Token, LexerError> {
         let (line, col) = self.pos();
-        let mut num_str = String::new();
-        let mut has_decimal = false;
-        while let Some((_, ch)) = self.current {
-            if ch.is_ascii_digit() || is_numeric_glyph(ch) {
-                num_str.push(ch);
-                self.advance_char();
-            } else if ch == '.' && !has_decimal {
-                has_decimal = true;
-                num_str.push(ch);
-                self.advance_char();
-            } else {
-                break;
-            }
-        }
-        let ascii_str: String = num_str
-            .chars()
-            .map(|c| {
-                if is_numeric_glyph(c) {
-                    glyph_to_digit(c).map(|d| (b'0' + d) as char).unwrap_or(c)
-                } else {
-                    c
-                }
-            })
-            .collect();
-        ascii_str
-            .parse::<f64>()
+        let ascii_str = self.scan_number_run(true);
+        parse_ascii_number(&ascii_str)
             .map(|n| Token::new(TokenKind::NumberLiteral(n), ascii_str.clone(), line, col))
-            .map_err(|_| LexerError::InvalidNumber(ascii_str, line, col))
+            .ok_or(LexerError::InvalidNumber(ascii_str, line, col))
     }
     fn lex_string(&mut self) -> Result<Token, LexerError> {
         let (line, col) = self.pos();
+        let start = self.current.map(|(i, _)| i).unwrap_or(0);
         self.advance_char(); // consume opening quote
         let mut content = String::new();
+        let mut parts = Vec::new();
         let mut escape = false;
-        while let Some((_, ch)) = self.current {
+        while let Some((idx, ch)) = self.current {
             if !escape {
                 match ch {
                     '"' => {
                         self.advance_char();
-                        return Ok(Token::new(TokenKind::StringLiteral(content.clone()), content, line, col));
+                        if parts.is_empty() {
+                            return Ok(Token::new(TokenKind::StringLiteral(content.clone()), content, line, col));
+                        }
+                        if !content.is_empty() {
+                            parts.push(StringPart::Text(content));
+                        }
+                        let lexeme = self.normalized[start..=idx].to_string();
+                        return Ok(Token::new(TokenKind::InterpolatedString(parts), lexeme, line, col));
+                    }
+                    '$' if self.peek_char() == Some('{') => {
+                        if !content.is_empty() {
+                            parts.push(StringPart::Text(std::mem::take(&mut content)));
+                        }
+                        parts.push(StringPart::Expr(self.lex_interpolation()?));
                     }
                     '\\' => {
                         escape = true;
@@ -614,6 +670,7 @@ impl Lexer {
                         escape = false;
                         continue;
                     }
+                    // `\$` (so `\${` stays literal text) and anything else: the character itself
                     other => other,
                 };
                 content.push(esc_ch);
@@ -623,6 +680,33 @@ impl Lexer {
         }
         Err(LexerError::UnterminatedString(line, col))
     }
+    /// Tokens of one `${ ... }` part, with the cursor on the `$`; the closing `}` is consumed
+    /// but not returned. Braces nest and strings inside are lexed as usual, so
+    /// `"${f("}")}"` closes at the last brace.
+    fn lex_interpolation(&mut self) -> Result<Vec<Token>, LexerError> {
+        self.advance_char(); // '$'
+        let (line, col) = self.pos();
+        self.advance_char(); // '{'
+        let mut tokens = Vec::new();
+        let mut depth = 0usize;
+        loop {
+            let token = match self.next_token() {
+                Ok(Some(token)) => token,
+                Ok(None) => continue,
+                // the rest of the file was swallowed by a quote inside the braces
+                Err(LexerError::UnterminatedString(..)) => return Err(LexerError::UnterminatedInterpolation(line, col)),
+                Err(e) => return Err(e),
+            };
+            match token.kind {
+                TokenKind::EOF => return Err(LexerError::UnterminatedInterpolation(line, col)),
+                TokenKind::CloseBrace if depth == 0 => return Ok(tokens),
+                TokenKind::CloseBrace => depth -= 1,
+                TokenKind::OpenBrace => depth += 1,
+                _ => {}
+            }
+            tokens.push(token);
+        }
+    }
     fn parse_unicode_escape(&mut self) -> Result<char, LexerError> {
         if self.current.map(|(_, c)| c) != Some('{') {
             return Err(LexerError::UnexpectedCharacter(
@@ -666,10 +750,14 @@ impl Lexer {
         // distinguish declarations and control flow correctly.
         match ident.as_str() {
             "let" => Token::new(TokenKind::Let, String::from("let"), line, col),
-            "function" => Token::new(TokenKind::Function, String::from("function"), line, col),
+            "function" | "fn" => Token::new(TokenKind::Function, String::from("function"), line, col),
             "return" => Token::new(TokenKind::Return, String::from("return"), line, col),
             "log" => Token::new(TokenKind::Log, String::from("log"), line, col),
             "qubit" => Token::new(TokenKind::Qubit, String::from("qubit"), line, col),
+            "qreg" => Token::new(TokenKind::Qreg, String::from("qreg"), line, col),
+            "enum" => Token::new(TokenKind::Enum, String::from("enum"), line, col),
+            "match" => Token::new(TokenKind::Match, String::from("match"), line, col),
+            "import" => Token::new(TokenKind::Import, String::from("import"), line, col),
             "superpose" => Token::new(TokenKind::Superpose, String::from("superpose"), line, col),
             "entangle" => Token::new(TokenKind::Entangle, String::from("entangle"), line, col),
             "measure" => Token::new(TokenKind::Measure, String::from("measure"), line, col),
@@ -679,6 +767,8 @@ impl Lexer {
             "for" => Token::new(TokenKind::For, String::from("for"), line, col),
             "while" => Token::new(TokenKind::While, String::from("while"), line, col),
             "in" => Token::new(TokenKind::In, String::from("in"), line, col),
+            "break" => Token::new(TokenKind::Break, String::from("break"), line, col),
+            "continue" => Token::new(TokenKind::Continue, String::from("continue"), line, col),
             "true" => Token::new(TokenKind::BooleanLiteral(true), String::from("true"), line, col),
             "false" => Token::new(TokenKind::BooleanLiteral(false), String::from("false"), line, col),
             _ => Token::new(TokenKind::Identifier(ident.clone()), ident, line, col),
@@ -713,12 +803,14 @@ impl Lexer {
     fn match_multi_char_operator(&mut self, ch: char) -> Option<TokenKind> {
         match (ch, self.peek_char()) {
             ('=', Some('=')) => Some(TokenKind::DoubleEquals),
+            ('=', Some('>')) => Some(TokenKind::FatArrow),
             ('!', Some('=')) => Some(TokenKind::NotEquals),
             ('<', Some('=')) => Some(TokenKind::LessEqual),
             ('>', Some('=')) => Some(TokenKind::GreaterEqual),
             (':', Some('=')) => Some(TokenKind::ColonEquals),
             ('&', Some('&')) => Some(TokenKind::AndAnd),
             ('|', Some('|')) => Some(TokenKind::OrOr),
+            ('*', Some('*')) => Some(TokenKind::StarStar),
             _ => None,
         }
     }
@@ -728,15 +820,21 @@ impl Lexer {
             '-' => Some(TokenKind::Minus),
             '*' => Some(TokenKind::Star),
             '/' => Some(TokenKind::Slash),
+            '%' => Some(TokenKind::Percent),
             '=' => Some(TokenKind::Equals),
             ';' => Some(TokenKind::Semicolon),
             ',' => Some(TokenKind::Comma),
+            '.' => Some(TokenKind::Dot),
+            ':' => Some(TokenKind::Colon), // ':=' handled above
             '(' => Some(TokenKind::OpenParen),
             ')' => Some(TokenKind::CloseParen),
             '{' => Some(TokenKind::OpenBrace),
             '}' => Some(TokenKind::CloseBrace),
+            '[' => Some(TokenKind::OpenBracket),
+            ']' => Some(TokenKind::CloseBracket),
             '<' => Some(TokenKind::LessThan),
             '>' => Some(TokenKind::GreaterThan),
+            '!' => Some(TokenKind::Bang), // '!=' handled above
             '|' => Some(TokenKind::Pipe), // single '|' retained for qubit or pipe future, '||' handled above
 
             // Many specialized glyphs are represented as hieroglyphic operations
@@ -758,6 +856,44 @@ fn is_identifier_part(ch: char) -> bool {
 fn is_safe_whitespace(ch: char) -> bool {
     matches!(ch, ' ' | '\t' | '\r' | '\n' | '\u{FEFF}')
 }
+/// Value of an ASCII numeric literal: decimal with optional fraction and exponent
+/// (`1.5e-9`), or `0x`/`0b` integers. `_` is allowed only between two digits.
+fn parse_ascii_number(s: &str) -> Option<f64> {
+    fn digits(s: &str, radix: u32) -> Option<String> {
+        let ok = !s.is_empty()
+            && !s.starts_with('_')
+            && !s.ends_with('_')
+            && !s.contains("__")
+            && s.chars().all(|c| c == '_' || c.is_digit(radix));
+        ok.then(|| s.replace('_', ""))
+    }
+    let radix = match s.get(..2) {
+        Some("0x" | "0X") => Some(16),
+        Some("0b" | "0B") => Some(2),
+        _ => None,
+    };
+    if let Some(radix) = radix {
+        return u64::from_str_radix(&digits(&s[2..], radix)?, radix).ok().map(|n| n as f64);
+    }
+    let (mantissa, exponent) = match s.find(['e', 'E']) {
+        Some(i) => (&s[..i], Some(&s[i + 1..])),
+        None => (s, None),
+    };
+    let mut text = match mantissa.split_once('.') {
+        Some((int, "")) => digits(int, 10)?,
+        Some((int, frac)) => format!("{}.{}", digits(int, 10)?, digits(frac, 10)?),
+        None => digits(mantissa, 10)?,
+    };
+    if let Some(exp) = exponent {
+        let (sign, exp) = match exp.strip_prefix(['+', '-']) {
+            Some(rest) => (&exp[..1], rest),
+            None => ("", exp),
+        };
+        text = format!("{}e{}{}", text, sign, digits(exp, 10)?);
+    }
+    text.parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
 fn is_numeric_glyph(ch: char) -> bool {
     (0x1D360..=0x1D369).contains(&(ch as u32))
 }
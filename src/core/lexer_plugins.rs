@@ -0,0 +1,111 @@
+//! Built-in optional lexer plugins, enabled by name through `LexerOptions::plugins`
+//! (the CLI reads the names from `[lexer.plugins]` in `Aeonmi.toml`).
+//!
+//! * `emoji-ops` — registers a custom token kind mapping ➕ ➖ ✖ ➗ 🟰 to `+ - * / ==`.
+//! * `dlp-redactor` — rejects string literals that look like credentials; the error
+//!   names the kind of secret but never echoes the literal.
+use std::sync::Arc;
+
+use crate::core::lexer::{CustomTokenKind, Lexer, LexerPlugin, LexerView};
+use crate::core::token::{StringPart, Token, TokenKind};
+
+/// Names accepted by [`install`].
+pub const AVAILABLE: &[&str] = &["emoji-ops", "dlp-redactor"];
+
+/// Install the built-in plugin `name` into `lexer`.
+pub fn install(lexer: &mut Lexer, name: &str) -> Result<(), String> {
+    match name {
+        "emoji-ops" => lexer.register_custom_token_kind(Arc::new(EmojiOps)),
+        "dlp-redactor" => lexer.add_plugin(DlpRedactor::default()),
+        _ => return Err(unknown(name)),
+    }
+    Ok(())
+}
+
+/// Check plugin names up front (e.g. while loading config) with the same message `install` gives.
+pub fn validate(names: &[String]) -> Result<(), String> {
+    match names.iter().find(|n| !AVAILABLE.contains(&n.as_str())) {
+        Some(n) => Err(unknown(n)),
+        None => Ok(()),
+    }
+}
+
+fn unknown(name: &str) -> String {
+    format!("unknown lexer plugin '{}' (available: {})", name, AVAILABLE.join(", "))
+}
+
+struct EmojiOps;
+
+impl CustomTokenKind for EmojiOps {
+    fn try_match(&self, lexer: &Lexer) -> Option<TokenKind> {
+        match lexer.current_char()? {
+            '➕' => Some(TokenKind::Plus),
+            '➖' => Some(TokenKind::Minus),
+            '✖' => Some(TokenKind::Star),
+            '➗' => Some(TokenKind::Slash),
+            '🟰' => Some(TokenKind::DoubleEquals),
+            _ => None,
+        }
+    }
+    fn name(&self) -> &str {
+        "emoji-ops"
+    }
+}
+
+#[derive(Default)]
+struct DlpRedactor {
+    error: Option<String>,
+}
+
+impl LexerPlugin for DlpRedactor {
+    fn name(&self) -> &str {
+        "dlp-redactor"
+    }
+    fn after_token(&mut self, _view: LexerView, token: &Token) {
+        let text = match &token.kind {
+            TokenKind::StringLiteral(s) => s.clone(),
+            // `${...}` parts were checked as tokens of their own
+            TokenKind::InterpolatedString(parts) => parts
+                .iter()
+                .filter_map(|p| match p {
+                    StringPart::Text(s) => Some(s.as_str()),
+                    StringPart::Expr(_) => None,
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+            _ => return,
+        };
+        if let Some(kind) = secret_kind(&text) {
+            self.error = Some(format!(
+                "string literal looks like {} (redacted); read it with env() instead",
+                kind
+            ));
+        }
+    }
+    fn take_error(&mut self) -> Option<String> {
+        self.error.take()
+    }
+}
+
+/// Which credential shape `s` contains, if any. Also the DLP check in front of the clipboard.
+pub fn secret_kind(s: &str) -> Option<&'static str> {
+    if s.contains("-----BEGIN") && s.contains("PRIVATE KEY") {
+        return Some("a private key");
+    }
+    s.split(|c: char| !c.is_ascii_alphanumeric() && c != '-' && c != '_')
+        .find_map(|word| {
+            let tail = |prefix: &str| word.strip_prefix(prefix).map(str::len).unwrap_or(0);
+            if word.len() == 20
+                && word.starts_with("AKIA")
+                && word.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+            {
+                Some("an AWS access key id")
+            } else if tail("sk-") >= 20 {
+                Some("an API secret key")
+            } else if tail("ghp_") >= 30 {
+                Some("a GitHub token")
+            } else {
+                None
+            }
+        })
+}
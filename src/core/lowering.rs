@@ -5,7 +5,33 @@ use crate::core::TokenKind; // Import TokenKind from the core module
 
 // Real AST -> IR lowering
 
+/// Lower `program`; in debug builds and under `--verify-ir` the result is checked by
+/// [`crate::core::ir_verify`] and a violation is an internal compiler error.
 pub fn lower_ast_to_ir(program: &crate::core::ast::ASTNode, name: &str) -> Result<Module, String> {
+    lower_ast_to_ir_with_hook(program, name, |_| {})
+}
+
+/// Lowering with `hook` run on the module before verification. Exists so tests can
+/// stand in for a buggy lowering; nothing else should need it.
+#[doc(hidden)]
+pub fn lower_ast_to_ir_with_hook(
+    program: &crate::core::ast::ASTNode,
+    name: &str,
+    hook: impl FnOnce(&mut Module),
+) -> Result<Module, String> {
+    let mut m = lower_unverified(program, name)?;
+    hook(&mut m);
+    if crate::core::ir_verify::enabled() {
+        let violations = crate::core::ir_verify::verify(&m);
+        if !violations.is_empty() {
+            return Err(crate::core::ir_verify::internal_error(&violations));
+        }
+    }
+    Ok(m)
+}
+
+/// Lowering without the verifier, for `aeonmi ir`, which reports violations itself.
+pub fn lower_unverified(program: &crate::core::ast::ASTNode, name: &str) -> Result<Module, String> {
     use crate::core::ast::ASTNode;
 
     // Expect a Program at the top; if not, wrap as single-item program.
@@ -34,6 +60,13 @@ pub fn lower_ast_to_ir(program: &crate::core::ast::ASTNode, name: &str) -> Resul
                     body: Block { stmts },
                 }));
             }
+            // Top-level enums are consts so every function can name them, like functions.
+            ASTNode::EnumDecl { name: enum_name, variants, .. } => {
+                decls.push(Decl::Const(ConstDecl {
+                    value: enum_value(&enum_name, &variants),
+                    name: enum_name,
+                }));
+            }
             // All other top-level items go into main function
             other => {
                 let stmt = lower_stmt_ast(&other)?;
@@ -63,25 +96,28 @@ fn lower_stmt_ast(n: &crate::core::ast::ASTNode) -> Result<Stmt, String> {
     use crate::core::ast::ASTNode as A;
 
     Ok(match n {
-        A::Block(items) => {
-            // Blocks are handled by `lower_block_ast` at call sites; produce a no-op here.
-            let _ = items;
-            Stmt::Expr(Expr::Object(vec![]))
-        }
+        A::Block(_) => Stmt::Block(lower_block_ast(n)?),
 
         A::Return(expr) => Stmt::Return(Some(lower_expr_ast(expr)?)),
-        A::Log(expr) => Stmt::Expr(Expr::Call {
-            callee: Box::new(Expr::Ident("log".into())),
+        A::Log { expr, line, column } => Stmt::Log {
             args: vec![lower_expr_ast(expr)?],
-        }),
-    A::Assignment { name, value, .. } => Stmt::Assign {
+            line: *line,
+            column: *column,
+        },
+    A::Assignment { name, value, line, column } => Stmt::Assign {
             target: Expr::Ident(name.clone()),
             value: lower_expr_ast(value)?,
+            line: *line,
+            column: *column,
         },
 
         A::Call { .. }
         | A::BinaryExpr { .. }
         | A::UnaryExpr { .. }
+        | A::Index { .. }
+        | A::Member { .. }
+        | A::ArrayLiteral { .. }
+        | A::ObjectLiteral { .. }
         | A::Identifier(_)
         | A::NumberLiteral(_)
         | A::StringLiteral(_)
@@ -103,21 +139,69 @@ fn lower_stmt_ast(n: &crate::core::ast::ASTNode) -> Result<Stmt, String> {
             Stmt::For {
                 init: init_stmt.map(Box::new),
                 cond: condition.as_ref().map(|b| lower_expr_ast(b)).transpose()?,
-                step: increment.as_ref().map(|b| lower_expr_ast(b)).transpose()?,
+                step: increment.as_ref().map(|b| lower_stmt_init_ast(b)).transpose()?.map(Box::new),
                 body: lower_block_ast(body)?,
             }
         }
 
+        A::ForIn { key, var, iterable, body, line, column } => lower_for_in_ast(key.as_deref(), var, iterable, body, *line, *column)?,
+        A::Break { .. } => Stmt::Break,
+        A::Continue { .. } => Stmt::Continue,
+
         // Decls at statement position
-    A::VariableDecl { name, value, .. } => Stmt::Let {
+    A::VariableDecl { name, value, line, column } => Stmt::Let {
             name: name.clone(),
             value: Some(lower_expr_ast(value)?),
+            line: *line,
+            column: *column,
         },
 
+        A::DestructuringDecl { targets, values, line, column } => Stmt::Destructure {
+            pattern: Pattern::Array(targets.iter().map(lower_pattern_ast).collect()),
+            value: Expr::Array(values.iter().map(lower_expr_ast).collect::<Result<Vec<_>, _>>()?),
+            line: *line,
+            column: *column,
+        },
+
+        A::Import { path, line, column } => {
+            return Err(format!("import \"{path}\" was not resolved before lowering (line {line}, column {column})"))
+        }
+
+        A::EnumDecl { name, variants, line, column } => Stmt::Let {
+            name: name.clone(),
+            value: Some(enum_value(name, variants)),
+            line: *line,
+            column: *column,
+        },
+
+        A::Match { subject, arms, line, column } => lower_match_ast(subject, arms, *line, *column)?,
+
         // Function within a statement position: ignore/emit no-op (top-level handled elsewhere).
         A::Function { .. } => Stmt::Expr(Expr::Object(vec![])),
 
-        A::QuantumOp { op, qubits } => {
+        // Allocates a |0> qubit in the VM's quantum register, keyed by its name.
+        A::QubitDecl { name, line, column } => Stmt::Let {
+            name: name.clone(),
+            line: *line,
+            column: *column,
+            value: Some(Expr::Call {
+                callee: Box::new(Expr::Ident("__qubit".into())),
+                args: vec![Expr::Lit(Lit::String(name.clone()))],
+            }),
+        },
+
+        // A register is an array of qubit handles labelled `r[0]`..; indexing is bounds-checked by the VM.
+        A::QregDecl { name, size, line, column } => Stmt::Let {
+            name: name.clone(),
+            line: *line,
+            column: *column,
+            value: Some(Expr::Call {
+                callee: Box::new(Expr::Ident("__qreg".into())),
+                args: vec![Expr::Lit(Lit::String(name.clone())), Expr::Lit(Lit::Number(*size as f64))],
+            }),
+        },
+
+        A::QuantumOp { op, qubits, .. } => {
             let (fname, args) = map_quantum_op(op, qubits)?;
             Stmt::Expr(Expr::Call {
                 callee: Box::new(Expr::Ident(fname)),
@@ -143,6 +227,134 @@ fn lower_stmt_ast(n: &crate::core::ast::ASTNode) -> Result<Stmt, String> {
     })
 }
 
+fn lower_pattern_ast(p: &crate::core::ast::Pattern) -> Pattern {
+    use crate::core::ast::Pattern as P;
+    match p {
+        P::Name { name, .. } => Pattern::Name(name.clone()),
+        P::Array { items, .. } => Pattern::Array(items.iter().map(lower_pattern_ast).collect()),
+    }
+}
+
+/// `enum E { A, B }` evaluates to `__enum("E", "A", "B")`, the VM's enum value.
+fn enum_value(name: &str, variants: &[String]) -> Expr {
+    let mut args = vec![Expr::Lit(Lit::String(name.to_string()))];
+    args.extend(variants.iter().map(|v| Expr::Lit(Lit::String(v.clone()))));
+    Expr::Call { callee: Box::new(Expr::Ident("__enum".into())), args }
+}
+
+/// `push(xs, v)` and `pop(xs)` change the array held by the variable `xs`, so they lower
+/// to `__push("xs", v)` / `__pop("xs", line, column)`, which update that variable in
+/// place (the position is for `pop`'s empty-array error). Anything but a variable as the
+/// first argument is rejected: the VM would change a copy where JS changes the original.
+fn lower_array_mutation(
+    callee: &crate::core::ast::ASTNode,
+    args: &[crate::core::ast::ASTNode],
+) -> Result<Option<Expr>, String> {
+    use crate::core::ast::ASTNode as A;
+    let (name, line, column) = match callee {
+        A::Identifier(name) => (name, 0, 0),
+        A::IdentifierSpanned { name, line, column, .. } => (name, *line, *column),
+        _ => return Ok(None),
+    };
+    let (builtin, arity) = match name.as_str() {
+        "push" => ("__push", 2),
+        "pop" => ("__pop", 1),
+        _ => return Ok(None),
+    };
+    let at = if line > 0 { format!(" (line {line}, column {column})") } else { String::new() };
+    if args.len() != arity {
+        return Err(format!("`{name}` expects {arity} argument{}, got {}{at}", if arity == 1 { "" } else { "s" }, args.len()));
+    }
+    let var = match &args[0] {
+        A::Identifier(v) | A::IdentifierSpanned { name: v, .. } => v.clone(),
+        _ => return Err(format!("`{name}` needs a variable as its first argument{at}")),
+    };
+    let mut lowered = vec![Expr::Lit(Lit::String(var))];
+    if builtin == "__push" {
+        lowered.push(lower_expr_ast(&args[1])?);
+    } else {
+        lowered.extend([line, column].map(|n| Expr::Lit(Lit::Number(n as f64))));
+    }
+    Ok(Some(Expr::Call { callee: Box::new(Expr::Ident(builtin.into())), args: lowered }))
+}
+
+/// `for x in xs { body }` becomes an index loop over `__iter(xs)` (elements, characters
+/// or sorted map keys); `for k, v in xs` loops over `__iter_keys(xs)` and reads `v` as
+/// `xs[k]`. The body is a nested block, so `continue` still runs the step.
+fn lower_for_in_ast(
+    key: Option<&str>,
+    var: &str,
+    iterable: &crate::core::ast::ASTNode,
+    body: &crate::core::ast::ASTNode,
+    line: usize,
+    column: usize,
+) -> Result<Stmt, String> {
+    const SOURCE: &str = "__for_src";
+    const ITEMS: &str = "__for_items";
+    const INDEX: &str = "__for_i";
+    let ident = |n: &str| Expr::Ident(n.into());
+    let call = |f: &str, arg: Expr| Expr::Call { callee: Box::new(ident(f)), args: vec![arg] };
+    let index = |target: Expr, i: Expr| Expr::Index { target: Box::new(target), index: Box::new(i) };
+    let let_ = |name: &str, value: Expr| Stmt::Let { name: name.into(), value: Some(value), line, column };
+    let current = index(ident(ITEMS), ident(INDEX));
+    let mut stmts = match key {
+        None => vec![let_(var, current)],
+        Some(k) => vec![let_(k, current.clone()), let_(var, index(ident(SOURCE), current))],
+    };
+    stmts.push(Stmt::Block(lower_block_ast(body)?));
+    let step = Stmt::Assign {
+        target: ident(INDEX),
+        value: Expr::Binary { left: Box::new(ident(INDEX)), op: BinOp::Add, right: Box::new(Expr::Lit(Lit::Number(1.0))) },
+        line,
+        column,
+    };
+    let items = call(if key.is_some() { "__iter_keys" } else { "__iter" }, ident(SOURCE));
+    Ok(Stmt::Block(Block {
+        stmts: vec![
+            let_(SOURCE, lower_expr_ast(iterable)?),
+            let_(ITEMS, items),
+            Stmt::For {
+                init: Some(Box::new(let_(INDEX, Expr::Lit(Lit::Number(0.0))))),
+                cond: Some(Expr::Binary { left: Box::new(ident(INDEX)), op: BinOp::Lt, right: Box::new(call("len", ident(ITEMS))) }),
+                step: Some(Box::new(step)),
+                body: Block { stmts },
+            },
+        ],
+    }))
+}
+
+/// `match` becomes a block that binds the subject once, then an `if`/`else if` chain
+/// testing each pattern with `==` in order; a `_` arm is the final `else`.
+fn lower_match_ast(
+    subject: &crate::core::ast::ASTNode,
+    arms: &[crate::core::ast::MatchArm],
+    line: usize,
+    column: usize,
+) -> Result<Stmt, String> {
+    const SUBJECT: &str = "__match";
+    let mut chain: Option<Block> = None;
+    for arm in arms.iter().rev() {
+        let body = lower_block_ast(&arm.body)?;
+        chain = Some(match &arm.pattern {
+            None => body,
+            Some(p) => Block {
+                stmts: vec![Stmt::If {
+                    cond: Expr::Binary {
+                        left: Box::new(Expr::Ident(SUBJECT.into())),
+                        op: BinOp::Eq,
+                        right: Box::new(lower_expr_ast(p)?),
+                    },
+                    then_block: body,
+                    else_block: chain,
+                }],
+            },
+        });
+    }
+    let mut stmts = vec![Stmt::Let { name: SUBJECT.into(), value: Some(lower_expr_ast(subject)?), line, column }];
+    stmts.extend(chain.map(|b| b.stmts).unwrap_or_default());
+    Ok(Stmt::Block(Block { stmts }))
+}
+
 fn lower_block_ast(n: &crate::core::ast::ASTNode) -> Result<Block, String> {
     use crate::core::ast::ASTNode as A;
     match n {
@@ -160,13 +372,17 @@ fn lower_block_ast(n: &crate::core::ast::ASTNode) -> Result<Block, String> {
 fn lower_stmt_init_ast(n: &crate::core::ast::ASTNode) -> Result<Stmt, String> {
     use crate::core::ast::ASTNode as A;
     Ok(match n {
-    A::VariableDecl { name, value, .. } => Stmt::Let {
+    A::VariableDecl { name, value, line, column } => Stmt::Let {
             name: name.clone(),
             value: Some(lower_expr_ast(value)?),
+            line: *line,
+            column: *column,
         },
-    A::Assignment { name, value, .. } => Stmt::Assign {
+    A::Assignment { name, value, line, column } => Stmt::Assign {
             target: Expr::Ident(name.clone()),
             value: lower_expr_ast(value)?,
+            line: *line,
+            column: *column,
         },
         A::Return(expr) => Stmt::Return(Some(lower_expr_ast(expr)?)),
         _ => Stmt::Expr(lower_expr_ast(n)?),
@@ -182,6 +398,8 @@ fn lower_expr_ast(n: &crate::core::ast::ASTNode) -> Result<Expr, String> {
         A::StringLiteral(s) => Expr::Lit(Lit::String(s.clone())),
         A::BooleanLiteral(b) => Expr::Lit(Lit::Bool(*b)),
 
+        // unary `+` is the identity
+        A::UnaryExpr { op: TokenKind::Plus, expr } => lower_expr_ast(expr)?,
         A::UnaryExpr { op, expr } => Expr::Unary {
             op: map_unop_token(op),
             expr: Box::new(lower_expr_ast(expr)?),
@@ -193,20 +411,42 @@ fn lower_expr_ast(n: &crate::core::ast::ASTNode) -> Result<Expr, String> {
             right: Box::new(lower_expr_ast(right)?),
         },
 
+        A::Index { target, index, .. } => Expr::Index {
+            target: Box::new(lower_expr_ast(target)?),
+            index: Box::new(lower_expr_ast(index)?),
+        },
+
+        A::Member { target, name, .. } => Expr::Member {
+            target: Box::new(lower_expr_ast(target)?),
+            name: name.clone(),
+        },
+
+        A::ArrayLiteral { items, .. } => Expr::Array(items.iter().map(lower_expr_ast).collect::<Result<Vec<_>, _>>()?),
+
+        A::ObjectLiteral { fields, .. } => Expr::Object(
+            fields
+                .iter()
+                .map(|(k, v)| Ok((k.clone(), lower_expr_ast(v)?)))
+                .collect::<Result<Vec<_>, String>>()?,
+        ),
+
         // Assignment is not an expression in IR; degrade to a no-op value.
         A::Assignment { .. } => Expr::Object(vec![]),
 
-        A::Call { callee, args } => Expr::Call {
-            callee: Box::new(lower_expr_ast(callee)?),
-            args: args.iter().map(|a| lower_expr_ast(a)).collect::<Result<Vec<_>, _>>()?,
+        A::Call { callee, args } => match lower_array_mutation(callee, args)? {
+            Some(call) => call,
+            None => Expr::Call {
+                callee: Box::new(lower_expr_ast(callee)?),
+                args: args.iter().map(|a| lower_expr_ast(a)).collect::<Result<Vec<_>, _>>()?,
+            },
         },
 
-        A::Log(e) => Expr::Call {
+        A::Log { expr: e, .. } => Expr::Call {
             callee: Box::new(Expr::Ident("log".into())),
             args: vec![lower_expr_ast(e)?],
         },
 
-        A::QuantumOp { op, qubits } => {
+        A::QuantumOp { op, qubits, .. } => {
             let (fname, args) = map_quantum_op(op, qubits)?;
             Expr::Call { callee: Box::new(Expr::Ident(fname)), args }
         }
@@ -229,8 +469,17 @@ fn lower_expr_ast(n: &crate::core::ast::ASTNode) -> Result<Expr, String> {
         | A::If { .. }
         | A::While { .. }
         | A::For { .. }
+        | A::ForIn { .. }
+        | A::Break { .. }
+        | A::Continue { .. }
         | A::Function { .. }
         | A::VariableDecl { .. }
+        | A::DestructuringDecl { .. }
+        | A::EnumDecl { .. }
+        | A::Import { .. }
+        | A::Match { .. }
+        | A::QubitDecl { .. }
+        | A::QregDecl { .. }
         | A::Return(_)
         | A::Program(_) => Expr::Object(vec![]),
 
@@ -245,7 +494,7 @@ fn lower_expr_ast(n: &crate::core::ast::ASTNode) -> Result<Expr, String> {
 fn map_binop(op: &str) -> BinOp {
     use BinOp::*;
     match op {
-        "+" => Add, "-" => Sub, "*" => Mul, "/" => Div, "%" => Mod,
+        "+" => Add, "-" => Sub, "*" => Mul, "/" => Div, "%" => Mod, "**" => Pow,
         "==" => Eq, "!=" => Ne, "<" => Lt, "<=" => Le, ">" => Gt, ">=" => Ge,
         "&&" => And, "||" => Or,
         _ => { eprintln!("[lowering] unknown binop `{}` -> Eq", op); Eq }
@@ -274,12 +523,16 @@ fn map_binop_token(tok: &TokenKind) -> BinOp {
         TokenKind::Minus => Sub,
         TokenKind::Star => Mul,
         TokenKind::Slash => Div,
+        TokenKind::Percent => Mod,
+        TokenKind::StarStar => Pow,
         TokenKind::DoubleEquals => Eq,
         TokenKind::NotEquals => Ne,
         TokenKind::LessThan => Lt,
         TokenKind::LessEqual => Le,
         TokenKind::GreaterThan => Gt,
         TokenKind::GreaterEqual => Ge,
+        TokenKind::AndAnd => And,
+        TokenKind::OrOr => Or,
         _ => { eprintln!("[lowering] unmapped token binop `{:?}` -> Eq", tok); Eq }
     }
 }
@@ -288,7 +541,7 @@ fn map_binop_token(tok: &TokenKind) -> BinOp {
 fn map_unop_token(tok: &TokenKind) -> UnOp {
     match tok {
         TokenKind::Minus => UnOp::Neg,
-        _ => UnOp::Not,
+        _ => UnOp::Not, // `!`; unary `+` never reaches here
     }
 }
 
@@ -3,32 +3,62 @@
 
 pub mod ai_emitter;
 pub mod ai_provider;
+pub mod ai_usage;
+pub mod analysis_budget; // `--analysis-timeout-ms` and the GUI: stop analysis between top-level items past a deadline
 pub mod ast;
+pub mod build_info; // `aeonmi info --json` / GUI `aeonmi_version`: version, commit, features, config paths
 pub mod code_generator;
 pub mod code_actions;
+pub mod clipboard; // `ai chat --copy` / `:copy`: system clipboard with a temp-file fallback
+pub mod columns; // byte columns <-> display columns / raw (non-NFC) source offsets
 pub mod compiler;
+pub mod completion; // editor completions (keywords, scope symbols, builtins, enum members)
+pub mod diag_sidecar; // `emit --diag-sidecar`: compile-time diagnostics next to the output
+pub mod deps_manifest; // `run` / `build`: <out>.deps.json, the sources an artifact was built from
 pub mod diagnostics;
 pub mod error;
+pub mod error_index;
+pub mod examples;
 pub mod formatter;
+pub mod fs_allow; // GUI load_file/save_file: allowed roots, path resolution, write audit log
+pub mod highlight;
+pub mod imports; // `import "x.ai";`: resolve relative to the importer, flatten before analysis
 pub mod ir;
+pub mod ir_verify; // IR invariants, checked after lowering (debug builds, --verify-ir, `aeonmi ir`)
 pub mod lexer;
+pub mod lexer_plugins;
 pub mod lowering;
 pub mod incremental;
 pub mod parser;
+pub mod preview;
+pub mod py_emitter;
 pub mod qpoly;
 pub mod quantum_extract;
+pub mod quantum_lint;
+pub mod quantum_templates;
+pub mod run_bundle; // `quantum run --save-run` bundles and `quantum compare`
 pub mod artifact_cache;
 pub mod api_keys;
 pub mod semantic_analyzer;
+pub mod source_map; // `emit/run --source-map`: V3 maps from generated JS back to the .ai source
+pub mod shutdown; // Ctrl-C: graceful stop for watch/serve loops, force exit on a second press
+pub mod state_inspect; // `qstate` in the shell: marginals and Bloch vectors of a statevector
+pub mod style;
 pub mod symbols;
 pub mod scope_map;
 pub mod types;
 pub mod titan;
 pub mod token;
+pub mod toolchain;
+pub mod usage_stats; // opt-in, local-only subcommand counters (`aeonmi stats`)
 pub use token::TokenKind; // Re-export only TokenKind; Token not needed externally currently
 #[macro_use]
-pub mod debug; // gated debug logging (AEONMI_DEBUG=1) provides debug_log! macro
+pub mod debug; // gated logging (-v/-vv or AEONMI_DEBUG=1|2): debug_log! / trace_log!
 pub mod vm;
+pub mod vm_display;
+pub mod vm_quantum;
+pub mod vm_trace;
+pub mod vm_snapshot;
 
 #[cfg(feature = "bytecode")]
 pub mod bytecode;
@@ -1,18 +1,50 @@
 //! Parser for Aeonmi/QUBE/Titan with precedence parsing + spanned errors.
 
-use crate::core::ast::{ASTNode, FunctionParam};
-use crate::core::token::{Token, TokenKind};
+use crate::core::ast::{ASTNode, FunctionParam, MatchArm, Pattern};
+use crate::core::diagnostics::{Span, Suggestion};
+use crate::core::semantic_analyzer::RelatedInfo;
+use crate::core::token::{StringPart, Token, TokenKind};
 
 #[derive(Debug, Clone)]
 pub struct ParserError {
+    /// What the parser was after, e.g. "Expected ';' after variable declaration".
     pub message: String,
     pub line: usize,
     pub column: usize,
+    /// Boxed so that every `Result<_, ParserError>` stays small on the happy path.
+    pub detail: Box<ParserErrorDetail>,
+}
+
+/// What a [`ParserError`] knows beyond its position, for the expected-set message and hints.
+#[derive(Debug, Clone, Default)]
+pub struct ParserErrorDetail {
+    /// Everything that would have been accepted here, described for humans ("';'", "an operator").
+    pub expected: Vec<String>,
+    /// The offending token as written, or "end of file".
+    pub found: Option<String>,
+    /// Likely fix for a common mistake (missing ';', unclosed '{', '=' for '==').
+    pub hint: Option<String>,
+    /// Other locations the hint refers to, e.g. the '{' that is never closed.
+    pub related: Vec<RelatedInfo>,
+    /// The hint as an edit, when the fix is certain (the missing ';', '==' for '=').
+    pub suggestion: Option<Suggestion>,
+}
+
+impl ParserError {
+    /// "expected one of: ';', an operator — found 'let'"; the message when nothing was expected.
+    pub fn summary(&self) -> String {
+        let found = self.detail.found.as_ref().map(|f| format!(" — found {f}")).unwrap_or_default();
+        match self.detail.expected.as_slice() {
+            [] => self.message.clone(),
+            [one] => format!("expected {one}{found}"),
+            many => format!("expected one of: {}{found}", many.join(", ")),
+        }
+    }
 }
 
 impl std::fmt::Display for ParserError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} at {}:{}", self.message, self.line, self.column)
+        write!(f, "{} at {}:{}", self.summary(), self.line, self.column)
     }
 }
 
@@ -21,6 +53,11 @@ impl std::error::Error for ParserError {}
 pub struct Parser {
     tokens: Vec<Token>,
     pos: usize,
+    /// Loops enclosing the current statement, for `break`/`continue`.
+    loop_depth: usize,
+    /// Token kinds tried by `check` at `expected_at`, for "expected one of" errors.
+    expected: Vec<TokenKind>,
+    expected_at: usize,
 }
 
 impl Parser {
@@ -33,14 +70,18 @@ impl Parser {
         if needs_eof {
             tokens.push(Token { kind: TokenKind::EOF, lexeme: String::new(), line: 0, column: 0 });
         }
-        Parser { tokens, pos: 0 }
+        Parser { tokens, pos: 0, loop_depth: 0, expected: Vec::new(), expected_at: 0 }
     }
 
     /// Main parse entrypoint: parses all tokens into program AST
     pub fn parse(&mut self) -> Result<ASTNode, ParserError> {
         let mut nodes = Vec::new();
         while !self.is_at_end() {
-            nodes.push(self.parse_statement()?);
+            let node = match self.peek().kind {
+                TokenKind::Import => self.parse_import()?,
+                _ => self.parse_statement()?,
+            };
+            nodes.push(node);
         }
         Ok(ASTNode::Program(nodes))
     }
@@ -55,7 +96,13 @@ impl Parser {
             TokenKind::If => self.parse_if(),
             TokenKind::While => self.parse_while(),
             TokenKind::For => self.parse_for(),
+            TokenKind::Enum => self.parse_enum_decl(),
+            TokenKind::Match => self.parse_match(),
+            TokenKind::Import => Err(self.err_at("'import' is only allowed at the top level of a file", self.peek().line, self.peek().column)),
+            TokenKind::Break | TokenKind::Continue => self.parse_loop_jump(),
             TokenKind::OpenBrace => Ok(self.parse_block()?),
+            TokenKind::Qubit => self.parse_qubit_decl(),
+            TokenKind::Qreg => self.parse_qreg_decl(),
             TokenKind::Superpose | TokenKind::Entangle | TokenKind::Measure | TokenKind::Dod => {
                 self.parse_quantum_op()
             }
@@ -82,6 +129,9 @@ impl Parser {
         self.consume(TokenKind::Let, "Expected 'let'")?;
         let line = self.peek().line;
         let column = self.peek().column;
+        if self.check(&TokenKind::OpenBracket) || self.peek_next().kind == TokenKind::Comma {
+            return self.parse_destructuring_decl(line, column);
+        }
         let name = self.consume_identifier("Expected variable name")?;
         self.consume(TokenKind::Equals, "Expected '=' in variable declaration")?;
         let value = self.parse_expression()?;
@@ -89,6 +139,46 @@ impl Parser {
         Ok(ASTNode::new_variable_decl_at(&name, value, line, column))
     }
 
+    /// `let a, [b, c] = e1, e2;` after the `let`: as many values as targets.
+    fn parse_destructuring_decl(&mut self, line: usize, column: usize) -> Result<ASTNode, ParserError> {
+        let mut targets = vec![self.parse_pattern()?];
+        while self.match_token(&[TokenKind::Comma]) {
+            targets.push(self.parse_pattern()?);
+        }
+        self.consume(TokenKind::Equals, "Expected '=' in variable declaration")?;
+        let mut values = vec![self.parse_expression()?];
+        while self.match_token(&[TokenKind::Comma]) {
+            values.push(self.parse_expression()?);
+        }
+        if values.len() != targets.len() {
+            let msg = format!("Expected {} values after '=', found {}", targets.len(), values.len());
+            return Err(self.err_at(&msg, line, column));
+        }
+        self.consume(TokenKind::Semicolon, "Expected ';' after variable declaration")?;
+        let mut seen = std::collections::HashSet::new();
+        for (name, l, c) in targets.iter().flat_map(Pattern::names) {
+            if !seen.insert(name) {
+                return Err(self.err_at(&format!("'{}' is bound twice in one declaration", name), l, c));
+            }
+        }
+        Ok(ASTNode::new_destructuring_decl_at(targets, values, line, column))
+    }
+
+    /// A name or a bracketed list of patterns.
+    fn parse_pattern(&mut self) -> Result<Pattern, ParserError> {
+        let (line, column) = (self.peek().line, self.peek().column);
+        if !self.match_token(&[TokenKind::OpenBracket]) {
+            let name = self.consume_identifier("Expected a variable name or '[' in destructuring pattern")?;
+            return Ok(Pattern::Name { name, line, column });
+        }
+        let mut items = vec![self.parse_pattern()?];
+        while self.match_token(&[TokenKind::Comma]) {
+            items.push(self.parse_pattern()?);
+        }
+        self.consume(TokenKind::CloseBracket, "Expected ']' after destructuring pattern")?;
+        Ok(Pattern::Array { items, line, column })
+    }
+
     fn parse_function_decl(&mut self) -> Result<ASTNode, ParserError> {
     let func_tok = self.consume(TokenKind::Function, "Expected 'function'")?;
     let func_line = func_tok.line; let func_col = func_tok.column;
@@ -106,7 +196,10 @@ impl Parser {
             }
         }
         self.consume(TokenKind::CloseParen, "Expected ')' after parameters")?;
-        let body = match self.parse_block()? {
+        let outer_loops = std::mem::take(&mut self.loop_depth);
+        let body = self.parse_block();
+        self.loop_depth = outer_loops;
+        let body = match body? {
             ASTNode::Block(stmts) => stmts,
             _ => return Err(self.err_here("Function body must be a block")),
         };
@@ -121,18 +214,19 @@ impl Parser {
     }
 
     fn parse_log(&mut self) -> Result<ASTNode, ParserError> {
+        let (line, column) = (self.peek().line, self.peek().column);
         self.consume(TokenKind::Log, "Expected 'log'")?;
         self.consume(TokenKind::OpenParen, "Expected '(' after log")?;
         let expr = self.parse_expression()?;
         self.consume(TokenKind::CloseParen, "Expected ')' after log arg")?;
         self.consume(TokenKind::Semicolon, "Expected ';' after log")?;
-        Ok(ASTNode::new_log(expr))
+        Ok(ASTNode::new_log_at(expr, line, column))
     }
 
     fn parse_if(&mut self) -> Result<ASTNode, ParserError> {
         self.consume(TokenKind::If, "Expected 'if'")?;
         self.consume(TokenKind::OpenParen, "Expected '(' after if")?;
-        let cond = self.parse_expression()?;
+        let cond = self.parse_condition(TokenKind::CloseParen)?;
         self.consume(TokenKind::CloseParen, "Expected ')' after condition")?;
         let then_branch = self.parse_statement()?;
         let else_branch = if self.match_token(&[TokenKind::Else]) {
@@ -146,14 +240,52 @@ impl Parser {
     fn parse_while(&mut self) -> Result<ASTNode, ParserError> {
         self.consume(TokenKind::While, "Expected 'while'")?;
         self.consume(TokenKind::OpenParen, "Expected '(' after while")?;
-        let cond = self.parse_expression()?;
+        let cond = self.parse_condition(TokenKind::CloseParen)?;
         self.consume(TokenKind::CloseParen, "Expected ')' after condition")?;
-        let body = self.parse_statement()?;
+        let body = self.parse_loop_body(false)?;
         Ok(ASTNode::new_while(cond, body))
     }
 
+    /// A condition ending at `end`. A lone `=` there is a typo for `==` far more often than an
+    /// intended assignment, so it is rejected rather than parsed as one.
+    fn parse_condition(&mut self, end: TokenKind) -> Result<ASTNode, ParserError> {
+        let cond = self.parse_logical_or()?;
+        if self.peek().kind == TokenKind::Equals && !self.check(&end) {
+            let mut err = self.err_expecting("Assignment used as a condition", self.expected_here(None));
+            err.detail.hint = Some("use '==' to compare; '=' assigns".into());
+            let eq = self.peek();
+            err.detail.suggestion = Some(Suggestion::replace("use '==' to compare; '=' assigns", Span { line: eq.line, col: eq.column, len: 1 }, "=="));
+            return Err(err);
+        }
+        Ok(cond)
+    }
+
+    /// A loop body, with `break`/`continue` allowed inside; `block` requires braces.
+    fn parse_loop_body(&mut self, block: bool) -> Result<ASTNode, ParserError> {
+        self.loop_depth += 1;
+        let body = if block { self.parse_block() } else { self.parse_statement() };
+        self.loop_depth -= 1;
+        body
+    }
+
+    fn parse_loop_jump(&mut self) -> Result<ASTNode, ParserError> {
+        let tok = self.advance().clone();
+        if self.loop_depth == 0 {
+            return Err(self.err_at(&format!("'{}' outside of a loop", tok.lexeme), tok.line, tok.column));
+        }
+        self.consume(TokenKind::Semicolon, &format!("Expected ';' after '{}'", tok.lexeme))?;
+        Ok(match tok.kind {
+            TokenKind::Break => ASTNode::Break { line: tok.line, column: tok.column },
+            _ => ASTNode::Continue { line: tok.line, column: tok.column },
+        })
+    }
+
     fn parse_for(&mut self) -> Result<ASTNode, ParserError> {
+        let (line, column) = (self.peek().line, self.peek().column);
         self.consume(TokenKind::For, "Expected 'for'")?;
+        if !self.check(&TokenKind::OpenParen) {
+            return self.parse_for_in(line, column);
+        }
         self.consume(TokenKind::OpenParen, "Expected '(' after for")?;
         let init = if !self.check(&TokenKind::Semicolon) {
             Some(self.parse_statement()?)
@@ -162,7 +294,7 @@ impl Parser {
             None
         };
         let condition = if !self.check(&TokenKind::Semicolon) {
-            Some(self.parse_expression()?)
+            Some(self.parse_condition(TokenKind::Semicolon)?)
         } else {
             None
         };
@@ -173,12 +305,119 @@ impl Parser {
             None
         };
         self.consume(TokenKind::CloseParen, "Expected ')' after for clauses")?;
-        let body = self.parse_statement()?;
+        let body = self.parse_loop_body(false)?;
         Ok(ASTNode::new_for(init, condition, increment, body))
     }
 
+    /// `for x in xs { }` / `for k, v in xs { }`, after the `for`.
+    fn parse_for_in(&mut self, line: usize, column: usize) -> Result<ASTNode, ParserError> {
+        let first = self.consume_identifier("Expected '(' or a loop variable after for")?;
+        let (key, var) = if self.match_token(&[TokenKind::Comma]) {
+            (Some(first), self.consume_identifier("Expected second loop variable after ','")?)
+        } else {
+            (None, first)
+        };
+        self.consume(TokenKind::In, "Expected 'in' after loop variable")?;
+        let iterable = self.parse_expression()?;
+        let body = self.parse_loop_body(true)?;
+        Ok(ASTNode::new_for_in_at(key, &var, iterable, body, line, column))
+    }
+
+    fn parse_enum_decl(&mut self) -> Result<ASTNode, ParserError> {
+        self.consume(TokenKind::Enum, "Expected 'enum'")?;
+        let (line, column) = (self.peek().line, self.peek().column);
+        let name = self.consume_identifier("Expected enum name")?;
+        self.consume(TokenKind::OpenBrace, "Expected '{' after enum name")?;
+        let mut variants = Vec::new();
+        while !self.check(&TokenKind::CloseBrace) {
+            variants.push(self.consume_identifier("Expected variant name")?);
+            if !self.match_token(&[TokenKind::Comma]) {
+                break;
+            }
+        }
+        self.consume(TokenKind::CloseBrace, "Expected '}' after enum variants")?;
+        let _ = self.match_token(&[TokenKind::Semicolon]);
+        if variants.is_empty() {
+            return Err(self.err_at("Enum must declare at least one variant", line, column));
+        }
+        Ok(ASTNode::new_enum_decl_at(&name, variants, line, column))
+    }
+
+    fn parse_import(&mut self) -> Result<ASTNode, ParserError> {
+        let (line, column) = (self.peek().line, self.peek().column);
+        self.consume(TokenKind::Import, "Expected 'import'")?;
+        let path = match self.peek().kind.clone() {
+            TokenKind::StringLiteral(path) => {
+                self.advance();
+                path
+            }
+            _ => return Err(self.err_at("Expected a quoted file path after 'import'", self.peek().line, self.peek().column)),
+        };
+        self.consume(TokenKind::Semicolon, "Expected ';' after import")?;
+        Ok(ASTNode::new_import_at(&path, line, column))
+    }
+
+    fn parse_match(&mut self) -> Result<ASTNode, ParserError> {
+        let (line, column) = (self.peek().line, self.peek().column);
+        self.consume(TokenKind::Match, "Expected 'match'")?;
+        self.consume(TokenKind::OpenParen, "Expected '(' after match")?;
+        let subject = self.parse_expression()?;
+        self.consume(TokenKind::CloseParen, "Expected ')' after match subject")?;
+        self.consume(TokenKind::OpenBrace, "Expected '{' to start match arms")?;
+        let mut arms = Vec::new();
+        while !self.check(&TokenKind::CloseBrace) && !self.is_at_end() {
+            let (arm_line, arm_column) = (self.peek().line, self.peek().column);
+            let pattern = match &self.peek().kind {
+                TokenKind::Identifier(n) if n == "_" => {
+                    self.advance();
+                    None
+                }
+                _ => Some(self.parse_expression()?),
+            };
+            self.consume(TokenKind::FatArrow, "Expected '=>' after match pattern")?;
+            let body = self.parse_statement()?;
+            let _ = self.match_token(&[TokenKind::Comma]);
+            arms.push(MatchArm { pattern, body, line: arm_line, column: arm_column });
+        }
+        self.consume(TokenKind::CloseBrace, "Expected '}' after match arms")?;
+        Ok(ASTNode::new_match_at(subject, arms, line, column))
+    }
+
+    fn parse_qubit_decl(&mut self) -> Result<ASTNode, ParserError> {
+        self.consume(TokenKind::Qubit, "Expected 'qubit'")?;
+        let line = self.peek().line;
+        let column = self.peek().column;
+        let name = self.consume_identifier("Expected qubit name")?;
+        self.consume(TokenKind::Semicolon, "Expected ';' after qubit declaration")?;
+        Ok(ASTNode::new_qubit_decl_at(&name, line, column))
+    }
+
+    fn parse_qreg_decl(&mut self) -> Result<ASTNode, ParserError> {
+        self.consume(TokenKind::Qreg, "Expected 'qreg'")?;
+        let line = self.peek().line;
+        let column = self.peek().column;
+        let name = self.consume_identifier("Expected register name")?;
+        self.consume(TokenKind::OpenBracket, "Expected '[' after register name")?;
+        let size_tok = self.peek().clone();
+        let size = match size_tok.kind {
+            TokenKind::NumberLiteral(n) if n >= 1.0 && n.fract() == 0.0 => n as usize,
+            _ => return Err(self.err_at("Register size must be a positive integer literal", size_tok.line, size_tok.column)),
+        };
+        self.advance();
+        self.consume(TokenKind::CloseBracket, "Expected ']' after register size")?;
+        self.consume(TokenKind::Semicolon, "Expected ';' after register declaration")?;
+        Ok(ASTNode::new_qreg_decl_at(&name, size, line, column))
+    }
+
     fn parse_quantum_op(&mut self) -> Result<ASTNode, ParserError> {
+        let (line, column) = (self.peek().line, self.peek().column);
         let op = self.advance().kind.clone();
+        let qubits = self.parse_quantum_args()?;
+        self.consume(TokenKind::Semicolon, "Expected ';' after quantum op")?;
+        Ok(ASTNode::new_quantum_op_at(op, qubits, line, column))
+    }
+
+    fn parse_quantum_args(&mut self) -> Result<Vec<ASTNode>, ParserError> {
         let mut qubits = Vec::new();
         if self.match_token(&[TokenKind::OpenParen]) {
             while !self.check(&TokenKind::CloseParen) {
@@ -189,8 +428,7 @@ impl Parser {
             }
             self.consume(TokenKind::CloseParen, "Expected ')' after qubits")?;
         }
-        self.consume(TokenKind::Semicolon, "Expected ';' after quantum op")?;
-        Ok(ASTNode::new_quantum_op(op, qubits))
+        Ok(qubits)
     }
 
     fn parse_hieroglyphic_op(&mut self) -> Result<ASTNode, ParserError> {
@@ -213,24 +451,21 @@ impl Parser {
     }
 
     /* ── Precedence ───────────────────────────────────────── */
-    pub fn parse_expression(&mut self) -> Result<ASTNode, ParserError> { self.parse_logical_or() }
-
-    // logical_or: logical_and ( '||' logical_and )*
-    fn parse_logical_or(&mut self) -> Result<ASTNode, ParserError> {
-        let mut expr = self.parse_logical_and()?;
-        while self.match_token(&[TokenKind::OrOr]) { let op = self.previous().kind.clone(); let right = self.parse_logical_and()?; expr = ASTNode::new_binary_expr(op, expr, right); }
-        Ok(expr)
-    }
-    // logical_and: equality ( '&&' equality )*
-    fn parse_logical_and(&mut self) -> Result<ASTNode, ParserError> {
-        let mut expr = self.parse_assignment()?; // parse below level (assignment/equality chain)
-        while self.match_token(&[TokenKind::AndAnd]) { let op = self.previous().kind.clone(); let right = self.parse_assignment()?; expr = ASTNode::new_binary_expr(op, expr, right); }
-        Ok(expr)
-    }
-
-    // assignment: Identifier '=' assignment | equality
+    // Loosest to tightest; binary levels are left-associative unless noted:
+    //   assignment  =            (right-associative)
+    //   logical_or  ||
+    //   logical_and &&
+    //   equality    == !=
+    //   comparison  < <= > >=
+    //   term        + -
+    //   factor      * / %
+    //   unary       ! - +
+    //   power       **           (right-associative; `-2 ** 2` is `-(2 ** 2)`)
+    pub fn parse_expression(&mut self) -> Result<ASTNode, ParserError> { self.parse_assignment() }
+
+    // assignment: Identifier '=' assignment | logical_or
     fn parse_assignment(&mut self) -> Result<ASTNode, ParserError> {
-        let expr = self.parse_equality()?;
+        let expr = self.parse_logical_or()?;
         if self.match_token(&[TokenKind::Equals]) {
             match expr {
                 ASTNode::Identifier(name) => {
@@ -246,6 +481,19 @@ impl Parser {
         }
     }
 
+    // logical_or: logical_and ( '||' logical_and )*
+    fn parse_logical_or(&mut self) -> Result<ASTNode, ParserError> {
+        let mut expr = self.parse_logical_and()?;
+        while self.match_token(&[TokenKind::OrOr]) { let op = self.previous().kind.clone(); let right = self.parse_logical_and()?; expr = ASTNode::new_binary_expr(op, expr, right); }
+        Ok(expr)
+    }
+    // logical_and: equality ( '&&' equality )*
+    fn parse_logical_and(&mut self) -> Result<ASTNode, ParserError> {
+        let mut expr = self.parse_equality()?;
+        while self.match_token(&[TokenKind::AndAnd]) { let op = self.previous().kind.clone(); let right = self.parse_equality()?; expr = ASTNode::new_binary_expr(op, expr, right); }
+        Ok(expr)
+    }
+
     fn parse_equality(&mut self) -> Result<ASTNode, ParserError> {
         let mut expr = self.parse_comparison()?;
         while self.match_token(&[TokenKind::DoubleEquals, TokenKind::NotEquals]) {
@@ -283,7 +531,7 @@ impl Parser {
 
     fn parse_factor(&mut self) -> Result<ASTNode, ParserError> {
         let mut expr = self.parse_unary()?;
-        while self.match_token(&[TokenKind::Star, TokenKind::Slash]) {
+        while self.match_token(&[TokenKind::Star, TokenKind::Slash, TokenKind::Percent]) {
             let op = self.previous().kind.clone();
             let right = self.parse_unary()?;
             expr = ASTNode::new_binary_expr(op, expr, right);
@@ -292,15 +540,25 @@ impl Parser {
     }
 
     fn parse_unary(&mut self) -> Result<ASTNode, ParserError> {
-        if self.match_token(&[TokenKind::Minus, TokenKind::Plus]) {
+        if self.match_token(&[TokenKind::Bang, TokenKind::Minus, TokenKind::Plus]) {
             let op = self.previous().kind.clone();
             let right = self.parse_unary()?;
             return Ok(ASTNode::new_unary_expr(op, right));
         }
-        self.parse_call()
+        self.parse_power()
     }
 
-    // support simple calls: primary ('(' args? ')')*
+    // power: call ( '**' unary )? — the exponent may carry its own sign (`2 ** -1`)
+    fn parse_power(&mut self) -> Result<ASTNode, ParserError> {
+        let base = self.parse_call()?;
+        if self.match_token(&[TokenKind::StarStar]) {
+            let exponent = self.parse_unary()?;
+            return Ok(ASTNode::new_binary_expr(TokenKind::StarStar, base, exponent));
+        }
+        Ok(base)
+    }
+
+    // support simple calls, indexing and member access: primary ( '(' args? ')' | '[' expr ']' | '.' name )*
     fn parse_call(&mut self) -> Result<ASTNode, ParserError> {
         let mut expr = self.parse_primary()?;
         loop {
@@ -316,6 +574,17 @@ impl Parser {
                 }
                 self.consume(TokenKind::CloseParen, "Expected ')' after arguments")?;
                 expr = ASTNode::new_call(expr, args);
+            } else if self.check(&TokenKind::OpenBracket) {
+                let (line, column) = (self.peek().line, self.peek().column);
+                self.advance();
+                let index = self.parse_expression()?;
+                self.consume(TokenKind::CloseBracket, "Expected ']' after index")?;
+                expr = ASTNode::new_index_at(expr, index, line, column);
+            } else if self.check(&TokenKind::Dot) {
+                self.advance();
+                let (line, column) = (self.peek().line, self.peek().column);
+                let name = self.consume_identifier("Expected member name after '.'")?;
+                expr = ASTNode::new_member_at(expr, &name, line, column);
             } else {
                 break;
             }
@@ -324,23 +593,93 @@ impl Parser {
     }
 
     fn parse_primary(&mut self) -> Result<ASTNode, ParserError> {
+        let at = self.pos;
         let tok = self.advance().clone();
         match tok.kind {
             TokenKind::NumberLiteral(v) => Ok(ASTNode::NumberLiteral(v)),
             TokenKind::StringLiteral(s) => Ok(ASTNode::StringLiteral(s)),
+            TokenKind::InterpolatedString(parts) => self.parse_interpolation(parts, tok.line, tok.column),
             TokenKind::BooleanLiteral(b) => Ok(ASTNode::BooleanLiteral(b)),
             TokenKind::Identifier(name) => Ok(ASTNode::new_identifier_spanned(&name, tok.line, tok.column, name.len())),
+            // `let bit = measure(q);` — quantum ops usable as values
+            TokenKind::Superpose | TokenKind::Entangle | TokenKind::Measure | TokenKind::Dod => {
+                let qubits = self.parse_quantum_args()?;
+                Ok(ASTNode::new_quantum_op_at(tok.kind, qubits, tok.line, tok.column))
+            }
             TokenKind::OpenParen => {
                 let expr = self.parse_expression()?;
                 self.consume(TokenKind::CloseParen, "Expected ')'")?;
                 Ok(expr)
             }
-            _ => Err(ParserError {
-                message: format!("Unexpected token {:?}", tok.kind),
-                line: tok.line,
-                column: tok.column,
-            }),
+            TokenKind::OpenBracket => {
+                let mut items = Vec::new();
+                while !self.check(&TokenKind::CloseBracket) {
+                    items.push(self.parse_expression()?);
+                    if !self.match_token(&[TokenKind::Comma]) {
+                        break;
+                    }
+                }
+                self.consume(TokenKind::CloseBracket, "Expected ']' after array elements")?;
+                Ok(ASTNode::new_array_literal_at(items, tok.line, tok.column))
+            }
+            TokenKind::OpenBrace => self.parse_object_fields(tok.line, tok.column),
+            _ => {
+                self.pos = at;
+                let msg = format!("Unexpected {}", describe_found(&tok));
+                Err(self.err_expecting(&msg, vec!["an expression".into()]))
+            }
+        }
+    }
+
+    /// The fields of `{ "k": v, name: w, }` after its `{`. A key may repeat; the last one wins at runtime.
+    fn parse_object_fields(&mut self, line: usize, column: usize) -> Result<ASTNode, ParserError> {
+        let mut fields = Vec::new();
+        while !self.check(&TokenKind::CloseBrace) {
+            let key = match self.peek().kind.clone() {
+                TokenKind::StringLiteral(k) | TokenKind::Identifier(k) => {
+                    self.advance();
+                    k
+                }
+                _ => return Err(self.err_expecting("Expected a field name in object literal", self.expected_here(Some("a string or name")))),
+            };
+            self.consume(TokenKind::Colon, "Expected ':' after field name")?;
+            fields.push((key, self.parse_expression()?));
+            if !self.match_token(&[TokenKind::Comma]) {
+                break;
+            }
         }
+        self.consume(TokenKind::CloseBrace, "Expected '}' after object fields")?;
+        Ok(ASTNode::new_object_literal_at(fields, line, column))
+    }
+
+    /// `"a ${x} b"` desugars to `"a " + x + " b"`. The chain always starts with a string
+    /// (`""` when the literal opens with `${`), so every `+` concatenates.
+    fn parse_interpolation(&mut self, parts: Vec<StringPart>, line: usize, column: usize) -> Result<ASTNode, ParserError> {
+        let mut parts = parts.into_iter().peekable();
+        let mut expr = match parts.next_if(|p| matches!(p, StringPart::Text(_))) {
+            Some(StringPart::Text(s)) => ASTNode::StringLiteral(s),
+            _ => ASTNode::StringLiteral(String::new()),
+        };
+        for part in parts {
+            let right = match part {
+                StringPart::Text(s) => ASTNode::StringLiteral(s),
+                StringPart::Expr(tokens) if tokens.is_empty() => {
+                    return Err(self.err_at("Empty interpolation: '${}' needs an expression", line, column));
+                }
+                StringPart::Expr(mut tokens) => {
+                    // errors at the end of the part point at the string
+                    tokens.push(Token::new(TokenKind::EOF, String::new(), line, column));
+                    let mut inner = Parser::new(tokens);
+                    let value = inner.parse_expression()?;
+                    if !inner.is_at_end() {
+                        return Err(inner.err_expecting("Expected '}' to close the interpolation", inner.expected_here(Some("'}'"))));
+                    }
+                    value
+                }
+            };
+            expr = ASTNode::new_binary_expr(TokenKind::Plus, expr, right);
+        }
+        Ok(expr)
     }
 
     /* ── Token utils ─────────────────────────────────────── */
@@ -364,7 +703,21 @@ impl Parser {
         &self.tokens[self.pos.min(self.tokens.len() - 1)]
     }
 
-    fn check(&self, kind: &TokenKind) -> bool {
+    /// The token after `peek()`, or the EOF.
+    fn peek_next(&self) -> &Token {
+        &self.tokens[(self.pos + 1).min(self.tokens.len() - 1)]
+    }
+
+    /// Whether the next token is `kind`; every kind tried at one position is remembered
+    /// for the error, should none of them match.
+    fn check(&mut self, kind: &TokenKind) -> bool {
+        if self.expected_at != self.pos {
+            self.expected_at = self.pos;
+            self.expected.clear();
+        }
+        if !self.expected.contains(kind) {
+            self.expected.push(kind.clone());
+        }
         !self.is_at_end() && &self.peek().kind == kind
     }
 
@@ -382,7 +735,7 @@ impl Parser {
         if self.check(&kind) {
             Ok(self.advance())
         } else {
-            Err(self.err_at(msg, self.peek().line, self.peek().column))
+            Err(self.err_expecting(msg, self.expected_here(None)))
         }
     }
 
@@ -391,7 +744,7 @@ impl Parser {
             self.advance();
             Ok(name)
         } else {
-            Err(self.err_at(msg, self.peek().line, self.peek().column))
+            Err(self.err_expecting(msg, self.expected_here(Some("a name"))))
         }
     }
 
@@ -408,6 +761,104 @@ impl Parser {
             message: msg.into(),
             line,
             column,
+            detail: Box::default(),
         }
     }
+
+    /// What `check` tried at the current position, after `first`. Once any binary operator
+    /// was tried the operators (and postfix `(`, `[`, `.`) collapse into "an operator".
+    fn expected_here(&self, first: Option<&str>) -> Vec<String> {
+        let tried: &[TokenKind] = if self.expected_at == self.pos { &self.expected } else { &[] };
+        let operators = tried.iter().any(is_binary_operator);
+        let mut out: Vec<String> = first.into_iter().map(String::from).collect();
+        for kind in tried {
+            if operators && (is_binary_operator(kind) || matches!(kind, TokenKind::OpenParen | TokenKind::OpenBracket | TokenKind::Dot)) {
+                continue;
+            }
+            let item = format!("'{kind}'");
+            if !out.contains(&item) {
+                out.push(item);
+            }
+        }
+        if operators {
+            out.push("an operator".into());
+        }
+        out
+    }
+
+    /// An error at the current token, with hints for the mistakes that usually cause it.
+    fn err_expecting(&self, msg: &str, expected: Vec<String>) -> ParserError {
+        let tok = self.peek();
+        let at_end = self.is_at_end();
+        let mut err = self.err_at(msg, tok.line, tok.column);
+        err.detail.found = Some(describe_found(tok));
+        if let Some(open) = self.unclosed_brace().filter(|_| at_end) {
+            err.detail.hint = Some(format!("add '}}' to close the block opened at line {}", open.line));
+            err.detail.related.push(RelatedInfo { message: "this '{' is never closed".into(), line: open.line, column: open.column, len: 1 });
+        } else if expected.iter().any(|e| e == "';'") && self.pos > 0 && (at_end || tok.line > self.previous().line) {
+            let prev = self.previous();
+            err.detail.hint = Some(format!("add ';' at the end of line {}", prev.line));
+            if let Some(len) = spelled_len(prev) {
+                err.detail.suggestion = Some(Suggestion::insert(format!("add ';' at the end of line {}", prev.line), prev.line, prev.column + len, ";"));
+            }
+        }
+        err.detail.expected = expected;
+        err
+    }
+
+    /// The innermost '{' before the current token that has no matching '}'.
+    fn unclosed_brace(&self) -> Option<&Token> {
+        let mut open = Vec::new();
+        for tok in &self.tokens[..self.pos] {
+            match tok.kind {
+                TokenKind::OpenBrace => open.push(tok),
+                TokenKind::CloseBrace => {
+                    open.pop();
+                }
+                _ => {}
+            }
+        }
+        open.pop()
+    }
+}
+
+fn is_binary_operator(kind: &TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::Plus
+            | TokenKind::Minus
+            | TokenKind::Star
+            | TokenKind::Slash
+            | TokenKind::Percent
+            | TokenKind::StarStar
+            | TokenKind::Equals
+            | TokenKind::DoubleEquals
+            | TokenKind::NotEquals
+            | TokenKind::LessThan
+            | TokenKind::LessEqual
+            | TokenKind::GreaterThan
+            | TokenKind::GreaterEqual
+            | TokenKind::AndAnd
+            | TokenKind::OrOr
+    )
+}
+
+/// Bytes `tok` takes in the source, when its lexeme spells it; a string's lexeme is its
+/// contents and punctuation has none, so of those only closing brackets are known.
+fn spelled_len(tok: &Token) -> Option<usize> {
+    match tok.kind {
+        TokenKind::StringLiteral(_) | TokenKind::EOF => None,
+        TokenKind::CloseParen | TokenKind::CloseBracket | TokenKind::CloseBrace if tok.lexeme.is_empty() => Some(1),
+        _ => (!tok.lexeme.is_empty()).then_some(tok.lexeme.len()),
+    }
+}
+
+/// A token as the user wrote it, for "found ..." in errors.
+fn describe_found(tok: &Token) -> String {
+    match &tok.kind {
+        TokenKind::EOF => "end of file".into(),
+        TokenKind::StringLiteral(s) => format!("string \"{s}\""),
+        _ if !tok.lexeme.is_empty() => format!("'{}'", tok.lexeme),
+        kind => format!("'{kind}'"),
+    }
 }
@@ -0,0 +1,116 @@
+//! In-memory compile preview for editors: the emitted JS or canonical AI text plus,
+//! per top-level function, the source lines it spans and the output lines it became,
+//! so a frontend can scroll-sync the two panes. Nothing touches the filesystem or
+//! the artifact cache.
+
+use serde::Serialize;
+
+use crate::cli::EmitKind;
+use crate::core::ast::ASTNode;
+use crate::core::code_generator::CodeGenerator;
+use crate::core::error_index;
+use crate::core::lexer::{Lexer, LexerOptions};
+use crate::core::parser::{Parser, ParserError};
+use crate::core::token::{Token, TokenKind};
+
+/// Line ranges are 1-based and inclusive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FunctionMapping {
+    pub name: String,
+    pub source_start: usize,
+    pub source_end: usize,
+    pub output_start: usize,
+    pub output_end: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Preview {
+    pub text: String,
+    /// Ordered by `source_start`.
+    pub mappings: Vec<FunctionMapping>,
+}
+
+/// Why no preview was produced; same fields as the `--diag-json` lines.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PreviewDiagnostic {
+    pub severity: &'static str,
+    pub message: String,
+    pub line: usize,
+    pub col: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'static str>,
+}
+
+impl PreviewDiagnostic {
+    fn error(message: String, line: usize, col: usize, code: Option<&'static str>) -> Self {
+        Self { severity: "error", message, line, col, code }
+    }
+}
+
+/// Compile `source` to `kind` in memory. Errors come back as diagnostics, never partial output.
+pub fn emit_preview(source: &str, kind: EmitKind) -> Result<Preview, Vec<PreviewDiagnostic>> {
+    emit_preview_with(source, kind, LexerOptions::default())
+}
+
+/// Compile `source` to `kind` in memory with the named built-in lexer plugins installed
+/// (see `lexer_plugins::AVAILABLE`); the embedder's counterpart to `[lexer.plugins]`.
+/// An unknown name or a plugin rejecting the source is a lex diagnostic naming the plugin.
+pub fn compile_source(source: &str, kind: EmitKind, plugins: &[String]) -> Result<String, Vec<PreviewDiagnostic>> {
+    let options = LexerOptions { plugins: plugins.to_vec(), ..Default::default() };
+    emit_preview_with(source, kind, options).map(|p| p.text)
+}
+
+fn emit_preview_with(source: &str, kind: EmitKind, options: LexerOptions) -> Result<Preview, Vec<PreviewDiagnostic>> {
+    let tokens = Lexer::with_options(source, options).tokenize().map_err(|e| {
+        let (line, col) = e.position();
+        vec![PreviewDiagnostic::error(format!("Lexing error: {}", e), line, col, Some(error_index::LEX_ERROR))]
+    })?;
+    let ast = Parser::new(tokens.clone()).parse().map_err(|e: ParserError| {
+        vec![PreviewDiagnostic::error(format!("Parsing error: {}", e.summary()), e.line, e.column, Some(error_index::PARSE_ERROR))]
+    })?;
+    let mut gen = match kind {
+        EmitKind::Js => CodeGenerator::new(),
+        EmitKind::Ai => CodeGenerator::new_ai(),
+    };
+    let (text, spans) = gen
+        .generate_mapped(&ast)
+        .map_err(|e| vec![PreviewDiagnostic::error(e, 0, 0, None)])?;
+
+    let mut mappings: Vec<FunctionMapping> = function_source_ranges(&ast, &tokens)
+        .into_iter()
+        .filter_map(|(name, source_start, source_end)| {
+            let span = spans.iter().find(|s| s.name == name)?;
+            Some(FunctionMapping { name, source_start, source_end, output_start: span.start_line, output_end: span.end_line })
+        })
+        .collect();
+    mappings.sort_by_key(|m| m.source_start);
+    Ok(Preview { text, mappings })
+}
+
+/// Source lines of each top-level function: from its `function` keyword to the brace closing its body.
+pub(crate) fn function_source_ranges(ast: &ASTNode, tokens: &[Token]) -> Vec<(String, usize, usize)> {
+    let ASTNode::Program(items) = ast else { return Vec::new() };
+    items
+        .iter()
+        .filter_map(|item| {
+            let ASTNode::Function { name, line, column, .. } = item else { return None };
+            let at = tokens.iter().position(|t| t.kind == TokenKind::Function && t.line == *line && t.column == *column)?;
+            let mut depth = 0usize;
+            let mut end = *line;
+            for t in &tokens[at..] {
+                match t.kind {
+                    TokenKind::OpenBrace => depth += 1,
+                    TokenKind::CloseBrace => {
+                        depth = depth.saturating_sub(1);
+                        if depth == 0 {
+                            end = t.line;
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Some((name.clone(), *line, end))
+        })
+        .collect()
+}
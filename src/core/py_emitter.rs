@@ -0,0 +1,496 @@
+//! Python 3 backend (used by `aeonmi export --format py`).
+//! Emits straight from the AST. `log`, `+` and `%` go through small prelude helpers so
+//! booleans, integral numbers, string concatenation and the sign of a remainder come out
+//! the way the native VM does. Array and object literals become lists and dicts; `a.b`
+//! goes through a helper too, since on a dict it is a key lookup. `push`, `pop`, `slice`
+//! and `len` are prelude helpers with the VM's checks, and a program that pushes or pops
+//! copies the lists it stores, since the VM's arrays are values.
+use crate::core::ast::{ASTNode, MatchArm, Pattern};
+use crate::core::code_generator::mutates_arrays;
+use crate::core::token::TokenKind;
+use std::collections::BTreeSet;
+
+const PRELUDE: &str = r#"def __aeonmi_str(v):
+    if v is True:
+        return "true"
+    if v is False:
+        return "false"
+    if v is None:
+        return "null"
+    if isinstance(v, float) and v.is_integer():
+        return str(int(v))
+    return str(v)
+
+
+def __aeonmi_add(a, b):
+    if isinstance(a, str) or isinstance(b, str):
+        return __aeonmi_str(a) + __aeonmi_str(b)
+    return a + b
+
+
+def __aeonmi_mod(a, b):
+    if b == 0:
+        raise ZeroDivisionError("modulo by zero")
+    r = abs(a) % abs(b)
+    return -r if a < 0 else r
+
+
+def __aeonmi_member(v, name):
+    if isinstance(v, dict):
+        return v[name]
+    return getattr(v, name)
+
+
+def __aeonmi_len(v):
+    if v is None:
+        return 0
+    return len(v)
+
+
+def __aeonmi_push(items, value):
+    if not isinstance(items, list):
+        raise TypeError("push expects an array, got " + __aeonmi_str(items))
+    items.append(value)
+    return len(items)
+
+
+def __aeonmi_pop(items, name):
+    if not isinstance(items, list):
+        raise TypeError("pop expects an array, got " + __aeonmi_str(items))
+    if not items:
+        raise IndexError("pop: `" + name + "` is empty")
+    return items.pop()
+
+
+def __aeonmi_slice(items, start, end):
+    if items is None:
+        items = []
+    if isinstance(items, str):
+        items = list(items)
+    if not isinstance(items, list):
+        raise TypeError("slice expects an array, got " + __aeonmi_str(items))
+    lo = min(max(int(start), 0), len(items))
+    return items[lo:max(min(max(int(end), 0), len(items)), lo)]
+
+
+def __aeonmi_copy(v):
+    if isinstance(v, list):
+        return [__aeonmi_copy(x) for x in v]
+    if isinstance(v, dict):
+        return {k: __aeonmi_copy(x) for k, x in v.items()}
+    return v
+
+"#;
+
+#[derive(Default)]
+pub struct PyEmitter {
+    indent: usize,
+    /// Copy lists and dicts where the VM would (see [`PyEmitter::stored`]); set for a
+    /// program that calls `push` or `pop`.
+    copy_values: bool,
+}
+
+impl PyEmitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn generate(&mut self, ast: &ASTNode) -> String {
+        let mut out = String::from(PRELUDE);
+        let mut body = String::new();
+        self.emit_stmt(ast, &mut body);
+        if !body.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&body);
+        out
+    }
+
+    fn line(&self, out: &mut String, text: &str) {
+        out.push_str(&"    ".repeat(self.indent));
+        out.push_str(text);
+        out.push('\n');
+    }
+
+    /// An indented suite; Python needs `pass` where the source block is empty.
+    fn emit_suite(&mut self, node: &ASTNode, out: &mut String) {
+        self.indent += 1;
+        let before = out.len();
+        self.emit_stmt(node, out);
+        if out.len() == before {
+            self.line(out, "pass");
+        }
+        self.indent -= 1;
+    }
+
+    fn emit_stmt(&mut self, node: &ASTNode, out: &mut String) {
+        match node {
+            // Python has no block scope, so nested blocks flatten into the enclosing suite.
+            ASTNode::Program(items) => {
+                let saved_copy = std::mem::replace(&mut self.copy_values, items.iter().any(mutates_arrays));
+                for it in items {
+                    self.emit_stmt(it, out);
+                }
+                self.copy_values = saved_copy;
+            }
+            ASTNode::Block(items) => {
+                for it in items {
+                    self.emit_stmt(it, out);
+                }
+            }
+            ASTNode::VariableDecl { name, value, .. } | ASTNode::Assignment { name, value, .. } => {
+                let v = self.stored(value);
+                self.line(out, &format!("{} = {}", name, v));
+            }
+            // Tuple unpacking, which also splits strings into characters.
+            ASTNode::DestructuringDecl { targets, values, .. } => {
+                let parts: Vec<String> = match &targets[..] {
+                    [Pattern::Array { items, .. }] => items.iter().map(py_pattern).collect(),
+                    _ => targets.iter().map(py_pattern).collect(),
+                };
+                let lhs = if parts.len() == 1 { format!("{},", parts[0]) } else { parts.join(", ") };
+                let rhs = values.iter().map(|v| self.stored(v)).collect::<Vec<_>>().join(", ");
+                self.line(out, &format!("{} = {}", lhs, rhs));
+            }
+            ASTNode::EnumDecl { name, variants, .. } => {
+                self.line(out, &format!("class {}:", name));
+                self.indent += 1;
+                for v in variants {
+                    self.line(out, &format!("{} = {}", v, py_string(&format!("{}.{}", name, v))));
+                }
+                self.indent -= 1;
+            }
+            ASTNode::Match { subject, arms, .. } => self.emit_match(subject, arms, out),
+            ASTNode::Function { name, params, body, .. } => {
+                let params: Vec<&str> = params.iter().map(|p| p.name.as_str()).collect();
+                self.line(out, &format!("def {}({}):", name, params.join(", ")));
+                let globals = outer_assignments(body, &params);
+                self.indent += 1;
+                if !globals.is_empty() {
+                    self.line(out, &format!("global {}", globals.into_iter().collect::<Vec<_>>().join(", ")));
+                }
+                self.indent -= 1;
+                self.emit_suite(&ASTNode::Block(body.clone()), out);
+                out.push('\n');
+            }
+            ASTNode::Return(expr) => {
+                let v = self.expr(expr);
+                self.line(out, &format!("return {}", v));
+            }
+            ASTNode::Log { expr, .. } => {
+                let v = self.expr(expr);
+                self.line(out, &format!("print(__aeonmi_str({}))", v));
+            }
+            ASTNode::If { .. } => self.emit_if(node, "if", out),
+            ASTNode::While { condition, body } => {
+                let c = self.expr(condition);
+                self.line(out, &format!("while {}:", c));
+                self.emit_suite(body, out);
+            }
+            // `for (init; cond; step) body` becomes `init; while cond: body; step`.
+            ASTNode::For { init, condition, increment, body } => {
+                if let Some(i) = init {
+                    self.emit_stmt(i, out);
+                }
+                let c = condition.as_ref().map(|c| self.expr(c)).unwrap_or_else(|| "True".into());
+                self.line(out, &format!("while {}:", c));
+                let mut loop_body = match &**body {
+                    ASTNode::Block(items) => items.clone(),
+                    other => vec![other.clone()],
+                };
+                loop_body.extend(increment.iter().map(|inc| (**inc).clone()));
+                self.emit_suite(&ASTNode::Block(loop_body), out);
+            }
+            // Maps go by sorted key, as in the VM; `__it` keeps the iterable evaluated once.
+            ASTNode::ForIn { key, var, iterable, body, .. } => {
+                let it = self.stored(iterable);
+                let head = match key {
+                    Some(k) => format!("for {k}, {var} in (sorted(__it.items()) if isinstance(__it := {it}, dict) else enumerate(__it)):"),
+                    None => format!("for {var} in (sorted(__it) if isinstance(__it := {it}, dict) else __it):"),
+                };
+                self.line(out, &head);
+                self.emit_suite(body, out);
+            }
+            ASTNode::Break { .. } => self.line(out, "break"),
+            ASTNode::Continue { .. } => self.line(out, "continue"),
+            ASTNode::QubitDecl { name, .. } => self.line(out, &format!("{} = \"|0>\"", name)),
+            ASTNode::QregDecl { name, size, .. } => self.line(out, &format!("{} = [\"|0>\"] * {}", name, size)),
+            ASTNode::Error(msg) => self.line(out, &format!("# ERROR NODE: {}", msg)),
+            _ => {
+                let e = self.expr(node);
+                self.line(out, &e);
+            }
+        }
+    }
+
+    /// `if` / `elif` / `else`, folding `else if` chains into `elif`.
+    fn emit_if(&mut self, node: &ASTNode, keyword: &str, out: &mut String) {
+        let ASTNode::If { condition, then_branch, else_branch } = node else { return };
+        let c = self.expr(condition);
+        self.line(out, &format!("{} {}:", keyword, c));
+        self.emit_suite(then_branch, out);
+        match else_branch.as_deref() {
+            Some(e @ ASTNode::If { .. }) => self.emit_if(e, "elif", out),
+            Some(e) => {
+                self.line(out, "else:");
+                self.emit_suite(e, out);
+            }
+            None => {}
+        }
+    }
+
+    fn emit_match(&mut self, subject: &ASTNode, arms: &[MatchArm], out: &mut String) {
+        let s = self.expr(subject);
+        self.line(out, &format!("__match = {}", s));
+        let mut first = true;
+        for arm in arms {
+            match &arm.pattern {
+                Some(p) => {
+                    let p = self.expr(p);
+                    self.line(out, &format!("{} __match == {}:", if first { "if" } else { "elif" }, p));
+                    self.emit_suite(&arm.body, out);
+                    first = false;
+                }
+                // `_` catches everything; later arms are unreachable
+                None => {
+                    if first {
+                        self.emit_stmt(&arm.body, out);
+                    } else {
+                        self.line(out, "else:");
+                        self.emit_suite(&arm.body, out);
+                    }
+                    break;
+                }
+            }
+        }
+    }
+
+    fn expr(&mut self, node: &ASTNode) -> String {
+        match node {
+            ASTNode::Identifier(s) | ASTNode::IdentifierSpanned { name: s, .. } => s.clone(),
+            ASTNode::NumberLiteral(n) => {
+                if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+                    format!("{}", *n as i64)
+                } else {
+                    format!("{}", n)
+                }
+            }
+            ASTNode::StringLiteral(s) => py_string(s),
+            ASTNode::BooleanLiteral(b) => (if *b { "True" } else { "False" }).to_string(),
+            ASTNode::BinaryExpr { op: TokenKind::Plus, left, right } => {
+                format!("__aeonmi_add({}, {})", self.expr(left), self.expr(right))
+            }
+            ASTNode::BinaryExpr { op: TokenKind::Percent, left, right } => {
+                format!("__aeonmi_mod({}, {})", self.expr(left), self.expr(right))
+            }
+            ASTNode::BinaryExpr { op, left, right } => {
+                format!("({} {} {})", self.expr(left), op_str(op), self.expr(right))
+            }
+            ASTNode::UnaryExpr { op: TokenKind::Bang, expr } => format!("(not {})", self.expr(expr)),
+            ASTNode::UnaryExpr { op, expr } => format!("({}{})", op_str(op), self.expr(expr)),
+            ASTNode::Index { target, index, .. } => format!("{}[{}]", self.expr(target), self.expr(index)),
+            ASTNode::Member { target, name, .. } => format!("__aeonmi_member({}, {})", self.expr(target), py_string(name)),
+            ASTNode::Call { callee, args } => {
+                let builtin = match &**callee {
+                    ASTNode::Identifier(n) | ASTNode::IdentifierSpanned { name: n, .. } => n.as_str(),
+                    _ => "",
+                };
+                match (builtin, args.first()) {
+                    // like the VM, a `null` variable becomes an empty list first
+                    ("push" | "pop", Some(ASTNode::Identifier(v) | ASTNode::IdentifierSpanned { name: v, .. })) => {
+                        let target = format!("({v} := [] if {v} is None else {v})");
+                        if builtin == "push" {
+                            let rest: Vec<String> = args[1..].iter().map(|a| self.stored(a)).collect();
+                            format!("__aeonmi_push({target}, {})", rest.join(", "))
+                        } else {
+                            format!("__aeonmi_pop({target}, {})", py_string(v))
+                        }
+                    }
+                    ("push" | "pop" | "slice" | "len", _) => {
+                        let a: Vec<String> = args.iter().map(|x| self.expr(x)).collect();
+                        format!("__aeonmi_{}({})", builtin, a.join(", "))
+                    }
+                    _ => {
+                        let a: Vec<String> = args.iter().map(|x| self.stored(x)).collect();
+                        format!("{}({})", self.expr(callee), a.join(", "))
+                    }
+                }
+            }
+            ASTNode::Assignment { name, value, .. } => format!("({} := {})", name, self.stored(value)),
+            ASTNode::QuantumOp { op, qubits, .. } => {
+                let opname = match op {
+                    TokenKind::Superpose => "superpose",
+                    TokenKind::Entangle => "entangle",
+                    TokenKind::Measure => "measure",
+                    TokenKind::Dod => "dod",
+                    _ => "qop",
+                };
+                let a: Vec<String> = qubits.iter().map(|q| self.expr(q)).collect();
+                format!("{}({})", opname, a.join(", "))
+            }
+            ASTNode::HieroglyphicOp { symbol, args } => {
+                let mut a = vec![py_string(symbol)];
+                a.extend(args.iter().map(|x| self.expr(x)));
+                format!("__glyph({})", a.join(", "))
+            }
+            ASTNode::ArrayLiteral { items, .. } => {
+                let a: Vec<String> = items.iter().map(|x| self.stored(x)).collect();
+                format!("[{}]", a.join(", "))
+            }
+            ASTNode::ObjectLiteral { fields, .. } => {
+                let f: Vec<String> = fields.iter().map(|(k, v)| format!("{}: {}", py_string(k), self.stored(v))).collect();
+                format!("{{{}}}", f.join(", "))
+            }
+            // statements have no value in expression position
+            ASTNode::Program(_)
+            | ASTNode::Function { .. }
+            | ASTNode::VariableDecl { .. }
+            | ASTNode::DestructuringDecl { .. }
+            | ASTNode::EnumDecl { .. }
+            | ASTNode::Import { .. }
+            | ASTNode::Block(_)
+            | ASTNode::Return(_)
+            | ASTNode::Log { .. }
+            | ASTNode::If { .. }
+            | ASTNode::While { .. }
+            | ASTNode::For { .. }
+            | ASTNode::ForIn { .. }
+            | ASTNode::Break { .. }
+            | ASTNode::Continue { .. }
+            | ASTNode::Match { .. }
+            | ASTNode::QubitDecl { .. }
+            | ASTNode::QregDecl { .. }
+            | ASTNode::Error(_) => "None".into(),
+        }
+    }
+
+    /// `node` where it is stored (bound, passed or put in a container): the VM stores a
+    /// copy, so in a program that changes lists a value that may be shared is copied.
+    fn stored(&mut self, node: &ASTNode) -> String {
+        let py = self.expr(node);
+        let shared = matches!(node, ASTNode::Identifier(_) | ASTNode::IdentifierSpanned { .. } | ASTNode::Index { .. } | ASTNode::Member { .. } | ASTNode::Call { .. });
+        if self.copy_values && shared {
+            format!("__aeonmi_copy({})", py)
+        } else {
+            py
+        }
+    }
+}
+
+fn op_str(op: &TokenKind) -> &'static str {
+    match op {
+        TokenKind::Minus => "-",
+        TokenKind::Star => "*",
+        TokenKind::Slash => "/",
+        TokenKind::StarStar => "**",
+        TokenKind::DoubleEquals => "==",
+        TokenKind::NotEquals => "!=",
+        TokenKind::LessThan => "<",
+        TokenKind::LessEqual => "<=",
+        TokenKind::GreaterThan => ">",
+        TokenKind::GreaterEqual => ">=",
+        TokenKind::AndAnd => "and",
+        TokenKind::OrOr => "or",
+        _ => "?",
+    }
+}
+
+/// Double-quoted Python literal; control characters use `\xNN` escapes.
+fn py_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\x{:02x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A destructuring target as a Python assignment target.
+fn py_pattern(p: &Pattern) -> String {
+    match p {
+        Pattern::Name { name, .. } => name.clone(),
+        Pattern::Array { items, .. } if items.len() == 1 => format!("({},)", py_pattern(&items[0])),
+        Pattern::Array { items, .. } => format!("({})", items.iter().map(py_pattern).collect::<Vec<_>>().join(", ")),
+    }
+}
+
+/// Names a function body assigns without declaring them itself; Python needs `global` for these.
+/// `push` and `pop` on a variable count, as they may rebind a `null` one.
+fn outer_assignments(body: &[ASTNode], params: &[&str]) -> BTreeSet<String> {
+    fn walk(n: &ASTNode, assigned: &mut BTreeSet<String>, local: &mut BTreeSet<String>) {
+        match n {
+            ASTNode::Assignment { name, value, .. } => {
+                assigned.insert(name.clone());
+                walk(value, assigned, local);
+            }
+            ASTNode::VariableDecl { name, value, .. } => {
+                local.insert(name.clone());
+                walk(value, assigned, local);
+            }
+            ASTNode::QubitDecl { name, .. } | ASTNode::QregDecl { name, .. } | ASTNode::EnumDecl { name, .. } => {
+                local.insert(name.clone());
+            }
+            ASTNode::DestructuringDecl { targets, values, .. } => {
+                local.extend(targets.iter().flat_map(Pattern::names).map(|(n, ..)| n.to_string()));
+                values.iter().for_each(|v| walk(v, assigned, local));
+            }
+            ASTNode::Block(items) => items.iter().for_each(|it| walk(it, assigned, local)),
+            ASTNode::If { condition, then_branch, else_branch } => {
+                walk(condition, assigned, local);
+                walk(then_branch, assigned, local);
+                if let Some(e) = else_branch {
+                    walk(e, assigned, local);
+                }
+            }
+            ASTNode::While { condition, body } => {
+                walk(condition, assigned, local);
+                walk(body, assigned, local);
+            }
+            ASTNode::ForIn { key, var, iterable, body, .. } => {
+                walk(iterable, assigned, local);
+                local.extend(key.iter().chain([var]).cloned());
+                walk(body, assigned, local);
+            }
+            ASTNode::For { init, condition, increment, body } => {
+                for c in [init, condition, increment].into_iter().flatten() {
+                    walk(c, assigned, local);
+                }
+                walk(body, assigned, local);
+            }
+            ASTNode::Match { subject, arms, .. } => {
+                walk(subject, assigned, local);
+                arms.iter().for_each(|a| walk(&a.body, assigned, local));
+            }
+            ASTNode::Call { callee, args } => {
+                if let (ASTNode::Identifier(f) | ASTNode::IdentifierSpanned { name: f, .. }, Some(ASTNode::Identifier(v) | ASTNode::IdentifierSpanned { name: v, .. })) =
+                    (&**callee, args.first())
+                {
+                    if f == "push" || f == "pop" {
+                        assigned.insert(v.clone());
+                    }
+                }
+                args.iter().for_each(|a| walk(a, assigned, local));
+            }
+            ASTNode::Return(e) | ASTNode::Log { expr: e, .. } | ASTNode::UnaryExpr { expr: e, .. } | ASTNode::Member { target: e, .. } => walk(e, assigned, local),
+            ASTNode::BinaryExpr { left, right, .. } | ASTNode::Index { target: left, index: right, .. } => {
+                walk(left, assigned, local);
+                walk(right, assigned, local);
+            }
+            ASTNode::ArrayLiteral { items, .. } => items.iter().for_each(|it| walk(it, assigned, local)),
+            ASTNode::ObjectLiteral { fields, .. } => fields.iter().for_each(|(_, v)| walk(v, assigned, local)),
+            _ => {}
+        }
+    }
+    let (mut assigned, mut local) = (BTreeSet::new(), BTreeSet::new());
+    body.iter().for_each(|it| walk(it, &mut assigned, &mut local));
+    assigned.retain(|n| !local.contains(n) && !params.contains(&n.as_str()));
+    assigned
+}
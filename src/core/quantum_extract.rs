@@ -1,55 +1,453 @@
 //! Extract a simple quantum circuit timeline from AST.
 use crate::core::ast::ASTNode;
-use serde::Serialize;
+use crate::core::token::TokenKind;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Clone)]
-pub struct QuantumGate { pub gate: String, pub qubits: Vec<String>, pub line: usize }
+/// `gate` is the op's token name as extracted (`Superpose`, `Entangle`, `Measure`, `Dod`).
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct QuantumGate {
+    pub gate: String,
+    pub qubits: Vec<String>,
+    pub line: usize,
+    /// Classical bit a measurement's result is stored in (`let m = measure(q);`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bit: Option<String>,
+    /// Set for gates inside `if (m == 1) { ... }`: they only run when the bit matches.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<ClassicalCondition>,
+}
+
+/// Classical control: the gate runs when `bit` (a measurement result) equals `value` (0 or 1).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ClassicalCondition { pub bit: String, pub value: u8 }
+
+/// An `if` around quantum gates whose condition is not a measured-bit test. Its gates are
+/// still extracted (unconditionally), so exporters that need the control refuse the circuit.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct CircuitDiagnostic { pub message: String, pub line: usize, pub column: usize }
 
-#[derive(Debug, Serialize, Clone)]
-pub struct QuantumCircuit { pub gates: Vec<QuantumGate>, pub qubit_count: usize }
+/// A `qreg name[size];` declaration; its wires are `name[0]`..`name[size-1]`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct QuantumRegisterInfo { pub name: String, pub size: usize }
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct QuantumCircuit {
+    pub gates: Vec<QuantumGate>,
+    pub qubit_count: usize,
+    pub registers: Vec<QuantumRegisterInfo>,
+    /// Wire labels: loose qubits (sorted) then register elements in declaration order.
+    pub wires: Vec<String>,
+    /// Classical bits measurements are stored in, in order of first store.
+    pub clbits: Vec<String>,
+    /// Conditions that could not be represented, in source order.
+    pub diagnostics: Vec<CircuitDiagnostic>,
+}
+
+/// What one top-level item adds to a circuit, extracted with the classical bits measured
+/// before it (a later `if (m == 1)` depends on them). [`assemble`] joins them back up;
+/// `incremental::quantum_circuit_cached` keeps them per item between edits.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SubCircuit {
+    pub gates: Vec<QuantumGate>,
+    /// Qubits in order of first use.
+    pub qubits: Vec<String>,
+    /// Every `qreg` declaration met, redeclarations included.
+    pub registers: Vec<QuantumRegisterInfo>,
+    /// Bits this item stores a measurement in first.
+    pub clbits: Vec<String>,
+    pub diagnostics: Vec<CircuitDiagnostic>,
+}
+
+impl SubCircuit {
+    /// Purely classical code: nothing to add to the circuit.
+    pub fn is_empty(&self) -> bool {
+        self.gates.is_empty() && self.qubits.is_empty() && self.registers.is_empty() && self.clbits.is_empty() && self.diagnostics.is_empty()
+    }
+}
 
 pub fn extract_circuit(ast: &ASTNode) -> QuantumCircuit {
-    let mut gates = Vec::new();
-    let mut qubit_names: Vec<String> = Vec::new();
-    walk(ast, &mut gates, &mut qubit_names);
-    qubit_names.sort(); qubit_names.dedup();
-    QuantumCircuit { gates, qubit_count: qubit_names.len() }
+    let items = match ast { ASTNode::Program(items) => items.as_slice(), other => std::slice::from_ref(other) };
+    let mut clbits: Vec<String> = Vec::new();
+    let parts: Vec<SubCircuit> = items.iter().map(|item| {
+        let part = extract_item(item, &clbits);
+        clbits.extend(part.clbits.iter().cloned());
+        part
+    }).collect();
+    assemble(&parts)
+}
+
+/// The part of the circuit `item` contributes, after items that stored `clbits`.
+pub fn extract_item(item: &ASTNode, clbits: &[String]) -> SubCircuit {
+    let mut x = Extractor { clbits: clbits.to_vec(), ..Extractor::default() };
+    x.walk(item);
+    let Extractor { gates, qubits, declared, clbits: mut all_bits, diagnostics, .. } = x;
+    SubCircuit { gates, qubits, registers: declared, clbits: all_bits.split_off(clbits.len()), diagnostics }
+}
+
+/// The circuit of a program whose top-level items extracted to `parts`, in order.
+pub fn assemble(parts: &[SubCircuit]) -> QuantumCircuit {
+    let (mut gates, mut qubits, mut registers, mut clbits, mut diagnostics) = (Vec::new(), Vec::<String>::new(), Vec::new(), Vec::<String>::new(), Vec::new());
+    for p in parts {
+        gates.extend(p.gates.iter().cloned());
+        for q in &p.qubits { if !qubits.contains(q) { qubits.push(q.clone()); } }
+        for r in &p.registers { declare_register(&mut registers, r.clone()); }
+        for b in &p.clbits { if !clbits.contains(b) { clbits.push(b.clone()); } }
+        diagnostics.extend(p.diagnostics.iter().cloned());
+    }
+    let mut wires: Vec<String> = qubits.into_iter().filter(|q| register_of(q, &registers).is_none()).collect();
+    wires.sort(); wires.dedup();
+    for r in &registers { for i in 0..r.size { wires.push(format!("{}[{}]", r.name, i)); } }
+    QuantumCircuit { gates, qubit_count: wires.len(), registers, wires, clbits, diagnostics }
+}
+
+/// A redeclared register replaces the earlier one and moves to the end; an identical one is a no-op.
+fn declare_register(regs: &mut Vec<QuantumRegisterInfo>, info: QuantumRegisterInfo) {
+    if !regs.contains(&info) { regs.retain(|r| r.name != info.name); regs.push(info); }
+}
+
+/// Wire label for a gate argument: `q`, `r[0]`, or a symbolic `r[i]` when the index is computed.
+pub fn qubit_label(node: &ASTNode) -> Option<String> {
+    match node {
+        ASTNode::Identifier(name) | ASTNode::IdentifierSpanned { name, .. } => Some(name.clone()),
+        ASTNode::Index { target, index, .. } => Some(format!("{}[{}]", qubit_label(target)?, index_text(index))),
+        _ => None,
+    }
+}
+
+fn index_text(node: &ASTNode) -> String {
+    match node {
+        ASTNode::NumberLiteral(n) if n.fract() == 0.0 => format!("{}", *n as i64),
+        ASTNode::NumberLiteral(n) => n.to_string(),
+        ASTNode::Identifier(name) | ASTNode::IdentifierSpanned { name, .. } => name.clone(),
+        ASTNode::BinaryExpr { op, left, right } => format!("{}{}{}", index_text(left), crate::core::token::TokenKind::to_string(op).trim(), index_text(right)),
+        _ => "?".into(),
+    }
+}
+
+/// Register that owns `label` (`r[2]` -> `r`), if any.
+fn register_of<'a>(label: &str, regs: &'a [QuantumRegisterInfo]) -> Option<&'a QuantumRegisterInfo> {
+    let (name, _) = label.split_once('[')?;
+    regs.iter().find(|r| r.name == name)
+}
+
+// ---------- JSON schema ----------
+//
+// Version 1:
+// { "version": 1,
+//   "qubits": ["a", "r[0]", ...],            wire labels, same order as `QuantumCircuit::wires`
+//   "registers": [{ "name": "r", "size": 3 }],
+//   "ops": [{ "name": "entangle", "targets": ["a", "r[0]"], "params": [], "line": 4 }],
+//   "measurements": [{ "op": 2, "target": "a" }] }   one per measure target, `op` indexes `ops`
+// Op names are the builtins: superpose, entangle, measure, dod. None take params yet.
+// A measure op may carry `"bit": "m"` (where its result is stored) and any op may carry
+// `"condition": { "bit": "m", "value": 1 }`, naming a bit an earlier measure stored.
+
+pub const CIRCUIT_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CircuitJson { version: u32, qubits: Vec<String>, #[serde(default)] registers: Vec<QuantumRegisterInfo>, ops: Vec<OpJson>, #[serde(default)] measurements: Vec<MeasurementJson> }
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OpJson {
+    name: String,
+    targets: Vec<String>,
+    #[serde(default)] params: Vec<f64>,
+    #[serde(default)] line: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")] bit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")] condition: Option<ClassicalCondition>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct MeasurementJson { op: usize, target: String }
+
+/// (schema name, extracted gate name, minimum targets)
+const OPS: &[(&str, &str, usize)] = &[("superpose", "Superpose", 1), ("entangle", "Entangle", 2), ("measure", "Measure", 1), ("dod", "Dod", 1)];
+
+/// A rejected circuit document; `op` is the index into `ops` when one op is at fault.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CircuitJsonError { pub op: Option<usize>, pub message: String }
+
+impl std::fmt::Display for CircuitJsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.op { Some(i) => write!(f, "op #{}: {}", i, self.message), None => f.write_str(&self.message) }
+    }
 }
 
-pub fn circuit_to_json(c: &QuantumCircuit) -> String { serde_json::to_string_pretty(c).unwrap_or_else(|_|"{}".into()) }
+fn measurements_of(ops: &[OpJson]) -> Vec<MeasurementJson> {
+    ops.iter().enumerate().filter(|(_, o)| o.name == "measure").flat_map(|(i, o)| o.targets.iter().map(move |t| MeasurementJson { op: i, target: t.clone() })).collect()
+}
+
+pub fn circuit_to_json(c: &QuantumCircuit) -> String {
+    let ops: Vec<OpJson> = c.gates.iter().map(|g| OpJson { name: OPS.iter().find(|o| o.1 == g.gate).map(|o| o.0.to_string()).unwrap_or_else(|| g.gate.to_lowercase()), targets: g.qubits.clone(), params: Vec::new(), line: g.line, bit: g.bit.clone(), condition: g.condition.clone() }).collect();
+    let doc = CircuitJson { version: CIRCUIT_SCHEMA_VERSION, qubits: c.wires.clone(), registers: c.registers.clone(), measurements: measurements_of(&ops), ops };
+    serde_json::to_string_pretty(&doc).unwrap_or_else(|_|"{}".into())
+}
+
+/// Parse and validate a circuit document written by [`circuit_to_json`] (or by hand / the GUI).
+pub fn circuit_from_json(text: &str) -> Result<QuantumCircuit, CircuitJsonError> {
+    let whole = |message: String| CircuitJsonError { op: None, message };
+    let raw: serde_json::Value = serde_json::from_str(text).map_err(|e| whole(format!("invalid JSON: {}", e)))?;
+    match raw.get("version").and_then(|v| v.as_u64()) {
+        Some(v) if v == CIRCUIT_SCHEMA_VERSION as u64 => {}
+        Some(v) => return Err(whole(format!("unsupported circuit schema version {} (this build reads version {})", v, CIRCUIT_SCHEMA_VERSION))),
+        None => return Err(whole("missing \"version\" field".into())),
+    }
+    let doc: CircuitJson = serde_json::from_value(raw).map_err(|e| whole(format!("malformed circuit: {}", e)))?;
+    for (i, r) in doc.registers.iter().enumerate() {
+        if r.size == 0 { return Err(whole(format!("register '{}' has size 0", r.name))); }
+        if doc.registers[..i].iter().any(|o| o.name == r.name) { return Err(whole(format!("register '{}' declared twice", r.name))); }
+    }
+    let mut seen = std::collections::HashSet::new();
+    for q in &doc.qubits {
+        if !seen.insert(q) { return Err(whole(format!("qubit '{}' listed twice", q))); }
+        if let Some((name, idx)) = q.strip_suffix(']').and_then(|q| q.split_once('[')) {
+            let Some(r) = doc.registers.iter().find(|r| r.name == name) else { return Err(whole(format!("qubit '{}' belongs to undeclared register '{}'", q, name))); };
+            if !idx.parse::<usize>().is_ok_and(|i| i < r.size) { return Err(whole(format!("qubit '{}' is outside register '{}' (size {})", q, name, r.size))); }
+        }
+    }
+    let mut gates = Vec::with_capacity(doc.ops.len());
+    let mut clbits: Vec<String> = Vec::new();
+    for (i, op) in doc.ops.iter().enumerate() {
+        let bad = |message: String| CircuitJsonError { op: Some(i), message };
+        let Some(&(_, gate, min)) = OPS.iter().find(|o| o.0 == op.name) else {
+            return Err(bad(format!("unknown op '{}' (expected one of: {})", op.name, OPS.iter().map(|o| o.0).collect::<Vec<_>>().join(", "))));
+        };
+        if op.targets.len() < min { return Err(bad(format!("{} needs at least {} target(s), got {}", op.name, min, op.targets.len()))); }
+        if !op.params.is_empty() { return Err(bad(format!("{} takes no params, got {}", op.name, op.params.len()))); }
+        if let Some(t) = op.targets.iter().find(|t| !doc.qubits.contains(t)) { return Err(bad(format!("target '{}' is not in \"qubits\"", t))); }
+        if let Some(t) = op.targets.iter().enumerate().find(|(k, t)| op.targets[..*k].contains(t)).map(|(_, t)| t) { return Err(bad(format!("target '{}' used twice", t))); }
+        if let Some(c) = &op.condition {
+            if !clbits.contains(&c.bit) { return Err(bad(format!("condition reads bit '{}' before a measure stores it", c.bit))); }
+            if c.value > 1 { return Err(bad(format!("condition compares bit '{}' with {} (expected 0 or 1)", c.bit, c.value))); }
+        }
+        if let Some(b) = &op.bit {
+            if op.name != "measure" { return Err(bad(format!("{} stores no bit (only measure does)", op.name))); }
+            if !clbits.contains(b) { clbits.push(b.clone()); }
+        }
+        gates.push(QuantumGate { gate: gate.to_string(), qubits: op.targets.clone(), line: op.line, bit: op.bit.clone(), condition: op.condition.clone() });
+    }
+    if !doc.measurements.is_empty() {
+        let expected = measurements_of(&doc.ops);
+        if let Some(m) = doc.measurements.iter().find(|m| !expected.contains(m)) {
+            return Err(CircuitJsonError { op: Some(m.op), message: format!("measurement of '{}' does not match a measure op", m.target) });
+        }
+    }
+    Ok(QuantumCircuit { gates, qubit_count: doc.qubits.len(), registers: doc.registers, wires: doc.qubits, clbits, diagnostics: Vec::new() })
+}
+
+/// A runnable `.ai` program for `c`: qubit / qreg / bit declarations, then one builtin call
+/// per gate; stored measurements assign their bit and conditional gates get an `if`.
+pub fn circuit_to_ai(c: &QuantumCircuit) -> String {
+    let mut out = String::new();
+    for w in c.wires.iter().filter(|w| register_of(w, &c.registers).is_none()) { out.push_str(&format!("qubit {};\n", w)); }
+    for r in &c.registers { out.push_str(&format!("qreg {}[{}];\n", r.name, r.size)); }
+    for b in &c.clbits { out.push_str(&format!("let {} = 0;\n", b)); }
+    for g in &c.gates {
+        let name = OPS.iter().find(|o| o.1 == g.gate).map(|o| o.0.to_string()).unwrap_or_else(|| g.gate.to_lowercase());
+        let mut stmt = format!("{}({});", name, g.qubits.join(", "));
+        if let Some(b) = &g.bit { stmt = format!("{} = {}", b, stmt); }
+        match &g.condition {
+            Some(cond) => out.push_str(&format!("if ({} == {}) {{ {} }}\n", cond.bit, cond.value, stmt)),
+            None => { out.push_str(&stmt); out.push('\n'); }
+        }
+    }
+    out
+}
 
 // Very small pseudo-QASM emitter (not full OpenQASM)
 // Format:
-// qreg q[<count>];\n
-// gate lines: <gate> q[<index>]; or <gate> q[i],q[j]; preserving order qubits appear first time sorted.
+// qreg q[<count>];\n              (loose qubits, sorted)
+// qreg <name>[<size>];\n         (one per declared register)
+// bit <name>;\n                 (one per classical bit a measurement is stored in)
+// gate lines: <gate> q[<index>]; or <gate> q[i],r[j]; register elements keep their own name.
+// A stored measurement is `<bit> = measure q[i];`, a conditional gate `if (<bit> == 1) <gate line>`.
 pub fn circuit_to_pseudo_qasm(c: &QuantumCircuit) -> String {
-    // Map qubit string names to indices stable sorted
-    let mut names: Vec<String> = c.gates.iter().flat_map(|g| g.qubits.clone()).collect();
+    // Map loose qubit names to indices stable sorted
+    let mut names: Vec<String> = c.gates.iter().flat_map(|g| g.qubits.clone()).filter(|q| register_of(q, &c.registers).is_none()).collect();
     names.sort(); names.dedup();
     let mut map = std::collections::HashMap::new();
-    for (i,n) in names.iter().enumerate() { map.insert(n.clone(), i); }
-    let mut out = format!("qreg q[{}];\n", names.len());
-    for g in &c.gates { if !g.qubits.is_empty() { let idxs: Vec<String> = g.qubits.iter().filter_map(|q| map.get(q)).map(|i| format!("q[{}]", i)).collect(); out.push_str(&format!("{} {};// line{}\n", g.gate.to_lowercase(), idxs.join(","), g.line)); } }
+    for (i,n) in names.iter().enumerate() { map.insert(n.clone(), format!("q[{}]", i)); }
+    let mut out = String::new();
+    if !names.is_empty() || c.registers.is_empty() { out.push_str(&format!("qreg q[{}];\n", names.len())); }
+    for r in &c.registers { out.push_str(&format!("qreg {}[{}];\n", r.name, r.size)); map.extend((0..r.size).map(|i| { let l = format!("{}[{}]", r.name, i); (l.clone(), l) })); }
+    for b in &c.clbits { out.push_str(&format!("bit {};\n", b)); }
+    for g in &c.gates {
+        if g.qubits.is_empty() { continue; }
+        let idxs: Vec<String> = g.qubits.iter().map(|q| map.get(q).cloned().unwrap_or_else(|| q.clone())).collect();
+        if let Some(cond) = &g.condition { out.push_str(&format!("if ({} == {}) ", cond.bit, cond.value)); }
+        if let Some(b) = &g.bit { out.push_str(&format!("{} = ", b)); }
+        out.push_str(&format!("{} {};// line{}\n", g.gate.to_lowercase(), idxs.join(","), g.line));
+    }
     out
 }
 
-fn walk(node: &ASTNode, gates: &mut Vec<QuantumGate>, qubits: &mut Vec<String>) {
+/// Plain-text circuit diagram, one row per wire (labelled `q`, `r[0]`, ...), one column per gate.
+/// Gates on a computed register index (`r[i]`) get their own row so they stay visible.
+/// Classical bits follow as double-line rows: a stored measurement drops onto its bit (`╩`)
+/// and a conditional gate hangs from the bit it tests (`●` for == 1, `○` for == 0).
+pub fn circuit_to_ascii(c: &QuantumCircuit) -> String {
+    let mut rows: Vec<String> = c.wires.clone();
+    for g in &c.gates { for q in &g.qubits { if !rows.contains(q) { rows.push(q.clone()); } } }
+    let quantum = rows.len();
+    rows.extend(c.clbits.iter().cloned());
+    let width = rows.iter().map(|r| r.chars().count()).max().unwrap_or(0) + 1;
+    let mut lines: Vec<String> = rows.iter().map(|r| format!("{:<width$}", r, width = width)).collect();
+    for g in &c.gates {
+        let pos: Vec<usize> = g.qubits.iter().filter_map(|q| rows[..quantum].iter().position(|r| r == q)).collect();
+        let (lo, hi) = (pos.iter().min().copied(), pos.iter().max().copied());
+        let bit_row = |name: &String| c.clbits.iter().position(|b| b == name).map(|i| quantum + i);
+        let stored = g.bit.as_ref().and_then(bit_row);
+        let tested = g.condition.as_ref().and_then(|cond| bit_row(&cond.bit).map(|row| (row, cond.value)));
+        // the double line runs from the gate's lowest wire down to the furthest bit it touches
+        let wire_end = stored.max(tested.map(|t| t.0));
+        for (row, line) in lines.iter_mut().enumerate() {
+            let below_gate = hi.is_some_and(|hi| hi < row) && wire_end.is_some_and(|end| row < end);
+            let sym = match pos.iter().position(|p| *p == row) {
+                Some(k) => gate_symbol(&g.gate, k),
+                None if tested.is_some_and(|t| t.0 == row) => if tested.is_some_and(|t| t.1 == 1) { '●' } else { '○' },
+                None if stored == Some(row) => '╩',
+                None if row < quantum && below_gate => '╫',
+                None if row < quantum && lo.is_some_and(|lo| lo < row) && hi.is_some_and(|hi| row < hi) => '│',
+                None if row < quantum => '─',
+                None if below_gate => '╬',
+                None => '═',
+            };
+            let wire = if row < quantum { '─' } else { '═' };
+            line.push(wire); line.push(sym); line.push(wire);
+        }
+    }
+    lines.iter().enumerate().map(|(row, l)| format!("{}{}\n", l, if row < quantum { '─' } else { '═' })).collect()
+}
+
+/// H for superpose, ●/⊕ for entangle's control/target, M for measure, X for dod.
+fn gate_symbol(gate: &str, arg: usize) -> char {
+    match (gate, arg) {
+        ("Superpose", _) => 'H',
+        ("Entangle", 0) => '●',
+        ("Entangle", _) => '⊕',
+        ("Measure", _) => 'M',
+        ("Dod", _) => 'X',
+        _ => '?',
+    }
+}
+
+/// Walk state for [`extract_circuit`].
+#[derive(Default)]
+struct Extractor {
+    gates: Vec<QuantumGate>,
+    qubits: Vec<String>,
+    /// `qreg` declarations in the order met; [`assemble`] resolves redeclarations.
+    declared: Vec<QuantumRegisterInfo>,
+    clbits: Vec<String>,
+    /// Condition of the `if` branch being walked; its gates inherit it.
+    condition: Option<ClassicalCondition>,
+    diagnostics: Vec<CircuitDiagnostic>,
+}
+
+impl Extractor {
+    fn walk(&mut self, node: &ASTNode) {
+        match node {
+            ASTNode::Program(items) | ASTNode::Block(items) => { for it in items { self.walk(it); } }
+            ASTNode::QubitDecl { name, .. } if !self.qubits.contains(name) => { self.qubits.push(name.clone()); }
+            ASTNode::QregDecl { name, size, .. } => self.declared.push(QuantumRegisterInfo { name: name.clone(), size: *size }),
+            ASTNode::QuantumOp { op, qubits: qs, line, .. } => {
+                let qn: Vec<String> = qs.iter().filter_map(qubit_label).collect();
+                for q in &qn { if !self.qubits.contains(q) { self.qubits.push(q.clone()); } }
+                self.gates.push(QuantumGate { gate: format!("{:?}", op), qubits: qn, line: *line, bit: None, condition: self.condition.clone() });
+            }
+            ASTNode::Function { body, .. } => { for it in body { self.walk(it); } }
+            ASTNode::If { condition, then_branch, else_branch } => self.walk_if(condition, then_branch, else_branch.as_deref()),
+            ASTNode::While { body, .. } => self.walk(body),
+            ASTNode::For { body, .. } | ASTNode::ForIn { body, .. } => self.walk(body),
+            ASTNode::Match { arms, .. } => { for arm in arms { self.walk(&arm.body); } }
+            ASTNode::Log { expr, .. } | ASTNode::Return(expr) => self.walk(expr),
+            ASTNode::Assignment { name, value, .. } | ASTNode::VariableDecl { name, value, .. } => {
+                self.walk(value);
+                if matches!(**value, ASTNode::QuantumOp { op: TokenKind::Measure, .. }) { self.store_last(name.clone()); }
+            }
+            ASTNode::DestructuringDecl { values, .. } => { for v in values { self.walk(v); } }
+            ASTNode::BinaryExpr { left, right, .. } => { self.walk(left); self.walk(right); }
+            ASTNode::UnaryExpr { expr, .. } => self.walk(expr),
+            ASTNode::Call { callee, args } => { self.walk(callee); for a in args { self.walk(a); } }
+            _ => {}
+        }
+    }
+
+    /// The last gate is a measurement whose result lands in `bit`.
+    fn store_last(&mut self, bit: String) {
+        if !self.clbits.contains(&bit) { self.clbits.push(bit.clone()); }
+        if let Some(g) = self.gates.last_mut() { g.bit = Some(bit); }
+    }
+
+    /// Branches of a bit test get its condition (the else branch the opposite value); any
+    /// other condition around gates is reported at the condition expression.
+    fn walk_if(&mut self, condition: &ASTNode, then_branch: &ASTNode, else_branch: Option<&ASTNode>) {
+        self.walk(condition);
+        let (first_gate, first_diagnostic) = (self.gates.len(), self.diagnostics.len());
+        let outer = self.condition.clone();
+        let control = if outer.is_some() { Err("nested classical conditions can't be exported") } else { self.bit_test(condition).ok_or("classical control needs a measured-bit test such as `m == 1`, `!m` or `measure(q)`") };
+        self.condition = control.clone().ok().or_else(|| outer.clone());
+        self.walk(then_branch);
+        if let Some(e) = else_branch {
+            self.condition = control.clone().ok().map(|c| ClassicalCondition { value: 1 - c.value, ..c }).or_else(|| outer.clone());
+            self.walk(e);
+        }
+        self.condition = outer;
+        if let (Err(message), Some(g)) = (control, self.gates.get(first_gate)) {
+            let (line, column) = expr_position(condition).unwrap_or((g.line, 1));
+            self.diagnostics.insert(first_diagnostic, CircuitDiagnostic { message: message.to_string(), line, column });
+        }
+    }
+
+    /// `m`, `!m`, `m == 1`, `0 != m` and the like, where `m` is a stored measurement or a
+    /// `measure(q)` right in the condition (which then stores into a fresh bit `cN`).
+    fn bit_test(&mut self, node: &ASTNode) -> Option<ClassicalCondition> {
+        match node {
+            ASTNode::UnaryExpr { op: TokenKind::Bang, expr } => self.bit_test(expr).map(|c| ClassicalCondition { value: 1 - c.value, ..c }),
+            ASTNode::BinaryExpr { op: op @ (TokenKind::DoubleEquals | TokenKind::NotEquals), left, right } => {
+                let (operand, value) = match (bit_literal(left), bit_literal(right)) {
+                    (None, Some(v)) => (left, v),
+                    (Some(v), None) => (right, v),
+                    _ => return None,
+                };
+                let bit = self.bit_operand(operand)?;
+                Some(ClassicalCondition { bit, value: if *op == TokenKind::DoubleEquals { value } else { 1 - value } })
+            }
+            other => self.bit_operand(other).map(|bit| ClassicalCondition { bit, value: 1 }),
+        }
+    }
+
+    fn bit_operand(&mut self, node: &ASTNode) -> Option<String> {
+        match node {
+            ASTNode::Identifier(name) | ASTNode::IdentifierSpanned { name, .. } => self.clbits.contains(name).then(|| name.clone()),
+            ASTNode::QuantumOp { op: TokenKind::Measure, .. } => {
+                // walk_if walked the condition first, so its measurement is the last gate
+                self.gates.last().filter(|g| g.gate == "Measure" && g.bit.is_none())?;
+                let bit = (0..).map(|i| format!("c{}", i)).find(|b| !self.clbits.contains(b))?;
+                self.store_last(bit.clone());
+                Some(bit)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// 0 or 1 as a number or boolean literal.
+fn bit_literal(node: &ASTNode) -> Option<u8> {
+    match node {
+        ASTNode::NumberLiteral(n) if *n == 0.0 || *n == 1.0 => Some(*n as u8),
+        ASTNode::BooleanLiteral(b) => Some(*b as u8),
+        _ => None,
+    }
+}
+
+/// Position of the leftmost spanned node in an expression.
+fn expr_position(node: &ASTNode) -> Option<(usize, usize)> {
     match node {
-        ASTNode::Program(items) | ASTNode::Block(items) => { for it in items { walk(it, gates, qubits); } }
-        ASTNode::QuantumOp { op, qubits: qs } => {
-            let qn: Vec<String> = qs.iter().filter_map(|q| match q { ASTNode::Identifier(name) => Some(name.clone()), ASTNode::IdentifierSpanned { name, .. } => Some(name.clone()), _ => None }).collect();
-            for q in &qn { if !qubits.contains(q) { qubits.push(q.clone()); } }
-            gates.push(QuantumGate { gate: format!("{:?}", op), qubits: qn, line: 0 });
-        }
-        ASTNode::Function { body, .. } => { for it in body { walk(it, gates, qubits); } }
-        ASTNode::If { then_branch, else_branch, .. } => { walk(then_branch, gates, qubits); if let Some(e)=else_branch { walk(e, gates, qubits); } }
-        ASTNode::While { body, .. } => walk(body, gates, qubits),
-        ASTNode::For { body, .. } => walk(body, gates, qubits),
-        ASTNode::Log(expr) | ASTNode::Return(expr) => walk(expr, gates, qubits),
-        ASTNode::Assignment { value, .. } | ASTNode::VariableDecl { value, .. } => walk(value, gates, qubits),
-        ASTNode::BinaryExpr { left, right, .. } => { walk(left, gates, qubits); walk(right, gates, qubits); }
-        ASTNode::UnaryExpr { expr, .. } => walk(expr, gates, qubits),
-        ASTNode::Call { callee, args } => { walk(callee, gates, qubits); for a in args { walk(a, gates, qubits); } }
-        _ => {}
+        ASTNode::IdentifierSpanned { line, column, .. } | ASTNode::QuantumOp { line, column, .. } => Some((*line, *column)),
+        ASTNode::BinaryExpr { left, right, .. } => expr_position(left).or_else(|| expr_position(right)),
+        ASTNode::UnaryExpr { expr, .. } => expr_position(expr),
+        ASTNode::Call { callee, .. } | ASTNode::Index { target: callee, .. } | ASTNode::Member { target: callee, .. } => expr_position(callee),
+        _ => None,
     }
 }
@@ -2,26 +2,37 @@
 #![allow(dead_code)]
 
 use anyhow::{bail, Context, Result};
+use serde::Serialize;
 use std::{fs, path::Path};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum OpKind {
     H,
     X,
     CNOT,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct Op {
     pub kind: OpKind,
     pub targets: Vec<usize>,
     pub params: Vec<f64>,
 }
 
-#[derive(Clone, Debug)]
+/// Named register: `name[i]` is qubit `offset + i`.
+#[derive(Clone, Debug, Serialize)]
+pub struct Register {
+    pub name: String,
+    pub offset: usize,
+    pub size: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub struct Circuit {
     pub n_qubits: usize,
     pub ops: Vec<Op>,
+    pub registers: Vec<Register>,
 }
 
 impl Circuit {
@@ -29,6 +40,15 @@ impl Circuit {
         Self {
             n_qubits,
             ops: Vec::new(),
+            registers: Vec::new(),
+        }
+    }
+
+    /// Wire label for qubit `q`: `r[1]` when it belongs to a register, `q[3]` otherwise.
+    pub fn wire_label(&self, q: usize) -> String {
+        match self.registers.iter().find(|r| (r.offset..r.offset + r.size).contains(&q)) {
+            Some(r) => format!("{}[{}]", r.name, q - r.offset),
+            None => format!("q[{}]", q),
         }
     }
     pub fn push(&mut self, op: Op) {
@@ -41,7 +61,9 @@ impl Circuit {
 ///   h i
 ///   x i
 ///   cnot c t
-/// Lines may contain comments starting with `//`.
+///   qreg NAME N
+/// Targets are plain indices or register elements (`r[0]`); registers are laid
+/// out after one another from qubit 0. Lines may contain comments starting with `//`.
 ///
 /// Examples:
 ///   qubits 1
@@ -50,6 +72,10 @@ impl Circuit {
 ///   qubits 2
 ///   h 0
 ///   cnot 0 1
+///
+///   qreg r 2
+///   h r[0]
+///   cnot r[0] r[1]
 pub fn parse_ai_to_ir(path: &Path) -> Result<Circuit> {
     let src =
         fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
@@ -57,6 +83,7 @@ pub fn parse_ai_to_ir(path: &Path) -> Result<Circuit> {
     let mut n_qubits: Option<usize> = None;
     let mut ops: Vec<Op> = Vec::new();
     let mut max_target_seen: isize = -1;
+    let mut registers: Vec<Register> = Vec::new();
 
     for (lineno, raw) in src.lines().enumerate() {
         let line = raw.split("//").next().unwrap_or("").trim();
@@ -83,11 +110,27 @@ pub fn parse_ai_to_ir(path: &Path) -> Result<Circuit> {
                 n_qubits = Some(n);
             }
 
+            "qreg" => {
+                if toks.len() != 3 {
+                    bail!(bad("usage: qreg <name> <N>"));
+                }
+                let size: usize = toks[2].parse().map_err(|_| bad("invalid register size"))?;
+                if size == 0 {
+                    bail!(bad("register size must be ≥ 1"));
+                }
+                if registers.iter().any(|r| r.name == toks[1]) {
+                    bail!(bad(&format!("register '{}' already declared", toks[1])));
+                }
+                let offset = registers.last().map(|r| r.offset + r.size).unwrap_or(0);
+                max_target_seen = max_target_seen.max((offset + size - 1) as isize);
+                registers.push(Register { name: toks[1].to_string(), offset, size });
+            }
+
             "h" | "x" => {
                 if toks.len() != 2 {
                     bail!(bad("usage: h <i>   or   x <i>"));
                 }
-                let i: usize = toks[1].parse().map_err(|_| bad("invalid target index"))?;
+                let i = resolve_target(toks[1], &registers).map_err(|e| bad(&e))?;
                 max_target_seen = max_target_seen.max(i as isize);
                 let kind = if kw == "h" { OpKind::H } else { OpKind::X };
                 ops.push(Op {
@@ -101,8 +144,8 @@ pub fn parse_ai_to_ir(path: &Path) -> Result<Circuit> {
                 if toks.len() != 3 {
                     bail!(bad("usage: cnot <control> <target>"));
                 }
-                let c: usize = toks[1].parse().map_err(|_| bad("invalid control index"))?;
-                let t: usize = toks[2].parse().map_err(|_| bad("invalid target index"))?;
+                let c = resolve_target(toks[1], &registers).map_err(|e| bad(&e))?;
+                let t = resolve_target(toks[2], &registers).map_err(|e| bad(&e))?;
                 if c == t {
                     bail!(bad("cnot control and target must differ"));
                 }
@@ -139,5 +182,24 @@ pub fn parse_ai_to_ir(path: &Path) -> Result<Circuit> {
         }
     }
 
-    Ok(Circuit { n_qubits, ops })
+    Ok(Circuit { n_qubits, ops, registers })
+}
+
+/// `3` -> 3, `r[1]` -> offset of `r` + 1 (bounds-checked against the register size).
+fn resolve_target(tok: &str, registers: &[Register]) -> std::result::Result<usize, String> {
+    let Some((name, rest)) = tok.split_once('[') else {
+        return tok.parse().map_err(|_| format!("invalid target '{tok}'"));
+    };
+    let idx: usize = rest
+        .strip_suffix(']')
+        .and_then(|i| i.parse().ok())
+        .ok_or_else(|| format!("invalid register index '{tok}'"))?;
+    let reg = registers
+        .iter()
+        .find(|r| r.name == name)
+        .ok_or_else(|| format!("unknown register '{name}'"))?;
+    if idx >= reg.size {
+        return Err(format!("index {idx} out of bounds for register '{name}' of size {}", reg.size));
+    }
+    Ok(reg.offset + idx)
 }
@@ -0,0 +1,128 @@
+//! Quantum usage lints: flags programs that are legal syntax but physically suspicious.
+//!
+//! Rules (all warnings):
+//! - AEO-Q001: measuring a qubit that was already measured
+//! - AEO-Q002: applying a gate to a qubit after it was measured
+//! - AEO-Q003: `entangle(q, q)` — a qubit entangled with itself
+//! - AEO-Q004: a `qubit` declaration that is never measured
+//!
+//! Tracking is flow-sensitive but conservative: after an `if`, a qubit counts as
+//! measured only when both branches measured it; loop bodies don't leak state.
+//! AEO-Q004 needs the whole program, so it is skipped when linting a single
+//! top-level node (the GUI's per-node diagnostic cache does this).
+use crate::core::ast::ASTNode;
+use crate::core::semantic_analyzer::{SemanticDiagnostic, Severity};
+use crate::core::token::TokenKind;
+use std::collections::{HashMap, HashSet};
+
+pub const RULE_DOUBLE_MEASURE: &str = "AEO-Q001";
+pub const RULE_GATE_AFTER_MEASURE: &str = "AEO-Q002";
+pub const RULE_SELF_ENTANGLE: &str = "AEO-Q003";
+pub const RULE_NEVER_MEASURED: &str = "AEO-Q004";
+
+/// qubit name -> (line, column) of the measurement that collapsed it
+type Measured = HashMap<String, (usize, usize)>;
+
+#[derive(Default)]
+struct Linter {
+    declared: Vec<(String, usize, usize)>,
+    ever_measured: HashSet<String>,
+    diags: Vec<SemanticDiagnostic>,
+}
+
+/// Run the quantum usage rules over a parsed program. Returns no diagnostics for classical code.
+pub fn lint_quantum(ast: &ASTNode) -> Vec<SemanticDiagnostic> {
+    let mut l = Linter::default();
+    let mut measured = Measured::new();
+    l.walk(ast, &mut measured);
+    if !matches!(ast, ASTNode::Program(_)) {
+        return l.diags;
+    }
+    for (name, line, column) in std::mem::take(&mut l.declared) {
+        if !l.ever_measured.contains(&name) {
+            l.warn(RULE_NEVER_MEASURED, format!("Qubit '{name}' is declared but never measured"), line, column, name.len());
+        }
+    }
+    l.diags
+}
+
+fn qubit_names(qubits: &[ASTNode]) -> Vec<String> {
+    qubits.iter().filter_map(|q| match q {
+        ASTNode::Identifier(name) | ASTNode::IdentifierSpanned { name, .. } => Some(name.clone()),
+        // `r[1]` is tracked per element; a computed index could be any element, so skip it
+        ASTNode::Index { index, .. } if matches!(**index, ASTNode::NumberLiteral(_)) => crate::core::quantum_extract::qubit_label(q),
+        _ => None,
+    }).collect()
+}
+
+fn op_name(op: &TokenKind) -> &'static str {
+    match op {
+        TokenKind::Superpose => "superpose",
+        TokenKind::Entangle => "entangle",
+        TokenKind::Measure => "measure",
+        TokenKind::Dod => "dod",
+        _ => "qop",
+    }
+}
+
+impl Linter {
+    fn warn(&mut self, rule: &'static str, message: String, line: usize, column: usize, len: usize) {
+        self.diags.push(SemanticDiagnostic { message, line, column, len: len.max(1), severity: Severity::Warning, rule: Some(rule), related: Vec::new(), fix: None });
+    }
+
+    fn walk(&mut self, node: &ASTNode, measured: &mut Measured) {
+        match node {
+            ASTNode::Program(items) | ASTNode::Block(items) => { for it in items { self.walk(it, measured); } }
+            ASTNode::Function { body, .. } => {
+                // Function bodies get their own timeline; parameters are unknown on entry.
+                let mut local = Measured::new();
+                for it in body { self.walk(it, &mut local); }
+            }
+            ASTNode::QubitDecl { name, line, column } => {
+                measured.remove(name);
+                self.declared.push((name.clone(), *line, *column));
+            }
+            ASTNode::If { then_branch, else_branch, .. } => {
+                let mut then_m = measured.clone();
+                self.walk(then_branch, &mut then_m);
+                let mut else_m = measured.clone();
+                if let Some(e) = else_branch { self.walk(e, &mut else_m); }
+                measured.clear();
+                for (q, at) in then_m { if else_m.contains_key(&q) { measured.insert(q, at); } }
+            }
+            ASTNode::While { body, .. } | ASTNode::For { body, .. } | ASTNode::ForIn { body, .. } => {
+                let mut inner = measured.clone();
+                self.walk(body, &mut inner);
+            }
+            ASTNode::QuantumOp { op, qubits, line, column } => self.quantum_op(op, qubits, *line, *column, measured),
+            // quantum ops can also appear as values, e.g. `let b = measure(q);`
+            ASTNode::VariableDecl { value: e, .. } | ASTNode::Assignment { value: e, .. } | ASTNode::Return(e) | ASTNode::Log { expr: e, .. } | ASTNode::UnaryExpr { expr: e, .. } => self.walk(e, measured),
+            ASTNode::BinaryExpr { left, right, .. } => { self.walk(left, measured); self.walk(right, measured); }
+            ASTNode::Call { callee, args } => { self.walk(callee, measured); for a in args { self.walk(a, measured); } }
+            _ => {}
+        }
+    }
+
+    fn quantum_op(&mut self, op: &TokenKind, qubits: &[ASTNode], line: usize, column: usize, measured: &mut Measured) {
+        let names = qubit_names(qubits);
+        let kind = op_name(op);
+        if matches!(op, TokenKind::Entangle) && names.len() >= 2 && names[0] == names[1] {
+            self.warn(RULE_SELF_ENTANGLE, format!("entangle({0}, {0}) entangles qubit '{0}' with itself", names[0]), line, column, kind.len());
+        }
+        for q in names {
+            match (op, measured.get(&q).copied()) {
+                (TokenKind::Measure, Some((ml, mc))) => {
+                    self.warn(RULE_DOUBLE_MEASURE, format!("Qubit '{q}' measured again (already measured at {ml}:{mc})"), line, column, kind.len());
+                }
+                (TokenKind::Measure, None) => {
+                    measured.insert(q.clone(), (line, column));
+                }
+                (_, Some((ml, mc))) => {
+                    self.warn(RULE_GATE_AFTER_MEASURE, format!("'{kind}' applied to qubit '{q}' after measurement at {ml}:{mc}"), line, column, kind.len());
+                }
+                (_, None) => {}
+            }
+            if matches!(op, TokenKind::Measure) { self.ever_measured.insert(q); }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! Parameterized starter circuits (`aeonmi quantum template`, the GUI's template picker).
+//!
+//! Each template builds an AST and prints it through the canonical AI emitter, then the
+//! result is parsed again before it is returned, so an instantiated template always
+//! parses. Templates that have a bundled example name it, so front ends can offer the
+//! annotated version from [`crate::core::examples`] next to the generated one.
+//!
+//! The VM's gate set is H (`superpose`), CNOT (`entangle`) and X (`dod`), which has no
+//! Toffoli; Grover is therefore limited to two qubits until a multi-controlled gate lands.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::core::ai_emitter::AiEmitter;
+use crate::core::ast::ASTNode;
+use crate::core::lexer::Lexer;
+use crate::core::parser::Parser;
+use crate::core::token::TokenKind;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct TemplateParam {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub default: usize,
+    pub min: usize,
+    pub max: usize,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Template {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub params: &'static [TemplateParam],
+    /// Name of the matching entry in the examples registry, if any.
+    pub example: Option<&'static str>,
+    #[serde(skip)]
+    build: fn(&BTreeMap<String, usize>) -> Vec<ASTNode>,
+}
+
+const SHOTS: TemplateParam = TemplateParam { name: "shots", description: "Shots in the printed histogram", default: 200, min: 1, max: 100_000 };
+
+pub static TEMPLATES: &[Template] = &[
+    Template { name: "bell", description: "Bell pair: two perfectly correlated qubits", params: &[SHOTS], example: Some("bell"), build: bell },
+    Template {
+        name: "ghz",
+        description: "GHZ state: n qubits that all measure 0 or all measure 1",
+        params: &[TemplateParam { name: "qubits", description: "Register size", default: 3, min: 2, max: 12 }, SHOTS],
+        example: None,
+        build: ghz,
+    },
+    Template {
+        name: "grover",
+        description: "Grover search for one marked basis state (2 qubits: one iteration finds it every time)",
+        params: &[
+            TemplateParam { name: "qubits", description: "Search register size", default: 2, min: 2, max: 2 },
+            TemplateParam { name: "marked", description: "Marked basis state, as an integer", default: 3, min: 0, max: 3 },
+            SHOTS,
+        ],
+        example: Some("grover"),
+        build: grover,
+    },
+    Template {
+        name: "teleportation",
+        description: "Teleport a basis state from Alice to Bob over a Bell pair",
+        params: &[TemplateParam { name: "state", description: "Bit Alice sends (0 or 1)", default: 1, min: 0, max: 1 }, SHOTS],
+        example: Some("teleport"),
+        build: teleportation,
+    },
+];
+
+/// Look up a template by name, ignoring ASCII case.
+pub fn find(name: &str) -> Option<&'static Template> {
+    TEMPLATES.iter().find(|t| t.name.eq_ignore_ascii_case(name.trim()))
+}
+
+/// `.ai` source for template `name`; missing params take their defaults, out-of-range or
+/// unknown ones are errors.
+pub fn instantiate(name: &str, params: &BTreeMap<String, usize>) -> Result<String, String> {
+    let t = find(name).ok_or_else(|| format!("unknown template '{name}' (known: {})", TEMPLATES.iter().map(|t| t.name).collect::<Vec<_>>().join(", ")))?;
+    if let Some(extra) = params.keys().find(|k| !t.params.iter().any(|p| p.name == k.as_str())) {
+        return Err(format!("template '{}' has no parameter '{extra}'", t.name));
+    }
+    let mut values = BTreeMap::new();
+    for p in t.params {
+        let v = params.get(p.name).copied().unwrap_or(p.default);
+        if v < p.min || v > p.max {
+            return Err(format!("{}: {} must be in {}..={}, got {v}", t.name, p.name, p.min, p.max));
+        }
+        values.insert(p.name.to_string(), v);
+    }
+    let body = AiEmitter::new().generate(&ASTNode::Program((t.build)(&values)))?;
+    let args: Vec<String> = values.iter().map(|(k, v)| format!("{k}={v}")).collect();
+    let source = format!("// template {} ({})\n{body}", t.name, args.join(", "));
+    let tokens = Lexer::from_str(&source).tokenize().map_err(|e| format!("template '{}' emitted invalid source: {e}", t.name))?;
+    Parser::new(tokens).parse().map_err(|e| format!("template '{}' emitted invalid source: {e}", t.name))?;
+    Ok(source)
+}
+
+// --- AST builders ---
+
+fn ident(name: &str) -> ASTNode {
+    ASTNode::Identifier(name.to_string())
+}
+
+fn num(n: usize) -> ASTNode {
+    ASTNode::NumberLiteral(n as f64)
+}
+
+fn call(name: &str, args: Vec<ASTNode>) -> ASTNode {
+    ASTNode::Call { callee: Box::new(ident(name)), args }
+}
+
+fn op(op: TokenKind, qubits: Vec<ASTNode>) -> ASTNode {
+    ASTNode::QuantumOp { op, qubits, line: 0, column: 0 }
+}
+
+fn qubit(name: &str) -> ASTNode {
+    ASTNode::QubitDecl { name: name.to_string(), line: 0, column: 0 }
+}
+
+fn reg(name: &str, i: usize) -> ASTNode {
+    ASTNode::Index { target: Box::new(ident(name)), index: Box::new(num(i)), line: 0, column: 0 }
+}
+
+fn log(expr: ASTNode) -> ASTNode {
+    ASTNode::Log { expr: Box::new(expr), line: 0, column: 0 }
+}
+
+fn function(name: &str, body: Vec<ASTNode>) -> ASTNode {
+    ASTNode::new_function(name, vec![], body)
+}
+
+/// `function name() { body }` then the histogram of `shots` runs of it.
+fn sampled(name: &str, body: Vec<ASTNode>, p: &BTreeMap<String, usize>) -> Vec<ASTNode> {
+    vec![function(name, body), log(call("run_shots", vec![num(p["shots"]), ident(name)]))]
+}
+
+fn bell(p: &BTreeMap<String, usize>) -> Vec<ASTNode> {
+    let (a, b) = (ident("a"), ident("b"));
+    sampled(
+        "bell",
+        vec![qubit("a"), qubit("b"), op(TokenKind::Superpose, vec![a.clone()]), op(TokenKind::Entangle, vec![a.clone(), b.clone()]), op(TokenKind::Measure, vec![a]), op(TokenKind::Measure, vec![b])],
+        p,
+    )
+}
+
+fn ghz(p: &BTreeMap<String, usize>) -> Vec<ASTNode> {
+    let n = p["qubits"];
+    let mut body = vec![ASTNode::QregDecl { name: "q".into(), size: n, line: 0, column: 0 }, op(TokenKind::Superpose, vec![reg("q", 0)])];
+    body.extend((1..n).map(|i| op(TokenKind::Entangle, vec![reg("q", 0), reg("q", i)])));
+    body.extend((0..n).map(|i| op(TokenKind::Measure, vec![reg("q", i)])));
+    sampled("ghz", body, p)
+}
+
+/// Controlled-Z from the available gates: H on the target around a CNOT.
+fn cz(control: ASTNode, target: ASTNode) -> Vec<ASTNode> {
+    vec![op(TokenKind::Superpose, vec![target.clone()]), op(TokenKind::Entangle, vec![control, target.clone()]), op(TokenKind::Superpose, vec![target])]
+}
+
+fn grover(p: &BTreeMap<String, usize>) -> Vec<ASTNode> {
+    let (n, marked) = (p["qubits"], p["marked"]);
+    let all = |gate: TokenKind| (0..n).map(move |i| op(gate.clone(), vec![reg("q", i)]));
+    // X on every qubit whose bit in `marked` is 0 (q[0] is the most significant bit)
+    let flip_zeros = || (0..n).filter(move |i| marked >> (n - 1 - i) & 1 == 0).map(|i| op(TokenKind::Dod, vec![reg("q", i)]));
+    let mut body = vec![ASTNode::QregDecl { name: "q".into(), size: n, line: 0, column: 0 }];
+    body.extend(all(TokenKind::Superpose));
+    // Oracle: phase-flip |marked>
+    body.extend(flip_zeros());
+    body.extend(cz(reg("q", 0), reg("q", 1)));
+    body.extend(flip_zeros());
+    // Diffusion: inversion about the mean
+    body.extend(all(TokenKind::Superpose));
+    body.extend(all(TokenKind::Dod));
+    body.extend(cz(reg("q", 0), reg("q", 1)));
+    body.extend(all(TokenKind::Dod));
+    body.extend(all(TokenKind::Superpose));
+    body.extend(all(TokenKind::Measure));
+    sampled("grover", body, p)
+}
+
+fn teleportation(p: &BTreeMap<String, usize>) -> Vec<ASTNode> {
+    let (alice, pair, bob) = (ident("alice"), ident("pair_a"), ident("bob"));
+    let mut body = vec![qubit("alice"), qubit("pair_a"), qubit("bob")];
+    // Bell pair shared by Alice (pair_a) and Bob
+    body.push(op(TokenKind::Superpose, vec![pair.clone()]));
+    body.push(op(TokenKind::Entangle, vec![pair.clone(), bob.clone()]));
+    if p["state"] == 1 {
+        body.push(op(TokenKind::Dod, vec![alice.clone()]));
+    }
+    body.push(op(TokenKind::Entangle, vec![alice.clone(), pair.clone()]));
+    body.push(op(TokenKind::Superpose, vec![alice.clone()]));
+    body.push(op(TokenKind::Measure, vec![alice]));
+    // Bob's X correction; the Z one only changes a phase that measurement cannot see
+    body.push(ASTNode::If {
+        condition: Box::new(ASTNode::BinaryExpr { op: TokenKind::DoubleEquals, left: Box::new(op(TokenKind::Measure, vec![pair])), right: Box::new(num(1)) }),
+        then_branch: Box::new(ASTNode::Block(vec![op(TokenKind::Dod, vec![bob.clone()])])),
+        else_branch: None,
+    });
+    body.push(op(TokenKind::Measure, vec![bob]));
+    sampled("teleport", body, p)
+}
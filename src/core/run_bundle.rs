@@ -0,0 +1,111 @@
+//! Quantum run bundles: the directory `aeonmi quantum run --save-run DIR` writes.
+//!
+//! A bundle holds the input source, the circuit that was simulated, the histogram, the
+//! statevector (up to [`MAX_STATEVECTOR_QUBITS`] qubits) and a `manifest.json` with the
+//! provenance: compiler version, source SHA-1, backend, shots, seed, noise model and wall
+//! time. `aeonmi quantum compare A B` reads two bundles back and reports the total
+//! variation distance between their histograms.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Bumped when the layout of the bundle changes.
+pub const SCHEMA: u32 = 1;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+pub const SOURCE_FILE: &str = "source.ai";
+pub const CIRCUIT_FILE: &str = "circuit.json";
+pub const HISTOGRAM_FILE: &str = "histogram.json";
+pub const STATEVECTOR_FILE: &str = "statevector.json";
+
+/// Larger states (2^n amplitudes) are left out of the bundle.
+pub const MAX_STATEVECTOR_QUBITS: usize = 10;
+
+/// Outcome bitstring (qubit 0 first) → count.
+pub type Histogram = BTreeMap<String, usize>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunManifest {
+    pub schema: u32,
+    pub compiler_version: String,
+    /// The file as given on the command line; its text is kept in `source.ai`.
+    pub source_file: String,
+    pub source_sha1: String,
+    pub backend: String,
+    pub qubits: usize,
+    pub shots: usize,
+    pub seed: u64,
+    /// Noise model the backend applied; `None` for noiseless simulation.
+    pub noise: Option<String>,
+    pub wall_time_ms: f64,
+    pub created_at: String,
+    /// Files written next to the manifest.
+    pub files: Vec<String>,
+}
+
+/// Everything a run leaves behind.
+#[derive(Debug, Clone)]
+pub struct RunBundle {
+    pub manifest: RunManifest,
+    pub source: String,
+    pub circuit: serde_json::Value,
+    pub histogram: Histogram,
+    /// `[re, im]` per basis state, when the register is small enough.
+    pub statevector: Option<Vec<[f64; 2]>>,
+}
+
+pub fn source_sha1(source: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(source.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+impl RunBundle {
+    /// Write the bundle into `dir` (created if missing); the manifest lists what was written.
+    pub fn write(&mut self, dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+        let mut files = vec![SOURCE_FILE, CIRCUIT_FILE, HISTOGRAM_FILE];
+        let put = |name: &str, text: String| std::fs::write(dir.join(name), text).with_context(|| format!("writing {}", dir.join(name).display()));
+        put(SOURCE_FILE, self.source.clone())?;
+        put(CIRCUIT_FILE, serde_json::to_string_pretty(&self.circuit)?)?;
+        put(HISTOGRAM_FILE, serde_json::to_string_pretty(&self.histogram)?)?;
+        if let Some(sv) = &self.statevector {
+            put(STATEVECTOR_FILE, serde_json::to_string(sv)?)?;
+            files.push(STATEVECTOR_FILE);
+        }
+        self.manifest.files = files.into_iter().map(str::to_string).collect();
+        put(MANIFEST_FILE, serde_json::to_string_pretty(&self.manifest)?)
+    }
+
+    /// Read a bundle written by [`RunBundle::write`].
+    pub fn load(dir: &Path) -> Result<Self> {
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).with_context(|| format!("reading {}", dir.join(name).display()));
+        let manifest: RunManifest = serde_json::from_str(&read(MANIFEST_FILE)?).with_context(|| format!("{}: not a run manifest", dir.join(MANIFEST_FILE).display()))?;
+        if manifest.schema != SCHEMA {
+            bail!("{}: unsupported run bundle schema {} (this build reads {})", dir.display(), manifest.schema, SCHEMA);
+        }
+        let histogram = serde_json::from_str(&read(HISTOGRAM_FILE)?).with_context(|| format!("{}: bad histogram", dir.display()))?;
+        let circuit = serde_json::from_str(&read(CIRCUIT_FILE)?).with_context(|| format!("{}: bad circuit", dir.display()))?;
+        let statevector = match manifest.files.iter().any(|f| f == STATEVECTOR_FILE) {
+            true => Some(serde_json::from_str(&read(STATEVECTOR_FILE)?).with_context(|| format!("{}: bad statevector", dir.display()))?),
+            false => None,
+        };
+        Ok(RunBundle { source: read(SOURCE_FILE)?, manifest, circuit, histogram, statevector })
+    }
+}
+
+/// Total variation distance between two histograms' empirical distributions:
+/// half the sum of absolute probability differences, in `[0, 1]`. Empty histograms count as
+/// identical to each other and maximally far from anything else.
+pub fn total_variation(a: &Histogram, b: &Histogram) -> f64 {
+    let (na, nb) = (a.values().sum::<usize>() as f64, b.values().sum::<usize>() as f64);
+    if na == 0.0 || nb == 0.0 {
+        return if na == nb { 0.0 } else { 1.0 };
+    }
+    let p = |h: &Histogram, n: f64, k: &str| h.get(k).copied().unwrap_or(0) as f64 / n;
+    let keys: std::collections::BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+    keys.into_iter().map(|k| (p(a, na, k) - p(b, nb, k)).abs()).sum::<f64>() / 2.0
+}
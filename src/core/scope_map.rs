@@ -25,6 +25,12 @@ impl ScopeMap {
         visit(ast, &mut sm, &mut stack, 0);
         sm
     }
+    /// Nesting depth of scope `id` (the program scope is 0).
+    pub fn depth(&self, id: usize) -> usize {
+        let mut depth = 0; let mut cur = id;
+        while let Some(Some(p)) = self.parents.get(cur) { depth += 1; cur = *p; }
+        depth
+    }
     pub fn occurrences_in_same_scope(&self, name: &str, line: usize, column: usize) -> Vec<(usize,usize,bool)> {
         let mut target_scope: Option<usize> = None;
         if let Some(entries) = self.symbols.get(name) {
@@ -37,6 +43,30 @@ impl ScopeMap {
     }
 }
 
+/// Rename the symbol at (`line`, `column`) and its same-scope occurrences in `source`.
+/// Columns are lexer byte columns; lines that were not NFC are mapped back onto the raw text.
+pub fn rename_symbol(source: &str, line: usize, column: usize, new_name: &str) -> Result<String, String> {
+    let ast = crate::core::incremental::parse_or_cached(source)?;
+    let sm = ScopeMap::build(&ast);
+    let Some(orig_name) = sm.symbols.iter().find(|(_, occs)| occs.iter().any(|o| o.line == line && o.column == column)).map(|(n, _)| n.clone()) else {
+        return Err("symbol not found at position".into());
+    };
+    if orig_name == new_name { return Ok(source.to_string()); }
+    let mut occs = sm.occurrences_in_same_scope(&orig_name, line, column);
+    if occs.is_empty() { return Err("no occurrences in scope".into()); }
+    // Right to left, so earlier edits on a line do not shift the columns still to come.
+    occs.sort_by_key(|o| std::cmp::Reverse((o.0, o.1)));
+    let mut lines: Vec<String> = source.lines().map(|s| s.to_string()).collect();
+    for (ol, oc, _is_def) in occs {
+        let Some(line_str) = lines.get_mut(ol.saturating_sub(1)) else { continue };
+        let Some(start) = crate::core::columns::source_offset(line_str, oc) else { continue };
+        if line_str[start..].starts_with(orig_name.as_str()) {
+            line_str.replace_range(start..start + orig_name.len(), new_name);
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
 fn visit(node: &ASTNode, sm: &mut ScopeMap, stack: &mut Vec<usize>, current: usize) {
     use ASTNode::*;
     match node {
@@ -53,20 +83,41 @@ fn visit(node: &ASTNode, sm: &mut ScopeMap, stack: &mut Vec<usize>, current: usi
             for st in body { visit(st, sm, stack, new_id); }
             stack.pop();
         }
+        QubitDecl { name, line, column } | QregDecl { name, line, column, .. } | EnumDecl { name, line, column, .. } => { record(sm, name, *line, *column, *stack.last().unwrap(), true); }
         VariableDecl { name, line, column, value } => { record(sm, name, *line, *column, *stack.last().unwrap(), true); visit(value, sm, stack, current); }
+        DestructuringDecl { targets, values, .. } => {
+            for v in values { visit(v, sm, stack, current); }
+            for (name, line, column) in targets.iter().flat_map(|t| t.names()) { record(sm, name, line, column, *stack.last().unwrap(), true); }
+        }
         Assignment { name, line, column, value } => { record(sm, name, *line, *column, *stack.last().unwrap(), false); visit(value, sm, stack, current); }
         If { condition, then_branch, else_branch } => { visit(condition, sm, stack, current); visit(then_branch, sm, stack, current); if let Some(e)=else_branch { visit(e, sm, stack, current); } }
         While { condition, body } => { visit(condition, sm, stack, current); visit(body, sm, stack, current); }
         For { init, condition, increment, body } => { if let Some(i)=init { visit(i, sm, stack, current); } if let Some(c)=condition { visit(c, sm, stack, current); } if let Some(inc)=increment { visit(inc, sm, stack, current); } visit(body, sm, stack, current); }
+        ForIn { key, var, iterable, body, .. } => {
+            visit(iterable, sm, stack, current);
+            let new_id = sm.parents.len(); sm.parents.push(Some(current)); stack.push(new_id);
+            for name in key.iter().chain([var]) { record(sm, name, 0, 0, new_id, true); }
+            visit(body, sm, stack, new_id);
+            stack.pop();
+        }
+        Break { .. } | Continue { .. } => {}
         BinaryExpr { left, right, .. } => { visit(left, sm, stack, current); visit(right, sm, stack, current); }
         UnaryExpr { expr, .. } => visit(expr, sm, stack, current),
+        Index { target, index, .. } => { visit(target, sm, stack, current); visit(index, sm, stack, current); }
+        Member { target, .. } => visit(target, sm, stack, current),
+        ArrayLiteral { items, .. } => { for it in items { visit(it, sm, stack, current); } }
+        ObjectLiteral { fields, .. } => { for (_, v) in fields { visit(v, sm, stack, current); } }
+        Match { subject, arms, .. } => {
+            visit(subject, sm, stack, current);
+            for arm in arms { if let Some(p) = &arm.pattern { visit(p, sm, stack, current); } visit(&arm.body, sm, stack, current); }
+        }
         Call { callee, args } => { visit(callee, sm, stack, current); for a in args { visit(a, sm, stack, current); } }
-        Return(expr) | Log(expr) => visit(expr, sm, stack, current),
+        Return(expr) | Log { expr, .. } => visit(expr, sm, stack, current),
         QuantumOp { qubits, .. } => { for q in qubits { visit(q, sm, stack, current); } }
         HieroglyphicOp { args, .. } => { for a in args { visit(a, sm, stack, current); } }
     Identifier(name) => { record(sm, name, 0,0, *stack.last().unwrap(), false); }
     IdentifierSpanned { name, line, column, len:_ } => { record(sm, name, *line, *column, *stack.last().unwrap(), false); }
-        NumberLiteral(_) | StringLiteral(_) | BooleanLiteral(_) | Error(_) => {}
+        NumberLiteral(_) | StringLiteral(_) | BooleanLiteral(_) | Import { .. } | Error(_) => {}
     }
 }
 
@@ -10,9 +10,81 @@
 //! 4. Coercion rules scaffold (e.g. number <-> string in concatenation) with warnings.
 //! 5. Quantum / glyph op arity validation.
 
-use crate::core::ast::{ASTNode, FunctionParam};
+use crate::core::analysis_budget::{AnalysisBudget, Truncation};
+use crate::core::ast::{ASTNode, FunctionParam, MatchArm, Pattern};
+use crate::core::code_actions::{QuickFix, TextEdit};
+use crate::core::error_index::{ENUM_COMPARISON, NON_EXHAUSTIVE_MATCH, REDECLARATION, NOT_INDEXABLE, NOT_ITERABLE, SHADOWED_VARIABLE, UNDECLARED_ASSIGNMENT, UNKNOWN_VARIANT};
+use crate::core::token::TokenKind;
+use serde::Serialize;
 use std::collections::{HashSet, HashMap};
 
+/// Functions the runtimes provide; calls to these never need a declaration.
+const BUILTIN_FUNCTIONS: &[&str] = &["print", "log", "time_ms", "rand", "env", "len", "push", "pop", "slice", "read_line", "read_all", "read_file", "write_file", "counts", "run_shots", "minimize", "grid_search", "simulate_ode", "logistic_map", "derivative", "simplify", "superpose", "entangle", "measure", "dod"];
+
+/// Names of all functions declared anywhere in `node`.
+pub fn function_names(node: &ASTNode) -> HashSet<String> {
+    let mut out = HashSet::new();
+    collect_function_names(node, &mut out);
+    out
+}
+
+fn collect_function_names(node: &ASTNode, out: &mut HashSet<String>) {
+    match node {
+        ASTNode::Program(items) | ASTNode::Block(items) => { for it in items { collect_function_names(it, out); } }
+        ASTNode::Function { name, body, .. } => { out.insert(name.clone()); for it in body { collect_function_names(it, out); } }
+        ASTNode::If { then_branch, else_branch, .. } => { collect_function_names(then_branch, out); if let Some(e) = else_branch { collect_function_names(e, out); } }
+        ASTNode::While { body, .. } | ASTNode::For { body, .. } | ASTNode::ForIn { body, .. } => collect_function_names(body, out),
+        _ => {}
+    }
+}
+
+fn is_relational(op: &TokenKind) -> bool {
+    matches!(op, TokenKind::LessThan | TokenKind::LessEqual | TokenKind::GreaterThan | TokenKind::GreaterEqual)
+}
+
+/// Position of the leftmost identifier in `node`, the best span binary expressions have.
+fn first_span(node: &ASTNode) -> Option<(usize, usize)> {
+    match node {
+        ASTNode::IdentifierSpanned { line, column, .. } => Some((*line, *column)),
+        ASTNode::BinaryExpr { left, right, .. } => first_span(left).or_else(|| first_span(right)),
+        ASTNode::UnaryExpr { expr, .. } => first_span(expr),
+        ASTNode::Call { callee, .. } | ASTNode::Index { target: callee, .. } | ASTNode::Member { target: callee, .. } => first_span(callee),
+        _ => None,
+    }
+}
+
+/// Where a top-level item starts, for notes about it as a whole.
+fn item_position(node: &ASTNode) -> Option<(usize, usize)> {
+    match node {
+        ASTNode::Function { line, column, .. }
+        | ASTNode::VariableDecl { line, column, .. }
+        | ASTNode::Assignment { line, column, .. }
+        | ASTNode::Log { line, column, .. }
+        | ASTNode::QubitDecl { line, column, .. }
+        | ASTNode::QregDecl { line, column, .. }
+        | ASTNode::QuantumOp { line, column, .. }
+        | ASTNode::ForIn { line, column, .. }
+        | ASTNode::Match { line, column, .. } => Some((*line, *column)),
+        other => first_span(other),
+    }
+}
+
+/// Short source-like rendering of an expression for diagnostic messages.
+fn render(node: &ASTNode) -> String {
+    match node {
+        ASTNode::Identifier(n) | ASTNode::IdentifierSpanned { name: n, .. } => n.clone(),
+        ASTNode::NumberLiteral(v) => v.to_string(),
+        ASTNode::StringLiteral(s) => format!("{s:?}"),
+        ASTNode::BooleanLiteral(b) => b.to_string(),
+        ASTNode::BinaryExpr { op, left, right } => format!("{} {} {}", render(left), op, render(right)),
+        ASTNode::UnaryExpr { op, expr } => format!("{}{}", op, render(expr)),
+        ASTNode::Call { callee, .. } => format!("{}(…)", render(callee)),
+        ASTNode::Index { target, index, .. } => format!("{}[{}]", render(target), render(index)),
+        ASTNode::Member { target, name, .. } => format!("{}.{}", render(target), name),
+        _ => "…".to_string(),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SemanticDiagnostic {
     pub message: String,
@@ -20,10 +92,31 @@ pub struct SemanticDiagnostic {
     pub column: usize,
     pub len: usize,
     pub severity: Severity,
+    /// Stable rule id (e.g. `AEO-Q001`); None for checks without an error index entry.
+    pub rule: Option<&'static str>,
+    /// Other spans the diagnostic refers to, e.g. the declaration a warning is about.
+    pub related: Vec<RelatedInfo>,
+    /// Concrete edits that resolve the diagnostic, for editor lightbulbs.
+    pub fix: Option<QuickFix>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RelatedInfo {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+    pub len: usize,
 }
 
+/// `Info` is only used for notes about the analysis itself, such as a truncation.
 #[derive(Debug, Clone, PartialEq)]
-pub enum Severity { Error, Warning }
+pub enum Severity { Error, Warning, Info }
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self { Severity::Error => "error", Severity::Warning => "warning", Severity::Info => "info" }
+    }
+}
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 enum ValueType { Number, String, Bool, Unknown }
@@ -31,15 +124,21 @@ enum ValueType { Number, String, Bool, Unknown }
 impl Default for ValueType { fn default() -> Self { ValueType::Unknown } }
 
 #[derive(Default)]
-struct VarInfo { line: usize, column: usize, used: bool, ty: ValueType }
+struct VarInfo { line: usize, column: usize, used: bool, ty: ValueType, qreg_size: Option<usize>, enum_ty: Option<String> }
+
+struct EnumInfo { variants: Vec<String>, line: usize, column: usize }
 
 pub struct SemanticAnalyzer {
     scopes: Vec<HashSet<String>>,
     var_meta: Vec<std::collections::HashMap<String, VarInfo>>, // parallel stack with metadata
     functions: HashMap<String, (usize, usize)>, // track function declarations (line,column) for duplicate detection
     used_functions: HashSet<String>,            // function call sites
+    declared_functions: HashSet<String>,        // every function in the program, collected up front so calls may precede declarations
+    enums: HashMap<String, EnumInfo>,
     errors: Vec<String>,            // legacy string list for existing callers
     diags: Vec<SemanticDiagnostic>, // unified diagnostics (errors + warnings)
+    budget: Option<AnalysisBudget>,
+    truncated: Option<Truncation>,
 }
 
 impl SemanticAnalyzer {
@@ -51,21 +150,55 @@ impl SemanticAnalyzer {
             diags: vec![],
             functions: HashMap::new(),
             used_functions: HashSet::new(),
+            declared_functions: HashSet::new(),
+            enums: HashMap::new(),
+            budget: None,
+            truncated: None,
         }
     }
 
+    /// Stop between top-level items once `budget` runs out, keeping the diagnostics found so far.
+    pub fn with_budget(mut self, budget: AnalysisBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Set when the budget ran out before the last top-level item.
+    pub fn truncation(&self) -> Option<&Truncation> {
+        self.truncated.as_ref()
+    }
+
+    /// Treat `names` as declared functions; for analyzing one item of a larger program
+    /// whose other functions are not in the tree being visited.
+    pub fn with_known_functions(mut self, names: impl IntoIterator<Item = String>) -> Self {
+        self.declared_functions.extend(names);
+        self
+    }
+
     pub fn analyze(&mut self, ast: &ASTNode) -> Result<(), String> {
+        collect_function_names(ast, &mut self.declared_functions);
         self.visit(ast, false);
-        self.post_pass();
-        self.flush_unused_warnings();
+        if self.truncated.is_none() {
+            self.post_pass();
+            self.flush_unused_warnings();
+        }
         if self.errors.is_empty() { Ok(()) } else { Err(self.errors.join("\n")) }
     }
 
     #[cfg_attr(not(test), allow(dead_code))]
     pub fn analyze_with_spans(&mut self, ast: &ASTNode) -> Vec<SemanticDiagnostic> {
+        collect_function_names(ast, &mut self.declared_functions);
         self.visit(ast, true);
+        // Whole-program checks would flag names the skipped items use, so a truncated run
+        // ends with a note at the first item it skipped instead.
+        if let Some(t) = &self.truncated {
+            let (line, column) = match ast { ASTNode::Program(items) => items.get(t.analyzed).and_then(item_position), _ => None }.unwrap_or((1, 1));
+            self.diags.push(SemanticDiagnostic { message: t.to_string(), line, column, len: 1, severity: Severity::Info, rule: None, related: Vec::new(), fix: None });
+            return self.diags.clone();
+        }
         self.post_pass();
         self.flush_unused_warnings();
+        self.diags.extend(crate::core::quantum_lint::lint_quantum(ast));
         self.diags.clone()
     }
 
@@ -76,7 +209,7 @@ impl SemanticAnalyzer {
         for (name,(line,column)) in self.functions.clone() { // clone to avoid borrow issues
             // skip if any scope recorded it as used identifier
         if !self.used_functions.contains(&name) {
-                self.diags.push(SemanticDiagnostic { message: format!("Unused function '{name}'"), line, column, len: name.len().max(1), severity: Severity::Warning });
+                self.diags.push(SemanticDiagnostic { message: format!("Unused function '{name}'"), line, column, len: name.len().max(1), severity: Severity::Warning, rule: None, related: Vec::new(), fix: None });
             }
         }
     }
@@ -94,7 +227,7 @@ impl SemanticAnalyzer {
             for (name, info) in map.into_iter() {
                 if !info.used {
                     let msg = format!("Unused variable '{}'", name);
-                    self.diags.push(SemanticDiagnostic { message: msg, line: info.line, column: info.column, len: name.len().max(1), severity: Severity::Warning });
+                    self.diags.push(SemanticDiagnostic { message: msg, line: info.line, column: info.column, len: name.len().max(1), severity: Severity::Warning, rule: None, related: Vec::new(), fix: None });
                 }
             }
         }
@@ -107,18 +240,80 @@ impl SemanticAnalyzer {
             let msg = format!("Redeclaration of '{}'", name);
             self.errors.push(msg.clone());
             if let (Some(l), Some(c)) = (line, column) {
-                self.diags.push(SemanticDiagnostic { message: msg, line: l, column: c, len: name.len().max(1), severity: Severity::Error });
+                let related = meta.get(name).filter(|prev| prev.line > 0)
+                    .map(|prev| RelatedInfo { message: format!("'{}' first declared here", name), line: prev.line, column: prev.column, len: name.len().max(1) })
+                    .into_iter().collect();
+                self.diags.push(SemanticDiagnostic { message: msg, line: l, column: c, len: name.len().max(1), severity: Severity::Error, rule: Some(REDECLARATION), related, fix: None });
             }
         } else {
             scope.insert(name.to_string());
             if let (Some(l), Some(c)) = (line, column) {
-                meta.insert(name.to_string(), VarInfo { line: l, column: c, used: false, ty: ValueType::Unknown });
+                meta.insert(name.to_string(), VarInfo { line: l, column: c, used: false, ty: ValueType::Unknown, qreg_size: None, enum_ty: None });
             } else {
-                meta.insert(name.to_string(), VarInfo { line: 0, column: 0, used: false, ty: ValueType::Unknown });
+                meta.insert(name.to_string(), VarInfo { line: 0, column: 0, used: false, ty: ValueType::Unknown, qreg_size: None, enum_ty: None });
+            }
+        }
+    }
+
+    /// Where `name` is declared in an enclosing scope, if it is.
+    fn outer_declaration(&self, name: &str) -> Option<(usize, usize)> {
+        let outer = &self.var_meta[..self.var_meta.len() - 1];
+        outer.iter().rev().find_map(|m| m.get(name)).filter(|info| info.line > 0).map(|info| (info.line, info.column))
+    }
+
+    /// The enum whose variant `node` evaluates to, when that is known statically.
+    fn enum_of(&self, node: &ASTNode) -> Option<String> {
+        match node {
+            ASTNode::Member { target, .. } => match &**target {
+                ASTNode::Identifier(e) | ASTNode::IdentifierSpanned { name: e, .. } if self.enums.contains_key(e) => Some(e.clone()),
+                _ => None,
+            },
+            ASTNode::Identifier(n) | ASTNode::IdentifierSpanned { name: n, .. } => {
+                self.var_meta.iter().rev().find_map(|m| m.get(n)).and_then(|info| info.enum_ty.clone())
             }
+            _ => None,
         }
     }
 
+    fn enum_related(&self, name: &str) -> Vec<RelatedInfo> {
+        self.enums.get(name).filter(|e| e.line > 0)
+            .map(|e| RelatedInfo { message: format!("enum '{name}' declared here"), line: e.line, column: e.column, len: name.len().max(1) })
+            .into_iter().collect()
+    }
+
+    /// Warn when a `match` whose patterns are all variants of one enum leaves some out and has no `_` arm.
+    fn check_exhaustive(&mut self, arms: &[MatchArm], line: usize, column: usize) {
+        if arms.iter().any(|a| a.pattern.is_none()) { return; }
+        let mut enum_name: Option<String> = None;
+        let mut covered = HashSet::new();
+        for arm in arms {
+            let Some(pattern) = &arm.pattern else { continue };
+            let (Some(e), ASTNode::Member { name, .. }) = (self.enum_of(pattern), pattern) else { return };
+            if *enum_name.get_or_insert_with(|| e.clone()) != e { return; }
+            covered.insert(name.clone());
+        }
+        let Some(e) = enum_name else { return };
+        let missing: Vec<&str> = self.enums[&e].variants.iter().filter(|v| !covered.contains(*v)).map(|v| v.as_str()).collect();
+        if missing.is_empty() { return; }
+        let msg = format!("Non-exhaustive match on enum '{e}': missing {}", missing.iter().map(|v| format!("{e}.{v}")).collect::<Vec<_>>().join(", "));
+        let related = self.enum_related(&e);
+        self.diags.push(SemanticDiagnostic { message: msg, line, column, len: "match".len(), severity: Severity::Warning, rule: Some(NON_EXHAUSTIVE_MATCH), related, fix: None });
+    }
+
+    fn qreg_size(&self, name: &str) -> Option<usize> {
+        self.var_meta.iter().rev().find_map(|m| m.get(name)).and_then(|info| info.qreg_size)
+    }
+
+    /// Declare a `let`-bound name, warning when it shadows one from an enclosing scope.
+    fn declare_variable(&mut self, name: &str, line: usize, column: usize, capture: bool) {
+        let redeclared = self.scopes.last().is_some_and(|s| s.contains(name));
+        if let (true, false, Some((l, c))) = (capture, redeclared, self.outer_declaration(name)) {
+            let related = vec![RelatedInfo { message: format!("shadowed declaration of '{name}'"), line: l, column: c, len: name.len().max(1) }];
+            self.diags.push(SemanticDiagnostic { message: format!("Variable '{name}' shadows an outer declaration"), line, column, len: name.len().max(1), severity: Severity::Warning, rule: Some(SHADOWED_VARIABLE), related, fix: None });
+        }
+        self.declare(name, Some(line), Some(column));
+    }
+
     fn is_declared(&self, name: &str) -> bool {
         for scope in self.scopes.iter().rev() {
             if scope.contains(name) {
@@ -131,7 +326,13 @@ impl SemanticAnalyzer {
     fn visit(&mut self, node: &ASTNode, capture: bool) {
         match node {
             ASTNode::Program(items) => {
-                for it in items {
+                for (i, it) in items.iter().enumerate() {
+                    if let Some(budget) = &mut self.budget {
+                        if let Err(t) = budget.admit(i, items) {
+                            self.truncated = Some(t);
+                            break;
+                        }
+                    }
                     self.visit(it, capture);
                 }
             }
@@ -147,7 +348,10 @@ impl SemanticAnalyzer {
                 if let Some((prev_l, prev_c)) = self.functions.get(name) {
                     let msg = format!("Duplicate function '{name}' (previous at {prev_l}:{prev_c})");
                     self.errors.push(msg.clone());
-                    if capture { self.diags.push(SemanticDiagnostic { message: msg, line: *line, column: *column, len: name.len().max(1), severity: Severity::Error }); }
+                    if capture {
+                        let related = vec![RelatedInfo { message: format!("'{name}' first defined here"), line: *prev_l, column: *prev_c, len: name.len().max(1) }];
+                        self.diags.push(SemanticDiagnostic { message: msg, line: *line, column: *column, len: name.len().max(1), severity: Severity::Error, rule: None, related, fix: None });
+                    }
                 } else {
                     self.functions.insert(name.clone(), (*line, *column));
                 }
@@ -168,7 +372,7 @@ impl SemanticAnalyzer {
                                                    ASTNode::Function { line, column, .. } => (*line,*column),
                                                    ASTNode::IdentifierSpanned { line, column, .. } => (*line,*column),
                                                    _ => (0,0) };
-                            self.diags.push(SemanticDiagnostic { message: "Unreachable code after return".into(), line: l, column: c, len: 1, severity: Severity::Warning });
+                            self.diags.push(SemanticDiagnostic { message: "Unreachable code after return".into(), line: l, column: c, len: 1, severity: Severity::Warning, rule: None, related: Vec::new(), fix: None });
                         }
                         // still traverse in case of further symbol usage (optionally skip)
                         self.visit(it, capture);
@@ -185,29 +389,112 @@ impl SemanticAnalyzer {
                 if saw_return {
                     // Path consistency heuristic: if last stmt not a Return warn missing terminal return.
                     if !matches!(body.last(), Some(ASTNode::Return(_))) {
-                        if capture { self.diags.push(SemanticDiagnostic { message: format!("Not all code paths return a value in function '{name}'"), line: *line, column: *column, len: name.len().max(1), severity: Severity::Warning }); }
+                        if capture { self.diags.push(SemanticDiagnostic { message: format!("Not all code paths return a value in function '{name}'"), line: *line, column: *column, len: name.len().max(1), severity: Severity::Warning, rule: None, related: Vec::new(), fix: None }); }
                     }
                     // Return type consistency (ignore Unknown)
                     let mut distinct: Vec<ValueType> = return_types.iter().copied().filter(|t| *t != ValueType::Unknown).collect();
                     distinct.sort_by(|a,b| (*a as u8).cmp(&(*b as u8)));
                     distinct.dedup();
                     if distinct.len() > 1 {
-                        if capture { self.diags.push(SemanticDiagnostic { message: format!("Inconsistent return types in function '{name}'"), line: *line, column: *column, len: name.len().max(1), severity: Severity::Warning }); }
+                        if capture { self.diags.push(SemanticDiagnostic { message: format!("Inconsistent return types in function '{name}'"), line: *line, column: *column, len: name.len().max(1), severity: Severity::Warning, rule: None, related: Vec::new(), fix: None }); }
                     }
                 }
             }
             ASTNode::VariableDecl { name, value, line, column } => {
                 self.visit(value, capture);
-                self.declare(name, Some(*line), Some(*column));
+                self.declare_variable(name, *line, *column, capture);
                 let ty = self.expr_type(value);
                 self.set_var_type(name, ty);
+                let enum_ty = self.enum_of(value);
+                if let Some(info) = self.var_meta.last_mut().unwrap().get_mut(name) { info.enum_ty = enum_ty; }
+            }
+            ASTNode::DestructuringDecl { targets, values, .. } => {
+                for v in values {
+                    self.visit(v, capture);
+                }
+                for (target, value) in targets.iter().zip(values) {
+                    let ty = self.expr_type(value);
+                    for (name, line, column) in target.names() {
+                        self.declare_variable(name, line, column, capture);
+                        match target {
+                            Pattern::Name { .. } => self.set_var_type(name, ty),
+                            // The parts of a string are its characters.
+                            Pattern::Array { .. } if ty == ValueType::String => self.set_var_type(name, ValueType::String),
+                            Pattern::Array { .. } => {}
+                        }
+                    }
+                }
             }
+            ASTNode::EnumDecl { name, variants, line, column } => {
+                self.declare(name, Some(*line), Some(*column));
+                self.mark_used(name);
+                let mut seen = HashSet::new();
+                for v in variants {
+                    if !seen.insert(v) {
+                        let msg = format!("Duplicate variant '{v}' in enum '{name}'");
+                        self.errors.push(msg.clone());
+                        if capture { self.diags.push(SemanticDiagnostic { message: msg, line: *line, column: *column, len: name.len().max(1), severity: Severity::Error, rule: None, related: Vec::new(), fix: None }); }
+                    }
+                }
+                self.enums.insert(name.clone(), EnumInfo { variants: variants.clone(), line: *line, column: *column });
+            }
+            ASTNode::Member { target, name, line, column } => {
+                self.visit(target, capture);
+                if let Some(e) = self.enum_of(node) {
+                    if !self.enums[&e].variants.contains(name) {
+                        let msg = format!("Enum '{e}' has no variant '{name}'");
+                        self.errors.push(msg.clone());
+                        if capture {
+                            let related = self.enum_related(&e);
+                            self.diags.push(SemanticDiagnostic { message: msg, line: *line, column: *column, len: name.len().max(1), severity: Severity::Error, rule: Some(UNKNOWN_VARIANT), related, fix: None });
+                        }
+                    }
+                }
+            }
+            ASTNode::Match { subject, arms, line, column } => {
+                self.visit(subject, capture);
+                for arm in arms {
+                    if let Some(p) = &arm.pattern {
+                        self.visit(p, capture);
+                        self.check_binary(&TokenKind::DoubleEquals, subject, p, capture);
+                    }
+                    self.visit(&arm.body, capture);
+                }
+                if capture { self.check_exhaustive(arms, *line, *column); }
+            }
+            ASTNode::ForIn { key, var, iterable, body, line, column } => {
+                self.visit(iterable, capture);
+                let ty = self.expr_type(iterable);
+                if matches!(ty, ValueType::Number | ValueType::Bool) {
+                    let what = if ty == ValueType::Number { "a number" } else { "a boolean" };
+                    let msg = format!("Cannot iterate over {what}; expected an array, string or map");
+                    self.errors.push(msg.clone());
+                    if capture { self.diags.push(SemanticDiagnostic { message: msg, line: *line, column: *column, len: "for".len(), severity: Severity::Error, rule: Some(NOT_ITERABLE), related: Vec::new(), fix: None }); }
+                }
+                self.begin_scope();
+                if let Some(k) = key {
+                    self.declare(k, Some(*line), Some(*column));
+                }
+                self.declare(var, Some(*line), Some(*column));
+                if ty == ValueType::String {
+                    self.set_var_type(var, ValueType::String);
+                    if let Some(k) = key { self.set_var_type(k, ValueType::Number); }
+                }
+                self.visit(body, capture);
+                self.end_scope();
+            }
+            ASTNode::Break { .. } | ASTNode::Continue { .. } => {}
             ASTNode::Assignment { name, value, line, column } => {
                 if !self.is_declared(name) {
                     let msg = format!("Assignment to undeclared variable '{}'", name);
                     self.errors.push(msg.clone());
                     if capture {
-                        self.diags.push(SemanticDiagnostic { message: msg, line: *line, column: *column, len: name.len().max(1), severity: Severity::Error });
+                        let fix = QuickFix {
+                            title: format!("Add missing 'let' for '{name}'"),
+                            kind: "addMissingLet".into(),
+                            edits: vec![TextEdit::insert(*line, *column, "let ")],
+                        };
+                        self.diags.push(SemanticDiagnostic { message: msg, line: *line, column: *column, len: name.len().max(1), severity: Severity::Error, rule: Some(UNDECLARED_ASSIGNMENT), related: Vec::new(), fix: Some(fix) });
                     }
                 }
                 // write counts as a use
@@ -215,7 +502,7 @@ impl SemanticAnalyzer {
                 self.mark_used(name);
             }
             ASTNode::Return(expr)
-            | ASTNode::Log(expr)
+            | ASTNode::Log { expr, .. }
             | ASTNode::While {
                 condition: expr,
                 body: _,
@@ -255,17 +542,43 @@ impl SemanticAnalyzer {
             ASTNode::BinaryExpr { op, left, right } => {
                 self.visit(left, capture);
                 self.visit(right, capture);
-                self.check_binary(op, left, right, capture);
+                match &**left {
+                    // `a < b < c` parses as `(a < b) < c`: a boolean compared with a number
+                    ASTNode::BinaryExpr { op: inner, right: middle, .. } if is_relational(op) && is_relational(inner) => {
+                        if capture {
+                            let (line, column) = first_span(left).unwrap_or((0, 0));
+                            let msg = format!(
+                                "Chained comparison '{} {} {}' compares a boolean with '{}'; did you mean '{} && {} {} {}'?",
+                                render(left), op, render(right), render(right), render(left), render(middle), op, render(right)
+                            );
+                            self.diags.push(SemanticDiagnostic { message: msg, line, column, len: 1, severity: Severity::Warning, rule: Some(crate::core::error_index::CHAINED_COMPARISON), related: Vec::new(), fix: None });
+                        }
+                    }
+                    _ => self.check_binary(op, left, right, capture),
+                }
             }
             ASTNode::UnaryExpr { expr, .. } => self.visit(expr, capture),
             ASTNode::Call { callee, args } => {
                 if let ASTNode::Identifier(n) = &**callee { self.used_functions.insert(n.clone()); }
-                if let ASTNode::IdentifierSpanned { name, .. } = &**callee { self.used_functions.insert(name.clone()); }
+                if let ASTNode::IdentifierSpanned { name, line, column, .. } = &**callee {
+                    self.used_functions.insert(name.clone());
+                    if !self.declared_functions.contains(name) && !self.is_declared(name) && !BUILTIN_FUNCTIONS.contains(&name.as_str()) {
+                        let msg = format!("Call to undefined function '{name}'");
+                        self.errors.push(msg.clone());
+                        if capture { self.diags.push(SemanticDiagnostic { message: msg, line: *line, column: *column, len: name.len().max(1), severity: Severity::Error, rule: None, related: Vec::new(), fix: None }); }
+                    }
+                }
                 self.visit(callee, capture);
                 for a in args {
                     self.visit(a, capture);
                 }
             }
+            ASTNode::ArrayLiteral { items, .. } => {
+                for item in items { self.visit(item, capture); }
+            }
+            ASTNode::ObjectLiteral { fields, .. } => {
+                for (_, value) in fields { self.visit(value, capture); }
+            }
 
             // literals / identifiers / quantum/glyph / error
             ASTNode::Identifier(name) => { self.mark_used(name); }
@@ -274,8 +587,36 @@ impl SemanticAnalyzer {
             | ASTNode::StringLiteral(_)
             | ASTNode::BooleanLiteral(_)
             | ASTNode::HieroglyphicOp { .. }
+            | ASTNode::Import { .. }
             | ASTNode::Error(_) => {}
-            ASTNode::QuantumOp { op, qubits } => {
+            ASTNode::QubitDecl { name, line, column } => {
+                self.declare(name, Some(*line), Some(*column));
+            }
+            ASTNode::QregDecl { name, size, line, column } => {
+                self.declare(name, Some(*line), Some(*column));
+                if let Some(info) = self.var_meta.last_mut().unwrap().get_mut(name) { info.qreg_size = Some(*size); }
+            }
+            ASTNode::Index { target, index, line, column } => {
+                self.visit(target, capture);
+                self.visit(index, capture);
+                // literal indices into a known register are checked now; the rest at runtime
+                let reg = match &**target { ASTNode::Identifier(n) | ASTNode::IdentifierSpanned { name: n, .. } => Some(n.as_str()), _ => None };
+                if let (Some(reg), ASTNode::NumberLiteral(i)) = (reg, &**index) {
+                    if let Some(size) = self.qreg_size(reg) {
+                        if *i < 0.0 || i.fract() != 0.0 || *i as usize >= size {
+                            let msg = format!("Index {} out of bounds for register '{}' of size {}", i, reg, size);
+                            self.errors.push(msg.clone());
+                            if capture { self.diags.push(SemanticDiagnostic { message: msg, line: *line, column: *column, len: 1, severity: Severity::Error, rule: Some(crate::core::error_index::QREG_OUT_OF_BOUNDS), related: Vec::new(), fix: None }); }
+                        }
+                    }
+                }
+                if let Some(what) = self.not_indexable(target, index) {
+                    let msg = format!("Cannot index {what}");
+                    self.errors.push(msg.clone());
+                    if capture { self.diags.push(SemanticDiagnostic { message: msg, line: *line, column: *column, len: 1, severity: Severity::Error, rule: Some(NOT_INDEXABLE), related: Vec::new(), fix: None }); }
+                }
+            }
+            ASTNode::QuantumOp { op, qubits, line, column } => {
                 // Arity validation
                 let qlen = qubits.len();
                 let (min, kind_name) = match op {
@@ -288,8 +629,9 @@ impl SemanticAnalyzer {
                 if qlen < min {
                     let msg = format!("Quantum op '{kind_name}' expects >= {min} qubit(s) but got {qlen}");
                     self.errors.push(msg.clone());
-                    if capture { self.diags.push(SemanticDiagnostic { message: msg, line: 0, column: 0, len: 1, severity: Severity::Error }); }
+                    if capture { self.diags.push(SemanticDiagnostic { message: msg, line: *line, column: *column, len: kind_name.len(), severity: Severity::Error, rule: None, related: Vec::new(), fix: None }); }
                 }
+                for q in qubits { self.visit(q, capture); }
             }
         }
     }
@@ -313,6 +655,21 @@ impl SemanticAnalyzer {
         ValueType::Unknown
     }
 
+    /// What `target[index]` indexes when that can never succeed: a number or boolean,
+    /// an array literal by a non-number, or an object literal by a non-string.
+    fn not_indexable(&self, target: &ASTNode, index: &ASTNode) -> Option<&'static str> {
+        match (target, index) {
+            (ASTNode::ArrayLiteral { .. }, ASTNode::StringLiteral(_) | ASTNode::BooleanLiteral(_)) => return Some("an array with a non-number key"),
+            (ASTNode::ObjectLiteral { .. }, ASTNode::NumberLiteral(_) | ASTNode::BooleanLiteral(_)) => return Some("an object with a non-string key"),
+            _ => {}
+        }
+        match self.expr_type(target) {
+            ValueType::Number => Some("a number"),
+            ValueType::Bool => Some("a boolean"),
+            _ => None,
+        }
+    }
+
     fn expr_type(&self, node: &ASTNode) -> ValueType {
         use ValueType::*;
         match node {
@@ -327,13 +684,14 @@ impl SemanticAnalyzer {
                     crate::core::token::TokenKind::Plus => {
                         if lt == String || rt == String { String } else if lt == Number && rt == Number { Number } else { Unknown }
                     }
-                    crate::core::token::TokenKind::Minus | crate::core::token::TokenKind::Star | crate::core::token::TokenKind::Slash => {
+                    crate::core::token::TokenKind::Minus | crate::core::token::TokenKind::Star | crate::core::token::TokenKind::Slash | crate::core::token::TokenKind::Percent | crate::core::token::TokenKind::StarStar => {
                         if lt == Number && rt == Number { Number } else { Unknown }
                     }
                     crate::core::token::TokenKind::DoubleEquals | crate::core::token::TokenKind::NotEquals | crate::core::token::TokenKind::LessThan | crate::core::token::TokenKind::LessEqual | crate::core::token::TokenKind::GreaterThan | crate::core::token::TokenKind::GreaterEqual => Bool,
                     _ => Unknown
                 }
             }
+            ASTNode::UnaryExpr { op: crate::core::token::TokenKind::Bang, .. } => Bool,
             ASTNode::UnaryExpr { op: _, expr } => self.expr_type(expr),
             ASTNode::Call { .. } => Unknown,
             _ => Unknown,
@@ -350,18 +708,29 @@ impl SemanticAnalyzer {
                 if lt == String && rt == String { return; }
                 if lt == Unknown || rt == Unknown { return; }
                 if (lt == String && rt == Number) || (lt == Number && rt == String) {
-                    if capture { self.diags.push(SemanticDiagnostic { message: "Implicit number/string coercion in '+'".into(), line: 0, column: 0, len: 1, severity: Severity::Warning }); }
+                    if capture { self.diags.push(SemanticDiagnostic { message: "Implicit number/string coercion in '+'".into(), line: 0, column: 0, len: 1, severity: Severity::Warning, rule: None, related: Vec::new(), fix: None }); }
                 } else { self.push_type_error("Invalid operands for '+'", capture); }
             }
-            TK::Minus | TK::Star | TK::Slash => { if lt != Number || rt != Number { if lt != Unknown && rt != Unknown { self.push_type_error("Arithmetic operands must be numbers", capture); } } }
+            TK::Minus | TK::Star | TK::Slash | TK::Percent | TK::StarStar => { if lt != Number || rt != Number { if lt != Unknown && rt != Unknown { self.push_type_error("Arithmetic operands must be numbers", capture); } } }
             TK::LessThan | TK::LessEqual | TK::GreaterThan | TK::GreaterEqual => { if lt != Number || rt != Number { if lt != Unknown && rt != Unknown { self.push_type_error("Comparison operands must be numbers", capture); } } }
+            TK::DoubleEquals | TK::NotEquals => {
+                if let (Some(a), Some(b)) = (self.enum_of(left), self.enum_of(right)) {
+                    if a != b && capture {
+                        let (line, column) = first_span(left).or_else(|| first_span(right)).unwrap_or((0, 0));
+                        let msg = format!("Comparing '{}' (enum '{a}') with '{}' (enum '{b}') is always {}", render(left), render(right), *op == TK::NotEquals);
+                        let mut related = self.enum_related(&a);
+                        related.extend(self.enum_related(&b));
+                        self.diags.push(SemanticDiagnostic { message: msg, line, column, len: 1, severity: Severity::Warning, rule: Some(ENUM_COMPARISON), related, fix: None });
+                    }
+                }
+            }
             _ => {}
         }
     }
 
     fn push_type_error(&mut self, msg: &str, capture: bool) {
         self.errors.push(msg.to_string());
-        if capture { self.diags.push(SemanticDiagnostic { message: msg.to_string(), line: 0, column: 0, len: 1, severity: Severity::Error }); }
+        if capture { self.diags.push(SemanticDiagnostic { message: msg.to_string(), line: 0, column: 0, len: 1, severity: Severity::Error, rule: None, related: Vec::new(), fix: None }); }
     }
 
     fn flush_unused_warnings(&mut self) {
@@ -373,7 +742,7 @@ impl SemanticAnalyzer {
                 for (name, info) in global.iter() {
                     if !info.used {
                         let msg = format!("Unused variable '{}'", name);
-                        self.diags.push(SemanticDiagnostic { message: msg, line: info.line, column: info.column, len: name.len().max(1), severity: Severity::Warning });
+                        self.diags.push(SemanticDiagnostic { message: msg, line: info.line, column: info.column, len: name.len().max(1), severity: Severity::Warning, rule: None, related: Vec::new(), fix: None });
                     }
                 }
             }
@@ -438,4 +807,17 @@ mod tests {
         let diags = a.analyze_with_spans(&ast);
         assert!(diags.iter().any(|d| d.message.contains("Unused variable 'unused'")));
     }
+
+    #[test]
+    fn chained_comparison_warns_with_suggestion() {
+        let toks = crate::core::lexer::Lexer::from_str("let a = 1;\nlet b = 2;\nlet c = 3;\nlog(a < b < c);\nlog(a < b && b < c);").tokenize().unwrap();
+        let ast = crate::core::parser::Parser::new(toks).parse().unwrap();
+        let diags = SemanticAnalyzer::new().analyze_with_spans(&ast);
+        let chained: Vec<_> = diags.iter().filter(|d| d.rule == Some(crate::core::error_index::CHAINED_COMPARISON)).collect();
+        assert_eq!(chained.len(), 1, "{diags:?}");
+        assert_eq!(chained[0].severity, Severity::Warning);
+        assert_eq!((chained[0].line, chained[0].column), (4, 5));
+        assert!(chained[0].message.contains("did you mean 'a < b && b < c'"), "{}", chained[0].message);
+        assert!(!diags.iter().any(|d| d.severity == Severity::Error), "{diags:?}");
+    }
 }
@@ -0,0 +1,112 @@
+//! Process-wide Ctrl-C handling for long-running loops (`--watch`, `serve`, `vault watch`).
+//!
+//! A loop calls [`listen`] on entry and polls the returned [`ShutdownToken`] between
+//! iterations; the first Ctrl-C raises the token so the loop can tear down (remove temp
+//! files, flush metrics, print a summary) and return normally. A second Ctrl-C while the
+//! first is still pending, or any Ctrl-C when no loop is listening, asks the handler in
+//! `main` to exit at once with [`FORCED_EXIT_CODE`].
+//!
+//! Loops take the token as a parameter, so tests drive them with a token of their own.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Conventional status for a process ended by SIGINT (128 + 2).
+pub const FORCED_EXIT_CODE: i32 = 130;
+
+/// Shared stop flag. Clones observe the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownToken(Arc<AtomicBool>);
+
+impl ShutdownToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn request(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+
+    /// The raw flag, for waits that poll an `AtomicBool` (see `watch::Watcher::wait`).
+    pub fn flag(&self) -> &AtomicBool {
+        &self.0
+    }
+
+    /// Sleep for `d` in short steps; false when woken early by a request.
+    pub fn sleep(&self, d: Duration) -> bool {
+        let end = Instant::now() + d;
+        loop {
+            if self.is_requested() {
+                return false;
+            }
+            let now = Instant::now();
+            if now >= end {
+                return true;
+            }
+            std::thread::sleep((end - now).min(Duration::from_millis(50)));
+        }
+    }
+}
+
+static GLOBAL: OnceLock<ShutdownToken> = OnceLock::new();
+static LISTENERS: AtomicUsize = AtomicUsize::new(0);
+
+/// The token raised by the Ctrl-C handler.
+pub fn global() -> ShutdownToken {
+    GLOBAL.get_or_init(ShutdownToken::new).clone()
+}
+
+/// Held by a loop that handles Ctrl-C itself. Entering clears any stale request;
+/// dropping clears it again so the next loop starts fresh.
+#[derive(Debug)]
+pub struct Listener {
+    token: ShutdownToken,
+}
+
+impl Listener {
+    pub fn token(&self) -> &ShutdownToken {
+        &self.token
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        self.token.reset();
+        LISTENERS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+pub fn listen() -> Listener {
+    let token = global();
+    token.reset();
+    LISTENERS.fetch_add(1, Ordering::SeqCst);
+    Listener { token }
+}
+
+/// What the Ctrl-C handler should do with a press.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// A listening loop was asked to stop.
+    Graceful,
+    /// Nothing will stop on its own (no listener, or a second press): exit now.
+    Force,
+}
+
+/// Record a Ctrl-C press against the global token.
+pub fn signal() -> Signal {
+    let token = global();
+    if LISTENERS.load(Ordering::SeqCst) == 0 || token.is_requested() {
+        return Signal::Force;
+    }
+    token.request();
+    Signal::Graceful
+}
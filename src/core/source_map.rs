@@ -0,0 +1,202 @@
+//! Version 3 source maps for the JS backend (`emit --source-map`, `run --source-map`).
+//!
+//! The code generator marks where each statement starts in its output; this module turns
+//! those marks into the `mappings` string Node and browsers read, so a stack trace through
+//! `output.js` points at the `.ai` line that produced it. Lines here are 0-based and columns
+//! count UTF-16 code units, as the format requires; `original_position` converts back to
+//! the 1-based lines the rest of the compiler prints.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// One generated position and the source position it came from (0-based).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mapping {
+    pub gen_line: usize,
+    pub gen_col: usize,
+    pub src_line: usize,
+    pub src_col: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceMap {
+    pub version: u32,
+    pub file: String,
+    pub sources: Vec<String>,
+    pub sources_content: Vec<String>,
+    pub names: Vec<String>,
+    pub mappings: String,
+}
+
+impl SourceMap {
+    /// A map from the generated `file` back to the single `source` (path as the map should
+    /// name it, relative to the map) whose text is `content`.
+    pub fn new(file: &str, source: &str, content: &str, mappings: &[Mapping]) -> Self {
+        SourceMap {
+            version: 3,
+            file: file.to_string(),
+            sources: vec![source.to_string()],
+            sources_content: vec![content.to_string()],
+            names: Vec::new(),
+            mappings: encode_mappings(mappings),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("source map serializes")
+    }
+
+    /// The mappings, decoded; segments without a source position are skipped.
+    pub fn decode(&self) -> Result<Vec<Mapping>, String> {
+        decode_mappings(&self.mappings)
+    }
+
+    /// 1-based source line and column for 1-based generated `line`/`column`: the last
+    /// mapping on that line at or before the column.
+    pub fn original_position(&self, line: usize, column: usize) -> Option<(usize, usize)> {
+        let (line, col) = (line.checked_sub(1)?, column.saturating_sub(1));
+        self.decode()
+            .ok()?
+            .into_iter()
+            .filter(|m| m.gen_line == line && m.gen_col <= col)
+            .max_by_key(|m| m.gen_col)
+            .map(|m| (m.src_line + 1, m.src_col + 1))
+    }
+}
+
+/// Append the base64 VLQ form of `value` to `out`.
+pub fn vlq_encode(value: i64, out: &mut String) {
+    let mut v = (if value < 0 { ((-value) << 1) | 1 } else { value << 1 }) as u64;
+    loop {
+        let mut digit = (v & 31) as usize;
+        v >>= 5;
+        if v > 0 {
+            digit |= 32;
+        }
+        out.push(BASE64[digit] as char);
+        if v == 0 {
+            break;
+        }
+    }
+}
+
+/// Read one VLQ value from `chars`; `None` on a bad digit or a truncated value.
+fn vlq_decode(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<i64> {
+    let (mut v, mut shift) = (0u64, 0);
+    loop {
+        let c = chars.next()?;
+        let digit = BASE64.iter().position(|&b| b as char == c)? as u64;
+        v |= (digit & 31) << shift;
+        shift += 5;
+        if digit & 32 == 0 {
+            break;
+        }
+    }
+    let magnitude = (v >> 1) as i64;
+    Some(if v & 1 == 1 { -magnitude } else { magnitude })
+}
+
+/// The `mappings` field: lines separated by `;`, segments by `,`, each segment the deltas
+/// of generated column, source index (always 0), source line and source column.
+pub fn encode_mappings(mappings: &[Mapping]) -> String {
+    let mut sorted = mappings.to_vec();
+    sorted.sort_by_key(|m| (m.gen_line, m.gen_col));
+    let mut out = String::new();
+    let (mut line, mut prev_gen_col, mut prev_src_line, mut prev_src_col) = (0, 0i64, 0i64, 0i64);
+    let mut first_on_line = true;
+    for m in sorted {
+        while line < m.gen_line {
+            out.push(';');
+            line += 1;
+            prev_gen_col = 0;
+            first_on_line = true;
+        }
+        if !first_on_line {
+            out.push(',');
+        }
+        first_on_line = false;
+        vlq_encode(m.gen_col as i64 - prev_gen_col, &mut out);
+        vlq_encode(0, &mut out);
+        vlq_encode(m.src_line as i64 - prev_src_line, &mut out);
+        vlq_encode(m.src_col as i64 - prev_src_col, &mut out);
+        prev_gen_col = m.gen_col as i64;
+        prev_src_line = m.src_line as i64;
+        prev_src_col = m.src_col as i64;
+    }
+    out
+}
+
+pub fn decode_mappings(text: &str) -> Result<Vec<Mapping>, String> {
+    let mut out = Vec::new();
+    let (mut src_line, mut src_col, mut source) = (0i64, 0i64, 0i64);
+    for (gen_line, line) in text.split(';').enumerate() {
+        let mut gen_col = 0i64;
+        for segment in line.split(',').filter(|s| !s.is_empty()) {
+            let mut chars = segment.chars().peekable();
+            let mut fields = Vec::new();
+            while chars.peek().is_some() {
+                fields.push(vlq_decode(&mut chars).ok_or_else(|| format!("bad VLQ segment `{segment}` on line {}", gen_line + 1))?);
+            }
+            match fields.len() {
+                1 => gen_col += fields[0],
+                4 | 5 => {
+                    gen_col += fields[0];
+                    source += fields[1];
+                    src_line += fields[2];
+                    src_col += fields[3];
+                    if gen_col < 0 || source < 0 || src_line < 0 || src_col < 0 {
+                        return Err(format!("negative position in segment `{segment}` on line {}", gen_line + 1));
+                    }
+                    out.push(Mapping { gen_line, gen_col: gen_col as usize, src_line: src_line as usize, src_col: src_col as usize });
+                }
+                n => return Err(format!("segment `{segment}` on line {} has {n} fields", gen_line + 1)),
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// `out.js` → `out.js.map`.
+pub fn map_path(out: &Path) -> PathBuf {
+    let mut name = out.as_os_str().to_owned();
+    name.push(".map");
+    PathBuf::from(name)
+}
+
+/// The trailing comment that points a runtime at the map written next to `out`.
+pub fn url_comment(out: &Path) -> String {
+    let name = map_path(out).file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    format!("//# sourceMappingURL={name}\n")
+}
+
+/// `input` as a map written next to `out` should name it: relative to `out`'s directory,
+/// or absolute when the two do not share a root; always with `/`.
+pub fn source_name(input: &Path, out: &Path) -> String {
+    let abs = |p: &Path| if p.is_absolute() { p.to_path_buf() } else { std::env::current_dir().unwrap_or_default().join(p) };
+    let input = abs(input);
+    let out_dir = abs(out).parent().map(Path::to_path_buf).unwrap_or_default();
+    let name = relative_to(&input, &out_dir).unwrap_or(input);
+    name.to_string_lossy().replace('\\', "/")
+}
+
+fn relative_to(path: &Path, base: &Path) -> Option<PathBuf> {
+    use std::path::Component;
+    let path: Vec<Component> = path.components().filter(|c| !matches!(c, Component::CurDir)).collect();
+    let base: Vec<Component> = base.components().filter(|c| !matches!(c, Component::CurDir)).collect();
+    // different roots (drives) or `..` in either path: leave it absolute
+    if path.first() != base.first() || path.iter().chain(&base).any(|c| matches!(c, Component::ParentDir)) {
+        return None;
+    }
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    let mut rel = PathBuf::new();
+    for _ in common..base.len() {
+        rel.push("..");
+    }
+    for c in &path[common..] {
+        rel.push(c);
+    }
+    Some(rel)
+}
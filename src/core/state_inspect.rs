@@ -0,0 +1,154 @@
+//! Views of a simulated statevector for the shell's `qstate`: basis-state probabilities,
+//! per-qubit marginals and Bloch-sphere coordinates.
+//!
+//! Amplitudes are `[re, im]` pairs and qubit `q` is bit `q` of the basis index (Titan's
+//! order, as in run bundles); bitstrings are printed qubit 0 first. A qubit entangled with
+//! the rest has a mixed reduced state, so its Bloch vector lies inside the sphere.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::core::style::{Role, Style};
+
+/// Probabilities below this are treated as zero in tables.
+const EPS: f64 = 1e-12;
+/// Basis states listed before the table is cut short.
+const MAX_ROWS: usize = 32;
+
+/// A statevector kept between shell commands.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateView {
+    /// The program it came from.
+    pub source: String,
+    pub qubits: usize,
+    pub amplitudes: Vec<[f64; 2]>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Bloch {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Bloch {
+    /// Distance from the centre: 1 for a pure single-qubit state, 0 when maximally mixed.
+    pub fn radius(&self) -> f64 {
+        (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+    }
+}
+
+/// One qubit on its own: marginal distribution and reduced state.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QubitView {
+    pub qubit: usize,
+    pub p0: f64,
+    pub p1: f64,
+    pub bloch: Bloch,
+}
+
+/// What `qstate --save` writes.
+#[derive(Debug, Clone, Serialize)]
+pub struct Report<'a> {
+    #[serde(flatten)]
+    pub state: &'a StateView,
+    /// Non-zero basis states, bitstring (qubit 0 first) to probability.
+    pub probabilities: BTreeMap<String, f64>,
+    pub per_qubit: Vec<QubitView>,
+}
+
+fn norm_sqr(a: &[f64; 2]) -> f64 {
+    a[0] * a[0] + a[1] * a[1]
+}
+
+pub fn bitstring(index: usize, qubits: usize) -> String {
+    (0..qubits).map(|q| if index >> q & 1 == 1 { '1' } else { '0' }).collect()
+}
+
+/// Probability of every basis state that has any.
+pub fn probabilities(state: &StateView) -> Vec<(String, f64)> {
+    state
+        .amplitudes
+        .iter()
+        .enumerate()
+        .map(|(i, a)| (bitstring(i, state.qubits), norm_sqr(a)))
+        .filter(|(_, p)| *p > EPS)
+        .collect()
+}
+
+/// `[P(q = 0), P(q = 1)]`, summing over every other qubit.
+pub fn marginal(state: &StateView, q: usize) -> [f64; 2] {
+    let mut p = [0.0; 2];
+    for (i, a) in state.amplitudes.iter().enumerate() {
+        p[i >> q & 1] += norm_sqr(a);
+    }
+    p
+}
+
+/// Bloch vector of qubit `q`'s reduced density matrix ρ = (I + xX + yY + zZ) / 2.
+pub fn bloch(state: &StateView, q: usize) -> Bloch {
+    let [p0, p1] = marginal(state, q);
+    // ρ01 = Σ a(..0..) · conj(a(..1..)) over the other qubits' values.
+    let (mut re, mut im) = (0.0, 0.0);
+    for (i, a) in state.amplitudes.iter().enumerate().filter(|(i, _)| i >> q & 1 == 0) {
+        let b = state.amplitudes[i | 1 << q];
+        re += a[0] * b[0] + a[1] * b[1];
+        im += a[1] * b[0] - a[0] * b[1];
+    }
+    Bloch { x: clean(2.0 * re), y: clean(-2.0 * im), z: clean(p0 - p1) }
+}
+
+/// Rounding noise (and -0.0) shown as 0.
+fn clean(v: f64) -> f64 {
+    if v.abs() < EPS { 0.0 } else { v }
+}
+
+pub fn qubit_view(state: &StateView, q: usize) -> QubitView {
+    let [p0, p1] = marginal(state, q);
+    QubitView { qubit: q, p0, p1, bloch: bloch(state, q) }
+}
+
+pub fn report(state: &StateView) -> Report<'_> {
+    Report { state, probabilities: probabilities(state).into_iter().collect(), per_qubit: (0..state.qubits).map(|q| qubit_view(state, q)).collect() }
+}
+
+fn bar(p: f64) -> String {
+    "#".repeat((p * 20.0).round() as usize)
+}
+
+/// The probability table and every qubit's marginal and Bloch vector, or one qubit in detail.
+pub fn render(st: &Style, state: &StateView, focus: Option<usize>) -> String {
+    let mut out = format!(
+        "{} {} ({} qubit{})\n",
+        st.paint("=== Quantum State Inspector ===", Role::Quantum),
+        state.source,
+        state.qubits,
+        if state.qubits == 1 { "" } else { "s" }
+    );
+    if let Some(q) = focus {
+        let v = qubit_view(state, q);
+        out.push_str(&format!("{}\n", st.paint(&format!("qubit {q}"), Role::Section)));
+        out.push_str(&format!("  P(0) = {:.4}  {}\n", v.p0, bar(v.p0)));
+        out.push_str(&format!("  P(1) = {:.4}  {}\n", v.p1, bar(v.p1)));
+        let b = v.bloch;
+        out.push_str(&format!("  Bloch (x, y, z) = ({:+.4}, {:+.4}, {:+.4}), |r| = {:.4}\n", b.x, b.y, b.z, b.radius()));
+        if b.radius() < 1.0 - 1e-6 {
+            out.push_str(&format!("  {}\n", st.paint("mixed: entangled with the other qubits", Role::Dim)));
+        }
+        return out;
+    }
+    out.push_str(&format!("{}\n", st.paint("basis states (qubit 0 first)", Role::Section)));
+    let probs = probabilities(state);
+    for (bits, p) in probs.iter().take(MAX_ROWS) {
+        out.push_str(&format!("  |{bits}{}  {p:.4}  {}\n", st.glyph("⟩", ">"), bar(*p)));
+    }
+    if probs.len() > MAX_ROWS {
+        out.push_str(&format!("  {} {} more\n", st.glyph("…", "..."), probs.len() - MAX_ROWS));
+    }
+    out.push_str(&format!("{}\n", st.paint("per qubit: P(1) and Bloch (x, y, z)", Role::Section)));
+    for q in 0..state.qubits {
+        let v = qubit_view(state, q);
+        let b = v.bloch;
+        out.push_str(&format!("  q{q}  P(1) = {:.4}  ({:+.3}, {:+.3}, {:+.3})\n", v.p1, b.x, b.y, b.z));
+    }
+    out
+}
@@ -0,0 +1,136 @@
+//! Terminal styling: one place that decides whether to emit color and unicode.
+//!
+//! Color: `AEONMI_COLOR=always|never` wins; otherwise (`auto`) color is off when
+//! `NO_COLOR` is set, `TERM=dumb`, or stdout is not a terminal.
+//! Unicode: off with `--no-unicode`, `AEONMI_UNICODE=0`, `TERM=dumb`, or a
+//! non-UTF-8 locale (`LC_ALL` / `LC_CTYPE` / `LANG`).
+//!
+//! Call sites paint through [`Style::paint`] and pick decorations with
+//! [`Style::glyph`] instead of using `colored` directly.
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    Always,
+    Auto,
+    Never,
+}
+
+impl ColorMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "always" => Some(Self::Always),
+            "auto" => Some(Self::Auto),
+            "never" => Some(Self::Never),
+            _ => None,
+        }
+    }
+}
+
+/// Semantic colors; the palette lives in [`Role::sgr`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// `error:` labels and underlines
+    Error,
+    /// `warn:` / `usage:` labels
+    Warn,
+    /// `-->` location arrows
+    Location,
+    /// message text and file positions
+    Strong,
+    /// gutters and secondary punctuation
+    Dim,
+    /// banner frame and prompt badge
+    Brand,
+    /// banner title and prompt caret
+    Title,
+    /// section headings, cwd, directory entries
+    Section,
+    /// quantum headings and badges
+    Quantum,
+    /// backend / example / gate names
+    Name,
+    /// builtin function names
+    Builtin,
+    /// QUBE names
+    Qube,
+    /// bold text in rendered markdown
+    Emphasis,
+}
+
+impl Role {
+    fn sgr(self) -> &'static str {
+        match self {
+            Role::Error => "1;91",
+            Role::Warn => "1;33",
+            Role::Location => "94",
+            Role::Strong => "97",
+            Role::Dim => "2",
+            Role::Brand => "1;38;2;225;0;180",
+            Role::Title => "1;38;2;255;240;0",
+            Role::Section => "38;2;130;0;200",
+            Role::Quantum => "1;38;2;0;255;180",
+            Role::Name => "38;2;255;180;0",
+            Role::Builtin => "38;2;100;255;100",
+            Role::Qube => "38;2;255;100;255",
+            Role::Emphasis => "1",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    pub color: bool,
+    pub unicode: bool,
+}
+
+impl Style {
+    pub const PLAIN: Style = Style { color: false, unicode: false };
+    pub const RICH: Style = Style { color: true, unicode: true };
+
+    pub fn paint(&self, text: &str, role: Role) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", role.sgr(), text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// `unicode` when unicode output is enabled, `ascii` otherwise.
+    pub fn glyph<'a>(&self, unicode: &'a str, ascii: &'a str) -> &'a str {
+        if self.unicode { unicode } else { ascii }
+    }
+
+    /// Decide from an environment lookup, terminal-ness of stdout and the `--no-unicode` flag.
+    pub fn detect(env: impl Fn(&str) -> Option<String>, is_tty: bool, no_unicode: bool) -> Style {
+        let set = |k: &str| env(k).filter(|v| !v.is_empty());
+        let dumb = set("TERM").as_deref() == Some("dumb");
+        let color = match set("AEONMI_COLOR").as_deref().and_then(ColorMode::parse).unwrap_or(ColorMode::Auto) {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => set("NO_COLOR").is_none() && !dumb && is_tty,
+        };
+        let locale = set("LC_ALL").or_else(|| set("LC_CTYPE")).or_else(|| set("LANG"));
+        let utf8_locale = locale.is_none_or(|l| {
+            let l = l.to_ascii_lowercase();
+            l.contains("utf-8") || l.contains("utf8")
+        });
+        let unicode = !no_unicode && set("AEONMI_UNICODE").as_deref() != Some("0") && !dumb && (cfg!(windows) || utf8_locale);
+        Style { color, unicode }
+    }
+}
+
+static STYLE: OnceLock<Style> = OnceLock::new();
+
+/// Fix the process-wide style (call once, early in `main`). Also steers any remaining `colored` output.
+pub fn init(no_unicode: bool) -> Style {
+    let style = *STYLE.get_or_init(|| Style::detect(|k| std::env::var(k).ok(), std::io::stdout().is_terminal(), no_unicode));
+    colored::control::set_override(style.color);
+    style
+}
+
+/// The process-wide style; detected from the environment if [`init`] was never called.
+pub fn current() -> Style {
+    *STYLE.get_or_init(|| Style::detect(|k| std::env::var(k).ok(), std::io::stdout().is_terminal(), false))
+}
@@ -12,9 +12,15 @@ pub struct SymbolInfo {
     pub end_column: usize,
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all="lowercase")]
-pub enum SymbolKind { Function, Variable, Parameter }
+pub enum SymbolKind { Function, Variable, Parameter, Enum }
+
+impl SymbolKind {
+    pub fn as_str(&self) -> &'static str {
+        match self { SymbolKind::Function => "function", SymbolKind::Variable => "variable", SymbolKind::Parameter => "parameter", SymbolKind::Enum => "enum" }
+    }
+}
 
 pub fn collect_symbols(ast: &ASTNode) -> Vec<SymbolInfo> {
     let mut out = Vec::new();
@@ -30,17 +36,33 @@ fn visit(node: &ASTNode, out: &mut Vec<SymbolInfo>) {
             for FunctionParam { name, line, column } in params { out.push(SymbolInfo { kind: SymbolKind::Parameter, name: name.clone(), line: *line, column: *column, end_line: *line, end_column: *column + name.len().max(1) }); }
             for st in body { visit(st, out); }
         }
-        ASTNode::VariableDecl { name, line, column, .. } => {
+        ASTNode::VariableDecl { name, line, column, .. } | ASTNode::QubitDecl { name, line, column } | ASTNode::QregDecl { name, line, column, .. } => {
             out.push(SymbolInfo { kind: SymbolKind::Variable, name: name.clone(), line: *line, column: *column, end_line: *line, end_column: *column + name.len().max(1) });
         }
+        ASTNode::DestructuringDecl { targets, .. } => {
+            for (name, line, column) in targets.iter().flat_map(|t| t.names()) {
+                out.push(SymbolInfo { kind: SymbolKind::Variable, name: name.to_string(), line, column, end_line: line, end_column: column + name.len().max(1) });
+            }
+        }
+        ASTNode::EnumDecl { name, line, column, .. } => {
+            out.push(SymbolInfo { kind: SymbolKind::Enum, name: name.clone(), line: *line, column: *column, end_line: *line, end_column: *column + name.len().max(1) });
+        }
         ASTNode::Assignment { .. }
         | ASTNode::Return(_)
-        | ASTNode::Log(_)
+        | ASTNode::Log { .. }
         | ASTNode::If { .. }
         | ASTNode::While { .. }
         | ASTNode::For { .. }
+        | ASTNode::ForIn { .. }
+        | ASTNode::Break { .. }
+        | ASTNode::Continue { .. }
+        | ASTNode::Match { .. }
         | ASTNode::BinaryExpr { .. }
         | ASTNode::UnaryExpr { .. }
+        | ASTNode::Index { .. }
+        | ASTNode::Member { .. }
+        | ASTNode::ArrayLiteral { .. }
+        | ASTNode::ObjectLiteral { .. }
         | ASTNode::Call { .. }
         | ASTNode::Identifier(_)
         | ASTNode::IdentifierSpanned { .. }
@@ -49,6 +71,7 @@ fn visit(node: &ASTNode, out: &mut Vec<SymbolInfo>) {
         | ASTNode::BooleanLiteral(_)
         | ASTNode::QuantumOp { .. }
         | ASTNode::HieroglyphicOp { .. }
+        | ASTNode::Import { .. }
         | ASTNode::Error(_) => {}
     }
 }
@@ -1,3 +1,102 @@
+//! Dynamical systems: Lyapunov exponents, bifurcation maps, attractor reconstruction, and
+//! the trajectories behind the VM's `simulate_ode` / `logistic_map` and `aeonmi math demo`.
+//!
+//! Trajectory functions check their inputs and stop at the first non-finite state rather
+//! than let NaN or infinity run through the rest of the output.
+use std::fmt;
+
+use super::numerical_solvers::rk4_step;
+
+/// Most points a trajectory may have; a smaller step over a longer span is refused.
+pub const MAX_POINTS: usize = 1_000_000;
+
+/// Why a trajectory could not be computed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrajectoryError<E> {
+    /// Bad arguments (step size, span, parameter range), described.
+    Invalid(String),
+    /// The state stopped being finite at time (or iteration) `t`, after `step` good steps.
+    Unstable { t: f64, step: usize },
+    /// The system function itself failed.
+    System(E),
+}
+
+impl<E: fmt::Display> fmt::Display for TrajectoryError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrajectoryError::Invalid(msg) => write!(f, "{msg}"),
+            TrajectoryError::Unstable { t, step } => {
+                write!(f, "state is no longer finite at t = {t} (after {step} steps); try a smaller step")
+            }
+            TrajectoryError::System(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+/// Integrate `y' = f(t, y)` with RK4 from `y0` at `t_span.0` to `t_span.1` in steps of `dt`
+/// (the last step is shortened to land on `t_span.1`). Returns `(t, y)` for every step,
+/// starting with `(t0, y0)`.
+pub fn ode_trajectory<F, E>(mut f: F, y0: &[f64], t_span: (f64, f64), dt: f64) -> Result<Vec<(f64, Vec<f64>)>, TrajectoryError<E>>
+where
+    F: FnMut(f64, &[f64]) -> Result<Vec<f64>, E>,
+{
+    let (t0, t1) = t_span;
+    if !(dt.is_finite() && dt > 0.0) {
+        return Err(TrajectoryError::Invalid(format!("step size must be a positive number, got {dt}")));
+    }
+    if !(t0.is_finite() && t1.is_finite() && t1 >= t0) {
+        return Err(TrajectoryError::Invalid(format!("time span must run forward, got {t0} to {t1}")));
+    }
+    let steps = ((t1 - t0) / dt).ceil() as usize;
+    if steps >= MAX_POINTS {
+        return Err(TrajectoryError::Invalid(format!("{steps} steps of {dt} exceed the {MAX_POINTS}-point limit; use a larger step")));
+    }
+    if y0.iter().any(|y| !y.is_finite()) {
+        return Err(TrajectoryError::Unstable { t: t0, step: 0 });
+    }
+    let mut points = Vec::with_capacity(steps + 1);
+    points.push((t0, y0.to_vec()));
+    let mut y = y0.to_vec();
+    for step in 0..steps {
+        let t = t0 + step as f64 * dt;
+        let h = dt.min(t1 - t);
+        let mut checked = |t: f64, y: &[f64]| {
+            let dy = f(t, y).map_err(TrajectoryError::System)?;
+            if dy.len() != y.len() {
+                return Err(TrajectoryError::Invalid(format!("system returned {} values for a {}-dimensional state", dy.len(), y.len())));
+            }
+            Ok(dy)
+        };
+        y = rk4_step(&mut checked, t, &y, h)?;
+        if y.iter().any(|v| !v.is_finite()) {
+            return Err(TrajectoryError::Unstable { t: t + h, step });
+        }
+        points.push((t + h, y.clone()));
+    }
+    Ok(points)
+}
+
+/// `x_{k+1} = r x_k (1 - x_k)` from `x0` for `n` steps: `n + 1` values, `x0` first.
+/// Needs `0 <= r <= 4` and `0 <= x0 <= 1`, where the map stays in `[0, 1]`.
+pub fn logistic_map(r: f64, x0: f64, n: usize) -> Result<Vec<f64>, TrajectoryError<std::convert::Infallible>> {
+    if !(0.0..=4.0).contains(&r) {
+        return Err(TrajectoryError::Invalid(format!("r must be between 0 and 4, got {r}")));
+    }
+    if !(0.0..=1.0).contains(&x0) {
+        return Err(TrajectoryError::Invalid(format!("x0 must be between 0 and 1, got {x0}")));
+    }
+    if n >= MAX_POINTS {
+        return Err(TrajectoryError::Invalid(format!("{n} iterations exceed the {MAX_POINTS}-point limit")));
+    }
+    let mut xs = Vec::with_capacity(n + 1);
+    xs.push(x0);
+    for _ in 0..n {
+        let x = xs[xs.len() - 1];
+        xs.push(r * x * (1.0 - x));
+    }
+    Ok(xs)
+}
+
 pub fn lyapunov_exponent(
     initial_conditions: &[f64],
     system: impl Fn(&[f64]) -> Vec<f64>,
@@ -88,3 +88,17 @@ where
 
     results
 }
+
+/// One classical Runge–Kutta (RK4) step of `y' = f(t, y)` from `(t, y)` with step `h`.
+/// `f` may fail; its error is passed through.
+pub fn rk4_step<F, E>(f: &mut F, t: f64, y: &[f64], h: f64) -> Result<Vec<f64>, E>
+where
+    F: FnMut(f64, &[f64]) -> Result<Vec<f64>, E>,
+{
+    let shifted = |y: &[f64], k: &[f64], s: f64| -> Vec<f64> { y.iter().zip(k).map(|(y, k)| y + s * k).collect() };
+    let k1 = f(t, y)?;
+    let k2 = f(t + h / 2.0, &shifted(y, &k1, h / 2.0))?;
+    let k3 = f(t + h / 2.0, &shifted(y, &k2, h / 2.0))?;
+    let k4 = f(t + h, &shifted(y, &k3, h))?;
+    Ok((0..y.len()).map(|i| y[i] + h / 6.0 * (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i])).collect())
+}
@@ -3,6 +3,16 @@
 //!
 //! - `gradient_descent`: vanilla 1D gradient descent for smooth functions.
 //! - `golden_section_search`: robust bracketing method for 1D unimodal minimization.
+//! - `nelder_mead`: derivative-free simplex search in N dimensions.
+//! - `simulated_annealing`: seeded random search that can climb out of local minima.
+//! - `grid_search`: every point of a regular grid.
+//!
+//! The N-dimensional routines take fallible objectives (`Result<f64, E>`) so a caller such
+//! as the VM can stop at the first evaluation that goes wrong.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::{Distribution, StandardNormal};
 
 /// Simple 1D gradient descent.
 /// `f`: objective, `grad`: derivative of `f`, `x0`: initial guess,
@@ -93,6 +103,160 @@ where
     xb
 }
 
+/// Best point found by an N-dimensional search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Minimum {
+    pub params: Vec<f64>,
+    pub value: f64,
+    /// Simplex steps, annealing steps or grid points, depending on the method.
+    pub iterations: usize,
+}
+
+/// Stopping rules for `nelder_mead` and `simulated_annealing`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    pub max_iter: usize,
+    /// Nelder–Mead stops once every vertex is within `tol` of the best, in both position and
+    /// value; annealing stops once its step size falls below `tol`.
+    pub tol: f64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits { max_iter: 5000, tol: 1e-10 }
+    }
+}
+
+/// Nelder–Mead simplex minimization from `x0` (standard coefficients: reflect 1, expand 2,
+/// contract 1/2, shrink 1/2). The first simplex moves each coordinate by 5% (0.00025 at 0).
+pub fn nelder_mead<F, E>(mut f: F, x0: &[f64], limits: Limits) -> Result<Minimum, E>
+where
+    F: FnMut(&[f64]) -> Result<f64, E>,
+{
+    let n = x0.len();
+    let mut simplex = vec![(x0.to_vec(), f(x0)?)];
+    for k in 0..n {
+        let mut x = x0.to_vec();
+        x[k] = if x[k] != 0.0 { x[k] * 1.05 } else { 0.00025 };
+        let fx = f(&x)?;
+        simplex.push((x, fx));
+    }
+    let mut iterations = 0;
+    while iterations < limits.max_iter {
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let (best, f_best) = (&simplex[0].0, simplex[0].1);
+        let spread = simplex[1..].iter().fold(0.0f64, |m, (x, fx)| {
+            let dx = x.iter().zip(best).fold(0.0f64, |d, (a, b)| d.max((a - b).abs()));
+            m.max(dx).max((fx - f_best).abs())
+        });
+        if spread <= limits.tol {
+            break;
+        }
+        iterations += 1;
+
+        let centroid: Vec<f64> = (0..n).map(|k| simplex[..n].iter().map(|(x, _)| x[k]).sum::<f64>() / n as f64).collect();
+        let toward = |t: f64, from: &[f64]| -> Vec<f64> { centroid.iter().zip(from).map(|(c, w)| c + t * (w - c)).collect() };
+        let worst = simplex[n].clone();
+        let reflected = toward(-1.0, &worst.0);
+        let f_reflected = f(&reflected)?;
+        if f_reflected < simplex[0].1 {
+            let expanded = toward(-2.0, &worst.0);
+            let f_expanded = f(&expanded)?;
+            simplex[n] = if f_expanded < f_reflected { (expanded, f_expanded) } else { (reflected, f_reflected) };
+        } else if f_reflected < simplex[n - 1].1 {
+            simplex[n] = (reflected, f_reflected);
+        } else {
+            // Contract toward the better of the worst vertex and its reflection.
+            let (anchor, f_anchor) = if f_reflected < worst.1 { (&reflected, f_reflected) } else { (&worst.0, worst.1) };
+            let contracted = toward(0.5, anchor);
+            let f_contracted = f(&contracted)?;
+            if f_contracted < f_anchor {
+                simplex[n] = (contracted, f_contracted);
+            } else {
+                let best = simplex[0].0.clone();
+                for vertex in simplex.iter_mut().skip(1) {
+                    let x: Vec<f64> = best.iter().zip(&vertex.0).map(|(b, v)| b + 0.5 * (v - b)).collect();
+                    let fx = f(&x)?;
+                    *vertex = (x, fx);
+                }
+            }
+        }
+    }
+    simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+    let (params, value) = simplex.swap_remove(0);
+    Ok(Minimum { params, value, iterations })
+}
+
+/// Simulated annealing from `x0`: Gaussian steps that shrink with the temperature, which
+/// cools linearly from 1 over `limits.max_iter` steps. Worse points are accepted with
+/// probability `exp(-Δ / T)`. Deterministic for a given `seed`.
+pub fn simulated_annealing<F, E>(mut f: F, x0: &[f64], limits: Limits, seed: u64) -> Result<Minimum, E>
+where
+    F: FnMut(&[f64]) -> Result<f64, E>,
+{
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut current = (x0.to_vec(), f(x0)?);
+    let mut best = current.clone();
+    let mut iterations = 0;
+    while iterations < limits.max_iter {
+        // The temperature doubles as the step size.
+        let temperature = 1.0 - iterations as f64 / limits.max_iter as f64;
+        if temperature < limits.tol {
+            break;
+        }
+        iterations += 1;
+        let candidate: Vec<f64> = current
+            .0
+            .iter()
+            .map(|x| {
+                let z: f64 = StandardNormal.sample(&mut rng);
+                x + temperature * z
+            })
+            .collect();
+        let f_candidate = f(&candidate)?;
+        let delta = f_candidate - current.1;
+        if delta <= 0.0 || rng.gen::<f64>() < (-delta / temperature).exp() {
+            current = (candidate, f_candidate);
+            if current.1 < best.1 {
+                best = current.clone();
+            }
+        }
+    }
+    Ok(Minimum { params: best.0, value: best.1, iterations })
+}
+
+/// Evaluate `f` on every point of the grid `ranges` (`(lo, hi, points)` per dimension, the
+/// points spaced evenly from `lo` to `hi` inclusive; one point means just `lo`) and keep the
+/// lowest. The first point wins ties.
+pub fn grid_search<F, E>(mut f: F, ranges: &[(f64, f64, usize)]) -> Result<Minimum, E>
+where
+    F: FnMut(&[f64]) -> Result<f64, E>,
+{
+    let coord = |(lo, hi, points): (f64, f64, usize), k: usize| {
+        if points <= 1 { lo } else { lo + (hi - lo) * k as f64 / (points - 1) as f64 }
+    };
+    let total: usize = ranges.iter().map(|r| r.2.max(1)).product();
+    let mut best: Option<(Vec<f64>, f64)> = None;
+    for index in 0..total {
+        let mut rest = index;
+        let x: Vec<f64> = ranges
+            .iter()
+            .map(|r| {
+                let points = r.2.max(1);
+                let k = rest % points;
+                rest /= points;
+                coord(*r, k)
+            })
+            .collect();
+        let fx = f(&x)?;
+        if best.as_ref().is_none_or(|(_, fb)| fx < *fb) {
+            best = Some((x, fx));
+        }
+    }
+    let (params, value) = best.unwrap_or((Vec::new(), f64::NAN));
+    Ok(Minimum { params, value, iterations: total })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -114,4 +278,36 @@ mod tests {
         let xmin = golden_section_search(f, 0.0, 6.0, 1e-7, 200);
         assert!((xmin - 3.0).abs() < 1e-6, "xmin={xmin}");
     }
+
+    fn rosenbrock(p: &[f64]) -> Result<f64, ()> {
+        Ok((1.0 - p[0]).powi(2) + 100.0 * (p[1] - p[0] * p[0]).powi(2))
+    }
+
+    #[test]
+    fn test_nelder_mead_rosenbrock() {
+        let m = nelder_mead(rosenbrock, &[-1.2, 1.0], Limits::default()).unwrap();
+        assert!((m.params[0] - 1.0).abs() < 1e-4 && (m.params[1] - 1.0).abs() < 1e-4, "{m:?}");
+        assert!(m.iterations < Limits::default().max_iter);
+    }
+
+    #[test]
+    fn test_annealing_is_seeded() {
+        let bowl = |p: &[f64]| -> Result<f64, ()> { Ok((p[0] - 2.0).powi(2) + (p[1] + 1.0).powi(2)) };
+        let limits = Limits { max_iter: 4000, tol: 1e-9 };
+        let m = simulated_annealing(bowl, &[0.0, 0.0], limits, 7).unwrap();
+        assert!(m.value < 1e-2, "{m:?}");
+        assert_eq!(m, simulated_annealing(bowl, &[0.0, 0.0], limits, 7).unwrap());
+    }
+
+    #[test]
+    fn test_grid_search_and_errors() {
+        let m = grid_search(rosenbrock, &[(-2.0, 2.0, 5), (-1.0, 1.0, 3)]).unwrap();
+        assert_eq!((m.params, m.value, m.iterations), (vec![1.0, 1.0], 0.0, 15));
+        let mut calls = 0;
+        let failing = |_: &[f64]| -> Result<f64, usize> {
+            calls += 1;
+            if calls == 3 { Err(calls) } else { Ok(0.0) }
+        };
+        assert_eq!(nelder_mead(failing, &[0.0, 0.0], Limits::default()), Err(3));
+    }
 }
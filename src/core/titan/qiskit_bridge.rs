@@ -1,15 +1,56 @@
 //! Qiskit bridge via pyo3 (feature: `qiskit`).
-//! Minimal demo: send a unitary to Python/Qiskit, build a circuit, and run shots.
+//! Send a unitary or a whole circuit to Python/Qiskit, transpile it, and run shots on Aer.
+//!
+//! Python exceptions never panic here: [`python_error`] turns them into
+//! `qiskit: <ExceptionType>: <message>` errors, with an install hint for missing modules.
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 use nalgebra::DMatrix;
 use num_complex::Complex64 as C64;
 use numpy::PyArray2;
 use pyo3::prelude::*;
 use pyo3::types::PyModule;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::core::quantum_ir::{Circuit, OpKind};
+
+/// Depth and gate counts of a circuit before and after `transpile`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranspileStats {
+    /// `optimization_level` passed to Qiskit; `None` leaves Qiskit's default.
+    pub level: Option<u8>,
+    pub basis_gates: Option<Vec<String>>,
+    /// Fake backend whose coupling map and basis were targeted.
+    pub backend: Option<String>,
+    pub depth_before: usize,
+    pub depth_after: usize,
+    pub gates_before: BTreeMap<String, usize>,
+    pub gates_after: BTreeMap<String, usize>,
+}
+
+/// Histogram of an Aer run together with what transpilation did to the circuit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CircuitRun {
+    pub shots: usize,
+    /// Bitstring (qubit 0 rightmost, as Qiskit prints it) → count.
+    pub counts: BTreeMap<String, u64>,
+    pub transpile: TranspileStats,
+}
+
+/// Readable error for an exception raised on the Python side.
+fn python_error(py: Python<'_>, err: PyErr) -> anyhow::Error {
+    let kind = err.get_type_bound(py).name().map(|n| n.into_owned()).unwrap_or_else(|_| "Exception".into());
+    let message = err.value_bound(py).to_string();
+    let hint = match kind.as_str() {
+        "ModuleNotFoundError" | "ImportError" => " (install with `pip install qiskit qiskit-aer`)",
+        _ => "",
+    };
+    anyhow!("qiskit: {kind}: {message}{hint}")
+}
 
 /// Convert a nalgebra complex matrix to a NumPy (complex128) 2D array.
-fn to_pyarray<'py>(py: Python<'py>, m: &DMatrix<C64>) -> &'py PyArray2<num_complex::Complex64> {
+fn to_pyarray<'py>(py: Python<'py>, m: &DMatrix<C64>) -> Result<&'py PyArray2<num_complex::Complex64>> {
     let (r, c) = (m.nrows(), m.ncols());
     let mut rows: Vec<Vec<num_complex::Complex64>> = Vec::with_capacity(r);
     for i in 0..r {
@@ -25,7 +66,7 @@ fn to_pyarray<'py>(py: Python<'py>, m: &DMatrix<C64>) -> &'py PyArray2<num_compl
     // Use a localized allow to keep the build warning-free without changing behavior.
     #[allow(deprecated)]
     {
-        PyArray2::from_vec2(py, &rows).expect("PyArray2::from_vec2 failed")
+        PyArray2::from_vec2(py, &rows).map_err(|e| anyhow!("cannot pass the unitary to NumPy: {e}"))
     }
 }
 
@@ -33,9 +74,8 @@ fn to_pyarray<'py>(py: Python<'py>, m: &DMatrix<C64>) -> &'py PyArray2<num_compl
 pub fn qiskit_version() -> Result<String> {
     Python::with_gil(|py| {
         // Use the new bound API to silence deprecation warnings in PyO3 0.21+.
-        let qiskit = PyModule::import_bound(py, "qiskit")?;
-        let ver: String = qiskit.getattr("__version__")?.extract()?;
-        Ok(ver)
+        let version = || -> PyResult<String> { PyModule::import_bound(py, "qiskit")?.getattr("__version__")?.extract() };
+        version().map_err(|e| python_error(py, e))
     })
 }
 
@@ -47,7 +87,7 @@ pub fn run_1q_unitary_shots(u: &DMatrix<C64>, shots: usize) -> Result<(u64, u64)
     }
 
     Python::with_gil(|py| -> Result<(u64, u64)> {
-        let np_u = to_pyarray(py, u);
+        let np_u = to_pyarray(py, u)?;
         let code = r#"
 import numpy as np
 from qiskit import QuantumCircuit, transpile
@@ -68,11 +108,80 @@ def run_u(u, shots):
 "#;
 
         // Use new bound constructor to silence deprecation warnings.
-        let m = PyModule::from_code_bound(py, code, "aeonmi_qiskit.py", "aeonmi_qiskit")?;
-        let func = m.getattr("run_u")?;
+        let run = || -> PyResult<(u64, u64)> {
+            let m = PyModule::from_code_bound(py, code, "aeonmi_qiskit.py", "aeonmi_qiskit")?;
+            // Let PyO3 build the argument tuple from a Rust tuple.
+            m.getattr("run_u")?.call1((np_u, shots))?.extract()
+        };
+        run().map_err(|e| python_error(py, e))
+    })
+}
+
+const RUN_CIRCUIT: &str = r#"
+import json
+from qiskit import QuantumCircuit, transpile
+from qiskit_aer import AerSimulator
+
+def fake_backend(name):
+    for module in ("qiskit_ibm_runtime.fake_provider", "qiskit.providers.fake_provider"):
+        try:
+            provider = __import__(module, fromlist=[name])
+        except ImportError:
+            continue
+        if hasattr(provider, name):
+            return getattr(provider, name)()
+    raise ValueError(f"unknown fake backend '{name}' (e.g. FakeManilaV2, from qiskit-ibm-runtime)")
 
-        // Let PyO3 build the argument tuple from a Rust tuple.
-        let (c0, c1): (u64, u64) = func.call1((np_u, shots))?.extract()?;
-        Ok((c0, c1))
+def stats(qc):
+    return qc.depth(), {name: int(n) for name, n in qc.count_ops().items()}
+
+def run(n, ops, shots, level, basis, backend):
+    qc = QuantumCircuit(n, n)
+    for name, targets in ops:
+        getattr(qc, name)(*targets)
+    qc.measure(range(n), range(n))
+    depth_before, gates_before = stats(qc)
+    target = fake_backend(backend) if backend else None
+    tqc = transpile(qc, backend=target, optimization_level=level, basis_gates=basis)
+    depth_after, gates_after = stats(tqc)
+    counts = AerSimulator().run(tqc, shots=shots).result().get_counts()
+    return json.dumps({
+        "shots": shots,
+        "counts": {k: int(v) for k, v in counts.items()},
+        "transpile": {
+            "level": level, "basis_gates": basis, "backend": backend,
+            "depth_before": depth_before, "depth_after": depth_after,
+            "gates_before": gates_before, "gates_after": gates_after,
+        },
     })
+"#;
+
+/// Transpile `circ` (measuring every qubit at the end) and run it on Aer.
+/// `level` is Qiskit's `optimization_level` (0..=3); empty `basis_gates` keeps the default
+/// basis; `backend` names a fake backend (e.g. `FakeManilaV2`) whose coupling map is targeted.
+pub fn run_circuit(circ: &Circuit, shots: usize, level: Option<u8>, basis_gates: &[String], backend: Option<&str>) -> Result<CircuitRun> {
+    if level.is_some_and(|l| l > 3) {
+        bail!("transpile level must be 0..=3");
+    }
+    let ops: Vec<(&str, Vec<usize>)> = circ
+        .ops
+        .iter()
+        .map(|op| {
+            let name = match op.kind {
+                OpKind::H => "h",
+                OpKind::X => "x",
+                OpKind::CNOT => "cx",
+            };
+            (name, op.targets.clone())
+        })
+        .collect();
+    let basis = (!basis_gates.is_empty()).then(|| basis_gates.to_vec());
+    let json = Python::with_gil(|py| {
+        let run = || -> PyResult<String> {
+            let m = PyModule::from_code_bound(py, RUN_CIRCUIT, "aeonmi_qiskit_run.py", "aeonmi_qiskit_run")?;
+            m.getattr("run")?.call1((circ.n_qubits, ops, shots, level, basis, backend))?.extract()
+        };
+        run().map_err(|e| python_error(py, e))
+    })?;
+    serde_json::from_str(&json).map_err(|e| anyhow!("qiskit: unexpected result from the bridge: {e}"))
 }
@@ -1,6 +1,22 @@
+//! Symbolic expressions over real variables: parsing from text, differentiation, and
+//! simplification to a canonical form.
+//!
+//! The grammar is the usual one: `+ - * /`, right-associative `^` binding tighter than
+//! unary minus, parentheses, and calls to the functions in [`Function::ALL`]. Text is
+//! tokenized by the language's own lexer; it has no `^`, so each side of one is lexed
+//! separately.
+//!
+//! [`simplify`] collects like terms and factors, folds constants and orders the result
+//! (terms by falling degree with the constant last, variables before other factors), so
+//! that equal inputs print the same way: `derivative of x^2 + 3*x` is `2*x + 3`.
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fmt;
 
-#[derive(Debug, Clone)]
+use crate::core::lexer::{Lexer, LexerError};
+use crate::core::token::TokenKind;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
     Constant(f64),
     Variable(String),
@@ -9,8 +25,76 @@ pub enum Expr {
     Mul(Box<Expr>, Box<Expr>),
     Div(Box<Expr>, Box<Expr>),
     Pow(Box<Expr>, Box<Expr>),
+    Call(Function, Box<Expr>),
+}
+
+/// Functions an expression may call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Exp,
+    Ln,
+    Sqrt,
 }
 
+impl Function {
+    pub const ALL: [Function; 6] = [Function::Sin, Function::Cos, Function::Tan, Function::Exp, Function::Ln, Function::Sqrt];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Function::Sin => "sin",
+            Function::Cos => "cos",
+            Function::Tan => "tan",
+            Function::Exp => "exp",
+            Function::Ln => "ln",
+            Function::Sqrt => "sqrt",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Function> {
+        Function::ALL.into_iter().find(|f| f.name() == name)
+    }
+
+    pub fn apply(self, x: f64) -> f64 {
+        match self {
+            Function::Sin => x.sin(),
+            Function::Cos => x.cos(),
+            Function::Tan => x.tan(),
+            Function::Exp => x.exp(),
+            Function::Ln => x.ln(),
+            Function::Sqrt => x.sqrt(),
+        }
+    }
+}
+
+fn supported() -> String {
+    Function::ALL.map(Function::name).join(", ")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    /// `found` at 1-based `column`.
+    Unexpected { found: String, column: usize },
+    UnexpectedEnd,
+    UnsupportedFunction(String),
+    Lex(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Unexpected { found, column } => write!(f, "unexpected `{found}` at column {column}"),
+            ParseError::UnexpectedEnd => write!(f, "unexpected end of expression"),
+            ParseError::UnsupportedFunction(name) => write!(f, "unsupported function `{name}` (supported: {})", supported()),
+            ParseError::Lex(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 impl Expr {
     pub fn evaluate(&self, variables: &HashMap<String, f64>) -> f64 {
         match self {
@@ -25,9 +109,23 @@ impl Expr {
             Expr::Pow(base, exponent) => {
                 base.evaluate(variables).powf(exponent.evaluate(variables))
             }
+            Expr::Call(function, arg) => function.apply(arg.evaluate(variables)),
         }
     }
 
+    /// Whether `var` occurs anywhere in the expression.
+    pub fn depends_on(&self, var: &str) -> bool {
+        match self {
+            Expr::Constant(_) => false,
+            Expr::Variable(name) => name == var,
+            Expr::Add(l, r) | Expr::Sub(l, r) | Expr::Mul(l, r) | Expr::Div(l, r) | Expr::Pow(l, r) => {
+                l.depends_on(var) || r.depends_on(var)
+            }
+            Expr::Call(_, arg) => arg.depends_on(var),
+        }
+    }
+
+    /// The derivative with respect to `var`, unsimplified.
     pub fn differentiate(&self, var: &str) -> Expr {
         match self {
             Expr::Constant(_) => Expr::Constant(0.0),
@@ -69,7 +167,8 @@ impl Expr {
                 )),
                 Box::new(Expr::Mul((*rhs).clone(), (*rhs).clone())),
             ),
-            Expr::Pow(base, exponent) => Expr::Mul(
+            // Power rule while the exponent is constant in `var`.
+            Expr::Pow(base, exponent) if !exponent.depends_on(var) => Expr::Mul(
                 Box::new(Expr::Mul(
                     Box::new(*(*exponent).clone()),
                     Box::new(Expr::Pow(
@@ -82,32 +181,510 @@ impl Expr {
                 )),
                 Box::new((*base).differentiate(var)),
             ),
+            // (b^e)' = b^e * (e' * ln(b) + e * b' / b)
+            Expr::Pow(base, exponent) => mul(
+                self.clone(),
+                Expr::Add(
+                    Box::new(mul(exponent.differentiate(var), call(Function::Ln, (**base).clone()))),
+                    Box::new(Expr::Div(
+                        Box::new(mul((**exponent).clone(), base.differentiate(var))),
+                        base.clone(),
+                    )),
+                ),
+            ),
+            Expr::Call(function, arg) => {
+                let u = (**arg).clone();
+                let outer = match function {
+                    Function::Sin => call(Function::Cos, u),
+                    Function::Cos => mul(Expr::Constant(-1.0), call(Function::Sin, u)),
+                    Function::Tan => Expr::Div(Box::new(Expr::Constant(1.0)), Box::new(pow(call(Function::Cos, u), Expr::Constant(2.0)))),
+                    Function::Exp => self.clone(),
+                    Function::Ln => Expr::Div(Box::new(Expr::Constant(1.0)), Box::new(u)),
+                    Function::Sqrt => Expr::Div(Box::new(Expr::Constant(1.0)), Box::new(mul(Expr::Constant(2.0), self.clone()))),
+                };
+                mul(outer, arg.differentiate(var))
+            }
         }
     }
 }
 
+fn mul(l: Expr, r: Expr) -> Expr {
+    Expr::Mul(Box::new(l), Box::new(r))
+}
+
+fn pow(b: Expr, e: Expr) -> Expr {
+    Expr::Pow(Box::new(b), Box::new(e))
+}
+
+fn call(f: Function, arg: Expr) -> Expr {
+    Expr::Call(f, Box::new(arg))
+}
+
+/// Parse, differentiate with respect to `var` and simplify.
+pub fn derivative(src: &str, var: &str) -> Result<Expr, ParseError> {
+    Ok(simplify(parse(src)?.differentiate(var)))
+}
+
+// ---------- parsing ----------
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Num(f64),
+    Ident(String),
+    Op(char),
+}
+
+/// Tokens with their 1-based columns.
+fn tokenize(src: &str) -> Result<Vec<(Tok, usize)>, ParseError> {
+    let mut out = Vec::new();
+    let mut offset = 0;
+    for (n, piece) in src.split('^').enumerate() {
+        if n > 0 {
+            out.push((Tok::Op('^'), offset));
+        }
+        let tokens = Lexer::from_str(piece).tokenize().map_err(|e| match e {
+            LexerError::UnexpectedCharacter(c, _, col) => ParseError::Unexpected { found: c.to_string(), column: offset + col.max(1) },
+            e => ParseError::Lex(e.to_string()),
+        })?;
+        for t in tokens {
+            let column = offset + t.column.max(1);
+            let tok = match t.kind {
+                TokenKind::EOF => break,
+                TokenKind::NumberLiteral(v) => Tok::Num(v),
+                TokenKind::Identifier(name) => Tok::Ident(name),
+                // `log` is a keyword of the language, not a name here.
+                TokenKind::Log => Tok::Ident("log".into()),
+                TokenKind::Plus => Tok::Op('+'),
+                TokenKind::Minus => Tok::Op('-'),
+                TokenKind::Star => Tok::Op('*'),
+                TokenKind::Slash => Tok::Op('/'),
+                TokenKind::StarStar => Tok::Op('^'),
+                TokenKind::OpenParen => Tok::Op('('),
+                TokenKind::CloseParen => Tok::Op(')'),
+                _ => return Err(ParseError::Unexpected { found: t.lexeme, column }),
+            };
+            out.push((tok, column));
+        }
+        offset += piece.chars().count() + 1;
+    }
+    Ok(out)
+}
+
+struct Parser {
+    tokens: Vec<(Tok, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn eat(&mut self, op: char) -> bool {
+        if self.peek() == Some(&Tok::Op(op)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn unexpected(&self) -> ParseError {
+        match self.tokens.get(self.pos) {
+            Some((tok, column)) => {
+                let found = match tok {
+                    Tok::Num(v) => format_number(*v),
+                    Tok::Ident(name) => name.clone(),
+                    Tok::Op(c) => c.to_string(),
+                };
+                ParseError::Unexpected { found, column: *column }
+            }
+            None => ParseError::UnexpectedEnd,
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, ParseError> {
+        let mut lhs = self.product()?;
+        loop {
+            if self.eat('+') {
+                lhs = Expr::Add(Box::new(lhs), Box::new(self.product()?));
+            } else if self.eat('-') {
+                lhs = Expr::Sub(Box::new(lhs), Box::new(self.product()?));
+            } else {
+                return Ok(lhs);
+            }
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, ParseError> {
+        let mut lhs = self.unary()?;
+        loop {
+            if self.eat('*') {
+                lhs = mul(lhs, self.unary()?);
+            } else if self.eat('/') {
+                lhs = Expr::Div(Box::new(lhs), Box::new(self.unary()?));
+            } else {
+                return Ok(lhs);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        if self.eat('-') {
+            return Ok(match self.unary()? {
+                Expr::Constant(v) => Expr::Constant(-v),
+                e => mul(Expr::Constant(-1.0), e),
+            });
+        }
+        if self.eat('+') {
+            return self.unary();
+        }
+        let base = self.atom()?;
+        if self.eat('^') {
+            return Ok(pow(base, self.unary()?));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr, ParseError> {
+        match self.peek().cloned() {
+            Some(Tok::Num(v)) => {
+                self.pos += 1;
+                Ok(Expr::Constant(v))
+            }
+            Some(Tok::Ident(name)) => {
+                self.pos += 1;
+                if !self.eat('(') {
+                    return Ok(Expr::Variable(name));
+                }
+                let function = Function::from_name(&name).ok_or(ParseError::UnsupportedFunction(name))?;
+                let arg = self.sum()?;
+                if !self.eat(')') {
+                    return Err(self.unexpected());
+                }
+                Ok(call(function, arg))
+            }
+            Some(Tok::Op('(')) => {
+                self.pos += 1;
+                let inner = self.sum()?;
+                if !self.eat(')') {
+                    return Err(self.unexpected());
+                }
+                Ok(inner)
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+}
+
+/// Parse `src` into an expression, as written (see [`simplify`]).
+pub fn parse(src: &str) -> Result<Expr, ParseError> {
+    let mut parser = Parser { tokens: tokenize(src)?, pos: 0 };
+    let expr = parser.sum()?;
+    if parser.pos < parser.tokens.len() {
+        return Err(parser.unexpected());
+    }
+    Ok(expr)
+}
+
+// ---------- simplification ----------
+
+/// Simplify to canonical form; see the module docs.
 pub fn simplify(expr: Expr) -> Expr {
     match expr {
-        Expr::Add(lhs, rhs) => match (*lhs, *rhs) {
-            (Expr::Constant(a), Expr::Constant(b)) => Expr::Constant(a + b),
-            (l, r) => Expr::Add(Box::new(l), Box::new(r)),
-        },
-        Expr::Sub(lhs, rhs) => match (*lhs, *rhs) {
-            (Expr::Constant(a), Expr::Constant(b)) => Expr::Constant(a - b),
-            (l, r) => Expr::Sub(Box::new(l), Box::new(r)),
-        },
-        Expr::Mul(lhs, rhs) => match (*lhs, *rhs) {
-            (Expr::Constant(a), Expr::Constant(b)) => Expr::Constant(a * b),
-            (l, r) => Expr::Mul(Box::new(l), Box::new(r)),
-        },
-        Expr::Div(lhs, rhs) => match (*lhs, *rhs) {
-            (Expr::Constant(a), Expr::Constant(b)) => Expr::Constant(a / b),
-            (l, r) => Expr::Div(Box::new(l), Box::new(r)),
-        },
-        Expr::Pow(base, exponent) => match (*base, *exponent) {
-            (Expr::Constant(a), Expr::Constant(b)) => Expr::Constant(a.powf(b)),
-            (b, e) => Expr::Pow(Box::new(b), Box::new(e)),
+        Expr::Add(lhs, rhs) => sum(vec![simplify(*lhs), simplify(*rhs)]),
+        Expr::Sub(lhs, rhs) => sum(vec![simplify(*lhs), product(vec![Expr::Constant(-1.0), simplify(*rhs)])]),
+        Expr::Mul(lhs, rhs) => product(vec![simplify(*lhs), simplify(*rhs)]),
+        Expr::Div(lhs, rhs) => product(vec![simplify(*lhs), power(simplify(*rhs), Expr::Constant(-1.0))]),
+        Expr::Pow(base, exponent) => power(simplify(*base), simplify(*exponent)),
+        Expr::Call(function, arg) => match simplify(*arg) {
+            // Only fold exact results: `exp(0)` is 1, but `sin(2)` stays as it is.
+            Expr::Constant(v) if is_integer(function.apply(v)) => Expr::Constant(function.apply(v)),
+            arg => call(function, arg),
         },
         _ => expr,
     }
 }
+
+fn is_integer(v: f64) -> bool {
+    v.is_finite() && v.fract() == 0.0
+}
+
+/// A numeric coefficient times powers, each `(base, exponent)`.
+struct Product {
+    coef: f64,
+    factors: Vec<(Expr, Expr)>,
+}
+
+impl Product {
+    /// Add canonical `e` to the product, `sign` 1 for a factor and -1 for a divisor.
+    fn absorb(&mut self, e: Expr, sign: f64) {
+        match e {
+            Expr::Constant(c) => self.coef *= c.powf(sign),
+            Expr::Mul(l, r) => {
+                self.absorb(*l, sign);
+                self.absorb(*r, sign);
+            }
+            Expr::Div(l, r) => {
+                self.absorb(*l, sign);
+                self.absorb(*r, -sign);
+            }
+            Expr::Pow(b, e) if sign < 0.0 => self.push(*b, product(vec![Expr::Constant(-1.0), *e])),
+            Expr::Pow(b, e) => self.push(*b, *e),
+            e => self.push(e, Expr::Constant(sign)),
+        }
+    }
+
+    fn push(&mut self, base: Expr, exponent: Expr) {
+        match self.factors.iter_mut().find(|(b, _)| *b == base) {
+            Some((_, e)) => *e = sum(vec![e.clone(), exponent]),
+            None => self.factors.push((base, exponent)),
+        }
+    }
+
+    fn of(items: Vec<Expr>) -> Product {
+        let mut p = Product { coef: 1.0, factors: Vec::new() };
+        for e in items {
+            p.absorb(e, 1.0);
+        }
+        p
+    }
+
+    /// Total exponent of plain variables, for ordering terms.
+    fn degree(&self) -> f64 {
+        self.factors
+            .iter()
+            .map(|(b, e)| match (b, e) {
+                (Expr::Variable(_), Expr::Constant(n)) => *n,
+                _ => 0.0,
+            })
+            .sum()
+    }
+
+    fn build(mut self) -> Expr {
+        let mut kept = Vec::new();
+        for (base, exponent) in self.factors {
+            match (&base, &exponent) {
+                (_, Expr::Constant(e)) if *e == 0.0 => {}
+                (Expr::Constant(b), Expr::Constant(e)) => self.coef *= b.powf(*e),
+                _ => kept.push((base, exponent)),
+            }
+        }
+        if self.coef == 0.0 || kept.is_empty() {
+            return Expr::Constant(self.coef);
+        }
+        kept.sort_by(|(a, _), (b, _)| factor_order(a, b));
+
+        let (mut num, mut den) = (Vec::new(), Vec::new());
+        let inverse = 1.0 / self.coef;
+        if !is_integer(self.coef) && is_integer(inverse) {
+            den.push(Expr::Constant(inverse.abs()));
+            self.coef = inverse.signum();
+        }
+        if self.coef != 1.0 {
+            num.push(Expr::Constant(self.coef));
+        }
+        for (base, exponent) in kept {
+            match exponent {
+                Expr::Constant(e) if e < 0.0 => den.push(raise(base, -e)),
+                e => num.push(raise_expr(base, e)),
+            }
+        }
+        let chain = |items: Vec<Expr>| items.into_iter().reduce(mul);
+        match (chain(num), chain(den)) {
+            (Some(n), None) => n,
+            (n, Some(d)) => Expr::Div(Box::new(n.unwrap_or(Expr::Constant(1.0))), Box::new(d)),
+            (None, None) => Expr::Constant(1.0),
+        }
+    }
+}
+
+fn raise(base: Expr, e: f64) -> Expr {
+    raise_expr(base, Expr::Constant(e))
+}
+
+fn raise_expr(base: Expr, e: Expr) -> Expr {
+    if e == Expr::Constant(1.0) { base } else { pow(base, e) }
+}
+
+/// Variables first, alphabetically, then everything else by how it prints.
+fn factor_order(a: &Expr, b: &Expr) -> Ordering {
+    match (a, b) {
+        (Expr::Variable(x), Expr::Variable(y)) => x.cmp(y),
+        (Expr::Variable(_), _) => Ordering::Less,
+        (_, Expr::Variable(_)) => Ordering::Greater,
+        _ => a.to_string().cmp(&b.to_string()),
+    }
+}
+
+fn product(items: Vec<Expr>) -> Expr {
+    Product::of(items).build()
+}
+
+fn power(base: Expr, exponent: Expr) -> Expr {
+    match (&base, &exponent) {
+        (Expr::Constant(b), Expr::Constant(e)) => return Expr::Constant(b.powf(*e)),
+        (_, Expr::Constant(e)) if *e == 0.0 => return Expr::Constant(1.0),
+        (_, Expr::Constant(e)) if *e == 1.0 => return base,
+        (Expr::Constant(b), _) if *b == 1.0 => return Expr::Constant(1.0),
+        _ => {}
+    }
+    // Integer powers distribute over products and multiply nested exponents.
+    if let Expr::Constant(n) = exponent {
+        if is_integer(n) {
+            match base {
+                Expr::Pow(b, e) => return power(*b, product(vec![*e, Expr::Constant(n)])),
+                Expr::Mul(..) | Expr::Div(..) => {
+                    let p = Product::of(vec![base]);
+                    let mut items = vec![Expr::Constant(p.coef.powf(n))];
+                    items.extend(p.factors.into_iter().map(|(b, e)| pow(b, product(vec![e, Expr::Constant(n)]))));
+                    return product(items);
+                }
+                _ => {}
+            }
+        }
+    }
+    product(vec![pow(base, exponent)])
+}
+
+fn sum(items: Vec<Expr>) -> Expr {
+    let mut flat = Vec::new();
+    let mut stack = items;
+    while let Some(e) = stack.pop() {
+        match e {
+            Expr::Add(l, r) => {
+                stack.push(*r);
+                stack.push(*l);
+            }
+            e => flat.push(e),
+        }
+    }
+
+    // (coefficient, rest of the term, degree); `rest` None for the constant.
+    let mut terms: Vec<(f64, Option<Expr>, f64)> = Vec::new();
+    for e in flat {
+        let p = Product::of(vec![e]);
+        let degree = p.degree();
+        let (coef, rest) = match (Product { coef: 1.0, factors: p.factors }).build() {
+            Expr::Constant(c) => (p.coef * c, None),
+            rest => (p.coef, Some(rest)),
+        };
+        match terms.iter_mut().find(|(_, r, _)| *r == rest) {
+            Some((c, _, _)) => *c += coef,
+            None => terms.push((coef, rest, degree)),
+        }
+    }
+    terms.retain(|(c, _, _)| *c != 0.0);
+    terms.sort_by(|(_, ra, da), (_, rb, db)| match (ra, rb) {
+        (None, None) => Ordering::Equal,
+        (None, _) => Ordering::Greater,
+        (_, None) => Ordering::Less,
+        (Some(a), Some(b)) => db.partial_cmp(da).unwrap_or(Ordering::Equal).then_with(|| a.to_string().cmp(&b.to_string())),
+    });
+    terms
+        .into_iter()
+        .map(|(c, rest, _)| match rest {
+            Some(rest) => product(vec![Expr::Constant(c), rest]),
+            None => Expr::Constant(c),
+        })
+        .reduce(|l, r| Expr::Add(Box::new(l), Box::new(r)))
+        .unwrap_or(Expr::Constant(0.0))
+}
+
+// ---------- printing ----------
+
+/// Whole numbers without a fractional part, everything else as Rust prints it.
+fn format_number(v: f64) -> String {
+    if is_integer(v) && v.abs() < 1e15 { format!("{}", v as i64) } else { v.to_string() }
+}
+
+const SUM: u8 = 1;
+const PRODUCT: u8 = 2;
+const NEGATION: u8 = 3;
+const POWER: u8 = 4;
+const ATOM: u8 = 5;
+
+impl Expr {
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Constant(v) if *v < 0.0 => NEGATION,
+            Expr::Constant(_) | Expr::Variable(_) | Expr::Call(..) => ATOM,
+            Expr::Add(..) | Expr::Sub(..) => SUM,
+            Expr::Mul(l, _) if **l == Expr::Constant(-1.0) => NEGATION,
+            Expr::Mul(..) | Expr::Div(..) => PRODUCT,
+            Expr::Pow(..) => POWER,
+        }
+    }
+
+    /// A term that prints with a leading minus sign, and the term without it.
+    fn negated(&self) -> Option<Expr> {
+        match self {
+            Expr::Constant(v) if *v < 0.0 => Some(Expr::Constant(-v)),
+            Expr::Mul(l, r) if **l == Expr::Constant(-1.0) => Some((**r).clone()),
+            Expr::Mul(l, r) => l.negated().map(|l| match l {
+                Expr::Constant(1.0) => (**r).clone(),
+                l => mul(l, (**r).clone()),
+            }),
+            Expr::Div(l, r) => l.negated().map(|l| Expr::Div(Box::new(l), r.clone())),
+            _ => None,
+        }
+    }
+}
+
+struct Operand<'a>(&'a Expr, u8);
+
+impl fmt::Display for Operand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.precedence() < self.1 { write!(f, "({})", self.0) } else { write!(f, "{}", self.0) }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Constant(v) => write!(f, "{}", format_number(*v)),
+            Expr::Variable(name) => write!(f, "{name}"),
+            Expr::Add(l, r) => match r.negated() {
+                Some(r) => write!(f, "{} - {}", l, Operand(&r, PRODUCT)),
+                None => write!(f, "{} + {}", l, r),
+            },
+            Expr::Sub(l, r) => write!(f, "{} - {}", l, Operand(r, PRODUCT)),
+            Expr::Mul(l, r) if **l == Expr::Constant(-1.0) => write!(f, "-{}", Operand(r, NEGATION)),
+            Expr::Mul(l, r) => write!(f, "{}*{}", Operand(l, PRODUCT), Operand(r, NEGATION)),
+            Expr::Div(l, r) => write!(f, "{}/{}", Operand(l, PRODUCT), Operand(r, POWER)),
+            Expr::Pow(b, e) => write!(f, "{}^{}", Operand(b, ATOM), Operand(e, POWER)),
+            Expr::Call(function, arg) => write!(f, "{}({})", function.name(), arg),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(src: &str) -> String {
+        derivative(src, "x").unwrap().to_string()
+    }
+
+    #[test]
+    fn canonical_forms_are_stable() {
+        for src in ["2*x + 3", "x^2*cos(x) + 2*x*sin(x)", "6*(2*x + 1)^2", "1/x", "-sin(x)"] {
+            let once = simplify(parse(src).unwrap());
+            assert_eq!(once.to_string(), src);
+            assert_eq!(simplify(once.clone()), once);
+        }
+    }
+
+    #[test]
+    fn derivatives_agree_with_finite_differences() {
+        let vars = |x: f64| HashMap::from([("x".to_string(), x)]);
+        for src in ["x^x", "tan(x)/x", "sqrt(1 + x^2)", "ln(x)*exp(-x)"] {
+            let f = parse(src).unwrap();
+            let df = derivative(src, "x").unwrap();
+            let (x, h) = (1.3, 1e-6);
+            let numeric = (f.evaluate(&vars(x + h)) - f.evaluate(&vars(x - h))) / (2.0 * h);
+            assert!((df.evaluate(&vars(x)) - numeric).abs() < 1e-5, "{src}: {df}");
+        }
+        assert_eq!(d("5"), "0");
+    }
+}
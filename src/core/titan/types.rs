@@ -33,6 +33,14 @@ impl QState {
         }
     }
 
+    /// |0..0⟩ on `n_qubits` qubits (2^n amplitudes). Not [`QState::zeros`], which is the
+    /// all-zero vector of length `n`.
+    pub fn ground(n_qubits: usize) -> Self {
+        let mut data = DVector::from_element(1 << n_qubits, C64::new(0.0, 0.0));
+        data[0] = C64::new(1.0, 0.0);
+        Self { data }
+    }
+
     pub fn len(&self) -> usize {
         self.data.len()
     }
@@ -1,10 +1,12 @@
 // src/core/token.rs
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
 pub enum TokenKind {
     // Identifiers and literals
     Identifier(String),
     NumberLiteral(f64),
     StringLiteral(String),
+    /// `"sum: ${a + b}"` — a string literal with `${...}` parts; plain strings stay `StringLiteral`.
+    InterpolatedString(Vec<StringPart>),
     BooleanLiteral(bool),
     QubitLiteral(String),
     
@@ -13,6 +15,8 @@ pub enum TokenKind {
     Minus,        // -
     Star,         // *
     Slash,        // /
+    Percent,      // %
+    StarStar,     // **
     Equals,       // =
     DoubleEquals, // ==
     NotEquals,    // !=
@@ -24,14 +28,20 @@ pub enum TokenKind {
     Pipe,         // |
     AndAnd,       // &&
     OrOr,         // ||
+    Bang,         // !
     
     // Delimiters
     OpenParen,    // (
     CloseParen,   // )
     OpenBrace,    // {
     CloseBrace,   // }
+    OpenBracket,  // [
+    CloseBracket, // ]
     Comma,        // ,
     Semicolon,    // ;
+    Dot,          // .
+    Colon,        // : (object literal fields)
+    FatArrow,     // =>
     
     // Keywords
     Function,
@@ -41,9 +51,15 @@ pub enum TokenKind {
     While,
     For,
     In,
+    Break,
+    Continue,
     Return,
     Log,
     Qubit,
+    Qreg,
+    Enum,
+    Match,
+    Import,
     
     // Quantum operations
     Superpose,
@@ -58,7 +74,15 @@ pub enum TokenKind {
     EOF,
 }
 
-#[derive(Debug, Clone)]
+/// One piece of an interpolated string, in source order.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub enum StringPart {
+    Text(String),
+    /// The tokens between `${` and its matching `}`, positioned in the enclosing source.
+    Expr(Vec<Token>),
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
 pub struct Token {
     pub kind: TokenKind,
     pub lexeme: String,
@@ -84,12 +108,15 @@ impl std::fmt::Display for TokenKind {
             TokenKind::Identifier(_) => "identifier",
             TokenKind::NumberLiteral(_) => "number",
             TokenKind::StringLiteral(_) => "string",
+            TokenKind::InterpolatedString(_) => "interpolated string",
             TokenKind::BooleanLiteral(_) => "boolean",
             TokenKind::QubitLiteral(_) => "qubit",
             TokenKind::Plus => "+",
             TokenKind::Minus => "-",
             TokenKind::Star => "*",
             TokenKind::Slash => "/",
+            TokenKind::Percent => "%",
+            TokenKind::StarStar => "**",
             TokenKind::Equals => "=",
             TokenKind::DoubleEquals => "==",
             TokenKind::NotEquals => "!=",
@@ -101,12 +128,18 @@ impl std::fmt::Display for TokenKind {
             TokenKind::Pipe => "|",
             TokenKind::AndAnd => "&&",
             TokenKind::OrOr => "||",
+            TokenKind::Bang => "!",
             TokenKind::OpenParen => "(",
             TokenKind::CloseParen => ")",
             TokenKind::OpenBrace => "{",
             TokenKind::CloseBrace => "}",
+            TokenKind::OpenBracket => "[",
+            TokenKind::CloseBracket => "]",
             TokenKind::Comma => ",",
+            TokenKind::Colon => ":",
             TokenKind::Semicolon => ";",
+            TokenKind::Dot => ".",
+            TokenKind::FatArrow => "=>",
             TokenKind::Function => "function",
             TokenKind::Let => "let",
             TokenKind::If => "if",
@@ -114,9 +147,15 @@ impl std::fmt::Display for TokenKind {
             TokenKind::While => "while",
             TokenKind::For => "for",
             TokenKind::In => "in",
+            TokenKind::Break => "break",
+            TokenKind::Continue => "continue",
             TokenKind::Return => "return",
             TokenKind::Log => "log",
             TokenKind::Qubit => "qubit",
+            TokenKind::Qreg => "qreg",
+            TokenKind::Enum => "enum",
+            TokenKind::Match => "match",
+            TokenKind::Import => "import",
             TokenKind::Superpose => "superpose",
             TokenKind::Entangle => "entangle",
             TokenKind::Measure => "measure",
@@ -135,6 +174,21 @@ impl std::fmt::Display for Token {
             TokenKind::Identifier(name) => write!(f, "Identifier('{}') @{}:{}", name, self.line, self.column),
             TokenKind::NumberLiteral(v) => write!(f, "Number({}) @{}:{}", v, self.line, self.column),
             TokenKind::StringLiteral(s) => write!(f, "String(\"{}\") @{}:{}", s, self.line, self.column),
+            TokenKind::InterpolatedString(parts) => {
+                write!(f, "Interpolated @{}:{}", self.line, self.column)?;
+                for part in parts {
+                    match part {
+                        StringPart::Text(s) => write!(f, "\n  Text(\"{}\")", s)?,
+                        StringPart::Expr(tokens) => {
+                            write!(f, "\n  Expr")?;
+                            for t in tokens {
+                                write!(f, "\n    {}", t)?;
+                            }
+                        }
+                    }
+                }
+                Ok(())
+            }
             TokenKind::BooleanLiteral(b) => write!(f, "Boolean({}) @{}:{}", b, self.line, self.column),
             TokenKind::QubitLiteral(q) => write!(f, "Qubit({}) @{}:{}", q, self.line, self.column),
             TokenKind::HieroglyphicOp(sym) => write!(f, "Hieroglyphic('{}') @{}:{}", sym, self.line, self.column),
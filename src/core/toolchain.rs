@@ -0,0 +1,123 @@
+//! External runtime detection (node, python, rustc) with cached probes.
+//!
+//! Probing spawns `<tool> --version`, which is slow enough to notice in watch
+//! loops, so results are cached for [`PROBE_TTL`]. Every fallback or missing-tool
+//! path should print one of the messages below so the guidance stays consistent.
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+pub const PROBE_TTL: Duration = Duration::from_secs(60);
+
+/// Printed when `run`/`exec` wanted node and fell back to the native VM.
+pub const NODE_FALLBACK_NOTICE: &str = "node not found; running natively — install node or pass --native to silence";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tool {
+    Node,
+    Python,
+    Rustc,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 3] = [Tool::Node, Tool::Python, Tool::Rustc];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Tool::Node => "node",
+            Tool::Python => "python",
+            Tool::Rustc => "rustc",
+        }
+    }
+
+    /// Executable to spawn for this tool on the current platform.
+    pub fn exe(self) -> &'static str {
+        match self {
+            Tool::Python if !cfg!(windows) => "python3",
+            t => t.name(),
+        }
+    }
+
+    /// What stops working without this tool (shown by `aeonmi doctor`).
+    pub fn features(self) -> &'static str {
+        match self {
+            Tool::Node => "run/exec via JS (native VM used instead), exec .js, `aeonmi node`, TUI/editor run",
+            Tool::Python => "exec .py, `aeonmi python`",
+            Tool::Rustc => "exec .rs",
+        }
+    }
+}
+
+/// One-line error for a command that cannot proceed without `tool`.
+pub fn missing_tool_message(tool: Tool, action: &str) -> String {
+    format!("{} not found; cannot {} — install {} and make sure `{}` is on PATH", tool.name(), action, tool.name(), tool.exe())
+}
+
+/// Run `<exe> --version`; `None` when it can't be spawned or exits non-zero.
+pub fn probe_version(tool: Tool) -> Option<String> {
+    let out = Command::new(tool.exe()).arg("--version").output().ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    // python 2 printed its version on stderr
+    let text = if out.stdout.is_empty() { out.stderr } else { out.stdout };
+    Some(String::from_utf8_lossy(&text).lines().next().unwrap_or("").trim().to_string())
+}
+
+/// Probe results keyed by tool, each valid for `ttl`. The probe is injectable for tests.
+pub struct ProbeCache<P: FnMut(Tool) -> Option<String>> {
+    probe: P,
+    ttl: Duration,
+    entries: HashMap<Tool, (Instant, Option<String>)>,
+}
+
+impl<P: FnMut(Tool) -> Option<String>> ProbeCache<P> {
+    pub fn new(probe: P, ttl: Duration) -> Self {
+        Self { probe, ttl, entries: HashMap::new() }
+    }
+
+    /// Cached version string for `tool` as of `now`, re-probing once the entry is older than the TTL.
+    pub fn version_at(&mut self, tool: Tool, now: Instant) -> Option<String> {
+        if let Some((at, v)) = self.entries.get(&tool) {
+            if now.saturating_duration_since(*at) < self.ttl {
+                return v.clone();
+            }
+        }
+        let v = (self.probe)(tool);
+        self.entries.insert(tool, (now, v.clone()));
+        v
+    }
+}
+
+type SystemCache = ProbeCache<fn(Tool) -> Option<String>>;
+static CACHE: OnceLock<Mutex<SystemCache>> = OnceLock::new();
+
+/// Installed version of `tool`, probed at most once per [`PROBE_TTL`].
+pub fn version(tool: Tool) -> Option<String> {
+    let cache = CACHE.get_or_init(|| Mutex::new(ProbeCache::new(probe_version as fn(Tool) -> Option<String>, PROBE_TTL)));
+    let mut guard = cache.lock().unwrap_or_else(|e| e.into_inner());
+    guard.version_at(tool, Instant::now())
+}
+
+pub fn available(tool: Tool) -> bool {
+    version(tool).is_some()
+}
+
+/// `aeonmi doctor` body: one line per tool, then the features lost to missing tools.
+pub fn doctor_report(found: &[(Tool, Option<String>)]) -> String {
+    let mut out = String::new();
+    for (tool, v) in found {
+        out.push_str(&format!("{:<8}{}\n", tool.name(), v.as_deref().unwrap_or("not found")));
+    }
+    let missing: Vec<&Tool> = found.iter().filter(|(_, v)| v.is_none()).map(|(t, _)| t).collect();
+    if missing.is_empty() {
+        out.push_str("all external tools found\n");
+    } else {
+        out.push_str("\nunavailable:\n");
+        for tool in missing {
+            out.push_str(&format!("  {:<8}{}\n", tool.name(), tool.features()));
+        }
+    }
+    out
+}
@@ -1,11 +1,48 @@
 //! Minimal type system scaffold.
 //! Provides primitive types and a simple inference + checking routine.
 
-use crate::core::ast::ASTNode;
+use crate::core::analysis_budget::{AnalysisBudget, Truncation};
+use crate::core::ast::{ASTNode, FunctionParam, Pattern};
 use serde::{Serialize, Deserialize};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub enum TypeKind { Number, Boolean, String, Void, Unknown }
+pub enum TypeKind {
+    Number, Boolean, String, Void, Unknown,
+    /// Variants of the named enum; each enum is its own type.
+    Enum(String),
+    /// Array whose elements all have this type (`Unknown` when mixed or not yet known).
+    Array(Box<TypeKind>),
+    /// Map from key type to value type, e.g. `counts()` is `Map<String, Number>`.
+    Map(Box<TypeKind>, Box<TypeKind>),
+    /// A function value: parameter types and return type.
+    Function(Vec<TypeKind>, Box<TypeKind>),
+}
+
+impl TypeKind {
+    pub fn array(elem: TypeKind) -> Self { TypeKind::Array(Box::new(elem)) }
+    pub fn map(key: TypeKind, value: TypeKind) -> Self { TypeKind::Map(Box::new(key), Box::new(value)) }
+    pub fn function(params: Vec<TypeKind>, ret: TypeKind) -> Self { TypeKind::Function(params, Box::new(ret)) }
+    fn is_known(&self) -> bool { !matches!(self, TypeKind::Unknown) }
+}
+
+impl std::fmt::Display for TypeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeKind::Number => write!(f, "Number"),
+            TypeKind::Boolean => write!(f, "Boolean"),
+            TypeKind::String => write!(f, "String"),
+            TypeKind::Void => write!(f, "Void"),
+            TypeKind::Unknown => write!(f, "Unknown"),
+            TypeKind::Enum(name) => write!(f, "{name}"),
+            TypeKind::Array(elem) => write!(f, "Array<{elem}>"),
+            TypeKind::Map(k, v) => write!(f, "Map<{k}, {v}>"),
+            TypeKind::Function(params, ret) => {
+                let ps: Vec<String> = params.iter().map(|p| p.to_string()).collect();
+                write!(f, "fn({}) -> {ret}", ps.join(", "))
+            }
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TypeInfo {
@@ -21,14 +58,51 @@ pub struct TypeContext {
     scopes: Vec<std::collections::HashMap<String, TypeKind>>,
     pub diags: Vec<TypeDiagnostic>,
     functions: std::collections::HashMap<String, (Vec<TypeKind>, TypeKind)>, // name -> (param types, return type)
+    enums: std::collections::HashSet<String>,
+    // Bodies kept so a call can re-infer the return type from its argument types.
+    bodies: std::collections::HashMap<String, (Vec<FunctionParam>, Vec<ASTNode>)>,
+    instantiating: std::collections::HashSet<String>,
+    /// Inferred type of each declaration (function, parameter, variable) by line, column and
+    /// name; parameters share their function's position.
+    pub declared: std::collections::HashMap<(usize, usize, String), TypeKind>,
+    budget: Option<AnalysisBudget>,
+    /// Set when the budget ran out before the last top-level item; `diags` covers the items before it.
+    pub truncated: Option<Truncation>,
+    /// Position of the innermost node being visited that has one; diagnostics point there.
+    at: (usize, usize),
 }
 
 impl TypeContext {
-    pub fn new() -> Self { Self { scopes: vec![Default::default()], diags: vec![], functions: Default::default() } }
+    pub fn new() -> Self { Self { scopes: vec![Default::default()], diags: vec![], functions: Default::default(), enums: Default::default(), bodies: Default::default(), instantiating: Default::default(), declared: Default::default(), budget: None, truncated: None, at: (0, 0) } }
+    /// Stop [`TypeContext::infer_program`] between top-level items once `budget` runs out.
+    pub fn with_budget(mut self, budget: AnalysisBudget) -> Self { self.budget = Some(budget); self }
+    /// Seed the type of a global the program does not declare itself (host-provided values).
+    pub fn declare_global(&mut self, name: &str, ty: TypeKind) { if let Some(s) = self.scopes.first_mut() { s.insert(name.to_string(), ty); } }
     fn begin_scope(&mut self){ self.scopes.push(Default::default()); }
     fn end_scope(&mut self){ self.scopes.pop(); }
     fn declare(&mut self, name: &str, ty: TypeKind) { if let Some(s) = self.scopes.last_mut() { s.insert(name.to_string(), ty); } }
-    fn lookup(&self, name: &str) -> TypeKind { for s in self.scopes.iter().rev() { if let Some(t) = s.get(name) { return t.clone(); } } TypeKind::Unknown }
+    // Instantiations re-run bodies with call-site types; only the declaration's own pass counts.
+    fn record(&mut self, line: usize, column: usize, name: &str, ty: &TypeKind) { if self.instantiating.is_empty() { self.declared.insert((line, column, name.to_string()), ty.clone()); } }
+    /// Inferred type of the declaration of `name` at `line`:`column`, after [`TypeContext::infer_program`].
+    pub fn type_at(&self, line: usize, column: usize, name: &str) -> Option<&TypeKind> { self.declared.get(&(line, column, name.to_string())) }
+    /// Declare every name in `p`; array patterns hand each name the element type of `ty`.
+    fn declare_pattern(&mut self, p: &Pattern, ty: TypeKind) {
+        match p {
+            Pattern::Name { name, line, column } => { self.record(*line, *column, name, &ty); self.declare(name, ty) }
+            Pattern::Array { items, .. } => {
+                let elem = match ty { TypeKind::Array(e) => *e, TypeKind::String => TypeKind::String, _ => TypeKind::Unknown };
+                for it in items { self.declare_pattern(it, elem.clone()); }
+            }
+        }
+    }
+    fn lookup_var(&self, name: &str) -> Option<TypeKind> { self.scopes.iter().rev().find_map(|s| s.get(name).cloned()) }
+    // Variables first, then declared functions as values.
+    fn lookup(&self, name: &str) -> TypeKind {
+        self.lookup_var(name).or_else(|| self.functions.get(name).map(|(ps, r)| TypeKind::function(ps.clone(), r.clone()))).unwrap_or(TypeKind::Unknown)
+    }
+    fn diag(&mut self, message: String) { self.diags.push(TypeDiagnostic { message, line: self.at.0, column: self.at.1 }); }
+    // At `node` itself: `if`, `while` and `for` have no position of their own to fall back on.
+    fn diag_at(&mut self, node: &ASTNode, message: String) { let (line, column) = position(node).unwrap_or(self.at); self.diags.push(TypeDiagnostic { message, line, column }); }
     fn update_if_unknown(&mut self, name: &str, ty: &TypeKind) {
         if *ty == TypeKind::Unknown || *ty == TypeKind::Void { return; }
         for s in self.scopes.iter().rev() {
@@ -39,11 +113,113 @@ impl TypeContext {
 
     pub fn infer_program(&mut self, ast: &ASTNode) { self.visit(ast); }
 
+    /// Diagnose `==`/`!=` (or a match arm) between variants of two different enums; true if it did.
+    fn check_enum_comparison(&mut self, lt: &TypeKind, rt: &TypeKind) -> bool {
+        if let (TypeKind::Enum(a), TypeKind::Enum(b)) = (lt, rt) {
+            if a != b { self.diags.push(TypeDiagnostic { message: format!("Comparison between different enums '{a}' and '{b}' is always false"), line: self.at.0, column: self.at.1 }); }
+            return true;
+        }
+        false
+    }
+
+    /// Return type of calling the declared function `name`; a return type that depends on
+    /// untyped parameters is re-inferred from the argument types (so generics flow through).
+    fn call_function(&mut self, name: &str, args: &[TypeKind]) -> TypeKind {
+        let Some((params, ret)) = self.functions.get(name).cloned() else { return TypeKind::Unknown; };
+        if params.len() != args.len() { self.diag(format!("Call arity mismatch for {name}")); return ret; }
+        if ret.is_known() || !args.iter().any(TypeKind::is_known) { return ret; }
+        self.instantiate(name, args)
+    }
+
+    // Re-run the body of `name` with its parameters bound to `args`, keeping only the return type.
+    fn instantiate(&mut self, name: &str, args: &[TypeKind]) -> TypeKind {
+        let Some((params, body)) = self.bodies.get(name).cloned() else { return TypeKind::Unknown; };
+        if !self.instantiating.insert(name.to_string()) { return TypeKind::Unknown; }
+        let diag_len = self.diags.len();
+        self.begin_scope();
+        for (p, t) in params.iter().zip(args) { self.declare(&p.name, t.clone()); }
+        let mut ret = TypeKind::Void;
+        for it in &body { if let ASTNode::Return(expr) = it { ret = self.visit(expr); } else { self.visit(it); } }
+        self.end_scope();
+        // The body was already checked on its own; don't report it again per call site.
+        self.diags.truncate(diag_len);
+        self.instantiating.remove(name);
+        ret
+    }
+
+    /// Apply a callback value to one argument of type `arg`, for `map`/`filter`.
+    fn apply_callback(&mut self, builtin: &str, callback: &TypeKind, callback_fn: Option<&str>, arg: &TypeKind) -> TypeKind {
+        match callback {
+            TypeKind::Function(params, ret) => {
+                if params.len() != 1 { self.diag(format!("{builtin} callback must take 1 parameter, takes {}", params.len())); return TypeKind::Unknown; }
+                if !compatible(&params[0], arg) { self.diag(format!("{builtin} callback expects {}, but the array holds {arg}", params[0])); }
+                if ret.is_known() { return (**ret).clone(); }
+                match callback_fn { Some(n) if arg.is_known() => self.instantiate(n, std::slice::from_ref(arg)), _ => TypeKind::Unknown }
+            }
+            TypeKind::Unknown => TypeKind::Unknown,
+            other => { self.diag(format!("{builtin} callback is not a function ({other})")); TypeKind::Unknown }
+        }
+    }
+
+    // Element type of an array argument to a collection builtin.
+    fn element_of(&mut self, builtin: &str, ty: &TypeKind) -> TypeKind {
+        match ty {
+            TypeKind::Array(elem) => (**elem).clone(),
+            TypeKind::Unknown => TypeKind::Unknown,
+            other => { self.diag(format!("{builtin} expects an array, got {other}")); TypeKind::Unknown }
+        }
+    }
+
+    /// Result type of a builtin call; unknown names (host functions) are `Unknown`.
+    /// `callback` names the declared function passed as the second argument, if any.
+    fn call_builtin(&mut self, name: &str, args: &[TypeKind], callback: Option<&str>) -> TypeKind {
+        let arg = |i: usize| args.get(i).cloned().unwrap_or(TypeKind::Unknown);
+        match name {
+            "len" | "rand" | "measure" => TypeKind::Number,
+            "env" | "read_line" | "read_all" | "read_file" => TypeKind::String,
+            "counts" => TypeKind::map(TypeKind::String, TypeKind::Number),
+            "map" => { let elem = self.element_of(name, &arg(0)); TypeKind::array(self.apply_callback(name, &arg(1), callback, &elem)) }
+            "filter" => {
+                let elem = self.element_of(name, &arg(0));
+                let kept = self.apply_callback(name, &arg(1), callback, &elem);
+                if kept.is_known() && kept != TypeKind::Boolean { self.diag(format!("filter callback returns {kept}, expected Boolean")); }
+                TypeKind::array(elem)
+            }
+            "push" => {
+                let (elem, value) = (self.element_of(name, &arg(0)), arg(1));
+                if !compatible(&elem, &value) { self.diag(format!("push of {value} onto Array<{elem}>")); }
+                TypeKind::Number
+            }
+            "pop" => self.element_of(name, &arg(0)),
+            "slice" => match arg(0) {
+                TypeKind::String => TypeKind::array(TypeKind::String),
+                other => TypeKind::array(self.element_of(name, &other)),
+            },
+            _ => TypeKind::Unknown,
+        }
+    }
+
     fn visit(&mut self, node: &ASTNode) -> TypeKind {
+        let outer = self.at;
+        if let Some(at) = position(node) { self.at = at; }
+        let ty = self.visit_node(node);
+        self.at = outer;
+        ty
+    }
+
+    fn visit_node(&mut self, node: &ASTNode) -> TypeKind {
         match node {
-            ASTNode::Program(items) => { for it in items { self.visit(it); } TypeKind::Void }
+            ASTNode::Program(items) => {
+                for (i, it) in items.iter().enumerate() {
+                    if let Some(budget) = &mut self.budget {
+                        if let Err(t) = budget.admit(i, items) { self.truncated = Some(t); break; }
+                    }
+                    self.visit(it);
+                }
+                TypeKind::Void
+            }
             ASTNode::Block(items) => { self.begin_scope(); for it in items { self.visit(it); } self.end_scope(); TypeKind::Void }
-            ASTNode::Function { name, params, body, .. } => {
+            ASTNode::Function { name, params, body, line, column } => {
                 self.begin_scope();
                 // Predeclare params
                 for p in params { self.declare(&p.name, TypeKind::Unknown); }
@@ -52,59 +228,182 @@ impl TypeContext {
                     if let ASTNode::Return(expr) = it { ret_type = self.visit(expr); } else { self.visit(it); }
                 }
                 let param_types: Vec<TypeKind> = params.iter().map(|p| self.lookup(&p.name)).collect();
+                for (p, t) in params.iter().zip(&param_types) { self.record(p.line, p.column, &p.name, t); }
+                self.record(*line, *column, name, &TypeKind::function(param_types.clone(), ret_type.clone()));
                 self.functions.insert(name.clone(), (param_types, ret_type.clone()));
+                self.bodies.insert(name.clone(), (params.clone(), body.clone()));
                 self.end_scope();
                 TypeKind::Void
             }
-            ASTNode::VariableDecl { name, value, line, column } => { let t = self.visit(value); self.declare(name, t.clone()); if t==TypeKind::Void { self.diags.push(TypeDiagnostic{ message: format!("Variable '{name}' initialized with void"), line:*line, column:*column }); } TypeKind::Void }
-            ASTNode::Assignment { name, value, line, column } => { let lhs = self.lookup(name); let rhs = self.visit(value); if lhs!=TypeKind::Unknown && lhs!=rhs && rhs!=TypeKind::Unknown { self.diags.push(TypeDiagnostic { message: format!("Type mismatch assigning {rhs:?} to {lhs:?}"), line:*line, column:*column }); } else if lhs==TypeKind::Unknown { self.update_if_unknown(name, &rhs); } TypeKind::Void }
+            ASTNode::EnumDecl { name, .. } => { self.enums.insert(name.clone()); TypeKind::Void }
+            ASTNode::Member { target, .. } => match &**target {
+                ASTNode::Identifier(e) | ASTNode::IdentifierSpanned { name: e, .. } if self.enums.contains(e) => TypeKind::Enum(e.clone()),
+                _ => match self.visit(target) {
+                    // `obj.key` reads a field of an object literal
+                    TypeKind::Map(_, value) => *value,
+                    _ => TypeKind::Unknown,
+                },
+            },
+            ASTNode::Match { subject, arms, .. } => {
+                let st = self.visit(subject);
+                for arm in arms {
+                    if let Some(p) = &arm.pattern { let pt = self.visit(p); self.check_enum_comparison(&st, &pt); }
+                    self.visit(&arm.body);
+                }
+                TypeKind::Void
+            }
+            ASTNode::VariableDecl { name, value, line, column } => { let t = self.visit(value); self.record(*line, *column, name, &t); self.declare(name, t.clone()); if t==TypeKind::Void { self.diags.push(TypeDiagnostic{ message: format!("Variable '{name}' initialized with void"), line:*line, column:*column }); } TypeKind::Void }
+            ASTNode::DestructuringDecl { targets, values, .. } => {
+                let types: Vec<TypeKind> = values.iter().map(|v| self.visit(v)).collect();
+                for (t, ty) in targets.iter().zip(types) { self.declare_pattern(t, ty); }
+                TypeKind::Void
+            }
+            ASTNode::Assignment { name, value, line, column } => { let lhs = self.lookup(name); let rhs = self.visit(value); if lhs!=TypeKind::Unknown && !compatible(&lhs, &rhs) { self.diags.push(TypeDiagnostic { message: format!("Type mismatch assigning {rhs:?} to {lhs:?}"), line:*line, column:*column }); } else if lhs==TypeKind::Unknown { self.update_if_unknown(name, &rhs); } TypeKind::Void }
             ASTNode::Return(expr) => { self.visit(expr); TypeKind::Void }
-            ASTNode::Log(expr) => { self.visit(expr); TypeKind::Void }
-            ASTNode::If { condition, then_branch, else_branch } => { let ct = self.visit(condition); if ct!=TypeKind::Boolean && ct!=TypeKind::Unknown { self.diags.push(TypeDiagnostic { message: "If condition not boolean".into(), line:0, column:0 }); } self.visit(then_branch); if let Some(e)=else_branch { self.visit(e); } TypeKind::Void }
-            ASTNode::While { condition, body } => { let ct=self.visit(condition); if ct!=TypeKind::Boolean && ct!=TypeKind::Unknown { self.diags.push(TypeDiagnostic { message: "While condition not boolean".into(), line:0, column:0 }); } self.visit(body); TypeKind::Void }
-            ASTNode::For { init, condition, increment, body } => { if let Some(i)=init { self.visit(i); } if let Some(c)=condition { let ct=self.visit(c); if ct!=TypeKind::Boolean && ct!=TypeKind::Unknown { self.diags.push(TypeDiagnostic { message: "For condition not boolean".into(), line:0, column:0 }); } } if let Some(inc)=increment { self.visit(inc); } self.visit(body); TypeKind::Void }
+            ASTNode::Log { expr, .. } => { self.visit(expr); TypeKind::Void }
+            ASTNode::If { condition, then_branch, else_branch } => { let ct = self.visit(condition); if ct!=TypeKind::Boolean && ct!=TypeKind::Unknown { self.diag_at(condition, "If condition not boolean".into()); } self.visit(then_branch); if let Some(e)=else_branch { self.visit(e); } TypeKind::Void }
+            ASTNode::While { condition, body } => { let ct=self.visit(condition); if ct!=TypeKind::Boolean && ct!=TypeKind::Unknown { self.diag_at(condition, "While condition not boolean".into()); } self.visit(body); TypeKind::Void }
+            ASTNode::For { init, condition, increment, body } => { if let Some(i)=init { self.visit(i); } if let Some(c)=condition { let ct=self.visit(c); if ct!=TypeKind::Boolean && ct!=TypeKind::Unknown { self.diag_at(c, "For condition not boolean".into()); } } if let Some(inc)=increment { self.visit(inc); } self.visit(body); TypeKind::Void }
+            ASTNode::ForIn { key, var, iterable, body, .. } => {
+                // Whether the value is iterable at all is the semantic analyzer's check (AEO-S008).
+                let (k, v, map) = match self.visit(iterable) {
+                    TypeKind::Array(elem) => (TypeKind::Number, *elem, false),
+                    TypeKind::String => (TypeKind::Number, TypeKind::String, false),
+                    TypeKind::Map(k, v) => (*k, *v, true),
+                    _ => (TypeKind::Unknown, TypeKind::Unknown, false),
+                };
+                self.begin_scope();
+                match key {
+                    Some(key) => { self.declare(key, k); self.declare(var, v); }
+                    // A map on its own yields its keys.
+                    None => self.declare(var, if map { k } else { v }),
+                }
+                self.visit(body);
+                self.end_scope();
+                TypeKind::Void
+            }
+            ASTNode::Break { .. } | ASTNode::Continue { .. } => TypeKind::Void,
             ASTNode::BinaryExpr { op, left, right } => {
                 let lt=self.visit(left); let rt=self.visit(right);
                 use crate::core::token::TokenKind::*;
                 let result = match op {
-                    Plus | Minus | Star | Slash => {
+                    // `+` with a string on either side concatenates (interpolation desugars to it)
+                    Plus if lt==TypeKind::String || rt==TypeKind::String => TypeKind::String,
+                    Plus | Minus | Star | Slash | Percent | StarStar => {
                         if lt==TypeKind::Unknown && rt==TypeKind::Number { return TypeKind::Number; }
                         if rt==TypeKind::Unknown && lt==TypeKind::Number { return TypeKind::Number; }
-                        if lt!=TypeKind::Number || rt!=TypeKind::Number { self.diags.push(TypeDiagnostic { message: "Arithmetic on non-number".into(), line:0, column:0 }); TypeKind::Unknown } else { TypeKind::Number }
+                        if lt!=TypeKind::Number || rt!=TypeKind::Number { self.diags.push(TypeDiagnostic { message: "Arithmetic on non-number".into(), line: self.at.0, column: self.at.1 }); TypeKind::Unknown } else { TypeKind::Number }
                     }
                     DoubleEquals | NotEquals => {
-                        if lt!=rt && lt!=TypeKind::Unknown && rt!=TypeKind::Unknown { self.diags.push(TypeDiagnostic { message: "Equality between different types".into(), line:0, column:0 }); }
+                        if self.check_enum_comparison(&lt, &rt) { return TypeKind::Boolean; }
+                        if lt!=rt && lt!=TypeKind::Unknown && rt!=TypeKind::Unknown { self.diags.push(TypeDiagnostic { message: "Equality between different types".into(), line: self.at.0, column: self.at.1 }); }
                         TypeKind::Boolean
                     }
                     GreaterThan | GreaterEqual | LessThan | LessEqual => {
-                        if lt!=TypeKind::Number || rt!=TypeKind::Number { self.diags.push(TypeDiagnostic { message: "Comparison on non-number".into(), line:0, column:0 }); }
+                        // an unknown side (a parameter, say) may be a number, as for arithmetic
+                        if (lt==TypeKind::Unknown && rt==TypeKind::Number) || (rt==TypeKind::Unknown && lt==TypeKind::Number) { return TypeKind::Boolean; }
+                        if lt!=TypeKind::Number || rt!=TypeKind::Number { self.diags.push(TypeDiagnostic { message: "Comparison on non-number".into(), line: self.at.0, column: self.at.1 }); }
                         TypeKind::Boolean
                     }
                     _ => { if lt!=rt && lt!=TypeKind::Unknown && rt!=TypeKind::Unknown { TypeKind::Unknown } else { lt } }
                 };
                 result
             }
+            ASTNode::UnaryExpr { op: crate::core::token::TokenKind::Bang, expr } => { self.visit(expr); TypeKind::Boolean }
             ASTNode::UnaryExpr { expr, .. } => self.visit(expr),
+            ASTNode::Index { target, index, .. } => {
+                let tt = self.visit(target); let it = self.visit(index);
+                match tt {
+                    TypeKind::Array(elem) => { if it.is_known() && it != TypeKind::Number { self.diag(format!("Array index must be Number, got {it}")); } *elem }
+                    TypeKind::Map(key, value) => { if it.is_known() && key.is_known() && it != *key { self.diag(format!("Map key must be {key}, got {it}")); } *value }
+                    _ => TypeKind::Unknown,
+                }
+            }
             ASTNode::Call { callee, args } => {
-                // Only support direct identifier calls for now
-                let (fname, f_info) = match &**callee { ASTNode::Identifier(n) | ASTNode::IdentifierSpanned { name: n, .. } => {
-                    let info = self.functions.get(n).cloned(); (n.clone(), info)
-                }, _ => (String::new(), None) };
-                for a in args { self.visit(a); }
-                if let Some((param_tys, ret_ty)) = f_info {
-                    // simple arity check
-                    if param_tys.len() != args.len() { self.diags.push(TypeDiagnostic { message: format!("Call arity mismatch for {fname}"), line:0, column:0 }); }
-                    ret_ty
-                } else { TypeKind::Unknown }
+                let arg_tys: Vec<TypeKind> = args.iter().map(|a| self.visit(a)).collect();
+                let name = match &**callee { ASTNode::Identifier(n) | ASTNode::IdentifierSpanned { name: n, .. } => Some(n.as_str()), _ => None };
+                // A variable shadows the function or builtin of the same name.
+                let callee_ty = match name {
+                    Some(n) => match self.lookup_var(n) {
+                        Some(t) => t,
+                        None if self.functions.contains_key(n) => return self.call_function(n, &arg_tys),
+                        None => {
+                            let callback = args.get(1).and_then(|a| match a { ASTNode::Identifier(f) | ASTNode::IdentifierSpanned { name: f, .. } if self.lookup_var(f).is_none() && self.functions.contains_key(f) => Some(f.as_str()), _ => None });
+                            return self.call_builtin(n, &arg_tys, callback);
+                        }
+                    },
+                    None => self.visit(callee),
+                };
+                match callee_ty {
+                    TypeKind::Function(params, ret) => { if params.len() != arg_tys.len() { self.diag(format!("Call arity mismatch for {}", name.unwrap_or("function value"))); } *ret }
+                    TypeKind::Unknown => TypeKind::Unknown,
+                    other => { self.diag(match name { Some(n) => format!("Call of non-function value '{n}' of type {other}"), None => format!("Call of non-function value of type {other}") }); TypeKind::Unknown }
+                }
+            }
+            ASTNode::ArrayLiteral { items, .. } => {
+                let types: Vec<TypeKind> = items.iter().map(|it| self.visit(it)).collect();
+                TypeKind::array(common_type(types))
+            }
+            // an object literal is a map from its (string) field names
+            ASTNode::ObjectLiteral { fields, .. } => {
+                let types: Vec<TypeKind> = fields.iter().map(|(_, v)| self.visit(v)).collect();
+                TypeKind::map(TypeKind::String, common_type(types))
             }
             ASTNode::Identifier(name) => self.lookup(name),
             ASTNode::IdentifierSpanned { name, .. } => self.lookup(name),
             ASTNode::NumberLiteral(_) => TypeKind::Number,
             ASTNode::StringLiteral(_) => TypeKind::String,
             ASTNode::BooleanLiteral(_) => TypeKind::Boolean,
-            ASTNode::QuantumOp { .. } => TypeKind::Void,
-            ASTNode::HieroglyphicOp { .. } => TypeKind::Void,
+            ASTNode::QubitDecl { .. } | ASTNode::QregDecl { .. } | ASTNode::QuantumOp { .. } => TypeKind::Void,
+            ASTNode::HieroglyphicOp { .. } | ASTNode::Import { .. } => TypeKind::Void,
             ASTNode::Error(_) => TypeKind::Unknown,
         }
     }
 }
+
+/// The element type of a literal holding values of `types`: their shared type, else `Unknown`
+/// (also for an empty literal, whose elements are not known yet).
+fn common_type(types: Vec<TypeKind>) -> TypeKind {
+    let mut types = types.into_iter();
+    let first = types.next().unwrap_or(TypeKind::Unknown);
+    if types.all(|t| t == first) { first } else { TypeKind::Unknown }
+}
+
+/// Whether a value of type `b` fits where `a` is expected; `Unknown` fits anything, recursively.
+fn compatible(a: &TypeKind, b: &TypeKind) -> bool {
+    match (a, b) {
+        (TypeKind::Unknown, _) | (_, TypeKind::Unknown) => true,
+        (TypeKind::Array(x), TypeKind::Array(y)) => compatible(x, y),
+        (TypeKind::Map(k1, v1), TypeKind::Map(k2, v2)) => compatible(k1, k2) && compatible(v1, v2),
+        (TypeKind::Function(p1, r1), TypeKind::Function(p2, r2)) => p1.len() == p2.len() && p1.iter().zip(p2).all(|(x, y)| compatible(x, y)) && compatible(r1, r2),
+        _ => a == b,
+    }
+}
+
+/// Where `node` starts, when it has a position: its own, or for an expression without one
+/// its leftmost identifier's.
+fn position(node: &ASTNode) -> Option<(usize, usize)> {
+    let at = match node {
+        ASTNode::Function { line, column, .. }
+        | ASTNode::VariableDecl { line, column, .. }
+        | ASTNode::DestructuringDecl { line, column, .. }
+        | ASTNode::EnumDecl { line, column, .. }
+        | ASTNode::Log { line, column, .. }
+        | ASTNode::ForIn { line, column, .. }
+        | ASTNode::Match { line, column, .. }
+        | ASTNode::Assignment { line, column, .. }
+        | ASTNode::Index { line, column, .. }
+        | ASTNode::Member { line, column, .. }
+        | ASTNode::ArrayLiteral { line, column, .. }
+        | ASTNode::ObjectLiteral { line, column, .. }
+        | ASTNode::IdentifierSpanned { line, column, .. }
+        | ASTNode::QubitDecl { line, column, .. }
+        | ASTNode::QregDecl { line, column, .. }
+        | ASTNode::QuantumOp { line, column, .. } => (*line, *column),
+        ASTNode::BinaryExpr { left, right, .. } => return position(left).or_else(|| position(right)),
+        ASTNode::UnaryExpr { expr, .. } => return position(expr),
+        ASTNode::Call { callee, .. } => return position(callee),
+        _ => return None,
+    };
+    (at.0 > 0).then_some(at)
+}
@@ -0,0 +1,124 @@
+//! Opt-in, local-only usage statistics: how often each subcommand ran, how it ended and
+//! roughly how long it took. Nothing here touches the network; the counters live in
+//! `<config dir>/aeonmi/usage_stats.json` (or `$AEONMI_CONFIG_DIR/usage_stats.json`) and
+//! are only ever read by `aeonmi stats show`.
+//!
+//! Recording is off unless the user config says
+//!
+//! ```toml
+//! [stats]
+//! enabled = true
+//! ```
+//!
+//! (`aeonmi config set stats.enabled true`). [`begin`] and [`finish`] bracket one CLI
+//! invocation; [`record`] swallows every error so counting can never slow down or fail
+//! the command being counted. Commands that leave through `std::process::exit` are not
+//! counted. Two processes finishing at the same instant may lose one increment.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Duration buckets, shortest first: (label, exclusive upper bound).
+pub const BUCKETS: [(&str, Duration); 4] = [
+    ("<100ms", Duration::from_millis(100)),
+    ("<1s", Duration::from_secs(1)),
+    ("<10s", Duration::from_secs(10)),
+    (">=10s", Duration::MAX),
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CommandStats {
+    pub ok: u64,
+    pub failed: u64,
+    /// Invocations per [`BUCKETS`] label.
+    pub durations: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageStats {
+    /// Unix seconds of the first invocation counted since the last reset.
+    pub since: Option<u64>,
+    /// Keyed by subcommand path, e.g. `run` or `vm eval`.
+    pub commands: BTreeMap<String, CommandStats>,
+}
+
+pub fn stats_path() -> PathBuf {
+    if let Ok(base) = std::env::var("AEONMI_CONFIG_DIR") { return PathBuf::from(base).join("usage_stats.json"); }
+    dirs_next::config_dir().unwrap_or(std::env::temp_dir()).join("aeonmi").join("usage_stats.json")
+}
+
+pub fn bucket(elapsed: Duration) -> &'static str {
+    BUCKETS.iter().find(|(_, bound)| elapsed < *bound).map_or(">=10s", |(label, _)| label)
+}
+
+/// The counters in `path`; a missing or unreadable file counts as empty.
+pub fn load(path: &Path) -> UsageStats {
+    fs::read_to_string(path).ok().and_then(|txt| serde_json::from_str(&txt).ok()).unwrap_or_default()
+}
+
+/// Count one invocation of `command` in `path`.
+pub fn add(path: &Path, command: &str, ok: bool, elapsed: Duration) -> std::io::Result<()> {
+    let mut stats = load(path);
+    stats.since.get_or_insert_with(super::ai_usage::now_secs);
+    let entry = stats.commands.entry(command.to_string()).or_default();
+    if ok { entry.ok += 1 } else { entry.failed += 1 }
+    *entry.durations.entry(bucket(elapsed).to_string()).or_default() += 1;
+    if let Some(dir) = path.parent() { fs::create_dir_all(dir)?; }
+    // Whole-file replace, so a reader never sees half a file.
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_vec_pretty(&stats).map_err(std::io::Error::other)?)?;
+    fs::rename(&tmp, path)
+}
+
+/// Count one invocation in the default file when `enabled`, ignoring failures.
+pub fn record(enabled: bool, command: &str, ok: bool, elapsed: Duration) {
+    if !enabled { return; }
+    if let Err(e) = add(&stats_path(), command, ok, elapsed) {
+        if crate::core::debug::is_enabled() { eprintln!("usage stats write failed: {e}"); }
+    }
+}
+
+/// Delete the counters; `false` when there were none.
+pub fn reset(path: &Path) -> std::io::Result<bool> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+struct Pending {
+    command: String,
+    started: Instant,
+}
+
+static PENDING: Mutex<Option<Pending>> = Mutex::new(None);
+
+/// Start timing this process's subcommand; a no-op unless `enabled`.
+pub fn begin(command: &str, enabled: bool) {
+    if enabled {
+        *PENDING.lock().unwrap_or_else(|e| e.into_inner()) = Some(Pending { command: command.to_string(), started: Instant::now() });
+    }
+}
+
+/// Count the subcommand passed to [`begin`], if any.
+pub fn finish(ok: bool) {
+    if let Some(p) = PENDING.lock().unwrap_or_else(|e| e.into_inner()).take() {
+        record(true, &p.command, ok, p.started.elapsed());
+    }
+}
+
+pub fn render(stats: &UsageStats) -> String {
+    let mut out = format!("{:<24} {:>6} {:>6}", "command", "ok", "failed");
+    for (label, _) in BUCKETS { out.push_str(&format!(" {:>7}", label)); }
+    out.push('\n');
+    for (name, c) in &stats.commands {
+        out.push_str(&format!("{:<24} {:>6} {:>6}", name, c.ok, c.failed));
+        for (label, _) in BUCKETS { out.push_str(&format!(" {:>7}", c.durations.get(label).copied().unwrap_or(0))); }
+        out.push('\n');
+    }
+    out
+}
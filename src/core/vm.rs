@@ -1,13 +1,28 @@
 #![cfg_attr(test, allow(dead_code, unused_variables))]
 //! Aeonmi VM: tree-walk interpreter over IR.
 //! Supports: literals, arrays/objects, let/assign, if/while/for, fn calls/returns,
-//! basic binary/unary ops, and built-ins: print, log, time_ms, rand, len.
+//! basic binary/unary ops, and built-ins: print, log, time_ms, rand, env, len.
+//! `read_file`/`write_file` only reach directories mounted with [`Interpreter::mount`].
+//! Quantum built-ins (superpose, entangle, measure, dod, run_shots, counts) act on
+//! a `QuantumRegister` kept beside the classical env; see `vm_quantum`.
+//! Nondeterministic results (rand, time_ms, measure, env) can be recorded and
+//! replayed through an optional `vm_trace::Trace`.
+//! `minimize` / `grid_search` drive Titan's optimizers with an Aeonmi function as the objective;
+//! `simulate_ode` / `logistic_map` return trajectories from Titan's dynamical-systems module.
+//! Under `Limits::max_memory_bytes` the interpreter keeps an approximate count of the
+//! bytes its variables hold, adjusted as they are bound, reassigned and go out of scope;
+//! without that limit none of it runs.
 
 use crate::core::ir::*;
-use std::collections::HashMap;
+use crate::core::vm_display;
+use crate::core::vm_quantum::{QuantumRegister, MAX_QUBITS};
+use crate::core::vm_trace::{self, Payload, Trace};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, Read, Write};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Once;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::{Arc, Mutex, Once};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Clone)]
 pub enum Value {
@@ -19,6 +34,16 @@ pub enum Value {
     Object(HashMap<String, Value>),
     Function(Function), // user-defined
     Builtin(Builtin),
+    /// An `enum` declaration; `E.Variant` reads its variants.
+    Enum(Arc<EnumDef>),
+    /// A variant, tagged with its enum and its index in declaration order.
+    Variant(Arc<EnumDef>, usize),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct EnumDef {
+    pub name: String,
+    pub variants: Vec<String>,
 }
 
 #[derive(Clone)]
@@ -37,11 +62,13 @@ impl std::fmt::Debug for Function {
     }
 }
 
+type BuiltinFn = fn(&mut Interpreter, Vec<Value>) -> Result<Value, RuntimeError>;
+
 #[derive(Clone)]
 pub struct Builtin {
     pub name: &'static str,
     pub arity: usize, // use usize::MAX for variadic
-    pub f: fn(&mut Interpreter, Vec<Value>) -> Result<Value, RuntimeError>,
+    pub f: BuiltinFn,
 }
 
 impl std::fmt::Debug for Builtin {
@@ -78,13 +105,22 @@ impl Env {
     }
 
     pub fn assign(&mut self, k: &str, v: Value) -> bool {
+        self.swap(k, v).is_some()
+    }
+
+    /// Assign `v` to the nearest `k`, returning the value it replaced (`None` if undefined).
+    pub fn swap(&mut self, k: &str, v: Value) -> Option<Value> {
         for frame in self.frames.iter_mut().rev() {
-            if frame.contains_key(k) {
-                frame.insert(k.to_string(), v);
-                return true;
+            if let Some(slot) = frame.get_mut(k) {
+                return Some(std::mem::replace(slot, v));
             }
         }
-        false
+        None
+    }
+
+    /// The nearest `k`, for in-place updates (`push` / `pop`).
+    pub fn get_mut(&mut self, k: &str) -> Option<&mut Value> {
+        self.frames.iter_mut().rev().find_map(|frame| frame.get_mut(k))
     }
 
     pub fn get(&self, k: &str) -> Option<Value> {
@@ -97,14 +133,116 @@ impl Env {
     }
 }
 
-#[derive(Debug)]
+/// One `print`/`log` invocation. `line`/`column` are 0 when the call site is unknown.
+#[derive(Debug, Clone)]
+pub struct LogEvent {
+    pub text: String,
+    pub values: Vec<Value>,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Cloneable in-memory sink; hand one to `set_output` and read it back afterwards.
+#[derive(Clone, Default)]
+pub struct OutputBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl OutputBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+impl Write for OutputBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+type LogHook = Box<dyn FnMut(&LogEvent) + Send>;
+
+/// Resource ceilings for untrusted programs; everything is unlimited by default.
+/// Crossing one is a runtime error, so the program stops where it is.
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    /// Checked at every loop iteration and function call.
+    pub deadline: Option<Instant>,
+    /// Deepest nesting of user function calls.
+    pub max_call_depth: Option<usize>,
+    /// Longest string `+` may build, in bytes.
+    pub max_string_bytes: Option<usize>,
+    /// Ceiling on the approximate bytes held by live values (see [`value_size`]).
+    pub max_memory_bytes: Option<usize>,
+    /// `env()` fails instead of reading the host environment.
+    pub deny_env: bool,
+}
+
 pub struct Interpreter {
     pub env: Env,
+    output: Box<dyn Write + Send>,
+    /// Source for `read_line` / `read_all` (default: stdin).
+    input: Box<dyn BufRead + Send>,
+    on_log: Option<LogHook>,
+    log_span: (usize, usize),
+    /// Quantum state lives outside `env`: shots reset it, classical values survive.
+    quantum: QuantumRegister,
+    /// Histogram (bitstring -> count) from the most recent `run_shots`.
+    shot_counts: BTreeMap<String, usize>,
+    /// Span of the innermost statement with a known position; tags trace entries.
+    pos: (usize, usize),
+    trace: Option<Trace>,
+    /// Top-level functions, bound before anything runs. Closures snapshot `env` when
+    /// they are defined, so names missing there fall back to this table; that is what
+    /// lets a function call one declared after it (and mutual recursion) work.
+    module_fns: HashMap<String, Value>,
+    limits: Limits,
+    call_depth: usize,
+    /// Approximate bytes held by bound variables; only maintained under a memory limit.
+    live_bytes: usize,
+    /// Canonical directories `read_file`/`write_file` may touch; none by default.
+    mounts: Vec<PathBuf>,
+}
+
+impl std::fmt::Debug for Interpreter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Interpreter").field("env", &self.env).finish_non_exhaustive()
+    }
 }
 
 #[derive(Debug)]
 pub struct RuntimeError {
     pub message: String,
+    pub kind: RuntimeErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RuntimeErrorKind {
+    /// Any failure described only by its message.
+    #[default]
+    Error,
+    /// Live values would have exceeded `Limits::max_memory_bytes`; both sizes in bytes.
+    OutOfMemory { used: usize, limit: usize },
+    /// A destructuring `let` met an array (or string) with a different number of elements.
+    ArityMismatch { expected: usize, actual: usize },
+    /// The objective of `minimize` / `grid_search` (called on `line`, 0 when unknown)
+    /// returned something other than a number on its `evaluation`-th call (1-based).
+    ObjectiveNotNumber { line: usize, evaluation: usize },
+    /// `pop` on an empty array; the position of the call (0 = unknown).
+    EmptyArray { line: usize, column: usize },
+    /// `/` or `%` with a zero right operand; the position of the enclosing statement.
+    DivisionByZero { line: usize, column: usize },
+}
+
+impl RuntimeError {
+    pub fn new(message: impl Into<String>) -> Self {
+        RuntimeError { message: message.into(), kind: RuntimeErrorKind::Error }
+    }
 }
 
 impl Default for Interpreter {
@@ -149,6 +287,14 @@ impl Interpreter {
                 f: builtin_rand,
             }),
         );
+        env.define(
+            "env".into(),
+            Value::Builtin(Builtin {
+                name: "env",
+                arity: 1,
+                f: builtin_env,
+            }),
+        );
         env.define(
             "len".into(),
             Value::Builtin(Builtin {
@@ -157,18 +303,251 @@ impl Interpreter {
                 f: builtin_len,
             }),
         );
-        Self { env }
+        env.define(
+            "read_line".into(),
+            Value::Builtin(Builtin {
+                name: "read_line",
+                arity: 0,
+                f: builtin_read_line,
+            }),
+        );
+        env.define(
+            "read_all".into(),
+            Value::Builtin(Builtin {
+                name: "read_all",
+                arity: 0,
+                f: builtin_read_all,
+            }),
+        );
+        env.define(
+            "__iter".into(),
+            Value::Builtin(Builtin {
+                name: "__iter",
+                arity: 1,
+                f: builtin_iter,
+            }),
+        );
+        env.define(
+            "__iter_keys".into(),
+            Value::Builtin(Builtin {
+                name: "__iter_keys",
+                arity: 1,
+                f: builtin_iter_keys,
+            }),
+        );
+        env.define(
+            "__enum".into(),
+            Value::Builtin(Builtin {
+                name: "__enum",
+                arity: usize::MAX,
+                f: builtin_enum,
+            }),
+        );
+        let quantum: [(&'static str, usize, BuiltinFn); 8] = [
+            ("__qubit", 1, builtin_qubit),
+            ("__qreg", 2, builtin_qreg),
+            ("superpose", 1, builtin_superpose),
+            ("entangle", 2, builtin_entangle),
+            ("measure", 1, builtin_measure),
+            ("dod", 1, builtin_dod),
+            ("run_shots", 2, builtin_run_shots),
+            ("counts", 0, builtin_counts),
+        ];
+        for (name, arity, f) in quantum {
+            env.define(name.into(), Value::Builtin(Builtin { name, arity, f }));
+        }
+        let optimize: [(&'static str, usize, BuiltinFn); 4] = [
+            ("minimize", usize::MAX, builtin_minimize),
+            ("grid_search", usize::MAX, builtin_grid_search),
+            ("simulate_ode", 4, builtin_simulate_ode),
+            ("logistic_map", 3, builtin_logistic_map),
+        ];
+        for (name, arity, f) in optimize {
+            env.define(name.into(), Value::Builtin(Builtin { name, arity, f }));
+        }
+        let symbolic: [(&'static str, usize, BuiltinFn); 2] = [("derivative", 2, builtin_derivative), ("simplify", 1, builtin_simplify)];
+        for (name, arity, f) in symbolic {
+            env.define(name.into(), Value::Builtin(Builtin { name, arity, f }));
+        }
+        // `push` / `pop` lower to `__push` / `__pop`, which update the variable named by their first argument
+        let arrays: [(&'static str, usize, BuiltinFn); 3] = [("__push", 2, builtin_push), ("__pop", 3, builtin_pop), ("slice", 3, builtin_slice)];
+        for (name, arity, f) in arrays {
+            env.define(name.into(), Value::Builtin(Builtin { name, arity, f }));
+        }
+        let files: [(&'static str, usize, BuiltinFn); 2] = [("read_file", 1, builtin_read_file), ("write_file", 2, builtin_write_file)];
+        for (name, arity, f) in files {
+            env.define(name.into(), Value::Builtin(Builtin { name, arity, f }));
+        }
+        Self {
+            env,
+            output: Box::new(std::io::stdout()),
+            input: Box::new(std::io::BufReader::new(std::io::stdin())),
+            on_log: None,
+            log_span: (0, 0),
+            quantum: QuantumRegister::new(),
+            shot_counts: BTreeMap::new(),
+            pos: (0, 0),
+            trace: None,
+            module_fns: HashMap::new(),
+            limits: Limits::default(),
+            call_depth: 0,
+            live_bytes: 0,
+            mounts: Vec::new(),
+        }
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    fn check_deadline(&self) -> Result<(), RuntimeError> {
+        match self.limits.deadline {
+            Some(d) if Instant::now() >= d => Err(err("time limit exceeded".into())),
+            _ => Ok(()),
+        }
+    }
+
+    /// Approximate bytes held by variables, as counted for `Limits::max_memory_bytes`
+    /// (always 0 without that limit).
+    pub fn live_bytes(&self) -> usize {
+        self.live_bytes
+    }
+
+    /// Fail if `extra` more bytes on top of the live ones would cross the memory limit.
+    fn check_memory(&self, extra: usize) -> Result<(), RuntimeError> {
+        match self.limits.max_memory_bytes {
+            Some(limit) if self.live_bytes + extra > limit => {
+                let used = self.live_bytes + extra;
+                Err(RuntimeError {
+                    message: format!("out of memory: {} bytes of live values exceed the {} byte limit", used, limit),
+                    kind: RuntimeErrorKind::OutOfMemory { used, limit },
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// `define` in the innermost scope, accounting for the value (and for any it shadows there).
+    fn bind(&mut self, name: String, v: Value) -> Result<(), RuntimeError> {
+        if self.limits.max_memory_bytes.is_none() {
+            self.env.define(name, v);
+            return Ok(());
+        }
+        let added = value_size(&v);
+        let frame = self.env.frames.last_mut().unwrap();
+        let removed = frame.insert(name, v).as_ref().map_or(0, value_size);
+        self.live_bytes = self.live_bytes.saturating_sub(removed);
+        self.check_memory(added)?;
+        self.live_bytes += added;
+        Ok(())
+    }
+
+    /// Bind each name in `pattern` to the matching element of `v`; strings split into characters.
+    fn destructure(&mut self, pattern: &Pattern, v: Value) -> Result<(), RuntimeError> {
+        let patterns = match pattern {
+            Pattern::Name(name) => return self.bind(name.clone(), v),
+            Pattern::Array(patterns) => patterns,
+        };
+        let items = match v {
+            Value::Array(items) => items,
+            Value::String(s) => s.chars().map(|c| Value::String(c.to_string())).collect(),
+            other => return Err(err(format!("cannot destructure {}", shown(&other)))),
+        };
+        if items.len() != patterns.len() {
+            let (expected, actual) = (patterns.len(), items.len());
+            return Err(RuntimeError {
+                message: format!("destructuring expected {} values, got {}", expected, actual),
+                kind: RuntimeErrorKind::ArityMismatch { expected, actual },
+            });
+        }
+        patterns.iter().zip(items).try_for_each(|(p, v)| self.destructure(p, v))
+    }
+
+    /// `assign` with the same accounting as `bind`; false when `name` is undefined.
+    fn rebind(&mut self, name: &str, v: Value) -> Result<bool, RuntimeError> {
+        if self.limits.max_memory_bytes.is_none() {
+            return Ok(self.env.assign(name, v));
+        }
+        let added = value_size(&v);
+        let Some(old) = self.env.swap(name, v) else { return Ok(false) };
+        self.live_bytes = self.live_bytes.saturating_sub(value_size(&old));
+        self.check_memory(added)?;
+        self.live_bytes += added;
+        Ok(true)
+    }
+
+    /// Leave the innermost scope, releasing what its variables held.
+    fn pop_scope(&mut self) {
+        if self.limits.max_memory_bytes.is_none() {
+            self.env.pop();
+        } else if let Some(frame) = self.env.frames.pop() {
+            let freed: usize = frame.values().map(value_size).sum();
+            self.live_bytes = self.live_bytes.saturating_sub(freed);
+        }
+    }
+
+    /// Record nondeterministic builtin results into, or replay them from, `trace`.
+    pub fn set_trace(&mut self, trace: Trace) {
+        self.trace = Some(trace);
+    }
+
+    pub fn take_trace(&mut self) -> Option<Trace> {
+        self.trace.take()
+    }
+
+    /// Pass one nondeterministic result through the trace, if any.
+    fn traced(&mut self, kind: vm_trace::Kind, key: &str, live: impl FnOnce() -> Payload) -> Result<Payload, RuntimeError> {
+        let pos = self.pos;
+        match self.trace.as_mut() {
+            Some(t) => t.step(kind, key, pos, live).map_err(|d| err(d.to_string())),
+            None => Ok(live()),
+        }
+    }
+
+    /// Redirect `print`/`log` output (default: stdout).
+    pub fn set_output(&mut self, out: Box<dyn Write + Send>) {
+        self.output = out;
+    }
+
+    /// Feed `read_line` / `read_all` from `input` instead of stdin.
+    pub fn set_input(&mut self, input: Box<dyn BufRead + Send>) {
+        self.input = input;
+    }
+
+    /// Receive a structured `LogEvent` for every `print`/`log`, in addition to text output.
+    pub fn on_log(&mut self, f: impl FnMut(&LogEvent) + Send + 'static) {
+        self.on_log = Some(Box::new(f));
+    }
+
+    fn emit_log(&mut self, values: Vec<Value>) -> Result<(), RuntimeError> {
+        let text = values.iter().map(|v| v.display_pretty(vm_display::LOG_DEPTH)).collect::<Vec<_>>().join(" ");
+        writeln!(self.output, "{}", text).map_err(|e| err(format!("output error: {}", e)))?;
+        // flush per line so output streams through shell pipelines as it is produced
+        self.output.flush().map_err(|e| err(format!("output error: {}", e)))?;
+        if let Some(cb) = self.on_log.as_mut() {
+            let (line, column) = self.log_span;
+            cb(&LogEvent { text, values, line, column });
+        }
+        Ok(())
     }
 
     pub fn run_module(&mut self, m: &Module) -> Result<(), RuntimeError> {
-        debug_log!("vm: run_module decls={} ", m.decls.len());
-        // Load top-level decls
+        self.run_main(m).map(|_| ())
+    }
+
+    /// [`Interpreter::run_module`], returning what `main` returned (`Null` without a `main`).
+    pub fn run_main(&mut self, m: &Module) -> Result<Value, RuntimeError> {
+        debug_log!("run_module decls={}", m.decls.len());
+        // Pass 1: bind every top-level function so bodies (and the initializers below)
+        // can reference any of them, whatever their order.
+        self.bind_module_fns(m);
+        // Pass 2: evaluate top-level consts/lets in order.
         for d in &m.decls {
-            debug_log!("vm: processing decl: {:?}", d);
+            trace_log!("processing decl: {:?}", d);
             match d {
                 Decl::Const(c) => {
                     let v = self.eval_expr(&c.value)?;
-                    self.env.define(c.name.clone(), v);
+                    self.bind(c.name.clone(), v)?;
                 }
                 Decl::Let(l) => {
                     let v = if let Some(e) = &l.value {
@@ -176,33 +555,142 @@ impl Interpreter {
                     } else {
                         Value::Null
                     };
-                    self.env.define(l.name.clone(), v);
-                }
-                Decl::Fn(f) => {
-                    debug_log!("vm: load fn '{}'", f.name);
-                    let func = Value::Function(Function {
-                        params: f.params.clone(),
-                        body: f.body.clone(),
-                        env: self.env.clone(),
-                    });
-                    self.env.define(f.name.clone(), func);
+                    self.bind(l.name.clone(), v)?;
                 }
+                Decl::Fn(_) => {}
             }
         }
+        // Re-bind so the closures also capture the module's consts and lets. Functions are
+        // only added to `env` afterwards: a snapshot holding the pass-1 closures would make
+        // calls between functions miss those consts.
+        self.bind_module_fns(m);
+        for (name, func) in &self.module_fns {
+            self.env.define(name.clone(), func.clone());
+        }
         // If there is a `main` fn with zero params, run it.
-        if let Some(Value::Function(_)) = self.env.get("main") {
-            debug_log!("vm: calling main()");
-            let _ = self.call_ident("main", vec![])?;
+        if let Some(Value::Function(_)) = self.lookup("main") {
+            debug_log!("calling main()");
+            self.call_ident("main", vec![])
         } else {
-            debug_log!("vm: no main() found");
+            debug_log!("no main() found");
+            Ok(Value::Null)
+        }
+    }
+
+    /// Run `m`'s declarations, then `top` statement by statement in the global scope, so
+    /// everything they bind is still there on the next call (an `aeonmi vm` session).
+    /// Unlike [`Interpreter::run_main`], no `main` is called. Returns the value of `top`'s
+    /// last statement when it is an expression, else `null`.
+    pub fn run_session(&mut self, m: &Module, top: &Block) -> Result<Value, RuntimeError> {
+        self.bind_module_fns(m);
+        for d in &m.decls {
+            match d {
+                Decl::Const(c) => {
+                    let v = self.eval_expr(&c.value)?;
+                    self.bind(c.name.clone(), v)?;
+                }
+                Decl::Let(l) => {
+                    let v = l.value.as_ref().map_or(Ok(Value::Null), |e| self.eval_expr(e))?;
+                    self.bind(l.name.clone(), v)?;
+                }
+                Decl::Fn(_) => {}
+            }
+        }
+        self.refresh_closures();
+        let mut last = Value::Null;
+        for (i, s) in top.stmts.iter().enumerate() {
+            if let (Stmt::Expr(e), true) = (s, i + 1 == top.stmts.len()) {
+                last = self.eval_expr(e)?;
+                self.refresh_closures();
+                continue;
+            }
+            match self.exec_stmt(s) {
+                ControlFlow::Ok => {}
+                ControlFlow::Err(e) => return Err(e),
+                _ => return Err(err("`return`/`break`/`continue` outside of a function".into())),
+            }
+            self.refresh_closures();
+        }
+        Ok(last)
+    }
+
+    /// Point every top-level function's closure at the current globals, so functions see
+    /// variables defined after them. Functions themselves are left out of the captured
+    /// scope: [`Interpreter::lookup`] finds them in `module_fns`.
+    fn refresh_closures(&mut self) {
+        let mut globals = self.env.frames[0].clone();
+        globals.retain(|name, _| !self.module_fns.contains_key(name));
+        let base = Env { frames: vec![globals] };
+        for f in self.module_fns.values_mut() {
+            if let Value::Function(f) = f {
+                f.env = base.clone();
+            }
+        }
+    }
+
+    /// Global variables and top-level functions, without the builtins' own bindings, sorted by name.
+    pub fn globals(&self) -> Vec<(String, Value)> {
+        let mut out: BTreeMap<String, Value> = self.env.frames[0]
+            .iter()
+            .filter(|(k, v)| !matches!(v, Value::Builtin(b) if b.name == k.as_str()))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        for (name, f) in &self.module_fns {
+            out.entry(name.clone()).or_insert_with(|| f.clone());
+        }
+        out.into_iter().collect()
+    }
+
+    /// Bind `globals` (as returned by [`Interpreter::globals`]) in the global scope;
+    /// functions become top-level functions closing over the restored variables.
+    pub fn restore_globals(&mut self, globals: Vec<(String, Value)>) -> Result<(), RuntimeError> {
+        for (name, v) in globals {
+            match v {
+                Value::Function(_) => {
+                    self.module_fns.insert(name, v);
+                }
+                v => self.bind(name, v)?,
+            }
+        }
+        self.refresh_closures();
+        Ok(())
+    }
+
+    /// Let `read_file`/`write_file` reach files under `dir`.
+    pub fn mount(&mut self, dir: &Path) -> std::io::Result<()> {
+        let dir = dir.canonicalize()?;
+        if !self.mounts.contains(&dir) {
+            self.mounts.push(dir);
         }
         Ok(())
     }
 
+    pub fn mounts(&self) -> &[PathBuf] {
+        &self.mounts
+    }
+
+    fn bind_module_fns(&mut self, m: &Module) {
+        for d in &m.decls {
+            if let Decl::Fn(f) = d {
+                debug_log!("load fn '{}'", f.name);
+                let func = Value::Function(Function {
+                    params: f.params.clone(),
+                    body: f.body.clone(),
+                    env: self.env.clone(),
+                });
+                self.module_fns.insert(f.name.clone(), func);
+            }
+        }
+    }
+
+    /// Resolve `name` in the current scope chain, then among top-level functions.
+    fn lookup(&self, name: &str) -> Option<Value> {
+        self.env.get(name).or_else(|| self.module_fns.get(name).cloned())
+    }
+
     fn call_ident(&mut self, name: &str, args: Vec<Value>) -> Result<Value, RuntimeError> {
         let callee = self
-            .env
-            .get(name)
+            .lookup(name)
             .ok_or_else(|| err(format!("Undefined function `{}`", name)))?;
         self.call_value(callee, args)
     }
@@ -228,25 +716,34 @@ impl Interpreter {
                         args.len()
                     )));
                 }
+                self.check_deadline()?;
+                if self.limits.max_call_depth.is_some_and(|max| self.call_depth >= max) {
+                    return Err(err(format!("call depth limit exceeded ({})", self.call_depth)));
+                }
                 // New scope with closure base
                 let saved = self.env.clone();
                 self.env = fun.env.clone();
                 self.env.push();
-                for (p, v) in fun.params.iter().zip(args.into_iter()) {
-                    self.env.define(p.clone(), v);
-                }
+                let bound = fun.params.iter().zip(args).try_for_each(|(p, v)| self.bind(p.clone(), v));
                 // Execute - don't create another scope in exec_block for function bodies
-                let ret = self.exec_function_block(&fun.body);
+                self.call_depth += 1;
+                let ret = match bound {
+                    Ok(()) => self.exec_function_block(&fun.body),
+                    Err(e) => ControlFlow::Err(e),
+                };
+                self.call_depth -= 1;
+                self.pop_scope();
                 // Restore
                 let out = match ret {
                     ControlFlow::Ok => Ok(Value::Null),
+                    ControlFlow::Break | ControlFlow::Continue => Err(err("`break`/`continue` outside of a loop".into())),
                     ControlFlow::Return(v) => Ok(v.unwrap_or(Value::Null)),
                     ControlFlow::Err(e) => Err(e),
                 };
                 self.env = saved;
                 out
             }
-            other => Err(err(format!("callee is not callable: {:?}", other))),
+            other => Err(err(format!("callee is not callable: {}", shown(&other)))),
         }
     }
 
@@ -256,17 +753,17 @@ impl Interpreter {
             match self.exec_stmt(s) {
                 ControlFlow::Ok => {}
                 other => {
-                    self.env.pop();
+                    self.pop_scope();
                     return other;
                 }
             }
         }
-        self.env.pop();
+        self.pop_scope();
         ControlFlow::Ok
     }
 
     fn exec_function_block(&mut self, b: &Block) -> ControlFlow {
-        debug_log!("vm: exec_function_block");
+        trace_log!("exec_function_block");
         // Don't create an additional scope - function call already created one
         for s in &b.stmts {
             match self.exec_stmt(s) {
@@ -288,6 +785,28 @@ impl Interpreter {
                 }
                 ControlFlow::Ok
             }
+            Log { args, line, column } => {
+                self.pos = (*line, *column);
+                let mut vals = Vec::with_capacity(args.len());
+                for a in args {
+                    match self.eval_expr(a) {
+                        Ok(v) => vals.push(v),
+                        Err(e) => return ControlFlow::Err(e),
+                    }
+                }
+                let callee = match self.env.get("log") {
+                    Some(v) => v,
+                    None => return ControlFlow::Err(err("Undefined function `log`".into())),
+                };
+                // Span is set only after args are evaluated so nested logs don't clobber it.
+                let prev = std::mem::replace(&mut self.log_span, (*line, *column));
+                let res = self.call_value(callee, vals);
+                self.log_span = prev;
+                match res {
+                    Ok(_) => ControlFlow::Ok,
+                    Err(e) => ControlFlow::Err(e),
+                }
+            }
             Return(None) => ControlFlow::Return(None),
             Return(Some(e)) => {
                 let v = match self.eval_expr(e) {
@@ -313,8 +832,14 @@ impl Interpreter {
                     ControlFlow::Ok
                 }
             }
+            Block(b) => self.exec_block(b),
+            Break => ControlFlow::Break,
+            Continue => ControlFlow::Continue,
             While { cond, body } => {
                 loop {
+                    if let Err(e) = self.check_deadline() {
+                        return ControlFlow::Err(e);
+                    }
                     let c = match self.eval_expr(cond) {
                         Ok(v) => self.truthy(&v),
                         Err(e) => return ControlFlow::Err(e),
@@ -323,7 +848,8 @@ impl Interpreter {
                         break;
                     }
                     match self.exec_block(body) {
-                        ControlFlow::Ok => {}
+                        ControlFlow::Ok | ControlFlow::Continue => {}
+                        ControlFlow::Break => break,
                         other => return other,
                     }
                 }
@@ -341,6 +867,9 @@ impl Interpreter {
                     }
                 }
                 loop {
+                    if let Err(e) = self.check_deadline() {
+                        return ControlFlow::Err(e);
+                    }
                     if let Some(c) = cond {
                         let ok = match self.eval_expr(c) {
                             Ok(v) => self.truthy(&v),
@@ -351,18 +880,20 @@ impl Interpreter {
                         }
                     }
                     match self.exec_block(body) {
-                        ControlFlow::Ok => {}
+                        ControlFlow::Ok | ControlFlow::Continue => {}
+                        ControlFlow::Break => break,
                         other => return other,
                     }
                     if let Some(st) = step {
-                        if let Err(e) = self.eval_expr(st) {
+                        if let ControlFlow::Err(e) = self.exec_stmt(st) {
                             return ControlFlow::Err(e);
                         }
                     }
                 }
                 ControlFlow::Ok
             }
-            Let { name, value } => {
+            Let { name, value, line, column } => {
+                self.pos = (*line, *column);
                 let v = if let Some(e) = value {
                     match self.eval_expr(e) {
                         Ok(v) => v,
@@ -371,21 +902,32 @@ impl Interpreter {
                 } else {
                     Value::Null
                 };
-                debug_log!("vm: let {} = {:?}", name, v);
-                self.env.define(name.clone(), v);
-                ControlFlow::Ok
+                trace_log!("let {} = {:?}", name, v);
+                match self.bind(name.clone(), v) {
+                    Ok(()) => ControlFlow::Ok,
+                    Err(e) => ControlFlow::Err(e),
+                }
             }
-            Assign { target, value } => {
+            Destructure { pattern, value, line, column } => {
+                self.pos = (*line, *column);
+                match self.eval_expr(value).and_then(|v| self.destructure(pattern, v)) {
+                    Ok(()) => ControlFlow::Ok,
+                    Err(e) => ControlFlow::Err(e),
+                }
+            }
+            Assign { target, value, line, column } => {
+                self.pos = (*line, *column);
                 // Only Ident target in v0
                 if let crate::core::ir::Expr::Ident(name) = target {
                     let v = match self.eval_expr(value) {
                         Ok(v) => v,
                         Err(e) => return ControlFlow::Err(e),
                     };
-                    if !self.env.assign(name, v) {
-                        return ControlFlow::Err(err(format!("Undefined variable `{}`", name)));
+                    match self.rebind(name, v) {
+                        Ok(true) => ControlFlow::Ok,
+                        Ok(false) => ControlFlow::Err(err(format!("Undefined variable `{}`", name))),
+                        Err(e) => ControlFlow::Err(e),
                     }
-                    ControlFlow::Ok
                 } else {
                     ControlFlow::Err(err(
                         "Only simple identifier assignment supported in v0".into()
@@ -405,12 +947,11 @@ impl Interpreter {
                 crate::core::ir::Lit::String(s) => Value::String(s.clone()),
             },
             Ident(s) => {
-                debug_log!("vm: lookup '{}'", s);
+                trace_log!("lookup '{}'", s);
                 let result = self
-                    .env
-                    .get(s)
+                    .lookup(s)
                     .ok_or_else(|| err(format!("Undefined identifier `{}`", s)))?;
-                debug_log!("vm: found '{}' -> {:?}", s, result);
+                trace_log!("found '{}' -> {:?}", s, result);
                 result
             }
             Call { callee, args } => {
@@ -429,30 +970,84 @@ impl Interpreter {
                 match op {
                     UnOp::Neg => match v {
                         Value::Number(n) => Value::Number(-n),
-                        other => return Err(err(format!("Unary `-` on non-number: {:?}", other))),
+                        other => return Err(err(format!("Unary `-` on non-number: {}", shown(&other)))),
                     },
                     UnOp::Not => Value::Bool(!self.truthy(&v)),
                 }
             }
             Binary { left, op, right } => {
                 let l = self.eval_expr(left)?;
+                // `&&` / `||` short-circuit like the JS backend
+                match op {
+                    BinOp::And if !self.truthy(&l) => return Ok(Value::Bool(false)),
+                    BinOp::Or if self.truthy(&l) => return Ok(Value::Bool(true)),
+                    _ => {}
+                }
                 let r = self.eval_expr(right)?;
-                self.eval_binop(op, l, r)?
+                let v = self.eval_binop(op, l, r)?;
+                if let (Value::String(s), Some(max)) = (&v, self.limits.max_string_bytes) {
+                    if s.len() > max {
+                        return Err(err(format!("string of {} bytes exceeds the {} byte limit", s.len(), max)));
+                    }
+                }
+                // A new string is live beside the variables until it is stored or dropped.
+                if let (Value::String(s), Some(_)) = (&v, self.limits.max_memory_bytes) {
+                    self.check_memory(s.len())?;
+                }
+                v
             }
             Array(items) => {
                 let mut out = Vec::with_capacity(items.len());
                 for it in items {
                     out.push(self.eval_expr(it)?);
                 }
-                Value::Array(out)
+                let v = Value::Array(out);
+                if self.limits.max_memory_bytes.is_some() {
+                    self.check_memory(value_size(&v))?;
+                }
+                v
             }
             Object(kvs) => {
                 let mut map = HashMap::with_capacity(kvs.len());
                 for (k, v) in kvs {
                     map.insert(k.clone(), self.eval_expr(v)?);
                 }
-                Value::Object(map)
+                let v = Value::Object(map);
+                if self.limits.max_memory_bytes.is_some() {
+                    self.check_memory(value_size(&v))?;
+                }
+                v
             }
+            Index { target, index } => {
+                let t = self.eval_expr(target)?;
+                let i = self.eval_expr(index)?;
+                match (t, i) {
+                    (Value::Array(items), Value::Number(n)) => {
+                        let len = items.len();
+                        if n < 0.0 || n.fract() != 0.0 || n as usize >= len {
+                            return Err(err(format!("index {} out of bounds (length {})", vm_display::js_number(n), len)));
+                        }
+                        items.into_iter().nth(n as usize).unwrap()
+                    }
+                    (Value::Object(map), Value::String(k)) => map.get(&k).cloned().unwrap_or(Value::Null),
+                    (Value::String(s), Value::Number(n)) => {
+                        let len = s.chars().count();
+                        if n < 0.0 || n.fract() != 0.0 || n as usize >= len {
+                            return Err(err(format!("index {} out of bounds (length {})", vm_display::js_number(n), len)));
+                        }
+                        Value::String(s.chars().nth(n as usize).unwrap().to_string())
+                    }
+                    (t, i) => return Err(err(format!("cannot index {} with {}", shown(&t), shown(&i)))),
+                }
+            }
+            Member { target, name } => match self.eval_expr(target)? {
+                Value::Enum(def) => match def.variants.iter().position(|v| v == name) {
+                    Some(i) => Value::Variant(def, i),
+                    None => return Err(err(format!("enum {} has no variant `{}`", def.name, name))),
+                },
+                Value::Object(map) => map.get(name).cloned().unwrap_or(Value::Null),
+                t => return Err(err(format!("cannot read `.{}` of {}", name, shown(&t)))),
+            },
         })
     }
 
@@ -464,12 +1059,21 @@ impl Interpreter {
                 (Value::String(a), Value::String(b)) => Ok(Value::String(format!("{}{}", a, b))),
                 (Value::String(a), b) => Ok(Value::String(format!("{}{}", a, display(&b)))),
                 (a, Value::String(b)) => Ok(Value::String(format!("{}{}", display(&a), b))),
-                (a, b) => Err(err(format!("`+` on incompatible types: {:?}, {:?}", a, b))),
+                (a, b) => Err(err(format!("`+` on incompatible types: {}, {}", shown(&a), shown(&b)))),
             },
             Sub => num2(l, r, |a, b| a - b),
             Mul => num2(l, r, |a, b| a * b),
+            Div | Mod if matches!(r, Value::Number(b) if b == 0.0) && matches!(l, Value::Number(_)) => {
+                let (line, column) = self.pos;
+                let what = if *op == Div { "division" } else { "modulo" };
+                Err(RuntimeError {
+                    message: format!("{} by zero (line {}, column {})", what, line, column),
+                    kind: RuntimeErrorKind::DivisionByZero { line, column },
+                })
+            }
             Div => num2(l, r, |a, b| a / b),
             Mod => num2(l, r, |a, b| a % b),
+            Pow => num2(l, r, f64::powf),
             Eq => Ok(Value::Bool(eq_val(&l, &r))),
             Ne => Ok(Value::Bool(!eq_val(&l, &r))),
             Lt => cmp2(l, r, |a, b| a < b),
@@ -489,13 +1093,15 @@ impl Interpreter {
             Value::String(s) => !s.is_empty(),
             Value::Array(a) => !a.is_empty(),
             Value::Object(o) => !o.is_empty(),
-            Value::Function(_) | Value::Builtin(_) => true,
+            Value::Function(_) | Value::Builtin(_) | Value::Enum(_) | Value::Variant(..) => true,
         }
     }
 }
 
 enum ControlFlow {
     Ok,
+    Break,
+    Continue,
     Return(Option<Value>),
     Err(RuntimeError),
 }
@@ -507,7 +1113,31 @@ impl From<RuntimeError> for ControlFlow {
 }
 
 fn err(msg: String) -> RuntimeError {
-    RuntimeError { message: msg }
+    RuntimeError::new(msg)
+}
+
+/// Approximate bytes `v` occupies: its slot plus the string, element and entry data it owns.
+/// Functions count as a slot only, since their closures share what they captured.
+pub fn value_size(v: &Value) -> usize {
+    const SLOT: usize = std::mem::size_of::<Value>();
+    match v {
+        Value::String(s) => SLOT + s.len(),
+        Value::Array(items) => SLOT + items.iter().map(value_size).sum::<usize>(),
+        Value::Object(map) => SLOT + map.iter().map(|(k, v)| std::mem::size_of::<String>() + k.len() + value_size(v)).sum::<usize>(),
+        _ => SLOT,
+    }
+}
+
+/// Process exit code for a value returned from `main`: a number truncated and clamped to
+/// 0..=255 (NaN is 0), `true` 0 and `false` 1, anything else 0. The JS backend's
+/// `__aeonmi_exit_code` helper applies the same rules.
+pub fn exit_code(v: &Value) -> i32 {
+    match v {
+        Value::Number(n) if n.is_nan() => 0,
+        Value::Number(n) => n.trunc().clamp(0.0, 255.0) as i32,
+        Value::Bool(b) => i32::from(!b),
+        _ => 0,
+    }
 }
 
 fn collect_vals(i: &mut Interpreter, es: &[Expr]) -> Result<Vec<Value>, RuntimeError> {
@@ -521,14 +1151,14 @@ fn collect_vals(i: &mut Interpreter, es: &[Expr]) -> Result<Vec<Value>, RuntimeE
 fn num2(l: Value, r: Value, f: fn(f64, f64) -> f64) -> Result<Value, RuntimeError> {
     match (l, r) {
         (Value::Number(a), Value::Number(b)) => Ok(Value::Number(f(a, b))),
-        (a, b) => Err(err(format!("numeric op on non-numbers: {:?}, {:?}", a, b))),
+        (a, b) => Err(err(format!("numeric op on non-numbers: {}, {}", shown(&a), shown(&b)))),
     }
 }
 
 fn cmp2(l: Value, r: Value, f: fn(f64, f64) -> bool) -> Result<Value, RuntimeError> {
     match (l, r) {
         (Value::Number(a), Value::Number(b)) => Ok(Value::Bool(f(a, b))),
-        (a, b) => Err(err(format!("comparison on non-numbers: {:?}, {:?}", a, b))),
+        (a, b) => Err(err(format!("comparison on non-numbers: {}, {}", shown(&a), shown(&b)))),
     }
 }
 
@@ -565,6 +1195,9 @@ fn eq_val(a: &Value, b: &Value) -> bool {
             true
         }
 
+        // Variants of the same enum compare by tag; different enums never match.
+        (Variant(x, i), Variant(y, j)) => i == j && x.name == y.name,
+
         // Functions/builtins: not comparable for now
         (Function(_), Function(_)) => false,
         (Builtin(_), Builtin(_)) => false,
@@ -575,15 +1208,89 @@ fn eq_val(a: &Value, b: &Value) -> bool {
 
 // ---------- Builtins ----------
 
-fn builtin_print(_i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
-    let parts: Vec<String> = args.iter().map(display).collect();
-    println!("{}", parts.join(" "));
+fn builtin_print(i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    i.emit_log(args)?;
     Ok(Value::Null)
 }
 
-fn builtin_time_ms(_i: &mut Interpreter, _args: Vec<Value>) -> Result<Value, RuntimeError> {
-    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-    Ok(Value::Number(now.as_millis() as f64))
+fn builtin_time_ms(i: &mut Interpreter, _args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let now = i.traced(vm_trace::Kind::TimeMs, "", || {
+        Payload::Number(SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as f64)
+    })?;
+    Ok(Value::Number(now.number()))
+}
+
+/// `env(name)`: the environment variable's value, or null when unset.
+fn builtin_env(i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if i.limits.deny_env {
+        return Err(err("env() is not available in the sandbox".into()));
+    }
+    let name = display(&args[0]);
+    let v = i.traced(vm_trace::Kind::Env, &name, || Payload::Text(std::env::var(&name).ok()))?;
+    Ok(v.text().map(Value::String).unwrap_or(Value::Null))
+}
+
+/// `path` with symlinks and `..` resolved, provided it lies under a mounted directory.
+/// For a file that does not exist yet, its directory must.
+fn mounted_path(i: &Interpreter, builtin: &str, path: &Value) -> Result<PathBuf, RuntimeError> {
+    let path = PathBuf::from(display(path));
+    let resolved = path.canonicalize().or_else(|_| {
+        let name = path.file_name().ok_or_else(|| err(format!("{}: `{}` is not a file path", builtin, path.display())))?;
+        let dir = match path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p,
+            _ => Path::new("."),
+        };
+        let dir = dir.canonicalize().map_err(|e| err(format!("{}: {}: {}", builtin, dir.display(), e)))?;
+        Ok::<_, RuntimeError>(dir.join(name))
+    })?;
+    if i.mounts.iter().any(|m| resolved.starts_with(m)) {
+        Ok(resolved)
+    } else {
+        Err(err(format!("{}: `{}` is outside the mounted directories (see `aeonmi vm mount`)", builtin, path.display())))
+    }
+}
+
+/// `read_file(path)`: the file's text; only under a mounted directory.
+fn builtin_read_file(i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let path = mounted_path(i, "read_file", &args[0])?;
+    std::fs::read_to_string(&path).map(Value::String).map_err(|e| err(format!("read_file: {}: {}", path.display(), e)))
+}
+
+/// `write_file(path, text)`: replace the file's contents; only under a mounted directory.
+fn builtin_write_file(i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let path = mounted_path(i, "write_file", &args[0])?;
+    std::fs::write(&path, display(&args[1])).map_err(|e| err(format!("write_file: {}: {}", path.display(), e)))?;
+    Ok(Value::Null)
+}
+
+/// `read_line()`: the next input line without its line ending, or null at end of input.
+fn builtin_read_line(i: &mut Interpreter, _args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let mut input = std::mem::replace(&mut i.input, Box::new(std::io::empty()));
+    let v = i.traced(vm_trace::Kind::ReadLine, "", || {
+        let mut buf = Vec::new();
+        match input.read_until(b'\n', &mut buf) {
+            Ok(0) | Err(_) => Payload::Text(None),
+            Ok(_) => {
+                if buf.ends_with(b"\n") { buf.pop(); }
+                if buf.ends_with(b"\r") { buf.pop(); }
+                Payload::Text(Some(String::from_utf8_lossy(&buf).into_owned()))
+            }
+        }
+    });
+    i.input = input;
+    Ok(v?.text().map(Value::String).unwrap_or(Value::Null))
+}
+
+/// `read_all()`: the rest of the input as one string (empty at end of input).
+fn builtin_read_all(i: &mut Interpreter, _args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let mut input = std::mem::replace(&mut i.input, Box::new(std::io::empty()));
+    let v = i.traced(vm_trace::Kind::ReadAll, "", || {
+        let mut buf = Vec::new();
+        let _ = input.read_to_end(&mut buf);
+        Payload::Text(Some(String::from_utf8_lossy(&buf).into_owned()))
+    });
+    i.input = input;
+    Ok(Value::String(v?.text().unwrap_or_default()))
 }
 
 static GLOBAL_SEED: AtomicU64 = AtomicU64::new(0);
@@ -619,9 +1326,379 @@ fn lcg_next() -> u64 {
     x
 }
 
-fn builtin_rand(_i: &mut Interpreter, _args: Vec<Value>) -> Result<Value, RuntimeError> {
-    let x = lcg_next();
-    Ok(Value::Number(((x >> 8) as f64) / (u32::MAX as f64)))
+fn builtin_rand(i: &mut Interpreter, _args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let r = i.traced(vm_trace::Kind::Rand, "", || {
+        let x = lcg_next();
+        Payload::Number(((x >> 8) as f64) / (u32::MAX as f64))
+    })?;
+    Ok(Value::Number(r.number()))
+}
+
+/// Uniform draw in [0, 1) from the seeded LCG. Raw successive LCG outputs are
+/// correlated, so each one goes through the splitmix64 finalizer first.
+fn unit_rand() -> f64 {
+    let mut z = lcg_next();
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+    (z >> 11) as f64 / (1u64 << 53) as f64
+}
+
+// ---------- Quantum builtins ----------
+
+fn qubit_handle(label: &str) -> Value {
+    let mut m = HashMap::new();
+    m.insert("qubit".to_string(), Value::String(label.to_string()));
+    Value::Object(m)
+}
+
+fn qubit_index(i: &mut Interpreter, v: &Value) -> Result<usize, RuntimeError> {
+    match v {
+        Value::Object(m) => match m.get("qubit") {
+            Some(Value::String(label)) => i.quantum.qubit(label).map_err(err),
+            _ => Err(err(format!("expected a qubit, got {}", shown(v)))),
+        },
+        other => Err(err(format!("expected a qubit, got {}", shown(other)))),
+    }
+}
+
+fn builtin_qubit(i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let label = display(&args[0]);
+    i.quantum.qubit(&label).map_err(err)?;
+    Ok(qubit_handle(&label))
+}
+
+/// `qreg r[n]` -> array of handles labelled `r[0]`..`r[n-1]`, allocated in order.
+fn builtin_qreg(i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let name = display(&args[0]);
+    let size = match args[1] {
+        Value::Number(n) if n >= 1.0 && n.fract() == 0.0 => n as usize,
+        ref other => return Err(err(format!("register size must be a positive integer, got {}", shown(other)))),
+    };
+    // checked before allocating anything: `qreg r[1e9];` must not reserve a billion handles
+    if size > MAX_QUBITS {
+        return Err(err(format!("register {}[{}] exceeds the {}-qubit limit", name, size, MAX_QUBITS)));
+    }
+    let mut handles = Vec::with_capacity(size);
+    for k in 0..size {
+        let label = format!("{}[{}]", name, k);
+        i.quantum.qubit(&label).map_err(err)?;
+        handles.push(qubit_handle(&label));
+    }
+    Ok(Value::Array(handles))
+}
+
+fn builtin_superpose(i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let q = qubit_index(i, &args[0])?;
+    i.quantum.h(q);
+    Ok(Value::Null)
+}
+
+fn builtin_entangle(i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let c = qubit_index(i, &args[0])?;
+    let t = qubit_index(i, &args[1])?;
+    if c == t {
+        return Err(err("entangle needs two distinct qubits".into()));
+    }
+    i.quantum.cnot(c, t);
+    Ok(Value::Null)
+}
+
+fn builtin_measure(i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let q = qubit_index(i, &args[0])?;
+    let draw = i.traced(vm_trace::Kind::Measure, "", || Payload::Number(unit_rand()))?;
+    Ok(Value::Number(i.quantum.measure(q, draw.number()) as f64))
+}
+
+fn builtin_dod(i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let q = qubit_index(i, &args[0])?;
+    i.quantum.x(q);
+    Ok(Value::Null)
+}
+
+/// `run_shots(n, f)`: call `f()` n times, resetting the quantum register before
+/// each shot. Classical state is untouched. Returns the histogram (also kept for `counts()`).
+fn builtin_run_shots(i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let mut args = args.into_iter();
+    let n = match args.next() {
+        Some(Value::Number(n)) if n >= 0.0 && n.fract() == 0.0 => n as usize,
+        other => return Err(err(format!("run_shots expects a whole shot count, got {}", other.as_ref().map_or("nothing".into(), shown)))),
+    };
+    let body = args.next().unwrap();
+    i.shot_counts.clear();
+    for _ in 0..n {
+        i.quantum.reset();
+        i.call_value(body.clone(), vec![])?;
+        let key = i.quantum.bitstring();
+        if !key.is_empty() {
+            *i.shot_counts.entry(key).or_insert(0) += 1;
+        }
+    }
+    i.quantum.reset();
+    builtin_counts(i, vec![])
+}
+
+/// `builtin (line N)` for errors about a call made on `line` (just `builtin` when unknown).
+fn call_site(builtin: &str, line: usize) -> String {
+    if line > 0 { format!("{} (line {})", builtin, line) } else { builtin.to_string() }
+}
+
+/// Number of arguments `f` takes, when that is fixed.
+fn fixed_arity(f: &Value) -> Option<usize> {
+    match f {
+        Value::Function(fun) => Some(fun.params.len()),
+        Value::Builtin(b) if b.arity != usize::MAX => Some(b.arity),
+        _ => None,
+    }
+}
+
+/// Call objective `f` at `x`: one number per parameter when `f` takes exactly `x.len()`
+/// arguments, otherwise the whole point as one array. Counts the call in `evaluations`.
+fn objective(i: &mut Interpreter, builtin: &str, line: usize, f: &Value, x: &[f64], evaluations: &mut usize) -> Result<f64, RuntimeError> {
+    *evaluations += 1;
+    let point: Vec<Value> = x.iter().map(|v| Value::Number(*v)).collect();
+    let args = if fixed_arity(f) == Some(x.len()) { point } else { vec![Value::Array(point)] };
+    match i.call_value(f.clone(), args)? {
+        Value::Number(n) => Ok(n),
+        other => Err(RuntimeError {
+            message: format!(
+                "{}: objective returned {} at [{}] (evaluation {}); it must return a number",
+                call_site(builtin, line),
+                shown(&other),
+                x.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", "),
+                evaluations
+            ),
+            kind: RuntimeErrorKind::ObjectiveNotNumber { line, evaluation: *evaluations },
+        }),
+    }
+}
+
+fn whole(builtin: &str, what: &str, v: &Value) -> Result<usize, RuntimeError> {
+    match v {
+        Value::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Ok(*n as usize),
+        other => Err(err(format!("{} expects a whole number for {}, got {}", builtin, what, shown(other)))),
+    }
+}
+
+fn numbers(builtin: &str, what: &str, v: &Value) -> Result<Vec<f64>, RuntimeError> {
+    let fail = || err(format!("{} expects {} to be an array of numbers, got {}", builtin, what, shown(v)));
+    match v {
+        Value::Array(items) => items.iter().map(|x| if let Value::Number(n) = x { Ok(*n) } else { Err(fail()) }).collect(),
+        _ => Err(fail()),
+    }
+}
+
+/// `minimize(f, initial, options)`: the lowest value of `f` found from `initial` (an array,
+/// or a number used for every parameter of `f`). Options (all optional): `method`
+/// (`"nelder_mead"`, the default, or `"annealing"`), `max_iter`, `tol` and `seed` (annealing).
+/// Returns `{best_params, best_value, iterations}`.
+fn builtin_minimize(i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    use crate::core::titan::optimization::{nelder_mead, simulated_annealing, Limits};
+    if !(2..=3).contains(&args.len()) {
+        return Err(err(format!("builtin `minimize` expected 2 or 3 args, got {}", args.len())));
+    }
+    let line = i.pos.0;
+    let f = args[0].clone();
+    let x0 = match &args[1] {
+        Value::Number(n) => vec![*n; fixed_arity(&f).unwrap_or(1).max(1)],
+        v => numbers("minimize", "initial", v)?,
+    };
+    if x0.is_empty() {
+        return Err(err("minimize needs at least one parameter".into()));
+    }
+    let (mut limits, mut annealing, mut seed) = (Limits::default(), false, 0);
+    match args.get(2) {
+        None | Some(Value::Null) => {}
+        Some(Value::Object(options)) => {
+            for (key, v) in options {
+                match (key.as_str(), v) {
+                    ("max_iter", v) => limits.max_iter = whole("minimize", "max_iter", v)?,
+                    ("tol", Value::Number(t)) if *t >= 0.0 => limits.tol = *t,
+                    ("seed", v) => seed = whole("minimize", "seed", v)? as u64,
+                    ("method", Value::String(m)) if m == "nelder_mead" || m == "annealing" => annealing = m == "annealing",
+                    ("tol" | "method", v) => return Err(err(format!("minimize: invalid {}: {}", key, shown(v)))),
+                    _ => return Err(err(format!("minimize: unknown option `{}` (known: method, max_iter, tol, seed)", key))),
+                }
+            }
+        }
+        Some(other) => return Err(err(format!("minimize expects an options map, got {}", shown(other)))),
+    }
+    let mut evaluations = 0;
+    let eval = |x: &[f64]| objective(i, "minimize", line, &f, x, &mut evaluations);
+    let best = if annealing { simulated_annealing(eval, &x0, limits, seed)? } else { nelder_mead(eval, &x0, limits)? };
+    Ok(minimum_value(best))
+}
+
+/// `grid_search(f, ranges)`: the lowest value of `f` over a grid, `ranges` holding one
+/// `[lo, hi, points]` per parameter. The ranges may also be passed inline as number triples:
+/// `grid_search(f, lo, hi, points, ...)`. Returns `{best_params, best_value, iterations}`.
+fn builtin_grid_search(i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    use crate::core::titan::optimization::grid_search;
+    let line = i.pos.0;
+    let mut args = args.into_iter();
+    let f = args.next().ok_or_else(|| err("grid_search expects an objective and ranges".into()))?;
+    let rest: Vec<Value> = args.collect();
+    let triples: Vec<Vec<f64>> = match rest.as_slice() {
+        [Value::Array(ranges)] => ranges.iter().map(|r| numbers("grid_search", "each range", r)).collect::<Result<_, _>>()?,
+        inline if !inline.is_empty() && inline.len() % 3 == 0 => {
+            numbers("grid_search", "the inline ranges", &Value::Array(rest.clone()))?.chunks(3).map(<[f64]>::to_vec).collect()
+        }
+        _ => return Err(err("grid_search expects ranges as [[lo, hi, points], ...] or inline lo, hi, points triples".into())),
+    };
+    let mut ranges = Vec::with_capacity(triples.len());
+    for t in &triples {
+        match t.as_slice() {
+            [lo, hi, points] if *points >= 1.0 && points.fract() == 0.0 => ranges.push((*lo, *hi, *points as usize)),
+            _ => return Err(err(format!("grid_search: each range is [lo, hi, points] with at least one point, got {:?}", t))),
+        }
+    }
+    if ranges.is_empty() {
+        return Err(err("grid_search needs at least one range".into()));
+    }
+    let mut evaluations = 0;
+    let best = grid_search(|x: &[f64]| objective(i, "grid_search", line, &f, x, &mut evaluations), &ranges)?;
+    Ok(minimum_value(best))
+}
+
+/// `simulate_ode(f, y0, t_span, dt)`: RK4 trajectory of `y' = f(t, y)`. `y0` is a number or
+/// an array (then `f` gets and returns arrays); `t_span` is an end time (starting at 0) or
+/// `[t0, t1]`. Returns `[t, y...]` per step. A bad step size or a state that stops being
+/// finite is a runtime error rather than NaN in the output.
+fn builtin_simulate_ode(i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    use crate::core::titan::chaos_theory_dynamical_systems::{ode_trajectory, TrajectoryError};
+    let line = i.pos.0;
+    let site = call_site("simulate_ode", line);
+    let mut args = args.into_iter();
+    let f = args.next().unwrap();
+    let (y0, scalar) = match args.next().unwrap() {
+        Value::Number(y) => (vec![y], true),
+        v => (numbers("simulate_ode", "y0", &v)?, false),
+    };
+    let t_span = match args.next().unwrap() {
+        Value::Number(t1) => (0.0, t1),
+        v => match numbers("simulate_ode", "t_span", &v)?.as_slice() {
+            [t0, t1] => (*t0, *t1),
+            _ => return Err(err(format!("{}: t_span is an end time or [t0, t1], got {}", site, shown(&v)))),
+        },
+    };
+    let dt = match args.next().unwrap() {
+        Value::Number(dt) => dt,
+        other => return Err(err(format!("{}: dt must be a number, got {}", site, shown(&other)))),
+    };
+    let system = |t: f64, y: &[f64]| -> Result<Vec<f64>, RuntimeError> {
+        let state = if scalar { Value::Number(y[0]) } else { Value::Array(y.iter().map(|v| Value::Number(*v)).collect()) };
+        match i.call_value(f.clone(), vec![Value::Number(t), state])? {
+            Value::Number(dy) if scalar => Ok(vec![dy]),
+            v @ Value::Array(_) if !scalar => numbers("simulate_ode", "the derivative", &v),
+            other => Err(err(format!(
+                "{}: f returned {} at t = {}; it must return {}",
+                site,
+                shown(&other),
+                t,
+                if scalar { "a number" } else { "an array of numbers" }
+            ))),
+        }
+    };
+    let points = ode_trajectory(system, &y0, t_span, dt).map_err(|e| match e {
+        TrajectoryError::System(e) => e,
+        TrajectoryError::Invalid(msg) => err(format!("{}: {}", site, msg)),
+        TrajectoryError::Unstable { t, step } => err(format!("{}: {}", site, TrajectoryError::<std::convert::Infallible>::Unstable { t, step })),
+    })?;
+    Ok(Value::Array(
+        points
+            .into_iter()
+            .map(|(t, y)| Value::Array(std::iter::once(t).chain(y).map(Value::Number).collect()))
+            .collect(),
+    ))
+}
+
+/// `logistic_map(r, x0, n)`: `[x0, x1, ..., xn]` with `x_{k+1} = r x_k (1 - x_k)`.
+fn builtin_logistic_map(i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let site = call_site("logistic_map", i.pos.0);
+    let (r, x0) = match (&args[0], &args[1]) {
+        (Value::Number(r), Value::Number(x0)) => (*r, *x0),
+        (a, b) => return Err(err(format!("{}: r and x0 must be numbers, got {} and {}", site, shown(a), shown(b)))),
+    };
+    let n = whole("logistic_map", "n", &args[2])?;
+    let xs = crate::core::titan::chaos_theory_dynamical_systems::logistic_map(r, x0, n).map_err(|e| err(format!("{}: {}", site, e)))?;
+    Ok(Value::Array(xs.into_iter().map(Value::Number).collect()))
+}
+
+/// `derivative(expr, var)`: the simplified derivative of a math expression string.
+fn builtin_derivative(i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    use crate::core::titan::symbolic_math::derivative;
+    let site = call_site("derivative", i.pos.0);
+    let (src, var) = match (&args[0], &args[1]) {
+        (Value::String(src), Value::String(var)) => (src, var),
+        (a, b) => return Err(err(format!("{}: expression and variable must be strings, got {} and {}", site, shown(a), shown(b)))),
+    };
+    if !matches!(var.chars().next(), Some(c) if c.is_alphabetic() || c == '_') || !var.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err(err(format!("{}: `{}` is not a variable name", site, var)));
+    }
+    let d = derivative(src, var).map_err(|e| err(format!("{}: {}", site, e)))?;
+    Ok(Value::String(d.to_string()))
+}
+
+/// `simplify(expr)`: a math expression string in canonical form.
+fn builtin_simplify(i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    use crate::core::titan::symbolic_math::{parse, simplify};
+    let site = call_site("simplify", i.pos.0);
+    let src = match &args[0] {
+        Value::String(src) => src,
+        v => return Err(err(format!("{}: expression must be a string, got {}", site, shown(v)))),
+    };
+    let e = parse(src).map_err(|e| err(format!("{}: {}", site, e)))?;
+    Ok(Value::String(simplify(e).to_string()))
+}
+
+fn minimum_value(m: crate::core::titan::optimization::Minimum) -> Value {
+    Value::Object(HashMap::from([
+        ("best_params".to_string(), Value::Array(m.params.into_iter().map(Value::Number).collect())),
+        ("best_value".to_string(), Value::Number(m.value)),
+        ("iterations".to_string(), Value::Number(m.iterations as f64)),
+    ]))
+}
+
+fn builtin_counts(i: &mut Interpreter, _args: Vec<Value>) -> Result<Value, RuntimeError> {
+    Ok(Value::Object(
+        i.shot_counts.iter().map(|(k, v)| (k.clone(), Value::Number(*v as f64))).collect(),
+    ))
+}
+
+/// `__enum(name, variants...)`: the value an `enum` declaration binds.
+fn builtin_enum(_i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let mut names = args.iter().map(display);
+    let name = names.next().ok_or_else(|| err("__enum expects a name".into()))?;
+    Ok(Value::Enum(Arc::new(EnumDef { name, variants: names.collect() })))
+}
+
+/// `__iter(xs)`: what `for x in xs` visits: an array's elements, a string's characters
+/// or a map's keys, sorted. `null` has nothing to visit.
+fn builtin_iter(i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    match args.into_iter().next().unwrap() {
+        Value::Array(items) => Ok(Value::Array(items)),
+        Value::String(s) => Ok(Value::Array(s.chars().map(|c| Value::String(c.to_string())).collect())),
+        v @ Value::Object(_) => builtin_iter_keys(i, vec![v]),
+        Value::Null => Ok(Value::Array(Vec::new())),
+        other => Err(err(format!("cannot iterate over {}", shown(&other)))),
+    }
+}
+
+/// `__iter_keys(xs)`: the keys `for k, v in xs` visits, `v` being `xs[k]`: indices of an
+/// array or string, or a map's keys, sorted.
+fn builtin_iter_keys(_i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let indices = |n: usize| Ok(Value::Array((0..n).map(|i| Value::Number(i as f64)).collect()));
+    match args.into_iter().next().unwrap() {
+        Value::Array(items) => indices(items.len()),
+        Value::String(s) => indices(s.chars().count()),
+        Value::Object(map) => {
+            let mut keys: Vec<String> = map.into_keys().collect();
+            keys.sort();
+            Ok(Value::Array(keys.into_iter().map(Value::String).collect()))
+        }
+        Value::Null => Ok(Value::Array(Vec::new())),
+        other => Err(err(format!("cannot iterate over {}", shown(&other)))),
+    }
 }
 
 fn builtin_len(_i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
@@ -637,38 +1714,91 @@ fn builtin_len(_i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeE
         Value::Array(items) => Ok(Value::Number(items.len() as f64)),
         Value::Object(map) => Ok(Value::Number(map.len() as f64)),
         Value::Null => Ok(Value::Number(0.0)),
-        other => Err(err(format!("len unsupported for value: {:?}", other))),
+        other => Err(err(format!("len unsupported for value: {}", shown(&other)))),
     }
 }
 
-fn display(v: &Value) -> String {
-    match v {
-        Value::Null => "null".into(),
-        Value::Bool(b) => b.to_string(),
-        Value::Number(n) => {
-            if n.fract() == 0.0 {
-                format!("{}", *n as i64)
-            } else {
-                n.to_string()
-            }
-        }
-        Value::String(s) => s.clone(),
-        Value::Array(a) => {
-            let parts: Vec<String> = a.iter().map(display).collect();
-            format!("[{}]", parts.join(", "))
-        }
-        Value::Object(o) => {
-            let mut parts: Vec<(String, String)> =
-                o.iter().map(|(k, v)| (k.clone(), display(v))).collect();
-            parts.sort_by(|a, b| a.0.cmp(&b.0));
-            let s = parts
-                .into_iter()
-                .map(|(k, v)| format!("{}: {}", k, v))
-                .collect::<Vec<_>>()
-                .join(", ");
-            format!("{{{}}}", s)
-        }
-        Value::Function(_) => "<fn>".to_string(),
-        Value::Builtin(b) => format!("<builtin:{}>", b.name),
+/// The array held by variable `name` for `push` / `pop` to update in place; `null`
+/// becomes an empty array first, as `len(null)` is 0.
+fn array_var<'a>(i: &'a mut Interpreter, builtin: &str, name: &str) -> Result<&'a mut Vec<Value>, RuntimeError> {
+    let slot = i.env.get_mut(name).ok_or_else(|| err(format!("Undefined variable `{}`", name)))?;
+    if let Value::Null = slot {
+        *slot = Value::Array(Vec::new());
+    }
+    match slot {
+        Value::Array(items) => Ok(items),
+        other => Err(err(format!("{} expects an array, got {}", builtin, shown(other)))),
+    }
+}
+
+/// `__push(name, value)`, what `push(xs, value)` lowers to: append to `xs` and return its
+/// new length, like JS's `Array.prototype.push`.
+fn builtin_push(i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let mut args = args.into_iter();
+    let (Some(Value::String(name)), Some(value)) = (args.next(), args.next()) else {
+        return Err(err("__push expects a variable name and a value".into()));
+    };
+    if i.limits.max_memory_bytes.is_some() {
+        let added = value_size(&value);
+        i.check_memory(added)?;
+        i.live_bytes += added;
+    }
+    let items = array_var(i, "push", &name)?;
+    items.push(value);
+    Ok(Value::Number(items.len() as f64))
+}
+
+/// `__pop(name, line, column)`, what `pop(xs)` lowers to: remove and return the last
+/// element of `xs`. Popping an empty array is an error at the call's position.
+fn builtin_pop(i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let (name, line, column) = match args.as_slice() {
+        [Value::String(name), Value::Number(line), Value::Number(column)] => (name.clone(), *line as usize, *column as usize),
+        _ => return Err(err("__pop expects a variable name and a position".into())),
+    };
+    let popped = array_var(i, "pop", &name)?.pop();
+    match popped {
+        Some(v) => {
+            if i.limits.max_memory_bytes.is_some() {
+                i.live_bytes = i.live_bytes.saturating_sub(value_size(&v));
+            }
+            Ok(v)
+        }
+        None => {
+            let at = if line > 0 { format!(" (line {}, column {})", line, column) } else { String::new() };
+            Err(RuntimeError {
+                message: format!("pop{}: `{}` is empty", at, name),
+                kind: RuntimeErrorKind::EmptyArray { line, column },
+            })
+        }
     }
 }
+
+/// `slice(xs, start, end)`: a copy of `xs[start..end]`. Bounds are truncated to whole
+/// numbers and clamped to the array, so out-of-range arguments give a shorter (possibly
+/// empty) array rather than an error. A string slices as the array of its characters
+/// (as `for` and destructuring see it) and `null` as an empty array.
+fn builtin_slice(_i: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let mut args = args.into_iter();
+    let items = match args.next().unwrap() {
+        Value::Array(items) => items,
+        Value::String(s) => s.chars().map(|c| Value::String(c.to_string())).collect(),
+        Value::Null => Vec::new(),
+        other => return Err(err(format!("slice expects an array, got {}", shown(&other)))),
+    };
+    let mut bound = |what: &str| match args.next().unwrap() {
+        Value::Number(n) if !n.is_nan() => Ok(n.trunc().clamp(0.0, items.len() as f64) as usize),
+        other => Err(err(format!("slice {} must be a number, got {}", what, shown(&other)))),
+    };
+    let start = bound("start")?;
+    let end = bound("end")?.max(start);
+    Ok(Value::Array(items[start..end].to_vec()))
+}
+
+fn display(v: &Value) -> String {
+    vm_display::display_plain(v)
+}
+
+/// A value quoted in a runtime error message.
+fn shown(v: &Value) -> String {
+    v.display_pretty(vm_display::ERROR_DEPTH)
+}
@@ -1,13 +1,23 @@
 //! Simple bytecode VM (feature: bytecode)
 use crate::core::bytecode::{Chunk, OpCode, Constant};
+use std::io::Write;
 
 #[derive(Debug, Clone)]
 pub enum Value { Number(f64), String(String), Bool(bool), Null }
 
+/// Same rendering as the tree-walking VM's `log`, so the two backends print identically.
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self { Value::Number(n) if n.fract() == 0.0 => write!(f, "{}", *n as i64), Value::Number(n) => write!(f, "{}", n), Value::String(s) => f.write_str(s), Value::Bool(b) => write!(f, "{}", b), Value::Null => f.write_str("null") }
+    }
+}
+
+impl Value { fn truthy(&self) -> bool { match self { Value::Null => false, Value::Bool(b) => *b, Value::Number(n) => *n != 0.0, Value::String(s) => !s.is_empty() } } }
+
 #[derive(Debug)]
 struct Frame { return_ip: usize, locals: Vec<Value> }
 
-pub struct VM<'a> { pub chunk: &'a Chunk, stack: Vec<Value>, ip: usize, frames: Vec<Frame>, pub stack_overflow: bool, max_frames: usize }
+pub struct VM<'a> { pub chunk: &'a Chunk, stack: Vec<Value>, ip: usize, frames: Vec<Frame>, pub stack_overflow: bool, pub error: Option<String>, max_frames: usize, output: Box<dyn Write + Send> }
 
 impl<'a> VM<'a> {
     pub fn new(chunk: &'a Chunk) -> Self {
@@ -16,8 +26,10 @@ impl<'a> VM<'a> {
             .and_then(|s| s.parse::<usize>().ok())
             .map(|n| n.clamp(4, 65_536))
             .unwrap_or(256);
-        Self { chunk, stack: Vec::new(), ip: 0, frames: vec![Frame { return_ip: usize::MAX, locals: vec![Value::Null; 64] }], stack_overflow: false, max_frames }
+        Self { chunk, stack: Vec::new(), ip: 0, frames: vec![Frame { return_ip: usize::MAX, locals: vec![Value::Null; (chunk.main_locals as usize).max(64)] }], stack_overflow: false, error: None, max_frames, output: Box::new(std::io::stdout()) }
     }
+    /// Redirect `log` output (stdout by default), e.g. to a `vm::OutputBuffer`.
+    pub fn set_output(&mut self, out: Box<dyn Write + Send>) { self.output = out; }
     pub fn run(&mut self) -> Option<Value> {
         while self.ip < self.chunk.code.len() {
             match self.chunk.code[self.ip] { op => { self.ip += 1; if !self.dispatch(op) { break; } } }
@@ -31,10 +43,19 @@ impl<'a> VM<'a> {
             LoadConst(i) => { let c = &self.chunk.constants[i as usize]; self.stack.push(match c { Constant::Number(n)=>Value::Number(*n), Constant::String(s)=>Value::String(s.clone()), Constant::Bool(b)=>Value::Bool(*b), Constant::Null=>Value::Null }); }
             LoadLocal(i) => { if let Some(frame) = self.frames.last() { let v = frame.locals.get(i as usize).cloned().unwrap_or(Value::Null); self.stack.push(v); } }
             StoreLocal(i) => { if let Some(frame) = self.frames.last_mut() { if let Some(v)= self.stack.last().cloned() { if (i as usize) < frame.locals.len() { frame.locals[i as usize] = v; } } } }
-            Add => add_any(self), Sub => bin(self, |a,b| a-b), Mul => bin(self, |a,b| a*b), Div => bin(self, |a,b| if b==0.0 { 0.0 } else { a/b }),
+            Add => add_any(self), Sub => bin(self, |a,b| a-b), Mul => bin(self, |a,b| a*b), Div | Mod if matches!(self.stack.last(), Some(Value::Number(b)) if *b == 0.0) => {
+                self.error = Some(format!("{} by zero", if op == Div { "division" } else { "modulo" }));
+                return false;
+            }
+            Div => bin(self, |a,b| a/b), Mod => bin(self, |a,b| a%b), Pow => bin(self, f64::powf),
+            Neg => { let v = self.stack.pop(); self.stack.push(match v { Some(Value::Number(n)) => Value::Number(-n), _ => Value::Null }); }
+            Not => { let v = self.stack.pop(); self.stack.push(Value::Bool(!v.is_some_and(|v| v.truthy()))); }
             Eq|Ne|Lt|Le|Gt|Ge => cmp(self, op),
             And => logical(self, true), Or => logical(self, false),
             Pop => { self.stack.pop(); },
+            Jump(t) => { self.ip = t as usize; }
+            JumpIfFalse(t) => { if !self.stack.pop().is_some_and(|v| v.truthy()) { self.ip = t as usize; } }
+            Log => { let v = self.stack.pop().unwrap_or(Value::Null); let _ = writeln!(self.output, "{}", v); }
             Return => {
                 // Pop current frame; if no previous frame, halt.
                 if let Some(frame) = self.frames.pop() {
@@ -58,14 +79,13 @@ impl<'a> VM<'a> {
                 }
             }
             Nop => { },
-            _ => { /* unimplemented ops ignored for now */ }
         }
         true
     }
 }
 
 fn bin(vm: &mut VM, f: impl Fn(f64,f64)->f64) { if let (Some(r), Some(l)) = (vm.stack.pop(), vm.stack.pop()) { if let (Value::Number(rb), Value::Number(lb)) = (r,l) { vm.stack.push(Value::Number(f(lb,rb))); } else { vm.stack.push(Value::Null); } } }
-fn cmp(vm: &mut VM, op: OpCode) { use OpCode::*; if let (Some(r), Some(l)) = (vm.stack.pop(), vm.stack.pop()) { if let (Value::Number(rb), Value::Number(lb)) = (r,l) { let res = match op { Eq=> lb==rb, Ne=> lb!=rb, Lt=> lb<rb, Le=> lb<=rb, Gt=> lb>rb, Ge=> lb>=rb, _=> false }; vm.stack.push(Value::Bool(res)); } else { vm.stack.push(Value::Bool(false)); } } }
+fn cmp(vm: &mut VM, op: OpCode) { use OpCode::*; if let (Some(r), Some(l)) = (vm.stack.pop(), vm.stack.pop()) { if matches!(op, Eq | Ne) { let same = match (&l, &r) { (Value::Number(a), Value::Number(b)) => a == b, (Value::String(a), Value::String(b)) => a == b, (Value::Bool(a), Value::Bool(b)) => a == b, (Value::Null, Value::Null) => true, _ => false }; vm.stack.push(Value::Bool(same == matches!(op, Eq))); } else if let (Value::Number(rb), Value::Number(lb)) = (r,l) { let res = match op { Eq=> lb==rb, Ne=> lb!=rb, Lt=> lb<rb, Le=> lb<=rb, Gt=> lb>rb, Ge=> lb>=rb, _=> false }; vm.stack.push(Value::Bool(res)); } else { vm.stack.push(Value::Bool(false)); } } }
 fn logical(vm: &mut VM, is_and: bool) { if let (Some(r), Some(l)) = (vm.stack.pop(), vm.stack.pop()) {
     let lb = matches!(l, Value::Bool(true)); let rb = matches!(r, Value::Bool(true));
     let res = if is_and { lb && rb } else { lb || rb }; vm.stack.push(Value::Bool(res));
@@ -0,0 +1,133 @@
+//! Text form of VM values, shared by `log`, string concatenation and error messages.
+//!
+//! Numbers print as JavaScript's `String(n)` does (`1` not `1.0`, `1e+21`, `NaN`), so
+//! native and Node runs of the same program agree. Strings are bare at the top level
+//! and quoted inside containers. [`Value::display_pretty`] breaks a container over
+//! several indented lines when its one-line form would be wider than [`PRETTY_WIDTH`],
+//! elides containers nested deeper than its limit as `[…]` / `{…}`, and prints
+//! `«cycle»` for a container that (through shared references) contains itself.
+
+use crate::core::vm::Value;
+
+/// Widest one-line container, indentation included, before it is split over lines.
+pub const PRETTY_WIDTH: usize = 72;
+/// Nesting `log` prints before eliding.
+pub const LOG_DEPTH: usize = 8;
+/// Nesting shown for values quoted in runtime error messages.
+pub const ERROR_DEPTH: usize = 2;
+
+impl Value {
+    /// Readable form of the value, nested containers shown `depth_limit` levels deep.
+    pub fn display_pretty(&self, depth_limit: usize) -> String {
+        Renderer { depth_limit, width: Some(PRETTY_WIDTH), ancestors: Vec::new() }.render(self, 0, 0)
+    }
+}
+
+/// One-line form with no depth limit: what string `+` and labels use.
+pub fn display_plain(v: &Value) -> String {
+    Renderer { depth_limit: usize::MAX, width: None, ancestors: Vec::new() }.render(v, 0, 0)
+}
+
+/// `n` as JavaScript's `Number.prototype.toString()` writes it.
+pub fn js_number(n: f64) -> String {
+    if n.is_nan() {
+        return "NaN".into();
+    }
+    if n.is_infinite() {
+        return if n > 0.0 { "Infinity" } else { "-Infinity" }.into();
+    }
+    if n == 0.0 {
+        return "0".into();
+    }
+    // Shortest round-trip digits and exponent, e.g. "1.2345e-7".
+    let sci = format!("{:e}", n.abs());
+    let (mantissa, exp) = sci.split_once('e').unwrap();
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let k = digits.len() as i32;
+    // Decimal point position relative to the digits (ECMA-262's `n`).
+    let point = exp.parse::<i32>().unwrap() + 1;
+    let body = if k <= point && point <= 21 {
+        format!("{digits}{}", "0".repeat((point - k) as usize))
+    } else if 0 < point && point <= 21 {
+        format!("{}.{}", &digits[..point as usize], &digits[point as usize..])
+    } else if -6 < point && point <= 0 {
+        format!("0.{}{digits}", "0".repeat(-point as usize))
+    } else {
+        let e = point - 1;
+        let sign = if e < 0 { '-' } else { '+' };
+        let lead = if k == 1 { digits.clone() } else { format!("{}.{}", &digits[..1], &digits[1..]) };
+        format!("{lead}e{sign}{}", e.abs())
+    };
+    if n < 0.0 { format!("-{body}") } else { body }
+}
+
+struct Renderer {
+    depth_limit: usize,
+    /// `None` keeps every container on one line.
+    width: Option<usize>,
+    /// Addresses of the containers being rendered, outermost first.
+    ancestors: Vec<usize>,
+}
+
+impl Renderer {
+    fn render(&mut self, v: &Value, depth: usize, indent: usize) -> String {
+        match v {
+            Value::Array(items) => {
+                let parts = self.nested(items as *const _ as usize, depth, "[…]", |r| items.iter().map(|it| r.render(it, depth + 1, indent + 2)).collect());
+                parts.map_or_else(|s| s, |parts| self.layout(parts, ('[', ']'), indent))
+            }
+            Value::Object(map) => {
+                let parts = self.nested(map as *const _ as usize, depth, "{…}", |r| {
+                    let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+                    entries.sort_by(|a, b| a.0.cmp(b.0));
+                    entries.into_iter().map(|(k, v)| format!("{k}: {}", r.render(v, depth + 1, indent + 2))).collect()
+                });
+                parts.map_or_else(|s| s, |parts| self.layout(parts, ('{', '}'), indent))
+            }
+            Value::String(s) if depth > 0 => serde_json::to_string(s).unwrap_or_else(|_| format!("{s:?}")),
+            other => scalar(other),
+        }
+    }
+
+    /// Rendered children of the container at `addr`, or the text replacing them when it
+    /// is too deep or already being rendered further out.
+    fn nested(&mut self, addr: usize, depth: usize, elided: &str, children: impl FnOnce(&mut Self) -> Vec<String>) -> Result<Vec<String>, String> {
+        if self.ancestors.contains(&addr) {
+            return Err("«cycle»".into());
+        }
+        if depth >= self.depth_limit {
+            return Err(elided.into());
+        }
+        self.ancestors.push(addr);
+        let parts = children(self);
+        self.ancestors.pop();
+        Ok(parts)
+    }
+
+    fn layout(&self, parts: Vec<String>, (open, close): (char, char), indent: usize) -> String {
+        if parts.is_empty() {
+            return format!("{open}{close}");
+        }
+        let one_line = format!("{open}{}{close}", parts.join(", "));
+        let fits = self.width.is_none_or(|w| indent + one_line.chars().count() <= w && !one_line.contains('\n'));
+        if fits {
+            return one_line;
+        }
+        let pad = " ".repeat(indent + 2);
+        format!("{open}\n{pad}{}\n{}{close}", parts.join(&format!(",\n{pad}")), " ".repeat(indent))
+    }
+}
+
+fn scalar(v: &Value) -> String {
+    match v {
+        Value::Null => "null".into(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => js_number(*n),
+        Value::String(s) => s.clone(),
+        Value::Function(_) => "<fn>".to_string(),
+        Value::Builtin(b) => format!("<builtin:{}>", b.name),
+        Value::Enum(def) => format!("<enum {}>", def.name),
+        Value::Variant(def, i) => format!("{}.{}", def.name, def.variants[*i]),
+        Value::Array(_) | Value::Object(_) => unreachable!("containers are rendered by Renderer"),
+    }
+}
@@ -0,0 +1,112 @@
+//! Quantum register for the native VM: a small state-vector simulator.
+//!
+//! The register is owned by the `Interpreter` next to (not inside) the classical
+//! environment, so a shot boundary can reset every qubit to |0> while variables
+//! keep their values. Qubits are addressed by label (the declared name); a label
+//! keeps its index across resets so shot-to-shot bitstrings line up.
+//!
+//! Gate set: `superpose` = H, `entangle(c, t)` = CNOT, `dod` = X (bit flip).
+//! These gates are real-valued, so amplitudes are stored as `f64`.
+
+use std::f64::consts::FRAC_1_SQRT_2;
+
+/// Hard cap; the state vector holds 2^n amplitudes.
+pub const MAX_QUBITS: usize = 16;
+
+#[derive(Debug, Clone)]
+pub struct QuantumRegister {
+    labels: Vec<String>,
+    amps: Vec<f64>,
+    outcomes: Vec<Option<u8>>,
+}
+
+impl Default for QuantumRegister {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QuantumRegister {
+    pub fn new() -> Self {
+        Self { labels: Vec::new(), amps: vec![1.0], outcomes: Vec::new() }
+    }
+
+    pub fn qubit_count(&self) -> usize {
+        self.labels.len()
+    }
+
+    /// Index for `label`, allocating a fresh |0> qubit on first use.
+    pub fn qubit(&mut self, label: &str) -> Result<usize, String> {
+        if let Some(i) = self.labels.iter().position(|l| l == label) {
+            return Ok(i);
+        }
+        if self.labels.len() >= MAX_QUBITS {
+            return Err(format!("quantum register full ({} qubits max)", MAX_QUBITS));
+        }
+        self.labels.push(label.to_string());
+        self.outcomes.push(None);
+        // new qubit is the next high bit, starting in |0>
+        let n = self.amps.len();
+        self.amps.resize(n * 2, 0.0);
+        Ok(self.labels.len() - 1)
+    }
+
+    pub fn h(&mut self, q: usize) {
+        let bit = 1 << q;
+        for i in 0..self.amps.len() {
+            if i & bit == 0 {
+                let (a, b) = (self.amps[i], self.amps[i | bit]);
+                self.amps[i] = (a + b) * FRAC_1_SQRT_2;
+                self.amps[i | bit] = (a - b) * FRAC_1_SQRT_2;
+            }
+        }
+    }
+
+    pub fn x(&mut self, q: usize) {
+        let bit = 1 << q;
+        for i in 0..self.amps.len() {
+            if i & bit == 0 {
+                self.amps.swap(i, i | bit);
+            }
+        }
+    }
+
+    pub fn cnot(&mut self, control: usize, target: usize) {
+        let (cb, tb) = (1 << control, 1 << target);
+        for i in 0..self.amps.len() {
+            if i & cb != 0 && i & tb == 0 {
+                self.amps.swap(i, i | tb);
+            }
+        }
+    }
+
+    /// Collapse qubit `q` using `r` in [0, 1) as the random draw; returns 0 or 1.
+    pub fn measure(&mut self, q: usize, r: f64) -> u8 {
+        let bit = 1 << q;
+        let p1: f64 = self.amps.iter().enumerate().filter(|(i, _)| i & bit != 0).map(|(_, a)| a * a).sum();
+        let outcome = u8::from(r < p1);
+        let keep = if outcome == 1 { p1 } else { 1.0 - p1 };
+        let norm = keep.sqrt();
+        for (i, a) in self.amps.iter_mut().enumerate() {
+            if ((i & bit != 0) as u8) == outcome {
+                *a /= norm;
+            } else {
+                *a = 0.0;
+            }
+        }
+        self.outcomes[q] = Some(outcome);
+        outcome
+    }
+
+    /// Return every qubit to |0> and forget measurements; labels keep their indices.
+    pub fn reset(&mut self) {
+        self.amps.iter_mut().for_each(|a| *a = 0.0);
+        self.amps[0] = 1.0;
+        self.outcomes.iter_mut().for_each(|o| *o = None);
+    }
+
+    /// Measured outcomes in allocation order (unmeasured qubits are skipped), e.g. "01".
+    pub fn bitstring(&self) -> String {
+        self.outcomes.iter().flatten().map(|b| if *b == 1 { '1' } else { '0' }).collect()
+    }
+}
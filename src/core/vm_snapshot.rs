@@ -0,0 +1,93 @@
+//! On-disk form of an interpreter's globals, written by `aeonmi vm snapshot`.
+//! Data is stored as tagged JSON values; functions keep their parameters and IR body but
+//! not their closure, since restoring rebinds every top-level function over the restored
+//! globals (see [`Interpreter::restore_globals`]). Builtins are stored by name and
+//! looked up again in the interpreter they are restored into.
+
+use crate::core::ir::Block;
+use crate::core::vm::{EnumDef, Env, Function, Interpreter, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    /// Names of the modules the session had evaluated, oldest first.
+    pub modules: Vec<String>,
+    pub globals: BTreeMap<String, Stored>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum Stored {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Stored>),
+    Object(BTreeMap<String, Stored>),
+    Function { params: Vec<String>, body: Block },
+    Builtin(String),
+    Enum { name: String, variants: Vec<String> },
+    Variant { name: String, variants: Vec<String>, index: usize },
+}
+
+impl Stored {
+    pub fn from_value(v: &Value) -> Stored {
+        match v {
+            Value::Null => Stored::Null,
+            Value::Bool(b) => Stored::Bool(*b),
+            Value::Number(n) => Stored::Number(*n),
+            Value::String(s) => Stored::String(s.clone()),
+            Value::Array(items) => Stored::Array(items.iter().map(Stored::from_value).collect()),
+            Value::Object(map) => Stored::Object(map.iter().map(|(k, v)| (k.clone(), Stored::from_value(v))).collect()),
+            Value::Function(f) => Stored::Function { params: f.params.clone(), body: f.body.clone() },
+            Value::Builtin(b) => Stored::Builtin(b.name.to_string()),
+            Value::Enum(def) => Stored::Enum { name: def.name.clone(), variants: def.variants.clone() },
+            Value::Variant(def, index) => Stored::Variant { name: def.name.clone(), variants: def.variants.clone(), index: *index },
+        }
+    }
+
+    /// The value again; builtins resolve against `interp` (unknown ones become null).
+    /// Enum definitions are shared per enum name, so restored variants still compare equal.
+    fn into_value(self, interp: &Interpreter, enums: &mut HashMap<String, Arc<EnumDef>>) -> Value {
+        match self {
+            Stored::Null => Value::Null,
+            Stored::Bool(b) => Value::Bool(b),
+            Stored::Number(n) => Value::Number(n),
+            Stored::String(s) => Value::String(s),
+            Stored::Array(items) => Value::Array(items.into_iter().map(|v| v.into_value(interp, enums)).collect()),
+            Stored::Object(map) => Value::Object(map.into_iter().map(|(k, v)| (k, v.into_value(interp, enums))).collect()),
+            Stored::Function { params, body } => Value::Function(Function { params, body, env: Env::new() }),
+            Stored::Builtin(name) => match interp.env.get(&name) {
+                Some(b @ Value::Builtin(_)) => b,
+                _ => Value::Null,
+            },
+            Stored::Enum { name, variants } => Value::Enum(shared_enum(enums, name, variants)),
+            Stored::Variant { name, variants, index } => Value::Variant(shared_enum(enums, name, variants), index),
+        }
+    }
+}
+
+fn shared_enum(enums: &mut HashMap<String, Arc<EnumDef>>, name: String, variants: Vec<String>) -> Arc<EnumDef> {
+    enums.entry(name.clone()).or_insert_with(|| Arc::new(EnumDef { name, variants })).clone()
+}
+
+/// The globals of `interp`, tagged with the session's module names.
+pub fn capture(interp: &Interpreter, modules: &[String]) -> Snapshot {
+    let globals = interp.globals().iter().map(|(k, v)| (k.clone(), Stored::from_value(v))).collect();
+    Snapshot { version: SNAPSHOT_VERSION, modules: modules.to_vec(), globals }
+}
+
+/// Bind the snapshot's globals in `interp`.
+pub fn restore(snapshot: Snapshot, interp: &mut Interpreter) -> Result<(), String> {
+    if snapshot.version != SNAPSHOT_VERSION {
+        return Err(format!("unsupported snapshot version {} (expected {})", snapshot.version, SNAPSHOT_VERSION));
+    }
+    let mut enums = HashMap::new();
+    let globals = snapshot.globals.into_iter().map(|(k, v)| (k, v.into_value(interp, &mut enums))).collect();
+    interp.restore_globals(globals).map_err(|e| e.message)
+}
@@ -0,0 +1,331 @@
+//! Record/replay of nondeterministic VM builtins (`aeonmi run --record` / `--replay`).
+//!
+//! Every call whose result can differ between runs (`rand`, `time_ms`, `measure`,
+//! `env`, `read_line`, `read_all`) goes through [`Trace::step`]. Recording appends
+//! the live result; replaying hands back the recorded one and fails with a
+//! [`Divergence`] when the program asks for something else. `measure` records the random draw that picked the outcome,
+//! so the state collapse replays exactly.
+//!
+//! File layout: `AEOT`, a version byte, then entries until EOF. Each entry is a kind
+//! byte, line and column as LEB128 varints, then the payload: `f64` LE for rand and
+//! measure, a varint for time_ms, and for env and the input reads the name (empty
+//! for reads) plus a 0/1 flag and value (strings are varint length + UTF-8).
+use std::fmt;
+use std::path::Path;
+
+pub const MAGIC: &[u8; 4] = b"AEOT";
+pub const VERSION: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Rand,
+    TimeMs,
+    Measure,
+    Env,
+    ReadLine,
+    ReadAll,
+}
+
+impl Kind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Rand => "rand",
+            Kind::TimeMs => "time_ms",
+            Kind::Measure => "measure",
+            Kind::Env => "env",
+            Kind::ReadLine => "read_line",
+            Kind::ReadAll => "read_all",
+        }
+    }
+
+    fn tag(self) -> u8 {
+        match self {
+            Kind::Rand => 1,
+            Kind::TimeMs => 2,
+            Kind::Measure => 3,
+            Kind::Env => 4,
+            Kind::ReadLine => 5,
+            Kind::ReadAll => 6,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            1 => Some(Kind::Rand),
+            2 => Some(Kind::TimeMs),
+            3 => Some(Kind::Measure),
+            4 => Some(Kind::Env),
+            5 => Some(Kind::ReadLine),
+            6 => Some(Kind::ReadAll),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Payload {
+    Number(f64),
+    Text(Option<String>),
+}
+
+impl Payload {
+    pub fn number(&self) -> f64 {
+        match self {
+            Payload::Number(n) => *n,
+            Payload::Text(_) => f64::NAN,
+        }
+    }
+
+    pub fn text(self) -> Option<String> {
+        match self {
+            Payload::Text(t) => t,
+            Payload::Number(_) => None,
+        }
+    }
+}
+
+/// One recorded call. `key` is the variable name for `env`, empty otherwise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub kind: Kind,
+    pub key: String,
+    pub value: Payload,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Entry {
+    fn describe(&self) -> String {
+        describe(self.kind, &self.key, self.line, self.column)
+    }
+}
+
+fn describe(kind: Kind, key: &str, line: usize, column: usize) -> String {
+    let call = if key.is_empty() { kind.name().to_string() } else { format!("{}(\"{}\")", kind.name(), key) };
+    if line == 0 {
+        call
+    } else {
+        format!("{} at {}:{}", call, line, column)
+    }
+}
+
+/// Replay no longer matches the program: `index` is the 1-based call number.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub index: usize,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "replay diverged at call #{}", self.index)?;
+        if self.line > 0 {
+            write!(f, " ({}:{})", self.line, self.column)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Trace {
+    Record(Vec<Entry>),
+    Replay { entries: Vec<Entry>, next: usize },
+}
+
+impl Trace {
+    pub fn recording() -> Self {
+        Trace::Record(Vec::new())
+    }
+
+    pub fn replaying(entries: Vec<Entry>) -> Self {
+        Trace::Replay { entries, next: 0 }
+    }
+
+    /// Route one nondeterministic call through the trace. `live` is only evaluated when recording.
+    pub fn step(
+        &mut self,
+        kind: Kind,
+        key: &str,
+        (line, column): (usize, usize),
+        live: impl FnOnce() -> Payload,
+    ) -> Result<Payload, Divergence> {
+        match self {
+            Trace::Record(entries) => {
+                let value = live();
+                entries.push(Entry { kind, key: key.to_string(), value: value.clone(), line, column });
+                Ok(value)
+            }
+            Trace::Replay { entries, next } => {
+                let index = *next + 1;
+                let diverged = |message: String| Divergence { index, line, column, message };
+                let Some(rec) = entries.get(*next) else {
+                    return Err(diverged(format!(
+                        "program called {} but the trace ends after {} calls",
+                        describe(kind, key, 0, 0),
+                        entries.len()
+                    )));
+                };
+                if rec.kind != kind || rec.key != key {
+                    return Err(diverged(format!(
+                        "program called {} but the trace recorded {}",
+                        describe(kind, key, 0, 0),
+                        rec.describe()
+                    )));
+                }
+                *next += 1;
+                Ok(rec.value.clone())
+            }
+        }
+    }
+
+    /// After a successful run: a replay that left calls unconsumed took a different path.
+    pub fn finish(&self) -> Result<(), Divergence> {
+        match self {
+            Trace::Replay { entries, next } if *next < entries.len() => {
+                let rec = &entries[*next];
+                Err(Divergence {
+                    index: next + 1,
+                    line: rec.line,
+                    column: rec.column,
+                    message: format!(
+                        "program finished after {} calls but the trace recorded {} (next: {})",
+                        next,
+                        entries.len(),
+                        rec.describe()
+                    ),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        match self {
+            Trace::Record(entries) | Trace::Replay { entries, .. } => entries,
+        }
+    }
+}
+
+// ---------- Encoding ----------
+
+fn put_varint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+fn put_str(out: &mut Vec<u8>, s: &str) {
+    put_varint(out, s.len() as u64);
+    out.extend_from_slice(s.as_bytes());
+}
+
+pub fn encode(entries: &[Entry]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(5 + entries.len() * 12);
+    out.extend_from_slice(MAGIC);
+    out.push(VERSION);
+    for e in entries {
+        out.push(e.kind.tag());
+        put_varint(&mut out, e.line as u64);
+        put_varint(&mut out, e.column as u64);
+        match (e.kind, &e.value) {
+            (Kind::TimeMs, v) => put_varint(&mut out, v.number().max(0.0) as u64),
+            (Kind::Env | Kind::ReadLine | Kind::ReadAll, Payload::Text(v)) => {
+                put_str(&mut out, &e.key);
+                match v {
+                    Some(s) => {
+                        out.push(1);
+                        put_str(&mut out, s);
+                    }
+                    None => out.push(0),
+                }
+            }
+            (Kind::Env | Kind::ReadLine | Kind::ReadAll, Payload::Number(_)) => {
+                put_str(&mut out, &e.key);
+                out.push(0);
+            }
+            (_, v) => out.extend_from_slice(&v.number().to_le_bytes()),
+        }
+    }
+    out
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Result<u8, String> {
+        let b = *self.buf.get(self.pos).ok_or("truncated trace")?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut v = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.byte()?;
+            v |= u64::from(b & 0x7f) << shift;
+            if b & 0x80 == 0 {
+                return Ok(v);
+            }
+        }
+        Err("malformed varint in trace".into())
+    }
+
+    fn f64(&mut self) -> Result<f64, String> {
+        let end = self.pos + 8;
+        let bytes = self.buf.get(self.pos..end).ok_or("truncated trace")?;
+        self.pos = end;
+        Ok(f64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = self.varint()? as usize;
+        let end = self.pos.checked_add(len).ok_or("truncated trace")?;
+        let bytes = self.buf.get(self.pos..end).ok_or("truncated trace")?;
+        self.pos = end;
+        String::from_utf8(bytes.to_vec()).map_err(|_| "invalid UTF-8 in trace".to_string())
+    }
+}
+
+pub fn decode(buf: &[u8]) -> Result<Vec<Entry>, String> {
+    if buf.len() < 5 || &buf[..4] != MAGIC {
+        return Err("not an aeonmi trace file".into());
+    }
+    if buf[4] != VERSION {
+        return Err(format!("unsupported trace version {} (this build reads version {})", buf[4], VERSION));
+    }
+    let mut r = Reader { buf, pos: 5 };
+    let mut entries = Vec::new();
+    while r.pos < buf.len() {
+        let tag = r.byte()?;
+        let kind = Kind::from_tag(tag).ok_or_else(|| format!("unknown trace entry kind {}", tag))?;
+        let line = r.varint()? as usize;
+        let column = r.varint()? as usize;
+        let (key, value) = match kind {
+            Kind::TimeMs => (String::new(), Payload::Number(r.varint()? as f64)),
+            Kind::Env | Kind::ReadLine | Kind::ReadAll => {
+                let key = r.string()?;
+                let value = if r.byte()? == 1 { Some(r.string()?) } else { None };
+                (key, Payload::Text(value))
+            }
+            Kind::Rand | Kind::Measure => (String::new(), Payload::Number(r.f64()?)),
+        };
+        entries.push(Entry { kind, key, value, line, column });
+    }
+    Ok(entries)
+}
+
+pub fn save(path: &Path, entries: &[Entry]) -> std::io::Result<()> {
+    std::fs::write(path, encode(entries))
+}
+
+pub fn load(path: &Path) -> Result<Vec<Entry>, String> {
+    let buf = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    decode(&buf).map_err(|e| format!("{}: {}", path.display(), e))
+}
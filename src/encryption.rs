@@ -146,7 +146,7 @@ pub fn quantum_encrypt(
         )
         .map_err(|e| anyhow!("aes-gcm encryption failure: {e}"))?;
 
-    let (kem_ciphertext, shared_secret) = kyber1024::encapsulate(&runtime.kyber_public);
+    let (shared_secret, kem_ciphertext) = kyber1024::encapsulate(&runtime.kyber_public);
 
     // Bind ciphertext + shared secret to produce a tamper-evident tag.
     let mut hasher = Sha512::new();
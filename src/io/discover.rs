@@ -0,0 +1,124 @@
+//! Input discovery for batch commands (`format`, `lint`, `typecheck`).
+//!
+//! Each input is a file (taken as-is, whatever its extension), a directory (walked
+//! recursively), or a glob such as `src/**/*.ai`. Walks skip `target/`, `.git/` and
+//! `node_modules/` and keep only files whose extension is in `exts`. Globs may use `\`
+//! as a separator, as typed on Windows. `excludes` are globs matched against the
+//! whole path, or against any single path component when they contain no `/`.
+//! The result is sorted and deduplicated so output order never depends on the walk.
+
+use anyhow::{Context, Result};
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::commands::exit_codes::{self, fail};
+
+/// Directory names never descended into.
+pub const IGNORED_DIRS: &[&str] = &["target", ".git", "node_modules"];
+
+/// Extensions used when a command is not given `--ext`.
+pub const DEFAULT_EXTS: &[&str] = &["ai"];
+
+fn is_glob(s: &str) -> bool {
+    s.contains(['*', '?', '[', '{'])
+}
+
+/// Forward-slash form of `p`, so patterns and paths compare the same on every platform.
+fn slashed(p: &Path) -> String {
+    p.to_string_lossy().replace('\\', "/")
+}
+
+fn glob(pattern: &str) -> Result<Glob> {
+    GlobBuilder::new(pattern).literal_separator(true).build().map_err(|e| fail(exit_codes::USAGE, format!("bad glob '{pattern}': {e}")))
+}
+
+struct Excludes {
+    paths: GlobSet,
+    components: GlobSet,
+}
+
+impl Excludes {
+    fn new(patterns: &[String]) -> Result<Self> {
+        let (mut paths, mut components) = (GlobSetBuilder::new(), GlobSetBuilder::new());
+        for p in patterns {
+            let p = p.replace('\\', "/");
+            let p = p.trim_end_matches('/');
+            if p.contains('/') { paths.add(glob(p)?); } else { components.add(glob(p)?); }
+        }
+        let build = |b: GlobSetBuilder| b.build().map_err(|e| fail(exit_codes::USAGE, format!("bad --exclude: {e}")));
+        Ok(Excludes { paths: build(paths)?, components: build(components)? })
+    }
+
+    fn matches(&self, p: &Path) -> bool {
+        let s = slashed(p);
+        let s = s.strip_prefix("./").unwrap_or(&s);
+        self.paths.is_match(s) || s.split('/').any(|c| self.components.is_match(c))
+    }
+}
+
+fn has_ext(p: &Path, exts: &[String]) -> bool {
+    p.extension().and_then(|e| e.to_str()).is_some_and(|e| exts.iter().any(|x| x.trim_start_matches('.').eq_ignore_ascii_case(e)))
+}
+
+/// Feed files under `root` with a wanted extension to `visit` in walk order, skipping
+/// ignored and excluded directories; stops early once `visit` returns false.
+fn visit_files(root: &Path, exts: &[String], excludes: &Excludes, visit: &mut dyn FnMut(PathBuf) -> bool) -> Result<()> {
+    let entries = WalkDir::new(root).into_iter().filter_entry(|e| {
+        e.depth() == 0 || !(e.file_type().is_dir() && e.file_name().to_str().is_some_and(|n| IGNORED_DIRS.contains(&n)) || excludes.matches(e.path()))
+    });
+    for entry in entries {
+        let entry = entry.with_context(|| format!("walking {}", root.display()))?;
+        if entry.file_type().is_file() && has_ext(entry.path(), exts) && !visit(entry.into_path()) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn walk(root: &Path, exts: &[String], excludes: &Excludes, out: &mut Vec<PathBuf>) -> Result<()> {
+    visit_files(root, exts, excludes, &mut |p| {
+        out.push(p);
+        true
+    })
+}
+
+/// Unsorted, streaming form of a directory walk for callers that cannot wait for the
+/// whole tree (the editor's file picker): same skipped directories, no excludes.
+pub fn walk_each(root: &Path, exts: &[String], mut visit: impl FnMut(PathBuf) -> bool) -> Result<()> {
+    visit_files(root, exts, &Excludes::new(&[])?, &mut visit)
+}
+
+/// Expand `inputs` into the sorted list of files to process; an empty `exts` means [`DEFAULT_EXTS`].
+pub fn discover(inputs: &[PathBuf], exts: &[String], excludes: &[String]) -> Result<Vec<PathBuf>> {
+    let exts: Vec<String> = if exts.is_empty() { DEFAULT_EXTS.iter().map(|e| e.to_string()).collect() } else { exts.to_vec() };
+    let excludes = Excludes::new(excludes)?;
+    let mut out = Vec::new();
+    for input in inputs {
+        let text = slashed(input);
+        if is_glob(&text) {
+            // Walk from the longest literal prefix, then keep what the whole pattern matches.
+            let literal: Vec<&str> = text.split('/').take_while(|c| !is_glob(c)).collect();
+            let root = if literal.is_empty() { PathBuf::from(".") } else { PathBuf::from(literal.join("/") + "/") };
+            let pattern = glob(text.strip_prefix("./").unwrap_or(&text))?.compile_matcher();
+            let mut found = Vec::new();
+            walk(&root, &exts, &excludes, &mut found)?;
+            out.extend(found.into_iter().filter(|p| {
+                let s = slashed(p);
+                pattern.is_match(s.strip_prefix("./").unwrap_or(&s))
+            }));
+        } else if input.is_dir() {
+            walk(input, &exts, &excludes, &mut out)?;
+        } else if !excludes.matches(input) {
+            out.push(input.clone());
+        }
+    }
+    for p in out.iter_mut() {
+        if let Ok(rest) = p.strip_prefix(".") {
+            *p = rest.to_path_buf();
+        }
+    }
+    out.sort();
+    out.dedup();
+    Ok(out)
+}
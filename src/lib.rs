@@ -1,11 +1,13 @@
 #![allow(dead_code)] // Large experimental surface; many analysis/AI/quantum scaffolds not yet invoked.
 // Make the same modules available from the library crate so anything under
 // src/tui/* (compiled as part of lib) can reach them via `crate::...`.
+#[macro_use] // first, so debug_log! / trace_log! are in scope for the modules below
+pub mod core;
+pub mod ai;
 pub mod cli;
 pub mod cli_vault;
 pub mod commands;
 pub mod config;
-pub mod core;
 pub mod encryption;
 pub mod io;
 pub mod tui;
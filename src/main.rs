@@ -1,17 +1,11 @@
-mod ai; // AI provider registry & implementations
-mod cli;
-mod cli_vault;
-mod commands;
-mod config; // resolve_config_path, etc.
-/// Aeonmi/QUBE main — subcommands + back-compat + neon shell by default.
-mod core;
-mod encryption;
-mod io;
-mod shell;
-mod tui; // tui::editor // neon Shard shell
-mod vault;
+// The library provides every module but the shell; compiling them again here would only
+// duplicate them (and their dead-code warnings) in each binary.
+#[macro_use]
+extern crate aeonmi_project;
+use aeonmi_project::{ai, cli, commands, config, core, tui};
+mod shell; // neon Shard shell
 
-use clap::Parser; // trait import enables AeonmiCli::parse()
+use clap::{CommandFactory, FromArgMatches};
 use std::path::PathBuf;
 
 #[cfg(feature = "quantum")]
@@ -25,22 +19,65 @@ fn set_console_title() {
     let _ = execute!(std::io::stdout(), SetTitle("Aeonmi Shard"));
 }
 
-fn main() -> anyhow::Result<()> {
-    println!("DEBUG: main() called");
-    set_console_title();
+fn main() {
+    let result = run_cli();
+    crate::core::usage_stats::finish(result.is_ok());
+    if let Err(e) = result {
+        if !commands::exit_codes::is_reported(&e) && !crate::core::diagnostics::quiet() {
+            eprintln!("Error: {:?}", e);
+        }
+        std::process::exit(commands::exit_codes::code_for(&e));
+    }
+}
 
-    let args = AeonmiCli::parse();
+fn run_cli() -> anyhow::Result<()> {
+    // `[defaults]` from config become flags; anything typed on the command line wins.
+    let (argv, default_warnings) = commands::defaults::apply(std::env::args_os().collect());
+    let matches = AeonmiCli::command().get_matches_from(argv);
+    let args = AeonmiCli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    crate::core::diagnostics::set_output_mode(args.quiet, args.diag_json);
+    crate::core::diagnostics::set_max_diagnostics(args.max_diagnostics);
+    crate::core::ir_verify::set_forced(args.verify_ir);
+    crate::core::analysis_budget::set_timeout_ms(args.analysis_timeout_ms);
+    if args.verbose > 0 {
+        crate::core::debug::set_verbosity(args.verbose);
+    }
+    let style = crate::core::style::init(args.no_unicode);
+    commands::defaults::print_warnings(&default_warnings);
+    // AEONMI_NATIVE is honoured as the starting mode only; nothing sets it afterwards.
+    commands::run::ExecutionMode::startup();
+
+    if !args.quiet {
+        // the title is an OSC escape; keep it out of logs and dumb terminals
+        if style.color && std::io::IsTerminal::is_terminal(&std::io::stdout()) {
+            set_console_title();
+        }
+    }
 
     let cfg_path = resolve_config_path(&args.config);
+    // `stats` itself is not counted; no subcommand means the shell.
+    let command = commands::defaults::command_path(&matches);
+    if command.first().map(String::as_str) != Some("stats") {
+        let command = if command.is_empty() { "shell".to_string() } else { command.join(" ") };
+        crate::core::usage_stats::begin(&command, crate::config::StatsSettings::load(cfg_path.as_deref()).enabled);
+    }
 
     // Guarantee a stub metrics file exists for tooling even before GUI loads.
     crate::core::incremental::ensure_metrics_file_exists();
     // Install shutdown flush guard so metrics are persisted on normal process exit.
     let _metrics_guard = crate::core::incremental::install_shutdown_flush_guard();
-    // Ctrl-C handler to force immediate metrics persistence on abrupt termination.
+    // Ctrl-C handler: persist metrics, then cancel an in-flight AI request or ask the
+    // listening watch/serve loop to stop. A second press (or nothing listening) exits now.
     {
         let _ = ctrlc::set_handler(|| {
             crate::core::incremental::force_persist_metrics();
+            if crate::ai::cancel::interrupt() {
+                return;
+            }
+            if crate::core::shutdown::signal() == crate::core::shutdown::Signal::Force {
+                eprintln!("\ninterrupted");
+                std::process::exit(crate::core::shutdown::FORCED_EXIT_CODE);
+            }
         });
     }
 
@@ -48,24 +85,12 @@ fn main() -> anyhow::Result<()> {
     if args.metrics_dump_flag {
         crate::core::incremental::load_metrics();
         use crate::core::incremental::{
-            get_deep_propagation, CALL_GRAPH_METRICS, FUNCTION_METRICS, SAVINGS_METRICS, VAR_DEPS,
+            get_deep_propagation, lock_cache, CALL_GRAPH_METRICS, FUNCTION_METRICS, SAVINGS_METRICS, VAR_DEPS,
         };
-        let m = CALL_GRAPH_METRICS
-            .lock()
-            .ok()
-            .map(|g| g.clone())
-            .unwrap_or_default();
-        let v = VAR_DEPS.lock().ok().map(|g| g.clone()).unwrap_or_default();
-        let fm = FUNCTION_METRICS
-            .lock()
-            .ok()
-            .map(|g| g.clone())
-            .unwrap_or_default();
-        let sm = SAVINGS_METRICS
-            .lock()
-            .ok()
-            .map(|g| g.clone())
-            .unwrap_or_default();
+        let m = lock_cache("call_graph", &CALL_GRAPH_METRICS).clone();
+        let v = lock_cache("var_deps", &VAR_DEPS).clone();
+        let fm = lock_cache("function_metrics", &FUNCTION_METRICS).clone();
+        let sm = lock_cache("savings", &SAVINGS_METRICS).clone();
         let function_metrics: std::collections::HashMap<String, serde_json::Value> = fm.iter().map(|(idx, fm)| (
             idx.to_string(),
             serde_json::json!({ "runs": fm.runs, "total_ns": fm.total_ns, "last_ns": fm.last_ns, "avg_ns": if fm.runs>0 { fm.total_ns / fm.runs as u128 } else { 0 } })
@@ -110,7 +135,7 @@ fn main() -> anyhow::Result<()> {
             Some("ai") => EmitKind::Ai,
             Some(other) => {
                 eprintln!("Unsupported --emit kind: {}", other);
-                proc_exit(2);
+                proc_exit(commands::exit_codes::USAGE);
             }
         };
 
@@ -124,6 +149,7 @@ fn main() -> anyhow::Result<()> {
             .out_legacy
             .clone()
             .unwrap_or_else(|| PathBuf::from(default_out));
+        let out = commands::compile::output_target(&input, out, false)?;
 
         return commands::compile::compile_pipeline(
             Some(input),
@@ -143,50 +169,36 @@ fn main() -> anyhow::Result<()> {
             input,
             emit,
             out,
+            no_overwrite,
             tokens,
             ast,
             debug_titan,
             watch,
+            diag_sidecar,
+            source_map,
         }) => {
-            if watch {
-                use std::thread::sleep;
-                use std::time::{Duration, SystemTime};
-                let mut last_mtime = std::fs::metadata(&input)
-                    .and_then(|m| m.modified())
-                    .unwrap_or(SystemTime::UNIX_EPOCH);
-                loop {
-                    let _ = commands::compile::compile_pipeline(
-                        Some(input.clone()),
-                        emit,
-                        out.clone(),
-                        tokens,
-                        ast,
-                        args.pretty_errors,
-                        args.no_sema,
-                        debug_titan,
-                    );
-                    sleep(Duration::from_millis(500));
-                    if let Ok(meta) = std::fs::metadata(&input) {
-                        if let Ok(m) = meta.modified() {
-                            if m > last_mtime {
-                                last_mtime = m;
-                                println!("[watch] detected change, rebuilding...");
-                                continue;
-                            }
-                        }
-                    }
+            let out = commands::compile::output_target(&input, out, no_overwrite)?;
+            if diag_sidecar && commands::compile::is_stdout(&out) {
+                return Err(commands::exit_codes::fail(commands::exit_codes::USAGE, "--diag-sidecar needs an output file, not `-`"));
+            }
+            if source_map && commands::compile::is_stdout(&out) {
+                return Err(commands::exit_codes::fail(commands::exit_codes::USAGE, "--source-map needs an output file, not `-`"));
+            }
+            if source_map && !matches!(emit, EmitKind::Js) {
+                return Err(commands::exit_codes::fail(commands::exit_codes::USAGE, "--source-map only applies to --emit js"));
+            }
+            let compile = |input: PathBuf, out: PathBuf| {
+                if diag_sidecar || source_map {
+                    let extras = commands::compile::Extras { diag_sidecar, source_map, ..Default::default() };
+                    commands::compile::compile_pipeline_with_extras(Some(input), emit, out, tokens, ast, args.pretty_errors, args.no_sema, extras)
+                } else {
+                    commands::compile::compile_pipeline(Some(input), emit, out, tokens, ast, args.pretty_errors, args.no_sema, debug_titan)
                 }
+            };
+            if watch {
+                commands::watch::watch_ai(&input, |_| compile(input.clone(), out.clone()))
             } else {
-                commands::compile::compile_pipeline(
-                    Some(input),
-                    emit,
-                    out,
-                    tokens,
-                    ast,
-                    args.pretty_errors,
-                    args.no_sema,
-                    debug_titan,
-                )
+                compile(input, out)
             }
         }
 
@@ -200,55 +212,49 @@ fn main() -> anyhow::Result<()> {
             opt_stats,
             opt_stats_json,
             disasm,
+            record,
+            replay,
+            max_memory_mb,
+            source_map,
         }) => {
+            let limits = crate::core::vm::Limits { max_memory_bytes: max_memory_mb.map(|mb| mb * 1024 * 1024), ..Default::default() };
+            let traced = record.is_some() || replay.is_some() || max_memory_mb.is_some();
+            let mode = commands::run::ExecutionMode::resolve(native);
             if watch {
-                use std::thread::sleep;
-                use std::time::{Duration, SystemTime};
-                let mut last_mtime = std::fs::metadata(&input)
-                    .and_then(|m| m.modified())
-                    .unwrap_or(SystemTime::UNIX_EPOCH);
-                loop {
-                    let _ = {
-                        // Optional AI emit only
-                        if let Some(ai_path) = &emit_ai {
-                            let _ = commands::compile::compile_pipeline(
-                                Some(input.clone()),
-                                EmitKind::Ai,
-                                ai_path.clone(),
-                                false,
-                                false,
-                                args.pretty_errors,
-                                args.no_sema,
-                                args.debug_titan,
-                            );
-                        }
-                        if native || std::env::var("AEONMI_NATIVE").ok().as_deref() == Some("1") {
-                            std::env::set_var("AEONMI_NATIVE", "1");
-                            crate::commands::run::run_native(
-                                &input,
-                                args.pretty_errors,
-                                args.no_sema,
-                            )
-                        } else {
-                            commands::run::main_with_opts(
-                                input.clone(),
-                                out.clone(),
-                                args.pretty_errors,
-                                args.no_sema,
-                            )
-                        }
-                    };
-                    sleep(Duration::from_millis(500));
-                    if let Ok(meta) = std::fs::metadata(&input) {
-                        if let Ok(m) = meta.modified() {
-                            if m > last_mtime {
-                                last_mtime = m;
-                                println!("[watch] detected change, rerunning...");
-                                continue;
-                            }
-                        }
+                commands::watch::watch_ai(&input, |_| {
+                    // Optional AI emit only
+                    if let Some(ai_path) = &emit_ai {
+                        let _ = commands::compile::compile_pipeline(
+                            Some(input.clone()),
+                            EmitKind::Ai,
+                            ai_path.clone(),
+                            false,
+                            false,
+                            args.pretty_errors,
+                            args.no_sema,
+                            args.debug_titan,
+                        );
                     }
-                }
+                    if traced || mode == commands::run::ExecutionMode::Native {
+                        crate::commands::run::run_native_traced(
+                            &input,
+                            args.pretty_errors,
+                            args.no_sema,
+                            record.as_deref(),
+                            replay.as_deref(),
+                            limits.clone(),
+                        )
+                    } else {
+                        commands::run::main_with_opts(
+                            input.clone(),
+                            out.clone(),
+                            args.pretty_errors,
+                            args.no_sema,
+                            mode,
+                            source_map,
+                        )
+                    }
+                })
             } else {
                 // Single run
                 if let Some(ai_path) = &emit_ai {
@@ -263,6 +269,16 @@ fn main() -> anyhow::Result<()> {
                         args.debug_titan,
                     );
                 }
+                if traced {
+                    return commands::run::run_native_traced(
+                        &input,
+                        args.pretty_errors,
+                        args.no_sema,
+                        record.as_deref(),
+                        replay.as_deref(),
+                        limits,
+                    );
+                }
                 if cfg!(feature = "bytecode")
                     && (bytecode
                         || disasm
@@ -301,12 +317,27 @@ fn main() -> anyhow::Result<()> {
                                 return Ok(());
                             }
                         };
-                        let chunk = BytecodeCompiler::new().compile(&ast);
+                        let chunk = match BytecodeCompiler::new().try_compile(&ast) {
+                            Ok(c) => c,
+                            Err(unsupported) => {
+                                for u in &unsupported {
+                                    eprintln!("{}: {}", input.display(), u);
+                                }
+                                return Err(commands::exit_codes::reported(
+                                    commands::exit_codes::COMPILE_DIAG,
+                                    format!("{} construct(s) not supported by the bytecode backend; run without --bytecode", unsupported.len()),
+                                ));
+                            }
+                        };
                         if disasm {
                             println!("{}", disassemble(&chunk));
                         }
                         let mut vm = VM::new(&chunk);
                         let result = vm.run();
+                        if let Some(e) = vm.error.take() {
+                            eprintln!("{} runtime error: {}", crate::core::style::current().paint("error:", crate::core::style::Role::Error), e);
+                            return Err(commands::exit_codes::reported(commands::exit_codes::RUNTIME, format!("runtime error: {}", e)));
+                        }
                         if let Some(r) = result {
                             println!("bytecode result: {:?}", r);
                         }
@@ -347,51 +378,89 @@ fn main() -> anyhow::Result<()> {
                             eprintln!("warning: stack overflow detected (frame limit)");
                         }
                     }
-                } else if native || std::env::var("AEONMI_NATIVE").ok().as_deref() == Some("1") {
-                    std::env::set_var("AEONMI_NATIVE", "1");
-                    return commands::run::main_with_opts(
-                        input,
-                        out,
-                        args.pretty_errors,
-                        args.no_sema,
-                    );
                 } else {
                     return commands::run::main_with_opts(
                         input,
                         out,
                         args.pretty_errors,
                         args.no_sema,
+                        mode,
+                        source_map,
                     );
                 }
                 Ok(())
             }
         }
 
+        Some(Command::Build { input, out, explain }) => match (explain, input) {
+            (Some(artifact), _) => commands::build::explain(&artifact),
+            // clap requires INPUT without --explain
+            (None, input) => commands::build::main(&input.unwrap_or_default(), &out, args.pretty_errors, args.no_sema),
+        },
+
+        Some(Command::Quantum {
+            action: Some(cli::QuantumAction::Import { file, emit, out }),
+            ..
+        }) => commands::circuit::import(&file, emit, out.as_deref()),
+
+        Some(Command::Quantum {
+            action: Some(cli::QuantumAction::Compare { a, b, json }),
+            ..
+        }) => commands::quantum_compare::main(&a, &b, json),
+
+        Some(Command::Quantum {
+            action: Some(cli::QuantumAction::Template { name, qubits, marked, state, shots, out }),
+            ..
+        }) => {
+            let params = [("qubits", qubits), ("marked", marked), ("state", state), ("shots", shots)];
+            let params = params.into_iter().filter_map(|(k, v)| v.map(|v| (k.to_string(), v))).collect();
+            commands::circuit::template(name.as_deref(), &params, out.as_deref())
+        }
+
         Some(Command::Quantum {
-            backend: _,
-            file: _,
-            shots: _,
+            action: None,
+            backend: _backend,
+            file: _file,
+            shots: _shots,
+            transpile_level: _transpile_level,
+            basis_gates: _basis_gates,
+            fake_backend: _fake_backend,
+            seed: _seed,
+            save_run: _save_run,
         }) => {
             #[cfg(feature = "quantum")]
             {
+                // clap enforces both when no subcommand is given
+                let (backend, file, shots) = (_backend.expect("backend"), _file.expect("file"), _shots);
                 let backend_str = match backend {
                     BackendKind::Titan => "titan",
                     BackendKind::Aer => "aer",
                     BackendKind::Ibmq => "ibmq",
                 };
-                return commands::quantum::quantum_run(file, backend_str, shots);
+                let transpile = commands::quantum::TranspileOptions { level: _transpile_level, basis_gates: _basis_gates, fake_backend: _fake_backend };
+                let record = commands::quantum::RecordOptions { seed: _seed, save_run: _save_run };
+                return commands::quantum::quantum_run(file, backend_str, shots, &transpile, &record);
             }
             #[cfg(not(feature = "quantum"))]
             {
                 eprintln!("The 'quantum' subcommand requires building with the `--features quantum` flag.");
-                std::process::exit(2);
+                std::process::exit(commands::exit_codes::USAGE);
             }
         }
 
-        Some(Command::Format { inputs, check }) => {
+        Some(Command::Format { inputs, discovery, check, backup, stdin, stdin_filename, style }) => {
             // Call the batch formatter. It returns 0 when no files changed,
             // 1 when files were reformatted.
-            match crate::commands::format::main(inputs, check) {
+            if stdin_filename.is_some() && !stdin {
+                return Err(commands::exit_codes::fail(commands::exit_codes::USAGE, "--stdin-filename needs --stdin"));
+            }
+            let style = style.settings();
+            let result = if stdin {
+                crate::commands::format::main_stdin(stdin_filename, check, args.pretty_errors, &style)
+            } else {
+                discovery.discover(&inputs).and_then(|paths| crate::commands::format::main(paths, check, backup, &style))
+            };
+            match result {
                 Ok(code) => {
                     if code != 0 {
                         std::process::exit(code);
@@ -402,15 +471,63 @@ fn main() -> anyhow::Result<()> {
             }
         }
 
-        Some(Command::Lint { inputs, fix }) => {
-            // TODO: hook to linter when ready
-            let _ = (inputs, fix);
-            println!("(lint) placeholder");
-            Ok(())
+        Some(Command::Doctest { files }) => match commands::doctest::main(files) {
+            Ok(commands::exit_codes::OK) => Ok(()),
+            Ok(code) => std::process::exit(code),
+            Err(e) => Err(e),
+        },
+
+        Some(Command::ExplainError { code, list }) => commands::explain_error::main(code, list),
+
+        Some(Command::Config { action: cli::ConfigAction::Effective { command, json } }) => commands::defaults::main_effective(command, cfg_path, json),
+        Some(Command::Config { action: cli::ConfigAction::Set { key, value } }) => commands::defaults::main_set(&key, &value, cfg_path),
+
+        Some(Command::Stats { action: cli::StatsAction::Show { json } }) => commands::stats::main_show(json, cfg_path),
+        Some(Command::Stats { action: cli::StatsAction::Reset }) => commands::stats::main_reset(),
+
+        Some(Command::Doctor) => commands::doctor::main(),
+
+        Some(Command::Info { json }) => commands::info::main(json, cfg_path),
+
+        Some(Command::Upgrade { check, install, yes, offline }) => commands::upgrade::main(check, install, yes, offline),
+
+        Some(Command::Examples { action }) => commands::examples::main(action, args.pretty_errors, args.no_sema),
+
+        Some(Command::Math { action: cli::MathAction::Demo { system, plot_csv, dt, t_end, r, iterations } }) => {
+            commands::math::demo(system, plot_csv.as_deref(), dt, t_end, r, iterations)
         }
+        Some(Command::Math { action: cli::MathAction::Derive { expr, wrt } }) => commands::math::derive(&expr, &wrt),
+
+        Some(Command::Ir { input, json }) => commands::ir::main(&input, json, args.pretty_errors),
+        Some(Command::Symbols { input, json, tree }) => {
+            use commands::symbols::Format;
+            let format = if json { Format::Json } else if tree { Format::Tree } else { Format::Lines };
+            commands::symbols::main(&input, format, args.pretty_errors)
+        }
+        Some(Command::Outline { input }) => commands::symbols::main(&input, commands::symbols::Format::Tree, args.pretty_errors),
+        Some(Command::Typecheck { inputs, discovery, timings, json, annotate: false }) => commands::typecheck::main(&discovery.discover(&inputs)?, timings, json, args.pretty_errors),
+        Some(Command::Typecheck { inputs, discovery, annotate: true, .. }) => commands::typecheck::annotate(&discovery.discover(&inputs)?, args.pretty_errors),
+
+        Some(Command::DiffAst { old, new, json }) => commands::diff_ast::main(&old, &new, json, args.pretty_errors),
+
+        Some(Command::Lint { inputs, discovery, fix }) => commands::lint::main_with_opts(discovery.discover(&inputs)?, fix),
 
         Some(Command::Repl) => commands::repl::main(),
 
+        Some(Command::Serve { port, host, sandbox, timeout_ms, max_body, max_output, max_string, max_memory_mb, concurrency }) => {
+            commands::serve::main(commands::serve::ServeOptions {
+                host,
+                port,
+                sandbox,
+                timeout: std::time::Duration::from_millis(timeout_ms),
+                max_body,
+                max_output,
+                max_string,
+                max_memory: max_memory_mb * 1024 * 1024,
+                concurrency: concurrency.max(1),
+            })
+        }
+
         Some(Command::Edit { file, tui }) => commands::edit::main(file, cfg_path, tui),
 
         Some(Command::New {
@@ -460,6 +577,8 @@ fn main() -> anyhow::Result<()> {
                             Some(out_js),
                             args.pretty_errors,
                             args.no_sema,
+                            commands::run::ExecutionMode::startup(),
+                            false,
                         );
                     }
                 }
@@ -471,7 +590,9 @@ fn main() -> anyhow::Result<()> {
         Some(Command::SaveAs { file }) => commands::fs::save_as(file),
         Some(Command::Close { file }) => commands::fs::close(file),
         Some(Command::Import { file }) => commands::fs::import(file),
-        Some(Command::Export { file, format }) => commands::fs::export(file, format),
+        Some(Command::Export { file, format, out, no_overwrite }) => {
+            commands::fs::export(file, format, out, no_overwrite)
+        }
         Some(Command::Upload { path }) => commands::fs::upload(path),
         Some(Command::Download { file }) => commands::fs::download(file),
 
@@ -505,13 +626,14 @@ fn main() -> anyhow::Result<()> {
             crate::cli::VmAction::Snapshot { name } => commands::vm::snapshot(name),
             crate::cli::VmAction::Restore { name } => commands::vm::restore(name),
             crate::cli::VmAction::Mount { dir } => commands::vm::mount(dir),
+            crate::cli::VmAction::Eval { source, file } => commands::vm::eval(source, file),
+            crate::cli::VmAction::Daemon => commands::vm::daemon(),
         },
 
         Some(Command::Ai { action }) => {
             match action {
-                crate::cli::AiAction::Suggest => {
-                    println!("ai: suggest (placeholder)");
-                    Ok(())
+                crate::cli::AiAction::Suggest { prompt, insert_at, provider } => {
+                    commands::ai_suggest::main(prompt, insert_at, provider, cfg_path)
                 }
                 crate::cli::AiAction::Debug => {
                     println!("ai: debug (placeholder)");
@@ -529,11 +651,24 @@ fn main() -> anyhow::Result<()> {
                     println!("ai: refactor {:?}", rule);
                     Ok(())
                 }
+                crate::cli::AiAction::Usage { since, json } => commands::ai_usage::main(since, json, cfg_path),
+                crate::cli::AiAction::Review { staged, range, provider, offline, json } => {
+                    let source = match (staged, range) {
+                        (_, Some(r)) => commands::ai_review::DiffSource::Range(r),
+                        (true, None) => commands::ai_review::DiffSource::Staged,
+                        (false, None) => commands::ai_review::DiffSource::WorkTree,
+                    };
+                    commands::ai_review::main(source, offline, provider, json, cfg_path)
+                }
                 crate::cli::AiAction::Chat {
                     provider,
                     prompt,
                     list,
                     stream,
+                    markdown,
+                    copy,
+                    force,
+                    json,
                 } => {
                     use crate::ai::AiRegistry;
                     let reg = AiRegistry::new();
@@ -548,23 +683,21 @@ fn main() -> anyhow::Result<()> {
                         }
                         return Ok(());
                     }
-                    let prov_name = provider.or_else(|| reg.list().first().map(|s| s.to_string()));
-                    if prov_name.is_none() {
+                    if reg.list().is_empty() {
                         println!("No AI providers enabled. Build with feature flags (e.g. --features ai-openai)");
                         return Ok(());
                     }
-                    let chosen = prov_name.unwrap();
-                    let prov = match reg.get(&chosen) {
-                        Some(p) => p,
-                        None => {
-                            println!(
-                                "Provider '{}' not found in enabled set: {:?}",
-                                chosen,
-                                reg.list()
-                            );
-                            return Ok(());
-                        }
-                    };
+                    if let Some(chosen) = provider.as_deref().filter(|p| reg.get(p).is_none()) {
+                        println!(
+                            "Provider '{}' not found in enabled set: {:?}",
+                            chosen,
+                            reg.list()
+                        );
+                        return Ok(());
+                    }
+                    // `--provider` goes first; the configured chain takes over when it is down.
+                    let chain: Vec<String> = provider.into_iter().chain(config::AiSettings::load(cfg_path.as_deref()).fallback).collect();
+                    let reg = reg.with_fallback(chain);
                     let prompt_text = match prompt {
                         Some(t) => t,
                         None => {
@@ -574,25 +707,90 @@ fn main() -> anyhow::Result<()> {
                             buf
                         }
                     };
+                    let style = crate::core::style::current();
+                    let mut reply = None;
+                    let first = reg.chain().first().map_or("", |p| p.name());
                     if stream {
+                        use crate::ai::cancel::{self, CancelToken};
+                        use crate::ai::StreamEvent;
+                        use crate::core::style::Role;
+                        let token = CancelToken::new();
+                        cancel::set_active(Some(token.clone()));
+                        let start = std::time::Instant::now();
+                        let mut spinner = crate::ai::spinner::Spinner::start(format!("waiting for {first}"), style);
                         let mut out = String::new();
-                        let mut cb = |chunk: &str| {
-                            print!("{}", chunk);
-                            out.push_str(chunk);
-                            std::io::Write::flush(&mut std::io::stdout()).ok();
+                        let mut cb = |event: StreamEvent| match event {
+                            StreamEvent::Chunk(chunk) => {
+                                // with --markdown the spinner keeps going until the reply can be rendered
+                                if !markdown {
+                                    spinner.stop();
+                                    print!("{}", chunk);
+                                    std::io::Write::flush(&mut std::io::stdout()).ok();
+                                }
+                                out.push_str(chunk);
+                            }
+                            StreamEvent::Restart { failed, error, next } => {
+                                spinner.stop();
+                                if !markdown && !out.is_empty() && !out.ends_with('\n') {
+                                    println!();
+                                }
+                                eprintln!("{} {failed} failed ({error}); restarting with {next}", style.paint("warn:", Role::Warn));
+                                out.clear();
+                            }
                         };
-                        if let Err(e) = prov.chat_stream(&prompt_text, &mut cb) {
-                            eprintln!("chat error: {e}");
+                        let res = reg.chat_stream_with_fallback(&prompt_text, &token, &mut cb);
+                        spinner.stop();
+                        cancel::set_active(None);
+                        if markdown {
+                            print!("{}", crate::ai::markdown::render(&out, style));
+                        }
+                        if !out.ends_with('\n') {
+                            println!();
+                        }
+                        let elapsed = start.elapsed().as_secs_f64();
+                        match res {
+                            Ok((answered, _)) if !crate::core::diagnostics::quiet() => {
+                                eprintln!("{}", style.paint(&format!("[{}] {:.1}s", answered, elapsed), Role::Dim));
+                                reply = Some(out);
+                            }
+                            Ok(_) => reply = Some(out),
+                            Err(e) if cancel::is_cancelled(&e) => eprintln!(
+                                "{} cancelled after {:.1}s; the response above is partial ({} chars)",
+                                style.paint("warn:", Role::Warn),
+                                elapsed,
+                                out.chars().count()
+                            ),
+                            Err(e) => eprintln!("chat error: {e}"),
                         }
-                        println!();
                     } else {
-                        match prov.chat(&prompt_text) {
-                            Ok(resp) => {
-                                println!("{}", resp);
+                        use crate::core::style::Role;
+                        match reg.chat_with_fallback(&prompt_text) {
+                            Ok(answer) if json => {
+                                println!("{}", serde_json::to_string_pretty(&answer)?);
+                                reply = Some(answer.reply);
+                            }
+                            Ok(answer) => {
+                                for f in &answer.failed {
+                                    eprintln!("{} {} failed ({})", style.paint("warn:", Role::Warn), f.provider, f.error);
+                                }
+                                if markdown {
+                                    println!("{}", crate::ai::markdown::render(&answer.reply, style));
+                                } else {
+                                    println!("{}", answer.reply);
+                                }
+                                if !answer.failed.is_empty() && !crate::core::diagnostics::quiet() {
+                                    eprintln!("{}", style.paint(&format!("[{}] answered", answer.provider), Role::Dim));
+                                }
+                                reply = Some(answer.reply);
                             }
                             Err(e) => eprintln!("chat error: {e}"),
                         }
                     }
+                    match reply.filter(|_| copy).map(|r| crate::core::clipboard::copy(&r, force)) {
+                        Some(Ok(done)) => eprintln!("{done}"),
+                        Some(Err(e)) => return Err(commands::exit_codes::fail(commands::exit_codes::USAGE, e.to_string())),
+                        None => {}
+                    }
                     Ok(())
                 }
             }
@@ -600,34 +798,38 @@ fn main() -> anyhow::Result<()> {
 
         Some(Command::Vault { action, tui }) => commands::vault::dispatch(action, tui),
 
-        Some(Command::Cargo { args }) => {
-            // Pass through to system cargo
-            let status = std::process::Command::new("cargo").args(&args).status();
-            match status {
-                Ok(s) if s.success() => Ok(()),
-                Ok(s) => {
-                    anyhow::bail!("cargo exited with status {}", s);
-                }
-                Err(e) => anyhow::bail!("failed to execute cargo: {e}"),
-            }
+        Some(Command::Cargo { with_secrets, mut args }) => {
+            let with_secrets = commands::secrets::SecretFlags::extract(&mut args, false).with_secrets.or(with_secrets);
+            commands::cargo::passthrough(with_secrets.as_deref(), &args)
         }
 
-        Some(Command::Python { args }) => {
-            let exe = if cfg!(windows) { "python" } else { "python3" };
-            let status = std::process::Command::new(exe).args(&args).status();
+        Some(Command::Python { with_secrets, mut args }) => {
+            use crate::core::toolchain::{available, missing_tool_message, Tool};
+            if !available(Tool::Python) {
+                anyhow::bail!(missing_tool_message(Tool::Python, "run python"));
+            }
+            let with_secrets = commands::secrets::SecretFlags::extract(&mut args, false).with_secrets.or(with_secrets);
+            let secrets = commands::secrets::prepare(with_secrets.as_deref(), std::path::Path::new(config::LexerSettings::MANIFEST), false, "python")?;
+            let status = secrets.apply(&mut std::process::Command::new(Tool::Python.exe())).args(&args).status();
             match status {
                 Ok(s) if s.success() => Ok(()),
                 Ok(s) => anyhow::bail!("python exited with status {}", s),
-                Err(e) => anyhow::bail!("failed to execute python: {e}"),
+                Err(e) => Err(secrets.scrub_error(anyhow::anyhow!("failed to execute python: {e}"))),
             }
         }
 
-        Some(Command::Node { args }) => {
-            let status = std::process::Command::new("node").args(&args).status();
+        Some(Command::Node { with_secrets, mut args }) => {
+            use crate::core::toolchain::{available, missing_tool_message, Tool};
+            if !available(Tool::Node) {
+                anyhow::bail!(missing_tool_message(Tool::Node, "run node"));
+            }
+            let with_secrets = commands::secrets::SecretFlags::extract(&mut args, false).with_secrets.or(with_secrets);
+            let secrets = commands::secrets::prepare(with_secrets.as_deref(), std::path::Path::new(config::LexerSettings::MANIFEST), false, "node")?;
+            let status = secrets.apply(&mut std::process::Command::new("node")).args(&args).status();
             match status {
                 Ok(s) if s.success() => Ok(()),
                 Ok(s) => anyhow::bail!("node exited with status {}", s),
-                Err(e) => anyhow::bail!("failed to execute node: {e}"),
+                Err(e) => Err(secrets.scrub_error(anyhow::anyhow!("failed to execute node: {e}"))),
             }
         }
 
@@ -660,63 +862,10 @@ fn main() -> anyhow::Result<()> {
             Ok(())
         }
 
-        Some(Command::MetricsTop { limit, json }) => {
-            crate::core::incremental::load_metrics();
-            use crate::core::incremental::{get_deep_propagation, FUNCTION_METRICS};
-            let data = FUNCTION_METRICS
-                .lock()
-                .ok()
-                .map(|g| g.clone())
-                .unwrap_or_default();
-            // rows: idx, last, total, runs, avg, ema
-            let mut rows: Vec<(usize, u128, u128, u64, u128, u128)> = data
-                .into_iter()
-                .map(|(idx, m)| {
-                    let avg = if m.runs > 0 {
-                        m.total_ns / m.runs as u128
-                    } else {
-                        0
-                    };
-                    (idx, m.last_ns, m.total_ns, m.runs, avg, m.ema_ns)
-                })
-                .collect();
-            // Sort by EMA (recent performance) descending fallback to avg
-            rows.sort_by(|a, b| b.5.cmp(&a.5).then(b.4.cmp(&a.4)));
-            rows.truncate(limit);
-            if json {
-                let j: Vec<serde_json::Value> = rows
-                    .iter()
-                    .map(|(i, last, total, runs, avg, ema)| {
-                        serde_json::json!({
-                            "index": i,
-                            "runs": runs,
-                            "last_ns": last,
-                            "total_ns": total,
-                            "avg_ns": avg,
-                            "ema_ns": ema
-                        })
-                    })
-                    .collect();
-                println!(
-                    "{}",
-                    serde_json::to_string_pretty(&j).unwrap_or_else(|_| "[]".to_string())
-                );
-            } else {
-                use crate::core::incremental::{EMA_ALPHA_RUNTIME, WINDOW_CAP_RUNTIME};
-                let alpha = EMA_ALPHA_RUNTIME.load(std::sync::atomic::Ordering::Relaxed);
-                let win = WINDOW_CAP_RUNTIME.load(std::sync::atomic::Ordering::Relaxed);
-                println!(
-                    "(deepPropagation={} ema_alpha={} window={})",
-                    get_deep_propagation(),
-                    alpha,
-                    win
-                );
-                println!("idx  runs  ema_ns    avg_ns    last_ns   total_ns");
-                for (i, last, total, runs, avg, ema) in rows {
-                    println!("{i:<4} {runs:<5} {ema:<9} {avg:<9} {last:<9} {total}");
-                }
-            }
-            Ok(())
+        Some(Command::MetricsTop { limit, sort, min_runs, json }) => commands::metrics_top::main(limit, sort, min_runs, json),
+        Some(Command::MetricsWatch { limit, sort }) => tui::metrics_watch::main(limit, sort),
+        Some(Command::MetricsDiff { files, baseline, threshold, fail_on_regression, json }) => {
+            commands::metrics_diff::main(&files, baseline.as_deref(), threshold, fail_on_regression, json)
         }
 
         Some(Command::MetricsConfig {
@@ -728,24 +877,24 @@ fn main() -> anyhow::Result<()> {
         }) => {
             use crate::core::incremental::{
                 reset_runtime_metrics_config, set_ema_alpha, set_history_cap as set_hist_cap,
-                set_window_capacity, EMA_ALPHA_RUNTIME, SAVINGS_METRICS, WINDOW_CAP_RUNTIME,
+                set_window_capacity, lock_cache, EMA_ALPHA_RUNTIME, SAVINGS_METRICS, WINDOW_CAP_RUNTIME,
             };
             if reset {
                 reset_runtime_metrics_config();
             }
             if let Some(a) = set_ema {
-                set_ema_alpha(a);
+                set_ema_alpha(a).map_err(anyhow::Error::msg)?;
             }
             if let Some(w) = set_window {
-                set_window_capacity(w);
+                set_window_capacity(w).map_err(anyhow::Error::msg)?;
             }
             if let Some(h) = set_history_cap {
-                set_hist_cap(h);
+                set_hist_cap(h).map_err(anyhow::Error::msg)?;
             }
             let alpha = EMA_ALPHA_RUNTIME.load(std::sync::atomic::Ordering::Relaxed);
             let win = WINDOW_CAP_RUNTIME.load(std::sync::atomic::Ordering::Relaxed);
             let hist_cap = {
-                let sm = SAVINGS_METRICS.lock().unwrap();
+                let sm = lock_cache("savings", &SAVINGS_METRICS);
                 sm.history_cap
             };
             if json {
@@ -956,26 +1105,9 @@ fn main() -> anyhow::Result<()> {
             }
             Ok(())
         }
+        Some(Command::MetricsReplay { file, edits, json, csv }) => commands::metrics_replay::main(&file, &edits, json, csv),
         Some(Command::MetricsExport { file }) => {
-            use crate::core::incremental::build_metrics_json;
-            let json = build_metrics_json();
-            if let Some(obj) = json.get("functionMetrics").and_then(|v| v.as_object()) {
-                let mut rows: Vec<(usize, u64, u128, u128, u128, u128)> = Vec::new();
-                for (k, v) in obj {
-                    if let Ok(idx) = k.parse::<usize>() {
-                        let runs = v.get("runs").and_then(|x| x.as_u64()).unwrap_or(0);
-                        let ema = v.get("ema_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128;
-                        let avg = v.get("avg_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128;
-                        let last = v.get("last_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128;
-                        let total = v.get("total_ns").and_then(|x| x.as_u64()).unwrap_or(0) as u128;
-                        rows.push((idx, runs, ema, avg, last, total));
-                    }
-                }
-                rows.sort_by(|a, b| b.2.cmp(&a.2));
-                let mut csv = String::from("index,runs,ema_ns,avg_ns,last_ns,total_ns\n");
-                for (i, r, e, a, l, t) in rows {
-                    csv.push_str(&format!("{i},{r},{e},{a},{l},{t}\n"));
-                }
+            if let Some(csv) = crate::core::incremental::function_metrics_csv() {
                 if let Err(e) = std::fs::write(&file, csv) {
                     eprintln!("export error: {e}");
                 } else {
@@ -988,15 +1120,21 @@ fn main() -> anyhow::Result<()> {
         }
         #[cfg(feature = "debug-metrics")]
         Some(Command::MetricsInjectSavings { partial, full }) => {
-            use crate::core::incremental::record_savings;
+            use crate::core::incremental::{load_metrics, record_savings};
+            // Build on the persisted state so successive injections accumulate.
+            load_metrics();
             record_savings(partial, full);
             println!("injected savings partial={partial} full={full}");
             Ok(())
         }
         #[cfg(feature = "debug-metrics")]
-        Some(Command::MetricsInjectFunc { index, dur }) => {
-            use crate::core::incremental::record_function_infer;
+        Some(Command::MetricsInjectFunc { index, dur, name, file, line }) => {
+            use crate::core::incremental::{load_metrics, record_function_infer, record_function_site};
+            load_metrics();
             record_function_infer(index, dur);
+            if let Some(name) = name {
+                record_function_site(index, &name, file.as_deref(), line);
+            }
             println!("injected func index={index} dur={dur}");
             Ok(())
         }
@@ -1005,33 +1143,61 @@ fn main() -> anyhow::Result<()> {
             file,
             args: passthrough,
             watch,
+            clear,
             keep_temp,
             no_run,
+            native,
+            with_secrets,
+            sandbox,
+            max_memory_mb,
         }) => {
-            use std::thread::sleep;
-            use std::time::{Duration, SystemTime};
             // Because the Exec command uses a trailing var arg to allow arbitrary program args,
             // flags like --keep-temp / --no-run placed after the file name are captured inside
             // passthrough. Tests pass them that way, so we detect and elevate them here.
             let mut keep_temp_flag = keep_temp;
             let mut no_run_flag = no_run;
+            let mut native_flag = native;
+            let mut watch_flag = watch;
+            let mut clear_flag = clear;
             let mut passthrough_filtered: Vec<String> = Vec::new();
             for a in &passthrough {
                 match a.as_str() {
+                    "--watch" => watch_flag = true,
+                    "--clear" => clear_flag = true,
                     "--keep-temp" => keep_temp_flag = true,
                     "--no-run" => no_run_flag = true,
+                    "--native" => native_flag = true,
                     _ => passthrough_filtered.push(a.clone()),
                 }
             }
+            let secret_flags = commands::secrets::SecretFlags::extract(&mut passthrough_filtered, true);
+            let sandbox_flag = sandbox || secret_flags.sandbox;
+            let secrets = commands::secrets::prepare(
+                secret_flags.with_secrets.or(with_secrets).as_deref(),
+                &file,
+                sandbox_flag,
+                "exec",
+            )?;
+            let limits = crate::core::vm::Limits {
+                deny_env: sandbox_flag,
+                max_memory_bytes: max_memory_mb.map(|mb| mb * 1024 * 1024),
+                ..Default::default()
+            };
+            #[allow(clippy::too_many_arguments)]
             fn run_once(
                 file: &PathBuf,
                 passthrough: &[String],
                 pretty: bool,
                 skip_sema: bool,
-                debug_titan: bool,
+                _debug_titan: bool,
                 keep_temp: bool,
                 no_run: bool,
+                mode: commands::run::ExecutionMode,
+                limits: &crate::core::vm::Limits,
+                secrets: &commands::secrets::SecretEnv,
             ) -> anyhow::Result<()> {
+                use commands::exit_codes::{self, fail, reported};
+                use crate::core::toolchain::{self, missing_tool_message, Tool};
                 let ext = file
                     .extension()
                     .and_then(|e| e.to_str())
@@ -1039,152 +1205,109 @@ fn main() -> anyhow::Result<()> {
                     .to_lowercase();
                 match ext.as_str() {
                     "ai" => {
-                        let force_native =
-                            std::env::var("AEONMI_NATIVE").ok().as_deref() == Some("1");
-                        let node_available = std::process::Command::new("node")
-                            .arg("--version")
-                            .output()
-                            .map(|o| o.status.success())
-                            .unwrap_or(false);
+                        let force_native = mode == commands::run::ExecutionMode::Native;
+                        let node_available = !force_native && toolchain::available(Tool::Node);
                         if force_native || !node_available {
+                            if !force_native && !no_run && !crate::core::diagnostics::quiet() {
+                                eprintln!("{}", toolchain::NODE_FALLBACK_NOTICE);
+                            }
                             if no_run {
                                 // Even in native/ no node environment, honor --no-run by producing JS artifact for tests.
                                 let out_js = PathBuf::from("__exec_tmp.js");
-                                commands::compile::compile_pipeline(
-                                    Some(file.clone()),
-                                    EmitKind::Js,
-                                    out_js.clone(),
-                                    false,
-                                    false,
-                                    pretty,
-                                    skip_sema,
-                                    debug_titan,
-                                )?;
+                                commands::compile::compile_for_run(file.clone(), out_js.clone(), pretty, skip_sema, false)?;
                                 if !keep_temp {
                                     let _ = std::fs::remove_file(&out_js);
                                 }
                                 Ok(())
                             } else {
                                 // Native interpretation path
-                                println!(
-                                    "DEBUG: EXEC PATH - native: executing '{}' via Aeonmi VM",
-                                    file.display()
-                                );
-                                use crate::core::diagnostics::{
-                                    emit_json_error, print_error, Span,
-                                };
+                                use crate::core::diagnostics::{quiet, report_error, report_parse_error, Span};
+                                use crate::core::error_index;
                                 use crate::core::lexer::Lexer;
-                                use crate::core::lexer::LexerError;
                                 use crate::core::lowering::lower_ast_to_ir;
-                                use crate::core::parser::{Parser as AeParser, ParserError};
+                                use crate::core::parser::Parser as AeParser;
                                 use crate::core::vm::Interpreter;
+                                debug_log!("native: executing '{}' via Aeonmi VM", file.display());
                                 let src = match std::fs::read_to_string(file) {
                                     Ok(s) => s,
-                                    Err(e) => anyhow::bail!("read error: {e}"),
+                                    Err(e) => return Err(fail(exit_codes::IO, format!("read error: {e}"))),
                                 };
-                                let mut lexer = Lexer::from_str(&src);
+                                let lexer_opts = match crate::config::LexerSettings::discover(file) {
+                                    Ok(s) => s.lexer_options(),
+                                    Err(e) => return Err(fail(exit_codes::USAGE, format!("{e:#}"))),
+                                };
+                                let mut lexer = Lexer::with_options(&src, lexer_opts);
                                 let tokens = match lexer.tokenize() {
                                     Ok(t) => t,
                                     Err(e) => {
-                                        if pretty {
-                                            match e {
-                                                LexerError::UnexpectedCharacter(_, line, col)
-                                                | LexerError::UnterminatedString(line, col)
-                                                | LexerError::InvalidNumber(_, line, col)
-                                                | LexerError::InvalidQubitLiteral(_, line, col)
-                                                | LexerError::UnterminatedComment(line, col) => {
-                                                    emit_json_error(
-                                                        &file.display().to_string(),
-                                                        &format!("{}", e),
-                                                        &Span::single(line, col),
-                                                    );
-                                                    print_error(
-                                                        &file.display().to_string(),
-                                                        &src,
-                                                        &format!("{}", e),
-                                                        Span::single(line, col),
-                                                    );
-                                                }
-                                                _ => eprintln!("lex error: {e}"),
-                                            }
-                                        } else {
-                                            eprintln!("lex error: {e}");
-                                        }
-                                        return Ok(());
+                                        let (line, col) = e.position();
+                                        report_error(
+                                            &file.display().to_string(),
+                                            &src,
+                                            &format!("Lexing error: {e}"),
+                                            Span::single(line, col),
+                                            Some(error_index::LEX_ERROR),
+                                            pretty,
+                                        );
+                                        return Err(reported(exit_codes::COMPILE_DIAG, "lexing failed"));
                                     }
                                 };
                                 let mut parser = AeParser::new(tokens.clone());
                                 let ast = match parser.parse() {
                                     Ok(a) => a,
-                                    Err(ParserError {
-                                        message,
-                                        line,
-                                        column,
-                                    }) => {
-                                        if pretty {
-                                            emit_json_error(
-                                                &file.display().to_string(),
-                                                &format!("Parsing error: {message}"),
-                                                &Span::single(line, column),
-                                            );
-                                            print_error(
-                                                &file.display().to_string(),
-                                                &src,
-                                                &format!("Parsing error: {message}"),
-                                                Span::single(line, column),
-                                            );
-                                        } else {
-                                            eprintln!("parse error: {message}");
-                                        }
-                                        return Ok(());
+                                    Err(e) => {
+                                        report_parse_error(&file.display().to_string(), &src, &e, pretty);
+                                        return Err(reported(exit_codes::COMPILE_DIAG, "parsing failed"));
                                     }
                                 };
-                                if skip_sema {
-                                    println!("note: semantic analysis skipped (native)");
+                                if skip_sema && !quiet() {
+                                    eprintln!("note: semantic analysis skipped (native)");
                                 }
-                                match lower_ast_to_ir(&ast, "main") {
-                                    Ok(module) => {
-                                        println!("DEBUG: About to call run_module in main.rs");
-                                        let mut interp = Interpreter::new();
-                                        if !no_run {
-                                            if let Err(e) = interp.run_module(&module) {
-                                                eprintln!("TEST ERROR: {}", e.message);
-                                            }
+                                let module = match lower_ast_to_ir(&ast, "main") {
+                                    Ok(m) => m,
+                                    Err(e) => {
+                                        if !quiet() {
+                                            eprintln!("lowering error: {e}");
                                         }
+                                        return Err(reported(exit_codes::COMPILE_DIAG, "lowering failed"));
                                     }
-                                    Err(e) => eprintln!("lowering error: {e}"),
+                                };
+                                let mut interp = Interpreter::new();
+                                interp.set_limits(limits.clone());
+                                if let Err(e) = interp.run_module(&module) {
+                                    if !quiet() {
+                                        eprintln!("runtime error: {}", e.message);
+                                    }
+                                    return Err(reported(exit_codes::RUNTIME, e.message));
                                 }
                                 Ok(())
                             }
                         } else {
+                            // The source map lets Node's stack traces name .ai lines.
                             let out_js = PathBuf::from("__exec_tmp.js");
-                            commands::compile::compile_pipeline(
-                                Some(file.clone()),
-                                EmitKind::Js,
-                                out_js.clone(),
-                                false,
-                                false,
-                                pretty,
-                                skip_sema,
-                                debug_titan,
-                            )?;
+                            let out_map = crate::core::source_map::map_path(&out_js);
+                            commands::compile::compile_for_run(file.clone(), out_js.clone(), pretty, skip_sema, true)?;
                             // If user only wants compilation (--no-run) and didn't request keep-temp, remove temp now
                             if no_run {
                                 if !keep_temp {
                                     let _ = std::fs::remove_file(&out_js);
+                                    let _ = std::fs::remove_file(&out_map);
                                 }
                             } else {
-                                let status = std::process::Command::new("node")
+                                let status = secrets
+                                    .apply(&mut std::process::Command::new("node"))
+                                    .arg("--enable-source-maps")
                                     .arg(&out_js)
                                     .args(passthrough)
                                     .status();
                                 match status {
                                     Ok(s) if s.success() => {}
-                                    Ok(s) => anyhow::bail!("node exited with status {}", s),
+                                    Ok(s) => return Err(fail(exit_codes::RUNTIME, format!("node exited with status {}", s))),
                                     Err(e) => anyhow::bail!("failed to execute node: {e}"),
                                 }
                                 if !keep_temp {
                                     let _ = std::fs::remove_file(&out_js);
+                                    let _ = std::fs::remove_file(&out_map);
                                 }
                             }
                             Ok(())
@@ -1194,32 +1317,42 @@ fn main() -> anyhow::Result<()> {
                         if no_run {
                             return Ok(());
                         }
-                        let status = std::process::Command::new("node")
+                        if !toolchain::available(Tool::Node) {
+                            return Err(fail(exit_codes::GENERIC, missing_tool_message(Tool::Node, "run .js files")));
+                        }
+                        let status = secrets
+                            .apply(&mut std::process::Command::new("node"))
                             .arg(&file)
                             .args(passthrough)
                             .status();
                         match status {
                             Ok(s) if s.success() => Ok(()),
-                            Ok(s) => anyhow::bail!("node exited with status {}", s),
+                            Ok(s) => Err(fail(exit_codes::RUNTIME, format!("node exited with status {}", s))),
                             Err(e) => anyhow::bail!("failed to execute node: {e}"),
                         }
                     }
                     "py" => {
-                        let exe = if cfg!(windows) { "python" } else { "python3" };
                         if no_run {
                             return Ok(());
                         }
-                        let status = std::process::Command::new(exe)
+                        if !toolchain::available(Tool::Python) {
+                            return Err(fail(exit_codes::GENERIC, missing_tool_message(Tool::Python, "run .py files")));
+                        }
+                        let status = secrets
+                            .apply(&mut std::process::Command::new(Tool::Python.exe()))
                             .arg(&file)
                             .args(passthrough)
                             .status();
                         match status {
                             Ok(s) if s.success() => Ok(()),
-                            Ok(s) => anyhow::bail!("python exited with status {}", s),
+                            Ok(s) => Err(fail(exit_codes::RUNTIME, format!("python exited with status {}", s))),
                             Err(e) => anyhow::bail!("failed to execute python: {e}"),
                         }
                     }
                     "rs" => {
+                        if !toolchain::available(Tool::Rustc) {
+                            return Err(fail(exit_codes::GENERIC, missing_tool_message(Tool::Rustc, "compile .rs files")));
+                        }
                         let out_exe = if cfg!(windows) {
                             "__exec_tmp_rs.exe"
                         } else {
@@ -1234,13 +1367,17 @@ fn main() -> anyhow::Result<()> {
                         match status_compile {
                             Ok(s) if s.success() => {
                                 if !no_run {
-                                    let status_run = std::process::Command::new(out_exe)
+                                    let status_run = secrets
+                                        .apply(&mut std::process::Command::new(std::path::Path::new(".").join(out_exe)))
                                         .args(passthrough)
                                         .status();
                                     match status_run {
                                         Ok(s) if s.success() => {}
                                         Ok(s) => {
-                                            anyhow::bail!("rust exec exited with status {}", s)
+                                            return Err(fail(
+                                                exit_codes::RUNTIME,
+                                                format!("rust exec exited with status {}", s),
+                                            ))
                                         }
                                         Err(e) => anyhow::bail!("failed to run rust exe: {e}"),
                                     }
@@ -1254,23 +1391,43 @@ fn main() -> anyhow::Result<()> {
                                 }
                                 Ok(())
                             }
-                            Ok(s) => anyhow::bail!("rustc exited with status {}", s),
+                            Ok(s) => Err(fail(
+                                exit_codes::COMPILE_DIAG,
+                                format!("rustc exited with status {}", s),
+                            )),
                             Err(e) => anyhow::bail!("failed to execute rustc: {e}"),
                         }
                     }
                     other => {
-                        anyhow::bail!(
-                            "Unsupported extension '{other}'. Supported: .ai .js .py .rs"
-                        );
+                        Err(fail(
+                            exit_codes::USAGE,
+                            format!("Unsupported extension '{other}'. Supported: .ai .js .py .rs"),
+                        ))
                     }
                 }
             }
-            if watch {
-                let mut last_mtime = std::fs::metadata(&file)
-                    .and_then(|m| m.modified())
-                    .unwrap_or(SystemTime::UNIX_EPOCH);
-                loop {
-                    let _ = run_once(
+            if watch_flag {
+                use commands::watch::{self, LoopOptions, Watcher};
+                let temp_js = keep_temp_flag.then(|| PathBuf::from("__exec_tmp.js"));
+                let mut watcher = Watcher::new(&watch::exec_watch_targets(&file, temp_js.as_deref()))?;
+                let listener = crate::core::shutdown::listen();
+                // An interrupted run can leave its temp output behind; only `--keep-temp` keeps it.
+                let temp_files = if keep_temp_flag {
+                    Vec::new()
+                } else {
+                    ["__exec_tmp.js", "__exec_tmp.js.map", if cfg!(windows) { "__exec_tmp_rs.exe" } else { "__exec_tmp_rs" }].map(PathBuf::from).to_vec()
+                };
+                let opts = LoopOptions { clear: clear_flag, temp_files, ..LoopOptions::from_env() };
+                let wait = |stop: &std::sync::atomic::AtomicBool| {
+                    // imports added since the last run are tracked from now on
+                    for target in watch::exec_watch_targets(&file, temp_js.as_deref()) {
+                        let _ = watcher.add(&target);
+                    }
+                    watcher.drain();
+                    watcher.wait(stop)
+                };
+                watch::run_loop(listener.token(), &opts, wait, |_| {
+                    run_once(
                         &file,
                         &passthrough_filtered,
                         args.pretty_errors,
@@ -1278,21 +1435,12 @@ fn main() -> anyhow::Result<()> {
                         args.debug_titan,
                         keep_temp_flag,
                         no_run_flag,
-                    );
-                    if std::env::var("AEONMI_WATCH_ONCE").ok().as_deref() == Some("1") {
-                        break;
-                    }
-                    sleep(Duration::from_millis(500));
-                    if let Ok(meta) = std::fs::metadata(&file) {
-                        if let Ok(m) = meta.modified() {
-                            if m > last_mtime {
-                                last_mtime = m;
-                                println!("[watch] change detected, re-running...");
-                                continue;
-                            }
-                        }
-                    }
-                }
+                        commands::run::ExecutionMode::resolve(native_flag),
+                        &limits,
+                        &secrets,
+                    )
+                    .map_err(|e| secrets.scrub_error(e))
+                });
                 Ok(())
             } else {
                 run_once(
@@ -1303,7 +1451,11 @@ fn main() -> anyhow::Result<()> {
                     args.debug_titan,
                     keep_temp_flag,
                     no_run_flag,
+                    commands::run::ExecutionMode::resolve(native_flag),
+                    &limits,
+                    &secrets,
                 )
+                .map_err(|e| secrets.scrub_error(e))
             }
         }
 
@@ -1312,8 +1464,6 @@ fn main() -> anyhow::Result<()> {
             emit_ai,
             watch,
         }) => {
-            use std::thread::sleep;
-            use std::time::{Duration, SystemTime};
             fn run_native_file(
                 p: &PathBuf,
                 emit_ai: &Option<PathBuf>,
@@ -1332,26 +1482,10 @@ fn main() -> anyhow::Result<()> {
                         false,
                     );
                 }
-                std::env::set_var("AEONMI_NATIVE", "1");
-                commands::run::main_with_opts(p.clone(), None, pretty, skip_sema)
+                commands::run::main_with_opts(p.clone(), None, pretty, skip_sema, commands::run::ExecutionMode::Native, false)
             }
             if watch {
-                let mut last_mtime = std::fs::metadata(&input)
-                    .and_then(|m| m.modified())
-                    .unwrap_or(SystemTime::UNIX_EPOCH);
-                loop {
-                    let _ = run_native_file(&input, &emit_ai, args.pretty_errors, args.no_sema);
-                    sleep(Duration::from_millis(500));
-                    if let Ok(meta) = std::fs::metadata(&input) {
-                        if let Ok(m) = meta.modified() {
-                            if m > last_mtime {
-                                last_mtime = m;
-                                println!("[watch] detected change, re-running (native)...");
-                                continue;
-                            }
-                        }
-                    }
-                }
+                commands::watch::watch_ai(&input, |_| run_native_file(&input, &emit_ai, args.pretty_errors, args.no_sema))
             } else {
                 run_native_file(&input, &emit_ai, args.pretty_errors, args.no_sema)
             }
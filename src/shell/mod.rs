@@ -1,23 +1,33 @@
-use colored::Colorize;
 use std::fs;
 use std::io::{self, Write};
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 
 use crate::cli::EmitKind;
 use crate::commands;
 use crate::commands::compile::compile_pipeline;
+use crate::commands::run::ExecutionMode;
+use crate::commands::shell_cwd::{self, ShellCwd};
+use crate::commands::watch;
+use crate::core::clipboard;
+use crate::core::shutdown;
+use crate::core::state_inspect::{self, StateView};
+use crate::core::style::{self, Role, Style};
 
 pub fn start(config_path: Option<PathBuf>, pretty: bool, skip_sema: bool) -> anyhow::Result<()> {
-    banner();
+    let st = style::current();
+    print!("{}", banner_text(&st));
+    let mut session = Session { config_path, pretty, skip_sema, quantum_state: None, last_output: None };
 
-    let mut cwd = std::env::current_dir()?;
+    let mut cwd = ShellCwd::new(std::env::current_dir()?);
+    // The shell survives a single Ctrl-C (it only stops a running `watch`); two in a row exit.
+    let listener = shutdown::listen();
     loop {
         // Prompt
         print!(
             "{} {} {} ",
-            "⟦AEONMI⟧".bold().truecolor(225, 0, 180),
-            cwd.display().to_string().truecolor(130, 0, 200),
-            "›".truecolor(255, 240, 0)
+            st.paint(st.glyph("⟦AEONMI⟧", "[AEONMI]"), Role::Brand),
+            st.paint(&cwd.current().display().to_string(), Role::Section),
+            st.paint(st.glyph("›", ">"), Role::Title)
         );
         io::stdout().flush().ok();
 
@@ -32,54 +42,102 @@ pub fn start(config_path: Option<PathBuf>, pretty: bool, skip_sema: bool) -> any
             continue;
         }
 
-        // Parse
-        let mut parts = shell_words(line);
+        // Parse, expanding `~` and `$VAR` in every word
+        let mut parts: Vec<String> = shell_words(line).iter().map(|w| shell_cwd::expand(w)).collect();
         if parts.is_empty() {
             continue;
         }
         let cmd = parts.remove(0);
 
-        match cmd.as_str() {
-            "help" | "?" => print_help(),
-            "exit" | "quit" => break,
+        if let Flow::Exit = session.dispatch(&mut cwd, &cmd, &parts)? {
+            break;
+        }
+        listener.token().reset();
+    }
+
+    Ok(())
+}
+
+enum Flow {
+    Continue,
+    Exit,
+}
+
+/// Options fixed for the lifetime of one shell, and what `qstate` inspects.
+struct Session {
+    config_path: Option<PathBuf>,
+    pretty: bool,
+    skip_sema: bool,
+    /// Final statevector of the last `qsim` / `qload`.
+    quantum_state: Option<StateView>,
+    /// What `:copy` copies: the last error, or the last `cat` / `qstate` output.
+    last_output: Option<String>,
+}
+
+impl Session {
+    /// Run one builtin. Shared by the prompt loop and `watch`, which re-runs a command line.
+    /// Relative paths are resolved against `cwd`; the process directory is never changed.
+    fn dispatch(&mut self, cwd: &mut ShellCwd, cmd: &str, parts: &[String]) -> anyhow::Result<Flow> {
+        let st = style::current();
+        let (config_path, pretty, skip_sema) = (&self.config_path, self.pretty, self.skip_sema);
+        // Print an error and keep it for `:copy`.
+        macro_rules! err {
+            ($($arg:tt)*) => {{
+                let msg = format!($($arg)*);
+                eprintln!("{} {}", st.paint("err:", Role::Error), msg);
+                self.last_output = Some(msg);
+            }};
+        }
+        match cmd {
+            "help" | "?" => print!("{}", help_text(&st)),
+            "exit" | "quit" => return Ok(Flow::Exit),
+            ":copy" => {
+                // :copy [--force] — the last error or `cat` / `qstate` output, to the clipboard
+                let force = parts.iter().any(|p| p == "--force");
+                match self.last_output.as_deref().map(|text| clipboard::copy(text, force)) {
+                    Some(Ok(done)) => println!("{done}"),
+                    // not kept for `:copy`, so `:copy --force` still copies the original
+                    Some(Err(e)) => eprintln!("{} {e}", st.paint("err:", Role::Error)),
+                    None => println!("nothing to copy yet"),
+                }
+            }
 
             // Navigation
-            "pwd" => println!("{}", cwd.display()),
+            "pwd" => println!("{}", cwd.current().display()),
             "cd" => {
-                let target = parts.first()
-                    .map(PathBuf::from)
-                    .unwrap_or_else(|| dirs_next::home_dir().unwrap_or(cwd.clone()));
-                if let Err(e) = std::env::set_current_dir(&target) {
-                    eprintln!("{} {}", "err:".red().bold(), e);
-                } else {
-                    cwd = std::env::current_dir()?;
+                let arg = parts.first().map(String::as_str);
+                match cwd.cd(arg, dirs_next::home_dir().as_deref()) {
+                    // like other shells, `cd -` echoes where it went
+                    Ok(dir) if arg == Some("-") => println!("{}", dir.display()),
+                    Ok(_) => {}
+                    Err(e) => err!("{}", e),
                 }
             }
             "ls" | "dir" => {
                 let path = parts.first()
-                    .map(PathBuf::from)
-                    .unwrap_or_else(|| cwd.clone());
+                    .map(|p| cwd.resolve(p))
+                    .unwrap_or_else(|| cwd.current().to_path_buf());
                 match fs::read_dir(&path) {
                     Ok(rd) => {
                         for entry in rd.flatten() {
                             let p = entry.path();
                             let name = entry.file_name().to_string_lossy().into_owned();
                             if p.is_dir() {
-                                println!("{}", format!("{name}/").truecolor(130, 0, 200));
+                                println!("{}", st.paint(&format!("{name}/"), Role::Section));
                             } else {
                                 println!("{name}");
                             }
                         }
                     }
-                    Err(e) => eprintln!("{} {}: {}", "err:".red().bold(), path.display(), e),
+                    Err(e) => err!("{}: {}", path.display(), e),
                 }
             }
 
             // FS ops
             "mkdir" => {
                 if let Some(p) = parts.first() {
-                    if let Err(e) = fs::create_dir_all(p) {
-                        eprintln!("{} {}", "err:".red().bold(), e);
+                    if let Err(e) = fs::create_dir_all(cwd.resolve(p)) {
+                        err!("{}", e);
                     }
                 } else {
                     usage("mkdir <path>");
@@ -87,14 +145,14 @@ pub fn start(config_path: Option<PathBuf>, pretty: bool, skip_sema: bool) -> any
             }
             "rm" => {
                 if let Some(p) = parts.first() {
-                    let pb = Path::new(p);
+                    let pb = cwd.resolve(p);
                     let res = if pb.is_dir() {
-                        fs::remove_dir_all(pb)
+                        fs::remove_dir_all(&pb)
                     } else {
-                        fs::remove_file(pb)
+                        fs::remove_file(&pb)
                     };
                     if let Err(e) = res {
-                        eprintln!("{} {}", "err:".red().bold(), e);
+                        err!("{}", e);
                     }
                 } else {
                     usage("rm <path>");
@@ -103,22 +161,25 @@ pub fn start(config_path: Option<PathBuf>, pretty: bool, skip_sema: bool) -> any
             "mv" => {
                 if parts.len() < 2 {
                     usage("mv <src> <dst>");
-                } else if let Err(e) = fs::rename(&parts[0], &parts[1]) {
-                    eprintln!("{} {}", "err:".red().bold(), e);
+                } else if let Err(e) = fs::rename(cwd.resolve(&parts[0]), cwd.resolve(&parts[1])) {
+                    err!("{}", e);
                 }
             }
             "cp" => {
                 if parts.len() < 2 {
                     usage("cp <src> <dst>");
-                } else if let Err(e) = fs::copy(&parts[0], &parts[1]).map(|_| ()) {
-                    eprintln!("{} {}", "err:".red().bold(), e);
+                } else if let Err(e) = fs::copy(cwd.resolve(&parts[0]), cwd.resolve(&parts[1])).map(|_| ()) {
+                    err!("{}", e);
                 }
             }
             "cat" => {
                 if let Some(p) = parts.first() {
-                    match fs::read_to_string(p) {
-                        Ok(s) => print!("{s}"),
-                        Err(e) => eprintln!("{} {}", "err:".red().bold(), e),
+                    match fs::read_to_string(cwd.resolve(p)) {
+                        Ok(s) => {
+                            print!("{s}");
+                            self.last_output = Some(s);
+                        }
+                        Err(e) => err!("{}", e),
                     }
                 } else {
                     usage("cat <file>");
@@ -143,17 +204,17 @@ pub fn start(config_path: Option<PathBuf>, pretty: bool, skip_sema: bool) -> any
                                 eprintln!("--config needs FILE");
                                 break;
                             }
-                            cfg = Some(PathBuf::from(&parts[i + 1]));
+                            cfg = Some(cwd.resolve(&parts[i + 1]));
                             i += 2;
                         }
                         other => {
-                            file = Some(PathBuf::from(other));
+                            file = Some(cwd.resolve(other));
                             i += 1;
                         }
                     }
                 }
                 if let Err(e) = commands::edit::main(file, cfg, tui) {
-                    eprintln!("{} {}", "err:".red().bold(), e);
+                    err!("{}", e);
                 }
             }
 
@@ -161,11 +222,11 @@ pub fn start(config_path: Option<PathBuf>, pretty: bool, skip_sema: bool) -> any
                 // compile <file.ai> [--emit js|ai] [--out FILE] [--no-sema]
                 if parts.is_empty() {
                     usage("compile <file.ai> [--emit js|ai] [--out FILE] [--no-sema]");
-                    continue;
+                    return Ok(Flow::Continue);
                 }
-                let mut input = PathBuf::from(&parts[0]);
+                let mut input = cwd.resolve(&parts[0]);
                 let mut emit = EmitKind::Js;
-                let mut out = PathBuf::from("output.js");
+                let mut out = cwd.resolve("output.js");
                 let mut j = 1;
                 while j < parts.len() {
                     match parts[j].as_str() {
@@ -175,12 +236,12 @@ pub fn start(config_path: Option<PathBuf>, pretty: bool, skip_sema: bool) -> any
                                 _ => EmitKind::Js,
                             };
                             if matches!(emit, EmitKind::Ai) {
-                                out = PathBuf::from("output.ai");
+                                out = cwd.resolve("output.ai");
                             }
                             j += 2;
                         }
                         "--out" if j + 1 < parts.len() => {
-                            out = PathBuf::from(&parts[j + 1]);
+                            out = cwd.resolve(&parts[j + 1]);
                             j += 2;
                         }
                         "--no-sema" => {
@@ -188,7 +249,7 @@ pub fn start(config_path: Option<PathBuf>, pretty: bool, skip_sema: bool) -> any
                             j += 1;
                         }
                         other => {
-                            input = PathBuf::from(other);
+                            input = cwd.resolve(other);
                             j += 1;
                         }
                     }
@@ -203,7 +264,7 @@ pub fn start(config_path: Option<PathBuf>, pretty: bool, skip_sema: bool) -> any
                     skip_sema,
                     false,
                 ) {
-                    eprintln!("{} {}", "err:".red().bold(), e);
+                    err!("{}", e);
                 }
             }
 
@@ -211,34 +272,40 @@ pub fn start(config_path: Option<PathBuf>, pretty: bool, skip_sema: bool) -> any
                 // run <file.ai> [--native] [--out FILE]
                 if parts.is_empty() {
                     usage("run <file.ai> [--native] [--out FILE]");
-                    continue;
+                    return Ok(Flow::Continue);
                 }
-                let input = PathBuf::from(&parts[0]);
+                let mut input: Option<PathBuf> = None;
                 let mut out: Option<PathBuf> = None;
                 let mut native = false;
-                let mut j = 1;
+                let mut j = 0;
                 while j < parts.len() {
                     match parts[j].as_str() {
                         "--out" if j + 1 < parts.len() => {
-                            out = Some(PathBuf::from(&parts[j + 1]));
+                            out = Some(cwd.resolve(&parts[j + 1]));
                             j += 2;
                         }
                         "--native" => {
                             native = true;
                             j += 1;
                         }
-                        _ => {
+                        other => {
+                            input.get_or_insert_with(|| cwd.resolve(other));
                             j += 1;
                         }
                     }
                 }
+                let Some(input) = input else {
+                    usage("run <file.ai> [--native] [--out FILE]");
+                    return Ok(Flow::Continue);
+                };
                 let res = if native {
                     commands::run::run_native(&input, pretty, skip_sema)
                 } else {
-                    commands::run::main_with_opts(input, out, pretty, skip_sema)
+                    let out = out.unwrap_or_else(|| cwd.resolve("aeonmi.run.js"));
+                    commands::run::main_with_opts(input, Some(out), pretty, skip_sema, ExecutionMode::startup(), false)
                 };
                 if let Err(e) = res {
-                    eprintln!("{} {}", "err:".red().bold(), e);
+                    err!("{}", e);
                 }
             }
 
@@ -246,27 +313,23 @@ pub fn start(config_path: Option<PathBuf>, pretty: bool, skip_sema: bool) -> any
                 // native-run <file.ai> [--out FILE]
                 if parts.is_empty() {
                     usage("native-run <file.ai> [--out FILE]");
-                    continue;
+                    return Ok(Flow::Continue);
                 }
-                let input = PathBuf::from(&parts[0]);
+                let input = cwd.resolve(&parts[0]);
                 let mut out: Option<PathBuf> = None;
                 let mut j = 1;
                 while j < parts.len() {
                     match parts[j].as_str() {
                         "--out" if j + 1 < parts.len() => {
-                            out = Some(PathBuf::from(&parts[j + 1]));
+                            out = Some(cwd.resolve(&parts[j + 1]));
                             j += 2;
                         }
                         _ => j += 1,
                     }
                 }
-                // Temporarily force native interpreter
-                let prev = std::env::var("AEONMI_NATIVE").ok();
-                std::env::set_var("AEONMI_NATIVE", "1");
-                let res = commands::run::main_with_opts(input, out, pretty, skip_sema);
-                if let Some(v) = prev { std::env::set_var("AEONMI_NATIVE", v); } else { std::env::remove_var("AEONMI_NATIVE"); }
+                let res = commands::run::main_with_opts(input, out, pretty, skip_sema, ExecutionMode::Native, false);
                 if let Err(e) = res {
-                    eprintln!("{} {}", "err:".red().bold(), e);
+                    err!("{}", e);
                 }
             }
 
@@ -277,9 +340,9 @@ pub fn start(config_path: Option<PathBuf>, pretty: bool, skip_sema: bool) -> any
                     // qsim <file.ai> [--shots NUM] [--backend titan|qiskit]
                     if parts.is_empty() {
                         usage("qsim <file.ai> [--shots NUM] [--backend titan|qiskit]");
-                        continue;
+                        return Ok(Flow::Continue);
                     }
-                    let input = PathBuf::from(&parts[0]);
+                    let input = cwd.resolve(&parts[0]);
                     let mut shots = None;
                     let mut backend = "titan";
                     let mut j = 1;
@@ -301,152 +364,225 @@ pub fn start(config_path: Option<PathBuf>, pretty: bool, skip_sema: bool) -> any
                         }
                     }
                     println!("{} Running quantum simulation on {} with {} backend...",
-                        "⟨Ψ⟩".truecolor(0, 255, 180),
+                        st.paint(st.glyph("⟨Ψ⟩", "<Q>"), Role::Quantum),
                         input.display(),
-                        backend.truecolor(255, 180, 0)
+                        st.paint(backend, Role::Name)
                     );
-                    if let Err(e) = commands::quantum::main(input, shots, backend) {
-                        eprintln!("{} {}", "err:".red().bold(), e);
+                    match commands::quantum::main(input.clone(), shots, backend) {
+                        Ok(()) => self.quantum_state = commands::quantum::titan_state(&input).ok(),
+                        Err(e) => err!("{}", e),
                     }
                 }
                 #[cfg(not(feature = "quantum"))]
                 {
-                    eprintln!("{} quantum support not built; recompile with --features quantum to use 'qsim'", "warn:".yellow().bold());
+                    eprintln!("{} quantum support not built; recompile with --features quantum to use 'qsim'", st.paint("warn:", Role::Warn));
+                }
+            }
+
+            "qload" => {
+                // qload <file.ai> — simulate on Titan without printing; `qstate` inspects the result
+                #[cfg(feature = "quantum")]
+                {
+                    let Some(file) = parts.first() else {
+                        usage("qload <file.ai>");
+                        return Ok(Flow::Continue);
+                    };
+                    match commands::quantum::titan_state(&cwd.resolve(file)) {
+                        Ok(state) => {
+                            println!("loaded {} qubit(s) from {}; inspect with 'qstate'", state.qubits, file);
+                            self.quantum_state = Some(state);
+                        }
+                        Err(e) => err!("{:#}", e),
+                    }
+                }
+                #[cfg(not(feature = "quantum"))]
+                {
+                    eprintln!("{} quantum support not built; recompile with --features quantum to use 'qload'", st.paint("warn:", Role::Warn));
                 }
             }
 
             "qstate" => {
-                // qstate - Display current quantum system state
-                println!("{}", "=== Quantum State Inspector ===".truecolor(0, 255, 180).bold());
+                // qstate [--qubit N] [--save FILE] — inspect the state of the last qsim/qload
+                let (mut focus, mut save) = (None, None);
+                let mut j = 0;
+                while j < parts.len() {
+                    match (parts[j].as_str(), parts.get(j + 1)) {
+                        ("--qubit", Some(n)) if n.parse::<usize>().is_ok() => focus = n.parse::<usize>().ok(),
+                        ("--save", Some(f)) => save = Some(cwd.resolve(f)),
+                        _ => {
+                            usage("qstate [--qubit N] [--save FILE]");
+                            return Ok(Flow::Continue);
+                        }
+                    }
+                    j += 2;
+                }
+                if let Some(state) = &self.quantum_state {
+                    if let Some(q) = focus.filter(|q| *q >= state.qubits) {
+                        err!("qubit {q} out of range (the state has {} qubits)", state.qubits);
+                        return Ok(Flow::Continue);
+                    }
+                    print!("{}", state_inspect::render(&st, state, focus));
+                    self.last_output = Some(state_inspect::render(&Style::PLAIN, state, focus));
+                    if let Some(path) = save {
+                        let json = serde_json::to_string_pretty(&state_inspect::report(state))?;
+                        match fs::write(&path, json) {
+                            Ok(()) => println!("saved {}", path.display()),
+                            Err(e) => err!("{}: {}", path.display(), e),
+                        }
+                    }
+                    return Ok(Flow::Continue);
+                }
+                println!("{}", st.paint("=== Quantum State Inspector ===", Role::Quantum));
                 println!("Available quantum backends:");
-                println!("  • {} - Native Titan quantum simulator", "titan".truecolor(255, 180, 0));
+                println!("  {} {} - Native Titan quantum simulator", st.glyph("•", "*"), st.paint("titan", Role::Name));
                 #[cfg(feature = "qiskit")]
-                println!("  • {} - Qiskit Aer backend", "qiskit".truecolor(100, 255, 100));
-                println!("  • {} - QUBE symbolic processor", "qube".truecolor(255, 100, 255));
+                println!("  {} {} - Qiskit Aer backend", st.glyph("•", "*"), st.paint("qiskit", Role::Builtin));
+                println!("  {} {} - QUBE symbolic processor", st.glyph("•", "*"), st.paint("qube", Role::Qube));
+                #[cfg(feature = "quantum")]
+                println!("No state loaded yet: run 'qsim <file.ai>' or 'qload <file.ai>', then 'qstate'.");
             }
 
             "qgates" => {
                 // qgates - Show available quantum gates
-                println!("{}", "=== Quantum Gate Library ===".truecolor(0, 255, 180).bold());
+                println!("{}", st.paint("=== Quantum Gate Library ===", Role::Quantum));
                 println!("Single-qubit gates:");
-                println!("  • {} - Pauli-X (bit flip)", "𓀁".truecolor(255, 180, 0));
-                println!("  • {} - Pauli-Y", "𓀂".truecolor(255, 180, 0));
-                println!("  • {} - Pauli-Z (phase flip)", "𓀃".truecolor(255, 180, 0));
-                println!("  • {} - Hadamard (superposition)", "𓀄".truecolor(255, 180, 0));
-                println!("  • {} - S gate (phase)", "𓀅".truecolor(255, 180, 0));
-                println!("  • {} - T gate", "𓀆".truecolor(255, 180, 0));
+                println!("  {} {} - Pauli-X (bit flip)", st.glyph("•", "*"), st.paint(st.glyph("𓀁", "X"), Role::Name));
+                println!("  {} {} - Pauli-Y", st.glyph("•", "*"), st.paint(st.glyph("𓀂", "Y"), Role::Name));
+                println!("  {} {} - Pauli-Z (phase flip)", st.glyph("•", "*"), st.paint(st.glyph("𓀃", "Z"), Role::Name));
+                println!("  {} {} - Hadamard (superposition)", st.glyph("•", "*"), st.paint(st.glyph("𓀄", "H"), Role::Name));
+                println!("  {} {} - S gate (phase)", st.glyph("•", "*"), st.paint(st.glyph("𓀅", "S"), Role::Name));
+                println!("  {} {} - T gate", st.glyph("•", "*"), st.paint(st.glyph("𓀆", "T"), Role::Name));
                 println!("\nTwo-qubit gates:");
-                println!("  • {} - CNOT (controlled-X)", "entangle()".truecolor(100, 255, 100));
-                println!("  • {} - CZ (controlled-Z)", "𓀇".truecolor(255, 180, 0));
+                println!("  {} {} - CNOT (controlled-X)", st.glyph("•", "*"), st.paint("entangle()", Role::Builtin));
+                println!("  {} {} - CZ (controlled-Z)", st.glyph("•", "*"), st.paint(st.glyph("𓀇", "CZ"), Role::Name));
                 println!("\nBuilt-in operations:");
-                println!("  • {} - Create superposition", "superpose()".truecolor(100, 255, 100));
-                println!("  • {} - Quantum measurement", "measure()".truecolor(100, 255, 100));
+                println!("  {} {} - Create superposition", st.glyph("•", "*"), st.paint("superpose()", Role::Builtin));
+                println!("  {} {} - Quantum measurement", st.glyph("•", "*"), st.paint("measure()", Role::Builtin));
             }
 
             "qexample" => {
-                // qexample [teleport|bell|error_correction|grover|qube]
-                let default = String::from("list");
-                let sel: &str = parts.first().map(|s| s.as_str()).unwrap_or(default.as_str());
-                match sel {
+                // qexample [list|<name>] — runs the embedded quantum examples (see core::examples)
+                match parts.first().map(|s| s.as_str()).unwrap_or("list") {
                     "list" => {
-                        println!("{}", "=== Quantum Example Showcase ===".truecolor(0, 255, 180).bold());
+                        println!("{}", st.paint("=== Quantum Example Showcase ===", Role::Quantum));
                         println!("Available examples:");
-                        println!("  • {} - Quantum teleportation protocol", "teleport".truecolor(255, 180, 0));
-                        println!("  • {} - Bell state preparation", "bell".truecolor(255, 180, 0));
-                        println!("  • {} - 3-qubit error correction", "error_correction".truecolor(255, 180, 0));
-                        println!("  • {} - Grover's search algorithm", "grover".truecolor(255, 180, 0));
-                        println!("  • {} - QUBE hieroglyphic programming", "qube".truecolor(255, 100, 255));
+                        for e in crate::core::examples::list(Some("quantum")) {
+                            println!("  {} {} - {}", st.glyph("•", "*"), st.paint(e.name, Role::Name), e.description);
+                        }
                         println!("\nUsage: qexample <name>");
                     }
-                    #[cfg(feature = "quantum")]
-                    "teleport" => {
-                        if let Err(e) = commands::run::main_with_opts(
-                            PathBuf::from("examples/quantum_teleportation.ai"), 
-                            None, pretty, skip_sema
-                        ) {
-                            eprintln!("{} {}", "err:".red().bold(), e);
+                    name => {
+                        if let Err(e) = commands::examples::run(name, pretty, skip_sema) {
+                            err!("{}", e);
+                            if crate::core::examples::find(name).is_none() {
+                                println!("Use 'qexample list' to see available examples");
+                            }
                         }
                     }
-                    #[cfg(feature = "quantum")]
-                    "error_correction" => {
-                        if let Err(e) = commands::run::main_with_opts(
-                            PathBuf::from("examples/quantum_error_correction.ai"), 
-                            None, pretty, skip_sema
-                        ) {
-                            eprintln!("{} {}", "err:".red().bold(), e);
-                        }
+                }
+            }
+
+            "watch" => {
+                // watch <command...> — re-run an internal command whenever files named in it change
+                let Some((inner, rest)) = parts.split_first() else {
+                    usage("watch <command...>");
+                    return Ok(Flow::Continue);
+                };
+                if matches!(inner.as_str(), "watch" | "exit" | "quit") {
+                    err!("'{inner}' cannot be watched");
+                    return Ok(Flow::Continue);
+                }
+                let named = watch::watch_targets(rest, cwd.current());
+                if named.is_empty() {
+                    err!("watch: no existing files in '{}' to watch", parts.join(" "));
+                    return Ok(Flow::Continue);
+                }
+                let mut targets = watch::with_ai_imports(&named);
+                let mut watcher = match watch::Watcher::new(&targets) {
+                    Ok(w) => w,
+                    Err(e) => {
+                        err!("watch: {e}");
+                        return Ok(Flow::Continue);
                     }
-                    #[cfg(feature = "quantum")]
-                    "grover" => {
-                        if let Err(e) = commands::run::main_with_opts(
-                            PathBuf::from("examples/grover_search.ai"), 
-                            None, pretty, skip_sema
-                        ) {
-                            eprintln!("{} {}", "err:".red().bold(), e);
-                        }
+                };
+                let listener = shutdown::listen();
+                let mut runs = 0;
+                loop {
+                    let names: Vec<String> = targets.iter().map(|t| t.strip_prefix(cwd.current()).unwrap_or(t).display().to_string()).collect();
+                    print!("{}", watch::CLEAR_SCREEN);
+                    println!(
+                        "{} {} {}",
+                        st.paint(&format!("[watch {}]", chrono::Local::now().format("%H:%M:%S")), Role::Section),
+                        parts.join(" "),
+                        st.paint(&format!("(watching {}; Ctrl+C to stop)", names.join(", ")), Role::Dim)
+                    );
+                    runs += 1;
+                    if let Flow::Exit = self.dispatch(cwd, inner, rest)? {
+                        break;
                     }
-                    #[cfg(feature = "quantum")]
-                    "qube" => {
-                        if let Err(e) = commands::run::main_with_opts(
-                            PathBuf::from("examples/qube_hieroglyphic.ai"), 
-                            None, pretty, skip_sema
-                        ) {
-                            eprintln!("{} {}", "err:".red().bold(), e);
-                        }
+                    io::stdout().flush().ok();
+                    if std::env::var("AEONMI_WATCH_ONCE").ok().as_deref() == Some("1") {
+                        break;
                     }
-                    #[cfg(not(feature = "quantum"))]
-                    "teleport" | "error_correction" | "grover" => {
-                        eprintln!("{} quantum feature not enabled; recompile with --features quantum", "warn:".yellow().bold());
+                    // the run may have changed what the files import
+                    targets = watch::with_ai_imports(&named);
+                    for t in &targets {
+                        let _ = watcher.add(t);
                     }
-                    other => {
-                        println!("{} Unknown example: {other}", "err:".red().bold());
-                        println!("Use 'qexample list' to see available examples");
+                    if listener.token().is_requested() || watcher.wait(listener.token().flag()) == watch::Wake::Stopped {
+                        break;
                     }
                 }
+                drop(listener);
+                crate::core::incremental::force_persist_metrics();
+                println!("{}", st.paint(&format!("watch stopped after {runs} run{}", if runs == 1 { "" } else { "s" }), Role::Section));
             }
 
-            // Fallback
-            other => eprintln!("{} unknown command: {other}", "err:".red().bold()),
+            // Fallback: an external program, started in the shell's directory
+            other => match std::process::Command::new(other).args(parts).current_dir(cwd.current()).status() {
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::NotFound => err!("unknown command: {other}"),
+                Err(e) => err!("{other}: {e}"),
+            },
         }
+        Ok(Flow::Continue)
     }
-
-    Ok(())
 }
 
-fn banner() {
-    println!(
-        "\n{}  \n{}  \n",
-        "╔══════════════════════════════════════════════════╗".truecolor(225, 0, 180),
-        "║                A e o n m i   S h a r d          ║"
-            .truecolor(255, 240, 0)
-            .bold(),
-    );
-    println!(
-        "{}  {}",
-        "╚══════════════════════════════════════════════════╝".truecolor(225, 0, 180),
-        "type 'help' for commands".truecolor(130, 0, 200)
-    );
+fn banner_text(st: &Style) -> String {
+    let (top, bottom) = if st.unicode {
+        ("╔══════════════════════════════════════════════════╗", "╚══════════════════════════════════════════════════╝")
+    } else {
+        ("+==================================================+", "+==================================================+")
+    };
+    format!(
+        "\n{}  \n{}  \n\n{}  {}\n",
+        st.paint(top, Role::Brand),
+        st.paint(st.glyph("║                A e o n m i   S h a r d          ║", "|                A e o n m i   S h a r d           |"), Role::Title),
+        st.paint(bottom, Role::Brand),
+        st.paint("type 'help' for commands", Role::Section)
+    )
 }
 
-fn print_help() {
-    println!(
+fn help_text(st: &Style) -> String {
+    format!(
         "{}\n\
-         {}\n  pwd                 # print working dir\n  cd [dir]            # change directory\n  ls [dir]            # list directory\n  mkdir <path>        # make directory\n  mv <src> <dst>      # move/rename\n  cp <src> <dst>      # copy file/dir\n\
+         {}\n  pwd                 # print working dir\n  cd [dir|-]          # change directory (~, $VAR expand)\n  ls [dir]            # list directory\n  mkdir <path>        # make directory\n  mv <src> <dst>      # move/rename\n  cp <src> <dst>      # copy file/dir\n\
          {}\n  cat <file>          # show file\n  rm <path>           # remove file/dir\n  edit [--tui] [FILE] # open editor (TUI with --tui)\n  exit                # quit shell\n\
-         {}\n  compile <file.ai> [--emit js|ai] [--out FILE] [--no-sema]\n  run <file.ai> [--native] [--out FILE] # run JS path or native if --native given\n  native-run <file.ai> [--out FILE] # legacy alias for native VM execution\n\
-         {}\n  qsim <file.ai> [--shots NUM] [--backend titan|qiskit] # quantum simulation\n  qstate              # display quantum system info\n  qgates              # show available quantum gates\n  qexample [name]     # run quantum examples\n\
-         {}\n  help                # show this help\n",
-        "Aeonmi Shard — Quantum Programming Shell".bold().truecolor(0, 255, 180),
-        "Navigation:".truecolor(130, 0, 200),
-        "Files:".truecolor(130, 0, 200),
-        "Build:".truecolor(130, 0, 200),
-        "Quantum:".truecolor(255, 180, 0),
-        "Help:".truecolor(130, 0, 200),
-    );
+         {}\n  compile <file.ai> [--emit js|ai] [--out FILE] [--no-sema]\n  run <file.ai> [--native] [--out FILE] # run JS path or native if --native given\n  native-run <file.ai> [--out FILE] # legacy alias for native VM execution\n  watch <command...>  # re-run a command when files it names change\n\
+         {}\n  qsim <file.ai> [--shots NUM] [--backend titan|qiskit] # quantum simulation\n  qload <file.ai>     # simulate without printing, for qstate\n  qstate [--qubit N] [--save FILE] # probabilities, marginals, Bloch vectors\n  qgates              # show available quantum gates\n  qexample [name]     # run quantum examples\n\
+         {}\n  help                # show this help\n  :copy [--force]     # copy the last error or cat/qstate output\n",
+        st.paint(st.glyph("Aeonmi Shard — Quantum Programming Shell", "Aeonmi Shard - Quantum Programming Shell"), Role::Quantum),
+        st.paint("Navigation:", Role::Section),
+        st.paint("Files:", Role::Section),
+        st.paint("Build:", Role::Section),
+        st.paint("Quantum:", Role::Name),
+        st.paint("Help:", Role::Section),
+    )
 }
 
 fn usage(s: &str) {
-    eprintln!("{} usage: {}", "usage:".yellow().bold(), s);
+    eprintln!("{} usage: {}", style::current().paint("usage:", Role::Warn), s);
 }
 
 fn shell_words(s: &str) -> Vec<String> {
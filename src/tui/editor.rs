@@ -20,13 +20,21 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
     Terminal,
 };
 
 use crate::cli::EmitKind;
 use crate::commands::compile::compile_pipeline_soft; // compile_pipeline unused in TUI (soft variant used)
+use crate::config::EditorSettings;
+use crate::core::diagnostics::{render_annotated, AnnotateOptions, Annotation};
+use crate::core::formatter::{format_ai, remap_cursor};
+use crate::core::lexer::Lexer;
+use crate::core::parser::Parser as AeParser;
 use crate::core::qpoly::QPolyMap;
+use crate::core::toolchain::{self, Tool};
+use crate::tui::minimap::{self, Geometry, Marker, Minimap};
+use crate::tui::picker::{self, Picker};
 
 // ---------- Palette / Theme ----------
 fn neon() -> (Color, Color, Color, Color) {
@@ -81,6 +89,8 @@ enum ButtonAction {
     ToggleMode,
     Search,
     ToggleMouse,
+    ToggleMinimap,
+    Format,
     Quit,
 }
 
@@ -97,7 +107,8 @@ struct App {
     dirty: bool,
     status: String,
     last_status_at: Instant,
-    diagnostics: Vec<String>,
+    /// From the last compile (F5); drawn with the `check --annotate` renderer.
+    diagnostics: Vec<Annotation>,
     qpoly: QPolyMap,
     emit_mode: EmitMode,
     show_key_debug: bool,
@@ -107,6 +118,8 @@ struct App {
     cursor_row: usize,
     cursor_col: usize,
     scroll: usize,
+    /// Display cells scrolled off the left edge of the buffer view.
+    hscroll: usize,
     mode: EditorMode,
     history: Vec<String>,
     history_index: usize,
@@ -119,23 +132,34 @@ struct App {
     last_match_row: Option<usize>,
     search_matches: Vec<usize>,
     search_index: usize,
+    settings: EditorSettings,
+    /// Project root the Ctrl+O picker lists files under.
+    root: PathBuf,
+    picker: Option<Picker>,
+    show_minimap: bool,
+    minimap: Minimap,
 }
 
 // ---------- App Impl ----------
 impl App {
-    fn new(filepath: PathBuf, qpoly: QPolyMap) -> Self {
+    fn new(filepath: PathBuf, qpoly: QPolyMap, settings: EditorSettings) -> Self {
         let buffer = if filepath.exists() {
             fs::read_to_string(&filepath).unwrap_or_default()
         } else {
             String::new()
         };
         let persisted_search = fs::read_to_string(".aeonmi_last_search").ok().unwrap_or_default();
+        let root = crate::config::project_root(&filepath);
+        let show_minimap = settings.minimap;
+        if filepath.is_file() {
+            let _ = picker::remember(&root, &filepath);
+        }
         Self {
             filepath,
             buffer,
             input: String::new(),
             dirty: false,
-            status: "⏎ append • Ctrl+S save • F4 emit=JS/AI • F5 compile • F6 run(JS) • F9 toggle-mouse • Esc/Ctrl+Q quit • F1 key-debug".into(),
+            status: "⏎ append • Ctrl+S save • Ctrl+O open • Ctrl+Alt+F format • F4 emit=JS/AI • F5 compile • F6 run(JS) • F7 minimap • F9 toggle-mouse • Esc/Ctrl+Q quit • F1 key-debug".into(),
             last_status_at: Instant::now(),
             diagnostics: vec![],
             qpoly,
@@ -146,6 +170,7 @@ impl App {
             cursor_row: 0,
             cursor_col: 0,
             scroll: 0,
+            hscroll: 0,
             mode: EditorMode::Append,
             history: Vec::new(),
             history_index: 0,
@@ -158,7 +183,56 @@ impl App {
             last_match_row: None,
             search_matches: Vec::new(),
             search_index: 0,
+            settings,
+            root,
+            picker: None,
+            show_minimap,
+            minimap: Minimap::default(),
+        }
+    }
+
+    fn open_picker(&mut self) {
+        if self.dirty {
+            self.set_status("Unsaved changes — Ctrl+S before opening another file");
+            return;
         }
+        self.picker = Some(Picker::open(&self.root, picker::load_recent(&self.root)));
+        self.set_status("Open: type to filter, ↑/↓ select, Enter open, Esc cancel");
+    }
+
+    /// Replace the buffer with `rel` (relative to the project root) and start fresh history.
+    fn open_file(&mut self, rel: &std::path::Path) {
+        let path = self.root.join(rel);
+        let text = match fs::read_to_string(&path) {
+            Ok(t) => t,
+            Err(e) => {
+                self.set_status(format!("Open failed: {}: {e}", path.display()));
+                return;
+            }
+        };
+        // Keep paths under the working directory short in the title bar.
+        let cwd = std::env::current_dir().ok().and_then(|d| d.canonicalize().ok());
+        let canon = path.canonicalize().unwrap_or_else(|_| path.clone());
+        self.filepath = cwd.and_then(|d| canon.strip_prefix(d).ok().map(|p| p.to_path_buf())).unwrap_or(path);
+        self.buffer = text;
+        self.dirty = false;
+        self.diagnostics.clear();
+        self.cursor_row = 0;
+        self.cursor_col = 0;
+        self.scroll = 0;
+        self.hscroll = 0;
+        self.history.clear();
+        self.history_index = 0;
+        self.search_active = false;
+        self.search_matches.clear();
+        self.last_match_row = None;
+        let _ = picker::remember(&self.root, &self.filepath);
+        self.set_status(format!("Opened {}", self.filepath.display()));
+    }
+
+    fn toggle_minimap(&mut self) {
+        self.show_minimap = !self.show_minimap;
+        self.set_status(if self.show_minimap { "Minimap ON" } else { "Minimap OFF" });
     }
 
     fn set_status(&mut self, s: impl Into<String>) {
@@ -207,6 +281,22 @@ impl App {
         }
     }
 
+    /// 0-based display cell of the cursor in `line`.
+    fn cursor_display_x(&self, line: &str) -> usize {
+        crate::core::columns::display_col(line, self.cursor_col + 1) - 1
+    }
+
+    /// Scroll horizontally just enough to keep the cursor inside a view `width` cells wide.
+    fn follow_cursor(&mut self, width: usize) {
+        let line = self.buffer.lines().nth(self.cursor_row).unwrap_or("");
+        let x = self.cursor_display_x(line);
+        if x < self.hscroll {
+            self.hscroll = x;
+        } else if width > 0 && x >= self.hscroll + width {
+            self.hscroll = x + 1 - width;
+        }
+    }
+
     fn find_next(&mut self) {
         if self.search_query.is_empty() { return; }
         if self.search_matches.is_empty() { self.rebuild_search_matches(); }
@@ -215,7 +305,7 @@ impl App {
         let r = self.search_matches[self.search_index];
         self.last_match_row = Some(r);
         self.cursor_row = r;
-        self.cursor_col = 0;
+        self.cursor_col = self.buffer.lines().nth(r).map_or(0, |l| match_col(l, &self.search_query));
         self.set_status(format!("Search: {}/{}", self.search_index + 1, self.search_matches.len()));
     }
 
@@ -227,7 +317,7 @@ impl App {
         let r = self.search_matches[self.search_index];
         self.last_match_row = Some(r);
         self.cursor_row = r;
-        self.cursor_col = 0;
+        self.cursor_col = self.buffer.lines().nth(r).map_or(0, |l| match_col(l, &self.search_query));
         self.set_status(format!("Search: {}/{}", self.search_index + 1, self.search_matches.len()));
     }
 
@@ -242,7 +332,7 @@ impl App {
         if !self.search_matches.is_empty() {
             self.last_match_row = Some(self.search_matches[0]);
             self.cursor_row = self.search_matches[0];
-            self.cursor_col = 0;
+            self.cursor_col = self.buffer.lines().nth(self.cursor_row).map_or(0, |l| match_col(l, &self.search_query));
         }
     }
 
@@ -263,12 +353,59 @@ impl App {
     }
 
     fn save(&mut self) -> Result<()> {
+        let formatted = self.settings.format_on_save && self.format_buffer();
         fs::write(&self.filepath, &self.buffer)?;
         self.dirty = false;
-        self.set_status(format!("Saved {}", self.filepath.display()));
+        match (self.settings.format_on_save, formatted) {
+            (true, false) => self.set_status(format!("Saved {} unformatted — {}", self.filepath.display(), self.status)),
+            _ => self.set_status(format!("Saved {}", self.filepath.display())),
+        }
         Ok(())
     }
 
+    /// Format the buffer in place as one undo step, keeping the cursor on the same code.
+    /// Leaves the text alone (and says why) when it does not parse; returns whether it formatted.
+    fn format_buffer(&mut self) -> bool {
+        let parsed = Lexer::from_str(&self.buffer)
+            .tokenize()
+            .map_err(|e| { let (l, c) = e.position(); format!("{l}:{c}: {e}") })
+            .and_then(|toks| AeParser::new(toks).parse().map_err(|e| format!("{}:{}: {}", e.line, e.column, e.summary())));
+        if let Err(e) = parsed {
+            self.set_status(format!("Format skipped: parse error at {e}"));
+            return false;
+        }
+        let formatted = format_ai(&self.buffer);
+        if formatted == self.buffer {
+            self.set_status("Already formatted");
+            return true;
+        }
+        let (row, col) = remap_cursor(&self.buffer, &formatted, (self.cursor_row, self.cursor_col));
+        self.snapshot(); // the whole format is a single undo step back to this text
+        self.buffer = formatted;
+        self.cursor_row = row;
+        self.cursor_col = col;
+        self.dirty = true;
+        self.set_status("Formatted");
+        true
+    }
+
+    /// Re-check the buffer for the diagnostics panel: a lex or parse error alone, or the
+    /// semantic and type diagnostics of a program that parses.
+    fn refresh_diagnostics(&mut self) {
+        let file = self.filepath.display().to_string();
+        let error = |line, col, message: String| vec![Annotation { severity: "error", line, col, len: 1, message }];
+        self.diagnostics = match Lexer::from_str(&self.buffer).tokenize() {
+            Err(e) => {
+                let (line, col) = e.position();
+                error(line, col, e.to_string())
+            }
+            Ok(tokens) => match AeParser::new(tokens).parse() {
+                Err(e) => error(e.line, e.column, e.summary()),
+                Ok(ast) => crate::commands::typecheck::annotations(&file, &self.buffer, &ast),
+            },
+        };
+    }
+
     fn compile(&mut self, pretty: bool, skip_sema: bool) {
         if self.dirty {
             if let Err(e) = self.save() {
@@ -276,6 +413,7 @@ impl App {
                 return;
             }
         }
+        self.refresh_diagnostics();
         match self.emit_mode {
             EmitMode::Ai => {
                 let out = PathBuf::from("output.ai");
@@ -334,6 +472,9 @@ impl App {
             skip_sema,
             false,
         ) {
+            Ok(()) if !toolchain::available(Tool::Node) => {
+                self.set_status(toolchain::missing_tool_message(Tool::Node, "run (compiled to aeonmi.run.js)"))
+            }
             Ok(()) => match std::process::Command::new("node").arg(&out).status() {
                 Ok(s) if !s.success() => self.set_status(format!("node exited with {s}")),
                 Err(e) => self.set_status(format!("Node not available: {e}")),
@@ -372,9 +513,9 @@ pub fn run_editor_tui(
     skip_sema: bool,
 ) -> Result<()> {
     let filepath = file.unwrap_or_else(|| PathBuf::from("untitled.ai"));
-    let map = if let Some(p) = config_path {
+    let map = if let Some(p) = &config_path {
         if p.exists() {
-            QPolyMap::from_toml_file(&p).unwrap_or_else(|e| {
+            QPolyMap::from_toml_file(p).unwrap_or_else(|e| {
                 eprintln!("(warn) failed to load {}: {e}", p.display());
                 QPolyMap::from_user_default_or_builtin()
             })
@@ -413,7 +554,8 @@ pub fn run_editor_tui(
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let app = App::new(filepath, map);
+    let settings = EditorSettings::load(config_path.as_deref());
+    let app = App::new(filepath, map, settings);
     let res = panic::catch_unwind(AssertUnwindSafe(|| run_app(&mut terminal, app, pretty, skip_sema)));
     // Explicit show cursor (guard will handle rest)
     let _ = terminal.show_cursor();
@@ -454,7 +596,11 @@ fn run_app(
     let tick_rate = Duration::from_millis(100);
 
     'outer: loop {
-        terminal.draw(|f| ui(f, &app))?;
+        if let Some(p) = app.picker.as_mut() {
+            p.poll();
+        }
+        app.follow_cursor(buffer_text_width(terminal.size()?.width, app.show_minimap));
+        terminal.draw(|f| ui(f, &mut app))?;
 
         if event::poll(tick_rate)? {
             match event::read()? {
@@ -477,6 +623,36 @@ fn run_app(
                         app.set_status(app.last_key_debug.clone());
                     }
 
+                    // The open-file overlay takes every key while it is up.
+                    if let Some(p) = app.picker.as_mut() {
+                        match code {
+                            KeyCode::Esc => {
+                                app.picker = None; // drops the walker, stopping the scan
+                                app.set_status("Open canceled");
+                            }
+                            KeyCode::Enter => {
+                                if let Some(rel) = p.selection().map(|r| r.to_path_buf()) {
+                                    app.picker = None;
+                                    app.open_file(&rel);
+                                }
+                            }
+                            KeyCode::Up => p.move_selection(-1),
+                            KeyCode::Down => p.move_selection(1),
+                            KeyCode::PageUp => p.move_selection(-10),
+                            KeyCode::PageDown => p.move_selection(10),
+                            KeyCode::Backspace => p.pop(),
+                            KeyCode::Char('c') | KeyCode::Char('q') | KeyCode::Char('o')
+                                if modifiers.contains(KeyModifiers::CONTROL) =>
+                            {
+                                app.picker = None;
+                                app.set_status("Open canceled");
+                            }
+                            KeyCode::Char(ch) => p.push(ch),
+                            _ => {}
+                        }
+                        continue;
+                    }
+
                     match (code, modifiers) {
                         (KeyCode::F(1), _) => {
                             app.show_key_debug = !app.show_key_debug;
@@ -506,6 +682,7 @@ fn run_app(
                         }
                         (KeyCode::F(5), _) => app.compile(pretty, skip_sema),
                         (KeyCode::F(6), _) => app.run(pretty, skip_sema),
+                        (KeyCode::F(7), _) => app.toggle_minimap(),
                         (KeyCode::F(3), _) => {
                             if app.search_active {
                                 app.find_next();
@@ -549,6 +726,9 @@ fn run_app(
                                 app.set_status(format!("Save failed: {e}"));
                             }
                         }
+                        (KeyCode::Char('f'), m) if m.contains(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                            app.format_buffer();
+                        }
                         (KeyCode::Char('f'), KeyModifiers::CONTROL) => {
                             app.search_active = true;
                             app.search_query.clear();
@@ -556,6 +736,7 @@ fn run_app(
                             app.set_status("Search: (type) Enter/ n next, Shift+N prev, Esc cancel");
                             let _ = fs::write(".aeonmi_last_search", "");
                         }
+                        (KeyCode::Char('o'), KeyModifiers::CONTROL) => app.open_picker(),
                         (KeyCode::Char('z'), KeyModifiers::CONTROL) => app.undo(),
                         (KeyCode::Char('y'), KeyModifiers::CONTROL) => app.redo(),
                         (KeyCode::Backspace, _) => {
@@ -847,6 +1028,10 @@ fn run_app(
                                                     );
                                                 }
                                             }
+                                            ButtonAction::ToggleMinimap => app.toggle_minimap(),
+                                            ButtonAction::Format => {
+                                                app.format_buffer();
+                                            }
                                             ButtonAction::Quit => {
                                                 if app.dirty {
                                                     app.set_status(
@@ -883,8 +1068,17 @@ fn run_app(
                                     .direction(Direction::Vertical)
                                     .constraints([Constraint::Min(5), Constraint::Length(5)].as_ref())
                                     .split(main_split[0]);
-                                let buf_area = left_split[0];
-                                if row >= buf_area.y && row < buf_area.y + buf_area.height {
+                                let (buf_area, map_area) = split_minimap(left_split[0], app.show_minimap);
+                                let on_map = map_area.filter(|m| {
+                                    column >= m.x && column < m.x + m.width && row >= m.y && row < m.y + m.height
+                                });
+                                if let Some(map) = on_map {
+                                    if app.mouse_capture && matches!(kind, MouseEventKind::Down(MouseButton::Left) | MouseEventKind::Drag(MouseButton::Left)) {
+                                        let height = map.height as usize;
+                                        app.scroll = app.minimap.geometry().scroll_for_click((row - map.y) as usize, height);
+                                        app.set_status(format!("Scroll → line {}", app.scroll + 1));
+                                    }
+                                } else if row >= buf_area.y && row < buf_area.y + buf_area.height {
                                     // Inside buffer block (including borders). Adjust for border offset 1.
                                     let click_line = (row - buf_area.y).saturating_sub(1) as usize + app.scroll;
                                     if click_line < app.buffer.lines().count() {
@@ -892,7 +1086,7 @@ fn run_app(
                                         let line_str = app.buffer.lines().nth(app.cursor_row).unwrap_or("");
                                         // Determine desired column based on displayed x (account for left border)
                                         if column >= buf_area.x + 1 { // inside after left border
-                                            let rel_x = (column - buf_area.x - 1) as usize; // character cells
+                                            let rel_x = (column - buf_area.x - 1) as usize + app.hscroll; // character cells
                                             // Walk chars to compute byte offset matching rel_x (monospace assumption, width=1)
                                             let mut byte_idx = 0;
                                             let mut cells = 0;
@@ -928,7 +1122,42 @@ fn run_app(
 }
 
 // ---------- Rendering ----------
-fn ui(f: &mut ratatui::Frame<'_>, app: &App) {
+/// Text cells inside the buffer block for a terminal `term_width` wide (same split as `ui`).
+fn buffer_text_width(term_width: u16, minimap: bool) -> usize {
+    let split = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(68), Constraint::Percentage(32)].as_ref())
+        .split(ratatui::layout::Rect::new(0, 0, term_width, 1));
+    split_minimap(split[0], minimap).0.width.saturating_sub(2) as usize // minus borders
+}
+
+/// The buffer block and, when shown, the minimap column right of it (beside the text
+/// rows, inside the block's top and bottom border lines).
+fn split_minimap(area: Rect, show: bool) -> (Rect, Option<Rect>) {
+    if !show || area.width <= minimap::WIDTH + 2 {
+        return (area, None);
+    }
+    let buffer = Rect { width: area.width - minimap::WIDTH, ..area };
+    let map = Rect {
+        x: area.x + buffer.width,
+        y: area.y + 1,
+        width: minimap::WIDTH,
+        height: area.height.saturating_sub(2),
+    };
+    (buffer, Some(map))
+}
+
+/// Byte offset of the first case-insensitive match of `query` in `line`, so a search jump
+/// scrolls it into view; 0 when lowercasing changes the line's byte length.
+fn match_col(line: &str, query: &str) -> usize {
+    let lower = line.to_lowercase();
+    match lower.find(&query.to_lowercase()) {
+        Some(b) if lower.len() == line.len() && line.is_char_boundary(b) => b,
+        _ => 0,
+    }
+}
+
+fn ui(f: &mut ratatui::Frame<'_>, app: &mut App) {
     let (accent, accent_alt, yellow, dim) = neon();
     let _unused = (accent_alt, dim); // silence unused for now
 
@@ -987,53 +1216,49 @@ fn ui(f: &mut ratatui::Frame<'_>, app: &App) {
         Style::default().fg(accent).add_modifier(Modifier::BOLD),
     ));
 
+    let (buf_area, map_area) = split_minimap(left_split[0], app.show_minimap);
     let lines: Vec<&str> = app.buffer.lines().collect();
-    let height = left_split[0].height.saturating_sub(2) as usize; // minus borders
+    let height = buf_area.height.saturating_sub(2) as usize; // minus borders
     let start = app.scroll.min(lines.len());
     let end = (start + height).min(lines.len());
-    let mut rendered = String::new();
-    for (i, l) in lines[start..end].iter().enumerate() {
-        if i > 0 {
-            rendered.push('\n');
-        }
-        rendered.push_str(l);
-    }
+    let width = buffer_text_width(f.size().width, app.show_minimap);
 
-    let highlight = |line: &str, query: Option<&str>| -> Line {
+    // Only the part of `line` inside `window` (a byte range) is turned into spans, so a
+    // very long line costs what is on screen; tokens are still found from the line start.
+    let highlight = |line: &str, window: std::ops::Range<usize>, query: Option<&str>| -> Line {
         use ratatui::text::Span;
         let mut spans: Vec<Span> = Vec::new();
+        let mut push = |from: usize, to: usize, style: Style| {
+            let (from, to) = (from.max(window.start), to.min(window.end));
+            if from < to {
+                spans.push(Span::styled(line[from..to].to_string(), style));
+            }
+        };
         let lower_q = query.map(|q| q.to_lowercase());
         let keywords = [
-            "function", "let", "if", "else", "while", "for", "return", "log", "superpose",
-            "entangle", "measure", "qubit",
+            "function", "let", "if", "else", "while", "for", "break", "continue", "return", "log", "superpose",
+            "entangle", "measure", "qubit", "enum", "match",
         ];
         let mut i = 0; // byte index, always maintained at a char boundary
-        while i < line.len() {
+        while i < line.len() && i < window.end {
             // safe because i is always at a boundary
             let rest = &line[i..];
             if rest.starts_with('"') {
                 if let Some(rel_end) = rest[1..].find('"') { // closing quote
                     let end = i + 1 + rel_end; // position of closing quote
-                    let token = &line[i..=end];
-                    spans.push(Span::styled(
-                        token.to_string(),
-                        Style::default().fg(Color::Rgb(255, 240, 0)),
-                    ));
+                    push(i, end + 1, Style::default().fg(Color::Rgb(255, 240, 0)));
                     i = end + 1; // move past closing quote
                     continue;
                 } else {
                     // Unterminated string: highlight rest and finish
-                    spans.push(Span::styled(
-                        rest.to_string(),
-                        Style::default().fg(Color::Rgb(255, 240, 0)),
-                    ));
+                    push(i, line.len(), Style::default().fg(Color::Rgb(255, 240, 0)));
                     break;
                 }
             }
             // Whitespace passthrough
             if let Some(ch) = rest.chars().next() {
                 if ch.is_whitespace() {
-                    spans.push(Span::raw(ch.to_string()));
+                    push(i, i + ch.len_utf8(), Style::default());
                     i += ch.len_utf8();
                     continue;
                 }
@@ -1050,26 +1275,13 @@ fn ui(f: &mut ratatui::Frame<'_>, app: &App) {
             let token = &line[i..end];
             let lower = token.to_lowercase();
             if keywords.contains(&lower.as_str()) {
-                spans.push(Span::styled(
-                    token.to_string(),
-                    Style::default().fg(Color::Rgb(130, 0, 200)).add_modifier(Modifier::BOLD),
-                ));
+                push(i, end, Style::default().fg(Color::Rgb(130, 0, 200)).add_modifier(Modifier::BOLD));
             } else if token.chars().all(|c| c.is_ascii_digit() || c == '.') && token.chars().any(|c| c.is_ascii_digit()) {
-                spans.push(Span::styled(
-                    token.to_string(),
-                    Style::default().fg(Color::Rgb(0, 255, 180)),
-                ));
-            } else if let Some(q) = &lower_q {
-                if !q.is_empty() && lower.contains(q) {
-                    spans.push(Span::styled(
-                        token.to_string(),
-                        Style::default().bg(Color::Rgb(255, 240, 0)).fg(Color::Black),
-                    ));
-                } else {
-                    spans.push(Span::raw(token.to_string()));
-                }
+                push(i, end, Style::default().fg(Color::Rgb(0, 255, 180)));
+            } else if lower_q.as_ref().is_some_and(|q| !q.is_empty() && lower.contains(q.as_str())) {
+                push(i, end, Style::default().bg(Color::Rgb(255, 240, 0)).fg(Color::Black));
             } else {
-                spans.push(Span::raw(token.to_string()));
+                push(i, end, Style::default());
             }
             i = end; // already exclusive end (next token starts here)
         }
@@ -1077,9 +1289,10 @@ fn ui(f: &mut ratatui::Frame<'_>, app: &App) {
     };
 
     let mut lines_styled: Vec<Line> = Vec::new();
-    for l in rendered.lines() {
+    for l in &lines[start..end] {
         lines_styled.push(highlight(
             l,
+            crate::core::columns::visible_range(l, app.hscroll, width),
             if app.search_active && !app.search_query.is_empty() {
                 Some(app.search_query.as_str())
             } else {
@@ -1098,19 +1311,28 @@ fn ui(f: &mut ratatui::Frame<'_>, app: &App) {
                 .add_modifier(Modifier::ITALIC),
         )]));
     }
-    let buf_par = Paragraph::new(Text::from(lines_styled))
-        .block(buf_block)
-        .wrap(Wrap { trim: false });
-    f.render_widget(buf_par, left_split[0]);
+    // No wrapping: lines scroll horizontally with the cursor instead.
+    let buf_par = Paragraph::new(Text::from(lines_styled)).block(buf_block);
+    f.render_widget(buf_par, buf_area);
+
+    if let Some(map) = map_area {
+        // the line split above is the one the minimap folds; markers only change with their sources
+        app.minimap.resize(Geometry::new(lines.len(), map.height as usize));
+        app.minimap.set_diagnostics(app.diagnostics.iter().filter(|d| d.line > 0).map(|d| {
+            (d.line - 1, if d.severity == "error" { Marker::Error } else { Marker::Warning })
+        }));
+        app.minimap.set_matches(if app.search_active { &app.search_matches } else { &[] });
+        draw_minimap(f, map, &app.minimap, start, height);
+    }
 
     if matches!(app.mode, EditorMode::Insert) {
         let cursor_screen_row = app.cursor_row.saturating_sub(start);
         if cursor_screen_row < height {
-            let cursor_x =
-                (app.cursor_col.min(lines.get(app.cursor_row).map(|l| l.len()).unwrap_or(0)) + 1)
-                    as u16; // +1 for left border
-            let cursor_y = (left_split[0].y + 1 + cursor_screen_row as u16) as u16; // +1 for top
-            f.set_cursor(left_split[0].x + cursor_x, cursor_y);
+            // cursor_col is a byte offset; the terminal wants cells
+            let line = lines.get(app.cursor_row).copied().unwrap_or("");
+            let cursor_x = (app.cursor_display_x(line).saturating_sub(app.hscroll) + 1) as u16; // +1 for left border
+            let cursor_y = buf_area.y + 1 + cursor_screen_row as u16; // +1 for top
+            f.set_cursor(buf_area.x + cursor_x, cursor_y);
         }
     }
 
@@ -1127,9 +1349,11 @@ fn ui(f: &mut ratatui::Frame<'_>, app: &App) {
             Style::default().fg(Color::Rgb(190, 190, 200)),
         ))]
     } else {
-        app.diagnostics
-            .iter()
-            .map(|d| ListItem::new(d.as_str()))
+        let plain = crate::core::style::Style { color: false, unicode: true };
+        let opt = AnnotateOptions { width: left_split[1].width.saturating_sub(2) as usize, max: 0, full_source: false };
+        render_annotated(&plain, &app.buffer, &app.diagnostics, &opt)
+            .lines()
+            .map(|l| ListItem::new(l.to_string()))
             .collect()
     };
     let diags = List::new(diags_items)
@@ -1161,6 +1385,80 @@ fn ui(f: &mut ratatui::Frame<'_>, app: &App) {
     f.render_widget(cheats, main_split[1]);
 
     draw_status_and_input(f, rows[3], rows[4], app, accent, yellow);
+
+    if let Some(p) = &app.picker {
+        draw_picker(f, rows[2], p, accent, yellow);
+    }
+}
+
+/// One row per minimap row: a diagnostic dot, a search-match diamond, and a lighter
+/// background on the rows the view shows.
+fn draw_minimap(f: &mut ratatui::Frame<'_>, area: Rect, map: &Minimap, scroll: usize, height: usize) {
+    let geometry = map.geometry();
+    let viewport = geometry.viewport(scroll, height);
+    let rows: Vec<Line> = (0..area.height as usize)
+        .map(|row| {
+            let base = if viewport.contains(&row) { Style::default().bg(Color::Rgb(60, 60, 80)) } else { Style::default() };
+            let used = row < geometry.used_rows();
+            let diag = match map.diagnostic(row) {
+                Some(Marker::Error) => Span::styled("●", base.fg(Color::Red)),
+                Some(_) => Span::styled("●", base.fg(Color::Rgb(255, 240, 0))),
+                None => Span::styled(if used { "│" } else { " " }, base.fg(Color::Rgb(90, 90, 90))),
+            };
+            let hit = Span::styled(if map.has_match(row) { "◆" } else { " " }, base.fg(Color::Rgb(255, 170, 0)));
+            Line::from(vec![diag, hit])
+        })
+        .collect();
+    f.render_widget(Paragraph::new(Text::from(rows)), area);
+}
+
+/// Ctrl+O overlay: query line on top, ranked files below, recent ones marked.
+fn draw_picker(f: &mut ratatui::Frame<'_>, area: Rect, p: &Picker, accent: Color, yellow: Color) {
+    let width = (area.width * 3 / 4).max(20).min(area.width);
+    let height = (area.height * 3 / 4).max(6).min(area.height);
+    let rect = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    f.render_widget(Clear, rect);
+    let title = format!(
+        " Open — {} file{}{} ",
+        p.matches().len(),
+        if p.matches().len() == 1 { "" } else { "s" },
+        if p.scanning() { " (scanning…)" } else { "" }
+    );
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(Span::styled(title, Style::default().fg(accent).add_modifier(Modifier::BOLD)));
+    let inner = block.inner(rect);
+    f.render_widget(block, rect);
+    let parts = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(1)].as_ref())
+        .split(inner);
+    f.render_widget(
+        Paragraph::new(Line::from(vec![Span::styled("> ", Style::default().fg(yellow)), Span::raw(p.query.clone())])),
+        parts[0],
+    );
+    f.set_cursor(parts[0].x + 2 + crate::core::columns::display_width(&p.query) as u16, parts[0].y);
+    let items: Vec<ListItem> = p
+        .matches()
+        .iter()
+        .map(|m| {
+            let name = m.path.display().to_string();
+            if m.recent {
+                ListItem::new(Line::from(vec![Span::styled("● ", Style::default().fg(yellow)), Span::raw(name)]))
+            } else {
+                ListItem::new(Line::from(vec![Span::raw("  "), Span::raw(name)]))
+            }
+        })
+        .collect();
+    let list = List::new(items).highlight_style(Style::default().fg(Color::Black).bg(accent).add_modifier(Modifier::BOLD));
+    let mut state = ListState::default();
+    state.select(if p.matches().is_empty() { None } else { Some(p.selected) });
+    f.render_stateful_widget(list, parts[1], &mut state);
 }
 
 fn draw_status_and_input(
@@ -1218,6 +1516,8 @@ fn draw_buttons_row(f: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
             ButtonAction::ToggleMode => (Color::Black, Color::Rgb(130, 0, 200)),
             ButtonAction::Search => (Color::Black, Color::Rgb(255, 170, 0)),
             ButtonAction::ToggleMouse => (Color::Black, Color::Rgb(90, 90, 90)),
+            ButtonAction::ToggleMinimap => (Color::Black, Color::Rgb(0, 200, 255)),
+            ButtonAction::Format => (Color::Black, Color::Rgb(0, 255, 120)),
             ButtonAction::Quit => (Color::White, Color::Red),
         };
         let para = Paragraph::new(Line::from(vec![Span::styled(
@@ -1242,6 +1542,8 @@ fn compute_button_specs(area: Rect, app: &App) -> Vec<ButtonSpec> {
             },
         ),
         (ButtonAction::Search, "Search".to_string()),
+        (ButtonAction::Format, "Format".to_string()),
+        (ButtonAction::ToggleMinimap, format!("Map:{}", if app.show_minimap { "On" } else { "Off" })),
         (
             ButtonAction::ToggleMouse,
             format!("Mouse:{}", if app.mouse_capture { "On" } else { "Off" }),
@@ -0,0 +1,453 @@
+//! `aeonmi metrics-watch`: a live dashboard over the persisted inference metrics.
+//!
+//! The metrics file is loaded on start and re-read whenever its modification time
+//! changes, so the dashboard follows whatever process is recording. The file does not
+//! carry per-function timing windows, so the dashboard keeps its own: each reload that
+//! shows more runs for a function appends that function's `last_ns`. The reinfer rate is
+//! the growth of the `reinfer_events` counter over the last [`RATE_SPAN_MS`].
+//!
+//! [`Dashboard`] is the data layer (ranking, windows, rates); the rest of the module draws
+//! it with ratatui. Keys: `s` cycles the sort column, `p`/space pauses reloading, `e`
+//! exports the function metrics as CSV (the `metrics-export` format) and `q`/Esc/Ctrl-C quits.
+
+use anyhow::Result;
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style as TuiStyle},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table},
+    Frame, Terminal,
+};
+
+use crate::cli::TopSort;
+use crate::commands::metrics_top::{rank, TopRow};
+use crate::core::shutdown::ShutdownToken;
+use crate::core::incremental::{
+    function_metrics_csv, load_metrics, lock_cache, metrics_file_location, reload_metrics_from, CallGraphMetrics,
+    FunctionInferenceMetric, SavingsMetrics, CALL_GRAPH_METRICS, FUNCTION_METRICS, SAVINGS_METRICS,
+    WINDOW_CAP_RUNTIME,
+};
+use crate::core::style;
+
+/// How often the metrics file is checked and the screen redrawn.
+pub const REFRESH: Duration = Duration::from_secs(1);
+
+/// Span the reinfer rate is measured over.
+pub const RATE_SPAN_MS: u64 = 60_000;
+
+const SORTS: [TopSort; 4] = [TopSort::Ema, TopSort::Avg, TopSort::Total, TopSort::Runs];
+
+/// The metrics as loaded at one refresh.
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    pub functions: HashMap<usize, FunctionInferenceMetric>,
+    pub savings: SavingsMetrics,
+    pub graph: CallGraphMetrics,
+}
+
+impl Snapshot {
+    /// Copy of the global metrics.
+    pub fn capture() -> Self {
+        Snapshot {
+            functions: lock_cache("function_metrics", &FUNCTION_METRICS).clone(),
+            savings: lock_cache("savings", &SAVINGS_METRICS).clone(),
+            graph: lock_cache("call_graph", &CALL_GRAPH_METRICS).clone(),
+        }
+    }
+}
+
+/// One table row: the ranked function and its recent timings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchRow {
+    pub top: TopRow,
+    pub window: Vec<u128>,
+}
+
+/// Dashboard state between refreshes.
+#[derive(Debug)]
+pub struct Dashboard {
+    pub sort: TopSort,
+    pub paused: bool,
+    pub limit: usize,
+    /// Last line shown under the table (export result, reload error).
+    pub status: Option<String>,
+    snapshot: Snapshot,
+    windows: HashMap<usize, VecDeque<u128>>,
+    seen_runs: HashMap<usize, u64>,
+    /// `(epoch ms, reinfer_events)` samples, oldest first.
+    reinfers: VecDeque<(u64, usize)>,
+}
+
+impl Dashboard {
+    pub fn new(sort: TopSort, limit: usize) -> Self {
+        Dashboard {
+            sort,
+            paused: false,
+            limit,
+            status: None,
+            snapshot: Snapshot::default(),
+            windows: HashMap::new(),
+            seen_runs: HashMap::new(),
+            reinfers: VecDeque::new(),
+        }
+    }
+
+    /// Take in a freshly loaded snapshot taken at `now_ms`. Ignored while paused.
+    pub fn observe(&mut self, snapshot: Snapshot, now_ms: u64) {
+        if self.paused {
+            return;
+        }
+        let cap = WINDOW_CAP_RUNTIME.load(Ordering::Relaxed).max(1);
+        self.windows.retain(|idx, _| snapshot.functions.contains_key(idx));
+        self.seen_runs.retain(|idx, _| snapshot.functions.contains_key(idx));
+        for (&idx, m) in &snapshot.functions {
+            let window = self.windows.entry(idx).or_default();
+            if !m.window.is_empty() {
+                // Recorded in this process: the recorder's own window is exact.
+                *window = m.window.clone();
+            } else {
+                let seen = self.seen_runs.get(&idx).copied();
+                if seen.is_some_and(|s| m.runs < s) {
+                    window.clear(); // metrics were reset
+                }
+                if m.runs > 0 && seen.is_none_or(|s| m.runs != s) {
+                    window.push_back(m.last_ns);
+                }
+            }
+            while window.len() > cap {
+                window.pop_front();
+            }
+            self.seen_runs.insert(idx, m.runs);
+        }
+
+        let events = snapshot.graph.reinfer_events;
+        if self.reinfers.back().is_some_and(|&(_, e)| events < e) {
+            self.reinfers.clear();
+        }
+        self.reinfers.push_back((now_ms, events));
+        while self.reinfers.front().is_some_and(|&(t, _)| now_ms.saturating_sub(t) > RATE_SPAN_MS) {
+            self.reinfers.pop_front();
+        }
+        self.snapshot = snapshot;
+    }
+
+    pub fn cycle_sort(&mut self) {
+        let at = SORTS.iter().position(|s| *s == self.sort).unwrap_or(0);
+        self.sort = SORTS[(at + 1) % SORTS.len()];
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// The top [`Dashboard::limit`] functions by the current sort, with their windows.
+    pub fn rows(&self) -> Vec<WatchRow> {
+        rank(&self.snapshot.functions, self.sort, 0, self.limit)
+            .into_iter()
+            .map(|top| {
+                let window = self.windows.get(&top.index).map(|w| w.iter().copied().collect()).unwrap_or_default();
+                WatchRow { top, window }
+            })
+            .collect()
+    }
+
+    /// Savings since metrics began, in percent of the estimated full-inference time.
+    pub fn cumulative_savings_pct(&self) -> f64 {
+        let s = &self.snapshot.savings;
+        pct(s.cumulative_savings_ns, s.cumulative_estimated_full_ns)
+    }
+
+    /// Savings over the recent sample window, in percent.
+    pub fn recent_savings_pct(&self) -> f64 {
+        let s = &self.snapshot.savings;
+        pct(s.history.iter().map(|x| x.savings_ns).sum(), s.window_est_full_ns)
+    }
+
+    pub fn graph(&self) -> &CallGraphMetrics {
+        &self.snapshot.graph
+    }
+
+    /// Reinfer events per minute over the samples of the last [`RATE_SPAN_MS`].
+    pub fn reinfer_rate(&self) -> f64 {
+        match (self.reinfers.front(), self.reinfers.back()) {
+            (Some(&(t0, e0)), Some(&(t1, e1))) if t1 > t0 => (e1 - e0) as f64 * 60_000.0 / (t1 - t0) as f64,
+            _ => 0.0,
+        }
+    }
+}
+
+fn pct(part: u128, whole: u128) -> f64 {
+    if whole > 0 {
+        part as f64 * 100.0 / whole as f64
+    } else {
+        0.0
+    }
+}
+
+/// `values` as bar glyphs scaled to the largest, keeping the last `width` values.
+pub fn sparkline(values: &[u128], width: usize, unicode: bool) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    const ASCII: [char; 4] = ['_', '.', '-', '#'];
+    let bars: &[char] = if unicode { &BARS } else { &ASCII };
+    let values = &values[values.len().saturating_sub(width)..];
+    let max = values.iter().copied().max().unwrap_or(0);
+    values
+        .iter()
+        .map(|&v| bars[(v * (bars.len() as u128 - 1)).checked_div(max).unwrap_or(0) as usize])
+        .collect()
+}
+
+fn sort_name(sort: TopSort) -> &'static str {
+    match sort {
+        TopSort::Ema => "ema",
+        TopSort::Avg => "avg",
+        TopSort::Total => "total",
+        TopSort::Runs => "runs",
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Write the `metrics-export` CSV into the working directory; the status line to show.
+fn export_snapshot() -> String {
+    let Some(csv) = function_metrics_csv() else { return "no function metrics to export".into() };
+    let file = PathBuf::from(format!("aeonmi_metrics_{}.csv", now_ms() / 1000));
+    match std::fs::write(&file, csv) {
+        Ok(()) => format!("exported {}", file.display()),
+        Err(e) => format!("export error: {e}"),
+    }
+}
+
+const SPARK_WIDTH: usize = 16;
+
+fn draw(f: &mut Frame, d: &Dashboard, path: &Path, unicode: bool) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(4), Constraint::Min(3), Constraint::Length(1)])
+        .split(f.size());
+
+    let g = d.graph();
+    let state = if d.paused { Span::styled(" PAUSED", TuiStyle::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)) } else { Span::raw("") };
+    let summary = vec![
+        Line::from(vec![
+            Span::raw(format!("savings {:.1}% cumulative, {:.1}% recent", d.cumulative_savings_pct(), d.recent_savings_pct())),
+            state,
+        ]),
+        Line::from(format!(
+            "call graph {} functions, {} edges   reinfer {} events, {:.1}/min",
+            g.functions,
+            g.edges,
+            g.reinfer_events,
+            d.reinfer_rate()
+        )),
+    ];
+    let title = format!(" metrics-watch: {} ", path.display());
+    f.render_widget(Paragraph::new(summary).block(Block::default().borders(Borders::ALL).title(title)), chunks[0]);
+
+    let rows = d.rows();
+    let header = Row::new(["idx", "function", "location", "runs", "ema_ns", "avg_ns", "total_ns", "recent"])
+        .style(TuiStyle::default().add_modifier(Modifier::BOLD));
+    let body: Vec<Row> = rows
+        .iter()
+        .map(|r| {
+            Row::new(vec![
+                Cell::from(r.top.index.to_string()),
+                Cell::from(r.top.label()).style(TuiStyle::default().fg(Color::Cyan)),
+                Cell::from(r.top.location()),
+                Cell::from(r.top.runs.to_string()),
+                Cell::from(r.top.ema_ns.to_string()),
+                Cell::from(r.top.avg_ns.to_string()),
+                Cell::from(r.top.total_ns.to_string()),
+                Cell::from(sparkline(&r.window, SPARK_WIDTH, unicode)),
+            ])
+        })
+        .collect();
+    let widths = [
+        Constraint::Length(5),
+        Constraint::Min(12),
+        Constraint::Min(12),
+        Constraint::Length(7),
+        Constraint::Length(11),
+        Constraint::Length(11),
+        Constraint::Length(12),
+        Constraint::Length(SPARK_WIDTH as u16),
+    ];
+    let title = format!(" top {} by {} ", d.limit, sort_name(d.sort));
+    let table = Table::new(body, widths).header(header).block(Block::default().borders(Borders::ALL).title(title));
+    if rows.is_empty() {
+        f.render_widget(Paragraph::new("no function metrics yet").block(Block::default().borders(Borders::ALL)), chunks[1]);
+    } else {
+        f.render_widget(table, chunks[1]);
+    }
+
+    let keys = "s sort  p pause  e export csv  q quit";
+    let footer = match &d.status {
+        Some(s) => format!("{keys}   {s}"),
+        None => keys.to_string(),
+    };
+    f.render_widget(Paragraph::new(footer).style(TuiStyle::default().fg(Color::DarkGray)), chunks[2]);
+}
+
+fn run_loop(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, d: &mut Dashboard, path: &Path, stop: &ShutdownToken) -> Result<()> {
+    let unicode = style::current().unicode;
+    let mut seen_mtime = modified(path);
+    while !stop.is_requested() {
+        terminal.draw(|f| draw(f, d, path, unicode))?;
+        if event::poll(REFRESH)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    // raw mode turns Ctrl-C into a key press rather than a signal
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+                    KeyCode::Char('s') => d.cycle_sort(),
+                    KeyCode::Char('p') | KeyCode::Char(' ') => d.toggle_pause(),
+                    KeyCode::Char('e') => d.status = Some(export_snapshot()),
+                    _ => {}
+                }
+            }
+            continue;
+        }
+        if d.paused {
+            continue;
+        }
+        let mtime = modified(path);
+        if mtime != seen_mtime {
+            seen_mtime = mtime;
+            if let Err(e) = reload_metrics_from(path) {
+                d.status = Some(format!("reload failed: {e}"));
+            }
+        }
+        d.observe(Snapshot::capture(), now_ms());
+    }
+    Ok(())
+}
+
+pub fn main(limit: usize, sort: TopSort) -> Result<()> {
+    load_metrics();
+    let path = metrics_file_location();
+    let mut dashboard = Dashboard::new(sort, limit);
+    dashboard.observe(Snapshot::capture(), now_ms());
+
+    struct TerminalGuard;
+    impl Drop for TerminalGuard {
+        fn drop(&mut self) {
+            let _ = terminal::disable_raw_mode();
+            let _ = execute!(io::stdout(), LeaveAlternateScreen);
+        }
+    }
+    terminal::enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    let _guard = TerminalGuard;
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    let shutdown = crate::core::shutdown::listen();
+    let res = run_loop(&mut terminal, &mut dashboard, &path, shutdown.token());
+    let _ = terminal.show_cursor();
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::incremental::FunctionSite;
+
+    fn metric(runs: u64, last_ns: u128, ema_ns: u128, name: &str) -> FunctionInferenceMetric {
+        FunctionInferenceMetric {
+            runs,
+            last_ns,
+            ema_ns,
+            total_ns: last_ns * runs as u128,
+            site: Some(FunctionSite { name: name.into(), file: None, line: 1 }),
+            ..Default::default()
+        }
+    }
+
+    fn snapshot(functions: &[(usize, FunctionInferenceMetric)], reinfers: usize) -> Snapshot {
+        Snapshot {
+            functions: functions.iter().cloned().collect(),
+            graph: CallGraphMetrics { functions: functions.len(), reinfer_events: reinfers, ..Default::default() },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn windows_grow_only_when_runs_change() {
+        let mut d = Dashboard::new(TopSort::Ema, 10);
+        d.observe(snapshot(&[(0, metric(1, 100, 100, "a"))], 0), 0);
+        d.observe(snapshot(&[(0, metric(1, 100, 100, "a"))], 0), 1_000);
+        d.observe(snapshot(&[(0, metric(3, 300, 140, "a"))], 0), 2_000);
+        assert_eq!(d.rows()[0].window, vec![100, 300]);
+        // A reset (fewer runs than before) starts the window over.
+        d.observe(snapshot(&[(0, metric(1, 50, 50, "a"))], 0), 3_000);
+        assert_eq!(d.rows()[0].window, vec![50]);
+        // Paused dashboards ignore new data.
+        d.toggle_pause();
+        d.observe(snapshot(&[(0, metric(2, 70, 60, "a"))], 0), 4_000);
+        assert_eq!(d.rows()[0].window, vec![50]);
+    }
+
+    #[test]
+    fn rows_follow_the_sort_and_limit() {
+        let mut d = Dashboard::new(TopSort::Ema, 2);
+        let fns = [(0, metric(9, 10, 10, "many")), (1, metric(1, 500, 500, "slow")), (2, metric(2, 40, 40, "mid"))];
+        d.observe(snapshot(&fns, 0), 0);
+        let names = |d: &Dashboard| d.rows().iter().map(|r| r.top.label()).collect::<Vec<_>>();
+        assert_eq!(names(&d), ["slow", "mid"]);
+        d.cycle_sort();
+        assert_eq!(d.sort, TopSort::Avg);
+        d.cycle_sort();
+        d.cycle_sort();
+        assert_eq!(d.sort, TopSort::Runs);
+        assert_eq!(names(&d), ["many", "mid"]);
+        d.cycle_sort();
+        assert_eq!(d.sort, TopSort::Ema);
+    }
+
+    #[test]
+    fn reinfer_rate_and_savings() {
+        let mut d = Dashboard::new(TopSort::Ema, 10);
+        d.observe(snapshot(&[], 10), 0);
+        assert_eq!(d.reinfer_rate(), 0.0);
+        d.observe(snapshot(&[], 15), 30_000);
+        assert_eq!(d.reinfer_rate(), 10.0);
+        // Samples older than the span drop out.
+        d.observe(snapshot(&[], 15), 90_000);
+        assert_eq!(d.reinfer_rate(), 0.0);
+
+        let mut snap = snapshot(&[], 15);
+        snap.savings.push_sample(25, 100);
+        snap.savings.push_sample(50, 100);
+        d.observe(snap, 91_000);
+        assert_eq!(d.cumulative_savings_pct(), 62.5);
+        assert_eq!(d.recent_savings_pct(), 62.5);
+    }
+
+    #[test]
+    fn sparkline_scales_to_the_largest_value() {
+        assert_eq!(sparkline(&[0, 50, 100], 10, true), "▁▄█");
+        assert_eq!(sparkline(&[0, 50, 100], 10, false), "_.#");
+        assert_eq!(sparkline(&[1, 2, 3, 4], 2, true), "▆█");
+        assert_eq!(sparkline(&[0, 0], 4, true), "▁▁");
+        assert_eq!(sparkline(&[], 4, true), "");
+    }
+}
@@ -0,0 +1,173 @@
+//! The editor's minimap: a column at the right edge of the buffer with one row per
+//! `lines_per_row` buffer lines, marking diagnostics, search matches and the lines on
+//! screen. Clicking a row scrolls the buffer there.
+//!
+//! Marker counts are kept per row and adjusted by what changed when the diagnostics or
+//! search matches are replaced; only a new line count or height re-buckets everything.
+
+use std::ops::Range;
+
+/// Terminal cells the column takes: diagnostics on the left, search matches on the right.
+pub const WIDTH: u16 = 2;
+
+/// In the order a row shows them: one row holding an error and a match draws the error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Marker {
+    Error,
+    Warning,
+    Match,
+}
+
+/// How the buffer's lines fold onto the rows of the column.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Geometry {
+    pub total_lines: usize,
+    pub rows: usize,
+}
+
+impl Geometry {
+    pub fn new(total_lines: usize, rows: usize) -> Self {
+        Self { total_lines, rows }
+    }
+
+    /// Buffer lines per row: 1 while the buffer fits, then as few as fill every row.
+    pub fn lines_per_row(&self) -> usize {
+        self.total_lines.div_ceil(self.rows.max(1)).max(1)
+    }
+
+    /// Rows that stand for some line; the rest of the column is blank.
+    pub fn used_rows(&self) -> usize {
+        self.total_lines.div_ceil(self.lines_per_row()).min(self.rows)
+    }
+
+    /// The row 0-based `line` is drawn on.
+    pub fn row_of(&self, line: usize) -> usize {
+        (line / self.lines_per_row()).min(self.rows.saturating_sub(1))
+    }
+
+    /// The first line `row` stands for, clamped to the last line.
+    pub fn first_line(&self, row: usize) -> usize {
+        (row * self.lines_per_row()).min(self.total_lines.saturating_sub(1))
+    }
+
+    /// Rows of the lines a view `height` tall shows from `scroll`.
+    pub fn viewport(&self, scroll: usize, height: usize) -> Range<usize> {
+        if self.total_lines == 0 || self.rows == 0 || scroll >= self.total_lines {
+            return 0..0;
+        }
+        let last = (scroll + height.max(1)).min(self.total_lines) - 1;
+        self.row_of(scroll)..self.row_of(last) + 1
+    }
+
+    /// Scroll that centres the lines of `row` in a view `height` tall, without scrolling
+    /// past the last screenful.
+    pub fn scroll_for_click(&self, row: usize, height: usize) -> usize {
+        let middle = self.first_line(row) + self.lines_per_row() / 2;
+        let max_scroll = self.total_lines.saturating_sub(height);
+        middle.saturating_sub(height / 2).min(max_scroll)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Minimap {
+    geometry: Geometry,
+    /// What the counts were built from, sorted: 0-based lines.
+    diagnostics: Vec<(usize, Marker)>,
+    matches: Vec<usize>,
+    /// Per row, indexed by `Marker as usize`.
+    counts: Vec<[u32; 3]>,
+}
+
+impl Minimap {
+    pub fn geometry(&self) -> Geometry {
+        self.geometry
+    }
+
+    /// Fold onto `geometry`, re-bucketing the markers when it differs from the last one.
+    pub fn resize(&mut self, geometry: Geometry) {
+        if geometry == self.geometry && self.counts.len() == geometry.rows {
+            return;
+        }
+        self.geometry = geometry;
+        self.counts = vec![[0; 3]; geometry.rows];
+        for (line, marker) in self.diagnostics.clone() {
+            self.bump(line, marker, 1);
+        }
+        for line in self.matches.clone() {
+            self.bump(line, Marker::Match, 1);
+        }
+    }
+
+    /// Replace the diagnostic markers (0-based lines); only rows whose markers changed are touched.
+    pub fn set_diagnostics(&mut self, diagnostics: impl IntoIterator<Item = (usize, Marker)>) {
+        let mut new: Vec<(usize, Marker)> = diagnostics.into_iter().collect();
+        new.sort_unstable();
+        if new == self.diagnostics {
+            return;
+        }
+        let old = std::mem::replace(&mut self.diagnostics, new);
+        let new = self.diagnostics.clone();
+        diff_sorted(&old, &new, |(line, marker), delta| self.bump(line, marker, delta));
+    }
+
+    /// Replace the search-match lines (0-based, ascending); only rows whose markers changed are touched.
+    pub fn set_matches(&mut self, lines: &[usize]) {
+        if lines == self.matches.as_slice() {
+            return;
+        }
+        let old = std::mem::replace(&mut self.matches, lines.to_vec());
+        diff_sorted(&old, lines, |line, delta| self.bump(line, Marker::Match, delta));
+    }
+
+    /// Markers on `row`, as counts indexed by `Marker as usize`.
+    pub fn counts(&self, row: usize) -> [u32; 3] {
+        self.counts.get(row).copied().unwrap_or_default()
+    }
+
+    /// The diagnostic a row shows, if any: errors before warnings.
+    pub fn diagnostic(&self, row: usize) -> Option<Marker> {
+        let c = self.counts(row);
+        [Marker::Error, Marker::Warning].into_iter().find(|m| c[*m as usize] > 0)
+    }
+
+    pub fn has_match(&self, row: usize) -> bool {
+        self.counts(row)[Marker::Match as usize] > 0
+    }
+
+    fn bump(&mut self, line: usize, marker: Marker, delta: i32) {
+        if self.geometry.total_lines == 0 || line >= self.geometry.total_lines {
+            return;
+        }
+        let row = self.geometry.row_of(line);
+        if let Some(c) = self.counts.get_mut(row) {
+            c[marker as usize] = c[marker as usize].saturating_add_signed(delta);
+        }
+    }
+}
+
+/// Call `f(item, -1)` for each item only in `old` and `f(item, 1)` for each only in `new`
+/// (both sorted; repeats count separately).
+fn diff_sorted<T: Ord + Copy>(old: &[T], new: &[T], mut f: impl FnMut(T, i32)) {
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        match (old.get(i), new.get(j)) {
+            (Some(a), Some(b)) if a == b => {
+                i += 1;
+                j += 1;
+            }
+            (Some(a), Some(b)) if a < b => {
+                f(*a, -1);
+                i += 1;
+            }
+            (Some(a), None) => {
+                f(*a, -1);
+                i += 1;
+            }
+            (_, Some(b)) => {
+                f(*b, 1);
+                j += 1;
+            }
+            (None, None) => unreachable!(),
+        }
+    }
+}
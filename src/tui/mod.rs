@@ -1,5 +1,8 @@
 // src/tui/mod.rs
 pub mod editor;
+pub mod metrics_watch;
+pub mod minimap;
+pub mod picker;
 // ...
 // launch with optional path
 // Editor::new(Some(PathBuf::from("tests/linear_algebra.ai")))?.run()?;
@@ -0,0 +1,261 @@
+//! Ctrl+O open-file picker: `.ai` files under the project root, fuzzy-filtered as you type,
+//! with the last [`RECENT_LIMIT`] opened files on top.
+//!
+//! The tree is walked on a background thread ([`Walker`]) that streams paths through a
+//! bounded channel, so a large repository fills the list progressively instead of freezing
+//! the editor. Closing the overlay drops the walker, which tells the thread to stop.
+//! Recent files live in `<root>/.aeonmi/recent-files.json`, relative to the root.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::io::discover;
+
+/// How many recently opened files are remembered.
+pub const RECENT_LIMIT: usize = 10;
+
+/// Paths the walker may queue before it waits for the editor to take them.
+pub const CHANNEL_CAPACITY: usize = 256;
+
+/// Score of `candidate` for `query`, or `None` when the query's characters (whitespace
+/// ignored, case-insensitive) do not all appear in order. Higher is better: consecutive
+/// runs, matches at the start of a path component or word, and matches inside the file
+/// name score up; skipped characters and long paths score down.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+    let Some(&first) = query.first() else { return Some(0) };
+    let cand: Vec<char> = candidate.chars().collect();
+    let name_start = cand.iter().rposition(|c| matches!(c, '/' | '\\')).map_or(0, |i| i + 1);
+    // Greedy from every place the first character occurs; keep the best alignment.
+    (0..cand.len())
+        .filter(|&i| same(cand[i], first))
+        .filter_map(|start| score_from(&query, &cand, start, name_start))
+        .max()
+        .map(|s| s - cand.len() as i64 / 8)
+}
+
+fn same(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+fn score_from(query: &[char], cand: &[char], start: usize, name_start: usize) -> Option<i64> {
+    let (mut score, mut next, mut prev) = (0i64, start, None::<usize>);
+    for &q in query {
+        let pos = (next..cand.len()).find(|&i| same(cand[i], q))?;
+        score += 1;
+        if q == cand[pos] {
+            score += 1;
+        }
+        if pos > 0 && prev == Some(pos - 1) {
+            score += 8;
+        } else if prev.is_some() {
+            score -= (pos - next).min(3) as i64;
+        }
+        if pos == 0 || matches!(cand[pos - 1], '/' | '\\' | '_' | '-' | '.' | ' ') {
+            score += 6;
+        }
+        if pos >= name_start {
+            score += 2;
+        }
+        prev = Some(pos);
+        next = pos + 1;
+    }
+    Some(score)
+}
+
+/// Background walk of `.ai` files under a root, streaming root-relative paths.
+pub struct Walker {
+    rx: Option<Receiver<PathBuf>>,
+    cancel: Arc<AtomicBool>,
+    handle: Option<JoinHandle<usize>>,
+}
+
+impl Walker {
+    pub fn spawn(root: PathBuf) -> Self {
+        let (tx, rx) = sync_channel(CHANNEL_CAPACITY);
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&cancel);
+        let handle = thread::spawn(move || {
+            let mut sent = 0;
+            let exts = [discover::DEFAULT_EXTS[0].to_string()];
+            let _ = discover::walk_each(&root, &exts, |p| {
+                if flag.load(Ordering::Relaxed) {
+                    return false;
+                }
+                let rel = p.strip_prefix(&root).map(Path::to_path_buf).unwrap_or(p);
+                // Fails once the receiver is gone, which also ends the walk.
+                if tx.send(rel).is_err() {
+                    return false;
+                }
+                sent += 1;
+                true
+            });
+            sent
+        });
+        Walker { rx: Some(rx), cancel, handle: Some(handle) }
+    }
+
+    /// Paths found since the last call, without blocking.
+    pub fn drain(&mut self) -> Vec<PathBuf> {
+        self.rx.as_ref().map(|rx| rx.try_iter().collect()).unwrap_or_default()
+    }
+
+    /// Whether the walk has ended; paths may still be queued for [`Walker::drain`].
+    pub fn finished(&self) -> bool {
+        self.handle.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+
+    /// Stop the walk and wait for the thread; returns how many paths it sent.
+    #[allow(dead_code)] // the editor only drops its walker
+    pub fn cancel(mut self) -> usize {
+        self.signal();
+        self.handle.take().and_then(|h| h.join().ok()).unwrap_or(0)
+    }
+
+    /// Ask the thread to stop. Dropping the receiver unblocks a send on a full channel.
+    fn signal(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+        self.rx = None;
+    }
+}
+
+impl Drop for Walker {
+    /// Signals without joining, so closing the overlay never waits on a slow filesystem.
+    fn drop(&mut self) {
+        self.signal();
+    }
+}
+
+/// One row of the picker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    pub path: PathBuf,
+    pub recent: bool,
+}
+
+/// State of the open-file overlay.
+pub struct Picker {
+    pub query: String,
+    pub selected: usize,
+    recent: Vec<PathBuf>,
+    files: Vec<PathBuf>,
+    ranked: Vec<Match>,
+    walker: Option<Walker>,
+}
+
+impl Picker {
+    /// Start walking `root`; `recent` (root-relative, newest first) is listed until the
+    /// walk catches up, and stays on top afterwards.
+    pub fn open(root: &Path, recent: Vec<PathBuf>) -> Self {
+        let recent = recent.into_iter().filter(|p| root.join(p).is_file()).collect();
+        let mut picker = Picker {
+            query: String::new(),
+            selected: 0,
+            recent,
+            files: Vec::new(),
+            ranked: Vec::new(),
+            walker: Some(Walker::spawn(root.to_path_buf())),
+        };
+        picker.rank();
+        picker
+    }
+
+    /// Take in what the walker found since the last call; returns whether anything changed
+    /// (new paths, or the walk ending).
+    pub fn poll(&mut self) -> bool {
+        let Some(walker) = self.walker.as_mut() else { return false };
+        let done = walker.finished();
+        let found = walker.drain();
+        if done {
+            self.walker = None;
+        }
+        if found.is_empty() {
+            return done;
+        }
+        self.files.extend(found);
+        self.rank();
+        true
+    }
+
+    /// Whether the background walk is still running.
+    pub fn scanning(&self) -> bool {
+        self.walker.is_some()
+    }
+
+    pub fn matches(&self) -> &[Match] {
+        &self.ranked
+    }
+
+    pub fn selection(&self) -> Option<&Path> {
+        self.ranked.get(self.selected).map(|m| m.path.as_path())
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.query.push(c);
+        self.selected = 0;
+        self.rank();
+    }
+
+    pub fn pop(&mut self) {
+        self.query.pop();
+        self.selected = 0;
+        self.rank();
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        let last = self.ranked.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    /// Recent files first (newest first, or by score), then the rest by score, then path.
+    fn rank(&mut self) {
+        let score = |p: &PathBuf| fuzzy_score(&self.query, &p.to_string_lossy().replace('\\', "/"));
+        let mut recent: Vec<(i64, usize, &PathBuf)> =
+            self.recent.iter().enumerate().filter_map(|(i, p)| score(p).map(|s| (s, i, p))).collect();
+        recent.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        let mut rest: Vec<(i64, &PathBuf)> =
+            self.files.iter().filter(|p| !self.recent.contains(p)).filter_map(|p| score(p).map(|s| (s, p))).collect();
+        rest.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        let ranked: Vec<Match> = recent
+            .into_iter()
+            .map(|(_, _, p)| Match { path: p.clone(), recent: true })
+            .chain(rest.into_iter().map(|(_, p)| Match { path: p.clone(), recent: false }))
+            .collect();
+        self.selected = self.selected.min(ranked.len().saturating_sub(1));
+        self.ranked = ranked;
+    }
+}
+
+fn recent_path(root: &Path) -> PathBuf {
+    root.join(".aeonmi").join("recent-files.json")
+}
+
+/// `file` relative to `root` when it lives under it, as stored in the recent list.
+pub fn relative_to(root: &Path, file: &Path) -> PathBuf {
+    let canon = |p: &Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+    canon(file).strip_prefix(canon(root)).map(Path::to_path_buf).unwrap_or_else(|_| file.to_path_buf())
+}
+
+/// Recently opened files under `root`, newest first; empty when none were recorded.
+pub fn load_recent(root: &Path) -> Vec<PathBuf> {
+    std::fs::read_to_string(recent_path(root)).ok().and_then(|t| serde_json::from_str(&t).ok()).unwrap_or_default()
+}
+
+/// Move `file` to the front of `root`'s recent list, keeping at most [`RECENT_LIMIT`].
+pub fn remember(root: &Path, file: &Path) -> Result<Vec<PathBuf>> {
+    let file = relative_to(root, file);
+    let mut recent = load_recent(root);
+    recent.retain(|p| *p != file);
+    recent.insert(0, file);
+    recent.truncate(RECENT_LIMIT);
+    let path = recent_path(root);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(&recent)?)?;
+    Ok(recent)
+}
@@ -80,7 +80,12 @@ pub struct DomainQuantumVault {
 
 impl DomainQuantumVault {
     pub fn open_default() -> Result<Self> {
-        let root = default_vault_path()?;
+        Self::open_at(default_vault_path()?)
+    }
+
+    /// Open the vault stored at `root`, creating an empty one (with fresh keys) if missing.
+    pub fn open_at<P: AsRef<Path>>(root: P) -> Result<Self> {
+        let root = root.as_ref().to_path_buf();
         if let Some(parent) = root.parent() {
             fs::create_dir_all(parent).context("create vault directory")?;
         }
@@ -463,7 +468,11 @@ impl DomainQuantumVault {
     }
 }
 
+/// `~/.aeonmi/vault/domain_quantum_vault.json`, or `AEONMI_VAULT_PATH` when set.
 fn default_vault_path() -> Result<PathBuf> {
+    if let Some(p) = std::env::var_os("AEONMI_VAULT_PATH").filter(|p| !p.is_empty()) {
+        return Ok(PathBuf::from(p));
+    }
     let mut path = dirs_next::home_dir().ok_or_else(|| anyhow!("home directory not found"))?;
     path.push(".aeonmi");
     path.push("vault");
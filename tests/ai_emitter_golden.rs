@@ -18,6 +18,8 @@ fn ai_emitter_golden_minimal() {
                         Stmt::Let {
                             name: "x".into(),
                             value: Some(Expr::Lit(Lit::Number(2.0))),
+                            line: 0,
+                            column: 0,
                         },
                         Stmt::Expr(Expr::Call {
                             callee: Box::new(Expr::Ident("print".into())),
@@ -0,0 +1,117 @@
+//! Provider fallback chains: a scripted provider that fails in front of the mock.
+use aeonmi_project::ai::cancel::CancelToken;
+use aeonmi_project::ai::mock::Mock;
+use aeonmi_project::ai::{AiProvider, AiRegistry, ProviderError, StreamEvent, RATE_LIMIT_RETRIES};
+use aeonmi_project::config::AiSettings;
+use anyhow::{bail, Result};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Once};
+use std::time::Duration;
+
+/// Fails every call with `status`, or with a content error when `status` is 0.
+struct Down {
+    status: u16,
+    calls: Arc<AtomicUsize>,
+}
+
+impl AiProvider for Down {
+    fn name(&self) -> &'static str { "down" }
+    fn chat(&self, _prompt: &str) -> Result<String> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if self.status == 0 {
+            bail!("empty response");
+        }
+        Err(ProviderError::Http { provider: "down", status: self.status, body: "no".into() }.into())
+    }
+    fn chat_stream(&self, prompt: &str, _cancel: &CancelToken, cb: &mut dyn FnMut(&str)) -> Result<()> {
+        cb("half a ");
+        self.chat(prompt).map(|_| ())
+    }
+}
+
+fn registry(status: u16) -> (AiRegistry, Arc<AtomicUsize>) {
+    // the usage ledger goes to a scratch dir
+    static LEDGER: Once = Once::new();
+    LEDGER.call_once(|| std::env::set_var("AEONMI_CONFIG_DIR", std::env::temp_dir().join("aeonmi-fallback-test")));
+    let calls = Arc::new(AtomicUsize::new(0));
+    let providers: Vec<Box<dyn AiProvider>> = vec![Box::new(Down { status, calls: calls.clone() }), Box::new(Mock::default())];
+    let reg = AiRegistry::with_providers(providers)
+        .with_fallback(vec!["down".into(), "ollama".into(), "mock".into()])
+        .with_retry_delay(Duration::ZERO);
+    (reg, calls)
+}
+
+#[test]
+fn outage_falls_over_to_the_next_provider() {
+    let (reg, calls) = registry(503);
+    assert_eq!(reg.chain().iter().map(|p| p.name()).collect::<Vec<_>>(), ["down", "mock"], "ollama is not enabled");
+    let answer = reg.chat_with_fallback("hello").unwrap();
+    assert_eq!((answer.provider, answer.reply.as_str()), ("mock", "mock: hello"));
+    assert_eq!(answer.failed.len(), 1);
+    assert_eq!(answer.failed[0].error, "down http error 503: no");
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn rate_limits_are_retried_before_moving_on() {
+    let (reg, calls) = registry(429);
+    assert_eq!(reg.chat_with_fallback("hello").unwrap().provider, "mock");
+    assert_eq!(calls.load(Ordering::SeqCst), RATE_LIMIT_RETRIES as usize + 1);
+}
+
+#[test]
+fn content_errors_do_not_fall_over() {
+    for status in [0, 400] {
+        let (reg, calls) = registry(status);
+        let err = reg.chat_with_fallback("hello").unwrap_err();
+        assert!(err.to_string().contains(if status == 0 { "empty response" } else { "400" }), "{err}");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
+
+#[test]
+fn broken_stream_restarts_with_a_notice() {
+    let (reg, _) = registry(502);
+    let mut seen = Vec::new();
+    let (answered, failed) = reg
+        .chat_stream_with_fallback("hello", &CancelToken::new(), &mut |e| match e {
+            StreamEvent::Chunk(c) => seen.push(c.to_string()),
+            StreamEvent::Restart { failed, next, .. } => seen.push(format!("<restart {failed} -> {next}>")),
+        })
+        .unwrap();
+    assert_eq!((answered, failed.len()), ("mock", 1));
+    assert_eq!(seen.concat(), "half a <restart down -> mock>mock: hello");
+}
+
+#[test]
+fn cli_reports_who_answered() {
+    assert_eq!(AiSettings::from_toml_str("[ai]\nfallback = [\"a\", \"b\"]\n").unwrap().fallback, ["a", "b"]);
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("qpoly.toml");
+    std::fs::write(&config, "[ai]\nfallback = [\"openai\", \"mock-outage\", \"mock\"]\n").unwrap();
+    let chat = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+            .arg("--config")
+            .arg(&config)
+            .args(["ai", "chat"])
+            .args(args)
+            .env("AEONMI_AI_MOCK", "1")
+            .env("AEONMI_AI_MOCK_OUTAGE", "503")
+            .env("AEONMI_CONFIG_DIR", dir.path())
+            .env("NO_COLOR", "1")
+            .output()
+            .expect("failed to run aeonmi_project")
+    };
+    let out = chat(&["--json", "hi"]);
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(v["provider"], "mock");
+    assert_eq!(v["reply"], "mock: hi");
+    assert_eq!(v["failed"][0]["provider"], "mock-outage");
+
+    let out = chat(&["--stream", "hi"]);
+    let (stdout, stderr) = (String::from_utf8_lossy(&out.stdout), String::from_utf8_lossy(&out.stderr));
+    assert_eq!(stdout, "mock: \nmock: hi\n");
+    assert!(stderr.contains("mock-outage failed (mock-outage http error 503: simulated outage); restarting with mock"), "{stderr}");
+    assert!(stderr.contains("[mock]"), "{stderr}");
+}
@@ -0,0 +1,113 @@
+use aeonmi_project::ai::review::{chunk, parse_diff, parse_reply, PromptTemplate, TOP_LEVEL};
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+const ORIGINAL: &str = "function add(a, b) {\n    return a + b;\n}\n\nfunction main() {\n    log(add(1, 2));\n}\n";
+const CHANGED: &str = "function add(a, b) {\n    return a + b;\n}\n\nfunction main() {\n    log(add(1, 2));\n    missing = 3;\n}\n";
+
+fn git(dir: &Path, args: &[&str]) {
+    let st = Command::new("git").current_dir(dir).args(args).output().expect("git");
+    assert!(st.status.success(), "git {args:?}: {}", String::from_utf8_lossy(&st.stderr));
+}
+
+/// A repository with one committed .ai file, modified (unstaged) in the working tree.
+fn repo() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    git(dir.path(), &["config", "user.email", "dev@example.com"]);
+    git(dir.path(), &["config", "user.name", "dev"]);
+    fs::write(dir.path().join("prog.ai"), ORIGINAL).unwrap();
+    fs::write(dir.path().join("notes.txt"), "x\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "init"]);
+    fs::write(dir.path().join("prog.ai"), CHANGED).unwrap();
+    fs::write(dir.path().join("notes.txt"), "y\n").unwrap();
+    dir
+}
+
+fn aeonmi(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .current_dir(dir)
+        .arg("--quiet")
+        .args(args)
+        .env("AEONMI_AI_MOCK", "1")
+        .env("AEONMI_CONFIG_DIR", dir.join(".cfg"))
+        .env("AEONMI_UNICODE", "0")
+        .output()
+        .expect("failed to run aeonmi_project")
+}
+
+#[test]
+fn diff_is_chunked_per_function() {
+    let diff = "diff --git a/p.ai b/p.ai\n--- a/p.ai\n+++ b/p.ai\n@@ -5,3 +5,4 @@ function main() {\n function main() {\n     log(add(1, 2));\n+    missing = 3;\n }\n";
+    let files = parse_diff(diff);
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].path, "p.ai");
+    assert!(files[0].changed.contains(&7));
+    let chunks = chunk(&files[0], CHANGED);
+    assert_eq!(chunks.len(), 1, "{chunks:?}");
+    assert_eq!(chunks[0].scope, "main");
+    assert_eq!((chunks[0].start, chunks[0].end), (5, 8));
+    assert!(chunks[0].source.contains("missing = 3"));
+    assert_ne!(chunks[0].scope, TOP_LEVEL);
+
+    let prompt = PromptTemplate::default().render(&chunks[0]);
+    assert!(prompt.contains("p.ai") && prompt.contains("missing = 3"), "{prompt}");
+    let comments = parse_reply(&chunks[0], "- L7 [warning] `missing` is not defined\nLGTM otherwise", "mock");
+    assert_eq!(comments.len(), 1);
+    assert_eq!((comments[0].line, comments[0].severity.as_str()), (7, "warning"));
+    assert!(parse_reply(&chunks[0], "LGTM", "mock").is_empty());
+}
+
+#[test]
+fn offline_review_reports_lint_on_changed_lines() {
+    let dir = repo();
+    let out = aeonmi(dir.path(), &["ai", "review", "--offline", "--json"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    let chunks = v["chunks"].as_array().unwrap();
+    assert_eq!(chunks.len(), 1, "only prog.ai is reviewed: {v}");
+    assert_eq!(chunks[0]["scope"], "main");
+    let comments = v["comments"].as_array().unwrap();
+    assert!(!comments.is_empty(), "{v}");
+    assert!(comments.iter().all(|c| c["line"] == 7 && c["source"] == "lint"), "{v}");
+
+    // nothing staged yet
+    let out = aeonmi(dir.path(), &["ai", "review", "--offline", "--staged"]);
+    assert!(out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).contains("no .ai changes"));
+}
+
+#[test]
+fn mock_provider_reviews_staged_and_range() {
+    let dir = repo();
+    git(dir.path(), &["add", "prog.ai"]);
+    let out = aeonmi(dir.path(), &["ai", "review", "--staged"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let text = String::from_utf8_lossy(&out.stdout);
+    assert!(text.contains("prog.ai") && text.contains("mock:"), "{text}");
+    assert!(text.contains("1 comment in 1 file (1 chunk reviewed)"), "{text}");
+
+    git(dir.path(), &["commit", "-q", "-m", "use missing"]);
+    let out = aeonmi(dir.path(), &["ai", "review", "--range", "HEAD~1..HEAD", "--json"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(v["comments"][0]["source"], "mock");
+    assert_eq!(v["comments"][0]["file"], "prog.ai");
+}
+
+#[test]
+fn prompt_template_can_be_overridden_from_config() {
+    let dir = repo();
+    fs::write(dir.path().join("review.txt"), "REVIEW {{scope}} lines {{changed}}").unwrap();
+    fs::write(dir.path().join("aeonmi.toml"), "[ai.prompts]\nreview = \"review.txt\"\n").unwrap();
+    let out = aeonmi(dir.path(), &["--config", "aeonmi.toml", "ai", "review", "--json"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(v["comments"][0]["message"], "mock: REVIEW main lines 7");
+
+    fs::write(dir.path().join("aeonmi.toml"), "[ai.prompts]\nreview = \"gone.txt\"\n").unwrap();
+    let out = aeonmi(dir.path(), &["--config", "aeonmi.toml", "ai", "review"]);
+    assert_eq!(out.status.code(), Some(2));
+}
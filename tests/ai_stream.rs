@@ -0,0 +1,86 @@
+use aeonmi_project::ai::cancel::{self, CancelToken};
+use aeonmi_project::ai::markdown::render;
+use aeonmi_project::ai::mock::Mock;
+use aeonmi_project::ai::AiProvider;
+use aeonmi_project::core::style::Style;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+const PROMPT: &str = "one two three four five six seven";
+
+#[test]
+fn uncancelled_stream_matches_full_reply() {
+    let mock = Mock { delay: Duration::from_millis(1), ..Default::default() };
+    let mut chunks = Vec::new();
+    mock.chat_stream(PROMPT, &CancelToken::new(), &mut |c| chunks.push(c.to_string())).unwrap();
+    assert_eq!(chunks.len(), 8);
+    assert_eq!(chunks.concat(), mock.chat(PROMPT).unwrap());
+}
+
+#[test]
+fn cancel_mid_stream_stops_early_with_partial_output() {
+    let mock = Mock { delay: Duration::from_millis(40), ..Default::default() };
+    let token = CancelToken::new();
+    let remote = token.clone();
+    let canceller = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(100));
+        remote.cancel();
+    });
+    let start = Instant::now();
+    let mut chunks = Vec::new();
+    let err = mock.chat_stream(PROMPT, &token, &mut |c| chunks.push(c.to_string())).unwrap_err();
+    canceller.join().unwrap();
+    assert!(cancel::is_cancelled(&err), "{err}");
+    assert!(!chunks.is_empty() && chunks.len() < 8, "got {} chunks", chunks.len());
+    // the pending delay is cut short rather than run to completion
+    assert!(start.elapsed() < Duration::from_millis(8 * 40));
+}
+
+#[test]
+fn cancelled_before_start_sends_nothing() {
+    let token = CancelToken::new();
+    token.cancel();
+    let mut called = false;
+    let err = Mock::default().chat_stream(PROMPT, &token, &mut |_| called = true).unwrap_err();
+    assert!(cancel::is_cancelled(&err));
+    assert!(!called);
+}
+
+#[test]
+fn interrupt_cancels_only_the_active_token() {
+    let token = CancelToken::new();
+    cancel::set_active(Some(token.clone()));
+    assert!(cancel::interrupt());
+    assert!(token.is_cancelled());
+    cancel::set_active(None);
+    assert!(!cancel::interrupt());
+}
+
+#[test]
+fn markdown_rendering() {
+    let reply = "# Title\nsome **bold** and `code`\n```aeonmi\nlet x = 1;\n```\n";
+    assert_eq!(render(reply, Style::PLAIN), reply);
+    let rich = render(reply, Style::RICH);
+    assert!(rich.contains("\x1b[1mbold\x1b[0m"), "{rich:?}");
+    assert!(!rich.contains("**"));
+    assert!(!rich.contains("# Title"));
+    // `let` is colored as a keyword inside the aeonmi block
+    assert!(rich.contains("mlet\x1b[0m"), "{rich:?}");
+    // other languages pass through untouched
+    assert!(render("```python\nlet = 1\n```\n", Style::RICH).contains("\nlet = 1\n"));
+}
+
+#[test]
+fn cli_stream_prints_only_the_reply_on_stdout() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .args(["ai", "chat", "--stream", "--provider", "mock", "hello there"])
+        .env("AEONMI_AI_MOCK", "1")
+        .env("AEONMI_AI_MOCK_DELAY_MS", "5")
+        .env("AEONMI_CONFIG_DIR", dir.path())
+        .output()
+        .expect("spawn aeonmi");
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "mock: hello there\n");
+    assert!(String::from_utf8_lossy(&out.stderr).contains("[mock]"));
+}
@@ -0,0 +1,81 @@
+use aeonmi_project::ai::suggest::{check, extract_code, insert, parse_location, SuggestTemplate, CODE_ONLY};
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn aeonmi(dir: &Path, bad_code: &str, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .current_dir(dir)
+        .args(args)
+        .env("AEONMI_AI_MOCK", "1")
+        .env("AEONMI_AI_MOCK_BAD_CODE", bad_code)
+        .env("AEONMI_CONFIG_DIR", dir.join(".cfg"))
+        .env("AEONMI_UNICODE", "0")
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("failed to run aeonmi_project")
+}
+
+#[test]
+fn replies_are_unfenced_and_parse_checked() {
+    assert_eq!(extract_code("Sure:\n```aeonmi\nlet x = 1;\n```\nEnjoy."), "let x = 1;\n");
+    assert_eq!(extract_code("\nlog(1);\n\n"), "log(1);\n");
+    assert!(check("function f(a) {\n    return a;\n}\n").is_ok());
+    let err = check("function f(a) {\n    return a;\n").unwrap_err();
+    assert!(err.starts_with("3:"), "{err}");
+    assert!(check("   \n").is_err());
+    let prompt = SuggestTemplate::default().render("  a function that deduplicates a list ");
+    assert!(prompt.contains("Write this: a function that deduplicates a list\n") && prompt.contains(CODE_ONLY), "{prompt}");
+}
+
+#[test]
+fn inserted_code_takes_the_indentation_of_its_block() {
+    let text = "function main() {\n    let a = 1;\n    if (a) {\n        log(a);\n    }\n}\n";
+    let code = "  let b = 2;\n  while (b) {\n      b = 0;\n  }\n";
+    let out = insert(text, 5, code).unwrap();
+    assert!(out.contains("        log(a);\n        let b = 2;\n        while (b) {\n            b = 0;\n        }\n    }\n"), "{out}");
+    let out = insert(text, 4, "log(0);\n").unwrap();
+    assert!(out.contains("    if (a) {\n        log(0);\n        log(a);"), "{out}");
+    assert!(insert(text, 7, "log(0);\n").unwrap().ends_with("}\nlog(0);\n"));
+    assert!(insert(text, 8, "log(0);\n").is_err());
+    assert_eq!(parse_location("src/a.ai:12").unwrap(), ("src/a.ai".to_string(), 12));
+    assert!(parse_location("a.ai").is_err() && parse_location("a.ai:0").is_err());
+}
+
+#[test]
+fn suggest_prints_parsable_code() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = aeonmi(dir.path(), "0", &["ai", "suggest", "--prompt", "a function that deduplicates a list"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let code = String::from_utf8_lossy(&out.stdout);
+    assert!(code.starts_with("function dedupe(text) {") && !code.contains("```"), "{code}");
+    assert!(check(&code).is_ok());
+    assert!(!String::from_utf8_lossy(&out.stderr).contains("did not parse"));
+}
+
+#[test]
+fn unparsable_reply_is_retried_then_inserted() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("prog.ai"), "let word = \"banana\";\nlog(dedupe(word));\n").unwrap();
+    let out = aeonmi(dir.path(), "1", &["ai", "suggest", "--prompt", "dedupe characters", "--insert-at", "prog.ai:2"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("reply did not parse"));
+    assert!(String::from_utf8_lossy(&out.stdout).contains("into prog.ai at line 2"));
+
+    let run = Command::new(env!("CARGO_BIN_EXE_aeonmi_project")).current_dir(dir.path()).args(["run", "prog.ai", "--native"]).output().unwrap();
+    assert!(run.status.success(), "{}", String::from_utf8_lossy(&run.stderr));
+    assert_eq!(String::from_utf8_lossy(&run.stdout).trim(), "ban");
+}
+
+#[test]
+fn a_second_bad_reply_fails_without_touching_the_file() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("prog.ai"), "log(1);\n").unwrap();
+    let out = aeonmi(dir.path(), "2", &["ai", "suggest", "--prompt", "x", "--insert-at", "prog.ai:1"]);
+    assert_eq!(out.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("still does not parse"), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(fs::read_to_string(dir.path().join("prog.ai")).unwrap(), "log(1);\n");
+
+    let out = aeonmi(dir.path(), "0", &["ai", "suggest", "--prompt", "x", "--insert-at", "prog.ai:9"]);
+    assert_eq!(out.status.code(), Some(2));
+}
@@ -0,0 +1,94 @@
+use aeonmi_project::core::ai_usage::{append, load, parse_since, summarize, PriceTable, TokenUsage, UsageRecord};
+use std::fs;
+use std::process::{Command, Output};
+
+const PRICES: &str = r#"
+[[rules]]
+chord = "->"
+glyph = "→"
+
+[ai.prices."mock-1"]
+prompt_per_1k = 1.0
+completion_per_1k = 2.0
+"#;
+
+fn aeonmi(config_dir: &std::path::Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .arg("--quiet")
+        .args(args)
+        .env("AEONMI_AI_MOCK", "1")
+        .env("AEONMI_CONFIG_DIR", config_dir)
+        .output()
+        .expect("failed to run aeonmi_project")
+}
+
+fn rec(ts: u64, model: &str, tokens: Option<(u64, u64)>, ok: bool) -> UsageRecord {
+    let usage = tokens.map(|(p, c)| TokenUsage { prompt_tokens: p, completion_tokens: c });
+    UsageRecord { ts, ..UsageRecord::new("mock", model, usage, 100, ok) }
+}
+
+#[test]
+fn ledger_append_and_rollup() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nested").join("ai_usage.jsonl");
+    append(&path, &rec(1_000, "mock-1", Some((1000, 500)), true)).unwrap();
+    append(&path, &rec(2_000, "mock-1", None, false)).unwrap();
+    append(&path, &rec(3_000, "other", Some((10, 10)), true)).unwrap();
+    // a torn line from a crashed writer must not hide the rest
+    let mut txt = fs::read_to_string(&path).unwrap();
+    txt.push_str("{\"ts\":4000,\"prov");
+    fs::write(&path, txt).unwrap();
+
+    let records = load(&path);
+    assert_eq!(records.len(), 3);
+    let prices = PriceTable::from_toml_str(PRICES).unwrap();
+    let all = summarize(&records, None, &prices);
+    assert_eq!((all.total.calls, all.total.failures), (3, 1));
+    assert_eq!((all.total.prompt_tokens, all.total.completion_tokens), (1010, 510));
+    assert_eq!(all.total.wall_ms, 300);
+    assert!((all.total.est_cost - 2.0).abs() < 1e-9, "{}", all.total.est_cost);
+    assert_eq!(all.total.unpriced_calls, 2, "no tokens + unknown model");
+    assert_eq!(all.by_model["mock/mock-1"].calls, 2);
+    assert_eq!(all.by_model["mock/other"].calls, 1);
+
+    let recent = summarize(&records, Some(2_000), &prices);
+    assert_eq!(recent.total.calls, 2);
+    assert_eq!(recent.total.est_cost, 0.0);
+}
+
+#[test]
+fn since_accepts_common_units() {
+    assert_eq!(parse_since("7d"), Ok(7 * 86_400));
+    assert_eq!(parse_since("30m"), Ok(1_800));
+    assert_eq!(parse_since("2w"), Ok(2 * 604_800));
+    assert!(parse_since("7y").is_err());
+    assert!(parse_since("d").is_err());
+}
+
+#[test]
+fn chat_calls_are_recorded_and_summarized() {
+    let dir = tempfile::tempdir().unwrap();
+    let cfg = dir.path().join("aeonmi.toml");
+    fs::write(&cfg, PRICES).unwrap();
+
+    let out = aeonmi(dir.path(), &["ai", "chat", "--provider", "mock", "hello there"]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), "mock: hello there");
+    // a failing call is still recorded, and still reported as a chat error
+    let out = aeonmi(dir.path(), &["ai", "chat", "--provider", "mock", "   "]);
+    assert!(String::from_utf8_lossy(&out.stderr).contains("empty prompt"));
+
+    let out = aeonmi(dir.path(), &["--config", cfg.to_str().unwrap(), "ai", "usage", "--since", "1h", "--json"]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    let total = &v["total"];
+    assert_eq!(total["calls"], 2);
+    assert_eq!(total["failures"], 1);
+    assert_eq!(total["prompt_tokens"], 2);
+    assert_eq!(total["completion_tokens"], 3);
+    // 2/1000 * $1 + 3/1000 * $2
+    assert!((total["est_cost"].as_f64().unwrap() - 0.008).abs() < 1e-9, "{v}");
+
+    let out = aeonmi(dir.path(), &["ai", "usage", "--since", "7y"]);
+    assert_eq!(out.status.code(), Some(2));
+}
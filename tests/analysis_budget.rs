@@ -0,0 +1,81 @@
+use std::process::Command;
+use std::time::Duration;
+
+use aeonmi_project::core::analysis_budget::AnalysisBudget;
+use aeonmi_project::core::ast::ASTNode;
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::parser::Parser;
+use aeonmi_project::core::semantic_analyzer::{SemanticAnalyzer, Severity};
+use aeonmi_project::core::types::TypeContext;
+
+// assignments to undeclared names on the first and last line
+const SRC: &str = "y = 1;\nlet x = 2;\nlog(x);\nz = \"s\" - 1;\n";
+
+fn parse(source: &str) -> ASTNode {
+    Parser::new(Lexer::from_str(source).tokenize().unwrap()).parse().unwrap()
+}
+
+/// Runs out after the first item: each item takes longer than the whole budget.
+fn slow_budget() -> AnalysisBudget {
+    AnalysisBudget::new(Duration::from_millis(20)).with_item_visitor(|_| std::thread::sleep(Duration::from_millis(50)))
+}
+
+#[test]
+fn analyzer_keeps_what_it_found_and_notes_where_it_stopped() {
+    let ast = parse(SRC);
+    let full = SemanticAnalyzer::new().analyze_with_spans(&ast);
+    assert!(full.iter().any(|d| d.line == 4 && d.severity == Severity::Error), "{full:?}");
+
+    let mut sema = SemanticAnalyzer::new().with_budget(slow_budget());
+    let partial = sema.analyze_with_spans(&ast);
+    assert_eq!(sema.truncation().map(|t| (t.analyzed, t.total)), Some((1, 4)));
+    let (notes, found): (Vec<_>, Vec<_>) = partial.iter().partition(|d| d.severity == Severity::Info);
+    assert_eq!(notes.len(), 1);
+    assert!(notes[0].message.starts_with("analysis truncated after 1 of 4 items (took "), "{}", notes[0].message);
+    assert_eq!(notes[0].line, 2, "points at the first skipped item");
+    // the first item's error, and no whole-program warnings about what was never seen
+    assert_eq!(found.iter().map(|d| d.line).collect::<Vec<_>>(), [1], "{found:?}");
+
+    // a budget that is not reached changes nothing
+    let mut sema = SemanticAnalyzer::new().with_budget(AnalysisBudget::new(Duration::from_secs(60)));
+    assert_eq!(sema.analyze_with_spans(&ast).len(), full.len());
+    assert!(sema.truncation().is_none());
+}
+
+#[test]
+fn type_inference_stops_between_items() {
+    let ast = parse(SRC);
+    let mut full = TypeContext::new();
+    full.infer_program(&ast);
+    assert!(!full.diags.is_empty() && full.truncated.is_none());
+
+    let mut ctx = TypeContext::new().with_budget(slow_budget());
+    ctx.infer_program(&ast);
+    assert_eq!(ctx.truncated.as_ref().map(|t| (t.analyzed, t.total)), Some((1, 4)));
+    assert!(ctx.diags.is_empty(), "line 4 was not inferred: {:?}", ctx.diags);
+}
+
+fn aeonmi(dir: &std::path::Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_aeonmi_project")).current_dir(dir).env("NO_COLOR", "1").args(args).output().unwrap()
+}
+
+#[test]
+fn cli_only_truncates_under_the_flag() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("big.ai"), "let s = \"x\";\nlog(s - 1);\n").unwrap();
+
+    assert_eq!(aeonmi(dir.path(), &["typecheck", "big.ai"]).status.code(), Some(3));
+    // a zero budget is spent before the first item
+    let out = aeonmi(dir.path(), &["typecheck", "big.ai", "--json", "--analysis-timeout-ms", "0"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let reports: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(reports[0]["truncated"]["analyzed"], 0);
+    assert_eq!(reports[0]["truncated"]["total"], 2);
+    assert_eq!(reports[0]["diagnostics"].as_array().unwrap().len(), 0);
+
+    let out = aeonmi(dir.path(), &["check", "--annotate", "big.ai", "--analysis-timeout-ms", "0"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("info: analysis truncated after 0 of 2 items"), "{stdout}");
+    assert!(stdout.contains("big.ai: 0 errors, 0 warnings"), "{stdout}");
+}
@@ -0,0 +1,76 @@
+use aeonmi_project::core::diagnostics::{render_annotated, AnnotateOptions, Annotation};
+use aeonmi_project::core::style::Style;
+use std::fs;
+use std::process::Command;
+
+fn ann(severity: &'static str, line: usize, col: usize, len: usize, message: &str) -> Annotation {
+    Annotation { severity, line, col, len, message: message.to_string() }
+}
+
+/// Overlapping and stacked spans over `tests/annotate/overlap.ai`, in no particular order.
+fn overlapping() -> Vec<Annotation> {
+    vec![
+        ann("warning", 13, 1, 22, "assignment outside any function runs at load time"),
+        ann("error", 13, 1, 18, "Assignment to undeclared variable 'undeclared_counter'"),
+        ann("warning", 13, 22, 1, "magic number"),
+        ann("warning", 2, 1, 15, "`limit` is never read after this"),
+        ann("error", 2, 5, 5, "Redeclaration of 'limit'"),
+        ann("warning", 10, 9, 5, "Variable 'total' shadows an outer declaration; the loop condition still reads the outer one, so this loop never ends"),
+        ann("error", 0, 0, 0, "Arithmetic on non-number"),
+    ]
+}
+
+fn source() -> String {
+    fs::read_to_string("tests/annotate/overlap.ai").unwrap()
+}
+
+fn golden(name: &str) -> String {
+    fs::read_to_string(format!("tests/annotate/{name}")).unwrap()
+}
+
+#[test]
+fn overlapping_spans_stack_under_their_line() {
+    let opt = AnnotateOptions { width: 72, max: 0, full_source: true };
+    assert_eq!(render_annotated(&Style::PLAIN, &source(), &overlapping(), &opt), golden("overlap.out"));
+}
+
+#[test]
+fn unicode_glyphs_and_flagged_lines_only() {
+    let st = Style { color: false, unicode: true };
+    let opt = AnnotateOptions { width: 72, max: 0, full_source: false };
+    assert_eq!(render_annotated(&st, &source(), &overlapping(), &opt), golden("overlap.flagged.out"));
+}
+
+#[test]
+fn max_diagnostics_caps_what_is_drawn() {
+    let opt = AnnotateOptions { width: 72, max: 3, full_source: false };
+    let text = render_annotated(&Style::PLAIN, &source(), &overlapping(), &opt);
+    // the unplaced error and line 2 come first
+    assert!(text.contains("Arithmetic on non-number") && text.contains("Redeclaration"), "{text}");
+    assert!(!text.contains("undeclared_counter"), "{text}");
+    assert!(text.ends_with("and 4 more diagnostics (re-run with --max-diagnostics 0 to see all)\n"), "{text}");
+
+    let colored = render_annotated(&Style::RICH, &source(), &overlapping(), &opt);
+    assert!(colored.contains("\x1b[1;91m^^^^^\x1b[0m"), "{colored}");
+}
+
+#[test]
+fn check_annotate_prints_the_file_with_its_diagnostics() {
+    let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .args(["check", "--annotate", "tests/annotate/overlap.ai"])
+        .env("NO_COLOR", "1")
+        .env("AEONMI_UNICODE", "0")
+        .env("COLUMNS", "60")
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(3), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout), golden("overlap.check.out"));
+
+    let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .args(["check", "--annotate", "--max-diagnostics", "1", "tests/annotate/overlap.ai"])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    let text = String::from_utf8_lossy(&out.stdout);
+    assert!(text.contains("and 3 more diagnostics"), "{text}");
+}
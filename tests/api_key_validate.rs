@@ -0,0 +1,111 @@
+#![cfg(any(feature = "ai-openai", feature = "ai-copilot", feature = "ai-perplexity", feature = "ai-deepseek"))]
+//! Validation pings against a mock provider (`AEONMI_AI_BASE_URL` points every provider at it).
+use aeonmi_project::ai::key_check::{set_validated, validate_key, validate_stored, KeyStatus};
+use aeonmi_project::core::api_keys::{get_api_key, set_api_key};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Mutex;
+
+// The base URL and key store are process-wide environment settings.
+static ENV: Mutex<()> = Mutex::new(());
+
+const GOOD: &str = "sk-good-0123";
+
+/// Accepts `GOOD` as a bearer token and answers 401 with the presented key in the body
+/// (as real providers do) otherwise; each request line and body is sent back to the test.
+fn mock_provider() -> (String, Receiver<(String, String)>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let (tx, rx) = channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let (mut auth, mut len) = (String::new(), 0);
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() { break; }
+                let (name, value) = line.split_once(':').unwrap();
+                match name.to_ascii_lowercase().as_str() {
+                    "authorization" => auth = value.trim().to_string(),
+                    "content-length" => len = value.trim().parse().unwrap(),
+                    _ => {}
+                }
+            }
+            let mut body = vec![0; len];
+            reader.read_exact(&mut body).unwrap();
+            let _ = tx.send((request_line.trim_end().to_string(), String::from_utf8(body).unwrap()));
+            let (status, reply) = if auth == format!("Bearer {GOOD}") {
+                ("200 OK", "{\"data\":[]}".to_string())
+            } else {
+                ("401 Unauthorized", format!("{{\"error\":\"Incorrect API key provided: {}\"}}", auth.trim_start_matches("Bearer ")))
+            };
+            let _ = write!(stream, "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{reply}", reply.len());
+        }
+    });
+    (base, rx)
+}
+
+#[test]
+fn pings_distinguish_ok_unauthorized_and_network_errors() {
+    let _g = ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let (base, requests) = mock_provider();
+    std::env::set_var("AEONMI_AI_BASE_URL", &base);
+    std::env::remove_var("AEONMI_COPILOT_ENDPOINT");
+
+    assert_eq!(validate_key("openai", GOOD).unwrap(), KeyStatus::Ok);
+    assert_eq!(requests.recv().unwrap().0, "GET /v1/models HTTP/1.1");
+    assert_eq!(validate_key("deepseek", GOOD).unwrap(), KeyStatus::Ok);
+    assert_eq!(requests.recv().unwrap().0, "GET /models HTTP/1.1");
+    // Providers without a models list get a one-token completion.
+    assert_eq!(validate_key("perplexity", GOOD).unwrap(), KeyStatus::Ok);
+    let (line, body) = requests.recv().unwrap();
+    assert_eq!(line, "POST /chat/completions HTTP/1.1");
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&body).unwrap()["max_tokens"], 1);
+    assert_eq!(validate_key("copilot", GOOD).unwrap(), KeyStatus::Ok);
+    assert_eq!(requests.recv().unwrap().0, "POST /v1/chat/completions HTTP/1.1");
+
+    let secret = "sk-wrong-secret-9876";
+    let KeyStatus::Unauthorized { message } = validate_key("openai", secret).unwrap() else { panic!("expected unauthorized") };
+    assert!(message.contains("HTTP 401") && message.contains("https://platform.openai.com/api-keys"), "{message}");
+    assert!(!message.contains(secret) && !message.contains("9876"), "the key leaked: {message}");
+
+    // Nothing listens on a port that was just released.
+    let closed = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    std::env::set_var("AEONMI_AI_BASE_URL", format!("http://{closed}"));
+    let KeyStatus::NetworkError { message } = validate_key("openai", secret).unwrap() else { panic!("expected a network error") };
+    assert!(message.starts_with("could not reach openai"), "{message}");
+    assert!(!message.contains(secret), "the key leaked: {message}");
+
+    let err = validate_key("faux", GOOD).unwrap_err();
+    assert!(err.contains("unknown provider `faux`"), "{err}");
+}
+
+#[test]
+fn validated_set_refuses_keys_the_provider_rejects() {
+    let _g = ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let (base, _requests) = mock_provider();
+    std::env::set_var("AEONMI_AI_BASE_URL", &base);
+    let dir = tempfile::tempdir().unwrap();
+    std::env::set_var("AEONMI_CONFIG_DIR", dir.path());
+
+    let err = validate_stored("deepseek").unwrap_err();
+    assert!(err.contains("no key stored for deepseek") && err.contains("https://platform.deepseek.com/api_keys"), "{err}");
+
+    let err = set_validated("deepseek", "sk-bad").unwrap_err();
+    assert!(err.starts_with("key not stored: deepseek rejected the key"), "{err}");
+    assert_eq!(get_api_key("deepseek"), None);
+
+    set_validated("deepseek", GOOD).unwrap();
+    assert_eq!(get_api_key("deepseek").as_deref(), Some(GOOD));
+    assert_eq!(validate_stored("deepseek").unwrap(), KeyStatus::Ok);
+
+    // Unvalidated storage still accepts anything; a later ping reports it.
+    set_api_key("deepseek", "sk-revoked").unwrap();
+    assert!(matches!(validate_stored("deepseek").unwrap(), KeyStatus::Unauthorized { .. }));
+}
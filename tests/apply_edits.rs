@@ -0,0 +1,105 @@
+use aeonmi_project::core::code_actions::{apply_edits, TextEdit};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+fn edit(line: usize, column: usize, end_line: usize, end_column: usize, text: &str) -> TextEdit {
+    TextEdit { line, column, end_line, end_column, new_text: text.to_string() }
+}
+
+#[test]
+fn edits_apply_together_and_reverse_restores_the_source() {
+    let source = "let a = 1;\nlog(a);\n";
+    let edits = [
+        edit(2, 5, 2, 6, "total"),
+        edit(1, 5, 1, 6, "total"),
+        // insertions at one point keep their order
+        edit(3, 1, 3, 1, "log("),
+        edit(3, 1, 3, 1, "total);\n"),
+    ];
+    let applied = apply_edits(source, &edits).unwrap();
+    assert_eq!(applied.text, "let total = 1;\nlog(total);\nlog(total);\n");
+    assert_eq!(
+        applied.reverse,
+        [edit(1, 5, 1, 10, "a"), edit(2, 5, 2, 10, "a"), edit(3, 1, 3, 5, ""), edit(3, 5, 4, 1, "")]
+    );
+    assert_eq!(apply_edits(&applied.text, &applied.reverse).unwrap().text, source);
+
+    // an empty list is a no-op
+    let applied = apply_edits(source, &[]).unwrap();
+    assert_eq!((applied.text.as_str(), applied.reverse.len()), (source, 0));
+}
+
+#[test]
+fn overlapping_and_stale_edits_name_the_edit_that_failed() {
+    let source = "let a = 1;\nlog(a);";
+    let err = apply_edits(source, &[edit(1, 1, 1, 4, "var"), edit(2, 1, 2, 4, "print"), edit(1, 3, 1, 6, "x")]).unwrap_err();
+    assert_eq!(err.index, 2);
+    assert_eq!(err.to_string(), "edit 2: overlaps edit 0 (1:1-1:4)");
+
+    // an insertion strictly inside a replaced range overlaps it; touching does not
+    assert_eq!(apply_edits(source, &[edit(1, 1, 1, 4, "var"), edit(1, 2, 1, 2, "!")]).unwrap_err().index, 1);
+    assert!(apply_edits(source, &[edit(1, 1, 1, 4, "var"), edit(1, 4, 1, 4, "!")]).is_ok());
+
+    let err = apply_edits(source, &[edit(1, 1, 1, 2, ""), edit(3, 1, 3, 1, "x")]).unwrap_err();
+    assert_eq!(err.to_string(), "edit 1: line 3 is outside the document (2 lines)");
+    let err = apply_edits(source, &[edit(2, 1, 2, 9, "")]).unwrap_err();
+    assert_eq!(err.to_string(), "edit 0: column 9 is outside line 2 (7 bytes)");
+    assert!(apply_edits(source, &[edit(1, 0, 1, 1, "")]).is_err());
+    let err = apply_edits(source, &[edit(2, 3, 2, 1, "")]).unwrap_err();
+    assert!(err.reason.contains("before it starts"), "{err}");
+    let err = apply_edits("log(\"é\");", &[edit(1, 7, 1, 7, "x")]).unwrap_err();
+    assert_eq!(err.to_string(), "edit 0: 1:7 is inside a character");
+}
+
+/// 1-based (line, byte column) of `at` in `text`.
+fn position(text: &str, at: usize) -> (usize, usize) {
+    let before = &text[..at];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (before.matches('\n').count() + 1, at - line_start + 1)
+}
+
+fn random_text(rng: &mut StdRng, max: usize) -> String {
+    const PIECES: [&str; 8] = ["a", "let ", "\n", "é", "😀", " ", "\r\n", "x;"];
+    (0..rng.gen_range(0..=max)).map(|_| *PIECES.choose(rng).unwrap()).collect()
+}
+
+#[test]
+fn apply_then_reverse_is_identity_for_random_edits() {
+    let mut rng = StdRng::seed_from_u64(0xAE0);
+    for case in 0..500 {
+        let source = random_text(&mut rng, 40);
+        let boundaries: Vec<usize> = source.char_indices().map(|(i, _)| i).chain([source.len()]).collect();
+        // sorted boundary offsets paired into non-overlapping (possibly empty) ranges
+        let mut cuts: Vec<usize> = (0..rng.gen_range(0..8)).map(|_| *boundaries.choose(&mut rng).unwrap()).collect();
+        cuts.sort();
+        if cuts.len() % 2 == 1 {
+            cuts.pop();
+        }
+        let mut ranges: Vec<(usize, usize, String)> = cuts.chunks(2).map(|c| (c[0], c[1], random_text(&mut rng, 4))).collect();
+        // two insertions at one point would make the reference below depend on list order
+        ranges.dedup_by(|b, a| a.0 == a.1 && b.0 == b.1 && a.0 == b.0);
+
+        let mut expected = source.clone();
+        for (start, end, text) in ranges.iter().rev() {
+            expected.replace_range(start..end, text);
+        }
+
+        let mut edits: Vec<TextEdit> = ranges
+            .iter()
+            .map(|(start, end, text)| {
+                let (line, column) = position(&source, *start);
+                let (end_line, end_column) = position(&source, *end);
+                edit(line, column, end_line, end_column, text)
+            })
+            .collect();
+        edits.shuffle(&mut rng);
+
+        let applied = apply_edits(&source, &edits).unwrap_or_else(|e| panic!("case {case}: {e}\n{source:?}\n{edits:?}"));
+        assert_eq!(applied.text, expected, "case {case}");
+        let undone = apply_edits(&applied.text, &applied.reverse).unwrap_or_else(|e| panic!("case {case}: reverse {e}"));
+        assert_eq!(undone.text, source, "case {case}");
+        // and the reverse of the reverse redoes the edits
+        assert_eq!(apply_edits(&source, &undone.reverse).unwrap().text, expected, "case {case}");
+    }
+}
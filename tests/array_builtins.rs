@@ -0,0 +1,128 @@
+use aeonmi_project::commands::run::run_source_captured;
+use aeonmi_project::core::ast::ASTNode;
+use aeonmi_project::core::code_generator::CodeGenerator;
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::parser::Parser;
+use aeonmi_project::core::py_emitter::PyEmitter;
+use aeonmi_project::core::toolchain::{self, Tool};
+use std::fs;
+use std::process::Command;
+
+const PROGRAM: &str = r#"let xs = slice("", 0, 0);
+push(xs, 1);
+push(xs, "two");
+let n = push(xs, 3);
+log("len " + n + " " + len(xs));
+let last = pop(xs);
+log("popped " + last + ", left " + len(xs));
+let all = slice(xs, -5, 99);
+log(len(all) + " " + all[0] + " " + all[1]);
+log(len(slice(xs, 1, 0)) + " " + len(slice(xs, 5, 9)) + " " + slice(xs, 1.7, 2)[0]);
+let word = slice("héllo", 1, 3);
+log(word[0] + word[1] + " " + len(word));
+function squares(k) {
+    let out = slice("", 0, 0);
+    let i = 0;
+    while (i < k) {
+        push(out, i * i);
+        i = i + 1;
+    }
+    return out;
+}
+let total = 0;
+for v in squares(4) {
+    total = total + v;
+}
+log(total);
+"#;
+
+const EXPECTED: &str = "len 3 3\npopped 3, left 2\n2 1 two\n0 0 two\nél 2\n14\n";
+
+fn parse(source: &str) -> ASTNode {
+    let tokens = Lexer::from_str(source).tokenize().expect("lex");
+    Parser::new(tokens).parse().expect("parse")
+}
+
+fn node(js: &str) -> std::process::Output {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("prog.js");
+    fs::write(&file, js).unwrap();
+    Command::new("node").arg(&file).output().unwrap()
+}
+
+#[test]
+fn push_pop_slice_behave_the_same_natively_and_in_js() {
+    let run = run_source_captured(PROGRAM).unwrap();
+    assert_eq!(run.output, EXPECTED);
+    if !toolchain::available(Tool::Node) {
+        eprintln!("node not found; skipping JS side");
+        return;
+    }
+    let js = CodeGenerator::new().generate(&parse(PROGRAM)).unwrap();
+    assert!(js.contains("__aeonmi_push((xs == null ? (xs = []) : xs), 1)"), "{js}");
+    let out = node(&js);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout), EXPECTED);
+}
+
+fn python(py: &str) -> std::process::Output {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("prog.py");
+    fs::write(&file, py).unwrap();
+    Command::new(Tool::Python.exe()).arg(&file).output().unwrap()
+}
+
+#[test]
+fn push_pop_slice_behave_the_same_in_python() {
+    let py = PyEmitter::new().generate(&parse(PROGRAM));
+    assert!(py.contains("__aeonmi_push((xs := [] if xs is None else xs), 1)"), "{py}");
+    if !toolchain::available(Tool::Python) {
+        eprintln!("python not found; skipping Python side");
+        return;
+    }
+    let out = python(&py);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout), EXPECTED);
+}
+
+#[test]
+fn python_copies_stored_lists_like_the_vm() {
+    let src = "let seen = slice(\"ab\", 0, 2);\nfunction grow(xs) {\n    push(xs, \"c\");\n    return len(xs);\n}\nlet copy = seen;\npop(copy);\nlog(grow(seen) + \" \" + len(seen) + \" \" + len(copy));\n";
+    assert_eq!(run_source_captured(src).unwrap().output, "3 2 1\n");
+    let py = PyEmitter::new().generate(&parse(src));
+    assert!(py.contains("copy = __aeonmi_copy(seen)"), "{py}");
+    if toolchain::available(Tool::Python) {
+        let out = python(&py);
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        assert_eq!(String::from_utf8_lossy(&out.stdout), "3 2 1\n");
+    }
+}
+
+#[test]
+fn pop_on_an_empty_array_fails_at_the_call() {
+    let src = "let xs = slice(\"a\", 0, 1);\npop(xs);\nlog(\"after one\");\n  pop(xs);\nlog(\"unreachable\");\n";
+    let err = run_source_captured(src).unwrap_err();
+    assert!(err.contains("pop (line 4, column 3): `xs` is empty"), "{err}");
+    if toolchain::available(Tool::Node) {
+        let out = node(&CodeGenerator::new().generate(&parse(src)).unwrap());
+        assert!(!out.status.success());
+        assert_eq!(String::from_utf8_lossy(&out.stdout), "after one\n");
+        assert!(String::from_utf8_lossy(&out.stderr).contains("pop: `xs` is empty"));
+    }
+    if toolchain::available(Tool::Python) {
+        let out = python(&PyEmitter::new().generate(&parse(src)));
+        assert!(!out.status.success());
+        assert_eq!(String::from_utf8_lossy(&out.stdout), "after one\n");
+        assert!(String::from_utf8_lossy(&out.stderr).contains("pop: `xs` is empty"));
+    }
+}
+
+#[test]
+fn mutation_needs_a_variable_and_an_array() {
+    let err = run_source_captured("let s = \"ab\";\npush(slice(s, 0, 1), 2);\n").unwrap_err();
+    assert!(err.contains("`push` needs a variable as its first argument (line 2, column 1)"), "{err}");
+    let err = run_source_captured("let s = \"ab\";\npush(s, 1);\n").unwrap_err();
+    assert!(err.contains("push expects an array, got ab"), "{err}");
+    let err = run_source_captured("let xs = slice(\"ab\", 0, 2);\nlog(slice(xs, \"1\", 2));\n").unwrap_err();
+    assert!(err.contains("slice start must be a number"), "{err}");
+}
@@ -22,5 +22,5 @@ fn assignment_and_function_call_pipeline() {
     assert!(js.contains("function add(a, b)"));
     assert!(js.contains("let x = 1;"));
     assert!(js.contains("x = add(x, 2);"));
-    assert!(js.contains("console.log(x);"));
+    assert!(js.contains("console.log(__aeonmi_display(x));"));
 }
@@ -0,0 +1,115 @@
+//! `run` / `build` reuse a compiled artifact until a file it was built from changes,
+//! per the dependency manifest (`<artifact>.deps.json`) recorded next to it.
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use aeonmi_project::core::deps_manifest::{compiler_identity, DepsManifest};
+use aeonmi_project::core::toolchain::{self, Tool};
+
+fn aeonmi(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_aeonmi_project")).current_dir(dir).args(args).output().unwrap()
+}
+
+fn text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// `main.ai` imports `lib.ai`, which imports `util.ai`; `other.ai` imports nothing.
+fn project() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("main.ai"), "import \"lib.ai\";\nlog(twice(21));\n").unwrap();
+    fs::write(dir.path().join("lib.ai"), "import \"util.ai\";\nfunction twice(x) {\n    return add(x, x);\n}\n").unwrap();
+    fs::write(dir.path().join("util.ai"), "function add(a, b) {\n    return a + b;\n}\n").unwrap();
+    fs::write(dir.path().join("other.ai"), "log(7);\n").unwrap();
+    dir
+}
+
+#[test]
+fn touching_an_import_rebuilds_only_the_artifact_that_uses_it() {
+    let dir = project();
+    let p = dir.path();
+    for (input, out) in [("main.ai", "main.js"), ("other.ai", "other.js")] {
+        let built = aeonmi(p, &["build", input, "-o", out]);
+        assert!(built.status.success(), "{}", text(&built.stderr));
+        assert!(p.join(format!("{out}.deps.json")).is_file());
+    }
+    let again = aeonmi(p, &["build", "main.ai", "-o", "main.js"]);
+    assert!(text(&again.stdout).contains("ok: 'main.js' is up to date."), "{}", text(&again.stdout));
+
+    fs::write(p.join("util.ai"), "function add(a, b) {\n    return b + a;\n}\n").unwrap();
+    let other_before = fs::read(p.join("other.js")).unwrap();
+    let main = aeonmi(p, &["-v", "build", "main.ai", "-o", "main.js"]);
+    let other = aeonmi(p, &["-v", "build", "other.ai", "-o", "other.js"]);
+    assert!(text(&main.stderr).contains("recompiling main.js: util.ai changed"), "{}", text(&main.stderr));
+    assert!(fs::read_to_string(p.join("main.js")).unwrap().contains("b + a"));
+    assert!(text(&other.stdout).contains("ok: 'other.js' is up to date."), "{}", text(&other.stderr));
+    assert!(text(&other.stderr).contains("reusing other.js"), "{}", text(&other.stderr));
+    assert_eq!(fs::read(p.join("other.js")).unwrap(), other_before);
+
+    // Another entry, or other settings, also need a fresh compile
+    let swapped = aeonmi(p, &["-v", "build", "other.ai", "-o", "main.js"]);
+    assert!(text(&swapped.stderr).contains("recompiling main.js: built from main.ai"), "{}", text(&swapped.stderr));
+    let no_sema = aeonmi(p, &["-v", "--no-sema", "build", "other.ai", "-o", "main.js"]);
+    assert!(text(&no_sema.stderr).contains("recompiling main.js: build options changed"), "{}", text(&no_sema.stderr));
+}
+
+#[test]
+fn run_recompiles_when_an_imported_file_changed() {
+    if !toolchain::available(Tool::Node) {
+        eprintln!("node not found; skipping");
+        return;
+    }
+    let dir = project();
+    let p = dir.path();
+    let first = aeonmi(p, &["run", "main.ai", "--out", "main.js"]);
+    assert_eq!(text(&first.stdout).trim(), "42", "{}", text(&first.stderr));
+    let other = aeonmi(p, &["run", "other.ai", "--out", "other.js"]);
+    assert_eq!(text(&other.stdout).trim(), "7", "{}", text(&other.stderr));
+
+    fs::write(p.join("lib.ai"), "import \"util.ai\";\nfunction twice(x) {\n    return add(x, x) + 1;\n}\n").unwrap();
+    let rerun = aeonmi(p, &["-v", "run", "main.ai", "--out", "main.js"]);
+    assert_eq!(text(&rerun.stdout).trim(), "43", "{}", text(&rerun.stderr));
+    assert!(text(&rerun.stderr).contains("recompiling main.js: lib.ai changed"), "{}", text(&rerun.stderr));
+    let other = aeonmi(p, &["-v", "run", "other.ai", "--out", "other.js"]);
+    assert_eq!(text(&other.stdout).trim(), "7");
+    assert!(text(&other.stderr).contains("reusing other.js: no dependency changed"), "{}", text(&other.stderr));
+    assert!(!text(&other.stderr).contains("ok: wrote js"), "{}", text(&other.stderr));
+}
+
+#[test]
+fn explain_prints_the_recorded_graph() {
+    let dir = project();
+    let p = dir.path();
+    assert!(aeonmi(p, &["build", "main.ai", "-o", "main.js"]).status.success());
+    fs::write(p.join("util.ai"), "function add(a, b) {\n    return a - b;\n}\n").unwrap();
+
+    let out = aeonmi(p, &["build", "--explain", "main.js"]);
+    assert!(out.status.success(), "{}", text(&out.stderr));
+    let lines: Vec<String> = text(&out.stdout).lines().map(str::to_string).collect();
+    assert!(lines[0].starts_with("main.js (compiler "), "{lines:?}");
+    assert!(lines[1].starts_with("  main.ai  "), "{lines:?}");
+    assert!(lines[2].starts_with("    lib.ai  ") && !lines[2].ends_with("(changed)"), "{lines:?}");
+    assert!(lines[3].starts_with("      util.ai  ") && lines[3].ends_with("(changed)"), "{lines:?}");
+
+    let missing = aeonmi(p, &["build", "--explain", "other.js"]);
+    assert_eq!(missing.status.code(), Some(5), "{}", text(&missing.stderr));
+}
+
+#[test]
+fn artifacts_from_another_compiler_build_are_rebuilt() {
+    let dir = project();
+    let p = dir.path();
+    assert!(aeonmi(p, &["build", "other.ai", "-o", "other.js"]).status.success());
+    let mut manifest = DepsManifest::load(&p.join("other.js")).expect("manifest");
+    assert_eq!(manifest.compiler_version, compiler_identity());
+    assert!(compiler_identity().starts_with(env!("CARGO_PKG_VERSION")));
+
+    // Same version, different build commit
+    manifest.compiler_version = format!("{}+000000000000", env!("CARGO_PKG_VERSION"));
+    manifest.save(&p.join("other.js")).unwrap();
+    let rebuilt = aeonmi(p, &["-v", "build", "other.ai", "-o", "other.js"]);
+    let expected = format!("recompiling other.js: built by compiler {}+000000000000", env!("CARGO_PKG_VERSION"));
+    assert!(text(&rebuilt.stderr).contains(&expected), "{}", text(&rebuilt.stderr));
+}
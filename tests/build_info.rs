@@ -0,0 +1,59 @@
+use std::process::Command;
+
+use aeonmi_project::core::build_info::{BuildInfo, FEATURES};
+
+fn bin() -> String {
+    env!("CARGO_BIN_EXE_aeonmi_project").to_string()
+}
+
+fn info_json(config_dir: &std::path::Path) -> serde_json::Value {
+    let out = Command::new(bin())
+        .args(["info", "--json"])
+        .env("AEONMI_CONFIG_DIR", config_dir)
+        .output()
+        .expect("run aeonmi info");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    serde_json::from_slice(&out.stdout).expect("info --json is JSON")
+}
+
+#[test]
+fn feature_flags_match_the_compiled_configuration() {
+    let dir = tempfile::tempdir().unwrap();
+    let v = info_json(dir.path());
+    let features = v["features"].as_object().expect("features object");
+    let expected = [
+        ("quantum", cfg!(feature = "quantum")),
+        ("bytecode", cfg!(feature = "bytecode")),
+        ("qiskit", cfg!(feature = "qiskit")),
+        ("ai-openai", cfg!(feature = "ai-openai")),
+        ("ai-copilot", cfg!(feature = "ai-copilot")),
+        ("ai-perplexity", cfg!(feature = "ai-perplexity")),
+        ("ai-deepseek", cfg!(feature = "ai-deepseek")),
+        ("debug-metrics", cfg!(feature = "debug-metrics")),
+    ];
+    for (name, on) in expected {
+        assert_eq!(features.get(name).and_then(|f| f.as_bool()), Some(on), "feature {name}: {v}");
+    }
+    assert_eq!(features.len(), FEATURES.len());
+}
+
+#[test]
+fn report_names_version_emit_kinds_and_paths() {
+    let dir = tempfile::tempdir().unwrap();
+    let v = info_json(dir.path());
+    assert_eq!(v["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(v["emit_kinds"], serde_json::json!(["js", "ai"]));
+    assert_eq!(v["metrics"]["schema"], "aeonmi.metrics");
+    assert!(v["metrics"]["version"].as_u64().is_some());
+    assert!(!v["target"].as_str().unwrap_or_default().is_empty());
+    let stats = v["config_files"]["usage_stats"]["path"].as_str().expect("usage_stats path");
+    assert!(stats.starts_with(dir.path().to_str().unwrap()), "{stats}");
+}
+
+#[test]
+fn library_report_round_trips_through_json() {
+    let info = BuildInfo::current(None);
+    let back: BuildInfo = serde_json::from_str(&serde_json::to_string(&info).unwrap()).unwrap();
+    assert_eq!(back, info);
+    assert!(info.render().contains("emit kinds: js, ai"), "{}", info.render());
+}
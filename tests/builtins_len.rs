@@ -30,7 +30,7 @@ fn len_builtin_emits_helper_and_invocation() {
         js
     );
     assert!(
-        js.contains("console.log(n);"),
+        js.contains("console.log(__aeonmi_display(n));"),
         "log should use rewritten binding: {}",
         js
     );
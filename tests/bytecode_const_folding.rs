@@ -10,3 +10,6 @@ fn fold_string_concat(){let v=eval("return \"a\" + \"b\";");match v {Some(Value:
 
 #[test]
 fn implicit_null_return(){let v=eval("fn foo(a){ let x = a + 1; } return foo(1);");match v {Some(Value::Null)=>{},_=>panic!("expected null got {v:?}")}}
+
+#[test]
+fn fold_radix_and_separated_literals(){let v=eval("return 0xFF + 0b1 + 1_000 + 2e1;");match v {Some(Value::Number(n))=>assert_eq!(n,1276.0),_=>panic!("bad {v:?}")}}
@@ -6,3 +6,5 @@ fn eval(src:&str)->Option<Value>{let mut l=Lexer::from_str(src);let toks=l.token
 fn logical_and_short_circuit(){let v=eval("let a=true; let b=false; return a && b; ");match v {Some(Value::Bool(b))=>assert!(!b),_=>panic!("bad {v:?}")}}
 #[test]
 fn logical_or_short_circuit(){let v=eval("let a=true; let b=false; return a || b; ");match v {Some(Value::Bool(b))=>assert!(b),_=>panic!("bad {v:?}")}}
+#[test]
+fn not_and_negate(){match eval("let a=0; let b=3; return !a && -b < 0; ") {Some(Value::Bool(b))=>assert!(b),v=>panic!("bad {v:?}")} match eval("let s=\"x\"; return !s; ") {Some(Value::Bool(b))=>assert!(!b),v=>panic!("bad {v:?}")}}
@@ -39,7 +39,7 @@ fn cli_compiles_basic_file() {
         "output JS missing expected code"
     );
     assert!(
-        js.contains("console.log(x);"),
+        js.contains("console.log(__aeonmi_display(x));"),
         "output JS missing expected console.log call"
     );
 }
@@ -0,0 +1,122 @@
+use aeonmi_project::commands::run::run_source_captured;
+use aeonmi_project::core::ast::ASTNode;
+use aeonmi_project::core::code_generator::CodeGenerator;
+use aeonmi_project::core::formatter::format_ai;
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::parser::Parser;
+use aeonmi_project::core::py_emitter::PyEmitter;
+use aeonmi_project::core::semantic_analyzer::SemanticAnalyzer;
+use aeonmi_project::core::toolchain::{self, Tool};
+use std::fs;
+use std::process::Command;
+
+const PROGRAM: &str = r#"let xs = [1, 2, [3, 4,],];
+let point = { "x": 10, y: 20, tags: ["a", "b"], };
+log(xs[2][1]);
+log(point.x + point["y"]);
+log(point.tags[1]);
+log(len(xs) + len([]));
+let empty = {};
+log(len(empty));
+"#;
+
+const EXPECTED: &str = "4\n30\nb\n3\n0\n";
+
+fn parse(source: &str) -> ASTNode {
+    let tokens = Lexer::from_str(source).tokenize().expect("lex");
+    Parser::new(tokens).parse().expect("parse")
+}
+
+fn node(js: &str) -> std::process::Output {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("prog.js");
+    fs::write(&file, js).unwrap();
+    Command::new("node").arg(&file).output().unwrap()
+}
+
+#[test]
+fn literals_nest_and_allow_trailing_commas() {
+    let ASTNode::Program(items) = parse("let v = [1, [2, 3,], { \"k\": [], n: {}, },];\n") else { panic!() };
+    let ASTNode::VariableDecl { value, .. } = &items[0] else { panic!("{items:?}") };
+    let ASTNode::ArrayLiteral { items, .. } = &**value else { panic!("{value:?}") };
+    assert_eq!(items.len(), 3);
+    assert!(matches!(&items[1], ASTNode::ArrayLiteral { items, .. } if items.len() == 2));
+    let ASTNode::ObjectLiteral { fields, .. } = &items[2] else { panic!("{:?}", items[2]) };
+    let keys: Vec<&str> = fields.iter().map(|(k, _)| k.as_str()).collect();
+    assert_eq!(keys, ["k", "n"]);
+
+    for bad in ["let v = [1, 2;\n", "let o = { \"k\" 1 };\n", "let o = { 1: 2 };\n"] {
+        let tokens = Lexer::from_str(bad).tokenize().expect("lex");
+        assert!(Parser::new(tokens).parse().is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn native_and_js_agree() {
+    let run = run_source_captured(PROGRAM).unwrap();
+    assert_eq!(run.output, EXPECTED);
+    let js = CodeGenerator::new().generate(&parse(PROGRAM)).unwrap();
+    assert!(js.contains(r#"{ "x": 10, "y": 20, "tags": ["a", "b"] }"#), "{js}");
+    if !toolchain::available(Tool::Node) {
+        eprintln!("node not found; skipping JS side");
+        return;
+    }
+    let out = node(&js);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout), EXPECTED);
+}
+
+#[test]
+fn python_export_builds_lists_and_dicts() {
+    let py = PyEmitter::new().generate(&parse(PROGRAM));
+    assert!(py.contains(r#"point = {"x": 10, "y": 20, "tags": ["a", "b"]}"#), "{py}");
+    if !toolchain::available(Tool::Python) {
+        eprintln!("python not found; skipping Python side");
+        return;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("prog.py");
+    fs::write(&file, py).unwrap();
+    let out = Command::new(Tool::Python.exe()).arg(&file).output().unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout), EXPECTED);
+}
+
+#[test]
+fn ai_emission_reparses() {
+    let ai = CodeGenerator::new_ai().generate(&parse(PROGRAM)).unwrap();
+    assert!(ai.contains(r#"{"x": 10, "y": 20, "tags": ["a", "b"]}"#), "{ai}");
+    assert_eq!(run_source_captured(&ai).unwrap().output, EXPECTED);
+}
+
+#[test]
+fn formatter_prints_short_literals_on_one_line() {
+    let out = format_ai("let xs = [ 1,2 ,\n  3, ];\nlet o = {\"a\" : 1,\n b:[ ],};\nlet e = { };\n");
+    assert_eq!(out, "let xs = [1, 2, 3];\nlet o = { \"a\": 1, b: [] };\nlet e = {};\n");
+}
+
+#[test]
+fn formatter_breaks_wide_literals_one_element_per_line() {
+    let src = "let names = [\"alpha beta gamma\", \"delta epsilon zeta\", \"eta theta iota\", { \"kappa\": [1, 2], \"lambda\": \"mu nu xi omicron\" }];\n";
+    let out = format_ai(src);
+    let expected = "let names = [\n    \"alpha beta gamma\",\n    \"delta epsilon zeta\",\n    \"eta theta iota\",\n    { \"kappa\": [1, 2], \"lambda\": \"mu nu xi omicron\" }\n];\n";
+    assert_eq!(out, expected);
+    assert_eq!(format_ai(&out), out);
+    assert!(out.lines().all(|l| l.len() <= 80), "{out}");
+}
+
+#[test]
+fn formatter_keeps_commented_literals_on_their_lines() {
+    let src = "let xs = [\n    1, // one\n    2\n];\n";
+    assert_eq!(format_ai(src), src);
+}
+
+#[test]
+fn indexing_a_non_indexable_value_is_a_semantic_error() {
+    let src = "let n = 3;\nlog(n[0]);\nlog([1, 2][\"a\"]);\nlog({ \"a\": 1 }[0]);\nlog([1, 2][1]);\nlog({ \"a\": 1 }[\"a\"]);\n";
+    let diags = SemanticAnalyzer::new().analyze_with_spans(&parse(src));
+    let hits: Vec<_> = diags.iter().filter(|d| d.rule == Some("AEO-S009")).collect();
+    assert_eq!(hits.len(), 3, "{diags:?}");
+    assert_eq!(hits[0].message, "Cannot index a number");
+    assert_eq!(hits.iter().map(|d| d.line).collect::<Vec<_>>(), [2, 3, 4]);
+}
@@ -16,7 +16,7 @@ fn pipeline_end_to_end_basic() {
 
     let js = std::fs::read_to_string(&out).expect("output exists");
     assert!(js.contains("let x = (2 + 3);") || js.contains("let x = 2 + 3;"));
-    assert!(js.contains("console.log(x);"));
+    assert!(js.contains("console.log(__aeonmi_display(x));"));
 }
 
 #[test]
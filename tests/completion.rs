@@ -0,0 +1,40 @@
+use aeonmi_project::core::completion::{complete, CompletionKind};
+
+#[test]
+fn function_prefix_ranks_first_with_snippet() {
+    let src = "function foo(a) { return a; }\nlet x = fo";
+    let items = complete(src, 2, 11);
+    let first = &items[0];
+    assert_eq!(first.label, "foo", "{items:?}");
+    assert_eq!(first.kind, CompletionKind::Function);
+    assert_eq!(first.insert_text, "foo(${1:a})");
+    assert!(items.iter().any(|i| i.label == "for" && i.kind == CompletionKind::Keyword), "{items:?}");
+}
+
+#[test]
+fn enum_members_after_dot_on_broken_buffer() {
+    let src = "enum Status { Todo, Doing, Done }\nlet s = Status.";
+    let items = complete(src, 2, 16);
+    let labels: Vec<&str> = items.iter().map(|i| i.label.as_str()).collect();
+    assert_eq!(labels, ["Doing", "Done", "Todo"]);
+    assert!(items.iter().all(|i| i.kind == CompletionKind::EnumMember));
+}
+
+#[test]
+fn locals_outrank_globals_and_stay_in_their_function() {
+    let src = "let total = 1;\nfunction f(tally) {\n    return t\n}\nfunction g(tick) { return tick; }\n";
+    let labels: Vec<String> = complete(src, 3, 13).into_iter().map(|i| i.label).collect();
+    assert_eq!(labels.first().map(String::as_str), Some("tally"), "{labels:?}");
+    assert!(labels.contains(&"total".to_string()), "{labels:?}");
+    assert!(!labels.contains(&"tick".to_string()), "{labels:?}");
+}
+
+#[test]
+fn builtins_and_quantum_ops_carry_snippets() {
+    let items = complete("ent", 1, 4);
+    let ent = items.iter().find(|i| i.label == "entangle").expect("entangle");
+    assert_eq!(ent.kind, CompletionKind::QuantumOp);
+    assert_eq!(ent.insert_text, "entangle(${1:a}, ${2:b})");
+    let items = complete("le", 1, 3);
+    assert!(items.iter().any(|i| i.label == "len" && i.insert_text == "len(${1:value})"), "{items:?}");
+}
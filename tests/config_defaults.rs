@@ -0,0 +1,90 @@
+use aeonmi_project::commands::defaults;
+use serde_json::Value;
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+fn aeonmi(dir: &Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .args(args)
+        .current_dir(dir)
+        .env("AEONMI_UNICODE", "0")
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap()
+}
+
+fn effective(dir: &Path, command: &str) -> Vec<Value> {
+    let out = aeonmi(dir, &["--config", "user.toml", "config", "effective", command, "--json"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    serde_json::from_slice::<Value>(&out.stdout).unwrap().as_array().unwrap().clone()
+}
+
+fn flag<'a>(flags: &'a [Value], name: &str) -> (&'a str, &'a str) {
+    let f = flags.iter().find(|f| f["flag"] == name).unwrap_or_else(|| panic!("{name} missing: {flags:?}"));
+    (f["value"].as_str().unwrap(), f["source"].as_str().unwrap())
+}
+
+#[test]
+fn project_manifest_overrides_user_config() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("user.toml"), "[defaults.run]\nnative = false\nout = \"user.js\"\n").unwrap();
+    fs::write(dir.path().join("Aeonmi.toml"), "[defaults]\npretty-errors = true\n\n[defaults.run]\nnative = true\n").unwrap();
+
+    let flags = effective(dir.path(), "run");
+    let (native, from) = flag(&flags, "--native");
+    assert_eq!(native, "true");
+    assert!(from.ends_with("Aeonmi.toml"), "{from}");
+    let (out, from) = flag(&flags, "--out");
+    assert_eq!(out, "user.js");
+    assert!(from.ends_with("user.toml"), "{from}");
+    // Top-level scalars reach subcommands through global flags.
+    assert!(flag(&flags, "--pretty-errors").1.ends_with("Aeonmi.toml"));
+    assert_eq!(flag(&flags, "--watch"), ("false", "default"));
+}
+
+#[test]
+fn effective_view_is_a_readable_table() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("user.toml"), "[defaults.run]\nnative = true\n").unwrap();
+    let out = aeonmi(dir.path(), &["--config", "user.toml", "config", "effective", "run"]);
+    let text = String::from_utf8_lossy(&out.stdout);
+    assert!(text.starts_with("run (effective defaults)\n"), "{text}");
+    let native = text.lines().find(|l| l.trim_start().starts_with("--native")).unwrap();
+    assert!(native.contains("true") && native.trim_end().ends_with("user.toml"), "{native}");
+
+    let unknown = aeonmi(dir.path(), &["config", "effective", "nope"]);
+    assert_eq!(unknown.status.code(), Some(2));
+}
+
+#[test]
+fn command_line_beats_configured_defaults() {
+    let dir = tempfile::tempdir().unwrap();
+    let user = dir.path().join("user.toml");
+    fs::write(&user, "[defaults.run]\nnative = true\nout = \"cfg.js\"\n").unwrap();
+    let argv = |words: &[&str]| words.iter().map(OsString::from).collect::<Vec<_>>();
+
+    let (applied, warnings) = defaults::apply(argv(&["aeonmi", "--config", user.to_str().unwrap(), "run", "a.ai"]));
+    assert!(warnings.is_empty(), "{warnings:?}");
+    let words: Vec<_> = applied.iter().map(|a| a.to_str().unwrap()).collect();
+    assert_eq!(&words[3..], ["run", "--native", "--out=cfg.js", "a.ai"]);
+
+    let (applied, _) = defaults::apply(argv(&["aeonmi", "--config", user.to_str().unwrap(), "run", "a.ai", "--out", "cli.js"]));
+    let words: Vec<_> = applied.iter().map(|a| a.to_str().unwrap()).collect();
+    assert_eq!(&words[3..], ["run", "--native", "a.ai", "--out", "cli.js"]);
+}
+
+#[test]
+fn unknown_keys_and_bad_values_warn_without_failing() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("prog.ai"), "log(1 + 1);\n").unwrap();
+    fs::write(dir.path().join("user.toml"), "[defaults]\nbogus = 1\n\n[defaults.nope]\nx = 1\n\n[defaults.run]\nwatch = \"yes\"\n").unwrap();
+    let out = aeonmi(dir.path(), &["--config", "user.toml", "run", "prog.ai", "--native"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(String::from_utf8_lossy(&out.stdout).contains('2'));
+    let err = String::from_utf8_lossy(&out.stderr);
+    for needle in ["bogus: unknown flag 'bogus'", "nope: unknown subcommand 'nope'", "run.watch: --watch expects true or false"] {
+        assert!(err.contains(needle), "missing {needle:?} in {err}");
+    }
+}
@@ -0,0 +1,111 @@
+//! Runs every `tests/conformance/*.ai` program through the tree-walking VM, the
+//! bytecode VM (feature `bytecode`) and the JS emitter (when node is installed),
+//! and checks each against the golden `.out` next to it. A program with a `.err` golden too
+//! must stop with that runtime error after printing its `.out`.
+use aeonmi_project::commands::run::run_source_captured;
+use aeonmi_project::core::code_generator::CodeGenerator;
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::parser::Parser;
+use aeonmi_project::core::toolchain::{self, Tool};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn corpus() -> Vec<(PathBuf, String, String, Option<String>)> {
+    let mut cases: Vec<_> = fs::read_dir("tests/conformance")
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|e| e == "ai"))
+        .map(|p| {
+            let source = fs::read_to_string(&p).unwrap();
+            let expected = fs::read_to_string(p.with_extension("out"))
+                .unwrap_or_else(|_| panic!("{} has no golden .out", p.display()));
+            let error = fs::read_to_string(p.with_extension("err")).ok().map(|e| e.trim_end().to_string());
+            (p, source, expected, error)
+        })
+        .collect();
+    cases.sort();
+    assert!(cases.len() >= 5, "conformance corpus went missing");
+    cases
+}
+
+/// Programs the bytecode compiler cannot take: it has no arrays or objects.
+#[cfg(feature = "bytecode")]
+const NOT_IN_BYTECODE: &[&str] = &["aliasing.ai", "collections.ai"];
+
+fn parse(source: &str) -> aeonmi_project::core::ast::ASTNode {
+    let tokens = Lexer::from_str(source).tokenize().unwrap();
+    Parser::new(tokens).parse().unwrap()
+}
+
+#[test]
+fn tree_walk_vm_matches_golden_output() {
+    for (path, source, expected, error) in corpus() {
+        match (run_source_captured(&source), error) {
+            (Ok(run), None) => assert_eq!(run.output, expected, "{}", path.display()),
+            (Err(e), Some(error)) => assert!(e.contains(&error), "{}: {}", path.display(), e),
+            (Ok(_), Some(error)) => panic!("{}: expected `{}`", path.display(), error),
+            (Err(e), None) => panic!("{}: {}", path.display(), e),
+        }
+    }
+}
+
+#[cfg(feature = "bytecode")]
+#[test]
+fn bytecode_vm_matches_golden_output() {
+    use aeonmi_project::core::bytecode::BytecodeCompiler;
+    use aeonmi_project::core::vm::OutputBuffer;
+    use aeonmi_project::core::vm_bytecode::VM;
+    for (path, source, expected, error) in corpus() {
+        if path.file_name().is_some_and(|n| NOT_IN_BYTECODE.contains(&n.to_str().unwrap())) {
+            continue;
+        }
+        let chunk = BytecodeCompiler::new().try_compile(&parse(&source)).unwrap_or_else(|u| {
+            let list: Vec<String> = u.iter().map(|u| u.to_string()).collect();
+            panic!("{}: {}", path.display(), list.join("; "))
+        });
+        let buf = OutputBuffer::new();
+        let mut vm = VM::new(&chunk);
+        vm.set_output(Box::new(buf.clone()));
+        vm.run();
+        assert_eq!(buf.contents(), expected, "{}", path.display());
+        match (&vm.error, error) {
+            (Some(e), Some(error)) => assert!(e.contains(&error), "{}: {}", path.display(), e),
+            (got, want) => assert!(got.is_none() && want.is_none(), "{}: got {:?}, expected {:?}", path.display(), got, want),
+        }
+    }
+}
+
+#[cfg(feature = "bytecode")]
+#[test]
+fn bytecode_reports_unsupported_constructs() {
+    use aeonmi_project::core::bytecode::BytecodeCompiler;
+    let ast = parse("let total = 0;\nfunction f(n) { return n + total; }\nqubit q;\nlog(len(\"abc\"));\n");
+    let errs = BytecodeCompiler::new().try_compile(&ast).unwrap_err();
+    let text: Vec<String> = errs.iter().map(|e| e.to_string()).collect();
+    assert_eq!(errs.len(), 3, "{text:?}");
+    assert!(text[0].contains("`total` is not a parameter or local of `f`"), "{text:?}");
+    assert!(text[1].starts_with("3:7: unsupported construct in bytecode: quantum operation"), "{text:?}");
+    assert!(text[2].contains("`len` is not a top-level function"), "{text:?}");
+}
+
+#[test]
+fn js_backend_matches_golden_output() {
+    if !toolchain::available(Tool::Node) {
+        eprintln!("node not found; skipping JS conformance");
+        return;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    for (path, source, expected, error) in corpus() {
+        let js = CodeGenerator::new().generate(&parse(&source)).unwrap();
+        let file = dir.path().join(path.with_extension("js").file_name().unwrap());
+        fs::write(&file, js).unwrap();
+        let out = Command::new("node").arg(&file).output().unwrap();
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        match error {
+            Some(error) => assert!(!out.status.success() && stderr.contains(&error), "{}: {}", path.display(), stderr),
+            None => assert!(out.status.success(), "{}: {}", path.display(), stderr),
+        }
+        assert_eq!(String::from_utf8_lossy(&out.stdout), expected, "{}", path.display());
+    }
+}
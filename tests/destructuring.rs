@@ -0,0 +1,133 @@
+use aeonmi_project::commands::run::run_source_captured;
+use aeonmi_project::core::ast::ASTNode;
+use aeonmi_project::core::code_generator::CodeGenerator;
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::lowering::lower_ast_to_ir;
+use aeonmi_project::core::parser::Parser;
+use aeonmi_project::core::semantic_analyzer::SemanticAnalyzer;
+use aeonmi_project::core::toolchain::{self, Tool};
+use aeonmi_project::core::vm::{Interpreter, OutputBuffer, RuntimeErrorKind, Value};
+use std::fs;
+use std::process::Command;
+
+const PROGRAM: &str = r#"let a, b = 1, 2;
+log(a + b);
+let [x, y] = "hi";
+log(y + x);
+let p, [q, r] = 3, "ab";
+log(p);
+log(q + r);
+function swap(s) {
+    let [u, v] = s;
+    return v + u;
+}
+log(swap("xy"));
+"#;
+
+fn parse(source: &str) -> ASTNode {
+    let tokens = Lexer::from_str(source).tokenize().expect("lex");
+    Parser::new(tokens).parse().expect("parse")
+}
+
+fn node(js: &str) -> std::process::Output {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("prog.js");
+    fs::write(&file, js).unwrap();
+    Command::new("node").arg(&file).output().unwrap()
+}
+
+/// `source` on the native VM with `pair = [1, ["a", "b"]]` already bound
+/// (there are no array literals to build it from inside the language).
+fn run_with_pair(source: &str) -> (String, Result<Value, aeonmi_project::core::vm::RuntimeError>) {
+    let module = lower_ast_to_ir(&parse(source), "main").unwrap();
+    let buf = OutputBuffer::new();
+    let mut interp = Interpreter::new();
+    interp.set_output(Box::new(buf.clone()));
+    let inner = Value::Array(vec![Value::String("a".into()), Value::String("b".into())]);
+    interp.env.define("pair".into(), Value::Array(vec![Value::Number(1.0), inner]));
+    let result = interp.run_main(&module);
+    (buf.contents(), result)
+}
+
+const PAIR_JS: &str = "const pair = [1, [\"a\", \"b\"]];\n";
+
+#[test]
+fn multi_declarations_and_string_patterns_agree_natively_and_in_js() {
+    let expected = "3\nih\n3\nab\nyx\n";
+    assert_eq!(run_source_captured(PROGRAM).unwrap().output, expected);
+    if !toolchain::available(Tool::Node) {
+        eprintln!("node not found; skipping JS side");
+        return;
+    }
+    let js = CodeGenerator::new().generate(&parse(PROGRAM)).unwrap();
+    let out = node(&js);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout), expected);
+}
+
+#[test]
+fn nested_patterns_bind_inner_elements() {
+    let src = "let [n, [s, t]] = pair;\nlog(n);\nlog(t + s);\n";
+    let (out, result) = run_with_pair(src);
+    result.unwrap();
+    assert_eq!(out, "1\nba\n");
+    if toolchain::available(Tool::Node) {
+        let js = CodeGenerator::new().generate(&parse(src)).unwrap();
+        let run = node(&format!("{PAIR_JS}{js}"));
+        assert!(run.status.success(), "{}", String::from_utf8_lossy(&run.stderr));
+        assert_eq!(String::from_utf8_lossy(&run.stdout), "1\nba\n");
+    }
+}
+
+#[test]
+fn wrong_element_count_fails_the_same_way_on_both_backends() {
+    let src = "let [n, [s, t, u]] = pair;\n";
+    let (_, result) = run_with_pair(src);
+    let err = result.unwrap_err();
+    assert_eq!(err.kind, RuntimeErrorKind::ArityMismatch { expected: 3, actual: 2 });
+    assert!(err.message.contains("destructuring expected 3 values, got 2"), "{}", err.message);
+
+    let err = run_source_captured("let [a, b] = \"abc\";\n").unwrap_err();
+    assert!(err.contains("destructuring expected 2 values, got 3"), "{err}");
+    if toolchain::available(Tool::Node) {
+        let js = CodeGenerator::new().generate(&parse(src)).unwrap();
+        let run = node(&format!("{PAIR_JS}{js}"));
+        assert!(!run.status.success());
+        assert!(String::from_utf8_lossy(&run.stderr).contains("destructuring expected 3 values, got 2"));
+    }
+}
+
+#[test]
+fn malformed_declarations_are_parse_errors() {
+    for (src, needle) in [
+        ("let a, b = 1;\n", "Expected 2 values after '=', found 1"),
+        ("let a, [b, a] = 1, pair;\n", "'a' is bound twice in one declaration"),
+        ("let [a, 1] = pair;\n", "Expected a variable name or '['"),
+    ] {
+        let err = Parser::new(Lexer::from_str(src).tokenize().unwrap()).parse().unwrap_err();
+        assert!(err.message.contains(needle), "{src}: {}", err.message);
+    }
+}
+
+#[test]
+fn destructured_names_are_declared_for_analysis() {
+    let diags = SemanticAnalyzer::new().analyze_with_spans(&parse("let a, [b, c] = 1, \"xy\";\nlog(a + b + c);\n"));
+    assert!(diags.iter().all(|d| !d.message.contains("ndeclared")), "{diags:?}");
+}
+
+#[test]
+fn formatter_separates_let_from_its_pattern() {
+    use aeonmi_project::core::formatter::format_ai;
+    let out = format_ai("let[a,[b,c]] = pair;\nlog(xs[0]);\n");
+    assert!(out.starts_with("let [a, [b, c]] = pair;"), "{out}");
+    assert!(out.contains("xs[0]"), "{out}");
+}
+
+#[cfg(feature = "bytecode")]
+#[test]
+fn bytecode_compiles_plain_lists_but_not_array_patterns() {
+    use aeonmi_project::core::bytecode::BytecodeCompiler;
+    assert!(BytecodeCompiler::new().try_compile(&parse("let a, b = 1, 2;\nlog(a + b);\n")).is_ok());
+    let errs = BytecodeCompiler::new().try_compile(&parse("let [a, b] = \"ab\";\n")).unwrap_err();
+    assert!(errs[0].to_string().contains("unsupported construct in bytecode: destructuring"), "{errs:?}");
+}
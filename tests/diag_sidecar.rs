@@ -0,0 +1,79 @@
+use std::fs;
+use std::path::Path;
+
+use aeonmi_project::cli::EmitKind;
+use aeonmi_project::commands::compile::compile_pipeline_with_sidecar;
+use aeonmi_project::core::artifact_cache::cache_stats;
+use aeonmi_project::core::diag_sidecar::DiagSidecar;
+
+const WARNINGS: &str = "let unused = 1;\nqubit q;\nsuperpose(q);\nlog(2);\n";
+
+fn emit(dir: &Path, extra: &[&str]) -> std::process::Output {
+    std::process::Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .current_dir(dir)
+        .args(["emit", "--emit", "ai", "w.ai", "-o", "out.ai"])
+        .args(extra)
+        .output()
+        .unwrap()
+}
+
+fn read(path: &Path) -> DiagSidecar {
+    serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[test]
+fn sidecar_records_warnings_with_codes_and_spans() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("w.ai"), WARNINGS).unwrap();
+    let out = emit(dir.path(), &["--diag-sidecar"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(String::from_utf8_lossy(&out.stdout).contains("ok: wrote 2 diagnostics to 'out.ai.diag.json'."));
+
+    let sidecar = read(&dir.path().join("out.ai.diag.json"));
+    assert_eq!(sidecar.schema, 1);
+    assert_eq!(sidecar.file, "w.ai");
+    assert_eq!(sidecar.source_sha1.len(), 40);
+    assert_eq!(sidecar.compiler_version, env!("CARGO_PKG_VERSION"));
+    assert!(!sidecar.sema_skipped);
+    let summary: Vec<(&str, Option<&str>, usize, usize)> =
+        sidecar.diagnostics.iter().map(|d| (d.severity.as_str(), d.code.as_deref(), d.line, d.column)).collect();
+    assert_eq!(summary, [("warning", None, 1, 5), ("warning", Some("AEO-Q004"), 2, 7)]);
+    assert!(sidecar.diagnostics[0].message.contains("unused"));
+}
+
+#[test]
+fn no_sema_is_recorded_and_stdout_is_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("w.ai"), WARNINGS).unwrap();
+    assert!(emit(dir.path(), &["--diag-sidecar", "--no-sema"]).status.success());
+    let sidecar = read(&dir.path().join("out.ai.diag.json"));
+    assert!(sidecar.sema_skipped);
+    assert!(sidecar.diagnostics.is_empty());
+
+    let to_stdout = std::process::Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .current_dir(dir.path())
+        .args(["emit", "w.ai", "-o", "-", "--diag-sidecar"])
+        .output()
+        .unwrap();
+    assert_eq!(to_stdout.status.code(), Some(2));
+}
+
+#[test]
+fn sidecar_round_trips_through_the_artifact_cache() {
+    let dir = tempfile::tempdir().unwrap();
+    let (a, b) = (dir.path().join("a.ai"), dir.path().join("b.ai"));
+    // Unique source so no other compile in this process shares the cache key.
+    let source = format!("{WARNINGS}// {}\n", dir.path().display());
+    fs::write(&a, &source).unwrap();
+    fs::write(&b, &source).unwrap();
+
+    let before = cache_stats().0;
+    compile_pipeline_with_sidecar(Some(a.clone()), EmitKind::Ai, dir.path().join("a.out.ai"), false, false, false, false).unwrap();
+    assert_eq!(cache_stats().0, before + 2, "output and sidecar are both cached");
+    compile_pipeline_with_sidecar(Some(b.clone()), EmitKind::Ai, dir.path().join("b.out.ai"), false, false, false, false).unwrap();
+    assert_eq!(cache_stats().0, before + 2, "second compile is served from the cache");
+
+    let (first, second) = (read(&dir.path().join("a.out.ai.diag.json")), read(&dir.path().join("b.out.ai.diag.json")));
+    assert_eq!(second.file, b.display().to_string());
+    assert_eq!(DiagSidecar { file: first.file.clone(), ..second }, first);
+}
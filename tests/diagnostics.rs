@@ -50,7 +50,7 @@ fn pretty_parser_error_shows_span() {
     let err = String::from_utf8_lossy(&output.stderr);
     assert!(err.contains("error:"), "no 'error:' in stderr\n{err}");
     assert!(
-        err.to_lowercase().contains("expected ';'"),
+        err.contains("expected one of: ';', an operator — found 'log'"),
         "parser message missing\n{err}"
     );
     // be flexible: parser can report line 1 or 2 depending on how the newline is handled
@@ -59,3 +59,29 @@ fn pretty_parser_error_shows_span() {
         "no file prefix in span\n{err}"
     );
 }
+
+#[test]
+fn plain_and_pretty_errors_both_name_the_file_line_and_column() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("bad_plain.ai");
+    fs::write(&input, "let x = 1\nlog(x);\n").unwrap();
+    let run = |pretty: bool| {
+        let mut cmd = Command::new(bin());
+        if pretty {
+            cmd.arg("--pretty-errors");
+        }
+        let output = cmd.arg("run").arg(&input).env("NO_COLOR", "1").output().expect("run");
+        assert!(!output.status.success());
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+    let path = input.display().to_string();
+
+    let plain = run(false);
+    let first = plain.lines().next().unwrap_or_default();
+    assert_eq!(first, format!("{path}:2:1: error[AEO-P001]: Parsing error: expected one of: ';', an operator — found 'log'"), "{plain}");
+    assert!(plain.contains("= hint: add ';' at the end of line 1"), "{plain}");
+
+    let pretty = run(true);
+    assert!(pretty.contains(&format!("--> {path}:2:1")), "{pretty}");
+    assert!(pretty.contains("   2 | log(x);"), "{pretty}");
+}
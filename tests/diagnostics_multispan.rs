@@ -0,0 +1,97 @@
+//! Errors with labeled secondary spans and suggested fixes, rendered and as `@@DIAG` JSON.
+use aeonmi_project::core::diagnostics::{json_error, parse_error_parts, render_error, MultiSpan, Notes, Span, Suggestion};
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::parser::Parser;
+use aeonmi_project::core::semantic_analyzer::{SemanticAnalyzer, SemanticDiagnostic};
+use aeonmi_project::core::style::Style;
+use std::process::Command;
+
+fn sema(src: &str) -> Vec<SemanticDiagnostic> {
+    let ast = Parser::new(Lexer::from_str(src).tokenize().unwrap()).parse().unwrap();
+    SemanticAnalyzer::new().analyze_with_spans(&ast)
+}
+
+#[test]
+fn labels_are_underlined_on_their_lines_in_order() {
+    let src = "let total = 0;\nlet total = 10;\nlog(total);\n";
+    let d = sema(src).into_iter().find(|d| d.message == "Redeclaration of 'total'").expect("redeclaration");
+    let out = render_error(&Style::PLAIN, "demo.ai", src, &d.message, &d, Some("AEO-S002"));
+    let expected = "\
+error[AEO-S002]: Redeclaration of 'total'
+--> demo.ai:2:5
+    2 |
+   1 | let total = 0;
+      |     ----- 'total' first declared here
+   2 | let total = 10;
+      |     ^^^^^
+      = run `aeonmi explain-error AEO-S002` for more
+
+";
+    assert_eq!(out, expected);
+
+    // a label on the primary line goes under the primary underline; far lines get a `...`
+    let src = "let a = f(b);\n\n\nlog(a);\n";
+    let spans = Span { line: 1, col: 9, len: 1 }.with_label(Span { line: 1, col: 11, len: 1 }, "not a number").with_label(Span { line: 4, col: 5, len: 1 }, "used here");
+    let out = render_error(&Style::PLAIN, "demo.ai", src, "bad call", spans, None);
+    assert_eq!(
+        out,
+        "error: bad call\n--> demo.ai:1:9\n    1 |\n   1 | let a = f(b);\n      |         ^\n      |           - not a number\n ...\n   4 | log(a);\n      |     - used here\n\n"
+    );
+}
+
+#[test]
+fn duplicate_function_labels_the_first_definition() {
+    let src = "function foo() { return 1; }\nfunction foo() { return 2; }\n";
+    let d = sema(src).into_iter().find(|d| d.message.starts_with("Duplicate function 'foo'")).expect("duplicate");
+    let spans = MultiSpan::from(&d);
+    assert_eq!(spans.primary.line, 2);
+    assert_eq!(spans.labels.len(), 1);
+    assert_eq!((spans.labels[0].span.line, spans.labels[0].message.as_str()), (1, "'foo' first defined here"));
+}
+
+#[test]
+fn suggestions_show_the_fixed_line() {
+    let src = "let n = 1\nlog(n);\n";
+    let err = Parser::new(Lexer::from_str(src).tokenize().unwrap()).parse().unwrap_err();
+    assert_eq!(err.detail.suggestion, Some(Suggestion::insert("add ';' at the end of line 1", 1, 10, ";")));
+    // a string's end is not known from its token, so only the hint is given
+    let err = Parser::new(Lexer::from_str("let s = \"a\"\nlog(s);\n").tokenize().unwrap()).parse().unwrap_err();
+    assert!(err.detail.suggestion.is_none() && err.detail.hint.is_some());
+
+    let replaced = render_error(&Style::PLAIN, "demo.ai", "if (a = b) {}\n", "oops", Span::single(1, 7).with_suggestion(Suggestion::replace("compare", Span::single(1, 7), "==")), None);
+    assert!(replaced.ends_with("      = help: compare\n   1 | if (a == b) {}\n      |       ~~\n\n"), "{replaced}");
+}
+
+#[test]
+fn json_carries_notes_and_suggestion() {
+    let src = "function f(n) {\n    log(n)\n}\n";
+    let err = Parser::new(Lexer::from_str(src).tokenize().unwrap()).parse().unwrap_err();
+    let (title, spans, notes) = parse_error_parts(&err);
+    let v: serde_json::Value = serde_json::from_str(&json_error("demo.ai", &title, &spans, Some("AEO-P001"), &notes).unwrap()).unwrap();
+    assert_eq!(v["suggestion"], serde_json::json!({ "message": "add ';' at the end of line 2", "line": 2, "col": 11, "len": 0, "replacement": ";" }));
+    assert!(v.get("hint").is_none(), "the suggestion replaces the hint: {v}");
+    assert!(v.get("notes").is_none());
+
+    let err = Parser::new(Lexer::from_str("function f(n) {\n    log(n);\n\n").tokenize().unwrap()).parse().unwrap_err();
+    let (title, spans, notes) = parse_error_parts(&err);
+    let v: serde_json::Value = serde_json::from_str(&json_error("demo.ai", &title, &spans, None, &notes).unwrap()).unwrap();
+    assert_eq!(v["notes"], serde_json::json!([{ "message": "this '{' is never closed", "line": 1, "col": 15, "len": 1 }]));
+    assert!(v["hint"].is_string());
+
+    // a single span keeps the old payload
+    let v: serde_json::Value = serde_json::from_str(&json_error("demo.ai", "boom", &MultiSpan::from(Span::single(3, 4)), None, &Notes::default()).unwrap()).unwrap();
+    assert_eq!(v, serde_json::json!({ "severity": "error", "message": "boom", "file": "demo.ai", "line": 3, "col": 4, "len": 1 }));
+}
+
+#[test]
+fn cli_diag_json_includes_the_suggestion() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("bad.ai");
+    std::fs::write(&input, "let x = 1\nlog(x);\n").unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_aeonmi")).args(["--quiet", "--diag-json", "run"]).arg(&input).arg("--native").output().unwrap();
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    let line = stderr.lines().find_map(|l| l.strip_prefix("@@DIAG:")).expect("diag line");
+    let v: serde_json::Value = serde_json::from_str(line).unwrap();
+    assert_eq!(v["suggestion"]["replacement"], ";", "{v}");
+    assert_eq!((v["suggestion"]["line"].as_u64(), v["suggestion"]["col"].as_u64()), (Some(1), Some(10)));
+}
@@ -0,0 +1,78 @@
+//! Terminal diagnostics sink: ordering, grouping of repeated messages and `--max-diagnostics`.
+use aeonmi_project::core::diagnostics::{Diagnostic, DiagnosticSink};
+use aeonmi_project::core::style::Style;
+use std::process::Command;
+
+/// 500 diagnostics pushed out of order: one message on 100 lines of `a.ai`, plus 400
+/// distinct ones alternating between `a.ai` and `b.ai`.
+fn flood(max: usize) -> String {
+    let mut sink = DiagnosticSink::with_max(max);
+    for i in (0..500).rev() {
+        let d = if i < 100 {
+            Diagnostic::error("a.ai", i * 4 + 1, 5, "Arithmetic on non-number")
+        } else {
+            Diagnostic::error(if i % 2 == 0 { "a.ai" } else { "b.ai" }, i, 1, format!("unknown name 'v{i}'"))
+        };
+        sink.push(d);
+    }
+    sink.render(&Style::PLAIN)
+}
+
+#[test]
+fn groups_repeats_and_truncates_with_a_count() {
+    let out = flood(50);
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[0], "error: Arithmetic on non-number (100 occurrences)");
+    assert_eq!(lines[1], "  --> a.ai:1:5, a.ai:5:5, a.ai:9:5 and 97 more");
+    assert_eq!(lines[2], "a.ai:100:1: error: unknown name 'v100'");
+    assert_eq!(lines[3], "a.ai:102:1: error: unknown name 'v102'");
+    // The group counts as one entry: 49 single diagnostics follow it.
+    assert_eq!(lines.len(), 2 + 49 + 1, "{out}");
+    assert!(lines[2..51].iter().all(|l| l.starts_with("a.ai:")), "{out}");
+    assert_eq!(lines[51], "and 351 more errors (re-run with --max-diagnostics 0 to see all)");
+}
+
+#[test]
+fn zero_lifts_the_limit_and_orders_by_file_then_line() {
+    let out = flood(0);
+    assert!(!out.contains("more errors"), "{out}");
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 2 + 400);
+    let first_b = lines.iter().position(|l| l.starts_with("b.ai:")).unwrap();
+    assert_eq!(lines[first_b], "b.ai:101:1: error: unknown name 'v101'");
+    assert!(lines[first_b..].iter().all(|l| l.starts_with("b.ai:")));
+    assert_eq!(lines.last(), Some(&"b.ai:499:1: error: unknown name 'v499'"));
+}
+
+#[test]
+fn a_message_on_two_lines_is_not_grouped() {
+    let mut sink = DiagnosticSink::with_max(50);
+    sink.push(Diagnostic::warning("x.ai", 7, 1, "trailing whitespace"));
+    sink.push(Diagnostic::warning("x.ai", 2, 1, "trailing whitespace"));
+    assert_eq!(sink.render(&Style::PLAIN), "x.ai:2:1: warning: trailing whitespace\nx.ai:7:1: warning: trailing whitespace\n");
+}
+
+#[test]
+fn lint_honors_max_diagnostics() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("x.ai");
+    std::fs::write(&file, "let a = 1 \nlet b = 2\nlet c = 3\nlet d = 4\n").unwrap();
+    let lint = |max: &str| {
+        let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+            .current_dir(dir.path())
+            .env("NO_COLOR", "1")
+            .args(["lint", "x.ai", "--max-diagnostics", max])
+            .output()
+            .unwrap();
+        assert_eq!(out.status.code(), Some(1));
+        String::from_utf8_lossy(&out.stdout).into_owned()
+    };
+    assert_eq!(
+        lint("1"),
+        "x.ai:1:10: warning: trailing whitespace\nand 4 more warnings (re-run with --max-diagnostics 0 to see all)\nx.ai: 5 problems\n"
+    );
+    assert_eq!(
+        lint("0"),
+        "x.ai:1:10: warning: trailing whitespace\nwarning: statement should end with ';' (4 occurrences)\n  --> x.ai:1:10, x.ai:2:10, x.ai:3:10 and 1 more\nx.ai: 5 problems\n"
+    );
+}
@@ -0,0 +1,112 @@
+use aeonmi_project::commands::diff_ast::{diff, render, Change, TOP_LEVEL};
+use aeonmi_project::core::ast::ASTNode;
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::parser::Parser;
+use aeonmi_project::core::style::Style;
+use std::process::Command;
+
+fn parse(src: &str) -> ASTNode {
+    Parser::new(Lexer::from_str(src).tokenize().unwrap()).parse().unwrap()
+}
+
+const BASE: &str = "function helper(x) { return x * 2; }
+function compute(n) {
+    let total = 0;
+    let i = 0;
+    while (i < n) {
+        if (i > 2) {
+            total = total + i;
+        }
+        i = i + 1;
+    }
+    return total;
+}
+log(compute(5));
+";
+
+#[test]
+fn reformat_has_no_semantic_changes() {
+    let reformatted = "function helper(x)
+{
+  return x * 2;
+}
+
+
+function compute(n) {
+  let total = 0;   let i = 0;
+  while (i < n) { if (i > 2) { total = total + i; } i = i + 1; }
+  return total;
+}
+log( compute(5) );";
+    let changes = diff(&parse(BASE), &parse(reformatted));
+    assert!(changes.is_empty(), "{changes:?}");
+    assert_eq!(render(&changes, &Style::PLAIN), "no semantic changes\n");
+}
+
+#[test]
+fn renamed_function_with_same_body() {
+    let renamed = BASE.replace("function helper(x)", "function double(x)");
+    let changes = diff(&parse(BASE), &parse(&renamed));
+    assert_eq!(changes, vec![Change::FunctionRenamed { from: "helper".into(), to: "double".into(), line: 1, similarity: 1.0 }]);
+    assert!(render(&changes, &Style::PLAIN).starts_with("~ function helper renamed to double (line 1, identical body)\n"));
+}
+
+#[test]
+fn rename_with_edits_is_still_a_rename() {
+    let old = "function a(n) { let x = n; let y = x + 1; let z = y + 2; return z; }";
+    let new = "function b(n) { let x = n; let y = x + 1; let z = y + 3; return z; }";
+    let changes = diff(&parse(old), &parse(new));
+    assert!(matches!(&changes[0], Change::FunctionRenamed { from, to, similarity, .. } if from == "a" && to == "b" && *similarity == 0.75), "{changes:?}");
+    assert!(matches!(&changes[1], Change::StatementChanged { scope, old, new, .. } if scope == "b" && old == "let z = y + 2" && new == "let z = y + 3"), "{changes:?}");
+    assert_eq!(changes.len(), 2);
+}
+
+#[test]
+fn change_deep_in_a_loop_is_reported_where_it_happened() {
+    let edited = BASE.replace("total = total + i;", "total = total + i * 2;");
+    let changes = diff(&parse(BASE), &parse(&edited));
+    assert_eq!(
+        changes,
+        vec![Change::StatementChanged {
+            scope: "compute > while (i < n) > if (i > 2)".into(),
+            line: 7,
+            old: "total = total + i".into(),
+            new: "total = total + (i * 2)".into(),
+        }]
+    );
+}
+
+#[test]
+fn added_removed_functions_and_statements() {
+    let old = "function gone() { return 1; }\nlet a = 1;\nlog(a);";
+    let new = "let a = 1;\nlet b = 2;\nlog(a);\nfunction fresh(q) { log(q); log(q); }";
+    let changes = diff(&parse(old), &parse(new));
+    assert_eq!(
+        changes,
+        vec![
+            Change::FunctionRemoved { name: "gone".into(), line: 1 },
+            Change::FunctionAdded { name: "fresh".into(), line: 4 },
+            Change::StatementAdded { scope: TOP_LEVEL.into(), line: 2, text: "let b = 2".into() },
+        ]
+    );
+}
+
+#[test]
+fn cli_text_and_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let (old, new) = (dir.path().join("old.ai"), dir.path().join("new.ai"));
+    std::fs::write(&old, BASE).unwrap();
+    std::fs::write(&new, BASE.replace("log(compute(5));", "log(compute(6));")).unwrap();
+    let run = |extra: &[&str]| Command::new(env!("CARGO_BIN_EXE_aeonmi_project")).arg("diff-ast").arg(&old).arg(&new).args(extra).env("NO_COLOR", "1").env("AEONMI_UNICODE", "0").output().unwrap();
+
+    let out = run(&[]);
+    assert!(out.status.success());
+    let text = String::from_utf8_lossy(&out.stdout);
+    assert!(text.contains("~ <top level> (line 13): `log(compute(5))` -> `log(compute(6))`"), "{text}");
+    assert!(text.ends_with("1 semantic change\n"), "{text}");
+
+    let out = run(&["--json"]);
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(v["changes"][0]["kind"], "statement_changed");
+    assert_eq!(v["changes"][0]["new"], "log(compute(6))");
+}
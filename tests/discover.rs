@@ -0,0 +1,72 @@
+use aeonmi_project::io::discover::discover;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn fixture() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for f in ["main.ai", "notes.txt", "src/a.ai", "src/nested/deep/b.ai", "src/nested/c.aeon", "src/gen/d.ai", "target/e.ai", ".git/f.ai", "node_modules/pkg/g.ai"] {
+        let p = dir.path().join(f);
+        fs::create_dir_all(p.parent().unwrap()).unwrap();
+        fs::write(p, "let x = 1;\n").unwrap();
+    }
+    dir
+}
+
+fn rel(root: &Path, files: Vec<PathBuf>) -> Vec<String> {
+    files.iter().map(|p| p.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/")).collect()
+}
+
+fn s(v: &[&str]) -> Vec<String> {
+    v.iter().map(|x| x.to_string()).collect()
+}
+
+#[test]
+fn directory_recurses_sorted_and_skips_ignored_dirs() {
+    let dir = fixture();
+    let files = discover(&[dir.path().to_path_buf()], &[], &[]).unwrap();
+    assert_eq!(rel(dir.path(), files), ["main.ai", "src/a.ai", "src/gen/d.ai", "src/nested/deep/b.ai"]);
+}
+
+#[test]
+fn globs_match_nested_paths_with_either_separator() {
+    let dir = fixture();
+    let root = dir.path().to_string_lossy().replace('\\', "/");
+    let unix = discover(&[PathBuf::from(format!("{root}/src/**/*.ai"))], &[], &[]).unwrap();
+    assert_eq!(rel(dir.path(), unix.clone()), ["src/a.ai", "src/gen/d.ai", "src/nested/deep/b.ai"]);
+    let windows = discover(&[PathBuf::from(format!("{root}\\src\\**\\*.ai"))], &[], &[]).unwrap();
+    assert_eq!(windows, unix);
+    let shallow = discover(&[PathBuf::from(format!("{root}/src/*.ai"))], &[], &[]).unwrap();
+    assert_eq!(rel(dir.path(), shallow), ["src/a.ai"]);
+}
+
+#[test]
+fn excludes_by_component_or_path() {
+    let dir = fixture();
+    let by_name = discover(&[dir.path().to_path_buf()], &[], &s(&["gen"])).unwrap();
+    assert_eq!(rel(dir.path(), by_name), ["main.ai", "src/a.ai", "src/nested/deep/b.ai"]);
+    let by_path = discover(&[dir.path().to_path_buf()], &[], &s(&["**/nested/**", "*.ai.bak"])).unwrap();
+    assert_eq!(rel(dir.path(), by_path), ["main.ai", "src/a.ai", "src/gen/d.ai"]);
+}
+
+#[test]
+fn ext_widens_and_explicit_files_are_kept() {
+    let dir = fixture();
+    let wide = discover(&[dir.path().join("src")], &s(&["ai", ".aeon"]), &[]).unwrap();
+    assert_eq!(rel(dir.path(), wide), ["src/a.ai", "src/gen/d.ai", "src/nested/c.aeon", "src/nested/deep/b.ai"]);
+    let explicit = discover(&[dir.path().join("notes.txt"), dir.path().join("main.ai"), dir.path().join("main.ai")], &[], &[]).unwrap();
+    assert_eq!(rel(dir.path(), explicit), ["main.ai", "notes.txt"]);
+}
+
+#[test]
+fn format_check_walks_a_directory() {
+    let dir = fixture();
+    fs::write(dir.path().join("src/nested/deep/b.ai"), "let   y=2;\n").unwrap();
+    let out = std::process::Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .current_dir(dir.path())
+        .args(["format", "--check", "--exclude", "gen", "."])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&out.stdout).replace('\\', "/");
+    assert_eq!(stdout.trim(), "src/nested/deep/b.ai");
+}
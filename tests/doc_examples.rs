@@ -0,0 +1,63 @@
+use aeonmi_project::commands::doctest::{check_block, check_file, extract_blocks};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+fn bin() -> String {
+    env!("CARGO_BIN_EXE_aeonmi_project").to_string()
+}
+
+#[test]
+fn language_guide_examples_pass() {
+    let guide = Path::new(env!("CARGO_MANIFEST_DIR")).join("docs/Aeonmi_Language_Guide.md");
+    let (count, failures) = check_file(&guide).expect("guide should be readable");
+    assert!(count >= 3, "expected seeded aeonmi blocks, found {}", count);
+    let report: Vec<String> = failures.iter().map(|f| f.to_string()).collect();
+    assert!(failures.is_empty(), "doc examples failed:\n{}", report.join("\n"));
+}
+
+#[test]
+fn extracts_run_blocks_with_expected_output() {
+    let md = "# T\n```ai\nlog(0);\n```\n```aeonmi\nlet a = 1;\n```\n\n```aeonmi,run\nlog(2);\n```\n```output\n2\n```\n";
+    let blocks = extract_blocks(md);
+    assert_eq!(blocks.len(), 2);
+    assert_eq!(blocks[0].line, 5);
+    assert!(!blocks[0].run);
+    assert_eq!(blocks[1].line, 9);
+    assert!(blocks[1].run);
+    assert_eq!(blocks[1].expected.as_deref(), Some("2\n"));
+    for b in &blocks {
+        check_block(b).expect("block should pass");
+    }
+}
+
+#[test]
+fn mismatch_reports_markdown_location() {
+    let dir = tempfile::tempdir().unwrap();
+    let md = dir.path().join("bad.md");
+    fs::write(
+        &md,
+        "intro\n\n```aeonmi,run\nlog(1 + 1);\n```\n```output\n3\n```\n\n```aeonmi\nlet = ;\n```\n",
+    )
+    .unwrap();
+    let (count, failures) = check_file(&md).unwrap();
+    assert_eq!(count, 2);
+    assert_eq!(failures.len(), 2);
+    assert_eq!(failures[0].line, 3);
+    assert!(failures[0].message.contains("output mismatch"));
+    assert_eq!(failures[1].line, 10);
+    assert!(failures[1].message.contains("parsing error"));
+
+    let output = Command::new(bin())
+        .arg("doctest")
+        .arg(&md)
+        .output()
+        .expect("failed to run aeonmi_project");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(&format!("{}:3:", md.display())),
+        "stderr:\n{}",
+        stderr
+    );
+}
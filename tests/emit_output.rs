@@ -0,0 +1,98 @@
+use aeonmi_project::core::code_generator::CodeGenerator;
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::parser::Parser;
+use aeonmi_project::core::py_emitter::PyEmitter;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+const SRC: &str = "let x = 2;\nfunction double(n) { return n * 2; }\nlog(double(x));\n";
+
+fn aeonmi(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .expect("spawn aeonmi")
+}
+
+fn ast() -> aeonmi_project::core::ast::ASTNode {
+    Parser::new(Lexer::from_str(SRC).tokenize().unwrap()).parse().unwrap()
+}
+
+#[test]
+fn out_dash_writes_only_code_to_stdout() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("prog.ai"), SRC).unwrap();
+    let out = aeonmi(dir.path(), &["emit", "prog.ai", "--out", "-", "--no-sema"]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    let expected = CodeGenerator::new().generate(&ast()).unwrap();
+    assert_eq!(String::from_utf8_lossy(&out.stdout), expected);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("note: semantic analysis skipped"));
+    assert!(!stderr.contains("DEBUG: main() called"));
+    assert!(!dir.path().join("-").exists());
+}
+
+#[test]
+fn out_dash_with_ai_and_token_dump() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("prog.ai"), SRC).unwrap();
+    let out = aeonmi(dir.path(), &["emit", "prog.ai", "--emit", "ai", "-o", "-", "--tokens"]);
+    assert!(out.status.success());
+    let expected = CodeGenerator::new_ai().generate(&ast()).unwrap();
+    assert_eq!(String::from_utf8_lossy(&out.stdout), expected);
+    assert!(String::from_utf8_lossy(&out.stderr).contains("=== Tokens ==="));
+}
+
+#[test]
+fn export_py_to_stdout() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("prog.ai"), SRC).unwrap();
+    let out = aeonmi(dir.path(), &["export", "prog.ai", "--format", "py", "--out", "-"]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout), PyEmitter::new().generate(&ast()));
+    assert!(!dir.path().join("prog.py").exists());
+}
+
+#[test]
+fn no_overwrite_refuses_existing_target() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("prog.ai"), SRC).unwrap();
+    fs::write(dir.path().join("prog.js"), "keep me").unwrap();
+    let out = aeonmi(dir.path(), &["emit", "prog.ai", "--out", "prog.js", "--no-overwrite"]);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("already exists"));
+    assert_eq!(fs::read_to_string(dir.path().join("prog.js")).unwrap(), "keep me");
+
+    let out = aeonmi(dir.path(), &["export", "prog.ai", "--format", "js", "--out", "prog.js", "--no-overwrite"]);
+    assert_eq!(out.status.code(), Some(2));
+    assert_eq!(fs::read_to_string(dir.path().join("prog.js")).unwrap(), "keep me");
+
+    let out = aeonmi(dir.path(), &["emit", "prog.ai", "--out", "fresh.js", "--no-overwrite"]);
+    assert!(out.status.success());
+    assert!(dir.path().join("fresh.js").exists());
+}
+
+#[test]
+fn out_naming_the_input_does_not_clobber_it() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("same.ai"), SRC).unwrap();
+    let out = aeonmi(dir.path(), &["emit", "same.ai", "--emit", "ai", "--out", "same.ai"]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(fs::read_to_string(dir.path().join("same.ai")).unwrap(), SRC);
+    assert!(dir.path().join("same.out.ai").exists());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("is the input file"));
+}
+
+#[test]
+fn format_backup_keeps_original() {
+    let dir = tempfile::tempdir().unwrap();
+    let messy = "let   x=1;\nlog( x );\n";
+    let file = dir.path().join("messy.ai");
+    fs::write(&file, messy).unwrap();
+    let out = aeonmi(dir.path(), &["format", "messy.ai", "--backup"]);
+    assert_eq!(out.status.code(), Some(1), "file should be reformatted");
+    assert_eq!(fs::read_to_string(dir.path().join("messy.ai.orig")).unwrap(), messy);
+    assert_ne!(fs::read_to_string(&file).unwrap(), messy);
+}
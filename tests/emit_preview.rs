@@ -0,0 +1,71 @@
+use aeonmi_project::cli::EmitKind;
+use aeonmi_project::core::preview::{emit_preview, FunctionMapping};
+
+const SOURCE: &str = r#"let base = 10;
+
+function add(a, b) {
+    return a + b;
+}
+
+function scale(x) {
+    let y = x * base;
+    if (y > 50) {
+        return y;
+    }
+    return 0;
+}
+log(add(1, 2));
+
+function greet(name) {
+    log("hi " + name);
+}
+"#;
+
+fn assert_ranges_sane(text: &str, mappings: &[FunctionMapping]) {
+    let lines: Vec<&str> = text.lines().collect();
+    for m in mappings {
+        assert!(m.source_start <= m.source_end && m.output_start <= m.output_end, "{m:?}");
+        assert!(m.output_end <= lines.len(), "{m:?} past end of output");
+        assert!(lines[m.output_start - 1].contains(&m.name), "{m:?} does not start at its header: {:?}", lines[m.output_start - 1]);
+        assert_eq!(lines[m.output_end - 1].trim_end(), "}", "{m:?}");
+    }
+    // source order, no overlaps
+    for w in mappings.windows(2) {
+        assert!(w[0].source_end < w[1].source_start, "{:?}", w);
+    }
+    // output ranges never overlap, whatever order the backend emits in
+    let mut by_output: Vec<_> = mappings.iter().collect();
+    by_output.sort_by_key(|m| m.output_start);
+    for w in by_output.windows(2) {
+        assert!(w[0].output_end < w[1].output_start, "{:?}", w);
+    }
+}
+
+#[test]
+fn js_preview_maps_functions_monotonically() {
+    let p = emit_preview(SOURCE, EmitKind::Js).unwrap();
+    let spans: Vec<(&str, usize, usize)> = p.mappings.iter().map(|m| (m.name.as_str(), m.source_start, m.source_end)).collect();
+    assert_eq!(spans, vec![("add", 3, 5), ("scale", 7, 13), ("greet", 16, 18)]);
+    assert_ranges_sane(&p.text, &p.mappings);
+    // JS keeps source order, so output follows source
+    for w in p.mappings.windows(2) {
+        assert!(w[0].output_end < w[1].output_start, "{:?}", w);
+    }
+}
+
+#[test]
+fn ai_preview_maps_functions() {
+    let p = emit_preview(SOURCE, EmitKind::Ai).unwrap();
+    assert!(p.text.starts_with("// aeonmi:1"));
+    assert_eq!(p.mappings.len(), 3);
+    assert_ranges_sane(&p.text, &p.mappings);
+}
+
+#[test]
+fn errors_are_diagnostics_not_partial_output() {
+    let errs = emit_preview("function f( {\n", EmitKind::Js).unwrap_err();
+    assert_eq!(errs.len(), 1);
+    assert_eq!(errs[0].severity, "error");
+    assert_eq!(errs[0].code, Some("AEO-P001"));
+    assert_eq!(errs[0].line, 1);
+}
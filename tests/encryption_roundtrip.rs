@@ -0,0 +1,17 @@
+use aeonmi_project::encryption::{quantum_decrypt, quantum_encrypt, vault_keygen};
+
+#[test]
+fn encrypted_payload_decrypts_with_the_same_keys() {
+    let keys = vault_keygen().unwrap();
+    let payload = quantum_encrypt(&keys, b"launch codes", b"vault:v1").unwrap();
+    assert_eq!(quantum_decrypt(&keys, &payload, b"vault:v1").unwrap(), b"launch codes");
+}
+
+#[test]
+fn wrong_aad_or_keys_are_rejected() {
+    let keys = vault_keygen().unwrap();
+    let payload = quantum_encrypt(&keys, b"secret", b"a").unwrap();
+    assert!(quantum_decrypt(&keys, &payload, b"b").is_err());
+    let other = vault_keygen().unwrap();
+    assert!(quantum_decrypt(&other, &payload, b"a").is_err());
+}
@@ -0,0 +1,95 @@
+use aeonmi_project::commands::run::run_source_captured;
+use aeonmi_project::core::ast::ASTNode;
+use aeonmi_project::core::code_generator::CodeGenerator;
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::parser::Parser;
+use aeonmi_project::core::semantic_analyzer::{SemanticAnalyzer, SemanticDiagnostic, Severity};
+use aeonmi_project::core::types::TypeContext;
+
+const DECLS: &str = "enum Status { Todo, Doing, Done }\nenum Priority { Low, High }\n";
+
+fn parse(source: &str) -> ASTNode {
+    let tokens = Lexer::from_str(source).tokenize().expect("lex");
+    Parser::new(tokens).parse().expect("parse")
+}
+
+fn analyze(source: &str) -> Vec<SemanticDiagnostic> {
+    SemanticAnalyzer::new().analyze_with_spans(&parse(source))
+}
+
+fn with_rule<'a>(diags: &'a [SemanticDiagnostic], rule: &str) -> Vec<&'a SemanticDiagnostic> {
+    diags.iter().filter(|d| d.rule == Some(rule)).collect()
+}
+
+#[test]
+fn non_exhaustive_match_lists_missing_variants() {
+    let src = format!("{DECLS}let s = Status.Todo;\nmatch (s) {{\n    Status.Todo => log(1);\n}}\n");
+    let diags = analyze(&src);
+    let hits = with_rule(&diags, "AEO-S005");
+    assert_eq!(hits.len(), 1, "{diags:?}");
+    let d = hits[0];
+    assert!(matches!(d.severity, Severity::Warning));
+    assert!(d.message.contains("Status.Doing, Status.Done"), "{}", d.message);
+    assert_eq!((d.line, d.column), (4, 1));
+    assert_eq!(d.related[0].line, 1, "related span points at the enum");
+}
+
+#[test]
+fn covered_or_wildcard_match_is_exhaustive() {
+    let all = format!("{DECLS}let s = Status.Todo;\nmatch (s) {{ Status.Todo => log(1); Status.Doing => log(2); Status.Done => log(3); }}\n");
+    let wild = format!("{DECLS}let s = Status.Todo;\nmatch (s) {{ Status.Todo => log(1); _ => log(0); }}\n");
+    assert!(with_rule(&analyze(&all), "AEO-S005").is_empty());
+    assert!(with_rule(&analyze(&wild), "AEO-S005").is_empty());
+}
+
+#[test]
+fn unknown_variant_is_an_error() {
+    let diags = analyze(&format!("{DECLS}let s = Status.Finished;\nlog(s);\n"));
+    let hits = with_rule(&diags, "AEO-S007");
+    assert_eq!(hits.len(), 1, "{diags:?}");
+    assert!(matches!(hits[0].severity, Severity::Error));
+    assert!(hits[0].message.contains("'Finished'"), "{}", hits[0].message);
+}
+
+#[test]
+fn comparing_different_enums_warns() {
+    let src = format!("{DECLS}let s = Status.Done;\nlog(s == Priority.High);\nlog(s == Status.Todo);\n");
+    let diags = analyze(&src);
+    let hits = with_rule(&diags, "AEO-S006");
+    assert_eq!(hits.len(), 1, "{diags:?}");
+    assert!(hits[0].message.contains("always false"), "{}", hits[0].message);
+
+    let mut types = TypeContext::new();
+    types.infer_program(&parse(&src));
+    let msgs: Vec<&str> = types.diags.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(msgs, ["Comparison between different enums 'Status' and 'Priority' is always false"]);
+}
+
+#[test]
+fn variants_compare_by_tag_in_native_vm() {
+    let src = format!("{DECLS}log(Status.Done == Status.Done);\nlog(Status.Todo == Priority.Low);\nlog(Status.Done);\n");
+    let run = run_source_captured(&src).unwrap();
+    assert_eq!(run.output, "true\nfalse\nStatus.Done\n");
+    let err = run_source_captured(&format!("{DECLS}log(Status.Nope);\n")).unwrap_err();
+    assert!(err.contains("enum Status has no variant `Nope`"), "{err}");
+}
+
+#[test]
+fn js_enums_are_frozen_string_tags() {
+    let js = CodeGenerator::new().generate(&parse(DECLS)).unwrap();
+    assert!(js.contains(r#"const Status = Object.freeze({ Todo: "Status.Todo", Doing: "Status.Doing", Done: "Status.Done" });"#), "{js}");
+}
+
+#[cfg(feature = "bytecode")]
+#[test]
+fn bytecode_variants_are_indices() {
+    use aeonmi_project::core::bytecode::BytecodeCompiler;
+    use aeonmi_project::core::vm::OutputBuffer;
+    use aeonmi_project::core::vm_bytecode::VM;
+    let chunk = BytecodeCompiler::new().try_compile(&parse(&format!("{DECLS}log(Status.Done);\nlog(Status.Doing == Status.Doing);\n"))).unwrap();
+    let buf = OutputBuffer::new();
+    let mut vm = VM::new(&chunk);
+    vm.set_output(Box::new(buf.clone()));
+    vm.run();
+    assert_eq!(buf.contents(), "2\ntrue\n");
+}
@@ -0,0 +1,52 @@
+use aeonmi_project::core::error_index::{lookup, EMITTED, INDEX};
+use std::fs;
+use std::process::{Command, Output};
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .args(args)
+        .env("AEONMI_NATIVE", "1")
+        .output()
+        .expect("failed to run aeonmi_project")
+}
+
+#[test]
+fn every_emitted_code_has_index_entry() {
+    for code in EMITTED {
+        let doc = lookup(code).unwrap_or_else(|| panic!("no error-index entry for {code}"));
+        assert!(doc.markdown.starts_with(&format!("# {}", code)), "{code} doc has wrong heading");
+        assert!(doc.markdown.contains("## Example"), "{code} doc lacks an example");
+        assert!(doc.markdown.contains("## Fix"), "{code} doc lacks a fix");
+    }
+    assert_eq!(INDEX.len(), EMITTED.len(), "index has entries for codes nothing emits");
+}
+
+#[test]
+fn explain_error_prints_entry_and_lists_codes() {
+    let out = run(&["explain-error", "aeo-q003"]);
+    assert_eq!(out.status.code(), Some(0));
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("entangled with itself"), "stdout:\n{stdout}");
+
+    let out = run(&["explain-error", "--list"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    for code in EMITTED {
+        assert!(stdout.contains(code), "--list missing {code}:\n{stdout}");
+    }
+
+    let out = run(&["explain-error", "AEO-X999"]);
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn pretty_errors_point_at_explain_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("bad.ai");
+    fs::write(&input, "let x = 1\nlog(x);").unwrap();
+    let out = run(&["--pretty-errors", "run", input.to_str().unwrap(), "--native"]);
+    assert_eq!(out.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("error[AEO-P001]"), "stderr:\n{stderr}");
+    assert!(stderr.contains("run `aeonmi explain-error AEO-P001` for more"), "stderr:\n{stderr}");
+    assert!(stderr.contains("\"code\":\"AEO-P001\""), "stderr:\n{stderr}");
+}
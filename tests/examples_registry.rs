@@ -0,0 +1,127 @@
+use aeonmi_project::commands::run::run_source_captured;
+use aeonmi_project::core::examples::{find, list, EXAMPLES};
+use aeonmi_project::core::imports::resolve;
+use aeonmi_project::core::lexer::{Lexer, LexerOptions};
+use aeonmi_project::core::lowering::lower_ast_to_ir;
+use aeonmi_project::core::parser::Parser;
+use std::collections::HashSet;
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn every_registered_example_compiles_and_runs() {
+    let mut names = HashSet::new();
+    for e in EXAMPLES {
+        assert!(names.insert(e.name), "duplicate example name {}", e.name);
+        assert!(!e.tags.is_empty() && !e.description.is_empty(), "{} needs a description and tags", e.name);
+        let path = Path::new("examples").join(e.file);
+        let on_disk = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(on_disk, e.source, "{} embeds a stale copy", e.file);
+        for (module, source) in e.modules {
+            let on_disk = std::fs::read_to_string(path.parent().unwrap().join(module)).unwrap();
+            assert_eq!(on_disk, *source, "{} embeds a stale copy of {}", e.file, module);
+        }
+
+        let tokens = Lexer::from_str(e.source).tokenize().unwrap_or_else(|err| panic!("{}: {}", e.file, err));
+        let ast = Parser::new(tokens).parse().unwrap_or_else(|err| panic!("{}: {}", e.file, err));
+        let flat = resolve(ast, &path, e.source, &LexerOptions::default()).unwrap_or_else(|err| panic!("{}: {}", e.file, err));
+        assert_eq!(flat.imported.len(), e.modules.len(), "{} imports files it does not list", e.file);
+        lower_ast_to_ir(&flat.program, "main").unwrap_or_else(|err| panic!("{}: {}", e.file, err));
+        if e.modules.is_empty() {
+            run_source_captured(e.source).unwrap_or_else(|err| panic!("{}: {}", e.file, err));
+        }
+    }
+}
+
+#[test]
+fn lookup_and_tag_filter() {
+    assert_eq!(find("Grover").map(|e| e.file), Some("grover_search.ai"));
+    assert!(find("qube").is_none());
+    let quantum: Vec<&str> = list(Some("quantum")).map(|e| e.name).collect();
+    assert!(quantum.contains(&"teleport") && quantum.contains(&"bell"));
+    assert!(!quantum.contains(&"hello"));
+    assert_eq!(list(None).count(), EXAMPLES.len());
+}
+
+#[test]
+fn examples_run_uses_embedded_source() {
+    // run from an empty directory: nothing under examples/ is reachable
+    let dir = tempfile::tempdir().unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .args(["--quiet", "examples", "run", "grover"])
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    assert!(String::from_utf8_lossy(&out.stdout).contains("Grover search result: 1 1"));
+
+    // an example with modules brings them along
+    let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .args(["--quiet", "examples", "run", "focusflow"])
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    assert!(String::from_utf8_lossy(&out.stdout).ends_with("FocusFlow self-check: 0 failed\n"));
+
+    let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .args(["examples", "show", "nope"])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("unknown example 'nope'"));
+}
+
+#[test]
+fn focusflow_matches_golden_output_on_vm_and_js() {
+    let entry = "examples/focusflow/focusflow.ai";
+    let expected = std::fs::read_to_string("examples/focusflow/focusflow.out").unwrap();
+    assert!(expected.ends_with("FocusFlow self-check: 0 failed\n"), "{expected}");
+    let native = Command::new(env!("CARGO_BIN_EXE_aeonmi_project")).args(["--quiet", "run", "--native", entry]).output().unwrap();
+    assert!(native.status.success(), "stderr: {}", String::from_utf8_lossy(&native.stderr));
+    assert_eq!(String::from_utf8_lossy(&native.stdout), expected);
+
+    if !aeonmi_project::core::toolchain::available(aeonmi_project::core::toolchain::Tool::Node) {
+        eprintln!("node not found; skipping JS run of focusflow");
+        return;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("focusflow.js");
+    let emit = Command::new(env!("CARGO_BIN_EXE_aeonmi_project")).args(["emit", entry, "--out"]).arg(&file).output().unwrap();
+    assert!(emit.status.success(), "stderr: {}", String::from_utf8_lossy(&emit.stderr));
+    let out = Command::new("node").arg(&file).output().unwrap();
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout), expected);
+}
+
+#[test]
+fn focusflow_type_checks_and_exports_to_every_format() {
+    let entry = "examples/focusflow/focusflow.ai";
+    let check = Command::new(env!("CARGO_BIN_EXE_aeonmi_project")).args(["check", entry]).output().unwrap();
+    assert!(check.status.success(), "stdout: {}\nstderr: {}", String::from_utf8_lossy(&check.stdout), String::from_utf8_lossy(&check.stderr));
+    assert!(String::from_utf8_lossy(&check.stdout).contains("focusflow.ai: 0 type errors"));
+
+    let expected = std::fs::read_to_string("examples/focusflow/focusflow.out").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    for format in ["json", "js", "py", "html"] {
+        let file = dir.path().join(format!("focusflow.{format}"));
+        let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project")).args(["export", entry, "--format", format, "--out"]).arg(&file).output().unwrap();
+        assert!(out.status.success(), "{format}: {}", String::from_utf8_lossy(&out.stderr));
+    }
+    // nothing quantum in it, which qasm reports rather than writing an empty circuit
+    let qasm = Command::new(env!("CARGO_BIN_EXE_aeonmi_project")).args(["export", entry, "--format", "qasm", "--out", "-"]).output().unwrap();
+    assert_eq!(qasm.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&qasm.stderr).contains("no quantum operations"));
+
+    // the exported programs carry utils.ai with them
+    use aeonmi_project::core::toolchain::{available, Tool};
+    for (tool, format) in [(Tool::Node, "js"), (Tool::Python, "py")] {
+        if !available(tool) {
+            eprintln!("{} not found; skipping the {format} export run", tool.name());
+            continue;
+        }
+        let out = Command::new(tool.exe()).arg(dir.path().join(format!("focusflow.{format}"))).output().unwrap();
+        assert!(out.status.success(), "{format}: {}", String::from_utf8_lossy(&out.stderr));
+        assert_eq!(String::from_utf8_lossy(&out.stdout), expected, "{format}");
+    }
+}
@@ -23,4 +23,5 @@ fn exec_ai_keeps_temp_with_flag() {
     assert!(status.success());
     assert!(Path::new("__exec_tmp.js").exists(), "temp js should remain when --keep-temp");
     let _ = fs::remove_file("__exec_tmp.js");
+    let _ = fs::remove_file("__exec_tmp.js.map");
 }
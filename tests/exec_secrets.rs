@@ -0,0 +1,137 @@
+use aeonmi_project::commands::secrets::{select, SecretFlags, SecretSpec};
+use aeonmi_project::vault::{DomainQuantumVault, VaultDomainProfile};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+const TOKEN: &str = "s3cr3t-registrar-token";
+
+/// A vault at `dir/vault.json` holding one string secret under `example.com`.
+fn seed_vault(dir: &Path) -> std::path::PathBuf {
+    let path = dir.join("vault.json");
+    let mut vault = DomainQuantumVault::open_at(&path).expect("create vault");
+    let profile = VaultDomainProfile {
+        domain: "example.com".into(),
+        registrar: "test".into(),
+        expiration: "2030-01-01".into(),
+        auto_renew: false,
+        dnssec_enabled: false,
+        registrar_lock: false,
+        blockchain_registry: None,
+        metadata: serde_json::json!({}),
+    };
+    vault.register_domain(profile, serde_json::json!(TOKEN)).expect("register");
+    path
+}
+
+/// Fake child: prints `REG_TOKEN` from its own environment.
+fn write_echo_child(dir: &Path) -> std::path::PathBuf {
+    let file = dir.join("echo_env.rs");
+    fs::write(&file, "fn main() { println!(\"{}\", std::env::var(\"REG_TOKEN\").unwrap_or_default()); }\n").unwrap();
+    file
+}
+
+fn exec(dir: &Path, vault: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .current_dir(dir)
+        .env("AEONMI_VAULT_PATH", vault)
+        .env_remove("REG_TOKEN")
+        .arg("exec")
+        .args(args)
+        .output()
+        .expect("spawn exec")
+}
+
+#[test]
+fn child_sees_secret_and_injection_is_audited() {
+    let dir = tempfile::tempdir().unwrap();
+    let vault = seed_vault(dir.path());
+    let child = write_echo_child(dir.path());
+    let out = exec(dir.path(), &vault, &[child.to_str().unwrap(), "--with-secrets", "REG_TOKEN=example.com"]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), TOKEN);
+    assert!(!String::from_utf8_lossy(&out.stderr).contains(TOKEN));
+
+    let state: serde_json::Value = serde_json::from_str(&fs::read_to_string(&vault).unwrap()).unwrap();
+    let audits = state["audits"].as_array().unwrap();
+    let inject = audits.iter().find(|a| a["category"] == "secret_inject").expect("audit entry");
+    assert_eq!(inject["detail"]["var"], "REG_TOKEN");
+    assert_eq!(inject["detail"]["entry"], "example.com");
+    assert!(!fs::read_to_string(&vault).unwrap().contains(TOKEN));
+}
+
+#[test]
+fn parent_environment_stays_clean() {
+    // A native run executes inside the aeonmi process itself, so env() reads the parent.
+    let dir = tempfile::tempdir().unwrap();
+    let vault = seed_vault(dir.path());
+    let file = dir.path().join("parent.ai");
+    fs::write(&file, "log(env(\"REG_TOKEN\"));\n").unwrap();
+    let out = exec(dir.path(), &vault, &[file.to_str().unwrap(), "--native", "--with-secrets", "REG_TOKEN=example.com"]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), "null");
+    assert!(std::env::var("REG_TOKEN").is_err());
+}
+
+#[test]
+fn manifest_mapping_is_used_without_flag() {
+    let dir = tempfile::tempdir().unwrap();
+    let vault = seed_vault(dir.path());
+    let child = write_echo_child(dir.path());
+    fs::write(dir.path().join("Aeonmi.toml"), "[secrets]\nREG_TOKEN = \"example.com\"\n").unwrap();
+    let out = exec(dir.path(), &vault, &[child.to_str().unwrap()]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), TOKEN);
+}
+
+#[test]
+fn sandbox_refuses_secrets() {
+    let dir = tempfile::tempdir().unwrap();
+    let vault = seed_vault(dir.path());
+    let child = write_echo_child(dir.path());
+    let out = exec(dir.path(), &vault, &[child.to_str().unwrap(), "--sandbox", "--with-secrets", "REG_TOKEN=example.com"]);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("refusing to inject secrets under --sandbox"));
+    assert!(!String::from_utf8_lossy(&out.stdout).contains(TOKEN));
+}
+
+#[test]
+fn unknown_entry_is_a_usage_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let vault = seed_vault(dir.path());
+    let child = write_echo_child(dir.path());
+    let out = exec(dir.path(), &vault, &[child.to_str().unwrap(), "--with-secrets", "missing.org"]);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("secret 'missing.org' for MISSING_ORG"));
+}
+
+#[test]
+fn selection_forms() {
+    let mut manifest = BTreeMap::new();
+    manifest.insert("API_KEY".to_string(), "api.example.com".to_string());
+    let specs = select("API_KEY, OTHER=b.org,c-d.net", &manifest).unwrap();
+    assert_eq!(
+        specs,
+        vec![
+            SecretSpec { var: "API_KEY".into(), entry: "api.example.com".into() },
+            SecretSpec { var: "OTHER".into(), entry: "b.org".into() },
+            SecretSpec { var: "C_D_NET".into(), entry: "c-d.net".into() },
+        ]
+    );
+    assert!(select("1BAD=x", &manifest).is_err());
+    assert!(select(" , ", &manifest).is_err());
+}
+
+#[test]
+fn flags_are_pulled_from_passthrough() {
+    let mut args: Vec<String> = ["a", "--with-secrets=X=y", "b", "--sandbox"].iter().map(|s| s.to_string()).collect();
+    let flags = SecretFlags::extract(&mut args, true);
+    assert_eq!(flags.with_secrets.as_deref(), Some("X=y"));
+    assert!(flags.sandbox);
+    assert_eq!(args, vec!["a", "b"]);
+
+    let mut args: Vec<String> = ["--sandbox"].iter().map(|s| s.to_string()).collect();
+    assert!(!SecretFlags::extract(&mut args, false).sandbox);
+    assert_eq!(args, vec!["--sandbox"]);
+}
@@ -13,6 +13,7 @@ fn exec_ai_compiles_and_runs() {
     assert!(status.success(), "exec ai should succeed");
     assert!(Path::new("__exec_tmp.js").exists(), "temp compiled js should exist");
     let _ = fs::remove_file("__exec_tmp.js"); // Clean up
+    let _ = fs::remove_file("__exec_tmp.js.map");
 }
 
 #[test]
@@ -35,20 +36,24 @@ fn native_run_env() {
     use std::process::Command;
     let output = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
         .env("AEONMI_NATIVE", "1")
+        .env("AEONMI_DEBUG", "1")
         .arg("run")
         .arg("examples/hello.ai")
         .output()
         .expect("failed to run native aeonmi");
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    assert!(stdout.contains("native: executing"), "stdout missing native execution marker: {stdout}");
+    // the marker is a debug log, on stderr so stdout stays pipeable
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("native: executing"), "stderr missing native execution marker: {stderr}");
 }
 
 #[test]
 fn shard_native_run_command() {
     use std::process::{Command, Stdio};
     let mut child = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .env("AEONMI_DEBUG", "1")
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
         .spawn()
         .expect("spawn shard");
     {
@@ -58,6 +63,6 @@ fn shard_native_run_command() {
         writeln!(stdin, "exit").ok();
     }
     let out = child.wait_with_output().expect("wait shard");
-    let stdout = String::from_utf8_lossy(&out.stdout);
-    assert!(stdout.contains("native: executing"), "stdout missing native execution marker in shard: {stdout}");
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("native: executing"), "stderr missing native execution marker in shard: {stderr}");
 }
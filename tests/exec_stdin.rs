@@ -0,0 +1,63 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn bin() -> &'static str {
+    env!("CARGO_BIN_EXE_aeonmi_project")
+}
+
+/// Run `args` with `input` piped to stdin; returns stdout.
+fn pipe(args: &[&str], dir: &std::path::Path, input: &str) -> String {
+    let mut child = Command::new(bin())
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("spawn aeonmi");
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let out = child.wait_with_output().expect("wait aeonmi");
+    assert!(out.status.success(), "exit {:?}; stderr=\n{}", out.status, String::from_utf8_lossy(&out.stderr));
+    String::from_utf8(out.stdout).unwrap()
+}
+
+const NUMBER_LINES: &str = r#"
+let n = 1;
+let line = read_line();
+while (line) {
+    log(n + ": " + line);
+    n = n + 1;
+    line = read_line();
+}
+log("lines: " + (n - 1));
+"#;
+
+#[test]
+fn exec_native_reads_piped_lines_in_order() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("number.ai"), NUMBER_LINES).unwrap();
+    let out = pipe(&["exec", "number.ai", "--native"], dir.path(), "alpha\r\nbeta\ngamma");
+    assert_eq!(out, "1: alpha\n2: beta\n3: gamma\nlines: 3\n");
+}
+
+#[test]
+fn run_output_feeds_next_stage() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("number.ai"), NUMBER_LINES).unwrap();
+    std::fs::write(dir.path().join("shout.ai"), "let all = read_all();\nlog(\"[\" + all + \"]\");\n").unwrap();
+    let first = pipe(&["run", "--native", "number.ai"], dir.path(), "x\ny\n");
+    let second = pipe(&["exec", "shout.ai", "--native"], dir.path(), &first);
+    assert_eq!(second, "[1: x\n2: y\nlines: 2\n]\n");
+}
+
+#[test]
+fn exec_js_reads_piped_lines() {
+    if Command::new("node").arg("--version").output().is_err() {
+        eprintln!("(skip) node not available");
+        return;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("number.ai"), NUMBER_LINES).unwrap();
+    let out = pipe(&["exec", "number.ai"], dir.path(), "alpha\nbeta\n");
+    assert!(out.ends_with("1: alpha\n2: beta\nlines: 2\n"), "unexpected output: {out}");
+}
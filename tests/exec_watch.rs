@@ -12,3 +12,72 @@ fn exec_watch_once_env_breaks_loop() {
     let status = cmd.status().unwrap();
     assert!(status.success());
 }
+
+#[test]
+fn rust_mod_scan_follows_nested_and_path_modules() {
+    use aeonmi_project::commands::watch::rust_mod_deps;
+    let dir = tempfile::tempdir().unwrap();
+    let write = |rel: &str, body: &str| {
+        let p = dir.path().join(rel);
+        fs::create_dir_all(p.parent().unwrap()).unwrap();
+        fs::write(p, body).unwrap();
+    };
+    write(
+        "main.rs",
+        "mod util;\npub(crate) mod net;\n// mod commented;\nmod inline { fn f() {} }\n#[path = \"extra/custom.rs\"]\nmod custom;\nmod missing;\nfn main() {}\n",
+    );
+    write("util.rs", "pub mod strings;\n");
+    write("util/strings.rs", "pub fn s() {}\n");
+    write("net/mod.rs", "mod tcp;\nmod util;\n");
+    write("net/tcp.rs", "");
+    write("extra/custom.rs", "");
+    write("commented.rs", "");
+    let deps: Vec<String> = rust_mod_deps(&dir.path().join("main.rs"))
+        .iter()
+        .map(|p| p.strip_prefix(dir.path()).unwrap().to_string_lossy().replace('\\', "/"))
+        .collect();
+    assert_eq!(deps, ["extra/custom.rs", "net/mod.rs", "net/tcp.rs", "util/strings.rs", "util.rs"]);
+}
+
+#[test]
+fn exec_targets_by_extension() {
+    use aeonmi_project::commands::watch::exec_watch_targets;
+    use std::path::{Path, PathBuf};
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("main.rs"), "mod a;\n").unwrap();
+    fs::write(dir.path().join("a.rs"), "").unwrap();
+    assert_eq!(exec_watch_targets(&dir.path().join("main.rs"), None), [dir.path().join("main.rs"), dir.path().join("a.rs")]);
+    let js = Path::new("__exec_tmp.js");
+    assert_eq!(exec_watch_targets(Path::new("p.ai"), Some(js)), [PathBuf::from("p.ai"), js.to_path_buf()]);
+    assert_eq!(exec_watch_targets(Path::new("p.py"), Some(js)), [PathBuf::from("p.py")]);
+}
+
+#[test]
+fn ai_targets_follow_the_import_closure() {
+    use aeonmi_project::commands::watch::ai_watch_targets;
+    let dir = tempfile::tempdir().unwrap();
+    let main = dir.path().join("main.ai");
+    fs::create_dir(dir.path().join("lib")).unwrap();
+    fs::write(dir.path().join("lib/a.ai"), "import \"b.ai\";\nlet a = b;\n").unwrap();
+    fs::write(dir.path().join("lib/b.ai"), "let b = 1;\n").unwrap();
+    fs::write(&main, "import \"lib/a.ai\";\nlog(a);\n").unwrap();
+    assert_eq!(ai_watch_targets(&main), [main.clone(), dir.path().join("lib/a.ai"), dir.path().join("lib/b.ai")]);
+
+    // the closure is read again on every call: a new import is picked up, and a missing
+    // one is watched so that creating it triggers a run
+    fs::write(&main, "import \"lib/a.ai\";\nimport \"c.ai\";\nlog(a);\n").unwrap();
+    assert_eq!(ai_watch_targets(&main).last(), Some(&dir.path().join("c.ai")));
+    fs::write(dir.path().join("c.ai"), "let c = 3;\n").unwrap();
+    assert_eq!(ai_watch_targets(&main).len(), 4);
+}
+
+#[test]
+fn status_line_reports_run_and_exit_code() {
+    use aeonmi_project::commands::exit_codes::{fail, RUNTIME};
+    use aeonmi_project::commands::watch::status_line;
+    use aeonmi_project::core::style::Style;
+    use std::time::Duration;
+    assert_eq!(status_line(1, Duration::from_millis(42), &Ok(()), &Style::PLAIN), "[watch] run 1 - 42ms - ok");
+    let err = Err(fail(RUNTIME, "boom"));
+    assert_eq!(status_line(3, Duration::from_millis(7), &err, &Style::PLAIN), "[watch] run 3 - 7ms - exit 4");
+}
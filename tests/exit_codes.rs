@@ -0,0 +1,83 @@
+use std::fs;
+use std::process::{Command, Output};
+
+fn bin() -> String {
+    env!("CARGO_BIN_EXE_aeonmi_project").to_string()
+}
+
+fn run(args: &[&str]) -> Output {
+    Command::new(bin())
+        .args(args)
+        .env("AEONMI_NATIVE", "1")
+        .output()
+        .expect("failed to run aeonmi_project")
+}
+
+fn write(dir: &tempfile::TempDir, name: &str, src: &str) -> String {
+    let p = dir.path().join(name);
+    fs::write(&p, src).unwrap();
+    p.to_str().unwrap().to_string()
+}
+
+#[test]
+fn ok_run_exits_zero() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = write(&dir, "ok.ai", "let x = 1; log(x);");
+    let out = run(&["run", &input, "--native"]);
+    assert_eq!(out.status.code(), Some(0));
+}
+
+#[test]
+fn unsupported_emit_is_usage_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = write(&dir, "ok.ai", "let x = 1;");
+    let out = run(&["--emit", "wasm", &input]);
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn parse_error_is_compile_diagnostic() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = write(&dir, "bad.ai", "let x = 1\nlog(x);");
+    let js = dir.path().join("out.js");
+    let out = run(&["emit", &input, "-o", js.to_str().unwrap()]);
+    assert_eq!(out.status.code(), Some(3));
+    let out = run(&["run", &input, "--native"]);
+    assert_eq!(out.status.code(), Some(3));
+    let out = run(&["exec", &input]);
+    assert_eq!(out.status.code(), Some(3));
+}
+
+#[test]
+fn runtime_error_exits_four() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = write(&dir, "boom.ai", "missing_fn(1);");
+    let out = run(&["run", &input, "--native"]);
+    assert_eq!(out.status.code(), Some(4));
+    let out = run(&["exec", &input]);
+    assert_eq!(out.status.code(), Some(4));
+}
+
+#[test]
+fn missing_input_is_io_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("nope.ai");
+    let missing = missing.to_str().unwrap();
+    let js = dir.path().join("out.js");
+    let out = run(&["emit", missing, "-o", js.to_str().unwrap()]);
+    assert_eq!(out.status.code(), Some(5));
+    let out = run(&["run", missing, "--native"]);
+    assert_eq!(out.status.code(), Some(5));
+}
+
+#[test]
+fn quiet_suppresses_human_output_but_keeps_diag_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = write(&dir, "bad.ai", "let x = 1\nlog(x);");
+    let out = run(&["--quiet", "--diag-json", "run", &input, "--native"]);
+    assert_eq!(out.status.code(), Some(3));
+    assert!(out.stdout.is_empty(), "stdout: {}", String::from_utf8_lossy(&out.stdout));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.lines().all(|l| l.starts_with("@@DIAG:")), "stderr:\n{stderr}");
+    assert!(stderr.contains("\"severity\":\"error\""), "stderr:\n{stderr}");
+}
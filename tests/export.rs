@@ -0,0 +1,74 @@
+//! Golden tests for `aeonmi export`: each `tests/export/<name>.<format>` is the expected
+//! export of `tests/export/<name>.ai`.
+use std::fs;
+use std::process::{Command, Output};
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .args(args)
+        .output()
+        .expect("failed to run aeonmi_project")
+}
+
+fn assert_golden(name: &str, format: &str) {
+    let dir = tempfile::tempdir().unwrap();
+    let out_path = dir.path().join(format!("{name}.{format}"));
+    let input = format!("tests/export/{name}.ai");
+    let out = run(&["export", &input, "--format", format, "--out", out_path.to_str().unwrap()]);
+    assert!(out.status.success(), "{format}: {}", String::from_utf8_lossy(&out.stderr));
+    let expected = fs::read_to_string(format!("tests/export/{name}.{format}")).unwrap();
+    assert_eq!(fs::read_to_string(&out_path).unwrap(), expected, "{name}.{format}");
+}
+
+#[test]
+fn json_dumps_the_ast() {
+    assert_golden("tiny", "json");
+}
+
+#[test]
+fn js_uses_the_js_emitter() {
+    assert_golden("program", "js");
+}
+
+#[test]
+fn py_uses_the_python_emitter() {
+    assert_golden("program", "py");
+}
+
+#[test]
+fn qasm_uses_the_circuit_exporter() {
+    assert_golden("bell", "qasm");
+}
+
+#[test]
+fn html_is_highlighted_and_escaped() {
+    assert_golden("tiny", "html");
+}
+
+#[test]
+fn default_output_sits_next_to_the_source() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("bell.ai");
+    fs::copy("tests/export/bell.ai", &input).unwrap();
+    let out = run(&["export", input.to_str().unwrap(), "--format", "qasm"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let expected = fs::read_to_string("tests/export/bell.qasm").unwrap();
+    assert_eq!(fs::read_to_string(dir.path().join("bell.qasm")).unwrap(), expected);
+}
+
+#[test]
+fn unknown_format_lists_supported_ones() {
+    let out = run(&["export", "tests/export/tiny.ai", "--format", "wasm"]);
+    assert_eq!(out.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("unknown format 'wasm' (supported: json, js, py, qasm, html)"), "{stderr}");
+}
+
+#[test]
+fn qasm_needs_quantum_operations() {
+    let dir = tempfile::tempdir().unwrap();
+    let out_path = dir.path().join("tiny.qasm");
+    let out = run(&["export", "tests/export/tiny.ai", "--format", "qasm", "--out", out_path.to_str().unwrap()]);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(!out_path.exists());
+}
@@ -0,0 +1,4 @@
+qreg q[2];
+superpose q[0];// line3
+entangle q[0],q[1];// line4
+measure q[0];// line5
@@ -0,0 +1,9 @@
+function add(a, b) {
+  return (a + b);
+}
+let total = add(1, 2);
+if ((total > 2)) {
+  console.log("a<b> & c");
+} else {
+  total = 0;
+}
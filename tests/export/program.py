@@ -0,0 +1,79 @@
+def __aeonmi_str(v):
+    if v is True:
+        return "true"
+    if v is False:
+        return "false"
+    if v is None:
+        return "null"
+    if isinstance(v, float) and v.is_integer():
+        return str(int(v))
+    return str(v)
+
+
+def __aeonmi_add(a, b):
+    if isinstance(a, str) or isinstance(b, str):
+        return __aeonmi_str(a) + __aeonmi_str(b)
+    return a + b
+
+
+def __aeonmi_mod(a, b):
+    if b == 0:
+        raise ZeroDivisionError("modulo by zero")
+    r = abs(a) % abs(b)
+    return -r if a < 0 else r
+
+
+def __aeonmi_member(v, name):
+    if isinstance(v, dict):
+        return v[name]
+    return getattr(v, name)
+
+
+def __aeonmi_len(v):
+    if v is None:
+        return 0
+    return len(v)
+
+
+def __aeonmi_push(items, value):
+    if not isinstance(items, list):
+        raise TypeError("push expects an array, got " + __aeonmi_str(items))
+    items.append(value)
+    return len(items)
+
+
+def __aeonmi_pop(items, name):
+    if not isinstance(items, list):
+        raise TypeError("pop expects an array, got " + __aeonmi_str(items))
+    if not items:
+        raise IndexError("pop: `" + name + "` is empty")
+    return items.pop()
+
+
+def __aeonmi_slice(items, start, end):
+    if items is None:
+        items = []
+    if isinstance(items, str):
+        items = list(items)
+    if not isinstance(items, list):
+        raise TypeError("slice expects an array, got " + __aeonmi_str(items))
+    lo = min(max(int(start), 0), len(items))
+    return items[lo:max(min(max(int(end), 0), len(items)), lo)]
+
+
+def __aeonmi_copy(v):
+    if isinstance(v, list):
+        return [__aeonmi_copy(x) for x in v]
+    if isinstance(v, dict):
+        return {k: __aeonmi_copy(x) for k, x in v.items()}
+    return v
+
+
+def add(a, b):
+    return __aeonmi_add(a, b)
+
+total = add(1, 2)
+if (total > 2):
+    print(__aeonmi_str("a<b> & c"))
+else:
+    total = 0
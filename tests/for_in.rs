@@ -0,0 +1,117 @@
+use aeonmi_project::commands::run::run_source_captured;
+use aeonmi_project::core::ast::ASTNode;
+use aeonmi_project::core::code_generator::CodeGenerator;
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::parser::Parser;
+use aeonmi_project::core::semantic_analyzer::{SemanticAnalyzer, Severity};
+use aeonmi_project::core::toolchain::{self, Tool};
+use aeonmi_project::core::types::TypeContext;
+use std::fs;
+use std::process::Command;
+
+const PROGRAM: &str = r#"for ch in "añ😀b" {
+    log(ch);
+}
+for i, ch in "xyz" {
+    if (ch == "y") {
+        continue;
+    }
+    log(i + "=" + ch);
+}
+qreg r[4];
+let seen = 0;
+for i, q in r {
+    if (i == 3) {
+        break;
+    }
+    seen = seen + 1;
+}
+log(seen);
+for a in "ab" {
+    for b in "12" {
+        log(a + b);
+    }
+}
+log(len("😀!"));
+"#;
+
+const EXPECTED: &str = "a\nñ\n😀\nb\n0=x\n2=z\n3\na1\na2\nb1\nb2\n2\n";
+
+fn parse(source: &str) -> ASTNode {
+    let tokens = Lexer::from_str(source).tokenize().expect("lex");
+    Parser::new(tokens).parse().expect("parse")
+}
+
+fn node(js: &str, dir: &std::path::Path) -> String {
+    let file = dir.join("prog.js");
+    fs::write(&file, js).unwrap();
+    let out = Command::new("node").arg(&file).output().unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    String::from_utf8_lossy(&out.stdout).into_owned()
+}
+
+#[test]
+fn strings_and_arrays_iterate_in_the_same_order_natively_and_in_js() {
+    let run = run_source_captured(PROGRAM).unwrap();
+    assert_eq!(run.output, EXPECTED);
+    if !toolchain::available(Tool::Node) {
+        eprintln!("node not found; skipping JS side");
+        return;
+    }
+    let js = CodeGenerator::new().generate(&parse(PROGRAM)).unwrap();
+    assert!(js.contains("for (let ch of __aeonmi_iter(\"añ😀b\"))"), "{js}");
+    assert_eq!(node(&js, tempfile::tempdir().unwrap().path()), EXPECTED);
+}
+
+#[test]
+fn maps_iterate_by_sorted_key_natively_and_in_js() {
+    // Both shots outcomes show up in 200 shots of a superposed qubit.
+    let src = "function shot() {\n    qubit q;\n    superpose(q);\n    measure(q);\n}\nlet m = run_shots(200, shot);\nfor k in m {\n    log(k);\n}\nlet total = 0;\nfor k, n in m {\n    total = total + n;\n}\nlog(total);\n";
+    let run = run_source_captured(src).unwrap();
+    assert_eq!(run.output, "0\n1\n200\n");
+
+    if !toolchain::available(Tool::Node) {
+        return;
+    }
+    // JS has no shot runner; drive the emitted helpers with an object instead.
+    let js = CodeGenerator::new().generate(&parse("for k, v in \"\" { log(k); }\n")).unwrap();
+    let probe = format!("{js}\nconst m = {{ b: 1, a: 2, \"10\": 3 }};\nconsole.log(JSON.stringify(__aeonmi_iter(m)), JSON.stringify(__aeonmi_entries(m)));\n");
+    let out = node(&probe, tempfile::tempdir().unwrap().path());
+    assert_eq!(out.trim(), r#"["10","a","b"] [["10",3],["a",2],["b",1]]"#);
+}
+
+#[test]
+fn break_and_continue_are_rejected_outside_loops() {
+    for src in ["break;\n", "function f() { continue; }\n", "while (true) { function g() { break; } }\n"] {
+        let tokens = Lexer::from_str(src).tokenize().unwrap();
+        let err = Parser::new(tokens).parse().unwrap_err();
+        assert!(err.message.contains("outside of a loop"), "{src}: {}", err.message);
+    }
+    assert!(Parser::new(Lexer::from_str("for x in \"a\" { if (x == \"a\") { break; } }\n").tokenize().unwrap()).parse().is_ok());
+}
+
+#[test]
+fn iterating_a_known_scalar_is_a_semantic_error() {
+    let diags = SemanticAnalyzer::new().analyze_with_spans(&parse("let n = 3;\nfor x in n {\n    log(x);\n}\nfor ch in \"ok\" {\n    log(ch);\n}\n"));
+    let hits: Vec<_> = diags.iter().filter(|d| d.rule == Some("AEO-S008")).collect();
+    assert_eq!(hits.len(), 1, "{diags:?}");
+    assert!(matches!(hits[0].severity, Severity::Error));
+    assert_eq!((hits[0].line, hits[0].column), (2, 1));
+    assert!(hits[0].message.contains("a number"), "{}", hits[0].message);
+}
+
+#[test]
+fn loop_variables_get_element_types() {
+    let mut ctx = TypeContext::new();
+    ctx.infer_program(&parse("for i, ch in \"abc\" {\n    let bad = ch - i;\n}\nfor k, n in counts() {\n    let ok = n + 1;\n}\n"));
+    assert_eq!(ctx.diags.len(), 1, "{:?}", ctx.diags);
+    assert!(ctx.diags[0].message.contains("Arithmetic on non-number"), "{:?}", ctx.diags);
+}
+
+#[cfg(feature = "bytecode")]
+#[test]
+fn bytecode_reports_for_in_as_unsupported() {
+    use aeonmi_project::core::bytecode::BytecodeCompiler;
+    let errs = BytecodeCompiler::new().try_compile(&parse("for ch in \"ab\" {\n    log(ch);\n}\n")).unwrap_err();
+    assert!(errs[0].to_string().starts_with("1:1: unsupported construct in bytecode: for-in loop"), "{errs:?}");
+}
@@ -0,0 +1,75 @@
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+use std::time::{Duration, SystemTime};
+
+fn format(dir: &Path, extra: &[&str]) -> Output {
+    std::process::Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .current_dir(dir)
+        .arg("format")
+        .args(extra)
+        .arg(".")
+        .output()
+        .unwrap()
+}
+
+fn summary(out: &Output) -> String {
+    String::from_utf8_lossy(&out.stderr).lines().last().unwrap_or_default().to_string()
+}
+
+fn mtime(p: &Path) -> SystemTime {
+    fs::metadata(p).unwrap().modified().unwrap()
+}
+
+#[test]
+fn unchanged_files_keep_their_mtime() {
+    let dir = tempfile::tempdir().unwrap();
+    let clean = dir.path().join("clean.ai");
+    let messy = dir.path().join("messy.ai");
+    fs::write(&clean, aeonmi_project::core::formatter::format_ai("let x = 1;\n")).unwrap();
+    fs::write(&messy, "let   y=2;\n").unwrap();
+    let old = SystemTime::now() - Duration::from_secs(3600);
+    fs::File::options().write(true).open(&clean).unwrap().set_modified(old).unwrap();
+
+    let out = format(dir.path(), &[]);
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), "formatted messy.ai");
+    assert_eq!(summary(&out), "1 formatted, 1 unchanged, 0 cached, 0 skipped");
+    assert_eq!(mtime(&clean), old);
+    assert!(dir.path().join(".aeonmi/fmt-cache.json").is_file());
+}
+
+#[test]
+fn cache_skips_known_files_until_the_source_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("a.ai");
+    fs::write(&file, "let   y=2;\n").unwrap();
+    assert_eq!(format(dir.path(), &[]).status.code(), Some(1));
+
+    let again = format(dir.path(), &[]);
+    assert_eq!(again.status.code(), Some(0));
+    assert!(again.stdout.is_empty());
+    assert_eq!(summary(&again), "0 formatted, 0 unchanged, 1 cached, 0 skipped");
+
+    fs::write(&file, "let   z=3;\n").unwrap();
+    let check = format(dir.path(), &["--check"]);
+    assert_eq!(check.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&check.stdout).trim(), "a.ai");
+    assert_eq!(summary(&check), "1 would format, 0 unchanged, 0 cached, 0 skipped");
+}
+
+#[test]
+fn cache_from_another_formatter_version_is_ignored() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = "let   y=2;\n";
+    fs::write(dir.path().join("a.ai"), source).unwrap();
+    fs::create_dir(dir.path().join(".aeonmi")).unwrap();
+    // A stale entry claiming the unformatted source is canonical.
+    let hash = format!("{:x}", <sha1::Sha1 as sha1::Digest>::digest(source.as_bytes()));
+    let stale = serde_json::json!({ "version": 0, "files": { "a.ai": hash } });
+    fs::write(dir.path().join(".aeonmi/fmt-cache.json"), stale.to_string()).unwrap();
+
+    let out = format(dir.path(), &["--check"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(summary(&out), "1 would format, 0 unchanged, 0 cached, 0 skipped");
+}
@@ -0,0 +1,50 @@
+use aeonmi_project::config::EditorSettings;
+use aeonmi_project::core::formatter::{format_ai, remap_cursor};
+
+/// Cursor as (line, col) of the first occurrence of `marker` in `text`.
+fn at(text: &str, marker: &str) -> (usize, usize) {
+    text.lines().enumerate().find_map(|(i, l)| l.find(marker).map(|c| (i, c))).unwrap()
+}
+
+#[test]
+fn cursor_follows_code_when_lines_above_split() {
+    let before = "let a = 1; let b = 2;\nlog(a + b);\n";
+    let after = format_ai(before);
+    assert_eq!(after, "let a = 1;\nlet b = 2;\nlog(a + b);\n");
+    // on `b` in `a + b`, one line further down after the split
+    assert_eq!(remap_cursor(before, &after, (1, 8)), (2, 8));
+    // end of the split line stays after its `;`
+    assert_eq!(remap_cursor(before, &after, (0, 21)), (1, 10));
+}
+
+#[test]
+fn cursor_follows_code_when_lines_above_merge() {
+    let before = "let a = 1;\n\n\n\nlet b = 2;\nlog(b);\n";
+    let after = format_ai(before);
+    assert_eq!(after, "let a = 1;\nlet b = 2;\nlog(b);\n");
+    assert_eq!(remap_cursor(before, &after, at(before, "b)")), at(&after, "b)"));
+    // a cursor on a collapsed blank line moves to the next code
+    assert_eq!(remap_cursor(before, &after, (3, 0)), (1, 0));
+}
+
+#[test]
+fn cursor_inside_reindented_block_keeps_its_token() {
+    let before = "function f(x){\nlet y=x*2;\n      return y;}\n";
+    let after = format_ai(before);
+    let (line, col) = remap_cursor(before, &after, at(before, "y;"));
+    assert_eq!(&after.lines().nth(line).unwrap()[col..], "y;");
+    // a cursor glued to the end of a token stays after it
+    let (line, col) = remap_cursor(before, &after, (1, 6));
+    assert!(after.lines().nth(line).unwrap()[..col].ends_with('='), "{after}");
+    // formatting is idempotent, so remapping through a no-op is the identity
+    assert_eq!(remap_cursor(&after, &after, (2, 10)), (2, 10));
+    // a cursor inside indentation snaps to the start of the code on that line
+    assert_eq!(remap_cursor(&after, &after, (2, 4)), (2, 8));
+}
+
+#[test]
+fn format_on_save_is_opt_in() {
+    assert!(!EditorSettings::from_toml_str("").unwrap().format_on_save);
+    let s = EditorSettings::from_toml_str("[[rules]]\nchord = \"->\"\nglyph = \"→\"\n\n[editor]\nformat_on_save = true\n").unwrap();
+    assert!(s.format_on_save);
+}
@@ -0,0 +1,136 @@
+//! `[format]` options: each one's effect as a snapshot, and idempotency under all of them.
+
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+use aeonmi_project::config::FormatSettings;
+use aeonmi_project::core::formatter::{format_ai, format_ai_with, FormatOptions, GlyphMode};
+
+const SAMPLE: &str = r#"// options.ai
+function describe(first_argument, second_argument, third_argument) {
+    if (first_argument <= second_argument) {
+        return combine(first_argument, second_argument, "a string, with commas", third_argument);
+    }
+    step(first_argument)
+    return 0;
+}
+// a comment (with an open bracket, and commas, left alone when wrapping
+let limit = 10;
+while (limit >= 0) {
+    limit = limit - 1
+}
+let same = describe(1, 2, 3) != 4;
+log(same);
+"#;
+
+fn with(f: impl FnOnce(&mut FormatOptions)) -> FormatOptions {
+    let mut opt = FormatOptions::default();
+    f(&mut opt);
+    opt
+}
+
+#[test]
+fn each_option_changes_only_its_own_aspect() {
+    insta::assert_snapshot!("indent_width_2", format_ai_with(SAMPLE, &with(|o| o.indent_width = 2)));
+    insta::assert_snapshot!("max_line_width_40", format_ai_with(SAMPLE, &with(|o| o.max_line_width = Some(40))));
+    let glyphs = format_ai_with(SAMPLE, &with(|o| o.glyphs = GlyphMode::Unicode));
+    insta::assert_snapshot!("glyphs_unicode", glyphs);
+    insta::assert_snapshot!("glyphs_ascii", format_ai_with(&glyphs, &with(|o| o.glyphs = GlyphMode::Ascii)));
+    insta::assert_snapshot!("always_semicolons", format_ai_with(SAMPLE, &with(|o| o.always_semicolons = true)));
+
+    // an empty `[format]` table is today's formatter
+    let empty = FormatSettings::from_toml_str("[format]\n").unwrap();
+    assert_eq!(empty.format_options(), FormatOptions::default());
+    let ascii = "let x = 1;\nlog(x);\n";
+    assert_eq!(format_ai_with(ascii, &empty.format_options()), format_ai(ascii));
+}
+
+#[test]
+fn glyph_modes_round_trip_and_keep_operator_pairs_whole() {
+    let ascii = with(|o| o.glyphs = GlyphMode::Ascii);
+    let unicode = with(|o| o.glyphs = GlyphMode::Unicode);
+    let src = "if (a <= b && c != d) {\nx := a ＝ b;\n}\n";
+    let glyphs = format_ai_with(src, &unicode);
+    assert!(glyphs.contains("(a ≤ b && c ≠ d)") && glyphs.contains("x ≔ a == b;"), "{glyphs}");
+    assert_eq!(format_ai_with(&glyphs, &ascii), format_ai_with(src, &ascii));
+    // `=>=` is a fat arrow and an `=`, not `=` and `>=`
+    assert_eq!(format_ai_with("a =>= b;\n", &unicode), "a =>= b;\n");
+    // strings and comments keep their spelling
+    assert_eq!(format_ai_with("log(\"<=\");\n// a <= b\n", &unicode), "log(\"<=\");\n// a <= b\n");
+}
+
+#[test]
+fn always_semicolons_leaves_conditions_enums_and_continued_lines_alone() {
+    let opt = with(|o| o.always_semicolons = true);
+    let src = "enum Color {\nRed,\nGreen\n}\nif (ready)\nstart()\nlet total = a\n+ b;\nfinish() // done\n";
+    assert_eq!(
+        format_ai_with(src, &opt),
+        "enum Color {\n        Red,\n    Green\n    }\nif (ready)\nstart();\nlet total = a\n+ b;\nfinish();\n// done\n"
+    );
+}
+
+#[test]
+fn wrapping_breaks_at_commas_inside_brackets_only() {
+    let opt = with(|o| o.max_line_width = Some(24));
+    let out = format_ai_with("let total = add(first, second, third);\nlog(\"one, two, three, four, five\");\n", &opt);
+    assert_eq!(out, "let total = add(first,\n    second, third);\nlog(\"one, two, three, four, five\");\n");
+    for line in out.lines().filter(|l| !l.contains('"')) {
+        assert!(line.len() <= 24, "{line:?}");
+    }
+}
+
+#[test]
+fn formatting_is_idempotent_under_every_combination() {
+    let mut sources = vec![SAMPLE.to_string()];
+    for ex in ["examples/hello.ai", "examples/control_flow.ai", "examples/functions.ai"] {
+        sources.push(fs::read_to_string(ex).unwrap());
+    }
+    for indent_width in [2, 4, 8] {
+        for max_line_width in [None, Some(30), Some(60)] {
+            for glyphs in [GlyphMode::Preserve, GlyphMode::Ascii, GlyphMode::Unicode] {
+                for always_semicolons in [false, true] {
+                    let opt = FormatOptions { indent_width, max_line_width, glyphs, always_semicolons, ..Default::default() };
+                    for src in &sources {
+                        let once = format_ai_with(src, &opt);
+                        assert_eq!(format_ai_with(&once, &opt), once, "{opt:?}\n{once}");
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn format(dir: &Path, extra: &[&str]) -> Output {
+    std::process::Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .current_dir(dir)
+        .env("NO_COLOR", "1")
+        .arg("format")
+        .args(extra)
+        .arg(".")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn manifest_options_apply_and_flags_override_them() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("a.ai");
+    fs::write(dir.path().join("Aeonmi.toml"), "[format]\nindent_width = 2\nalways_semicolons = true\n").unwrap();
+    fs::write(&file, "function f(x) {\nreturn x;\n}\nf(1)\n").unwrap();
+
+    assert_eq!(format(dir.path(), &[]).status.code(), Some(1));
+    assert_eq!(fs::read_to_string(&file).unwrap(), "function f(x) {\n    return x;\n    }\nf(1);\n");
+    // the cache remembers the options: other flags make the file unformatted again
+    let out = format(dir.path(), &["--check", "--indent-width", "4", "--always-semicolons=false"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), "a.ai");
+    assert_eq!(format(dir.path(), &["--check"]).status.code(), Some(0));
+
+    assert_eq!(format(dir.path(), &["--indent-width", "0"]).status.code(), Some(2));
+    assert_eq!(format(dir.path(), &["--glyphs", "emoji"]).status.code(), Some(2));
+    fs::write(dir.path().join("Aeonmi.toml"), "[format]\nindent_width = 40\n").unwrap();
+    let out = format(dir.path(), &[]);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("indent_width must be between 1 and 16"));
+}
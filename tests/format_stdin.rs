@@ -0,0 +1,85 @@
+use std::io::Write;
+use std::path::Path;
+use std::process::{Output, Stdio};
+
+use aeonmi_project::commands::format::unified_diff;
+use aeonmi_project::core::formatter::format_ai;
+
+const MESSY: &str = "let   a=1;\nlog(a);\nlet   b=2;\n";
+
+fn fmt_stdin(dir: &Path, input: &str, extra: &[&str]) -> Output {
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .current_dir(dir)
+        .env("NO_COLOR", "1")
+        .args(["fmt", "--stdin"])
+        .args(extra)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn stdin_is_formatted_to_stdout_without_touching_the_cache() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = fmt_stdin(dir.path(), MESSY, &[]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout), format_ai(MESSY));
+    assert!(out.stderr.is_empty(), "no summary in editor mode");
+    assert!(!dir.path().join(".aeonmi").exists());
+
+    // canonical input passes --check silently
+    let out = fmt_stdin(dir.path(), &format_ai(MESSY), &["--check"]);
+    assert_eq!(out.status.code(), Some(0));
+    assert!(out.stdout.is_empty());
+}
+
+#[test]
+fn check_prints_a_unified_diff() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = fmt_stdin(dir.path(), MESSY, &["--check", "--stdin-filename", "src/m.ai"]);
+    assert_eq!(out.status.code(), Some(1));
+    let diff = String::from_utf8_lossy(&out.stdout);
+    assert!(diff.starts_with("--- src/m.ai\n+++ src/m.ai (formatted)\n@@ -1,3 +1,3 @@\n"), "{diff}");
+    assert!(diff.contains("-let   a=1;\n") && diff.contains("\n log(a);\n"), "{diff}");
+}
+
+#[test]
+fn parse_errors_exit_3_and_name_the_stdin_filename() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = fmt_stdin(dir.path(), "let a = ;\n", &["--stdin-filename", "src/bad.ai", "--pretty-errors"]);
+    assert_eq!(out.status.code(), Some(3));
+    assert!(out.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("src/bad.ai:1:9"), "{stderr}");
+
+    let out = fmt_stdin(dir.path(), "log(\"open\n", &[]);
+    assert_eq!(out.status.code(), Some(3));
+
+    // files and --stdin do not mix; --stdin-filename needs --stdin
+    assert_eq!(fmt_stdin(dir.path(), "", &["x.ai"]).status.code(), Some(2));
+    let out = std::process::Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .current_dir(dir.path())
+        .args(["fmt", "--stdin-filename", "x.ai", "."])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn unified_diff_groups_nearby_changes_into_hunks() {
+    let old: String = (1..=20).map(|i| format!("l{i}\n")).collect();
+    let new = old.replace("l2\n", "L2\n").replace("l4\n", "").replace("l18\n", "l18\nnew\n");
+    let diff = unified_diff("a", "b", &old, &new);
+    let hunks: Vec<&str> = diff.lines().filter(|l| l.starts_with("@@")).collect();
+    assert_eq!(hunks, ["@@ -1,7 +1,6 @@", "@@ -16,5 +15,6 @@"]);
+    assert!(diff.contains("-l2\n+L2\n l3\n-l4\n"), "{diff}");
+    assert!(diff.ends_with(" l18\n+new\n l19\n l20\n"), "{diff}");
+
+    assert_eq!(unified_diff("a", "b", "x\n", "x\n"), "");
+    // an empty side counts from the line before
+    assert_eq!(unified_diff("a", "b", "", "x\n"), "--- a\n+++ b\n@@ -0,0 +1,1 @@\n+x\n");
+}
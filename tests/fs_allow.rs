@@ -0,0 +1,92 @@
+use aeonmi_project::core::fs_allow::{FsAccessError, FsAllowList};
+use std::fs;
+use std::path::Path;
+
+/// `<tmp>/project` (allowed, with `main.ai`) next to `<tmp>/secret` (not allowed, with `key.txt`).
+fn layout() -> (tempfile::TempDir, FsAllowList) {
+    let dir = tempfile::tempdir().unwrap();
+    let project = dir.path().join("project");
+    fs::create_dir_all(project.join("src")).unwrap();
+    fs::create_dir(dir.path().join("secret")).unwrap();
+    fs::write(project.join("main.ai"), "log(1);").unwrap();
+    fs::write(dir.path().join("secret/key.txt"), "hunter2").unwrap();
+    let allow = FsAllowList::with_root(&project).unwrap();
+    (dir, allow)
+}
+
+fn code(e: &FsAccessError) -> String {
+    serde_json::to_value(e).unwrap()["code"].as_str().unwrap().to_string()
+}
+
+#[test]
+fn reads_and_writes_stay_inside_the_roots() {
+    let (dir, mut allow) = layout();
+    let project = dir.path().join("project");
+    assert_eq!(allow.read(&project.join("main.ai")).unwrap(), "log(1);");
+    // `..` that stays inside is fine; a new file only needs its directory to exist
+    allow.write(&project.join("src/../src/new.ai"), "log(2);").unwrap();
+    assert_eq!(fs::read_to_string(project.join("src/new.ai")).unwrap(), "log(2);");
+
+    let escape = project.join("../secret/key.txt");
+    let err = allow.read(&escape).unwrap_err();
+    assert_eq!(code(&err), "outside_allowed_roots");
+    let err = allow.write(&project.join("src/../../secret/new.txt"), "x").unwrap_err();
+    assert_eq!(code(&err), "outside_allowed_roots");
+    assert!(!dir.path().join("secret/new.txt").exists());
+
+    let err = allow.write(&project.join("missing/dir/file.ai"), "x").unwrap_err();
+    assert_eq!(code(&err), "invalid_path");
+}
+
+#[test]
+fn errors_are_structured() {
+    let (dir, allow) = layout();
+    let path = dir.path().join("project/../secret/key.txt");
+    let json = serde_json::to_value(allow.read(&path).unwrap_err()).unwrap();
+    assert_eq!(json["code"], "outside_allowed_roots");
+    assert_eq!(json["path"], path.display().to_string());
+    let resolved = dir.path().join("secret/key.txt").canonicalize().unwrap();
+    assert_eq!(json["resolved"], resolved.display().to_string());
+    assert_eq!(json["roots"][0], allow.roots()[0].display().to_string());
+    assert!(json["message"].as_str().unwrap().contains("outside the allowed directories"), "{json}");
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks_are_judged_by_their_target() {
+    use std::os::unix::fs::symlink;
+    let (dir, mut allow) = layout();
+    let project = dir.path().join("project");
+    symlink(dir.path().join("secret"), project.join("linked_dir")).unwrap();
+    symlink(dir.path().join("secret/key.txt"), project.join("linked_key")).unwrap();
+    symlink(dir.path().join("secret/absent.txt"), project.join("dangling")).unwrap();
+    symlink(project.join("main.ai"), project.join("src/alias.ai")).unwrap();
+
+    assert_eq!(code(&allow.read(&project.join("linked_key")).unwrap_err()), "outside_allowed_roots");
+    assert_eq!(code(&allow.read(&project.join("linked_dir/key.txt")).unwrap_err()), "outside_allowed_roots");
+    assert_eq!(code(&allow.write(&project.join("linked_dir/planted.txt"), "x").unwrap_err()), "outside_allowed_roots");
+    assert_eq!(code(&allow.write(&project.join("dangling"), "x").unwrap_err()), "invalid_path");
+    assert!(!dir.path().join("secret/planted.txt").exists() && !dir.path().join("secret/absent.txt").exists());
+    assert_eq!(fs::read_to_string(dir.path().join("secret/key.txt")).unwrap(), "hunter2");
+    // a link that stays inside the project is allowed
+    assert_eq!(allow.read(&project.join("src/alias.ai")).unwrap(), "log(1);");
+}
+
+#[test]
+fn picked_directories_extend_the_list_and_writes_are_audited() {
+    let (dir, mut allow) = layout();
+    let secret = dir.path().join("secret");
+    assert!(allow.write(&secret.join("notes.ai"), "denied").is_err());
+    let root = allow.allow_root(&secret).unwrap();
+    assert_eq!(allow.roots().len(), 2);
+    assert_eq!(allow.allow_root(&dir.path().join("secret/../secret")).unwrap(), root, "same root once");
+    assert_eq!(allow.roots().len(), 2);
+    allow.write(&secret.join("notes.ai"), "allowed").unwrap();
+    assert!(allow.allow_root(&secret.join("key.txt")).is_err(), "a root must be a directory");
+
+    let log = allow.audit_log();
+    assert_eq!(log.len(), 2);
+    assert_eq!((log[0].outcome.as_str(), log[0].bytes), ("outside_allowed_roots", 6));
+    assert_eq!((log[1].outcome.as_str(), log[1].bytes), ("ok", 7));
+    assert_eq!(Path::new(&log[1].resolved), root.join("notes.ai"));
+}
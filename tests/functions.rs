@@ -24,10 +24,10 @@ fn function_decl_and_body() {
     // Function signature + body structure
     assert!(js.contains("function add(a, b)"));
     assert!(js.contains("let sum = (a + b);"));
-    assert!(js.contains("console.log(sum);"));
+    assert!(js.contains("console.log(__aeonmi_display(sum));"));
     assert!(js.contains("return sum;"));
 
     // Post-function code
     assert!(js.contains("let r = (1 + 2);"));
-    assert!(js.contains("console.log(r);"));
+    assert!(js.contains("console.log(__aeonmi_display(r));"));
 }
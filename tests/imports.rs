@@ -0,0 +1,192 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use aeonmi_project::core::ast::ASTNode;
+use aeonmi_project::core::imports::{resolve, Flattened, ImportError};
+use aeonmi_project::core::lexer::{Lexer, LexerOptions};
+use aeonmi_project::core::parser::Parser;
+
+fn bin() -> String {
+    env!("CARGO_BIN_EXE_aeonmi_project").to_string()
+}
+
+fn flatten(path: &Path) -> Result<Flattened, Box<ImportError>> {
+    let source = fs::read_to_string(path).unwrap();
+    let tokens = Lexer::from_str(&source).tokenize().expect("lex");
+    let ast = Parser::new(tokens).parse().expect("parse");
+    resolve(ast, path, &source, &LexerOptions::default())
+}
+
+fn items(f: &Flattened) -> &[ASTNode] {
+    match &f.program {
+        ASTNode::Program(items) => items,
+        other => panic!("not a program: {other:?}"),
+    }
+}
+
+#[test]
+fn imported_items_are_spliced_in_place() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("lib")).unwrap();
+    fs::write(dir.path().join("lib/util.ai"), "function double(x) { return x * 2; }\n").unwrap();
+    let main = dir.path().join("main.ai");
+    fs::write(&main, "let a = 1;\nimport \"lib/util.ai\";\nlog(double(a));\n").unwrap();
+
+    let f = flatten(&main).expect("resolve");
+    let items = items(&f);
+    assert_eq!(items.len(), 3);
+    assert!(matches!(&items[1], ASTNode::Function { name, .. } if name == "double"), "{items:?}");
+    assert_eq!(f.origins.len(), 3);
+    assert!(f.origins[1].ends_with("lib/util.ai"), "{:?}", f.origins);
+    assert_eq!(f.origins[2], main);
+    assert_eq!(f.imported.len(), 1);
+}
+
+#[test]
+fn importing_twice_is_idempotent() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("shared.ai"), "let k = 7;\n").unwrap();
+    fs::write(dir.path().join("b.ai"), "import \"shared.ai\";\n").unwrap();
+    let main = dir.path().join("main.ai");
+    fs::write(&main, "import \"shared.ai\";\nimport \"b.ai\";\nimport \"./shared.ai\";\nlog(k);\n").unwrap();
+
+    let f = flatten(&main).expect("resolve");
+    let decls = items(&f).iter().filter(|n| matches!(n, ASTNode::VariableDecl { name, .. } if name == "k")).count();
+    assert_eq!(decls, 1);
+    assert_eq!(f.imported.len(), 2, "shared.ai and b.ai, each once");
+}
+
+#[test]
+fn cycle_is_reported_with_the_chain() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("a.ai"), "import \"b.ai\";\n").unwrap();
+    fs::write(dir.path().join("b.ai"), "let x = 1;\nimport \"a.ai\";\n").unwrap();
+
+    match flatten(&dir.path().join("a.ai")).map_err(|e| *e) {
+        Err(e @ ImportError::Cycle { line, column, .. }) => {
+            assert_eq!((line, column), (2, 1));
+            let msg = e.message();
+            assert!(msg.starts_with("import cycle: "), "{msg}");
+            assert!(msg.contains("a.ai -> ") && msg.contains("b.ai -> ") && msg.ends_with("a.ai"), "{msg}");
+        }
+        other => panic!("expected a cycle, got {other:?}"),
+    }
+}
+
+#[test]
+fn missing_file_names_the_resolved_path() {
+    let dir = tempfile::tempdir().unwrap();
+    let main = dir.path().join("main.ai");
+    fs::write(&main, "import \"nope.ai\";\n").unwrap();
+    match flatten(&main).map_err(|e| *e) {
+        Err(e @ ImportError::NotFound { .. }) => assert!(e.message().contains("nope.ai"), "{}", e.message()),
+        other => panic!("expected not found, got {other:?}"),
+    }
+}
+
+#[test]
+fn import_inside_a_block_is_a_parse_error() {
+    let tokens = Lexer::from_str("if (true) { import \"x.ai\"; }").tokenize().expect("lex");
+    let err = Parser::new(tokens).parse().expect_err("nested import");
+    assert!(err.message.contains("top level"), "{}", err.message);
+}
+
+#[test]
+fn native_and_js_runs_see_the_flattened_program() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("math.ai"), "function triple(x) { return x * 3; }\n").unwrap();
+    let main = dir.path().join("main.ai");
+    fs::write(&main, "import \"math.ai\";\nlog(triple(14));\n").unwrap();
+
+    let native = Command::new(bin()).args(["run", "--native"]).arg(&main).current_dir(dir.path()).output().unwrap();
+    assert!(native.status.success(), "{}", String::from_utf8_lossy(&native.stderr));
+    assert!(String::from_utf8_lossy(&native.stdout).contains("42"));
+
+    let out = dir.path().join("out.js");
+    let emit = Command::new(bin()).arg("emit").arg(&main).arg("--out").arg(&out).current_dir(dir.path()).output().unwrap();
+    assert!(emit.status.success(), "{}", String::from_utf8_lossy(&emit.stderr));
+    let js = fs::read_to_string(&out).unwrap();
+    assert!(js.contains("function triple"), "{js}");
+}
+
+#[test]
+fn ast_command_lists_origins() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("lib.ai"), "let base = 1;\n").unwrap();
+    let main = dir.path().join("main.ai");
+    fs::write(&main, "import \"lib.ai\";\nlog(base);\n").unwrap();
+
+    let out = Command::new(bin()).arg("ast").arg(&main).current_dir(dir.path()).output().unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("=== Origins ==="), "{stdout}");
+    assert!(stdout.contains("[0] ") && stdout.contains("lib.ai"), "{stdout}");
+}
+
+/// `main.ai` calling `helper` from `helper.ai`, in `dir`.
+fn two_file_program(dir: &Path, helper: &str) -> std::path::PathBuf {
+    fs::write(dir.join("helper.ai"), helper).unwrap();
+    let main = dir.join("main.ai");
+    fs::write(&main, "import \"helper.ai\";\nlet n = helper(2);\nlog(n);\n").unwrap();
+    main
+}
+
+#[test]
+fn check_lint_typecheck_and_ir_see_imported_items() {
+    let dir = tempfile::tempdir().unwrap();
+    let main = two_file_program(dir.path(), "function helper(x) { return x + 1; }\n");
+    for args in [&["typecheck"][..], &["typecheck", "--annotate"], &["lint"], &["ir"]] {
+        let out = Command::new(bin()).args(args).arg(&main).current_dir(dir.path()).output().unwrap();
+        let text = format!("{}{}", String::from_utf8_lossy(&out.stdout), String::from_utf8_lossy(&out.stderr));
+        assert!(out.status.success(), "{args:?}: {text}");
+        assert!(!text.contains("undefined function"), "{args:?}: {text}");
+    }
+}
+
+#[test]
+fn diagnostics_in_imported_items_name_the_imported_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let helper = "function helper(x) { return x + 1; }\nfunction bad() {\n    let s = \"x\";\n    s = 1;\n}\n";
+    let main = two_file_program(dir.path(), helper);
+
+    let f = flatten(&main).expect("resolve");
+    let ASTNode::Program(numbered) = f.numbered() else { panic!("not a program") };
+    let ASTNode::Function { line, .. } = &numbered[1] else { panic!("{:?}", numbered[1]) };
+    let (file, in_file) = f.locate(*line);
+    assert!(file.ends_with("helper.ai") && in_file == 2, "{file:?}:{in_file}");
+    assert_eq!(f.locate(2), (main.as_path(), 2));
+
+    let typecheck = Command::new(bin()).arg("typecheck").arg(&main).current_dir(dir.path()).output().unwrap();
+    assert_eq!(typecheck.status.code(), Some(3));
+    let stdout = String::from_utf8_lossy(&typecheck.stdout);
+    let line = stdout.lines().find(|l| l.contains("Type mismatch")).unwrap_or_else(|| panic!("{stdout}"));
+    assert!(line.contains("helper.ai:4:5:"), "{stdout}");
+    assert!(stdout.contains("main.ai: 0 type errors") && stdout.contains("helper.ai: 1 type error"), "{stdout}");
+
+    let annotate = Command::new(bin()).args(["typecheck", "--annotate"]).arg(&main).current_dir(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&annotate.stdout);
+    assert!(stdout.contains("helper.ai: 1 error") && stdout.contains("main.ai: 0 errors"), "{stdout}");
+    assert!(stdout.contains("    s = 1;"), "the imported file is listed: {stdout}");
+
+    let out = dir.path().join("out.js");
+    let emit = Command::new(bin()).arg("emit").arg(&main).arg("--out").arg(&out).arg("--diag-sidecar").current_dir(dir.path()).output().unwrap();
+    assert!(emit.status.success(), "{}", String::from_utf8_lossy(&emit.stderr));
+    let sidecar = fs::read_to_string(dir.path().join("out.js.diag.json")).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&sidecar).unwrap();
+    let d = json["diagnostics"].as_array().unwrap().iter().find(|d| d["pass"] == "types").unwrap_or_else(|| panic!("{sidecar}"));
+    assert!(d["file"].as_str().is_some_and(|f| f.ends_with("helper.ai")) && d["line"] == 4, "{sidecar}");
+}
+
+#[test]
+fn expression_type_errors_in_imported_files_point_into_them() {
+    let dir = tempfile::tempdir().unwrap();
+    let helper = "function helper(x) {\n    let s = \"x\";\n    if (s) { log(1); }\n    return s - x;\n}\n";
+    let main = two_file_program(dir.path(), helper);
+    let typecheck = Command::new(bin()).arg("check").arg(&main).current_dir(dir.path()).output().unwrap();
+    assert_eq!(typecheck.status.code(), Some(3));
+    let stdout = String::from_utf8_lossy(&typecheck.stdout);
+    assert!(stdout.contains("helper.ai:3:9: error: If condition not boolean"), "{stdout}");
+    assert!(stdout.contains("helper.ai:4:12: error: Arithmetic on non-number"), "{stdout}");
+    assert!(stdout.contains("main.ai: 0 type errors") && stdout.contains("helper.ai: 2 type errors"), "{stdout}");
+}
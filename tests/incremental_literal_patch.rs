@@ -0,0 +1,77 @@
+use aeonmi_project::core::ast::ASTNode;
+use aeonmi_project::core::incremental::{parse_or_cached, parse_or_partial, snapshot_call_graph_metrics, LAST_REPLACED_INDEX};
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::parser::Parser;
+use std::sync::Mutex;
+
+// The parse cache and counters are process-global; keep the tests in this file from interleaving.
+static CACHE: Mutex<()> = Mutex::new(());
+
+fn full_parse(src: &str) -> ASTNode {
+    Parser::new(Lexer::from_str(src).tokenize().unwrap()).parse().unwrap()
+}
+
+fn source(text: &str, note: &str) -> String {
+    format!("function greet(name) {{\n    let prefix = \"{text}\";\n    return prefix + name;\n}}\n⍝ {note}\nlet banner = greet(\"x\");\nlog(banner);\n")
+}
+
+#[test]
+fn repeated_string_edits_patch_in_place() {
+    let _g = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    let mut text = "Lorem ipsum dolor sit amet, consectetur adipiscing elit ".repeat(20);
+    parse_or_cached(&source(&text, "greeting")).unwrap();
+    let before = snapshot_call_graph_metrics();
+
+    for (i, word) in ["alpha ", "beta ", "", "gamma"].iter().enumerate() {
+        if word.is_empty() { text.truncate(text.len() - 12); } else { text.insert_str(100 + i, word); }
+        let src = source(&text, "greeting");
+        *LAST_REPLACED_INDEX.lock().unwrap() = Some(99);
+        let (ast, partial) = parse_or_partial(&src).unwrap();
+        assert!(partial);
+        assert_eq!(*LAST_REPLACED_INDEX.lock().unwrap(), None, "literal patch must not mark a node for re-analysis");
+        assert_eq!(ast, full_parse(&src), "edit {i}");
+    }
+    // a comment edit changes no tokens at all
+    let src = source(&text, "greeting, edited twice");
+    let (ast, _) = parse_or_partial(&src).unwrap();
+    assert_eq!(ast, full_parse(&src));
+
+    let after = snapshot_call_graph_metrics();
+    assert_eq!(after.fast_literal_patch - before.fast_literal_patch, 5);
+    assert_eq!(after.reinfer_events, before.reinfer_events);
+}
+
+#[test]
+fn edits_that_can_change_tokens_or_positions_reparse() {
+    let _g = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    let base = "let a = \"one\"; let b = a;\nlet c = \"two\";\nlog(a + b + c);\n";
+    parse_or_cached(base).unwrap();
+    let patched = || snapshot_call_graph_metrics().fast_literal_patch;
+
+    for edited in [
+        // closes the string early
+        "let a = \"o\"ne\"; let b = a;\nlet c = \"two\";\nlog(a + b + c);\n",
+        // escape sequence
+        "let a = \"o\\ne\"; let b = a;\nlet c = \"two\";\nlog(a + b + c);\n",
+        // width change with a spanned identifier later on the line
+        "let a = \"one!\"; let b = a;\nlet c = \"two\";\nlog(a + b + c);\n",
+        // newline inside the string shifts every later line
+        "let a = \"one\"; let b = a;\nlet c = \"t\nwo\";\nlog(a + b + c);\n",
+    ] {
+        parse_or_cached(base).unwrap();
+        let count = patched();
+        match parse_or_partial(edited) {
+            Ok((ast, _)) => assert_eq!(ast, full_parse(edited)),
+            Err(_) => assert!(Lexer::from_str(edited).tokenize().is_err() || Parser::new(Lexer::from_str(edited).tokenize().unwrap()).parse().is_err()),
+        }
+        assert_eq!(patched(), count, "{edited:?} must not be patched");
+    }
+
+    // same width, nothing shifts: patched
+    parse_or_cached(base).unwrap();
+    let count = patched();
+    let edited = "let a = \"won\"; let b = a;\nlet c = \"two\";\nlog(a + b + c);\n";
+    let (ast, _) = parse_or_partial(edited).unwrap();
+    assert_eq!(ast, full_parse(edited));
+    assert_eq!(patched(), count + 1);
+}
@@ -0,0 +1,45 @@
+//! A panic while an incremental cache is locked must not take every later caller down.
+use aeonmi_project::core::incremental::{cache_health, lock_cache, parse_or_cached, parse_or_partial, record_function_infer, record_function_site, DIAG_CACHE, FUNCTION_METRICS, LAST_TRIVIA_SHIFT};
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::parser::Parser;
+use aeonmi_project::core::semantic_analyzer::SemanticAnalyzer;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+#[test]
+fn poisoned_caches_are_cleared_and_reported() {
+    assert!(cache_health().healthy);
+    let src = "function f() {\n    log(missing);\n}\n";
+    parse_or_cached(src).unwrap();
+    let ast = Parser::new(Lexer::from_str(src).tokenize().unwrap()).parse().unwrap();
+    lock_cache("diagnostics", &DIAG_CACHE).reported = SemanticAnalyzer::new().analyze_with_spans(&ast);
+    record_function_site(7, "f", Some("a.ai"), 1);
+
+    // Panic with two caches held, as a bug inside TypeContext would.
+    let panicked = catch_unwind(AssertUnwindSafe(|| {
+        let _diags = DIAG_CACHE.lock().unwrap();
+        let _metrics = FUNCTION_METRICS.lock().unwrap();
+        panic!("boom while holding the caches");
+    }));
+    assert!(panicked.is_err());
+    assert!(DIAG_CACHE.is_poisoned() && FUNCTION_METRICS.is_poisoned());
+
+    // A comment-only edit shifts the cached diagnostics: it runs, on a cleared cache.
+    parse_or_partial(&format!("// note\n{src}")).unwrap();
+    assert!(lock_cache("trivia_shift", &LAST_TRIVIA_SHIFT).is_some());
+    assert!(lock_cache("diagnostics", &DIAG_CACHE).reported.is_empty());
+    assert!(!DIAG_CACHE.is_poisoned());
+
+    record_function_infer(3, 1_000);
+    let fm = lock_cache("function_metrics", &FUNCTION_METRICS).clone();
+    assert!(!fm.contains_key(&7), "old entries are gone");
+    assert_eq!(fm[&3].runs, 1);
+
+    let health = cache_health();
+    assert!(!health.healthy);
+    let reset: Vec<&str> = health.resets.iter().map(|r| r.cache).collect();
+    assert_eq!(reset, ["diagnostics", "function_metrics"]);
+
+    // Healthy locks do not add entries.
+    lock_cache("diagnostics", &DIAG_CACHE).reported.clear();
+    assert_eq!(cache_health().resets.len(), 2);
+}
@@ -0,0 +1,49 @@
+use aeonmi_project::core::ast::ASTNode;
+use aeonmi_project::core::incremental::{compute_call_edges, compute_var_deps_items, reinfer_set};
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::parser::Parser;
+
+fn items(code: &str) -> Vec<ASTNode> {
+    match Parser::new(Lexer::from_str(code).tokenize().expect("lex")).parse().expect("parse") {
+        ASTNode::Program(items) => items,
+        other => panic!("not a program: {other:?}"),
+    }
+}
+
+#[test]
+fn uses_inside_collection_literals_are_dependencies() {
+    let items = items(
+        r#"
+let limit = 1;
+function bump() { limit = 2; }
+function pack() { return [1, { cap: limit }]; }
+function helper() { return 1; }
+function wrap() { return { "v": [helper()] }; }
+"#,
+    );
+    let deps = compute_var_deps_items(&items);
+    assert!(deps.reads.get("limit").is_some_and(|r| r.contains(&2)), "{:?}", deps.reads);
+    assert_eq!(compute_call_edges(&items)[4], vec![3]);
+    assert_eq!(reinfer_set(&items, 1), vec![2]);
+    assert_eq!(reinfer_set(&items, 3), vec![4]);
+}
+
+#[test]
+fn uses_inside_match_arms_and_member_targets_are_dependencies() {
+    let items = items(
+        r#"
+let mode = 1;
+function set_mode() { mode = 2; }
+function pick(x) { match (x) { 1 => log(mode); _ => log(0); } }
+function helper() { return 1; }
+function route(x) { match (x) { 1 => log(helper()); _ => log(0); } }
+function config() { return { "n": 1 }; }
+function read() { return config().n; }
+"#,
+    );
+    let deps = compute_var_deps_items(&items);
+    assert!(deps.reads.get("mode").is_some_and(|r| r.contains(&2)), "{:?}", deps.reads);
+    assert_eq!(reinfer_set(&items, 1), vec![2]);
+    assert_eq!(reinfer_set(&items, 3), vec![4]);
+    assert_eq!(reinfer_set(&items, 5), vec![6]);
+}
@@ -0,0 +1,110 @@
+use aeonmi_project::core::ast::ASTNode;
+use aeonmi_project::core::incremental::{parse_or_cached, parse_or_partial, snapshot_call_graph_metrics, LineShift, DIAG_CACHE, LAST_REPLACED_INDEX, LAST_TRIVIA_SHIFT};
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::parser::Parser;
+use aeonmi_project::core::semantic_analyzer::SemanticAnalyzer;
+use std::sync::Mutex;
+
+// The parse cache and counters are process-global; keep the tests in this file from interleaving.
+static CACHE: Mutex<()> = Mutex::new(());
+
+fn full_parse(src: &str) -> ASTNode {
+    Parser::new(Lexer::from_str(src).tokenize().unwrap()).parse().unwrap()
+}
+
+fn diag_lines(ast: &ASTNode) -> Vec<usize> {
+    let mut lines: Vec<usize> = SemanticAnalyzer::new().analyze_with_spans(ast).iter().map(|d| d.line).collect();
+    lines.sort_unstable();
+    lines
+}
+
+/// 200 functions, every tenth reading an undeclared name.
+fn program() -> String {
+    (0..200)
+        .map(|i| {
+            let body = if i % 10 == 0 { "log(missing);" } else { "let a = 1;\n    log(a);" };
+            format!("function f{i}() {{\n    {body}\n    return {i};\n}}\n")
+        })
+        .collect()
+}
+
+#[test]
+fn typing_a_comment_above_the_program_does_not_reparse() {
+    let _g = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    let src = program();
+    let ast = parse_or_cached(&src).unwrap();
+    let reported = SemanticAnalyzer::new().analyze_with_spans(&ast);
+    assert!(!reported.is_empty());
+    DIAG_CACHE.lock().unwrap().reported = reported;
+    let before = snapshot_call_graph_metrics();
+
+    let mut header = String::from("\n");
+    parse_or_partial(&format!("{header}{src}")).unwrap();
+    assert_eq!(*LAST_TRIVIA_SHIFT.lock().unwrap(), Some(LineShift { after_line: 0, delta: 1 }));
+    let mut keystrokes = 1;
+    for ch in "⍝ dedupe helpers".chars() {
+        header.insert(header.len() - 1, ch);
+        *LAST_REPLACED_INDEX.lock().unwrap() = Some(99);
+        let (cached, partial) = parse_or_partial(&format!("{header}{src}")).unwrap();
+        keystrokes += 1;
+        assert!(partial);
+        assert_eq!(cached, ast, "the cached tree is returned untouched");
+        assert_eq!(*LAST_REPLACED_INDEX.lock().unwrap(), None);
+        // Typing inside the comment moves nothing.
+        assert_eq!(*LAST_TRIVIA_SHIFT.lock().unwrap(), Some(LineShift { after_line: 1, delta: 0 }));
+    }
+    let after = snapshot_call_graph_metrics();
+    assert_eq!(after.trivia_fast_path - before.trivia_fast_path, keystrokes);
+    assert_eq!(after.fast_literal_patch, before.fast_literal_patch);
+
+    // The cached diagnostics now sit where a fresh analysis of the edited source puts them.
+    let edited = format!("{header}{src}");
+    let mut shifted: Vec<usize> = DIAG_CACHE.lock().unwrap().reported.iter().map(|d| d.line).collect();
+    shifted.sort_unstable();
+    assert_eq!(shifted, diag_lines(&full_parse(&edited)));
+    assert_eq!(shifted[0], 2, "f0 now starts on line 2");
+
+    // The first edit that changes tokens refreshes the stale positions with a full parse;
+    // the spans it records make the next edit a splice again.
+    let edited = edited.replace("return 50;", "return 51;");
+    let (ast, partial) = parse_or_partial(&edited).unwrap();
+    assert!(!partial);
+    assert_eq!(ast, full_parse(&edited));
+    assert_eq!(*LAST_TRIVIA_SHIFT.lock().unwrap(), None);
+    let edited = edited.replace("return 51;", "return 52;");
+    let (ast, partial) = parse_or_partial(&edited).unwrap();
+    assert!(partial);
+    assert_eq!(*LAST_REPLACED_INDEX.lock().unwrap(), Some(50));
+    assert_eq!(ast, full_parse(&edited));
+}
+
+#[test]
+fn edits_that_change_tokens_are_not_trivia() {
+    let _g = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    let base = "let s = \"one\ntwo\";\nfunction f() {\n    return s;\n}\n";
+    let trivia = || snapshot_call_graph_metrics().trivia_fast_path;
+
+    for edited in [
+        // a new line inside a multi-line string looks like a comment on its own
+        "let s = \"one\n⍝ x\ntwo\";\nfunction f() {\n    return s;\n}\n",
+        // re-indenting code moves columns
+        "let s = \"one\ntwo\";\nfunction f() {\n  return s;\n}\n",
+        // a token typed above the program
+        "let\nlet s = \"one\ntwo\";\nfunction f() {\n    return s;\n}\n",
+    ] {
+        parse_or_cached(base).unwrap();
+        let count = trivia();
+        if let Ok((ast, _)) = parse_or_partial(edited) {
+            assert_eq!(ast, full_parse(edited));
+        }
+        assert_eq!(trivia(), count, "{edited:?} is not trivia");
+        assert_eq!(*LAST_TRIVIA_SHIFT.lock().unwrap(), None);
+    }
+
+    // A comment and a blank line inside a function body are.
+    parse_or_cached(base).unwrap();
+    let count = trivia();
+    let (_, partial) = parse_or_partial("let s = \"one\ntwo\";\nfunction f() {\n    ⍝ note\n\n    return s;\n}\n").unwrap();
+    assert!(partial);
+    assert_eq!(trivia(), count + 1);
+}
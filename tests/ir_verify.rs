@@ -0,0 +1,99 @@
+use aeonmi_project::core::ast::ASTNode;
+use aeonmi_project::core::ir::{Decl, Expr, Module, Stmt};
+use aeonmi_project::core::ir_verify::verify;
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::lowering::{lower_ast_to_ir, lower_ast_to_ir_with_hook};
+use aeonmi_project::core::parser::Parser;
+use std::fs;
+use std::process::Command;
+
+const PROGRAM: &str = "function f(s) {\n    for i, ch in s {\n        log(i + ch);\n    }\n}\nlet a, [b, c] = 1, \"xy\";\nqreg r[2];\nlog(-a + a * 2);\n";
+
+fn parse(source: &str) -> ASTNode {
+    let tokens = Lexer::from_str(source).tokenize().expect("lex");
+    Parser::new(tokens).parse().expect("parse")
+}
+
+fn body<'a>(m: &'a mut Module, name: &str) -> &'a mut Vec<Stmt> {
+    m.decls
+        .iter_mut()
+        .find_map(|d| match d {
+            Decl::Fn(f) if f.name == name => Some(&mut f.body.stmts),
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn dump_shows_desugared_module() {
+    let text = lower_ast_to_ir(&parse(PROGRAM), "main").unwrap().to_string();
+    assert!(text.starts_with("module main\n"), "{text}");
+    assert!(text.contains("fn f(s) {\n"), "{text}");
+    assert!(text.contains("for (let __for_i = 0; (__for_i < len(__for_items)); __for_i = (__for_i + 1)) {"), "{text}");
+    assert!(text.contains("    let [a, [b, c]] = [1, \"xy\"];  # 6:5\n"), "{text}");
+    assert!(text.contains("log((-a + (a * 2)));"), "{text}");
+}
+
+#[test]
+fn shipped_programs_lower_to_valid_ir() {
+    let dirs = ["examples", "tests/conformance"];
+    let mut checked = 0;
+    for dir in dirs {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|e| e != "ai") {
+                continue;
+            }
+            let src = fs::read_to_string(&path).unwrap();
+            let Ok(tokens) = Lexer::from_str(&src).tokenize() else { continue };
+            let Ok(ast) = Parser::new(tokens).parse() else { continue };
+            let m = aeonmi_project::core::lowering::lower_unverified(&ast, "main").unwrap();
+            assert_eq!(verify(&m), vec![], "{}", path.display());
+            checked += 1;
+        }
+    }
+    assert!(checked > 5, "only {checked} programs checked");
+}
+
+#[test]
+fn broken_lowering_is_an_internal_compiler_error() {
+    let ast = parse(PROGRAM);
+    // Drop the `let __for_items = ...` that the for-in loop reads.
+    let err = lower_ast_to_ir_with_hook(&ast, "main", |m| {
+        let Stmt::Block(b) = &mut body(m, "f")[0] else { panic!("for-in lowers to a block") };
+        b.stmts.remove(1);
+    })
+    .unwrap_err();
+    assert!(err.contains("internal compiler error: invalid IR in `f`: temporary `__for_items` is used before it is defined"), "{err}");
+    assert!(err.contains("compiler bug"), "{err}");
+
+    let err = lower_ast_to_ir_with_hook(&ast, "main", |m| body(m, "main").insert(0, Stmt::Break)).unwrap_err();
+    assert!(err.contains("invalid IR in `main`: `break` outside a loop"), "{err}");
+
+    let err = lower_ast_to_ir_with_hook(&ast, "main", |m| {
+        let Stmt::Let { value: Some(Expr::Call { args, .. }), .. } = &mut body(m, "main")[1] else { panic!("qreg lowers to a call") };
+        args.pop();
+    })
+    .unwrap_err();
+    assert!(err.contains("intrinsic `__qreg` takes 2 argument(s), called with 1"), "{err}");
+}
+
+#[test]
+fn ir_command_prints_text_and_json() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("prog.ai"), PROGRAM).unwrap();
+    let aeonmi = |args: &[&str]| Command::new(env!("CARGO_BIN_EXE_aeonmi_project")).current_dir(dir.path()).args(args).env("NO_COLOR", "1").output().unwrap();
+
+    let out = aeonmi(&["ir", "prog.ai"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(String::from_utf8_lossy(&out.stdout).contains("fn main() {"));
+
+    let out = aeonmi(&["ir", "prog.ai", "--json"]);
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(v["module"]["name"], "main");
+    assert_eq!(v["violations"].as_array().unwrap().len(), 0);
+
+    let out = aeonmi(&["--verify-ir", "run", "prog.ai", "--native"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), "1");
+}
@@ -0,0 +1,56 @@
+//! Diagnostics on very long lines show a clipped excerpt with the caret still on the span.
+use aeonmi_project::core::columns::visible_range;
+use aeonmi_project::core::diagnostics::{render_error, Span};
+use aeonmi_project::core::style::Style;
+
+fn excerpt_and_caret(out: &str) -> (&str, usize) {
+    let lines: Vec<&str> = out.lines().collect();
+    let caret = lines.iter().position(|l| l.contains('^')).expect("caret line");
+    // Both lines start with the gutter and `| `; compare what follows it.
+    let after_bar = |l: &str| l.find("| ").unwrap() + 2;
+    let text = &lines[caret - 1][after_bar(lines[caret - 1])..];
+    (text, lines[caret].find('^').unwrap() - after_bar(lines[caret]))
+}
+
+#[test]
+fn caret_stays_on_span_in_a_100k_line() {
+    let src = format!("let a = {}oops{};", "x".repeat(50_000), "y".repeat(50_000));
+    let col = src.find("oops").unwrap() + 1;
+    let out = render_error(&Style::PLAIN, "big.ai", &src, "bad", Span { line: 1, col, len: 4 }, None);
+    assert!(out.len() < 400, "{} chars", out.len());
+    let (text, at) = excerpt_and_caret(&out);
+    assert!(text.contains("...x") && text.ends_with("y..."), "{text}");
+    assert_eq!(&text[at..at + 4], "oops", "{out}");
+    assert_eq!(out.lines().filter(|l| l.ends_with("^^^^")).count(), 1, "{out}");
+}
+
+#[test]
+fn clipping_at_either_end_keeps_one_marker() {
+    let src = format!("oops{}", "z".repeat(100_000));
+    let out = render_error(&Style::PLAIN, "big.ai", &src, "bad", Span { line: 1, col: 1, len: 4 }, None);
+    let (text, at) = excerpt_and_caret(&out);
+    assert!(text.ends_with("z...") && !text.contains("...o"), "{text}");
+    assert_eq!(&text[at..at + 4], "oops");
+
+    let src = format!("{};", "z".repeat(100_000));
+    let out = render_error(&Style::PLAIN, "big.ai", &src, "bad", Span { line: 1, col: src.len(), len: 1 }, None);
+    let (text, at) = excerpt_and_caret(&out);
+    assert!(text.contains("...z") && text.ends_with(';'), "{text}");
+    assert_eq!(&text[at..], ";");
+}
+
+#[test]
+fn short_lines_are_not_clipped() {
+    let src = "let a = oops;";
+    let out = render_error(&Style::PLAIN, "t.ai", src, "bad", Span { line: 1, col: 9, len: 4 }, None);
+    assert!(out.contains("| let a = oops;\n"), "{out}");
+}
+
+#[test]
+fn visible_range_counts_cells() {
+    assert_eq!(visible_range("abcdef", 2, 3), 2..5);
+    assert_eq!(visible_range("abc", 5, 3), 3..3);
+    // `中` takes two cells: a window edge inside it leaves it out.
+    assert_eq!(visible_range("a中b", 2, 2), 4..5);
+    assert_eq!(visible_range("a中b", 0, 2), 0..1);
+}
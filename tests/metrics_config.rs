@@ -0,0 +1,61 @@
+use aeonmi_project::core::incremental::{reset_runtime_metrics_config, set_ema_alpha, set_history_cap, set_window_capacity, MetricsRuntimeConfig};
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::Mutex;
+
+// The knobs are process-global; keep the tests in this file from interleaving.
+static KNOBS: Mutex<()> = Mutex::new(());
+
+#[test]
+fn out_of_range_values_are_rejected_and_leave_config_unchanged() {
+    let _g = KNOBS.lock().unwrap_or_else(|e| e.into_inner());
+    reset_runtime_metrics_config();
+    let before = MetricsRuntimeConfig::current();
+    assert_eq!(before, MetricsRuntimeConfig::default());
+
+    assert_eq!(set_ema_alpha(0).unwrap_err(), "ema_alpha must be between 1 and 100 (got 0)");
+    assert!(set_ema_alpha(101).is_err());
+    assert!(set_window_capacity(3).is_err());
+    assert!(set_window_capacity(257).is_err());
+    assert!(set_history_cap(7).is_err());
+    assert_eq!(MetricsRuntimeConfig::current(), before);
+
+    // one bad knob rejects the whole config
+    let errors = MetricsRuntimeConfig { ema_alpha: 50, window: 1000, history_cap: 2 }.apply().unwrap_err();
+    assert_eq!(errors.len(), 2, "{errors:?}");
+    assert!(errors[0].starts_with("window") && errors[1].starts_with("history_cap"), "{errors:?}");
+    assert_eq!(MetricsRuntimeConfig::current(), before);
+
+    set_ema_alpha(100).unwrap();
+    assert_eq!(MetricsRuntimeConfig::current().ema_alpha, 100);
+    reset_runtime_metrics_config();
+}
+
+#[test]
+fn prefs_round_trip_restores_config() {
+    let _g = KNOBS.lock().unwrap_or_else(|e| e.into_inner());
+    let chosen = MetricsRuntimeConfig { ema_alpha: 35, window: 64, history_cap: 128 };
+    let mut prefs = HashMap::from([("theme".to_string(), "dark".to_string())]);
+    chosen.write_prefs(&mut prefs);
+    // prefs.json stores a flat string map
+    let saved = serde_json::to_string(&prefs).unwrap();
+    let loaded: HashMap<String, String> = serde_json::from_str(&saved).unwrap();
+    assert_eq!(loaded["theme"], "dark");
+
+    let restored = MetricsRuntimeConfig::from_prefs(&loaded);
+    assert_eq!(restored, chosen);
+    restored.apply().unwrap();
+    assert_eq!(MetricsRuntimeConfig::current(), chosen);
+
+    // missing or garbled keys fall back to defaults
+    let partial = HashMap::from([(MetricsRuntimeConfig::PREF_WINDOW.to_string(), "lots".to_string())]);
+    assert_eq!(MetricsRuntimeConfig::from_prefs(&partial), MetricsRuntimeConfig::default());
+    reset_runtime_metrics_config();
+}
+
+#[test]
+fn cli_reports_out_of_range_setting() {
+    let out = Command::new(env!("CARGO_BIN_EXE_aeonmi")).args(["metrics-config", "--set-ema", "0"]).output().unwrap();
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("ema_alpha must be between 1 and 100"), "{}", String::from_utf8_lossy(&out.stderr));
+}
@@ -0,0 +1,65 @@
+use aeonmi_project::commands::metrics_diff::{diff, load, render, FunctionStats};
+use aeonmi_project::core::style::Style;
+use std::path::Path;
+use std::process::Command;
+
+fn fixtures(threshold: f64) -> aeonmi_project::commands::metrics_diff::MetricsDiff {
+    let before = load(Path::new("tests/metrics_diff/before.json")).unwrap();
+    let after = load(Path::new("tests/metrics_diff/after.json")).unwrap();
+    diff(&before, &after, threshold)
+}
+
+#[test]
+fn functions_align_by_name_with_known_deltas() {
+    let d = fixtures(10.0);
+    let names: Vec<&str> = d.functions.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["emit", "new_helper", "old_helper", "parse_header"]);
+    let parse = &d.functions[3];
+    assert_eq!(parse.after, Some(FunctionStats { runs: 6, ema_ns: 1300, avg_ns: 1050 }));
+    assert_eq!((parse.ema_change_pct, parse.avg_change_pct, parse.runs_delta), (Some(30.0), Some(5.0), 2));
+    assert!(parse.regression);
+    let emit = &d.functions[0];
+    assert_eq!((emit.ema_change_pct, emit.runs_delta, emit.regression), (Some(-25.0), 1, false));
+    assert_eq!((d.functions[1].before, d.functions[2].after), (None, None));
+    assert_eq!((d.call_graph_edges.delta, d.variable_edges.delta, d.call_graph_functions.delta), (2.0, 2.0, 0.0));
+    assert_eq!((d.savings_pct.delta, d.partial_pct.delta), (-15.0, 15.0));
+    assert_eq!(d.regressions, ["parse_header: ema +30.0%", "cumulative savings -15.0 pts"]);
+    assert!(fixtures(50.0).regressions.is_empty());
+}
+
+#[test]
+fn table_shows_arrows_and_summary() {
+    assert_eq!(
+        render(&fixtures(10.0), &Style::PLAIN),
+        "\
+function      runs         ema_ns                 avg_ns
+emit          2 -> 3 (+1)  2000 -> 1500 (-25.0%)  1800 -> 1800 (+0.0%)
+new_helper    - -> 1 (+1)  - -> 700               - -> 700
+old_helper    1 -> - (-1)  500 -> -               500 -> -
+parse_header  4 -> 6 (+2)  1000 -> 1300 (+30.0%)  1000 -> 1050 (+5.0%)
+call graph: functions 3 -> 3 (+0), edges 2 -> 4 (+2)
+variable edges: 1 -> 3 (+2)
+savings: cumulative 40.0% -> 25.0% (-15.0 pts), partial 60.0% -> 75.0% (+15.0 pts)
+regressions beyond 10%:
+  parse_header: ema +30.0%
+  cumulative savings -15.0 pts
+"
+    );
+}
+
+fn run(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_aeonmi")).arg("metrics-diff").args(args).output().expect("run metrics-diff")
+}
+
+#[test]
+fn cli_json_and_fail_on_regression() {
+    let out = run(&["tests/metrics_diff/before.json", "tests/metrics_diff/after.json", "--json"]);
+    assert!(out.status.success());
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(v["regressions"].as_array().unwrap().len(), 2);
+    let out = run(&["--baseline", "tests/metrics_diff/before.json", "tests/metrics_diff/after.json", "--fail-on-regression"]);
+    assert_eq!(out.status.code(), Some(1));
+    let out = run(&["tests/metrics_diff/before.json", "tests/metrics_diff/after.json", "--fail-on-regression", "--threshold", "50"]);
+    assert!(out.status.success());
+    assert_eq!(run(&["tests/metrics_diff/before.json"]).status.code(), Some(2));
+}
@@ -6,8 +6,8 @@ fn metrics_ema_window_savings_and_pruning() {
     // Reset all state
     reset_metrics_full();
     // Configure EMA alpha = 50, window size = 4
-    set_ema_alpha(50);
-    set_window_capacity(4);
+    set_ema_alpha(50).unwrap();
+    set_window_capacity(4).unwrap();
     // Record sequence of durations for function index 0
     record_function_infer(0, 100); // runs=1 ema=100
     record_function_infer(0, 200); // ema=(100*50+200*50)/100=150
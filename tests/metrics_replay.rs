@@ -0,0 +1,78 @@
+use std::process::Command;
+
+fn replay(script: &str, extra: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .args(["--quiet", "metrics-replay", "examples/metrics_replay/pipeline.ai", script])
+        .args(extra)
+        .output()
+        .unwrap()
+}
+
+/// (mode, changed, reinfer) per step; timings are deliberately not asserted.
+fn steps(script: &str) -> Vec<(String, Option<String>, Vec<String>)> {
+    let out = replay(script, &["--json"]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    v["steps"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| {
+            assert!(s["incremental_ns"].as_u64().unwrap() > 0 && s["full_ns"].as_u64().unwrap() > 0);
+            let names = s["reinfer"].as_array().unwrap().iter().map(|n| n.as_str().unwrap().to_string()).collect();
+            (s["mode"].as_str().unwrap().to_string(), s["changed"].as_str().map(str::to_string), names)
+        })
+        .collect()
+}
+
+fn step(mode: &str, changed: Option<&str>, reinfer: &[&str]) -> (String, Option<String>, Vec<String>) {
+    (mode.to_string(), changed.map(str::to_string), reinfer.iter().map(|s| s.to_string()).collect())
+}
+
+#[test]
+fn leaf_edits_reinfer_only_callers() {
+    assert_eq!(
+        steps("examples/metrics_replay/leaf_edits.json"),
+        vec![
+            step("partial", Some("checksum"), &[]),
+            step("partial", Some("offset"), &["transform", "report"]),
+            step("partial", Some("report"), &[]),
+            step("full", None, &[]),
+            step("full", None, &[]),
+        ]
+    );
+}
+
+#[test]
+fn shared_edits_follow_variable_and_call_edges() {
+    assert_eq!(
+        steps("examples/metrics_replay/shared_edits.json"),
+        vec![
+            step("partial", Some("set_rate"), &["scale", "transform", "report"]),
+            step("partial", Some("scale"), &["transform", "report"]),
+            step("partial", Some("transform"), &["report"]),
+            step("partial", Some("rate"), &[]),
+        ]
+    );
+}
+
+#[test]
+fn csv_output_and_bad_scripts() {
+    let out = replay("examples/metrics_replay/leaf_edits.json", &["--csv"]);
+    let text = String::from_utf8(out.stdout).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "step,label,mode,changed,reinfer_count,incremental_ns,full_ns,savings_ns,cumulative_savings_ns");
+    assert_eq!(lines.len(), 6);
+    assert!(lines[2].starts_with("2,tweak offset,partial,offset,2,"), "{}", lines[2]);
+
+    let dir = tempfile::tempdir().unwrap();
+    let script = dir.path().join("bad.json");
+    std::fs::write(&script, r#"[{"op": "delete", "start": 40, "end": 41, "label": "past the end"}]"#).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .args(["metrics-replay", "examples/metrics_replay/pipeline.ai"])
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("step 1 (past the end): line range 40-41 is outside 1-26"));
+}
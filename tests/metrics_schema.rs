@@ -21,3 +21,47 @@ fn metrics_json_includes_v5_fields() {
     // functionMetrics should be an object
     val.get("functionMetrics").and_then(|v| v.as_object()).expect("functionMetrics object");
 }
+
+use aeonmi_project::core::incremental::{build_metrics_json, load_metrics_from, migrate_metrics, MetricsLoadError, METRICS_SCHEMA, METRICS_VERSION};
+
+fn fixture(version: u32) -> serde_json::Value {
+    let text = std::fs::read_to_string(format!("tests/metrics_schema/v{version}.json")).unwrap();
+    serde_json::from_str(&text).unwrap()
+}
+
+#[test]
+fn historical_versions_migrate_to_current() {
+    let current = fixture(METRICS_VERSION);
+    assert_eq!(migrate_metrics(current.clone()).unwrap(), current);
+    for version in 3..METRICS_VERSION {
+        assert_eq!(migrate_metrics(fixture(version)).unwrap(), current, "v{version} did not migrate to v{METRICS_VERSION}");
+    }
+}
+
+#[test]
+fn newer_version_is_refused() {
+    let mut val = fixture(METRICS_VERSION);
+    val["version"] = (METRICS_VERSION + 1).into();
+    let err = migrate_metrics(val).unwrap_err();
+    assert_eq!(err, MetricsLoadError::Newer { found: METRICS_VERSION as u64 + 1 });
+    assert!(err.to_string().contains("newer than this build supports"), "{err}");
+}
+
+#[test]
+fn unknown_schema_and_missing_version_are_invalid() {
+    let mut val = fixture(METRICS_VERSION);
+    val["schema"] = "something.else".into();
+    assert!(matches!(migrate_metrics(val), Err(MetricsLoadError::Invalid(_))));
+    assert!(matches!(migrate_metrics(serde_json::json!({"metrics": {}})), Err(MetricsLoadError::Invalid(_))));
+    assert!(matches!(migrate_metrics(serde_json::json!({"version": 2})), Err(MetricsLoadError::Invalid(_))));
+}
+
+#[test]
+fn loaded_var_deps_serialize_sorted() {
+    load_metrics_from(std::path::Path::new("tests/metrics_schema/v3.json")).unwrap();
+    let json = build_metrics_json();
+    assert_eq!(json["schema"], METRICS_SCHEMA);
+    assert_eq!(json["varReads"], serde_json::json!({"count": [1], "total": [0, 2]}));
+    let text = serde_json::to_string(&json["varReads"]).unwrap();
+    assert_eq!(text, r#"{"count":[1],"total":[0,2]}"#);
+}
@@ -0,0 +1,103 @@
+use aeonmi_project::cli::TopSort;
+use aeonmi_project::commands::metrics_top::{rank, render_table};
+use aeonmi_project::core::incremental::{FunctionInferenceMetric, FunctionSite};
+use aeonmi_project::core::style::Style;
+use std::collections::HashMap;
+
+fn metric(total_ns: u128, runs: u64, ema_ns: u128, site: Option<(&str, Option<&str>, usize)>) -> FunctionInferenceMetric {
+    FunctionInferenceMetric {
+        total_ns,
+        runs,
+        last_ns: ema_ns,
+        ema_ns,
+        site: site.map(|(name, file, line)| FunctionSite { name: name.to_string(), file: file.map(str::to_string), line }),
+        ..Default::default()
+    }
+}
+
+fn sample() -> HashMap<usize, FunctionInferenceMetric> {
+    HashMap::from([
+        (0, metric(6000, 3, 2500, Some(("parse_header", Some("src/io/reader.ai"), 3)))),
+        (2, metric(3000, 1, 3000, Some(("checksum_with_a_really_long_name", Some("examples/metrics/deeply/nested/util.ai"), 41)))),
+        (5, metric(1000, 4, 200, None)),
+    ])
+}
+
+#[test]
+fn table_shows_names_locations_and_share() {
+    let rows = rank(&sample(), TopSort::Ema, 0, 10);
+    assert_eq!(
+        render_table(&rows, 200, &Style::PLAIN),
+        "\
+idx  function                          location                                   runs  ema_ns  avg_ns  total_ns  last_ns  %total
+2    checksum_with_a_really_long_name  examples/metrics/deeply/nested/util.ai:41     1    3000    3000      3000     3000    30.0
+0    parse_header                      src/io/reader.ai:3                            3    2500    2000      6000     2500    60.0
+5    #5                                -                                             4     200     250      1000      200    10.0
+"
+    );
+}
+
+#[test]
+fn narrow_terminal_truncates_location_then_name() {
+    let rows = rank(&sample(), TopSort::Total, 0, 10);
+    let table = render_table(&rows, 96, &Style::PLAIN);
+    assert_eq!(
+        table,
+        "\
+idx  function                          location  runs  ema_ns  avg_ns  total_ns  last_ns  %total
+0    parse_header                      ~er.ai:3     3    2500    2000      6000     2500    60.0
+2    checksum_with_a_really_long_name  ~l.ai:41     1    3000    3000      3000     3000    30.0
+5    #5                                -            4     200     250      1000      200    10.0
+"
+    );
+    assert!(table.lines().all(|l| l.len() <= 96));
+    let tight = render_table(&rows, 60, &Style { color: false, unicode: true });
+    assert!(tight.contains("2    checksu…  …l.ai:41"), "{tight}");
+    // numeric columns are never cut, so the floor is the fixed part plus the minimum name/location widths
+    assert!(tight.lines().all(|l| l.chars().count() <= 72), "{tight}");
+}
+
+#[test]
+fn sort_keys_and_min_runs() {
+    let order = |sort, min_runs| rank(&sample(), sort, min_runs, 10).iter().map(|r| r.index).collect::<Vec<_>>();
+    assert_eq!(order(TopSort::Ema, 0), vec![2, 0, 5]);
+    assert_eq!(order(TopSort::Avg, 0), vec![2, 0, 5]);
+    assert_eq!(order(TopSort::Total, 0), vec![0, 2, 5]);
+    assert_eq!(order(TopSort::Runs, 0), vec![5, 0, 2]);
+    assert_eq!(order(TopSort::Ema, 3), vec![0, 5]);
+    // the share is of all recorded time, so filtering does not rescale it
+    let rows = rank(&sample(), TopSort::Runs, 0, 1);
+    assert_eq!((rows.len(), rows[0].pct_total), (1, 10.0));
+}
+
+#[cfg(feature = "debug-metrics")]
+#[test]
+fn cli_reports_injected_functions() {
+    use std::process::Command;
+    let home = tempfile::tempdir().unwrap();
+    let run = |args: &[&str]| {
+        let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+            .env("XDG_CONFIG_HOME", home.path())
+            .env("COLUMNS", "200")
+            .arg("--quiet")
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{args:?}: {}", String::from_utf8_lossy(&out.stderr));
+        String::from_utf8(out.stdout).unwrap()
+    };
+    run(&["metrics-inject-func", "--index", "0", "--dur", "400", "--name", "main", "--file", "app.ai", "--line", "12"]);
+    run(&["metrics-inject-func", "--index", "0", "--dur", "400"]);
+    run(&["metrics-inject-func", "--index", "3", "--dur", "1200", "--name", "helper", "--line", "2"]);
+
+    let v: serde_json::Value = serde_json::from_str(&run(&["metrics-top", "--json", "--sort", "total"])).unwrap();
+    assert_eq!(v[0]["name"], "helper", "{v:#}");
+    assert_eq!((v[0]["file"].clone(), v[0]["line"].clone()), (serde_json::Value::Null, serde_json::json!(2)));
+    assert_eq!((v[1]["name"].as_str(), v[1]["file"].as_str(), v[1]["runs"].as_u64()), (Some("main"), Some("app.ai"), Some(2)));
+    assert_eq!(v[1]["pct_total"], 40.0);
+
+    let table = run(&["metrics-top", "--min-runs", "2"]);
+    assert!(table.contains("sort=ema"), "{table}");
+    assert!(table.contains("0    main      app.ai:12"), "{table}");
+    assert!(!table.contains("helper"), "{table}");
+}
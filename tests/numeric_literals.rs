@@ -0,0 +1,98 @@
+use aeonmi_project::core::code_generator::CodeGenerator;
+use aeonmi_project::core::formatter::format_ai;
+use aeonmi_project::core::lexer::{Lexer, LexerError, LexerOptions};
+use aeonmi_project::core::parser::Parser;
+use aeonmi_project::core::token::TokenKind;
+
+fn lex_one(src: &str) -> Result<(f64, String), LexerError> {
+    let tokens = Lexer::from_str(src).tokenize()?;
+    match &tokens[0].kind {
+        TokenKind::NumberLiteral(n) => {
+            assert_eq!(tokens[1].kind, TokenKind::EOF, "{src}: trailing tokens {:?}", &tokens[1..]);
+            Ok((*n, tokens[0].lexeme.clone()))
+        }
+        other => panic!("{src}: expected a number, got {other:?}"),
+    }
+}
+
+#[test]
+fn valid_forms() {
+    let cases: &[(&str, f64)] = &[
+        ("0", 0.0),
+        ("42", 42.0),
+        ("3.25", 3.25),
+        ("7.", 7.0),
+        ("1_000_000", 1_000_000.0),
+        ("1_0.2_5", 10.25),
+        ("1e3", 1000.0),
+        ("1E3", 1000.0),
+        ("1e+3", 1000.0),
+        ("1e-9", 1e-9),
+        ("2.5e-3", 0.0025),
+        ("1e1_0", 1e10),
+        ("0xFF", 255.0),
+        ("0xff_ff", 65535.0),
+        ("0X1e", 30.0),
+        ("0b1010", 10.0),
+        ("0B1111_0000", 240.0),
+    ];
+    for (src, want) in cases {
+        let (n, lexeme) = lex_one(src).unwrap_or_else(|e| panic!("{src}: {e}"));
+        assert_eq!(n, *want, "{src}");
+        assert_eq!(lexeme, *src);
+    }
+}
+
+#[test]
+fn invalid_forms_are_one_error() {
+    for src in [
+        "1__2", "1_", "1_.5", "1._5", "1.2.3", "1..2", "0x", "0x_1", "0xG", "0b", "0b102", "0b_1", "1e", "1e+",
+        "1e_5", "1e5_", "12abc", "0x1.5", "1e999",
+    ] {
+        let toks = format!("let x = {src};");
+        match Lexer::from_str(&toks).tokenize() {
+            Err(LexerError::InvalidNumber(text, line, col)) => {
+                assert!(src.starts_with(&text), "{src}: reported {text:?}");
+                assert_eq!((line, col), (1, 9), "{src}");
+            }
+            other => panic!("{src}: expected InvalidNumber, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn exponent_sign_and_hex_digits_do_not_swallow_operators() {
+    // `1e` followed by `-x` is a malformed exponent, not `1e` minus `x`
+    assert!(matches!(Lexer::from_str("1e-x").tokenize(), Err(LexerError::InvalidNumber(t, _, _)) if t == "1e"));
+    // in hex, `E` is a digit and `-` stays an operator
+    let toks = Lexer::from_str("0x1E-1;").tokenize().unwrap();
+    let kinds: Vec<&TokenKind> = toks.iter().map(|t| &t.kind).collect();
+    assert_eq!(kinds, vec![&TokenKind::NumberLiteral(30.0), &TokenKind::Minus, &TokenKind::NumberLiteral(1.0), &TokenKind::Semicolon, &TokenKind::EOF]);
+}
+
+#[test]
+fn mixed_numeral_mode_accepts_new_forms() {
+    // U+1D361/U+1D362 are the glyph digits 1 and 2, mapped to ASCII before validation
+    let opts = LexerOptions { allow_mixed_numerals: true, ..Default::default() };
+    let lex = |src: &str| Lexer::with_options(src, opts.clone()).tokenize();
+    assert_eq!(lex("1\u{1D362}_000").unwrap()[0].kind, TokenKind::NumberLiteral(12000.0));
+    assert_eq!(lex("1\u{1D362}e2").unwrap()[0].kind, TokenKind::NumberLiteral(1200.0));
+    assert_eq!(lex("0x1\u{1D361}").unwrap()[0].kind, TokenKind::NumberLiteral(17.0));
+    assert!(matches!(lex("1\u{1D361}__2"), Err(LexerError::InvalidNumber(..))));
+}
+
+#[test]
+fn js_prints_values_in_decimal() {
+    let tokens = Lexer::from_str("let a = 0xFF; let b = 0b101; let c = 1_000; let d = 1e-9; let e = 1e300;").tokenize().unwrap();
+    let js = CodeGenerator::new().generate(&Parser::new(tokens).parse().unwrap()).unwrap();
+    for want in ["a = 255;", "b = 5;", "c = 1000;", "d = 0.000000001;"] {
+        assert!(js.contains(want), "missing {want:?} in {js}");
+    }
+    assert!(js.contains(&format!("e = 1{};", "0".repeat(300))), "large integral literals must not saturate: {js}");
+}
+
+#[test]
+fn formatter_keeps_literals_whole() {
+    assert_eq!(format_ai("let x=1E-9;let y = 0XfF+0B1_0;\n"), "let x=1e-9;\nlet y = 0xfF+0b1_0;\n");
+    assert_eq!(format_ai("let v1E2 = 2;\n"), "let v1E2 = 2;\n");
+}
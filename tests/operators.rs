@@ -0,0 +1,90 @@
+use aeonmi_project::commands::run::run_source_captured;
+use aeonmi_project::core::ast::ASTNode;
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::parser::Parser;
+use aeonmi_project::core::token::TokenKind;
+
+fn parse(source: &str) -> ASTNode {
+    let tokens = Lexer::from_str(source).tokenize().expect("lex");
+    Parser::new(tokens).parse().expect("parse")
+}
+
+/// The expression of the program's only statement.
+fn expr(source: &str) -> ASTNode {
+    match parse(source) {
+        ASTNode::Program(mut items) => items.remove(0),
+        other => panic!("{other:?}"),
+    }
+}
+
+#[test]
+fn lexer_keeps_star_star_whole() {
+    let kinds: Vec<_> = Lexer::from_str("a ** b * c % d").tokenize().unwrap().into_iter().map(|t| t.kind).collect();
+    assert_eq!(kinds[1], TokenKind::StarStar);
+    assert_eq!(kinds[3], TokenKind::Star);
+    assert_eq!(kinds[5], TokenKind::Percent);
+}
+
+#[test]
+fn exponent_is_right_associative_and_binds_tighter_than_unary_minus() {
+    // -a ** b is -(a ** b)
+    let ASTNode::UnaryExpr { op: TokenKind::Minus, expr: inner } = expr("-a ** b;") else { panic!() };
+    assert!(matches!(*inner, ASTNode::BinaryExpr { op: TokenKind::StarStar, .. }));
+
+    // a ** b ** c is a ** (b ** c)
+    let ASTNode::BinaryExpr { op: TokenKind::StarStar, left, right } = expr("a ** b ** c;") else { panic!() };
+    assert!(matches!(*left, ASTNode::Identifier(_) | ASTNode::IdentifierSpanned { .. }));
+    assert!(matches!(*right, ASTNode::BinaryExpr { op: TokenKind::StarStar, .. }));
+
+    // % sits with * and /, left to right
+    let ASTNode::BinaryExpr { op: TokenKind::Star, left, .. } = expr("a % b * c;") else { panic!() };
+    assert!(matches!(*left, ASTNode::BinaryExpr { op: TokenKind::Percent, .. }));
+
+    let run = run_source_captured("log(-2 ** 2);\nlog(2 ** 3 ** 2);\nlog(-7 % 3);\nlog(7.5 % 2);\n").unwrap();
+    assert_eq!(run.output, "-4\n512\n-1\n1.5\n");
+}
+
+#[test]
+fn division_and_modulo_by_zero_are_runtime_errors() {
+    let err = run_source_captured("let z = 0;\nlog(\"before\");\nlog(1 / z);\n").unwrap_err();
+    assert!(err.contains("division by zero (line 3"), "{err}");
+    let err = run_source_captured("let z = 0;\nlet r = 5 % z;\n").unwrap_err();
+    assert!(err.contains("modulo by zero (line 2"), "{err}");
+    // zero on the left is fine
+    assert_eq!(run_source_captured("log(0 / 4);\nlog(0 % 4);\n").unwrap().output, "0\n0\n");
+}
+
+#[cfg(feature = "bytecode")]
+mod bytecode {
+    use super::parse;
+    use aeonmi_project::core::bytecode::{disassemble, BytecodeCompiler};
+    use aeonmi_project::core::vm::OutputBuffer;
+    use aeonmi_project::core::vm_bytecode::VM;
+
+    #[test]
+    fn exponent_and_modulo_fold_to_one_constant() {
+        let chunk = BytecodeCompiler::new().compile(&parse("log(2 ** 3 % 5);"));
+        assert_eq!(chunk.opt_stats.const_folds, 1);
+        let listing = disassemble(&chunk);
+        assert!(listing.contains("[0] num 3"), "{listing}");
+        assert!(!listing.contains("POW") && !listing.contains("MOD"), "{listing}");
+
+        // with a variable operand the ops are emitted
+        let chunk = BytecodeCompiler::new().compile(&parse("let x = 2; log(x ** 3 % 5);"));
+        let listing = disassemble(&chunk);
+        assert!(listing.contains("POW") && listing.contains("MOD"), "{listing}");
+    }
+
+    #[test]
+    fn division_by_zero_stops_the_vm() {
+        // a constant zero divisor is not folded away
+        let chunk = BytecodeCompiler::new().compile(&parse("log(1); log(4 % 0); log(2);"));
+        assert!(disassemble(&chunk).contains("MOD"));
+        let buf = OutputBuffer::new();
+        let mut vm = VM::new(&chunk);
+        vm.set_output(Box::new(buf.clone()));
+        vm.run();
+        assert_eq!(vm.error.as_deref(), Some("modulo by zero"));
+        assert_eq!(buf.contents(), "1\n");
+    }
+}
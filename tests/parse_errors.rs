@@ -0,0 +1,44 @@
+//! Rendered parse errors: expected-token sets and the hints for common mistakes.
+use aeonmi_project::core::diagnostics::{parse_error_parts, render_error_with_notes};
+use aeonmi_project::core::error_index::PARSE_ERROR;
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::parser::{Parser, ParserError};
+use aeonmi_project::core::style::Style;
+
+fn parse_err(src: &str) -> ParserError {
+    Parser::new(Lexer::from_str(src).tokenize().unwrap()).parse().unwrap_err()
+}
+
+fn render(src: &str) -> String {
+    let err = parse_err(src);
+    let (title, span, notes) = parse_error_parts(&err);
+    render_error_with_notes(&Style::PLAIN, "demo.ai", src, &title, &span, Some(PARSE_ERROR), &notes)
+}
+
+#[test]
+fn missing_semicolon_before_next_statement() {
+    insta::assert_snapshot!(render("let total = 5\nlog(total);\n"));
+}
+
+#[test]
+fn unclosed_brace_at_end_of_file() {
+    let err = parse_err("function f(n) {\n    log(n);\n\n");
+    assert_eq!(err.detail.related.len(), 1);
+    assert_eq!((err.detail.related[0].line, err.detail.related[0].column), (1, 15));
+    insta::assert_snapshot!(render("function f(n) {\n    log(n);\n\n"));
+}
+
+#[test]
+fn assignment_in_condition() {
+    insta::assert_snapshot!(render("let x = 1;\nwhile (x = 2) {\n    log(x);\n}\n"));
+}
+
+#[test]
+fn expected_sets_fold_operators() {
+    assert_eq!(parse_err("log(f(1, 2;\n").summary(), "expected one of: ',', ')', an operator — found ';'");
+    assert_eq!(parse_err("let = 4;\n").summary(), "expected one of: a name, '[' — found '='");
+    assert_eq!(parse_err("log(1 + );\n").summary(), "expected an expression — found ')'");
+    let err = parse_err("if x { }\n");
+    assert_eq!(err.summary(), "expected '(' — found 'x'");
+    assert_eq!(err.detail.hint, None);
+}
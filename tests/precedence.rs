@@ -34,3 +34,51 @@ fn unary_then_binary() {
 
     assert!(js.contains("let y = ((-1) + 2);") || js.contains("let y = (-1 + 2);"));
 }
+
+/// Fully parenthesized rendering of the parsed expression statement.
+fn shape(code: &str) -> String {
+    use aeonmi_project::core::ast::ASTNode;
+    fn go(n: &ASTNode) -> String {
+        match n {
+            ASTNode::BinaryExpr { op, left, right } => format!("({} {} {})", go(left), op, go(right)),
+            ASTNode::UnaryExpr { op, expr } => format!("({}{})", op, go(expr)),
+            ASTNode::Assignment { name, value, .. } => format!("({} = {})", name, go(value)),
+            ASTNode::IdentifierSpanned { name, .. } | ASTNode::Identifier(name) => name.clone(),
+            ASTNode::NumberLiteral(v) => v.to_string(),
+            ASTNode::BooleanLiteral(b) => b.to_string(),
+            other => panic!("unexpected node {other:?}"),
+        }
+    }
+    let toks = Lexer::from_str(code).tokenize().unwrap();
+    match Parser::new(toks).parse().unwrap() {
+        ASTNode::Program(items) => go(&items[0]),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn precedence_table() {
+    assert_eq!(shape("a || b && c"), "(a || (b && c))");
+    assert_eq!(shape("a && b || c"), "((a && b) || c)");
+    assert_eq!(shape("a < b && b < c"), "((a < b) && (b < c))");
+    assert_eq!(shape("a == b < c"), "(a == (b < c))");
+    assert_eq!(shape("a + b < c * d"), "((a + b) < (c * d))");
+    assert_eq!(shape("!a && b"), "((!a) && b)");
+    assert_eq!(shape("!a == b"), "((!a) == b)");
+    assert_eq!(shape("-a * b"), "((-a) * b)");
+    assert_eq!(shape("a - b - c"), "((a - b) - c)");
+    assert_eq!(shape("a < b < c"), "((a < b) < c)");
+    // assignment is loosest and right-associative
+    assert_eq!(shape("x = a || b"), "(x = (a || b))");
+    assert_eq!(shape("x = y = a && b"), "(x = (y = (a && b)))");
+}
+
+#[test]
+fn bang_is_distinct_from_not_equals() {
+    assert_eq!(shape("a != !b"), "(a != (!b))");
+    assert_eq!(shape("!!a"), "(!(!a))");
+    let toks = Lexer::from_str("x = !y;").tokenize().unwrap();
+    let mut cg = CodeGenerator::new();
+    let js = cg.generate(&Parser::new(toks).parse().unwrap()).unwrap();
+    assert!(js.contains("x = !y"), "{js}");
+}
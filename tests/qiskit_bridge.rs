@@ -1,7 +1,26 @@
 #[cfg(feature = "qiskit")]
 mod q {
+    use aeonmi_project::core::quantum_ir::{Circuit, Op, OpKind};
     use aeonmi_project::core::titan::{gates, qiskit_bridge};
 
+    /// Qiskit and Aer importable from the embedded interpreter.
+    fn python_available() -> bool {
+        let ok = qiskit_bridge::qiskit_version().is_ok();
+        if !ok {
+            eprintln!("skipping: qiskit is not importable");
+        }
+        ok
+    }
+
+    /// Pairs of self-inverse gates: a level 3 transpile cancels all of them.
+    fn redundant() -> Circuit {
+        let mut c = Circuit::new(2);
+        for (kind, targets) in [(OpKind::H, vec![0]), (OpKind::H, vec![0]), (OpKind::CNOT, vec![0, 1]), (OpKind::CNOT, vec![0, 1]), (OpKind::X, vec![1]), (OpKind::X, vec![1])] {
+            c.push(Op { kind, targets, params: vec![] });
+        }
+        c
+    }
+
     #[test]
     fn qiskit_version_ok() {
         let v = qiskit_bridge::qiskit_version().unwrap();
@@ -18,4 +37,29 @@ mod q {
         println!("H shots => 0: {}, 1: {}", c0, c1);
         assert!(diff < 400, "imbalanced: {c0} vs {c1}");
     }
+
+    #[test]
+    fn level_3_transpiles_shallower_than_level_0() {
+        if !python_available() {
+            return;
+        }
+        let l0 = qiskit_bridge::run_circuit(&redundant(), 200, Some(0), &[], None).unwrap();
+        let l3 = qiskit_bridge::run_circuit(&redundant(), 200, Some(3), &[], None).unwrap();
+        assert_eq!(l0.transpile.depth_before, l3.transpile.depth_before);
+        assert_eq!(l0.transpile.gates_after.get("cx"), Some(&2));
+        assert!(l3.transpile.depth_after < l0.transpile.depth_after, "level 3 {:?} vs level 0 {:?}", l3.transpile, l0.transpile);
+        // Every gate cancels, so both runs only ever see |00>.
+        assert_eq!(l3.counts.get("00"), Some(&200));
+        assert_eq!(l0.counts.get("00"), Some(&200));
+    }
+
+    #[test]
+    fn python_errors_become_readable_messages() {
+        if !python_available() {
+            return;
+        }
+        let err = qiskit_bridge::run_circuit(&redundant(), 10, Some(1), &[], Some("NoSuchBackend")).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.starts_with("qiskit: ValueError: unknown fake backend 'NoSuchBackend'"), "{msg}");
+    }
 }
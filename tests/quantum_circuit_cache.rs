@@ -0,0 +1,44 @@
+use aeonmi_project::core::incremental::{build_metrics_json, parse_or_cached, quantum_circuit_cached};
+use aeonmi_project::core::quantum_extract::extract_circuit;
+
+const PROGRAM: &str = "function prep(a, b) { superpose(a); entangle(a, b); }
+function score(n) { return n * 2; }
+function readout(q) { let m = measure(q); if (m == 1) { dod(q); } }
+";
+
+/// Circuit cache hits and misses since the last call.
+fn counters(last: &mut (u64, u64)) -> (u64, u64) {
+    let v = build_metrics_json();
+    let now = (v["quantumExtract"]["hits"].as_u64().unwrap(), v["quantumExtract"]["misses"].as_u64().unwrap());
+    let delta = (now.0 - last.0, now.1 - last.1);
+    *last = now;
+    delta
+}
+
+fn check(source: &str) {
+    let cached = quantum_circuit_cached(source).unwrap();
+    assert_eq!(cached, extract_circuit(&parse_or_cached(source).unwrap()), "{source}");
+}
+
+#[test]
+fn only_edited_quantum_functions_are_re_extracted() {
+    let mut last = (0, 0);
+    counters(&mut last);
+    check(PROGRAM);
+    assert_eq!(counters(&mut last), (0, 2), "first extraction: both quantum functions");
+    check(PROGRAM);
+    assert_eq!(counters(&mut last), (2, 0), "same source: everything reused");
+
+    let classical = PROGRAM.replace("n * 2", "n * 3");
+    check(&classical);
+    assert_eq!(counters(&mut last), (2, 0), "a classical edit is a full hit");
+
+    let quantum = classical.replace("superpose(a)", "superpose(b)");
+    check(&quantum);
+    assert_eq!(counters(&mut last), (1, 1), "one quantum function changed");
+
+    // a measurement stored before `readout` changes the bits it starts with, so it is re-extracted too
+    let measured = quantum.replace("function score(n) { return n * 3; }", "let m = measure(x);");
+    check(&measured);
+    assert_eq!(counters(&mut last), (1, 2));
+}
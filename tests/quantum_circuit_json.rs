@@ -0,0 +1,75 @@
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::parser::Parser;
+use aeonmi_project::core::quantum_extract::{circuit_from_json, circuit_to_ai, circuit_to_json, extract_circuit, QuantumCircuit};
+use std::process::Command;
+
+const SRC: &str = "qubit a;\nqubit b;\nqreg r[3];\nsuperpose(a);\nentangle(a, r[1]);\ndod(b);\nentangle(r[1], r[2]);\nmeasure(a);\nmeasure(r[2]);\n";
+
+fn extract(src: &str) -> QuantumCircuit {
+    let tokens = Lexer::from_str(src).tokenize().unwrap();
+    extract_circuit(&Parser::new(tokens).parse().unwrap())
+}
+
+#[test]
+fn json_round_trips_and_reextracts() {
+    let original = extract(SRC);
+    let json = circuit_to_json(&original);
+    let v: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(v["version"], 1);
+    assert_eq!(v["qubits"], serde_json::json!(["a", "b", "r[0]", "r[1]", "r[2]"]));
+    assert_eq!(v["ops"][1], serde_json::json!({"name": "entangle", "targets": ["a", "r[1]"], "params": [], "line": 5}));
+    assert_eq!(v["measurements"], serde_json::json!([{"op": 4, "target": "a"}, {"op": 5, "target": "r[2]"}]));
+
+    let back = circuit_from_json(&json).unwrap();
+    assert_eq!(back, original);
+
+    // the synthesized program extracts to the same circuit (lines aside)
+    let program = circuit_to_ai(&back);
+    let mut again = extract(&program);
+    assert_eq!(again.wires, original.wires);
+    assert_eq!(again.registers, original.registers);
+    for (g, o) in again.gates.iter_mut().zip(&original.gates) {
+        g.line = o.line;
+    }
+    assert_eq!(again, original);
+}
+
+#[test]
+fn validation_names_the_offending_op() {
+    let doc = |ops: &str| format!(r#"{{"version": 1, "qubits": ["a", "b"], "ops": [{{"name": "superpose", "targets": ["a"]}}, {ops}]}}"#);
+    let err = |ops: &str| circuit_from_json(&doc(ops)).unwrap_err().to_string();
+    assert_eq!(err(r#"{"name": "toffoli", "targets": ["a"]}"#), "op #1: unknown op 'toffoli' (expected one of: superpose, entangle, measure, dod)");
+    assert_eq!(err(r#"{"name": "entangle", "targets": ["a"]}"#), "op #1: entangle needs at least 2 target(s), got 1");
+    assert_eq!(err(r#"{"name": "measure", "targets": ["z"]}"#), "op #1: target 'z' is not in \"qubits\"");
+    assert_eq!(err(r#"{"name": "dod", "targets": ["b"], "params": [0.5]}"#), "op #1: dod takes no params, got 1");
+
+    let future = circuit_from_json(r#"{"version": 2, "qubits": [], "ops": []}"#).unwrap_err();
+    assert!(future.op.is_none() && future.message.contains("unsupported circuit schema version 2"));
+    let bad_reg = circuit_from_json(r#"{"version": 1, "qubits": ["r[3]"], "registers": [{"name": "r", "size": 3}], "ops": []}"#).unwrap_err();
+    assert_eq!(bad_reg.to_string(), "qubit 'r[3]' is outside register 'r' (size 3)");
+}
+
+#[test]
+fn cli_import_emits_runnable_program() {
+    let dir = tempfile::tempdir().unwrap();
+    let json = dir.path().join("bell.json");
+    std::fs::write(&json, circuit_to_json(&extract("qubit a;\nqubit b;\nsuperpose(a);\nentangle(a, b);\n"))).unwrap();
+    let ai = dir.path().join("bell.ai");
+    let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .args(["--quiet", "quantum", "import"])
+        .arg(&json)
+        .arg("--out")
+        .arg(&ai)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    let program = std::fs::read_to_string(&ai).unwrap();
+    assert!(program.ends_with("qubit a;\nqubit b;\nsuperpose(a);\nentangle(a, b);\n"), "{program}");
+    let run = Command::new(env!("CARGO_BIN_EXE_aeonmi_project")).args(["--quiet", "run", "--native"]).arg(&ai).output().unwrap();
+    assert!(run.status.success(), "stderr: {}", String::from_utf8_lossy(&run.stderr));
+
+    std::fs::write(&json, r#"{"version": 1, "qubits": ["a"], "ops": [{"name": "entangle", "targets": ["a"]}]}"#).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project")).args(["quantum", "import"]).arg(&json).output().unwrap();
+    assert_eq!(out.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("op #0: entangle needs at least 2 target(s)"));
+}
@@ -0,0 +1,85 @@
+use std::process::Command;
+
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::parser::Parser;
+use aeonmi_project::core::quantum_extract::{
+    circuit_from_json, circuit_to_ascii, circuit_to_json, circuit_to_pseudo_qasm, extract_circuit, ClassicalCondition, QuantumCircuit,
+};
+
+fn extract(src: &str) -> QuantumCircuit {
+    let tokens = Lexer::from_str(src).tokenize().unwrap();
+    extract_circuit(&Parser::new(tokens).parse().unwrap())
+}
+
+#[test]
+fn teleportation_exports_the_conditional_correction() {
+    let dir = tempfile::tempdir().unwrap();
+    let out_path = dir.path().join("teleport.qasm");
+    let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .args(["export", "examples/quantum_teleportation.ai", "--format", "qasm", "--out", out_path.to_str().unwrap()])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let qasm = std::fs::read_to_string(&out_path).unwrap();
+    let lines: Vec<&str> = qasm.lines().collect();
+    assert_eq!(lines[..4], ["qreg q[3];", "bit alice_result1;", "bit alice_result2;", "bit final_result;"], "{qasm}");
+    assert!(lines.contains(&"alice_result2 = measure q[2];// line22"), "{qasm}");
+    let conditional: Vec<&str> = lines.iter().copied().filter(|l| l.starts_with("if ")).collect();
+    assert_eq!(conditional, ["if (alice_result2 == 1) dod q[1];// line27"], "{qasm}");
+}
+
+#[test]
+fn bit_tests_attach_conditions_and_else_flips_them() {
+    let circ = extract(
+        "qubit a;\nqubit b;\nlet m = measure(a);\nif (!m) { dod(b); } else { superpose(b); }\nif (0 != m) { dod(a); }\nif (measure(b)) { dod(a); }\n",
+    );
+    let cond = |bit: &str, value| Some(ClassicalCondition { bit: bit.into(), value });
+    let conditions: Vec<_> = circ.gates.iter().map(|g| g.condition.clone()).collect();
+    assert_eq!(conditions, [None, cond("m", 0), cond("m", 1), cond("m", 1), None, cond("c0", 1)]);
+    // a measurement right in the condition stores into a fresh bit
+    assert_eq!(circ.clbits, ["m", "c0"]);
+    assert_eq!(circ.gates[4].bit.as_deref(), Some("c0"));
+    assert!(circ.diagnostics.is_empty(), "{:?}", circ.diagnostics);
+
+    // conditions survive the JSON round trip
+    let back = circuit_from_json(&circuit_to_json(&circ)).unwrap();
+    assert_eq!(back.gates, circ.gates);
+    assert_eq!(back.clbits, circ.clbits);
+    let mut doc: serde_json::Value = serde_json::from_str(&circuit_to_json(&circ)).unwrap();
+    doc["ops"][0].as_object_mut().unwrap().remove("bit");
+    let err = circuit_from_json(&doc.to_string()).unwrap_err();
+    assert!(err.message.contains("before a measure stores it"), "{err}");
+}
+
+#[test]
+fn other_conditions_around_gates_point_at_the_expression() {
+    let circ = extract("qubit q;\nlet n = 2;\nlet m = measure(q);\nif (n > 1) { log(n); }\nif (m == 2) {\n  dod(q);\n}\n");
+    assert_eq!(circ.diagnostics.len(), 1, "{:?}", circ.diagnostics);
+    assert_eq!((circ.diagnostics[0].line, circ.diagnostics[0].column), (5, 5));
+    // the gate is still extracted, just without a condition
+    assert_eq!(circ.gates[1].condition, None);
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("nested.ai"), "qubit q;\nlet m = measure(q);\nif (m) {\n  if (m == 0) { dod(q); }\n}\n").unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .current_dir(dir.path())
+        .env("NO_COLOR", "1")
+        .args(["export", "nested.ai", "--format", "qasm", "--out", "-"])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("nested.ai:4:7: export: nested classical conditions"), "{stderr}");
+}
+
+#[test]
+fn ascii_draws_classical_bits_as_double_lines() {
+    let circ = extract("qubit a;\nqubit b;\nlet m = measure(a);\nif (m == 1) { dod(b); }\n");
+    let ascii = circuit_to_ascii(&circ);
+    let rows: Vec<&str> = ascii.lines().collect();
+    assert_eq!(rows, ["a ─M─────", "b ─╫──X──", "m ═╩══●══"], "{ascii}");
+    assert!(circuit_to_pseudo_qasm(&circ).ends_with("m = measure q[0];// line3\nif (m == 1) dod q[1];// line4\n"));
+
+    // no bits, no classical rows
+    assert_eq!(circuit_to_ascii(&extract("qubit a;\nmeasure(a);\n")).lines().count(), 1);
+}
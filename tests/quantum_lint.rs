@@ -0,0 +1,65 @@
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::parser::Parser as AeParser;
+use aeonmi_project::core::semantic_analyzer::{SemanticAnalyzer, SemanticDiagnostic, Severity};
+
+fn lint(src: &str) -> Vec<SemanticDiagnostic> {
+    let toks = Lexer::from_str(src).tokenize().unwrap();
+    let ast = AeParser::new(toks).parse().unwrap();
+    SemanticAnalyzer::new()
+        .analyze_with_spans(&ast)
+        .into_iter()
+        .filter(|d| d.rule.is_some_and(|r| r.starts_with("AEO-Q")))
+        .collect()
+}
+
+fn rules(ds: &[SemanticDiagnostic]) -> Vec<&'static str> {
+    ds.iter().filter_map(|d| d.rule).collect()
+}
+
+#[test]
+fn bell_pair_is_clean() {
+    let src = "qubit a;\nqubit b;\nsuperpose(a);\nentangle(a, b);\nmeasure(a);\nmeasure(b);\n";
+    let ds = lint(src);
+    assert!(ds.is_empty(), "unexpected warnings: {ds:?}");
+}
+
+#[test]
+fn double_measure_warns_at_second_measure() {
+    let src = "qubit q;\nsuperpose(q);\nmeasure(q);\nmeasure(q);\n";
+    let ds = lint(src);
+    assert_eq!(rules(&ds), vec!["AEO-Q001"]);
+    assert_eq!((ds[0].line, ds[0].column), (4, 1));
+    assert!(matches!(ds[0].severity, Severity::Warning));
+    assert!(ds[0].message.contains("already measured at 3:1"), "{}", ds[0].message);
+}
+
+#[test]
+fn gate_after_measure_warns() {
+    let src = "qubit q;\nmeasure(q);\nsuperpose(q);\n";
+    let ds = lint(src);
+    assert_eq!(rules(&ds), vec!["AEO-Q002"]);
+    assert_eq!(ds[0].line, 3);
+}
+
+#[test]
+fn self_entangle_warns() {
+    let src = "qubit q;\nentangle(q, q);\nmeasure(q);\n";
+    let ds = lint(src);
+    assert_eq!(rules(&ds), vec!["AEO-Q003"]);
+    assert_eq!((ds[0].line, ds[0].column, ds[0].len), (2, 1, "entangle".len()));
+}
+
+#[test]
+fn unmeasured_qubit_warns_on_declaration() {
+    let src = "qubit a;\nqubit b;\nsuperpose(a);\nmeasure(a);\n";
+    let ds = lint(src);
+    assert_eq!(rules(&ds), vec!["AEO-Q004"]);
+    assert_eq!(ds[0].line, 2);
+    assert!(ds[0].message.contains("'b'"), "{}", ds[0].message);
+}
+
+#[test]
+fn measure_in_one_branch_does_not_collapse() {
+    let src = "qubit q;\nif (true) { measure(q); }\nmeasure(q);\n";
+    assert!(lint(src).is_empty());
+}
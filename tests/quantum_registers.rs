@@ -0,0 +1,85 @@
+use aeonmi_project::commands::run::run_source_captured;
+use aeonmi_project::core::ast::ASTNode;
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::parser::Parser as AeParser;
+use aeonmi_project::core::quantum_extract::{circuit_to_ascii, circuit_to_pseudo_qasm, extract_circuit};
+use aeonmi_project::core::semantic_analyzer::{SemanticAnalyzer, Severity};
+
+fn parse(src: &str) -> ASTNode {
+    let toks = Lexer::from_str(src).tokenize().unwrap();
+    AeParser::new(toks).parse().unwrap()
+}
+
+const GHZ: &str = r#"
+function ghz() {
+  qreg r[3];
+  superpose(r[0]);
+  let i = 0;
+  while (i < 2) {
+    entangle(r[i], r[i + 1]);
+    i = i + 1;
+  }
+  measure(r[0]);
+  measure(r[1]);
+  measure(r[2]);
+}
+log(run_shots(200, ghz));
+"#;
+
+#[test]
+fn literal_index_out_of_bounds_is_a_semantic_error() {
+    let ast = parse("qreg r[3];\nsuperpose(r[2]);\nsuperpose(r[3]);\n");
+    let ds = SemanticAnalyzer::new().analyze_with_spans(&ast);
+    let oob: Vec<_> = ds.iter().filter(|d| d.rule == Some("AEO-Q005")).collect();
+    assert_eq!(oob.len(), 1, "{ds:?}");
+    assert_eq!(oob[0].severity, Severity::Error);
+    assert_eq!(oob[0].line, 3);
+    assert!(oob[0].message.contains("out of bounds"), "{}", oob[0].message);
+}
+
+#[test]
+fn ghz_over_register_loop_only_yields_all_zero_or_all_one() {
+    let run = run_source_captured(GHZ).expect("program should run");
+    let hist = run.output.trim().trim_matches(|c| c == '{' || c == '}').to_string();
+    let keys: Vec<&str> = hist.split(", ").map(|p| p.split_once(": ").unwrap().0).collect();
+    assert!(!keys.is_empty() && keys.iter().all(|k| *k == "000" || *k == "111"), "{}", run.output);
+    let total: usize = hist.split(", ").map(|p| p.split_once(": ").unwrap().1.parse::<usize>().unwrap()).sum();
+    assert_eq!(total, 200);
+}
+
+#[test]
+fn computed_index_out_of_bounds_fails_at_runtime() {
+    let err = run_source_captured("qreg r[2];\nlet i = 5;\nsuperpose(r[i]);\n").expect_err("index 5 must fail");
+    let msg = format!("{err:#}");
+    assert!(msg.contains("out of bounds"), "{msg}");
+}
+
+#[test]
+fn oversized_registers_fail_with_the_qubit_limit() {
+    for size in ["17", "1e9"] {
+        let err = run_source_captured(&format!("qreg r[{size}];\n")).expect_err("register must be rejected");
+        let msg = format!("{err:#}");
+        assert!(msg.contains("exceeds the 16-qubit limit"), "{size}: {msg}");
+    }
+}
+
+#[test]
+fn circuit_exports_label_register_wires() {
+    let ast = parse("qreg r[3];\nsuperpose(r[0]);\nentangle(r[0], r[2]);\nmeasure(r[2]);\n");
+    let circ = extract_circuit(&ast);
+    assert_eq!(circ.wires, vec!["r[0]", "r[1]", "r[2]"]);
+    assert_eq!(circ.qubit_count, 3);
+    assert_eq!(circ.gates[1].qubits, vec!["r[0]", "r[2]"]);
+
+    let qasm = circuit_to_pseudo_qasm(&circ);
+    assert!(qasm.starts_with("qreg r[3];\n"), "{qasm}");
+    assert!(!qasm.contains("qreg q["), "{qasm}");
+    assert!(qasm.contains("entangle r[0],r[2];"), "{qasm}");
+
+    let ascii = circuit_to_ascii(&circ);
+    let rows: Vec<&str> = ascii.lines().collect();
+    assert_eq!(rows.len(), 3, "{ascii}");
+    assert!(rows[0].starts_with("r[0] ─H──●──"), "{ascii}");
+    assert!(rows[1].starts_with("r[1] ────│──"), "{ascii}");
+    assert!(rows[2].starts_with("r[2] ────⊕──M"), "{ascii}");
+}
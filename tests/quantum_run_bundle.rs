@@ -0,0 +1,108 @@
+use aeonmi_project::core::run_bundle::{self, Histogram, RunBundle, RunManifest};
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn aeonmi(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .current_dir(dir)
+        .args(args)
+        .env("AEONMI_UNICODE", "0")
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("failed to run aeonmi_project")
+}
+
+fn hist(pairs: &[(&str, usize)]) -> Histogram {
+    pairs.iter().map(|(k, v)| (k.to_string(), *v)).collect()
+}
+
+fn bundle(seed: u64, histogram: Histogram) -> RunBundle {
+    let source = "qubits 1\nh 0\n".to_string();
+    RunBundle {
+        manifest: RunManifest {
+            schema: run_bundle::SCHEMA,
+            compiler_version: "test".into(),
+            source_file: "coin.ai".into(),
+            source_sha1: run_bundle::source_sha1(&source),
+            backend: "titan".into(),
+            qubits: 1,
+            shots: histogram.values().sum(),
+            seed,
+            noise: None,
+            wall_time_ms: 1.0,
+            created_at: "2026-01-01T00:00:00+00:00".into(),
+            files: Vec::new(),
+        },
+        source,
+        circuit: serde_json::json!({ "n_qubits": 1 }),
+        histogram,
+        statevector: None,
+    }
+}
+
+#[test]
+fn total_variation_distance() {
+    let fair = hist(&[("0", 50), ("1", 50)]);
+    assert_eq!(run_bundle::total_variation(&fair, &hist(&[("0", 5), ("1", 5)])), 0.0);
+    assert!((run_bundle::total_variation(&fair, &hist(&[("0", 75), ("1", 25)])) - 0.25).abs() < 1e-12);
+    assert_eq!(run_bundle::total_variation(&hist(&[("00", 3)]), &hist(&[("11", 9)])), 1.0);
+    assert_eq!(run_bundle::total_variation(&Histogram::new(), &Histogram::new()), 0.0);
+}
+
+#[test]
+fn compare_reads_bundles_back() {
+    let dir = tempfile::tempdir().unwrap();
+    bundle(1, hist(&[("0", 60), ("1", 40)])).write(&dir.path().join("a")).unwrap();
+    bundle(2, hist(&[("0", 40), ("1", 60)])).write(&dir.path().join("b")).unwrap();
+    let loaded = RunBundle::load(&dir.path().join("a")).unwrap();
+    assert_eq!(loaded.manifest.files, ["source.ai", "circuit.json", "histogram.json"]);
+    assert_eq!(loaded.source, "qubits 1\nh 0\n");
+
+    let out = aeonmi(dir.path(), &["quantum", "compare", "a", "b", "--json"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert!((v["total_variation"].as_f64().unwrap() - 0.2).abs() < 1e-12, "{v}");
+    assert_eq!(v["same_source"], true);
+
+    let out = aeonmi(dir.path(), &["quantum", "compare", "a", "b"]);
+    let text = String::from_utf8_lossy(&out.stdout);
+    assert!(text.contains("total variation distance: 0.2000"), "{text}");
+    assert!(text.contains("seed 1") && text.contains("seed 2"), "{text}");
+
+    let out = aeonmi(dir.path(), &["quantum", "compare", "a", "missing"]);
+    assert_eq!(out.status.code(), Some(5));
+}
+
+#[cfg(feature = "quantum")]
+#[test]
+fn seeded_runs_are_reproducible() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("bell.ai"), "qubits 2\nh 0\ncnot 0 1\n").unwrap();
+    for (name, seed) in [("r1", "7"), ("r2", "7"), ("r3", "8")] {
+        let out = aeonmi(dir.path(), &["quantum", "titan", "bell.ai", "--shots", "500", "--seed", seed, "--save-run", name]);
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    }
+    let r1 = RunBundle::load(&dir.path().join("r1")).unwrap();
+    assert_eq!(r1.manifest.seed, 7);
+    assert_eq!(r1.manifest.shots, 500);
+    assert_eq!(r1.manifest.compiler_version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(r1.histogram.keys().collect::<Vec<_>>(), ["00", "11"], "a Bell pair only measures 00 or 11");
+    assert_eq!(r1.statevector.as_ref().map(Vec::len), Some(4));
+    assert_eq!(r1.circuit["ops"][1]["kind"], "cnot");
+
+    let tvd = |b: &str| {
+        let out = aeonmi(dir.path(), &["quantum", "compare", "r1", b, "--json"]);
+        let v: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+        v["total_variation"].as_f64().unwrap()
+    };
+    assert_eq!(tvd("r2"), 0.0);
+    assert!(tvd("r3") > 0.0);
+
+    // Outcome strings list qubit 0 first.
+    std::fs::write(dir.path().join("flip.ai"), "qubits 2\nx 1\n").unwrap();
+    let out = aeonmi(dir.path(), &["quantum", "titan", "flip.ai", "--shots", "10"]);
+    assert!(String::from_utf8_lossy(&out.stdout).contains("Titan shots => 01: 10"), "{}", String::from_utf8_lossy(&out.stdout));
+
+    let out = aeonmi(dir.path(), &["quantum", "aer", "bell.ai", "--save-run", "r4"]);
+    assert!(!out.status.success());
+}
@@ -0,0 +1,59 @@
+use aeonmi_project::commands::run::run_source_captured;
+use aeonmi_project::core::examples;
+use aeonmi_project::core::quantum_templates::{find, instantiate, TEMPLATES};
+use std::collections::BTreeMap;
+
+fn params(pairs: &[(&str, usize)]) -> BTreeMap<String, usize> {
+    pairs.iter().map(|(k, v)| (k.to_string(), *v)).collect()
+}
+
+/// Histogram keys printed by the template's `run_shots` line.
+fn outcomes(source: &str) -> Vec<String> {
+    let out = run_source_captured(source).expect("run").output;
+    let hist = out.lines().last().expect("histogram").trim_matches(|c| c == '{' || c == '}').to_string();
+    hist.split(", ").map(|kv| kv.split(':').next().unwrap().to_string()).collect()
+}
+
+#[test]
+fn every_template_compiles_across_its_parameter_range() {
+    for t in TEMPLATES {
+        let mut combos = vec![BTreeMap::new()];
+        for p in t.params.iter().filter(|p| p.name != "shots") {
+            combos = combos.into_iter().flat_map(|c| (p.min..=p.max).map(move |v| { let mut c = c.clone(); c.insert(p.name.to_string(), v); c })).collect();
+        }
+        for mut c in combos {
+            c.insert("shots".into(), 20);
+            let src = instantiate(t.name, &c).unwrap_or_else(|e| panic!("{} {c:?}: {e}", t.name));
+            assert!(src.starts_with(&format!("// template {} (", t.name)), "{src}");
+            run_source_captured(&src).unwrap_or_else(|e| panic!("{} {c:?}: {e}\n{src}", t.name));
+        }
+        if let Some(ex) = t.example {
+            assert!(examples::find(ex).is_some(), "{} names missing example {ex}", t.name);
+        }
+    }
+}
+
+#[test]
+fn circuits_give_the_expected_outcomes() {
+    for n in [2, 4, 6] {
+        let keys = outcomes(&instantiate("ghz", &params(&[("qubits", n), ("shots", 50)])).unwrap());
+        assert!(keys.iter().all(|k| *k == "0".repeat(n) || *k == "1".repeat(n)), "ghz {n}: {keys:?}");
+    }
+    for marked in 0..4 {
+        let keys = outcomes(&instantiate("grover", &params(&[("marked", marked), ("shots", 20)])).unwrap());
+        assert_eq!(keys, [format!("{:02b}", marked)], "grover marked={marked}");
+    }
+    for state in [0, 1] {
+        let keys = outcomes(&instantiate("teleportation", &params(&[("state", state), ("shots", 40)])).unwrap());
+        assert!(keys.iter().all(|k| k.ends_with(&state.to_string())), "teleport {state}: {keys:?}");
+    }
+}
+
+#[test]
+fn bad_names_and_parameters_are_rejected() {
+    assert!(instantiate("nope", &BTreeMap::new()).unwrap_err().contains("unknown template 'nope'"));
+    assert_eq!(instantiate("ghz", &params(&[("qubits", 1)])).unwrap_err(), "ghz: qubits must be in 2..=12, got 1");
+    assert_eq!(instantiate("grover", &params(&[("qubits", 3)])).unwrap_err(), "grover: qubits must be in 2..=2, got 3");
+    assert_eq!(instantiate("bell", &params(&[("qubits", 3)])).unwrap_err(), "template 'bell' has no parameter 'qubits'");
+    assert_eq!(find("GHZ").map(|t| t.name), Some("ghz"));
+}
@@ -0,0 +1,104 @@
+use aeonmi_project::commands::repl::{is_incomplete, load_history, Repl, Reply};
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn session(repl: &mut Repl, input: &str) -> String {
+    let mut out = Vec::new();
+    repl.run(&mut input.as_bytes(), &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn entries_share_one_environment_and_errors_keep_it() {
+    let mut repl = Repl::new(None);
+    let out = session(
+        &mut repl,
+        "let x = 40;\nfunction add(a, b) { return a + b; }\nlog(add(x, 2));\nlog(missing);\nlet y = ;\nlog(x);\n",
+    );
+    assert!(out.contains("42\n"), "{out}");
+    assert!(out.contains("error: runtime error:"), "{out}");
+    assert!(out.contains("error: parsing error:"), "{out}");
+    assert!(out.ends_with("40\nae> \n"), "{out}");
+
+    assert_eq!(repl.eval(":env"), Reply::Output("add(a, b)\nx = 40\n".into()));
+    assert_eq!(repl.eval(":reset"), Reply::Output("(session reset)\n".into()));
+    assert_eq!(repl.eval(":env"), Reply::Output("(empty)\n".into()));
+    assert!(matches!(repl.eval(":bogus"), Reply::Error { .. }));
+}
+
+#[test]
+fn entries_ending_in_an_expression_echo_its_value() {
+    let mut repl = Repl::new(None);
+    assert_eq!(repl.eval("1 + 2"), Reply::Output("3\n".into()));
+    assert_eq!(repl.eval("function f(n) { return [n, \"x\" + n]; }"), Reply::Output(String::new()));
+    assert_eq!(repl.eval("f(2);"), Reply::Output("[2, \"x2\"]\n".into()));
+    assert_eq!(repl.eval("let o = { a: 1 };\nlog(\"before\");\no"), Reply::Output("before\n{a: 1}\n".into()));
+    // statements, `log` and calls returning null print nothing of their own
+    assert_eq!(repl.eval("let y = 5;"), Reply::Output(String::new()));
+    assert_eq!(repl.eval("log(y);"), Reply::Output("5\n".into()));
+    assert_eq!(repl.eval("function g() { }\ng();"), Reply::Output(String::new()));
+
+    let out = session(&mut repl, "y * 2\n\"s\"\n");
+    assert!(out.contains("10\n") && out.contains("s\n"), "{out}");
+}
+
+#[test]
+fn unbalanced_input_continues_instead_of_failing() {
+    assert!(is_incomplete("function f() {\n"));
+    assert!(is_incomplete("log((1 + 2)\n"));
+    assert!(is_incomplete("let s = \"open\n"));
+    assert!(is_incomplete("let xs = [1, ⦅ ] ⦆\n"));
+    assert!(!is_incomplete("log(\"{\"); // {\n"));
+    assert!(!is_incomplete("log(1));\n"), "stray closers go to the parser");
+
+    let mut repl = Repl::new(None);
+    let out = session(&mut repl, "function twice(n) {\n  return n * 2;\n}\nlog(twice(\n  21));\nlet z = {\n:cancel\nlog(1);\n");
+    assert_eq!(out.matches("... ").count(), 4, "{out}");
+    assert!(!out.contains("error"), "{out}");
+    assert!(out.contains("42\n") && out.contains("1\n"), "{out}");
+    assert_eq!(repl.history(), ["function twice(n) {\n  return n * 2;\n}", "log(twice(\n  21));", "log(1);"]);
+}
+
+#[test]
+fn load_runs_a_file_in_the_session() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("lib")).unwrap();
+    fs::write(dir.path().join("lib/util.ai"), "function inc(n) { return n + 1; }\n").unwrap();
+    let main = dir.path().join("main.ai");
+    fs::write(&main, "import \"lib/util.ai\";\nlet base = 9;\nlog(\"loaded\");\n").unwrap();
+
+    let mut repl = Repl::new(None);
+    assert_eq!(repl.eval(&format!(":load {}", main.display())), Reply::Output("loaded\n".into()));
+    assert_eq!(repl.eval("log(inc(base));"), Reply::Output("10\n".into()));
+    assert!(matches!(repl.eval(":load nowhere.ai"), Reply::Error { .. }));
+}
+
+#[test]
+fn history_persists_across_sessions() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("aeonmi").join("repl_history");
+    let mut first = Repl::new(Some(path.clone()));
+    session(&mut first, "let a = 1;\nif (a == 1) {\n  log(\"one\");\n}\n");
+
+    let second = Repl::new(Some(path.clone()));
+    assert_eq!(second.history(), ["let a = 1;", "if (a == 1) {\n  log(\"one\");\n}"]);
+    assert_eq!(load_history(&path), second.history());
+}
+
+#[test]
+fn cli_repl_reads_stdin_and_writes_history_to_the_config_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_aeonmi"))
+        .arg("repl")
+        .env("AEONMI_CONFIG_DIR", dir.path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"let n = 6;\nlog(n * 7);\n:quit\n").unwrap();
+    let out = child.wait_with_output().unwrap();
+    assert!(out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).contains("42\n"));
+    assert_eq!(fs::read_to_string(dir.path().join("repl_history")).unwrap(), "let n = 6;\nlog(n * 7);\n:quit\n");
+}
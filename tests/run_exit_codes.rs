@@ -0,0 +1,80 @@
+//! `run` exits with the code `main` returns, identically on the native VM and under Node.
+
+use std::fs;
+use std::process::Command;
+
+use aeonmi_project::core::toolchain::{self, Tool};
+
+const PROGRAMS: &[(&str, &str, i32)] = &[
+    ("zero", "log(1);\nreturn 0;\n", 0),
+    ("three", "function main() {\n    log(\"failing\");\n    return 3;\n}\n", 3),
+    ("true", "return 2 > 1;\n", 0),
+    ("false", "let ok = false;\nif (ok == false) {\n    return ok;\n}\nlog(\"unreachable\");\n", 1),
+    ("clamped", "return 300.9;\n", 255),
+    ("negative", "return 0 - 7;\n", 0),
+    ("string", "return \"3\";\n", 0),
+    ("no_return", "log(1);\n", 0),
+];
+
+fn run(dir: &std::path::Path, name: &str, native: bool) -> (Option<i32>, String) {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"));
+    cmd.current_dir(dir).arg("run").arg(format!("{name}.ai"));
+    if native {
+        cmd.arg("--native");
+    }
+    let out = cmd.output().unwrap();
+    (out.status.code(), String::from_utf8_lossy(&out.stderr).into_owned())
+}
+
+fn write_programs() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for (name, source, _) in PROGRAMS {
+        fs::write(dir.path().join(format!("{name}.ai")), source).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn native_exit_code_follows_main_return_value() {
+    let dir = write_programs();
+    for (name, _, expected) in PROGRAMS {
+        let (code, stderr) = run(dir.path(), name, true);
+        assert_eq!(code, Some(*expected), "{name}: {stderr}");
+    }
+}
+
+#[test]
+fn node_exit_code_matches_native() {
+    if !toolchain::available(Tool::Node) {
+        eprintln!("node not found; skipping");
+        return;
+    }
+    let dir = write_programs();
+    for (name, _, expected) in PROGRAMS {
+        let (code, stderr) = run(dir.path(), name, false);
+        assert_eq!(code, Some(*expected), "{name}: {stderr}");
+    }
+}
+
+#[test]
+fn js_runtime_errors_still_exit_four() {
+    if !toolchain::available(Tool::Node) {
+        eprintln!("node not found; skipping");
+        return;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("boom.ai"), "missing_fn(1);\n").unwrap();
+    assert_eq!(run(dir.path(), "boom", false).0, Some(4));
+    assert_eq!(run(dir.path(), "boom", true).0, Some(4));
+}
+
+#[test]
+fn doctest_fails_blocks_that_exit_non_zero() {
+    let dir = tempfile::tempdir().unwrap();
+    let md = dir.path().join("guide.md");
+    fs::write(&md, "```aeonmi,run\nlog(1);\nreturn 2;\n```\n").unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project")).arg("doctest").arg(&md).output().unwrap();
+    assert_ne!(out.status.code(), Some(0));
+    let text = String::from_utf8_lossy(&out.stdout).into_owned() + &String::from_utf8_lossy(&out.stderr);
+    assert!(text.contains("program exited with code 2"), "{text}");
+}
@@ -1,17 +1,19 @@
 use aeonmi_project::core::lexer::Lexer;
 use aeonmi_project::core::parser::Parser as AeParser;
-use aeonmi_project::core::semantic_analyzer::{SemanticAnalyzer, Severity};
+use aeonmi_project::core::code_actions::TextEdit;
+use aeonmi_project::core::semantic_analyzer::{SemanticAnalyzer, SemanticDiagnostic, Severity};
 
-fn gather(source: &str) -> Vec<(String, Severity)> {
+fn analyze(source: &str) -> Vec<SemanticDiagnostic> {
     let mut lexer = Lexer::from_str(source);
     let tokens = lexer.tokenize().expect("lex");
     let mut parser = AeParser::new(tokens);
     let ast = parser.parse().expect("parse");
     let mut sema = SemanticAnalyzer::new();
     sema.analyze_with_spans(&ast)
-        .into_iter()
-        .map(|d| (d.message, d.severity))
-        .collect()
+}
+
+fn gather(source: &str) -> Vec<(String, Severity)> {
+    analyze(source).into_iter().map(|d| (d.message, d.severity)).collect()
 }
 
 #[test]
@@ -55,3 +57,54 @@ fn bar() { return 1; let z = 3; }
     let diags = gather(src);
     assert!(diags.iter().any(|(m,s)| matches!(s, Severity::Warning) && m.contains("Unreachable code after return")), "expected unreachable code warning: {diags:?}");
 }
+
+#[test]
+fn calls_may_precede_declarations() {
+    let src = r#"
+log(is_even(4));
+fn is_even(n) { if (n == 0) { return true; } return is_odd(n - 1); }
+fn is_odd(n) { if (n == 0) { return false; } return is_even(n - 1); }
+"#;
+    let diags = gather(src);
+    assert!(!diags.iter().any(|(_,s)| matches!(s, Severity::Error)), "forward references must not be errors: {diags:?}");
+}
+
+#[test]
+fn undefined_function_error() {
+    let src = r#"
+let f = 1;
+log(len("ab"));
+log(missing(2));
+"#;
+    let diags = gather(src);
+    let errors: Vec<&String> = diags.iter().filter(|(_,s)| matches!(s, Severity::Error)).map(|(m,_)| m).collect();
+    assert_eq!(errors, vec!["Call to undefined function 'missing'"], "{diags:?}");
+}
+
+#[test]
+fn undeclared_assignment_has_quick_fix_but_no_related_info() {
+    let diags = analyze("score = 10;\nlog(score);\n");
+    let d = diags.iter().find(|d| d.message.contains("undeclared variable 'score'")).expect("undeclared error");
+    assert_eq!(d.rule, Some("AEO-S004"));
+    assert!(d.related.is_empty(), "{:?}", d.related);
+    let fix = d.fix.as_ref().expect("quick fix");
+    assert_eq!(fix.kind, "addMissingLet");
+    assert_eq!(fix.edits, vec![TextEdit::insert(1, 1, "let ")]);
+}
+
+#[test]
+fn shadowing_warning_points_at_shadowed_declaration() {
+    let src = "let count = 0;\nif (true) {\n    let count = 1;\n    log(count);\n}\nlog(count);\n";
+    let diags = analyze(src);
+    let d = diags.iter().find(|d| d.rule == Some("AEO-S003")).expect("shadow warning");
+    assert_eq!(d.severity, Severity::Warning);
+    assert_eq!((d.line, d.column), (3, 9));
+    assert_eq!(d.related.len(), 1);
+    assert_eq!((d.related[0].line, d.related[0].column, d.related[0].len), (1, 5, 5));
+    assert!(d.fix.is_none());
+    // same-scope redeclaration is an error that links the first declaration, not a shadow
+    let diags = analyze("let a = 1;\nlet a = 2;\nlog(a);\n");
+    assert!(!diags.iter().any(|d| d.rule == Some("AEO-S003")), "{diags:?}");
+    let d = diags.iter().find(|d| d.rule == Some("AEO-S002")).expect("redeclaration");
+    assert_eq!((d.related[0].line, d.related[0].column), (1, 5));
+}
@@ -0,0 +1,139 @@
+use reqwest::blocking::Client;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+struct Server {
+    child: Child,
+    base: String,
+}
+
+impl Server {
+    fn start(extra: &[&str]) -> Server {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+            .args(["serve", "--port", "0", "--sandbox"])
+            .args(extra)
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("spawn aeonmi serve");
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().unwrap()).read_line(&mut line).unwrap();
+        let base = line.trim().strip_prefix("listening on ").expect("listen banner").to_string();
+        Server { child, base }
+    }
+
+    fn post(&self, path: &str, body: Value) -> (u16, Value) {
+        let res = Client::new().post(format!("{}{}", self.base, path)).json(&body).send().unwrap();
+        (res.status().as_u16(), res.json().unwrap())
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn health_compile_and_run() {
+    let server = Server::start(&[]);
+    let health: Value = reqwest::blocking::get(format!("{}/health", server.base)).unwrap().json().unwrap();
+    assert_eq!(health, json!({ "status": "ok" }));
+
+    let (status, body) = server.post("/compile", json!({ "source": "let x = 1 + 2;\nlog(x);", "emit": "js" }));
+    assert_eq!(status, 200);
+    assert_eq!(body["ok"], true, "{body}");
+    assert!(body["output"].as_str().unwrap().contains("console.log"), "{body}");
+
+    let (_, body) = server.post("/compile", json!({ "source": "let = ;" }));
+    assert_eq!(body["ok"], false);
+    assert!(!body["diagnostics"].as_array().unwrap().is_empty(), "{body}");
+
+    let (status, body) = server.post("/run", json!({ "source": "let i = 0;\nwhile (i < 3) { print(i); i = i + 1; }" }));
+    assert_eq!(status, 200);
+    assert_eq!(body["ok"], true, "{body}");
+    assert_eq!(body["output"], "0\n1\n2\n");
+    assert!(body["elapsed_ms"].as_f64().unwrap() >= 0.0);
+}
+
+#[test]
+fn runs_are_limited_and_sandboxed() {
+    let server = Server::start(&["--timeout-ms", "300", "--max-output", "64"]);
+
+    let (_, body) = server.post("/run", json!({ "source": "print(\"before\");\nwhile (true) { }" }));
+    assert_eq!(body["ok"], false);
+    assert!(body["error"].as_str().unwrap().contains("time limit exceeded"), "{body}");
+    assert_eq!(body["output"], "before\n");
+    assert!(body["elapsed_ms"].as_f64().unwrap() < 2000.0, "{body}");
+
+    let (_, body) = server.post("/run", json!({ "source": "function f(n) { return f(n + 1); }\nf(0);" }));
+    assert!(body["error"].as_str().unwrap().contains("call depth limit"), "{body}");
+
+    let (_, body) = server.post("/run", json!({ "source": "let i = 0;\nwhile (i < 100) { print(\"spam spam spam\"); i = i + 1; }" }));
+    assert!(body["error"].as_str().unwrap().contains("output limit exceeded"), "{body}");
+
+    let (_, body) = server.post("/run", json!({ "source": "print(env(\"PATH\"));" }));
+    assert!(body["error"].as_str().unwrap().contains("not available in the sandbox"), "{body}");
+
+    // state does not leak between requests
+    server.post("/run", json!({ "source": "let leaked = 1;" }));
+    let (_, body) = server.post("/run", json!({ "source": "print(leaked);" }));
+    assert_eq!(body["ok"], false, "{body}");
+}
+
+#[test]
+fn runs_are_memory_limited() {
+    let server = Server::start(&["--max-memory-mb", "1", "--max-string", "100000000"]);
+    let source = "let s = \"xxxxxxxxxxxxxxxx\";\nwhile (true) { s = s + s; }";
+    let (_, body) = server.post("/run", json!({ "source": source }));
+    assert_eq!(body["ok"], false, "{body}");
+    assert!(body["error"].as_str().unwrap().contains("out of memory"), "{body}");
+}
+
+#[test]
+fn rejects_oversized_malformed_and_unknown_requests() {
+    let server = Server::start(&["--max-body", "128"]);
+    let (status, body) = server.post("/run", json!({ "source": "x".repeat(500) }));
+    assert_eq!(status, 413);
+    assert!(body["error"].as_str().unwrap().contains("128 byte limit"), "{body}");
+
+    let res = Client::new().post(format!("{}/run", server.base)).body("{not json").send().unwrap();
+    assert_eq!(res.status().as_u16(), 400);
+
+    let (status, _) = server.post("/compile", json!({ "source": "let x = 1;", "emit": "wasm" }));
+    assert_eq!(status, 400);
+    let (status, _) = server.post("/nope", json!({}));
+    assert_eq!(status, 404);
+    assert_eq!(reqwest::blocking::get(format!("{}/run", server.base)).unwrap().status().as_u16(), 405);
+}
+
+#[test]
+fn concurrency_limit_returns_busy() {
+    let server = Server::start(&["--concurrency", "1", "--timeout-ms", "1500"]);
+    let url = format!("{}/run", server.base);
+    let slow = std::thread::spawn(move || {
+        Client::new().post(url).json(&json!({ "source": "while (true) { }" })).send().unwrap().status().as_u16()
+    });
+    std::thread::sleep(Duration::from_millis(400));
+    let (status, body) = server.post("/run", json!({ "source": "print(1);" }));
+    assert_eq!(status, 503, "{body}");
+    // health checks bypass the limit
+    assert!(reqwest::blocking::get(format!("{}/health", server.base)).unwrap().status().is_success());
+    assert_eq!(slow.join().unwrap(), 200);
+}
+
+#[test]
+fn idle_connections_are_capped_before_a_thread_is_spawned() {
+    let server = Server::start(&["--concurrency", "1"]);
+    let addr = server.base.strip_prefix("http://").unwrap().to_string();
+    // concurrency 1 + 16 spare connections, all left idle
+    let idle: Vec<_> = (0..17).map(|_| std::net::TcpStream::connect(&addr).unwrap()).collect();
+    std::thread::sleep(Duration::from_millis(300));
+    let res = reqwest::blocking::get(format!("{}/health", server.base)).unwrap();
+    assert_eq!(res.status().as_u16(), 503);
+    drop(idle);
+    std::thread::sleep(Duration::from_millis(300));
+    assert!(reqwest::blocking::get(format!("{}/health", server.base)).unwrap().status().is_success());
+}
@@ -0,0 +1,57 @@
+use aeonmi_project::commands::shell_cwd::{expand_with, ShellCwd};
+use std::path::Path;
+
+fn vars(name: &str) -> Option<String> {
+    match name {
+        "PROJ" => Some("demo".into()),
+        "EMPTY" => Some(String::new()),
+        _ => None,
+    }
+}
+
+#[test]
+fn relative_paths_resolve_against_the_shell_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let before = std::env::current_dir().unwrap();
+    let mut cwd = ShellCwd::new(dir.path().to_path_buf());
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+
+    assert_eq!(cwd.resolve("main.ai"), dir.path().join("main.ai"));
+    assert_eq!(cwd.resolve("sub/../main.ai"), dir.path().join("main.ai"));
+    cwd.cd(Some("./sub"), None).unwrap();
+    assert_eq!(cwd.current(), dir.path().join("sub"));
+    assert_eq!(cwd.resolve("x.ai"), dir.path().join("sub").join("x.ai"));
+    let abs = dir.path().join("abs.ai");
+    assert_eq!(cwd.resolve(&abs), abs);
+    assert_eq!(std::env::current_dir().unwrap(), before, "process cwd must not change");
+}
+
+#[test]
+fn cd_dash_toggles_and_errors_leave_cwd_alone() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("a/b")).unwrap();
+    let mut cwd = ShellCwd::new(dir.path().to_path_buf());
+    assert!(cwd.cd(Some("-"), None).unwrap_err().contains("no previous directory"));
+
+    cwd.cd(Some("a/b"), None).unwrap();
+    assert_eq!(cwd.cd(Some("-"), None).unwrap(), dir.path());
+    assert_eq!(cwd.cd(Some("-"), None).unwrap(), dir.path().join("a/b"));
+
+    let err = cwd.cd(Some("missing"), None).unwrap_err();
+    assert!(err.contains("not a directory"), "{err}");
+    assert_eq!(cwd.current(), dir.path().join("a/b"));
+
+    cwd.cd(None, Some(dir.path())).unwrap();
+    assert_eq!(cwd.current(), dir.path());
+}
+
+#[test]
+fn tilde_and_variables_expand() {
+    let home = Path::new("/home/ae");
+    assert_eq!(expand_with("~", Some(home), vars), "/home/ae");
+    assert_eq!(expand_with("~/src/$PROJ.ai", Some(home), vars), "/home/ae/src/demo.ai");
+    assert_eq!(expand_with("${PROJ}_v2/x", Some(home), vars), "demo_v2/x");
+    assert_eq!(expand_with("a~b/$MISSING/c$EMPTY", Some(home), vars), "a~b//c");
+    assert_eq!(expand_with("cost: $5 or $", Some(home), vars), "cost: $5 or $");
+    assert_eq!(expand_with("~user/x", Some(home), vars), "~user/x");
+}
@@ -31,3 +31,33 @@ fn shell_run_native_skips_js_emit() {
     assert!(stdout.contains("10"), "expected native result in stdout: {stdout}");
     assert!(!dir.path().join("aeonmi.run.js").exists(), "unexpected JS file emitted in shell native run mode");
 }
+
+#[test]
+fn shell_native_run_does_not_stick() {
+    let dir = tempfile::tempdir().unwrap();
+    let ai_path = dir.path().join("demo.ai");
+    std::fs::write(&ai_path, "let a = 5; let b = a * 2; log(b);").unwrap();
+
+    let mut child = Command::new(bin())
+        .current_dir(dir.path())
+        .env_remove("AEONMI_NATIVE")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to start shell");
+    {
+        let stdin = child.stdin.as_mut().expect("stdin");
+        writeln!(stdin, "native-run {}", ai_path.display()).unwrap();
+        writeln!(stdin, "run {}", ai_path.display()).unwrap();
+        writeln!(stdin, "exit").unwrap();
+    }
+    let output = child.wait_with_output().expect("shell run");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr=\n{stderr}");
+    // The plain `run` goes through JS (or says it fell back because node is missing).
+    assert!(
+        dir.path().join("aeonmi.run.js").exists() || stderr.contains("node not found"),
+        "second run stayed native: stderr=\n{stderr}"
+    );
+}
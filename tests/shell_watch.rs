@@ -0,0 +1,137 @@
+use aeonmi_project::commands::watch::{watch_targets, with_ai_imports, Wake, Watcher};
+use aeonmi_project::core::shutdown::{self, Signal};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+fn args(s: &str) -> Vec<String> {
+    s.split_whitespace().map(str::to_string).collect()
+}
+
+#[test]
+fn targets_are_existing_inputs_not_flags_or_outputs() {
+    let dir = tempfile::tempdir().unwrap();
+    for f in ["main.ai", "lib.ai", "out.js"] {
+        std::fs::write(dir.path().join(f), "").unwrap();
+    }
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    let got = watch_targets(&args("main.ai --emit js --out out.js lib.ai missing.ai sub main.ai -o lib.ai"), dir.path());
+    assert_eq!(got, vec![dir.path().join("main.ai"), dir.path().join("lib.ai")]);
+    assert!(watch_targets(&args("--native"), dir.path()).is_empty());
+}
+
+#[test]
+fn ai_targets_bring_their_imports() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("main.ai"), "import \"lib.ai\";\nlog(k);\n").unwrap();
+    std::fs::write(dir.path().join("lib.ai"), "let k = 1;\n").unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "").unwrap();
+    let named = watch_targets(&args("notes.txt main.ai lib.ai"), dir.path());
+    let p = |f: &str| dir.path().join(f);
+    assert_eq!(with_ai_imports(&named), vec![p("notes.txt"), p("main.ai"), p("lib.ai")]);
+}
+
+#[test]
+fn stop_flag_ends_wait_promptly() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("a.ai");
+    std::fs::write(&file, "log(1);").unwrap();
+    let watcher = Watcher::new(&[file]).unwrap();
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let flag = stop.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(200));
+        flag.store(true, Ordering::SeqCst);
+    });
+    let start = Instant::now();
+    assert_eq!(watcher.wait(&stop), Wake::Stopped);
+    assert!(start.elapsed() < Duration::from_secs(2), "took {:?}", start.elapsed());
+
+    // the Ctrl-C handler raises the listening loop's token
+    let listener = shutdown::listen();
+    assert_eq!(shutdown::signal(), Signal::Graceful);
+    assert_eq!(watcher.wait(listener.token().flag()), Wake::Stopped);
+}
+
+#[test]
+fn change_to_target_wakes_once_per_burst() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("a.ai");
+    let other = dir.path().join("b.ai");
+    std::fs::write(&file, "log(1);").unwrap();
+    let watcher = Watcher::new(std::slice::from_ref(&file)).unwrap();
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let (flag, f) = (stop.clone(), file.clone());
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(100));
+        std::fs::write(&other, "ignored").unwrap();
+        for i in 0..3 {
+            std::fs::write(&f, format!("log({i});")).unwrap();
+        }
+        std::thread::sleep(Duration::from_secs(5));
+        flag.store(true, Ordering::SeqCst);
+    });
+    let changed: Vec<PathBuf> = match watcher.wait(&stop) {
+        Wake::Changed(paths) => paths,
+        Wake::Stopped => panic!("no change seen"),
+    };
+    assert_eq!(changed, vec![file.canonicalize().unwrap()]);
+}
+
+#[test]
+fn shell_watch_runs_command_with_header() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "hello from notes\n").unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .current_dir(dir.path())
+        .env("AEONMI_WATCH_ONCE", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    {
+        let stdin = child.stdin.as_mut().unwrap();
+        writeln!(stdin, "watch cat notes.txt").unwrap();
+        writeln!(stdin, "watch exit").unwrap();
+        writeln!(stdin, "watch cat nothing.txt").unwrap();
+        writeln!(stdin, "exit").unwrap();
+    }
+    let out = child.wait_with_output().unwrap();
+    assert!(out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stdout.contains("[watch ") && stdout.contains("cat notes.txt"), "{stdout}");
+    assert!(stdout.contains("hello from notes") && stdout.contains("watch stopped"), "{stdout}");
+    assert!(stderr.contains("'exit' cannot be watched"), "{stderr}");
+    assert!(stderr.contains("watch: no existing files in 'cat nothing.txt' to watch"), "{stderr}");
+}
+
+#[test]
+fn shell_watch_tracks_imported_files() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("main.ai"), "import \"lib.ai\";\nlog(k);\n").unwrap();
+    std::fs::write(dir.path().join("lib.ai"), "let k = 41 + 1;\n").unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .current_dir(dir.path())
+        .env("AEONMI_WATCH_ONCE", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    {
+        let stdin = child.stdin.as_mut().unwrap();
+        writeln!(stdin, "watch cat main.ai").unwrap();
+        writeln!(stdin, "exit").unwrap();
+    }
+    let out = child.wait_with_output().unwrap();
+    assert!(out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("(watching main.ai, lib.ai;"), "{stdout}");
+}
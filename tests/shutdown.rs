@@ -0,0 +1,197 @@
+use aeonmi_project::commands::watch::{run_loop, LoopOptions, Wake};
+use aeonmi_project::core::shutdown::{self, ShutdownToken, Signal};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+#[test]
+fn token_raised_by_a_run_stops_the_loop_and_cleans_up() {
+    let dir = tempfile::tempdir().unwrap();
+    let temp = dir.path().join("__exec_tmp.js");
+    let missing = dir.path().join("never_written.js");
+    let stop = ShutdownToken::new();
+    let opts = LoopOptions { temp_files: vec![temp.clone(), missing], ..Default::default() };
+
+    let mut waits = 0;
+    let summary = run_loop(
+        &stop,
+        &opts,
+        |_| {
+            waits += 1;
+            Wake::Changed(vec![])
+        },
+        |n| {
+            std::fs::write(&temp, "console.log(1);").unwrap();
+            if n == 3 {
+                stop.request(); // Ctrl-C arrives while the third run is going
+                anyhow::bail!("interrupted run");
+            }
+            Ok(())
+        },
+    );
+    assert_eq!((summary.runs, summary.failed), (3, 1));
+    assert_eq!(summary.removed, vec![temp.clone()]);
+    assert!(!temp.exists());
+    assert_eq!(waits, 3);
+}
+
+#[test]
+fn token_raised_while_waiting_ends_the_loop() {
+    let stop = ShutdownToken::new();
+    let remote = stop.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(100));
+        remote.request();
+    });
+    let started = Instant::now();
+    let summary = run_loop(
+        &stop,
+        &LoopOptions::default(),
+        |flag: &AtomicBool| {
+            while !flag.load(Ordering::SeqCst) {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Wake::Stopped
+        },
+        |_| Ok(()),
+    );
+    assert_eq!(summary.runs, 1);
+    assert!(summary.removed.is_empty());
+    assert!(started.elapsed() < Duration::from_secs(2));
+
+    // a token that is already raised never runs
+    let summary = run_loop(&stop, &LoopOptions::default(), |_| Wake::Stopped, |_| panic!("ran after stop"));
+    assert_eq!(summary.runs, 0);
+}
+
+#[test]
+fn first_press_is_graceful_and_second_forces() {
+    assert_eq!(shutdown::signal(), Signal::Force, "nothing listening");
+    {
+        let listener = shutdown::listen();
+        assert!(!listener.token().is_requested());
+        assert_eq!(shutdown::signal(), Signal::Graceful);
+        assert!(listener.token().is_requested());
+        assert_eq!(shutdown::signal(), Signal::Force);
+    }
+    assert!(!shutdown::global().is_requested(), "leaving the loop clears the request");
+
+    let token = ShutdownToken::new();
+    assert!(token.sleep(Duration::from_millis(20)));
+    token.request();
+    assert!(!token.sleep(Duration::from_secs(5)));
+}
+
+#[cfg(unix)]
+mod signals {
+    use std::io::{BufRead, BufReader, Read};
+    use std::process::{Child, Command, Stdio};
+    use std::time::Duration;
+
+    fn interrupt(child: &Child) {
+        let ok = Command::new("kill").args(["-INT", &child.id().to_string()]).status().unwrap().success();
+        assert!(ok, "kill -INT failed");
+    }
+
+    /// Read stdout lines until one contains `needle`; returns everything read so far.
+    fn read_until(out: &mut BufReader<std::process::ChildStdout>, needle: &str) -> String {
+        let mut seen = String::new();
+        loop {
+            let mut line = String::new();
+            if out.read_line(&mut line).unwrap() == 0 {
+                panic!("stdout closed before {needle:?}:\n{seen}");
+            }
+            seen.push_str(&line);
+            if line.contains(needle) {
+                return seen;
+            }
+        }
+    }
+
+    #[test]
+    fn ctrl_c_stops_run_watch_with_a_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("prog.ai"), "log(\"tick\");\n").unwrap();
+        let mut child = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+            .args(["run", "--watch", "--native", "prog.ai"])
+            .current_dir(dir.path())
+            .env("NO_COLOR", "1")
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut out = BufReader::new(child.stdout.take().unwrap());
+        read_until(&mut out, "[watch] run 1");
+        interrupt(&child);
+        let mut rest = String::new();
+        out.read_to_string(&mut rest).unwrap();
+        let status = child.wait().unwrap();
+        assert!(status.success(), "{status:?}\n{rest}");
+        assert!(rest.contains("[watch] stopped after 1 run"), "{rest}");
+    }
+
+    #[test]
+    fn a_burst_of_saves_reruns_run_watch_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let prog = dir.path().join("prog.ai");
+        std::fs::write(&prog, "log(\"tick\");\n").unwrap();
+        let mut child = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+            .args(["run", "--watch", "--native", "prog.ai"])
+            .current_dir(dir.path())
+            .env("NO_COLOR", "1")
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut out = BufReader::new(child.stdout.take().unwrap());
+        read_until(&mut out, "[watch] run 1");
+        for i in 0..3 {
+            std::fs::write(&prog, format!("log(\"tock {i}\");\n")).unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let second = read_until(&mut out, "[watch] run 2");
+        assert!(second.contains("tock 2"), "{second}");
+        std::thread::sleep(Duration::from_millis(500));
+        interrupt(&child);
+        let mut rest = String::new();
+        out.read_to_string(&mut rest).unwrap();
+        assert!(child.wait().unwrap().success());
+        assert!(rest.contains("[watch] stopped after 2 runs"), "debounced into one rerun:\n{rest}");
+    }
+
+    #[test]
+    fn second_ctrl_c_forces_serve_to_exit() {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+            .args(["serve", "--port", "0", "--timeout-ms", "5000"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut out = BufReader::new(child.stdout.take().unwrap());
+        let banner = read_until(&mut out, "listening on");
+        let addr = banner.trim().rsplit("http://").next().unwrap().to_string();
+        // an idle connection keeps the graceful shutdown waiting
+        let _idle = std::net::TcpStream::connect(&addr).unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+        interrupt(&child);
+        std::thread::sleep(Duration::from_millis(300));
+        interrupt(&child);
+        let status = child.wait().unwrap();
+        assert_eq!(status.code(), Some(130), "{status:?}");
+    }
+
+    #[test]
+    fn ctrl_c_stops_serve_after_open_requests() {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+            .args(["serve", "--port", "0"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut out = BufReader::new(child.stdout.take().unwrap());
+        let banner = read_until(&mut out, "listening on");
+        let base = banner.trim().strip_prefix("listening on ").unwrap().to_string();
+        let health = reqwest::blocking::get(format!("{base}/health")).unwrap();
+        assert_eq!(health.status().as_u16(), 200);
+        interrupt(&child);
+        let mut rest = String::new();
+        out.read_to_string(&mut rest).unwrap();
+        assert!(child.wait().unwrap().success());
+        assert!(rest.contains("[serve] stopped after 1 request"), "{rest}");
+    }
+}
@@ -0,0 +1,138 @@
+use std::fs;
+use std::path::Path;
+
+use aeonmi_project::core::code_generator::CodeGenerator;
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::parser::Parser;
+use aeonmi_project::core::source_map::{decode_mappings, encode_mappings, vlq_encode, Mapping, SourceMap};
+use aeonmi_project::core::toolchain::{self, Tool};
+
+const PROG: &str = "let a = 1;\nfunction f(x) {\n    log(x);\n    return missing(x);\n}\nlog(a);\nf(a);\n";
+
+fn mapped(source: &str) -> (String, SourceMap) {
+    let tokens = Lexer::from_str(source).tokenize().unwrap();
+    let ast = Parser::new(tokens).parse().unwrap();
+    CodeGenerator::new().generate_source_mapped(&ast, Path::new("out/prog.js"), "../prog.ai", source).unwrap()
+}
+
+fn aeonmi(dir: &Path, args: &[&str]) -> std::process::Output {
+    std::process::Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .current_dir(dir)
+        .env("NO_COLOR", "1")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn vlq_values_round_trip() {
+    let encode = |v| {
+        let mut s = String::new();
+        vlq_encode(v, &mut s);
+        s
+    };
+    assert_eq!(encode(0), "A");
+    assert_eq!(encode(1), "C");
+    assert_eq!(encode(-1), "D");
+    assert_eq!(encode(16), "gB");
+    assert_eq!(encode(-1000), "x+B");
+
+    let mappings = [
+        Mapping { gen_line: 0, gen_col: 0, src_line: 0, src_col: 0 },
+        Mapping { gen_line: 2, gen_col: 4, src_line: 5, src_col: 2 },
+        Mapping { gen_line: 2, gen_col: 20, src_line: 1, src_col: 0 },
+    ];
+    let text = encode_mappings(&mappings);
+    assert_eq!(text, "AAAA;;IAKE,gBAJF");
+    assert_eq!(decode_mappings(&text).unwrap(), mappings);
+    assert!(decode_mappings("AA!A").is_err());
+}
+
+#[test]
+fn each_statement_maps_to_its_source_line() {
+    let (js, map) = mapped(PROG);
+    assert!(js.ends_with("f(a);\n//# sourceMappingURL=prog.js.map\n"), "{js}");
+    assert_eq!(map.file, "prog.js");
+    assert_eq!(map.sources, ["../prog.ai"]);
+    assert_eq!(map.sources_content, [PROG]);
+
+    let lines: Vec<&str> = js.lines().collect();
+    let at = |needle: &str| {
+        let line = lines.iter().position(|l| l.contains(needle)).unwrap();
+        let col = lines[line].find(|c: char| !c.is_whitespace()).unwrap();
+        map.original_position(line + 1, col + 1)
+    };
+    // declarations map to the name they declare
+    assert_eq!(at("let a"), Some((1, 5)));
+    assert_eq!(at("function f"), Some((2, 1)));
+    assert_eq!(at("console.log(__aeonmi_display(x))"), Some((3, 5)));
+    // `return` has no position of its own; it maps to its expression
+    assert_eq!(at("return missing"), Some((4, 12)));
+    assert_eq!(at("f(a)"), Some((7, 1)));
+    // the URL comment maps to nothing
+    assert_eq!(map.original_position(lines.len(), 1), None);
+
+    let json: serde_json::Value = serde_json::from_str(&map.to_json()).unwrap();
+    assert_eq!(json["version"], 3);
+    assert!(json["sourcesContent"].is_array() && json["names"].is_array());
+}
+
+#[test]
+fn columns_count_utf16_units() {
+    // `é` is two UTF-8 bytes but one UTF-16 unit; `😀` is four bytes and two units
+    let (js, map) = mapped("log(\"é\"); log(1);\nlet s = \"😀\"; log(s);\nlog(\"\u{E000}1:1\u{E001}\");\n");
+    // a string that looks like a mark is escaped, not taken for one
+    assert!(js.contains(r#"console.log("\uE0001:1\uE001");"#), "{js}");
+    let mappings = map.decode().unwrap();
+    // `log(s)` brings in the display helper ahead of the program
+    let first = js.lines().position(|l| l.contains("console.log(\"é\")")).unwrap();
+    let second = mappings.iter().find(|m| m.gen_line == first + 1).unwrap();
+    assert_eq!((second.src_line, second.src_col), (0, 10));
+    let fourth = mappings.iter().find(|m| m.gen_line == first + 3).unwrap();
+    assert_eq!((fourth.src_line, fourth.src_col), (1, 14));
+}
+
+#[test]
+fn emit_writes_the_map_next_to_the_output() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("prog.ai"), PROG).unwrap();
+    let out = aeonmi(dir.path(), &["emit", "prog.ai", "-o", "build/prog.js", "--source-map"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(String::from_utf8_lossy(&out.stdout).contains("ok: wrote source map to 'build/prog.js.map'."));
+    let js = fs::read_to_string(dir.path().join("build/prog.js")).unwrap();
+    assert!(js.ends_with("//# sourceMappingURL=prog.js.map\n"));
+    let map: SourceMap = serde_json::from_str(&fs::read_to_string(dir.path().join("build/prog.js.map")).unwrap()).unwrap();
+    assert_eq!(map.sources, ["../prog.ai"]);
+
+    // without the flag there is no comment
+    assert!(aeonmi(dir.path(), &["emit", "prog.ai", "-o", "plain.js"]).status.success());
+    assert!(!fs::read_to_string(dir.path().join("plain.js")).unwrap().contains("sourceMappingURL"));
+
+    assert_eq!(aeonmi(dir.path(), &["emit", "prog.ai", "-o", "-", "--source-map"]).status.code(), Some(2));
+    assert_eq!(aeonmi(dir.path(), &["emit", "--emit", "ai", "prog.ai", "-o", "p.ai", "--source-map"]).status.code(), Some(2));
+}
+
+#[test]
+fn runtime_errors_point_at_the_ai_line() {
+    if !toolchain::available(Tool::Node) {
+        eprintln!("node not available; skipping");
+        return;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("prog.ai"), PROG).unwrap();
+
+    let run = aeonmi(dir.path(), &["run", "prog.ai", "--source-map"]);
+    assert_eq!(run.status.code(), Some(4));
+    let stderr = String::from_utf8_lossy(&run.stderr);
+    assert!(stderr.contains("prog.ai:4:12"), "{stderr}");
+    assert!(stderr.contains("prog.ai:7:1"), "{stderr}");
+
+    let exec = aeonmi(dir.path(), &["exec", "prog.ai"]);
+    assert!(!exec.status.success());
+    assert!(String::from_utf8_lossy(&exec.stderr).contains("prog.ai:4:12"));
+
+    // a clean exec leaves neither temp file behind
+    fs::write(dir.path().join("ok.ai"), "log(1);\n").unwrap();
+    assert!(aeonmi(dir.path(), &["exec", "ok.ai"]).status.success());
+    assert!(!dir.path().join("__exec_tmp.js").exists() && !dir.path().join("__exec_tmp.js.map").exists());
+}
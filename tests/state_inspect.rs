@@ -0,0 +1,65 @@
+//! `qstate` math: marginals and Bloch vectors against states with known answers.
+use aeonmi_project::core::state_inspect::{bloch, marginal, probabilities, report, StateView};
+
+const H: f64 = std::f64::consts::FRAC_1_SQRT_2;
+
+fn state(qubits: usize, amplitudes: &[[f64; 2]]) -> StateView {
+    StateView { source: "test".into(), qubits, amplitudes: amplitudes.to_vec() }
+}
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
+}
+
+#[test]
+fn bell_pair_marginals_are_uniform_and_reduced_states_mixed() {
+    // (|00⟩ + |11⟩) / √2
+    let bell = state(2, &[[H, 0.0], [0.0, 0.0], [0.0, 0.0], [H, 0.0]]);
+    let probs = probabilities(&bell);
+    assert_eq!(probs.iter().map(|(b, _)| b.as_str()).collect::<Vec<_>>(), ["00", "11"]);
+    assert!(probs.iter().all(|(_, p)| close(*p, 0.5)));
+    for q in 0..2 {
+        let [p0, p1] = marginal(&bell, q);
+        assert!(close(p0, 0.5) && close(p1, 0.5), "qubit {q}: {p0} {p1}");
+        let b = bloch(&bell, q);
+        assert!(close(b.radius(), 0.0), "qubit {q} should be maximally mixed: {b:?}");
+    }
+}
+
+#[test]
+fn product_states_keep_pure_bloch_vectors() {
+    // qubit 0 in |+i⟩ = (|0⟩ + i|1⟩) / √2, qubit 1 in |1⟩: index = q0 + 2·q1
+    let s = state(2, &[[0.0, 0.0], [0.0, 0.0], [H, 0.0], [0.0, H]]);
+    let b0 = bloch(&s, 0);
+    assert!(close(b0.x, 0.0) && close(b0.y, 1.0) && close(b0.z, 0.0), "{b0:?}");
+    let b1 = bloch(&s, 1);
+    assert!(close(b1.z, -1.0) && close(b1.radius(), 1.0), "{b1:?}");
+    assert!(close(marginal(&s, 1)[1], 1.0));
+
+    // |+⟩ on one qubit
+    let plus = state(1, &[[H, 0.0], [H, 0.0]]);
+    let b = bloch(&plus, 0);
+    assert!(close(b.x, 1.0) && close(b.y, 0.0) && close(b.z, 0.0), "{b:?}");
+}
+
+#[test]
+fn saved_report_lists_probabilities_and_every_qubit() {
+    let bell = state(2, &[[H, 0.0], [0.0, 0.0], [0.0, 0.0], [H, 0.0]]);
+    let v = serde_json::to_value(report(&bell)).unwrap();
+    assert_eq!(v["qubits"], 2);
+    assert!(close(v["probabilities"]["11"].as_f64().unwrap(), 0.5));
+    assert_eq!(v["per_qubit"].as_array().unwrap().len(), 2);
+    assert_eq!(v["per_qubit"][1]["bloch"]["z"], 0.0);
+}
+
+#[cfg(feature = "quantum")]
+#[test]
+fn titan_bell_circuit_matches_the_reference_state() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("bell.ai");
+    std::fs::write(&file, "qubits 2\nh 0\ncnot 0 1\n").unwrap();
+    let s = aeonmi_project::commands::quantum::titan_state(&file).unwrap();
+    assert_eq!(s.qubits, 2);
+    assert_eq!(probabilities(&s).len(), 2);
+    assert!(close(marginal(&s, 1)[1], 0.5));
+}
@@ -0,0 +1,45 @@
+use std::process::{Command, Output};
+
+fn run_hello(native: bool, extra: &[&str]) -> Output {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("hello.run.js");
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"));
+    cmd.env_remove("AEONMI_DEBUG").args(extra).args(["run", "examples/hello.ai", "--out"]).arg(&out);
+    if native {
+        cmd.env("AEONMI_NATIVE", "1");
+    } else {
+        cmd.env_remove("AEONMI_NATIVE");
+    }
+    cmd.output().expect("spawn aeonmi run")
+}
+
+#[test]
+fn run_stdout_is_exactly_program_output() {
+    for native in [true, false] {
+        let out = run_hello(native, &[]);
+        assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+        assert_eq!(out.stdout, b"42\n", "native={native}: {:?}", String::from_utf8_lossy(&out.stdout));
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        assert!(!stderr.contains("DEBUG"), "native={native}: unexpected debug output: {stderr}");
+    }
+}
+
+#[test]
+fn verbose_logs_go_to_stderr_with_targets() {
+    let out = run_hello(true, &["-v"]);
+    assert!(out.status.success());
+    assert_eq!(out.stdout, b"42\n");
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    let line = stderr
+        .lines()
+        .find(|l| l.contains("native: executing"))
+        .unwrap_or_else(|| panic!("no run log in: {stderr}"));
+    assert!(line.contains(" DEBUG aeonmi_project::commands::run: "), "{line}");
+    // HH:MM:SS.mmm timestamp first
+    assert_eq!(line.as_bytes()[2], b':', "{line}");
+    assert!(!stderr.contains(" TRACE "), "-v must not enable trace logs");
+
+    let out = run_hello(true, &["-vv"]);
+    assert_eq!(out.stdout, b"42\n");
+    assert!(String::from_utf8_lossy(&out.stderr).contains(" TRACE aeonmi_project::core::vm: "));
+}
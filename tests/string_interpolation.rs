@@ -0,0 +1,150 @@
+use aeonmi_project::commands::run::run_source_captured;
+use aeonmi_project::core::ai_emitter::AiEmitter;
+use aeonmi_project::core::ast::ASTNode;
+use aeonmi_project::core::code_generator::CodeGenerator;
+use aeonmi_project::core::lexer::{Lexer, LexerError};
+use aeonmi_project::core::parser::Parser;
+use aeonmi_project::core::token::{StringPart, TokenKind};
+use aeonmi_project::core::toolchain::{self, Tool};
+use std::fs;
+use std::process::Command;
+
+const PROGRAM: &str = r#"let a = 1;
+let b = 2;
+function shout(s) {
+    return s + "!";
+}
+log("sum: ${a + b}, joined: ${a}${b}");
+log("${shout("}")} and ${shout("${a * 10}")}");
+log("cost \${a} is literal, ${b} is not");
+let i = 0;
+while (i < 2) {
+    log("row ${i}: ${i * b}");
+    i = i + 1;
+}
+"#;
+
+const EXPECTED: &str = "sum: 3, joined: 12\n}! and 10!\ncost ${a} is literal, 2 is not\nrow 0: 0\nrow 1: 2\n";
+
+fn parse(source: &str) -> ASTNode {
+    let tokens = Lexer::from_str(source).tokenize().expect("lex");
+    Parser::new(tokens).parse().expect("parse")
+}
+
+#[test]
+fn lexer_splits_text_and_expression_parts() {
+    let tokens = Lexer::from_str(r#"log("x = ${f("}") + 1}!");"#).tokenize().unwrap();
+    let TokenKind::InterpolatedString(parts) = &tokens[2].kind else { panic!("{:?}", tokens[2]) };
+    assert_eq!(parts.len(), 3);
+    assert_eq!(parts[0], StringPart::Text("x = ".into()));
+    let StringPart::Expr(expr) = &parts[1] else { panic!("{parts:?}") };
+    let kinds: Vec<_> = expr.iter().map(|t| t.kind.clone()).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::Identifier("f".into()),
+            TokenKind::OpenParen,
+            TokenKind::StringLiteral("}".into()),
+            TokenKind::CloseParen,
+            TokenKind::Plus,
+            TokenKind::NumberLiteral(1.0),
+        ]
+    );
+    assert_eq!((expr[0].line, expr[0].column), (1, 12), "parts keep their source positions");
+    assert_eq!(parts[2], StringPart::Text("!".into()));
+    assert_eq!(tokens[3].kind, TokenKind::CloseParen);
+
+    let shown = tokens[2].to_string();
+    assert!(shown.starts_with("Interpolated @1:5\n  Text(\"x = \")\n  Expr\n    Identifier('f') @1:12"), "{shown}");
+
+    // escaped `\${` stays a plain string
+    let tokens = Lexer::from_str(r#""\${x}""#).tokenize().unwrap();
+    assert_eq!(tokens[0].kind, TokenKind::StringLiteral("${x}".into()));
+}
+
+#[test]
+fn unterminated_interpolation_points_at_the_opening_brace() {
+    let err = Lexer::from_str("let s = \"a ${x + 1;\nlog(s);\n").tokenize().unwrap_err();
+    assert!(matches!(err, LexerError::UnterminatedInterpolation(1, 13)), "{err:?}");
+    assert!(err.to_string().contains("'${' at 1:13 is never closed"), "{err}");
+    // a quote inside the braces that swallows the rest of the file is the same mistake
+    let err = Lexer::from_str("log(\"${f(\"x)}\");\n").tokenize().unwrap_err();
+    assert_eq!(err.position(), (1, 7), "{err}");
+
+    let err = Parser::new(Lexer::from_str("log(\"${}\");").tokenize().unwrap()).parse().unwrap_err();
+    assert!(err.to_string().contains("Empty interpolation"), "{err}");
+    let err = Parser::new(Lexer::from_str("log(\"${a b}\");").tokenize().unwrap()).parse().unwrap_err();
+    assert!(err.to_string().contains("expected one of: '}'"), "{err}");
+}
+
+#[test]
+fn interpolation_desugars_to_concatenation_everywhere() {
+    let run = run_source_captured(PROGRAM).unwrap();
+    assert_eq!(run.output, EXPECTED);
+
+    let ast = parse(PROGRAM);
+    let ai = AiEmitter::new().generate(&ast).unwrap();
+    assert!(ai.contains(r#""sum: " + (a + b)"#), "{ai}");
+    assert!(ai.contains(r#""cost \${a} is literal, ""#), "{ai}");
+
+    if !toolchain::available(Tool::Node) {
+        eprintln!("node not found; skipping JS side");
+        return;
+    }
+    let js = CodeGenerator::new().generate(&ast).unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("prog.js");
+    fs::write(&file, js).unwrap();
+    let out = Command::new("node").arg(&file).output().unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout), EXPECTED);
+}
+
+#[test]
+fn tokens_command_shows_the_parts() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("prog.ai");
+    fs::write(&file, "let n = 2;\nlog(\"n is ${n}\");\n").unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .current_dir(dir.path())
+        .arg("tokens")
+        .arg(&file)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    let text = format!("{}{}", String::from_utf8_lossy(&out.stdout), String::from_utf8_lossy(&out.stderr));
+    assert!(out.status.success(), "{text}");
+    assert!(text.contains("Interpolated @2:5\n  Text(\"n is \")\n  Expr\n    Identifier('n') @2:13"), "{text}");
+}
+
+#[test]
+fn formatter_leaves_interpolated_parts_alone() {
+    let src = "log(\"a ${f(\"}\")} b ${ {x} }\");\n";
+    assert_eq!(aeonmi_project::core::formatter::format_ai(src), src);
+}
+
+#[test]
+fn check_accepts_interpolation_and_string_concatenation() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("greet.ai");
+    fs::write(&file, "let name = \"Ada\";\nlet n = 3;\nlog(\"hi ${name}, ${n} left\");\nlog(n + \" items\");\nlet s = \"x\";\nlog(s - 1);\n").unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .env("XDG_CONFIG_HOME", dir.path())
+        .arg("check")
+        .arg(&file)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    // only the subtraction is an error
+    assert_eq!(stdout.matches("Arithmetic on non-number").count(), 1, "{stdout}");
+    assert_eq!(out.status.code(), Some(3), "{stdout}");
+
+    fs::write(&file, "let name = \"Ada\";\nlog(\"hi ${name}\");\n").unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .env("XDG_CONFIG_HOME", dir.path())
+        .arg("check")
+        .arg(&file)
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(0), "{}", String::from_utf8_lossy(&out.stdout));
+}
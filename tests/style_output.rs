@@ -0,0 +1,93 @@
+use aeonmi_project::core::diagnostics::{render_error, Span};
+use aeonmi_project::core::style::Style;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn detect(vars: &[(&str, &str)], tty: bool, no_unicode: bool) -> Style {
+    let lookup = |k: &str| vars.iter().find(|(n, _)| *n == k).map(|(_, v)| v.to_string());
+    Style::detect(lookup, tty, no_unicode)
+}
+
+fn strip_ansi(s: &str) -> String {
+    let mut out = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            for c in chars.by_ref() {
+                if c == 'm' {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Run the interactive shell with `help` on stdin and return its stdout.
+fn shell_help(args: &[&str], env: &[(&str, &str)]) -> String {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"));
+    cmd.args(args).env_remove("NO_COLOR").env_remove("AEONMI_COLOR").env("LANG", "C.UTF-8");
+    for (k, v) in env {
+        cmd.env(k, v);
+    }
+    let mut child = cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).spawn().unwrap();
+    child.stdin.take().unwrap().write_all(b"help\nexit\n").unwrap();
+    let out = child.wait_with_output().unwrap();
+    assert!(out.status.success());
+    String::from_utf8(out.stdout).unwrap()
+}
+
+#[test]
+fn detection_honours_no_color_aeonmi_color_and_locale() {
+    assert_eq!(detect(&[("LANG", "en_US.UTF-8")], true, false), Style::RICH);
+    assert!(!detect(&[("NO_COLOR", "1")], true, false).color);
+    assert!(!detect(&[], false, false).color, "pipes get no color by default");
+    assert!(detect(&[("NO_COLOR", "1"), ("AEONMI_COLOR", "always")], false, false).color);
+    assert!(!detect(&[("AEONMI_COLOR", "never")], true, false).color);
+    // empty NO_COLOR doesn't count, per no-color.org
+    assert!(detect(&[("NO_COLOR", "")], true, false).color);
+    assert_eq!(detect(&[("TERM", "dumb")], true, false), Style::PLAIN);
+    assert!(!detect(&[], true, true).unicode);
+    assert!(!detect(&[("AEONMI_UNICODE", "0")], true, false).unicode);
+    if !cfg!(windows) {
+        assert!(!detect(&[("LC_ALL", "C"), ("LANG", "en_US.UTF-8")], true, false).unicode);
+    }
+}
+
+#[test]
+fn diagnostic_renders_colored_and_plain() {
+    let src = "let x = 1\nlog(x);\n";
+    let span = Span { line: 2, col: 1, len: 3 };
+    let plain = render_error(&Style::PLAIN, "demo.ai", src, "Expected ';'", span, Some("AEO-P001"));
+    let expected = [
+        "error[AEO-P001]: Expected ';'",
+        "--> demo.ai:2:1",
+        "    2 |",
+        "   2 | log(x);",
+        "      | ^^^",
+        "      = run `aeonmi explain-error AEO-P001` for more",
+        "",
+        "",
+    ];
+    assert_eq!(plain, expected.join("\n"));
+    let colored = render_error(&Style::RICH, "demo.ai", src, "Expected ';'", span, Some("AEO-P001"));
+    assert!(colored.starts_with("\x1b[1;91merror[AEO-P001]:\x1b[0m \x1b[97mExpected ';'\x1b[0m\n"), "{colored:?}");
+    assert_eq!(strip_ansi(&colored), plain);
+}
+
+#[test]
+fn shell_help_plain_matches_colored_text() {
+    let colored = shell_help(&["--quiet"], &[("AEONMI_COLOR", "always")]);
+    let plain = shell_help(&["--quiet"], &[("NO_COLOR", "1")]);
+    assert!(colored.contains("\x1b["), "{colored:?}");
+    assert!(!plain.contains('\x1b'), "{plain:?}");
+    assert_eq!(strip_ansi(&colored), plain);
+    assert!(plain.contains("╔═") && plain.contains("Navigation:"));
+
+    let ascii = shell_help(&["--quiet", "--no-unicode"], &[("NO_COLOR", "1")]);
+    assert!(ascii.is_ascii(), "{ascii}");
+    assert!(ascii.contains("+====") && ascii.contains("[AEONMI]") && ascii.contains("Quantum:"));
+    assert_eq!(ascii.lines().count(), plain.lines().count());
+}
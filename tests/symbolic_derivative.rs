@@ -0,0 +1,83 @@
+//! `derivative` / `simplify` builtins and `aeonmi math derive`: golden canonical output.
+use aeonmi_project::commands::run::run_source_captured;
+use std::process::Command;
+
+fn derive(expr: &str, var: &str) -> String {
+    let out = run_source_captured(&format!("let d = derivative(\"{expr}\", \"{var}\");\nlog(d);\n")).unwrap().output;
+    out.trim_end().to_string()
+}
+
+#[test]
+fn polynomials() {
+    for (expr, want) in [
+        ("x^2 + 3*x", "2*x + 3"),
+        ("x^3 - 2*x^2 + x - 7", "3*x^2 - 4*x + 1"),
+        ("5", "0"),
+        ("x*x*x", "3*x^2"),
+        ("(x + 1)*(x - 1)", "2*x"),
+        ("y*x^2 + y", "2*x*y"),
+        ("1/x", "-1/x^2"),
+    ] {
+        assert_eq!(derive(expr, "x"), want, "d/dx {expr}");
+    }
+    assert_eq!(derive("x^2*y + y^3", "y"), "x^2 + 3*y^2");
+}
+
+#[test]
+fn products_and_quotients() {
+    for (expr, want) in [
+        ("x^2*sin(x)", "x^2*cos(x) + 2*x*sin(x)"),
+        ("x*exp(x)", "x*exp(x) + exp(x)"),
+        ("x/(x + 1)", "1/(x + 1)^2"),
+        ("sin(x)*cos(x)", "cos(x)^2 - sin(x)^2"),
+    ] {
+        assert_eq!(derive(expr, "x"), want, "d/dx {expr}");
+    }
+}
+
+#[test]
+fn chain_rule() {
+    for (expr, want) in [
+        ("sin(x^2)", "2*x*cos(x^2)"),
+        ("(2*x + 1)^3", "6*(2*x + 1)^2"),
+        ("exp(3*x)", "3*exp(3*x)"),
+        ("ln(x^2 + 1)", "2*x/(x^2 + 1)"),
+        ("sqrt(x)", "1/(2*sqrt(x))"),
+        ("cos(2*x)", "-2*sin(2*x)"),
+        ("tan(x)", "1/cos(x)^2"),
+    ] {
+        assert_eq!(derive(expr, "x"), want, "d/dx {expr}");
+    }
+}
+
+#[test]
+fn simplify_and_errors() {
+    let out = run_source_captured("let s = simplify(\"x + x + 2*3 - x*1\");\nlog(s);\n").unwrap().output;
+    assert_eq!(out.trim_end(), "x + 6");
+
+    let err = run_source_captured("let d = derivative(\"abs(x)\", \"x\");\n").unwrap_err();
+    assert!(err.contains("derivative (line 1): unsupported function `abs` (supported: sin, cos, tan, exp, ln, sqrt)"), "{err}");
+    let err = run_source_captured("let d = derivative(\"x ) 2\", \"x\");\n").unwrap_err();
+    assert!(err.contains("unexpected `)` at column 3"), "{err}");
+    let err = run_source_captured("let d = derivative(\"x^2\", \"2x\");\n").unwrap_err();
+    assert!(err.contains("`2x` is not a variable name"), "{err}");
+}
+
+#[test]
+fn cli_derive() {
+    let run = |args: &[&str]| {
+        let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+            .args(args)
+            .env("NO_COLOR", "1")
+            .output()
+            .expect("failed to run aeonmi_project");
+        (out.status.code(), String::from_utf8_lossy(&out.stdout).into_owned(), String::from_utf8_lossy(&out.stderr).into_owned())
+    };
+    let (code, out, _) = run(&["math", "derive", "x^2 + 3*x", "--wrt", "x"]);
+    assert_eq!((code, out.as_str()), (Some(0), "2*x + 3\n"));
+    let (code, out, _) = run(&["math", "derive", "t^2*x", "--wrt", "t"]);
+    assert_eq!((code, out.as_str()), (Some(0), "2*t*x\n"));
+    let (code, _, err) = run(&["math", "derive", "floor(x)"]);
+    assert_eq!(code, Some(2));
+    assert!(err.contains("supported: sin, cos, tan, exp, ln, sqrt"), "{err}");
+}
@@ -0,0 +1,65 @@
+//! Golden tests for `aeonmi symbols` / `aeonmi outline`: each `tests/symbols/outline.<ext>`
+//! is the expected output for `tests/symbols/outline.ai`.
+use std::fs;
+use std::process::{Command, Output};
+
+const INPUT: &str = "tests/symbols/outline.ai";
+
+fn run(args: &[&str], unicode: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .args(args)
+        .env("NO_COLOR", "1")
+        .env("AEONMI_UNICODE", unicode)
+        .output()
+        .expect("failed to run aeonmi_project")
+}
+
+fn assert_golden(args: &[&str], unicode: &str, golden: &str) -> String {
+    let out = run(args, unicode);
+    assert!(out.status.success(), "{args:?}: {}", String::from_utf8_lossy(&out.stderr));
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert_eq!(stdout, fs::read_to_string(format!("tests/symbols/{golden}")).unwrap(), "{golden}");
+    stdout
+}
+
+#[test]
+fn lines_are_tab_separated_with_containers() {
+    let text = assert_golden(&["symbols", INPUT], "1", "outline.txt");
+    assert!(text.contains("7:1\tparameter\tcount.word\tUnknown\n"));
+}
+
+#[test]
+fn tree_nests_locals_under_their_function() {
+    assert_golden(&["symbols", INPUT, "--tree"], "1", "outline.tree");
+    assert_golden(&["outline", INPUT], "0", "outline.ascii.tree");
+}
+
+#[test]
+fn json_follows_the_outline_schema() {
+    let text = assert_golden(&["symbols", INPUT, "--json"], "1", "outline.json");
+    let v: serde_json::Value = serde_json::from_str(&text).unwrap();
+    fn check(node: &serde_json::Value) -> usize {
+        let obj = node.as_object().unwrap();
+        let mut keys: Vec<&str> = obj.keys().map(String::as_str).collect();
+        keys.sort_unstable();
+        assert_eq!(keys, ["children", "column", "end_column", "end_line", "kind", "line", "name", "type"]);
+        assert!(["function", "variable", "parameter", "enum"].contains(&obj["kind"].as_str().unwrap()));
+        assert!(obj["type"].is_null() || obj["type"].is_string());
+        1 + obj["children"].as_array().unwrap().iter().map(check).sum::<usize>()
+    }
+    let nodes = v.as_array().unwrap();
+    assert_eq!(nodes.iter().map(check).sum::<usize>(), 12);
+    let count = nodes.iter().find(|n| n["name"] == "count").unwrap();
+    assert_eq!(count["type"], "fn(Unknown, Unknown) -> Number");
+    assert_eq!(count["children"][2]["name"], "total");
+}
+
+#[test]
+fn unparsable_input_is_a_compile_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let bad = dir.path().join("bad.ai");
+    fs::write(&bad, "function (\n").unwrap();
+    let out = run(&["symbols", bad.to_str().unwrap()], "0");
+    assert_eq!(out.status.code(), Some(3));
+    assert!(out.stdout.is_empty());
+}
@@ -0,0 +1,74 @@
+use aeonmi_project::core::toolchain::{doctor_report, ProbeCache, Tool, NODE_FALLBACK_NOTICE};
+use std::fs;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+#[test]
+fn probe_cache_honours_ttl_per_tool() {
+    let mut calls: Vec<Tool> = Vec::new();
+    let start = Instant::now();
+    let mut cache = ProbeCache::new(
+        |t| {
+            calls.push(t);
+            (t == Tool::Node).then(|| "v20.0.0".to_string())
+        },
+        Duration::from_secs(60),
+    );
+    assert_eq!(cache.version_at(Tool::Node, start).as_deref(), Some("v20.0.0"));
+    assert_eq!(cache.version_at(Tool::Node, start + Duration::from_secs(59)).as_deref(), Some("v20.0.0"));
+    // misses are cached too
+    assert_eq!(cache.version_at(Tool::Rustc, start), None);
+    assert_eq!(cache.version_at(Tool::Rustc, start + Duration::from_secs(30)), None);
+    // expired entry re-probes
+    cache.version_at(Tool::Node, start + Duration::from_secs(60));
+    drop(cache);
+    assert_eq!(calls, vec![Tool::Node, Tool::Rustc, Tool::Node]);
+}
+
+#[test]
+fn doctor_report_lists_versions_and_lost_features() {
+    let report = doctor_report(&[
+        (Tool::Node, None),
+        (Tool::Python, Some("Python 3.12.1".into())),
+        (Tool::Rustc, None),
+    ]);
+    let lines: Vec<&str> = report.lines().collect();
+    assert_eq!(lines[0], "node    not found");
+    assert_eq!(lines[1], "python  Python 3.12.1");
+    assert_eq!(lines[2], "rustc   not found");
+    assert_eq!(lines[4], "unavailable:");
+    assert!(lines[5].starts_with("  node    run/exec via JS"), "{report}");
+    assert_eq!(lines[6], "  rustc   exec .rs");
+    assert_eq!(lines.len(), 7, "{report}");
+
+    let all = doctor_report(&[(Tool::Node, Some("v20.0.0".into()))]);
+    assert_eq!(all, "node    v20.0.0\nall external tools found\n");
+}
+
+#[test]
+fn exec_without_node_falls_back_with_one_line_notice() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("hello.ai");
+    fs::write(&input, "log(\"hi\");\n").unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .args(["exec", input.to_str().unwrap()])
+        .current_dir(dir.path())
+        .env("PATH", dir.path())
+        .env_remove("AEONMI_NATIVE")
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    assert!(String::from_utf8_lossy(&out.stdout).contains("hi"));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert_eq!(stderr.matches(NODE_FALLBACK_NOTICE).count(), 1, "stderr: {stderr}");
+
+    // --native silences it
+    let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .args(["exec", input.to_str().unwrap(), "--native"])
+        .current_dir(dir.path())
+        .env("PATH", dir.path())
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    assert!(!String::from_utf8_lossy(&out.stderr).contains("node not found"));
+}
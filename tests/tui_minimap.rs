@@ -0,0 +1,88 @@
+use aeonmi_project::tui::minimap::{Geometry, Marker, Minimap};
+
+#[test]
+fn short_buffers_get_one_row_per_line() {
+    let g = Geometry::new(10, 40);
+    assert_eq!(g.lines_per_row(), 1);
+    assert_eq!(g.used_rows(), 10);
+    assert_eq!(g.row_of(7), 7);
+    assert_eq!(g.first_line(7), 7);
+    assert_eq!(g.first_line(30), 9, "rows past the end clamp to the last line");
+}
+
+#[test]
+fn long_buffers_fold_evenly_onto_every_row() {
+    let g = Geometry::new(1000, 40);
+    assert_eq!(g.lines_per_row(), 25);
+    assert_eq!(g.used_rows(), 40);
+    assert_eq!(g.row_of(0), 0);
+    assert_eq!(g.row_of(24), 0);
+    assert_eq!(g.row_of(25), 1);
+    assert_eq!(g.row_of(999), 39);
+    assert_eq!(g.first_line(39), 975);
+
+    // 101 lines on 40 rows: 3 per row, the last row holding the remainder
+    let g = Geometry::new(101, 40);
+    assert_eq!(g.lines_per_row(), 3);
+    assert_eq!(g.used_rows(), 34);
+    assert_eq!(g.row_of(100), 33);
+
+    let empty = Geometry::new(0, 40);
+    assert_eq!(empty.used_rows(), 0);
+    assert_eq!(empty.viewport(0, 20), 0..0);
+}
+
+#[test]
+fn viewport_covers_the_rows_of_the_lines_on_screen() {
+    let g = Geometry::new(1000, 40);
+    assert_eq!(g.viewport(0, 20), 0..1);
+    assert_eq!(g.viewport(20, 20), 0..2, "lines 20..40 straddle rows 0 and 1");
+    assert_eq!(g.viewport(990, 20), 39..40, "a view past the end stops at the last line");
+    assert_eq!(Geometry::new(10, 40).viewport(2, 5), 2..7);
+}
+
+#[test]
+fn clicking_centres_the_row_and_clamps_to_the_last_screenful() {
+    let g = Geometry::new(1000, 40);
+    // row 10 is lines 250..275: its middle (262) lands mid-view
+    assert_eq!(g.scroll_for_click(10, 20), 252);
+    assert_eq!(g.scroll_for_click(0, 20), 2);
+    assert_eq!(g.scroll_for_click(39, 20), 977);
+    assert_eq!(g.scroll_for_click(99, 20), 980, "never past total - height");
+
+    let short = Geometry::new(10, 40);
+    assert_eq!(short.scroll_for_click(8, 20), 0, "a buffer that fits never scrolls");
+    assert_eq!(Geometry::new(30, 40).scroll_for_click(25, 10), 20);
+}
+
+#[test]
+fn markers_follow_their_sources_incrementally() {
+    let mut m = Minimap::default();
+    m.resize(Geometry::new(100, 10));
+    m.set_diagnostics([(5, Marker::Warning), (7, Marker::Error), (55, Marker::Warning)]);
+    m.set_matches(&[1, 3, 95]);
+    assert_eq!(m.diagnostic(0), Some(Marker::Error), "an error outranks a warning on the same row");
+    assert_eq!(m.counts(0), [1, 1, 2]);
+    assert_eq!(m.diagnostic(5), Some(Marker::Warning));
+    assert!(m.has_match(9));
+
+    // replacing the sources only moves what changed
+    m.set_diagnostics([(5, Marker::Warning), (99, Marker::Error)]);
+    m.set_matches(&[3]);
+    assert_eq!(m.counts(0), [0, 1, 1]);
+    assert_eq!(m.diagnostic(5), None);
+    assert_eq!(m.diagnostic(9), Some(Marker::Error));
+    assert!(!m.has_match(9));
+
+    // a new height re-buckets the same markers
+    m.resize(Geometry::new(100, 100));
+    assert_eq!(m.counts(3), [0, 0, 1]);
+    assert_eq!(m.diagnostic(5), Some(Marker::Warning));
+    assert_eq!(m.diagnostic(99), Some(Marker::Error));
+
+    // markers past the end of a shrunk buffer are dropped
+    m.resize(Geometry::new(50, 100));
+    assert_eq!(m.diagnostic(99), None);
+    m.set_matches(&[]);
+    assert!((0..100).all(|r| !m.has_match(r)));
+}
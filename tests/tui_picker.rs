@@ -0,0 +1,106 @@
+use aeonmi_project::tui::picker::{self, fuzzy_score, Picker, Walker, CHANNEL_CAPACITY, RECENT_LIMIT};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+fn tree(files: &[&str]) -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for f in files {
+        let p = dir.path().join(f);
+        fs::create_dir_all(p.parent().unwrap()).unwrap();
+        fs::write(p, "log(1);\n").unwrap();
+    }
+    dir
+}
+
+fn settle(p: &mut Picker) {
+    let start = Instant::now();
+    while p.scanning() {
+        p.poll();
+        assert!(start.elapsed() < Duration::from_secs(10), "walk never finished");
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn fuzzy_scorer_prefers_contiguous_name_matches() {
+    assert_eq!(fuzzy_score("", "anything.ai"), Some(0));
+    assert_eq!(fuzzy_score("xyz", "src/main.ai"), None);
+    assert_eq!(fuzzy_score("mian", "src/main.ai"), None, "order matters");
+    assert!(fuzzy_score("MAIN", "src/main.ai").is_some(), "case-insensitive");
+    assert!(fuzzy_score("s m", "src/main.ai").is_some(), "whitespace is ignored");
+
+    let main = fuzzy_score("main", "src/main.ai").unwrap();
+    assert!(main > fuzzy_score("main", "src/domain_info.ai").unwrap(), "word start beats mid-word");
+    assert!(main > fuzzy_score("main", "m/a/i/n.ai").unwrap(), "contiguous beats scattered");
+    assert!(main > fuzzy_score("main", "examples/very/deep/nested/folder/main.ai").unwrap(), "shorter paths win ties");
+    // The best alignment is kept, not the first one: "lib" prefix vs the file name.
+    assert!(fuzzy_score("lin", "lib/linear.ai").unwrap() > fuzzy_score("lin", "lib/x/in.ai").unwrap());
+    assert!(fuzzy_score("qc", "quantum/circuit.ai").unwrap() > fuzzy_score("qc", "quick.ai").unwrap());
+}
+
+#[test]
+fn picker_lists_ai_files_with_recent_first() {
+    let dir = tree(&["src/main.ai", "src/util.ai", "examples/demo.ai", "notes.txt", "target/gen.ai", ".git/hook.ai"]);
+    let root = dir.path();
+    for f in ["examples/demo.ai", "src/util.ai"] {
+        picker::remember(root, &root.join(f)).unwrap();
+    }
+    let recent = picker::load_recent(root);
+    assert_eq!(recent, [PathBuf::from("src/util.ai"), PathBuf::from("examples/demo.ai")]);
+
+    let mut p = Picker::open(root, recent);
+    settle(&mut p);
+    let rows: Vec<(String, bool)> = p.matches().iter().map(|m| (m.path.to_string_lossy().replace('\\', "/"), m.recent)).collect();
+    assert_eq!(
+        rows,
+        [("src/util.ai".into(), true), ("examples/demo.ai".into(), true), ("src/main.ai".into(), false)],
+        "target/ and .git/ are skipped and recent files are not repeated"
+    );
+
+    for c in "main".chars() {
+        p.push(c);
+    }
+    assert_eq!(p.selection(), Some(Path::new("src/main.ai")));
+    p.pop();
+    p.move_selection(5);
+    assert_eq!(p.selected, p.matches().len() - 1);
+}
+
+#[test]
+fn recent_list_is_capped_and_deduplicated() {
+    let names: Vec<String> = (0..RECENT_LIMIT + 3).map(|i| format!("f{i}.ai")).collect();
+    let dir = tree(&names.iter().map(String::as_str).collect::<Vec<_>>());
+    for n in &names {
+        picker::remember(dir.path(), &dir.path().join(n)).unwrap();
+    }
+    let recent = picker::remember(dir.path(), &dir.path().join("f5.ai")).unwrap();
+    assert_eq!(recent.len(), RECENT_LIMIT);
+    assert_eq!(recent[0], PathBuf::from("f5.ai"));
+    assert_eq!(recent.iter().filter(|p| p.as_path() == Path::new("f5.ai")).count(), 1);
+    assert_eq!(picker::load_recent(dir.path()), recent);
+}
+
+#[test]
+fn closing_the_picker_cancels_the_background_walk() {
+    let names: Vec<String> = (0..CHANNEL_CAPACITY * 4).map(|i| format!("d{}/f{i}.ai", i % 7)).collect();
+    let dir = tree(&names.iter().map(String::as_str).collect::<Vec<_>>());
+
+    // Nobody drains: the walker fills the channel, then must still stop when cancelled.
+    let walker = Walker::spawn(dir.path().to_path_buf());
+    std::thread::sleep(Duration::from_millis(50));
+    let start = Instant::now();
+    let sent = walker.cancel();
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(sent <= CHANNEL_CAPACITY, "walker kept going after cancel: {sent}");
+
+    // Draining to the end sees every file.
+    let mut walker = Walker::spawn(dir.path().to_path_buf());
+    let mut seen = Vec::new();
+    while !walker.finished() {
+        seen.extend(walker.drain());
+    }
+    seen.extend(walker.drain());
+    assert_eq!(seen.len(), names.len());
+    assert_eq!(walker.cancel(), names.len());
+}
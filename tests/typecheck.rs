@@ -0,0 +1,64 @@
+use aeonmi_project::commands::typecheck::{check, render};
+use aeonmi_project::core::ast::ASTNode;
+use aeonmi_project::core::incremental::FUNCTION_METRICS;
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::parser::Parser;
+use aeonmi_project::core::style::Style;
+use std::process::Command;
+
+const SRC: &str = "let n = 1;\nfunction ok(a) { return a + 1; }\nfunction bad() { let s = \"x\"; return s - 1; }\n";
+
+fn parse(source: &str) -> ASTNode {
+    Parser::new(Lexer::from_str(source).tokenize().expect("lex")).parse().expect("parse")
+}
+
+#[test]
+fn reports_type_errors_and_records_each_function() {
+    let report = check(&parse(SRC), "demo.ai");
+    assert!(report.diagnostics.iter().any(|d| d.message.contains("Arithmetic on non-number")), "{:?}", report.diagnostics);
+    let mut names: Vec<(usize, &str)> = report.timings.iter().map(|t| (t.index, t.name.as_str())).collect();
+    names.sort();
+    assert_eq!(names, [(1, "ok"), (2, "bad")]);
+    assert!(report.timings.windows(2).all(|w| w[0].ns >= w[1].ns));
+    let fm = FUNCTION_METRICS.lock().unwrap();
+    for idx in [1, 2] {
+        let site = fm.get(&idx).and_then(|m| m.site.clone()).expect("recorded");
+        assert_eq!(site.file.as_deref(), Some("demo.ai"));
+    }
+}
+
+#[test]
+fn render_lists_errors_timings_and_summary() {
+    let report = check(&parse(SRC), "demo.ai");
+    let text = render(&report, true, &Style::PLAIN);
+    assert!(text.contains("demo.ai:3:38: error: Arithmetic on non-number"), "{text}");
+    let header = text.lines().find(|l| l.starts_with("function")).expect("timings header");
+    assert!(header.ends_with("infer_ns"), "{header}");
+    assert!(text.ends_with("demo.ai: 1 type error in 2 functions\n"), "{text}");
+    assert!(!render(&report, false, &Style::PLAIN).contains("infer_ns"));
+}
+
+#[test]
+fn cli_exit_code_and_metrics_file() {
+    let home = tempfile::tempdir().unwrap();
+    let file = home.path().join("bad.ai");
+    std::fs::write(&file, SRC).unwrap();
+    let run = |path: &std::path::Path| {
+        Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+            .env("XDG_CONFIG_HOME", home.path())
+            .args(["typecheck", "--timings"])
+            .arg(path)
+            .output()
+            .unwrap()
+    };
+    let out = run(&file);
+    assert_eq!(out.status.code(), Some(3), "{}", String::from_utf8_lossy(&out.stderr));
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("Arithmetic on non-number") && stdout.contains("infer_ns"), "{stdout}");
+    let metrics = std::fs::read_to_string(home.path().join("aeonmi").join("aeonmi_metrics.json")).unwrap();
+    assert!(metrics.contains("\"bad\""), "{metrics}");
+
+    let good = home.path().join("good.ai");
+    std::fs::write(&good, "function ok(a) { return a + 1; }\n").unwrap();
+    assert_eq!(run(&good).status.code(), Some(0));
+}
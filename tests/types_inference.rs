@@ -11,3 +11,47 @@ fn arithmetic_type_check() {
     ctx.infer_program(&ast);
     assert!(ctx.diags.is_empty(), "Unexpected diagnostics: {:?}", ctx.diags);
 }
+
+use aeonmi_project::core::types::TypeKind;
+
+fn diags_with(src: &str, globals: &[(&str, TypeKind)]) -> Vec<String> {
+    let tokens = Lexer::from_str(src).tokenize().expect("lex");
+    let ast = Parser::new(tokens).parse().expect("parse");
+    let mut ctx = TypeContext::new();
+    for (name, ty) in globals { ctx.declare_global(name, ty.clone()); }
+    ctx.infer_program(&ast);
+    ctx.diags.into_iter().map(|d| d.message).collect()
+}
+
+#[test]
+fn element_type_mismatches_are_diagnosed() {
+    let xs = [("xs", TypeKind::array(TypeKind::Number))];
+    let d = diags_with("push(xs, \"seven\");", &xs);
+    assert_eq!(d, vec!["push of String onto Array<Number>"]);
+    let d = diags_with("let c = counts(); let n = c[0];", &[]);
+    assert_eq!(d, vec!["Map key must be String, got Number"]);
+    let d = diags_with("let s = \"text\"; let x = s();", &[]);
+    assert_eq!(d, vec!["Call of non-function value 's' of type String"]);
+    let d = diags_with("function lower(s) { return s == \"a\"; } let ys = map(xs, lower);", &[("xs", TypeKind::array(TypeKind::String))]);
+    assert!(d.is_empty(), "{d:?}");
+    assert!(diags_with("push(xs, 8); let y = xs[1] * 2;", &xs).is_empty());
+}
+
+#[test]
+fn generics_propagate_through_chained_higher_order_calls() {
+    let words = [("words", TypeKind::array(TypeKind::String))];
+    // `same` has no return type of its own; each call re-infers it from the element type.
+    let src = "function same(x) { return x; } function long(w) { return len(w) > 3; }\nlet ys = map(filter(words, long), same);\nlet n = ys[0] * 2;";
+    assert_eq!(diags_with(src, &words), vec!["Arithmetic on non-number"]);
+    let nums = [("words", TypeKind::array(TypeKind::Number))];
+    let src = "function same(x) { return x; } function double(x) { return x * 2; }\nlet ys = map(map(words, same), double);\nlet n = ys[0] * 2;";
+    assert!(diags_with(src, &nums).is_empty());
+    let d = diags_with("function double(x) { return x * 2; } let zs = filter(words, double);", &nums);
+    assert_eq!(d, vec!["filter callback returns Number, expected Boolean"]);
+}
+
+#[test]
+fn comparisons_with_an_untyped_parameter_check_like_arithmetic() {
+    assert!(diags_with("function big(n) { return n > 7 && 3 <= n; }", &[]).is_empty());
+    assert_eq!(diags_with("function big(n) { return n > \"7\"; }", &[]), vec!["Comparison on non-number"]);
+}
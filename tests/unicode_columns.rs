@@ -0,0 +1,62 @@
+use aeonmi_project::core::columns::{display_col, source_offset};
+use aeonmi_project::core::diagnostics::{render_error, Span};
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::scope_map::rename_symbol;
+use aeonmi_project::core::style::Style;
+use aeonmi_project::core::token::TokenKind;
+
+fn column_of(source: &str, ident: &str) -> usize {
+    let tokens = Lexer::from_str(source).tokenize().expect("lex");
+    tokens.iter().find(|t| t.kind == TokenKind::Identifier(ident.into())).expect("ident").column
+}
+
+#[test]
+fn lexer_columns_are_byte_offsets() {
+    let src = "let s = \"⚡𓀀\"; let x = 1;";
+    let col = column_of(src, "x");
+    assert_eq!(&src[col - 1..col], "x");
+}
+
+#[test]
+fn display_columns_count_cells() {
+    assert_eq!(display_col("⚡x", 4), 3); // emoji is 3 bytes, 2 cells
+    assert_eq!(display_col("中文x", 7), 5);
+    assert_eq!(display_col("e\u{301}x", 4), 2); // combining mark takes no cell
+    assert_eq!(display_col("⚡x", 2), 1); // inside a char: floor to its start
+}
+
+#[test]
+fn source_offset_maps_nfc_columns_onto_raw_text() {
+    let raw = "let s = \"e\u{301}\"; let x = 1;";
+    let col = column_of(raw, "x");
+    let at = source_offset(raw, col).expect("boundary");
+    assert_eq!(&raw[at..at + 1], "x");
+    assert_eq!(source_offset("⚡x", 2), None);
+}
+
+#[test]
+fn rename_next_to_wide_glyphs() {
+    let src = "let a = \"⚡𓀀\"; let b = a;\nlog(a);";
+    let col = column_of(src, "a");
+    let out = rename_symbol(src, 1, col, "alpha").expect("rename");
+    assert_eq!(out, "let alpha = \"⚡𓀀\"; let b = alpha;\nlog(alpha);");
+}
+
+#[test]
+fn rename_after_combining_characters_keeps_raw_text() {
+    let src = "let s = \"e\u{301}\"; let t = s;\nlog(s);";
+    let col = column_of(src, "t");
+    let out = rename_symbol(src, 1, col, "total").expect("rename");
+    assert_eq!(out, "let s = \"e\u{301}\"; let total = s;\nlog(s);");
+}
+
+#[test]
+fn caret_lines_up_under_cjk_and_emoji() {
+    let src = "log(\"中文⚡\"); oops;";
+    let col = src.find("oops").unwrap() + 1;
+    let out = render_error(&Style::PLAIN, "t.ai", src, "bad", Span { line: 1, col, len: 4 }, None);
+    let caret = out.lines().find(|l| l.contains('^')).expect("caret line");
+    // `log("` + 中文 (2 cells each) + ⚡ (2 cells) + `"); `, after the 8-cell gutter
+    assert_eq!(caret.find('^'), Some(8 + 5 + 4 + 2 + 4), "{out}");
+    assert!(caret.ends_with(" ^^^^"), "{caret}");
+}
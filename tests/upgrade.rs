@@ -0,0 +1,120 @@
+use aeonmi_project::commands::upgrade::{breaking_highlights, confirm, parse_feed, report, Version};
+use std::process::Command;
+
+fn v(s: &str) -> Version {
+    Version::parse(s).unwrap()
+}
+
+const CRATES_IO: &str = r#"{
+  "crate": {"name": "aeonmi", "max_version": "0.4.0-beta.1", "max_stable_version": "0.3.1"},
+  "versions": [
+    {"num": "0.4.0-beta.1", "yanked": false},
+    {"num": "0.3.2", "yanked": true},
+    {"num": "0.3.1", "yanked": false},
+    {"num": "0.2.0", "yanked": false}
+  ]
+}"#;
+
+const GITHUB: &str = r###"[
+  {"tag_name": "v0.5.0", "draft": true, "prerelease": false, "body": "- BREAKING: draft only"},
+  {"tag_name": "v0.4.0", "draft": false, "prerelease": false,
+   "body": "## Breaking changes\n- `metrics.json` moves to schema 3; old files are migrated on load\n- `metrics-top --sort` renamed to `--by`\n\n## Fixes\n- faster watch\n"},
+  {"tag_name": "v0.3.0", "draft": false, "prerelease": false,
+   "body": "* **breaking:** `aeonmi run` exits 4 on runtime errors\n* new `slice` builtin\n"},
+  {"tag_name": "v0.2.0", "draft": false, "prerelease": false, "body": "- BREAKING: already installed"},
+  {"tag_name": "v0.4.1-rc.1", "draft": false, "prerelease": true, "body": "- BREAKING: not out yet"}
+]"###;
+
+#[test]
+fn versions_compare_by_semver_precedence() {
+    assert!(v("0.2.0") < v("0.2.1"));
+    assert!(v("0.10.0") > v("0.9.9"), "numeric, not lexical");
+    assert!(v("1.0.0-rc.1") < v("1.0.0"));
+    assert!(v("1.0.0-alpha") < v("1.0.0-alpha.1"));
+    assert!(v("1.0.0-rc.2") < v("1.0.0-rc.10"));
+    assert!(v("1.0.0-1") < v("1.0.0-alpha"));
+    assert_eq!(v("v0.3.0+build.7"), v("0.3.0"));
+    assert_eq!(v("0.3.0-beta.2").to_string(), "0.3.0-beta.2");
+    for bad in ["0.3", "0.3.0.1", "x.1.0", "0.3.0-", ""] {
+        assert!(Version::parse(bad).is_none(), "{bad}");
+    }
+    assert_eq!(Version::current(), v(env!("CARGO_PKG_VERSION")));
+}
+
+#[test]
+fn crates_io_feed_skips_yanked_and_prerelease_versions() {
+    let feed = parse_feed(CRATES_IO).unwrap();
+    assert_eq!(feed.latest, v("0.3.1"));
+    assert!(!feed.releases.iter().any(|r| r.version == v("0.3.2")), "yanked");
+    assert!(breaking_highlights(&feed, &v("0.2.0")).is_empty());
+
+    let text = report(&v("0.2.0"), &feed);
+    assert!(text.starts_with("aeonmi 0.2.0 is installed; 0.3.1 is available.\n"), "{text}");
+    assert!(text.contains("no release notes"), "{text}");
+    assert!(text.contains("cargo install aeonmi --locked"), "{text}");
+
+    // the summary without a versions list is enough
+    let feed = parse_feed(r#"{"crate": {"max_stable_version": "0.2.0"}}"#).unwrap();
+    assert_eq!(report(&v("0.2.0"), &feed), "aeonmi 0.2.0 is up to date.\n");
+    assert!(report(&v("0.9.0"), &feed).contains("newer than the latest release (0.2.0)"));
+
+    assert!(parse_feed("<html>rate limited</html>").is_err());
+    assert!(parse_feed(r#"{"errors": [{"detail": "Not Found"}]}"#).is_err());
+    assert!(parse_feed(r#"{"crate": {}, "versions": [{"num": "1.0.0-rc.1"}]}"#).is_err(), "no stable version");
+}
+
+#[test]
+fn github_feed_lists_breaking_changes_since_the_installed_version() {
+    let feed = parse_feed(GITHUB).unwrap();
+    assert_eq!(feed.latest, v("0.4.0"), "drafts and prereleases are not offered");
+    assert_eq!(
+        breaking_highlights(&feed, &v("0.2.0")),
+        vec![
+            "0.3.0: **breaking:** `aeonmi run` exits 4 on runtime errors",
+            "0.4.0: `metrics.json` moves to schema 3; old files are migrated on load",
+            "0.4.0: `metrics-top --sort` renamed to `--by`",
+        ]
+    );
+    assert_eq!(breaking_highlights(&feed, &v("0.3.0")).len(), 2);
+
+    let text = report(&v("0.3.0"), &feed);
+    assert!(text.contains("Breaking changes since your version:\n  - 0.4.0: `metrics.json` moves"), "{text}");
+    assert!(!text.contains("faster watch") && !text.contains("slice"), "{text}");
+}
+
+#[test]
+fn install_confirmation_defaults_to_no() {
+    assert!(confirm("Install?", &mut "y\n".as_bytes()));
+    assert!(confirm("Install?", &mut " YES \n".as_bytes()));
+    assert!(!confirm("Install?", &mut "\n".as_bytes()));
+    assert!(!confirm("Install?", &mut "nope\n".as_bytes()));
+    assert!(!confirm("Install?", &mut "".as_bytes()));
+}
+
+#[test]
+fn offline_never_reaches_the_feed() {
+    let run = |args: &[&str], env: Option<(&str, &str)>| {
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"));
+        cmd.arg("upgrade").args(args).env("NO_COLOR", "1").env("AEONMI_UPGRADE_URL", "http://127.0.0.1:9/unreachable");
+        if let Some((k, val)) = env {
+            cmd.env(k, val);
+        }
+        cmd.output().unwrap()
+    };
+    let version = env!("CARGO_PKG_VERSION");
+
+    let out = run(&["--check", "--offline"], None);
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8_lossy(&out.stdout), format!("aeonmi {version} (offline: not checking for updates)\n"));
+
+    let out = run(&["--check"], Some(("AEONMI_OFFLINE", "1")));
+    assert!(out.status.success());
+    let out = run(&["--install", "--yes"], Some(("AEONMI_OFFLINE", "1")));
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("--install needs the network"));
+
+    // without --check nothing is fetched
+    let out = run(&[], None);
+    assert!(out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).contains("aeonmi upgrade --check"));
+}
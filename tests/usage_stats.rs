@@ -0,0 +1,89 @@
+//! Local usage counters: library entry points against a temp config dir, then the CLI.
+use aeonmi_project::config::StatsSettings;
+use aeonmi_project::core::usage_stats::{begin, bucket, finish, load, record, reset, stats_path};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::Duration;
+
+// `stats_path` follows the process-wide AEONMI_CONFIG_DIR.
+static ENV: Mutex<()> = Mutex::new(());
+
+#[test]
+fn counters_accumulate_across_invocations() {
+    let _g = ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let dir = tempfile::tempdir().unwrap();
+    std::env::set_var("AEONMI_CONFIG_DIR", dir.path());
+    let enabled = StatsSettings::from_toml_str("[stats]\nenabled = true\n").unwrap().enabled;
+
+    record(enabled, "run", true, Duration::from_millis(20));
+    record(enabled, "run", false, Duration::from_millis(400));
+    record(enabled, "vm eval", true, Duration::from_secs(12));
+    begin("run", enabled);
+    finish(true);
+    // Nothing pending: a second finish counts nothing.
+    finish(true);
+
+    let stats = load(&stats_path());
+    assert!(stats.since.is_some());
+    let run = &stats.commands["run"];
+    assert_eq!((run.ok, run.failed), (2, 1));
+    assert_eq!(run.durations["<100ms"], 2);
+    assert_eq!(run.durations["<1s"], 1);
+    assert_eq!(stats.commands["vm eval"].durations[">=10s"], 1);
+    assert_eq!(bucket(Duration::from_secs(1)), "<10s");
+
+    assert!(reset(&stats_path()).unwrap());
+    assert!(load(&stats_path()).commands.is_empty());
+    assert!(!reset(&stats_path()).unwrap());
+}
+
+#[test]
+fn disabled_mode_writes_nothing() {
+    let _g = ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let dir = tempfile::tempdir().unwrap();
+    std::env::set_var("AEONMI_CONFIG_DIR", dir.path());
+    let enabled = StatsSettings::from_toml_str("[editor]\nformat_on_save = true\n").unwrap().enabled;
+    assert!(!enabled, "stats are opt-in");
+
+    record(enabled, "run", true, Duration::from_millis(5));
+    begin("run", enabled);
+    finish(false);
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn cli_counts_only_after_opting_in() {
+    let dir = tempfile::tempdir().unwrap();
+    let user = dir.path().join("user.toml");
+    std::fs::write(&user, "# keep me\n[editor]\nformat_on_save = true\n").unwrap();
+    let run = |args: &[&str]| {
+        let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+            .arg("--config")
+            .arg(&user)
+            .args(args)
+            .env("AEONMI_CONFIG_DIR", dir.path().join("cfg"))
+            .env("NO_COLOR", "1")
+            .output()
+            .expect("failed to run aeonmi_project");
+        (out.status.code(), String::from_utf8_lossy(&out.stdout).into_owned())
+    };
+
+    run(&["explain-error", "--list"]);
+    assert!(!dir.path().join("cfg").exists(), "disabled by default");
+
+    assert_eq!(run(&["config", "set", "stats.enabled", "true"]).0, Some(0));
+    let cfg = std::fs::read_to_string(&user).unwrap();
+    assert!(cfg.starts_with("# keep me\n") && cfg.contains("[stats]\nenabled = true\n"), "{cfg}");
+    assert_eq!(run(&["config", "set", "stats.enabled", "maybe"]).0, Some(2));
+
+    run(&["explain-error", "--list"]);
+    run(&["explain-error", "--list"]);
+    let (code, shown) = run(&["stats", "show"]);
+    assert_eq!(code, Some(0));
+    assert!(shown.contains("nothing leaves this machine") && shown.contains("recording: on"), "{shown}");
+    let (_, json) = run(&["stats", "show", "--json"]);
+    let v: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(v["commands"]["explain-error"]["ok"], 2);
+    assert_eq!(v["commands"]["config set"]["failed"], 1);
+    assert!(v["commands"].get("stats show").is_none(), "stats does not count itself");
+}
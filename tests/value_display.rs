@@ -0,0 +1,87 @@
+use aeonmi_project::commands::run::run_source_captured;
+use aeonmi_project::core::vm::Value;
+use aeonmi_project::core::vm_display::{display_plain, js_number, LOG_DEPTH};
+use std::collections::HashMap;
+
+fn s(v: &str) -> Value {
+    Value::String(v.into())
+}
+
+fn obj(entries: &[(&str, Value)]) -> Value {
+    Value::Object(entries.iter().map(|(k, v)| (k.to_string(), v.clone())).collect::<HashMap<_, _>>())
+}
+
+/// `String(x)` as printed by node for each input.
+const NODE_NUMBERS: &[(f64, &str)] = &[
+    (1.0, "1"),
+    (-1.0, "-1"),
+    (0.5, "0.5"),
+    (0.1 + 0.2, "0.30000000000000004"),
+    (1e21, "1e+21"),
+    (1e20, "100000000000000000000"),
+    (123456789012345680000.0, "123456789012345680000"),
+    (1e-7, "1e-7"),
+    (1.5e-6, "0.0000015"),
+    (0.000001, "0.000001"),
+    (-0.0, "0"),
+    (1.0 / 3.0, "0.3333333333333333"),
+    (9007199254740992.0, "9007199254740992"),
+    (1e300, "1e+300"),
+    (f64::NAN, "NaN"),
+    (f64::INFINITY, "Infinity"),
+    (f64::NEG_INFINITY, "-Infinity"),
+    (100.0, "100"),
+    (-2.5e-10, "-2.5e-10"),
+    (12345.678, "12345.678"),
+];
+
+#[test]
+fn numbers_match_node() {
+    for &(n, expected) in NODE_NUMBERS {
+        assert_eq!(js_number(n), expected, "{n:e}");
+    }
+}
+
+#[test]
+fn strings_are_bare_only_at_top_level() {
+    assert_eq!(s("hi").display_pretty(LOG_DEPTH), "hi");
+    let v = Value::Array(vec![s("a \"b\""), Value::Number(2.0), Value::Null]);
+    assert_eq!(v.display_pretty(LOG_DEPTH), r#"["a \"b\"", 2, null]"#);
+    assert_eq!(obj(&[("k", s("v")), ("a", Value::Bool(true))]).display_pretty(LOG_DEPTH), r#"{a: true, k: "v"}"#);
+}
+
+#[test]
+fn wide_nesting_breaks_over_indented_lines() {
+    let row = |i: usize| Value::Array((0..5).map(|j| s(&format!("cell-{i}-{j}"))).collect());
+    let table = obj(&[("rows", Value::Array(vec![row(1), row(2)])), ("n", Value::Number(2.0))]);
+    let expected = r#"{
+  n: 2,
+  rows: [
+    ["cell-1-0", "cell-1-1", "cell-1-2", "cell-1-3", "cell-1-4"],
+    ["cell-2-0", "cell-2-1", "cell-2-2", "cell-2-3", "cell-2-4"]
+  ]
+}"#;
+    assert_eq!(table.display_pretty(LOG_DEPTH), expected);
+    // the plain form never breaks lines
+    assert!(!display_plain(&table).contains('\n'));
+}
+
+#[test]
+fn depth_limit_elides_deep_containers() {
+    let mut v = Value::Number(1.0);
+    for _ in 0..4 {
+        v = Value::Array(vec![v]);
+    }
+    assert_eq!(v.display_pretty(LOG_DEPTH), "[[[[1]]]]");
+    assert_eq!(v.display_pretty(2), "[[[…]]]");
+    assert_eq!(obj(&[("inner", obj(&[("x", Value::Null)]))]).display_pretty(1), "{inner: {…}}");
+    // Values cannot share storage yet, so no container can contain itself and
+    // «cycle» is unreachable; a deep chain still renders without a cycle marker.
+    assert!(!v.display_pretty(usize::MAX).contains("«cycle»"));
+}
+
+#[test]
+fn log_uses_the_pretty_form() {
+    let run = run_source_captured("log(0.1 + 0.2);\nlog(1e21);\nlog(10 / 4);\nlog(\"top\");").unwrap();
+    assert_eq!(run.output, "0.30000000000000004\n1e+21\n2.5\ntop\n");
+}
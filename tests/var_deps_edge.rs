@@ -21,9 +21,9 @@ function f() {
 "#;
     let ast = parse(code);
     let deps = compute_var_deps(&ast);
-    // Expect function index 0 writes x and i; reads x/i inside conditions
-    assert!(deps.writes.get("x").map(|s| s.contains(&0)).unwrap_or(false));
-    assert!(deps.reads.get("x").map(|s| s.contains(&0)).unwrap_or(false));
+    // x and i are locals of f (let / loop var), so they create no top-level edges
+    assert!(!deps.writes.contains_key("x") && !deps.writes.contains_key("i"));
+    assert!(!deps.reads.contains_key("x") && !deps.reads.contains_key("i"));
 }
 
 #[test]
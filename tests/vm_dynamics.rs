@@ -0,0 +1,76 @@
+//! `simulate_ode` / `logistic_map` builtins and `aeonmi math demo`.
+use aeonmi_project::commands::run::run_source_captured;
+use std::process::Command;
+
+fn numbers(source: &str) -> Vec<f64> {
+    let out = run_source_captured(source).unwrap().output;
+    out.lines().map(|l| l.parse().unwrap_or_else(|_| panic!("{l} in {out}"))).collect()
+}
+
+#[test]
+fn logistic_map_settles_on_known_fixed_points() {
+    // r < 1: extinction; 1 < r < 3: the fixed point 1 - 1/r; r = 3.2: a 2-cycle.
+    let v = numbers("let xs = logistic_map(0.5, 0.3, 200);\nlog(xs[200]);\nlog(len(xs));\n");
+    assert!(v[0].abs() < 1e-12 && v[1] == 201.0, "{v:?}");
+    let v = numbers("let xs = logistic_map(2.5, 0.2, 500);\nlog(xs[500]);\n");
+    assert!((v[0] - 0.6).abs() < 1e-12, "{v:?}");
+    let v = numbers("let xs = logistic_map(3.2, 0.2, 1000);\nlog(xs[999]);\nlog(xs[1000]);\n");
+    let (lo, hi) = (v[0].min(v[1]), v[0].max(v[1]));
+    // The 2-cycle of r x (1 - x): (r + 1 ± sqrt((r - 3)(r + 1))) / 2r.
+    let d = (0.2f64 * 4.2).sqrt();
+    assert!((lo - (4.2 - d) / 6.4).abs() < 1e-9 && (hi - (4.2 + d) / 6.4).abs() < 1e-9, "{v:?}");
+
+    let err = run_source_captured("let xs = logistic_map(4.5, 0.2, 10);\n").unwrap_err();
+    assert!(err.contains("logistic_map (line 1): r must be between 0 and 4, got 4.5"), "{err}");
+}
+
+#[test]
+fn rk4_on_exponential_growth_matches_e_to_the_t() {
+    let src = "function f(t, y) {\n    return y;\n}\nlet pts = simulate_ode(f, 1, 1, 0.1);\nlet last = pts[len(pts) - 1];\nlog(len(pts));\nlog(last[0]);\nlog(last[1]);\n";
+    let v = numbers(src);
+    assert_eq!(v[0], 11.0);
+    assert!((v[1] - 1.0).abs() < 1e-12);
+    assert!((v[2] - 1f64.exp()).abs() < 1e-5, "{v:?}");
+
+    let fine = numbers(&src.replace("simulate_ode(f, 1, 1, 0.1)", "simulate_ode(f, 1, 2, 0.01)"));
+    assert!((fine[2] - 2f64.exp()).abs() < 1e-8, "{fine:?}");
+}
+
+#[test]
+fn bad_steps_and_blow_ups_are_errors_not_nan() {
+    let cube = "function f(t, y) {\n    return y * y * y;\n}\n";
+    let err = run_source_captured(&format!("{cube}let p = simulate_ode(f, 1, 1, 0);\n")).unwrap_err();
+    assert!(err.contains("simulate_ode (line 4): step size must be a positive number, got 0"), "{err}");
+    let err = run_source_captured(&format!("{cube}let p = simulate_ode(f, 10, 2, 0.5);\n")).unwrap_err();
+    assert!(err.contains("state is no longer finite at t = 1 (after 1 steps)"), "{err}");
+    let err = run_source_captured("function g(t, y) {\n    return \"up\";\n}\nlet p = simulate_ode(g, 1, 1, 0.5);\n").unwrap_err();
+    assert!(err.contains("simulate_ode (line 4): f returned up at t = 0; it must return a number"), "{err}");
+}
+
+#[test]
+fn math_demo_writes_plot_csv() {
+    let dir = tempfile::tempdir().unwrap();
+    let csv = dir.path().join("logistic.csv");
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+            .args(["math", "demo"])
+            .args(args)
+            .env("NO_COLOR", "1")
+            .output()
+            .expect("failed to run aeonmi_project")
+    };
+    let out = run(&["logistic", "--r", "2.5", "--iterations", "50", "--plot-csv", csv.to_str().unwrap()]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let text = std::fs::read_to_string(&csv).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!((lines[0], lines[1], lines.len()), ("n,x", "0,0.2", 52));
+
+    let out = run(&["exp"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("last:  t = 1.000000, y = 2.718282") && stdout.contains("exact: y = 2.718282"), "{stdout}");
+
+    assert_eq!(run(&["lorenz", "--dt", "0"]).status.code(), Some(2));
+    let out = run(&["lorenz", "--dt", "0.5"]);
+    assert_eq!(out.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("no longer finite"));
+}
@@ -0,0 +1,91 @@
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::lowering::lower_ast_to_ir;
+use aeonmi_project::core::parser::Parser;
+use aeonmi_project::core::vm::{value_size, Interpreter, Limits, OutputBuffer, RuntimeError, RuntimeErrorKind, Value};
+use std::process::Command;
+
+const LIMIT: usize = 256 * 1024;
+
+/// 1 KiB of `x`, built by doubling.
+const CHUNK: &str = "let chunk = \"x\";\nlet i = 0;\nwhile (i < 10) { chunk = chunk + chunk; i = i + 1; }\n";
+
+fn run(source: &str, limit: Option<usize>) -> (Interpreter, String, Result<(), RuntimeError>) {
+    let tokens = Lexer::from_str(source).tokenize().unwrap();
+    let module = lower_ast_to_ir(&Parser::new(tokens).parse().unwrap(), "main").unwrap();
+    let buf = OutputBuffer::new();
+    let mut interp = Interpreter::new();
+    interp.set_output(Box::new(buf.clone()));
+    interp.set_limits(Limits { max_memory_bytes: limit, ..Default::default() });
+    let result = interp.run_module(&module);
+    (interp, buf.contents(), result)
+}
+
+fn out_of_memory(result: Result<(), RuntimeError>) -> (usize, usize) {
+    match result {
+        Err(RuntimeError { kind: RuntimeErrorKind::OutOfMemory { used, limit }, message }) => {
+            assert!(message.contains("out of memory"), "{message}");
+            (used, limit)
+        }
+        other => panic!("expected out of memory, got {other:?}"),
+    }
+}
+
+#[test]
+fn growing_string_stops_near_the_limit() {
+    let source = format!("{CHUNK}let s = \"\";\nwhile (true) {{ s = s + chunk; log(len(s)); }}");
+    let (_, out, result) = run(&source, Some(LIMIT));
+    let (used, limit) = out_of_memory(result);
+    assert_eq!(limit, LIMIT);
+    assert!(used > LIMIT && used < LIMIT + 4 * 1024, "used {used}");
+    // `s + chunk` is live beside `s` until the assignment, so `s` tops out near half the limit.
+    let last: usize = out.lines().last().unwrap().parse().unwrap();
+    assert!(last > LIMIT / 2 - 4 * 1024 && last <= LIMIT / 2, "last length {last}");
+}
+
+#[test]
+fn value_size_counts_owned_data() {
+    let slot = std::mem::size_of::<Value>();
+    assert_eq!(value_size(&Value::Number(1.0)), slot);
+    assert_eq!(value_size(&Value::String("x".repeat(100))), slot + 100);
+    let nested = Value::Array(vec![Value::String("x".repeat(10)), Value::Array(vec![Value::Null])]);
+    assert_eq!(value_size(&nested), slot + (slot + 10) + (slot + slot));
+}
+
+#[test]
+fn scopes_release_their_values() {
+    let source = format!(
+        "{CHUNK}function build(chunk) {{ let s = \"\"; let j = 0; while (j < 100) {{ s = s + chunk; j = j + 1; }} return len(s); }}\n\
+         let k = 0;\nwhile (k < 20) {{ build(chunk); k = k + 1; }}\nlog(k);"
+    );
+    let (interp, out, result) = run(&source, Some(LIMIT));
+    assert!(result.is_ok(), "{result:?}");
+    assert_eq!(out, "20\n");
+    assert!(interp.live_bytes() < 4 * 1024, "live {}", interp.live_bytes());
+}
+
+#[test]
+fn no_limit_means_no_accounting() {
+    let source = format!("{CHUNK}let s = chunk + chunk;\nlog(len(s));");
+    let (interp, out, result) = run(&source, None);
+    assert!(result.is_ok());
+    assert_eq!(out, "2048\n");
+    assert_eq!(interp.live_bytes(), 0);
+}
+
+#[test]
+fn cli_max_memory_mb_is_a_runtime_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("grow.ai");
+    std::fs::write(&file, format!("{CHUNK}let s = \"\";\nwhile (true) {{ s = s + chunk; }}")).unwrap();
+    for cmd in [&["run"][..], &["exec", "--native"][..]] {
+        let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+            .args(cmd)
+            .arg(&file)
+            .args(["--max-memory-mb", "1"])
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        assert_eq!(out.status.code(), Some(4), "{cmd:?}: {stderr}");
+        assert!(stderr.contains("1048576 byte limit"), "{cmd:?}: {stderr}");
+    }
+}
@@ -0,0 +1,73 @@
+//! `minimize` / `grid_search` on the native VM, driving Titan's optimizers.
+use aeonmi_project::commands::run::run_source_captured;
+use aeonmi_project::core::lexer::Lexer;
+use aeonmi_project::core::lowering::lower_ast_to_ir;
+use aeonmi_project::core::parser::Parser;
+use aeonmi_project::core::vm::{Interpreter, OutputBuffer, RuntimeError, RuntimeErrorKind, Value};
+use std::collections::HashMap;
+
+/// `source` with `globals` bound first (there are no array or map literals to build them from).
+fn run_with(source: &str, globals: Vec<(&str, Value)>) -> (String, Result<Value, RuntimeError>) {
+    let tokens = Lexer::from_str(source).tokenize().expect("lex");
+    let module = lower_ast_to_ir(&Parser::new(tokens).parse().expect("parse"), "main").unwrap();
+    let buf = OutputBuffer::new();
+    let mut interp = Interpreter::new();
+    interp.set_output(Box::new(buf.clone()));
+    for (name, v) in globals {
+        interp.env.define(name.into(), v);
+    }
+    let result = interp.run_main(&module);
+    (buf.contents(), result)
+}
+
+fn nums(xs: &[f64]) -> Value {
+    Value::Array(xs.iter().map(|x| Value::Number(*x)).collect())
+}
+
+/// The numbers after `key = ` in the example's output.
+fn reported(out: &str, key: &str) -> f64 {
+    let line = out.lines().find_map(|l| l.strip_prefix(&format!("{key} = "))).unwrap_or_else(|| panic!("{key} in {out}"));
+    line.parse().unwrap()
+}
+
+#[test]
+fn rosenbrock_example_converges_to_one_one() {
+    let src = std::fs::read_to_string("examples/optimize_rosenbrock.ai").unwrap();
+    let out = run_source_captured(&src).unwrap().output;
+    assert!(out.starts_with("grid: x = 1, y = 1\ngrid points: 81\n"), "{out}");
+    assert!((reported(&out, "x") - 1.0).abs() < 1e-6, "{out}");
+    assert!((reported(&out, "y") - 1.0).abs() < 1e-6, "{out}");
+    assert!(reported(&out, "value") < 1e-12, "{out}");
+}
+
+#[test]
+fn array_objectives_and_options() {
+    let src = "function bowl(p) {\n    let a = p[0] - 3;\n    let b = p[1] + 2;\n    return a * a + b * b;\n}\n\
+               let r = minimize(bowl, start, options);\nlog(r.iterations);\n\
+               let g = grid_search(bowl, ranges);\nlog(g.best_params[0]);\nlog(g.best_params[1]);\n";
+    let options = Value::Object(HashMap::from([
+        ("method".to_string(), Value::String("annealing".into())),
+        ("max_iter".to_string(), Value::Number(300.0)),
+        ("seed".to_string(), Value::Number(4.0)),
+    ]));
+    let ranges = Value::Array(vec![nums(&[0.0, 4.0, 5.0]), nums(&[-4.0, 0.0, 5.0])]);
+    let (out, result) = run_with(src, vec![("start", nums(&[0.0, 0.0])), ("options", options), ("ranges", ranges)]);
+    result.unwrap();
+    assert_eq!(out, "300\n3\n-2\n");
+
+    let bad = Value::Object(HashMap::from([("tolerance".to_string(), Value::Number(1.0))]));
+    let (_, result) = run_with("function f(x) { return x; }\nminimize(f, 1, bad);\n", vec![("bad", bad)]);
+    assert!(result.unwrap_err().message.contains("unknown option `tolerance`"));
+}
+
+#[test]
+fn non_numeric_objective_is_a_structured_error() {
+    let src = "function f(x) {\n    if (x > 0.7) {\n        return \"oops\";\n    }\n    return x;\n}\n\nlet r = grid_search(f, 0, 1, 3);\n";
+    let (_, result) = run_with(src, vec![]);
+    let e = result.unwrap_err();
+    assert_eq!(e.kind, RuntimeErrorKind::ObjectiveNotNumber { line: 8, evaluation: 3 });
+    assert_eq!(e.message, "grid_search (line 8): objective returned oops at [1] (evaluation 3); it must return a number");
+
+    let err = run_source_captured("function f(x, y) { return x < y; }\nlet r = minimize(f, 0);\n").unwrap_err();
+    assert!(err.contains("minimize (line 2): objective returned false at [0, 0] (evaluation 1)"), "{err}");
+}
@@ -0,0 +1,56 @@
+use aeonmi_project::commands::run::run_source_captured;
+use aeonmi_project::core::ir::*;
+use aeonmi_project::core::vm::{Interpreter, OutputBuffer};
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn captured_output_matches_program_logs() {
+    let src = "let i = 0;\nwhile (i < 3) {\n  log(i);\n  i = i + 1;\n}\nlog(\"done\");\n";
+    let run = run_source_captured(src).expect("program should run");
+    assert_eq!(run.output, "0\n1\n2\ndone\n");
+}
+
+#[test]
+fn log_events_carry_source_span() {
+    let src = "let x = 2;\n  log(x + 1);\nprint(\"p\");\n";
+    let run = run_source_captured(src).expect("program should run");
+    assert_eq!(run.events.len(), 2);
+    assert_eq!(run.events[0].text, "3");
+    assert_eq!((run.events[0].line, run.events[0].column), (2, 3));
+    // print is an ordinary call; no statement span is known
+    assert_eq!(run.events[1].text, "p");
+    assert_eq!(run.events[1].line, 0);
+}
+
+#[test]
+fn set_output_redirects_builtin_printing() {
+    let module = Module {
+        name: "t".into(),
+        imports: vec![],
+        decls: vec![Decl::Fn(FnDecl {
+            name: "main".into(),
+            params: vec![],
+            body: Block {
+                stmts: vec![Stmt::Expr(Expr::Call {
+                    callee: Box::new(Expr::Ident("print".into())),
+                    args: vec![Expr::Lit(Lit::String("a".into())), Expr::Lit(Lit::Number(1.0))],
+                })],
+            },
+        })],
+    };
+    let buf = OutputBuffer::new();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = seen.clone();
+    let mut interp = Interpreter::new();
+    interp.set_output(Box::new(buf.clone()));
+    interp.on_log(move |ev| sink.lock().unwrap().push(ev.text.clone()));
+    interp.run_module(&module).unwrap();
+    assert_eq!(buf.contents(), "a 1\n");
+    assert_eq!(*seen.lock().unwrap(), vec!["a 1".to_string()]);
+}
+
+#[test]
+fn runtime_errors_are_reported_not_printed() {
+    let err = run_source_captured("nope(1);").unwrap_err();
+    assert!(err.starts_with("runtime error:"), "{err}");
+}
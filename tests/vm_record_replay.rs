@@ -0,0 +1,103 @@
+use aeonmi_project::core::vm_trace::{decode, encode, Entry, Kind, Payload, Trace, VERSION};
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+const PROGRAM: &str = r#"
+function flip() {
+  qubit r;
+  superpose(r);
+  measure(r);
+}
+let a = rand();
+qubit q;
+qubit p;
+superpose(q);
+entangle(q, p);
+let m = measure(q);
+let n = measure(p);
+let h = env("AEONMI_TRACE_DEMO");
+log(a);
+log(m);
+log(n);
+log(h);
+log(run_shots(20, flip));
+"#;
+
+fn run(dir: &Path, file: &str, seed: &str, demo: &str, extra: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .arg("--quiet")
+        .arg("run")
+        .arg(dir.join(file))
+        .args(extra)
+        .env("AEONMI_SEED", seed)
+        .env("AEONMI_TRACE_DEMO", demo)
+        .output()
+        .expect("failed to run aeonmi_project")
+}
+
+#[test]
+fn replay_reproduces_recorded_output() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("prog.ai"), PROGRAM).unwrap();
+    let trace = dir.path().join("trace.bin");
+    let trace = trace.to_str().unwrap();
+
+    let recorded = run(dir.path(), "prog.ai", "7", "first", &["--record", trace]);
+    assert!(recorded.status.success(), "stderr: {}", String::from_utf8_lossy(&recorded.stderr));
+    let bytes = fs::read(trace).unwrap();
+    assert_eq!(&bytes[..5], b"AEOT\x01");
+
+    // different seed and environment: only the trace decides the results
+    for seed in ["7", "12345"] {
+        let replayed = run(dir.path(), "prog.ai", seed, "second", &["--replay", trace]);
+        assert!(replayed.status.success(), "stderr: {}", String::from_utf8_lossy(&replayed.stderr));
+        assert_eq!(replayed.stdout, recorded.stdout);
+    }
+    assert!(String::from_utf8_lossy(&recorded.stdout).contains("first"));
+}
+
+#[test]
+fn replay_reports_divergence_with_call_index_and_span() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("prog.ai"), "let a = rand();\nlet b = rand();\nlog(a + b);\n").unwrap();
+    fs::write(dir.path().join("other.ai"), "let a = rand();\nqubit q;\nlet m = measure(q);\nlog(m);\n").unwrap();
+    let trace = dir.path().join("trace.bin");
+    let trace = trace.to_str().unwrap();
+    assert!(run(dir.path(), "prog.ai", "1", "", &["--record", trace]).status.success());
+
+    let out = Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+        .args(["run", dir.path().join("other.ai").to_str().unwrap(), "--replay", trace])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(4));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr.contains("replay diverged at call #2 (3:5): program called measure but the trace recorded rand at 2:5"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn trace_format_round_trips_and_checks_version() {
+    let mut t = Trace::recording();
+    t.step(Kind::TimeMs, "", (3, 9), || Payload::Number(1_700_000_000_123.0)).unwrap();
+    t.step(Kind::Env, "HOME", (4, 1), || Payload::Text(None)).unwrap();
+    t.step(Kind::Measure, "", (0, 0), || Payload::Number(0.25)).unwrap();
+    let bytes = encode(t.entries());
+    assert_eq!(bytes.len(), 34, "5 header + 9 + 9 + 11");
+    let entries = decode(&bytes).unwrap();
+    assert_eq!(entries, t.entries());
+    assert_eq!(entries[1], Entry { kind: Kind::Env, key: "HOME".into(), value: Payload::Text(None), line: 4, column: 1 });
+
+    let mut future = bytes.clone();
+    future[4] = VERSION + 1;
+    assert!(decode(&future).unwrap_err().contains("unsupported trace version"));
+    assert!(decode(&bytes[..bytes.len() - 1]).unwrap_err().contains("truncated"));
+
+    let mut replay = Trace::replaying(entries);
+    replay.step(Kind::TimeMs, "", (3, 9), || unreachable!()).unwrap();
+    let d = replay.step(Kind::Env, "PATH", (4, 1), || unreachable!()).unwrap_err();
+    assert_eq!(d.index, 2);
+    assert_eq!(d.to_string(), "replay diverged at call #2 (4:1): program called env(\"PATH\") but the trace recorded env(\"HOME\") at 4:1");
+}
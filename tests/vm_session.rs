@@ -0,0 +1,89 @@
+//! End-to-end `aeonmi vm`: a background daemon per test, its state under a temp config dir.
+use std::path::Path;
+use std::process::{Command, Output};
+
+struct Vm<'a> {
+    config: &'a Path,
+}
+
+impl Vm<'_> {
+    fn run(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_aeonmi_project"))
+            .arg("vm")
+            .args(args)
+            .env("AEONMI_CONFIG_DIR", self.config)
+            .env("NO_COLOR", "1")
+            .output()
+            .expect("failed to run aeonmi_project")
+    }
+
+    fn ok(&self, args: &[&str]) -> String {
+        let out = self.run(args);
+        assert!(out.status.success(), "vm {args:?}: {}", String::from_utf8_lossy(&out.stderr));
+        String::from_utf8(out.stdout).unwrap()
+    }
+}
+
+impl Drop for Vm<'_> {
+    // Never leave a daemon behind, whatever the test did.
+    fn drop(&mut self) {
+        self.run(&["stop"]);
+    }
+}
+
+#[test]
+fn globals_survive_snapshot_and_restore_into_a_fresh_vm() {
+    let dir = tempfile::tempdir().unwrap();
+    let vm = Vm { config: dir.path() };
+    assert!(vm.ok(&["start"]).starts_with("vm: started"));
+    assert!(vm.ok(&["start"]).starts_with("vm: already running"));
+
+    vm.ok(&["eval", "let x = 41;\nfunction bump(n) {\n    return n + x;\n}\n"]);
+    // Functions see the globals as they are now, not as they were when defined.
+    assert_eq!(vm.ok(&["eval", "x = x + 1;\nlog(bump(0));"]), "42\n");
+    let status = vm.ok(&["status"]);
+    assert!(status.contains("modules: 2 (<eval 1>, <eval 2>)"), "{status}");
+    assert!(status.contains("globals: bump, x"), "{status}");
+
+    assert!(vm.ok(&["snapshot", "s1"]).contains("snapshot 's1' saved (2 globals)"));
+    assert!(dir.path().join("vm/snapshots/s1.json").is_file());
+    assert_eq!(vm.ok(&["stop"]), "vm: stopped\n");
+    assert_eq!(vm.ok(&["status"]), "vm: not running\n");
+
+    vm.ok(&["start"]);
+    let out = vm.run(&["eval", "log(x);"]);
+    assert_eq!(out.status.code(), Some(4), "a fresh vm has no x");
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Undefined identifier `x`"));
+    vm.ok(&["restore", "s1"]);
+    assert_eq!(vm.ok(&["eval", "log(x);\nlog(bump(1));"]), "42\n43\n");
+    assert!(vm.ok(&["status"]).contains("modules: 3 (<eval 1>, <eval 2>, <eval 3>)"));
+
+    assert_eq!(vm.run(&["restore", "missing"]).status.code(), Some(5));
+    assert_eq!(vm.run(&["snapshot", "../escape"]).status.code(), Some(2));
+    assert_eq!(vm.run(&["eval", "let = ;"]).status.code(), Some(3));
+}
+
+#[test]
+fn file_builtins_only_reach_mounted_directories() {
+    let dir = tempfile::tempdir().unwrap();
+    let data = tempfile::tempdir().unwrap();
+    std::fs::write(data.path().join("in.txt"), "hello").unwrap();
+    let vm = Vm { config: dir.path() };
+    vm.ok(&["start"]);
+
+    let path = data.path().join("in.txt").display().to_string();
+    let read = format!("log(read_file(\"{path}\"));");
+    let out = vm.run(&["eval", &read]);
+    assert_eq!(out.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("outside the mounted directories"));
+
+    vm.ok(&["mount", data.path().to_str().unwrap()]);
+    assert_eq!(vm.ok(&["eval", &read]), "hello\n");
+    let copy = data.path().join("out.txt").display().to_string();
+    vm.ok(&["eval", &format!("write_file(\"{copy}\", read_file(\"{path}\") + \"!\");")]);
+    assert_eq!(std::fs::read_to_string(data.path().join("out.txt")).unwrap(), "hello!");
+    // `..` cannot climb out of the mount.
+    let escape = format!("log(read_file(\"{}/../x\"));", data.path().display());
+    assert_eq!(vm.run(&["eval", &escape]).status.code(), Some(4));
+    assert!(vm.ok(&["status"]).contains(&format!("mounts: {}", data.path().canonicalize().unwrap().display())));
+}